@@ -1,5 +1,6 @@
 fn main() {
     configure_edsdk();
+    #[cfg(feature = "app")]
     tauri_build::build()
 }
 
@@ -0,0 +1,732 @@
+// Tauri app — managed state, command registration, and startup.
+//
+// Everything here is a thin layer over the core modules; it's only built
+// with the `app` feature.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tauri::{Emitter, Manager};
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::commands::{
+    cancel_device_operation, canon_take_photo, generate_compat_report, get_camera,
+    get_camera_control, get_camera_controls, get_camera_formats, get_camera_formats_grouped,
+    get_canon_enabled, get_control_latency_stats, get_default_camera, get_device_capabilities,
+    get_exposure_seconds, get_focus_normalized, get_show_suppressed_devices, get_tally_auto,
+    list_cameras, refresh_camera_names, reset_camera_control, seed_default_camera,
+    set_camera_control, set_camera_control_auto, set_canon_enabled, set_default_camera,
+    set_exposure_seconds, set_focus_normalized, set_show_suppressed_devices, set_tally,
+    set_tally_auto, suggest_default_camera, suggest_powerline_frequency, CameraState,
+};
+use crate::camera::hotplug_bridge::start_hotplug_watcher;
+use crate::camera::platform::native_backends;
+use crate::camera::queue::{DeviceQueue, OpProgress};
+use crate::camera::siblings::group_siblings;
+use crate::camera::startup::{discover_progressively, StartupConfig, StartupPhase, StartupUpdate};
+use crate::camera::types::CameraDevice;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::diagnostics::soak_task::run_soak_test;
+use crate::diagnostics::stream::DiagnosticsStream;
+use crate::diagnostics::stream_task::{start_diagnostics_stream, stop_diagnostics_stream};
+use crate::i18n::commands::{get_locale, set_locale};
+use crate::i18n::{self, Locale};
+use crate::input::commands::{get_key_bindings, key_input, set_key_bindings, start_key_input};
+use crate::integration::commands::{
+    get_control_api, regenerate_control_api_token, set_control_api_enabled, start_control_api,
+    ControlApiState,
+};
+use crate::integration::midi::commands::{
+    cancel_midi_learn, get_midi, list_midi_inputs, open_midi_input, remove_midi_mapping,
+    set_midi_input, set_midi_mapping, start_midi_learn, MidiState,
+};
+use crate::preset::commands::{apply_preset, delete_preset, list_presets, save_preset};
+use crate::preview::commands::{
+    canon_set_af_point, canon_trigger_af, capture_snapshot, configure_thumbnails,
+    disable_shm_export, enable_shm_export, get_active_gpu, get_diagnostics, get_encoding_stats,
+    get_exposure_mask, get_frame, get_frame_chunked, get_frame_meta, get_frame_raw,
+    get_keep_default_warm, get_resource_usage, get_resource_usage_per_device, get_thumbnail,
+    get_video_sources, list_gpu_adapters, reset_combined_zoom, run_pipeline_benchmark,
+    run_resource_sampler, set_colour_space, set_combined_zoom, set_full_resolution_autostart,
+    set_gpu_adapter, set_jpeg_quality_profile, set_keep_default_warm, set_output_processors,
+    set_placeholder_on_error, set_post_processing, set_preview_orientation, set_video_source,
+    start_all_previews, start_preview, stop_frame_stream, stop_preview, stream_frames,
+    subscribe_preview, unsubscribe_preview, upgrade_preview, wait_for_first_frame, PreviewState,
+};
+use crate::preview::gpu::GpuState;
+use crate::preview::timelapse_task::{start_timelapse, stop_timelapse, TimelapseState};
+use crate::scene::commands::{activate_scene, delete_scene, list_scenes, save_scene};
+use crate::settings::commands::{
+    export_settings, forget_camera, get_auto_start_non_primary, get_prune_after_days,
+    get_reconcile_saved_settings, get_saved_settings, get_settings_drift, get_ui_state,
+    import_settings, list_known_cameras, reset_to_defaults, revert_to_preset,
+    set_auto_start_non_primary, set_prune_after_days, set_reconcile_saved_settings, set_ui_state,
+    SettingsState,
+};
+use crate::settings::control_cache::unix_now;
+use crate::settings::guard_monitor::{
+    get_control_guards, remove_control_guard, run_guard_monitor, set_control_guard,
+    GuardMonitorState,
+};
+use crate::settings::persist::{SaveDegraded, DEGRADED_AFTER_PANICS, SAVE_LOOP_RESTART};
+use crate::settings::scheduler::{get_schedule, run_scheduler, set_schedule, SchedulerState};
+use crate::settings::store::SettingsStore;
+use crate::settings::sync_watch::{
+    get_sync_dir, run_sync, set_sync_dir, watch_sync_dir, SyncState,
+};
+use crate::settings::ui_state::UiStateStore;
+use crate::supervisor::run_task_with_restart;
+use crate::{camera, preview, settings, tray};
+
+/// Holds an optional Canon SDK reference for creating live view sessions.
+///
+/// Stored as Tauri managed state so the preview commands can access the
+/// SDK when creating Canon capture sessions. The SDK is loaded and released
+/// at runtime when the Canon integration is toggled.
+pub struct CanonSdkState {
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    sdk: std::sync::RwLock<Option<Arc<camera::canon::remote::EdsSdkHost>>>,
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    handle_map: camera::canon::backend::HandleMap,
+    #[cfg(not(all(feature = "canon", target_os = "windows")))]
+    _phantom: (),
+}
+
+impl CanonSdkState {
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    fn new() -> Self {
+        Self {
+            sdk: std::sync::RwLock::new(None),
+            handle_map: Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
+        }
+    }
+
+    #[cfg(not(all(feature = "canon", target_os = "windows")))]
+    fn new() -> Self {
+        Self { _phantom: () }
+    }
+
+    /// Get the Canon SDK reference, if available.
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    pub fn sdk(&self) -> Option<Arc<camera::canon::remote::EdsSdkHost>> {
+        self.sdk.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Get the Canon SDK reference (always None on non-Canon builds).
+    #[cfg(not(all(feature = "canon", target_os = "windows")))]
+    pub fn sdk(&self) -> Option<Arc<()>> {
+        None
+    }
+
+    /// Look up the CameraHandle for a device_path (e.g. `edsdk://Canon EOS R5`).
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    pub fn find_handle(&self, device_path: &str) -> Option<camera::canon::api::CameraHandle> {
+        self.handle_map.lock().unwrap().get(device_path).copied()
+    }
+
+    /// Look up the CameraHandle (always None on non-Canon builds).
+    #[cfg(not(all(feature = "canon", target_os = "windows")))]
+    pub fn find_handle(&self, _device_path: &str) -> Option<()> {
+        None
+    }
+
+    /// Initialise EDSDK (if it isn't already) and build a Canon backend
+    /// over it.
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    pub fn load_backend(&self) -> Result<Box<dyn CameraBackend>, String> {
+        use crate::camera::canon::backend::CanonBackend;
+        use crate::camera::canon::remote::EdsSdkHost;
+
+        let mut slot = self.sdk.write().unwrap_or_else(|e| e.into_inner());
+        let sdk = match slot.as_ref() {
+            Some(sdk) => Arc::clone(sdk),
+            None => {
+                let sdk = Arc::new(
+                    EdsSdkHost::load()
+                        .map_err(|e| format!("Canon EDSDK initialisation failed: {e}"))?,
+                );
+                *slot = Some(Arc::clone(&sdk));
+                tracing::info!("Canon EDSDK backend initialised");
+                sdk
+            }
+        };
+        Ok(Box::new(CanonBackend::new(
+            sdk,
+            Arc::clone(&self.handle_map),
+        )))
+    }
+
+    /// Canon support isn't compiled in.
+    #[cfg(not(all(feature = "canon", target_os = "windows")))]
+    pub fn load_backend(&self) -> Result<Box<dyn CameraBackend>, String> {
+        Err("Canon support not available in this build".to_string())
+    }
+
+    /// Drop our SDK reference and forget known camera handles.
+    ///
+    /// Call after the Canon backend has been swapped out. EDSDK terminates
+    /// once the last session holding the SDK has stopped.
+    #[cfg(all(feature = "canon", target_os = "windows"))]
+    pub fn unload(&self) {
+        if self
+            .sdk
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .is_some()
+        {
+            tracing::info!("Canon EDSDK backend released");
+        }
+        self.handle_map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Nothing to release on non-Canon builds.
+    #[cfg(not(all(feature = "canon", target_os = "windows")))]
+    pub fn unload(&self) {}
+
+    /// Whether the SDK is currently loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.sdk().is_some()
+    }
+}
+
+/// Create the camera backend for the current platform.
+///
+/// Builds a `CompositeBackend` that merges device lists from the native
+/// backends (see [`native_backends`]) and, when the `canon` feature is
+/// enabled and `canon_enabled` is set, `CanonBackend`.
+///
+/// Also returns a `CanonSdkState` for live view session creation.
+fn create_camera_state(canon_enabled: bool) -> (CameraState, CanonSdkState) {
+    let canon_sdk_state = CanonSdkState::new();
+    let canon = if canon_enabled {
+        match canon_sdk_state.load_backend() {
+            Ok(backend) => Some(backend),
+            Err(e) => {
+                tracing::warn!("{e}");
+                None
+            }
+        }
+    } else {
+        tracing::info!("Canon EDSDK backend disabled in settings");
+        None
+    };
+
+    (CameraState::new(native_backends(), canon), canon_sdk_state)
+}
+
+/// Find connected cameras backend by backend, restoring saved settings and
+/// starting previews for each as it appears. Emits `startup-progress` as
+/// discovery proceeds, and `cameras-changed` with everything found so far
+/// as each backend answers.
+///
+/// Runs after setup has returned, so the window and tray are up while a
+/// slow capture card is still being waited on.
+async fn initialise_devices(app: tauri::AppHandle) {
+    let camera_state = app.state::<CameraState>();
+    let store = Arc::clone(&app.state::<SettingsState>().store);
+    let mut found: Vec<CameraDevice> = Vec::new();
+    let mut seeded = false;
+
+    discover_progressively(
+        camera_state.sources(),
+        camera_state.show_suppressed(),
+        StartupConfig::default(),
+        |update| match update {
+            StartupUpdate::Devices(devices) => {
+                found.extend(devices.iter().cloned());
+                // Let the UI list them straight away rather than waiting on
+                // a list_cameras call held up by the slow backend
+                if let Err(e) = app.emit("cameras-changed", group_siblings(found.clone())) {
+                    tracing::warn!("Failed to emit cameras-changed event: {e}");
+                }
+                start_devices(&app, &store, &devices, &found);
+            }
+            StartupUpdate::Progress(progress) => {
+                // Suggest a default camera on first run once every backend
+                // has had its chance to answer
+                if progress.phase != StartupPhase::Enumerating && !seeded {
+                    seeded = true;
+                    seed_default_camera(&camera_state.backend, &store, &found);
+                    prune_unseen_cameras(&store, &found);
+                    preview::commands::refresh_warm_default_from(&app, &found);
+                }
+                if let Err(e) = app.emit("startup-progress", &progress) {
+                    tracing::warn!("Failed to emit startup-progress event: {e}");
+                }
+            }
+        },
+    )
+    .await;
+}
+
+/// Prune saved cameras not seen for the configured number of days, now
+/// that every backend has listed what's connected.
+fn prune_unseen_cameras(store: &SettingsStore, found: &[CameraDevice]) {
+    match settings::housekeeping::prune_unseen(store, found, unix_now()) {
+        Ok(Some(report)) => tracing::info!(
+            "Pruned {} cameras not seen recently, archived to {}",
+            report.pruned.len(),
+            report.archive.display()
+        ),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to prune unseen cameras: {e}"),
+    }
+}
+
+/// Bring up newly found `devices`: pick up renames, restore saved settings,
+/// point keep-warm at the default among everything `found` so far, then
+/// auto-start previews.
+fn start_devices(
+    app: &tauri::AppHandle,
+    store: &SettingsStore,
+    devices: &[CameraDevice],
+    found: &[CameraDevice],
+) {
+    // Pick up names changed by driver updates since the last run
+    refresh_camera_names(app, store, devices);
+
+    let camera_state = app.state::<CameraState>();
+    for device in devices {
+        let restored = settings::apply::apply_saved_settings(
+            &camera_state.backend,
+            store,
+            &app.state::<ControlLatencyState>(),
+            device.id.as_str(),
+        );
+        if !restored.applied.is_empty() {
+            tracing::info!(
+                "Restored {} settings for '{}'",
+                restored.applied.len(),
+                device.name
+            );
+        }
+        if let Some(reconciled) = restored.reconciled_event(device.id.as_str(), &device.name) {
+            let _ = app.emit("settings-reconciled", reconciled);
+        }
+    }
+
+    preview::commands::refresh_warm_default_from(app, found);
+    auto_start_previews(app, store, devices);
+}
+
+/// Start preview sessions for `devices` that don't have one yet.
+fn auto_start_previews(app: &tauri::AppHandle, store: &SettingsStore, devices: &[CameraDevice]) {
+    let preview_state = app.state::<PreviewState>();
+    #[allow(unused_variables)]
+    let canon_sdk_state = app.state::<CanonSdkState>();
+    let gpu_state = app.state::<GpuState>();
+    let gpu = gpu_state.context();
+    let mut sessions = preview_state.sessions.lock();
+    for device in devices {
+        let device_id = device.id.as_str().to_string();
+        if sessions.contains_key(&device_id) {
+            continue;
+        }
+
+        let on_error = {
+            let app_handle = app.clone();
+            std::sync::Arc::new(move |payload: preview::capture::PreviewErrorPayload| {
+                let _ = app_handle.emit("preview-error", payload);
+            }) as preview::capture::ErrorCallback
+        };
+        let on_content = {
+            let app_handle = app.clone();
+            std::sync::Arc::new(move |dev_id: &str, health| {
+                let _ = app_handle.emit(
+                    "preview-content-warning",
+                    preview::capture::PreviewContentPayload {
+                        device_id: dev_id.to_string(),
+                        health,
+                    },
+                );
+            }) as preview::capture::ContentCallback
+        };
+        let on_started = {
+            let app_handle = app.clone();
+            std::sync::Arc::new(move |payload: preview::capture::PreviewStartedPayload| {
+                let _ = app_handle.emit("preview-started", payload);
+            }) as preview::capture::StartedCallback
+        };
+        let on_recovering = {
+            let app_handle = app.clone();
+            std::sync::Arc::new(move |payload: preview::capture::PreviewRecoveringPayload| {
+                let _ = app_handle.emit("preview-recovering", payload);
+            }) as preview::capture::RecoveringCallback
+        };
+
+        // Canon live view: device_path starts with "edsdk://"
+        if device.device_path.starts_with("edsdk://") {
+            #[cfg(all(feature = "canon", target_os = "windows"))]
+            {
+                if let (Some(sdk), Some(handle)) = (
+                    canon_sdk_state.sdk(),
+                    canon_sdk_state.find_handle(&device.device_path),
+                ) {
+                    let session = preview::capture::CanonCaptureSession::new(
+                        device_id.clone(),
+                        device.device_path.clone(),
+                        sdk,
+                        handle,
+                        preview::commands::saved_preview_format(store, &device_id),
+                        Some(on_error),
+                        Some(on_started),
+                        preview::commands::FRAME_JPEG_QUALITY,
+                    );
+                    let session = preview::capture::PreviewSession::Canon(session);
+                    if let Some(camera) = store.get_camera(&device_id) {
+                        session.set_post_processing(camera.post_processing);
+                    }
+                    sessions.insert(device_id, session);
+                    tracing::info!(
+                        "Auto-started Canon preview for '{}' at startup",
+                        device.name
+                    );
+                }
+            }
+            continue;
+        }
+
+        let session = preview::commands::startup_capture_session(
+            store,
+            device,
+            gpu.clone(),
+            Some(on_error),
+            Some(on_content),
+            Some(on_started),
+            Some(on_recovering),
+        );
+        sessions.insert(
+            device_id,
+            preview::capture::PreviewSession::DirectShow(session),
+        );
+        tracing::info!(
+            "Auto-started preview session for '{}' at startup",
+            device.name
+        );
+    }
+}
+
+/// Run a store's save loop, restarting it if it panics and emitting
+/// `settings-degraded` once it has panicked repeatedly.
+fn spawn_save_loop<F, Fut>(app: &tauri::AppHandle, store: &'static str, task: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        run_task_with_restart(
+            SAVE_LOOP_RESTART,
+            |_| task(),
+            |attempt, _| {
+                if attempt == DEGRADED_AFTER_PANICS {
+                    let payload = SaveDegraded {
+                        store,
+                        panics: attempt,
+                    };
+                    let _ = app.emit("settings-degraded", payload);
+                }
+            },
+        )
+        .await
+    });
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            None,
+        ))
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {}))
+        .manage(PreviewState::new())
+        .manage(GpuState::new())
+        .manage(ControlLatencyState::default())
+        .manage(SchedulerState::default())
+        .manage(GuardMonitorState::default())
+        .manage(SyncState::default())
+        .manage(TimelapseState::default())
+        .manage(DiagnosticsStream::default())
+        .manage(ControlApiState::default())
+        .manage(MidiState::default())
+        .invoke_handler(tauri::generate_handler![
+            list_cameras,
+            get_camera,
+            get_camera_control,
+            get_camera_controls,
+            get_camera_formats,
+            get_camera_formats_grouped,
+            get_device_capabilities,
+            set_camera_control,
+            set_camera_control_auto,
+            set_exposure_seconds,
+            get_exposure_seconds,
+            set_focus_normalized,
+            get_focus_normalized,
+            reset_camera_control,
+            get_control_latency_stats,
+            generate_compat_report,
+            cancel_device_operation,
+            get_canon_enabled,
+            set_canon_enabled,
+            get_show_suppressed_devices,
+            set_show_suppressed_devices,
+            suggest_default_camera,
+            suggest_powerline_frequency,
+            set_tally,
+            get_tally_auto,
+            set_tally_auto,
+            canon_take_photo,
+            get_default_camera,
+            set_default_camera,
+            start_preview,
+            wait_for_first_frame,
+            start_all_previews,
+            upgrade_preview,
+            stop_preview,
+            start_timelapse,
+            stop_timelapse,
+            get_frame,
+            get_frame_chunked,
+            get_frame_raw,
+            get_frame_meta,
+            stream_frames,
+            stop_frame_stream,
+            subscribe_preview,
+            unsubscribe_preview,
+            enable_shm_export,
+            disable_shm_export,
+            get_thumbnail,
+            capture_snapshot,
+            get_exposure_mask,
+            configure_thumbnails,
+            set_preview_orientation,
+            set_jpeg_quality_profile,
+            set_output_processors,
+            get_video_sources,
+            set_video_source,
+            set_post_processing,
+            set_colour_space,
+            set_combined_zoom,
+            reset_combined_zoom,
+            set_placeholder_on_error,
+            set_full_resolution_autostart,
+            canon_set_af_point,
+            canon_trigger_af,
+            get_diagnostics,
+            start_diagnostics_stream,
+            stop_diagnostics_stream,
+            get_encoding_stats,
+            get_resource_usage,
+            get_resource_usage_per_device,
+            run_pipeline_benchmark,
+            run_soak_test,
+            reset_to_defaults,
+            list_known_cameras,
+            forget_camera,
+            get_prune_after_days,
+            set_prune_after_days,
+            get_settings_drift,
+            revert_to_preset,
+            export_settings,
+            import_settings,
+            save_preset,
+            list_presets,
+            delete_preset,
+            apply_preset,
+            save_scene,
+            list_scenes,
+            delete_scene,
+            activate_scene,
+            get_saved_settings,
+            get_auto_start_non_primary,
+            set_auto_start_non_primary,
+            get_reconcile_saved_settings,
+            set_reconcile_saved_settings,
+            get_locale,
+            set_locale,
+            get_ui_state,
+            set_ui_state,
+            get_schedule,
+            set_schedule,
+            get_control_guards,
+            set_control_guard,
+            remove_control_guard,
+            get_sync_dir,
+            set_sync_dir,
+            list_gpu_adapters,
+            get_active_gpu,
+            set_gpu_adapter,
+            get_keep_default_warm,
+            set_keep_default_warm,
+            get_control_api,
+            set_control_api_enabled,
+            regenerate_control_api_token,
+            list_midi_inputs,
+            get_midi,
+            set_midi_input,
+            set_midi_mapping,
+            remove_midi_mapping,
+            start_midi_learn,
+            cancel_midi_learn,
+            key_input,
+            get_key_bindings,
+            set_key_bindings,
+        ])
+        .setup(|app| {
+            if cfg!(debug_assertions) {
+                app.handle().plugin(
+                    tauri_plugin_log::Builder::new()
+                        .targets([
+                            tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Stdout),
+                            tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Webview),
+                            tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::LogDir {
+                                file_name: None,
+                            }),
+                        ])
+                        .level(log::LevelFilter::Debug)
+                        .build(),
+                )?;
+            }
+
+            // Exclusive device operations report progress to the frontend
+            let handle = app.handle().clone();
+            app.manage(DeviceQueue::with_observer(Arc::new(
+                move |progress: &OpProgress| {
+                    let _ = handle.emit("device-operation", progress);
+                },
+            )));
+
+            // Initialise settings persistence
+            let settings_path = app
+                .path()
+                .app_data_dir()
+                .expect("app data dir should be available")
+                .join("cameras.json");
+            let store = Arc::new(SettingsStore::new(settings_path.clone()));
+            let saving = Arc::clone(&store);
+            spawn_save_loop(app.handle(), "settings", move || saving.debounce_task());
+            let ui_state = Arc::new(UiStateStore::new(
+                settings_path.with_file_name("ui-state.json"),
+            ));
+            let saving = Arc::clone(&ui_state);
+            spawn_save_loop(app.handle(), "ui_state", move || saving.debounce_task());
+            app.manage(SettingsState {
+                store: Arc::clone(&store),
+                ui_state,
+            });
+            // Before anything builds labels, so the tray and descriptors
+            // come up in the chosen language
+            if let Some(locale) = store.locale().as_deref().and_then(Locale::parse) {
+                i18n::set_active(locale);
+            }
+            if let Some(mb) = store.jpeg_cache_limit_mb() {
+                app.state::<PreviewState>()
+                    .set_cache_limit(mb as usize * 1024 * 1024);
+            }
+            if let Some(max) = store.max_preview_sessions() {
+                app.state::<PreviewState>().set_max_sessions(max as usize);
+            }
+            if let Some(kb) = store.max_frame_payload_kb() {
+                app.state::<PreviewState>()
+                    .set_max_response_bytes(kb as usize * 1024);
+            }
+
+            // Built after the settings load so the Canon preference is honoured
+            let (camera_state, canon_sdk_state) = create_camera_state(store.canon_enabled());
+            camera_state.set_show_suppressed(store.show_suppressed_devices());
+            app.manage(camera_state);
+            app.manage(canon_sdk_state);
+
+            // Keep the default camera warm if the user opted in. Its default
+            // is filled in as cameras are found; a warmed camera already has
+            // its session, so the auto-start skips it
+            let window_visible = app
+                .get_webview_window("main")
+                .and_then(|w| w.is_visible().ok())
+                .unwrap_or(true);
+            preview::commands::setup_warm(app.handle(), &[], window_visible)?;
+
+            tray::setup_tray(app.handle())?;
+
+            start_hotplug_watcher(app.handle(), &app.state::<CameraState>().backend);
+
+            // Find cameras, restore their settings and start their previews
+            // in the background, so a slow backend can't hold up the tray
+            let handle = app.handle().clone();
+            std::thread::Builder::new()
+                .name("startup".to_string())
+                .spawn(move || tauri::async_runtime::block_on(initialise_devices(handle)))?;
+
+            // Apply scheduled presets; the first evaluation runs straight away
+            tauri::async_runtime::spawn(run_scheduler(app.handle().clone()));
+
+            // Hold guarded controls under their ceilings
+            tauri::async_runtime::spawn(run_guard_monitor(app.handle().clone()));
+
+            // Sample each session's CPU time for the diagnostics view
+            tauri::async_runtime::spawn(run_resource_sampler(app.handle().clone()));
+
+            // Sync settings through the shared folder, if one is set
+            if let Some(dir) = store.sync_dir() {
+                if let Err(e) = watch_sync_dir(app.handle(), Some(std::path::Path::new(&dir))) {
+                    tracing::warn!("Failed to watch the sync folder: {e}");
+                }
+            }
+            tauri::async_runtime::spawn(run_sync(app.handle().clone()));
+
+            if store.control_api_enabled() {
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = start_control_api(&handle).await {
+                        tracing::warn!("Failed to start the control API: {e}");
+                    }
+                });
+            }
+
+            app.manage(start_key_input(app.handle())?);
+
+            if let Some(input) = store.midi_input().filter(|_| cfg!(feature = "midi")) {
+                if let Err(e) = open_midi_input(app.handle(), &input) {
+                    tracing::warn!("Failed to open MIDI input: {e}");
+                }
+            }
+
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Only intercept close on the main window (hide to tray instead of quitting).
+            // Other windows (e.g. settings) close and destroy normally.
+            if window.label() == "main" {
+                match event {
+                    tauri::WindowEvent::CloseRequested { api, .. } => {
+                        api.prevent_close();
+                        let _ = window.hide();
+                        preview::commands::main_window_visibility_changed(
+                            window.app_handle(),
+                            false,
+                        );
+                    }
+                    // Showing or hiding the window moves focus
+                    tauri::WindowEvent::Focused(_) => {
+                        if let Ok(visible) = window.is_visible() {
+                            preview::commands::main_window_visibility_changed(
+                                window.app_handle(),
+                                visible,
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
@@ -0,0 +1,103 @@
+//! Out-of-process EDSDK helper.
+//!
+//! Links the (32-bit) Canon EDSDK and serves it to the app over the named
+//! pipe given with `--pipe`, using the protocol in
+//! `camera::canon::protocol`. The app starts one helper at a time and
+//! kills it when it's no longer needed; the helper also exits when the app
+//! closes the pipe.
+
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use std::process::ExitCode;
+
+#[cfg(target_os = "windows")]
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let pipe = match (args.next().as_deref(), args.next()) {
+        (Some("--pipe"), Some(pipe)) => pipe,
+        _ => {
+            eprintln!("usage: edsdk-helper --pipe <name>");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&pipe) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("edsdk-helper: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Initialise the SDK, then create the pipe and serve the app. The pipe only
+/// appears once the SDK is up, so the app sees a failed start as an exit.
+#[cfg(target_os = "windows")]
+fn run(pipe: &str) -> Result<(), String> {
+    use cameras_lib::camera::canon::protocol::serve;
+    use cameras_lib::camera::canon::sdk::EdsSdk;
+
+    let sdk = EdsSdk::new().map_err(|e| e.to_string())?;
+    let pipe = listen(pipe)?;
+    let mut reader = pipe
+        .try_clone()
+        .map_err(|e| format!("failed to clone the pipe: {e}"))?;
+    let mut writer = pipe;
+    serve(&sdk, &mut reader, &mut writer).map_err(|e| format!("connection failed: {e}"))
+}
+
+/// Create the pipe and wait for the app to connect. Only the first instance
+/// of the name is accepted, and only local clients, so nothing else can
+/// stand in for either side.
+#[cfg(target_os = "windows")]
+fn listen(name: &str) -> Result<std::fs::File, String> {
+    use std::os::windows::io::FromRawHandle;
+
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::{CloseHandle, ERROR_PIPE_CONNECTED};
+    use windows::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_WAIT,
+    };
+
+    const BUFFER_SIZE: u32 = 1024 * 1024;
+
+    let handle = unsafe {
+        CreateNamedPipeW(
+            &HSTRING::from(name),
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            1,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            None,
+        )
+    };
+    if handle.is_invalid() {
+        return Err(format!(
+            "failed to create pipe {name}: {}",
+            windows::core::Error::from_win32()
+        ));
+    }
+
+    // The app may connect between creating the pipe and waiting for it
+    if let Err(e) = unsafe { ConnectNamedPipe(handle, None) } {
+        if e.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+            unsafe {
+                let _ = CloseHandle(handle);
+            }
+            return Err(format!("failed waiting for the app on {name}: {e}"));
+        }
+    }
+
+    // SAFETY: the handle is a connected pipe we own; the File closes it.
+    Ok(unsafe { std::fs::File::from_raw_handle(handle.0) })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn main() -> ExitCode {
+    eprintln!("edsdk-helper only runs on Windows");
+    ExitCode::FAILURE
+}
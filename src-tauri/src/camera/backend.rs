@@ -1,7 +1,7 @@
-use crate::camera::error::Result;
+use crate::camera::error::{CameraError, Result};
 use crate::camera::types::{
-    CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId, FormatDescriptor,
-    HotplugEvent,
+    BackendCapabilities, CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId,
+    FormatDescriptor, HotplugEvent,
 };
 
 /// Platform-agnostic camera backend trait.
@@ -25,17 +25,128 @@ pub trait CameraBackend: Send + Sync {
     /// Read a single control value.
     fn get_control(&self, id: &DeviceId, control: &ControlId) -> Result<ControlValue>;
 
-    /// Write a single control value.
+    /// Write a single control value. Puts the control in manual mode.
     fn set_control(&self, id: &DeviceId, control: &ControlId, value: ControlValue) -> Result<()>;
 
+    /// Switch a control between automatic and manual mode, keeping its
+    /// current value.
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, auto: bool) -> Result<()>;
+
+    /// Write a control by its descriptor ID, for backends whose controls
+    /// don't all have a `ControlId`. Defaults to [`set_control`](Self::set_control)
+    /// for IDs that parse; others are rejected for the backend's own devices
+    /// and `DeviceNotFound` for the rest, so a composite routes on.
+    fn set_control_by_str(&self, id: &DeviceId, control: &str, value: ControlValue) -> Result<()> {
+        match ControlId::from_str_id(control) {
+            Some(control) => self.set_control(id, &control, value),
+            None => {
+                self.device_capabilities(id)?;
+                Err(CameraError::ControlWrite(format!(
+                    "unknown control '{control}' on {id}"
+                )))
+            }
+        }
+    }
+
     /// Get supported video formats for a device.
     fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>>;
+
+    /// Optional features this backend supports. Defaults to everything the
+    /// trait offers besides relative PTZ.
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities::default()
+    }
+
+    /// Optional features for one device, or `DeviceNotFound` if this backend
+    /// doesn't own it. Defaults to [`capabilities`](Self::capabilities) for
+    /// any device `enumerate_devices` lists; override it where the backend
+    /// can look the device up more cheaply.
+    ///
+    /// A backend that fails to enumerate owns no devices, as in
+    /// `CompositeBackend::enumerate_devices`, so routing moves on to the next.
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        let devices = self.enumerate_devices().unwrap_or_default();
+        if devices.iter().any(|d| &d.id == id) {
+            Ok(self.capabilities())
+        } else {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+    }
+
+    /// Look one device up, or `None` if this backend doesn't list it.
+    /// Defaults to searching `enumerate_devices`; override it where the
+    /// backend keeps the devices it has seen.
+    fn get_device(&self, id: &DeviceId) -> Result<Option<CameraDevice>> {
+        Ok(self.enumerate_devices()?.into_iter().find(|d| &d.id == id))
+    }
+
+    /// Fire the shutter to take a still photo. Defaults to `Unsupported`
+    /// for the backend's own devices, and `DeviceNotFound` for any other so
+    /// a composite routes on.
+    fn take_photo(&self, id: &DeviceId) -> Result<()> {
+        self.device_capabilities(id)?;
+        Err(CameraError::Unsupported(format!(
+            "{id} can't take still photos"
+        )))
+    }
+}
+
+/// Lets one backend instance be shared by successive composites, so
+/// rebuilding the backend list doesn't re-initialise every platform backend.
+impl<T: CameraBackend + ?Sized> CameraBackend for std::sync::Arc<T> {
+    fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
+        (**self).enumerate_devices()
+    }
+
+    fn watch_hotplug(&self, callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
+        (**self).watch_hotplug(callback)
+    }
+
+    fn get_controls(&self, id: &DeviceId) -> Result<Vec<ControlDescriptor>> {
+        (**self).get_controls(id)
+    }
+
+    fn get_control(&self, id: &DeviceId, control: &ControlId) -> Result<ControlValue> {
+        (**self).get_control(id, control)
+    }
+
+    fn set_control(&self, id: &DeviceId, control: &ControlId, value: ControlValue) -> Result<()> {
+        (**self).set_control(id, control, value)
+    }
+
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, auto: bool) -> Result<()> {
+        (**self).set_control_auto(id, control, auto)
+    }
+
+    fn set_control_by_str(&self, id: &DeviceId, control: &str, value: ControlValue) -> Result<()> {
+        (**self).set_control_by_str(id, control, value)
+    }
+
+    fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
+        (**self).get_formats(id)
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        (**self).capabilities()
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        (**self).device_capabilities(id)
+    }
+
+    fn get_device(&self, id: &DeviceId) -> Result<Option<CameraDevice>> {
+        (**self).get_device(id)
+    }
+
+    fn take_photo(&self, id: &DeviceId) -> Result<()> {
+        (**self).take_photo(id)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::camera::error::CameraError;
+    use crate::camera::types::DeviceKind;
 
     /// Mock backend for testing trait contract.
     struct MockBackend {
@@ -68,6 +179,15 @@ mod tests {
             Ok(())
         }
 
+        fn set_control_auto(
+            &self,
+            _id: &DeviceId,
+            _control: &ControlId,
+            _auto: bool,
+        ) -> Result<()> {
+            Ok(())
+        }
+
         fn get_formats(&self, _id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             Ok(vec![])
         }
@@ -81,6 +201,10 @@ mod tests {
                 name: "Test Camera".to_string(),
                 device_path: "test-path".to_string(),
                 is_connected: true,
+                kind: DeviceKind::Primary,
+                primary_id: None,
+                identity: None,
+                suppressed_by: None,
             }],
         };
 
@@ -103,6 +227,56 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn default_device_capabilities_cover_listed_devices_only() {
+        let backend = MockBackend {
+            devices: vec![CameraDevice {
+                id: DeviceId::new("test:id"),
+                name: "Test Camera".to_string(),
+                device_path: "test-path".to_string(),
+                is_connected: true,
+                kind: DeviceKind::Primary,
+                primary_id: None,
+                identity: None,
+                suppressed_by: None,
+            }],
+        };
+
+        assert_eq!(
+            backend
+                .device_capabilities(&DeviceId::new("test:id"))
+                .unwrap(),
+            BackendCapabilities::default()
+        );
+        assert!(matches!(
+            backend.device_capabilities(&DeviceId::new("unknown")),
+            Err(CameraError::DeviceNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn default_get_device_finds_listed_devices_only() {
+        let backend = MockBackend {
+            devices: vec![CameraDevice {
+                id: DeviceId::new("test:id"),
+                name: "Test Camera".to_string(),
+                device_path: "test-path".to_string(),
+                is_connected: true,
+                kind: DeviceKind::Primary,
+                primary_id: None,
+                identity: None,
+                suppressed_by: None,
+            }],
+        };
+
+        let device = backend.get_device(&DeviceId::new("test:id")).unwrap();
+        assert_eq!(device.map(|d| d.name), Some("Test Camera".to_string()));
+        assert!(backend
+            .get_device(&DeviceId::new("unknown"))
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn trait_object_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -5,7 +5,9 @@
 
 use crate::camera::error::Result;
 
-use super::types::{EdsDeviceInfo, EdsPropertyDesc, EdsPropertyID};
+use super::types::{
+    EdsCameraCommand, EdsDeviceInfo, EdsPoint, EdsPropertyDesc, EdsPropertyID, EvfGeometry,
+};
 
 /// Opaque camera handle used across the API boundary.
 ///
@@ -56,6 +58,28 @@ pub trait EdsSdkApi: Send + Sync {
         prop: EdsPropertyID,
     ) -> Result<EdsPropertyDesc>;
 
+    /// Write a point-valued property (e.g. `PROP_ID_EVF_ZOOM_POSITION`).
+    fn set_point_property(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+        value: EdsPoint,
+    ) -> Result<()>;
+
+    /// Geometry of the most recently downloaded live view frame.
+    ///
+    /// Returns `None` when live view isn't running or no frame has arrived
+    /// yet, since EDSDK only reports the geometry with an EVF image.
+    fn evf_geometry(&self, camera: CameraHandle) -> Result<Option<EvfGeometry>>;
+
+    /// Send a camera command (`EdsSendCommand`).
+    fn send_command(
+        &self,
+        camera: CameraHandle,
+        command: EdsCameraCommand,
+        param: i32,
+    ) -> Result<()>;
+
     /// Process pending EDSDK events.
     fn get_event(&self) -> Result<()>;
 }
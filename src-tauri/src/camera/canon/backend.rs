@@ -6,18 +6,19 @@
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::camera::backend::CameraBackend;
 use crate::camera::error::{CameraError, Result};
 use crate::camera::types::{
-    CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId, FormatDescriptor,
-    HotplugEvent,
+    BackendCapabilities, CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId,
+    FormatDescriptor, HotplugEvent,
 };
 
 use super::api::{CameraHandle, EdsSdkApi};
-use super::controls::get_canon_controls;
+use super::controls::{control_property, get_canon_controls};
 use super::discovery::discover_cameras;
-use super::hotplug::CanonHotplugWatcher;
+use super::hotplug::{CanonHotplugWatcher, DEFAULT_POLL_INTERVAL};
 use super::types::*;
 
 /// Internal state for a discovered Canon camera.
@@ -44,6 +45,8 @@ pub struct CanonBackend<S: EdsSdkApi> {
     /// Shared with the hotplug watcher via `Arc`.
     dirty: Arc<AtomicBool>,
     hotplug_watcher: Mutex<Option<CanonHotplugWatcher>>,
+    /// How often the hotplug watcher re-enumerates.
+    hotplug_interval: Duration,
     handle_map: HandleMap,
 }
 
@@ -56,10 +59,24 @@ impl<S: EdsSdkApi> CanonBackend<S> {
             cached_devices: Mutex::new(Vec::new()),
             dirty: Arc::new(AtomicBool::new(true)), // first call always discovers
             hotplug_watcher: Mutex::new(None),
+            hotplug_interval: DEFAULT_POLL_INTERVAL,
             handle_map,
         }
     }
 
+    /// Re-enumerate every `interval` to find cameras plugged in or out,
+    /// instead of every [`DEFAULT_POLL_INTERVAL`]. Takes effect from the
+    /// next `watch_hotplug`.
+    pub fn with_hotplug_interval(mut self, interval: Duration) -> Self {
+        self.hotplug_interval = interval;
+        self
+    }
+
+    /// How often the hotplug watcher re-enumerates.
+    pub fn hotplug_interval(&self) -> Duration {
+        self.hotplug_interval
+    }
+
     /// Get a reference to the SDK implementation.
     pub fn sdk(&self) -> &Arc<S> {
         &self.sdk
@@ -213,7 +230,12 @@ impl<S: EdsSdkApi + 'static> CameraBackend for CanonBackend<S> {
             dirty.store(true, Ordering::Relaxed);
             callback(event);
         });
-        let watcher = CanonHotplugWatcher::start(Arc::clone(&self.sdk), wrapped);
+        let watcher = CanonHotplugWatcher::start_with_interval(
+            Arc::clone(&self.sdk),
+            wrapped,
+            self.hotplug_interval,
+        );
+        // A watcher already running is stopped as it's replaced
         let mut guard = self.hotplug_watcher.lock().unwrap();
         *guard = Some(watcher);
         Ok(())
@@ -237,6 +259,26 @@ impl<S: EdsSdkApi + 'static> CameraBackend for CanonBackend<S> {
         self.sdk.set_property(handle, prop, value.value())
     }
 
+    /// Canon controls are written by their descriptor ID, so ones without a
+    /// `ControlId`, like white balance and picture style, can be restored.
+    fn set_control_by_str(&self, id: &DeviceId, control: &str, value: ControlValue) -> Result<()> {
+        let handle = self.find_handle_with_session(id)?;
+        let prop = control_property(control).ok_or_else(|| {
+            CameraError::ControlWrite(format!("control '{control}' is not a Canon property"))
+        })?;
+        self.sdk.set_property(handle, prop, value.value())
+    }
+
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, _auto: bool) -> Result<()> {
+        // Automatic exposure and focus follow the body's shooting mode;
+        // there's no per-property flag to flip
+        self.find_handle_with_session(id)?;
+        Err(CameraError::ControlWrite(format!(
+            "{} has no automatic mode on Canon cameras",
+            control.display_name()
+        )))
+    }
+
     fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
         // Verify device exists
         let _ = self.find_handle(id)?;
@@ -247,12 +289,54 @@ impl<S: EdsSdkApi + 'static> CameraBackend for CanonBackend<S> {
             height: 640,
             fps: 5.0,
             pixel_format: "JPEG".to_string(),
+            min_fps: None,
+            max_fps: None,
         }])
     }
+
+    /// Live view previews at the body's own fixed format, and automatic
+    /// modes follow the shooting mode rather than a per-control flag.
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            supports_preview: true,
+            supports_hotplug: true,
+            ..BackendCapabilities::none()
+        }
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        self.find_handle(id)?;
+        Ok(self.capabilities())
+    }
+
+    /// Press the shutter all the way and let go, without autofocusing so
+    /// a lens that can't find focus doesn't hold the shot back. The still
+    /// is saved wherever the body saves to; live view carries on around it.
+    fn take_photo(&self, id: &DeviceId) -> Result<()> {
+        let handle = self.find_handle_with_session(id)?;
+        let pressed = self.sdk.send_command(
+            handle,
+            CAMERA_COMMAND_PRESS_SHUTTER,
+            SHUTTER_BUTTON_COMPLETELY_NON_AF,
+        );
+        // Released even if the press failed, so the button isn't left held
+        let released =
+            self.sdk
+                .send_command(handle, CAMERA_COMMAND_PRESS_SHUTTER, SHUTTER_BUTTON_OFF);
+        // The body queues events for the shot and stalls until they're read
+        if let Err(e) = self.sdk.get_event() {
+            tracing::debug!("Processing Canon events after taking a photo: {e}");
+        }
+        pressed.and(released)
+    }
 }
 
 impl<S: EdsSdkApi> Drop for CanonBackend<S> {
     fn drop(&mut self) {
+        // Stop the watcher first so it doesn't enumerate mid-cleanup
+        if let Some(mut watcher) = self.hotplug_watcher.get_mut().unwrap().take() {
+            watcher.stop();
+        }
         self.close_all_sessions();
     }
 }
@@ -262,6 +346,7 @@ mod tests {
     use super::*;
     use crate::camera::canon::mock::MockEdsSdk;
     use crate::camera::canon::types::PROP_ID_ISO_SPEED;
+    use crate::camera::types::DeviceKind;
 
     fn make_handle_map() -> HandleMap {
         Arc::new(Mutex::new(HashMap::new()))
@@ -358,6 +443,21 @@ mod tests {
         assert!(backend.get_formats(&DeviceId::new("nonexistent")).is_err());
     }
 
+    #[test]
+    fn capabilities_offer_preview_and_hotplug_only() {
+        let backend = make_backend();
+        assert!(backend
+            .device_capabilities(&DeviceId::new("canon:SER001"))
+            .is_err());
+        backend.enumerate_devices().unwrap();
+
+        let caps = backend
+            .device_capabilities(&DeviceId::new("canon:SER001"))
+            .unwrap();
+        assert!(caps.supports_preview && caps.supports_hotplug);
+        assert!(!caps.supports_formats && !caps.supports_auto_toggle);
+    }
+
     #[test]
     fn non_canon_control_returns_error() {
         let backend = make_backend();
@@ -389,6 +489,128 @@ mod tests {
         assert!(guard.is_some(), "watcher handle should be stored");
     }
 
+    #[test]
+    fn hotplug_reports_cameras_plugged_in_after_startup() {
+        let mock = Arc::new(MockEdsSdk::new().with_camera("Canon EOS R5", Some("SER001")));
+        let backend = CanonBackend::new(Arc::clone(&mock), make_handle_map())
+            .with_hotplug_interval(Duration::from_millis(10));
+        assert_eq!(backend.hotplug_interval(), Duration::from_millis(10));
+        assert_eq!(backend.enumerate_devices().unwrap().len(), 1);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        backend
+            .watch_hotplug(Box::new(move |event| {
+                let _ = tx.lock().unwrap().send(event);
+            }))
+            .unwrap();
+        while mock.events_processed() == 0 {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        mock.add_camera("Canon EOS R6", Some("SER002"));
+        match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            HotplugEvent::Connected(device) => assert_eq!(device.id.as_str(), "canon:SER002"),
+            other => panic!("expected a connection, got {other:?}"),
+        }
+        // The next enumeration rediscovers and opens a session for it
+        let devices = backend.enumerate_devices().unwrap();
+        assert_eq!(devices.len(), 2);
+        assert!(backend
+            .find_handle_with_session(&DeviceId::new("canon:SER002"))
+            .is_ok());
+
+        assert!(mock.remove_camera("SER001"));
+        match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            HotplugEvent::Disconnected { id } => assert_eq!(id.as_str(), "canon:SER001"),
+            other => panic!("expected a disconnection, got {other:?}"),
+        }
+        assert_eq!(backend.enumerate_devices().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn hotplug_interval_defaults() {
+        assert_eq!(make_backend().hotplug_interval(), DEFAULT_POLL_INTERVAL);
+    }
+
+    #[test]
+    fn dropping_the_backend_stops_the_hotplug_watcher() {
+        let mock = Arc::new(MockEdsSdk::new().with_cameras(1));
+        let backend = CanonBackend::new(Arc::clone(&mock), make_handle_map());
+        backend.watch_hotplug(Box::new(|_| {})).unwrap();
+        assert!(Arc::strong_count(&mock) > 2);
+
+        // Returns promptly despite the default 3s interval
+        let started = std::time::Instant::now();
+        drop(backend);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(Arc::strong_count(&mock), 1);
+    }
+
+    #[test]
+    fn take_photo_presses_and_releases_the_shutter_during_live_view() {
+        let mock = Arc::new(
+            MockEdsSdk::new()
+                .with_camera("Canon EOS R5", Some("SER001"))
+                .with_live_view_frame(vec![0xFF, 0xD8, 0xFF, 0xD9]),
+        );
+        let backend = CanonBackend::new(Arc::clone(&mock), make_handle_map());
+        backend.enumerate_devices().unwrap();
+        let handle = backend
+            .find_handle_with_session(&DeviceId::new("canon:SER001"))
+            .unwrap();
+        mock.start_live_view(handle).unwrap();
+
+        backend.take_photo(&DeviceId::new("canon:SER001")).unwrap();
+
+        assert_eq!(
+            mock.commands_sent(),
+            vec![
+                (
+                    handle,
+                    CAMERA_COMMAND_PRESS_SHUTTER,
+                    SHUTTER_BUTTON_COMPLETELY_NON_AF
+                ),
+                (handle, CAMERA_COMMAND_PRESS_SHUTTER, SHUTTER_BUTTON_OFF),
+            ]
+        );
+        assert_eq!(mock.events_processed(), 1);
+        // Live view was left running
+        assert!(mock.download_evf_image(handle).is_ok());
+    }
+
+    #[test]
+    fn take_photo_failure_still_releases_the_shutter() {
+        let mock = Arc::new(
+            MockEdsSdk::new()
+                .with_camera("Canon EOS R5", Some("SER001"))
+                .with_error(
+                    "send_command",
+                    CameraError::CanonDeviceBusy("busy".to_string()),
+                ),
+        );
+        let backend = CanonBackend::new(Arc::clone(&mock), make_handle_map());
+        backend.enumerate_devices().unwrap();
+
+        let result = backend.take_photo(&DeviceId::new("canon:SER001"));
+        assert!(matches!(result, Err(CameraError::CanonDeviceBusy(_))));
+        let handle = backend
+            .find_handle_with_session(&DeviceId::new("canon:SER001"))
+            .unwrap();
+        assert_eq!(
+            mock.commands_sent(),
+            vec![(handle, CAMERA_COMMAND_PRESS_SHUTTER, SHUTTER_BUTTON_OFF)]
+        );
+        assert_eq!(mock.events_processed(), 1);
+    }
+
+    #[test]
+    fn take_photo_needs_an_open_session() {
+        let backend = make_backend();
+        let result = backend.take_photo(&DeviceId::new("canon:SER001"));
+        assert!(matches!(result, Err(CameraError::DeviceNotFound(_))));
+    }
+
     #[test]
     fn canon_backend_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -447,6 +669,10 @@ mod tests {
                         name: "Canon EOS R5".to_string(),
                         device_path: "edsdk://Canon EOS R5".to_string(),
                         is_connected: true,
+                        kind: DeviceKind::Primary,
+                        primary_id: None,
+                        identity: None,
+                        suppressed_by: None,
                     },
                     session_open: false,
                 },
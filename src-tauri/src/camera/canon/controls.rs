@@ -6,6 +6,7 @@
 
 use crate::camera::error::Result;
 use crate::camera::types::{ControlDescriptor, ControlFlags, ControlOption, ControlType};
+use crate::i18n::{self, Domain};
 
 use super::api::{CameraHandle, EdsSdkApi};
 use super::types::*;
@@ -13,8 +14,8 @@ use super::types::*;
 /// A mapping definition from an EDSDK property to a `ControlDescriptor`.
 struct PropertyMapping {
     prop_id: EdsPropertyID,
+    /// IPC id, also the catalogue key of its display name.
     control_id: &'static str,
-    name: &'static str,
     control_type: ControlType,
     group: &'static str,
 }
@@ -24,40 +25,55 @@ const MAPPINGS: &[PropertyMapping] = &[
     PropertyMapping {
         prop_id: PROP_ID_ISO_SPEED,
         control_id: "canon_iso",
-        name: "ISO",
         control_type: ControlType::Select,
         group: "camera",
     },
     PropertyMapping {
         prop_id: PROP_ID_AV,
         control_id: "canon_aperture",
-        name: "Aperture",
         control_type: ControlType::Select,
         group: "camera",
     },
     PropertyMapping {
         prop_id: PROP_ID_TV,
         control_id: "canon_shutter_speed",
-        name: "Shutter Speed",
         control_type: ControlType::Select,
         group: "camera",
     },
     PropertyMapping {
         prop_id: PROP_ID_WHITE_BALANCE,
         control_id: "canon_white_balance",
-        name: "White Balance",
         control_type: ControlType::Select,
         group: "camera",
     },
     PropertyMapping {
         prop_id: PROP_ID_EXPOSURE_COMPENSATION,
         control_id: "canon_exposure_compensation",
-        name: "Exposure Compensation",
         control_type: ControlType::Slider,
         group: "camera",
     },
+    PropertyMapping {
+        prop_id: PROP_ID_PICTURE_STYLE,
+        control_id: "canon_picture_style",
+        control_type: ControlType::Select,
+        group: "camera",
+    },
+    PropertyMapping {
+        prop_id: PROP_ID_IMAGE_QUALITY,
+        control_id: "canon_image_quality",
+        control_type: ControlType::Select,
+        group: "camera",
+    },
 ];
 
+/// The EDSDK property behind a Canon control ID, such as `canon_iso`.
+pub fn control_property(control_id: &str) -> Option<EdsPropertyID> {
+    MAPPINGS
+        .iter()
+        .find(|m| m.control_id == control_id)
+        .map(|m| m.prop_id)
+}
+
 /// Build all Canon control descriptors for a camera.
 pub fn get_canon_controls<S: EdsSdkApi>(
     sdk: &S,
@@ -69,7 +85,7 @@ pub fn get_canon_controls<S: EdsSdkApi>(
         match build_descriptor(sdk, camera, mapping) {
             Ok(desc) => descriptors.push(desc),
             Err(e) => {
-                tracing::debug!("Canon control '{}' unavailable: {e}", mapping.name);
+                tracing::debug!("Canon control '{}' unavailable: {e}", mapping.control_id);
             }
         }
     }
@@ -107,13 +123,14 @@ fn build_descriptor<S: EdsSdkApi>(
 
     Ok(ControlDescriptor {
         id: mapping.control_id.to_string(),
-        name: mapping.name.to_string(),
+        name: i18n::text(Domain::Control, mapping.control_id).to_string(),
         control_type: mapping.control_type,
         group: mapping.group.to_string(),
         min,
         max,
         step,
         default: None,
+        default_auto: false,
         current,
         flags: ControlFlags {
             supports_auto: false,
@@ -129,10 +146,12 @@ fn build_descriptor<S: EdsSdkApi>(
 pub fn translate_value(prop_id: EdsPropertyID, value: i32) -> String {
     match prop_id {
         PROP_ID_ISO_SPEED => translate_iso(value),
-        PROP_ID_AV => translate_aperture(value),
-        PROP_ID_TV => translate_shutter_speed(value),
+        PROP_ID_AV => i18n::localise_decimals(translate_aperture(value)),
+        PROP_ID_TV => i18n::localise_decimals(translate_shutter_speed(value)),
         PROP_ID_WHITE_BALANCE => translate_white_balance(value),
-        PROP_ID_EXPOSURE_COMPENSATION => translate_exposure_comp(value),
+        PROP_ID_EXPOSURE_COMPENSATION => i18n::localise_decimals(translate_exposure_comp(value)),
+        PROP_ID_PICTURE_STYLE => translate_picture_style(value),
+        PROP_ID_IMAGE_QUALITY => translate_image_quality(value),
         _ => format!("{value}"),
     }
 }
@@ -278,21 +297,22 @@ fn translate_shutter_speed(value: i32) -> String {
 
 /// Translate EDSDK white balance value to display label.
 fn translate_white_balance(value: i32) -> String {
-    match value {
-        0 => "Auto".to_string(),
-        1 => "Daylight".to_string(),
-        2 => "Cloudy".to_string(),
-        3 => "Tungsten".to_string(),
-        4 => "Fluorescent".to_string(),
-        5 => "Flash".to_string(),
-        6 => "Manual".to_string(),
-        8 => "Shade".to_string(),
-        9 => "Colour Temperature".to_string(),
-        15 => "Custom 1".to_string(),
-        16 => "Custom 2".to_string(),
-        18 => "Custom 3".to_string(),
-        _ => format!("WB {value}"),
-    }
+    let key = match value {
+        0 => "white_balance.auto",
+        1 => "white_balance.daylight",
+        2 => "white_balance.cloudy",
+        3 => "white_balance.tungsten",
+        4 => "white_balance.fluorescent",
+        5 => "white_balance.flash",
+        6 => "white_balance.manual",
+        8 => "white_balance.shade",
+        9 => "white_balance.colour_temperature",
+        15 => "white_balance.custom_1",
+        16 => "white_balance.custom_2",
+        18 => "white_balance.custom_3",
+        _ => return format!("WB {value}"),
+    };
+    i18n::text(Domain::Value, key).to_string()
 }
 
 /// Translate EDSDK exposure compensation value to display label.
@@ -308,6 +328,58 @@ fn translate_exposure_comp(value: i32) -> String {
     }
 }
 
+/// Translate EDSDK picture style value to display label.
+fn translate_picture_style(value: i32) -> String {
+    let key = match value {
+        0x21 => "picture_style.user_defined_1",
+        0x22 => "picture_style.user_defined_2",
+        0x23 => "picture_style.user_defined_3",
+        0x81 => "picture_style.standard",
+        0x82 => "picture_style.portrait",
+        0x83 => "picture_style.landscape",
+        0x84 => "picture_style.neutral",
+        0x85 => "picture_style.faithful",
+        0x86 => "picture_style.monochrome",
+        0x87 => "picture_style.auto",
+        0x88 => "picture_style.fine_detail",
+        _ => return format!("Style {value:#X}"),
+    };
+    i18n::text(Domain::Value, key).to_string()
+}
+
+/// Translate EDSDK image quality value to display label.
+///
+/// The value packs the size and compression of up to two images per shot;
+/// these are the combinations cameras offer.
+fn translate_image_quality(value: i32) -> String {
+    match value {
+        0x0064FF0F => "RAW".to_string(),
+        0x0164FF0F => "M-RAW".to_string(),
+        0x0264FF0F => "S-RAW".to_string(),
+        0x0063FF0F => "C-RAW".to_string(),
+        0x0013FF0F => "L Fine".to_string(),
+        0x0012FF0F => "L Normal".to_string(),
+        0x0113FF0F => "M Fine".to_string(),
+        0x0112FF0F => "M Normal".to_string(),
+        0x0213FF0F => "S Fine".to_string(),
+        0x0212FF0F => "S Normal".to_string(),
+        0x0E13FF0F => "S1 Fine".to_string(),
+        0x0E12FF0F => "S1 Normal".to_string(),
+        0x0F13FF0F => "S2 Fine".to_string(),
+        0x0F12FF0F => "S2 Normal".to_string(),
+        0x1013FF0F => "S3 Fine".to_string(),
+        0x00640013 => "RAW + L Fine".to_string(),
+        0x00640012 => "RAW + L Normal".to_string(),
+        0x00640113 => "RAW + M Fine".to_string(),
+        0x00640112 => "RAW + M Normal".to_string(),
+        0x00640213 => "RAW + S Fine".to_string(),
+        0x00640212 => "RAW + S Normal".to_string(),
+        0x00630013 => "C-RAW + L Fine".to_string(),
+        0x00630012 => "C-RAW + L Normal".to_string(),
+        _ => format!("Quality {value:#X}"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -367,6 +439,39 @@ mod tests {
         assert_eq!(translate_exposure_comp(16), "+2.0");
     }
 
+    #[test]
+    fn translates_picture_style_values() {
+        assert_eq!(translate_picture_style(0x81), "Standard");
+        assert_eq!(translate_picture_style(0x82), "Portrait");
+        assert_eq!(translate_picture_style(0x83), "Landscape");
+        assert_eq!(translate_picture_style(0x84), "Neutral");
+        assert_eq!(translate_picture_style(0x85), "Faithful");
+        assert_eq!(translate_picture_style(0x86), "Monochrome");
+        assert_eq!(translate_picture_style(0x21), "User Defined 1");
+    }
+
+    #[test]
+    fn translates_unknown_picture_style_with_hex() {
+        let label = translate_picture_style(0x41);
+        assert!(label.contains("0x41"), "got: {label}");
+    }
+
+    #[test]
+    fn translates_image_quality_values() {
+        assert_eq!(translate_image_quality(0x0064FF0F), "RAW");
+        assert_eq!(translate_image_quality(0x0013FF0F), "L Fine");
+        assert_eq!(translate_image_quality(0x0112FF0F), "M Normal");
+        assert_eq!(translate_image_quality(0x0213FF0F), "S Fine");
+        assert_eq!(translate_image_quality(0x00640013), "RAW + L Fine");
+        assert_eq!(translate_image_quality(0x00640212), "RAW + S Normal");
+    }
+
+    #[test]
+    fn translates_unknown_image_quality_with_hex() {
+        let label = translate_image_quality(0x0765FF0F);
+        assert!(label.contains("0x765FF0F"), "got: {label}");
+    }
+
     #[test]
     fn get_canon_controls_returns_descriptors() {
         let mock = MockEdsSdk::new()
@@ -400,6 +505,20 @@ mod tests {
         assert_eq!(options[2].label, "400");
     }
 
+    #[test]
+    fn control_ids_map_to_their_properties() {
+        assert_eq!(control_property("canon_iso"), Some(PROP_ID_ISO_SPEED));
+        assert_eq!(
+            control_property("canon_white_balance"),
+            Some(PROP_ID_WHITE_BALANCE)
+        );
+        assert_eq!(
+            control_property("canon_picture_style"),
+            Some(PROP_ID_PICTURE_STYLE)
+        );
+        assert_eq!(control_property("brightness"), None);
+    }
+
     #[test]
     fn controls_have_correct_groups() {
         let mock = MockEdsSdk::new()
@@ -412,6 +531,54 @@ mod tests {
         assert_eq!(iso.group, "camera");
     }
 
+    #[test]
+    fn picture_style_and_image_quality_are_selects() {
+        let mock = MockEdsSdk::new()
+            .with_cameras(1)
+            .with_property(0, PROP_ID_PICTURE_STYLE, 0x81)
+            .with_property_desc(0, PROP_ID_PICTURE_STYLE, vec![0x81, 0x82, 0x86])
+            .with_property(0, PROP_ID_IMAGE_QUALITY, 0x0013FF0F)
+            .with_property_desc(
+                0,
+                PROP_ID_IMAGE_QUALITY,
+                vec![0x0064FF0F, 0x0013FF0F, 0x00640013],
+            );
+
+        let controls = get_canon_controls(&mock, CameraHandle(0)).unwrap();
+
+        let style = controls
+            .iter()
+            .find(|c| c.id == "canon_picture_style")
+            .unwrap();
+        assert_eq!(style.name, "Picture Style");
+        assert_eq!(style.control_type, ControlType::Select);
+        assert_eq!(style.current, 0x81);
+        let labels: Vec<_> = style
+            .options
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|o| o.label.as_str())
+            .collect();
+        assert_eq!(labels, ["Standard", "Portrait", "Monochrome"]);
+
+        let quality = controls
+            .iter()
+            .find(|c| c.id == "canon_image_quality")
+            .unwrap();
+        assert_eq!(quality.name, "Image Quality");
+        assert_eq!(quality.control_type, ControlType::Select);
+        assert_eq!(quality.current, 0x0013FF0F);
+        let labels: Vec<_> = quality
+            .options
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|o| o.label.as_str())
+            .collect();
+        assert_eq!(labels, ["RAW", "L Fine", "RAW + L Fine"]);
+    }
+
     #[test]
     fn exposure_compensation_is_slider_type() {
         let mock =
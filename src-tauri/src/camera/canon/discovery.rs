@@ -4,7 +4,7 @@
 //! instances with `canon:<port>` device IDs.
 
 use crate::camera::error::Result;
-use crate::camera::types::{CameraDevice, DeviceId};
+use crate::camera::types::{CameraDevice, DeviceId, DeviceKind, PhysicalIdentity};
 
 use super::api::{CameraHandle, EdsSdkApi};
 
@@ -35,6 +35,15 @@ pub fn discover_cameras<S: EdsSdkApi>(sdk: &S) -> Result<Vec<(CameraHandle, Came
                         name: model.clone(),
                         device_path: format!("edsdk://{model}"),
                         is_connected: true,
+                        kind: DeviceKind::Primary,
+                        primary_id: None,
+                        // EDSDK only reports the body's serial once a
+                        // session is open
+                        identity: Some(PhysicalIdentity {
+                            serial: None,
+                            model: Some(model.clone()),
+                        }),
+                        suppressed_by: None,
                     },
                 ));
             }
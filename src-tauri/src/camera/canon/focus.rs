@@ -0,0 +1,234 @@
+//! Live view focus control — tap-to-focus and AF point placement.
+//!
+//! The frontend reports clicks as normalised `[0, 1]` coordinates in the
+//! live view frame. EDSDK positions the AF (zoom) rectangle by its top-left
+//! corner in the camera's EVF coordinate system, which is usually the full
+//! sensor size rather than the live view resolution.
+
+use crate::camera::error::{CameraError, Result};
+
+use super::api::{CameraHandle, EdsSdkApi};
+use super::types::{
+    EdsPoint, EvfGeometry, CAMERA_COMMAND_DO_EVF_AF, EVF_AF_OFF, EVF_AF_ON,
+    PROP_ID_EVF_ZOOM_POSITION,
+};
+
+/// Map a normalised live view point to the zoom position that centres the
+/// AF rectangle on it.
+///
+/// Points are clamped so the rectangle stays inside the frame — a click at
+/// the very edge focuses as close to it as the camera allows. Returns an
+/// error when live view isn't active (`geometry` is `None`) or the point
+/// isn't finite.
+pub fn af_zoom_position(x: f32, y: f32, geometry: Option<EvfGeometry>) -> Result<EdsPoint> {
+    let geometry = geometry.ok_or_else(|| {
+        CameraError::CanonSdkError("live view is not active — start the preview first".into())
+    })?;
+    if !x.is_finite() || !y.is_finite() {
+        return Err(CameraError::ControlWrite(format!(
+            "AF point must be finite, got ({x}, {y})"
+        )));
+    }
+
+    let frame = geometry.coordinate_system;
+    let rect = geometry.zoom_rect.size;
+    if frame.width <= 0 || frame.height <= 0 {
+        return Err(CameraError::CanonSdkError(format!(
+            "invalid EVF coordinate system {}x{}",
+            frame.width, frame.height
+        )));
+    }
+
+    Ok(EdsPoint {
+        x: centre_on(x, frame.width, rect.width),
+        y: centre_on(y, frame.height, rect.height),
+    })
+}
+
+/// Top-left offset along one axis that centres a `rect`-long span on the
+/// normalised position `t` within `extent`, kept inside `[0, extent - rect]`.
+fn centre_on(t: f32, extent: i32, rect: i32) -> i32 {
+    let rect = rect.clamp(0, extent);
+    let centre = f64::from(t.clamp(0.0, 1.0)) * f64::from(extent);
+    let offset = (centre - f64::from(rect) / 2.0).round() as i32;
+    offset.clamp(0, extent - rect)
+}
+
+/// Move the AF point to a normalised live view position.
+///
+/// Returns the zoom position written to the camera.
+pub fn set_af_point<S: EdsSdkApi + ?Sized>(
+    sdk: &S,
+    camera: CameraHandle,
+    x: f32,
+    y: f32,
+) -> Result<EdsPoint> {
+    let position = af_zoom_position(x, y, sdk.evf_geometry(camera)?)?;
+    sdk.set_point_property(camera, PROP_ID_EVF_ZOOM_POSITION, position)?;
+    Ok(position)
+}
+
+/// Run one live view autofocus pass at the current AF point.
+///
+/// EDSDK keeps focusing until AF is switched off again, so this sends the
+/// on/off pair. Fails if live view isn't active.
+pub fn trigger_af<S: EdsSdkApi + ?Sized>(sdk: &S, camera: CameraHandle) -> Result<()> {
+    if sdk.evf_geometry(camera)?.is_none() {
+        return Err(CameraError::CanonSdkError(
+            "live view is not active — start the preview first".into(),
+        ));
+    }
+    sdk.send_command(camera, CAMERA_COMMAND_DO_EVF_AF, EVF_AF_ON)?;
+    sdk.send_command(camera, CAMERA_COMMAND_DO_EVF_AF, EVF_AF_OFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::canon::mock::MockEdsSdk;
+    use crate::camera::canon::types::{EdsRect, EdsSize};
+
+    /// EOS R-style geometry: 6000x4000 coordinate system, 1200x800 AF rect.
+    fn geometry() -> EvfGeometry {
+        EvfGeometry {
+            coordinate_system: EdsSize {
+                width: 6000,
+                height: 4000,
+            },
+            zoom_rect: EdsRect {
+                point: EdsPoint { x: 2400, y: 1600 },
+                size: EdsSize {
+                    width: 1200,
+                    height: 800,
+                },
+            },
+        }
+    }
+
+    fn live_mock() -> MockEdsSdk {
+        let mock = MockEdsSdk::new()
+            .with_cameras(1)
+            .with_evf_geometry(0, geometry());
+        mock.start_live_view(CameraHandle(0)).unwrap();
+        mock
+    }
+
+    #[test]
+    fn centre_click_centres_af_rect() {
+        let p = af_zoom_position(0.5, 0.5, Some(geometry())).unwrap();
+        assert_eq!(p, EdsPoint { x: 2400, y: 1600 });
+    }
+
+    #[test]
+    fn click_maps_through_coordinate_system_not_live_view_pixels() {
+        // 25% across, 75% down → centre at (1500, 3000)
+        let p = af_zoom_position(0.25, 0.75, Some(geometry())).unwrap();
+        assert_eq!(p, EdsPoint { x: 900, y: 2600 });
+    }
+
+    #[test]
+    fn clicks_near_edges_keep_rect_inside_frame() {
+        let g = Some(geometry());
+        assert_eq!(
+            af_zoom_position(0.0, 0.0, g).unwrap(),
+            EdsPoint { x: 0, y: 0 }
+        );
+        assert_eq!(
+            af_zoom_position(1.0, 1.0, g).unwrap(),
+            EdsPoint { x: 4800, y: 3200 }
+        );
+        assert_eq!(
+            af_zoom_position(0.02, 0.98, g).unwrap(),
+            EdsPoint { x: 0, y: 3200 }
+        );
+    }
+
+    #[test]
+    fn out_of_range_clicks_are_clamped() {
+        let g = Some(geometry());
+        assert_eq!(
+            af_zoom_position(-0.5, 1.5, g).unwrap(),
+            EdsPoint { x: 0, y: 3200 }
+        );
+    }
+
+    #[test]
+    fn rejects_when_live_view_not_active() {
+        let err = af_zoom_position(0.5, 0.5, None).unwrap_err();
+        assert!(err.to_string().contains("live view is not active"));
+    }
+
+    #[test]
+    fn rejects_non_finite_points() {
+        assert!(af_zoom_position(f32::NAN, 0.5, Some(geometry())).is_err());
+        assert!(af_zoom_position(0.5, f32::INFINITY, Some(geometry())).is_err());
+    }
+
+    #[test]
+    fn rect_larger_than_frame_is_pinned_to_origin() {
+        let mut g = geometry();
+        g.zoom_rect.size = EdsSize {
+            width: 8000,
+            height: 5000,
+        };
+        assert_eq!(
+            af_zoom_position(0.7, 0.3, Some(g)).unwrap(),
+            EdsPoint { x: 0, y: 0 }
+        );
+    }
+
+    #[test]
+    fn set_af_point_writes_zoom_position() {
+        let mock = live_mock();
+        let written = set_af_point(&mock, CameraHandle(0), 0.25, 0.75).unwrap();
+
+        assert_eq!(
+            mock.point_writes(0, PROP_ID_EVF_ZOOM_POSITION),
+            vec![written]
+        );
+        assert_eq!(written, EdsPoint { x: 900, y: 2600 });
+    }
+
+    #[test]
+    fn set_af_point_rejected_when_live_view_stopped() {
+        let mock = live_mock();
+        mock.stop_live_view(CameraHandle(0)).unwrap();
+
+        assert!(set_af_point(&mock, CameraHandle(0), 0.5, 0.5).is_err());
+        assert!(mock.point_writes(0, PROP_ID_EVF_ZOOM_POSITION).is_empty());
+    }
+
+    #[test]
+    fn trigger_af_sends_on_then_off() {
+        let mock = live_mock();
+        trigger_af(&mock, CameraHandle(0)).unwrap();
+        assert_eq!(
+            mock.commands_sent(),
+            vec![
+                (CameraHandle(0), CAMERA_COMMAND_DO_EVF_AF, EVF_AF_ON),
+                (CameraHandle(0), CAMERA_COMMAND_DO_EVF_AF, EVF_AF_OFF),
+            ]
+        );
+    }
+
+    #[test]
+    fn trigger_af_rejected_without_live_view() {
+        let mock = MockEdsSdk::new()
+            .with_cameras(1)
+            .with_evf_geometry(0, geometry());
+        assert!(trigger_af(&mock, CameraHandle(0)).is_err());
+        assert!(mock.commands_sent().is_empty());
+    }
+
+    #[test]
+    fn sdk_errors_propagate() {
+        let mock = live_mock().with_error(
+            "set_point_property",
+            CameraError::CanonDeviceBusy("busy".into()),
+        );
+        assert!(matches!(
+            set_af_point(&mock, CameraHandle(0), 0.5, 0.5),
+            Err(CameraError::CanonDeviceBusy(_))
+        ));
+    }
+}
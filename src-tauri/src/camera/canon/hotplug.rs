@@ -1,24 +1,27 @@
 //! Canon hotplug detection via periodic re-enumeration.
 //!
 //! EDSDK doesn't have a reliable push-based connection notification,
-//! so we poll for camera list changes at regular intervals.
+//! so we poll for camera list changes at regular intervals, diff the
+//! cameras found against those known and report the difference as
+//! `HotplugEvent`s. Stopping or dropping the watcher wakes the thread
+//! straight away rather than waiting out the interval.
 
 use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
 use crate::camera::canon::api::EdsSdkApi;
 use crate::camera::canon::discovery::discover_cameras;
-use crate::camera::types::{DeviceId, HotplugEvent};
+use crate::camera::types::{CameraDevice, DeviceId, HotplugEvent};
 
 /// Default re-enumeration interval.
-const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(3);
 
-/// Hotplug watcher for Canon cameras.
+/// Hotplug watcher for Canon cameras. Stops when dropped.
 pub struct CanonHotplugWatcher {
-    running: Arc<AtomicBool>,
+    stop: Option<Sender<()>>,
     thread: Option<JoinHandle<()>>,
 }
 
@@ -31,42 +34,51 @@ impl CanonHotplugWatcher {
         Self::start_with_interval(sdk, callback, DEFAULT_POLL_INTERVAL)
     }
 
-    /// Start with a custom polling interval (useful for testing).
+    /// Start, re-enumerating every `interval`.
     pub fn start_with_interval<S: EdsSdkApi + 'static>(
         sdk: Arc<S>,
         callback: Box<dyn Fn(HotplugEvent) + Send>,
         interval: Duration,
     ) -> Self {
-        let running = Arc::new(AtomicBool::new(true));
-        let running_clone = Arc::clone(&running);
+        let (stop, stopped) = mpsc::channel();
 
         let thread = std::thread::Builder::new()
             .name("canon-hotplug".to_string())
             .spawn(move || {
-                poll_connections(&*sdk, &callback, &running_clone, interval);
+                poll_connections(&*sdk, &callback, &stopped, interval);
             })
             .expect("failed to spawn Canon hotplug thread");
 
         Self {
-            running,
+            stop: Some(stop),
             thread: Some(thread),
         }
     }
 
-    /// Stop the hotplug watcher.
+    /// Stop the hotplug watcher and wait for its thread. Idempotent.
     pub fn stop(&mut self) {
-        self.running.store(false, Ordering::Relaxed);
+        // Dropping the sender wakes the thread too
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
         if let Some(handle) = self.thread.take() {
             let _ = handle.join();
         }
     }
 }
 
-/// Polling loop that detects connection/disconnection events.
+impl Drop for CanonHotplugWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Polling loop that detects connection/disconnection events until told
+/// to stop.
 fn poll_connections<S: EdsSdkApi>(
     sdk: &S,
     callback: &dyn Fn(HotplugEvent),
-    running: &AtomicBool,
+    stop: &Receiver<()>,
     interval: Duration,
 ) {
     let mut known_ids: HashSet<DeviceId> = HashSet::new();
@@ -81,11 +93,10 @@ fn poll_connections<S: EdsSdkApi>(
     // Also process EDSDK events each cycle
     let _ = sdk.get_event();
 
-    while running.load(Ordering::Relaxed) {
-        std::thread::sleep(interval);
-
-        if !running.load(Ordering::Relaxed) {
-            break;
+    loop {
+        match stop.recv_timeout(interval) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
         }
 
         // Process pending EDSDK events
@@ -98,27 +109,43 @@ fn poll_connections<S: EdsSdkApi>(
                 continue;
             }
         };
+        let current: Vec<CameraDevice> = current.into_iter().map(|(_, d)| d).collect();
 
-        let current_ids: HashSet<DeviceId> = current.iter().map(|(_, d)| d.id.clone()).collect();
-
-        // Detect new connections
-        for (_, device) in &current {
-            if !known_ids.contains(&device.id) {
-                tracing::info!("Canon camera connected: {}", device.name);
-                callback(HotplugEvent::Connected(device.clone()));
+        for event in diff_cameras(&mut known_ids, &current) {
+            match &event {
+                HotplugEvent::Connected(device) => {
+                    tracing::info!("Canon camera connected: {}", device.name);
+                }
+                HotplugEvent::Disconnected { id } => {
+                    tracing::info!("Canon camera disconnected: {id}");
+                }
             }
+            callback(event);
         }
+    }
+}
 
-        // Detect disconnections
-        for id in &known_ids {
-            if !current_ids.contains(id) {
-                tracing::info!("Canon camera disconnected: {id}");
-                callback(HotplugEvent::Disconnected { id: id.clone() });
-            }
-        }
+/// Events for the difference between the cameras `known` and those found
+/// now, `current`, which then become the known ones. Connections come
+/// first, in enumeration order, then disconnections in ID order.
+fn diff_cameras(known: &mut HashSet<DeviceId>, current: &[CameraDevice]) -> Vec<HotplugEvent> {
+    let current_ids: HashSet<DeviceId> = current.iter().map(|d| d.id.clone()).collect();
 
-        known_ids = current_ids;
-    }
+    let mut events: Vec<HotplugEvent> = current
+        .iter()
+        .filter(|device| !known.contains(&device.id))
+        .map(|device| HotplugEvent::Connected(device.clone()))
+        .collect();
+
+    let mut gone: Vec<&DeviceId> = known.difference(&current_ids).collect();
+    gone.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    events.extend(
+        gone.into_iter()
+            .map(|id| HotplugEvent::Disconnected { id: id.clone() }),
+    );
+
+    *known = current_ids;
+    events
 }
 
 #[cfg(test)]
@@ -126,6 +153,113 @@ mod tests {
     use super::*;
     use crate::camera::canon::mock::MockEdsSdk;
     use std::sync::Mutex;
+    use std::time::Instant;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn describe(event: &HotplugEvent) -> String {
+        match event {
+            HotplugEvent::Connected(device) => format!("connected {}", device.id),
+            HotplugEvent::Disconnected { id } => format!("disconnected {id}"),
+        }
+    }
+
+    fn devices(sdk: &MockEdsSdk) -> Vec<CameraDevice> {
+        discover_cameras(sdk)
+            .unwrap()
+            .into_iter()
+            .map(|(_, device)| device)
+            .collect()
+    }
+
+    /// A watcher polling every 10ms that sends what it reports down a
+    /// channel.
+    fn watch(mock: &Arc<MockEdsSdk>) -> (CanonHotplugWatcher, Receiver<String>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let watcher = CanonHotplugWatcher::start_with_interval(
+            Arc::clone(mock),
+            Box::new(move |event| {
+                let _ = tx.lock().unwrap().send(describe(&event));
+            }),
+            Duration::from_millis(10),
+        );
+        (watcher, rx)
+    }
+
+    #[test]
+    fn diff_reports_connections_then_disconnections() {
+        let mock = MockEdsSdk::new()
+            .with_camera("Canon EOS R5", Some("SER001"))
+            .with_camera("Canon EOS R6", Some("SER002"));
+        let mut known: HashSet<DeviceId> = devices(&mock).into_iter().map(|d| d.id).collect();
+
+        mock.remove_camera("SER001");
+        mock.remove_camera("SER002");
+        mock.add_camera("Canon EOS R8", Some("SER003"));
+        mock.add_camera("Canon EOS R3", Some("SER004"));
+        let events: Vec<String> = diff_cameras(&mut known, &devices(&mock))
+            .iter()
+            .map(describe)
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "connected canon:SER003",
+                "connected canon:SER004",
+                "disconnected canon:SER001",
+                "disconnected canon:SER002",
+            ]
+        );
+
+        // Nothing changed since
+        assert!(diff_cameras(&mut known, &devices(&mock)).is_empty());
+    }
+
+    #[test]
+    fn cameras_plugged_in_and_out_are_reported_in_order() {
+        let mock = Arc::new(MockEdsSdk::new().with_camera("Canon EOS R5", Some("SER001")));
+        let (mut watcher, events) = watch(&mock);
+        // Events are first processed after the initial enumeration
+        let deadline = Instant::now() + WAIT;
+        while mock.events_processed() == 0 {
+            assert!(Instant::now() < deadline, "watcher never started");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        mock.add_camera("Canon EOS R6", Some("SER002"));
+        assert_eq!(events.recv_timeout(WAIT).unwrap(), "connected canon:SER002");
+
+        assert!(mock.remove_camera("SER001"));
+        assert_eq!(
+            events.recv_timeout(WAIT).unwrap(),
+            "disconnected canon:SER001"
+        );
+
+        // Plugged back in, under a new handle but the same device ID
+        mock.add_camera("Canon EOS R5", Some("SER001"));
+        assert_eq!(events.recv_timeout(WAIT).unwrap(), "connected canon:SER001");
+
+        watcher.stop();
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn dropping_the_watcher_stops_it_without_waiting_out_the_interval() {
+        let mock = Arc::new(MockEdsSdk::new());
+        let watcher = CanonHotplugWatcher::start_with_interval(
+            Arc::clone(&mock),
+            Box::new(|_| {}),
+            Duration::from_secs(60),
+        );
+        std::thread::sleep(Duration::from_millis(20));
+
+        let started = Instant::now();
+        drop(watcher);
+        assert!(started.elapsed() < WAIT);
+        // The thread has exited and let go of the SDK
+        assert_eq!(Arc::strong_count(&mock), 1);
+    }
 
     #[test]
     fn detects_initial_cameras() {
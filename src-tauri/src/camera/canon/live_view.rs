@@ -1,21 +1,28 @@
-//! Canon live view polling thread.
+//! Canon live view as a capture source.
 //!
-//! Spawns a thread that polls `download_evf_image()` at configurable
-//! intervals and pushes JPEG frames directly into a `JpegFrameBuffer`.
-//! Canon live view delivers JPEG natively, so no encoding step is needed.
+//! Live view runs as the capture graph of an ordinary preview session: a
+//! thread polls `download_evf_image()` at the session's frame rate, decodes
+//! each JPEG to RGB and delivers it into the session's `FrameBuffer`, so the
+//! encode worker, watchdog, diagnostics and every frame consumer treat Canon
+//! frames like any other camera's.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
 use crate::camera::canon::api::{CameraHandle, EdsSdkApi};
-use crate::preview::encode_worker::{JpegFrame, JpegFrameBuffer};
-use crate::preview::mf_jpeg::encoder::EncoderKind;
+use crate::diagnostics::accounting::{CpuTimer, CpuWork};
+use crate::diagnostics::stats::DropReason;
+use crate::preview::capture::{Frame, GraphContext, GraphRunner, PreviewStartedPayload};
+use crate::preview::mode::NegotiatedFormat;
 
-/// Default polling interval for live view frames (~5fps).
+/// Polling interval when the session asks for no particular rate (~5fps).
 const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
+/// Fastest the SDK is polled, whatever rate is asked for. Canon bodies
+/// don't refresh live view much beyond 30fps.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(33);
+
 /// How long without a successful frame before logging a stuck warning.
 const STUCK_THRESHOLD: Duration = Duration::from_secs(5);
 
@@ -115,84 +122,6 @@ enum ErrorAction {
     LogStuckAndError { seconds: u64, last_error: String },
 }
 
-/// Active live view session for a Canon camera.
-pub struct LiveViewSession {
-    running: Arc<AtomicBool>,
-    thread: Option<JoinHandle<()>>,
-    jpeg_buffer: Arc<JpegFrameBuffer>,
-}
-
-impl LiveViewSession {
-    /// Start a live view session.
-    ///
-    /// Opens a session with the camera, enables EVF output, then spawns a
-    /// polling thread that downloads JPEG frames into the provided buffer.
-    pub fn start<S: EdsSdkApi + 'static>(
-        sdk: Arc<S>,
-        camera: CameraHandle,
-        jpeg_buffer: Arc<JpegFrameBuffer>,
-    ) -> crate::camera::error::Result<Self> {
-        Self::start_with_interval(sdk, camera, jpeg_buffer, DEFAULT_POLL_INTERVAL)
-    }
-
-    /// Start with a custom polling interval (useful for testing).
-    ///
-    /// The caller must ensure that a session is already open for the camera
-    /// (e.g. via `CanonBackend::enumerate_devices`). This method only starts
-    /// live view output and the polling thread.
-    pub fn start_with_interval<S: EdsSdkApi + 'static>(
-        sdk: Arc<S>,
-        camera: CameraHandle,
-        jpeg_buffer: Arc<JpegFrameBuffer>,
-        interval: Duration,
-    ) -> crate::camera::error::Result<Self> {
-        sdk.start_live_view(camera)?;
-
-        let running = Arc::new(AtomicBool::new(true));
-        let running_clone = Arc::clone(&running);
-        let buffer_clone = Arc::clone(&jpeg_buffer);
-
-        let thread = std::thread::Builder::new()
-            .name(format!("canon-lv-{}", camera.0))
-            .spawn(move || {
-                poll_live_view(&*sdk, camera, &buffer_clone, &running_clone, interval);
-            })
-            .map_err(|e| {
-                crate::camera::error::CameraError::CanonSdkError(format!(
-                    "failed to spawn live view thread: {e}"
-                ))
-            })?;
-
-        Ok(Self {
-            running,
-            thread: Some(thread),
-            jpeg_buffer,
-        })
-    }
-
-    /// Get a reference to the JPEG frame buffer.
-    pub fn jpeg_buffer(&self) -> &Arc<JpegFrameBuffer> {
-        &self.jpeg_buffer
-    }
-
-    /// Check if the session is still running.
-    pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::Relaxed)
-    }
-
-    /// Stop the live view session.
-    ///
-    /// Stops the polling thread and disables EVF output. Does not close the
-    /// camera session — that is managed by `CanonBackend`.
-    pub fn stop<S: EdsSdkApi>(self, sdk: &S, camera: CameraHandle) {
-        self.running.store(false, Ordering::Relaxed);
-        if let Some(handle) = self.thread {
-            let _ = handle.join();
-        }
-        let _ = sdk.stop_live_view(camera);
-    }
-}
-
 /// RAII guard for COM on the live view thread.
 #[cfg(target_os = "windows")]
 struct LiveViewComGuard {
@@ -225,44 +154,64 @@ impl Drop for LiveViewComGuard {
     }
 }
 
-/// Polling loop that runs on the live view thread.
+/// How often to poll for frames to deliver `fps` frames per second.
+fn poll_interval(fps: f32) -> Duration {
+    if fps.is_finite() && fps > 0.0 {
+        Duration::from_secs_f64(1.0 / f64::from(fps)).max(MIN_POLL_INTERVAL)
+    } else {
+        DEFAULT_POLL_INTERVAL
+    }
+}
+
+/// Capture graph running `camera`'s live view, for
+/// [`CaptureSession::with_runner`](crate::preview::capture::CaptureSession::with_runner).
+pub fn live_view_runner<S: EdsSdkApi + 'static>(sdk: Arc<S>, camera: CameraHandle) -> GraphRunner {
+    Arc::new(move |ctx: &GraphContext| run_live_view(&*sdk, camera, ctx))
+}
+
+/// Start live view and deliver its frames into `ctx` until `ctx.running`
+/// is cleared, then stop it again.
 ///
+/// The caller must ensure that a session is already open for the camera
+/// (e.g. via `CanonBackend::enumerate_devices`); it isn't closed here.
 /// Initialises COM STA on this thread (EDSDK requires COM on every calling
-/// thread) before entering the download loop.
-fn poll_live_view<S: EdsSdkApi>(
+/// thread) before starting.
+fn run_live_view<S: EdsSdkApi + ?Sized>(
     sdk: &S,
     camera: CameraHandle,
-    jpeg_buffer: &JpegFrameBuffer,
-    running: &AtomicBool,
-    interval: Duration,
-) {
+    ctx: &GraphContext,
+) -> Result<(), String> {
     // EDSDK requires COM STA on every thread that calls it.
     #[cfg(target_os = "windows")]
     let _com = LiveViewComGuard::init();
 
-    let mut stats = PollStats::new(Instant::now());
+    sdk.start_live_view(camera)
+        .map_err(|e| format!("failed to start Canon live view: {e}"))?;
+    ctx.running.store(true, Ordering::Relaxed);
+    tracing::info!("Started Canon live view for {}", ctx.device_id);
+
+    let interval = poll_interval(ctx.fps);
+    let epoch = Instant::now();
+    let mut stats = PollStats::new(epoch);
 
-    while running.load(Ordering::Relaxed) {
+    while ctx.running.load(Ordering::Relaxed) {
+        let polled = Instant::now();
         match sdk.download_evf_image(camera) {
-            Ok(jpeg_data) => {
-                let size = jpeg_data.len();
-                // Canon live view delivers JPEG natively — push directly
-                // into the JPEG buffer, bypassing RGB encoding entirely.
-                jpeg_buffer.update(JpegFrame {
-                    jpeg_bytes: jpeg_data,
-                    width: 960,  // Canon live view typical resolution
-                    height: 640, // Canon live view typical resolution
-                    encoder_kind: EncoderKind::CpuFallback, // Not really encoded, just a label
-                });
-
-                match stats.on_frame(size, Instant::now()) {
-                    FrameAction::LogFirstFrame { size } => {
-                        tracing::info!("Canon live view: first frame received ({size} bytes)");
-                    }
-                    FrameAction::LogPeriodicCount { count } => {
-                        tracing::debug!("Canon live view: {count} frames delivered");
-                    }
-                    FrameAction::None => {}
+            Ok(jpeg) => {
+                let timer = CpuTimer::start();
+                let delivered = deliver_evf_frame(ctx, &jpeg, epoch);
+                ctx.cpu.record(CpuWork::Capture, timer.elapsed());
+                match delivered {
+                    Ok(()) => match stats.on_frame(jpeg.len(), Instant::now()) {
+                        FrameAction::LogFirstFrame { size } => {
+                            tracing::info!("Canon live view: first frame received ({size} bytes)");
+                        }
+                        FrameAction::LogPeriodicCount { count } => {
+                            tracing::debug!("Canon live view: {count} frames delivered");
+                        }
+                        FrameAction::None => {}
+                    },
+                    Err(e) => tracing::debug!("Canon live view: dropped frame: {e}"),
                 }
             }
             Err(e) => {
@@ -291,8 +240,84 @@ fn poll_live_view<S: EdsSdkApi>(
                 }
             }
         }
-        std::thread::sleep(interval);
+        // Time spent downloading and decoding counts towards the interval
+        std::thread::sleep(interval.saturating_sub(polled.elapsed()));
+    }
+
+    match sdk.stop_live_view(camera) {
+        Ok(()) => tracing::info!("Stopped Canon live view for {}", ctx.device_id),
+        Err(e) => tracing::debug!("Canon live view: failed to stop: {e}"),
+    }
+    Ok(())
+}
+
+/// Decode one live view JPEG and deliver it like a captured frame: into the
+/// ring, to the encode worker and into the session's stats. The first frame,
+/// and any at a new size, set the session's negotiated format.
+fn deliver_evf_frame(ctx: &GraphContext, jpeg: &[u8], epoch: Instant) -> Result<(), String> {
+    let started = Instant::now();
+    let image = match image::load_from_memory_with_format(jpeg, image::ImageFormat::Jpeg) {
+        Ok(image) => image.into_rgb8(),
+        Err(e) => {
+            ctx.stats.lock().record_drop(DropReason::DecodeFailed);
+            return Err(format!("failed to decode live view frame: {e}"));
+        }
+    };
+    let (width, height) = image.dimensions();
+
+    let resized = {
+        let mut negotiated = ctx.negotiated.lock();
+        let unchanged = negotiated
+            .as_ref()
+            .is_some_and(|format| (format.width, format.height) == (width, height));
+        if unchanged {
+            None
+        } else {
+            let format = NegotiatedFormat {
+                width,
+                height,
+                pixel_format: "JPEG".to_string(),
+            };
+            *negotiated = Some(format.clone());
+            Some(format)
+        }
+    };
+    if let (Some(format), Some(callback)) = (resized, &ctx.on_started) {
+        callback(PreviewStartedPayload::new(&ctx.device_id, format));
+    }
+
+    // Live view frames carry no timestamp of their own
+    let timestamp_us = epoch.elapsed().as_micros() as u64;
+    let rgb = image.into_raw();
+    let frame_bytes = rgb.len();
+    let worker_rgb = ctx.frame_sender.as_ref().map(|_| rgb.clone());
+    // Counted before it's published, so the stats never lag the buffer's
+    // sequence
+    {
+        let mut stats = ctx.stats.lock();
+        stats.record_frame(frame_bytes, timestamp_us);
+        stats.record_conversion(started.elapsed());
+    }
+    let sequence = ctx.buffer.push(Frame {
+        data: rgb,
+        width,
+        height,
+        timestamp_us,
+        device_timestamp_us: timestamp_us,
+    });
+    if let (Some(sender), Some(worker_rgb)) = (&ctx.frame_sender, worker_rgb) {
+        sender.send(
+            Frame {
+                data: worker_rgb,
+                width,
+                height,
+                timestamp_us,
+                device_timestamp_us: timestamp_us,
+            },
+            sequence,
+        );
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -300,119 +325,157 @@ mod tests {
     use super::*;
     use crate::camera::canon::mock::MockEdsSdk;
 
-    /// Minimal valid JPEG for testing.
+    use crate::camera::error::CameraError;
+    use crate::preview::capture::{CaptureSession, ErrorCallback, StartedCallback};
+    use crate::preview::mode::PreviewFormat;
+    use crate::supervisor::RestartPolicy;
+    use parking_lot::Mutex;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    /// A 48x32 live view frame.
     fn test_jpeg() -> Vec<u8> {
-        vec![0xFF, 0xD8, 0xFF, 0xD9]
+        crate::preview::compress::compress_jpeg(&[128; 48 * 32 * 3], 48, 32, 90)
     }
 
-    #[test]
-    fn live_view_session_pushes_frames_to_jpeg_buffer() {
-        let mock = Arc::new(
-            MockEdsSdk::new()
-                .with_cameras(1)
-                .with_live_view_frame(test_jpeg()),
-        );
-        let jpeg_buffer = Arc::new(JpegFrameBuffer::new());
-        let camera = CameraHandle(0);
-
-        let session = LiveViewSession::start_with_interval(
-            Arc::clone(&mock),
-            camera,
-            Arc::clone(&jpeg_buffer),
-            Duration::from_millis(10),
+    fn live_view_session(
+        mock: &Arc<MockEdsSdk>,
+        on_error: Option<ErrorCallback>,
+        on_started: Option<StartedCallback>,
+    ) -> CaptureSession {
+        CaptureSession::with_runner(
+            "canon:MOCK0001".to_string(),
+            String::new(),
+            String::new(),
+            PreviewFormat {
+                width: 640,
+                height: 480,
+                fps: 100.0,
+            },
+            RestartPolicy {
+                max_attempts: 2,
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(10),
+            },
+            on_error,
+            on_started,
+            75,
+            live_view_runner(Arc::clone(mock), CameraHandle(0)),
         )
-        .unwrap();
-
-        // Wait for a few frames
-        std::thread::sleep(Duration::from_millis(50));
-
-        assert!(session.is_running());
-        assert!(jpeg_buffer.sequence() > 0, "frames should have been pushed");
+    }
 
-        let frame = jpeg_buffer.latest().unwrap();
-        assert_eq!(frame.jpeg_bytes, test_jpeg());
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + WAIT;
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
 
-        session.stop(&*mock, camera);
+    #[test]
+    fn poll_interval_follows_the_frame_rate() {
+        assert_eq!(poll_interval(4.0), Duration::from_millis(250));
+        assert_eq!(poll_interval(120.0), MIN_POLL_INTERVAL);
+        assert_eq!(poll_interval(0.0), DEFAULT_POLL_INTERVAL);
+        assert_eq!(poll_interval(f32::NAN), DEFAULT_POLL_INTERVAL);
     }
 
     #[test]
-    fn live_view_session_stops_cleanly() {
+    fn live_view_frames_are_decoded_into_the_frame_buffer() {
         let mock = Arc::new(
             MockEdsSdk::new()
                 .with_cameras(1)
                 .with_live_view_frame(test_jpeg()),
         );
-        let jpeg_buffer = Arc::new(JpegFrameBuffer::new());
-        let camera = CameraHandle(0);
-
-        let session = LiveViewSession::start_with_interval(
-            Arc::clone(&mock),
-            camera,
-            Arc::clone(&jpeg_buffer),
-            Duration::from_millis(10),
-        )
-        .unwrap();
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let on_started: StartedCallback = {
+            let started = Arc::clone(&started);
+            Arc::new(move |payload| started.lock().push(payload))
+        };
+        let mut session = live_view_session(&mock, None, Some(on_started));
+
+        wait_for(|| session.buffer().sequence() >= 3 && session.diagnostics().frame_count >= 3);
+        assert!(session.is_running());
+        let frame = session.buffer().latest().unwrap();
+        assert_eq!((frame.width, frame.height), (48, 32));
+        assert_eq!(frame.data.len(), 48 * 32 * 3);
+
+        let format = session.negotiated_format().unwrap();
+        assert_eq!((format.width, format.height), (48, 32));
+        assert_eq!(format.pixel_format, "JPEG");
+        // Announced once, not per frame
+        assert_eq!(started.lock().len(), 1);
+
+        let diagnostics = session.diagnostics();
+        assert!(diagnostics.frame_count >= 3);
+        assert!(session.since_last_frame().is_some());
+        // The encode worker compresses them like any other camera's
+        wait_for(|| {
+            session
+                .jpeg_buffer()
+                .is_some_and(|buffer| buffer.sequence() > 0)
+        });
 
-        session.stop(&*mock, camera);
-        // Should not panic or hang
+        session.stop();
+        assert!(!session.is_running());
+        // Live view was stopped with the session
+        assert!(mock.download_evf_image(CameraHandle(0)).is_err());
     }
 
     #[test]
-    fn live_view_handles_object_not_ready() {
-        // No live view frame configured — download_evf_image will return error
-        // but the session should not crash
-        let mock = Arc::new(MockEdsSdk::new().with_cameras(1));
-        let jpeg_buffer = Arc::new(JpegFrameBuffer::new());
-        let camera = CameraHandle(0);
-
-        // Manually start live view on mock so download attempts proceed
-        mock.start_live_view(camera).unwrap();
-
-        let running = Arc::new(AtomicBool::new(true));
-        let running_clone = Arc::clone(&running);
-        let buffer_clone = Arc::clone(&jpeg_buffer);
-
-        let handle = std::thread::spawn(move || {
-            poll_live_view(
-                &*mock,
-                camera,
-                &buffer_clone,
-                &running_clone,
-                Duration::from_millis(5),
-            );
-        });
-
-        std::thread::sleep(Duration::from_millis(30));
-        running.store(false, Ordering::Relaxed);
-        handle.join().unwrap();
+    fn undecodable_frames_are_dropped() {
+        let mock = Arc::new(
+            MockEdsSdk::new()
+                .with_cameras(1)
+                .with_live_view_frame(vec![0xFF, 0xD8, 0xFF, 0xD9]),
+        );
+        let mut session = live_view_session(&mock, None, None);
 
-        // No frames should have been pushed (no frame data configured)
-        assert_eq!(jpeg_buffer.sequence(), 0);
+        wait_for(|| session.diagnostics().drops_by_reason.decode_failed > 0);
+        assert_eq!(session.buffer().sequence(), 0);
+        assert_eq!(session.negotiated_format(), None);
+        session.stop();
     }
 
     #[test]
-    fn live_view_starts_and_stops_without_managing_session() {
+    fn live_view_that_cannot_start_fails_the_session() {
+        // Busy on the first attempt and the restart
+        let busy = || CameraError::CanonSdkError("device busy".to_string());
         let mock = Arc::new(
             MockEdsSdk::new()
                 .with_cameras(1)
-                .with_live_view_frame(test_jpeg()),
+                .with_error("start_live_view", busy())
+                .with_error("start_live_view", busy()),
         );
-        let jpeg_buffer = Arc::new(JpegFrameBuffer::new());
-        let camera = CameraHandle(0);
-
-        let session = LiveViewSession::start_with_interval(
-            Arc::clone(&mock),
-            camera,
-            Arc::clone(&jpeg_buffer),
-            Duration::from_millis(10),
-        )
-        .unwrap();
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let on_error: ErrorCallback = {
+            let errors = Arc::clone(&errors);
+            Arc::new(move |payload| errors.lock().push(payload))
+        };
+        let mut session = live_view_session(&mock, Some(on_error), None);
+
+        wait_for(|| session.is_failed());
+        assert!(session
+            .last_error()
+            .unwrap()
+            .contains("failed to start Canon live view"));
+        wait_for(|| !errors.lock().is_empty());
+        session.stop();
+    }
 
-        // Session should be running (live view started, not camera session)
-        assert!(session.is_running());
+    #[test]
+    fn frames_are_not_polled_before_live_view_is_ready() {
+        // No live view frame configured: download_evf_image keeps failing,
+        // but the session keeps polling rather than failing
+        let mock = Arc::new(MockEdsSdk::new().with_cameras(1));
+        let mut session = live_view_session(&mock, None, None);
 
-        // Stop disables live view but does not close the camera session
-        session.stop(&*mock, camera);
+        wait_for(|| session.is_running());
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(session.is_running());
+        assert!(!session.is_failed());
+        assert_eq!(session.buffer().sequence(), 0);
+        session.stop();
     }
 
     // ------------------------------------------------------------------
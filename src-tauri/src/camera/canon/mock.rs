@@ -9,7 +9,9 @@ use std::sync::Mutex;
 use crate::camera::error::{CameraError, Result};
 
 use super::api::{CameraHandle, EdsSdkApi};
-use super::types::{EdsDeviceInfo, EdsPropertyDesc, EdsPropertyID};
+use super::types::{
+    EdsCameraCommand, EdsDeviceInfo, EdsPoint, EdsPropertyDesc, EdsPropertyID, EvfGeometry,
+};
 
 /// A simulated Canon camera in the mock.
 #[derive(Debug, Clone)]
@@ -18,7 +20,29 @@ struct MockCamera {
     serial: Option<String>,
     properties: HashMap<EdsPropertyID, i32>,
     property_descs: HashMap<EdsPropertyID, Vec<i32>>,
+    /// Every point-property write, in order.
+    point_writes: Vec<(EdsPropertyID, EdsPoint)>,
+    /// Geometry reported while live view is active.
+    evf_geometry: Option<EvfGeometry>,
     session_open: bool,
+    /// Cleared when the camera is unplugged; its handle stays taken so
+    /// other cameras' handles don't shift.
+    connected: bool,
+}
+
+impl MockCamera {
+    fn new(model: &str, serial: Option<&str>) -> Self {
+        Self {
+            model: model.to_string(),
+            serial: serial.map(|s| s.to_string()),
+            properties: HashMap::new(),
+            property_descs: HashMap::new(),
+            point_writes: Vec::new(),
+            evf_geometry: None,
+            session_open: false,
+            connected: true,
+        }
+    }
 }
 
 /// Configurable error injection for a specific operation.
@@ -42,6 +66,7 @@ struct MockState {
     live_view_active: HashMap<usize, bool>,
     error_injections: Vec<ErrorInjection>,
     events_processed: u32,
+    commands_sent: Vec<(CameraHandle, EdsCameraCommand, i32)>,
 }
 
 impl MockEdsSdk {
@@ -54,24 +79,44 @@ impl MockEdsSdk {
                 live_view_active: HashMap::new(),
                 error_injections: Vec::new(),
                 events_processed: 0,
+                commands_sent: Vec::new(),
             }),
         }
     }
 
     /// Add a camera with a specific model name and serial number.
     pub fn with_camera(self, model: &str, serial: Option<&str>) -> Self {
-        let mut state = self.state.lock().unwrap();
-        state.cameras.push(MockCamera {
-            model: model.to_string(),
-            serial: serial.map(|s| s.to_string()),
-            properties: HashMap::new(),
-            property_descs: HashMap::new(),
-            session_open: false,
-        });
-        drop(state);
+        self.add_camera(model, serial);
         self
     }
 
+    /// Plug in a camera after construction, returning its handle. A camera
+    /// plugged back in gets a new handle, as EDSDK gives it a new reference.
+    pub fn add_camera(&self, model: &str, serial: Option<&str>) -> CameraHandle {
+        let mut state = self.state.lock().unwrap();
+        state.cameras.push(MockCamera::new(model, serial));
+        CameraHandle(state.cameras.len() - 1)
+    }
+
+    /// Unplug the connected camera with `serial`. It drops out of
+    /// `camera_list` and calls on its handle fail. Returns whether it was
+    /// connected.
+    pub fn remove_camera(&self, serial: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let camera = state
+            .cameras
+            .iter_mut()
+            .find(|cam| cam.connected && cam.serial.as_deref() == Some(serial));
+        match camera {
+            Some(cam) => {
+                cam.connected = false;
+                cam.session_open = false;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Add N cameras with auto-generated names and serials.
     pub fn with_cameras(self, count: usize) -> Self {
         let mut result = self;
@@ -117,12 +162,24 @@ impl MockEdsSdk {
         self
     }
 
+    /// Set the live view geometry reported for a camera while live view is
+    /// active.
+    pub fn with_evf_geometry(self, camera_idx: usize, geometry: EvfGeometry) -> Self {
+        let mut state = self.state.lock().unwrap();
+        if let Some(cam) = state.cameras.get_mut(camera_idx) {
+            cam.evf_geometry = Some(geometry);
+        }
+        drop(state);
+        self
+    }
+
     /// Inject an error for a specific operation name.
     ///
     /// Operation names: `"camera_list"`, `"open_session"`, `"close_session"`,
     /// `"get_device_info"`, `"start_live_view"`, `"stop_live_view"`,
     /// `"download_evf_image"`, `"get_property"`, `"set_property"`,
-    /// `"get_property_desc"`, `"get_event"`.
+    /// `"get_property_desc"`, `"set_point_property"`, `"evf_geometry"`,
+    /// `"send_command"`, `"get_event"`.
     pub fn with_error(self, operation: &'static str, error: CameraError) -> Self {
         let mut state = self.state.lock().unwrap();
         state
@@ -136,6 +193,33 @@ impl MockEdsSdk {
     pub fn events_processed(&self) -> u32 {
         self.state.lock().unwrap().events_processed
     }
+
+    /// Return every value written to a point property on a camera, in order.
+    pub fn point_writes(&self, camera_idx: usize, prop: EdsPropertyID) -> Vec<EdsPoint> {
+        let state = self.state.lock().unwrap();
+        state
+            .cameras
+            .get(camera_idx)
+            .map(|cam| {
+                cam.point_writes
+                    .iter()
+                    .filter(|(p, _)| *p == prop)
+                    .map(|(_, point)| *point)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return every command sent, in order, as `(camera, command, param)`.
+    pub fn commands_sent(&self) -> Vec<(CameraHandle, EdsCameraCommand, i32)> {
+        self.state.lock().unwrap().commands_sent.clone()
+    }
+}
+
+impl Default for MockEdsSdk {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MockState {
@@ -155,12 +239,14 @@ impl MockState {
     fn get_camera(&self, handle: CameraHandle) -> Result<&MockCamera> {
         self.cameras
             .get(handle.0)
+            .filter(|cam| cam.connected)
             .ok_or_else(|| CameraError::DeviceNotFound(format!("mock camera {}", handle.0)))
     }
 
     fn get_camera_mut(&mut self, handle: CameraHandle) -> Result<&mut MockCamera> {
         self.cameras
             .get_mut(handle.0)
+            .filter(|cam| cam.connected)
             .ok_or_else(|| CameraError::DeviceNotFound(format!("mock camera {}", handle.0)))
     }
 }
@@ -169,7 +255,13 @@ impl EdsSdkApi for MockEdsSdk {
     fn camera_list(&self) -> Result<Vec<CameraHandle>> {
         let mut state = self.state.lock().unwrap();
         state.check_error("camera_list")?;
-        Ok((0..state.cameras.len()).map(CameraHandle).collect())
+        Ok(state
+            .cameras
+            .iter()
+            .enumerate()
+            .filter(|(_, cam)| cam.connected)
+            .map(|(i, _)| CameraHandle(i))
+            .collect())
     }
 
     fn open_session(&self, camera: CameraHandle) -> Result<()> {
@@ -288,6 +380,44 @@ impl EdsSdkApi for MockEdsSdk {
         })
     }
 
+    fn set_point_property(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+        value: EdsPoint,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check_error("set_point_property")?;
+        let cam = state.get_camera_mut(camera)?;
+        cam.point_writes.push((prop, value));
+        Ok(())
+    }
+
+    fn evf_geometry(&self, camera: CameraHandle) -> Result<Option<EvfGeometry>> {
+        let mut state = self.state.lock().unwrap();
+        state.check_error("evf_geometry")?;
+        let geometry = state.get_camera(camera)?.evf_geometry;
+        let is_active = state
+            .live_view_active
+            .get(&camera.0)
+            .copied()
+            .unwrap_or(false);
+        Ok(geometry.filter(|_| is_active))
+    }
+
+    fn send_command(
+        &self,
+        camera: CameraHandle,
+        command: EdsCameraCommand,
+        param: i32,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check_error("send_command")?;
+        let _ = state.get_camera(camera)?;
+        state.commands_sent.push((camera, command, param));
+        Ok(())
+    }
+
     fn get_event(&self) -> Result<()> {
         let mut state = self.state.lock().unwrap();
         state.check_error("get_event")?;
@@ -10,9 +10,12 @@ pub mod controls;
 pub mod discovery;
 #[cfg(all(feature = "canon", target_os = "windows"))]
 pub mod ffi;
+pub mod focus;
 pub mod hotplug;
 pub mod live_view;
 pub mod mock;
+pub mod protocol;
+pub mod remote;
 #[cfg(all(feature = "canon", target_os = "windows"))]
 pub mod sdk;
 pub mod types;
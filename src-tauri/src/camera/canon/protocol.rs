@@ -0,0 +1,844 @@
+//! Wire protocol between the app and the out-of-process EDSDK helper.
+//!
+//! Some older bodies are only supported by a 32-bit EDSDK, which can't be
+//! loaded into the 64-bit app. The `edsdk-helper` binary links that SDK and
+//! serves the `EdsSdkApi` surface over a named pipe; `RemoteEdsSdk` is the
+//! app's end.
+//!
+//! Every frame is a little-endian `u32` length followed by that many bytes:
+//! a kind byte, then the body. Message bodies are JSON. Live view images are
+//! sent as an image frame — the request ID as a little-endian `u64`, then
+//! the raw JPEG — so a 30fps stream isn't inflated by base64.
+//!
+//! A connection opens with each side sending `Hello` with its protocol
+//! version; the helper answers requests one at a time, in order.
+
+use std::io::{self, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::error::{CameraError, Result};
+
+use super::api::{CameraHandle, EdsSdkApi};
+use super::types::{
+    EdsCameraCommand, EdsDeviceInfo, EdsPoint, EdsPropertyDesc, EdsPropertyID, EvfGeometry,
+};
+
+/// Bumped whenever a change would confuse the other side.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest frame either side accepts, so a corrupt length can't make the
+/// reader allocate gigabytes. Comfortably above a full-size EVF JPEG.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+const KIND_MESSAGE: u8 = 1;
+const KIND_IMAGE: u8 = 2;
+
+/// An `EdsSdkApi` call, with camera handles as their raw index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "camelCase")]
+pub enum Call {
+    CameraList,
+    OpenSession {
+        camera: usize,
+    },
+    CloseSession {
+        camera: usize,
+    },
+    GetDeviceInfo {
+        camera: usize,
+    },
+    StartLiveView {
+        camera: usize,
+    },
+    StopLiveView {
+        camera: usize,
+    },
+    DownloadEvfImage {
+        camera: usize,
+    },
+    GetProperty {
+        camera: usize,
+        prop: EdsPropertyID,
+    },
+    SetProperty {
+        camera: usize,
+        prop: EdsPropertyID,
+        value: i32,
+    },
+    GetPropertyDesc {
+        camera: usize,
+        prop: EdsPropertyID,
+    },
+    SetPointProperty {
+        camera: usize,
+        prop: EdsPropertyID,
+        value: EdsPoint,
+    },
+    EvfGeometry {
+        camera: usize,
+    },
+    SendCommand {
+        camera: usize,
+        command: EdsCameraCommand,
+        param: i32,
+    },
+    GetEvent,
+}
+
+impl Call {
+    /// The method name, for error messages.
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::CameraList => "camera_list",
+            Self::OpenSession { .. } => "open_session",
+            Self::CloseSession { .. } => "close_session",
+            Self::GetDeviceInfo { .. } => "get_device_info",
+            Self::StartLiveView { .. } => "start_live_view",
+            Self::StopLiveView { .. } => "stop_live_view",
+            Self::DownloadEvfImage { .. } => "download_evf_image",
+            Self::GetProperty { .. } => "get_property",
+            Self::SetProperty { .. } => "set_property",
+            Self::GetPropertyDesc { .. } => "get_property_desc",
+            Self::SetPointProperty { .. } => "set_point_property",
+            Self::EvfGeometry { .. } => "evf_geometry",
+            Self::SendCommand { .. } => "send_command",
+            Self::GetEvent => "get_event",
+        }
+    }
+}
+
+/// A successful call's result. `download_evf_image` answers with an image
+/// frame instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum Reply {
+    Done,
+    Cameras(Vec<usize>),
+    DeviceInfo(WireDeviceInfo),
+    Value(i32),
+    PropertyDesc(Vec<i32>),
+    EvfGeometry(Option<EvfGeometry>),
+}
+
+/// `EdsDeviceInfo` without its fixed-size C buffers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WireDeviceInfo {
+    pub port_name: String,
+    pub description: String,
+    pub sub_type: u32,
+}
+
+impl From<&EdsDeviceInfo> for WireDeviceInfo {
+    fn from(info: &EdsDeviceInfo) -> Self {
+        Self {
+            port_name: info.port_name(),
+            description: info.model_name(),
+            sub_type: info.device_sub_type,
+        }
+    }
+}
+
+impl From<WireDeviceInfo> for EdsDeviceInfo {
+    fn from(info: WireDeviceInfo) -> Self {
+        Self {
+            port_name: c_string_buffer(&info.port_name),
+            device_description: c_string_buffer(&info.description),
+            device_sub_type: info.sub_type,
+            reserved: 0,
+        }
+    }
+}
+
+/// Copy `s` into a null-terminated buffer, truncating to fit.
+fn c_string_buffer(s: &str) -> [u8; 256] {
+    let mut buf = [0u8; 256];
+    let len = s.len().min(buf.len() - 1);
+    buf[..len].copy_from_slice(&s.as_bytes()[..len]);
+    buf
+}
+
+/// Which `CameraError` a failed call returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    DeviceNotFound,
+    ComInit,
+    Enumeration,
+    ControlQuery,
+    ControlWrite,
+    FormatQuery,
+    Hotplug,
+    Unsupported,
+    CanonSdk,
+    CanonSessionNotOpen,
+    CanonDeviceBusy,
+    /// A kind added by a newer helper; read as a Canon SDK error.
+    #[serde(other)]
+    Other,
+}
+
+/// A `CameraError` on the wire. Converting back gives the same variant and
+/// message, so callers that match on either behave as they would in-process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl From<CameraError> for WireError {
+    fn from(error: CameraError) -> Self {
+        let (kind, message) = match error {
+            CameraError::DeviceNotFound(m) => (ErrorKind::DeviceNotFound, m),
+            CameraError::ComInit(m) => (ErrorKind::ComInit, m),
+            CameraError::Enumeration(m) => (ErrorKind::Enumeration, m),
+            CameraError::ControlQuery(m) => (ErrorKind::ControlQuery, m),
+            CameraError::ControlWrite(m) => (ErrorKind::ControlWrite, m),
+            CameraError::FormatQuery(m) => (ErrorKind::FormatQuery, m),
+            CameraError::Hotplug(m) => (ErrorKind::Hotplug, m),
+            CameraError::Unsupported(m) => (ErrorKind::Unsupported, m),
+            CameraError::CanonSdkError(m) => (ErrorKind::CanonSdk, m),
+            CameraError::CanonSessionNotOpen(m) => (ErrorKind::CanonSessionNotOpen, m),
+            CameraError::CanonDeviceBusy(m) => (ErrorKind::CanonDeviceBusy, m),
+        };
+        Self { kind, message }
+    }
+}
+
+impl From<WireError> for CameraError {
+    fn from(error: WireError) -> Self {
+        let m = error.message;
+        match error.kind {
+            ErrorKind::DeviceNotFound => Self::DeviceNotFound(m),
+            ErrorKind::ComInit => Self::ComInit(m),
+            ErrorKind::Enumeration => Self::Enumeration(m),
+            ErrorKind::ControlQuery => Self::ControlQuery(m),
+            ErrorKind::ControlWrite => Self::ControlWrite(m),
+            ErrorKind::FormatQuery => Self::FormatQuery(m),
+            ErrorKind::Hotplug => Self::Hotplug(m),
+            ErrorKind::Unsupported => Self::Unsupported(m),
+            ErrorKind::CanonSdk | ErrorKind::Other => Self::CanonSdkError(m),
+            ErrorKind::CanonSessionNotOpen => Self::CanonSessionNotOpen(m),
+            ErrorKind::CanonDeviceBusy => Self::CanonDeviceBusy(m),
+        }
+    }
+}
+
+/// A JSON message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Message {
+    Hello {
+        version: u32,
+    },
+    Request {
+        id: u64,
+        call: Call,
+    },
+    Response {
+        id: u64,
+        result: std::result::Result<Reply, WireError>,
+    },
+}
+
+/// One frame off the pipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Message(Message),
+    /// A live view JPEG answering request `id`.
+    Image {
+        id: u64,
+        jpeg: Vec<u8>,
+    },
+}
+
+/// Write a frame in a single write, so a frame is never interleaved with
+/// another writer's.
+pub fn write_frame<W: Write + ?Sized>(writer: &mut W, frame: &Frame) -> io::Result<()> {
+    let mut body = Vec::new();
+    match frame {
+        Frame::Message(message) => {
+            body.push(KIND_MESSAGE);
+            serde_json::to_writer(&mut body, message).map_err(io::Error::other)?;
+        }
+        Frame::Image { id, jpeg } => {
+            body.reserve(1 + 8 + jpeg.len());
+            body.push(KIND_IMAGE);
+            body.extend_from_slice(&id.to_le_bytes());
+            body.extend_from_slice(jpeg);
+        }
+    }
+    if body.len() > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("frame of {} bytes is over the limit", body.len()),
+        ));
+    }
+
+    let mut buf = Vec::with_capacity(4 + body.len());
+    buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&body);
+    writer.write_all(&buf)?;
+    writer.flush()
+}
+
+/// Read the next frame. Returns `None` if the other side closed the pipe
+/// between frames; closing part-way through one is an error.
+pub fn read_frame<R: Read + ?Sized>(reader: &mut R) -> io::Result<Option<Frame>> {
+    let mut len = [0u8; 4];
+    let mut filled = 0;
+    while filled < len.len() {
+        match reader.read(&mut len[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len == 0 || len > MAX_FRAME_LEN {
+        return Err(invalid(format!("bad frame length {len}")));
+    }
+
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body)?;
+    match body[0] {
+        KIND_MESSAGE => serde_json::from_slice(&body[1..])
+            .map(|message| Some(Frame::Message(message)))
+            .map_err(|e| invalid(format!("bad message: {e}"))),
+        KIND_IMAGE => {
+            let Some(id) = body.get(1..9) else {
+                return Err(invalid("image frame without a request ID".to_string()));
+            };
+            let id = u64::from_le_bytes(id.try_into().expect("slice is 8 bytes"));
+            body.drain(..9);
+            Ok(Some(Frame::Image { id, jpeg: body }))
+        }
+        kind => Err(invalid(format!("unknown frame kind {kind}"))),
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Check the other side's `Hello` against our version.
+///
+/// # Errors
+///
+/// Returns `CameraError::CanonSdkError` if the first frame isn't a `Hello`
+/// or the versions differ.
+pub fn check_hello(frame: Option<&Frame>) -> Result<()> {
+    match frame {
+        Some(Frame::Message(Message::Hello { version })) if *version == PROTOCOL_VERSION => Ok(()),
+        Some(Frame::Message(Message::Hello { version })) => Err(CameraError::CanonSdkError(
+            format!("EDSDK helper speaks protocol version {version}, expected {PROTOCOL_VERSION}"),
+        )),
+        Some(_) => Err(CameraError::CanonSdkError(
+            "EDSDK helper didn't open with a handshake".to_string(),
+        )),
+        None => Err(CameraError::CanonSdkError(
+            "EDSDK helper closed the connection during the handshake".to_string(),
+        )),
+    }
+}
+
+/// Make `call` on `sdk` and build the frame that answers request `id`.
+pub fn answer<S: EdsSdkApi + ?Sized>(sdk: &S, id: u64, call: Call) -> Frame {
+    let result = match call {
+        Call::DownloadEvfImage { camera } => match sdk.download_evf_image(CameraHandle(camera)) {
+            Ok(jpeg) => return Frame::Image { id, jpeg },
+            Err(e) => Err(e),
+        },
+        Call::CameraList => sdk
+            .camera_list()
+            .map(|handles| Reply::Cameras(handles.into_iter().map(|h| h.0).collect())),
+        Call::OpenSession { camera } => {
+            sdk.open_session(CameraHandle(camera)).map(|()| Reply::Done)
+        }
+        Call::CloseSession { camera } => sdk
+            .close_session(CameraHandle(camera))
+            .map(|()| Reply::Done),
+        Call::GetDeviceInfo { camera } => sdk
+            .get_device_info(CameraHandle(camera))
+            .map(|info| Reply::DeviceInfo(WireDeviceInfo::from(&info))),
+        Call::StartLiveView { camera } => sdk
+            .start_live_view(CameraHandle(camera))
+            .map(|()| Reply::Done),
+        Call::StopLiveView { camera } => sdk
+            .stop_live_view(CameraHandle(camera))
+            .map(|()| Reply::Done),
+        Call::GetProperty { camera, prop } => sdk
+            .get_property(CameraHandle(camera), prop)
+            .map(Reply::Value),
+        Call::SetProperty {
+            camera,
+            prop,
+            value,
+        } => sdk
+            .set_property(CameraHandle(camera), prop, value)
+            .map(|()| Reply::Done),
+        Call::GetPropertyDesc { camera, prop } => sdk
+            .get_property_desc(CameraHandle(camera), prop)
+            .map(|desc| Reply::PropertyDesc(desc.prop_desc)),
+        Call::SetPointProperty {
+            camera,
+            prop,
+            value,
+        } => sdk
+            .set_point_property(CameraHandle(camera), prop, value)
+            .map(|()| Reply::Done),
+        Call::EvfGeometry { camera } => sdk
+            .evf_geometry(CameraHandle(camera))
+            .map(Reply::EvfGeometry),
+        Call::SendCommand {
+            camera,
+            command,
+            param,
+        } => sdk
+            .send_command(CameraHandle(camera), command, param)
+            .map(|()| Reply::Done),
+        Call::GetEvent => sdk.get_event().map(|()| Reply::Done),
+    };
+    Frame::Message(Message::Response {
+        id,
+        result: result.map_err(WireError::from),
+    })
+}
+
+/// Serve `sdk` to one client until it closes the pipe. This is the
+/// helper's main loop.
+///
+/// # Errors
+///
+/// Returns the I/O error that ended the connection, or `InvalidData` if the
+/// client broke the protocol or speaks another version.
+pub fn serve<S, R, W>(sdk: &S, reader: &mut R, writer: &mut W) -> io::Result<()>
+where
+    S: EdsSdkApi + ?Sized,
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let hello = read_frame(reader)?;
+    write_frame(
+        writer,
+        &Frame::Message(Message::Hello {
+            version: PROTOCOL_VERSION,
+        }),
+    )?;
+    check_hello(hello.as_ref()).map_err(|e| invalid(e.to_string()))?;
+
+    while let Some(frame) = read_frame(reader)? {
+        let Frame::Message(Message::Request { id, call }) = frame else {
+            return Err(invalid("expected a request".to_string()));
+        };
+        write_frame(writer, &answer(sdk, id, call))?;
+    }
+    Ok(())
+}
+
+/// Read the answer to request `id` off a frame.
+///
+/// # Errors
+///
+/// Returns the call's own error, or `CameraError::CanonSdkError` if the
+/// frame answers another request or isn't an answer at all.
+pub fn take_answer(frame: Frame, id: u64) -> Result<Answer> {
+    match frame {
+        Frame::Image { id: got, jpeg } if got == id => Ok(Answer::Image(jpeg)),
+        Frame::Message(Message::Response { id: got, result }) if got == id => {
+            result.map(Answer::Reply).map_err(CameraError::from)
+        }
+        _ => Err(CameraError::CanonSdkError(format!(
+            "EDSDK helper sent an unexpected frame while answering request {id}"
+        ))),
+    }
+}
+
+/// A successful answer to a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Reply(Reply),
+    Image(Vec<u8>),
+}
+
+impl Answer {
+    fn unexpected(method: &str) -> CameraError {
+        CameraError::CanonSdkError(format!(
+            "EDSDK helper answered {method} with the wrong type"
+        ))
+    }
+
+    pub fn into_done(self, method: &str) -> Result<()> {
+        match self {
+            Self::Reply(Reply::Done) => Ok(()),
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+
+    pub fn into_cameras(self, method: &str) -> Result<Vec<CameraHandle>> {
+        match self {
+            Self::Reply(Reply::Cameras(handles)) => {
+                Ok(handles.into_iter().map(CameraHandle).collect())
+            }
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+
+    pub fn into_device_info(self, method: &str) -> Result<EdsDeviceInfo> {
+        match self {
+            Self::Reply(Reply::DeviceInfo(info)) => Ok(info.into()),
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+
+    pub fn into_value(self, method: &str) -> Result<i32> {
+        match self {
+            Self::Reply(Reply::Value(value)) => Ok(value),
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+
+    pub fn into_property_desc(self, method: &str) -> Result<EdsPropertyDesc> {
+        match self {
+            Self::Reply(Reply::PropertyDesc(values)) => Ok(EdsPropertyDesc {
+                num_elements: values.len(),
+                prop_desc: values,
+            }),
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+
+    pub fn into_evf_geometry(self, method: &str) -> Result<Option<EvfGeometry>> {
+        match self {
+            Self::Reply(Reply::EvfGeometry(geometry)) => Ok(geometry),
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+
+    pub fn into_image(self, method: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Image(jpeg) => Ok(jpeg),
+            _ => Err(Self::unexpected(method)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::canon::mock::MockEdsSdk;
+    use crate::camera::canon::types::{EdsRect, EdsSize, PROP_ID_ISO_SPEED};
+
+    fn round_trip(frame: &Frame) -> Frame {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, frame).unwrap();
+        let mut reader = buf.as_slice();
+        let read = read_frame(&mut reader).unwrap().unwrap();
+        assert!(reader.is_empty(), "frame left bytes behind");
+        read
+    }
+
+    fn request(id: u64, call: Call) -> Frame {
+        Frame::Message(Message::Request { id, call })
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let frames = [
+            Frame::Message(Message::Hello { version: 1 }),
+            request(
+                7,
+                Call::SetPointProperty {
+                    camera: 2,
+                    prop: 0x0000_0157,
+                    value: EdsPoint { x: -3, y: 40 },
+                },
+            ),
+            request(8, Call::GetEvent),
+            Frame::Message(Message::Response {
+                id: 9,
+                result: Ok(Reply::EvfGeometry(Some(EvfGeometry {
+                    coordinate_system: EdsSize {
+                        width: 6000,
+                        height: 4000,
+                    },
+                    zoom_rect: EdsRect::default(),
+                }))),
+            }),
+            Frame::Message(Message::Response {
+                id: 10,
+                result: Err(WireError {
+                    kind: ErrorKind::CanonDeviceBusy,
+                    message: "busy".to_string(),
+                }),
+            }),
+        ];
+        for frame in &frames {
+            assert_eq!(&round_trip(frame), frame);
+        }
+    }
+
+    #[test]
+    fn images_are_sent_as_raw_bytes() {
+        let jpeg: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        let frame = Frame::Image {
+            id: 42,
+            jpeg: jpeg.clone(),
+        };
+
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &frame).unwrap();
+        // Length, kind and ID are the only overhead
+        assert_eq!(buf.len(), 4 + 1 + 8 + jpeg.len());
+        assert_eq!(round_trip(&frame), frame);
+    }
+
+    #[test]
+    fn calls_use_method_names_on_the_wire() {
+        let json = serde_json::to_value(Message::Request {
+            id: 1,
+            call: Call::GetProperty {
+                camera: 0,
+                prop: PROP_ID_ISO_SPEED,
+            },
+        })
+        .unwrap();
+        assert_eq!(json["type"], "request");
+        assert_eq!(json["call"]["method"], "getProperty");
+        assert_eq!(json["call"]["params"]["prop"], PROP_ID_ISO_SPEED);
+    }
+
+    #[test]
+    fn frames_are_read_back_to_back() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &request(1, Call::CameraList)).unwrap();
+        write_frame(
+            &mut buf,
+            &Frame::Image {
+                id: 2,
+                jpeg: vec![],
+            },
+        )
+        .unwrap();
+
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some(request(1, Call::CameraList))
+        );
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some(Frame::Image {
+                id: 2,
+                jpeg: vec![]
+            })
+        );
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_and_corrupt_frames_are_errors() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &request(1, Call::CameraList)).unwrap();
+
+        let mut truncated = &buf[..buf.len() - 1];
+        assert!(read_frame(&mut truncated).is_err());
+        let mut half_length = &buf[..2];
+        assert!(read_frame(&mut half_length).is_err());
+
+        let mut unknown_kind = buf.clone();
+        unknown_kind[4] = 9;
+        assert!(read_frame(&mut unknown_kind.as_slice()).is_err());
+
+        let oversized = ((MAX_FRAME_LEN + 1) as u32).to_le_bytes();
+        assert!(read_frame(&mut oversized.as_slice()).is_err());
+
+        let mut bad_json = vec![3, 0, 0, 0, KIND_MESSAGE];
+        bad_json.extend_from_slice(b"{}");
+        assert!(read_frame(&mut bad_json.as_slice()).is_err());
+    }
+
+    #[test]
+    fn errors_keep_their_variant_and_message() {
+        let errors = [
+            CameraError::DeviceNotFound("a".into()),
+            CameraError::ComInit("b".into()),
+            CameraError::Enumeration("c".into()),
+            CameraError::ControlQuery("d".into()),
+            CameraError::ControlWrite("e".into()),
+            CameraError::FormatQuery("f".into()),
+            CameraError::Hotplug("g".into()),
+            CameraError::Unsupported("k".into()),
+            CameraError::CanonSdkError("live view data not ready yet".into()),
+            CameraError::CanonSessionNotOpen("i".into()),
+            CameraError::CanonDeviceBusy("j".into()),
+        ];
+        for error in errors {
+            let wire: WireError = serde_json::from_str(
+                &serde_json::to_string(&WireError::from(error.clone())).unwrap(),
+            )
+            .unwrap();
+            let back = CameraError::from(wire);
+            assert_eq!(
+                std::mem::discriminant(&back),
+                std::mem::discriminant(&error)
+            );
+            assert_eq!(back.to_string(), error.to_string());
+        }
+    }
+
+    #[test]
+    fn unknown_error_kinds_become_sdk_errors() {
+        let wire: WireError =
+            serde_json::from_str(r#"{"kind":"somethingNew","message":"oops"}"#).unwrap();
+        assert_eq!(wire.kind, ErrorKind::Other);
+        assert!(matches!(
+            CameraError::from(wire),
+            CameraError::CanonSdkError(m) if m == "oops"
+        ));
+    }
+
+    #[test]
+    fn device_info_survives_the_wire() {
+        let info = EdsDeviceInfo::from(WireDeviceInfo {
+            port_name: "\\\\.\\USB001".to_string(),
+            description: "Canon EOS 5D Mark II".to_string(),
+            sub_type: 3,
+        });
+        assert_eq!(info.model_name(), "Canon EOS 5D Mark II");
+        assert_eq!(info.port_name(), "\\\\.\\USB001");
+        assert_eq!(WireDeviceInfo::from(&info).sub_type, 3);
+
+        let long = "x".repeat(300);
+        let info = EdsDeviceInfo::from(WireDeviceInfo {
+            port_name: String::new(),
+            description: long,
+            sub_type: 0,
+        });
+        assert_eq!(info.model_name().len(), 255);
+    }
+
+    #[test]
+    fn handshake_checks_the_version() {
+        let hello = |version| Frame::Message(Message::Hello { version });
+        assert!(check_hello(Some(&hello(PROTOCOL_VERSION))).is_ok());
+
+        let err = check_hello(Some(&hello(PROTOCOL_VERSION + 1))).unwrap_err();
+        assert!(err.to_string().contains("protocol version"));
+        assert!(check_hello(Some(&request(1, Call::GetEvent))).is_err());
+        assert!(check_hello(None).is_err());
+    }
+
+    #[test]
+    fn answers_cover_replies_images_and_errors() {
+        let mock = MockEdsSdk::new()
+            .with_camera("Canon EOS 40D", Some("SER1"))
+            .with_property(0, PROP_ID_ISO_SPEED, 0x48)
+            .with_live_view_frame(vec![0xFF, 0xD8, 0xFF, 0xD9])
+            .with_error(
+                "start_live_view",
+                CameraError::CanonDeviceBusy("busy".into()),
+            );
+
+        let answer_of = |id, call| take_answer(answer(&mock, id, call), id);
+
+        assert_eq!(
+            answer_of(1, Call::CameraList)
+                .unwrap()
+                .into_cameras("camera_list")
+                .unwrap(),
+            vec![CameraHandle(0)]
+        );
+        assert_eq!(
+            answer_of(
+                2,
+                Call::GetProperty {
+                    camera: 0,
+                    prop: PROP_ID_ISO_SPEED
+                }
+            )
+            .unwrap()
+            .into_value("get_property")
+            .unwrap(),
+            0x48
+        );
+        assert!(matches!(
+            answer_of(3, Call::StartLiveView { camera: 0 }),
+            Err(CameraError::CanonDeviceBusy(_))
+        ));
+        answer_of(4, Call::StartLiveView { camera: 0 })
+            .unwrap()
+            .into_done("start_live_view")
+            .unwrap();
+        assert_eq!(
+            answer_of(5, Call::DownloadEvfImage { camera: 0 })
+                .unwrap()
+                .into_image("download_evf_image")
+                .unwrap(),
+            vec![0xFF, 0xD8, 0xFF, 0xD9]
+        );
+        assert!(matches!(
+            answer_of(6, Call::OpenSession { camera: 5 }),
+            Err(CameraError::DeviceNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn answers_to_other_requests_are_rejected() {
+        let mock = MockEdsSdk::new();
+        let frame = answer(&mock, 1, Call::GetEvent);
+        assert!(take_answer(frame, 2).is_err());
+
+        let answer = take_answer(answer(&mock, 3, Call::GetEvent), 3).unwrap();
+        assert!(answer.into_value("get_event").is_err());
+    }
+
+    #[test]
+    fn serve_answers_until_the_client_hangs_up() {
+        let mock = MockEdsSdk::new().with_cameras(2);
+        let mut input = Vec::new();
+        write_frame(
+            &mut input,
+            &Frame::Message(Message::Hello {
+                version: PROTOCOL_VERSION,
+            }),
+        )
+        .unwrap();
+        write_frame(&mut input, &request(1, Call::CameraList)).unwrap();
+        write_frame(&mut input, &request(2, Call::GetEvent)).unwrap();
+
+        let mut output = Vec::new();
+        serve(&mock, &mut input.as_slice(), &mut output).unwrap();
+
+        let mut reader = output.as_slice();
+        check_hello(read_frame(&mut reader).unwrap().as_ref()).unwrap();
+        let cameras = take_answer(read_frame(&mut reader).unwrap().unwrap(), 1).unwrap();
+        assert_eq!(
+            cameras.into_cameras("camera_list").unwrap(),
+            vec![CameraHandle(0), CameraHandle(1)]
+        );
+        take_answer(read_frame(&mut reader).unwrap().unwrap(), 2).unwrap();
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+        assert_eq!(mock.events_processed(), 1);
+    }
+
+    #[test]
+    fn serve_refuses_other_versions() {
+        let mock = MockEdsSdk::new();
+        let mut input = Vec::new();
+        write_frame(&mut input, &Frame::Message(Message::Hello { version: 0 })).unwrap();
+        write_frame(&mut input, &request(1, Call::CameraList)).unwrap();
+
+        let mut output = Vec::new();
+        let err = serve(&mock, &mut input.as_slice(), &mut output).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Our own hello still goes out so the client can report the mismatch
+        let mut reader = output.as_slice();
+        check_hello(read_frame(&mut reader).unwrap().as_ref()).unwrap();
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+}
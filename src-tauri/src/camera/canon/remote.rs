@@ -0,0 +1,881 @@
+//! `RemoteEdsSdk` — `EdsSdkApi` proxied to the out-of-process EDSDK helper.
+//!
+//! Each call is sent over the helper's pipe and waited on with a timeout.
+//! A helper that crashes, hangs or breaks the protocol is killed, and the
+//! next call starts a new one; sessions and live view that were open are
+//! reopened on it, so a crash costs a few failed calls rather than the
+//! camera. See `protocol` for the wire format.
+
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::process::Child;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::camera::error::{CameraError, Result};
+
+use super::api::{CameraHandle, EdsSdkApi};
+use super::protocol::{
+    check_hello, read_frame, take_answer, write_frame, Answer, Call, Frame, Message,
+    PROTOCOL_VERSION,
+};
+use super::types::{
+    EdsCameraCommand, EdsDeviceInfo, EdsPoint, EdsPropertyDesc, EdsPropertyID, EvfGeometry,
+};
+
+/// How long a call may take before the helper is presumed hung.
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Least time between helper starts, so one that crashes on start-up isn't
+/// restarted in a tight loop.
+pub const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Both ends of a connection to a freshly started helper.
+pub struct Link {
+    pub reader: Box<dyn Read + Send>,
+    pub writer: Box<dyn Write + Send>,
+    /// The helper process, killed when the connection is dropped.
+    pub child: Option<Child>,
+}
+
+/// Starts a helper and connects to it.
+pub trait Connector: Send + Sync {
+    fn connect(&self) -> Result<Link>;
+}
+
+/// A live connection, with a thread reading frames off the pipe so calls
+/// can wait with a timeout.
+struct Connection {
+    writer: Box<dyn Write + Send>,
+    frames: mpsc::Receiver<io::Result<Option<Frame>>>,
+    child: Option<Child>,
+    next_id: u64,
+}
+
+impl Connection {
+    /// Start the reader thread and do the handshake.
+    fn open(link: Link, timeout: Duration) -> Result<Self> {
+        let Link {
+            mut reader,
+            writer,
+            child,
+        } = link;
+        let (tx, frames) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("edsdk-helper-reader".to_string())
+            .spawn(move || loop {
+                let frame = read_frame(&mut reader);
+                let done = !matches!(frame, Ok(Some(_)));
+                if tx.send(frame).is_err() || done {
+                    break;
+                }
+            })
+            .map_err(|e| CameraError::CanonSdkError(format!("EDSDK helper reader: {e}")))?;
+
+        let mut connection = Self {
+            writer,
+            frames,
+            child,
+            next_id: 1,
+        };
+        connection
+            .send(&Frame::Message(Message::Hello {
+                version: PROTOCOL_VERSION,
+            }))
+            .and_then(|()| connection.receive(timeout))
+            .map_err(CameraError::CanonSdkError)
+            .and_then(|hello| check_hello(hello.as_ref()))?;
+        Ok(connection)
+    }
+
+    fn send(&mut self, frame: &Frame) -> std::result::Result<(), String> {
+        write_frame(&mut self.writer, frame).map_err(|e| format!("write failed: {e}"))
+    }
+
+    fn receive(&mut self, timeout: Duration) -> std::result::Result<Option<Frame>, String> {
+        match self.frames.recv_timeout(timeout) {
+            Ok(Ok(frame)) => Ok(frame),
+            Ok(Err(e)) => Err(format!("read failed: {e}")),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                Err(format!("no answer within {}s", timeout.as_secs_f32()))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err("connection closed".to_string()),
+        }
+    }
+
+    /// Send a request and wait for its frame. An `Err` means the
+    /// connection can't be trusted any more.
+    fn round_trip(
+        &mut self,
+        call: Call,
+        timeout: Duration,
+    ) -> std::result::Result<(u64, Frame), String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&Frame::Message(Message::Request { id, call }))?;
+        match self.receive(timeout)? {
+            Some(frame) => Ok((id, frame)),
+            None => Err("helper exited".to_string()),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(Default)]
+struct RemoteState {
+    connection: Option<Connection>,
+    last_start: Option<Instant>,
+    /// Whether a helper has been started before, so the next one is a restart.
+    started: bool,
+    sessions: HashSet<CameraHandle>,
+    live_view: HashSet<CameraHandle>,
+}
+
+/// `EdsSdkApi` served by the EDSDK helper.
+///
+/// Calls are made one at a time. Camera handles are the helper's; they
+/// stay valid across a restart because the helper enumerates the same
+/// cameras in the same order.
+pub struct RemoteEdsSdk<C: Connector> {
+    connector: C,
+    timeout: Duration,
+    restart_delay: Duration,
+    state: Mutex<RemoteState>,
+}
+
+impl<C: Connector> RemoteEdsSdk<C> {
+    /// Start the helper and connect to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CameraError::CanonSdkError` if the helper can't be started
+    /// or fails the handshake.
+    pub fn new(connector: C) -> Result<Self> {
+        let sdk = Self {
+            connector,
+            timeout: CALL_TIMEOUT,
+            restart_delay: RESTART_DELAY,
+            state: Mutex::new(RemoteState::default()),
+        };
+        sdk.ensure_connected(&mut sdk.lock())?;
+        Ok(sdk)
+    }
+
+    /// Use a different call timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Use a different least time between helper starts.
+    pub fn with_restart_delay(mut self, delay: Duration) -> Self {
+        self.restart_delay = delay;
+        self
+    }
+
+    /// Whether a helper is currently connected.
+    pub fn is_connected(&self) -> bool {
+        self.lock().connection.is_some()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RemoteState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Connect if there's no live connection, reopening what the last
+    /// helper had open.
+    fn ensure_connected(&self, state: &mut RemoteState) -> Result<()> {
+        if state.connection.is_some() {
+            return Ok(());
+        }
+        if let Some(last) = state.last_start {
+            if last.elapsed() < self.restart_delay {
+                return Err(CameraError::CanonSdkError(
+                    "EDSDK helper is restarting".to_string(),
+                ));
+            }
+        }
+        state.last_start = Some(Instant::now());
+
+        let mut connection = Connection::open(self.connector.connect()?, self.timeout)?;
+        if std::mem::replace(&mut state.started, true) {
+            tracing::info!("EDSDK helper restarted");
+            self.reopen(&mut connection, state);
+        }
+        state.connection = Some(connection);
+        Ok(())
+    }
+
+    /// Reopen sessions and live view on a restarted helper. Cameras that
+    /// can't be reopened are forgotten; their next call reports the error.
+    fn reopen(&self, connection: &mut Connection, state: &mut RemoteState) {
+        let mut replay = |call: Call| match connection.round_trip(call.clone(), self.timeout) {
+            Ok((id, frame)) => take_answer(frame, id).is_ok(),
+            Err(e) => {
+                tracing::warn!("EDSDK helper: reopening with {} failed: {e}", call.method());
+                false
+            }
+        };
+
+        replay(Call::CameraList);
+        state
+            .sessions
+            .retain(|camera| replay(Call::OpenSession { camera: camera.0 }));
+        let sessions = &state.sessions;
+        state.live_view.retain(|camera| {
+            sessions.contains(camera) && replay(Call::StartLiveView { camera: camera.0 })
+        });
+    }
+
+    /// Make a call, starting a helper first if needed. A broken connection
+    /// is dropped (killing its helper) so the next call starts a new one.
+    fn call(&self, call: Call) -> Result<Answer> {
+        let mut state = self.lock();
+        self.ensure_connected(&mut state)?;
+        let method = call.method();
+        let connection = state.connection.as_mut().expect("connected above");
+        match connection.round_trip(call, self.timeout) {
+            Ok((id, frame)) => take_answer(frame, id),
+            Err(e) => {
+                tracing::warn!("EDSDK helper failed during {method}: {e}");
+                state.connection = None;
+                Err(CameraError::CanonSdkError(format!(
+                    "EDSDK helper failed during {method}: {e}"
+                )))
+            }
+        }
+    }
+}
+
+impl<C: Connector> EdsSdkApi for RemoteEdsSdk<C> {
+    fn camera_list(&self) -> Result<Vec<CameraHandle>> {
+        self.call(Call::CameraList)?.into_cameras("camera_list")
+    }
+
+    fn open_session(&self, camera: CameraHandle) -> Result<()> {
+        self.call(Call::OpenSession { camera: camera.0 })?
+            .into_done("open_session")?;
+        self.lock().sessions.insert(camera);
+        Ok(())
+    }
+
+    fn close_session(&self, camera: CameraHandle) -> Result<()> {
+        {
+            let mut state = self.lock();
+            state.sessions.remove(&camera);
+            state.live_view.remove(&camera);
+        }
+        self.call(Call::CloseSession { camera: camera.0 })?
+            .into_done("close_session")
+    }
+
+    fn get_device_info(&self, camera: CameraHandle) -> Result<EdsDeviceInfo> {
+        self.call(Call::GetDeviceInfo { camera: camera.0 })?
+            .into_device_info("get_device_info")
+    }
+
+    fn start_live_view(&self, camera: CameraHandle) -> Result<()> {
+        self.call(Call::StartLiveView { camera: camera.0 })?
+            .into_done("start_live_view")?;
+        self.lock().live_view.insert(camera);
+        Ok(())
+    }
+
+    fn stop_live_view(&self, camera: CameraHandle) -> Result<()> {
+        self.lock().live_view.remove(&camera);
+        self.call(Call::StopLiveView { camera: camera.0 })?
+            .into_done("stop_live_view")
+    }
+
+    fn download_evf_image(&self, camera: CameraHandle) -> Result<Vec<u8>> {
+        self.call(Call::DownloadEvfImage { camera: camera.0 })?
+            .into_image("download_evf_image")
+    }
+
+    fn get_property(&self, camera: CameraHandle, prop: EdsPropertyID) -> Result<i32> {
+        self.call(Call::GetProperty {
+            camera: camera.0,
+            prop,
+        })?
+        .into_value("get_property")
+    }
+
+    fn set_property(&self, camera: CameraHandle, prop: EdsPropertyID, value: i32) -> Result<()> {
+        self.call(Call::SetProperty {
+            camera: camera.0,
+            prop,
+            value,
+        })?
+        .into_done("set_property")
+    }
+
+    fn get_property_desc(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+    ) -> Result<EdsPropertyDesc> {
+        self.call(Call::GetPropertyDesc {
+            camera: camera.0,
+            prop,
+        })?
+        .into_property_desc("get_property_desc")
+    }
+
+    fn set_point_property(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+        value: EdsPoint,
+    ) -> Result<()> {
+        self.call(Call::SetPointProperty {
+            camera: camera.0,
+            prop,
+            value,
+        })?
+        .into_done("set_point_property")
+    }
+
+    fn evf_geometry(&self, camera: CameraHandle) -> Result<Option<EvfGeometry>> {
+        self.call(Call::EvfGeometry { camera: camera.0 })?
+            .into_evf_geometry("evf_geometry")
+    }
+
+    fn send_command(
+        &self,
+        camera: CameraHandle,
+        command: EdsCameraCommand,
+        param: i32,
+    ) -> Result<()> {
+        self.call(Call::SendCommand {
+            camera: camera.0,
+            command,
+            param,
+        })?
+        .into_done("send_command")
+    }
+
+    fn get_event(&self) -> Result<()> {
+        self.call(Call::GetEvent)?.into_done("get_event")
+    }
+}
+
+/// Starts `edsdk-helper.exe` on a fresh named pipe.
+#[cfg(target_os = "windows")]
+pub struct HelperProcess {
+    path: std::path::PathBuf,
+}
+
+#[cfg(target_os = "windows")]
+impl HelperProcess {
+    /// How long a starting helper has to create its pipe.
+    const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new(path: std::path::PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The helper shipped next to the app's executable, if there is one.
+    pub fn bundled() -> Option<Self> {
+        let path = std::env::current_exe()
+            .ok()?
+            .with_file_name("edsdk-helper.exe");
+        path.is_file().then(|| Self::new(path))
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Connector for HelperProcess {
+    fn connect(&self) -> Result<Link> {
+        use std::os::windows::process::CommandExt;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        static NEXT_PIPE: AtomicU32 = AtomicU32::new(0);
+
+        let pipe = format!(
+            r"\\.\pipe\cameras-edsdk-{}-{}",
+            std::process::id(),
+            NEXT_PIPE.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut child = std::process::Command::new(&self.path)
+            .arg("--pipe")
+            .arg(&pipe)
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .map_err(|e| {
+                CameraError::CanonSdkError(format!("failed to start {}: {e}", self.path.display()))
+            })?;
+
+        // The helper creates the pipe once the SDK is up; poll until it does
+        let deadline = Instant::now() + Self::START_TIMEOUT;
+        let file = loop {
+            match std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&pipe)
+            {
+                Ok(file) => break file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound && Instant::now() < deadline => {}
+                Err(e) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(CameraError::CanonSdkError(format!(
+                        "failed to connect to the EDSDK helper: {e}"
+                    )));
+                }
+            }
+            if let Ok(Some(status)) = child.try_wait() {
+                return Err(CameraError::CanonSdkError(format!(
+                    "EDSDK helper exited during start-up ({status})"
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        let reader = file
+            .try_clone()
+            .map_err(|e| CameraError::CanonSdkError(format!("EDSDK helper pipe: {e}")))?;
+
+        Ok(Link {
+            reader: Box::new(reader),
+            writer: Box::new(file),
+            child: Some(child),
+        })
+    }
+}
+
+/// Whether the Canon backend should use the EDSDK helper instead of the
+/// in-process SDK. Set `EDSDK_HELPER=1` for bodies only the 32-bit SDK
+/// supports.
+pub fn helper_requested() -> bool {
+    std::env::var("EDSDK_HELPER").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// The EDSDK the Canon backend talks to: loaded into this process, or
+/// served by the helper.
+#[cfg(all(feature = "canon", target_os = "windows"))]
+pub enum EdsSdkHost {
+    InProcess(super::sdk::EdsSdk),
+    Helper(RemoteEdsSdk<HelperProcess>),
+}
+
+#[cfg(all(feature = "canon", target_os = "windows"))]
+impl EdsSdkHost {
+    /// Load the SDK the way `helper_requested` asks for.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CameraError::CanonSdkError` if the SDK can't be initialised,
+    /// or the helper was requested but isn't installed or won't start.
+    pub fn load() -> Result<Self> {
+        if !helper_requested() {
+            return super::sdk::EdsSdk::new().map(Self::InProcess);
+        }
+        let helper = HelperProcess::bundled().ok_or_else(|| {
+            CameraError::CanonSdkError("edsdk-helper.exe not found next to the app".to_string())
+        })?;
+        RemoteEdsSdk::new(helper).map(Self::Helper)
+    }
+
+    fn api(&self) -> &dyn EdsSdkApi {
+        match self {
+            Self::InProcess(sdk) => sdk,
+            Self::Helper(sdk) => sdk,
+        }
+    }
+}
+
+#[cfg(all(feature = "canon", target_os = "windows"))]
+impl EdsSdkApi for EdsSdkHost {
+    fn camera_list(&self) -> Result<Vec<CameraHandle>> {
+        self.api().camera_list()
+    }
+
+    fn open_session(&self, camera: CameraHandle) -> Result<()> {
+        self.api().open_session(camera)
+    }
+
+    fn close_session(&self, camera: CameraHandle) -> Result<()> {
+        self.api().close_session(camera)
+    }
+
+    fn get_device_info(&self, camera: CameraHandle) -> Result<EdsDeviceInfo> {
+        self.api().get_device_info(camera)
+    }
+
+    fn start_live_view(&self, camera: CameraHandle) -> Result<()> {
+        self.api().start_live_view(camera)
+    }
+
+    fn stop_live_view(&self, camera: CameraHandle) -> Result<()> {
+        self.api().stop_live_view(camera)
+    }
+
+    fn download_evf_image(&self, camera: CameraHandle) -> Result<Vec<u8>> {
+        self.api().download_evf_image(camera)
+    }
+
+    fn get_property(&self, camera: CameraHandle, prop: EdsPropertyID) -> Result<i32> {
+        self.api().get_property(camera, prop)
+    }
+
+    fn set_property(&self, camera: CameraHandle, prop: EdsPropertyID, value: i32) -> Result<()> {
+        self.api().set_property(camera, prop, value)
+    }
+
+    fn get_property_desc(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+    ) -> Result<EdsPropertyDesc> {
+        self.api().get_property_desc(camera, prop)
+    }
+
+    fn set_point_property(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+        value: EdsPoint,
+    ) -> Result<()> {
+        self.api().set_point_property(camera, prop, value)
+    }
+
+    fn evf_geometry(&self, camera: CameraHandle) -> Result<Option<EvfGeometry>> {
+        self.api().evf_geometry(camera)
+    }
+
+    fn send_command(
+        &self,
+        camera: CameraHandle,
+        command: EdsCameraCommand,
+        param: i32,
+    ) -> Result<()> {
+        self.api().send_command(camera, command, param)
+    }
+
+    fn get_event(&self) -> Result<()> {
+        self.api().get_event()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::camera::canon::mock::MockEdsSdk;
+    use crate::camera::canon::protocol::{answer, serve};
+    use crate::camera::canon::types::PROP_ID_ISO_SPEED;
+
+    /// One direction of an in-memory pipe. Reads end once the writer is
+    /// dropped, like a pipe whose other end has closed.
+    struct PipeReader {
+        rx: mpsc::Receiver<Vec<u8>>,
+        pending: Vec<u8>,
+    }
+
+    impl Read for PipeReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() {
+                match self.rx.recv() {
+                    Ok(bytes) => self.pending = bytes,
+                    Err(_) => return Ok(0),
+                }
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    struct PipeWriter(mpsc::Sender<Vec<u8>>);
+
+    impl Write for PipeWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .send(buf.to_vec())
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn pipe() -> (PipeReader, PipeWriter) {
+        let (tx, rx) = mpsc::channel();
+        (
+            PipeReader {
+                rx,
+                pending: Vec::new(),
+            },
+            PipeWriter(tx),
+        )
+    }
+
+    /// How a fake helper behaves after the handshake.
+    #[derive(Clone, Copy)]
+    enum Fault {
+        None,
+        /// Exit after answering this many requests.
+        CrashAfter(usize),
+        /// Read requests but never answer.
+        Hang,
+        /// Answer the handshake with this version.
+        Version(u32),
+    }
+
+    /// Serves a shared mock on a thread per connection — the helper
+    /// process, without the process.
+    struct FakeHelper {
+        sdk: Arc<MockEdsSdk>,
+        faults: Mutex<Vec<Fault>>,
+        starts: AtomicUsize,
+    }
+
+    impl FakeHelper {
+        /// Connections take their fault from `faults` in turn; later ones
+        /// are healthy.
+        fn new(sdk: MockEdsSdk, faults: Vec<Fault>) -> Arc<Self> {
+            Arc::new(Self {
+                sdk: Arc::new(sdk),
+                faults: Mutex::new(faults),
+                starts: AtomicUsize::new(0),
+            })
+        }
+
+        fn starts(&self) -> usize {
+            self.starts.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Connector for Arc<FakeHelper> {
+        fn connect(&self) -> Result<Link> {
+            self.starts.fetch_add(1, Ordering::SeqCst);
+            let fault = {
+                let mut faults = self.faults.lock().unwrap();
+                if faults.is_empty() {
+                    Fault::None
+                } else {
+                    faults.remove(0)
+                }
+            };
+            let (client_reader, mut helper_writer) = pipe();
+            let (mut helper_reader, client_writer) = pipe();
+            let sdk = Arc::clone(&self.sdk);
+            std::thread::spawn(move || match fault {
+                Fault::None => {
+                    let _ = serve(sdk.as_ref(), &mut helper_reader, &mut helper_writer);
+                }
+                Fault::CrashAfter(count) => {
+                    let _ = read_frame(&mut helper_reader);
+                    let hello = Frame::Message(Message::Hello {
+                        version: PROTOCOL_VERSION,
+                    });
+                    write_frame(&mut helper_writer, &hello).unwrap();
+                    for _ in 0..count {
+                        let Ok(Some(Frame::Message(Message::Request { id, call }))) =
+                            read_frame(&mut helper_reader)
+                        else {
+                            return;
+                        };
+                        write_frame(&mut helper_writer, &answer(sdk.as_ref(), id, call)).unwrap();
+                    }
+                }
+                Fault::Hang => {
+                    let _ = read_frame(&mut helper_reader);
+                    let hello = Frame::Message(Message::Hello {
+                        version: PROTOCOL_VERSION,
+                    });
+                    write_frame(&mut helper_writer, &hello).unwrap();
+                    // Swallow requests until the client hangs up
+                    while let Ok(Some(_)) = read_frame(&mut helper_reader) {}
+                }
+                Fault::Version(version) => {
+                    let _ = read_frame(&mut helper_reader);
+                    let hello = Frame::Message(Message::Hello { version });
+                    let _ = write_frame(&mut helper_writer, &hello);
+                }
+            });
+            Ok(Link {
+                reader: Box::new(client_reader),
+                writer: Box::new(client_writer),
+                child: None,
+            })
+        }
+    }
+
+    fn remote(helper: &Arc<FakeHelper>) -> RemoteEdsSdk<Arc<FakeHelper>> {
+        RemoteEdsSdk::new(Arc::clone(helper))
+            .unwrap()
+            .with_timeout(Duration::from_millis(200))
+            .with_restart_delay(Duration::ZERO)
+    }
+
+    #[test]
+    fn calls_are_proxied_to_the_helper() {
+        let helper = FakeHelper::new(
+            MockEdsSdk::new()
+                .with_camera("Canon EOS 400D", Some("SER1"))
+                .with_property(0, PROP_ID_ISO_SPEED, 0x48)
+                .with_property_desc(0, PROP_ID_ISO_SPEED, vec![0x48, 0x50])
+                .with_live_view_frame(vec![0xFF, 0xD8, 0xFF, 0xD9]),
+            vec![],
+        );
+        let sdk = remote(&helper);
+
+        let cameras = sdk.camera_list().unwrap();
+        assert_eq!(cameras, vec![CameraHandle(0)]);
+        let camera = cameras[0];
+        sdk.open_session(camera).unwrap();
+        assert_eq!(
+            sdk.get_device_info(camera).unwrap().model_name(),
+            "Canon EOS 400D"
+        );
+        assert_eq!(sdk.get_property(camera, PROP_ID_ISO_SPEED).unwrap(), 0x48);
+        sdk.set_property(camera, PROP_ID_ISO_SPEED, 0x50).unwrap();
+        assert_eq!(
+            helper.sdk.get_property(camera, PROP_ID_ISO_SPEED).unwrap(),
+            0x50
+        );
+        assert_eq!(
+            sdk.get_property_desc(camera, PROP_ID_ISO_SPEED)
+                .unwrap()
+                .prop_desc,
+            vec![0x48, 0x50]
+        );
+
+        sdk.start_live_view(camera).unwrap();
+        assert_eq!(
+            sdk.download_evf_image(camera).unwrap(),
+            vec![0xFF, 0xD8, 0xFF, 0xD9]
+        );
+        assert_eq!(sdk.evf_geometry(camera).unwrap(), None);
+        sdk.send_command(camera, 0x0000_0004, 1).unwrap();
+        assert_eq!(helper.sdk.commands_sent(), vec![(camera, 0x0000_0004, 1)]);
+        sdk.get_event().unwrap();
+        assert_eq!(helper.sdk.events_processed(), 1);
+        assert_eq!(helper.starts(), 1);
+    }
+
+    #[test]
+    fn helper_errors_come_back_as_the_same_camera_error() {
+        let helper = FakeHelper::new(
+            MockEdsSdk::new()
+                .with_cameras(1)
+                .with_error("open_session", CameraError::CanonDeviceBusy("busy".into())),
+            vec![],
+        );
+        let sdk = remote(&helper);
+
+        assert!(matches!(
+            sdk.open_session(CameraHandle(0)),
+            Err(CameraError::CanonDeviceBusy(m)) if m == "busy"
+        ));
+        assert!(matches!(
+            sdk.get_property(CameraHandle(3), PROP_ID_ISO_SPEED),
+            Err(CameraError::DeviceNotFound(_))
+        ));
+        // Call errors don't cost the connection
+        assert!(sdk.is_connected());
+        assert_eq!(helper.starts(), 1);
+    }
+
+    #[test]
+    fn crashed_helper_is_restarted_with_sessions_reopened() {
+        let helper = FakeHelper::new(
+            MockEdsSdk::new()
+                .with_cameras(1)
+                .with_live_view_frame(vec![1, 2, 3]),
+            vec![Fault::CrashAfter(2)],
+        );
+        let sdk = remote(&helper);
+        let camera = CameraHandle(0);
+        sdk.open_session(camera).unwrap();
+        sdk.start_live_view(camera).unwrap();
+
+        // The helper dies before answering
+        let err = sdk.download_evf_image(camera).unwrap_err();
+        assert!(err.to_string().contains("download_evf_image"), "{err}");
+        assert!(!sdk.is_connected());
+
+        // The next call starts a new helper, which has live view running again
+        helper.sdk.stop_live_view(camera).unwrap();
+        assert_eq!(sdk.download_evf_image(camera).unwrap(), vec![1, 2, 3]);
+        assert_eq!(helper.starts(), 2);
+    }
+
+    #[test]
+    fn closed_sessions_are_not_reopened() {
+        let helper = FakeHelper::new(
+            MockEdsSdk::new()
+                .with_cameras(1)
+                .with_live_view_frame(vec![1]),
+            vec![Fault::CrashAfter(3)],
+        );
+        let sdk = remote(&helper);
+        let camera = CameraHandle(0);
+        sdk.open_session(camera).unwrap();
+        sdk.start_live_view(camera).unwrap();
+        sdk.close_session(camera).unwrap();
+        helper.sdk.stop_live_view(camera).unwrap();
+
+        assert!(sdk.get_event().is_err());
+        assert!(sdk.get_event().is_ok());
+        assert!(sdk.download_evf_image(camera).is_err());
+    }
+
+    #[test]
+    fn hung_helper_times_out_and_is_replaced() {
+        let helper = FakeHelper::new(MockEdsSdk::new().with_cameras(1), vec![Fault::Hang]);
+        let sdk = remote(&helper);
+
+        let started = Instant::now();
+        let err = sdk.camera_list().unwrap_err();
+        assert!(err.to_string().contains("no answer"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        assert_eq!(sdk.camera_list().unwrap(), vec![CameraHandle(0)]);
+        assert_eq!(helper.starts(), 2);
+    }
+
+    #[test]
+    fn restarts_are_rate_limited() {
+        let helper = FakeHelper::new(
+            MockEdsSdk::new().with_cameras(1),
+            vec![Fault::CrashAfter(0)],
+        );
+        let sdk = RemoteEdsSdk::new(Arc::clone(&helper))
+            .unwrap()
+            .with_timeout(Duration::from_millis(200))
+            .with_restart_delay(Duration::from_secs(60));
+
+        assert!(sdk.camera_list().is_err());
+        let err = sdk.camera_list().unwrap_err();
+        assert!(err.to_string().contains("restarting"), "{err}");
+        assert_eq!(helper.starts(), 1);
+    }
+
+    #[test]
+    fn version_mismatch_fails_to_connect() {
+        let helper = FakeHelper::new(
+            MockEdsSdk::new(),
+            vec![Fault::Version(PROTOCOL_VERSION + 1)],
+        );
+        let err = RemoteEdsSdk::new(Arc::clone(&helper))
+            .err()
+            .expect("handshake should fail");
+        assert!(err.to_string().contains("protocol version"), "{err}");
+    }
+}
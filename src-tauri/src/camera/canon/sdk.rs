@@ -87,6 +87,9 @@ pub struct EdsSdk {
     cameras: Mutex<HashMap<CameraHandle, SendSyncPtr>>,
     /// The camera list reference from the most recent enumeration.
     camera_list_ref: Mutex<Option<SendSyncPtr>>,
+    /// EVF geometry read from each camera's latest live view frame.
+    /// Cleared when live view stops.
+    evf_geometry: Mutex<HashMap<CameraHandle, EvfGeometry>>,
 }
 
 impl EdsSdk {
@@ -121,6 +124,7 @@ impl EdsSdk {
             _com: com,
             cameras: Mutex::new(HashMap::new()),
             camera_list_ref: Mutex::new(None),
+            evf_geometry: Mutex::new(HashMap::new()),
         })
     }
 
@@ -147,6 +151,8 @@ impl EdsSdk {
                 ffi::EdsRelease(ptr.0);
             }
         }
+
+        self.evf_geometry.lock().unwrap().clear();
     }
 }
 
@@ -292,6 +298,7 @@ impl EdsSdkApi for EdsSdk {
                 &evf_mode as *const u32 as *const std::ffi::c_void,
             )
         };
+        self.evf_geometry.lock().unwrap().remove(&camera);
         if err != EDS_ERR_OK {
             return Err(CameraError::CanonSdkError(format!(
                 "stop_live_view (set EVF output) failed: {}",
@@ -341,6 +348,14 @@ impl EdsSdkApi for EdsSdk {
             // Read the JPEG data from the memory stream
             let data = read_stream_data(stream);
 
+            // AF point placement needs the geometry that came with this frame
+            match read_evf_geometry(evf_image) {
+                Some(geometry) => {
+                    self.evf_geometry.lock().unwrap().insert(camera, geometry);
+                }
+                None => tracing::debug!("EVF geometry unavailable for camera {}", camera.0),
+            }
+
             ffi::EdsRelease(evf_image);
             ffi::EdsRelease(stream);
 
@@ -421,6 +436,57 @@ impl EdsSdkApi for EdsSdk {
         }
     }
 
+    fn set_point_property(
+        &self,
+        camera: CameraHandle,
+        prop: EdsPropertyID,
+        value: EdsPoint,
+    ) -> Result<()> {
+        let camera_ref = self.get_camera_ref(camera)?;
+        let err = unsafe {
+            ffi::EdsSetPropertyData(
+                camera_ref,
+                prop,
+                0,
+                std::mem::size_of::<EdsPoint>() as u32,
+                &value as *const EdsPoint as *const std::ffi::c_void,
+            )
+        };
+        if err != EDS_ERR_OK {
+            return Err(CameraError::CanonSdkError(format!(
+                "EdsSetPropertyData(0x{prop:04X}) failed: {} (0x{:08X})",
+                error_description(err),
+                err
+            )));
+        }
+        Ok(())
+    }
+
+    fn evf_geometry(&self, camera: CameraHandle) -> Result<Option<EvfGeometry>> {
+        // Validate the handle so unknown cameras error rather than report
+        // "live view not active"
+        self.get_camera_ref(camera)?;
+        Ok(self.evf_geometry.lock().unwrap().get(&camera).copied())
+    }
+
+    fn send_command(
+        &self,
+        camera: CameraHandle,
+        command: EdsCameraCommand,
+        param: i32,
+    ) -> Result<()> {
+        let camera_ref = self.get_camera_ref(camera)?;
+        let err = unsafe { ffi::EdsSendCommand(camera_ref, command, param) };
+        if err != EDS_ERR_OK {
+            return Err(CameraError::CanonSdkError(format!(
+                "EdsSendCommand(0x{command:04X}, {param}) failed: {} (0x{:08X})",
+                error_description(err),
+                err
+            )));
+        }
+        Ok(())
+    }
+
     fn get_event(&self) -> Result<()> {
         unsafe {
             let err = ffi::EdsGetEvent();
@@ -466,6 +532,34 @@ mod tests {
     }
 }
 
+/// Read the coordinate system and zoom rectangle attached to an EVF image.
+///
+/// Returns `None` if either property can't be read (older bodies).
+unsafe fn read_evf_geometry(evf_image: EdsEvfImageRef) -> Option<EvfGeometry> {
+    let mut geometry = EvfGeometry::default();
+    let err = ffi::EdsGetPropertyData(
+        evf_image,
+        PROP_ID_EVF_COORDINATE_SYSTEM,
+        0,
+        std::mem::size_of::<EdsSize>() as u32,
+        &mut geometry.coordinate_system as *mut EdsSize as *mut std::ffi::c_void,
+    );
+    if err != EDS_ERR_OK {
+        return None;
+    }
+    let err = ffi::EdsGetPropertyData(
+        evf_image,
+        PROP_ID_EVF_ZOOM_RECT,
+        0,
+        std::mem::size_of::<EdsRect>() as u32,
+        &mut geometry.zoom_rect as *mut EdsRect as *mut std::ffi::c_void,
+    );
+    if err != EDS_ERR_OK {
+        return None;
+    }
+    Some(geometry)
+}
+
 /// Read all data from an EDSDK memory stream.
 ///
 /// Reads the stream length via `EdsGetLength`, then copies the raw bytes
@@ -2,6 +2,8 @@
 //!
 //! Values sourced from the Canon EDSDK C header files (EDSDK.h, EDSDKTypes.h).
 
+use serde::{Deserialize, Serialize};
+
 /// EDSDK error code type.
 pub type EdsError = u32;
 
@@ -69,6 +71,43 @@ pub struct EdsPropertyDesc {
     pub prop_desc: Vec<i32>,
 }
 
+/// Point in EDSDK coordinates. Layout matches `tagEdsPoint`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct EdsPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Size in EDSDK coordinates. Layout matches `tagEdsSize`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct EdsSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Rectangle in EDSDK coordinates. Layout matches `tagEdsRect`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct EdsRect {
+    pub point: EdsPoint,
+    pub size: EdsSize,
+}
+
+/// Geometry reported with each live view frame.
+///
+/// Zoom position and AF point are set in the coordinate system (typically
+/// the full sensor size), not in live view pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvfGeometry {
+    /// Extent of the coordinate system used by `PROP_ID_EVF_ZOOM_POSITION`.
+    pub coordinate_system: EdsSize,
+    /// Current zoom (AF) rectangle within the coordinate system.
+    pub zoom_rect: EdsRect,
+}
+
 // --- Error codes ---
 
 /// Operation completed successfully.
@@ -120,10 +159,20 @@ pub const PROP_ID_TV: EdsPropertyID = 0x00000404;
 pub const PROP_ID_EXPOSURE_COMPENSATION: EdsPropertyID = 0x00000406;
 /// White balance property.
 pub const PROP_ID_WHITE_BALANCE: EdsPropertyID = 0x00000403;
+/// Image quality property: size, compression and RAW of the stills taken.
+pub const PROP_ID_IMAGE_QUALITY: EdsPropertyID = 0x00000100;
+/// Picture style property.
+pub const PROP_ID_PICTURE_STYLE: EdsPropertyID = 0x00000114;
 /// Battery level property.
 pub const PROP_ID_BATTERY_LEVEL: EdsPropertyID = 0x00000006;
 /// EVF output device property (used to enable/disable live view).
 pub const PROP_ID_EVF_OUTPUT_DEVICE: EdsPropertyID = 0x00000500;
+/// EVF zoom position (`EdsPoint`): top-left of the zoom/AF rectangle.
+pub const PROP_ID_EVF_ZOOM_POSITION: EdsPropertyID = 0x00000508;
+/// EVF coordinate system (`EdsSize`), read from the EVF image.
+pub const PROP_ID_EVF_COORDINATE_SYSTEM: EdsPropertyID = 0x00000540;
+/// EVF zoom rectangle (`EdsRect`), read from the EVF image.
+pub const PROP_ID_EVF_ZOOM_RECT: EdsPropertyID = 0x00000541;
 
 // --- EVF output device flags ---
 
@@ -140,6 +189,19 @@ pub const CAMERA_COMMAND_EVF_MODE: EdsCameraCommand = 0x00000002;
 pub const CAMERA_COMMAND_TAKE_PICTURE: EdsCameraCommand = 0x00000000;
 /// Press the shutter button.
 pub const CAMERA_COMMAND_PRESS_SHUTTER: EdsCameraCommand = 0x00000004;
+/// Run (or cancel) live view autofocus at the current AF point.
+pub const CAMERA_COMMAND_DO_EVF_AF: EdsCameraCommand = 0x00000102;
+
+/// `CAMERA_COMMAND_PRESS_SHUTTER` parameter: release the shutter button.
+pub const SHUTTER_BUTTON_OFF: i32 = 0x00000000;
+/// `CAMERA_COMMAND_PRESS_SHUTTER` parameter: press the shutter button all
+/// the way without autofocusing first.
+pub const SHUTTER_BUTTON_COMPLETELY_NON_AF: i32 = 0x00010003;
+
+/// `CAMERA_COMMAND_DO_EVF_AF` parameter: stop autofocus.
+pub const EVF_AF_OFF: i32 = 0;
+/// `CAMERA_COMMAND_DO_EVF_AF` parameter: start autofocus.
+pub const EVF_AF_ON: i32 = 1;
 
 // --- State events ---
 
@@ -197,12 +259,31 @@ mod tests {
         assert_eq!(PROP_ID_TV, 0x00000404);
         assert_eq!(PROP_ID_EXPOSURE_COMPENSATION, 0x00000406);
         assert_eq!(PROP_ID_WHITE_BALANCE, 0x00000403);
+        assert_eq!(PROP_ID_IMAGE_QUALITY, 0x00000100);
+        assert_eq!(PROP_ID_PICTURE_STYLE, 0x00000114);
     }
 
     #[test]
     fn command_constants_are_defined() {
         assert_eq!(CAMERA_COMMAND_EVF_MODE, 0x00000002);
         assert_eq!(CAMERA_COMMAND_TAKE_PICTURE, 0x00000000);
+        assert_eq!(CAMERA_COMMAND_DO_EVF_AF, 0x00000102);
+        assert_eq!(CAMERA_COMMAND_PRESS_SHUTTER, 0x00000004);
+        assert_eq!(SHUTTER_BUTTON_COMPLETELY_NON_AF, 0x00010003);
+    }
+
+    #[test]
+    fn evf_property_ids_have_correct_values() {
+        assert_eq!(PROP_ID_EVF_ZOOM_POSITION, 0x00000508);
+        assert_eq!(PROP_ID_EVF_COORDINATE_SYSTEM, 0x00000540);
+        assert_eq!(PROP_ID_EVF_ZOOM_RECT, 0x00000541);
+    }
+
+    #[test]
+    fn geometry_structs_match_c_layout() {
+        assert_eq!(std::mem::size_of::<EdsPoint>(), 8);
+        assert_eq!(std::mem::size_of::<EdsSize>(), 8);
+        assert_eq!(std::mem::size_of::<EdsRect>(), 16);
     }
 
     #[test]
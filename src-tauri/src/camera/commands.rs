@@ -1,43 +1,524 @@
-use tauri::State;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager, State};
 
 use crate::camera::backend::CameraBackend;
-use crate::camera::error::humanise_error;
+use crate::camera::composite::CompositeBackend;
+use crate::camera::formats::{group_formats, ResolutionGroup};
+use crate::camera::powerline::{self, PowerLineSuggestion};
+use crate::camera::queue::DeviceQueue;
+use crate::camera::siblings::group_siblings;
+use crate::camera::suggest::{describe_device, rank_devices, CameraSuggestion};
+use crate::camera::swap::{SwapOutcome, SwappableBackend};
+use crate::camera::tally::{self, plan_tally, TallyTrigger};
 use crate::camera::types::{
-    CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId, FormatDescriptor,
+    BackendCapabilities, CameraDevice, ControlId, ControlValue, ControlValueResponse, DeviceId,
+    FormatDescriptor, SnappedValue,
+};
+use crate::camera::units;
+use crate::diagnostics::compat::{assemble_report, observe_device, CompatReport};
+use crate::diagnostics::control_latency::{ControlLatencyState, ControlLatencyStats};
+use crate::error::{code, AppError};
+use crate::preview::capture::PreviewSession;
+use crate::preview::commands::{probe_preview, refresh_warm_default, PreviewState};
+use crate::settings::apply::{
+    find_descriptor, find_descriptor_by_str, parse_control_id, read_control, reset_control,
+    write_control, write_control_auto, write_control_by_str,
 };
 use crate::settings::commands::SettingsState;
+use crate::settings::control_cache::{lookup_controls, refresh_controls, unix_now, CameraControls};
+use crate::settings::store::SettingsStore;
+use crate::CanonSdkState;
 
 /// Shared camera state managed by Tauri.
+///
+/// Commands go through `backend`, which can be rebuilt at runtime (e.g. when
+/// the Canon integration is toggled). Platform backends are created once and
+/// reused by every rebuild.
 pub struct CameraState {
-    pub backend: Box<dyn CameraBackend>,
+    pub backend: SwappableBackend,
+    platform: Vec<Arc<dyn CameraBackend>>,
+    /// The Canon backend in the current composite, if loaded.
+    canon: Mutex<Option<Arc<dyn CameraBackend>>>,
+    show_suppressed: Arc<AtomicBool>,
 }
 
-/// Parse a string control ID to a `ControlId` enum, returning a
-/// human-readable error on failure.
-fn parse_control_id(s: &str) -> Result<ControlId, String> {
-    ControlId::from_str_id(s).ok_or_else(|| format!("Unknown control: '{s}'"))
+impl CameraState {
+    pub fn new(
+        platform: Vec<Arc<dyn CameraBackend>>,
+        canon: Option<Box<dyn CameraBackend>>,
+    ) -> Self {
+        let show_suppressed = Arc::new(AtomicBool::new(false));
+        let canon: Option<Arc<dyn CameraBackend>> = canon.map(Arc::from);
+        Self {
+            backend: SwappableBackend::new(compose(&platform, canon.clone(), &show_suppressed)),
+            platform,
+            canon: Mutex::new(canon),
+            show_suppressed,
+        }
+    }
+
+    /// Replace the backend with the platform backends plus `canon`.
+    ///
+    /// See [`SwappableBackend::swap`] for how hotplug watchers and device
+    /// changes are handled.
+    pub fn rebuild(
+        &self,
+        canon: Option<Box<dyn CameraBackend>>,
+    ) -> crate::camera::error::Result<SwapOutcome> {
+        let canon: Option<Arc<dyn CameraBackend>> = canon.map(Arc::from);
+        let outcome = self
+            .backend
+            .swap(|| compose(&self.platform, canon.clone(), &self.show_suppressed))?;
+        *self.canon.lock().unwrap_or_else(|e| e.into_inner()) = canon;
+        Ok(outcome)
+    }
+
+    /// The backends the composite is built from, each with its priority,
+    /// for enumerating them one by one.
+    pub fn sources(&self) -> Vec<(Arc<dyn CameraBackend>, u8)> {
+        let canon = self.canon.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        self.platform
+            .iter()
+            .map(|b| (Arc::clone(b), PLATFORM_PRIORITY))
+            .chain(canon.map(|b| (b, CANON_PRIORITY)))
+            .collect()
+    }
+
+    /// Whether suppressed duplicates are listed.
+    pub fn show_suppressed(&self) -> bool {
+        self.show_suppressed.load(Ordering::Relaxed)
+    }
+
+    /// List devices hidden as duplicates of another backend's, marked with
+    /// `suppressed_by`, from the next enumeration on.
+    pub fn set_show_suppressed(&self, show: bool) {
+        self.show_suppressed.store(show, Ordering::Relaxed);
+    }
+}
+
+/// Priority of the platform backends when one camera is exposed twice.
+const PLATFORM_PRIORITY: u8 = 0;
+/// Priority of the Canon backend: EDSDK controls a Canon body fully, so it
+/// wins over the same body seen through the EOS Webcam Utility driver.
+const CANON_PRIORITY: u8 = 1;
+
+fn compose(
+    platform: &[Arc<dyn CameraBackend>],
+    canon: Option<Arc<dyn CameraBackend>>,
+    show_suppressed: &Arc<AtomicBool>,
+) -> Box<dyn CameraBackend> {
+    let mut backends: Vec<(Box<dyn CameraBackend>, u8)> = platform
+        .iter()
+        .map(|b| {
+            (
+                Box::new(Arc::clone(b)) as Box<dyn CameraBackend>,
+                PLATFORM_PRIORITY,
+            )
+        })
+        .collect();
+    backends.extend(canon.map(|b| (Box::new(b) as Box<dyn CameraBackend>, CANON_PRIORITY)));
+    Box::new(
+        CompositeBackend::with_priorities(backends)
+            .with_show_suppressed(Arc::clone(show_suppressed)),
+    )
 }
 
 /// List all connected cameras.
+///
+/// Saved settings whose camera now reports a different name are renamed to
+/// match, each announced with a `camera-renamed` event.
+#[tauri::command]
+pub async fn list_cameras(
+    app: AppHandle,
+    state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+) -> Result<Vec<CameraDevice>, AppError> {
+    let devices = state.backend.enumerate_devices().map(group_siblings)?;
+    refresh_camera_names(&app, &settings_state.store, &devices);
+    Ok(devices)
+}
+
+/// Look up one camera, or `None` if it isn't connected.
+///
+/// Cheaper than [`list_cameras`] where the backend remembers the devices it
+/// has seen; a device it doesn't know yet is found by enumerating.
+#[tauri::command]
+pub async fn get_camera(
+    state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<Option<CameraDevice>, AppError> {
+    Ok(state.backend.get_device(&DeviceId::new(device_id.trim()))?)
+}
+
+/// Whether the Canon EDSDK backend is enabled.
 #[tauri::command]
-pub async fn list_cameras(state: State<'_, CameraState>) -> Result<Vec<CameraDevice>, String> {
+pub async fn get_canon_enabled(settings_state: State<'_, SettingsState>) -> Result<bool, AppError> {
+    Ok(settings_state.store.canon_enabled())
+}
+
+/// Load or unload the Canon EDSDK backend without restarting the app.
+///
+/// Canon cameras appearing or disappearing are reported as `camera-hotplug`
+/// events, then the full list is emitted as `cameras-changed` and returned.
+/// The preference is saved even if the SDK fails to load.
+#[tauri::command]
+pub async fn set_canon_enabled(
+    app: AppHandle,
+    state: State<'_, CameraState>,
+    canon_state: State<'_, CanonSdkState>,
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<Vec<CameraDevice>, AppError> {
+    settings_state.store.set_canon_enabled(enabled);
+
+    if enabled == canon_state.is_loaded() {
+        return state
+            .backend
+            .enumerate_devices()
+            .map(group_siblings)
+            .map_err(AppError::from);
+    }
+
+    let canon = if enabled {
+        Some(
+            canon_state
+                .load_backend()
+                .map_err(AppError::with_code(code::CANON_SDK))?,
+        )
+    } else {
+        None
+    };
+    let outcome = state.rebuild(canon);
+    // Release the SDK once the Canon backend is gone: after a successful
+    // disable, or when a freshly loaded one never made it in
+    if enabled != outcome.is_ok() {
+        canon_state.unload();
+    }
+    let devices = group_siblings(outcome?.devices);
+    refresh_camera_names(&app, &settings_state.store, &devices);
+
+    if let Err(e) = app.emit("cameras-changed", &devices) {
+        tracing::warn!("Failed to emit cameras-changed event: {e}");
+    }
+    Ok(devices)
+}
+
+/// Whether cameras hidden as duplicates of another backend's device are
+/// listed.
+#[tauri::command]
+pub async fn get_show_suppressed_devices(
+    settings_state: State<'_, SettingsState>,
+) -> Result<bool, AppError> {
+    Ok(settings_state.store.show_suppressed_devices())
+}
+
+/// List (or hide) cameras suppressed as duplicates of another backend's
+/// device, e.g. a Canon body seen through both EDSDK and the EOS Webcam
+/// Utility. Listed ones carry `suppressedBy`. The new list is emitted as
+/// `cameras-changed` and returned.
+#[tauri::command]
+pub async fn set_show_suppressed_devices(
+    app: AppHandle,
+    state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<Vec<CameraDevice>, AppError> {
+    settings_state.store.set_show_suppressed_devices(enabled);
+    state.set_show_suppressed(enabled);
+
+    let devices = state.backend.enumerate_devices().map(group_siblings)?;
+    if let Err(e) = app.emit("cameras-changed", &devices) {
+        tracing::warn!("Failed to emit cameras-changed event: {e}");
+    }
+    Ok(devices)
+}
+
+/// How long each preview gets to deliver a frame when suggesting a default
+/// camera with probing on.
+const SUGGEST_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Rank connected cameras for use as the default, best first, with the
+/// score breakdown for each.
+///
+/// With `probe`, cameras whose preview is running are also scored on
+/// whether it delivers a frame within a short wait; cameras without a
+/// running preview are scored without it.
+#[tauri::command]
+pub async fn suggest_default_camera(
+    app: AppHandle,
+    state: State<'_, CameraState>,
+    probe: bool,
+) -> Result<Vec<CameraSuggestion>, AppError> {
+    let devices = state.backend.enumerate_devices().map(group_siblings)?;
+
+    let mut infos = Vec::with_capacity(devices.len());
+    for device in &devices {
+        let produced_frame = if probe {
+            probe_preview(&app, device.id.as_str(), SUGGEST_PROBE_TIMEOUT).await
+        } else {
+            None
+        };
+        infos.push(describe_device(&state.backend, device, produced_frame));
+    }
+    Ok(rank_devices(&infos))
+}
+
+/// Power-line frequency (anti-flicker) setting suggested for this machine,
+/// inferred from the OS region.
+#[tauri::command]
+pub async fn suggest_powerline_frequency() -> Result<PowerLineSuggestion, AppError> {
+    Ok(powerline::suggest_powerline_frequency())
+}
+
+/// Turn a camera's tally light on or off. The state isn't saved: it
+/// reflects what's live now, not a setting to restore.
+#[tauri::command]
+pub async fn set_tally(
+    state: State<'_, CameraState>,
+    device_id: String,
+    on: bool,
+) -> Result<(), AppError> {
+    let id = DeviceId::new(&device_id);
+    find_descriptor(&state.backend, &id, &ControlId::TallyLight)?;
+    write_tally(&state.backend, &id, on)
+}
+
+/// Whether tally lights follow the default camera and scene activations.
+#[tauri::command]
+pub async fn get_tally_auto(settings_state: State<'_, SettingsState>) -> Result<bool, AppError> {
+    Ok(settings_state.store.tally_auto())
+}
+
+/// Make tally lights follow the default camera and scene activations, or
+/// leave them to `set_tally`.
+#[tauri::command]
+pub async fn set_tally_auto(
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_state.store.set_tally_auto(enabled);
+    Ok(())
+}
+
+fn write_tally(backend: &dyn CameraBackend, id: &DeviceId, on: bool) -> Result<(), AppError> {
+    backend
+        .set_control(
+            id,
+            &ControlId::TallyLight,
+            ControlValue::new(i32::from(on), None, None),
+        )
+        .map_err(AppError::from)
+}
+
+/// Light the tally of whatever `trigger` made live and turn every other
+/// connected camera's off, if tally lights are automatic. Cameras without a
+/// tally light are skipped; failed writes are logged.
+pub fn follow_tally(app: &AppHandle, trigger: TallyTrigger) {
+    if !app.state::<SettingsState>().store.tally_auto() {
+        return;
+    }
+    let camera = app.state::<CameraState>();
+    let backend = &camera.backend;
+    let devices = match backend.enumerate_devices() {
+        Ok(devices) => devices,
+        Err(e) => {
+            tracing::warn!("Can't update tally lights: {e}");
+            return;
+        }
+    };
+    let capable: Vec<&DeviceId> = devices
+        .iter()
+        .filter(|device| {
+            backend
+                .get_controls(&device.id)
+                .is_ok_and(|controls| tally::has_tally(&controls))
+        })
+        .map(|device| &device.id)
+        .collect();
+
+    for (device_id, on) in plan_tally(capable.iter().map(|id| id.as_str()), trigger) {
+        if let Err(e) = write_tally(backend, &DeviceId::new(&device_id), on) {
+            tracing::warn!(
+                "Failed to turn tally light of {device_id} {}: {e}",
+                if on { "on" } else { "off" }
+            );
+        }
+    }
+}
+
+/// Fire a Canon camera's shutter to take a still, saved wherever the body
+/// saves to. Live view keeps running. Other cameras are rejected as
+/// `unsupported`.
+#[tauri::command]
+pub async fn canon_take_photo(
+    state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<(), AppError> {
     state
         .backend
-        .enumerate_devices()
-        .map_err(|e| humanise_error(&e.to_string()))
+        .take_photo(&DeviceId::new(&device_id))
+        .map_err(AppError::from)
+}
+
+/// Generate an anonymous compatibility report for the connected cameras and
+/// write it as JSON to `path`, returning it for review.
+///
+/// Serials, device paths and device IDs are redacted. Frame stats are only
+/// reported for cameras whose preview is running.
+#[tauri::command]
+pub async fn generate_compat_report(
+    app: AppHandle,
+    state: State<'_, CameraState>,
+    path: String,
+) -> Result<CompatReport, AppError> {
+    let devices = state.backend.enumerate_devices()?;
+
+    let observations: Vec<_> = devices
+        .into_iter()
+        .map(|device| {
+            // Canon sessions don't keep capture counters.
+            let diagnostics = app.try_state::<PreviewState>().and_then(|preview| {
+                let sessions = preview.sessions.lock();
+                match sessions.get(device.id.as_str()) {
+                    Some(session @ PreviewSession::DirectShow(_)) => Some(session.diagnostics()),
+                    _ => None,
+                }
+            });
+            observe_device(&state.backend, device, diagnostics)
+        })
+        .collect();
+
+    let report = assemble_report(
+        &app.package_info().version.to_string(),
+        std::env::consts::OS,
+        &observations,
+    );
+    let json =
+        serde_json::to_string_pretty(&report).map_err(AppError::with_code(code::INTERNAL))?;
+    std::fs::write(&path, json).map_err(|e| {
+        AppError::new(
+            code::SETTINGS_IO,
+            format!("Failed to write the report: {e}"),
+        )
+    })?;
+    Ok(report)
+}
+
+/// Seed the default camera preference from the top-ranked of `devices` if
+/// it hasn't been set. Doesn't probe — previews may not be running yet.
+pub fn seed_default_camera(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    devices: &[CameraDevice],
+) {
+    if store.default_camera().is_some() {
+        return;
+    }
+    let infos: Vec<_> = group_siblings(devices.to_vec())
+        .iter()
+        .map(|device| describe_device(backend, device, None))
+        .collect();
+    if let Some(best) = rank_devices(&infos).first() {
+        tracing::info!(
+            "Suggested '{}' as the default camera (score {})",
+            best.name,
+            best.score
+        );
+        store.set_default_camera(&best.device_id);
+    }
+}
+
+/// Rename saved cameras to the names `devices` now report, emitting a
+/// `camera-renamed` event for each, and mark them seen. Driver updates can
+/// change a camera's friendly name without changing its device ID.
+pub fn refresh_camera_names(app: &AppHandle, store: &SettingsStore, devices: &[CameraDevice]) {
+    store.mark_seen(devices, unix_now());
+    for rename in store.reconcile_names(devices) {
+        tracing::info!(
+            "Camera '{}' is now reported as '{}'",
+            rename.old_name,
+            rename.new_name
+        );
+        if let Err(e) = app.emit("camera-renamed", &rename) {
+            tracing::warn!("Failed to emit camera-renamed event: {e}");
+        }
+    }
+}
+
+/// Device ID of the default camera, if one has been chosen or suggested.
+#[tauri::command]
+pub async fn get_default_camera(
+    settings_state: State<'_, SettingsState>,
+) -> Result<Option<String>, AppError> {
+    Ok(settings_state.store.default_camera())
+}
+
+/// Choose the default camera. With automatic tally lights on, its light
+/// comes on and the others go off.
+#[tauri::command]
+pub async fn set_default_camera(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+) -> Result<(), AppError> {
+    settings_state.store.set_default_camera(&device_id);
+    refresh_warm_default(&app);
+    follow_tally(&app, TallyTrigger::DefaultCamera(&device_id));
+    Ok(())
 }
 
 /// Get all supported controls for a camera.
+///
+/// Serves the last-known list from the settings cache when it's still valid
+/// for the device (`cached: true`), then queries the camera in the
+/// background and emits `controls-refreshed` with the fresh list.
 #[tauri::command]
 pub async fn get_camera_controls(
+    app: AppHandle,
     state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
     device_id: String,
-) -> Result<Vec<ControlDescriptor>, String> {
+) -> Result<CameraControls, AppError> {
     let id = DeviceId::new(device_id);
-    state
-        .backend
-        .get_controls(&id)
-        .map_err(|e| humanise_error(&e.to_string()))
+    let device = state.backend.known_device(&id);
+    let controls = lookup_controls(
+        &state.backend,
+        &settings_state.store,
+        &id,
+        device.as_ref(),
+        unix_now(),
+    )?;
+
+    if let (true, Some(device)) = (controls.cached, device) {
+        spawn_controls_refresh(app, device);
+    }
+    Ok(controls)
+}
+
+/// Query `device`'s controls off the command thread, update the cache and
+/// emit `controls-refreshed`.
+fn spawn_controls_refresh(app: AppHandle, device: CameraDevice) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (Some(camera), Some(settings)) = (
+            app.try_state::<CameraState>(),
+            app.try_state::<SettingsState>(),
+        ) else {
+            return;
+        };
+        match refresh_controls(&camera.backend, &settings.store, &device, unix_now()) {
+            Ok(refreshed) => {
+                if let Err(e) = app.emit("controls-refreshed", &refreshed) {
+                    tracing::warn!("Failed to emit controls-refreshed event: {e}");
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Refreshing controls for '{}' failed: {e}", device.name);
+            }
+        }
+    });
 }
 
 /// Get supported video formats for a camera.
@@ -45,96 +526,235 @@ pub async fn get_camera_controls(
 pub async fn get_camera_formats(
     state: State<'_, CameraState>,
     device_id: String,
-) -> Result<Vec<FormatDescriptor>, String> {
+) -> Result<Vec<FormatDescriptor>, AppError> {
+    let id = DeviceId::new(device_id);
+    state.backend.get_formats(&id).map_err(AppError::from)
+}
+
+/// Get supported video formats grouped by resolution, largest first, with
+/// the frame rates each pixel format offers.
+#[tauri::command]
+pub async fn get_camera_formats_grouped(
+    state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<Vec<ResolutionGroup>, AppError> {
     let id = DeviceId::new(device_id);
     state
         .backend
         .get_formats(&id)
-        .map_err(|e| humanise_error(&e.to_string()))
+        .map(|formats| group_formats(&formats))
+        .map_err(AppError::from)
+}
+
+/// Get which optional features a camera's backend supports, so the UI can
+/// hide the ones it doesn't rather than let them fail.
+#[tauri::command]
+pub async fn get_device_capabilities(
+    state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<BackendCapabilities, AppError> {
+    let id = DeviceId::new(device_id);
+    state
+        .backend
+        .device_capabilities(&id)
+        .map_err(AppError::from)
 }
 
 /// Set a camera control value and persist the change.
+///
+/// The value is clamped to the control's range and snapped to its step; the
+/// response carries the value written so the UI can move the slider to it.
 #[tauri::command]
 pub async fn set_camera_control(
     state: State<'_, CameraState>,
     settings_state: State<'_, SettingsState>,
+    latency_state: State<'_, ControlLatencyState>,
     device_id: String,
     control_id: String,
     value: i32,
     camera_name: String,
-) -> Result<(), String> {
-    let id = DeviceId::new(&device_id);
+) -> Result<SnappedValue, AppError> {
+    write_control_by_str(
+        &state.backend,
+        &settings_state.store,
+        &latency_state,
+        &device_id,
+        &camera_name,
+        &control_id,
+        value,
+    )
+}
+
+/// Read one control's current value, for refreshing a single widget
+/// without fetching every control.
+#[tauri::command]
+pub async fn get_camera_control(
+    state: State<'_, CameraState>,
+    device_id: String,
+    control_id: String,
+) -> Result<ControlValueResponse, AppError> {
     let control = parse_control_id(&control_id)?;
+    read_control(&state.backend, &device_id, control)
+}
 
-    // Look up the descriptor to know the valid range
-    let descriptors = state
-        .backend
-        .get_controls(&id)
-        .map_err(|e| humanise_error(&e.to_string()))?;
-    let desc = descriptors
-        .iter()
-        .find(|d| d.id == control_id)
-        .ok_or_else(|| {
-            format!(
-                "Control '{}' not supported on this device",
-                control.display_name()
-            )
-        })?;
+/// Switch a control between automatic and manual mode and persist the mode.
+///
+/// Switching to manual keeps the value the control currently holds.
+#[tauri::command]
+pub async fn set_camera_control_auto(
+    state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    control_id: String,
+    auto: bool,
+    camera_name: String,
+) -> Result<(), AppError> {
+    let control = parse_control_id(&control_id)?;
+    write_control_auto(
+        &state.backend,
+        &settings_state.store,
+        &device_id,
+        &camera_name,
+        control,
+        auto,
+    )
+}
 
-    if desc.flags.is_read_only {
-        return Err(format!("Control '{}' is read-only", control.display_name()));
+/// Set absolute exposure in seconds, converted to the device's log2 scale.
+///
+/// Values outside the device range are clamped. Returns the exposure time
+/// actually applied, in seconds.
+#[tauri::command]
+pub async fn set_exposure_seconds(
+    state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    latency_state: State<'_, ControlLatencyState>,
+    device_id: String,
+    seconds: f64,
+    camera_name: String,
+) -> Result<f64, AppError> {
+    let id = DeviceId::new(&device_id);
+    let desc = find_descriptor(&state.backend, &id, &ControlId::Exposure)?;
+    let native = units::exposure_seconds_to_native(seconds, desc.min, desc.max)
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    if native.clamped {
+        tracing::info!(
+            "Exposure {seconds}s outside range of {device_id}, clamped to step {}",
+            native.value
+        );
     }
 
-    let clamped = ControlValue::new(value, desc.min, desc.max);
-    state
-        .backend
-        .set_control(&id, &control, clamped)
-        .map_err(|e| humanise_error(&e.to_string()))?;
+    let written = write_control(
+        &state.backend,
+        &settings_state.store,
+        &latency_state,
+        &device_id,
+        &camera_name,
+        ControlId::Exposure,
+        native.value,
+    )?;
+    Ok(units::exposure_native_to_seconds(written.value.value()))
+}
 
-    settings_state
-        .store
-        .set_control(&device_id, &camera_name, &control_id, clamped.value());
+/// Get the current absolute exposure in seconds.
+#[tauri::command]
+pub async fn get_exposure_seconds(
+    state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<f64, AppError> {
+    let id = DeviceId::new(&device_id);
+    let desc = find_descriptor(&state.backend, &id, &ControlId::Exposure)?;
+    Ok(units::exposure_native_to_seconds(desc.current))
+}
 
-    Ok(())
+/// Set focus as a position in `[0, 1]` across the device's focus range.
+///
+/// Returns the normalised position actually applied, after rounding to the
+/// device's native scale.
+#[tauri::command]
+pub async fn set_focus_normalized(
+    state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    latency_state: State<'_, ControlLatencyState>,
+    device_id: String,
+    value: f64,
+    camera_name: String,
+) -> Result<f64, AppError> {
+    let id = DeviceId::new(&device_id);
+    let desc = find_descriptor(&state.backend, &id, &ControlId::Focus)?;
+    let native = units::normalised_to_native(value, desc.min, desc.max)
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+
+    let written = write_control(
+        &state.backend,
+        &settings_state.store,
+        &latency_state,
+        &device_id,
+        &camera_name,
+        ControlId::Focus,
+        native.value,
+    )?;
+    units::native_to_normalised(written.value.value(), desc.min, desc.max)
+        .map_err(AppError::with_code(code::CONTROL_UNAVAILABLE))
 }
 
-/// Reset a camera control to its default value.
+/// Get the current focus as a position in `[0, 1]`.
+#[tauri::command]
+pub async fn get_focus_normalized(
+    state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<f64, AppError> {
+    let id = DeviceId::new(&device_id);
+    let desc = find_descriptor(&state.backend, &id, &ControlId::Focus)?;
+    units::native_to_normalised(desc.current, desc.min, desc.max)
+        .map_err(AppError::with_code(code::CONTROL_UNAVAILABLE))
+}
+
+/// Reset a camera control to its power-on state: automatic mode when that's
+/// its factory default, otherwise its default value.
 ///
-/// Returns the default value that was applied.
+/// Returns the value the control holds afterwards: the default that was
+/// written, or for a control put back in auto the value it had when auto took
+/// over.
 #[tauri::command]
 pub async fn reset_camera_control(
     state: State<'_, CameraState>,
+    latency_state: State<'_, ControlLatencyState>,
     device_id: String,
     control_id: String,
-) -> Result<i32, String> {
-    let id = DeviceId::new(device_id);
-    let control = parse_control_id(&control_id)?;
+) -> Result<i32, AppError> {
+    let id = DeviceId::new(&device_id);
+    let desc = find_descriptor_by_str(&state.backend, &id, &control_id)?;
 
-    let descriptors = state
-        .backend
-        .get_controls(&id)
-        .map_err(|e| humanise_error(&e.to_string()))?;
-    let desc = descriptors
-        .iter()
-        .find(|d| d.id == control_id)
+    reset_control(&state.backend, &latency_state, &device_id, &desc)?
+        .map(|reset| reset.value)
         .ok_or_else(|| {
-            format!(
-                "Control '{}' not supported on this device",
-                control.display_name()
+            AppError::new(
+                code::CONTROL_UNAVAILABLE,
+                format!("No default value for '{}'", desc.name),
             )
-        })?;
-
-    let default_val = desc
-        .default
-        .ok_or_else(|| format!("No default value for '{}'", control.display_name()))?;
+        })
+}
 
-    let clamped = ControlValue::new(default_val, desc.min, desc.max);
-    state
-        .backend
-        .set_control(&id, &control, clamped)
-        .map_err(|e| humanise_error(&e.to_string()))?;
+/// Get per-control write latency statistics for a camera.
+#[tauri::command]
+pub async fn get_control_latency_stats(
+    latency_state: State<'_, ControlLatencyState>,
+    device_id: String,
+) -> Result<Vec<ControlLatencyStats>, AppError> {
+    Ok(latency_state.stats_for_device(&device_id))
+}
 
-    Ok(default_val)
+/// Cancel a queued or running device operation (preview restart, preset
+/// apply, ...) by the ID reported in its `device-operation` events.
+///
+/// Returns whether the operation was still pending.
+#[tauri::command]
+pub async fn cancel_device_operation(
+    queue: State<'_, DeviceQueue>,
+    op_id: u64,
+) -> Result<bool, AppError> {
+    Ok(queue.cancel(op_id))
 }
 
 #[cfg(test)]
@@ -144,7 +764,7 @@ mod tests {
     use crate::camera::error::{CameraError, Result};
     use crate::camera::types::{
         CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue,
-        DeviceId, FormatDescriptor, HotplugEvent,
+        DeviceId, DeviceKind, FormatDescriptor, HotplugEvent,
     };
     use std::sync::Mutex;
 
@@ -189,6 +809,13 @@ mod tests {
             Ok(())
         }
 
+        fn set_control_auto(&self, id: &DeviceId, _control: &ControlId, _auto: bool) -> Result<()> {
+            if !self.devices.iter().any(|d| &d.id == id) {
+                return Err(CameraError::DeviceNotFound(id.to_string()));
+            }
+            Ok(())
+        }
+
         fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             if self.devices.iter().any(|d| &d.id == id) {
                 Ok(self.formats.clone())
@@ -205,6 +832,10 @@ mod tests {
                 name: "Test Camera".to_string(),
                 device_path: "test-path".to_string(),
                 is_connected: true,
+                kind: DeviceKind::Primary,
+                primary_id: None,
+                identity: None,
+                suppressed_by: None,
             }],
             controls: vec![ControlDescriptor {
                 id: "brightness".to_string(),
@@ -215,6 +846,7 @@ mod tests {
                 max: Some(255),
                 step: Some(1),
                 default: Some(128),
+                default_auto: false,
                 current: 128,
                 flags: ControlFlags {
                     supports_auto: false,
@@ -229,6 +861,8 @@ mod tests {
                 height: 1080,
                 fps: 30.0,
                 pixel_format: "MJPG".to_string(),
+                min_fps: None,
+                max_fps: None,
             }],
             last_set: Mutex::new(None),
         }
@@ -276,13 +910,103 @@ mod tests {
 
     #[test]
     fn camera_state_holds_backend() {
-        let state = CameraState {
-            backend: Box::new(make_test_backend()),
-        };
+        let state = CameraState::new(vec![Arc::new(make_test_backend())], None);
         let devices = state.backend.enumerate_devices().unwrap();
         assert_eq!(devices.len(), 1);
     }
 
+    #[test]
+    fn camera_state_rebuild_keeps_platform_backends() {
+        let state = CameraState::new(vec![Arc::new(make_test_backend())], None);
+        state.backend.enumerate_devices().unwrap();
+
+        let outcome = state.rebuild(None).unwrap();
+        assert_eq!(outcome.devices.len(), 1);
+        assert!(outcome.events.is_empty());
+        assert!(state
+            .backend
+            .get_controls(&DeviceId::new("test-device"))
+            .is_ok());
+    }
+
+    #[test]
+    fn camera_state_sources_follow_rebuilds() {
+        let state = CameraState::new(vec![Arc::new(make_test_backend())], None);
+        let priorities =
+            |state: &CameraState| -> Vec<u8> { state.sources().iter().map(|(_, p)| *p).collect() };
+        assert_eq!(priorities(&state), [PLATFORM_PRIORITY]);
+
+        state.rebuild(Some(Box::new(make_test_backend()))).unwrap();
+        assert_eq!(priorities(&state), [PLATFORM_PRIORITY, CANON_PRIORITY]);
+
+        state.rebuild(None).unwrap();
+        assert_eq!(priorities(&state), [PLATFORM_PRIORITY]);
+    }
+
+    #[test]
+    fn camera_state_routes_dummy_platform_and_canon_cameras() {
+        use crate::camera::dummy::DummyBackend;
+        use crate::diagnostics::control_latency::ControlLatencyState;
+        use crate::settings::apply::apply_saved_settings;
+        use crate::settings::store::SettingsStore;
+
+        // DUMMY_CAMERA=1 adds the dummy next to the real platform backend
+        let mut canon = make_test_backend();
+        canon.devices[0].id = DeviceId::new("canon:1");
+        canon.devices[0].name = "Canon EOS R5".to_string();
+        canon.devices[0].device_path = "canon-path".to_string();
+        canon.controls[0].id = "iso".to_string();
+        canon.controls[0].name = "ISO".to_string();
+        let state = CameraState::new(
+            vec![Arc::new(make_test_backend()), Arc::new(DummyBackend::new())],
+            Some(Box::new(canon)),
+        );
+
+        // What list_cameras returns
+        let devices = state
+            .backend
+            .enumerate_devices()
+            .map(group_siblings)
+            .unwrap();
+        let mut ids: Vec<&str> = devices.iter().map(|d| d.id.as_str()).collect();
+        ids.sort_unstable();
+        let dummy_id = DummyBackend::device_id();
+        let mut expected = vec!["canon:1", "test-device", dummy_id.as_str()];
+        expected.sort_unstable();
+        assert_eq!(ids, expected);
+
+        // Each camera's controls come from its own backend
+        let control_ids = |id: &str| -> Vec<String> {
+            state
+                .backend
+                .get_controls(&DeviceId::new(id))
+                .unwrap()
+                .into_iter()
+                .map(|c| c.id)
+                .collect()
+        };
+        assert_eq!(control_ids("test-device"), ["brightness"]);
+        assert_eq!(control_ids("canon:1"), ["iso"]);
+        assert!(control_ids(dummy_id.as_str()).contains(&"contrast".to_string()));
+        assert!(state
+            .backend
+            .get_controls(&DeviceId::new("nonexistent"))
+            .is_err());
+
+        // Saved settings are restored through the composite at startup
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+        store.set_control(dummy_id.as_str(), "Dummy Test Camera", "contrast", 70);
+        let latency = ControlLatencyState::default();
+        let restored = apply_saved_settings(&state.backend, &store, &latency, dummy_id.as_str());
+        assert_eq!(restored.applied.len(), 1);
+        let contrast = state
+            .backend
+            .get_control(&dummy_id, &ControlId::Contrast)
+            .unwrap();
+        assert_eq!(contrast.value(), 70);
+    }
+
     // --- parse_control_id tests ---
 
     #[test]
@@ -301,8 +1025,9 @@ mod tests {
     #[test]
     fn parse_control_id_rejects_unknown_strings() {
         let err = parse_control_id("nonexistent").unwrap_err();
-        assert!(err.contains("Unknown control"));
-        assert!(err.contains("nonexistent"));
+        assert_eq!(err.code, code::INVALID_ARGUMENT);
+        assert!(err.message.contains("Unknown control"));
+        assert!(err.message.contains("nonexistent"));
     }
 
     #[test]
@@ -378,7 +1103,7 @@ mod tests {
 
     #[test]
     fn parse_control_id_error_includes_control_name() {
-        let err = parse_control_id("fake_control").unwrap_err();
+        let err = parse_control_id("fake_control").unwrap_err().message;
         assert!(
             err.contains("fake_control"),
             "error should include the attempted control name: {err}"
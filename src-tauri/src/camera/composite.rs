@@ -3,38 +3,72 @@
 //! Routes control operations to the correct backend by trying each
 //! until one succeeds (the backend that owns the device will succeed,
 //! others will return `DeviceNotFound`).
+//!
+//! When two backends expose the same physical camera, the device from the
+//! higher-priority backend is kept and the other is hidden (see
+//! [`merge_devices`]).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::camera::backend::CameraBackend;
 use crate::camera::error::{CameraError, Result};
+use crate::camera::identity::{merge_devices, BackendDevices};
 use crate::camera::types::{
-    CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId, FormatDescriptor,
-    HotplugEvent,
+    BackendCapabilities, CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId,
+    DeviceKind, FormatDescriptor, HotplugEvent,
 };
 
 /// A camera backend that delegates to multiple sub-backends.
 ///
-/// `enumerate_devices` merges results from all backends (logging failures).
+/// `enumerate_devices` merges results from all backends (logging failures),
+/// hiding duplicates of a camera a higher-priority backend also exposes.
 /// Control operations are routed by trying each backend until one succeeds.
 pub struct CompositeBackend {
     backends: Vec<Box<dyn CameraBackend>>,
+    priorities: Vec<u8>,
+    show_suppressed: Arc<AtomicBool>,
 }
 
 impl CompositeBackend {
-    /// Create a new composite from the given backends.
+    /// Create a new composite from the given backends, all at the same
+    /// priority: of two devices for one camera, the earlier backend's wins.
     pub fn new(backends: Vec<Box<dyn CameraBackend>>) -> Self {
-        Self { backends }
+        Self::with_priorities(backends.into_iter().map(|b| (b, 0)).collect())
+    }
+
+    /// Create a composite from backends paired with their priority. Higher
+    /// wins when two backends expose the same camera.
+    pub fn with_priorities(backends: Vec<(Box<dyn CameraBackend>, u8)>) -> Self {
+        let (backends, priorities) = backends.into_iter().unzip();
+        Self {
+            backends,
+            priorities,
+            show_suppressed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// List suppressed duplicates too, with `suppressed_by` set, while
+    /// `show` is on. The flag is shared so it can be changed later.
+    pub fn with_show_suppressed(mut self, show: Arc<AtomicBool>) -> Self {
+        self.show_suppressed = show;
+        self
     }
 }
 
 impl CameraBackend for CompositeBackend {
     fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
-        let mut all = Vec::new();
-        for backend in &self.backends {
+        let mut lists = Vec::new();
+        for (backend, &priority) in self.backends.iter().zip(&self.priorities) {
             match backend.enumerate_devices() {
-                Ok(devices) => all.extend(devices),
+                Ok(devices) => lists.push(BackendDevices { priority, devices }),
                 Err(e) => tracing::warn!("Backend enumeration failed: {e}"),
             }
         }
+        let mut all = merge_devices(lists);
+        if !self.show_suppressed.load(Ordering::Relaxed) {
+            all.retain(|d| d.suppressed_by.is_none());
+        }
         Ok(all)
     }
 
@@ -69,9 +103,61 @@ impl CameraBackend for CompositeBackend {
         route_to_backend(&self.backends, |b| b.set_control(id, control, value), id)
     }
 
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, auto: bool) -> Result<()> {
+        route_to_backend(
+            &self.backends,
+            |b| b.set_control_auto(id, control, auto),
+            id,
+        )
+    }
+
+    fn set_control_by_str(&self, id: &DeviceId, control: &str, value: ControlValue) -> Result<()> {
+        route_to_backend(
+            &self.backends,
+            |b| b.set_control_by_str(id, control, value),
+            id,
+        )
+    }
+
     fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
         route_to_backend(&self.backends, |b| b.get_formats(id), id)
     }
+
+    /// Everything any of the backends supports. A device's own backend may
+    /// support less; see [`device_capabilities`](Self::device_capabilities).
+    fn capabilities(&self) -> BackendCapabilities {
+        self.backends
+            .iter()
+            .map(|b| b.capabilities())
+            .fold(BackendCapabilities::none(), BackendCapabilities::union)
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        route_to_backend(&self.backends, |b| b.device_capabilities(id), id)
+    }
+
+    /// Ask each backend in turn. A device that another backend's duplicate
+    /// could hide is checked against the merged list, so the lookup agrees
+    /// with `enumerate_devices`.
+    fn get_device(&self, id: &DeviceId) -> Result<Option<CameraDevice>> {
+        for backend in &self.backends {
+            match backend.get_device(id) {
+                Ok(Some(device))
+                    if device.identity.is_some() && device.kind == DeviceKind::Primary =>
+                {
+                    return Ok(self.enumerate_devices()?.into_iter().find(|d| &d.id == id));
+                }
+                Ok(Some(device)) => return Ok(Some(device)),
+                Ok(None) => continue,
+                Err(e) => tracing::warn!("Backend device lookup failed: {e}"),
+            }
+        }
+        Ok(None)
+    }
+
+    fn take_photo(&self, id: &DeviceId) -> Result<()> {
+        route_to_backend(&self.backends, |b| b.take_photo(id), id)
+    }
 }
 
 /// Try each backend until one succeeds. Returns the first success or
@@ -99,16 +185,17 @@ mod tests {
     use super::*;
     use crate::camera::error::CameraError;
     use crate::camera::types::{
-        CameraDevice, ControlDescriptor, ControlFlags, ControlType, DeviceId, FormatDescriptor,
-        HotplugEvent,
+        CameraDevice, ControlDescriptor, ControlFlags, ControlType, DeviceId, DeviceKind,
+        FormatDescriptor, HotplugEvent, PhysicalIdentity,
     };
-    use std::sync::{Arc, Mutex};
+    use std::sync::Mutex;
 
     /// Simple test backend that returns pre-configured devices.
     struct StubBackend {
         devices: Vec<CameraDevice>,
         controls: Vec<ControlDescriptor>,
         formats: Vec<FormatDescriptor>,
+        capabilities: BackendCapabilities,
     }
 
     impl StubBackend {
@@ -119,6 +206,10 @@ mod tests {
                     name: name.to_string(),
                     device_path: format!("{prefix}://path"),
                     is_connected: true,
+                    kind: DeviceKind::Primary,
+                    primary_id: None,
+                    identity: None,
+                    suppressed_by: None,
                 }],
                 controls: vec![ControlDescriptor {
                     id: "brightness".to_string(),
@@ -129,6 +220,7 @@ mod tests {
                     max: Some(255),
                     step: Some(1),
                     default: Some(128),
+                    default_auto: false,
                     current: 128,
                     flags: ControlFlags {
                         supports_auto: false,
@@ -143,7 +235,10 @@ mod tests {
                     height: 1080,
                     fps: 30.0,
                     pixel_format: "MJPG".to_string(),
+                    min_fps: None,
+                    max_fps: None,
                 }],
+                capabilities: BackendCapabilities::default(),
             }
         }
     }
@@ -186,6 +281,14 @@ mod tests {
             }
         }
 
+        fn set_control_auto(&self, id: &DeviceId, _control: &ControlId, _auto: bool) -> Result<()> {
+            if self.devices.iter().any(|d| &d.id == id) {
+                Ok(())
+            } else {
+                Err(CameraError::DeviceNotFound(id.to_string()))
+            }
+        }
+
         fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             if self.devices.iter().any(|d| &d.id == id) {
                 Ok(self.formats.clone())
@@ -193,6 +296,10 @@ mod tests {
                 Err(CameraError::DeviceNotFound(id.to_string()))
             }
         }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            self.capabilities
+        }
     }
 
     /// Backend that always fails enumeration.
@@ -219,6 +326,9 @@ mod tests {
         ) -> Result<()> {
             Err(CameraError::DeviceNotFound(id.to_string()))
         }
+        fn set_control_auto(&self, id: &DeviceId, _control: &ControlId, _auto: bool) -> Result<()> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
         fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             Err(CameraError::DeviceNotFound(id.to_string()))
         }
@@ -337,6 +447,169 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    /// Stub backend exposing one camera with a model identity.
+    fn identified(prefix: &str, name: &str) -> Box<dyn CameraBackend> {
+        let mut stub = StubBackend::new(prefix, name);
+        stub.devices[0].identity = Some(PhysicalIdentity {
+            serial: None,
+            model: Some(name.to_string()),
+        });
+        Box::new(stub)
+    }
+
+    #[test]
+    fn duplicate_camera_is_listed_once_from_the_preferred_backend() {
+        let composite = CompositeBackend::with_priorities(vec![
+            (identified("ds", "EOS R5"), 0),
+            (identified("canon", "Canon EOS R5"), 10),
+        ]);
+
+        let devices = composite.enumerate_devices().unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id, DeviceId::new("canon:device1"));
+
+        // The hidden device still routes to its backend
+        assert!(composite.get_controls(&DeviceId::new("ds:device1")).is_ok());
+    }
+
+    #[test]
+    fn suppressed_duplicates_are_listed_on_request() {
+        let show = Arc::new(AtomicBool::new(true));
+        let composite = CompositeBackend::with_priorities(vec![
+            (identified("ds", "EOS R5"), 0),
+            (identified("canon", "Canon EOS R5"), 10),
+        ])
+        .with_show_suppressed(Arc::clone(&show));
+
+        let devices = composite.enumerate_devices().unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].id, DeviceId::new("ds:device1"));
+        assert_eq!(
+            devices[0].suppressed_by,
+            Some(DeviceId::new("canon:device1"))
+        );
+        assert_eq!(devices[1].suppressed_by, None);
+
+        show.store(false, Ordering::Relaxed);
+        assert_eq!(composite.enumerate_devices().unwrap().len(), 1);
+    }
+
+    /// Stub backend exposing one camera with only `capabilities`.
+    fn capable(prefix: &str, capabilities: BackendCapabilities) -> Box<dyn CameraBackend> {
+        let mut stub = StubBackend::new(prefix, "Camera");
+        stub.capabilities = capabilities;
+        Box::new(stub)
+    }
+
+    #[test]
+    fn capabilities_are_the_union_of_every_backend() {
+        let composite = CompositeBackend::new(vec![
+            capable(
+                "ds",
+                BackendCapabilities {
+                    supports_formats: true,
+                    ..BackendCapabilities::none()
+                },
+            ),
+            capable(
+                "canon",
+                BackendCapabilities {
+                    supports_preview: true,
+                    supports_hotplug: true,
+                    ..BackendCapabilities::none()
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            composite.capabilities(),
+            BackendCapabilities {
+                supports_preview: true,
+                supports_formats: true,
+                supports_hotplug: true,
+                ..BackendCapabilities::none()
+            }
+        );
+        assert_eq!(
+            CompositeBackend::new(vec![]).capabilities(),
+            BackendCapabilities::none()
+        );
+    }
+
+    #[test]
+    fn device_capabilities_route_to_the_owning_backend() {
+        let canon = BackendCapabilities {
+            supports_preview: true,
+            ..BackendCapabilities::none()
+        };
+        let composite = CompositeBackend::new(vec![
+            Box::new(FailingBackend),
+            capable("ds", BackendCapabilities::default()),
+            capable("canon", canon),
+        ]);
+
+        assert_eq!(
+            composite
+                .device_capabilities(&DeviceId::new("canon:device1"))
+                .unwrap(),
+            canon
+        );
+        assert_eq!(
+            composite
+                .device_capabilities(&DeviceId::new("ds:device1"))
+                .unwrap(),
+            BackendCapabilities::default()
+        );
+        assert!(matches!(
+            composite.device_capabilities(&DeviceId::new("usb:unknown")),
+            Err(CameraError::DeviceNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn get_device_asks_each_backend() {
+        let composite = CompositeBackend::new(vec![
+            Box::new(FailingBackend),
+            Box::new(StubBackend::new("ds", "Webcam")),
+            Box::new(StubBackend::new("canon", "EOS R5")),
+        ]);
+
+        let device = composite
+            .get_device(&DeviceId::new("canon:device1"))
+            .unwrap();
+        assert_eq!(device.map(|d| d.name), Some("EOS R5".to_string()));
+        assert!(composite
+            .get_device(&DeviceId::new("usb:unknown"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn get_device_agrees_with_enumeration_on_duplicates() {
+        let show = Arc::new(AtomicBool::new(false));
+        let composite = CompositeBackend::with_priorities(vec![
+            (identified("ds", "EOS R5"), 0),
+            (identified("canon", "Canon EOS R5"), 10),
+        ])
+        .with_show_suppressed(Arc::clone(&show));
+
+        assert!(composite
+            .get_device(&DeviceId::new("ds:device1"))
+            .unwrap()
+            .is_none());
+        assert!(composite
+            .get_device(&DeviceId::new("canon:device1"))
+            .unwrap()
+            .is_some());
+
+        show.store(true, Ordering::Relaxed);
+        let device = composite
+            .get_device(&DeviceId::new("ds:device1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(device.suppressed_by, Some(DeviceId::new("canon:device1")));
+    }
+
     #[test]
     fn empty_composite_enumerates_zero_devices() {
         let composite = CompositeBackend::new(vec![]);
@@ -362,11 +635,14 @@ mod canon_e2e_tests {
     use super::*;
     use crate::camera::canon::backend::CanonBackend;
     use crate::camera::canon::mock::MockEdsSdk;
-    use crate::camera::canon::types::PROP_ID_ISO_SPEED;
+    use crate::camera::canon::types::{
+        CAMERA_COMMAND_PRESS_SHUTTER, PROP_ID_ISO_SPEED, SHUTTER_BUTTON_COMPLETELY_NON_AF,
+        SHUTTER_BUTTON_OFF,
+    };
     use crate::camera::error::CameraError;
     use crate::camera::types::{
         CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue,
-        DeviceId, FormatDescriptor, HotplugEvent,
+        DeviceId, DeviceKind, FormatDescriptor, HotplugEvent,
     };
     use std::collections::HashMap;
     use std::sync::{Arc, Mutex};
@@ -384,6 +660,10 @@ mod canon_e2e_tests {
                     name: "Logitech BRIO".to_string(),
                     device_path: "ds://logitech".to_string(),
                     is_connected: true,
+                    kind: DeviceKind::Primary,
+                    primary_id: None,
+                    identity: None,
+                    suppressed_by: None,
                 }],
             }
         }
@@ -409,6 +689,7 @@ mod canon_e2e_tests {
                     max: Some(255),
                     step: Some(1),
                     default: Some(128),
+                    default_auto: false,
                     current: 128,
                     flags: ControlFlags {
                         supports_auto: false,
@@ -444,6 +725,14 @@ mod canon_e2e_tests {
             }
         }
 
+        fn set_control_auto(&self, id: &DeviceId, _control: &ControlId, _auto: bool) -> Result<()> {
+            if self.devices.iter().any(|d| &d.id == id) {
+                Ok(())
+            } else {
+                Err(CameraError::DeviceNotFound(id.to_string()))
+            }
+        }
+
         fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             if self.devices.iter().any(|d| &d.id == id) {
                 Ok(vec![FormatDescriptor {
@@ -451,6 +740,8 @@ mod canon_e2e_tests {
                     height: 1080,
                     fps: 30.0,
                     pixel_format: "MJPG".to_string(),
+                    min_fps: None,
+                    max_fps: None,
                 }])
             } else {
                 Err(CameraError::DeviceNotFound(id.to_string()))
@@ -464,9 +755,13 @@ mod canon_e2e_tests {
             .with_camera("Canon EOS R5", Some("SER001"))
             .with_property(0, PROP_ID_ISO_SPEED, 0x48)
             .with_property_desc(0, PROP_ID_ISO_SPEED, vec![0x48, 0x50, 0x58]);
+        make_composite_with(Arc::new(mock))
+    }
 
+    /// Build a composite with the Canon cameras of `mock` and one DirectShow stub.
+    fn make_composite_with(mock: Arc<MockEdsSdk>) -> CompositeBackend {
         let handle_map = Arc::new(Mutex::new(HashMap::new()));
-        let canon: Box<dyn CameraBackend> = Box::new(CanonBackend::new(Arc::new(mock), handle_map));
+        let canon: Box<dyn CameraBackend> = Box::new(CanonBackend::new(mock, handle_map));
         let ds: Box<dyn CameraBackend> = Box::new(DirectShowStub::new());
 
         CompositeBackend::new(vec![ds, canon])
@@ -574,4 +869,62 @@ mod canon_e2e_tests {
             "DS device should not expose Canon-specific controls"
         );
     }
+
+    #[test]
+    fn takes_photos_on_canon_cameras() {
+        let mock = Arc::new(MockEdsSdk::new().with_camera("Canon EOS R5", Some("SER001")));
+        let composite = make_composite_with(Arc::clone(&mock));
+        composite.enumerate_devices().unwrap();
+
+        composite
+            .take_photo(&DeviceId::new("canon:SER001"))
+            .unwrap();
+        let shutter: Vec<i32> = mock
+            .commands_sent()
+            .into_iter()
+            .map(|(_, command, param)| {
+                assert_eq!(command, CAMERA_COMMAND_PRESS_SHUTTER);
+                param
+            })
+            .collect();
+        assert_eq!(
+            shutter,
+            [SHUTTER_BUTTON_COMPLETELY_NON_AF, SHUTTER_BUTTON_OFF]
+        );
+        assert_eq!(mock.events_processed(), 1);
+    }
+
+    #[test]
+    fn canon_shutter_errors_come_back_through_the_composite() {
+        let mock = Arc::new(
+            MockEdsSdk::new()
+                .with_camera("Canon EOS R5", Some("SER001"))
+                .with_error(
+                    "send_command",
+                    CameraError::CanonSdkError("take picture failed".to_string()),
+                ),
+        );
+        let composite = make_composite_with(mock);
+        composite.enumerate_devices().unwrap();
+
+        match composite.take_photo(&DeviceId::new("canon:SER001")) {
+            Err(CameraError::CanonSdkError(m)) => assert_eq!(m, "take picture failed"),
+            other => panic!("expected the injected Canon SDK error, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn ds_cameras_cannot_take_photos() {
+        let composite = make_composite();
+        composite.enumerate_devices().unwrap();
+
+        match composite.take_photo(&DeviceId::new("ds:logitech-brio")) {
+            Err(CameraError::Unsupported(m)) => assert!(m.contains("ds:logitech-brio"), "{m}"),
+            other => panic!("expected Unsupported, got: {other:?}"),
+        }
+        assert!(matches!(
+            composite.take_photo(&DeviceId::new("nonexistent:device")),
+            Err(CameraError::DeviceNotFound(_))
+        ));
+    }
 }
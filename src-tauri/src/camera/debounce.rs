@@ -0,0 +1,230 @@
+//! Coalescing of device-change notifications.
+//!
+//! Plugging in a hub with two cameras fires a burst of device-change
+//! notifications, and re-enumerating on each is slow and races the driver
+//! initialising. A [`Debouncer`] gathers a burst into one enumeration, run
+//! once the notifications have been quiet for its window. A burst that never
+//! goes quiet is still enumerated [`MAX_WINDOWS`] windows after it started,
+//! so a chattering driver can't hold off enumeration forever.
+//!
+//! An enumeration that catches a device mid-initialisation can ask for one
+//! retry a short while later.
+//!
+//! The debouncer only keeps time; whoever owns it arms a timer for
+//! [`Debouncer::due`] and calls [`Debouncer::take_due`] when it fires.
+
+use std::time::{Duration, Instant};
+
+/// Window used unless the backend is configured with another.
+pub const DEFAULT_WINDOW: Duration = Duration::from_millis(500);
+
+/// How long after an enumeration that caught a device mid-initialisation to
+/// enumerate again.
+pub const RETRY_DELAY: Duration = Duration::from_millis(750);
+
+/// Longest a burst can put off its enumeration, in windows since its first
+/// notification.
+pub const MAX_WINDOWS: u32 = 4;
+
+/// Which enumeration of a burst is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// The burst's first enumeration.
+    First,
+    /// The one retry of a burst whose first enumeration asked for it.
+    Retry,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Burst {
+    started: Instant,
+    due: Instant,
+    pass: Pass,
+}
+
+/// Gathers bursts of device-change notifications into single enumerations.
+#[derive(Debug)]
+pub struct Debouncer {
+    window: Duration,
+    pending: Option<Burst>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: None,
+        }
+    }
+
+    /// How long notifications must be quiet before a burst is enumerated.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Note a notification at `now`, returning when the burst it belongs to
+    /// is due.
+    pub fn notify(&mut self, now: Instant) -> Instant {
+        let window = self.window;
+        let burst = self.pending.get_or_insert(Burst {
+            started: now,
+            due: now,
+            pass: Pass::First,
+        });
+        let latest = burst.started + window * MAX_WINDOWS;
+        burst.due = burst.due.max((now + window).min(latest));
+        burst.due
+    }
+
+    /// When the pending burst is due, if there is one.
+    pub fn due(&self) -> Option<Instant> {
+        self.pending.map(|burst| burst.due)
+    }
+
+    /// Take the pending burst if it's due at `now`, returning which
+    /// enumeration to run for it.
+    pub fn take_due(&mut self, now: Instant) -> Option<Pass> {
+        let burst = self.pending.filter(|burst| burst.due <= now)?;
+        self.pending = None;
+        Some(burst.pass)
+    }
+
+    /// Ask for the enumeration just taken as `pass` to be run again `delay`
+    /// after `now`. Only a first pass is retried; returns when the retry is
+    /// due, or `None` if it won't be.
+    pub fn retry(&mut self, pass: Pass, now: Instant, delay: Duration) -> Option<Instant> {
+        if pass == Pass::Retry {
+            return None;
+        }
+        let due = now + delay;
+        let burst = self.pending.get_or_insert(Burst {
+            started: now,
+            due,
+            pass: Pass::Retry,
+        });
+        burst.pass = Pass::Retry;
+        burst.due = burst.due.max(due);
+        Some(burst.due)
+    }
+}
+
+impl Default for Debouncer {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(500);
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// Play `notifications` (offsets from the start) through a debouncer,
+    /// polling every 10ms as a timer would, and return the offsets the
+    /// enumerations ran at.
+    fn simulate(notifications: &[u64], until: u64) -> Vec<(u64, Pass)> {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        let mut enumerations = Vec::new();
+        for t in (0..=until).step_by(10) {
+            let now = start + ms(t);
+            for _ in notifications.iter().filter(|&&n| n == t) {
+                debouncer.notify(now);
+            }
+            if let Some(pass) = debouncer.take_due(now) {
+                enumerations.push((t, pass));
+            }
+        }
+        enumerations
+    }
+
+    #[test]
+    fn a_burst_is_enumerated_once_after_it_goes_quiet() {
+        // A hub with two cameras: arrivals, devnode changes and more
+        let burst = [0, 0, 20, 40, 40, 90, 150, 160];
+        assert_eq!(simulate(&burst, 2000), vec![(660, Pass::First)]);
+    }
+
+    #[test]
+    fn a_single_notification_waits_one_window() {
+        assert_eq!(simulate(&[100], 2000), vec![(600, Pass::First)]);
+    }
+
+    #[test]
+    fn bursts_further_apart_than_the_window_are_enumerated_separately() {
+        assert_eq!(
+            simulate(&[0, 50, 1000, 1100], 3000),
+            vec![(550, Pass::First), (1600, Pass::First)]
+        );
+    }
+
+    #[test]
+    fn a_burst_that_never_goes_quiet_is_still_enumerated() {
+        let chatter: Vec<u64> = (0..=5000).step_by(100).collect();
+        // Every MAX_WINDOWS windows while the chatter goes on, rather than
+        // never
+        assert_eq!(
+            simulate(&chatter, 5000),
+            vec![(2000, Pass::First), (4100, Pass::First)]
+        );
+    }
+
+    #[test]
+    fn nothing_is_due_without_notifications() {
+        let mut debouncer = Debouncer::default();
+        assert_eq!(debouncer.window(), DEFAULT_WINDOW);
+        assert_eq!(debouncer.due(), None);
+        assert_eq!(debouncer.take_due(Instant::now()), None);
+    }
+
+    #[test]
+    fn notifications_report_when_the_burst_is_due() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        assert_eq!(debouncer.notify(start), start + WINDOW);
+        assert_eq!(debouncer.notify(start + ms(100)), start + ms(600));
+        assert_eq!(debouncer.due(), Some(start + ms(600)));
+        assert_eq!(debouncer.take_due(start + ms(599)), None);
+        assert_eq!(debouncer.take_due(start + ms(600)), Some(Pass::First));
+        assert_eq!(debouncer.due(), None);
+    }
+
+    #[test]
+    fn a_first_pass_is_retried_once() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        debouncer.notify(start);
+        let first = start + WINDOW;
+        assert_eq!(debouncer.take_due(first), Some(Pass::First));
+
+        let retry_at = debouncer.retry(Pass::First, first, RETRY_DELAY);
+        assert_eq!(retry_at, Some(first + RETRY_DELAY));
+        assert_eq!(debouncer.take_due(first + ms(10)), None);
+        assert_eq!(debouncer.take_due(first + RETRY_DELAY), Some(Pass::Retry));
+
+        // The retry isn't retried again
+        assert_eq!(debouncer.retry(Pass::Retry, first, RETRY_DELAY), None);
+        assert_eq!(debouncer.due(), None);
+    }
+
+    #[test]
+    fn notifications_during_a_retry_fold_into_it() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        debouncer.notify(start);
+        debouncer.take_due(start + WINDOW);
+        let retry_at = debouncer
+            .retry(Pass::First, start + WINDOW, RETRY_DELAY)
+            .unwrap();
+
+        // The driver finishes initialising and says so before the retry
+        assert_eq!(debouncer.notify(start + ms(600)), retry_at);
+        assert_eq!(debouncer.take_due(retry_at), Some(Pass::Retry));
+        assert_eq!(debouncer.due(), None);
+    }
+}
@@ -3,9 +3,11 @@ use std::sync::Mutex;
 
 use crate::camera::backend::CameraBackend;
 use crate::camera::error::{CameraError, Result};
+use crate::camera::powerline;
+use crate::camera::tally;
 use crate::camera::types::{
-    CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue, DeviceId,
-    FormatDescriptor, HotplugEvent,
+    BackendCapabilities, CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType,
+    ControlValue, DeviceId, DeviceKind, FormatDescriptor, HotplugEvent,
 };
 
 const DUMMY_DEVICE_ID: &str = "dummy:test:camera-001";
@@ -62,8 +64,55 @@ const CONTROL_DEFS: &[ControlDef] = &[
         default: 6500,
         group: "exposure",
     },
+    // A select rather than a slider; see `descriptor`
+    ControlDef {
+        id: ControlId::PowerLineFrequency,
+        name: "Power Line Frequency",
+        min: 0,
+        max: 3,
+        default: 2,
+        group: "exposure",
+    },
+    // A toggle; see `descriptor`
+    ControlDef {
+        id: ControlId::TallyLight,
+        name: "Tally Light",
+        min: 0,
+        max: 1,
+        default: 0,
+        group: "advanced",
+    },
 ];
 
+/// Descriptor for a simulated control holding `current`.
+fn descriptor(def: &ControlDef, current: i32) -> ControlDescriptor {
+    if def.id == ControlId::PowerLineFrequency {
+        return powerline::descriptor(def.min, def.max, Some(def.default), current);
+    }
+    if def.id == ControlId::TallyLight {
+        return tally::descriptor(current);
+    }
+    ControlDescriptor {
+        id: def.id.as_id_str().to_string(),
+        name: def.name.to_string(),
+        control_type: ControlType::Slider,
+        group: def.group.to_string(),
+        min: Some(def.min),
+        max: Some(def.max),
+        step: Some(1),
+        default: Some(def.default),
+        default_auto: false,
+        current,
+        flags: ControlFlags {
+            supports_auto: false,
+            is_auto_enabled: false,
+            is_read_only: false,
+        },
+        options: None,
+        supported: true,
+    }
+}
+
 /// Minimal valid JPEG — a 1x1 red pixel.
 ///
 /// Generated from a standard JFIF structure.
@@ -99,8 +148,8 @@ fn test_pattern_jpeg() -> Vec<u8> {
 /// A fake camera backend for testing without real hardware.
 ///
 /// Provides simulated controls (Brightness, Contrast, Saturation, Sharpness,
-/// White Balance) that store values in memory. Returns a minimal JPEG test
-/// pattern for frame capture.
+/// White Balance, Power Line Frequency, Tally Light) that store values in
+/// memory. Returns a minimal JPEG test pattern for frame capture.
 ///
 /// Enable via `DUMMY_CAMERA=1` environment variable.
 pub struct DummyBackend {
@@ -135,6 +184,12 @@ impl DummyBackend {
     }
 }
 
+impl Default for DummyBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CameraBackend for DummyBackend {
     fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
         Ok(vec![CameraDevice {
@@ -142,6 +197,10 @@ impl CameraBackend for DummyBackend {
             name: DUMMY_DEVICE_NAME.to_string(),
             device_path: "dummy://test-camera".to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         }])
     }
 
@@ -158,24 +217,7 @@ impl CameraBackend for DummyBackend {
         let values = self.control_values.lock().unwrap();
         let descriptors = CONTROL_DEFS
             .iter()
-            .map(|def| ControlDescriptor {
-                id: def.id.as_id_str().to_string(),
-                name: def.name.to_string(),
-                control_type: ControlType::Slider,
-                group: def.group.to_string(),
-                min: Some(def.min),
-                max: Some(def.max),
-                step: Some(1),
-                default: Some(def.default),
-                current: values.get(&def.id).copied().unwrap_or(def.default),
-                flags: ControlFlags {
-                    supports_auto: false,
-                    is_auto_enabled: false,
-                    is_read_only: false,
-                },
-                options: None,
-                supported: true,
-            })
+            .map(|def| descriptor(def, values.get(&def.id).copied().unwrap_or(def.default)))
             .collect();
 
         Ok(descriptors)
@@ -211,15 +253,25 @@ impl CameraBackend for DummyBackend {
                 CameraError::ControlWrite(format!("unsupported control: {control:?}"))
             })?;
 
-        let clamped = ControlValue::new(value.value(), Some(def.min), Some(def.max));
-        self.control_values
-            .lock()
-            .unwrap()
-            .insert(*control, clamped.value());
+        let mut values = self.control_values.lock().unwrap();
+        let current = values.get(control).copied().unwrap_or(def.default);
+        let clamped = descriptor(def, current).clamp(value.value()).value;
+        values.insert(*control, clamped.value());
 
         Ok(())
     }
 
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, _auto: bool) -> Result<()> {
+        if id != &Self::device_id() {
+            return Err(CameraError::DeviceNotFound(id.to_string()));
+        }
+        // None of the simulated controls support auto
+        Err(CameraError::ControlWrite(format!(
+            "{} has no automatic mode",
+            control.display_name()
+        )))
+    }
+
     fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
         if id != &Self::device_id() {
             return Err(CameraError::DeviceNotFound(id.to_string()));
@@ -230,13 +282,32 @@ impl CameraBackend for DummyBackend {
             height: 1,
             fps: 30.0,
             pixel_format: "JPEG".to_string(),
+            min_fps: None,
+            max_fps: None,
         }])
     }
+
+    /// Previews the test pattern; the single format is a placeholder and
+    /// there's no automatic mode or hotplug to offer.
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            supports_preview: true,
+            ..BackendCapabilities::none()
+        }
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        if id != &Self::device_id() {
+            return Err(CameraError::DeviceNotFound(id.to_string()));
+        }
+        Ok(self.capabilities())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::camera::powerline::PowerLineFrequency;
 
     #[test]
     fn dummy_backend_enumerates_one_device() {
@@ -257,10 +328,10 @@ mod tests {
     }
 
     #[test]
-    fn dummy_backend_has_five_controls() {
+    fn dummy_backend_has_seven_controls() {
         let backend = DummyBackend::new();
         let controls = backend.get_controls(&DummyBackend::device_id()).unwrap();
-        assert_eq!(controls.len(), 5);
+        assert_eq!(controls.len(), 7);
 
         let ids: Vec<&str> = controls.iter().map(|c| c.id.as_str()).collect();
         assert!(ids.contains(&"brightness"));
@@ -268,6 +339,44 @@ mod tests {
         assert!(ids.contains(&"saturation"));
         assert!(ids.contains(&"sharpness"));
         assert!(ids.contains(&"white_balance"));
+        assert!(ids.contains(&"power_line_frequency"));
+        assert!(ids.contains(&"tally_light"));
+    }
+
+    #[test]
+    fn dummy_backend_tally_light_is_a_toggle() {
+        let backend = DummyBackend::new();
+        let id = DummyBackend::device_id();
+        let control = ControlId::TallyLight;
+
+        let controls = backend.get_controls(&id).unwrap();
+        let desc = controls.iter().find(|c| c.id == "tally_light").unwrap();
+        assert_eq!(desc.control_type, ControlType::Toggle);
+        assert_eq!(desc.current, 0);
+
+        let on = ControlValue::new(1, None, None);
+        backend.set_control(&id, &control, on).unwrap();
+        assert_eq!(backend.get_control(&id, &control).unwrap(), on);
+    }
+
+    #[test]
+    fn dummy_backend_power_line_frequency_is_a_select() {
+        let backend = DummyBackend::new();
+        let id = DummyBackend::device_id();
+        let control = ControlId::PowerLineFrequency;
+
+        let controls = backend.get_controls(&id).unwrap();
+        let desc = controls
+            .iter()
+            .find(|c| c.id == "power_line_frequency")
+            .unwrap();
+        assert_eq!(desc.control_type, ControlType::Select);
+        assert_eq!(desc.current, PowerLineFrequency::Hz60.value());
+        assert_eq!(desc.options.as_ref().unwrap().len(), 4);
+
+        let hz50 = ControlValue::new(PowerLineFrequency::Hz50.value(), None, None);
+        backend.set_control(&id, &control, hz50).unwrap();
+        assert_eq!(backend.get_control(&id, &control).unwrap(), hz50);
     }
 
     #[test]
@@ -384,6 +493,24 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn dummy_backend_offers_preview_only() {
+        let backend = DummyBackend::new();
+        let caps = backend
+            .device_capabilities(&DummyBackend::device_id())
+            .unwrap();
+        assert_eq!(
+            caps,
+            BackendCapabilities {
+                supports_preview: true,
+                ..BackendCapabilities::none()
+            }
+        );
+        assert!(backend
+            .device_capabilities(&DeviceId::new("other"))
+            .is_err());
+    }
+
     #[test]
     fn dummy_backend_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -24,6 +24,9 @@ pub enum CameraError {
     #[error("hotplug registration failed: {0}")]
     Hotplug(String),
 
+    #[error("not supported: {0}")]
+    Unsupported(String),
+
     #[error("Canon SDK error: {0}")]
     CanonSdkError(String),
 
@@ -37,93 +40,10 @@ pub enum CameraError {
 /// Convenience Result alias.
 pub type Result<T> = std::result::Result<T, CameraError>;
 
-/// Known Windows HRESULT codes and their user-friendly translations.
-const HRESULT_TRANSLATIONS: &[(&str, &str)] = &[
-    ("0x800705AA", "Camera is in use by another application"),
-    (
-        "0x80070005",
-        "Access denied — close other camera apps and retry",
-    ),
-    ("0x80004005", "Camera returned an unspecified error"),
-    ("0x80070020", "Camera is locked by another process"),
-    (
-        "0x8007001F",
-        "A device attached to the system is not functioning",
-    ),
-];
-
-/// Replace known HRESULT codes and Canon SDK messages with human-friendly text.
-pub fn humanise_error(msg: &str) -> String {
-    for &(code, friendly) in HRESULT_TRANSLATIONS {
-        if msg.contains(code) {
-            return friendly.to_string();
-        }
-    }
-    // Canon SDK error translations
-    if msg.contains("camera is busy") {
-        return "Canon camera is busy — wait a moment and try again".to_string();
-    }
-    if msg.contains("session not open") || msg.contains("SESSION_NOT_OPEN") {
-        return "Canon camera session is not open — reconnect the camera".to_string();
-    }
-    if msg.contains("camera disconnected") || msg.contains("COMM_DISCONNECTED") {
-        return "Canon camera was disconnected".to_string();
-    }
-    msg.to_string()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn humanise_translates_insufficient_resources() {
-        let msg = "CoCreateInstance failed: 0x800705AA";
-        assert_eq!(
-            humanise_error(msg),
-            "Camera is in use by another application"
-        );
-    }
-
-    #[test]
-    fn humanise_translates_access_denied() {
-        let msg = "BindToObject failed: 0x80070005";
-        assert_eq!(
-            humanise_error(msg),
-            "Access denied — close other camera apps and retry"
-        );
-    }
-
-    #[test]
-    fn humanise_translates_sharing_violation() {
-        let msg = "something 0x80070020 happened";
-        assert_eq!(humanise_error(msg), "Camera is locked by another process");
-    }
-
-    #[test]
-    fn humanise_passes_through_unknown_errors() {
-        let msg = "some random error without HRESULT";
-        assert_eq!(humanise_error(msg), msg);
-    }
-
-    #[test]
-    fn humanise_translates_canon_busy() {
-        let msg = "Canon SDK error: camera is busy — retry shortly";
-        assert!(humanise_error(msg).contains("Canon camera is busy"));
-    }
-
-    #[test]
-    fn humanise_translates_canon_session_not_open() {
-        let msg = "no camera session not open";
-        assert!(humanise_error(msg).contains("session is not open"));
-    }
-
-    #[test]
-    fn humanise_translates_canon_disconnected() {
-        let msg = "Canon SDK: camera disconnected";
-        assert!(humanise_error(msg).contains("disconnected"));
-    }
-
     #[test]
     fn camera_error_display_is_human_readable() {
         let err = CameraError::DeviceNotFound("cam-1".to_string());
@@ -0,0 +1,345 @@
+// Grouping of a camera's flat format list by resolution.
+//
+// Drivers list every resolution once per pixel format and frame rate, so
+// 1920x1080 can appear as MJPG@30, MJPG@60, YUY2@5 and NV12@30. The grouped
+// form lists each resolution once with the rates each pixel format offers.
+// Reported rates are noisy: NTSC-style 29.97 sits next to 30, and some
+// drivers report 0 when the frame interval is unknown.
+
+use serde::Serialize;
+
+use crate::camera::types::FormatDescriptor;
+
+/// Rates within this fraction of each other are treated as the same rate.
+const FPS_TOLERANCE: f32 = 0.01;
+
+/// One resolution and the pixel formats available at it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionGroup {
+    pub width: u32,
+    pub height: u32,
+    /// Sorted by highest frame rate, then by name.
+    pub formats: Vec<PixelFormatRates>,
+}
+
+/// The frame rates one pixel format offers at a resolution.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PixelFormatRates {
+    pub pixel_format: String,
+    /// Distinct rates, descending. Empty if the driver only reported 0.
+    pub fps: Vec<f32>,
+    /// Lowest rate of any advertised frame-interval range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_fps: Option<f32>,
+    /// Highest rate of any advertised frame-interval range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fps: Option<f32>,
+}
+
+/// Whether `fps` is a usable rate rather than a driver placeholder.
+fn is_rate(fps: f32) -> bool {
+    fps.is_finite() && fps > 0.0
+}
+
+/// The rate a descriptor offers: its nominal rate, or the top of its range
+/// when the nominal rate is missing.
+fn offered_rate(format: &FormatDescriptor) -> Option<f32> {
+    [Some(format.fps), format.max_fps]
+        .into_iter()
+        .flatten()
+        .find(|&fps| is_rate(fps))
+}
+
+/// Whether two rates are within [`FPS_TOLERANCE`] of each other.
+fn same_rate(a: f32, b: f32) -> bool {
+    (a - b).abs() <= a.max(b) * FPS_TOLERANCE
+}
+
+/// Sort rates descending and merge near-duplicates, keeping the higher.
+fn merge_rates(mut rates: Vec<f32>) -> Vec<f32> {
+    rates.sort_by(|a, b| b.total_cmp(a));
+    rates.dedup_by(|later, kept| same_rate(*later, *kept));
+    rates
+}
+
+fn merge_bound(current: Option<f32>, next: Option<f32>, pick: fn(f32, f32) -> f32) -> Option<f32> {
+    match (current, next.filter(|&fps| is_rate(fps))) {
+        (Some(a), Some(b)) => Some(pick(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Group `formats` by resolution, largest first (by pixel count, then
+/// width). Entries with a zero width or height are dropped.
+pub fn group_formats(formats: &[FormatDescriptor]) -> Vec<ResolutionGroup> {
+    let mut groups: Vec<ResolutionGroup> = Vec::new();
+    for format in formats {
+        if format.width == 0 || format.height == 0 {
+            continue;
+        }
+        let group = match groups
+            .iter()
+            .position(|g| g.width == format.width && g.height == format.height)
+        {
+            Some(index) => &mut groups[index],
+            None => {
+                groups.push(ResolutionGroup {
+                    width: format.width,
+                    height: format.height,
+                    formats: Vec::new(),
+                });
+                groups.last_mut().expect("just pushed")
+            }
+        };
+        let rates = match group
+            .formats
+            .iter()
+            .position(|f| f.pixel_format == format.pixel_format)
+        {
+            Some(index) => &mut group.formats[index],
+            None => {
+                group.formats.push(PixelFormatRates {
+                    pixel_format: format.pixel_format.clone(),
+                    fps: Vec::new(),
+                    min_fps: None,
+                    max_fps: None,
+                });
+                group.formats.last_mut().expect("just pushed")
+            }
+        };
+        rates.fps.extend(offered_rate(format));
+        rates.min_fps = merge_bound(rates.min_fps, format.min_fps, f32::min);
+        rates.max_fps = merge_bound(rates.max_fps, format.max_fps, f32::max);
+    }
+
+    for group in &mut groups {
+        for rates in &mut group.formats {
+            rates.fps = merge_rates(std::mem::take(&mut rates.fps));
+        }
+        group.formats.sort_by(|a, b| {
+            let top = |r: &PixelFormatRates| r.fps.first().copied().unwrap_or(0.0);
+            top(b)
+                .total_cmp(&top(a))
+                .then_with(|| a.pixel_format.cmp(&b.pixel_format))
+        });
+    }
+    groups.sort_by(|a, b| {
+        let pixels = |g: &ResolutionGroup| u64::from(g.width) * u64::from(g.height);
+        pixels(b)
+            .cmp(&pixels(a))
+            .then_with(|| b.width.cmp(&a.width))
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(width: u32, height: u32, pixel_format: &str, fps: f32) -> FormatDescriptor {
+        FormatDescriptor {
+            width,
+            height,
+            fps,
+            pixel_format: pixel_format.to_string(),
+            min_fps: None,
+            max_fps: None,
+        }
+    }
+
+    fn ranged(format: FormatDescriptor, min: f32, max: f32) -> FormatDescriptor {
+        FormatDescriptor {
+            min_fps: Some(min),
+            max_fps: Some(max),
+            ..format
+        }
+    }
+
+    /// Width, height and each pixel format's rates.
+    type Summary<'a> = (u32, u32, Vec<(&'a str, Vec<f32>)>);
+
+    fn summary(groups: &[ResolutionGroup]) -> Vec<Summary<'_>> {
+        groups
+            .iter()
+            .map(|g| {
+                let formats = g
+                    .formats
+                    .iter()
+                    .map(|f| (f.pixel_format.as_str(), f.fps.clone()))
+                    .collect();
+                (g.width, g.height, formats)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn one_resolution_across_pixel_formats_becomes_one_group() {
+        let formats = vec![
+            format(1920, 1080, "MJPG", 30.0),
+            format(1920, 1080, "MJPG", 60.0),
+            format(1920, 1080, "YUY2", 5.0),
+            format(1920, 1080, "NV12", 30.0),
+        ];
+        assert_eq!(
+            summary(&group_formats(&formats)),
+            vec![(
+                1920,
+                1080,
+                vec![
+                    ("MJPG", vec![60.0, 30.0]),
+                    ("NV12", vec![30.0]),
+                    ("YUY2", vec![5.0]),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn resolutions_are_sorted_largest_first() {
+        let formats = vec![
+            format(640, 480, "YUY2", 30.0),
+            format(3840, 2160, "MJPG", 30.0),
+            format(1280, 720, "MJPG", 60.0),
+            // Same pixel count as 1280x720; the wider one sorts first
+            format(960, 960, "MJPG", 30.0),
+            format(1920, 1080, "MJPG", 30.0),
+        ];
+        let sizes: Vec<_> = group_formats(&formats)
+            .iter()
+            .map(|g| (g.width, g.height))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                (3840, 2160),
+                (1920, 1080),
+                (1280, 720),
+                (960, 960),
+                (640, 480)
+            ]
+        );
+    }
+
+    #[test]
+    fn near_duplicate_rates_merge_keeping_the_higher() {
+        let formats = vec![
+            format(1280, 720, "YUY2", 29.97),
+            format(1280, 720, "YUY2", 30.0),
+            format(1280, 720, "YUY2", 30.0),
+            format(1280, 720, "YUY2", 15.0),
+            format(1280, 720, "YUY2", 14.985),
+            format(1280, 720, "YUY2", 24.0),
+            format(1280, 720, "YUY2", 25.0),
+        ];
+        let groups = group_formats(&formats);
+        assert_eq!(groups[0].formats[0].fps, vec![30.0, 25.0, 24.0, 15.0]);
+    }
+
+    #[test]
+    fn zero_fps_entries_keep_the_format_without_a_rate() {
+        let formats = vec![
+            format(1920, 1080, "H264", 0.0),
+            format(1920, 1080, "MJPG", 30.0),
+            format(1280, 720, "H264", 0.0),
+            format(1280, 720, "H264", 30.0),
+            format(640, 480, "RGB24", f32::NAN),
+        ];
+        assert_eq!(
+            summary(&group_formats(&formats)),
+            vec![
+                (1920, 1080, vec![("MJPG", vec![30.0]), ("H264", vec![])]),
+                (1280, 720, vec![("H264", vec![30.0])]),
+                (640, 480, vec![("RGB24", vec![])]),
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_fps_with_a_range_uses_the_top_of_the_range() {
+        let formats = vec![ranged(format(1920, 1080, "NV12", 0.0), 5.0, 30.0)];
+        let rates = &group_formats(&formats)[0].formats[0];
+        assert_eq!(rates.fps, vec![30.0]);
+        assert_eq!((rates.min_fps, rates.max_fps), (Some(5.0), Some(30.0)));
+    }
+
+    #[test]
+    fn ranges_widen_across_entries_and_ignore_zero_bounds() {
+        let formats = vec![
+            ranged(format(1920, 1080, "MJPG", 30.0), 15.0, 30.0),
+            ranged(format(1920, 1080, "MJPG", 60.0), 0.0, 60.0),
+            format(1920, 1080, "MJPG", 24.0),
+            ranged(format(1920, 1080, "MJPG", 30.0), 5.0, 30.0),
+        ];
+        let rates = &group_formats(&formats)[0].formats[0];
+        assert_eq!(rates.fps, vec![60.0, 30.0, 24.0]);
+        assert_eq!((rates.min_fps, rates.max_fps), (Some(5.0), Some(60.0)));
+    }
+
+    #[test]
+    fn zero_sized_entries_are_dropped() {
+        let formats = vec![
+            format(0, 0, "MJPG", 30.0),
+            format(1920, 0, "MJPG", 30.0),
+            format(640, 480, "MJPG", 30.0),
+        ];
+        assert_eq!(group_formats(&formats).len(), 1);
+        assert!(group_formats(&[]).is_empty());
+    }
+
+    #[test]
+    fn messy_webcam_dump_groups_cleanly() {
+        // Trimmed from a Logitech C920 on DirectShow, unsorted as enumerated
+        let formats = vec![
+            format(640, 480, "YUY2", 30.0),
+            format(640, 480, "YUY2", 29.97),
+            format(1920, 1080, "YUY2", 5.0),
+            format(1920, 1080, "MJPG", 30.0),
+            format(640, 480, "MJPG", 30.0),
+            format(1920, 1080, "MJPG", 29.97),
+            format(1920, 1080, "H264", 30.0),
+            format(1920, 1080, "H264", 0.0),
+            format(640, 480, "MJPG", 30.0),
+            format(1280, 720, "MJPG", 30.0),
+            format(1280, 720, "MJPG", 60.0),
+            format(1280, 720, "YUY2", 10.0),
+            format(1280, 720, "YUY2", 7.5),
+            format(640, 480, "MJPG", 15.0),
+        ];
+        assert_eq!(
+            summary(&group_formats(&formats)),
+            vec![
+                (
+                    1920,
+                    1080,
+                    vec![
+                        ("H264", vec![30.0]),
+                        ("MJPG", vec![30.0]),
+                        ("YUY2", vec![5.0]),
+                    ]
+                ),
+                (
+                    1280,
+                    720,
+                    vec![("MJPG", vec![60.0, 30.0]), ("YUY2", vec![10.0, 7.5])]
+                ),
+                (
+                    640,
+                    480,
+                    vec![("MJPG", vec![30.0, 15.0]), ("YUY2", vec![30.0])]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_serialise_camel_case() {
+        let groups = group_formats(&[ranged(format(1280, 720, "MJPG", 30.0), 5.0, 30.0)]);
+        let json = serde_json::to_value(&groups).unwrap();
+        assert_eq!(json[0]["width"], 1280);
+        assert_eq!(json[0]["formats"][0]["pixelFormat"], "MJPG");
+        assert_eq!(json[0]["formats"][0]["fps"], serde_json::json!([30.0]));
+        assert_eq!(json[0]["formats"][0]["minFps"], 5.0);
+    }
+}
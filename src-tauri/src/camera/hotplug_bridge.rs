@@ -1,79 +1,239 @@
+use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager};
 
 use crate::camera::backend::CameraBackend;
-use crate::camera::commands::CameraState;
-use crate::camera::types::HotplugEvent;
-use crate::preview::commands::{start_preview_for_device, stop_preview_for_device};
-use crate::settings::commands::{apply_saved_settings, SettingsState};
+use crate::camera::commands::{refresh_camera_names, seed_default_camera, CameraState};
+use crate::camera::types::{CameraDevice, HotplugEvent};
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::preview::commands::{
+    refresh_warm_default, start_preview_for_device, stop_preview_for_device,
+};
+use crate::preview::timelapse_task::stop_timelapse_for_device;
+use crate::settings::apply::apply_saved_settings;
+use crate::settings::commands::SettingsState;
+use crate::settings::scheduler::SchedulerState;
+use crate::settings::store::SettingsStore;
+
+/// Where the bridge sends its events: the app handle, or a fake in tests.
+pub trait HotplugEmitter {
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S);
+}
+
+impl HotplugEmitter for AppHandle {
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Err(e) = self.emit(event, payload) {
+            tracing::warn!("Failed to emit {event} event: {e}");
+        }
+    }
+}
 
 /// Start watching for hotplug events and forward them as Tauri events.
 ///
-/// On `Connected` events, also auto-applies saved settings and emits a
-/// `"settings-restored"` event to notify the frontend.
+/// On `Connected` events, also auto-applies saved settings, emits a
+/// `"settings-restored"` event to notify the frontend (and
+/// `"settings-reconciled"` if saved values had to be adjusted) and has the
+/// scheduler re-apply the camera's scheduled preset.
 pub fn start_hotplug_watcher(app_handle: &AppHandle, backend: &dyn CameraBackend) {
     let handle = app_handle.clone();
 
     let result = backend.watch_hotplug(Box::new(move |event: HotplugEvent| {
-        if let Err(e) = handle.emit("camera-hotplug", &event) {
-            tracing::warn!("Failed to emit camera-hotplug event: {e}");
-        }
+        forward_hotplug_event(&handle, &event, |event| {
+            handle_hotplug_event(&handle, event)
+        });
+    }));
 
-        match &event {
-            HotplugEvent::Connected(ref device) => {
-                // Auto-start capture session for the newly connected camera
-                start_preview_for_device(&handle, device.id.as_str());
-
-                // Auto-apply saved settings
-                let settings_state = handle.try_state::<SettingsState>();
-                let camera_state = handle.try_state::<CameraState>();
-
-                if let (Some(settings), Some(camera)) = (settings_state, camera_state) {
-                    let applied = apply_saved_settings(
-                        camera.backend.as_ref(),
-                        &settings.store,
-                        device.id.as_str(),
-                    );
-                    if !applied.is_empty() {
-                        tracing::info!(
-                            "Auto-applied {} settings for '{}' on hotplug",
-                            applied.len(),
-                            device.name
-                        );
-                        let _ = handle.emit(
-                            "settings-restored",
-                            serde_json::json!({
-                                "deviceId": device.id.as_str(),
-                                "cameraName": device.name,
-                                "controlsApplied": applied.len(),
-                            }),
-                        );
+    if let Err(e) = result {
+        tracing::warn!("Failed to start hotplug watcher: {e}");
+    }
+}
+
+/// The event announcing just this camera: `camera-connected` or
+/// `camera-disconnected`.
+pub fn device_event_name(event: &HotplugEvent) -> &'static str {
+    match event {
+        HotplugEvent::Connected(_) => "camera-connected",
+        HotplugEvent::Disconnected { .. } => "camera-disconnected",
+    }
+}
+
+/// Send `event` to the frontend around `handle` dealing with it.
+///
+/// `camera-hotplug` goes out first so the camera list updates at once.
+/// [`device_event_name`] follows, with the same payload, once `handle` is
+/// done, so a camera's saved settings are already on it when the frontend
+/// hears it connected.
+pub fn forward_hotplug_event(
+    emitter: &impl HotplugEmitter,
+    event: &HotplugEvent,
+    handle: impl FnOnce(&HotplugEvent),
+) {
+    emitter.emit_event("camera-hotplug", event);
+    handle(event);
+    emitter.emit_event(device_event_name(event), event);
+}
+
+/// Apply `device`'s saved settings, announcing them with
+/// `settings-restored` (and `settings-reconciled` if saved values had to be
+/// adjusted).
+pub fn restore_device_settings(
+    emitter: &impl HotplugEmitter,
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device: &CameraDevice,
+) {
+    let restored = apply_saved_settings(backend, store, latency, device.id.as_str());
+    let applied = &restored.applied;
+    if !applied.is_empty() {
+        tracing::info!(
+            "Auto-applied {} settings for '{}' on hotplug",
+            applied.len(),
+            device.name
+        );
+        emitter.emit_event(
+            "settings-restored",
+            serde_json::json!({
+                "deviceId": device.id.as_str(),
+                "cameraName": device.name,
+                "controlsApplied": applied.len(),
+            }),
+        );
+    }
+    if let Some(reconciled) = restored.reconciled_event(device.id.as_str(), &device.name) {
+        emitter.emit_event("settings-reconciled", reconciled);
+    }
+}
+
+/// React to a camera connecting or disconnecting: start or stop its
+/// preview, restore its settings and refresh the default camera. Also used
+/// by the soak test to simulate hotplug.
+pub fn handle_hotplug_event(handle: &AppHandle, event: &HotplugEvent) {
+    match event {
+        HotplugEvent::Connected(device) => {
+            // A driver update may have renamed a camera we have settings for
+            if let Some(settings) = handle.try_state::<SettingsState>() {
+                refresh_camera_names(handle, &settings.store, std::slice::from_ref(device));
+            }
+
+            // The first camera plugged in may need a default suggested
+            if let (Some(settings), Some(camera)) = (
+                handle.try_state::<SettingsState>(),
+                handle.try_state::<CameraState>(),
+            ) {
+                if settings.store.default_camera().is_none() {
+                    if let Ok(devices) = camera.backend.enumerate_devices() {
+                        seed_default_camera(&camera.backend, &settings.store, &devices);
                     }
                 }
             }
-            HotplugEvent::Disconnected { id } => {
-                // Clean up capture session for the disconnected camera
-                stop_preview_for_device(&handle, id.as_str());
+
+            // Warm a new default camera first, so auto-start skips it
+            refresh_warm_default(handle);
+
+            // Auto-start capture session for the newly connected camera
+            start_preview_for_device(handle, device.id.as_str());
+
+            // Auto-apply saved settings
+            let settings_state = handle.try_state::<SettingsState>();
+            let camera_state = handle.try_state::<CameraState>();
+            let latency_state = handle.try_state::<ControlLatencyState>();
+
+            if let (Some(settings), Some(camera), Some(latency)) =
+                (settings_state, camera_state, latency_state)
+            {
+                restore_device_settings(handle, &camera.backend, &settings.store, &latency, device);
             }
-        }
-    }));
 
-    if let Err(e) = result {
-        tracing::warn!("Failed to start hotplug watcher: {e}");
+            // The schedule's preset goes on top of the restored settings
+            if let Some(scheduler) = handle.try_state::<SchedulerState>() {
+                scheduler.wake(Some(device.id.as_str()));
+            }
+        }
+        HotplugEvent::Disconnected { id } => {
+            // Clean up capture session and time-lapse for the disconnected camera
+            stop_preview_for_device(handle, id.as_str());
+            stop_timelapse_for_device(handle, id.as_str());
+            refresh_warm_default(handle);
+        }
     }
+
+    crate::tray::notify_activity(handle);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::camera::dummy::DummyBackend;
     use crate::camera::error::{CameraError, Result};
     use crate::camera::types::{
-        CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId, FormatDescriptor,
-        HotplugEvent,
+        CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId, DeviceKind,
+        FormatDescriptor, HotplugEvent,
     };
     use std::sync::{Arc, Mutex};
 
     type HotplugCallback = Arc<Mutex<Option<Box<dyn Fn(HotplugEvent) + Send>>>>;
 
+    /// Emitter that collects events in the order they were sent.
+    #[derive(Clone, Default)]
+    struct FakeEmitter {
+        events: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+    }
+
+    impl FakeEmitter {
+        fn names(&self) -> Vec<String> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect()
+        }
+
+        fn payload(&self, name: &str) -> serde_json::Value {
+            let events = self.events.lock().unwrap();
+            let (_, payload) = events.iter().find(|(n, _)| n == name).unwrap();
+            payload.clone()
+        }
+    }
+
+    impl HotplugEmitter for FakeEmitter {
+        fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) {
+            let payload = serde_json::to_value(payload).unwrap();
+            self.events
+                .lock()
+                .unwrap()
+                .push((event.to_string(), payload));
+        }
+    }
+
+    /// Bridge hotplug events from a mock backend to `emitter`, restoring
+    /// settings from `store` onto `cameras` as the app does. Returns the
+    /// slot to fire synthetic events through.
+    fn bridge(
+        emitter: &FakeEmitter,
+        cameras: Arc<DummyBackend>,
+        store: Arc<SettingsStore>,
+    ) -> HotplugCallback {
+        let (backend, callback_slot) = MockHotplugBackend::new();
+        let emitter = emitter.clone();
+        let latency = ControlLatencyState::default();
+        backend
+            .watch_hotplug(Box::new(move |event: HotplugEvent| {
+                forward_hotplug_event(&emitter, &event, |event| {
+                    if let HotplugEvent::Connected(device) = event {
+                        restore_device_settings(&emitter, &*cameras, &store, &latency, device);
+                    }
+                })
+            }))
+            .expect("watch_hotplug should succeed");
+        callback_slot
+    }
+
+    fn fire(callback_slot: &HotplugCallback, event: HotplugEvent) {
+        let callback = callback_slot.lock().unwrap();
+        callback.as_ref().expect("callback should be registered")(event);
+    }
+
     /// Mock backend that captures the hotplug callback and lets tests invoke it.
     struct MockHotplugBackend {
         callback: HotplugCallback,
@@ -118,6 +278,15 @@ mod tests {
             Ok(())
         }
 
+        fn set_control_auto(
+            &self,
+            _id: &DeviceId,
+            _control: &ControlId,
+            _auto: bool,
+        ) -> Result<()> {
+            Ok(())
+        }
+
         fn get_formats(&self, _id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             Ok(vec![])
         }
@@ -152,6 +321,15 @@ mod tests {
             Ok(())
         }
 
+        fn set_control_auto(
+            &self,
+            _id: &DeviceId,
+            _control: &ControlId,
+            _auto: bool,
+        ) -> Result<()> {
+            Ok(())
+        }
+
         fn get_formats(&self, _id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
             Ok(vec![])
         }
@@ -198,6 +376,10 @@ mod tests {
             name: "Test Camera".to_string(),
             device_path: "test-path".to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         }));
 
         let events = received_events.lock().unwrap();
@@ -253,6 +435,72 @@ mod tests {
         assert_eq!(payload["cameraName"], "Test Camera");
     }
 
+    #[test]
+    fn connected_camera_is_announced_once_its_settings_are_restored() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(SettingsStore::new(dir.path().join("cameras.json")));
+        let id = DummyBackend::device_id();
+        store.set_control(id.as_str(), "Dummy Test Camera", "contrast", 70);
+        let cameras = Arc::new(DummyBackend::new());
+        let emitter = FakeEmitter::default();
+        let slot = bridge(&emitter, Arc::clone(&cameras), store);
+
+        let device = cameras.enumerate_devices().unwrap().remove(0);
+        fire(&slot, HotplugEvent::Connected(device.clone()));
+
+        assert_eq!(
+            emitter.names(),
+            vec!["camera-hotplug", "settings-restored", "camera-connected"]
+        );
+        let contrast = cameras.get_control(&id, &ControlId::Contrast).unwrap();
+        assert_eq!(contrast.value(), 70);
+
+        // Same shape as camera-hotplug: the device, tagged
+        let payload = emitter.payload("camera-connected");
+        assert_eq!(payload, emitter.payload("camera-hotplug"));
+        assert_eq!(payload["type"], "connected");
+        assert_eq!(payload["id"], id.as_str());
+        assert_eq!(payload["name"], device.name.as_str());
+    }
+
+    #[test]
+    fn connected_camera_without_saved_settings_is_still_announced() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(SettingsStore::new(dir.path().join("cameras.json")));
+        let cameras = Arc::new(DummyBackend::new());
+        let emitter = FakeEmitter::default();
+        let slot = bridge(&emitter, Arc::clone(&cameras), store);
+
+        let device = cameras.enumerate_devices().unwrap().remove(0);
+        fire(&slot, HotplugEvent::Connected(device));
+
+        assert_eq!(emitter.names(), vec!["camera-hotplug", "camera-connected"]);
+    }
+
+    #[test]
+    fn disconnected_camera_is_announced_by_id() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(SettingsStore::new(dir.path().join("cameras.json")));
+        let emitter = FakeEmitter::default();
+        let slot = bridge(&emitter, Arc::new(DummyBackend::new()), store);
+
+        fire(
+            &slot,
+            HotplugEvent::Disconnected {
+                id: DeviceId::new("test:001"),
+            },
+        );
+
+        assert_eq!(
+            emitter.names(),
+            vec!["camera-hotplug", "camera-disconnected"]
+        );
+        assert_eq!(
+            emitter.payload("camera-disconnected"),
+            serde_json::json!({ "type": "disconnected", "id": "test:001" })
+        );
+    }
+
     #[test]
     fn hotplug_bridge_logs_error_on_watch_failure() {
         // FailingHotplugBackend.watch_hotplug returns Err — start_hotplug_watcher
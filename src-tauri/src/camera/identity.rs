@@ -0,0 +1,390 @@
+// Recognising one physical camera exposed by more than one backend.
+//
+// With Canon's EOS Webcam Utility installed, a Canon body shows up once
+// through EDSDK and once as a DirectShow device, and the two fight over
+// controls and saved settings. Backends annotate their devices with what
+// they know of the physical camera (a serial number, a model name), and
+// the composite backend keeps the device from the higher-priority backend,
+// marking the other `suppressed_by` it.
+//
+// Serials decide when both devices have one. Otherwise the models must be
+// equal, ignoring case, punctuation and a leading vendor name, and each must
+// be the only device of that model in its backend, so two identical webcams
+// are never taken for one camera seen twice.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::camera::types::{CameraDevice, DeviceKind};
+
+/// Vendor names some backends put in front of the model and others don't.
+const VENDOR_PREFIXES: &[&str] = &["canon"];
+
+/// Devices enumerated by one backend, with that backend's priority.
+#[derive(Debug, Clone)]
+pub struct BackendDevices {
+    /// Higher wins when two backends expose the same camera.
+    pub priority: u8,
+    pub devices: Vec<CameraDevice>,
+}
+
+/// USB serial number from a device path such as
+/// `\\?\usb#vid_04a9&pid_3294#0123456789ab#{guid}\global`.
+///
+/// `None` for paths without VID/PID, for interfaces of composite devices,
+/// and for instance IDs Windows generated because the device reports no
+/// serial (these contain `&`).
+pub fn usb_serial(device_path: &str) -> Option<String> {
+    let parts: Vec<&str> = device_path.split('#').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    let hardware = parts[1].to_ascii_lowercase();
+    if !hardware.contains("vid_") || !hardware.contains("pid_") || hardware.contains("mi_") {
+        return None;
+    }
+    let instance = parts[2];
+    if instance.is_empty() || instance.starts_with('{') || instance.contains('&') {
+        return None;
+    }
+    Some(instance.to_string())
+}
+
+/// Model name reduced to lowercase words, without a leading vendor name.
+fn normalise_model(model: &str) -> Option<String> {
+    let mut tokens: Vec<String> = model
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_ascii_lowercase())
+        .collect();
+    if tokens.len() > 1 && VENDOR_PREFIXES.contains(&tokens[0].as_str()) {
+        tokens.remove(0);
+    }
+    (!tokens.is_empty()).then(|| tokens.join(" "))
+}
+
+fn normalise_serial(serial: &str) -> Option<String> {
+    let serial = serial.trim();
+    (!serial.is_empty()).then(|| serial.to_ascii_uppercase())
+}
+
+/// A device's identity, prepared for matching.
+struct Candidate {
+    backend: usize,
+    serial: Option<String>,
+    model: Option<String>,
+    /// Whether no other device of its backend has the same model.
+    unique_model: bool,
+}
+
+impl Candidate {
+    fn same_camera(&self, other: &Candidate) -> bool {
+        if self.backend == other.backend {
+            return false;
+        }
+        match (&self.serial, &other.serial) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                self.unique_model
+                    && other.unique_model
+                    && self.model.is_some()
+                    && self.model == other.model
+            }
+        }
+    }
+}
+
+/// Merge device lists from several backends, in backend order.
+///
+/// A device matching one from a higher-priority backend gets
+/// `suppressed_by` set to that device's ID; between equal priorities the
+/// earlier backend wins. Only primary devices with an identity are
+/// compared, and never two from the same backend.
+pub fn merge_devices(lists: Vec<BackendDevices>) -> Vec<CameraDevice> {
+    let mut merged = Vec::new();
+    let mut candidates = Vec::new();
+    let mut rank = Vec::new();
+
+    for (backend, list) in lists.into_iter().enumerate() {
+        let models: Vec<Option<String>> = list
+            .devices
+            .iter()
+            .map(|d| {
+                d.identity
+                    .as_ref()
+                    .and_then(|id| id.model.as_deref())
+                    .and_then(normalise_model)
+            })
+            .collect();
+        let mut model_counts: HashMap<&str, usize> = HashMap::new();
+        for model in models.iter().flatten() {
+            *model_counts.entry(model).or_default() += 1;
+        }
+
+        let backend_candidates: Vec<Option<Candidate>> = list
+            .devices
+            .iter()
+            .zip(&models)
+            .map(|(device, model)| {
+                let identity = device.identity.as_ref()?;
+                (device.kind == DeviceKind::Primary).then(|| Candidate {
+                    backend,
+                    serial: identity.serial.as_deref().and_then(normalise_serial),
+                    unique_model: model.as_deref().is_some_and(|m| model_counts[m] == 1),
+                    model: model.clone(),
+                })
+            })
+            .collect();
+
+        rank.extend(std::iter::repeat((Reverse(list.priority), backend)).take(list.devices.len()));
+        candidates.extend(backend_candidates);
+        merged.extend(list.devices);
+    }
+
+    // Winners first: the highest priority, then the earliest backend
+    let mut order: Vec<usize> = (0..merged.len()).collect();
+    order.sort_by_key(|&i| rank[i]);
+
+    for (position, &owner) in order.iter().enumerate() {
+        if merged[owner].suppressed_by.is_some() {
+            continue;
+        }
+        let Some(owner_candidate) = &candidates[owner] else {
+            continue;
+        };
+        let owner_id = merged[owner].id.clone();
+        for &other in &order[position + 1..] {
+            let matches = candidates[other]
+                .as_ref()
+                .is_some_and(|c| owner_candidate.same_camera(c));
+            if matches && merged[other].suppressed_by.is_none() {
+                merged[other].suppressed_by = Some(owner_id.clone());
+            }
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{DeviceId, PhysicalIdentity};
+
+    fn device(id: &str, name: &str, serial: Option<&str>) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: name.to_string(),
+            device_path: String::new(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: Some(PhysicalIdentity {
+                serial: serial.map(str::to_string),
+                model: Some(name.to_string()),
+            }),
+            suppressed_by: None,
+        }
+    }
+
+    fn backend(priority: u8, devices: Vec<CameraDevice>) -> BackendDevices {
+        BackendDevices { priority, devices }
+    }
+
+    /// (device ID, suppressed by) for every merged device.
+    fn suppression(merged: &[CameraDevice]) -> Vec<(&str, Option<&str>)> {
+        merged
+            .iter()
+            .map(|d| {
+                (
+                    d.id.as_str(),
+                    d.suppressed_by.as_ref().map(DeviceId::as_str),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn model_match_suppresses_the_lower_priority_device() {
+        let merged = merge_devices(vec![
+            backend(
+                0,
+                vec![
+                    device("ds:brio", "Logitech BRIO", None),
+                    device("ds:eos", "EOS R5", None),
+                ],
+            ),
+            backend(10, vec![device("canon:usb1", "Canon EOS R5", None)]),
+        ]);
+        assert_eq!(
+            suppression(&merged),
+            [
+                ("ds:brio", None),
+                ("ds:eos", Some("canon:usb1")),
+                ("canon:usb1", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn serial_match_wins_over_differing_names() {
+        let merged = merge_devices(vec![
+            backend(0, vec![device("ds:eos", "EOS Webcam", Some("0123abc"))]),
+            backend(
+                10,
+                vec![device("canon:usb1", "Canon EOS R5", Some("0123ABC "))],
+            ),
+        ]);
+        assert_eq!(merged[0].suppressed_by, Some(DeviceId::new("canon:usb1")));
+        assert_eq!(merged[1].suppressed_by, None);
+    }
+
+    #[test]
+    fn differing_serials_keep_identical_models_apart() {
+        let merged = merge_devices(vec![
+            backend(0, vec![device("ds:eos", "Canon EOS R5", Some("A1"))]),
+            backend(10, vec![device("canon:usb1", "Canon EOS R5", Some("B2"))]),
+        ]);
+        assert!(merged.iter().all(|d| d.suppressed_by.is_none()));
+    }
+
+    #[test]
+    fn ambiguous_models_are_not_matched_by_name() {
+        // Two R5s over DirectShow: which one EDSDK sees can't be told by name
+        let merged = merge_devices(vec![
+            backend(
+                0,
+                vec![
+                    device("ds:eos1", "EOS R5", None),
+                    device("ds:eos2", "EOS R5", None),
+                ],
+            ),
+            backend(10, vec![device("canon:usb1", "Canon EOS R5", None)]),
+        ]);
+        assert!(merged.iter().all(|d| d.suppressed_by.is_none()));
+    }
+
+    #[test]
+    fn similar_names_are_not_matches() {
+        let cases = [
+            ("Canon EOS R5", "Canon EOS R5 C"),
+            ("Canon EOS R5", "Canon EOS R6"),
+            ("Canon EOS R5", "EOS Webcam Utility"),
+            ("Canon", "Canon EOS R5"),
+            ("HD Webcam", "USB HD Webcam"),
+        ];
+        for (ds_name, canon_name) in cases {
+            let merged = merge_devices(vec![
+                backend(0, vec![device("ds:1", ds_name, None)]),
+                backend(10, vec![device("canon:1", canon_name, None)]),
+            ]);
+            assert!(
+                merged.iter().all(|d| d.suppressed_by.is_none()),
+                "{ds_name:?} vs {canon_name:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn devices_without_identity_are_never_matched() {
+        let mut ds = device("ds:eos", "Canon EOS R5", None);
+        ds.identity = None;
+        let merged = merge_devices(vec![
+            backend(0, vec![ds]),
+            backend(10, vec![device("canon:usb1", "Canon EOS R5", None)]),
+        ]);
+        assert!(merged.iter().all(|d| d.suppressed_by.is_none()));
+    }
+
+    #[test]
+    fn siblings_and_same_backend_devices_are_never_matched() {
+        let mut infrared = device("ds:brio-ir", "Logitech BRIO", Some("ABC"));
+        infrared.kind = DeviceKind::Infrared;
+        let merged = merge_devices(vec![
+            backend(
+                0,
+                vec![
+                    device("ds:brio", "Logitech BRIO", Some("ABC")),
+                    device("ds:brio-2", "Logitech BRIO", Some("ABC")),
+                ],
+            ),
+            backend(10, vec![infrared]),
+        ]);
+        assert!(merged.iter().all(|d| d.suppressed_by.is_none()));
+    }
+
+    #[test]
+    fn priority_decides_regardless_of_backend_order() {
+        let merged = merge_devices(vec![
+            backend(10, vec![device("canon:usb1", "Canon EOS R5", None)]),
+            backend(0, vec![device("ds:eos", "EOS R5", None)]),
+        ]);
+        assert_eq!(
+            suppression(&merged),
+            [("canon:usb1", None), ("ds:eos", Some("canon:usb1"))]
+        );
+
+        // Equal priorities: the earlier backend keeps its device
+        let merged = merge_devices(vec![
+            backend(0, vec![device("ds:eos", "EOS R5", None)]),
+            backend(0, vec![device("canon:usb1", "Canon EOS R5", None)]),
+        ]);
+        assert_eq!(
+            suppression(&merged),
+            [("ds:eos", None), ("canon:usb1", Some("ds:eos"))]
+        );
+    }
+
+    #[test]
+    fn every_duplicate_points_at_the_preferred_device() {
+        let merged = merge_devices(vec![
+            backend(0, vec![device("ds:eos", "EOS R5", None)]),
+            backend(5, vec![device("mf:eos", "EOS R5", None)]),
+            backend(10, vec![device("canon:usb1", "Canon EOS R5", None)]),
+        ]);
+        assert_eq!(
+            suppression(&merged),
+            [
+                ("ds:eos", Some("canon:usb1")),
+                ("mf:eos", Some("canon:usb1")),
+                ("canon:usb1", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn usb_serial_reads_real_serials_only() {
+        let cases = [
+            (
+                r"\\?\usb#vid_04a9&pid_3294#0123456789ab#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global",
+                Some("0123456789ab"),
+            ),
+            // Generated instance ID: the device has no serial
+            (
+                r"\\?\usb#vid_046d&pid_0825#5&1a2b3c4d&0&2#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global",
+                None,
+            ),
+            // Interface of a composite device
+            (
+                r"\\?\usb#vid_046d&pid_085e&mi_00#7&2d1a5c2b&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global",
+                None,
+            ),
+            (
+                r"\\?\root#image#0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}",
+                None,
+            ),
+            ("", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(usb_serial(path).as_deref(), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn models_ignore_case_punctuation_and_vendor() {
+        assert_eq!(normalise_model("Canon EOS R5").as_deref(), Some("eos r5"));
+        assert_eq!(normalise_model("EOS-R5").as_deref(), Some("eos r5"));
+        assert_eq!(normalise_model("canon").as_deref(), Some("canon"));
+        assert_eq!(normalise_model(" - ").as_deref(), None);
+    }
+}
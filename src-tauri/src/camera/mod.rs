@@ -2,10 +2,23 @@
 
 pub mod backend;
 pub mod canon;
+#[cfg(feature = "app")]
 pub mod commands;
 pub mod composite;
+pub mod debounce;
 pub mod dummy;
 pub mod error;
+pub mod formats;
+#[cfg(feature = "app")]
 pub mod hotplug_bridge;
+pub mod identity;
 pub mod platform;
+pub mod powerline;
+pub mod queue;
+pub mod siblings;
+pub mod startup;
+pub mod suggest;
+pub mod swap;
+pub mod tally;
 pub mod types;
+pub mod units;
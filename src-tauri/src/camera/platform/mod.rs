@@ -1,5 +1,36 @@
+use std::sync::Arc;
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::dummy::DummyBackend;
+
+#[cfg(not(target_os = "windows"))]
+pub mod null;
+pub mod stream_caps;
+
 #[cfg(target_os = "windows")]
 pub mod windows;
 
+#[cfg(not(target_os = "windows"))]
+pub use null::NullBackend;
 #[cfg(target_os = "windows")]
 pub use windows::WindowsBackend;
+
+/// Backends for the cameras this platform supports natively:
+/// - `WindowsBackend` (DirectShow) on Windows, `NullBackend` elsewhere
+/// - `DummyBackend` when `DUMMY_CAMERA=1` is set
+///
+/// Canon cameras need the EDSDK loaded and are added separately.
+pub fn native_backends() -> Vec<Arc<dyn CameraBackend>> {
+    let mut backends: Vec<Arc<dyn CameraBackend>> = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    backends.push(Arc::new(WindowsBackend::new()));
+
+    #[cfg(not(target_os = "windows"))]
+    backends.push(Arc::new(NullBackend));
+
+    if DummyBackend::is_enabled() {
+        backends.push(Arc::new(DummyBackend::new()));
+    }
+    backends
+}
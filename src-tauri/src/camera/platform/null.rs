@@ -0,0 +1,52 @@
+use crate::camera::backend::CameraBackend;
+use crate::camera::error::{CameraError, Result};
+use crate::camera::types::{
+    BackendCapabilities, CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId,
+    FormatDescriptor, HotplugEvent,
+};
+
+/// No-op backend used on platforms without a native camera backend.
+pub struct NullBackend;
+
+impl CameraBackend for NullBackend {
+    fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
+        Ok(vec![])
+    }
+
+    fn watch_hotplug(&self, _callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_controls(&self, _id: &DeviceId) -> Result<Vec<ControlDescriptor>> {
+        Ok(vec![])
+    }
+
+    fn get_control(&self, _id: &DeviceId, _control: &ControlId) -> Result<ControlValue> {
+        Err(CameraError::DeviceNotFound("no backend".to_string()))
+    }
+
+    fn set_control(
+        &self,
+        _id: &DeviceId,
+        _control: &ControlId,
+        _value: ControlValue,
+    ) -> Result<()> {
+        Err(CameraError::DeviceNotFound("no backend".to_string()))
+    }
+
+    fn set_control_auto(&self, _id: &DeviceId, _control: &ControlId, _auto: bool) -> Result<()> {
+        Err(CameraError::DeviceNotFound("no backend".to_string()))
+    }
+
+    fn get_formats(&self, _id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
+        Ok(vec![])
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities::none()
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        Err(CameraError::DeviceNotFound(id.to_string()))
+    }
+}
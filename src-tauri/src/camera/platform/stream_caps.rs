@@ -0,0 +1,263 @@
+// Parsing of DirectShow VIDEO_STREAM_CONFIG_CAPS buffers.
+//
+// `IAMStreamConfig::GetStreamCaps` fills a caller-provided byte buffer with
+// this structure alongside the media type. It is parsed from raw bytes (rather
+// than cast) so the logic is platform-independent and testable.
+
+/// Size in bytes of `VIDEO_STREAM_CONFIG_CAPS` (strmif.h).
+pub const VIDEO_STREAM_CONFIG_CAPS_SIZE: usize = 128;
+
+// Field offsets within VIDEO_STREAM_CONFIG_CAPS. The two LONGLONG frame
+// interval fields are 8-byte aligned, leaving 4 bytes of padding at 100.
+const OFFSET_MIN_OUTPUT_SIZE: usize = 60;
+const OFFSET_MAX_OUTPUT_SIZE: usize = 68;
+const OFFSET_MIN_FRAME_INTERVAL: usize = 104;
+const OFFSET_MAX_FRAME_INTERVAL: usize = 112;
+const OFFSET_MIN_BITS_PER_SECOND: usize = 120;
+const OFFSET_MAX_BITS_PER_SECOND: usize = 124;
+
+/// 100ns units per second (REFERENCE_TIME).
+const REFERENCE_TIME_PER_SECOND: f64 = 10_000_000.0;
+
+/// Typed subset of `VIDEO_STREAM_CONFIG_CAPS` used for format reporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfigCaps {
+    pub min_output_size: (i32, i32),
+    pub max_output_size: (i32, i32),
+    /// Shortest frame interval in 100ns units (highest fps).
+    pub min_frame_interval: i64,
+    /// Longest frame interval in 100ns units (lowest fps).
+    pub max_frame_interval: i64,
+    pub min_bits_per_second: i32,
+    pub max_bits_per_second: i32,
+}
+
+fn read_i32(buf: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap_or([0; 4]))
+}
+
+fn read_i64(buf: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap_or([0; 8]))
+}
+
+/// The `AvgTimePerFrame` for `fps`, rounded to the nearest 100ns unit, or
+/// `None` for 0fps, which means the camera may pick.
+pub fn fps_to_frame_interval(fps: f32) -> Option<i64> {
+    if !(fps > 0.0 && fps.is_finite()) {
+        return None;
+    }
+    Some((REFERENCE_TIME_PER_SECOND / fps as f64).round().max(1.0) as i64)
+}
+
+/// The frame rate an `AvgTimePerFrame` stands for, or `None` if the driver
+/// left it unset.
+pub fn frame_interval_to_fps(interval: i64) -> Option<f64> {
+    (interval > 0).then(|| REFERENCE_TIME_PER_SECOND / interval as f64)
+}
+
+/// Parse a `VIDEO_STREAM_CONFIG_CAPS` buffer.
+///
+/// Returns `None` if the buffer is too small to hold the structure (e.g. an
+/// audio capability, or a driver reporting a bogus size).
+pub fn parse_stream_config_caps(buf: &[u8]) -> Option<StreamConfigCaps> {
+    if buf.len() < VIDEO_STREAM_CONFIG_CAPS_SIZE {
+        return None;
+    }
+
+    Some(StreamConfigCaps {
+        min_output_size: (
+            read_i32(buf, OFFSET_MIN_OUTPUT_SIZE),
+            read_i32(buf, OFFSET_MIN_OUTPUT_SIZE + 4),
+        ),
+        max_output_size: (
+            read_i32(buf, OFFSET_MAX_OUTPUT_SIZE),
+            read_i32(buf, OFFSET_MAX_OUTPUT_SIZE + 4),
+        ),
+        min_frame_interval: read_i64(buf, OFFSET_MIN_FRAME_INTERVAL),
+        max_frame_interval: read_i64(buf, OFFSET_MAX_FRAME_INTERVAL),
+        min_bits_per_second: read_i32(buf, OFFSET_MIN_BITS_PER_SECOND),
+        max_bits_per_second: read_i32(buf, OFFSET_MAX_BITS_PER_SECOND),
+    })
+}
+
+impl StreamConfigCaps {
+    /// The advertised `(min_fps, max_fps)` range, or `None` if the frame
+    /// intervals are missing or inconsistent.
+    pub fn fps_range(&self) -> Option<(f32, f32)> {
+        if self.min_frame_interval <= 0 || self.max_frame_interval < self.min_frame_interval {
+            return None;
+        }
+        let min_fps = REFERENCE_TIME_PER_SECOND / self.max_frame_interval as f64;
+        let max_fps = REFERENCE_TIME_PER_SECOND / self.min_frame_interval as f64;
+        Some((min_fps as f32, max_fps as f32))
+    }
+
+    /// The `AvgTimePerFrame` to request `fps`, or `None` if it falls outside
+    /// the advertised range.
+    pub fn frame_interval_for_fps(&self, fps: f32) -> Option<i64> {
+        let interval = fps_to_frame_interval(fps)?;
+        if self.fps_range().is_none()
+            || interval < self.min_frame_interval
+            || interval > self.max_frame_interval
+        {
+            return None;
+        }
+        Some(interval)
+    }
+
+    /// The `AvgTimePerFrame` to request `fps`, clamped into the advertised
+    /// frame-interval range so a rate the camera can't do asks for the
+    /// nearest one it can. Unclamped when the range is missing, and `None`
+    /// for 0fps.
+    pub fn clamped_frame_interval(&self, fps: f32) -> Option<i64> {
+        let interval = fps_to_frame_interval(fps)?;
+        if self.fps_range().is_none() {
+            return Some(interval);
+        }
+        Some(interval.clamp(self.min_frame_interval, self.max_frame_interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a VIDEO_STREAM_CONFIG_CAPS byte fixture.
+    fn caps_fixture(
+        min_size: (i32, i32),
+        max_size: (i32, i32),
+        min_interval: i64,
+        max_interval: i64,
+    ) -> Vec<u8> {
+        let mut buf = vec![0u8; VIDEO_STREAM_CONFIG_CAPS_SIZE];
+        buf[OFFSET_MIN_OUTPUT_SIZE..][..4].copy_from_slice(&min_size.0.to_le_bytes());
+        buf[OFFSET_MIN_OUTPUT_SIZE + 4..][..4].copy_from_slice(&min_size.1.to_le_bytes());
+        buf[OFFSET_MAX_OUTPUT_SIZE..][..4].copy_from_slice(&max_size.0.to_le_bytes());
+        buf[OFFSET_MAX_OUTPUT_SIZE + 4..][..4].copy_from_slice(&max_size.1.to_le_bytes());
+        buf[OFFSET_MIN_FRAME_INTERVAL..][..8].copy_from_slice(&min_interval.to_le_bytes());
+        buf[OFFSET_MAX_FRAME_INTERVAL..][..8].copy_from_slice(&max_interval.to_le_bytes());
+        buf[OFFSET_MIN_BITS_PER_SECOND..][..4].copy_from_slice(&1_000i32.to_le_bytes());
+        buf[OFFSET_MAX_BITS_PER_SECOND..][..4].copy_from_slice(&2_000i32.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn parses_all_fields_from_fixture() {
+        let buf = caps_fixture((640, 480), (1920, 1080), 333_333, 2_000_000);
+        let caps = parse_stream_config_caps(&buf).unwrap();
+
+        assert_eq!(caps.min_output_size, (640, 480));
+        assert_eq!(caps.max_output_size, (1920, 1080));
+        assert_eq!(caps.min_frame_interval, 333_333);
+        assert_eq!(caps.max_frame_interval, 2_000_000);
+        assert_eq!(caps.min_bits_per_second, 1_000);
+        assert_eq!(caps.max_bits_per_second, 2_000);
+    }
+
+    #[test]
+    fn rejects_undersized_buffer() {
+        let buf = vec![0u8; VIDEO_STREAM_CONFIG_CAPS_SIZE - 1];
+        assert!(parse_stream_config_caps(&buf).is_none());
+        assert!(parse_stream_config_caps(&[]).is_none());
+    }
+
+    #[test]
+    fn accepts_oversized_buffer() {
+        let mut buf = caps_fixture((1, 1), (2, 2), 333_333, 333_333);
+        buf.extend_from_slice(&[0xFF; 16]);
+        assert!(parse_stream_config_caps(&buf).is_some());
+    }
+
+    #[test]
+    fn fps_range_from_intervals() {
+        // 5-30fps
+        let buf = caps_fixture((1920, 1080), (1920, 1080), 333_333, 2_000_000);
+        let (min, max) = parse_stream_config_caps(&buf).unwrap().fps_range().unwrap();
+        assert!((min - 5.0).abs() < 0.01, "min fps {min}");
+        assert!((max - 30.0).abs() < 0.01, "max fps {max}");
+    }
+
+    #[test]
+    fn fps_range_none_for_zero_or_inverted_intervals() {
+        let zero = caps_fixture((1, 1), (1, 1), 0, 0);
+        assert!(parse_stream_config_caps(&zero)
+            .unwrap()
+            .fps_range()
+            .is_none());
+
+        let inverted = caps_fixture((1, 1), (1, 1), 2_000_000, 333_333);
+        assert!(parse_stream_config_caps(&inverted)
+            .unwrap()
+            .fps_range()
+            .is_none());
+    }
+
+    #[test]
+    fn frame_interval_for_fps_within_range() {
+        let buf = caps_fixture((1920, 1080), (1920, 1080), 333_333, 2_000_000);
+        let caps = parse_stream_config_caps(&buf).unwrap();
+
+        assert_eq!(caps.frame_interval_for_fps(10.0), Some(1_000_000));
+        assert_eq!(caps.frame_interval_for_fps(5.0), Some(2_000_000));
+        assert_eq!(caps.frame_interval_for_fps(30.0), Some(333_333));
+    }
+
+    #[test]
+    fn frame_interval_for_fps_outside_range() {
+        let buf = caps_fixture((1920, 1080), (1920, 1080), 333_333, 2_000_000);
+        let caps = parse_stream_config_caps(&buf).unwrap();
+
+        assert_eq!(caps.frame_interval_for_fps(60.0), None);
+        assert_eq!(caps.frame_interval_for_fps(1.0), None);
+        assert_eq!(caps.frame_interval_for_fps(0.0), None);
+    }
+
+    #[test]
+    fn fps_to_frame_interval_rounds_to_the_nearest_unit() {
+        assert_eq!(fps_to_frame_interval(30.0), Some(333_333));
+        assert_eq!(fps_to_frame_interval(60.0), Some(166_667));
+        assert_eq!(fps_to_frame_interval(29.97), Some(333_667));
+        assert_eq!(fps_to_frame_interval(7.5), Some(1_333_333));
+        assert_eq!(fps_to_frame_interval(1.0), Some(10_000_000));
+        // Absurd rates still ask for a positive interval
+        assert_eq!(fps_to_frame_interval(1e9), Some(1));
+    }
+
+    #[test]
+    fn zero_fps_means_dont_care() {
+        assert_eq!(fps_to_frame_interval(0.0), None);
+        assert_eq!(fps_to_frame_interval(-30.0), None);
+        assert_eq!(fps_to_frame_interval(f32::NAN), None);
+        assert_eq!(fps_to_frame_interval(f32::INFINITY), None);
+
+        let buf = caps_fixture((1920, 1080), (1920, 1080), 333_333, 2_000_000);
+        let caps = parse_stream_config_caps(&buf).unwrap();
+        assert_eq!(caps.clamped_frame_interval(0.0), None);
+    }
+
+    #[test]
+    fn clamped_frame_interval_stays_within_the_advertised_range() {
+        // 5-30fps
+        let buf = caps_fixture((1920, 1080), (1920, 1080), 333_333, 2_000_000);
+        let caps = parse_stream_config_caps(&buf).unwrap();
+
+        assert_eq!(caps.clamped_frame_interval(15.0), Some(666_667));
+        assert_eq!(caps.clamped_frame_interval(60.0), Some(333_333));
+        assert_eq!(caps.clamped_frame_interval(1.0), Some(2_000_000));
+    }
+
+    #[test]
+    fn clamped_frame_interval_is_unclamped_without_a_range() {
+        let buf = caps_fixture((1, 1), (1, 1), 0, 0);
+        let caps = parse_stream_config_caps(&buf).unwrap();
+        assert_eq!(caps.clamped_frame_interval(60.0), Some(166_667));
+    }
+
+    #[test]
+    fn frame_interval_to_fps_inverts_the_interval() {
+        let fps = frame_interval_to_fps(333_333).unwrap();
+        assert!((fps - 30.0).abs() < 0.001, "fps {fps}");
+        assert_eq!(frame_interval_to_fps(0), None);
+        assert_eq!(frame_interval_to_fps(-1), None);
+    }
+}
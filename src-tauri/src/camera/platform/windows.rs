@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use tracing::{debug, error, info, warn};
 use windows::core::{Interface, GUID};
 use windows::Win32::Media::DirectShow::{IAMCameraControl, IAMVideoProcAmp};
+use windows::Win32::Media::KernelStreaming::{IKsControl, IKsTopologyInfo};
 use windows::Win32::Media::MediaFoundation::{
     CLSID_SystemDeviceEnum, CLSID_VideoInputDeviceCategory,
 };
@@ -14,16 +16,25 @@ use windows::Win32::System::Com::{
 use windows::Win32::System::Variant::VARIANT;
 
 use crate::camera::backend::CameraBackend;
+use crate::camera::debounce::{self, Debouncer, Pass};
 use crate::camera::error::{CameraError, Result};
+use crate::camera::identity::usb_serial;
+use crate::camera::platform::stream_caps::parse_stream_config_caps;
+use crate::camera::powerline::{self, PowerLineFrequency};
+use crate::camera::siblings::classify_devices;
+use crate::camera::tally::{self, XuLed};
 use crate::camera::types::{
-    CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue, DeviceId,
-    FormatDescriptor, HotplugEvent,
+    abbreviate_path, normalise_device_path, same_device_path, BackendCapabilities, CameraDevice,
+    ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue, DeviceId, DeviceKind,
+    FormatDescriptor, HotplugEvent, PhysicalIdentity,
 };
+use crate::supervisor::{panic_message, run_with_restart, RestartPolicy, SupervisedOutcome};
 
 /// Raw device info extracted from DirectShow enumeration.
 #[derive(Debug, Clone)]
 pub struct RawDeviceInfo {
     pub friendly_name: String,
+    /// Normalised with [`normalise_device_path`]; empty for virtual cameras.
     pub device_path: String,
 }
 
@@ -34,6 +45,7 @@ pub trait DeviceEnumerator: Send + Sync {
 }
 
 /// Real DirectShow device enumerator.
+#[derive(Default)]
 pub struct DirectShowEnumerator;
 
 impl DirectShowEnumerator {
@@ -100,9 +112,14 @@ unsafe fn enumerate_directshow_devices() -> Result<Vec<RawDeviceInfo>> {
         let friendly_name = read_property_string(&bag, "FriendlyName")
             .unwrap_or_else(|| "Unknown Camera".to_string());
 
-        let device_path = read_property_string(&bag, "DevicePath").unwrap_or_default();
+        let device_path = read_property_string(&bag, "DevicePath")
+            .map(|path| normalise_device_path(&path))
+            .unwrap_or_default();
 
-        debug!("Discovered device: name={friendly_name}, path={device_path}");
+        debug!(
+            "Discovered device: name={friendly_name}, path={}",
+            abbreviate_path(&device_path)
+        );
 
         devices.push(RawDeviceInfo {
             friendly_name,
@@ -202,6 +219,9 @@ pub struct WindowsBackend {
     /// Cached IBaseFilter per device path to avoid repeated COM enumeration
     /// that causes resource conflicts when a capture graph is active.
     filter_cache: Arc<Mutex<HashMap<String, SendFilter>>>,
+    /// How long device-change notifications must be quiet before the
+    /// hotplug watcher re-enumerates.
+    hotplug_window: Duration,
 }
 
 impl WindowsBackend {
@@ -212,6 +232,7 @@ impl WindowsBackend {
             enumerator: Box::new(DirectShowEnumerator::new()),
             known_devices: Arc::new(Mutex::new(HashMap::new())),
             filter_cache: Arc::new(Mutex::new(HashMap::new())),
+            hotplug_window: debounce::DEFAULT_WINDOW,
         }
     }
 
@@ -222,6 +243,16 @@ impl WindowsBackend {
             enumerator,
             known_devices: Arc::new(Mutex::new(HashMap::new())),
             filter_cache: Arc::new(Mutex::new(HashMap::new())),
+            hotplug_window: debounce::DEFAULT_WINDOW,
+        }
+    }
+
+    /// Gather device-change notifications within `window` of each other
+    /// into one re-enumeration, instead of the default 500ms.
+    pub fn with_hotplug_window(self, window: Duration) -> Self {
+        Self {
+            hotplug_window: window,
+            ..self
         }
     }
 
@@ -235,14 +266,17 @@ impl WindowsBackend {
         let cache_key = if device_path.is_empty() {
             friendly_name.to_string()
         } else {
-            device_path.to_string()
+            normalise_device_path(device_path)
         };
 
         // Check cache first
         {
             let cache = self.filter_cache.lock().unwrap();
             if let Some(entry) = cache.get(&cache_key) {
-                debug!("using cached IBaseFilter for {cache_key}");
+                debug!(
+                    "using cached IBaseFilter for {}",
+                    abbreviate_path(&cache_key)
+                );
                 return Ok(entry.0.clone());
             }
         }
@@ -260,25 +294,48 @@ impl WindowsBackend {
 
     /// Convert raw device info into a `CameraDevice`.
     fn make_device(raw: &RawDeviceInfo) -> CameraDevice {
-        let id = if raw.device_path.is_empty() {
-            DeviceId::new(format!("name:{}", raw.friendly_name))
+        // Enumerators other than DirectShow's may not have normalised it
+        let device_path = normalise_device_path(&raw.device_path);
+        let id = if device_path.is_empty() {
+            DeviceId::from_friendly_name(&raw.friendly_name)
         } else {
-            DeviceId::from_device_path(&raw.device_path)
+            DeviceId::from_device_path(&device_path)
         };
 
         CameraDevice {
             id,
             name: raw.friendly_name.clone(),
-            device_path: raw.device_path.clone(),
+            identity: Some(PhysicalIdentity {
+                serial: usb_serial(&device_path),
+                model: Some(raw.friendly_name.clone()),
+            }),
+            device_path,
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            suppressed_by: None,
         }
     }
+
+    /// Convert a full enumeration into devices, marking IR/depth filters as
+    /// siblings of the colour filter on the same physical camera.
+    fn make_devices(raw_devices: &[RawDeviceInfo]) -> Vec<CameraDevice> {
+        let mut devices: Vec<CameraDevice> = raw_devices.iter().map(Self::make_device).collect();
+        classify_devices(&mut devices);
+        devices
+    }
+}
+
+impl Default for WindowsBackend {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CameraBackend for WindowsBackend {
     fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
         let raw_devices = self.enumerator.enumerate_raw()?;
-        let devices: Vec<CameraDevice> = raw_devices.iter().map(Self::make_device).collect();
+        let devices = Self::make_devices(&raw_devices);
 
         let mut known = self.known_devices.lock().unwrap();
         known.clear();
@@ -293,12 +350,43 @@ impl CameraBackend for WindowsBackend {
     fn watch_hotplug(&self, callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
         let enumerator_known = Arc::clone(&self.known_devices);
         let filter_cache = Arc::clone(&self.filter_cache);
+        let callback: SharedHotplugCallback = Arc::new(Mutex::new(callback));
+        let window = self.hotplug_window;
 
         std::thread::Builder::new()
             .name("camera-hotplug".to_string())
             .spawn(move || {
-                if let Err(e) = run_hotplug_loop(enumerator_known, filter_cache, callback) {
-                    error!("Hotplug loop exited with error: {e}");
+                // Restart the loop if the window procedure panics, keeping
+                // the existing known_devices map so no events are replayed.
+                let outcome = run_with_restart(
+                    RestartPolicy::default(),
+                    |attempt| {
+                        if attempt > 1 {
+                            warn!("restarting hotplug loop (attempt {attempt})");
+                        }
+                        run_hotplug_loop(
+                            Arc::clone(&enumerator_known),
+                            Arc::clone(&filter_cache),
+                            Arc::clone(&callback),
+                            window,
+                        )
+                    },
+                    |_, _| {
+                        enumerator_known.clear_poison();
+                        filter_cache.clear_poison();
+                    },
+                );
+                match outcome {
+                    SupervisedOutcome::Completed(Ok(())) => {}
+                    SupervisedOutcome::Completed(Err(e)) => {
+                        error!("Hotplug loop exited with error: {e}");
+                    }
+                    SupervisedOutcome::GaveUp {
+                        attempts,
+                        last_panic,
+                    } => {
+                        error!("Hotplug loop gave up after {attempts} panics, last: {last_panic}");
+                    }
                 }
             })
             .map_err(|e| CameraError::Hotplug(format!("Failed to spawn hotplug thread: {e}")))?;
@@ -346,6 +434,20 @@ impl CameraBackend for WindowsBackend {
         unsafe { set_device_control_with_filter(&filter, control, value) }
     }
 
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, auto: bool) -> Result<()> {
+        let known = self.known_devices.lock().unwrap();
+        let device = known
+            .values()
+            .find(|d| &d.id == id)
+            .ok_or_else(|| CameraError::DeviceNotFound(id.to_string()))?;
+        let device_path = device.device_path.clone();
+        let friendly_name = device.name.clone();
+        drop(known);
+
+        let filter = self.get_or_create_filter(&device_path, &friendly_name)?;
+        unsafe { set_device_control_auto_with_filter(&filter, control, auto) }
+    }
+
     fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
         let known = self.known_devices.lock().unwrap();
         let device = known
@@ -359,6 +461,29 @@ impl CameraBackend for WindowsBackend {
         let filter = self.get_or_create_filter(&device_path, &friendly_name)?;
         unsafe { query_device_formats_with_filter(&filter) }
     }
+
+    /// DirectShow offers everything but relative PTZ, which would need the
+    /// `*_Relative` camera control properties.
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities::default()
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        let known = self.known_devices.lock().unwrap();
+        if !known.values().any(|d| &d.id == id) {
+            return Err(CameraError::DeviceNotFound(id.to_string()));
+        }
+        Ok(self.capabilities())
+    }
+
+    /// Answer from the devices seen by the last enumeration or hotplug
+    /// event, enumerating again only for a device that isn't among them.
+    fn get_device(&self, id: &DeviceId) -> Result<Option<CameraDevice>> {
+        if let Some(device) = self.known_devices.lock().unwrap().get(id.as_str()) {
+            return Ok(Some(device.clone()));
+        }
+        Ok(self.enumerate_devices()?.into_iter().find(|d| &d.id == id))
+    }
 }
 
 /// Helper: find a device filter by device path, falling back to
@@ -412,7 +537,7 @@ unsafe fn find_device_filter(
 
         // Match by device path first, fall back to friendly name for
         // virtual cameras that may not have a DevicePath property.
-        let matched = if !device_path.is_empty() && path == device_path {
+        let matched = if !device_path.is_empty() && same_device_path(&path, device_path) {
             true
         } else if !friendly_name.is_empty() && path.is_empty() && name == friendly_name {
             info!("matched device by FriendlyName: {name}");
@@ -506,6 +631,17 @@ fn flags_to_control_flags(caps_flags: i32, cur_flags: i32) -> ControlFlags {
     }
 }
 
+/// Whether the flags `GetRange` reports name automatic as the control's
+/// default mode.
+///
+/// The range flags describe the property's default mode rather than only
+/// what it supports: auto alone means the camera powers on in auto and the
+/// numeric default is just where the value starts, while manual (with or
+/// without auto) means the numeric default is what reset should write.
+fn default_mode_is_auto(caps_flags: i32) -> bool {
+    caps_flags & 0x3 == 0x1
+}
+
 /// Query controls from a pre-resolved IBaseFilter via DirectShow.
 ///
 /// # Safety
@@ -548,6 +684,7 @@ unsafe fn query_device_controls_with_filter(
                     max,
                     step,
                     default,
+                    default_auto: default_mode_is_auto(caps_flags),
                     current,
                     caps_flags,
                     cur_flags,
@@ -589,6 +726,7 @@ unsafe fn query_device_controls_with_filter(
                     max,
                     step,
                     default,
+                    default_auto: default_mode_is_auto(caps_flags),
                     current,
                     caps_flags,
                     cur_flags,
@@ -597,9 +735,284 @@ unsafe fn query_device_controls_with_filter(
         }
     }
 
+    controls.extend(query_power_line_frequency(filter));
+    controls.extend(query_tally_light(filter));
+
     Ok(controls)
 }
 
+/// PROPSETID_VIDCAP_VIDEOPROCAMP.
+const PROPSETID_VIDCAP_VIDEOPROCAMP: GUID = GUID::from_u128(0xc6e13360_30ac_11d0_a18c_00a0c9118956);
+const KSPROPERTY_TYPE_GET: u32 = 0x1;
+const KSPROPERTY_TYPE_SET: u32 = 0x2;
+/// KSPROPERTY_VIDEOPROCAMP_FLAGS_MANUAL.
+const KSPROPERTY_VIDEOPROCAMP_FLAGS_MANUAL: u32 = 0x2;
+
+/// KSPROPERTY_VIDEOPROCAMP_S. The leading KSPROPERTY is a union with a
+/// 64-bit member, hence the alignment.
+#[repr(C, align(8))]
+struct KsVideoProcAmpProperty {
+    set: GUID,
+    id: u32,
+    flags: u32,
+    value: i32,
+    value_flags: u32,
+    capabilities: u32,
+}
+
+/// Query the power-line frequency control through the extended video
+/// procamp index, falling back to the KS property for drivers that only
+/// expose it there.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn query_power_line_frequency(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+) -> Option<ControlDescriptor> {
+    if let Ok(video_proc) = filter.cast::<IAMVideoProcAmp>() {
+        let mut min = 0i32;
+        let mut max = 0i32;
+        let mut step = 0i32;
+        let mut default = 0i32;
+        let mut caps_flags = 0i32;
+        if video_proc
+            .GetRange(
+                powerline::PROCAMP_PROPERTY,
+                &mut min,
+                &mut max,
+                &mut step,
+                &mut default,
+                &mut caps_flags,
+            )
+            .is_ok()
+        {
+            let mut current = default;
+            let mut cur_flags = 0i32;
+            let _ = video_proc.Get(powerline::PROCAMP_PROPERTY, &mut current, &mut cur_flags);
+            return Some(powerline::descriptor(min, max, Some(default), current));
+        }
+    }
+
+    let current = ks_power_line_frequency(filter, KSPROPERTY_TYPE_GET, 0)
+        .inspect_err(|e| debug!("power-line frequency not supported on this device: {e}"))
+        .ok()?;
+    // The KS property doesn't report its range; assume UVC's, offering auto
+    // only if the camera is already using it.
+    let max = if current == PowerLineFrequency::Auto.value() {
+        PowerLineFrequency::Auto.value()
+    } else {
+        PowerLineFrequency::Hz60.value()
+    };
+    Some(powerline::descriptor(0, max, None, current))
+}
+
+/// Get or set the power-line frequency through IKsControl, returning the
+/// value the driver reports.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn ks_power_line_frequency(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+    flags: u32,
+    value: i32,
+) -> windows::core::Result<i32> {
+    let control = filter.cast::<IKsControl>()?;
+    let mut property = KsVideoProcAmpProperty {
+        set: PROPSETID_VIDCAP_VIDEOPROCAMP,
+        id: powerline::PROCAMP_PROPERTY as u32,
+        flags,
+        value,
+        value_flags: KSPROPERTY_VIDEOPROCAMP_FLAGS_MANUAL,
+        capabilities: 0,
+    };
+    let size = std::mem::size_of::<KsVideoProcAmpProperty>() as u32;
+    let ptr: *mut KsVideoProcAmpProperty = &mut property;
+    let mut returned = 0u32;
+    control.KsProperty(
+        (ptr as *const KsVideoProcAmpProperty).cast(),
+        size,
+        ptr.cast(),
+        size,
+        &mut returned,
+    )?;
+    Ok(property.value)
+}
+
+/// Set the power-line frequency, trying the extended video procamp index
+/// before the KS property.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn set_power_line_frequency(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+    value: i32,
+) -> Result<()> {
+    let procamp = filter
+        .cast::<IAMVideoProcAmp>()
+        .and_then(|video_proc| video_proc.Set(powerline::PROCAMP_PROPERTY, value, 2));
+    let Err(procamp_err) = procamp else {
+        return Ok(());
+    };
+    debug!("procamp power-line frequency write failed ({procamp_err}), trying KS property");
+
+    ks_power_line_frequency(filter, KSPROPERTY_TYPE_SET, value)
+        .map(|_| ())
+        .map_err(|e| {
+            CameraError::ControlWrite(format!(
+                "Failed to set {} to {value}: {e}",
+                ControlId::PowerLineFrequency.display_name()
+            ))
+        })
+}
+
+/// KSNODETYPE_DEV_SPECIFIC, the node type of extension units.
+const KSNODETYPE_DEV_SPECIFIC: GUID = GUID::from_u128(0x941c7ac0_c559_11d0_8a2b_00a0c9255ac1);
+/// Addresses a property to the topology node in `KsNodeProperty`.
+const KSPROPERTY_TYPE_TOPOLOGY: u32 = 0x1000_0000;
+
+/// KSP_NODE: a KSPROPERTY addressed to a topology node. The control's data
+/// travels in a separate buffer.
+#[repr(C, align(8))]
+struct KsNodeProperty {
+    set: GUID,
+    id: u32,
+    flags: u32,
+    node_id: u32,
+    reserved: u32,
+}
+
+/// A camera's tally LED: the control, the extension unit node answering for
+/// it and the control's current data.
+struct TallyLed {
+    control: IKsControl,
+    node: u32,
+    led: &'static XuLed,
+    data: Vec<u8>,
+}
+
+/// Topology nodes of the filter that may be extension units.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn extension_unit_nodes(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+) -> Vec<u32> {
+    let Ok(topology) = filter.cast::<IKsTopologyInfo>() else {
+        return Vec::new();
+    };
+    let Ok(count) = topology.NumNodes() else {
+        return Vec::new();
+    };
+    (0..count)
+        .filter(|&node| {
+            topology
+                .get_NodeType(node)
+                .is_ok_and(|node_type| node_type == KSNODETYPE_DEV_SPECIFIC)
+        })
+        .collect()
+}
+
+/// Get or set an extension unit control on `node`. Returns the number of
+/// bytes the driver filled in `data`.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn ks_extension_unit(
+    control: &IKsControl,
+    node: u32,
+    led: &XuLed,
+    flags: u32,
+    data: &mut [u8],
+) -> windows::core::Result<usize> {
+    let property = KsNodeProperty {
+        set: GUID::from_u128(led.unit),
+        id: led.selector,
+        flags: flags | KSPROPERTY_TYPE_TOPOLOGY,
+        node_id: node,
+        reserved: 0,
+    };
+    let mut returned = 0u32;
+    control.KsProperty(
+        (&property as *const KsNodeProperty).cast(),
+        std::mem::size_of::<KsNodeProperty>() as u32,
+        data.as_mut_ptr().cast(),
+        data.len() as u32,
+        &mut returned,
+    )?;
+    Ok(returned as usize)
+}
+
+/// Find the camera's tally LED by reading each known LED control from each
+/// extension unit node until one answers.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn find_tally_led(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+) -> Option<TallyLed> {
+    let control = filter.cast::<IKsControl>().ok()?;
+    for node in extension_unit_nodes(filter) {
+        for led in tally::XU_LEDS {
+            let mut data = vec![0u8; led.size];
+            match ks_extension_unit(&control, node, led, KSPROPERTY_TYPE_GET, &mut data) {
+                Ok(returned) if returned > led.mode_offset => {
+                    data.truncate(returned);
+                    return Some(TallyLed {
+                        control,
+                        node,
+                        led,
+                        data,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => debug!("{} LED control not on node {node}: {e}", led.vendor),
+            }
+        }
+    }
+    None
+}
+
+/// Query the tally light through a vendor extension unit, if the camera
+/// has a known one.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn query_tally_light(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+) -> Option<ControlDescriptor> {
+    let found = find_tally_led(filter)?;
+    let current = found.led.value_from(&found.data)?;
+    Some(tally::descriptor(current))
+}
+
+/// Turn the tally light on or off, keeping the rest of the LED control's
+/// data as the camera reported it.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn set_tally_light(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+    value: i32,
+) -> Result<()> {
+    let name = ControlId::TallyLight.display_name();
+    let found = find_tally_led(filter).ok_or_else(|| {
+        CameraError::ControlWrite(format!("Failed to set {name}: no known LED control"))
+    })?;
+    let mut data = found
+        .led
+        .with_mode(&found.data, value != 0)
+        .map_err(|e| CameraError::ControlWrite(format!("Failed to set {name}: {e}")))?;
+    ks_extension_unit(
+        &found.control,
+        found.node,
+        found.led,
+        KSPROPERTY_TYPE_SET,
+        &mut data,
+    )
+    .map(|_| ())
+    .map_err(|e| CameraError::ControlWrite(format!("Failed to set {name} to {value}: {e}")))
+}
+
 /// Raw control data from DirectShow for building a descriptor.
 struct RawControlData {
     control_id: ControlId,
@@ -607,6 +1020,7 @@ struct RawControlData {
     max: i32,
     step: i32,
     default: i32,
+    default_auto: bool,
     current: i32,
     caps_flags: i32,
     cur_flags: i32,
@@ -621,6 +1035,7 @@ fn make_control_descriptor(data: RawControlData) -> ControlDescriptor {
         max,
         step,
         default,
+        default_auto,
         current,
         caps_flags,
         cur_flags,
@@ -640,6 +1055,7 @@ fn make_control_descriptor(data: RawControlData) -> ControlDescriptor {
         max: Some(max),
         step: Some(step),
         default: Some(default),
+        default_auto,
         current,
         flags: flags_to_control_flags(caps_flags, cur_flags),
         options: None,
@@ -658,6 +1074,13 @@ unsafe fn set_device_control_with_filter(
 ) -> Result<()> {
     let name = control.display_name();
 
+    if *control == ControlId::PowerLineFrequency {
+        return set_power_line_frequency(filter, value.value());
+    }
+    if *control == ControlId::TallyLight {
+        return set_tally_light(filter, value.value());
+    }
+
     if let Some(prop_index) = control_id_to_camera_property(control) {
         let cam_ctrl = filter.cast::<IAMCameraControl>().map_err(|e| {
             CameraError::ControlWrite(format!(
@@ -665,6 +1088,9 @@ unsafe fn set_device_control_with_filter(
             ))
         })?;
 
+        let value = snap_to_range(name, value, |min, max, step, default, caps| {
+            cam_ctrl.GetRange(prop_index, min, max, step, default, caps)
+        });
         cam_ctrl
             .Set(prop_index, value.value(), 2) // 2 = manual mode
             .map_err(|e| {
@@ -677,6 +1103,9 @@ unsafe fn set_device_control_with_filter(
             ))
         })?;
 
+        let value = snap_to_range(name, value, |min, max, step, default, caps| {
+            video_proc.GetRange(prop_index, min, max, step, default, caps)
+        });
         video_proc
             .Set(prop_index, value.value(), 2) // 2 = manual mode
             .map_err(|e| {
@@ -691,6 +1120,80 @@ unsafe fn set_device_control_with_filter(
     Ok(())
 }
 
+/// Snap `value` onto the range and step the driver reports, so a value
+/// between steps isn't rejected. Left as is if the range can't be read.
+fn snap_to_range(
+    name: &str,
+    value: ControlValue,
+    get_range: impl FnOnce(
+        &mut i32,
+        &mut i32,
+        &mut i32,
+        &mut i32,
+        &mut i32,
+    ) -> windows::core::Result<()>,
+) -> ControlValue {
+    let (mut min, mut max, mut step, mut default, mut caps) = (0, 0, 0, 0, 0);
+    if get_range(&mut min, &mut max, &mut step, &mut default, &mut caps).is_err() {
+        return value;
+    }
+    let snapped = ControlValue::snapped(value.value(), Some(min), Some(max), Some(step));
+    if snapped.snapped {
+        debug!(
+            "{name}: {} is off the device's grid, writing {}",
+            value.value(),
+            snapped.value.value()
+        );
+    }
+    snapped.value
+}
+
+/// Switch a control between auto (flag 1) and manual (flag 2) mode on a
+/// pre-resolved IBaseFilter, writing back the value it currently holds.
+///
+/// # Safety
+/// Calls COM APIs. Caller must ensure COM is initialised on the current thread.
+unsafe fn set_device_control_auto_with_filter(
+    filter: &windows::Win32::Media::DirectShow::IBaseFilter,
+    control: &ControlId,
+    auto: bool,
+) -> Result<()> {
+    let name = control.display_name();
+    let mode = if auto { 1 } else { 2 };
+    let mut current = 0i32;
+    let mut cur_flags = 0i32;
+
+    if let Some(prop_index) = control_id_to_camera_property(control) {
+        let cam_ctrl = filter.cast::<IAMCameraControl>().map_err(|e| {
+            CameraError::ControlWrite(format!(
+                "Failed to set {name} mode: IAMCameraControl not supported ({e})"
+            ))
+        })?;
+
+        cam_ctrl
+            .Get(prop_index, &mut current, &mut cur_flags)
+            .and_then(|()| cam_ctrl.Set(prop_index, current, mode))
+            .map_err(|e| CameraError::ControlWrite(format!("Failed to set {name} mode: {e}")))?;
+    } else if let Some(prop_index) = control_id_to_procamp_property(control) {
+        let video_proc = filter.cast::<IAMVideoProcAmp>().map_err(|e| {
+            CameraError::ControlWrite(format!(
+                "Failed to set {name} mode: IAMVideoProcAmp not supported ({e})"
+            ))
+        })?;
+
+        video_proc
+            .Get(prop_index, &mut current, &mut cur_flags)
+            .and_then(|()| video_proc.Set(prop_index, current, mode))
+            .map_err(|e| CameraError::ControlWrite(format!("Failed to set {name} mode: {e}")))?;
+    } else {
+        return Err(CameraError::ControlWrite(format!(
+            "Unknown control: {name}"
+        )));
+    }
+
+    Ok(())
+}
+
 /// Query supported formats from a pre-resolved IBaseFilter.
 ///
 /// # Safety
@@ -771,12 +1274,15 @@ unsafe fn query_device_formats_with_filter(
                 };
 
                 let fourcc = fourcc_to_string(mt_ref.subtype);
+                let fps_range = parse_stream_config_caps(&scc).and_then(|c| c.fps_range());
 
                 formats.push(FormatDescriptor {
                     width,
                     height,
                     fps,
                     pixel_format: fourcc,
+                    min_fps: fps_range.map(|(min, _)| min),
+                    max_fps: fps_range.map(|(_, max)| max),
                 });
             }
 
@@ -812,9 +1318,9 @@ const DBT_DEVNODES_CHANGED: usize = 0x0007;
 const DBT_DEVICEARRIVAL: usize = 0x8000;
 const DBT_DEVICEREMOVECOMPLETE: usize = 0x8004;
 
-/// Minimum interval between re-enumerations triggered by DBT_DEVNODES_CHANGED,
-/// to avoid excessive COM calls when the system fires rapid bursts.
-const DEVNODES_DEBOUNCE_MS: u128 = 500;
+/// ID of the timer the hotplug window re-enumerates on once a burst of
+/// device changes goes quiet.
+const HOTPLUG_TIMER_ID: usize = 1;
 
 /// Classify a WM_DEVICECHANGE wparam for logging.
 fn describe_device_change(wparam: usize) -> &'static str {
@@ -838,14 +1344,19 @@ fn should_reenumerate(wparam: usize) -> bool {
     )
 }
 
+/// Hotplug callback shared across supervised restarts of the hotplug loop.
+type SharedHotplugCallback = Arc<Mutex<Box<dyn Fn(HotplugEvent) + Send>>>;
+
 /// Context passed to the hotplug window procedure via GWLP_USERDATA.
 struct HotplugContext {
     known_devices: Arc<Mutex<HashMap<String, CameraDevice>>>,
     filter_cache: Arc<Mutex<HashMap<String, SendFilter>>>,
-    callback: Box<dyn Fn(HotplugEvent) + Send>,
-    /// Timestamp of the last re-enumeration triggered by DBT_DEVNODES_CHANGED,
-    /// used for debouncing rapid-fire broadcasts.
-    last_devnodes_change: Mutex<std::time::Instant>,
+    callback: SharedHotplugCallback,
+    /// Gathers bursts of device changes into single re-enumerations.
+    debouncer: Mutex<Debouncer>,
+    /// Message of a panic caught in the window procedure. The message loop
+    /// exits and re-raises it so the supervisor can restart the loop.
+    panic_message: Mutex<Option<String>>,
 }
 
 /// Diff known devices against a fresh enumeration, returning events for
@@ -873,25 +1384,64 @@ fn diff_devices(
     events
 }
 
-/// Re-enumerate devices and fire hotplug events for any changes.
+/// Whether `current` has a device `known` doesn't that came without a
+/// device path. A camera whose driver is still initialising can enumerate
+/// like that, then reappear with its path (and so another ID) moments later.
+fn awaiting_driver(
+    known: &HashMap<String, CameraDevice>,
+    current: &HashMap<String, CameraDevice>,
+) -> bool {
+    current
+        .iter()
+        .any(|(id, device)| device.device_path.is_empty() && !known.contains_key(id))
+}
+
+/// Arm the hotplug timer to fire at `due`, replacing any earlier one.
 ///
-/// When triggered by `DBT_DEVNODES_CHANGED` the call is debounced so that
-/// rapid-fire broadcasts (common during USB enumeration) don't cause a
-/// storm of COM enumerations.
-fn handle_device_change(ctx: &HotplugContext, wparam: usize) {
-    if wparam == DBT_DEVNODES_CHANGED {
-        let mut last = ctx.last_devnodes_change.lock().unwrap();
-        let elapsed = last.elapsed().as_millis();
-        if elapsed < DEVNODES_DEBOUNCE_MS {
-            debug!(
-                "debounced DBT_DEVNODES_CHANGED ({}ms since last re-enum)",
-                elapsed
-            );
-            return;
-        }
-        *last = std::time::Instant::now();
+/// # Safety
+/// Calls Win32 `SetTimer` on the hotplug window.
+unsafe fn arm_hotplug_timer(hwnd: windows::Win32::Foundation::HWND, due: Instant) {
+    use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+
+    let delay = due.saturating_duration_since(Instant::now());
+    // USER_TIMER_MINIMUM is 10ms; shorter delays are rounded up anyway
+    let delay_ms = delay.as_millis().clamp(10, u128::from(u32::MAX)) as u32;
+    SetTimer(Some(hwnd), HOTPLUG_TIMER_ID, delay_ms, None);
+}
+
+/// Note a device change, putting off the re-enumeration until the burst it
+/// belongs to goes quiet.
+///
+/// # Safety
+/// Arms a Win32 timer on the hotplug window.
+unsafe fn handle_device_change(ctx: &HotplugContext, hwnd: windows::Win32::Foundation::HWND) {
+    let due = ctx.debouncer.lock().unwrap().notify(Instant::now());
+    arm_hotplug_timer(hwnd, due);
+}
+
+/// Re-enumerate if a burst of device changes is due, retrying once shortly
+/// afterwards if a new device's driver isn't ready yet.
+///
+/// # Safety
+/// Calls Win32 `KillTimer` and `SetTimer` on the hotplug window.
+unsafe fn handle_hotplug_timer(ctx: &HotplugContext, hwnd: windows::Win32::Foundation::HWND) {
+    use windows::Win32::UI::WindowsAndMessaging::KillTimer;
+
+    let _ = KillTimer(Some(hwnd), HOTPLUG_TIMER_ID);
+    let pass = ctx.debouncer.lock().unwrap().take_due(Instant::now());
+    if let Some(pass) = pass {
+        reenumerate(ctx, pass);
+    }
+    // Still pending if the timer fired early, a retry was asked for or a
+    // notification arrived meanwhile
+    let due = ctx.debouncer.lock().unwrap().due();
+    if let Some(due) = due {
+        arm_hotplug_timer(hwnd, due);
     }
+}
 
+/// Re-enumerate devices and fire hotplug events for any changes.
+fn reenumerate(ctx: &HotplugContext, pass: Pass) {
     let current_raw = match unsafe { enumerate_directshow_devices() } {
         Ok(devs) => devs,
         Err(e) => {
@@ -900,15 +1450,23 @@ fn handle_device_change(ctx: &HotplugContext, wparam: usize) {
         }
     };
 
-    let current: HashMap<String, CameraDevice> = current_raw
-        .iter()
-        .map(|raw| {
-            let dev = WindowsBackend::make_device(raw);
-            (dev.id.as_str().to_string(), dev)
-        })
+    let current: HashMap<String, CameraDevice> = WindowsBackend::make_devices(&current_raw)
+        .into_iter()
+        .map(|dev| (dev.id.as_str().to_string(), dev))
         .collect();
 
     let mut known = ctx.known_devices.lock().unwrap();
+    if awaiting_driver(&known, &current) {
+        let retry =
+            ctx.debouncer
+                .lock()
+                .unwrap()
+                .retry(pass, Instant::now(), debounce::RETRY_DELAY);
+        if retry.is_some() {
+            debug!("new device without a device path; enumerating again shortly");
+            return;
+        }
+    }
     let events = diff_devices(&mut known, current);
     drop(known);
 
@@ -925,9 +1483,10 @@ fn handle_device_change(ctx: &HotplugContext, wparam: usize) {
         }
     }
 
+    let callback = ctx.callback.lock().unwrap_or_else(|e| e.into_inner());
     for event in events {
         info!("Hotplug event: {event:?}");
-        (ctx.callback)(event);
+        callback(event);
     }
 }
 
@@ -978,7 +1537,8 @@ unsafe fn register_device_class(hwnd: windows::Win32::Foundation::HWND, guid: GU
     Ok(())
 }
 
-/// Run the hot-plug detection message loop.
+/// Run the hot-plug detection message loop, re-enumerating once each burst
+/// of device changes has been quiet for `window`.
 ///
 /// Creates a hidden (zero-size, off-screen) window instead of a message-only
 /// window (`HWND_MESSAGE`) so that it can receive both **targeted** device
@@ -986,15 +1546,19 @@ unsafe fn register_device_class(hwnd: windows::Win32::Foundation::HWND, guid: GU
 /// **broadcast** messages (`DBT_DEVNODES_CHANGED`). Message-only windows
 /// are excluded from broadcast delivery, which caused newly connected USB
 /// cameras to go undetected.
+///
+/// If the window procedure panics, the loop tears down its window and
+/// re-raises the panic on this thread for the supervisor to handle.
 fn run_hotplug_loop(
     known_devices: Arc<Mutex<HashMap<String, CameraDevice>>>,
     filter_cache: Arc<Mutex<HashMap<String, SendFilter>>>,
-    callback: Box<dyn Fn(HotplugEvent) + Send>,
+    callback: SharedHotplugCallback,
+    window: Duration,
 ) -> Result<()> {
     use windows::Win32::UI::WindowsAndMessaging::{
-        CreateWindowExW, DispatchMessageW, GetMessageW, RegisterClassW, SetWindowLongPtrW,
-        TranslateMessage, CS_HREDRAW, CS_VREDRAW, GWLP_USERDATA, MSG, WINDOW_EX_STYLE,
-        WINDOW_STYLE, WNDCLASSW,
+        CreateWindowExW, DestroyWindow, DispatchMessageW, GetMessageW, RegisterClassW,
+        SetWindowLongPtrW, TranslateMessage, CS_HREDRAW, CS_VREDRAW, GWLP_USERDATA, MSG,
+        WINDOW_EX_STYLE, WINDOW_STYLE, WNDCLASSW,
     };
 
     unsafe {
@@ -1029,17 +1593,15 @@ fn run_hotplug_loop(
         .map_err(|e| CameraError::Hotplug(format!("CreateWindowExW failed: {e}")))?;
 
         // Store context on the HWND so wnd_proc can access it.
-        // Initialise last_devnodes_change far enough in the past so the
-        // first DBT_DEVNODES_CHANGED is never debounced.
         let ctx = Box::new(HotplugContext {
             known_devices,
             filter_cache,
             callback,
-            last_devnodes_change: Mutex::new(
-                std::time::Instant::now() - std::time::Duration::from_secs(10),
-            ),
+            debouncer: Mutex::new(Debouncer::new(window)),
+            panic_message: Mutex::new(None),
         });
-        SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(ctx) as isize);
+        let ctx_ptr = Box::into_raw(ctx);
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, ctx_ptr as isize);
 
         // Register for targeted notifications on both camera categories
         // so we catch devices regardless of which GUID class they expose.
@@ -1056,6 +1618,16 @@ fn run_hotplug_loop(
             DispatchMessageW(&msg);
         }
 
+        // Detach and destroy the window before freeing its context so a
+        // restarted loop on this thread can't dispatch into stale state.
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+        let _ = DestroyWindow(hwnd);
+        let ctx = Box::from_raw(ctx_ptr);
+
+        if let Some(message) = ctx.panic_message.lock().unwrap().take() {
+            std::panic::resume_unwind(Box::new(message));
+        }
+
         Ok(())
     }
 }
@@ -1068,23 +1640,39 @@ unsafe extern "system" fn hotplug_wnd_proc(
     lparam: windows::Win32::Foundation::LPARAM,
 ) -> windows::Win32::Foundation::LRESULT {
     use windows::Win32::UI::WindowsAndMessaging::{
-        DefWindowProcW, GetWindowLongPtrW, GWLP_USERDATA, WM_DEVICECHANGE,
+        DefWindowProcW, GetWindowLongPtrW, PostQuitMessage, GWLP_USERDATA, WM_DEVICECHANGE,
+        WM_TIMER,
     };
 
-    if msg == WM_DEVICECHANGE {
-        let wparam_val = wparam.0;
-        let should_handle = should_reenumerate(wparam_val);
-
-        if should_handle {
-            info!(
-                "WM_DEVICECHANGE: {} (0x{:04X})",
-                describe_device_change(wparam_val),
-                wparam_val
-            );
-            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
-            if ptr != 0 {
-                let ctx = &*(ptr as *const HotplugContext);
-                handle_device_change(ctx, wparam_val);
+    let wparam_val = wparam.0;
+    let timer = msg == WM_TIMER && wparam_val == HOTPLUG_TIMER_ID;
+    let device_change = msg == WM_DEVICECHANGE && should_reenumerate(wparam_val);
+    if device_change {
+        info!(
+            "WM_DEVICECHANGE: {} (0x{:04X})",
+            describe_device_change(wparam_val),
+            wparam_val
+        );
+    }
+
+    if timer || device_change {
+        let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
+        if ptr != 0 {
+            let ctx = &*(ptr as *const HotplugContext);
+            // Panics must not unwind across the FFI boundary. Stash the
+            // message and quit the loop so it can be re-raised safely.
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                if timer {
+                    handle_hotplug_timer(ctx, hwnd);
+                } else {
+                    handle_device_change(ctx, hwnd);
+                }
+            }));
+            if let Err(payload) = result {
+                let message = panic_message(payload.as_ref());
+                error!("hotplug window procedure panicked: {message}");
+                *ctx.panic_message.lock().unwrap() = Some(message);
+                PostQuitMessage(0);
             }
         }
     }
@@ -1129,6 +1717,64 @@ mod tests {
         assert!(devices.is_empty());
     }
 
+    #[test]
+    fn get_device_answers_from_known_devices() {
+        let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator {
+            devices: vec![RawDeviceInfo {
+                friendly_name: "Test Camera".to_string(),
+                device_path: r"\\?\usb#vid_046d&pid_085e&mi_00#serial123#{guid}".to_string(),
+            }],
+        }));
+        let id = backend.enumerate_devices().unwrap()[0].id.clone();
+
+        // Renaming the cached device shows the lookup didn't enumerate again
+        backend
+            .known_devices
+            .lock()
+            .unwrap()
+            .get_mut(id.as_str())
+            .unwrap()
+            .name = "Cached Camera".to_string();
+
+        let device = backend.get_device(&id).unwrap().unwrap();
+        assert_eq!(device.name, "Cached Camera");
+    }
+
+    #[test]
+    fn get_device_enumerates_when_not_known() {
+        let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator {
+            devices: vec![RawDeviceInfo {
+                friendly_name: "Test Camera".to_string(),
+                device_path: r"\\?\usb#vid_046d&pid_085e&mi_00#serial123#{guid}".to_string(),
+            }],
+        }));
+        let id = WindowsBackend::make_devices(&backend.enumerator.enumerate_raw().unwrap())[0]
+            .id
+            .clone();
+        assert!(backend.known_devices.lock().unwrap().is_empty());
+
+        let device = backend.get_device(&id).unwrap().unwrap();
+        assert_eq!(device.name, "Test Camera");
+        assert!(backend
+            .known_devices
+            .lock()
+            .unwrap()
+            .contains_key(id.as_str()));
+    }
+
+    #[test]
+    fn get_device_returns_none_for_unknown_device() {
+        let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator {
+            devices: vec![RawDeviceInfo {
+                friendly_name: "Test Camera".to_string(),
+                device_path: r"\\?\usb#vid_046d&pid_085e&mi_00#serial123#{guid}".to_string(),
+            }],
+        }));
+
+        let device = backend.get_device(&DeviceId::new("missing")).unwrap();
+        assert!(device.is_none());
+    }
+
     #[test]
     fn enumerate_devices_have_non_empty_names() {
         let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator {
@@ -1167,6 +1813,41 @@ mod tests {
         );
     }
 
+    /// A 400-character device path like those seen behind Thunderbolt docks.
+    fn dock_path(port: u32) -> String {
+        let path = format!(
+            r"\\?\usb#vid_046d&pid_085e&mi_00#9&{}&{port:04}#{{65e8773d-8f56-11d0-a3b9-00a0c9223196}}\global",
+            "2f1b3c4d&0&".repeat(25)
+        );
+        format!("{path}{}", "x".repeat(400 - path.len()))
+    }
+
+    #[test]
+    fn enumerate_devices_with_long_dock_paths() {
+        let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator {
+            devices: vec![
+                RawDeviceInfo {
+                    friendly_name: "Logitech BRIO".to_string(),
+                    device_path: dock_path(1),
+                },
+                RawDeviceInfo {
+                    friendly_name: "Logitech BRIO".to_string(),
+                    device_path: dock_path(2),
+                },
+            ],
+        }));
+
+        let devices = backend.enumerate_devices().unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_ne!(devices[0].id, devices[1].id);
+        for (device, port) in devices.iter().zip([1, 2]) {
+            // The path is kept whole for DirectShow; only the ID is short
+            assert_eq!(device.device_path, dock_path(port));
+            assert!(device.id.as_str().len() < 32, "got: {}", device.id);
+        }
+        assert_eq!(backend.known_devices.lock().unwrap().len(), 2);
+    }
+
     #[test]
     fn get_controls_errors_for_unknown_device() {
         let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator { devices: vec![] }));
@@ -1196,6 +1877,48 @@ mod tests {
         assert!(device.id.as_str().starts_with("046d:085e:"));
     }
 
+    #[test]
+    fn make_device_normalises_path_variants() {
+        let variants = [
+            r"\\?\usb#vid_046d&pid_085e&mi_00#6&2d5d6f8f&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global",
+            r"\\?\USB#VID_046D&PID_085E&MI_00#6&2D5D6F8F&0&0000#{65E8773D-8F56-11D0-A3B9-00A0C9223196}",
+        ];
+        let devices: Vec<CameraDevice> = variants
+            .iter()
+            .map(|path| {
+                WindowsBackend::make_device(&RawDeviceInfo {
+                    friendly_name: "Logitech BRIO".to_string(),
+                    device_path: path.to_string(),
+                })
+            })
+            .collect();
+        assert_eq!(devices[0].id, devices[1].id);
+        assert_eq!(devices[0].device_path, devices[1].device_path);
+        assert_eq!(devices[0].device_path, normalise_device_path(variants[1]));
+    }
+
+    #[test]
+    fn replug_with_a_different_path_spelling_fires_no_events() {
+        let raw = |path: &str| RawDeviceInfo {
+            friendly_name: "Logitech BRIO".to_string(),
+            device_path: path.to_string(),
+        };
+        let by_id = |raw: &[RawDeviceInfo]| -> HashMap<String, CameraDevice> {
+            WindowsBackend::make_devices(raw)
+                .into_iter()
+                .map(|dev| (dev.id.as_str().to_string(), dev))
+                .collect()
+        };
+        let mut known = by_id(&[raw(
+            r"\\?\usb#vid_046d&pid_085e&mi_00#6&2d5d6f8f&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global",
+        )]);
+        let current = by_id(&[raw(
+            r"\\?\USB#VID_046D&PID_085E&MI_00#6&2D5D6F8F&0&0000#{65E8773D-8F56-11D0-A3B9-00A0C9223196}",
+        )]);
+
+        assert!(diff_devices(&mut known, current).is_empty());
+    }
+
     #[test]
     fn make_device_fallback_for_empty_path() {
         let raw = RawDeviceInfo {
@@ -1211,6 +1934,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn new_devices_without_a_path_are_awaited() {
+        let by_id = |raw: &[(&str, &str)]| -> HashMap<String, CameraDevice> {
+            let raw: Vec<_> = raw
+                .iter()
+                .map(|&(name, path)| RawDeviceInfo {
+                    friendly_name: name.to_string(),
+                    device_path: path.to_string(),
+                })
+                .collect();
+            WindowsBackend::make_devices(&raw)
+                .into_iter()
+                .map(|dev| (dev.id.as_str().to_string(), dev))
+                .collect()
+        };
+        let brio = (
+            "Logitech BRIO",
+            r"\\?\usb#vid_046d&pid_085e&mi_00#6&2d5d6f8f&0&0000",
+        );
+        let known = by_id(&[brio]);
+
+        // Its driver is still initialising
+        assert!(awaiting_driver(&known, &by_id(&[brio, ("USB Camera", "")])));
+        // New and ready
+        assert!(!awaiting_driver(
+            &known,
+            &by_id(&[brio, ("USB Camera", r"\\?\usb#vid_0c45&pid_6366")])
+        ));
+        // A virtual camera already known never has a path
+        let obs = ("OBS Virtual Camera", "");
+        assert!(!awaiting_driver(&by_id(&[obs]), &by_id(&[obs])));
+    }
+
+    #[test]
+    fn backend_hotplug_window_defaults_and_can_be_set() {
+        let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator { devices: vec![] }));
+        assert_eq!(backend.hotplug_window, debounce::DEFAULT_WINDOW);
+        let backend = backend.with_hotplug_window(Duration::from_millis(250));
+        assert_eq!(backend.hotplug_window, Duration::from_millis(250));
+    }
+
     #[test]
     fn enumerate_stores_virtual_cameras_by_id() {
         let backend = WindowsBackend::with_enumerator(Box::new(MockEnumerator {
@@ -1273,6 +2037,7 @@ mod tests {
             max: 255,
             step: 1,
             default: 128,
+            default_auto: false,
             current: 100,
             caps_flags: 0x03,
             cur_flags: 0x00,
@@ -1300,6 +2065,7 @@ mod tests {
             max: 1,
             step: 1,
             default: 1,
+            default_auto: false,
             current: 1,
             caps_flags: 0x00,
             cur_flags: 0x00,
@@ -1308,6 +2074,24 @@ mod tests {
         assert_eq!(desc.control_type, ControlType::Toggle);
     }
 
+    #[test]
+    fn make_control_descriptor_carries_default_auto() {
+        let desc = make_control_descriptor(RawControlData {
+            control_id: ControlId::WhiteBalance,
+            min: 2800,
+            max: 6500,
+            step: 10,
+            default: 4600,
+            default_auto: true,
+            current: 4600,
+            caps_flags: 0x01,
+            cur_flags: 0x01,
+        });
+
+        assert!(desc.default_auto);
+        assert!(desc.resets_to_auto());
+    }
+
     #[test]
     fn make_control_descriptor_auto_enabled() {
         let desc = make_control_descriptor(RawControlData {
@@ -1316,6 +2100,7 @@ mod tests {
             max: 1,
             step: 1,
             default: -5,
+            default_auto: false,
             current: -5,
             caps_flags: 0x03,
             cur_flags: 0x01,
@@ -1337,6 +2122,10 @@ mod tests {
             name: "New Camera".to_string(),
             device_path: "/dev/video0".to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         };
 
         let mut current = HashMap::new();
@@ -1554,6 +2343,22 @@ mod tests {
         assert_eq!(control_id_to_procamp_property(&ControlId::Exposure), None);
     }
 
+    #[test]
+    fn power_line_frequency_is_outside_the_standard_procamp_range() {
+        // Written through its own path at the extended index instead
+        assert_eq!(
+            control_id_to_procamp_property(&ControlId::PowerLineFrequency),
+            None
+        );
+        assert_eq!(powerline::PROCAMP_PROPERTY, 13);
+    }
+
+    #[test]
+    fn ks_video_procamp_property_matches_the_kernel_layout() {
+        // sizeof(KSPROPERTY_VIDEOPROCAMP_S) on both x86 and x64
+        assert_eq!(std::mem::size_of::<KsVideoProcAmpProperty>(), 40);
+    }
+
     // --- flags_to_control_flags tests ---
 
     #[test]
@@ -1586,6 +2391,14 @@ mod tests {
         assert!(!flags.is_read_only);
     }
 
+    #[test]
+    fn default_mode_is_auto_only_when_auto_is_named_alone() {
+        assert!(default_mode_is_auto(0x01));
+        assert!(!default_mode_is_auto(0x03));
+        assert!(!default_mode_is_auto(0x02));
+        assert!(!default_mode_is_auto(0x00));
+    }
+
     #[test]
     fn fourcc_to_string_converts_known_formats() {
         let mjpg_guid = GUID::from_values(
@@ -1613,6 +2426,10 @@ mod tests {
             name: name.to_string(),
             device_path: path.to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         }
     }
 
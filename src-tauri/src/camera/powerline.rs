@@ -0,0 +1,302 @@
+// Power-line frequency (anti-flicker) control.
+//
+// UVC cameras expose it as a video procamp property DirectShow has no name
+// for: index 13 on IAMVideoProcAmp where the driver accepts it, otherwise
+// KSPROPERTY_VIDEOPROCAMP_POWERLINE_FREQUENCY through IKsControl. Values
+// follow UVC: 0 disabled, 1 50 Hz, 2 60 Hz, 3 auto.
+//
+// Cameras often ship set to 60 Hz, which flickers under 50 Hz mains, so the
+// UI is offered a setting inferred from the OS region.
+
+use serde::Serialize;
+
+use crate::camera::types::{
+    ControlDescriptor, ControlFlags, ControlId, ControlOption, ControlType,
+};
+use crate::i18n::{self, Domain};
+
+/// Video procamp property index of the power-line frequency control.
+pub const PROCAMP_PROPERTY: i32 = 13;
+
+/// A power-line frequency setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerLineFrequency {
+    Disabled,
+    Hz50,
+    Hz60,
+    Auto,
+}
+
+impl PowerLineFrequency {
+    pub const ALL: [Self; 4] = [Self::Disabled, Self::Hz50, Self::Hz60, Self::Auto];
+
+    /// Control value, as UVC numbers it.
+    pub fn value(self) -> i32 {
+        match self {
+            Self::Disabled => 0,
+            Self::Hz50 => 1,
+            Self::Hz60 => 2,
+            Self::Auto => 3,
+        }
+    }
+
+    pub fn from_value(value: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.value() == value)
+    }
+
+    /// Option label, in the active locale.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Disabled => i18n::text(Domain::Value, "power_line.disabled"),
+            Self::Hz50 => "50 Hz",
+            Self::Hz60 => "60 Hz",
+            Self::Auto => i18n::text(Domain::Value, "power_line.auto"),
+        }
+    }
+}
+
+/// Select options for the settings in `min..=max`.
+pub fn options(min: i32, max: i32) -> Vec<ControlOption> {
+    PowerLineFrequency::ALL
+        .into_iter()
+        .filter(|f| (min..=max).contains(&f.value()))
+        .map(|f| ControlOption {
+            value: f.value(),
+            label: f.label().to_string(),
+        })
+        .collect()
+}
+
+/// Descriptor for a power-line frequency control accepting `min..=max`.
+pub fn descriptor(min: i32, max: i32, default: Option<i32>, current: i32) -> ControlDescriptor {
+    let control = ControlId::PowerLineFrequency;
+    ControlDescriptor {
+        id: control.as_id_str().to_string(),
+        name: control.display_name().to_string(),
+        control_type: ControlType::Select,
+        group: control.group().to_string(),
+        min: Some(min),
+        max: Some(max),
+        step: Some(1),
+        default,
+        default_auto: false,
+        current,
+        flags: ControlFlags {
+            supports_auto: false,
+            is_auto_enabled: false,
+            is_read_only: false,
+        },
+        options: Some(options(min, max)),
+        supported: true,
+    }
+}
+
+/// Suggested power-line frequency for this machine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerLineSuggestion {
+    /// Control value to suggest.
+    pub value: i32,
+    pub label: String,
+    /// ISO 3166 region the suggestion was inferred from, if one was found.
+    pub region: Option<String>,
+}
+
+/// Regions whose mains run at 60 Hz. Japan, which runs both, is absent and
+/// handled separately.
+const REGIONS_60HZ: &[&str] = &[
+    "AG", "AI", "AS", "AW", "BM", "BR", "BS", "BZ", "CA", "CO", "CR", "CU", "DO", "EC", "FM", "GT",
+    "GU", "GY", "HN", "HT", "KN", "KR", "KY", "LR", "MH", "MP", "MS", "MX", "NI", "PA", "PE", "PH",
+    "PR", "PW", "SA", "SR", "SV", "TC", "TT", "TW", "UM", "US", "VE", "VG", "VI",
+];
+
+/// Regions split between 50 and 60 Hz.
+const REGIONS_MIXED: &[&str] = &["JP"];
+
+/// Mains frequency for an ISO 3166 alpha-2 region, or `None` where the
+/// region runs both.
+pub fn frequency_for_region(region: &str) -> Option<PowerLineFrequency> {
+    let region = region.to_ascii_uppercase();
+    if REGIONS_MIXED.contains(&region.as_str()) {
+        None
+    } else if REGIONS_60HZ.contains(&region.as_str()) {
+        Some(PowerLineFrequency::Hz60)
+    } else {
+        Some(PowerLineFrequency::Hz50)
+    }
+}
+
+/// ISO 3166 alpha-2 region from a locale name such as `en-GB`,
+/// `en_US.UTF-8` or `zh-Hant-TW`. `None` for locales without one, like `C`
+/// or `es-419`.
+pub fn region_from_locale(locale: &str) -> Option<String> {
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    name.split(['-', '_'])
+        .skip(1)
+        .find(|tag| tag.len() == 2 && tag.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|tag| tag.to_ascii_uppercase())
+}
+
+/// Suggest a setting for `locale`: the region's mains frequency, or auto
+/// when the region is unknown or runs both.
+pub fn suggest_for_locale(locale: Option<&str>) -> PowerLineSuggestion {
+    let region = locale.and_then(region_from_locale);
+    let frequency = region
+        .as_deref()
+        .and_then(frequency_for_region)
+        .unwrap_or(PowerLineFrequency::Auto);
+    PowerLineSuggestion {
+        value: frequency.value(),
+        label: frequency.label().to_string(),
+        region,
+    }
+}
+
+/// Suggest a setting from the OS locale.
+pub fn suggest_powerline_frequency() -> PowerLineSuggestion {
+    suggest_for_locale(os_locale().as_deref())
+}
+
+#[cfg(target_os = "windows")]
+fn os_locale() -> Option<String> {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+    // The length includes the terminating null
+    let len = usize::try_from(len).ok()?.checked_sub(1)?;
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn os_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_follow_uvc() {
+        for (frequency, value) in PowerLineFrequency::ALL.into_iter().zip(0..) {
+            assert_eq!(frequency.value(), value);
+            assert_eq!(PowerLineFrequency::from_value(value), Some(frequency));
+        }
+        assert_eq!(PowerLineFrequency::from_value(4), None);
+        assert_eq!(PowerLineFrequency::from_value(-1), None);
+    }
+
+    #[test]
+    fn descriptor_is_a_select_limited_to_the_range() {
+        let desc = descriptor(0, 2, Some(2), 1);
+        assert_eq!(desc.id, "power_line_frequency");
+        assert_eq!(desc.name, "Power Line Frequency");
+        assert_eq!(desc.control_type, ControlType::Select);
+        assert_eq!(desc.group, "exposure");
+        assert_eq!(desc.default, Some(2));
+        assert_eq!(desc.current, 1);
+        let labels: Vec<_> = desc
+            .options
+            .unwrap()
+            .into_iter()
+            .map(|o| (o.value, o.label))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                (0, "Disabled".to_string()),
+                (1, "50 Hz".to_string()),
+                (2, "60 Hz".to_string()),
+            ]
+        );
+
+        let with_auto = descriptor(0, 3, None, 3);
+        assert_eq!(with_auto.options.unwrap().len(), 4);
+        // Some drivers can't turn it off
+        let no_disable = descriptor(1, 2, Some(1), 1);
+        assert_eq!(no_disable.options.unwrap()[0].label, "50 Hz");
+    }
+
+    #[test]
+    fn descriptor_serialises_for_the_frontend() {
+        let json = serde_json::to_value(descriptor(0, 3, Some(2), 1)).unwrap();
+        assert_eq!(json["id"], "power_line_frequency");
+        assert_eq!(json["controlType"], "select");
+        assert_eq!(json["default"], 2);
+        assert_eq!(json["options"][3]["value"], 3);
+        assert_eq!(json["options"][3]["label"], "Auto");
+    }
+
+    #[test]
+    fn region_from_locale_handles_common_forms() {
+        let cases = [
+            ("en-GB", Some("GB")),
+            ("en_US.UTF-8", Some("US")),
+            ("de_DE@euro", Some("DE")),
+            ("zh-Hant-TW", Some("TW")),
+            ("pt-br", Some("BR")),
+            ("es-419", None),
+            ("fr", None),
+            ("C", None),
+            ("C.UTF-8", None),
+            ("", None),
+        ];
+        for (locale, expected) in cases {
+            assert_eq!(
+                region_from_locale(locale).as_deref(),
+                expected,
+                "locale {locale:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn frequency_for_region_maps_mains() {
+        let cases = [
+            ("US", Some(PowerLineFrequency::Hz60)),
+            ("CA", Some(PowerLineFrequency::Hz60)),
+            ("BR", Some(PowerLineFrequency::Hz60)),
+            ("KR", Some(PowerLineFrequency::Hz60)),
+            ("tw", Some(PowerLineFrequency::Hz60)),
+            ("GB", Some(PowerLineFrequency::Hz50)),
+            ("DE", Some(PowerLineFrequency::Hz50)),
+            ("AU", Some(PowerLineFrequency::Hz50)),
+            ("AR", Some(PowerLineFrequency::Hz50)),
+            ("CN", Some(PowerLineFrequency::Hz50)),
+            ("JP", None),
+        ];
+        for (region, expected) in cases {
+            assert_eq!(frequency_for_region(region), expected, "region {region}");
+        }
+    }
+
+    #[test]
+    fn suggestion_falls_back_to_auto() {
+        let uk = suggest_for_locale(Some("en-GB"));
+        assert_eq!((uk.value, uk.label.as_str()), (1, "50 Hz"));
+        assert_eq!(uk.region.as_deref(), Some("GB"));
+
+        let us = suggest_for_locale(Some("en_US.UTF-8"));
+        assert_eq!(us.value, 2);
+
+        let japan = suggest_for_locale(Some("ja-JP"));
+        assert_eq!((japan.value, japan.region.as_deref()), (3, Some("JP")));
+
+        let unknown = suggest_for_locale(None);
+        assert_eq!((unknown.value, unknown.region), (3, None));
+    }
+
+    #[test]
+    fn suggestion_serialises_to_camel_case() {
+        let json = serde_json::to_value(suggest_for_locale(Some("nl-NL"))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "value": 1, "label": "50 Hz", "region": "NL" })
+        );
+    }
+}
@@ -0,0 +1,665 @@
+//! Per-device operation queue.
+//!
+//! Operations that need a device to themselves — a format change, starting,
+//! stopping or restarting its preview, applying a preset, a bracket capture,
+//! a combined zoom — must not interleave: a preset written while the session
+//! restarts lands on a graph that is being torn down. `DeviceQueue` runs
+//! those one at a time per device, in submission order, on a worker task per
+//! device, while different devices run in parallel. Cheap reads (frames,
+//! control lists) bypass the queue and run straight away.
+//!
+//! Each operation gets an `OpContext` to report progress on and to check for
+//! cancellation. Cancelling a queued operation drops it before it starts;
+//! cancelling a running one is cooperative.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot, watch, Notify};
+
+use crate::camera::types::DeviceId;
+use crate::error::{code, AppError};
+
+/// What an operation does to its device, which decides whether it queues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpKind {
+    FormatChange,
+    PreviewStart,
+    PreviewStop,
+    PreviewRestart,
+    PresetApply,
+    BracketCapture,
+    ZoomChange,
+    FrameRead,
+    ControlRead,
+}
+
+impl OpKind {
+    /// Whether the operation needs the device to itself. Exclusive
+    /// operations are serialised per device; the rest bypass the queue.
+    pub fn is_exclusive(self) -> bool {
+        match self {
+            Self::FormatChange
+            | Self::PreviewStart
+            | Self::PreviewStop
+            | Self::PreviewRestart
+            | Self::PresetApply
+            | Self::BracketCapture
+            | Self::ZoomChange => true,
+            Self::FrameRead | Self::ControlRead => false,
+        }
+    }
+}
+
+/// Why an operation produced no result.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OpError {
+    /// Cancelled before it started, or it stopped early after a cancel.
+    #[error("Operation cancelled")]
+    Cancelled,
+    /// The operation itself failed.
+    #[error("{}", .0.message)]
+    Failed(AppError),
+}
+
+impl From<OpError> for AppError {
+    fn from(e: OpError) -> Self {
+        match e {
+            OpError::Cancelled => AppError::new(code::CANCELLED, OpError::Cancelled.to_string()),
+            OpError::Failed(e) => e,
+        }
+    }
+}
+
+/// Progress of an operation, as passed to the queue's observer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpProgress {
+    pub op_id: u64,
+    pub device_id: String,
+    pub kind: OpKind,
+    /// 0.0 when the operation starts, 1.0 once it has finished.
+    pub progress: f32,
+}
+
+/// Called with every progress update, e.g. to forward them to the frontend.
+pub type ProgressObserver = Arc<dyn Fn(&OpProgress) + Send + Sync>;
+
+/// Cancellation flag shared by an operation, its handle and the queue.
+#[derive(Default)]
+struct CancelFlag {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelFlag {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Handed to a running operation.
+pub struct OpContext {
+    id: u64,
+    device_id: DeviceId,
+    kind: OpKind,
+    cancel: Arc<CancelFlag>,
+    progress: watch::Sender<f32>,
+    observer: Option<ProgressObserver>,
+}
+
+impl OpContext {
+    /// ID the operation can be cancelled by.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Report how far along the operation is, from 0.0 to 1.0.
+    pub fn report(&self, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        self.progress.send_replace(progress);
+        if let Some(observer) = &self.observer {
+            observer(&OpProgress {
+                op_id: self.id,
+                device_id: self.device_id.as_str().to_string(),
+                kind: self.kind,
+                progress,
+            });
+        }
+    }
+
+    /// Whether the operation has been asked to stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Resolves once the operation has been asked to stop.
+    pub async fn cancelled(&self) {
+        loop {
+            // Registered before the check so a cancel in between still wakes it
+            let notified = self.cancel.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// A submitted operation.
+pub struct OpHandle<T> {
+    id: u64,
+    cancel: Arc<CancelFlag>,
+    progress: watch::Receiver<f32>,
+    result: oneshot::Receiver<Result<T, OpError>>,
+}
+
+impl<T> OpHandle<T> {
+    /// ID the operation can be cancelled by through [`DeviceQueue::cancel`].
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Ask the operation to stop.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Progress updates, from 0.0 to 1.0.
+    pub fn progress(&self) -> watch::Receiver<f32> {
+        self.progress.clone()
+    }
+
+    /// Wait for the operation to finish.
+    pub async fn wait(self) -> Result<T, OpError> {
+        self.result.await.unwrap_or_else(|_| {
+            Err(OpError::Failed(AppError::new(
+                code::INTERNAL,
+                "Operation was aborted",
+            )))
+        })
+    }
+}
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+#[derive(Default)]
+struct Inner {
+    workers: Mutex<HashMap<DeviceId, mpsc::UnboundedSender<Job>>>,
+    /// Cancel flags of operations that haven't finished, by ID.
+    pending: Mutex<HashMap<u64, Arc<CancelFlag>>>,
+    next_id: AtomicU64,
+    observer: Option<ProgressObserver>,
+}
+
+/// Removes an operation from the pending list once its job is gone.
+struct ForgetOnDrop {
+    inner: Arc<Inner>,
+    id: u64,
+}
+
+impl Drop for ForgetOnDrop {
+    fn drop(&mut self) {
+        self.inner.pending.lock().remove(&self.id);
+    }
+}
+
+/// Serialises exclusive operations per device. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct DeviceQueue {
+    inner: Arc<Inner>,
+}
+
+impl DeviceQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A queue that reports every operation's progress to `observer`.
+    pub fn with_observer(observer: ProgressObserver) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                observer: Some(observer),
+                ..Inner::default()
+            }),
+        }
+    }
+
+    /// Submit an operation on `device_id`. Exclusive kinds run after every
+    /// exclusive operation submitted before them on the same device; the
+    /// rest start straight away. Must be called from within a Tokio runtime.
+    pub fn submit<T, F, Fut>(&self, device_id: &DeviceId, kind: OpKind, op: F) -> OpHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(OpContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancel = Arc::new(CancelFlag::default());
+        let (progress_tx, progress_rx) = watch::channel(0.0);
+        let (result_tx, result_rx) = oneshot::channel();
+        self.inner.pending.lock().insert(id, Arc::clone(&cancel));
+
+        let ctx = OpContext {
+            id,
+            device_id: device_id.clone(),
+            kind,
+            cancel: Arc::clone(&cancel),
+            progress: progress_tx,
+            observer: self.inner.observer.clone(),
+        };
+        let forget = ForgetOnDrop {
+            inner: Arc::clone(&self.inner),
+            id,
+        };
+        let job: Job = Box::pin(async move {
+            // Dropped when the job ends, even by panicking
+            let _forget = forget;
+            let result = if ctx.is_cancelled() {
+                Err(OpError::Cancelled)
+            } else {
+                let cancel = Arc::clone(&ctx.cancel);
+                ctx.report(0.0);
+                let progress = ctx.progress.clone();
+                let observer = ctx.observer.clone();
+                let done = OpProgress {
+                    op_id: ctx.id,
+                    device_id: ctx.device_id.as_str().to_string(),
+                    kind: ctx.kind,
+                    progress: 1.0,
+                };
+                let result = op(ctx).await;
+                progress.send_replace(1.0);
+                if let Some(observer) = observer {
+                    observer(&done);
+                }
+                match result {
+                    Ok(value) => Ok(value),
+                    // An operation that gave up after a cancel was cancelled
+                    Err(_) if cancel.is_cancelled() => Err(OpError::Cancelled),
+                    Err(e) => Err(OpError::Failed(e)),
+                }
+            };
+            let _ = result_tx.send(result);
+        });
+
+        if kind.is_exclusive() {
+            self.enqueue(device_id, job);
+        } else {
+            tokio::spawn(job);
+        }
+
+        OpHandle {
+            id,
+            cancel,
+            progress: progress_rx,
+            result: result_rx,
+        }
+    }
+
+    /// Submit an operation and wait for it to finish.
+    pub async fn run<T, F, Fut>(
+        &self,
+        device_id: &DeviceId,
+        kind: OpKind,
+        op: F,
+    ) -> Result<T, OpError>
+    where
+        T: Send + 'static,
+        F: FnOnce(OpContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    {
+        self.submit(device_id, kind, op).wait().await
+    }
+
+    /// Cancel a queued or running operation by ID. Returns whether it was
+    /// still pending.
+    pub fn cancel(&self, op_id: u64) -> bool {
+        match self.inner.pending.lock().get(&op_id) {
+            Some(flag) => {
+                flag.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Hand a job to the device's worker, starting one if needed.
+    fn enqueue(&self, device_id: &DeviceId, job: Job) {
+        let mut workers = self.inner.workers.lock();
+        let job = match workers.get(device_id) {
+            Some(tx) => match tx.send(job) {
+                Ok(()) => return,
+                // The worker is gone; start a new one below
+                Err(mpsc::error::SendError(job)) => job,
+            },
+            None => job,
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<Job>();
+        let _ = tx.send(job);
+        workers.insert(device_id.clone(), tx);
+        tokio::spawn(async move {
+            while let Some(job) = rx.recv().await {
+                // Its own task, so a panicking operation can't take the
+                // worker (and every operation queued behind it) down
+                if let Err(e) = tokio::spawn(job).await {
+                    tracing::warn!("Device operation panicked: {e}");
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    /// Start and end of each fake operation, in the order they happened.
+    #[derive(Clone, Default)]
+    struct Log(Arc<Mutex<Vec<String>>>);
+
+    impl Log {
+        fn push(&self, entry: String) {
+            self.0.lock().push(entry);
+        }
+
+        fn entries(&self) -> Vec<String> {
+            self.0.lock().clone()
+        }
+    }
+
+    /// An operation that logs its start and end around a short sleep.
+    fn fake_op(
+        log: &Log,
+        name: &str,
+        duration: Duration,
+    ) -> impl FnOnce(OpContext) -> Pin<Box<dyn Future<Output = Result<String, AppError>> + Send>>
+    {
+        let log = log.clone();
+        let name = name.to_string();
+        move |_ctx| {
+            Box::pin(async move {
+                log.push(format!("start {name}"));
+                sleep(duration).await;
+                log.push(format!("end {name}"));
+                Ok(name)
+            })
+        }
+    }
+
+    fn device(id: &str) -> DeviceId {
+        DeviceId::new(id)
+    }
+
+    #[tokio::test]
+    async fn exclusive_operations_on_one_device_run_in_submission_order() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+        let cam = device("cam-1");
+
+        // Later operations are quicker, so any overlap would reorder the log
+        let handles = vec![
+            queue.submit(
+                &cam,
+                OpKind::FormatChange,
+                fake_op(&log, "a", Duration::from_millis(30)),
+            ),
+            queue.submit(
+                &cam,
+                OpKind::PresetApply,
+                fake_op(&log, "b", Duration::from_millis(20)),
+            ),
+            queue.submit(
+                &cam,
+                OpKind::PreviewRestart,
+                fake_op(&log, "c", Duration::from_millis(10)),
+            ),
+        ];
+        for handle in handles {
+            handle.wait().await.unwrap();
+        }
+
+        assert_eq!(
+            log.entries(),
+            ["start a", "end a", "start b", "end b", "start c", "end c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn different_devices_run_in_parallel() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+
+        let first = queue.submit(
+            &device("cam-1"),
+            OpKind::PreviewStart,
+            fake_op(&log, "cam-1", Duration::from_millis(50)),
+        );
+        let second = queue.submit(
+            &device("cam-2"),
+            OpKind::PreviewStart,
+            fake_op(&log, "cam-2", Duration::from_millis(50)),
+        );
+        first.wait().await.unwrap();
+        second.wait().await.unwrap();
+
+        let entries = log.entries();
+        let started: Vec<_> = entries[..2].to_vec();
+        assert!(started.contains(&"start cam-1".to_string()));
+        assert!(started.contains(&"start cam-2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn reads_bypass_a_busy_device() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+        let cam = device("cam-1");
+
+        let restart = queue.submit(
+            &cam,
+            OpKind::PreviewRestart,
+            fake_op(&log, "restart", Duration::from_millis(50)),
+        );
+        sleep(Duration::from_millis(10)).await;
+        let read = queue.submit(
+            &cam,
+            OpKind::FrameRead,
+            fake_op(&log, "read", Duration::ZERO),
+        );
+        read.wait().await.unwrap();
+
+        // The read finished while the restart still held the device
+        assert_eq!(log.entries(), ["start restart", "start read", "end read"]);
+        restart.wait().await.unwrap();
+    }
+
+    #[test]
+    fn only_device_changing_kinds_are_exclusive() {
+        assert!(OpKind::FormatChange.is_exclusive());
+        assert!(OpKind::PreviewStart.is_exclusive());
+        assert!(OpKind::PreviewStop.is_exclusive());
+        assert!(OpKind::PreviewRestart.is_exclusive());
+        assert!(OpKind::PresetApply.is_exclusive());
+        assert!(OpKind::BracketCapture.is_exclusive());
+        assert!(OpKind::ZoomChange.is_exclusive());
+        assert!(!OpKind::FrameRead.is_exclusive());
+        assert!(!OpKind::ControlRead.is_exclusive());
+    }
+
+    #[tokio::test]
+    async fn cancelled_queued_operation_never_starts() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+        let cam = device("cam-1");
+
+        let first = queue.submit(
+            &cam,
+            OpKind::PresetApply,
+            fake_op(&log, "first", Duration::from_millis(30)),
+        );
+        let second = queue.submit(
+            &cam,
+            OpKind::PresetApply,
+            fake_op(&log, "second", Duration::ZERO),
+        );
+        let third = queue.submit(
+            &cam,
+            OpKind::PresetApply,
+            fake_op(&log, "third", Duration::ZERO),
+        );
+        assert!(queue.cancel(second.id()));
+
+        assert_eq!(first.wait().await, Ok("first".to_string()));
+        assert_eq!(second.wait().await, Err(OpError::Cancelled));
+        assert_eq!(third.wait().await, Ok("third".to_string()));
+        assert_eq!(
+            log.entries(),
+            ["start first", "end first", "start third", "end third"]
+        );
+    }
+
+    #[tokio::test]
+    async fn running_operation_stops_when_cancelled() {
+        let queue = DeviceQueue::new();
+        let cam = device("cam-1");
+
+        let bracket = queue.submit(&cam, OpKind::BracketCapture, |ctx: OpContext| async move {
+            for shot in 0..100 {
+                if ctx.is_cancelled() {
+                    return Err(AppError::new(code::CANCELLED, "stopped"));
+                }
+                ctx.report(shot as f32 / 100.0);
+                sleep(Duration::from_millis(5)).await;
+            }
+            Ok(())
+        });
+        let mut progress = bracket.progress();
+        progress.wait_for(|p| *p > 0.0).await.unwrap();
+        bracket.cancel();
+
+        assert_eq!(bracket.wait().await, Err(OpError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn cancelled_resolves_for_a_waiting_operation() {
+        let queue = DeviceQueue::new();
+        let cam = device("cam-1");
+
+        let handle = queue.submit(&cam, OpKind::FormatChange, |ctx: OpContext| async move {
+            ctx.cancelled().await;
+            Err::<(), _>(AppError::new(code::CANCELLED, "gave up"))
+        });
+        sleep(Duration::from_millis(10)).await;
+        handle.cancel();
+
+        assert_eq!(handle.wait().await, Err(OpError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn cancel_of_a_finished_operation_is_refused() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+        let handle = queue.submit(
+            &device("cam-1"),
+            OpKind::PreviewStop,
+            fake_op(&log, "stop", Duration::ZERO),
+        );
+        let id = handle.id();
+        handle.wait().await.unwrap();
+
+        assert!(!queue.cancel(id));
+    }
+
+    #[tokio::test]
+    async fn failures_are_reported_and_the_queue_carries_on() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+        let cam = device("cam-1");
+
+        let failing = queue.submit(&cam, OpKind::PresetApply, |_ctx: OpContext| async {
+            Err::<String, _>(AppError::new(code::DEVICE_NOT_FOUND, "device not found"))
+        });
+        let next = queue.submit(
+            &cam,
+            OpKind::PresetApply,
+            fake_op(&log, "next", Duration::ZERO),
+        );
+
+        assert_eq!(
+            failing.wait().await,
+            Err(OpError::Failed(AppError::new(
+                code::DEVICE_NOT_FOUND,
+                "device not found"
+            )))
+        );
+        assert_eq!(next.wait().await, Ok("next".to_string()));
+    }
+
+    #[tokio::test]
+    async fn a_panicking_operation_does_not_stall_the_device() {
+        let queue = DeviceQueue::new();
+        let log = Log::default();
+        let cam = device("cam-1");
+
+        let panicking = queue.submit(&cam, OpKind::PreviewStart, |_ctx: OpContext| async {
+            if true {
+                panic!("driver crashed");
+            }
+            Ok(())
+        });
+        let next = queue.submit(
+            &cam,
+            OpKind::PreviewStart,
+            fake_op(&log, "next", Duration::ZERO),
+        );
+
+        assert!(matches!(panicking.wait().await, Err(OpError::Failed(_))));
+        assert_eq!(next.wait().await, Ok("next".to_string()));
+    }
+
+    #[tokio::test]
+    async fn observer_sees_start_progress_and_finish() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let queue = DeviceQueue::with_observer(Arc::new(move |p: &OpProgress| {
+            sink.lock().push((p.device_id.clone(), p.kind, p.progress));
+        }));
+
+        queue
+            .run(
+                &device("cam-1"),
+                OpKind::PresetApply,
+                |ctx: OpContext| async move {
+                    ctx.report(0.5);
+                    Ok(())
+                },
+            )
+            .await
+            .unwrap();
+
+        let kind = OpKind::PresetApply;
+        assert_eq!(
+            *seen.lock(),
+            [
+                ("cam-1".to_string(), kind, 0.0),
+                ("cam-1".to_string(), kind, 0.5),
+                ("cam-1".to_string(), kind, 1.0),
+            ]
+        );
+    }
+}
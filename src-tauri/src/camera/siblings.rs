@@ -0,0 +1,368 @@
+// Grouping of DirectShow filters that belong to the same physical camera.
+//
+// Composite USB cameras expose one filter per interface: a Brio shows up as
+// "Logitech BRIO" and "Logitech BRIO (Infrared)", a RealSense as separate
+// "... Depth" and "... RGB" devices. Siblings share VID:PID and the parent
+// instance in their device path; the part of the name that differs between
+// them says which stream each one carries.
+
+use std::collections::HashMap;
+
+use crate::camera::types::{CameraDevice, DeviceKind};
+
+/// Key shared by all interfaces of one physical USB device, derived from a
+/// device path such as `\\?\usb#vid_046d&pid_085e&mi_02#7&2d1a5c2b&0&0002#{guid}`.
+///
+/// The interface number (`mi_XX`) and the last instance segment differ per
+/// interface, so both are dropped. Returns `None` for paths without VID/PID.
+pub fn sibling_key(device_path: &str) -> Option<String> {
+    let lower = device_path.to_ascii_lowercase();
+    let parts: Vec<&str> = lower.split('#').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+
+    let hardware = parts[1];
+    if !hardware.contains("vid_") || !hardware.contains("pid_") {
+        return None;
+    }
+    let hardware: Vec<&str> = hardware
+        .split('&')
+        .filter(|segment| !segment.starts_with("mi_"))
+        .collect();
+
+    let instance = parts[2];
+    if instance.is_empty() || instance.starts_with('{') {
+        return None;
+    }
+    let parent = instance
+        .rsplit_once('&')
+        .map_or(instance, |(prefix, _)| prefix);
+
+    Some(format!("{}#{parent}", hardware.join("&")))
+}
+
+/// Lowercase alphanumeric words of a device name.
+fn name_tokens(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_ascii_lowercase())
+        .collect()
+}
+
+/// Classify a stream from the words that distinguish it from its siblings.
+///
+/// `allow_depth` is off for standalone devices, whose full name may contain
+/// "depth" as part of the product name (e.g. "RealSense Depth Camera").
+fn classify_tokens(tokens: &[String], allow_depth: bool) -> DeviceKind {
+    if tokens.iter().any(|t| t == "infrared" || t == "ir") {
+        DeviceKind::Infrared
+    } else if allow_depth && tokens.iter().any(|t| t == "depth") {
+        DeviceKind::Depth
+    } else {
+        DeviceKind::Primary
+    }
+}
+
+/// Number of leading tokens shared by every name in the group.
+fn common_prefix_len(names: &[Vec<String>]) -> usize {
+    let Some(first) = names.first() else {
+        return 0;
+    };
+    (0..first.len())
+        .take_while(|&i| names.iter().all(|n| n.get(i) == first.get(i)))
+        .count()
+}
+
+/// Set `kind` and `primary_id` on every device, grouping siblings by
+/// [`sibling_key`].
+///
+/// Within a group, each device is classified by the words its name does not
+/// share with the others. Standalone devices are only marked non-primary for
+/// unambiguous infrared names.
+pub fn classify_devices(devices: &mut [CameraDevice]) {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, device) in devices.iter().enumerate() {
+        if let Some(key) = sibling_key(&device.device_path) {
+            groups.entry(key).or_default().push(i);
+        }
+    }
+
+    for (i, device) in devices.iter_mut().enumerate() {
+        let grouped = sibling_key(&device.device_path)
+            .and_then(|key| groups.get(&key))
+            .is_some_and(|members| members.len() > 1 && members.contains(&i));
+        if !grouped {
+            device.kind = classify_tokens(&name_tokens(&device.name), false);
+            device.primary_id = None;
+        }
+    }
+
+    for members in groups.values().filter(|m| m.len() > 1) {
+        let names: Vec<Vec<String>> = members
+            .iter()
+            .map(|&i| name_tokens(&devices[i].name))
+            .collect();
+        let shared = common_prefix_len(&names);
+
+        for (&i, tokens) in members.iter().zip(&names) {
+            devices[i].kind = classify_tokens(&tokens[shared..], true);
+        }
+
+        let primary = members
+            .iter()
+            .find(|&&i| devices[i].kind == DeviceKind::Primary)
+            .map(|&i| devices[i].id.clone());
+        for &i in members {
+            devices[i].primary_id = match devices[i].kind {
+                DeviceKind::Primary => None,
+                _ => primary.clone(),
+            };
+        }
+    }
+}
+
+/// Order devices so each primary is immediately followed by its siblings.
+///
+/// Primaries keep their relative order; non-primary devices without a
+/// listed primary stay where they are.
+pub fn group_siblings(devices: Vec<CameraDevice>) -> Vec<CameraDevice> {
+    let has_listed_primary = |d: &CameraDevice| {
+        d.primary_id
+            .as_ref()
+            .is_some_and(|p| devices.iter().any(|other| &other.id == p))
+    };
+
+    let mut grouped = Vec::with_capacity(devices.len());
+    for device in devices.iter().filter(|d| !has_listed_primary(d)) {
+        grouped.push(device.clone());
+        grouped.extend(
+            devices
+                .iter()
+                .filter(|d| d.primary_id.as_ref() == Some(&device.id))
+                .cloned(),
+        );
+    }
+    grouped
+}
+
+/// Whether a device should get a preview session without being asked for.
+/// Never for a duplicate another backend's device is preferred over.
+pub fn should_auto_start(device: &CameraDevice, include_non_primary: bool) -> bool {
+    device.suppressed_by.is_none() && (include_non_primary || device.kind == DeviceKind::Primary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::DeviceId;
+
+    const BRIO_RGB: &str = r"\\?\usb#vid_046d&pid_085e&mi_00#7&2d1a5c2b&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global";
+    const BRIO_IR: &str = r"\\?\usb#vid_046d&pid_085e&mi_02#7&2d1a5c2b&0&0002#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global";
+    const REALSENSE_DEPTH: &str = r"\\?\usb#vid_8086&pid_0b07&mi_00#6&1f3a9c41&0&0000#{e5323777-f976-4f5b-9b55-b94699c46e44}\global";
+    const REALSENSE_RGB: &str = r"\\?\usb#vid_8086&pid_0b07&mi_03#6&1f3a9c41&0&0003#{e5323777-f976-4f5b-9b55-b94699c46e44}\global";
+    const KINECT_VIDEO: &str = r"\\?\usb#vid_045e&pid_02c4&mi_00#8&3b1e77f0&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global";
+    const KINECT_DEPTH: &str = r"\\?\usb#vid_045e&pid_02c4&mi_02#8&3b1e77f0&0&0002#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global";
+    const KINECT_IR: &str = r"\\?\usb#vid_045e&pid_02c4&mi_04#8&3b1e77f0&0&0004#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global";
+
+    fn device(path: &str, name: &str) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::from_device_path(path),
+            name: name.to_string(),
+            device_path: path.to_string(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn kinds(devices: &[CameraDevice]) -> Vec<DeviceKind> {
+        devices.iter().map(|d| d.kind).collect()
+    }
+
+    #[test]
+    fn sibling_key_ignores_interface_and_instance_suffix() {
+        assert_eq!(sibling_key(BRIO_RGB), sibling_key(BRIO_IR));
+        assert_eq!(
+            sibling_key(BRIO_RGB).unwrap(),
+            "vid_046d&pid_085e#7&2d1a5c2b&0"
+        );
+        assert_ne!(sibling_key(BRIO_RGB), sibling_key(REALSENSE_RGB));
+    }
+
+    #[test]
+    fn sibling_key_distinguishes_two_identical_cameras() {
+        let second_brio = r"\\?\usb#vid_046d&pid_085e&mi_00#7&11aa22bb&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global";
+        assert_ne!(sibling_key(BRIO_RGB), sibling_key(second_brio));
+    }
+
+    #[test]
+    fn sibling_key_none_without_vid_pid_or_instance() {
+        assert_eq!(sibling_key(""), None);
+        assert_eq!(sibling_key(r"\\?\root#image#0000#{guid}"), None);
+        assert_eq!(sibling_key(r"\\?\usb#vid_046d&pid_085e#{guid}"), None);
+    }
+
+    #[test]
+    fn brio_infrared_is_marked_as_sibling_of_rgb() {
+        let mut devices = vec![
+            device(BRIO_RGB, "Logitech BRIO"),
+            device(BRIO_IR, "Logitech BRIO (Infrared)"),
+        ];
+        classify_devices(&mut devices);
+
+        assert_eq!(
+            kinds(&devices),
+            vec![DeviceKind::Primary, DeviceKind::Infrared]
+        );
+        assert_eq!(devices[0].primary_id, None);
+        assert_eq!(devices[1].primary_id, Some(devices[0].id.clone()));
+    }
+
+    #[test]
+    fn realsense_depth_is_not_confused_by_product_name() {
+        let mut devices = vec![
+            device(
+                REALSENSE_DEPTH,
+                "Intel(R) RealSense(TM) Depth Camera 435 with RGB Module Depth",
+            ),
+            device(
+                REALSENSE_RGB,
+                "Intel(R) RealSense(TM) Depth Camera 435 with RGB Module RGB",
+            ),
+        ];
+        classify_devices(&mut devices);
+
+        assert_eq!(
+            kinds(&devices),
+            vec![DeviceKind::Depth, DeviceKind::Primary]
+        );
+        assert_eq!(devices[0].primary_id, Some(devices[1].id.clone()));
+    }
+
+    #[test]
+    fn kinect_style_sensors_are_classified() {
+        let mut devices = vec![
+            device(KINECT_DEPTH, "Kinect V2 Depth Sensor"),
+            device(KINECT_VIDEO, "Kinect V2 Video Sensor"),
+            device(KINECT_IR, "Kinect V2 IR Sensor"),
+        ];
+        classify_devices(&mut devices);
+
+        assert_eq!(
+            kinds(&devices),
+            vec![DeviceKind::Depth, DeviceKind::Primary, DeviceKind::Infrared]
+        );
+        let primary = Some(devices[1].id.clone());
+        assert_eq!(devices[0].primary_id, primary);
+        assert_eq!(devices[2].primary_id, primary);
+    }
+
+    #[test]
+    fn standalone_devices_only_flag_infrared_names() {
+        let mut devices = vec![
+            device(
+                r"\\?\usb#vid_8086&pid_0b3a&mi_00#6&aaaa&0&0000#{guid}",
+                "Intel(R) RealSense(TM) Depth Camera 455",
+            ),
+            device(
+                r"\\?\usb#vid_04f2&pid_b6b6&mi_02#6&bbbb&0&0002#{guid}",
+                "Integrated IR Camera",
+            ),
+            device("", "OBS Virtual Camera"),
+        ];
+        classify_devices(&mut devices);
+
+        assert_eq!(
+            kinds(&devices),
+            vec![
+                DeviceKind::Primary,
+                DeviceKind::Infrared,
+                DeviceKind::Primary
+            ]
+        );
+        assert!(devices.iter().all(|d| d.primary_id.is_none()));
+    }
+
+    #[test]
+    fn ir_substring_in_a_word_is_not_infrared() {
+        let mut devices = vec![device(
+            r"\\?\usb#vid_1234&pid_5678#serial01#{guid}",
+            "Mirage HD Webcam",
+        )];
+        classify_devices(&mut devices);
+        assert_eq!(devices[0].kind, DeviceKind::Primary);
+    }
+
+    #[test]
+    fn unrelated_cameras_are_left_alone() {
+        let mut devices = vec![
+            device(BRIO_RGB, "Logitech BRIO"),
+            device(
+                REALSENSE_RGB,
+                "Intel(R) RealSense(TM) Depth Camera 435 with RGB Module RGB",
+            ),
+        ];
+        classify_devices(&mut devices);
+        assert_eq!(
+            kinds(&devices),
+            vec![DeviceKind::Primary, DeviceKind::Primary]
+        );
+    }
+
+    #[test]
+    fn group_siblings_places_siblings_after_primary() {
+        let mut devices = vec![
+            device(BRIO_IR, "Logitech BRIO (Infrared)"),
+            device(REALSENSE_DEPTH, "RealSense Module Depth"),
+            device(BRIO_RGB, "Logitech BRIO"),
+            device(REALSENSE_RGB, "RealSense Module RGB"),
+        ];
+        classify_devices(&mut devices);
+        let names: Vec<String> = group_siblings(devices)
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "Logitech BRIO",
+                "Logitech BRIO (Infrared)",
+                "RealSense Module RGB",
+                "RealSense Module Depth",
+            ]
+        );
+    }
+
+    #[test]
+    fn group_siblings_keeps_orphaned_siblings() {
+        let mut ir = device(BRIO_IR, "Logitech BRIO (Infrared)");
+        ir.kind = DeviceKind::Infrared;
+        ir.primary_id = Some(DeviceId::new("gone"));
+        let grouped = group_siblings(vec![ir]);
+        assert_eq!(grouped.len(), 1);
+    }
+
+    #[test]
+    fn auto_start_skips_non_primary_unless_enabled() {
+        let mut ir = device(BRIO_IR, "Logitech BRIO (Infrared)");
+        ir.kind = DeviceKind::Infrared;
+        let rgb = device(BRIO_RGB, "Logitech BRIO");
+
+        assert!(should_auto_start(&rgb, false));
+        assert!(!should_auto_start(&ir, false));
+        assert!(should_auto_start(&ir, true));
+    }
+
+    #[test]
+    fn auto_start_skips_suppressed_duplicates() {
+        let mut eos = device(BRIO_RGB, "EOS R5");
+        eos.suppressed_by = Some(DeviceId::new("canon:usb1"));
+
+        assert!(!should_auto_start(&eos, false));
+        assert!(!should_auto_start(&eos, true));
+    }
+}
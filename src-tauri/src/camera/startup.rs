@@ -0,0 +1,492 @@
+//! Progressive device discovery at startup.
+//!
+//! Each backend is enumerated on its own blocking thread with a time limit,
+//! so one misbehaving capture card can't hold up the rest of startup.
+//! Devices are announced as each backend answers; a backend that misses its
+//! deadline is skipped for now and checked again later, and one that fails
+//! is called again after a delay.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::error::Result;
+use crate::camera::identity::{merge_devices, BackendDevices};
+use crate::camera::types::{CameraDevice, DeviceId};
+
+/// Time limits and retries for startup enumeration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupConfig {
+    /// How long each backend gets to answer before it's skipped for now.
+    pub backend_timeout: Duration,
+    /// Wait before calling a backend again after it failed.
+    pub retry_delay: Duration,
+    /// Answers waited for (timeouts) or calls made (failures) before a
+    /// backend is given up on.
+    pub max_attempts: u32,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            backend_timeout: Duration::from_secs(5),
+            retry_delay: Duration::from_secs(10),
+            max_attempts: 3,
+        }
+    }
+}
+
+/// How far startup discovery has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StartupPhase {
+    /// Waiting for backends to answer for the first time.
+    Enumerating,
+    /// Every backend has answered or been skipped; skipped ones may still
+    /// add devices.
+    Ready,
+    /// Every backend has answered or been given up on.
+    Complete,
+}
+
+/// Payload of the `startup-progress` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupProgress {
+    pub phase: StartupPhase,
+    /// Devices announced so far.
+    pub devices_found: usize,
+    /// Backends that haven't answered yet.
+    pub pending_backends: usize,
+}
+
+/// Reported by [`discover_progressively`] as discovery proceeds.
+#[derive(Debug, Clone)]
+pub enum StartupUpdate {
+    /// Devices seen for the first time, in merged order.
+    Devices(Vec<CameraDevice>),
+    Progress(StartupProgress),
+}
+
+/// What a backend's watcher reports back.
+enum BackendEvent {
+    /// Missed its deadline or failed; still being retried.
+    Skipped(usize),
+    Listed(usize, Vec<CameraDevice>),
+    GaveUp(usize),
+}
+
+/// Enumerate `sources` (backends paired with their duplicate-suppression
+/// priority) concurrently, calling `on_update` as devices and progress come
+/// in. Returns every device announced.
+///
+/// Devices are merged across the backends that have answered so far, so a
+/// late higher-priority backend can't hide a device already announced.
+/// Suppressed duplicates are only announced with `show_suppressed`.
+pub async fn discover_progressively(
+    sources: Vec<(Arc<dyn CameraBackend>, u8)>,
+    show_suppressed: bool,
+    config: StartupConfig,
+    mut on_update: impl FnMut(StartupUpdate),
+) -> Vec<CameraDevice> {
+    let count = sources.len();
+    let mut priorities = Vec::with_capacity(count);
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    for (index, (backend, priority)) in sources.into_iter().enumerate() {
+        priorities.push(priority);
+        tokio::spawn(watch_backend(index, backend, config, events_tx.clone()));
+    }
+    drop(events_tx);
+
+    let mut lists: Vec<Option<Vec<CameraDevice>>> = vec![None; count];
+    let mut answered = vec![false; count];
+    let mut resolved = 0;
+    let mut announced: Vec<CameraDevice> = Vec::new();
+    let mut seen: HashSet<DeviceId> = HashSet::new();
+
+    let progress = |answered: &[bool], resolved: usize, found: usize| {
+        let phase = if resolved == answered.len() {
+            StartupPhase::Complete
+        } else if answered.iter().all(|&a| a) {
+            StartupPhase::Ready
+        } else {
+            StartupPhase::Enumerating
+        };
+        StartupUpdate::Progress(StartupProgress {
+            phase,
+            devices_found: found,
+            pending_backends: answered.len() - resolved,
+        })
+    };
+
+    on_update(progress(&answered, resolved, 0));
+
+    while let Some(event) = events.recv().await {
+        match event {
+            BackendEvent::Skipped(index) => answered[index] = true,
+            BackendEvent::GaveUp(index) => {
+                answered[index] = true;
+                resolved += 1;
+            }
+            BackendEvent::Listed(index, devices) => {
+                answered[index] = true;
+                resolved += 1;
+                lists[index] = Some(devices);
+
+                let merged = merge_devices(
+                    lists
+                        .iter()
+                        .zip(&priorities)
+                        .filter_map(|(list, &priority)| {
+                            list.clone()
+                                .map(|devices| BackendDevices { priority, devices })
+                        })
+                        .collect(),
+                );
+                let new: Vec<_> = merged
+                    .into_iter()
+                    .filter(|d| show_suppressed || d.suppressed_by.is_none())
+                    .filter(|d| seen.insert(d.id.clone()))
+                    .collect();
+                if !new.is_empty() {
+                    announced.extend(new.iter().cloned());
+                    on_update(StartupUpdate::Devices(new));
+                }
+            }
+        }
+        on_update(progress(&answered, resolved, announced.len()));
+    }
+
+    announced
+}
+
+/// Enumerate one backend, reporting the outcome on `events`.
+///
+/// A call that misses its deadline can't be cancelled, so later checks wait
+/// on that same call rather than piling up new ones behind it.
+async fn watch_backend(
+    index: usize,
+    backend: Arc<dyn CameraBackend>,
+    config: StartupConfig,
+    events: mpsc::UnboundedSender<BackendEvent>,
+) {
+    let mut attempt = 1;
+    let mut skipped = false;
+    let mut call = spawn_enumerate(&backend);
+
+    loop {
+        let failure = match tokio::time::timeout(config.backend_timeout, &mut call).await {
+            Ok(Ok(Ok(devices))) => {
+                let _ = events.send(BackendEvent::Listed(index, devices));
+                return;
+            }
+            Ok(Ok(Err(e))) => Some(e.to_string()),
+            Ok(Err(e)) => Some(format!("enumeration panicked: {e}")),
+            Err(_) => None,
+        };
+
+        match &failure {
+            Some(e) => {
+                tracing::warn!("Backend {index} enumeration failed (attempt {attempt}): {e}")
+            }
+            None => tracing::warn!(
+                "Backend {index} didn't answer within {:?} (attempt {attempt})",
+                config.backend_timeout
+            ),
+        }
+        if attempt >= config.max_attempts {
+            tracing::warn!("Giving up on backend {index} at startup");
+            let _ = events.send(BackendEvent::GaveUp(index));
+            return;
+        }
+        if !skipped {
+            skipped = true;
+            let _ = events.send(BackendEvent::Skipped(index));
+        }
+        attempt += 1;
+
+        if failure.is_some() {
+            tokio::time::sleep(config.retry_delay).await;
+            call = spawn_enumerate(&backend);
+        }
+    }
+}
+
+fn spawn_enumerate(backend: &Arc<dyn CameraBackend>) -> JoinHandle<Result<Vec<CameraDevice>>> {
+    let backend = Arc::clone(backend);
+    tokio::task::spawn_blocking(move || backend.enumerate_devices())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::error::CameraError;
+    use crate::camera::types::{
+        ControlDescriptor, ControlId, ControlValue, DeviceKind, FormatDescriptor, HotplugEvent,
+        PhysicalIdentity,
+    };
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Instant;
+
+    /// Backend that answers after `delay`, failing its first `failures`
+    /// calls.
+    struct StubBackend {
+        devices: Vec<CameraDevice>,
+        delay: Duration,
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    impl StubBackend {
+        fn new(ids: &[&str]) -> Self {
+            Self {
+                devices: ids.iter().map(|id| device(id)).collect(),
+                delay: Duration::ZERO,
+                failures: 0,
+                calls: AtomicU32::new(0),
+            }
+        }
+
+        fn slow(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        fn failing(mut self, failures: u32) -> Self {
+            self.failures = failures;
+            self
+        }
+    }
+
+    impl CameraBackend for StubBackend {
+        fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(self.delay);
+            if call < self.failures {
+                return Err(CameraError::Enumeration("driver not ready".to_string()));
+            }
+            Ok(self.devices.clone())
+        }
+
+        fn watch_hotplug(&self, _callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_controls(&self, id: &DeviceId) -> Result<Vec<ControlDescriptor>> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn get_control(&self, id: &DeviceId, _control: &ControlId) -> Result<ControlValue> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn set_control(&self, id: &DeviceId, _: &ControlId, _: ControlValue) -> Result<()> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn set_control_auto(&self, id: &DeviceId, _: &ControlId, _: bool) -> Result<()> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+    }
+
+    fn device(id: &str) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: id.to_string(),
+            device_path: format!("stub://{id}"),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn config() -> StartupConfig {
+        StartupConfig {
+            backend_timeout: Duration::from_millis(50),
+            retry_delay: Duration::from_millis(10),
+            max_attempts: 3,
+        }
+    }
+
+    fn source(backend: StubBackend, priority: u8) -> (Arc<dyn CameraBackend>, u8) {
+        (Arc::new(backend), priority)
+    }
+
+    fn ids(devices: &[CameraDevice]) -> Vec<&str> {
+        devices.iter().map(|d| d.id.as_str()).collect()
+    }
+
+    /// Run discovery, recording every update with the time it arrived.
+    async fn run(
+        sources: Vec<(Arc<dyn CameraBackend>, u8)>,
+        config: StartupConfig,
+    ) -> (Vec<CameraDevice>, Vec<(Duration, StartupUpdate)>) {
+        let start = Instant::now();
+        let mut updates = Vec::new();
+        let devices = discover_progressively(sources, false, config, |u| {
+            updates.push((start.elapsed(), u))
+        })
+        .await;
+        (devices, updates)
+    }
+
+    fn first_phase(updates: &[(Duration, StartupUpdate)], phase: StartupPhase) -> Option<Duration> {
+        updates.iter().find_map(|(at, u)| match u {
+            StartupUpdate::Progress(p) if p.phase == phase => Some(*at),
+            _ => None,
+        })
+    }
+
+    fn last_progress(updates: &[(Duration, StartupUpdate)]) -> StartupProgress {
+        match &updates.last().unwrap().1 {
+            StartupUpdate::Progress(p) => p.clone(),
+            other => panic!("expected progress, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn slow_backend_does_not_hold_up_the_others() {
+        let slow = Duration::from_millis(400);
+        let (devices, updates) = run(
+            vec![
+                source(StubBackend::new(&["hung-card"]).slow(slow), 0),
+                source(StubBackend::new(&["brio", "c920"]), 0),
+            ],
+            StartupConfig {
+                max_attempts: 20,
+                ..config()
+            },
+        )
+        .await;
+
+        // The fast backend's devices come first, well before the slow one
+        // answers, and startup is ready as soon as the slow one is skipped
+        let (at, first) = updates
+            .iter()
+            .find_map(|(at, u)| match u {
+                StartupUpdate::Devices(d) => Some((*at, d)),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(ids(first), ["brio", "c920"]);
+        assert!(at < slow, "fast devices arrived after {at:?}");
+        let ready = first_phase(&updates, StartupPhase::Ready).unwrap();
+        assert!(ready < slow, "ready after {ready:?}");
+
+        // The slow backend is picked up when it finally answers
+        assert_eq!(ids(&devices), ["brio", "c920", "hung-card"]);
+        assert!(first_phase(&updates, StartupPhase::Complete).unwrap() >= slow);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn slow_backend_is_waited_on_not_called_again() {
+        let backend = Arc::new(StubBackend::new(&["hung-card"]).slow(Duration::from_millis(120)));
+        let devices = discover_progressively(
+            vec![(Arc::clone(&backend) as Arc<dyn CameraBackend>, 0)],
+            false,
+            StartupConfig {
+                max_attempts: 10,
+                ..config()
+            },
+            |_| {},
+        )
+        .await;
+        assert_eq!(ids(&devices), ["hung-card"]);
+        assert_eq!(backend.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn failing_backend_is_retried() {
+        let backend = Arc::new(StubBackend::new(&["capture-card"]).failing(2));
+        let devices = discover_progressively(
+            vec![(Arc::clone(&backend) as Arc<dyn CameraBackend>, 0)],
+            false,
+            config(),
+            |_| {},
+        )
+        .await;
+        assert_eq!(ids(&devices), ["capture-card"]);
+        assert_eq!(backend.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn backend_is_given_up_on_after_max_attempts() {
+        let (devices, updates) = run(
+            vec![
+                source(StubBackend::new(&["broken"]).failing(u32::MAX), 0),
+                source(StubBackend::new(&["brio"]), 0),
+            ],
+            config(),
+        )
+        .await;
+        assert_eq!(ids(&devices), ["brio"]);
+        assert_eq!(
+            last_progress(&updates),
+            StartupProgress {
+                phase: StartupPhase::Complete,
+                devices_found: 1,
+                pending_backends: 0,
+            }
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn duplicates_from_a_lower_priority_backend_are_not_announced() {
+        let identity = Some(PhysicalIdentity {
+            serial: Some("1234".to_string()),
+            ..Default::default()
+        });
+        let mut webcam_utility = StubBackend::new(&["ds:eos"]);
+        webcam_utility.devices[0].identity = identity.clone();
+        let mut canon = StubBackend::new(&["edsdk:eos"]);
+        canon.devices[0].identity = identity;
+
+        let (devices, _) = run(
+            vec![
+                source(webcam_utility.slow(Duration::from_millis(20)), 0),
+                source(canon, 1),
+            ],
+            config(),
+        )
+        .await;
+        assert_eq!(ids(&devices), ["edsdk:eos"]);
+    }
+
+    #[tokio::test]
+    async fn no_backends_completes_straight_away() {
+        let (devices, updates) = run(Vec::new(), config()).await;
+        assert!(devices.is_empty());
+        assert_eq!(
+            last_progress(&updates),
+            StartupProgress {
+                phase: StartupPhase::Complete,
+                devices_found: 0,
+                pending_backends: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn progress_serialises_camel_case() {
+        let json = serde_json::to_value(StartupProgress {
+            phase: StartupPhase::Ready,
+            devices_found: 2,
+            pending_backends: 1,
+        })
+        .unwrap();
+        assert_eq!(json["phase"], "ready");
+        assert_eq!(json["devicesFound"], 2);
+        assert_eq!(json["pendingBackends"], 1);
+    }
+}
@@ -0,0 +1,385 @@
+// Default camera suggestion.
+//
+// Enumeration order often puts an IR sensor or a virtual camera first, so on
+// first run the default camera is picked by score instead. Each device is
+// scored from what is known about it without opening it — its name, kind,
+// controls and formats — plus, optionally, whether its preview produced a
+// frame within a short probe. Scoring is pure over `DeviceInfo`; gathering
+// the info and probing are left to the caller.
+
+use serde::Serialize;
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::types::{CameraDevice, DeviceKind};
+use crate::preview::quirks::quirk_profile_for;
+
+/// Penalty for a virtual camera (OBS, NDI, Snap Camera and the like).
+const VIRTUAL_PENALTY: i32 = -100;
+/// Penalty for an infrared or depth filter of a composite camera.
+const SIBLING_PENALTY: i32 = -200;
+/// Points per supported control.
+const POINTS_PER_CONTROL: i32 = 2;
+/// Cap on the controls score, reached at 20 controls.
+const MAX_CONTROLS_SCORE: i32 = 40;
+/// Pixels per point of the resolution score.
+const PIXELS_PER_POINT: u64 = 100_000;
+/// Cap on the resolution score, reached at about 4 MP.
+const MAX_RESOLUTION_SCORE: i32 = 40;
+/// Bonus for producing a frame within the probe, and penalty for not.
+const PROBE_SCORE: i32 = 50;
+
+/// Words in a friendly name that mark a virtual camera, matched whole and
+/// case-insensitively. Cameras with a quirk profile count as virtual too.
+const VIRTUAL_NAME_WORDS: &[&str] = &[
+    "virtual", "vcam", "droidcam", "epoccam", "ivcam", "manycam", "mmhmm", "camo",
+];
+
+/// What the scorer knows about a device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    pub id: String,
+    pub name: String,
+    pub kind: DeviceKind,
+    /// Number of controls the device supports.
+    pub control_count: usize,
+    /// Largest advertised frame size, if the device reported any formats.
+    pub max_resolution: Option<(u32, u32)>,
+    /// Whether the device produced a frame within the probe, or `None` if it
+    /// wasn't probed.
+    pub produced_frame: Option<bool>,
+}
+
+/// Per-signal contributions to a device's score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreBreakdown {
+    pub virtual_camera: i32,
+    pub sibling: i32,
+    pub controls: i32,
+    pub resolution: i32,
+    pub probe: i32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> i32 {
+        self.virtual_camera + self.sibling + self.controls + self.resolution + self.probe
+    }
+}
+
+/// A ranked device, as returned by `suggest_default_camera`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraSuggestion {
+    pub device_id: String,
+    pub name: String,
+    pub score: i32,
+    pub breakdown: ScoreBreakdown,
+}
+
+/// Whether a friendly name looks like a virtual camera.
+pub fn is_virtual_camera(name: &str) -> bool {
+    if quirk_profile_for(name).is_some() {
+        return true;
+    }
+    name.to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| VIRTUAL_NAME_WORDS.contains(&word))
+}
+
+/// Score a single device.
+pub fn score_device(info: &DeviceInfo) -> ScoreBreakdown {
+    let virtual_camera = if is_virtual_camera(&info.name) {
+        VIRTUAL_PENALTY
+    } else {
+        0
+    };
+    let sibling = match info.kind {
+        DeviceKind::Primary => 0,
+        DeviceKind::Infrared | DeviceKind::Depth => SIBLING_PENALTY,
+    };
+    let controls = i32::try_from(info.control_count)
+        .unwrap_or(i32::MAX)
+        .saturating_mul(POINTS_PER_CONTROL)
+        .min(MAX_CONTROLS_SCORE);
+    let resolution = info.max_resolution.map_or(0, |(width, height)| {
+        let points = u64::from(width) * u64::from(height) / PIXELS_PER_POINT;
+        points.min(MAX_RESOLUTION_SCORE as u64) as i32
+    });
+    let probe = match info.produced_frame {
+        Some(true) => PROBE_SCORE,
+        Some(false) => -PROBE_SCORE,
+        None => 0,
+    };
+
+    ScoreBreakdown {
+        virtual_camera,
+        sibling,
+        controls,
+        resolution,
+        probe,
+    }
+}
+
+/// Rank devices best first. Ties keep enumeration order.
+pub fn rank_devices(devices: &[DeviceInfo]) -> Vec<CameraSuggestion> {
+    let mut ranked: Vec<CameraSuggestion> = devices
+        .iter()
+        .map(|info| {
+            let breakdown = score_device(info);
+            CameraSuggestion {
+                device_id: info.id.clone(),
+                name: info.name.clone(),
+                score: breakdown.total(),
+                breakdown,
+            }
+        })
+        .collect();
+    ranked.sort_by_key(|s| std::cmp::Reverse(s.score));
+    ranked
+}
+
+/// Gather scoring info for `device` from its controls and formats.
+///
+/// Devices that fail to report either are scored without them.
+pub fn describe_device(
+    backend: &dyn CameraBackend,
+    device: &CameraDevice,
+    produced_frame: Option<bool>,
+) -> DeviceInfo {
+    let control_count = backend
+        .get_controls(&device.id)
+        .map(|controls| controls.iter().filter(|c| c.supported).count())
+        .unwrap_or(0);
+    let max_resolution = backend.get_formats(&device.id).ok().and_then(|formats| {
+        formats
+            .iter()
+            .map(|f| (f.width, f.height))
+            .max_by_key(|&(w, h)| u64::from(w) * u64::from(h))
+    });
+
+    DeviceInfo {
+        id: device.id.as_str().to_string(),
+        name: device.name.clone(),
+        kind: device.kind,
+        control_count,
+        max_resolution,
+        produced_frame,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, name: &str) -> DeviceInfo {
+        DeviceInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind: DeviceKind::Primary,
+            control_count: 0,
+            max_resolution: None,
+            produced_frame: None,
+        }
+    }
+
+    fn ids(ranked: &[CameraSuggestion]) -> Vec<&str> {
+        ranked.iter().map(|s| s.device_id.as_str()).collect()
+    }
+
+    #[test]
+    fn virtual_cameras_are_recognised_by_quirk_profile_and_name() {
+        assert!(is_virtual_camera("OBS Virtual Camera"));
+        assert!(is_virtual_camera("NDI Virtual Input (2)"));
+        assert!(is_virtual_camera("Snap Camera"));
+        assert!(is_virtual_camera("XSplit VCam"));
+        assert!(is_virtual_camera("DroidCam Source 3"));
+        assert!(is_virtual_camera("Camo"));
+        assert!(is_virtual_camera("ManyCam Virtual Webcam"));
+    }
+
+    #[test]
+    fn real_cameras_are_not_virtual() {
+        assert!(!is_virtual_camera("Logitech BRIO"));
+        assert!(!is_virtual_camera("OBSBOT Tiny 2"));
+        assert!(!is_virtual_camera("Integrated Camera"));
+        assert!(!is_virtual_camera("Canon EOS R5"));
+        // Whole words only
+        assert!(!is_virtual_camera("Camouflage Cam"));
+    }
+
+    #[test]
+    fn unknown_device_scores_zero() {
+        let breakdown = score_device(&info("cam", "USB Camera"));
+        assert_eq!(breakdown, ScoreBreakdown::default());
+        assert_eq!(breakdown.total(), 0);
+    }
+
+    #[test]
+    fn virtual_camera_is_penalised() {
+        let breakdown = score_device(&info("obs", "OBS Virtual Camera"));
+        assert_eq!(breakdown.virtual_camera, VIRTUAL_PENALTY);
+        assert_eq!(breakdown.total(), VIRTUAL_PENALTY);
+    }
+
+    #[test]
+    fn infrared_and_depth_siblings_are_penalised() {
+        for kind in [DeviceKind::Infrared, DeviceKind::Depth] {
+            let sibling = DeviceInfo {
+                kind,
+                ..info("ir", "Logitech BRIO (Infrared)")
+            };
+            assert_eq!(score_device(&sibling).sibling, SIBLING_PENALTY);
+        }
+        assert_eq!(score_device(&info("brio", "Logitech BRIO")).sibling, 0);
+    }
+
+    #[test]
+    fn controls_score_per_control_up_to_the_cap() {
+        let with = |count| DeviceInfo {
+            control_count: count,
+            ..info("cam", "USB Camera")
+        };
+        assert_eq!(score_device(&with(3)).controls, 6);
+        assert_eq!(score_device(&with(20)).controls, MAX_CONTROLS_SCORE);
+        assert_eq!(score_device(&with(usize::MAX)).controls, MAX_CONTROLS_SCORE);
+    }
+
+    #[test]
+    fn resolution_scores_by_pixel_count_up_to_the_cap() {
+        let with = |size| DeviceInfo {
+            max_resolution: Some(size),
+            ..info("cam", "USB Camera")
+        };
+        assert_eq!(score_device(&with((640, 480))).resolution, 3);
+        assert_eq!(score_device(&with((1920, 1080))).resolution, 20);
+        assert_eq!(
+            score_device(&with((3840, 2160))).resolution,
+            MAX_RESOLUTION_SCORE
+        );
+        assert_eq!(
+            score_device(&with((u32::MAX, u32::MAX))).resolution,
+            MAX_RESOLUTION_SCORE
+        );
+    }
+
+    #[test]
+    fn probe_rewards_frames_and_penalises_silence() {
+        let probed = |result| DeviceInfo {
+            produced_frame: result,
+            ..info("cam", "USB Camera")
+        };
+        assert_eq!(score_device(&probed(Some(true))).probe, PROBE_SCORE);
+        assert_eq!(score_device(&probed(Some(false))).probe, -PROBE_SCORE);
+        assert_eq!(score_device(&probed(None)).probe, 0);
+    }
+
+    #[test]
+    fn total_sums_every_signal() {
+        let device = DeviceInfo {
+            kind: DeviceKind::Infrared,
+            control_count: 5,
+            max_resolution: Some((1920, 1080)),
+            produced_frame: Some(true),
+            ..info("cam", "Virtual IR")
+        };
+        let breakdown = score_device(&device);
+        assert_eq!(
+            breakdown,
+            ScoreBreakdown {
+                virtual_camera: VIRTUAL_PENALTY,
+                sibling: SIBLING_PENALTY,
+                controls: 10,
+                resolution: 20,
+                probe: PROBE_SCORE,
+            }
+        );
+        assert_eq!(breakdown.total(), -100 - 200 + 10 + 20 + 50);
+    }
+
+    #[test]
+    fn real_camera_outranks_ir_sibling_and_virtual_camera_listed_first() {
+        let devices = [
+            DeviceInfo {
+                kind: DeviceKind::Infrared,
+                control_count: 4,
+                max_resolution: Some((640, 360)),
+                ..info("brio-ir", "Logitech BRIO (Infrared)")
+            },
+            DeviceInfo {
+                control_count: 6,
+                max_resolution: Some((1920, 1080)),
+                ..info("obs", "OBS Virtual Camera")
+            },
+            DeviceInfo {
+                control_count: 14,
+                max_resolution: Some((4096, 2160)),
+                ..info("brio", "Logitech BRIO")
+            },
+        ];
+        assert_eq!(ids(&rank_devices(&devices)), ["brio", "obs", "brio-ir"]);
+    }
+
+    #[test]
+    fn richer_camera_wins_between_real_cameras() {
+        let devices = [
+            DeviceInfo {
+                control_count: 6,
+                max_resolution: Some((1280, 720)),
+                ..info("integrated", "Integrated Camera")
+            },
+            DeviceInfo {
+                control_count: 12,
+                max_resolution: Some((1920, 1080)),
+                ..info("c920", "HD Pro Webcam C920")
+            },
+        ];
+        let ranked = rank_devices(&devices);
+        assert_eq!(ids(&ranked), ["c920", "integrated"]);
+        assert_eq!(ranked[0].score, 24 + 20);
+        assert_eq!(ranked[1].score, 12 + 9);
+    }
+
+    #[test]
+    fn camera_that_produced_no_frame_drops_below_one_that_did() {
+        let devices = [
+            DeviceInfo {
+                control_count: 14,
+                max_resolution: Some((1920, 1080)),
+                produced_frame: Some(false),
+                ..info("c920", "HD Pro Webcam C920")
+            },
+            DeviceInfo {
+                control_count: 6,
+                max_resolution: Some((1280, 720)),
+                produced_frame: Some(true),
+                ..info("integrated", "Integrated Camera")
+            },
+        ];
+        assert_eq!(ids(&rank_devices(&devices)), ["integrated", "c920"]);
+    }
+
+    #[test]
+    fn ties_keep_enumeration_order() {
+        let devices = [
+            info("a", "USB Camera"),
+            info("b", "USB Camera"),
+            info("c", "USB Camera"),
+        ];
+        assert_eq!(ids(&rank_devices(&devices)), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn no_devices_ranks_nothing() {
+        assert!(rank_devices(&[]).is_empty());
+    }
+
+    #[test]
+    fn suggestion_serialises_with_breakdown_in_camel_case() {
+        let ranked = rank_devices(&[info("obs", "OBS Virtual Camera")]);
+        let json = serde_json::to_value(&ranked[0]).unwrap();
+        assert_eq!(json["deviceId"], "obs");
+        assert_eq!(json["score"], VIRTUAL_PENALTY);
+        assert_eq!(json["breakdown"]["virtualCamera"], VIRTUAL_PENALTY);
+        assert_eq!(json["breakdown"]["probe"], 0);
+    }
+}
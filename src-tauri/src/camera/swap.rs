@@ -0,0 +1,574 @@
+//! Runtime backend replacement.
+//!
+//! `SwappableBackend` forwards every `CameraBackend` call to whichever
+//! backend is current, so commands keep working while the backend is rebuilt
+//! (e.g. when the Canon integration is switched on or off). Hotplug
+//! callbacks are registered through a `HotplugSubscription` — a guard that
+//! stops forwarding once cancelled, because backends can't unregister their
+//! watchers.
+
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::error::Result;
+use crate::camera::types::{
+    BackendCapabilities, CameraDevice, ControlDescriptor, ControlId, ControlValue, DeviceId,
+    FormatDescriptor, HotplugEvent,
+};
+
+/// Hotplug callback shared between successive backends.
+pub type SharedHotplugCallback = Arc<dyn Fn(HotplugEvent) + Send + Sync>;
+
+/// Keeps a hotplug registration live. Once cancelled (or dropped) the
+/// backend's callback becomes a no-op.
+pub struct HotplugSubscription {
+    active: Arc<Mutex<bool>>,
+}
+
+impl HotplugSubscription {
+    /// Register `callback` with `backend`, gated by the returned guard.
+    pub fn subscribe(backend: &dyn CameraBackend, callback: SharedHotplugCallback) -> Result<Self> {
+        let active = Arc::new(Mutex::new(true));
+        let gate = Arc::clone(&active);
+        backend.watch_hotplug(Box::new(move |event| {
+            // Held for the whole call so `cancel` waits for in-flight events
+            let active = gate.lock().unwrap_or_else(|e| e.into_inner());
+            if *active {
+                callback(event);
+            }
+        }))?;
+        Ok(Self { active })
+    }
+
+    /// Stop forwarding events. Blocks until any callback in progress has
+    /// returned, so nothing is delivered from this registration afterwards.
+    pub fn cancel(&self) {
+        *self.active.lock().unwrap_or_else(|e| e.into_inner()) = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for HotplugSubscription {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Result of replacing the backend.
+#[derive(Debug, Clone)]
+pub struct SwapOutcome {
+    /// Devices reported by the new backend.
+    pub devices: Vec<CameraDevice>,
+    /// Connect/disconnect events between the old and new device lists,
+    /// already delivered to the hotplug callback.
+    pub events: Vec<HotplugEvent>,
+}
+
+/// Events that turn the `before` device list into `after`: disconnects
+/// first, then connects, each in list order.
+pub fn device_diff(before: &[CameraDevice], after: &[CameraDevice]) -> Vec<HotplugEvent> {
+    let gone = before
+        .iter()
+        .filter(|old| !after.iter().any(|new| new.id == old.id))
+        .map(|old| HotplugEvent::Disconnected { id: old.id.clone() });
+    let added = after
+        .iter()
+        .filter(|new| !before.iter().any(|old| old.id == new.id))
+        .map(|new| HotplugEvent::Connected(new.clone()));
+    gone.chain(added).collect()
+}
+
+/// A `CameraBackend` that can be replaced at runtime.
+pub struct SwappableBackend {
+    current: RwLock<Arc<dyn CameraBackend>>,
+    callback: Mutex<Option<SharedHotplugCallback>>,
+    subscription: Mutex<Option<HotplugSubscription>>,
+    /// Devices last seen through enumeration or hotplug, used to work out
+    /// what changed across a swap.
+    known: Arc<Mutex<Vec<CameraDevice>>>,
+    /// Serialises swaps so two rebuilds can't interleave their steps.
+    swapping: Mutex<()>,
+}
+
+impl SwappableBackend {
+    pub fn new(backend: Box<dyn CameraBackend>) -> Self {
+        Self {
+            current: RwLock::new(Arc::from(backend)),
+            callback: Mutex::new(None),
+            subscription: Mutex::new(None),
+            known: Arc::new(Mutex::new(Vec::new())),
+            swapping: Mutex::new(()),
+        }
+    }
+
+    /// The backend calls are currently forwarded to.
+    pub fn current(&self) -> Arc<dyn CameraBackend> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Devices last reported by enumeration or hotplug. Doesn't query the
+    /// backend.
+    pub fn known_devices(&self) -> Vec<CameraDevice> {
+        self.lock_known().clone()
+    }
+
+    /// The last known device with `id`, as reported by enumeration or
+    /// hotplug. Doesn't query the backend.
+    pub fn known_device(&self, id: &DeviceId) -> Option<CameraDevice> {
+        self.lock_known().iter().find(|d| &d.id == id).cloned()
+    }
+
+    /// Whether a hotplug registration is currently live.
+    pub fn is_watching(&self) -> bool {
+        self.lock_subscription()
+            .as_ref()
+            .is_some_and(HotplugSubscription::is_active)
+    }
+
+    /// Replace the backend with the one `build` returns.
+    ///
+    /// Runs in a fixed order so no callback fires on the old backend once it
+    /// has been replaced and no device change goes unreported:
+    ///
+    /// 1. cancel the old hotplug subscription
+    /// 2. build the new backend
+    /// 3. enumerate it
+    /// 4. make it current
+    /// 5. deliver connect/disconnect events for the difference
+    /// 6. subscribe to its hotplug events
+    ///
+    /// If enumeration fails the old backend stays current and its watchers
+    /// are restarted.
+    pub fn swap(&self, build: impl FnOnce() -> Box<dyn CameraBackend>) -> Result<SwapOutcome> {
+        let _swapping = self.swapping.lock().unwrap_or_else(|e| e.into_inner());
+        let callback = self.lock_callback().clone();
+        if let Some(subscription) = self.lock_subscription().take() {
+            subscription.cancel();
+        }
+
+        let backend: Arc<dyn CameraBackend> = Arc::from(build());
+        let devices = match backend.enumerate_devices() {
+            Ok(devices) => devices,
+            Err(e) => {
+                if let Some(callback) = callback {
+                    self.resubscribe(&*self.current(), callback);
+                }
+                return Err(e);
+            }
+        };
+
+        let old = std::mem::replace(
+            &mut *self.current.write().unwrap_or_else(|e| e.into_inner()),
+            Arc::clone(&backend),
+        );
+        let events = {
+            let mut known = self.lock_known();
+            let events = device_diff(&known, &devices);
+            *known = devices.clone();
+            events
+        };
+
+        if let Some(callback) = callback {
+            for event in &events {
+                callback(event.clone());
+            }
+            self.resubscribe(&*backend, callback);
+        }
+
+        // Drop the old backend only once nothing refers to it any more
+        drop(old);
+        Ok(SwapOutcome { devices, events })
+    }
+
+    /// Subscribe `callback` to `backend`, keeping `known` current from its
+    /// events. Replaces (and cancels) any previous subscription.
+    fn subscribe(
+        &self,
+        backend: &dyn CameraBackend,
+        callback: SharedHotplugCallback,
+    ) -> Result<()> {
+        if let Some(old) = self.lock_subscription().take() {
+            old.cancel();
+        }
+        let known = Arc::clone(&self.known);
+        let tracked: SharedHotplugCallback = Arc::new(move |event: HotplugEvent| {
+            track_event(&known, &event);
+            callback(event);
+        });
+        let subscription = HotplugSubscription::subscribe(backend, tracked)?;
+        *self.lock_subscription() = Some(subscription);
+        Ok(())
+    }
+
+    /// `subscribe`, logging failures — the swap itself has already happened.
+    fn resubscribe(&self, backend: &dyn CameraBackend, callback: SharedHotplugCallback) {
+        if let Err(e) = self.subscribe(backend, callback) {
+            tracing::warn!("Backend hotplug registration failed: {e}");
+        }
+    }
+
+    fn lock_callback(&self) -> std::sync::MutexGuard<'_, Option<SharedHotplugCallback>> {
+        self.callback.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_subscription(&self) -> std::sync::MutexGuard<'_, Option<HotplugSubscription>> {
+        self.subscription.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_known(&self) -> std::sync::MutexGuard<'_, Vec<CameraDevice>> {
+        self.known.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Apply a hotplug event to the known device list.
+fn track_event(known: &Mutex<Vec<CameraDevice>>, event: &HotplugEvent) {
+    let mut known = known.lock().unwrap_or_else(|e| e.into_inner());
+    match event {
+        HotplugEvent::Connected(device) => {
+            known.retain(|d| d.id != device.id);
+            known.push(device.clone());
+        }
+        HotplugEvent::Disconnected { id } => known.retain(|d| &d.id != id),
+    }
+}
+
+impl CameraBackend for SwappableBackend {
+    fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
+        let devices = self.current().enumerate_devices()?;
+        *self.lock_known() = devices.clone();
+        Ok(devices)
+    }
+
+    /// Register the hotplug callback. It stays registered across swaps.
+    fn watch_hotplug(&self, callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
+        let callback = Mutex::new(callback);
+        let shared: SharedHotplugCallback = Arc::new(move |event| {
+            if let Ok(cb) = callback.lock() {
+                cb(event);
+            }
+        });
+        *self.lock_callback() = Some(Arc::clone(&shared));
+        self.subscribe(&*self.current(), shared)
+    }
+
+    fn get_controls(&self, id: &DeviceId) -> Result<Vec<ControlDescriptor>> {
+        self.current().get_controls(id)
+    }
+
+    fn get_control(&self, id: &DeviceId, control: &ControlId) -> Result<ControlValue> {
+        self.current().get_control(id, control)
+    }
+
+    fn set_control(&self, id: &DeviceId, control: &ControlId, value: ControlValue) -> Result<()> {
+        self.current().set_control(id, control, value)
+    }
+
+    fn set_control_auto(&self, id: &DeviceId, control: &ControlId, auto: bool) -> Result<()> {
+        self.current().set_control_auto(id, control, auto)
+    }
+
+    fn set_control_by_str(&self, id: &DeviceId, control: &str, value: ControlValue) -> Result<()> {
+        self.current().set_control_by_str(id, control, value)
+    }
+
+    fn get_formats(&self, id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
+        self.current().get_formats(id)
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.current().capabilities()
+    }
+
+    fn device_capabilities(&self, id: &DeviceId) -> Result<BackendCapabilities> {
+        self.current().device_capabilities(id)
+    }
+
+    fn get_device(&self, id: &DeviceId) -> Result<Option<CameraDevice>> {
+        self.current().get_device(id)
+    }
+
+    fn take_photo(&self, id: &DeviceId) -> Result<()> {
+        self.current().take_photo(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::error::CameraError;
+    use crate::camera::types::DeviceKind;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+    type CallbackSlot = Arc<Mutex<Option<Box<dyn Fn(HotplugEvent) + Send>>>>;
+
+    /// Backend with a fixed device list that logs enumerate/watch calls and
+    /// keeps its hotplug callback so tests can fire events from it.
+    struct StubBackend {
+        name: &'static str,
+        devices: Vec<CameraDevice>,
+        fail_enumerate: bool,
+        callback: CallbackSlot,
+        log: Log,
+    }
+
+    impl StubBackend {
+        fn new(name: &'static str, device_ids: &[&str], log: &Log) -> (Self, CallbackSlot) {
+            let callback: CallbackSlot = Arc::new(Mutex::new(None));
+            let backend = Self {
+                name,
+                devices: device_ids.iter().map(|id| device(id)).collect(),
+                fail_enumerate: false,
+                callback: Arc::clone(&callback),
+                log: Arc::clone(log),
+            };
+            (backend, callback)
+        }
+    }
+
+    impl CameraBackend for StubBackend {
+        fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("enumerate {}", self.name));
+            if self.fail_enumerate {
+                return Err(CameraError::Enumeration("stub".into()));
+            }
+            Ok(self.devices.clone())
+        }
+
+        fn watch_hotplug(&self, callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("watch {}", self.name));
+            *self.callback.lock().unwrap() = Some(callback);
+            Ok(())
+        }
+
+        fn get_controls(&self, id: &DeviceId) -> Result<Vec<ControlDescriptor>> {
+            if self.devices.iter().any(|d| &d.id == id) {
+                Ok(vec![])
+            } else {
+                Err(CameraError::DeviceNotFound(id.to_string()))
+            }
+        }
+
+        fn get_control(&self, id: &DeviceId, _control: &ControlId) -> Result<ControlValue> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn set_control(
+            &self,
+            id: &DeviceId,
+            _control: &ControlId,
+            _value: ControlValue,
+        ) -> Result<()> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn set_control_auto(&self, id: &DeviceId, _control: &ControlId, _auto: bool) -> Result<()> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn get_formats(&self, _id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
+            Ok(vec![])
+        }
+    }
+
+    fn device(id: &str) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: id.to_string(),
+            device_path: format!("path://{id}"),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn fire(slot: &CallbackSlot, event: HotplugEvent) {
+        if let Some(cb) = slot.lock().unwrap().as_ref() {
+            cb(event);
+        }
+    }
+
+    fn describe(event: &HotplugEvent) -> String {
+        match event {
+            HotplugEvent::Connected(d) => format!("+{}", d.id),
+            HotplugEvent::Disconnected { id } => format!("-{id}"),
+        }
+    }
+
+    /// Swappable backend over `old`, watching with a callback that records
+    /// every delivered event.
+    fn watched(old: StubBackend) -> (SwappableBackend, Log) {
+        let received: Log = Arc::new(Mutex::new(Vec::new()));
+        let swappable = SwappableBackend::new(Box::new(old));
+        let sink = Arc::clone(&received);
+        swappable
+            .watch_hotplug(Box::new(move |event| {
+                sink.lock().unwrap().push(describe(&event));
+            }))
+            .unwrap();
+        (swappable, received)
+    }
+
+    #[test]
+    fn device_diff_reports_disconnects_then_connects() {
+        let before = [device("usb:a"), device("canon:b")];
+        let after = [device("usb:a"), device("usb:c")];
+        let events: Vec<_> = device_diff(&before, &after).iter().map(describe).collect();
+        assert_eq!(events, ["-canon:b", "+usb:c"]);
+    }
+
+    #[test]
+    fn subscription_stops_forwarding_once_cancelled() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (backend, slot) = StubBackend::new("old", &[], &log);
+        let count = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&count);
+        let subscription = HotplugSubscription::subscribe(
+            &backend,
+            Arc::new(move |_| *counter.lock().unwrap() += 1),
+        )
+        .unwrap();
+
+        fire(&slot, HotplugEvent::Connected(device("usb:a")));
+        subscription.cancel();
+        fire(&slot, HotplugEvent::Connected(device("usb:b")));
+        drop(subscription);
+        fire(&slot, HotplugEvent::Connected(device("usb:c")));
+
+        assert_eq!(*count.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn swap_runs_steps_in_order() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, _) = StubBackend::new("old", &["usb:a"], &log);
+        let (swappable, _) = watched(old);
+        swappable.enumerate_devices().unwrap();
+        log.lock().unwrap().clear();
+
+        let build_log = Arc::clone(&log);
+        let (new, _) = StubBackend::new("new", &["usb:a"], &log);
+        swappable
+            .swap(move || {
+                build_log.lock().unwrap().push("build".to_string());
+                Box::new(new)
+            })
+            .unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["build", "enumerate new", "watch new"]
+        );
+        assert!(swappable.is_watching());
+    }
+
+    #[test]
+    fn no_callbacks_fire_from_old_backend_after_swap() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, old_slot) = StubBackend::new("old", &["usb:a"], &log);
+        let (swappable, received) = watched(old);
+        swappable.enumerate_devices().unwrap();
+
+        let (new, new_slot) = StubBackend::new("new", &["usb:a"], &log);
+        swappable.swap(|| Box::new(new)).unwrap();
+
+        fire(&old_slot, HotplugEvent::Connected(device("usb:stale")));
+        fire(&new_slot, HotplugEvent::Connected(device("usb:fresh")));
+
+        assert_eq!(*received.lock().unwrap(), ["+usb:fresh"]);
+    }
+
+    #[test]
+    fn swap_reports_devices_added_and_removed_by_the_rebuild() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, _) = StubBackend::new("old", &["usb:a"], &log);
+        let (swappable, received) = watched(old);
+        swappable.enumerate_devices().unwrap();
+
+        // Enabling Canon adds its camera
+        let (with_canon, _) = StubBackend::new("with-canon", &["usb:a", "canon:r5"], &log);
+        let outcome = swappable.swap(|| Box::new(with_canon)).unwrap();
+        assert_eq!(outcome.devices.len(), 2);
+        assert_eq!(*received.lock().unwrap(), ["+canon:r5"]);
+
+        // Disabling it removes the camera again
+        let (without, _) = StubBackend::new("without", &["usb:a"], &log);
+        swappable.swap(|| Box::new(without)).unwrap();
+        assert_eq!(*received.lock().unwrap(), ["+canon:r5", "-canon:r5"]);
+    }
+
+    #[test]
+    fn hotplug_during_old_backend_is_not_replayed_or_lost() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, old_slot) = StubBackend::new("old", &["usb:a"], &log);
+        let (swappable, received) = watched(old);
+        swappable.enumerate_devices().unwrap();
+
+        // Seen live before the swap, so the swap must not report it again
+        fire(&old_slot, HotplugEvent::Connected(device("usb:b")));
+
+        // Unplugged while watchers were down: only the diff can report it
+        let (new, _) = StubBackend::new("new", &["usb:b"], &log);
+        swappable.swap(|| Box::new(new)).unwrap();
+
+        assert_eq!(*received.lock().unwrap(), ["+usb:b", "-usb:a"]);
+    }
+
+    #[test]
+    fn known_device_tracks_enumeration_and_hotplug() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, slot) = StubBackend::new("old", &["usb:a"], &log);
+        let (swappable, _) = watched(old);
+        assert!(swappable.known_device(&DeviceId::new("usb:a")).is_none());
+
+        swappable.enumerate_devices().unwrap();
+        assert!(swappable.known_device(&DeviceId::new("usb:a")).is_some());
+
+        fire(
+            &slot,
+            HotplugEvent::Disconnected {
+                id: DeviceId::new("usb:a"),
+            },
+        );
+        assert!(swappable.known_device(&DeviceId::new("usb:a")).is_none());
+    }
+
+    #[test]
+    fn calls_are_routed_to_the_current_backend() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, _) = StubBackend::new("old", &["usb:a"], &log);
+        let swappable = SwappableBackend::new(Box::new(old));
+        assert!(swappable.get_controls(&DeviceId::new("usb:a")).is_ok());
+
+        let (new, _) = StubBackend::new("new", &["canon:r5"], &log);
+        swappable.swap(|| Box::new(new)).unwrap();
+
+        assert!(swappable.get_controls(&DeviceId::new("usb:a")).is_err());
+        assert!(swappable.get_controls(&DeviceId::new("canon:r5")).is_ok());
+    }
+
+    #[test]
+    fn failed_enumeration_keeps_old_backend_and_its_watcher() {
+        let log: Log = Arc::new(Mutex::new(Vec::new()));
+        let (old, old_slot) = StubBackend::new("old", &["usb:a"], &log);
+        let (swappable, received) = watched(old);
+
+        let (mut broken, _) = StubBackend::new("broken", &["canon:r5"], &log);
+        broken.fail_enumerate = true;
+        assert!(swappable.swap(|| Box::new(broken)).is_err());
+
+        assert!(swappable.get_controls(&DeviceId::new("usb:a")).is_ok());
+        fire(&old_slot, HotplugEvent::Connected(device("usb:b")));
+        assert_eq!(*received.lock().unwrap(), ["+usb:b"]);
+    }
+}
@@ -0,0 +1,268 @@
+// Onboard LED as a tally light.
+//
+// UVC has no standard LED control, so cameras that let software drive
+// their LED do it through a vendor extension unit (XU): a block of
+// vendor-defined controls addressed by the unit's GUID and a control
+// selector. `XU_LEDS` lists the LED controls whose layout is known; a
+// camera exposes the tally light if its driver answers for one of them.
+//
+// In automatic mode the lights follow what's live: making a camera the
+// default, or activating a scene, lights the cameras going live and turns
+// every other camera's light off.
+
+use crate::camera::types::{ControlDescriptor, ControlFlags, ControlId, ControlType};
+use crate::scene::types::{Scene, SceneAction};
+
+/// An LED control in a vendor extension unit. The control's data is read,
+/// the mode byte changed and the whole buffer written back, so other
+/// fields (blink frequency, say) keep their values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XuLed {
+    pub vendor: &'static str,
+    /// GUID of the extension unit.
+    pub unit: u128,
+    /// Control selector within the unit.
+    pub selector: u32,
+    /// Length of the control's data.
+    pub size: usize,
+    /// Offset of the mode byte within the data.
+    pub mode_offset: usize,
+    pub mode_on: u8,
+    pub mode_off: u8,
+}
+
+/// Logitech's user hardware control unit, LED 1 mode: 0 off, 1 on, 2 blink,
+/// 3 auto (lit while streaming), followed by two bytes of blink frequency.
+pub const LOGITECH_LED1: XuLed = XuLed {
+    vendor: "Logitech",
+    unit: 0x63610682_5070_49ab_b8cc_b3855e8d221f,
+    selector: 1,
+    size: 3,
+    mode_offset: 0,
+    mode_on: 1,
+    mode_off: 0,
+};
+
+/// LED controls tried in order when querying a camera.
+pub const XU_LEDS: &[XuLed] = &[LOGITECH_LED1];
+
+impl XuLed {
+    /// Control value for the mode in `data`: 1 when the LED is held on.
+    /// Other modes (blinking, lit while streaming) read as off, as they
+    /// don't say the camera is live.
+    pub fn value_from(&self, data: &[u8]) -> Option<i32> {
+        let mode = *data.get(self.mode_offset)?;
+        Some(i32::from(mode == self.mode_on))
+    }
+
+    /// `data` with the mode set for `on`. Fails if the driver returned less
+    /// data than the layout needs.
+    pub fn with_mode(&self, data: &[u8], on: bool) -> Result<Vec<u8>, String> {
+        if data.len() <= self.mode_offset {
+            return Err(format!(
+                "{} LED control returned {} bytes, expected {}",
+                self.vendor,
+                data.len(),
+                self.size
+            ));
+        }
+        let mut data = data.to_vec();
+        data[self.mode_offset] = if on { self.mode_on } else { self.mode_off };
+        Ok(data)
+    }
+}
+
+/// Descriptor for a tally light currently `current` (0 off, 1 on).
+pub fn descriptor(current: i32) -> ControlDescriptor {
+    let control = ControlId::TallyLight;
+    ControlDescriptor {
+        id: control.as_id_str().to_string(),
+        name: control.display_name().to_string(),
+        control_type: ControlType::Toggle,
+        group: control.group().to_string(),
+        min: Some(0),
+        max: Some(1),
+        step: Some(1),
+        default: Some(0),
+        default_auto: false,
+        current,
+        flags: ControlFlags {
+            supports_auto: false,
+            is_auto_enabled: false,
+            is_read_only: false,
+        },
+        options: None,
+        supported: true,
+    }
+}
+
+/// Whether `controls` include a tally light.
+pub fn has_tally(controls: &[ControlDescriptor]) -> bool {
+    controls
+        .iter()
+        .any(|c| c.id == ControlId::TallyLight.as_id_str())
+}
+
+/// What just went live.
+#[derive(Debug, Clone, Copy)]
+pub enum TallyTrigger<'a> {
+    /// A camera was made the default.
+    DefaultCamera(&'a str),
+    /// A scene was activated.
+    Scene(&'a Scene),
+}
+
+impl TallyTrigger<'_> {
+    /// Device IDs of the cameras that are live. A scene's cameras are live
+    /// unless their actions end by stopping or pausing the preview.
+    pub fn live(&self) -> Vec<&str> {
+        match self {
+            Self::DefaultCamera(device_id) => vec![device_id],
+            Self::Scene(scene) => scene
+                .cameras
+                .iter()
+                .filter(|camera| {
+                    !camera
+                        .actions
+                        .iter()
+                        .rev()
+                        .find(|action| is_preview_action(action))
+                        .is_some_and(|action| {
+                            matches!(action, SceneAction::StopPreview | SceneAction::PausePreview)
+                        })
+                })
+                .map(|camera| camera.device_id.as_str())
+                .collect(),
+        }
+    }
+}
+
+fn is_preview_action(action: &SceneAction) -> bool {
+    matches!(
+        action,
+        SceneAction::StartPreview | SceneAction::StopPreview | SceneAction::PausePreview
+    )
+}
+
+/// Light state for each camera in `capable` (those with a tally light):
+/// on for the live ones, off for the rest. Cameras without a tally light
+/// are left out, live or not. Sorted by device ID, without duplicates.
+pub fn plan_tally<'a>(
+    capable: impl IntoIterator<Item = &'a str>,
+    trigger: TallyTrigger<'_>,
+) -> Vec<(String, bool)> {
+    let live = trigger.live();
+    let mut plan: Vec<(String, bool)> = capable
+        .into_iter()
+        .map(|device_id| (device_id.to_string(), live.contains(&device_id)))
+        .collect();
+    plan.sort();
+    plan.dedup();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::types::SceneCamera;
+
+    fn scene(cameras: &[(&str, Vec<SceneAction>)]) -> Scene {
+        Scene {
+            name: "Live".to_string(),
+            cameras: cameras
+                .iter()
+                .map(|(device_id, actions)| SceneCamera {
+                    device_id: device_id.to_string(),
+                    actions: actions.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn plan(capable: &[&str], trigger: TallyTrigger) -> Vec<(String, bool)> {
+        plan_tally(capable.iter().copied(), trigger)
+    }
+
+    #[test]
+    fn descriptor_is_an_advanced_toggle() {
+        let desc = descriptor(1);
+        assert_eq!(desc.id, "tally_light");
+        assert_eq!(desc.control_type, ControlType::Toggle);
+        assert_eq!(desc.group, "advanced");
+        assert_eq!((desc.min, desc.max), (Some(0), Some(1)));
+        assert_eq!(desc.current, 1);
+        assert!(has_tally(&[desc]));
+        assert!(!has_tally(&[]));
+    }
+
+    #[test]
+    fn logitech_mode_byte_round_trips() {
+        let led = LOGITECH_LED1;
+        assert_eq!(led.value_from(&[1, 0, 5]), Some(1));
+        assert_eq!(led.value_from(&[0, 0, 5]), Some(0));
+        // Lit while streaming isn't held on
+        assert_eq!(led.value_from(&[3, 0, 5]), Some(0));
+        assert_eq!(led.value_from(&[]), None);
+
+        assert_eq!(led.with_mode(&[3, 0, 5], true).unwrap(), vec![1, 0, 5]);
+        assert_eq!(led.with_mode(&[1, 0, 5], false).unwrap(), vec![0, 0, 5]);
+        assert!(led.with_mode(&[], true).is_err());
+    }
+
+    #[test]
+    fn default_camera_lights_alone() {
+        assert_eq!(
+            plan(&["b", "a", "c"], TallyTrigger::DefaultCamera("b")),
+            vec![
+                ("a".to_string(), false),
+                ("b".to_string(), true),
+                ("c".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn cameras_without_a_light_are_skipped() {
+        assert_eq!(
+            plan(&["a"], TallyTrigger::DefaultCamera("b")),
+            vec![("a".to_string(), false)]
+        );
+        assert!(plan(&[], TallyTrigger::DefaultCamera("b")).is_empty());
+    }
+
+    #[test]
+    fn scene_lights_its_live_cameras() {
+        let scene = scene(&[
+            ("a", vec![SceneAction::StartPreview]),
+            ("b", vec![]),
+            ("c", vec![SceneAction::StopPreview]),
+            (
+                "d",
+                vec![SceneAction::PausePreview, SceneAction::StartPreview],
+            ),
+            (
+                "e",
+                vec![SceneAction::StartPreview, SceneAction::PausePreview],
+            ),
+        ]);
+        assert_eq!(
+            plan(&["a", "b", "c", "d", "e", "f"], TallyTrigger::Scene(&scene)),
+            vec![
+                ("a".to_string(), true),
+                ("b".to_string(), true),
+                ("c".to_string(), false),
+                ("d".to_string(), true),
+                ("e".to_string(), false),
+                ("f".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates_are_planned_once() {
+        assert_eq!(
+            plan(&["a", "a"], TallyTrigger::DefaultCamera("a")),
+            vec![("a".to_string(), true)]
+        );
+    }
+}
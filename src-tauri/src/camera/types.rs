@@ -1,6 +1,21 @@
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt;
 
+use crate::i18n::{self, Domain};
+
+/// Longest serial or friendly name kept verbatim in a `DeviceId`. Longer
+/// ones (docks and hubs can produce hundreds of characters) are hashed so
+/// IDs stay short enough for settings keys and logs.
+const MAX_ID_PART_LEN: usize = 32;
+
+/// Longest device path written to logs and error messages unabbreviated.
+const MAX_LOGGED_PATH_CHARS: usize = 80;
+
+/// Reference strings Windows may append to a device interface path. The
+/// same interface is reported with and without them.
+const DEVICE_PATH_SUFFIXES: &[&str] = &[r"\global"];
+
 /// Stable camera identifier (VID:PID + serial or hash of device path).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DeviceId(String);
@@ -15,35 +30,58 @@ impl DeviceId {
     ///
     /// Windows USB device paths typically contain `vid_XXXX&pid_XXXX`.
     /// Falls back to a hash of the full path if VID/PID cannot be extracted.
+    ///
+    /// The path is normalised first, so every spelling of the same path
+    /// gives the same ID.
     pub fn from_device_path(path: &str) -> Self {
-        let lower = path.to_lowercase();
+        let path = normalise_device_path(path);
 
-        let vid = extract_field(&lower, "vid_");
-        let pid = extract_field(&lower, "pid_");
+        let vid = extract_field(&path, "vid_");
+        let pid = extract_field(&path, "pid_");
 
         match (vid, pid) {
             (Some(v), Some(p)) => {
                 // Try to find a serial number (segment after pid)
-                if let Some(serial) = extract_serial(&lower) {
+                if let Some(serial) = extract_serial(&path) {
+                    if serial.len() > MAX_ID_PART_LEN {
+                        let hash = simple_hash(&serial);
+                        return Self(format!("{v}:{p}:{hash:016x}"));
+                    }
                     Self(format!("{v}:{p}:{serial}"))
                 } else {
                     // Fallback: use a hash of the full path
-                    let hash = simple_hash(path);
+                    let hash = simple_hash(&path);
                     Self(format!("{v}:{p}:{hash:016x}"))
                 }
             }
             _ => {
                 // No VID/PID found — use full path hash
-                let hash = simple_hash(path);
+                let hash = simple_hash(&path);
                 Self(format!("unknown:{hash:016x}"))
             }
         }
     }
 
+    /// ID for a device without a device path (usually a virtual camera),
+    /// derived from its friendly name.
+    pub fn from_friendly_name(name: &str) -> Self {
+        if name.len() > MAX_ID_PART_LEN {
+            Self(format!("name:{:016x}", simple_hash(name)))
+        } else {
+            Self(format!("name:{name}"))
+        }
+    }
+
     /// Return the inner string representation.
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Short fixed-length tag for places with length limits, such as thread
+    /// names. Stable across runs, but not guaranteed unique.
+    pub fn short_tag(&self) -> String {
+        short_tag(&self.0)
+    }
 }
 
 impl fmt::Display for DeviceId {
@@ -52,6 +90,69 @@ impl fmt::Display for DeviceId {
     }
 }
 
+/// Eight hex digits derived from `id`. See [`DeviceId::short_tag`].
+pub fn short_tag(id: &str) -> String {
+    format!("{:08x}", simple_hash(id) as u32)
+}
+
+/// Shorten a long device path for logs and error messages, keeping its
+/// start (bus, VID/PID) and end (interface GUID) either side of an ellipsis.
+pub fn abbreviate_path(path: &str) -> Cow<'_, str> {
+    let len = path.chars().count();
+    if len <= MAX_LOGGED_PATH_CHARS {
+        return Cow::Borrowed(path);
+    }
+    let tail_len = MAX_LOGGED_PATH_CHARS / 3;
+    let head_len = MAX_LOGGED_PATH_CHARS - tail_len - 1;
+    let head: String = path.chars().take(head_len).collect();
+    let tail: String = path.chars().skip(len - tail_len).collect();
+    Cow::Owned(format!("{head}…{tail}"))
+}
+
+/// Canonical form of a device path, for storing and comparing.
+///
+/// The same camera can be reported with different letter casing (in the
+/// VID/PID, instance ID or interface GUID) and with or without a trailing
+/// `\global`, depending on whether it was enumerated at boot or after a
+/// hotplug. This lowercases the path and strips those suffixes.
+pub fn normalise_device_path(path: &str) -> String {
+    let mut path = path.trim().to_lowercase();
+    loop {
+        let trimmed = path.trim_end_matches('\\');
+        match DEVICE_PATH_SUFFIXES
+            .iter()
+            .find_map(|suffix| trimmed.strip_suffix(suffix))
+        {
+            Some(rest) => path = rest.to_string(),
+            None => {
+                path.truncate(trimmed.len());
+                return path;
+            }
+        }
+    }
+}
+
+/// Whether two device paths name the same device, however each is spelt.
+pub fn same_device_path(a: &str, b: &str) -> bool {
+    normalise_device_path(a) == normalise_device_path(b)
+}
+
+/// USB vendor and product IDs (`vvvv:pppp`) from a device path, without the
+/// serial or any other part of the path.
+pub fn vendor_product(path: &str) -> Option<String> {
+    let lower = path.to_lowercase();
+    Some(format!(
+        "{}:{}",
+        extract_field(&lower, "vid_")?,
+        extract_field(&lower, "pid_")?
+    ))
+}
+
+/// Serial number (or instance ID) segment of a device path, lowercased.
+pub fn device_serial(path: &str) -> Option<String> {
+    extract_serial(&path.to_lowercase())
+}
+
 /// Extract a 4-char hex field from a device path (e.g. "vid_" or "pid_").
 fn extract_field(lower_path: &str, prefix: &str) -> Option<String> {
     let start = lower_path.find(prefix)? + prefix.len();
@@ -89,6 +190,22 @@ fn simple_hash(s: &str) -> u64 {
     hash
 }
 
+/// Role of a device among sibling filters of the same physical camera.
+///
+/// Some cameras (Brio, RealSense, Kinect) expose extra DirectShow filters for
+/// infrared or depth streams alongside the colour stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceKind {
+    /// The colour stream, or a standalone camera.
+    #[default]
+    Primary,
+    /// Infrared stream (e.g. Windows Hello sensors).
+    Infrared,
+    /// Depth stream.
+    Depth,
+}
+
 /// Discovered camera device.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -97,6 +214,25 @@ pub struct CameraDevice {
     pub name: String,
     pub device_path: String,
     pub is_connected: bool,
+    pub kind: DeviceKind,
+    /// For non-primary devices, the primary sibling of the same physical camera.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_id: Option<DeviceId>,
+    /// What the backend knows of the physical camera, for recognising it
+    /// when another backend exposes it too. Not sent to the frontend.
+    #[serde(skip)]
+    pub identity: Option<PhysicalIdentity>,
+    /// Set when another backend's device for the same physical camera is
+    /// preferred: that device's ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppressed_by: Option<DeviceId>,
+}
+
+/// Physical camera behind a device, as far as its backend can tell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhysicalIdentity {
+    pub serial: Option<String>,
+    pub model: Option<String>,
 }
 
 /// Identifies a specific camera control.
@@ -122,6 +258,10 @@ pub enum ControlId {
     WhiteBalance,
     BacklightCompensation,
     Gain,
+    /// Anti-flicker setting; see `camera::powerline`.
+    PowerLineFrequency,
+    /// Onboard LED used as a tally light; see `camera::tally`.
+    TallyLight,
     // Canon EDSDK properties
     Iso,
     Aperture,
@@ -130,31 +270,9 @@ pub enum ControlId {
 }
 
 impl ControlId {
-    /// Human-readable display name.
+    /// Human-readable display name, in the active locale.
     pub fn display_name(self) -> &'static str {
-        match self {
-            Self::Pan => "Pan",
-            Self::Tilt => "Tilt",
-            Self::Roll => "Roll",
-            Self::Zoom => "Zoom",
-            Self::Exposure => "Exposure",
-            Self::Iris => "Iris",
-            Self::Focus => "Focus",
-            Self::Brightness => "Brightness",
-            Self::Contrast => "Contrast",
-            Self::Hue => "Hue",
-            Self::Saturation => "Saturation",
-            Self::Sharpness => "Sharpness",
-            Self::Gamma => "Gamma",
-            Self::ColorEnable => "Colour Enable",
-            Self::WhiteBalance => "White Balance",
-            Self::BacklightCompensation => "Backlight Compensation",
-            Self::Gain => "Gain",
-            Self::Iso => "ISO",
-            Self::Aperture => "Aperture",
-            Self::ShutterSpeed => "Shutter Speed",
-            Self::ExposureCompensation => "Exposure Compensation",
-        }
+        i18n::text(Domain::Control, self.as_id_str())
     }
 
     /// Snake-case string identifier for IPC.
@@ -177,6 +295,8 @@ impl ControlId {
             Self::WhiteBalance => "white_balance",
             Self::BacklightCompensation => "backlight_compensation",
             Self::Gain => "gain",
+            Self::PowerLineFrequency => "power_line_frequency",
+            Self::TallyLight => "tally_light",
             Self::Iso => "canon_iso",
             Self::Aperture => "canon_aperture",
             Self::ShutterSpeed => "canon_shutter_speed",
@@ -184,7 +304,7 @@ impl ControlId {
         }
     }
 
-    /// Group for accordion UI section.
+    /// Group for accordion UI section. A stable id; see `group_label()`.
     pub fn group(self) -> &'static str {
         match self {
             Self::Brightness
@@ -194,14 +314,24 @@ impl ControlId {
             | Self::Sharpness
             | Self::Gamma
             | Self::Gain => "image",
-            Self::Exposure | Self::WhiteBalance | Self::BacklightCompensation => "exposure",
+            Self::Exposure
+            | Self::WhiteBalance
+            | Self::BacklightCompensation
+            | Self::PowerLineFrequency => "exposure",
             Self::Focus | Self::Zoom | Self::Iris => "focus",
-            Self::Pan | Self::Tilt | Self::Roll | Self::ColorEnable => "advanced",
+            Self::Pan | Self::Tilt | Self::Roll | Self::ColorEnable | Self::TallyLight => {
+                "advanced"
+            }
             Self::Iso | Self::Aperture | Self::ShutterSpeed | Self::ExposureCompensation => {
                 "camera"
             }
         }
     }
+
+    /// Label of `group()`, in the active locale.
+    pub fn group_label(self) -> &'static str {
+        i18n::text(Domain::Group, self.group())
+    }
 }
 
 impl ControlId {
@@ -227,6 +357,8 @@ impl ControlId {
             "white_balance" => Some(Self::WhiteBalance),
             "backlight_compensation" => Some(Self::BacklightCompensation),
             "gain" => Some(Self::Gain),
+            "power_line_frequency" => Some(Self::PowerLineFrequency),
+            "tally_light" => Some(Self::TallyLight),
             "canon_iso" => Some(Self::Iso),
             "canon_aperture" => Some(Self::Aperture),
             "canon_shutter_speed" => Some(Self::ShutterSpeed),
@@ -237,7 +369,7 @@ impl ControlId {
 }
 
 /// Type of UI control widget.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ControlType {
     Slider,
@@ -246,7 +378,7 @@ pub enum ControlType {
 }
 
 /// Control capability flags.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ControlFlags {
     pub supports_auto: bool,
@@ -255,7 +387,7 @@ pub struct ControlFlags {
 }
 
 /// A selectable option for a `ControlType::Select` control.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ControlOption {
     pub value: i32,
@@ -263,7 +395,7 @@ pub struct ControlOption {
 }
 
 /// Full metadata for a single camera control (matches frontend ControlDescriptor).
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ControlDescriptor {
     pub id: String,
@@ -274,6 +406,10 @@ pub struct ControlDescriptor {
     pub max: Option<i32>,
     pub step: Option<i32>,
     pub default: Option<i32>,
+    /// Whether the control powers on in automatic mode, so its factory
+    /// default is auto rather than `default`.
+    #[serde(default)]
+    pub default_auto: bool,
     pub current: i32,
     pub flags: ControlFlags,
     /// Available options for `ControlType::Select` controls (e.g. ISO values).
@@ -282,6 +418,20 @@ pub struct ControlDescriptor {
     pub supported: bool,
 }
 
+impl ControlDescriptor {
+    /// Whether resetting the control re-enables automatic mode instead of
+    /// writing the numeric default.
+    pub fn resets_to_auto(&self) -> bool {
+        self.flags.supports_auto && self.default_auto
+    }
+
+    /// Clamp `value` to the control's range and snap it onto its step grid.
+    /// See [`ControlValue::snapped`].
+    pub fn clamp(&self, value: i32) -> SnappedValue {
+        ControlValue::snapped(value, self.min, self.max, self.step)
+    }
+}
+
 /// A control value, clamped to valid range on construction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct ControlValue(i32);
@@ -299,19 +449,101 @@ impl ControlValue {
         Self(v)
     }
 
+    /// Clamp to [min, max] and snap to the nearest `min + n * step`, so the
+    /// value is one the driver accepts.
+    ///
+    /// Halfway values round up. A value that would round past `max` takes
+    /// the grid point below it instead; with a step larger than the range
+    /// that leaves only `min`. Steps of 0 or 1 (or negative) don't align,
+    /// and without a `min` the grid starts at 0.
+    pub fn snapped(
+        value: i32,
+        min: Option<i32>,
+        max: Option<i32>,
+        step: Option<i32>,
+    ) -> SnappedValue {
+        let clamped = Self::new(value, min, max).0;
+        let inverted = matches!((min, max), (Some(lo), Some(hi)) if lo > hi);
+        let aligned = match step {
+            Some(step) if step > 1 && !inverted => {
+                align_to_step(clamped, min.unwrap_or(0), max, step)
+            }
+            _ => clamped,
+        };
+        SnappedValue {
+            value: Self(aligned),
+            snapped: aligned != value,
+        }
+    }
+
     /// Return the raw i32 value.
     pub fn value(self) -> i32 {
         self.0
     }
 }
 
+/// The nearest `origin + n * step` to `value`, rounding halfway values up,
+/// kept at or below `max` and within `i32`.
+fn align_to_step(value: i32, origin: i32, max: Option<i32>, step: i32) -> i32 {
+    let (value, origin, step) = (i64::from(value), i64::from(origin), i64::from(step));
+    let n = (2 * (value - origin) + step).div_euclid(2 * step);
+    let mut aligned = origin + n * step;
+    let ceiling = max.map_or(i64::from(i32::MAX), i64::from);
+    if aligned > ceiling {
+        aligned -= step;
+    }
+    if aligned < i64::from(i32::MIN) {
+        aligned += step;
+    }
+    aligned as i32
+}
+
+/// A requested control value after clamping and step alignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnappedValue {
+    pub value: ControlValue,
+    /// The value differs from the one requested, because it was out of
+    /// range or between steps.
+    pub snapped: bool,
+}
+
+/// A control's current value as read back from the camera, with what a
+/// single widget needs to show it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlValueResponse {
+    pub value: i32,
+    pub is_auto_enabled: bool,
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
 /// Camera video format descriptor.
+///
+/// `fps` is the nominal (default) frame rate. When the device advertises a
+/// frame-interval range, `min_fps`/`max_fps` bound the rates it accepts.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct FormatDescriptor {
     pub width: u32,
     pub height: u32,
     pub fps: f32,
     pub pixel_format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_fps: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fps: Option<f32>,
+}
+
+impl FormatDescriptor {
+    /// Whether this format can run at `fps`, falling back to the nominal
+    /// rate when no range is advertised.
+    pub fn supports_fps(&self, fps: f32) -> bool {
+        match (self.min_fps, self.max_fps) {
+            (Some(min), Some(max)) => fps >= min - 0.01 && fps <= max + 0.01,
+            _ => (fps - self.fps).abs() < 0.01,
+        }
+    }
 }
 
 impl Eq for FormatDescriptor {}
@@ -324,18 +556,19 @@ impl PartialOrd for FormatDescriptor {
 
 impl Ord for FormatDescriptor {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // Sort by total pixels descending, then by fps descending
+        // Sort by total pixels descending, then by fps descending, then by
+        // widest advertised range (max descending, min ascending)
         let self_pixels = self.width * self.height;
         let other_pixels = other.width * other.height;
+        let cmp_f32 = |a: f32, b: f32| a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal);
+        let max_or_nominal = |f: &Self| f.max_fps.unwrap_or(f.fps);
+        let min_or_nominal = |f: &Self| f.min_fps.unwrap_or(f.fps);
         other_pixels
             .cmp(&self_pixels)
-            .then_with(|| {
-                other
-                    .fps
-                    .partial_cmp(&self.fps)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            })
+            .then_with(|| cmp_f32(other.fps, self.fps))
             .then_with(|| self.pixel_format.cmp(&other.pixel_format))
+            .then_with(|| cmp_f32(max_or_nominal(other), max_or_nominal(self)))
+            .then_with(|| cmp_f32(min_or_nominal(self), min_or_nominal(other)))
     }
 }
 
@@ -347,12 +580,78 @@ pub enum HotplugEvent {
     Disconnected { id: DeviceId },
 }
 
+/// Which optional features a backend supports, so the frontend can hide
+/// what a camera can't do rather than let it fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendCapabilities {
+    /// Live preview frames can be captured.
+    pub supports_preview: bool,
+    /// `get_formats` lists the camera's real formats rather than a
+    /// placeholder.
+    pub supports_formats: bool,
+    /// Controls can be switched between automatic and manual mode.
+    pub supports_auto_toggle: bool,
+    /// Devices are reported as they connect and disconnect.
+    pub supports_hotplug: bool,
+    /// Pan, tilt and zoom can be moved relative to their position.
+    pub supports_relative_ptz: bool,
+}
+
+impl BackendCapabilities {
+    /// Everything either `self` or `other` supports.
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            supports_preview: self.supports_preview || other.supports_preview,
+            supports_formats: self.supports_formats || other.supports_formats,
+            supports_auto_toggle: self.supports_auto_toggle || other.supports_auto_toggle,
+            supports_hotplug: self.supports_hotplug || other.supports_hotplug,
+            supports_relative_ptz: self.supports_relative_ptz || other.supports_relative_ptz,
+        }
+    }
+
+    /// Nothing at all.
+    pub fn none() -> Self {
+        Self {
+            supports_preview: false,
+            supports_formats: false,
+            supports_auto_toggle: false,
+            supports_hotplug: false,
+            supports_relative_ptz: false,
+        }
+    }
+}
+
+/// Everything the UI offered before capabilities existed, so a backend that
+/// doesn't say otherwise keeps behaving as it did. No backend moves PTZ
+/// relatively yet.
+impl Default for BackendCapabilities {
+    fn default() -> Self {
+        Self {
+            supports_preview: true,
+            supports_formats: true,
+            supports_auto_toggle: true,
+            supports_hotplug: true,
+            supports_relative_ptz: false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     // --- DeviceId tests ---
 
+    #[test]
+    fn vendor_product_and_serial_from_device_path() {
+        let path = r"\\?\USB#VID_046D&PID_085E&MI_00#6&ABC123&0&0000#{guid}";
+        assert_eq!(vendor_product(path), Some("046d:085e".to_string()));
+        assert_eq!(device_serial(path), Some("6&abc123&0&0000".to_string()));
+        assert_eq!(vendor_product("no ids here"), None);
+        assert_eq!(device_serial("no ids here"), None);
+    }
+
     #[test]
     fn device_id_creation_and_equality() {
         let id1 = DeviceId::new("046d:085e:abc123");
@@ -417,6 +716,145 @@ mod tests {
         assert!(s.starts_with("046d:085e:"), "got: {s}");
     }
 
+    /// One webcam's path as reported at boot, after a hotplug and by another
+    /// enumeration source.
+    const PATH_VARIANTS: [&str; 4] = [
+        r"\\?\usb#vid_046d&pid_085e&mi_00#6&2d5d6f8f&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global",
+        r"\\?\USB#VID_046D&PID_085E&MI_00#6&2D5D6F8F&0&0000#{65E8773D-8F56-11D0-A3B9-00A0C9223196}",
+        r"\\?\usb#vid_046d&pid_085e&mi_00#6&2d5d6f8f&0&0000#{65E8773D-8F56-11D0-A3B9-00A0C9223196}\GLOBAL",
+        r"\\?\usb#VID_046D&PID_085E&mi_00#6&2d5d6f8f&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}\global\",
+    ];
+
+    #[test]
+    fn device_path_variants_normalise_to_one_path() {
+        for variant in PATH_VARIANTS {
+            assert_eq!(
+                normalise_device_path(variant),
+                r"\\?\usb#vid_046d&pid_085e&mi_00#6&2d5d6f8f&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}",
+                "{variant}"
+            );
+            assert!(same_device_path(variant, PATH_VARIANTS[0]));
+        }
+    }
+
+    #[test]
+    fn device_path_variants_give_one_device_id() {
+        let id = DeviceId::from_device_path(PATH_VARIANTS[0]);
+        assert_eq!(id.as_str(), "046d:085e:6&2d5d6f8f&0&0000");
+        for variant in PATH_VARIANTS {
+            assert_eq!(DeviceId::from_device_path(variant), id, "{variant}");
+        }
+    }
+
+    #[test]
+    fn hashed_device_ids_ignore_path_spelling() {
+        // No usable serial and no VID/PID both fall back to a path hash
+        let no_serial = [
+            r"\\?\usb#vid_046d&pid_085e#ab#{guid}\global",
+            r"\\?\USB#VID_046D&PID_085E#AB#{GUID}",
+        ];
+        assert_eq!(
+            DeviceId::from_device_path(no_serial[0]),
+            DeviceId::from_device_path(no_serial[1])
+        );
+        let unknown = [
+            r"\\?\Root#Image#0000#{GUID}\global",
+            r"\\?\root#image#0000#{guid}",
+        ];
+        assert_eq!(
+            DeviceId::from_device_path(unknown[0]),
+            DeviceId::from_device_path(unknown[1])
+        );
+    }
+
+    #[test]
+    fn normalising_keeps_distinct_devices_apart() {
+        let other_port = PATH_VARIANTS[0].replace("0&0000", "0&0001");
+        assert!(!same_device_path(PATH_VARIANTS[0], &other_port));
+        assert_ne!(
+            DeviceId::from_device_path(PATH_VARIANTS[0]),
+            DeviceId::from_device_path(&other_port)
+        );
+        // Only a trailing `\global` is a suffix
+        let inner = r"\\?\usb#vid_046d&pid_085e#global#{guid}";
+        assert_eq!(normalise_device_path(inner), inner);
+        assert_eq!(normalise_device_path(""), "");
+        assert_eq!(normalise_device_path(" /dev/video0 "), "/dev/video0");
+    }
+
+    /// A 400-character path like those produced behind Thunderbolt docks.
+    fn long_path(serial_suffix: &str) -> String {
+        let serial = format!("9&{}&{serial_suffix}", "2f1b3c4d&0&".repeat(25));
+        let path = format!(
+            r"\\?\usb#vid_046d&pid_085e&mi_00#{serial}#{{65e8773d-8f56-11d0-a3b9-00a0c9223196}}\global"
+        );
+        format!("{path}{}", "x".repeat(400 - path.len()))
+    }
+
+    #[test]
+    fn device_id_from_long_device_path_is_short() {
+        let path = long_path("0000");
+        assert_eq!(path.len(), 400);
+        let id = DeviceId::from_device_path(&path);
+        assert!(id.as_str().starts_with("046d:085e:"), "got: {id}");
+        assert!(id.as_str().len() <= 10 + 16, "got: {id}");
+        assert_eq!(id, DeviceId::from_device_path(&path));
+    }
+
+    #[test]
+    fn long_device_paths_differing_at_the_end_get_different_ids() {
+        let a = DeviceId::from_device_path(&long_path("0000"));
+        let b = DeviceId::from_device_path(&long_path("0001"));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn device_id_from_friendly_name_hashes_long_names() {
+        assert_eq!(
+            DeviceId::from_friendly_name("OBS Virtual Camera").as_str(),
+            "name:OBS Virtual Camera"
+        );
+
+        let long = "Virtual Camera ".repeat(10);
+        let id = DeviceId::from_friendly_name(&long);
+        assert_eq!(id.as_str().len(), "name:".len() + 16);
+        assert_ne!(id, DeviceId::from_friendly_name(&format!("{long}2")));
+    }
+
+    #[test]
+    fn short_tag_is_eight_hex_digits() {
+        let tag = DeviceId::from_device_path(&long_path("0000")).short_tag();
+        assert_eq!(tag.len(), 8);
+        assert!(tag.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(short_tag(&long_path("0000")).len(), 8);
+    }
+
+    #[test]
+    fn short_paths_are_logged_in_full() {
+        let path = r"\\?\usb#vid_046d&pid_085e&mi_00#6&abc12345&0&0000#{guid}";
+        assert_eq!(abbreviate_path(path), path);
+    }
+
+    #[test]
+    fn long_paths_are_abbreviated_keeping_both_ends() {
+        let path = long_path("0000");
+        let short = abbreviate_path(&path);
+        assert_eq!(short.chars().count(), MAX_LOGGED_PATH_CHARS);
+        assert!(
+            short.starts_with(r"\\?\usb#vid_046d&pid_085e"),
+            "got: {short}"
+        );
+        assert!(short.ends_with("xxx"), "got: {short}");
+        assert!(short.contains('…'));
+    }
+
+    #[test]
+    fn abbreviation_respects_multibyte_characters() {
+        let path = "камера#".repeat(40);
+        let short = abbreviate_path(&path);
+        assert_eq!(short.chars().count(), MAX_LOGGED_PATH_CHARS);
+    }
+
     // --- CameraDevice tests ---
 
     #[test]
@@ -426,6 +864,10 @@ mod tests {
             name: "Logitech BRIO".to_string(),
             device_path: r"\\?\usb#vid_046d&pid_085e".to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         };
         assert_eq!(device.name, "Logitech BRIO");
         assert!(device.is_connected);
@@ -439,10 +881,36 @@ mod tests {
             name: "Test Cam".to_string(),
             device_path: "path".to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         };
         let json = serde_json::to_value(&device).unwrap();
         assert_eq!(json["name"], "Test Cam");
         assert_eq!(json["isConnected"], true);
+        assert!(json.get("suppressedBy").is_none());
+    }
+
+    #[test]
+    fn camera_device_serialises_suppression_but_not_identity() {
+        let device = CameraDevice {
+            id: DeviceId::new("ds:eos"),
+            name: "EOS R5".to_string(),
+            device_path: "path".to_string(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: Some(PhysicalIdentity {
+                serial: Some("123456".to_string()),
+                model: Some("EOS R5".to_string()),
+            }),
+            suppressed_by: Some(DeviceId::new("canon:usb1")),
+        };
+        let json = serde_json::to_value(&device).unwrap();
+        assert_eq!(json["suppressedBy"], "canon:usb1");
+        assert!(json.get("identity").is_none());
+        assert!(!json.to_string().contains("123456"));
     }
 
     // --- ControlDescriptor tests ---
@@ -458,6 +926,7 @@ mod tests {
             max: Some(255),
             step: Some(1),
             default: Some(128),
+            default_auto: false,
             current: 128,
             flags: ControlFlags {
                 supports_auto: false,
@@ -481,10 +950,73 @@ mod tests {
         assert_eq!(json["flags"]["isAutoEnabled"], false);
         assert_eq!(json["flags"]["isReadOnly"], false);
         assert_eq!(json["supported"], true);
+        assert_eq!(json["defaultAuto"], false);
         // options should be absent (not null) when None
         assert!(json.get("options").is_none());
     }
 
+    fn exposure(supports_auto: bool, default_auto: bool) -> ControlDescriptor {
+        ControlDescriptor {
+            id: "exposure".to_string(),
+            name: "Exposure".to_string(),
+            control_type: ControlType::Slider,
+            group: "exposure".to_string(),
+            min: Some(-11),
+            max: Some(1),
+            step: Some(1),
+            default: Some(-5),
+            default_auto,
+            current: -7,
+            flags: ControlFlags {
+                supports_auto,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    #[test]
+    fn control_descriptor_round_trips_default_auto() {
+        let desc = exposure(true, true);
+        let json = serde_json::to_value(&desc).unwrap();
+        assert_eq!(json["defaultAuto"], true);
+        assert!(json.get("default_auto").is_none());
+
+        let back: ControlDescriptor = serde_json::from_value(json).unwrap();
+        assert_eq!(back, desc);
+    }
+
+    #[test]
+    fn control_descriptor_without_default_auto_deserialises_as_manual() {
+        let mut json = serde_json::to_value(exposure(true, true)).unwrap();
+        json.as_object_mut().unwrap().remove("defaultAuto");
+
+        let desc: ControlDescriptor = serde_json::from_value(json).unwrap();
+        assert!(!desc.default_auto);
+    }
+
+    #[test]
+    fn resets_to_auto_when_auto_is_supported_and_the_default() {
+        assert!(exposure(true, true).resets_to_auto());
+    }
+
+    #[test]
+    fn resets_to_value_when_auto_is_supported_but_not_the_default() {
+        assert!(!exposure(true, false).resets_to_auto());
+    }
+
+    #[test]
+    fn resets_to_value_when_auto_is_the_default_but_unsupported() {
+        assert!(!exposure(false, true).resets_to_auto());
+    }
+
+    #[test]
+    fn resets_to_value_when_auto_is_neither_supported_nor_the_default() {
+        assert!(!exposure(false, false).resets_to_auto());
+    }
+
     // --- ControlValue tests ---
 
     #[test]
@@ -511,6 +1043,123 @@ mod tests {
         assert_eq!(v.value(), 42);
     }
 
+    fn snap(value: i32, min: i32, max: i32, step: i32) -> (i32, bool) {
+        let s = ControlValue::snapped(value, Some(min), Some(max), Some(step));
+        (s.value.value(), s.snapped)
+    }
+
+    #[test]
+    fn snapped_keeps_values_on_the_grid() {
+        for v in [-8, -6, -4, -2] {
+            assert_eq!(snap(v, -8, -2, 2), (v, false));
+        }
+        assert_eq!(snap(0, 0, 255, 5), (0, false));
+        assert_eq!(snap(255, 0, 255, 5), (255, false));
+    }
+
+    #[test]
+    fn snapped_rounds_to_the_nearest_step_from_min() {
+        assert_eq!(snap(12, 0, 100, 5), (10, true));
+        assert_eq!(snap(13, 0, 100, 5), (15, true));
+        // The grid starts at min, not at zero
+        assert_eq!(snap(5, 1, 100, 4), (5, false));
+        assert_eq!(snap(6, 1, 100, 4), (5, true));
+        assert_eq!(snap(8, 1, 100, 4), (9, true));
+    }
+
+    #[test]
+    fn snapped_rounds_halfway_values_up() {
+        assert_eq!(snap(-7, -8, -2, 2), (-6, true));
+        assert_eq!(snap(5, 0, 100, 10), (10, true));
+    }
+
+    #[test]
+    fn snapped_handles_negative_ranges() {
+        assert_eq!(snap(-12, -13, -1, 3), (-13, true));
+        assert_eq!(snap(-11, -13, -1, 3), (-10, true));
+        assert_eq!(snap(-2, -13, -1, 3), (-1, true));
+        // Range straddling zero
+        assert_eq!(snap(-1, -9, 9, 3), (0, true));
+        assert_eq!(snap(2, -9, 9, 3), (3, true));
+    }
+
+    #[test]
+    fn snapped_never_rounds_past_max() {
+        // Grid 0, 3, 6, 9; max 10 isn't on it
+        assert_eq!(snap(10, 0, 10, 3), (9, true));
+        assert_eq!(snap(11, 0, 10, 4), (8, true));
+        assert_eq!(snap(500, 0, 10, 4), (8, true));
+    }
+
+    #[test]
+    fn snapped_clamps_before_aligning() {
+        assert_eq!(snap(-100, -8, -2, 2), (-8, true));
+        assert_eq!(snap(100, -8, -2, 2), (-2, true));
+        assert_eq!(snap(-9, -8, -2, 2), (-8, true));
+    }
+
+    #[test]
+    fn snapped_with_a_step_larger_than_the_range_leaves_min() {
+        assert_eq!(snap(3, 0, 4, 10), (0, true));
+        assert_eq!(snap(4, 0, 4, 10), (0, true));
+        assert_eq!(snap(0, 0, 4, 10), (0, false));
+    }
+
+    #[test]
+    fn snapped_ignores_degenerate_steps() {
+        assert_eq!(snap(7, 0, 10, 0), (7, false));
+        assert_eq!(snap(7, 0, 10, 1), (7, false));
+        assert_eq!(snap(7, 0, 10, -2), (7, false));
+        assert_eq!(snap(20, 0, 10, 0), (10, true));
+        let unbounded = ControlValue::snapped(7, None, None, None);
+        assert_eq!((unbounded.value.value(), unbounded.snapped), (7, false));
+    }
+
+    #[test]
+    fn snapped_handles_a_single_value_range() {
+        assert_eq!(snap(3, 3, 3, 2), (3, false));
+        assert_eq!(snap(9, 3, 3, 2), (3, true));
+    }
+
+    #[test]
+    fn snapped_leaves_an_inverted_range_clamped_only() {
+        assert_eq!(snap(5, 10, 0, 3), (0, true));
+    }
+
+    #[test]
+    fn snapped_without_min_aligns_from_zero() {
+        let s = ControlValue::snapped(7, None, Some(100), Some(4));
+        assert_eq!(s.value.value(), 8);
+        let s = ControlValue::snapped(-7, None, None, Some(4));
+        assert_eq!(s.value.value(), -8);
+    }
+
+    #[test]
+    fn snapped_stays_within_i32() {
+        let s = ControlValue::snapped(i32::MAX, None, None, Some(10));
+        assert_eq!(s.value.value(), 2_147_483_640);
+        let s = ControlValue::snapped(i32::MIN, None, None, Some(10));
+        assert_eq!(s.value.value(), -2_147_483_640);
+        // Grid i32::MIN, -1, i32::MAX - 1
+        let s = ControlValue::snapped(i32::MAX, Some(i32::MIN), Some(i32::MAX), Some(i32::MAX));
+        assert_eq!(s.value.value(), i32::MAX - 1);
+    }
+
+    #[test]
+    fn descriptor_clamp_uses_its_range_and_step() {
+        let mut desc = exposure(true, false);
+        desc.min = Some(-8);
+        desc.max = Some(-2);
+        desc.step = Some(2);
+        let snapped = desc.clamp(-7);
+        assert_eq!(snapped.value.value(), -6);
+        assert!(snapped.snapped);
+        assert_eq!(
+            serde_json::to_value(snapped).unwrap(),
+            serde_json::json!({ "value": -6, "snapped": true })
+        );
+    }
+
     // --- HotplugEvent tests ---
 
     #[test]
@@ -520,6 +1169,10 @@ mod tests {
             name: "Test".to_string(),
             device_path: "path".to_string(),
             is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
         };
         let event = HotplugEvent::Connected(device);
         let json = serde_json::to_value(&event).unwrap();
@@ -546,12 +1199,16 @@ mod tests {
             height: 1080,
             fps: 30.0,
             pixel_format: "MJPG".to_string(),
+            min_fps: None,
+            max_fps: None,
         };
         let f2 = FormatDescriptor {
             width: 1920,
             height: 1080,
             fps: 30.0,
             pixel_format: "MJPG".to_string(),
+            min_fps: None,
+            max_fps: None,
         };
         assert_eq!(f1, f2);
     }
@@ -563,12 +1220,16 @@ mod tests {
             height: 1080,
             fps: 30.0,
             pixel_format: "MJPG".to_string(),
+            min_fps: None,
+            max_fps: None,
         };
         let sd = FormatDescriptor {
             width: 640,
             height: 480,
             fps: 30.0,
             pixel_format: "MJPG".to_string(),
+            min_fps: None,
+            max_fps: None,
         };
 
         let mut formats = [sd.clone(), hd.clone()];
@@ -585,12 +1246,16 @@ mod tests {
             height: 1080,
             fps: 60.0,
             pixel_format: "MJPG".to_string(),
+            min_fps: None,
+            max_fps: None,
         };
         let f30 = FormatDescriptor {
             width: 1920,
             height: 1080,
             fps: 30.0,
             pixel_format: "MJPG".to_string(),
+            min_fps: None,
+            max_fps: None,
         };
 
         let mut formats = [f30.clone(), f60.clone()];
@@ -600,12 +1265,81 @@ mod tests {
         assert_eq!(formats[1], f30);
     }
 
+    #[test]
+    fn format_descriptor_ordering_widest_range_first_and_dedup_keeps_ranges() {
+        let ranged = FormatDescriptor {
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+            pixel_format: "MJPG".to_string(),
+            min_fps: Some(5.0),
+            max_fps: Some(30.0),
+        };
+        let fixed = FormatDescriptor {
+            min_fps: Some(30.0),
+            ..ranged.clone()
+        };
+
+        let mut formats = vec![fixed.clone(), ranged.clone(), ranged.clone()];
+        formats.sort();
+        formats.dedup();
+
+        assert_eq!(formats, vec![ranged, fixed]);
+    }
+
+    #[test]
+    fn format_descriptor_supports_fps_within_range() {
+        let f = FormatDescriptor {
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+            pixel_format: "MJPG".to_string(),
+            min_fps: Some(5.0),
+            max_fps: Some(30.0),
+        };
+        assert!(f.supports_fps(5.0));
+        assert!(f.supports_fps(15.0));
+        assert!(f.supports_fps(30.0));
+        assert!(!f.supports_fps(60.0));
+        assert!(!f.supports_fps(2.0));
+    }
+
+    #[test]
+    fn format_descriptor_without_range_supports_only_nominal_fps() {
+        let f = FormatDescriptor {
+            width: 640,
+            height: 480,
+            fps: 30.0,
+            pixel_format: "YUY2".to_string(),
+            min_fps: None,
+            max_fps: None,
+        };
+        assert!(f.supports_fps(30.0));
+        assert!(!f.supports_fps(15.0));
+    }
+
+    #[test]
+    fn format_descriptor_omits_missing_range_in_json() {
+        let f = FormatDescriptor {
+            width: 640,
+            height: 480,
+            fps: 30.0,
+            pixel_format: "YUY2".to_string(),
+            min_fps: None,
+            max_fps: None,
+        };
+        let json = serde_json::to_value(&f).unwrap();
+        assert!(json.get("min_fps").is_none());
+        assert!(json.get("max_fps").is_none());
+    }
+
     // --- ControlId tests ---
 
     #[test]
     fn control_id_groups_are_correct() {
         assert_eq!(ControlId::Brightness.group(), "image");
         assert_eq!(ControlId::Exposure.group(), "exposure");
+        assert_eq!(ControlId::PowerLineFrequency.group(), "exposure");
         assert_eq!(ControlId::Focus.group(), "focus");
         assert_eq!(ControlId::Pan.group(), "advanced");
         assert_eq!(ControlId::Iso.group(), "camera");
@@ -652,6 +1386,7 @@ mod tests {
             max: None,
             step: None,
             default: None,
+            default_auto: false,
             current: 0x48,
             flags: ControlFlags {
                 supports_auto: false,
@@ -747,6 +1482,29 @@ mod tests {
         assert_eq!(ControlId::from_str_id("gain"), Some(ControlId::Gain));
     }
 
+    #[test]
+    fn power_line_frequency_ids_match() {
+        let control = ControlId::PowerLineFrequency;
+        assert_eq!(control.as_id_str(), "power_line_frequency");
+        assert_eq!(
+            serde_json::to_value(control).unwrap(),
+            "power_line_frequency"
+        );
+        assert_eq!(
+            ControlId::from_str_id("power_line_frequency"),
+            Some(control)
+        );
+    }
+
+    #[test]
+    fn tally_light_ids_match() {
+        let control = ControlId::TallyLight;
+        assert_eq!(control.as_id_str(), "tally_light");
+        assert_eq!(serde_json::to_value(control).unwrap(), "tally_light");
+        assert_eq!(ControlId::from_str_id("tally_light"), Some(control));
+        assert_eq!(control.group(), "advanced");
+    }
+
     #[test]
     fn from_str_id_returns_none_for_unknown() {
         assert_eq!(ControlId::from_str_id("nonexistent"), None);
@@ -791,6 +1549,8 @@ mod tests {
             ControlId::WhiteBalance,
             ControlId::BacklightCompensation,
             ControlId::Gain,
+            ControlId::PowerLineFrequency,
+            ControlId::TallyLight,
             ControlId::Iso,
             ControlId::Aperture,
             ControlId::ShutterSpeed,
@@ -803,6 +1563,46 @@ mod tests {
                 Some(control),
                 "roundtrip failed for {str_id}"
             );
+            // Lookups fall back to the key, so a missing entry shows as one
+            assert_ne!(control.display_name(), str_id, "no catalogue entry");
+            assert_ne!(control.group_label(), control.group(), "no group label");
         }
     }
+
+    // --- BackendCapabilities tests ---
+
+    #[test]
+    fn backend_capabilities_serialise_as_camel_case_flags() {
+        let json = serde_json::to_value(BackendCapabilities::default()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "supportsPreview": true,
+                "supportsFormats": true,
+                "supportsAutoToggle": true,
+                "supportsHotplug": true,
+                "supportsRelativePtz": false,
+            })
+        );
+    }
+
+    #[test]
+    fn backend_capabilities_union_keeps_either_side() {
+        let preview_only = BackendCapabilities {
+            supports_preview: true,
+            ..BackendCapabilities::none()
+        };
+        let hotplug_only = BackendCapabilities {
+            supports_hotplug: true,
+            ..BackendCapabilities::none()
+        };
+
+        let both = preview_only.union(hotplug_only);
+        assert!(both.supports_preview && both.supports_hotplug);
+        assert!(!both.supports_formats && !both.supports_auto_toggle);
+        assert_eq!(
+            BackendCapabilities::none().union(BackendCapabilities::none()),
+            BackendCapabilities::none()
+        );
+    }
 }
@@ -0,0 +1,274 @@
+// Conversions between device-native control values and physical units.
+//
+// DirectShow reports exposure as log2 seconds (so -6 means 1/64s) and focus
+// as an opaque device-specific integer. These helpers map both onto
+// device-independent units using the control's advertised range, so values
+// can be carried between camera models.
+
+/// A native control value produced by a unit conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativeValue {
+    /// Value in the device's native scale, within its advertised range.
+    pub value: i32,
+    /// Whether the requested value fell outside the device range and was
+    /// clamped to the nearest supported value.
+    pub clamped: bool,
+}
+
+/// Clamp `value` to the optional `[min, max]` range, noting whether it moved.
+fn clamp_native(value: i64, min: Option<i32>, max: Option<i32>) -> NativeValue {
+    let lo = min.map_or(i64::from(i32::MIN), i64::from);
+    let hi = max.map_or(i64::from(i32::MAX), i64::from);
+    let clamped_value = value.clamp(lo, hi.max(lo));
+    NativeValue {
+        value: clamped_value as i32,
+        clamped: clamped_value != value,
+    }
+}
+
+/// Convert an exposure time in seconds to DirectShow log2 steps.
+///
+/// Rounds to the nearest whole step and clamps to the device range.
+/// Returns an error for non-positive or non-finite durations.
+pub fn exposure_seconds_to_native(
+    seconds: f64,
+    min: Option<i32>,
+    max: Option<i32>,
+) -> Result<NativeValue, String> {
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!(
+            "Exposure must be a positive number of seconds, got {seconds}"
+        ));
+    }
+    let steps = seconds.log2().round() as i64;
+    Ok(clamp_native(steps, min, max))
+}
+
+/// Convert DirectShow log2 exposure steps to seconds.
+pub fn exposure_native_to_seconds(native: i32) -> f64 {
+    2f64.powi(native)
+}
+
+/// Convert a normalised value in `[0, 1]` to the device's native range.
+///
+/// Out-of-range inputs are clamped (and reported as such). Requires both
+/// range bounds, since a linear mapping is meaningless without them.
+pub fn normalised_to_native(
+    value: f64,
+    min: Option<i32>,
+    max: Option<i32>,
+) -> Result<NativeValue, String> {
+    if !value.is_finite() {
+        return Err(format!("Normalised value must be finite, got {value}"));
+    }
+    let (lo, hi) = range_bounds(min, max)?;
+    let clamped = value.clamp(0.0, 1.0);
+    let native = lo as f64 + clamped * (hi as f64 - lo as f64);
+    Ok(NativeValue {
+        value: native.round() as i32,
+        clamped: clamped != value,
+    })
+}
+
+/// Convert a native value to `[0, 1]` within the device range.
+///
+/// Values outside the range are clamped. A degenerate range (min == max)
+/// maps to 0.
+pub fn native_to_normalised(
+    native: i32,
+    min: Option<i32>,
+    max: Option<i32>,
+) -> Result<f64, String> {
+    let (lo, hi) = range_bounds(min, max)?;
+    if hi == lo {
+        return Ok(0.0);
+    }
+    let value = (native as f64 - lo as f64) / (hi as f64 - lo as f64);
+    Ok(value.clamp(0.0, 1.0))
+}
+
+fn range_bounds(min: Option<i32>, max: Option<i32>) -> Result<(i32, i32), String> {
+    match (min, max) {
+        (Some(lo), Some(hi)) if lo <= hi => Ok((lo, hi)),
+        (Some(lo), Some(hi)) => Err(format!("Invalid control range [{lo}, {hi}]")),
+        _ => Err("Control does not report a range".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposure_seconds_map_to_log2_steps() {
+        let v = exposure_seconds_to_native(1.0 / 64.0, Some(-13), Some(-1)).unwrap();
+        assert_eq!(
+            v,
+            NativeValue {
+                value: -6,
+                clamped: false
+            }
+        );
+
+        let v = exposure_seconds_to_native(1.0, Some(-13), Some(0)).unwrap();
+        assert_eq!(v.value, 0);
+    }
+
+    #[test]
+    fn exposure_seconds_round_to_nearest_step() {
+        // log2(1/50) ≈ -5.64 → -6; log2(1/40) ≈ -5.32 → -5
+        assert_eq!(
+            exposure_seconds_to_native(1.0 / 50.0, Some(-13), Some(-1))
+                .unwrap()
+                .value,
+            -6
+        );
+        assert_eq!(
+            exposure_seconds_to_native(1.0 / 40.0, Some(-13), Some(-1))
+                .unwrap()
+                .value,
+            -5
+        );
+    }
+
+    #[test]
+    fn exposure_clamps_when_device_range_excludes_value() {
+        // Device only supports 1/8s..1/2048s; request 1s
+        let v = exposure_seconds_to_native(1.0, Some(-11), Some(-3)).unwrap();
+        assert_eq!(
+            v,
+            NativeValue {
+                value: -3,
+                clamped: true
+            }
+        );
+
+        // Request 1/10000s on the same device
+        let v = exposure_seconds_to_native(1.0 / 10_000.0, Some(-11), Some(-3)).unwrap();
+        assert_eq!(
+            v,
+            NativeValue {
+                value: -11,
+                clamped: true
+            }
+        );
+    }
+
+    #[test]
+    fn exposure_without_range_is_unclamped() {
+        let v = exposure_seconds_to_native(1.0 / 4096.0, None, None).unwrap();
+        assert_eq!(
+            v,
+            NativeValue {
+                value: -12,
+                clamped: false
+            }
+        );
+    }
+
+    #[test]
+    fn exposure_rejects_non_positive_or_non_finite_seconds() {
+        assert!(exposure_seconds_to_native(0.0, Some(-13), Some(0)).is_err());
+        assert!(exposure_seconds_to_native(-0.5, Some(-13), Some(0)).is_err());
+        assert!(exposure_seconds_to_native(f64::NAN, Some(-13), Some(0)).is_err());
+        assert!(exposure_seconds_to_native(f64::INFINITY, Some(-13), Some(0)).is_err());
+    }
+
+    #[test]
+    fn exposure_native_to_seconds_is_inverse() {
+        assert_eq!(exposure_native_to_seconds(-6), 1.0 / 64.0);
+        assert_eq!(exposure_native_to_seconds(0), 1.0);
+        assert_eq!(exposure_native_to_seconds(2), 4.0);
+        for step in -13..=2 {
+            let seconds = exposure_native_to_seconds(step);
+            assert_eq!(
+                exposure_seconds_to_native(seconds, None, None)
+                    .unwrap()
+                    .value,
+                step
+            );
+        }
+    }
+
+    #[test]
+    fn normalised_maps_linearly_onto_range() {
+        assert_eq!(
+            normalised_to_native(0.0, Some(0), Some(250)).unwrap().value,
+            0
+        );
+        assert_eq!(
+            normalised_to_native(1.0, Some(0), Some(250)).unwrap().value,
+            250
+        );
+        assert_eq!(
+            normalised_to_native(0.5, Some(0), Some(250)).unwrap().value,
+            125
+        );
+        // Offset, negative range
+        assert_eq!(
+            normalised_to_native(0.5, Some(-10), Some(30))
+                .unwrap()
+                .value,
+            10
+        );
+    }
+
+    #[test]
+    fn normalised_clamps_out_of_range_inputs() {
+        assert_eq!(
+            normalised_to_native(1.5, Some(0), Some(100)).unwrap(),
+            NativeValue {
+                value: 100,
+                clamped: true
+            }
+        );
+        assert_eq!(
+            normalised_to_native(-0.2, Some(0), Some(100)).unwrap(),
+            NativeValue {
+                value: 0,
+                clamped: true
+            }
+        );
+        assert!(normalised_to_native(f64::NAN, Some(0), Some(100)).is_err());
+    }
+
+    #[test]
+    fn normalised_requires_a_valid_range() {
+        assert!(normalised_to_native(0.5, None, Some(100)).is_err());
+        assert!(normalised_to_native(0.5, Some(0), None).is_err());
+        assert!(normalised_to_native(0.5, Some(100), Some(0)).is_err());
+        assert!(native_to_normalised(50, None, None).is_err());
+    }
+
+    #[test]
+    fn native_to_normalised_round_trips() {
+        assert_eq!(native_to_normalised(0, Some(0), Some(250)).unwrap(), 0.0);
+        assert_eq!(native_to_normalised(250, Some(0), Some(250)).unwrap(), 1.0);
+        assert_eq!(native_to_normalised(10, Some(-10), Some(30)).unwrap(), 0.5);
+        for native in 0..=250 {
+            let n = native_to_normalised(native, Some(0), Some(250)).unwrap();
+            assert_eq!(
+                normalised_to_native(n, Some(0), Some(250)).unwrap().value,
+                native
+            );
+        }
+    }
+
+    #[test]
+    fn native_to_normalised_handles_degenerate_and_extreme_ranges() {
+        assert_eq!(native_to_normalised(5, Some(5), Some(5)).unwrap(), 0.0);
+        assert_eq!(native_to_normalised(500, Some(0), Some(100)).unwrap(), 1.0);
+
+        let full = (Some(i32::MIN), Some(i32::MAX));
+        assert_eq!(native_to_normalised(i32::MIN, full.0, full.1).unwrap(), 0.0);
+        assert_eq!(native_to_normalised(i32::MAX, full.0, full.1).unwrap(), 1.0);
+        assert_eq!(
+            normalised_to_native(1.0, full.0, full.1).unwrap().value,
+            i32::MAX
+        );
+        assert_eq!(
+            normalised_to_native(0.0, full.0, full.1).unwrap().value,
+            i32::MIN
+        );
+    }
+}
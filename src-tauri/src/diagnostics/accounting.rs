@@ -0,0 +1,494 @@
+// Per-device CPU accounting for preview sessions.
+//
+// When the fans spin up, the question is which camera is responsible. Each
+// session owns a CpuMeter; the frame callback and the encode worker time
+// their work with a CpuTimer and add it to the meter, so one device's CPU
+// time is gathered from several threads. A sampler reads the meters every
+// few seconds into ResourceAccounting, which turns the running totals into
+// a share of one core over the last minute.
+//
+// Work a thread hands to short-lived helper threads and waits for (the
+// banded frame conversion) is reported back with record_offloaded, so the
+// calling thread's timers still count it.
+
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Span the CPU percentage is averaged over.
+pub const CPU_WINDOW: Duration = Duration::from_secs(60);
+
+/// What a stretch of CPU time was spent on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuWork {
+    /// Converting and buffering frames as they arrive from the camera.
+    Capture,
+    /// Rendering and JPEG-compressing frames in the encode worker.
+    Compress,
+}
+
+#[derive(Debug, Default)]
+struct CpuTotals {
+    capture_ns: AtomicU64,
+    compress_ns: AtomicU64,
+}
+
+/// Running CPU totals for one session. Clones share the same totals, so
+/// every thread working for a device can hold one.
+#[derive(Debug, Clone, Default)]
+pub struct CpuMeter {
+    totals: Arc<CpuTotals>,
+}
+
+impl CpuMeter {
+    pub fn record(&self, work: CpuWork, time: Duration) {
+        let counter = match work {
+            CpuWork::Capture => &self.totals.capture_ns,
+            CpuWork::Compress => &self.totals.compress_ns,
+        };
+        counter.fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// CPU time recorded so far.
+    pub fn times(&self) -> CpuTimes {
+        CpuTimes {
+            capture: Duration::from_nanos(self.totals.capture_ns.load(Ordering::Relaxed)),
+            compress: Duration::from_nanos(self.totals.compress_ns.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// CPU time a session has used, by kind of work.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub capture: Duration,
+    pub compress: Duration,
+}
+
+impl CpuTimes {
+    pub fn total(&self) -> Duration {
+        self.capture + self.compress
+    }
+}
+
+/// Times the CPU a stretch of work uses on the current thread.
+///
+/// Uses the thread's own CPU time where the OS reports it, so time spent
+/// blocked or preempted isn't counted. Elsewhere it falls back to the wall
+/// time between start and `elapsed`, which is close enough for work that
+/// doesn't wait on anything.
+#[derive(Debug, Clone, Copy)]
+pub struct CpuTimer {
+    start: TimerStart,
+    offloaded: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TimerStart {
+    Thread(Duration),
+    Busy(Instant),
+}
+
+impl CpuTimer {
+    pub fn start() -> Self {
+        match thread_cpu_time() {
+            Some(time) => Self {
+                start: TimerStart::Thread(time),
+                offloaded: offloaded(),
+            },
+            None => Self::busy(),
+        }
+    }
+
+    /// Measure wall time even where thread CPU time is available.
+    pub fn busy() -> Self {
+        Self {
+            start: TimerStart::Busy(Instant::now()),
+            offloaded: offloaded(),
+        }
+    }
+
+    /// Time since the timer started, including work this thread offloaded
+    /// meanwhile.
+    pub fn elapsed(&self) -> Duration {
+        let own = match self.start {
+            TimerStart::Thread(start) => {
+                thread_cpu_time().map_or(Duration::ZERO, |now| now.saturating_sub(start))
+            }
+            TimerStart::Busy(start) => start.elapsed(),
+        };
+        own + offloaded().saturating_sub(self.offloaded)
+    }
+}
+
+thread_local! {
+    /// CPU time helper threads have spent on this thread's behalf.
+    static OFFLOADED: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Count `time` helper threads spent on work this thread waited for
+/// towards this thread's running [`CpuTimer`]s.
+pub fn record_offloaded(time: Duration) {
+    OFFLOADED.with(|total| total.set(total.get() + time));
+}
+
+fn offloaded() -> Duration {
+    OFFLOADED.with(Cell::get)
+}
+
+/// User plus kernel time of the current thread, via `GetThreadTimes`.
+#[cfg(target_os = "windows")]
+fn thread_cpu_time() -> Option<Duration> {
+    use windows::Win32::Foundation::FILETIME;
+    use windows::Win32::System::Threading::{GetCurrentThread, GetThreadTimes};
+
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    // SAFETY: GetCurrentThread returns a pseudo-handle that needs no
+    // closing, and every out-pointer is a live FILETIME.
+    unsafe {
+        GetThreadTimes(
+            GetCurrentThread(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+        .ok()?;
+    }
+    // FILETIME counts 100ns intervals
+    let ticks = |t: FILETIME| (u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime);
+    Some(Duration::from_nanos((ticks(kernel) + ticks(user)) * 100))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Resources one device's session is using.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceResources {
+    /// CPU used over the last minute, as a share of one core (can pass 100
+    /// when work for the device runs on several threads at once).
+    pub cpu_percent_1m: f64,
+    /// CPU spent capturing since the session started.
+    pub capture_thread_time_ms: u64,
+    /// CPU spent compressing since the session started.
+    pub compress_time_ms: u64,
+    /// Bytes of frames held in the session's buffers.
+    pub buffer_bytes: usize,
+    /// Bytes of the device's cached frame and thumbnail.
+    pub cache_bytes: usize,
+}
+
+impl DeviceResources {
+    fn add(&mut self, other: &Self) {
+        self.cpu_percent_1m += other.cpu_percent_1m;
+        self.capture_thread_time_ms += other.capture_thread_time_ms;
+        self.compress_time_ms += other.compress_time_ms;
+        self.buffer_bytes += other.buffer_bytes;
+        self.cache_bytes += other.cache_bytes;
+    }
+}
+
+/// Resources by device ID, plus their sum.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsagePerDevice {
+    pub devices: HashMap<String, DeviceResources>,
+    pub totals: DeviceResources,
+}
+
+impl ResourceUsagePerDevice {
+    pub fn new(devices: HashMap<String, DeviceResources>) -> Self {
+        let mut totals = DeviceResources::default();
+        for resources in devices.values() {
+            totals.add(resources);
+        }
+        Self { devices, totals }
+    }
+}
+
+/// Samples of each device's CPU total, for the one-minute percentage.
+#[derive(Debug, Default)]
+pub struct ResourceAccounting {
+    samples: HashMap<String, VecDeque<(Instant, Duration)>>,
+}
+
+impl ResourceAccounting {
+    /// Record a device's CPU total at `now`.
+    ///
+    /// A total lower than the last one means the device got a new session;
+    /// its history starts again. Samples older than the window are dropped,
+    /// except the newest of them, so the percentage covers a full window.
+    pub fn sample(&mut self, device_id: &str, times: CpuTimes, now: Instant) {
+        let total = times.total();
+        let samples = self.samples.entry(device_id.to_string()).or_default();
+        if samples.back().is_some_and(|&(_, last)| total < last) {
+            samples.clear();
+        }
+        samples.push_back((now, total));
+        while samples
+            .get(1)
+            .is_some_and(|&(at, _)| now.saturating_duration_since(at) >= CPU_WINDOW)
+        {
+            samples.pop_front();
+        }
+    }
+
+    /// Forget devices `is_live` rejects.
+    pub fn retain(&mut self, mut is_live: impl FnMut(&str) -> bool) {
+        self.samples.retain(|id, _| is_live(id));
+    }
+
+    /// CPU used between the oldest and newest samples in the window, as a
+    /// percentage of one core. Zero until a device has two samples.
+    pub fn cpu_percent(&self, device_id: &str) -> f64 {
+        let Some(samples) = self.samples.get(device_id) else {
+            return 0.0;
+        };
+        let (Some(&(first_at, first)), Some(&(last_at, last))) = (samples.front(), samples.back())
+        else {
+            return 0.0;
+        };
+        let wall = last_at.saturating_duration_since(first_at);
+        if wall.is_zero() {
+            return 0.0;
+        }
+        (last - first).as_secs_f64() / wall.as_secs_f64() * 100.0
+    }
+
+    /// A device's resources, from its session's CPU totals and buffered and
+    /// cached bytes.
+    pub fn device_resources(
+        &self,
+        device_id: &str,
+        times: CpuTimes,
+        buffer_bytes: usize,
+        cache_bytes: usize,
+    ) -> DeviceResources {
+        DeviceResources {
+            cpu_percent_1m: self.cpu_percent(device_id),
+            capture_thread_time_ms: times.capture.as_millis() as u64,
+            compress_time_ms: times.compress.as_millis() as u64,
+            buffer_bytes,
+            cache_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn capture(ms: u64) -> CpuTimes {
+        CpuTimes {
+            capture: Duration::from_millis(ms),
+            compress: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn meter_adds_up_work_from_every_thread() {
+        let meter = CpuMeter::default();
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let meter = meter.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        let work = if i % 2 == 0 {
+                            CpuWork::Capture
+                        } else {
+                            CpuWork::Compress
+                        };
+                        meter.record(work, Duration::from_micros(10));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let times = meter.times();
+        assert_eq!(times.capture, ms(2));
+        assert_eq!(times.compress, ms(2));
+        assert_eq!(times.total(), ms(4));
+    }
+
+    #[test]
+    fn busy_timer_measures_elapsed_work() {
+        let timer = CpuTimer::busy();
+        std::thread::sleep(ms(20));
+        assert!(timer.elapsed() >= ms(20));
+    }
+
+    #[test]
+    fn timer_counts_offloaded_work() {
+        let timer = CpuTimer::busy();
+        record_offloaded(ms(500));
+        assert!(timer.elapsed() >= ms(500));
+
+        // Only on the thread that offloaded it
+        let elsewhere = std::thread::spawn(move || timer.elapsed()).join().unwrap();
+        assert!(elsewhere < ms(500));
+    }
+
+    #[test]
+    fn timer_never_goes_backwards() {
+        let timer = CpuTimer::start();
+        let mut x = 0u64;
+        for i in 0..100_000u64 {
+            x = x.wrapping_mul(31).wrapping_add(i);
+        }
+        std::hint::black_box(x);
+        let first = timer.elapsed();
+        assert!(timer.elapsed() >= first);
+    }
+
+    #[test]
+    fn percent_is_cpu_over_wall_time() {
+        let t0 = Instant::now();
+        let mut accounting = ResourceAccounting::default();
+        assert_eq!(accounting.cpu_percent("cam"), 0.0);
+
+        accounting.sample("cam", capture(0), t0);
+        assert_eq!(accounting.cpu_percent("cam"), 0.0);
+
+        // 500ms of CPU over 10s is 5% of a core
+        accounting.sample("cam", capture(500), t0 + ms(10_000));
+        assert!((accounting.cpu_percent("cam") - 5.0).abs() < 1e-9);
+
+        // Several threads can use more than one core between them
+        accounting.sample("cam", capture(20_500), t0 + ms(20_000));
+        assert!((accounting.cpu_percent("cam") - 102.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn percent_only_covers_the_last_minute() {
+        let t0 = Instant::now();
+        let mut accounting = ResourceAccounting::default();
+
+        // Busy for the first minute, idle after
+        accounting.sample("cam", capture(0), t0);
+        accounting.sample("cam", capture(30_000), t0 + ms(60_000));
+        assert!((accounting.cpu_percent("cam") - 50.0).abs() < 1e-9);
+
+        for s in (65..=120).step_by(5) {
+            accounting.sample("cam", capture(30_000), t0 + ms(s * 1000));
+        }
+        assert_eq!(accounting.cpu_percent("cam"), 0.0);
+    }
+
+    #[test]
+    fn window_keeps_one_sample_at_its_start() {
+        let t0 = Instant::now();
+        let mut accounting = ResourceAccounting::default();
+
+        // Samples 45s apart: the one before the window still anchors it
+        accounting.sample("cam", capture(0), t0);
+        accounting.sample("cam", capture(9_000), t0 + ms(45_000));
+        accounting.sample("cam", capture(18_000), t0 + ms(90_000));
+        assert!((accounting.cpu_percent("cam") - 20.0).abs() < 1e-9);
+        assert_eq!(accounting.samples["cam"].len(), 3);
+
+        // Once the 45s sample is a minute old, the first one goes
+        accounting.sample("cam", capture(24_000), t0 + ms(120_000));
+        assert_eq!(accounting.samples["cam"].len(), 3);
+        assert!((accounting.cpu_percent("cam") - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_new_session_starts_history_again() {
+        let t0 = Instant::now();
+        let mut accounting = ResourceAccounting::default();
+        accounting.sample("cam", capture(0), t0);
+        accounting.sample("cam", capture(5_000), t0 + ms(10_000));
+
+        accounting.sample("cam", capture(100), t0 + ms(15_000));
+        assert_eq!(accounting.cpu_percent("cam"), 0.0);
+        accounting.sample("cam", capture(600), t0 + ms(20_000));
+        assert!((accounting.cpu_percent("cam") - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn devices_are_kept_apart_and_forgotten() {
+        let t0 = Instant::now();
+        let mut accounting = ResourceAccounting::default();
+        accounting.sample("a", capture(0), t0);
+        accounting.sample("b", capture(0), t0);
+        accounting.sample("a", capture(1_000), t0 + ms(10_000));
+        accounting.sample("b", capture(3_000), t0 + ms(10_000));
+        assert!((accounting.cpu_percent("a") - 10.0).abs() < 1e-9);
+        assert!((accounting.cpu_percent("b") - 30.0).abs() < 1e-9);
+
+        accounting.retain(|id| id == "b");
+        assert_eq!(accounting.cpu_percent("a"), 0.0);
+        assert!((accounting.cpu_percent("b") - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn usage_totals_every_device() {
+        let t0 = Instant::now();
+        let mut accounting = ResourceAccounting::default();
+        let a = CpuTimes {
+            capture: ms(1_200),
+            compress: ms(3_400),
+        };
+        accounting.sample("a", CpuTimes::default(), t0);
+        accounting.sample("a", a, t0 + ms(46_000));
+
+        let devices = HashMap::from([
+            (
+                "a".to_string(),
+                accounting.device_resources("a", a, 1_000, 200),
+            ),
+            (
+                "b".to_string(),
+                accounting.device_resources("b", capture(50), 3_000, 0),
+            ),
+        ]);
+        let usage = ResourceUsagePerDevice::new(devices);
+
+        let a = &usage.devices["a"];
+        assert!((a.cpu_percent_1m - 10.0).abs() < 1e-9);
+        assert_eq!(a.capture_thread_time_ms, 1_200);
+        assert_eq!(a.compress_time_ms, 3_400);
+        assert_eq!(usage.totals.capture_thread_time_ms, 1_250);
+        assert_eq!(usage.totals.buffer_bytes, 4_000);
+        assert_eq!(usage.totals.cache_bytes, 200);
+        assert!((usage.totals.cpu_percent_1m - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn serialises_with_the_documented_field_names() {
+        let json = serde_json::to_value(ResourceUsagePerDevice::new(HashMap::from([(
+            "cam".to_string(),
+            DeviceResources::default(),
+        )])))
+        .unwrap();
+        let cam = &json["devices"]["cam"];
+        for field in [
+            "cpuPercent1m",
+            "captureThreadTimeMs",
+            "compressTimeMs",
+            "bufferBytes",
+            "cacheBytes",
+        ] {
+            assert!(cam.get(field).is_some(), "missing {field}");
+        }
+        assert!(json["totals"].get("cpuPercent1m").is_some());
+    }
+}
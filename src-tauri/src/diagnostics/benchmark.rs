@@ -0,0 +1,615 @@
+// In-process pipeline benchmark.
+//
+// Times each stage a frame goes through on its way to the frontend — colour
+// conversion, the session's ring buffer, the thumbnail downscaler, JPEG
+// compression and base64 — on synthesised frames, so "high CPU" reports can
+// be pinned to a stage on the reporter's own hardware without a camera
+// attached. Stages call the production functions; only the frames are fake.
+//
+// Timing takes a clock so the aggregation and early-stop logic can be tested
+// with fake timings.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::preview::capture::{Frame, FrameBuffer};
+use crate::preview::colour::ColourSpace;
+use crate::preview::compress::{compress_jpeg, downscale_rgb};
+use crate::preview::graph::{
+    convert_bgr_bottom_up_to_rgb, convert_nv12_to_rgb, convert_yuy2_to_rgb,
+};
+use crate::preview::mode::SessionMode;
+use crate::preview::quality::QualityProfile;
+use crate::preview::quirks::bgr24_stride;
+use crate::preview::thumbnail::{thumbnail_size, ThumbnailConfig};
+
+/// Longest a benchmark runs before stopping early.
+pub const MAX_RUNTIME: Duration = Duration::from_secs(5);
+
+/// Most iterations a benchmark accepts.
+pub const MAX_ITERATIONS: u32 = 1000;
+
+/// Largest frame a benchmark accepts.
+const MAX_FRAME_SIZE: (u32, u32) = (7680, 4320);
+
+/// A timed pipeline stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Nv12ToRgb,
+    Yuy2ToRgb,
+    /// Bottom-up BGR24 with DWORD-aligned rows, as DirectShow delivers it.
+    Rgb24ToRgb,
+    /// Push into a full session's ring buffer, the capture thread's hot
+    /// path. The pixels are moved in, so their size doesn't matter.
+    BufferPush,
+    /// Resize to the default thumbnail size.
+    Downscale,
+    /// JPEG compression at the given quality.
+    Jpeg(u8),
+    /// Base64 of the JPEG at the default quality, as `get_frame` returns it.
+    Base64,
+}
+
+impl Stage {
+    pub fn name(self) -> String {
+        match self {
+            Self::Nv12ToRgb => "nv12_to_rgb".to_string(),
+            Self::Yuy2ToRgb => "yuy2_to_rgb".to_string(),
+            Self::Rgb24ToRgb => "rgb24_to_rgb".to_string(),
+            Self::BufferPush => "buffer_push".to_string(),
+            Self::Downscale => "downscale".to_string(),
+            Self::Jpeg(quality) => format!("jpeg_q{quality}"),
+            Self::Base64 => "base64".to_string(),
+        }
+    }
+}
+
+/// Timings of one stage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageReport {
+    pub stage: String,
+    pub samples: u32,
+    pub mean_us: f64,
+    pub min_us: f64,
+    pub max_us: f64,
+    /// Frames per second the stage alone could sustain, if it was timed.
+    pub max_fps: Option<f64>,
+}
+
+/// A delivery path from one capture format to a base64 JPEG.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineReport {
+    /// Capture format the path starts from, e.g. `nv12`.
+    pub format: String,
+    /// Stages the path runs, in order.
+    pub stages: Vec<String>,
+    /// Sum of the stages' mean timings.
+    pub mean_us: f64,
+    /// Frames per second the path could sustain on one core, if every stage
+    /// was timed.
+    pub max_fps: Option<f64>,
+}
+
+/// Result of a pipeline benchmark.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub width: u32,
+    pub height: u32,
+    pub iterations_requested: u32,
+    /// Iterations every stage completed.
+    pub iterations_completed: u32,
+    /// Whether the run stopped early at [`MAX_RUNTIME`].
+    pub timed_out: bool,
+    pub stages: Vec<StageReport>,
+    pub pipelines: Vec<PipelineReport>,
+}
+
+/// Runs one stage on its prepared input.
+type StageFn<'a> = Box<dyn FnMut() + 'a>;
+
+/// Samples collected for one stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageSamples {
+    pub stage: Stage,
+    pub samples: Vec<Duration>,
+}
+
+/// Benchmark the frame pipeline at `width`x`height` for `iterations`,
+/// stopping early after [`MAX_RUNTIME`]. Blocks for the whole run.
+pub fn run_pipeline_benchmark(
+    width: u32,
+    height: u32,
+    iterations: u32,
+) -> Result<BenchmarkReport, String> {
+    validate(width, height, iterations)?;
+    let (w, h) = (width as usize, height as usize);
+    let profile = QualityProfile::default();
+    let colour = ColourSpace::default_for(height);
+
+    let nv12 = synthesise_nv12(w, h);
+    let yuy2 = synthesise_yuy2(w, h);
+    let stride = bgr24_stride(width);
+    let bgr = synthesise_bgr(w, h, stride);
+    let rgb = convert_nv12_to_rgb(&nv12, w, h, colour);
+    let (thumb_width, thumb_height) = thumbnail_size(&ThumbnailConfig::default(), (width, height));
+    let jpeg = compress_jpeg(&rgb, width, height, profile.quality);
+    let ring = FrameBuffer::new(SessionMode::Full.frame_buffer_capacity());
+
+    let mut stages: Vec<(Stage, StageFn)> = vec![
+        (
+            Stage::Nv12ToRgb,
+            Box::new(|| drop(black_box(convert_nv12_to_rgb(&nv12, w, h, colour)))),
+        ),
+        (
+            Stage::Yuy2ToRgb,
+            Box::new(|| drop(black_box(convert_yuy2_to_rgb(&yuy2, w, h, colour)))),
+        ),
+        (
+            Stage::Rgb24ToRgb,
+            Box::new(|| drop(black_box(convert_bgr_bottom_up_to_rgb(&bgr, w, h, stride)))),
+        ),
+        (
+            Stage::BufferPush,
+            Box::new(|| {
+                black_box(ring.push(Frame {
+                    data: Vec::new(),
+                    width,
+                    height,
+                    timestamp_us: 0,
+                    device_timestamp_us: 0,
+                }));
+            }),
+        ),
+        (
+            Stage::Downscale,
+            Box::new(|| {
+                drop(black_box(downscale_rgb(
+                    &rgb,
+                    width,
+                    height,
+                    thumb_width,
+                    thumb_height,
+                )))
+            }),
+        ),
+    ];
+    for quality in [profile.min_quality, profile.quality, profile.max_quality] {
+        let rgb = &rgb;
+        stages.push((
+            Stage::Jpeg(quality),
+            Box::new(move || drop(black_box(compress_jpeg(rgb, width, height, quality)))),
+        ));
+    }
+    stages.push((
+        Stage::Base64,
+        Box::new(|| {
+            drop(black_box(base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                &jpeg,
+            )))
+        }),
+    ));
+
+    let started = Instant::now();
+    let (samples, timed_out) =
+        time_stages(&mut stages, iterations, MAX_RUNTIME, || started.elapsed());
+    Ok(assemble_report(
+        width,
+        height,
+        iterations,
+        &samples,
+        timed_out,
+        profile.quality,
+    ))
+}
+
+fn validate(width: u32, height: u32, iterations: u32) -> Result<(), String> {
+    let (max_width, max_height) = MAX_FRAME_SIZE;
+    if width < 2
+        || height < 2
+        || width % 2 != 0
+        || height % 2 != 0
+        || width > max_width
+        || height > max_height
+    {
+        return Err(format!(
+            "Benchmark frames must be even-sized between 2x2 and {max_width}x{max_height}, got {width}x{height}"
+        ));
+    }
+    if !(1..=MAX_ITERATIONS).contains(&iterations) {
+        return Err(format!(
+            "Benchmark iterations must be between 1 and {MAX_ITERATIONS}, got {iterations}"
+        ));
+    }
+    Ok(())
+}
+
+/// Run each stage once per iteration, timing every call with `now`.
+///
+/// Stops before the next call once `budget` has passed since the start, so
+/// stages may end up one sample apart. Returns the samples and whether the
+/// run stopped early.
+fn time_stages(
+    stages: &mut [(Stage, StageFn<'_>)],
+    iterations: u32,
+    budget: Duration,
+    mut now: impl FnMut() -> Duration,
+) -> (Vec<StageSamples>, bool) {
+    let mut samples: Vec<_> = stages
+        .iter()
+        .map(|(stage, _)| StageSamples {
+            stage: *stage,
+            samples: Vec::with_capacity(iterations as usize),
+        })
+        .collect();
+    let start = now();
+    for _ in 0..iterations {
+        for ((_, run), stage) in stages.iter_mut().zip(&mut samples) {
+            let before = now();
+            if before.saturating_sub(start) >= budget {
+                return (samples, true);
+            }
+            run();
+            stage.samples.push(now().saturating_sub(before));
+        }
+    }
+    (samples, false)
+}
+
+/// Mean, min and max of one stage's samples.
+fn summarise(samples: &StageSamples) -> StageReport {
+    let count = samples.samples.len();
+    let micros = |d: &Duration| d.as_secs_f64() * 1_000_000.0;
+    let (mean_us, min_us, max_us) = if count == 0 {
+        (0.0, 0.0, 0.0)
+    } else {
+        let total: f64 = samples.samples.iter().map(micros).sum();
+        let min = samples.samples.iter().min().map(micros).unwrap_or_default();
+        let max = samples.samples.iter().max().map(micros).unwrap_or_default();
+        (total / count as f64, min, max)
+    };
+    StageReport {
+        stage: samples.stage.name(),
+        samples: count as u32,
+        mean_us,
+        min_us,
+        max_us,
+        max_fps: fps(mean_us),
+    }
+}
+
+/// Frames per second sustainable at `mean_us` per frame; `None` for a zero
+/// mean, which can't be told apart from an untimed stage.
+fn fps(mean_us: f64) -> Option<f64> {
+    (mean_us > 0.0).then(|| 1_000_000.0 / mean_us)
+}
+
+/// Build the report from collected samples. Each capture format's pipeline
+/// is its conversion, JPEG at `quality` and base64.
+fn assemble_report(
+    width: u32,
+    height: u32,
+    iterations: u32,
+    samples: &[StageSamples],
+    timed_out: bool,
+    quality: u8,
+) -> BenchmarkReport {
+    let stages: Vec<StageReport> = samples.iter().map(summarise).collect();
+    let iterations_completed = stages.iter().map(|s| s.samples).min().unwrap_or(0);
+
+    let pipelines = [
+        ("nv12", Stage::Nv12ToRgb),
+        ("yuy2", Stage::Yuy2ToRgb),
+        ("rgb24", Stage::Rgb24ToRgb),
+    ]
+    .into_iter()
+    .map(|(format, conversion)| {
+        let path = [conversion, Stage::Jpeg(quality), Stage::Base64].map(Stage::name);
+        let timed: Option<Vec<&StageReport>> = path
+            .iter()
+            .map(|name| stages.iter().find(|s| &s.stage == name && s.samples > 0))
+            .collect();
+        let mean_us = timed
+            .as_ref()
+            .map(|timed| timed.iter().map(|s| s.mean_us).sum())
+            .unwrap_or(0.0);
+        PipelineReport {
+            format: format.to_string(),
+            stages: path.to_vec(),
+            mean_us,
+            max_fps: timed.and_then(|_| fps(mean_us)),
+        }
+    })
+    .collect();
+
+    BenchmarkReport {
+        width,
+        height,
+        iterations_requested: iterations,
+        iterations_completed,
+        timed_out,
+        stages,
+        pipelines,
+    }
+}
+
+/// NV12 frame with a diagonal luma gradient and varying chroma.
+fn synthesise_nv12(width: usize, height: usize) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(width * height * 3 / 2);
+    for y in 0..height {
+        frame.extend((0..width).map(|x| ((x + y) % 256) as u8));
+    }
+    frame.extend((0..width * height / 2).map(|i| (i % 256) as u8));
+    frame
+}
+
+/// YUY2 frame with a horizontal luma gradient and varying chroma.
+fn synthesise_yuy2(width: usize, height: usize) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(width * height * 2);
+    for y in 0..height {
+        for x in (0..width).step_by(2) {
+            frame.extend([(x % 256) as u8, (y % 256) as u8, ((x + 1) % 256) as u8, 128]);
+        }
+    }
+    frame
+}
+
+/// BGR24 frame with rows `stride` bytes apart.
+fn synthesise_bgr(width: usize, height: usize, stride: usize) -> Vec<u8> {
+    let mut frame = vec![0u8; stride * height];
+    for (y, row) in frame.chunks_exact_mut(stride).enumerate() {
+        for (x, pixel) in row[..width * 3].chunks_exact_mut(3).enumerate() {
+            pixel.copy_from_slice(&[128, (y % 256) as u8, (x % 256) as u8]);
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn us(micros: &[u64]) -> Vec<Duration> {
+        micros.iter().map(|&m| Duration::from_micros(m)).collect()
+    }
+
+    fn samples(stage: Stage, micros: &[u64]) -> StageSamples {
+        StageSamples {
+            stage,
+            samples: us(micros),
+        }
+    }
+
+    #[test]
+    fn summarise_aggregates_samples() {
+        let report = summarise(&samples(Stage::Nv12ToRgb, &[10, 30, 20]));
+        assert_eq!(report.stage, "nv12_to_rgb");
+        assert_eq!(report.samples, 3);
+        assert_eq!(report.mean_us, 20.0);
+        assert_eq!(report.min_us, 10.0);
+        assert_eq!(report.max_us, 30.0);
+        assert_eq!(report.max_fps, Some(50_000.0));
+    }
+
+    #[test]
+    fn summarise_without_samples_has_no_fps() {
+        let report = summarise(&samples(Stage::Jpeg(75), &[]));
+        assert_eq!(report.stage, "jpeg_q75");
+        assert_eq!(report.samples, 0);
+        assert_eq!(
+            (report.mean_us, report.min_us, report.max_us),
+            (0.0, 0.0, 0.0)
+        );
+        assert_eq!(report.max_fps, None);
+    }
+
+    #[test]
+    fn summarise_keeps_sub_microsecond_precision() {
+        let report = summarise(&StageSamples {
+            stage: Stage::Base64,
+            samples: vec![Duration::from_nanos(250), Duration::from_nanos(750)],
+        });
+        assert_eq!(report.mean_us, 0.5);
+        assert_eq!(report.max_fps, Some(2_000_000.0));
+    }
+
+    #[test]
+    fn pipelines_sum_their_stage_means() {
+        let collected = [
+            samples(Stage::Nv12ToRgb, &[1000, 3000]),
+            samples(Stage::Yuy2ToRgb, &[500, 500]),
+            samples(Stage::Rgb24ToRgb, &[250, 250]),
+            samples(Stage::Downscale, &[100, 100]),
+            samples(Stage::Jpeg(40), &[2000, 2000]),
+            samples(Stage::Jpeg(75), &[2500, 3500]),
+            samples(Stage::Base64, &[1000, 1000]),
+        ];
+        let report = assemble_report(1920, 1080, 2, &collected, false, 75);
+
+        assert_eq!(report.iterations_completed, 2);
+        assert!(!report.timed_out);
+        assert_eq!(report.stages.len(), 7);
+
+        let nv12 = &report.pipelines[0];
+        assert_eq!(nv12.format, "nv12");
+        assert_eq!(nv12.stages, ["nv12_to_rgb", "jpeg_q75", "base64"]);
+        assert_eq!(nv12.mean_us, 6000.0);
+        assert_eq!(nv12.max_fps.map(f64::round), Some(167.0));
+
+        let yuy2 = &report.pipelines[1];
+        assert_eq!(
+            (yuy2.mean_us, yuy2.max_fps),
+            (4500.0, Some(1_000_000.0 / 4500.0))
+        );
+
+        let rgb24 = &report.pipelines[2];
+        assert_eq!(
+            (rgb24.mean_us, rgb24.max_fps),
+            (4250.0, Some(1_000_000.0 / 4250.0))
+        );
+    }
+
+    #[test]
+    fn pipeline_with_an_untimed_stage_has_no_fps() {
+        let collected = [
+            samples(Stage::Nv12ToRgb, &[1000, 1000]),
+            samples(Stage::Yuy2ToRgb, &[1000]),
+            samples(Stage::Rgb24ToRgb, &[1000]),
+            samples(Stage::Jpeg(75), &[1000]),
+            samples(Stage::Base64, &[]),
+        ];
+        let report = assemble_report(640, 480, 2, &collected, true, 75);
+
+        assert!(report.timed_out);
+        assert_eq!(report.iterations_requested, 2);
+        assert_eq!(report.iterations_completed, 0);
+        for pipeline in &report.pipelines {
+            assert_eq!((pipeline.mean_us, pipeline.max_fps), (0.0, None));
+        }
+    }
+
+    /// A clock that advances `step` every time it's read.
+    fn fake_clock(step: Duration) -> impl FnMut() -> Duration {
+        let mut now = Duration::ZERO;
+        move || {
+            now += step;
+            now
+        }
+    }
+
+    fn counting_stages(counts: &mut [u32]) -> Vec<(Stage, StageFn<'_>)> {
+        let stages = [Stage::Nv12ToRgb, Stage::Base64];
+        stages
+            .into_iter()
+            .zip(counts.iter_mut())
+            .map(|(stage, count)| (stage, Box::new(move || *count += 1) as StageFn))
+            .collect()
+    }
+
+    #[test]
+    fn time_stages_runs_every_iteration_within_budget() {
+        let mut counts = [0; 2];
+        let mut stages = counting_stages(&mut counts);
+        let step = Duration::from_millis(1);
+        let (samples, timed_out) =
+            time_stages(&mut stages, 4, Duration::from_secs(5), fake_clock(step));
+        drop(stages);
+
+        assert!(!timed_out);
+        assert_eq!(counts, [4, 4]);
+        assert_eq!(samples[0].stage, Stage::Nv12ToRgb);
+        assert_eq!(samples[1].stage, Stage::Base64);
+        // Each call is bracketed by two clock reads
+        assert!(samples.iter().all(|s| s.samples == [step; 4]));
+    }
+
+    #[test]
+    fn time_stages_stops_once_the_budget_is_spent() {
+        let mut counts = [0; 2];
+        let mut stages = counting_stages(&mut counts);
+        let (samples, timed_out) = time_stages(
+            &mut stages,
+            100,
+            Duration::from_millis(10),
+            fake_clock(Duration::from_millis(1)),
+        );
+        drop(stages);
+
+        assert!(timed_out);
+        // Each call spends 2ms; the sixth would start 11ms in
+        assert_eq!(counts, [3, 2]);
+        assert_eq!(samples[0].samples.len(), 3);
+        assert_eq!(samples[1].samples.len(), 2);
+        let report = assemble_report(64, 48, 100, &samples, timed_out, 75);
+        assert_eq!(report.iterations_completed, 2);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for (width, height, iterations) in [
+            (0, 480, 10),
+            (641, 480, 10),
+            (640, 1, 10),
+            (7682, 4320, 10),
+            (640, 480, 0),
+            (640, 480, MAX_ITERATIONS + 1),
+        ] {
+            assert!(
+                run_pipeline_benchmark(width, height, iterations).is_err(),
+                "{width}x{height} x{iterations}"
+            );
+        }
+    }
+
+    #[test]
+    fn runs_every_stage_on_synthesised_frames() {
+        let report = run_pipeline_benchmark(64, 48, 2).unwrap();
+        assert_eq!((report.width, report.height), (64, 48));
+        assert_eq!(report.iterations_completed, 2);
+        assert!(!report.timed_out);
+        let names: Vec<_> = report.stages.iter().map(|s| s.stage.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "nv12_to_rgb",
+                "yuy2_to_rgb",
+                "rgb24_to_rgb",
+                "buffer_push",
+                "downscale",
+                "jpeg_q40",
+                "jpeg_q75",
+                "jpeg_q90",
+                "base64",
+            ]
+        );
+        assert!(report.stages.iter().all(|s| s.samples == 2));
+        assert!(report
+            .stages
+            .iter()
+            .all(|s| s.min_us <= s.mean_us && s.mean_us <= s.max_us));
+    }
+
+    #[test]
+    fn synthesised_frames_convert_to_full_rgb() {
+        let (width, height) = (6, 4);
+        let stride = bgr24_stride(width as u32);
+        let expected = width * height * 3;
+        let colour = ColourSpace::default_for(height as u32);
+        assert_eq!(
+            convert_nv12_to_rgb(&synthesise_nv12(width, height), width, height, colour).len(),
+            expected
+        );
+        assert_eq!(
+            convert_yuy2_to_rgb(&synthesise_yuy2(width, height), width, height, colour).len(),
+            expected
+        );
+        assert_eq!(
+            convert_bgr_bottom_up_to_rgb(
+                &synthesise_bgr(width, height, stride),
+                width,
+                height,
+                stride
+            )
+            .len(),
+            expected
+        );
+    }
+
+    #[test]
+    fn report_serialises_to_camel_case() {
+        let report = assemble_report(64, 48, 1, &[samples(Stage::Jpeg(75), &[40])], false, 75);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["iterationsRequested"], 1);
+        assert_eq!(json["iterationsCompleted"], 1);
+        assert_eq!(json["timedOut"], false);
+        assert_eq!(json["stages"][0]["stage"], "jpeg_q75");
+        assert_eq!(json["stages"][0]["meanUs"], 40.0);
+        assert_eq!(json["stages"][0]["maxFps"], 25_000.0);
+        assert_eq!(json["pipelines"][0]["maxFps"], serde_json::Value::Null);
+    }
+}
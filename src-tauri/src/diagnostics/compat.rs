@@ -0,0 +1,565 @@
+// Hardware compatibility reports.
+//
+// To help prioritise new quirks and converters, users can generate a report
+// describing each connected camera: its USB IDs, advertised formats, the
+// subtype the capture graph negotiated, whether frames flowed, which
+// controls it has and which optional features its backend supports. Nothing is sent anywhere — the report is written to a file
+// the user chooses so they can review it first. Serials, device paths and
+// device IDs (which embed serials) are redacted.
+//
+// Assembly and redaction are pure over `CameraObservation`; gathering the
+// observations is left to the caller. Anything that wasn't gathered is
+// marked "not collected" rather than omitted.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::types::{
+    abbreviate_path, device_serial, vendor_product, BackendCapabilities, CameraDevice,
+    ControlDescriptor, DeviceKind, FormatDescriptor,
+};
+use crate::diagnostics::stats::DiagnosticSnapshot;
+
+/// Bumped when the report layout changes incompatibly.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Replaces identifying strings found in free text.
+const REDACTED: &str = "[redacted]";
+
+/// Reason given for a camera without a running preview.
+const NO_PREVIEW: &str = "no preview running for this camera";
+
+/// Reason given for data this version doesn't record.
+const NOT_RECORDED: &str = "not recorded";
+
+/// What was gathered about one camera, before redaction.
+#[derive(Debug, Clone)]
+pub struct CameraObservation {
+    pub device: CameraDevice,
+    /// Advertised formats, or why they couldn't be queried.
+    pub formats: Result<Vec<FormatDescriptor>, String>,
+    /// Subtype the capture graph negotiated, if recorded.
+    pub negotiated_subtype: Option<String>,
+    /// Capture stats of the camera's running preview, if any.
+    pub diagnostics: Option<DiagnosticSnapshot>,
+    /// Controls, or why they couldn't be queried.
+    pub controls: Result<Vec<ControlDescriptor>, String>,
+    /// Whether an effectiveness probe saw each control (by ID) change the
+    /// image, if one has run.
+    pub control_probe: Option<BTreeMap<String, bool>>,
+    /// Optional features of the camera's backend, or why they couldn't be
+    /// queried.
+    pub capabilities: Result<BackendCapabilities, String>,
+}
+
+/// A report section, or why it's missing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Section<T> {
+    Collected { data: T },
+    NotCollected { reason: String },
+}
+
+/// Anonymous compatibility report for every connected camera.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatReport {
+    pub report_version: u32,
+    pub app_version: String,
+    pub os: String,
+    pub cameras: Vec<CameraReport>,
+}
+
+/// Compatibility details for one camera.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraReport {
+    /// `camera-1`, `camera-2`, … in enumeration order. Stands in for the
+    /// device ID, which embeds the serial.
+    pub label: String,
+    /// USB `vvvv:pppp`, or `None` for devices without USB IDs (usually
+    /// virtual cameras).
+    pub vendor_product: Option<String>,
+    pub name: String,
+    pub kind: DeviceKind,
+    pub formats: Section<Vec<FormatReport>>,
+    pub negotiated_subtype: Section<String>,
+    pub frames: Section<FrameReport>,
+    pub controls: Section<Vec<ControlReport>>,
+    /// Whether each control (by ID) was judged functional.
+    pub functional_controls: Section<BTreeMap<String, bool>>,
+    pub capabilities: Section<BackendCapabilities>,
+}
+
+/// An advertised format.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatReport {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+    pub pixel_format: String,
+}
+
+/// Frame delivery of a running preview.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameReport {
+    pub frames_flowed: bool,
+    pub frame_count: u64,
+    pub drop_count: u64,
+    pub drop_rate: f64,
+    pub fps: f64,
+    pub panic_count: u64,
+}
+
+/// A control the camera exposes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlReport {
+    pub id: String,
+    pub supported: bool,
+    pub read_only: bool,
+    pub supports_auto: bool,
+}
+
+/// Gather what the backend knows about `device`. `diagnostics` are the
+/// stats of its running preview, if any.
+///
+/// The capture graph only logs the subtype it negotiates and no control
+/// effectiveness probe exists yet, so both are left unset.
+pub fn observe_device(
+    backend: &dyn CameraBackend,
+    device: CameraDevice,
+    diagnostics: Option<DiagnosticSnapshot>,
+) -> CameraObservation {
+    CameraObservation {
+        formats: backend.get_formats(&device.id).map_err(|e| e.to_string()),
+        controls: backend.get_controls(&device.id).map_err(|e| e.to_string()),
+        capabilities: backend
+            .device_capabilities(&device.id)
+            .map_err(|e| e.to_string()),
+        device,
+        negotiated_subtype: None,
+        diagnostics,
+        control_probe: None,
+    }
+}
+
+/// Assemble the report for `observations`, redacting serials, device paths
+/// and device IDs wherever they appear.
+pub fn assemble_report(
+    app_version: &str,
+    os: &str,
+    observations: &[CameraObservation],
+) -> CompatReport {
+    let redactor = Redactor::new(observations.iter().map(|o| &o.device));
+    let cameras = observations
+        .iter()
+        .enumerate()
+        .map(|(index, observation)| camera_report(index, observation, &redactor))
+        .collect();
+
+    CompatReport {
+        report_version: REPORT_VERSION,
+        app_version: app_version.to_string(),
+        os: os.to_string(),
+        cameras,
+    }
+}
+
+fn camera_report(
+    index: usize,
+    observation: &CameraObservation,
+    redactor: &Redactor,
+) -> CameraReport {
+    let device = &observation.device;
+
+    let formats = match &observation.formats {
+        Ok(formats) => Section::Collected {
+            data: formats
+                .iter()
+                .map(|f| FormatReport {
+                    width: f.width,
+                    height: f.height,
+                    fps: f.fps,
+                    pixel_format: redactor.redact(&f.pixel_format),
+                })
+                .collect(),
+        },
+        Err(e) => redactor.not_collected(e),
+    };
+
+    let negotiated_subtype = match &observation.negotiated_subtype {
+        Some(subtype) => Section::Collected {
+            data: redactor.redact(subtype),
+        },
+        None => redactor.not_collected(NOT_RECORDED),
+    };
+
+    let frames = match &observation.diagnostics {
+        Some(stats) => Section::Collected {
+            data: FrameReport {
+                frames_flowed: stats.frame_count > 0,
+                frame_count: stats.frame_count,
+                drop_count: stats.drop_count,
+                drop_rate: stats.drop_rate,
+                fps: stats.fps,
+                panic_count: stats.panic_count,
+            },
+        },
+        None => redactor.not_collected(NO_PREVIEW),
+    };
+
+    let controls = match &observation.controls {
+        Ok(controls) => Section::Collected {
+            data: controls
+                .iter()
+                .map(|c| ControlReport {
+                    id: redactor.redact(&c.id),
+                    supported: c.supported,
+                    read_only: c.flags.is_read_only,
+                    supports_auto: c.flags.supports_auto,
+                })
+                .collect(),
+        },
+        Err(e) => redactor.not_collected(e),
+    };
+
+    let functional_controls = match &observation.control_probe {
+        Some(probe) => Section::Collected {
+            data: probe
+                .iter()
+                .map(|(id, functional)| (redactor.redact(id), *functional))
+                .collect(),
+        },
+        None => redactor.not_collected(NOT_RECORDED),
+    };
+
+    let capabilities = match &observation.capabilities {
+        Ok(capabilities) => Section::Collected {
+            data: *capabilities,
+        },
+        Err(e) => redactor.not_collected(e),
+    };
+
+    CameraReport {
+        label: format!("camera-{}", index + 1),
+        vendor_product: vendor_product(&device.device_path),
+        name: redactor.redact(&device.name),
+        kind: device.kind,
+        formats,
+        negotiated_subtype,
+        frames,
+        controls,
+        functional_controls,
+        capabilities,
+    }
+}
+
+/// Replaces identifying strings of every reported device in free text.
+struct Redactor {
+    /// Longest first, so a device path is replaced before the serial in it.
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    fn new<'a>(devices: impl Iterator<Item = &'a CameraDevice>) -> Self {
+        let mut secrets = Vec::new();
+        for device in devices {
+            let path = device.device_path.as_str();
+            if !path.is_empty() {
+                secrets.push(path.to_string());
+                secrets.push(abbreviate_path(path).into_owned());
+            }
+            secrets.extend(device_serial(path));
+            // IDs derived from a friendly name hold nothing the name doesn't.
+            if !device.id.as_str().starts_with("name:") {
+                secrets.push(device.id.as_str().to_string());
+            }
+            if let Some(primary) = &device.primary_id {
+                secrets.push(primary.as_str().to_string());
+            }
+        }
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    /// `text` with every secret replaced, ignoring ASCII case.
+    fn redact(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_string(), |text, secret| {
+            replace_ignore_case(&text, secret)
+        })
+    }
+
+    /// A section missing for `reason`, redacted like any other text.
+    fn not_collected<T>(&self, reason: &str) -> Section<T> {
+        Section::NotCollected {
+            reason: self.redact(reason),
+        }
+    }
+}
+
+/// Replace each occurrence of `needle` in `text`, ignoring ASCII case.
+///
+/// ASCII lowercasing keeps byte offsets, so matches in the lowercased text
+/// index the original.
+fn replace_ignore_case(text: &str, needle: &str) -> String {
+    if needle.is_empty() {
+        return text.to_string();
+    }
+    let lower_text = text.to_ascii_lowercase();
+    let lower_needle = needle.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in lower_text.match_indices(&lower_needle) {
+        out.push_str(&text[last..start]);
+        out.push_str(REDACTED);
+        last = start + needle.len();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{ControlFlags, ControlType, DeviceId};
+
+    const PATH: &str =
+        r"\\?\usb#vid_046d&pid_085e&mi_00#6&1a2b3c4d&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}";
+    const SERIAL: &str = "6&1a2b3c4d&0&0000";
+
+    fn usb_device() -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::from_device_path(PATH),
+            name: "Logitech BRIO".to_string(),
+            device_path: PATH.to_string(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn control(id: &str) -> ControlDescriptor {
+        ControlDescriptor {
+            id: id.to_string(),
+            name: id.to_string(),
+            control_type: ControlType::Slider,
+            group: "image".to_string(),
+            min: Some(0),
+            max: Some(255),
+            step: Some(1),
+            default: Some(128),
+            default_auto: false,
+            current: 128,
+            flags: ControlFlags {
+                supports_auto: id == "focus",
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    fn observation(device: CameraDevice) -> CameraObservation {
+        CameraObservation {
+            device,
+            formats: Ok(vec![FormatDescriptor {
+                width: 1920,
+                height: 1080,
+                fps: 30.0,
+                pixel_format: "MJPG".to_string(),
+                min_fps: None,
+                max_fps: None,
+            }]),
+            negotiated_subtype: None,
+            diagnostics: None,
+            controls: Ok(vec![control("brightness"), control("focus")]),
+            control_probe: None,
+            capabilities: Ok(BackendCapabilities::default()),
+        }
+    }
+
+    #[test]
+    fn reports_usb_ids_formats_and_controls() {
+        let report = assemble_report("1.2.3", "windows", &[observation(usb_device())]);
+
+        assert_eq!(report.report_version, REPORT_VERSION);
+        assert_eq!(report.app_version, "1.2.3");
+        assert_eq!(report.os, "windows");
+        let camera = &report.cameras[0];
+        assert_eq!(camera.label, "camera-1");
+        assert_eq!(camera.vendor_product.as_deref(), Some("046d:085e"));
+        assert_eq!(camera.name, "Logitech BRIO");
+        assert_eq!(
+            camera.formats,
+            Section::Collected {
+                data: vec![FormatReport {
+                    width: 1920,
+                    height: 1080,
+                    fps: 30.0,
+                    pixel_format: "MJPG".to_string(),
+                }]
+            }
+        );
+        let Section::Collected { data: controls } = &camera.controls else {
+            panic!("controls not collected");
+        };
+        assert_eq!(controls.len(), 2);
+        assert!(controls[1].supports_auto);
+    }
+
+    #[test]
+    fn missing_data_is_marked_not_collected() {
+        let observation = CameraObservation {
+            formats: Err("device busy".to_string()),
+            capabilities: Err("device busy".to_string()),
+            ..observation(usb_device())
+        };
+        let report = assemble_report("1.2.3", "windows", &[observation]);
+        let camera = &report.cameras[0];
+
+        fn not_collected<T>(reason: &str) -> Section<T> {
+            Section::NotCollected {
+                reason: reason.to_string(),
+            }
+        }
+        assert_eq!(camera.formats, not_collected("device busy"));
+        assert_eq!(camera.negotiated_subtype, not_collected(NOT_RECORDED));
+        assert_eq!(camera.frames, not_collected(NO_PREVIEW));
+        assert_eq!(camera.functional_controls, not_collected(NOT_RECORDED));
+        assert_eq!(camera.capabilities, not_collected("device busy"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["cameras"][0]["frames"]["status"], "notCollected");
+        assert_eq!(json["cameras"][0]["formats"]["reason"], "device busy");
+    }
+
+    #[test]
+    fn collected_sections_carry_their_data() {
+        let observation = CameraObservation {
+            negotiated_subtype: Some("YUY2".to_string()),
+            diagnostics: Some(DiagnosticSnapshot {
+                fps: 29.5,
+                frame_count: 600,
+                drop_count: 6,
+                drop_rate: 0.01,
+                ..Default::default()
+            }),
+            control_probe: Some(BTreeMap::from([
+                ("brightness".to_string(), true),
+                ("focus".to_string(), false),
+            ])),
+            ..observation(usb_device())
+        };
+        let report = assemble_report("1.2.3", "windows", &[observation]);
+        let camera = &report.cameras[0];
+
+        assert_eq!(
+            camera.negotiated_subtype,
+            Section::Collected {
+                data: "YUY2".to_string()
+            }
+        );
+        let Section::Collected { data: frames } = &camera.frames else {
+            panic!("frames not collected");
+        };
+        assert!(frames.frames_flowed);
+        assert_eq!(frames.drop_count, 6);
+        assert_eq!(frames.drop_rate, 0.01);
+        let Section::Collected { data: functional } = &camera.functional_controls else {
+            panic!("control probe not collected");
+        };
+        assert_eq!(functional.get("focus"), Some(&false));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["cameras"][0]["frames"]["status"], "collected");
+        assert_eq!(json["cameras"][0]["frames"]["data"]["framesFlowed"], true);
+        assert_eq!(
+            json["cameras"][0]["capabilities"]["data"]["supportsPreview"],
+            true
+        );
+    }
+
+    #[test]
+    fn idle_preview_reports_no_frames_flowed() {
+        let observation = CameraObservation {
+            diagnostics: Some(DiagnosticSnapshot::default()),
+            ..observation(usb_device())
+        };
+        let report = assemble_report("1.2.3", "windows", &[observation]);
+        let Section::Collected { data: frames } = &report.cameras[0].frames else {
+            panic!("frames not collected");
+        };
+        assert!(!frames.frames_flowed);
+    }
+
+    #[test]
+    fn no_serial_path_or_device_id_appears_in_the_output() {
+        let device = usb_device();
+        let id = device.id.as_str().to_string();
+        assert!(id.contains(SERIAL));
+
+        // Identifying strings leak into names and error messages in practice.
+        let infrared = CameraDevice {
+            id: DeviceId::new(format!("{id}:ir")),
+            name: format!("BRIO IR ({SERIAL})"),
+            kind: DeviceKind::Infrared,
+            primary_id: Some(device.id.clone()),
+            ..device.clone()
+        };
+        let failing = CameraObservation {
+            formats: Err(format!("Camera not found: {}", PATH.to_uppercase())),
+            controls: Err(format!("Failed to open {}", abbreviate_path(PATH))),
+            negotiated_subtype: Some(format!("RGB24 via {id}")),
+            ..observation(infrared)
+        };
+        let report = assemble_report("1.2.3", "windows", &[observation(device), failing]);
+        let json = serde_json::to_string(&report).unwrap().to_lowercase();
+
+        assert!(!json.contains(SERIAL), "serial leaked: {json}");
+        assert!(!json.contains("1a2b3c4d"), "serial leaked: {json}");
+        assert!(!json.contains(&id), "device ID leaked: {json}");
+        let escaped_path = serde_json::to_string(PATH).unwrap();
+        assert!(
+            !json.contains(escaped_path.trim_matches('"')),
+            "device path leaked: {json}"
+        );
+        assert!(json.contains("046d:085e"));
+        assert!(json.contains(REDACTED));
+    }
+
+    #[test]
+    fn virtual_cameras_keep_their_name() {
+        let device = CameraDevice {
+            id: DeviceId::from_friendly_name("OBS Virtual Camera"),
+            name: "OBS Virtual Camera".to_string(),
+            device_path: String::new(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        };
+        let report = assemble_report("1.2.3", "windows", &[observation(device)]);
+        let camera = &report.cameras[0];
+        assert_eq!(camera.name, "OBS Virtual Camera");
+        assert_eq!(camera.vendor_product, None);
+    }
+
+    #[test]
+    fn replacement_ignores_case_and_keeps_surrounding_text() {
+        assert_eq!(
+            replace_ignore_case("id ABC-123 and abc-123.", "abc-123"),
+            "id [redacted] and [redacted]."
+        );
+        assert_eq!(replace_ignore_case("nothing here", "abc"), "nothing here");
+    }
+}
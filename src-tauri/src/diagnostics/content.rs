@@ -0,0 +1,343 @@
+// Content health — spots sessions that deliver frames with nothing in them.
+//
+// A privacy shutter, an HDCP-protected source behind a capture card or a
+// broken driver can keep the graph running and the sequence climbing while
+// every frame is black, or the same frame over and over. Every Nth frame is
+// sampled on a coarse grid; the classifier below turns those samples into a
+// health state with hysteresis, so a dark scene that briefly moves doesn't
+// flap between states.
+
+use serde::Serialize;
+
+/// Grid points sampled along each axis.
+const GRID: usize = 16;
+
+/// What the frames a session delivers look like.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentHealth {
+    #[default]
+    Ok,
+    /// Uniformly black.
+    Black,
+    /// The same frame repeated.
+    Frozen,
+}
+
+/// Sampling interval and classifier thresholds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentConfig {
+    /// Sample every Nth frame.
+    pub sample_every: u64,
+    /// Highest mean luma (0–255) that still counts as black.
+    pub black_mean_max: f64,
+    /// Highest luma variance that still counts as black. Sensor noise in a
+    /// dark but real scene sits well above this.
+    pub black_variance_max: f64,
+    /// Consecutive black samples before the session is reported black.
+    pub black_after: u32,
+    /// Consecutive samples repeating the previous checksum before the
+    /// session is reported frozen.
+    pub frozen_after: u32,
+    /// Consecutive healthy samples before a warning clears.
+    pub recover_after: u32,
+}
+
+impl Default for ContentConfig {
+    fn default() -> Self {
+        Self {
+            sample_every: 30,
+            black_mean_max: 16.0,
+            black_variance_max: 4.0,
+            black_after: 3,
+            frozen_after: 5,
+            recover_after: 2,
+        }
+    }
+}
+
+/// Luma statistics and a checksum from one sampled frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LumaSample {
+    pub mean: f64,
+    pub variance: f64,
+    /// FNV-1a over the sampled RGB values; only equality matters.
+    pub checksum: u64,
+}
+
+/// Sample a tightly packed RGB24 frame on a `GRID`x`GRID` grid.
+///
+/// Returns `None` if `rgb` is too short for `width`x`height`.
+pub fn sample_luma(rgb: &[u8], width: u32, height: u32) -> Option<LumaSample> {
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 || rgb.len() < width * height * 3 {
+        return None;
+    }
+
+    let mut checksum: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    let mut count = 0.0;
+    for gy in 0..GRID {
+        let y = (gy * 2 + 1) * height / (GRID * 2);
+        for gx in 0..GRID {
+            let x = (gx * 2 + 1) * width / (GRID * 2);
+            let i = (y * width + x) * 3;
+            let pixel = &rgb[i..i + 3];
+            for &byte in pixel {
+                checksum = (checksum ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+            // BT.601 luma
+            let luma = 0.299 * f64::from(pixel[0])
+                + 0.587 * f64::from(pixel[1])
+                + 0.114 * f64::from(pixel[2]);
+            sum += luma;
+            sum_sq += luma * luma;
+            count += 1.0;
+        }
+    }
+
+    let mean = sum / count;
+    Some(LumaSample {
+        mean,
+        variance: (sum_sq / count - mean * mean).max(0.0),
+        checksum,
+    })
+}
+
+/// Classifies a session's frames from periodic samples.
+#[derive(Debug, Clone)]
+pub struct ContentMonitor {
+    config: ContentConfig,
+    frames: u64,
+    health: ContentHealth,
+    last_checksum: Option<u64>,
+    black_run: u32,
+    repeat_run: u32,
+    healthy_run: u32,
+}
+
+impl ContentMonitor {
+    pub fn new(config: ContentConfig) -> Self {
+        Self {
+            config,
+            frames: 0,
+            health: ContentHealth::Ok,
+            last_checksum: None,
+            black_run: 0,
+            repeat_run: 0,
+            healthy_run: 0,
+        }
+    }
+
+    /// Current classification.
+    pub fn health(&self) -> ContentHealth {
+        self.health
+    }
+
+    /// Count a delivered RGB24 frame, sampling it if it's due.
+    ///
+    /// Returns the new classification when it changes.
+    pub fn on_frame(&mut self, rgb: &[u8], width: u32, height: u32) -> Option<ContentHealth> {
+        let due = self.frames % self.config.sample_every.max(1) == 0;
+        self.frames += 1;
+        if !due {
+            return None;
+        }
+        self.observe(sample_luma(rgb, width, height)?)
+    }
+
+    /// Feed one sample to the classifier, returning the new classification
+    /// when it changes.
+    ///
+    /// Black wins over frozen, since black frames also repeat. A warning
+    /// only clears after `recover_after` healthy samples in a row.
+    pub fn observe(&mut self, sample: LumaSample) -> Option<ContentHealth> {
+        let config = &self.config;
+        let is_black =
+            sample.mean <= config.black_mean_max && sample.variance <= config.black_variance_max;
+        let repeated = self.last_checksum == Some(sample.checksum);
+        self.last_checksum = Some(sample.checksum);
+
+        self.black_run = if is_black { self.black_run + 1 } else { 0 };
+        self.repeat_run = if repeated { self.repeat_run + 1 } else { 0 };
+        self.healthy_run = if is_black || repeated {
+            0
+        } else {
+            self.healthy_run + 1
+        };
+
+        let next = if self.black_run >= config.black_after {
+            ContentHealth::Black
+        } else if self.repeat_run >= config.frozen_after {
+            ContentHealth::Frozen
+        } else if self.healthy_run >= config.recover_after {
+            ContentHealth::Ok
+        } else {
+            self.health
+        };
+
+        if next == self.health {
+            return None;
+        }
+        self.health = next;
+        Some(next)
+    }
+}
+
+impl Default for ContentMonitor {
+    fn default() -> Self {
+        Self::new(ContentConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: u32 = 64;
+    const H: u32 = 48;
+
+    fn solid(value: u8) -> Vec<u8> {
+        vec![value; (W * H * 3) as usize]
+    }
+
+    /// A dark frame with low-level noise, different for each `seed`.
+    fn dark_noise(seed: u32) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..W * H * 3)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state % 24) as u8
+            })
+            .collect()
+    }
+
+    /// Feed `frames` to a monitor sampling every frame, returning each
+    /// reported change.
+    fn run(monitor: &mut ContentMonitor, frames: &[Vec<u8>]) -> Vec<ContentHealth> {
+        frames
+            .iter()
+            .filter_map(|f| monitor.on_frame(f, W, H))
+            .collect()
+    }
+
+    fn every_frame() -> ContentMonitor {
+        ContentMonitor::new(ContentConfig {
+            sample_every: 1,
+            ..ContentConfig::default()
+        })
+    }
+
+    #[test]
+    fn sample_of_a_solid_frame_has_no_variance() {
+        let sample = sample_luma(&solid(200), W, H).unwrap();
+        assert!((sample.mean - 200.0).abs() < 0.01);
+        assert!(sample.variance < 0.01);
+    }
+
+    #[test]
+    fn sample_of_noise_has_variance_and_distinct_checksums() {
+        let a = sample_luma(&dark_noise(1), W, H).unwrap();
+        let b = sample_luma(&dark_noise(2), W, H).unwrap();
+        assert!(a.variance > ContentConfig::default().black_variance_max);
+        assert_ne!(a.checksum, b.checksum);
+    }
+
+    #[test]
+    fn sample_rejects_a_short_buffer() {
+        assert!(sample_luma(&[0; 10], W, H).is_none());
+        assert!(sample_luma(&[], 0, 0).is_none());
+    }
+
+    #[test]
+    fn black_frames_are_reported_after_the_threshold() {
+        let mut monitor = every_frame();
+        let frames = vec![solid(0); 2];
+        assert!(run(&mut monitor, &frames).is_empty());
+        assert_eq!(
+            monitor.on_frame(&solid(0), W, H),
+            Some(ContentHealth::Black)
+        );
+        // No repeat reports while it stays black
+        assert!(run(&mut monitor, &vec![solid(0); 10]).is_empty());
+        assert_eq!(monitor.health(), ContentHealth::Black);
+    }
+
+    #[test]
+    fn repeated_frames_are_reported_frozen() {
+        let mut monitor = every_frame();
+        let frame = dark_noise(7);
+        // The first sample has nothing to repeat; five repeats follow
+        let changes = run(&mut monitor, &vec![frame; 6]);
+        assert_eq!(changes, vec![ContentHealth::Frozen]);
+    }
+
+    #[test]
+    fn dark_moving_scene_stays_ok() {
+        let mut monitor = every_frame();
+        let frames: Vec<_> = (0..50).map(dark_noise).collect();
+        assert!(run(&mut monitor, &frames).is_empty());
+        assert_eq!(monitor.health(), ContentHealth::Ok);
+    }
+
+    #[test]
+    fn brief_movement_does_not_clear_black() {
+        let mut monitor = every_frame();
+        run(&mut monitor, &vec![solid(0); 3]);
+        assert_eq!(monitor.health(), ContentHealth::Black);
+
+        // A single moving frame between black ones, repeatedly
+        let mut frames = Vec::new();
+        for seed in 0..5 {
+            frames.push(dark_noise(seed));
+            frames.push(solid(0));
+        }
+        assert!(run(&mut monitor, &frames).is_empty());
+        assert_eq!(monitor.health(), ContentHealth::Black);
+    }
+
+    #[test]
+    fn sustained_movement_clears_a_warning() {
+        let mut monitor = every_frame();
+        run(&mut monitor, &vec![solid(0); 3]);
+        let frames: Vec<_> = (0..2).map(dark_noise).collect();
+        assert_eq!(run(&mut monitor, &frames), vec![ContentHealth::Ok]);
+    }
+
+    #[test]
+    fn frozen_picture_going_black_reports_black() {
+        let mut monitor = every_frame();
+        let mut frames = vec![dark_noise(3); 6];
+        frames.extend(vec![solid(0); 3]);
+        assert_eq!(
+            run(&mut monitor, &frames),
+            vec![ContentHealth::Frozen, ContentHealth::Black]
+        );
+    }
+
+    #[test]
+    fn only_every_nth_frame_is_sampled() {
+        let mut monitor = ContentMonitor::new(ContentConfig {
+            sample_every: 30,
+            ..ContentConfig::default()
+        });
+        // Samples at frames 0, 30 and 60 — three samples, enough for black
+        let frames = vec![solid(0); 60];
+        assert!(run(&mut monitor, &frames).is_empty());
+        assert_eq!(
+            monitor.on_frame(&solid(0), W, H),
+            Some(ContentHealth::Black)
+        );
+    }
+
+    #[test]
+    fn health_serialises_camel_case() {
+        assert_eq!(
+            serde_json::to_value(ContentHealth::Frozen).unwrap(),
+            "frozen"
+        );
+    }
+}
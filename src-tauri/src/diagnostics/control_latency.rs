@@ -0,0 +1,271 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Control writes slower than this are logged as slow (once per control).
+pub const DEFAULT_SLOW_THRESHOLD: Duration = Duration::from_millis(250);
+
+/// Number of recent samples kept per (device, control) for mean/p95.
+const WINDOW_SIZE: usize = 64;
+
+/// Rolling latency samples for a single (device, control) pair.
+#[derive(Debug, Default)]
+struct LatencyWindow {
+    samples_us: VecDeque<u64>,
+    total_count: u64,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, elapsed: Duration) {
+        if self.samples_us.len() == WINDOW_SIZE {
+            self.samples_us.pop_front();
+        }
+        self.samples_us.push_back(elapsed.as_micros() as u64);
+        self.total_count += 1;
+    }
+
+    fn mean_us(&self) -> f64 {
+        if self.samples_us.is_empty() {
+            return 0.0;
+        }
+        self.samples_us.iter().sum::<u64>() as f64 / self.samples_us.len() as f64
+    }
+
+    /// 95th percentile using the nearest-rank method.
+    fn p95_us(&self) -> u64 {
+        if self.samples_us.is_empty() {
+            return 0;
+        }
+        let mut sorted: Vec<u64> = self.samples_us.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+        sorted[rank.saturating_sub(1)]
+    }
+}
+
+/// Latency statistics for one control, for IPC serialisation.
+///
+/// `count` is the total number of writes recorded; `mean_ms` and `p95_ms`
+/// cover the most recent writes only.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlLatencyStats {
+    pub control_id: String,
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// Accumulates per-(device, control) write latency.
+pub struct ControlLatencyTracker {
+    windows: HashMap<(String, String), LatencyWindow>,
+    warned: HashSet<(String, String)>,
+    slow_threshold: Duration,
+}
+
+impl ControlLatencyTracker {
+    /// Create a tracker that flags writes slower than `slow_threshold`.
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self {
+            windows: HashMap::new(),
+            warned: HashSet::new(),
+            slow_threshold,
+        }
+    }
+
+    /// Record a control write.
+    ///
+    /// Returns `true` the first time this control exceeds the slow
+    /// threshold, so the caller can log a warning exactly once.
+    pub fn record(&mut self, device_id: &str, control_id: &str, elapsed: Duration) -> bool {
+        let key = (device_id.to_string(), control_id.to_string());
+        self.windows.entry(key.clone()).or_default().record(elapsed);
+        elapsed > self.slow_threshold && self.warned.insert(key)
+    }
+
+    /// Latency statistics for every control written on a device, sorted by
+    /// control id.
+    pub fn stats_for_device(&self, device_id: &str) -> Vec<ControlLatencyStats> {
+        let mut stats: Vec<ControlLatencyStats> = self
+            .windows
+            .iter()
+            .filter(|((dev, _), _)| dev == device_id)
+            .map(|((_, control), window)| ControlLatencyStats {
+                control_id: control.clone(),
+                count: window.total_count,
+                mean_ms: window.mean_us() / 1000.0,
+                p95_ms: window.p95_us() as f64 / 1000.0,
+            })
+            .collect();
+        stats.sort_by(|a, b| a.control_id.cmp(&b.control_id));
+        stats
+    }
+}
+
+impl Default for ControlLatencyTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOW_THRESHOLD)
+    }
+}
+
+/// Order control writes so the slowest (by p95) are issued first.
+///
+/// Controls without recorded stats are treated as fast and keep their
+/// relative order after the known-slow ones.
+pub fn order_by_latency(stats: &[ControlLatencyStats], controls: &[String]) -> Vec<String> {
+    let p95_of = |control: &str| {
+        stats
+            .iter()
+            .find(|s| s.control_id == control)
+            .map_or(0.0, |s| s.p95_ms)
+    };
+    let mut ordered = controls.to_vec();
+    ordered.sort_by(|a, b| p95_of(b).total_cmp(&p95_of(a)));
+    ordered
+}
+
+/// Tauri-managed state wrapping the control latency tracker.
+#[derive(Default)]
+pub struct ControlLatencyState {
+    pub tracker: Mutex<ControlLatencyTracker>,
+}
+
+impl ControlLatencyState {
+    /// Run a control write, recording its wall time.
+    pub fn time_write<T>(&self, device_id: &str, control_id: &str, write: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = write();
+        let elapsed = start.elapsed();
+        if self.tracker.lock().record(device_id, control_id, elapsed) {
+            tracing::warn!(
+                "Slow control write: '{control_id}' on {device_id} took {}ms",
+                elapsed.as_millis()
+            );
+        }
+        result
+    }
+
+    /// Latency statistics for a device.
+    pub fn stats_for_device(&self, device_id: &str) -> Vec<ControlLatencyStats> {
+        self.tracker.lock().stats_for_device(device_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn record_accumulates_count_and_mean() {
+        let mut tracker = ControlLatencyTracker::default();
+        tracker.record("cam", "brightness", ms(10));
+        tracker.record("cam", "brightness", ms(30));
+
+        let stats = tracker.stats_for_device("cam");
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].count, 2);
+        assert!((stats[0].mean_ms - 20.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn p95_uses_nearest_rank() {
+        let mut tracker = ControlLatencyTracker::default();
+        for i in 1..=20 {
+            tracker.record("cam", "white_balance", ms(i));
+        }
+        let stats = tracker.stats_for_device("cam");
+        // ceil(20 * 0.95) = 19th smallest
+        assert!((stats[0].p95_ms - 19.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn window_keeps_only_recent_samples() {
+        let mut tracker = ControlLatencyTracker::default();
+        for _ in 0..WINDOW_SIZE {
+            tracker.record("cam", "focus", ms(500));
+        }
+        for _ in 0..WINDOW_SIZE {
+            tracker.record("cam", "focus", ms(10));
+        }
+        let stats = tracker.stats_for_device("cam");
+        assert_eq!(stats[0].count, (WINDOW_SIZE * 2) as u64);
+        assert!((stats[0].mean_ms - 10.0).abs() < 0.001);
+        assert!((stats[0].p95_ms - 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn stats_are_scoped_per_device_and_sorted() {
+        let mut tracker = ControlLatencyTracker::default();
+        tracker.record("cam-a", "zoom", ms(5));
+        tracker.record("cam-a", "brightness", ms(5));
+        tracker.record("cam-b", "contrast", ms(5));
+
+        let stats = tracker.stats_for_device("cam-a");
+        let ids: Vec<&str> = stats.iter().map(|s| s.control_id.as_str()).collect();
+        assert_eq!(ids, vec!["brightness", "zoom"]);
+        assert!(tracker.stats_for_device("cam-c").is_empty());
+    }
+
+    #[test]
+    fn slow_write_is_flagged_only_once() {
+        let mut tracker = ControlLatencyTracker::new(ms(250));
+        assert!(!tracker.record("cam", "white_balance", ms(100)));
+        assert!(tracker.record("cam", "white_balance", ms(400)));
+        assert!(!tracker.record("cam", "white_balance", ms(400)));
+        // A different control is flagged independently
+        assert!(tracker.record("cam", "focus", ms(300)));
+    }
+
+    #[test]
+    fn write_at_threshold_is_not_slow() {
+        let mut tracker = ControlLatencyTracker::new(ms(250));
+        assert!(!tracker.record("cam", "focus", ms(250)));
+    }
+
+    #[test]
+    fn order_puts_slowest_controls_first() {
+        let stats = vec![
+            ControlLatencyStats {
+                control_id: "brightness".to_string(),
+                count: 3,
+                mean_ms: 5.0,
+                p95_ms: 8.0,
+            },
+            ControlLatencyStats {
+                control_id: "white_balance".to_string(),
+                count: 3,
+                mean_ms: 400.0,
+                p95_ms: 450.0,
+            },
+        ];
+        let controls = vec![
+            "brightness".to_string(),
+            "contrast".to_string(),
+            "white_balance".to_string(),
+        ];
+
+        let ordered = order_by_latency(&stats, &controls);
+        assert_eq!(ordered, vec!["white_balance", "brightness", "contrast"]);
+    }
+
+    #[test]
+    fn order_preserves_input_order_without_stats() {
+        let controls = vec!["zoom".to_string(), "focus".to_string(), "iris".to_string()];
+        assert_eq!(order_by_latency(&[], &controls), controls);
+    }
+
+    #[test]
+    fn time_write_records_and_returns_result() {
+        let state = ControlLatencyState::default();
+        let result = state.time_write("cam", "gain", || 42);
+        assert_eq!(result, 42);
+        let stats = state.stats_for_device("cam");
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].count, 1);
+    }
+}
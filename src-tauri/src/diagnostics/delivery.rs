@@ -0,0 +1,195 @@
+// End-to-end frame delivery accounting.
+//
+// DiagnosticStats only sees drops inside the capture callback. Frames can
+// also be lost after that: a consumer polling slower than the camera never
+// sees some frames, and once the ring buffer wraps they are gone. Buffers
+// own a DeliveryTracker, consumers report the sequence number they used, and
+// the tracker works out how many frames each consumer saw or missed.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::Serialize;
+
+/// Consumer name for frames delivered to the preview by `get_frame`.
+pub const PREVIEW_CONSUMER: &str = "preview";
+
+/// Consumer name for frames rendered as sidebar thumbnails.
+pub const THUMBNAIL_CONSUMER: &str = "thumbnail";
+
+/// Consumer name for frames written as time-lapse stills.
+pub const TIMELAPSE_CONSUMER: &str = "timelapse";
+
+/// Delivery counts for one consumer of a frame buffer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameDelivery {
+    pub consumer: String,
+    /// Frames pushed into the buffer since the session started.
+    pub produced: u64,
+    /// Distinct frames this consumer has used.
+    pub consumed: u64,
+    /// Frames that left the buffer before this consumer saw them.
+    pub overwritten_unseen: u64,
+    /// Share of produced frames this consumer has used (0.0 - 100.0).
+    pub seen_percent: f64,
+}
+
+#[derive(Debug, Default)]
+struct ConsumerState {
+    consumed: u64,
+    last: u64,
+    /// Consumed sequences that may still be in the buffer, oldest first.
+    /// Holds at most `capacity` entries.
+    in_buffer: VecDeque<u64>,
+}
+
+/// Per-consumer accounting for a buffer holding the last `capacity` frames.
+///
+/// Sequences are the buffer's own 1-based push numbers. A frame with
+/// sequence `s` is overwritten once `s + capacity` has been pushed.
+#[derive(Debug)]
+pub struct DeliveryTracker {
+    capacity: u64,
+    consumers: HashMap<&'static str, ConsumerState>,
+}
+
+impl DeliveryTracker {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity as u64,
+            consumers: HashMap::new(),
+        }
+    }
+
+    /// Record that `consumer` used the frame with `sequence`.
+    ///
+    /// Re-reading the same frame (or an older one) is not counted again, and
+    /// sequences beyond `produced` are ignored.
+    pub fn record_consumed(&mut self, consumer: &'static str, sequence: u64, produced: u64) {
+        if sequence == 0 || sequence > produced {
+            return;
+        }
+        let state = self.consumers.entry(consumer).or_default();
+        if sequence <= state.last {
+            return;
+        }
+        state.consumed += 1;
+        state.last = sequence;
+        state.in_buffer.push_back(sequence);
+        // Anything at or below this can't be in the buffer any more
+        let evicted = sequence.saturating_sub(self.capacity);
+        while state.in_buffer.front().is_some_and(|&s| s <= evicted) {
+            state.in_buffer.pop_front();
+        }
+    }
+
+    /// Delivery counts for every consumer that has reported, sorted by name.
+    pub fn snapshot(&self, produced: u64) -> Vec<FrameDelivery> {
+        let evicted = produced.saturating_sub(self.capacity);
+        let mut deliveries: Vec<FrameDelivery> = self
+            .consumers
+            .iter()
+            .map(|(name, state)| {
+                let still_buffered = state.in_buffer.iter().filter(|&&s| s > evicted).count();
+                let consumed_evicted = state.consumed - still_buffered as u64;
+                FrameDelivery {
+                    consumer: (*name).to_string(),
+                    produced,
+                    consumed: state.consumed,
+                    overwritten_unseen: evicted - consumed_evicted,
+                    seen_percent: if produced == 0 {
+                        0.0
+                    } else {
+                        state.consumed as f64 / produced as f64 * 100.0
+                    },
+                }
+            })
+            .collect();
+        deliveries.sort_by(|a, b| a.consumer.cmp(&b.consumer));
+        deliveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delivery<'a>(snapshot: &'a [FrameDelivery], consumer: &str) -> &'a FrameDelivery {
+        snapshot.iter().find(|d| d.consumer == consumer).unwrap()
+    }
+
+    #[test]
+    fn empty_tracker_reports_no_consumers() {
+        assert!(DeliveryTracker::new(3).snapshot(10).is_empty());
+    }
+
+    #[test]
+    fn duplicate_and_stale_reports_count_once() {
+        let mut tracker = DeliveryTracker::new(3);
+        tracker.record_consumed("a", 2, 2);
+        tracker.record_consumed("a", 2, 2);
+        tracker.record_consumed("a", 1, 2);
+        let snapshot = tracker.snapshot(2);
+        assert_eq!(delivery(&snapshot, "a").consumed, 1);
+    }
+
+    #[test]
+    fn ignores_unproduced_sequences() {
+        let mut tracker = DeliveryTracker::new(3);
+        tracker.record_consumed("a", 0, 5);
+        tracker.record_consumed("a", 6, 5);
+        assert!(tracker.snapshot(5).is_empty());
+    }
+
+    #[test]
+    fn skipped_frames_become_overwritten_once_evicted() {
+        let mut tracker = DeliveryTracker::new(3);
+        tracker.record_consumed("a", 1, 4);
+        tracker.record_consumed("a", 4, 4);
+
+        // 2 and 3 are unseen but still in the ring
+        let d = tracker.snapshot(4)[0].clone();
+        assert_eq!((d.consumed, d.overwritten_unseen), (2, 0));
+
+        // Pushing 5 and 6 evicts 2 and 3
+        let d = tracker.snapshot(6)[0].clone();
+        assert_eq!((d.consumed, d.overwritten_unseen), (2, 2));
+    }
+
+    #[test]
+    fn produced_partitions_into_consumed_unseen_and_pending() {
+        let mut tracker = DeliveryTracker::new(3);
+        for seq in [2, 3, 7, 9] {
+            tracker.record_consumed("a", seq, seq);
+        }
+        // Evicted by 10: 1..=7. Consumed among those: 2, 3, 7.
+        let d = tracker.snapshot(10)[0].clone();
+        assert_eq!(d.produced, 10);
+        assert_eq!(d.consumed, 4);
+        assert_eq!(d.overwritten_unseen, 4);
+        // Frames 8 and 10 are still in the ring, unseen
+        assert_eq!(d.produced - d.consumed - d.overwritten_unseen, 2);
+    }
+
+    #[test]
+    fn seen_percent_tracks_half_rate_consumer() {
+        let mut tracker = DeliveryTracker::new(3);
+        for seq in (2..=100).step_by(2) {
+            tracker.record_consumed(PREVIEW_CONSUMER, seq, seq);
+        }
+        let d = delivery(&tracker.snapshot(100), PREVIEW_CONSUMER).clone();
+        assert_eq!(d.consumed, 50);
+        assert!((d.seen_percent - 50.0).abs() < 1e-9);
+        // Every odd frame up to 97 has been overwritten; 99 is still buffered
+        assert_eq!(d.overwritten_unseen, 49);
+    }
+
+    #[test]
+    fn single_slot_buffer_loses_every_skipped_frame() {
+        let mut tracker = DeliveryTracker::new(1);
+        tracker.record_consumed("a", 1, 1);
+        tracker.record_consumed("a", 5, 5);
+        let d = tracker.snapshot(5)[0].clone();
+        assert_eq!((d.consumed, d.overwritten_unseen), (2, 3));
+    }
+}
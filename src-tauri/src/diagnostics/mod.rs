@@ -1,3 +1,15 @@
 // Diagnostics — performance stats collection and reporting.
 
+pub mod accounting;
+pub mod benchmark;
+pub mod compat;
+pub mod content;
+pub mod control_latency;
+pub mod delivery;
+pub mod soak;
+#[cfg(feature = "app")]
+pub mod soak_task;
 pub mod stats;
+pub mod stream;
+#[cfg(feature = "app")]
+pub mod stream_task;
@@ -0,0 +1,851 @@
+// Soak test: random churn of previews, presets, controls, hotplug and
+// thumbnail sizes, with invariants checked after every operation.
+//
+// Leaked capture threads, lock-order deadlocks and hotplug state that's
+// never freed only show after hours of real-world use. The soak test
+// compresses that churn into a few minutes. Operations are picked by a
+// seeded scheduler from those that make sense in the modelled state, so a
+// failing run can be replayed from its seed. A SoakTarget carries them out:
+// the app's own state in soak_task, or a fake in the tests below.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+/// Most iterations a soak run accepts.
+pub const MAX_ITERATIONS: u32 = 100_000;
+
+/// Thumbnail widths the scheduler picks from.
+const THUMBNAIL_WIDTHS: [u32; 5] = [96, 160, 240, 320, 480];
+
+/// Small deterministic RNG (SplitMix64). Not for anything but picking soak
+/// operations: the same seed must give the same run on every platform and
+/// in every version, which a dependency can't promise.
+#[derive(Debug, Clone)]
+pub struct SoakRng {
+    state: u64,
+}
+
+impl SoakRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`, which must be positive. The slight bias of
+    /// taking the remainder doesn't matter here.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// One thing the soak test does. Devices are indexes into the target's
+/// soak devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SoakOp {
+    /// Start (or restart) the device's preview.
+    StartPreview { device: usize },
+    /// Stop the device's preview; also done when it has none.
+    StopPreview { device: usize },
+    /// Apply the soak preset.
+    ApplyPreset { device: usize },
+    /// Set a control to `percent` of the way through its range.
+    SetControl { device: usize, percent: u8 },
+    /// Handle the device disconnecting, as the hotplug watcher would.
+    Unplug { device: usize },
+    /// Handle the device connecting again, which auto-starts its preview.
+    Replug { device: usize },
+    /// Change every device's thumbnail size.
+    ResizeThumbnails { width: u32 },
+}
+
+impl SoakOp {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::StartPreview { .. } => "startPreview",
+            Self::StopPreview { .. } => "stopPreview",
+            Self::ApplyPreset { .. } => "applyPreset",
+            Self::SetControl { .. } => "setControl",
+            Self::Unplug { .. } => "unplug",
+            Self::Replug { .. } => "replug",
+            Self::ResizeThumbnails { .. } => "resizeThumbnails",
+        }
+    }
+}
+
+impl fmt::Display for SoakOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StartPreview { device }
+            | Self::StopPreview { device }
+            | Self::ApplyPreset { device }
+            | Self::Unplug { device }
+            | Self::Replug { device } => write!(f, "{} on device {device}", self.name()),
+            Self::SetControl { device, percent } => {
+                write!(f, "{} to {percent}% on device {device}", self.name())
+            }
+            Self::ResizeThumbnails { width } => write!(f, "{} to {width}px", self.name()),
+        }
+    }
+}
+
+/// How often each kind of operation is picked, relative to the others. A
+/// zero weight leaves that kind out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpWeights {
+    pub start_preview: u32,
+    pub stop_preview: u32,
+    pub apply_preset: u32,
+    pub set_control: u32,
+    pub unplug: u32,
+    pub replug: u32,
+    pub resize_thumbnails: u32,
+}
+
+impl Default for OpWeights {
+    fn default() -> Self {
+        Self {
+            start_preview: 4,
+            stop_preview: 3,
+            apply_preset: 2,
+            set_control: 4,
+            unplug: 1,
+            replug: 1,
+            resize_thumbnails: 2,
+        }
+    }
+}
+
+/// What the soak test expects of the soak devices: which are connected
+/// and which have a preview session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoakModel {
+    connected: Vec<bool>,
+    previewing: Vec<bool>,
+}
+
+impl SoakModel {
+    /// `devices` devices, all connected and none previewing.
+    pub fn new(devices: usize) -> Self {
+        Self {
+            connected: vec![true; devices],
+            previewing: vec![false; devices],
+        }
+    }
+
+    pub fn devices(&self) -> usize {
+        self.connected.len()
+    }
+
+    pub fn is_connected(&self, device: usize) -> bool {
+        self.connected[device]
+    }
+
+    pub fn is_previewing(&self, device: usize) -> bool {
+        self.previewing[device]
+    }
+
+    /// Preview sessions the soak devices should have.
+    pub fn expected_sessions(&self) -> usize {
+        self.previewing.iter().filter(|&&p| p).count()
+    }
+
+    /// Update the expectation for `op` having succeeded.
+    pub fn apply(&mut self, op: &SoakOp) {
+        match *op {
+            SoakOp::StartPreview { device } | SoakOp::Replug { device } => {
+                self.connected[device] = true;
+                self.previewing[device] = true;
+            }
+            SoakOp::StopPreview { device } => self.previewing[device] = false,
+            SoakOp::Unplug { device } => {
+                self.connected[device] = false;
+                self.previewing[device] = false;
+            }
+            SoakOp::ApplyPreset { .. }
+            | SoakOp::SetControl { .. }
+            | SoakOp::ResizeThumbnails { .. } => {}
+        }
+    }
+}
+
+/// Kinds of operation, for weighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpKind {
+    StartPreview,
+    StopPreview,
+    ApplyPreset,
+    SetControl,
+    Unplug,
+    Replug,
+    ResizeThumbnails,
+}
+
+impl OpKind {
+    const ALL: [Self; 7] = [
+        Self::StartPreview,
+        Self::StopPreview,
+        Self::ApplyPreset,
+        Self::SetControl,
+        Self::Unplug,
+        Self::Replug,
+        Self::ResizeThumbnails,
+    ];
+
+    fn weight(self, weights: &OpWeights) -> u32 {
+        match self {
+            Self::StartPreview => weights.start_preview,
+            Self::StopPreview => weights.stop_preview,
+            Self::ApplyPreset => weights.apply_preset,
+            Self::SetControl => weights.set_control,
+            Self::Unplug => weights.unplug,
+            Self::Replug => weights.replug,
+            Self::ResizeThumbnails => weights.resize_thumbnails,
+        }
+    }
+
+    /// Devices this kind of operation can be done to in `model`'s state.
+    /// Disconnected devices can only be replugged or stopped.
+    fn eligible(self, model: &SoakModel) -> Vec<usize> {
+        (0..model.devices())
+            .filter(|&d| match self {
+                Self::StopPreview => true,
+                Self::Replug => !model.is_connected(d),
+                _ => model.is_connected(d),
+            })
+            .collect()
+    }
+}
+
+/// Picks operations at random, weighted, from those that make sense in
+/// the modelled state.
+#[derive(Debug, Clone)]
+pub struct SoakScheduler {
+    rng: SoakRng,
+    weights: OpWeights,
+}
+
+impl SoakScheduler {
+    pub fn new(seed: u64, weights: OpWeights) -> Self {
+        Self {
+            rng: SoakRng::new(seed),
+            weights,
+        }
+    }
+
+    /// The next operation, or `None` if no weighted kind can be done in
+    /// `model`'s state.
+    pub fn next(&mut self, model: &SoakModel) -> Option<SoakOp> {
+        let candidates: Vec<(OpKind, u32, Vec<usize>)> = OpKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                let weight = kind.weight(&self.weights);
+                let devices = kind.eligible(model);
+                let usable =
+                    weight > 0 && (kind == OpKind::ResizeThumbnails || !devices.is_empty());
+                usable.then_some((kind, weight, devices))
+            })
+            .collect();
+        let total: u64 = candidates.iter().map(|(_, w, _)| u64::from(*w)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = self.rng.below(total);
+        let (kind, _, devices) = candidates
+            .into_iter()
+            .find(|(_, weight, _)| {
+                let hit = pick < u64::from(*weight);
+                pick = pick.saturating_sub(u64::from(*weight));
+                hit
+            })
+            .expect("pick is below the total weight");
+
+        let op = if kind == OpKind::ResizeThumbnails {
+            let width = THUMBNAIL_WIDTHS[self.rng.below(THUMBNAIL_WIDTHS.len() as u64) as usize];
+            SoakOp::ResizeThumbnails { width }
+        } else {
+            let device = devices[self.rng.below(devices.len() as u64) as usize];
+            match kind {
+                OpKind::StartPreview => SoakOp::StartPreview { device },
+                OpKind::StopPreview => SoakOp::StopPreview { device },
+                OpKind::ApplyPreset => SoakOp::ApplyPreset { device },
+                OpKind::SetControl => SoakOp::SetControl {
+                    device,
+                    percent: self.rng.below(101) as u8,
+                },
+                OpKind::Unplug => SoakOp::Unplug { device },
+                OpKind::Replug => SoakOp::Replug { device },
+                OpKind::ResizeThumbnails => unreachable!(),
+            }
+        };
+        Some(op)
+    }
+}
+
+/// What a target looks like after an operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoakObservation {
+    /// Preview sessions of the soak devices.
+    pub sessions: usize,
+    /// Threads in the process, where the OS reports it.
+    pub threads: Option<usize>,
+    /// Bytes of frames held by session buffers.
+    pub buffer_bytes: usize,
+    /// Bytes held by the frame and thumbnail caches.
+    pub cache_bytes: usize,
+    /// Locks that couldn't be taken in time (or were poisoned).
+    pub stuck_locks: Vec<String>,
+}
+
+/// Bounds a target's observations must stay within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoakLimits {
+    /// Threads each expected session may add over the baseline.
+    pub threads_per_session: usize,
+    /// Further threads allowed over the baseline, for pools that grow
+    /// lazily.
+    pub thread_slack: usize,
+    /// Buffered bytes each expected session may hold.
+    pub buffer_bytes_per_session: usize,
+    /// Bytes the caches may hold in total.
+    pub cache_bytes: usize,
+}
+
+/// Compare an observation with what `model` expects. `baseline_threads` is
+/// the thread count before the first operation.
+pub fn check(
+    model: &SoakModel,
+    limits: &SoakLimits,
+    baseline_threads: Option<usize>,
+    observed: &SoakObservation,
+) -> Result<(), String> {
+    if !observed.stuck_locks.is_empty() {
+        return Err(format!(
+            "locks not released: {}",
+            observed.stuck_locks.join(", ")
+        ));
+    }
+
+    let expected = model.expected_sessions();
+    if observed.sessions != expected {
+        return Err(format!(
+            "{} preview sessions, expected {expected}",
+            observed.sessions
+        ));
+    }
+
+    if let (Some(baseline), Some(threads)) = (baseline_threads, observed.threads) {
+        let bound = baseline + limits.thread_slack + limits.threads_per_session * expected;
+        if threads > bound {
+            return Err(format!(
+                "{threads} threads, more than {bound} ({baseline} at the start)"
+            ));
+        }
+    }
+
+    let buffer_bound = limits.buffer_bytes_per_session * expected;
+    if observed.buffer_bytes > buffer_bound {
+        return Err(format!(
+            "{} bytes of buffered frames, more than {buffer_bound}",
+            observed.buffer_bytes
+        ));
+    }
+
+    if observed.cache_bytes > limits.cache_bytes {
+        return Err(format!(
+            "{} bytes cached, more than {}",
+            observed.cache_bytes, limits.cache_bytes
+        ));
+    }
+
+    Ok(())
+}
+
+/// Where soak operations are carried out.
+pub trait SoakTarget {
+    /// Number of devices operations are spread over.
+    fn devices(&self) -> usize;
+
+    fn limits(&self) -> SoakLimits;
+
+    fn perform(&mut self, op: &SoakOp) -> Result<(), String>;
+
+    fn observe(&mut self) -> SoakObservation;
+}
+
+/// The first broken invariant of a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoakViolation {
+    /// Iteration the violation showed after, counting from 1; 0 if the
+    /// target was already broken before the first operation.
+    pub iteration: u32,
+    /// The operation of that iteration.
+    pub operation: Option<SoakOp>,
+    pub message: String,
+}
+
+/// Outcome of a soak run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoakReport {
+    /// Seed to pass to reproduce the run.
+    pub seed: u64,
+    pub iterations: u32,
+    /// Iterations whose operation was done and passed the checks.
+    pub completed: u32,
+    /// Operations done, by kind.
+    pub operations: BTreeMap<String, u32>,
+    pub violation: Option<SoakViolation>,
+}
+
+/// Run `iterations` operations picked from `seed` against `target`,
+/// checking invariants after each. Stops at the first violation; an
+/// operation that fails counts as one.
+pub fn run_soak(
+    target: &mut dyn SoakTarget,
+    iterations: u32,
+    seed: u64,
+    weights: OpWeights,
+) -> SoakReport {
+    let mut report = SoakReport {
+        seed,
+        iterations,
+        completed: 0,
+        operations: BTreeMap::new(),
+        violation: None,
+    };
+    let mut model = SoakModel::new(target.devices());
+    let mut scheduler = SoakScheduler::new(seed, weights);
+    let limits = target.limits();
+
+    let baseline = target.observe();
+    if let Err(message) = check(&model, &limits, None, &baseline) {
+        report.violation = Some(SoakViolation {
+            iteration: 0,
+            operation: None,
+            message,
+        });
+        return report;
+    }
+
+    for iteration in 1..=iterations {
+        let Some(op) = scheduler.next(&model) else {
+            break;
+        };
+        *report.operations.entry(op.name().to_string()).or_default() += 1;
+
+        let outcome = target
+            .perform(&op)
+            .map_err(|e| format!("{op} failed: {e}"))
+            .and_then(|()| {
+                model.apply(&op);
+                check(&model, &limits, baseline.threads, &target.observe())
+            });
+        if let Err(message) = outcome {
+            tracing::warn!("Soak test (seed {seed}) broke at iteration {iteration}: {message}");
+            report.violation = Some(SoakViolation {
+                iteration,
+                operation: Some(op),
+                message,
+            });
+            return report;
+        }
+        report.completed = iteration;
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: SoakLimits = SoakLimits {
+        threads_per_session: 3,
+        thread_slack: 2,
+        buffer_bytes_per_session: 1000,
+        cache_bytes: 5000,
+    };
+
+    /// A target that tracks sessions like the app should, with switches
+    /// for the bugs the soak test is meant to catch.
+    #[derive(Default)]
+    struct FakeTarget {
+        devices: usize,
+        sessions: Vec<bool>,
+        threads: usize,
+        performed: Vec<SoakOp>,
+        /// Stopping leaves the session's threads running.
+        leak_threads: bool,
+        /// Unplugging doesn't stop the session.
+        keep_session_on_unplug: bool,
+        /// Every resize grows the cache without bound.
+        unbounded_cache: bool,
+        cache_bytes: usize,
+        /// Fail the nth operation (counting from 1).
+        fail_at: Option<usize>,
+        stuck_after: Option<usize>,
+    }
+
+    impl FakeTarget {
+        fn new(devices: usize) -> Self {
+            Self {
+                devices,
+                sessions: vec![false; devices],
+                threads: 10,
+                ..Self::default()
+            }
+        }
+
+        fn stop(&mut self, device: usize) {
+            if std::mem::take(&mut self.sessions[device]) && !self.leak_threads {
+                self.threads -= 3;
+            }
+        }
+
+        fn start(&mut self, device: usize) {
+            self.stop(device);
+            self.sessions[device] = true;
+            self.threads += 3;
+        }
+    }
+
+    impl SoakTarget for FakeTarget {
+        fn devices(&self) -> usize {
+            self.devices
+        }
+
+        fn limits(&self) -> SoakLimits {
+            LIMITS
+        }
+
+        fn perform(&mut self, op: &SoakOp) -> Result<(), String> {
+            self.performed.push(*op);
+            if self.fail_at == Some(self.performed.len()) {
+                return Err("camera busy".to_string());
+            }
+            match *op {
+                SoakOp::StartPreview { device } | SoakOp::Replug { device } => self.start(device),
+                SoakOp::StopPreview { device } => self.stop(device),
+                SoakOp::Unplug { device } if !self.keep_session_on_unplug => self.stop(device),
+                SoakOp::ResizeThumbnails { width } => {
+                    self.cache_bytes = if self.unbounded_cache {
+                        self.cache_bytes + width as usize * 10
+                    } else {
+                        width as usize
+                    };
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+
+        fn observe(&mut self) -> SoakObservation {
+            let stuck = self.stuck_after.is_some_and(|n| self.performed.len() >= n);
+            SoakObservation {
+                sessions: self.sessions.iter().filter(|&&s| s).count(),
+                threads: Some(self.threads),
+                buffer_bytes: 0,
+                cache_bytes: self.cache_bytes,
+                stuck_locks: if stuck {
+                    vec!["preview sessions".to_string()]
+                } else {
+                    Vec::new()
+                },
+            }
+        }
+    }
+
+    fn ops(seed: u64, weights: OpWeights, devices: usize, count: usize) -> Vec<SoakOp> {
+        let mut model = SoakModel::new(devices);
+        let mut scheduler = SoakScheduler::new(seed, weights);
+        (0..count)
+            .map(|_| {
+                let op = scheduler.next(&model).unwrap();
+                model.apply(&op);
+                op
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rng_matches_reference_splitmix64() {
+        let mut rng = SoakRng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(rng.next_u64(), 0x06c4_5d18_8009_454f);
+    }
+
+    #[test]
+    fn same_seed_gives_same_operations() {
+        let weights = OpWeights::default();
+        assert_eq!(ops(42, weights, 3, 200), ops(42, weights, 3, 200));
+        assert_ne!(ops(42, weights, 3, 200), ops(43, weights, 3, 200));
+    }
+
+    #[test]
+    fn zero_weights_leave_kinds_out() {
+        let weights = OpWeights {
+            start_preview: 1,
+            stop_preview: 1,
+            apply_preset: 0,
+            set_control: 0,
+            unplug: 0,
+            replug: 0,
+            resize_thumbnails: 0,
+        };
+        for op in ops(7, weights, 2, 500) {
+            assert!(
+                matches!(op, SoakOp::StartPreview { .. } | SoakOp::StopPreview { .. }),
+                "{op}"
+            );
+        }
+    }
+
+    #[test]
+    fn weights_set_how_often_kinds_are_picked() {
+        let weights = OpWeights {
+            start_preview: 3,
+            stop_preview: 1,
+            apply_preset: 0,
+            set_control: 0,
+            unplug: 0,
+            replug: 0,
+            resize_thumbnails: 0,
+        };
+        let picked = ops(11, weights, 1, 4000);
+        let starts = picked
+            .iter()
+            .filter(|op| matches!(op, SoakOp::StartPreview { .. }))
+            .count();
+        assert!((2800..3200).contains(&starts), "{starts} starts");
+    }
+
+    #[test]
+    fn disconnected_devices_are_only_replugged_or_stopped() {
+        let mut model = SoakModel::new(3);
+        let mut scheduler = SoakScheduler::new(5, OpWeights::default());
+        for _ in 0..2000 {
+            let op = scheduler.next(&model).unwrap();
+            match op {
+                SoakOp::Replug { device } => assert!(!model.is_connected(device)),
+                SoakOp::StopPreview { .. } | SoakOp::ResizeThumbnails { .. } => {}
+                SoakOp::StartPreview { device }
+                | SoakOp::ApplyPreset { device }
+                | SoakOp::SetControl { device, .. }
+                | SoakOp::Unplug { device } => assert!(model.is_connected(device)),
+            }
+            model.apply(&op);
+        }
+    }
+
+    #[test]
+    fn nothing_to_do_ends_the_schedule() {
+        let weights = OpWeights {
+            start_preview: 0,
+            stop_preview: 0,
+            apply_preset: 0,
+            set_control: 0,
+            unplug: 0,
+            replug: 1,
+            resize_thumbnails: 0,
+        };
+        // Every device is connected, so there's nothing to replug
+        let mut scheduler = SoakScheduler::new(1, weights);
+        assert_eq!(scheduler.next(&SoakModel::new(2)), None);
+    }
+
+    #[test]
+    fn model_follows_sessions_through_hotplug() {
+        let mut model = SoakModel::new(2);
+        model.apply(&SoakOp::StartPreview { device: 0 });
+        model.apply(&SoakOp::StartPreview { device: 1 });
+        assert_eq!(model.expected_sessions(), 2);
+
+        model.apply(&SoakOp::Unplug { device: 0 });
+        assert!(!model.is_connected(0));
+        assert_eq!(model.expected_sessions(), 1);
+
+        model.apply(&SoakOp::Replug { device: 0 });
+        assert!(model.is_previewing(0));
+        model.apply(&SoakOp::StopPreview { device: 1 });
+        assert_eq!(model.expected_sessions(), 1);
+    }
+
+    #[test]
+    fn check_reports_each_broken_invariant() {
+        let mut model = SoakModel::new(1);
+        model.apply(&SoakOp::StartPreview { device: 0 });
+        let healthy = SoakObservation {
+            sessions: 1,
+            threads: Some(13),
+            buffer_bytes: 1000,
+            cache_bytes: 5000,
+            stuck_locks: Vec::new(),
+        };
+        assert_eq!(check(&model, &LIMITS, Some(10), &healthy), Ok(()));
+
+        let cases = [
+            (
+                SoakObservation {
+                    sessions: 2,
+                    ..healthy.clone()
+                },
+                "2 preview sessions",
+            ),
+            (
+                SoakObservation {
+                    threads: Some(16),
+                    ..healthy.clone()
+                },
+                "16 threads",
+            ),
+            (
+                SoakObservation {
+                    buffer_bytes: 1001,
+                    ..healthy.clone()
+                },
+                "buffered",
+            ),
+            (
+                SoakObservation {
+                    cache_bytes: 5001,
+                    ..healthy.clone()
+                },
+                "cached",
+            ),
+            (
+                SoakObservation {
+                    stuck_locks: vec!["preview sessions".to_string()],
+                    ..healthy.clone()
+                },
+                "locks not released: preview sessions",
+            ),
+        ];
+        for (observed, message) in cases {
+            let error = check(&model, &LIMITS, Some(10), &observed).unwrap_err();
+            assert!(error.contains(message), "{error}");
+        }
+
+        // Without a thread count, thread growth isn't checked
+        let unknown = SoakObservation {
+            threads: None,
+            ..healthy
+        };
+        assert_eq!(check(&model, &LIMITS, Some(10), &unknown), Ok(()));
+    }
+
+    #[test]
+    fn a_healthy_target_completes_every_iteration() {
+        let mut target = FakeTarget::new(3);
+        let report = run_soak(&mut target, 300, 9, OpWeights::default());
+        assert_eq!(report.violation, None);
+        assert_eq!(report.completed, 300);
+        assert_eq!(report.seed, 9);
+        assert_eq!(report.operations.values().sum::<u32>(), 300);
+    }
+
+    #[test]
+    fn leaked_threads_are_caught() {
+        let mut target = FakeTarget::new(2);
+        target.leak_threads = true;
+        let report = run_soak(&mut target, 300, 3, OpWeights::default());
+        let violation = report.violation.unwrap();
+        assert!(
+            violation.message.contains("threads"),
+            "{}",
+            violation.message
+        );
+        assert_eq!(report.completed, violation.iteration - 1);
+    }
+
+    #[test]
+    fn sessions_left_behind_by_unplug_are_caught() {
+        let mut target = FakeTarget::new(2);
+        target.keep_session_on_unplug = true;
+        let report = run_soak(&mut target, 500, 4, OpWeights::default());
+        let violation = report.violation.unwrap();
+        assert!(matches!(violation.operation, Some(SoakOp::Unplug { .. })));
+        assert!(
+            violation.message.contains("expected"),
+            "{}",
+            violation.message
+        );
+    }
+
+    #[test]
+    fn unbounded_caches_are_caught() {
+        let mut target = FakeTarget::new(1);
+        target.unbounded_cache = true;
+        let report = run_soak(&mut target, 500, 8, OpWeights::default());
+        let violation = report.violation.unwrap();
+        assert!(
+            violation.message.contains("cached"),
+            "{}",
+            violation.message
+        );
+    }
+
+    #[test]
+    fn failed_operations_and_stuck_locks_stop_the_run() {
+        let mut target = FakeTarget::new(1);
+        target.fail_at = Some(5);
+        let report = run_soak(&mut target, 100, 2, OpWeights::default());
+        let violation = report.violation.unwrap();
+        assert_eq!(violation.iteration, 5);
+        assert!(violation.message.ends_with("failed: camera busy"));
+        assert_eq!(report.completed, 4);
+
+        let mut target = FakeTarget::new(1);
+        target.stuck_after = Some(1);
+        let report = run_soak(&mut target, 100, 2, OpWeights::default());
+        assert_eq!(report.violation.unwrap().iteration, 1);
+    }
+
+    #[test]
+    fn a_violation_replays_from_its_seed() {
+        let run = |seed| {
+            let mut target = FakeTarget::new(2);
+            target.keep_session_on_unplug = true;
+            let report = run_soak(&mut target, 500, seed, OpWeights::default());
+            (report, target.performed)
+        };
+        let (first, first_ops) = run(21);
+        let (again, again_ops) = run(first.seed);
+        assert_eq!(first, again);
+        assert_eq!(first_ops, again_ops);
+    }
+
+    #[test]
+    fn report_serialises_to_camel_case() {
+        let report = SoakReport {
+            seed: 1,
+            iterations: 2,
+            completed: 1,
+            operations: BTreeMap::from([("setControl".to_string(), 2)]),
+            violation: Some(SoakViolation {
+                iteration: 2,
+                operation: Some(SoakOp::SetControl {
+                    device: 0,
+                    percent: 40,
+                }),
+                message: "boom".to_string(),
+            }),
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["operations"]["setControl"], 2);
+        assert_eq!(json["violation"]["iteration"], 2);
+        assert_eq!(json["violation"]["operation"]["kind"], "setControl");
+        assert_eq!(json["violation"]["operation"]["percent"], 40);
+    }
+}
@@ -0,0 +1,300 @@
+// Runs the soak test against the app itself.
+//
+// The soak devices are the dummy camera (DUMMY_CAMERA=1), so a run never
+// touches a real camera. Operations go through the same commands the
+// frontend calls and the same handling the hotplug watcher does. A run
+// writes to the dummy camera's saved settings, leaves a "soak-test" preset
+// behind and ends with the dummy camera's preview stopped.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager};
+
+use crate::camera::commands::CameraState;
+use crate::camera::dummy::DummyBackend;
+use crate::camera::hotplug_bridge::handle_hotplug_event;
+use crate::camera::types::{CameraDevice, ControlId, HotplugEvent};
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::diagnostics::soak::{
+    run_soak, OpWeights, SoakLimits, SoakObservation, SoakOp, SoakReport, SoakTarget,
+    MAX_ITERATIONS,
+};
+use crate::error::{code, AppError};
+use crate::preset::commands::queue_preset_apply;
+use crate::preset::types::Preset;
+use crate::preview::commands::{
+    configure_thumbnails, get_thumbnail, start_preview, stop_preview, stop_preview_session,
+    PreviewState, AUTO_START_FPS, AUTO_START_SIZE,
+};
+use crate::preview::limits::process_thread_count;
+use crate::preview::mode::SessionMode;
+use crate::settings::apply::{find_descriptor, write_control};
+use crate::settings::commands::SettingsState;
+
+/// Preset the soak test applies, captured from the dummy camera at the start.
+const SOAK_PRESET_ID: &str = "soak-test";
+
+/// Control the soak test moves.
+const SOAK_CONTROL: ControlId = ControlId::Brightness;
+
+/// How long a lock may stay taken before it counts as stuck.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Capture, watchdog and encode worker threads.
+const THREADS_PER_SESSION: usize = 3;
+
+/// Threads the async runtime and device queue may add while the test runs.
+const THREAD_SLACK: usize = 8;
+
+/// Set while a soak test runs; only one may run at a time.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Whether the soak test may run: in debug builds, or with `SOAK_TEST=1`.
+pub fn soak_test_enabled() -> bool {
+    cfg!(debug_assertions) || std::env::var("SOAK_TEST").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// The app's own state, driven on the soak devices.
+struct AppTarget {
+    app: AppHandle,
+    devices: Vec<CameraDevice>,
+}
+
+impl AppTarget {
+    fn id(&self, device: usize) -> String {
+        self.devices[device].id.to_string()
+    }
+
+    /// Set the soak control `percent` of the way through its range, as the
+    /// control slider does.
+    fn set_control(&self, device: usize, percent: u8) -> Result<(), AppError> {
+        let camera = self.app.state::<CameraState>();
+        let settings = self.app.state::<SettingsState>();
+        let latency = self.app.state::<ControlLatencyState>();
+        let device = &self.devices[device];
+
+        let desc = find_descriptor(&camera.backend, &device.id, &SOAK_CONTROL)?;
+        let (min, max) = (desc.min.unwrap_or(0), desc.max.unwrap_or(0));
+        let value = min + ((i64::from(max - min) * i64::from(percent)) / 100) as i32;
+        write_control(
+            &camera.backend,
+            &settings.store,
+            &latency,
+            device.id.as_str(),
+            &device.name,
+            SOAK_CONTROL,
+            value,
+        )
+        .map(drop)
+    }
+
+    /// Change every device's thumbnail size, then draw a thumbnail for each
+    /// device with a frame so the cache churns too.
+    fn resize_thumbnails(&self, width: u32) -> Result<(), AppError> {
+        let app = &self.app;
+        tauri::async_runtime::block_on(configure_thumbnails(
+            app.state(),
+            width,
+            width * 3 / 4,
+            1.0,
+            None,
+        ))?;
+        for device in 0..self.devices.len() {
+            // Fails until the session has a frame, which is fine here
+            let _ = tauri::async_runtime::block_on(get_thumbnail(
+                app.state(),
+                self.id(device),
+                None,
+                None,
+                None,
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl SoakTarget for AppTarget {
+    fn devices(&self) -> usize {
+        self.devices.len()
+    }
+
+    fn limits(&self) -> SoakLimits {
+        let (width, height) = AUTO_START_SIZE;
+        let frame_bytes = width as usize * height as usize * 3;
+        SoakLimits {
+            threads_per_session: THREADS_PER_SESSION,
+            thread_slack: THREAD_SLACK,
+            // Raw frames plus the latest JPEG, which is smaller than a raw one
+            buffer_bytes_per_session: (SessionMode::Full.frame_buffer_capacity() + 1) * frame_bytes,
+            cache_bytes: self.app.state::<PreviewState>().cache_limit(),
+        }
+    }
+
+    fn perform(&mut self, op: &SoakOp) -> Result<(), String> {
+        let app = &self.app;
+        let result = match *op {
+            SoakOp::StartPreview { device } => tauri::async_runtime::block_on(start_preview(
+                app.clone(),
+                app.state(),
+                app.state(),
+                self.id(device),
+                AUTO_START_SIZE.0,
+                AUTO_START_SIZE.1,
+                AUTO_START_FPS,
+                None,
+                None,
+            )),
+            SoakOp::StopPreview { device } => tauri::async_runtime::block_on(stop_preview(
+                app.clone(),
+                app.state(),
+                self.id(device),
+            )),
+            SoakOp::ApplyPreset { device } => tauri::async_runtime::block_on(queue_preset_apply(
+                app,
+                &self.id(device),
+                SOAK_PRESET_ID,
+                &self.devices[device].name,
+            ))
+            .map(drop),
+            SoakOp::SetControl { device, percent } => self.set_control(device, percent),
+            SoakOp::Unplug { device } => {
+                let id = self.devices[device].id.clone();
+                handle_hotplug_event(app, &HotplugEvent::Disconnected { id });
+                Ok(())
+            }
+            SoakOp::Replug { device } => {
+                let event = HotplugEvent::Connected(self.devices[device].clone());
+                handle_hotplug_event(app, &event);
+                Ok(())
+            }
+            SoakOp::ResizeThumbnails { width } => self.resize_thumbnails(width),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    fn observe(&mut self) -> SoakObservation {
+        let state = self.app.state::<PreviewState>();
+        let stuck_locks: Vec<String> = state
+            .stuck_locks(LOCK_TIMEOUT)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        if !stuck_locks.is_empty() {
+            return SoakObservation {
+                stuck_locks,
+                ..SoakObservation::default()
+            };
+        }
+
+        let (sessions, buffer_bytes) = {
+            let sessions = state.sessions.lock();
+            let soak_sessions: Vec<_> = self
+                .devices
+                .iter()
+                .filter_map(|d| sessions.get(d.id.as_str()))
+                .collect();
+            (
+                soak_sessions.len(),
+                soak_sessions.iter().map(|s| s.buffered_bytes()).sum(),
+            )
+        };
+        SoakObservation {
+            sessions,
+            threads: process_thread_count(),
+            buffer_bytes,
+            cache_bytes: state.cache_bytes(),
+            stuck_locks,
+        }
+    }
+}
+
+/// Churn previews, presets, controls, hotplug and thumbnail sizes on the
+/// dummy camera for `iterations` operations picked from `seed`, checking
+/// for leaked sessions and threads, oversized buffers and caches and stuck
+/// locks after each. Only available in development builds or with
+/// `SOAK_TEST=1`.
+///
+/// A violation is reported rather than returned as an error; pass the
+/// report's seed to replay the run.
+#[tauri::command]
+pub async fn run_soak_test(
+    app: AppHandle,
+    iterations: u32,
+    seed: u64,
+) -> Result<SoakReport, AppError> {
+    if !soak_test_enabled() {
+        return Err(AppError::new(
+            code::UNSUPPORTED,
+            "the soak test is only available in development builds, or with SOAK_TEST=1",
+        ));
+    }
+    if !(1..=MAX_ITERATIONS).contains(&iterations) {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!(
+                "Soak test iterations must be between 1 and {MAX_ITERATIONS}, got {iterations}"
+            ),
+        ));
+    }
+    if app.state::<SettingsState>().store.keep_default_warm() {
+        return Err(AppError::new(
+            code::UNSUPPORTED,
+            "turn off keeping the default camera warm first; it starts unexpected sessions",
+        ));
+    }
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::new(
+            code::ALREADY_RUNNING,
+            "a soak test is already running",
+        ));
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || soak(&app, iterations, seed)).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    result.map_err(|e| AppError::new(code::INTERNAL, format!("soak test failed: {e}")))?
+}
+
+/// Set up the soak devices, run the test and stop their previews again.
+fn soak(app: &AppHandle, iterations: u32, seed: u64) -> Result<SoakReport, AppError> {
+    let camera = app.state::<CameraState>();
+    let devices: Vec<CameraDevice> = camera
+        .backend
+        .enumerate_devices()?
+        .into_iter()
+        .filter(|d| d.id == DummyBackend::device_id())
+        .collect();
+    if devices.is_empty() {
+        return Err(AppError::new(
+            code::DEVICE_NOT_FOUND,
+            "the soak test runs on the dummy camera; start the app with DUMMY_CAMERA=1",
+        ));
+    }
+
+    // Start with no previews, and a preset to apply
+    let store = &app.state::<SettingsState>().store;
+    for device in &devices {
+        stop_preview_session(app, device.id.as_str());
+        let descriptors = camera.backend.get_controls(&device.id)?;
+        store.save_preset(
+            SOAK_PRESET_ID,
+            Preset::capture("Soak test", &descriptors, false),
+        );
+    }
+
+    tracing::info!("Soak test starting: {iterations} iterations, seed {seed}");
+    let mut target = AppTarget {
+        app: app.clone(),
+        devices,
+    };
+    let report = run_soak(&mut target, iterations, seed, OpWeights::default());
+    for device in &target.devices {
+        stop_preview_session(app, device.id.as_str());
+    }
+    tracing::info!(
+        "Soak test finished: {} of {iterations} iterations (seed {seed})",
+        report.completed
+    );
+    Ok(report)
+}
@@ -1,5 +1,79 @@
 use serde::Serialize;
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::content::ContentHealth;
+use super::delivery::FrameDelivery;
+use crate::preview::mode::NegotiatedFormat;
+
+/// Gaps between device timestamps longer than this are stalls rather than
+/// frame intervals, and are left out of `capture_fps`.
+const MAX_FRAME_INTERVAL_US: u64 = 1_000_000;
+
+/// Samples behind the rolling conversion time and `current_fps`: two
+/// seconds at 30fps.
+const ROLLING_WINDOW: usize = 60;
+
+/// Why the capture callback dropped a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// The callback was handed no buffer.
+    NullBuffer,
+    /// The sample's pixel format isn't one we convert.
+    UnsupportedFormat,
+    /// The buffer isn't the size the negotiated format implies.
+    SizeMismatch,
+    /// An MJPG sample or Canon live view frame that didn't decode.
+    DecodeFailed,
+}
+
+/// Dropped frames by [`DropReason`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropCounts {
+    pub null_buffer: u64,
+    pub unsupported_format: u64,
+    pub size_mismatch: u64,
+    pub decode_failed: u64,
+}
+
+impl DropCounts {
+    fn record(&mut self, reason: DropReason) {
+        let count = match reason {
+            DropReason::NullBuffer => &mut self.null_buffer,
+            DropReason::UnsupportedFormat => &mut self.unsupported_format,
+            DropReason::SizeMismatch => &mut self.size_mismatch,
+            DropReason::DecodeFailed => &mut self.decode_failed,
+        };
+        *count += 1;
+    }
+}
+
+/// The last [`ROLLING_WINDOW`] samples of a measurement, for its rolling
+/// mean and maximum.
+#[derive(Debug, Default)]
+struct Rolling {
+    samples: VecDeque<u64>,
+    total: u64,
+}
+
+impl Rolling {
+    fn push(&mut self, sample: u64) {
+        if self.samples.len() == ROLLING_WINDOW {
+            self.total -= self.samples.pop_front().unwrap_or(0);
+        }
+        self.samples.push_back(sample);
+        self.total += sample;
+    }
+
+    fn mean(&self) -> Option<f64> {
+        (!self.samples.is_empty()).then(|| self.total as f64 / self.samples.len() as f64)
+    }
+
+    fn max(&self) -> Option<u64> {
+        self.samples.iter().copied().max()
+    }
+}
 
 /// Collects diagnostic statistics for a camera preview session.
 pub struct DiagnosticStats {
@@ -10,6 +84,25 @@ pub struct DiagnosticStats {
     last_frame_time: Option<Instant>,
     latency_us: u64,
     usb_bus_info: Option<String>,
+    /// Frame rate the capture graph negotiated, if the driver said.
+    negotiated_fps: Option<f64>,
+    panic_count: u64,
+    /// Latest device timestamp that didn't go backwards.
+    last_capture_us: Option<u64>,
+    /// Frames whose device timestamp was earlier than the previous frame's.
+    timestamp_regressions: u64,
+    /// Sum and count of forward frame intervals, for `capture_fps`.
+    interval_total_us: u64,
+    interval_count: u64,
+    /// Latest content classification from the capture callback.
+    content_health: ContentHealth,
+    /// Stills written to disk by `capture_snapshot`.
+    snapshot_count: u64,
+    drops_by_reason: DropCounts,
+    /// Recent conversion-and-push times of delivered frames.
+    conversion_us: Rolling,
+    /// Recent forward frame intervals, for `current_fps`.
+    recent_intervals_us: Rolling,
 }
 
 /// Snapshot of diagnostic stats for IPC serialisation.
@@ -23,6 +116,41 @@ pub struct DiagnosticSnapshot {
     pub latency_ms: f64,
     pub bandwidth_bps: u64,
     pub usb_bus_info: Option<String>,
+    /// Frame rate the camera agreed to when the graph connected; `None`
+    /// until then, or if the driver doesn't say.
+    pub negotiated_fps: Option<f64>,
+    pub panic_count: u64,
+    /// Frame rate by the camera's own timestamps, or 0 before two frames.
+    pub capture_fps: f64,
+    /// Frames the camera stamped earlier than the frame before.
+    pub timestamp_regressions: u64,
+    /// Whether frames are black or frozen even though the graph is running.
+    pub content_health: ContentHealth,
+    /// Stills written to disk by `capture_snapshot`.
+    pub snapshot_count: u64,
+    /// Frames dropped by the capture callback, by why. Sums to
+    /// `drop_count`.
+    pub drops_by_reason: DropCounts,
+    /// Mean time the capture callback spent converting and pushing each of
+    /// the last few delivered frames, or 0 before the first.
+    pub conversion_avg_us: f64,
+    /// Longest of those times.
+    pub conversion_max_us: u64,
+    /// Frame rate by the camera's timestamps over the last couple of
+    /// seconds, unlike `capture_fps`, which covers the whole session.
+    pub current_fps: f64,
+    /// JPEG quality get_frame is currently compressing at, once it has had
+    /// to compress a frame.
+    pub effective_jpeg_quality: Option<u8>,
+    /// Most recent get_frame encode durations, oldest first.
+    pub recent_encode_ms: Vec<f64>,
+    /// End-to-end delivery counts for each consumer of the session's frames.
+    pub frame_delivery: Vec<FrameDelivery>,
+    /// Bytes held by the frame and thumbnail JPEG caches across all devices.
+    pub jpeg_cache_bytes: u64,
+    /// Frame size and pixel format the camera settled on, once the capture
+    /// graph is connected.
+    pub negotiated_format: Option<NegotiatedFormat>,
 }
 
 impl DiagnosticStats {
@@ -36,6 +164,17 @@ impl DiagnosticStats {
             last_frame_time: None,
             latency_us: 0,
             usb_bus_info: None,
+            negotiated_fps: None,
+            panic_count: 0,
+            last_capture_us: None,
+            timestamp_regressions: 0,
+            interval_total_us: 0,
+            interval_count: 0,
+            content_health: ContentHealth::Ok,
+            snapshot_count: 0,
+            drops_by_reason: DropCounts::default(),
+            conversion_us: Rolling::default(),
+            recent_intervals_us: Rolling::default(),
         }
     }
 
@@ -44,12 +183,36 @@ impl DiagnosticStats {
         self.usb_bus_info = info;
     }
 
-    /// Record a successfully captured frame.
+    /// Set the frame rate the capture graph negotiated.
+    pub fn set_negotiated_fps(&mut self, fps: Option<f64>) {
+        self.negotiated_fps = fps;
+    }
+
+    /// Record a successfully captured frame, with the timestamp the device
+    /// gave it.
+    ///
+    /// A timestamp earlier than the previous frame's is counted as a
+    /// regression and otherwise ignored, so it skews neither the capture
+    /// frame rate nor the latency. Repeated timestamps and stalls longer
+    /// than a second don't count towards the capture frame rate either.
     pub fn record_frame(&mut self, bytes: usize, capture_timestamp_us: u64) {
         self.frame_count += 1;
         self.total_bytes += bytes as u64;
         self.last_frame_time = Some(Instant::now());
 
+        if let Some(previous) = self.last_capture_us {
+            let Some(interval) = capture_timestamp_us.checked_sub(previous) else {
+                self.timestamp_regressions += 1;
+                return;
+            };
+            if interval > 0 && interval <= MAX_FRAME_INTERVAL_US {
+                self.interval_total_us += interval;
+                self.interval_count += 1;
+                self.recent_intervals_us.push(interval);
+            }
+        }
+        self.last_capture_us = Some(capture_timestamp_us);
+
         // Calculate latency as time since capture timestamp
         let now_us = self.start_time.elapsed().as_micros() as u64;
         if capture_timestamp_us <= now_us {
@@ -57,9 +220,31 @@ impl DiagnosticStats {
         }
     }
 
-    /// Record a dropped frame.
-    pub fn record_drop(&mut self) {
+    /// Record the content classification the capture callback last reported.
+    pub fn set_content_health(&mut self, health: ContentHealth) {
+        self.content_health = health;
+    }
+
+    /// Record a frame dropped for `reason`.
+    pub fn record_drop(&mut self, reason: DropReason) {
         self.drop_count += 1;
+        self.drops_by_reason.record(reason);
+    }
+
+    /// Record how long the capture callback took to convert a frame and
+    /// push it to its consumers.
+    pub fn record_conversion(&mut self, elapsed: Duration) {
+        self.conversion_us.push(elapsed.as_micros() as u64);
+    }
+
+    /// Record a panic caught on the capture path.
+    pub fn record_panic(&mut self) {
+        self.panic_count += 1;
+    }
+
+    /// Record a still written to disk.
+    pub fn record_snapshot(&mut self) {
+        self.snapshot_count += 1;
     }
 
     /// Calculate current FPS based on elapsed time.
@@ -71,6 +256,23 @@ impl DiagnosticStats {
         self.frame_count as f64 / elapsed
     }
 
+    /// Frame rate implied by the intervals between device timestamps, or 0
+    /// before there are any.
+    pub fn capture_fps(&self) -> f64 {
+        if self.interval_total_us == 0 {
+            return 0.0;
+        }
+        self.interval_count as f64 * 1_000_000.0 / self.interval_total_us as f64
+    }
+
+    /// [`capture_fps`](Self::capture_fps) over the most recent intervals
+    /// only, so it follows a camera that slows down in low light.
+    pub fn current_fps(&self) -> f64 {
+        self.recent_intervals_us
+            .mean()
+            .map_or(0.0, |interval| 1_000_000.0 / interval)
+    }
+
     /// Drop rate as a percentage (0.0 - 100.0).
     pub fn drop_rate(&self) -> f64 {
         let total = self.frame_count + self.drop_count;
@@ -80,6 +282,21 @@ impl DiagnosticStats {
         (self.drop_count as f64 / total as f64) * 100.0
     }
 
+    /// Frames captured so far.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Time since the session started, or since the last reset.
+    pub fn uptime(&self) -> std::time::Duration {
+        self.start_time.elapsed()
+    }
+
+    /// Time since the last captured frame, or `None` before the first.
+    pub fn since_last_frame(&self) -> Option<std::time::Duration> {
+        self.last_frame_time.map(|t| t.elapsed())
+    }
+
     /// Latest capture-to-delivery latency in milliseconds.
     pub fn latency_ms(&self) -> f64 {
         self.latency_us as f64 / 1000.0
@@ -103,6 +320,17 @@ impl DiagnosticStats {
         self.last_frame_time = None;
         self.latency_us = 0;
         self.usb_bus_info = None;
+        self.negotiated_fps = None;
+        self.panic_count = 0;
+        self.last_capture_us = None;
+        self.timestamp_regressions = 0;
+        self.interval_total_us = 0;
+        self.interval_count = 0;
+        self.content_health = ContentHealth::Ok;
+        self.snapshot_count = 0;
+        self.drops_by_reason = DropCounts::default();
+        self.conversion_us = Rolling::default();
+        self.recent_intervals_us = Rolling::default();
     }
 
     /// Take a serialisable snapshot.
@@ -115,6 +343,21 @@ impl DiagnosticStats {
             latency_ms: self.latency_ms(),
             bandwidth_bps: self.bandwidth_bps(),
             usb_bus_info: self.usb_bus_info.clone(),
+            negotiated_fps: self.negotiated_fps,
+            panic_count: self.panic_count,
+            capture_fps: self.capture_fps(),
+            timestamp_regressions: self.timestamp_regressions,
+            content_health: self.content_health,
+            snapshot_count: self.snapshot_count,
+            drops_by_reason: self.drops_by_reason,
+            conversion_avg_us: self.conversion_us.mean().unwrap_or(0.0),
+            conversion_max_us: self.conversion_us.max().unwrap_or(0),
+            current_fps: self.current_fps(),
+            effective_jpeg_quality: None,
+            recent_encode_ms: Vec::new(),
+            frame_delivery: Vec::new(),
+            jpeg_cache_bytes: 0,
+            negotiated_format: None,
         }
     }
 }
@@ -152,12 +395,104 @@ mod tests {
     #[test]
     fn record_drop_increments_drop_count() {
         let mut stats = DiagnosticStats::new();
-        stats.record_drop();
+        stats.record_drop(DropReason::SizeMismatch);
         assert_eq!(stats.drop_count, 1);
-        stats.record_drop();
+        stats.record_drop(DropReason::SizeMismatch);
         assert_eq!(stats.drop_count, 2);
     }
 
+    #[test]
+    fn drops_are_counted_by_reason() {
+        let mut stats = DiagnosticStats::new();
+        // As the capture callback reports them
+        stats.record_drop(DropReason::NullBuffer);
+        stats.record_drop(DropReason::DecodeFailed);
+        stats.record_drop(DropReason::UnsupportedFormat);
+        stats.record_drop(DropReason::SizeMismatch);
+        stats.record_drop(DropReason::SizeMismatch);
+
+        let snap = stats.snapshot();
+        assert_eq!(
+            snap.drops_by_reason,
+            DropCounts {
+                null_buffer: 1,
+                unsupported_format: 1,
+                size_mismatch: 2,
+                decode_failed: 1,
+            }
+        );
+        assert_eq!(snap.drop_count, 5);
+        let json = serde_json::to_value(&snap).unwrap();
+        assert_eq!(json["dropsByReason"]["sizeMismatch"], 2);
+        assert_eq!(json["dropsByReason"]["nullBuffer"], 1);
+
+        stats.reset();
+        assert_eq!(stats.snapshot().drops_by_reason, DropCounts::default());
+    }
+
+    #[test]
+    fn rolling_mean_and_max_cover_the_last_window() {
+        let mut rolling = Rolling::default();
+        assert_eq!(rolling.mean(), None);
+        assert_eq!(rolling.max(), None);
+
+        rolling.push(10);
+        rolling.push(30);
+        assert_eq!(rolling.mean(), Some(20.0));
+        assert_eq!(rolling.max(), Some(30));
+
+        // A full window of 100s pushes the early samples out
+        for _ in 0..ROLLING_WINDOW {
+            rolling.push(100);
+        }
+        assert_eq!(rolling.mean(), Some(100.0));
+        assert_eq!(rolling.max(), Some(100));
+        assert_eq!(rolling.total, 100 * ROLLING_WINDOW as u64);
+
+        rolling.push(400);
+        let expected = (100 * (ROLLING_WINDOW as u64 - 1) + 400) as f64 / ROLLING_WINDOW as f64;
+        assert_eq!(rolling.mean(), Some(expected));
+        assert_eq!(rolling.max(), Some(400));
+    }
+
+    #[test]
+    fn conversion_times_are_averaged_in_microseconds() {
+        let mut stats = DiagnosticStats::new();
+        assert_eq!(stats.snapshot().conversion_avg_us, 0.0);
+        stats.record_conversion(Duration::from_micros(1500));
+        stats.record_conversion(Duration::from_micros(2500));
+
+        let json = serde_json::to_value(stats.snapshot()).unwrap();
+        assert_eq!(json["conversionAvgUs"], 2000.0);
+        assert_eq!(json["conversionMaxUs"], 2500);
+
+        stats.reset();
+        assert_eq!(stats.snapshot().conversion_max_us, 0);
+    }
+
+    #[test]
+    fn current_fps_follows_the_recent_frame_interval() {
+        let mut stats = DiagnosticStats::new();
+        assert_eq!(stats.current_fps(), 0.0);
+        let mut t = 0;
+        for _ in 0..100 {
+            t += 33_333;
+            stats.record_frame(1, t);
+        }
+        // The camera halves its rate in low light
+        for _ in 0..ROLLING_WINDOW {
+            t += 66_667;
+            stats.record_frame(1, t);
+        }
+        let snap = stats.snapshot();
+        assert!(
+            (snap.current_fps - 15.0).abs() < 0.01,
+            "{}",
+            snap.current_fps
+        );
+        assert!(snap.capture_fps > 20.0, "{}", snap.capture_fps);
+    }
+
     #[test]
     fn fps_returns_correct_rate() {
         let mut stats = DiagnosticStats::new();
@@ -177,7 +512,7 @@ mod tests {
         let mut stats = DiagnosticStats::new();
         stats.record_frame(1000, 0);
         stats.record_frame(1000, 0);
-        stats.record_drop();
+        stats.record_drop(DropReason::NullBuffer);
         // 1 drop out of 3 total = 33.3%
         let rate = stats.drop_rate();
         assert!(
@@ -205,7 +540,7 @@ mod tests {
     fn reset_clears_all_counters() {
         let mut stats = DiagnosticStats::new();
         stats.record_frame(1000, 0);
-        stats.record_drop();
+        stats.record_drop(DropReason::NullBuffer);
         stats.reset();
         assert_eq!(stats.frame_count, 0);
         assert_eq!(stats.drop_count, 0);
@@ -230,6 +565,19 @@ mod tests {
         assert_eq!(snap.usb_bus_info, Some("USB 3.0 Bus 2".to_string()));
     }
 
+    #[test]
+    fn snapshot_includes_negotiated_fps() {
+        let mut stats = DiagnosticStats::new();
+        assert_eq!(stats.snapshot().negotiated_fps, None);
+
+        stats.set_negotiated_fps(Some(30.0));
+        let json = serde_json::to_value(stats.snapshot()).unwrap();
+        assert_eq!(json["negotiatedFps"], 30.0);
+
+        stats.reset();
+        assert_eq!(stats.snapshot().negotiated_fps, None);
+    }
+
     #[test]
     fn snapshot_usb_bus_info_none_serialises_as_null() {
         let stats = DiagnosticStats::new();
@@ -246,4 +594,115 @@ mod tests {
         let json = serde_json::to_value(&snap).unwrap();
         assert_eq!(json["usbBusInfo"], "USB 2.0 Bus 1");
     }
+
+    #[test]
+    fn content_health_is_reported_and_reset() {
+        let mut stats = DiagnosticStats::new();
+        assert_eq!(stats.snapshot().content_health, ContentHealth::Ok);
+        stats.set_content_health(ContentHealth::Black);
+        let json = serde_json::to_value(stats.snapshot()).unwrap();
+        assert_eq!(json["contentHealth"], "black");
+
+        stats.reset();
+        assert_eq!(stats.snapshot().content_health, ContentHealth::Ok);
+    }
+
+    #[test]
+    fn record_panic_is_reported_in_snapshot() {
+        let mut stats = DiagnosticStats::new();
+        stats.record_panic();
+        let json = serde_json::to_value(stats.snapshot()).unwrap();
+        assert_eq!(json["panicCount"], 1);
+
+        stats.reset();
+        assert_eq!(stats.snapshot().panic_count, 0);
+    }
+
+    #[test]
+    fn record_snapshot_is_reported_in_snapshot() {
+        let mut stats = DiagnosticStats::new();
+        stats.record_snapshot();
+        stats.record_snapshot();
+        let json = serde_json::to_value(stats.snapshot()).unwrap();
+        assert_eq!(json["snapshotCount"], 2);
+
+        stats.reset();
+        assert_eq!(stats.snapshot().snapshot_count, 0);
+    }
+
+    #[test]
+    fn capture_fps_follows_device_timestamps() {
+        let mut stats = DiagnosticStats::new();
+        assert_eq!(stats.capture_fps(), 0.0);
+        for i in 0..31 {
+            stats.record_frame(1000, i * 33_333);
+        }
+        let fps = stats.capture_fps();
+        assert!((fps - 30.0).abs() < 0.01, "got {fps}");
+        assert_eq!(stats.timestamp_regressions, 0);
+    }
+
+    #[test]
+    fn regression_burst_is_counted_and_ignored() {
+        let mut stats = DiagnosticStats::new();
+        for i in 0..10 {
+            stats.record_frame(1000, 10_000_000 + i * 33_333);
+        }
+        // Five frames stamped ~2s earlier, then back on the timeline
+        for i in 0..5 {
+            stats.record_frame(1000, 8_000_000 + i * 33_333);
+        }
+        for i in 10..20 {
+            stats.record_frame(1000, 10_000_000 + i * 33_333);
+        }
+
+        assert_eq!(stats.timestamp_regressions, 5);
+        assert_eq!(stats.frame_count, 25);
+        let fps = stats.capture_fps();
+        assert!((fps - 30.0).abs() < 0.01, "got {fps}");
+        assert_eq!(stats.snapshot().timestamp_regressions, 5);
+    }
+
+    #[test]
+    fn duplicate_timestamps_are_not_regressions() {
+        let mut stats = DiagnosticStats::new();
+        stats.record_frame(1000, 100_000);
+        stats.record_frame(1000, 100_000);
+        stats.record_frame(1000, 133_333);
+        stats.record_frame(1000, 133_333);
+
+        assert_eq!(stats.timestamp_regressions, 0);
+        assert_eq!(stats.interval_count, 1);
+        let fps = stats.capture_fps();
+        assert!((fps - 30.0).abs() < 0.01, "got {fps}");
+    }
+
+    #[test]
+    fn large_forward_jump_is_left_out_of_capture_fps() {
+        let mut stats = DiagnosticStats::new();
+        stats.record_frame(1000, 0);
+        stats.record_frame(1000, 33_333);
+        stats.record_frame(1000, 60_000_000);
+        stats.record_frame(1000, 60_033_333);
+
+        assert_eq!(stats.timestamp_regressions, 0);
+        assert_eq!(stats.interval_count, 2);
+        let fps = stats.capture_fps();
+        assert!((fps - 30.0).abs() < 0.01, "got {fps}");
+    }
+
+    #[test]
+    fn reset_clears_timestamp_tracking() {
+        let mut stats = DiagnosticStats::new();
+        stats.record_frame(1000, 2_000_000);
+        stats.record_frame(1000, 1_000_000);
+        stats.record_frame(1000, 2_033_333);
+        stats.reset();
+
+        assert_eq!(stats.timestamp_regressions, 0);
+        assert_eq!(stats.capture_fps(), 0.0);
+        // The first frame after a reset has nothing to regress from
+        stats.record_frame(1000, 0);
+        assert_eq!(stats.timestamp_regressions, 0);
+    }
 }
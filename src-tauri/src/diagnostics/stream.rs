@@ -0,0 +1,329 @@
+//! Streaming every session's diagnostics at once, for views that show all
+//! cameras: one worker snapshots each active session every interval and
+//! sends the lot as a single update, instead of the frontend polling
+//! `get_diagnostics` per device.
+//!
+//! Sessions are looked up afresh at every tick, so cameras plugged in or
+//! unplugged mid-stream simply appear in or drop out of the next update.
+//! With no sessions for [`IDLE_GRACE`] the stream stops itself rather than
+//! waking up to send empty updates.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use super::stats::DiagnosticSnapshot;
+
+/// Shortest interval the stream may run at.
+pub const MIN_INTERVAL_MS: u64 = 100;
+
+/// Longest interval the stream may run at.
+pub const MAX_INTERVAL_MS: u64 = 60_000;
+
+/// How long the stream keeps running with no sessions before it stops.
+/// Long enough to ride out a camera being replugged or a preview restarted.
+pub const IDLE_GRACE: Duration = Duration::from_secs(30);
+
+/// Every active session's diagnostics, by device ID.
+pub type DiagnosticsUpdate = BTreeMap<String, DiagnosticSnapshot>;
+
+/// The interval to stream at: `interval_ms`, which must be between
+/// [`MIN_INTERVAL_MS`] and [`MAX_INTERVAL_MS`].
+pub fn stream_interval(interval_ms: u64) -> Result<Duration, String> {
+    if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+        return Err(format!(
+            "interval must be between {MIN_INTERVAL_MS} and {MAX_INTERVAL_MS} ms, got \
+             {interval_ms}"
+        ));
+    }
+    Ok(Duration::from_millis(interval_ms))
+}
+
+/// Where a stream gets its snapshots and sends its updates.
+pub trait StreamTarget: Send + 'static {
+    /// Diagnostics of the sessions active right now.
+    fn snapshot(&mut self) -> DiagnosticsUpdate;
+
+    /// Send one update.
+    fn emit(&mut self, update: &DiagnosticsUpdate);
+
+    /// The stream stopped itself after [`IDLE_GRACE`] without sessions.
+    fn idle(&mut self) {}
+}
+
+/// Why a stream's worker finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEnd {
+    /// Stopped or replaced by a caller.
+    Stopped,
+    /// No sessions for the grace period.
+    Idle,
+}
+
+struct Running {
+    stop: Sender<()>,
+    worker: JoinHandle<StreamEnd>,
+}
+
+/// Tauri-managed state holding the stream's worker, if one was started.
+#[derive(Default)]
+pub struct DiagnosticsStream {
+    running: Mutex<Option<Running>>,
+}
+
+impl DiagnosticsStream {
+    /// Stream `target`'s diagnostics every `interval`, stopping after
+    /// `grace` without sessions. A stream already running is replaced, so
+    /// starting again just changes the interval.
+    pub fn start<T: StreamTarget>(
+        &self,
+        mut target: T,
+        interval: Duration,
+        grace: Duration,
+    ) -> Result<(), String> {
+        let mut running = self.running.lock();
+        if let Some(old) = running.take() {
+            let _ = old.stop.send(());
+            let _ = old.worker.join();
+        }
+        let (stop, stopped) = mpsc::channel();
+        let worker = std::thread::Builder::new()
+            .name("diagnostics-stream".to_string())
+            .spawn(move || {
+                let end = run_stream(&mut target, interval, grace, &stopped);
+                if end == StreamEnd::Idle {
+                    target.idle();
+                }
+                end
+            })
+            .map_err(|e| format!("Failed to start diagnostics stream: {e}"))?;
+        *running = Some(Running { stop, worker });
+        Ok(())
+    }
+
+    /// Stop the stream and wait for its worker, which wakes as soon as it's
+    /// told. Returns whether one was running; stopping again, or after the
+    /// stream went idle, does nothing.
+    pub fn stop(&self) -> bool {
+        let Some(running) = self.running.lock().take() else {
+            return false;
+        };
+        // A worker that went idle has dropped its receiver
+        let _ = running.stop.send(());
+        matches!(running.worker.join(), Ok(StreamEnd::Stopped))
+    }
+
+    /// Whether a stream is running.
+    pub fn is_running(&self) -> bool {
+        self.running
+            .lock()
+            .as_ref()
+            .is_some_and(|r| !r.worker.is_finished())
+    }
+}
+
+/// Emit an update now and every `interval` until told to stop or there
+/// have been no sessions for `grace`.
+fn run_stream(
+    target: &mut impl StreamTarget,
+    interval: Duration,
+    grace: Duration,
+    stop: &Receiver<()>,
+) -> StreamEnd {
+    let mut idle_since: Option<Instant> = None;
+    loop {
+        let update = target.snapshot();
+        if update.is_empty() {
+            let since = *idle_since.get_or_insert_with(Instant::now);
+            if since.elapsed() >= grace {
+                return StreamEnd::Idle;
+            }
+        } else {
+            idle_since = None;
+        }
+        target.emit(&update);
+
+        match stop.recv_timeout(interval) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return StreamEnd::Stopped,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const TICK: Duration = Duration::from_millis(10);
+    const WAIT: Duration = Duration::from_secs(5);
+
+    /// Sessions by device ID and frame count, shared with the test so it
+    /// can plug and unplug cameras mid-stream.
+    type Sessions = Arc<Mutex<BTreeMap<String, u64>>>;
+
+    struct MockTarget {
+        sessions: Sessions,
+        updates: Sender<DiagnosticsUpdate>,
+        idle: Option<Sender<()>>,
+    }
+
+    impl StreamTarget for MockTarget {
+        fn snapshot(&mut self) -> DiagnosticsUpdate {
+            self.sessions
+                .lock()
+                .iter()
+                .map(|(id, &frame_count)| {
+                    let snapshot = DiagnosticSnapshot {
+                        frame_count,
+                        ..DiagnosticSnapshot::default()
+                    };
+                    (id.clone(), snapshot)
+                })
+                .collect()
+        }
+
+        fn emit(&mut self, update: &DiagnosticsUpdate) {
+            let _ = self.updates.send(update.clone());
+        }
+
+        fn idle(&mut self) {
+            if let Some(idle) = self.idle.take() {
+                let _ = idle.send(());
+            }
+        }
+    }
+
+    fn mock(sessions: &[(&str, u64)]) -> (MockTarget, Sessions, Receiver<DiagnosticsUpdate>) {
+        let sessions: Sessions = Arc::new(Mutex::new(
+            sessions
+                .iter()
+                .map(|&(id, n)| (id.to_string(), n))
+                .collect(),
+        ));
+        let (updates, received) = mpsc::channel();
+        let target = MockTarget {
+            sessions: Arc::clone(&sessions),
+            updates,
+            idle: None,
+        };
+        (target, sessions, received)
+    }
+
+    fn frame_counts(update: &DiagnosticsUpdate) -> Vec<(&str, u64)> {
+        update
+            .iter()
+            .map(|(id, snapshot)| (id.as_str(), snapshot.frame_count))
+            .collect()
+    }
+
+    #[test]
+    fn intervals_are_range_checked() {
+        assert_eq!(stream_interval(1000), Ok(Duration::from_secs(1)));
+        assert!(stream_interval(MIN_INTERVAL_MS).is_ok());
+        assert!(stream_interval(MAX_INTERVAL_MS).is_ok());
+        assert!(stream_interval(MIN_INTERVAL_MS - 1).is_err());
+        assert!(stream_interval(MAX_INTERVAL_MS + 1).is_err());
+    }
+
+    #[test]
+    fn each_update_holds_every_session() {
+        let (target, _, updates) = mock(&[("cam-1", 10), ("cam-2", 20), ("cam-3", 30)]);
+        let stream = DiagnosticsStream::default();
+        stream.start(target, TICK, WAIT).unwrap();
+
+        let update = updates.recv_timeout(WAIT).unwrap();
+        assert_eq!(
+            frame_counts(&update),
+            vec![("cam-1", 10), ("cam-2", 20), ("cam-3", 30)]
+        );
+        // And again at the next tick
+        assert_eq!(updates.recv_timeout(WAIT).unwrap().len(), 3);
+        assert!(stream.stop());
+    }
+
+    #[test]
+    fn sessions_come_and_go_mid_stream() {
+        let (target, sessions, updates) = mock(&[("cam-1", 1)]);
+        let stream = DiagnosticsStream::default();
+        stream.start(target, TICK, WAIT).unwrap();
+        assert_eq!(
+            frame_counts(&updates.recv_timeout(WAIT).unwrap()),
+            vec![("cam-1", 1)]
+        );
+
+        sessions.lock().insert("cam-2".to_string(), 2);
+        let plugged = std::iter::from_fn(|| updates.recv_timeout(WAIT).ok())
+            .find(|update| update.len() == 2)
+            .unwrap();
+        assert_eq!(frame_counts(&plugged), vec![("cam-1", 1), ("cam-2", 2)]);
+
+        sessions.lock().remove("cam-1");
+        let unplugged = std::iter::from_fn(|| updates.recv_timeout(WAIT).ok())
+            .find(|update| !update.contains_key("cam-1"))
+            .unwrap();
+        assert_eq!(frame_counts(&unplugged), vec![("cam-2", 2)]);
+        assert!(stream.stop());
+    }
+
+    #[test]
+    fn the_stream_stops_itself_without_sessions() {
+        let (mut target, sessions, updates) = mock(&[("cam-1", 1)]);
+        let (idle, went_idle) = mpsc::channel();
+        target.idle = Some(idle);
+        let stream = DiagnosticsStream::default();
+        stream
+            .start(target, TICK, Duration::from_millis(50))
+            .unwrap();
+        updates.recv_timeout(WAIT).unwrap();
+
+        sessions.lock().clear();
+        went_idle.recv_timeout(WAIT).unwrap();
+        // Empty updates were sent during the grace period
+        assert!(updates.try_iter().any(|update| update.is_empty()));
+        assert!(!stream.is_running());
+        // Nothing left to stop
+        assert!(!stream.stop());
+    }
+
+    #[test]
+    fn stopping_is_idempotent() {
+        let stream = DiagnosticsStream::default();
+        assert!(!stream.stop());
+
+        let (target, _, updates) = mock(&[("cam-1", 1)]);
+        stream.start(target, TICK, WAIT).unwrap();
+        updates.recv_timeout(WAIT).unwrap();
+        assert!(stream.is_running());
+
+        assert!(stream.stop());
+        assert!(!stream.is_running());
+        assert!(!stream.stop());
+        // The worker is gone, so nothing more arrives
+        while updates.try_recv().is_ok() {}
+        std::thread::sleep(TICK * 3);
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[test]
+    fn starting_again_replaces_the_stream() {
+        let stream = DiagnosticsStream::default();
+        let (first, _, first_updates) = mock(&[("cam-1", 1)]);
+        stream.start(first, TICK, WAIT).unwrap();
+        first_updates.recv_timeout(WAIT).unwrap();
+
+        let (second, _, second_updates) = mock(&[("cam-2", 2)]);
+        stream.start(second, TICK, WAIT).unwrap();
+        // The first worker has been joined and dropped its target
+        while first_updates.try_recv().is_ok() {}
+        assert!(first_updates.recv_timeout(TICK * 3).is_err());
+        assert_eq!(
+            frame_counts(&second_updates.recv_timeout(WAIT).unwrap()),
+            vec![("cam-2", 2)]
+        );
+        assert!(stream.stop());
+    }
+}
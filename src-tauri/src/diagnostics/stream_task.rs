@@ -0,0 +1,64 @@
+//! Streams every preview session's diagnostics to the frontend as
+//! `diagnostics-update` events; see [`super::stream`].
+
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::stream::{
+    stream_interval, DiagnosticsStream, DiagnosticsUpdate, StreamTarget, IDLE_GRACE,
+};
+use crate::error::{code, AppError};
+use crate::preview::commands::PreviewState;
+
+/// The app's preview sessions, streamed as Tauri events.
+struct AppTarget {
+    app: AppHandle,
+}
+
+impl StreamTarget for AppTarget {
+    fn snapshot(&mut self) -> DiagnosticsUpdate {
+        self.app.state::<PreviewState>().diagnostics_by_device()
+    }
+
+    fn emit(&mut self, update: &DiagnosticsUpdate) {
+        if let Err(e) = self.app.emit("diagnostics-update", update) {
+            tracing::warn!("Failed to emit diagnostics-update event: {e}");
+        }
+    }
+
+    fn idle(&mut self) {
+        tracing::info!("Diagnostics stream stopped with no sessions to report");
+        if let Err(e) = self.app.emit("diagnostics-stream-stopped", ()) {
+            tracing::warn!("Failed to emit diagnostics-stream-stopped event: {e}");
+        }
+    }
+}
+
+/// Emit a `diagnostics-update` event every `interval_ms` (100 to 60000)
+/// holding every active preview session's diagnostics by device ID, as
+/// `get_diagnostics` reports each. Starting again changes the interval.
+///
+/// After 30 seconds without sessions the stream stops itself and emits
+/// `diagnostics-stream-stopped`.
+#[tauri::command]
+pub async fn start_diagnostics_stream(
+    app: AppHandle,
+    state: State<'_, DiagnosticsStream>,
+    interval_ms: u64,
+) -> Result<(), AppError> {
+    let interval =
+        stream_interval(interval_ms).map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    state
+        .start(AppTarget { app }, interval, IDLE_GRACE)
+        .map_err(AppError::with_code(code::INTERNAL))?;
+    tracing::info!("Started diagnostics stream every {interval_ms}ms");
+    Ok(())
+}
+
+/// Stop the diagnostics stream. Returns whether one was running; stopping
+/// when none is does nothing.
+#[tauri::command]
+pub async fn stop_diagnostics_stream(
+    state: State<'_, DiagnosticsStream>,
+) -> Result<bool, AppError> {
+    Ok(state.stop())
+}
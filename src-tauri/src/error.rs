@@ -0,0 +1,380 @@
+//! Errors as the frontend sees them.
+//!
+//! Every command fails with an [`AppError`]: a stable, machine-readable
+//! `code` the frontend can branch on (show a retry button, hint at closing
+//! other camera apps), a message for display, and optional structured
+//! details. Codes never change once shipped; messages may be reworded or
+//! localised freely. The codes live in [`code`], and [`humanise_error`]
+//! maps the ones with a friendlier default message to its text.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::camera::error::CameraError;
+use crate::i18n::{self, Domain};
+
+/// The error codes the frontend can branch on.
+pub mod code {
+    /// The device isn't connected, or no device has that ID.
+    pub const DEVICE_NOT_FOUND: &str = "device_not_found";
+    /// Another application has the camera open.
+    pub const DEVICE_BUSY: &str = "device_busy";
+    /// The system refused access to the camera.
+    pub const ACCESS_DENIED: &str = "access_denied";
+    /// The camera or its driver failed.
+    pub const DEVICE_FAILED: &str = "device_failed";
+    /// Cameras couldn't be listed.
+    pub const ENUMERATION_FAILED: &str = "enumeration_failed";
+    /// Device arrival and removal can't be watched.
+    pub const HOTPLUG_FAILED: &str = "hotplug_failed";
+    /// The camera's formats couldn't be read.
+    pub const FORMAT_QUERY_FAILED: &str = "format_query_failed";
+    /// The camera doesn't offer the format asked for.
+    pub const FORMAT_UNSUPPORTED: &str = "format_unsupported";
+    /// The camera doesn't have the control, or it can't be read.
+    pub const CONTROL_UNAVAILABLE: &str = "control_unavailable";
+    /// The camera refused a control value.
+    pub const CONTROL_REJECTED: &str = "control_rejected";
+    /// The operation isn't supported by this camera or platform.
+    pub const UNSUPPORTED: &str = "unsupported";
+    /// The Canon SDK failed.
+    pub const CANON_SDK: &str = "canon_sdk";
+    /// The Canon camera is busy; retrying shortly usually works.
+    pub const CANON_BUSY: &str = "canon_busy";
+    /// No session is open with the Canon camera.
+    pub const CANON_SESSION_NOT_OPEN: &str = "canon_session_not_open";
+    /// The Canon camera was disconnected.
+    pub const CANON_DISCONNECTED: &str = "canon_disconnected";
+    /// The camera has no preview running.
+    pub const PREVIEW_NOT_RUNNING: &str = "preview_not_running";
+    /// The preview only captures thumbnails; upgrade it for full frames.
+    pub const PREVIEW_THUMBNAIL_ONLY: &str = "preview_thumbnail_only";
+    /// The preview couldn't start or failed while running.
+    pub const PREVIEW_FAILED: &str = "preview_failed";
+    /// No more previews can run at once.
+    pub const PREVIEW_LIMIT: &str = "preview_limit";
+    /// The preview is running but hasn't captured a frame yet; retry shortly.
+    pub const FRAME_UNAVAILABLE: &str = "frame_unavailable";
+    /// Settings, presets or other files couldn't be read or written.
+    pub const SETTINGS_IO: &str = "settings_io";
+    /// No preset, scene or other named item has that name.
+    pub const NOT_FOUND: &str = "not_found";
+    /// An argument was out of range or malformed.
+    pub const INVALID_ARGUMENT: &str = "invalid_argument";
+    /// The operation is already running and can't be started twice.
+    pub const ALREADY_RUNNING: &str = "already_running";
+    /// The operation was cancelled.
+    pub const CANCELLED: &str = "cancelled";
+    /// An integration (control API, MIDI) failed.
+    pub const INTEGRATION_FAILED: &str = "integration_failed";
+    /// Anything else.
+    pub const INTERNAL: &str = "internal";
+
+    /// Every code.
+    pub const ALL: &[&str] = &[
+        DEVICE_NOT_FOUND,
+        DEVICE_BUSY,
+        ACCESS_DENIED,
+        DEVICE_FAILED,
+        ENUMERATION_FAILED,
+        HOTPLUG_FAILED,
+        FORMAT_QUERY_FAILED,
+        FORMAT_UNSUPPORTED,
+        CONTROL_UNAVAILABLE,
+        CONTROL_REJECTED,
+        UNSUPPORTED,
+        CANON_SDK,
+        CANON_BUSY,
+        CANON_SESSION_NOT_OPEN,
+        CANON_DISCONNECTED,
+        PREVIEW_NOT_RUNNING,
+        PREVIEW_THUMBNAIL_ONLY,
+        PREVIEW_FAILED,
+        PREVIEW_LIMIT,
+        FRAME_UNAVAILABLE,
+        SETTINGS_IO,
+        NOT_FOUND,
+        INVALID_ARGUMENT,
+        ALREADY_RUNNING,
+        CANCELLED,
+        INTEGRATION_FAILED,
+        INTERNAL,
+    ];
+}
+
+/// An error returned by a command or carried by an event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppError {
+    /// One of the [`code`]s.
+    pub code: &'static str,
+    pub message: String,
+    /// Structured context, such as the underlying error a friendlier
+    /// message replaced. Omitted when there is none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl AppError {
+    /// An error with `code` and `message` as given.
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// An error with `code`, shown with the code's default message if it has
+    /// one. `cause` is kept in the details when it's replaced.
+    pub fn humanised(code: &'static str, cause: impl Into<String>) -> Self {
+        let cause = cause.into();
+        match humanise_error(code) {
+            Some(message) => Self::new(code, message).with_details(serde_json::json!({
+                "cause": cause
+            })),
+            None => Self::new(code, cause),
+        }
+    }
+
+    /// An error for a failure reported as text, coded by the Windows or
+    /// Canon error it mentions, or `fallback` if none.
+    pub fn classify(fallback: &'static str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::humanised(recognise(&message).unwrap_or(fallback), message)
+    }
+
+    /// This error with `details` attached.
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// A `map_err` adapter giving errors shown as text `code`.
+    pub fn with_code<E: std::fmt::Display>(code: &'static str) -> impl Fn(E) -> Self {
+        move |e| Self::new(code, e.to_string())
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Callers that report errors as text, like the HTTP API and logs, keep the
+/// message.
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.message
+    }
+}
+
+impl From<CameraError> for AppError {
+    fn from(e: CameraError) -> Self {
+        let code = match &e {
+            CameraError::DeviceNotFound(_) => code::DEVICE_NOT_FOUND,
+            CameraError::ComInit(_) => code::DEVICE_FAILED,
+            CameraError::Enumeration(_) => code::ENUMERATION_FAILED,
+            CameraError::ControlQuery(_) => code::CONTROL_UNAVAILABLE,
+            CameraError::ControlWrite(_) => code::CONTROL_REJECTED,
+            CameraError::FormatQuery(_) => code::FORMAT_QUERY_FAILED,
+            CameraError::Hotplug(_) => code::HOTPLUG_FAILED,
+            CameraError::Unsupported(_) => code::UNSUPPORTED,
+            CameraError::CanonSdkError(_) => code::CANON_SDK,
+            CameraError::CanonSessionNotOpen(_) => code::CANON_SESSION_NOT_OPEN,
+            CameraError::CanonDeviceBusy(_) => code::CANON_BUSY,
+        };
+        let message = e.to_string();
+        match code {
+            code::DEVICE_NOT_FOUND => Self::new(code, message),
+            _ => Self::humanised(recognise(&message).unwrap_or(code), message),
+        }
+    }
+}
+
+/// Windows HRESULTs and Canon SDK message fragments, and the codes they
+/// mean.
+const RECOGNISED: &[(&str, &str)] = &[
+    ("0x800705AA", code::DEVICE_BUSY),
+    ("0x80070020", code::DEVICE_BUSY),
+    ("0x80070005", code::ACCESS_DENIED),
+    ("0x80004005", code::DEVICE_FAILED),
+    ("0x8007001F", code::DEVICE_FAILED),
+    ("camera is busy", code::CANON_BUSY),
+    ("session not open", code::CANON_SESSION_NOT_OPEN),
+    ("SESSION_NOT_OPEN", code::CANON_SESSION_NOT_OPEN),
+    ("camera disconnected", code::CANON_DISCONNECTED),
+    ("COMM_DISCONNECTED", code::CANON_DISCONNECTED),
+];
+
+/// The code for the first Windows or Canon error `message` mentions.
+fn recognise(message: &str) -> Option<&'static str> {
+    RECOGNISED
+        .iter()
+        .find(|(pattern, _)| message.contains(pattern))
+        .map(|&(_, code)| code)
+}
+
+/// The default message for `code` in the active locale, for the codes that
+/// have one.
+pub fn humanise_error(code: &str) -> Option<&'static str> {
+    i18n::lookup(Domain::Error, code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn every_camera_error(detail: &str) -> Vec<CameraError> {
+        let d = || detail.to_string();
+        vec![
+            CameraError::DeviceNotFound(d()),
+            CameraError::ComInit(d()),
+            CameraError::Enumeration(d()),
+            CameraError::ControlQuery(d()),
+            CameraError::ControlWrite(d()),
+            CameraError::FormatQuery(d()),
+            CameraError::Hotplug(d()),
+            CameraError::Unsupported(d()),
+            CameraError::CanonSdkError(d()),
+            CameraError::CanonSessionNotOpen(d()),
+            CameraError::CanonDeviceBusy(d()),
+        ]
+    }
+
+    #[test]
+    fn codes_are_unique_snake_case() {
+        let unique: BTreeSet<_> = code::ALL.iter().collect();
+        assert_eq!(unique.len(), code::ALL.len());
+        for code in code::ALL {
+            assert!(
+                code.chars().all(|c| c.is_ascii_lowercase() || c == '_'),
+                "{code}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_default_message_belongs_to_a_code() {
+        for &code in code::ALL {
+            if let Some(message) = humanise_error(code) {
+                assert!(!message.is_empty());
+            }
+        }
+        assert_eq!(humanise_error("no_such_code"), None);
+    }
+
+    #[test]
+    fn camera_errors_map_to_codes() {
+        let codes: Vec<&str> = every_camera_error("detail")
+            .into_iter()
+            .map(|e| AppError::from(e).code)
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                code::DEVICE_NOT_FOUND,
+                code::DEVICE_FAILED,
+                code::ENUMERATION_FAILED,
+                code::CONTROL_UNAVAILABLE,
+                code::CONTROL_REJECTED,
+                code::FORMAT_QUERY_FAILED,
+                code::HOTPLUG_FAILED,
+                code::UNSUPPORTED,
+                code::CANON_SDK,
+                code::CANON_SESSION_NOT_OPEN,
+                code::CANON_BUSY,
+            ]
+        );
+        for code in codes {
+            assert!(code::ALL.contains(&code), "{code} isn't registered");
+        }
+    }
+
+    #[test]
+    fn windows_errors_refine_the_code() {
+        let busy = AppError::from(CameraError::ComInit(
+            "CoCreateInstance failed: 0x800705AA".to_string(),
+        ));
+        assert_eq!(busy.code, code::DEVICE_BUSY);
+        assert_eq!(busy.message, "Camera is in use by another application");
+
+        let denied = AppError::from(CameraError::ControlWrite(
+            "BindToObject failed: 0x80070005".to_string(),
+        ));
+        assert_eq!(denied.code, code::ACCESS_DENIED);
+        assert_eq!(
+            denied.message,
+            "Access denied — close other camera apps and retry"
+        );
+
+        let locked = AppError::classify(code::PREVIEW_FAILED, "something 0x80070020 happened");
+        assert_eq!(locked.code, code::DEVICE_BUSY);
+    }
+
+    #[test]
+    fn canon_errors_refine_the_code() {
+        let busy = AppError::from(CameraError::CanonSdkError(
+            "camera is busy — retry shortly".to_string(),
+        ));
+        assert_eq!(busy.code, code::CANON_BUSY);
+        assert!(busy.message.contains("Canon camera is busy"));
+
+        let gone = AppError::classify(code::PREVIEW_FAILED, "Canon SDK: camera disconnected");
+        assert_eq!(gone.code, code::CANON_DISCONNECTED);
+        assert!(gone.message.contains("disconnected"));
+    }
+
+    #[test]
+    fn unrecognised_errors_keep_their_text() {
+        let err = AppError::from(CameraError::DeviceNotFound("cam-1".to_string()));
+        assert_eq!(err.message, "device not found: cam-1");
+        assert_eq!(err.details, None);
+
+        let err = AppError::classify(code::PREVIEW_FAILED, "some random error");
+        assert_eq!(err.code, code::PREVIEW_FAILED);
+        assert_eq!(err.message, "some random error");
+    }
+
+    #[test]
+    fn humanised_errors_keep_the_cause() {
+        let err = AppError::humanised(code::DEVICE_BUSY, "0x800705AA");
+        assert_eq!(
+            err.details,
+            Some(serde_json::json!({ "cause": "0x800705AA" }))
+        );
+    }
+
+    #[test]
+    fn json_shape_is_pinned() {
+        let plain = AppError::new(code::NOT_FOUND, "preset not found: Studio");
+        assert_eq!(
+            serde_json::to_string(&plain).unwrap(),
+            r#"{"code":"not_found","message":"preset not found: Studio"}"#
+        );
+
+        let detailed = AppError::from(CameraError::ControlWrite(
+            "Failed to set Zoom: 0x800705AA".to_string(),
+        ));
+        assert_eq!(
+            serde_json::to_string(&detailed).unwrap(),
+            concat!(
+                r#"{"code":"device_busy","#,
+                r#""message":"Camera is in use by another application","#,
+                r#""details":{"cause":"control write failed: Failed to set Zoom: 0x800705AA"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn with_code_adapts_text_errors() {
+        let err: AppError = Err::<(), _>("disk full")
+            .map_err(AppError::with_code(code::SETTINGS_IO))
+            .unwrap_err();
+        assert_eq!(err, AppError::new(code::SETTINGS_IO, "disk full"));
+        assert_eq!(err.to_string(), "disk full (settings_io)");
+    }
+}
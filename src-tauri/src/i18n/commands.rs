@@ -0,0 +1,33 @@
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::i18n::{self, Locale};
+use crate::settings::commands::SettingsState;
+
+/// Switch the language of backend-produced text: control names, group
+/// labels, errors and the tray menu. Takes a language tag such as `de` or
+/// `nl-BE`; languages without a catalogue fall back to English. Returns the
+/// code of the locale now in use.
+///
+/// Descriptors already sent keep their names until controls are next read.
+#[tauri::command]
+pub async fn set_locale(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+    locale: String,
+) -> Result<String, AppError> {
+    let resolved = Locale::parse(&locale).unwrap_or(Locale::En);
+    if resolved.code() != locale {
+        tracing::info!("Locale '{locale}' resolved to '{}'", resolved.code());
+    }
+    i18n::set_active(resolved);
+    settings_state.store.set_locale(resolved.code());
+    crate::tray::refresh_labels(&app);
+    Ok(resolved.code().to_string())
+}
+
+/// Code of the locale backend-produced text is in.
+#[tauri::command]
+pub async fn get_locale() -> Result<String, AppError> {
+    Ok(i18n::active().code().to_string())
+}
@@ -0,0 +1,97 @@
+//! German catalogue.
+
+use super::Catalogue;
+
+pub(super) static CATALOGUE: Catalogue = Catalogue {
+    controls: &[
+        ("pan", "Schwenken"),
+        ("tilt", "Neigen"),
+        ("roll", "Rollen"),
+        ("zoom", "Zoom"),
+        ("exposure", "Belichtung"),
+        ("iris", "Iris"),
+        ("focus", "Fokus"),
+        ("brightness", "Helligkeit"),
+        ("contrast", "Kontrast"),
+        ("hue", "Farbton"),
+        ("saturation", "Sättigung"),
+        ("sharpness", "Schärfe"),
+        ("gamma", "Gamma"),
+        ("color_enable", "Farbe aktivieren"),
+        ("white_balance", "Weißabgleich"),
+        ("backlight_compensation", "Gegenlichtkompensation"),
+        ("gain", "Verstärkung"),
+        ("power_line_frequency", "Netzfrequenz"),
+        ("tally_light", "Tally-Licht"),
+        ("canon_iso", "ISO"),
+        ("canon_aperture", "Blende"),
+        ("canon_shutter_speed", "Verschlusszeit"),
+        ("canon_white_balance", "Weißabgleich"),
+        ("canon_exposure_compensation", "Belichtungskorrektur"),
+        ("canon_picture_style", "Bildstil"),
+        ("canon_image_quality", "Bildqualität"),
+    ],
+    groups: &[
+        ("image", "Bild"),
+        ("exposure", "Belichtung"),
+        ("focus", "Fokus"),
+        ("advanced", "Erweitert"),
+        ("camera", "Kamera"),
+    ],
+    errors: &[
+        (
+            "device_busy",
+            "Die Kamera wird von einer anderen Anwendung verwendet",
+        ),
+        (
+            "access_denied",
+            "Zugriff verweigert — andere Kamera-Apps schließen und erneut versuchen",
+        ),
+        (
+            "device_failed",
+            "Die Kamera hat einen unbekannten Fehler gemeldet",
+        ),
+        (
+            "canon_busy",
+            "Die Canon-Kamera ist beschäftigt — kurz warten und erneut versuchen",
+        ),
+        (
+            "canon_session_not_open",
+            "Die Sitzung der Canon-Kamera ist nicht geöffnet — Kamera neu verbinden",
+        ),
+        ("canon_disconnected", "Die Canon-Kamera wurde getrennt"),
+    ],
+    tray: &[
+        ("show_hide", "Anzeigen/Ausblenden"),
+        ("app_settings", "App-Einstellungen"),
+        ("quit", "Beenden"),
+        ("settings_window_title", "App-Einstellungen"),
+    ],
+    values: &[
+        ("white_balance.auto", "Automatisch"),
+        ("white_balance.daylight", "Tageslicht"),
+        ("white_balance.cloudy", "Bewölkt"),
+        ("white_balance.tungsten", "Kunstlicht"),
+        ("white_balance.fluorescent", "Leuchtstofflampe"),
+        ("white_balance.flash", "Blitz"),
+        ("white_balance.manual", "Manuell"),
+        ("white_balance.shade", "Schatten"),
+        ("white_balance.colour_temperature", "Farbtemperatur"),
+        ("white_balance.custom_1", "Benutzerdefiniert 1"),
+        ("white_balance.custom_2", "Benutzerdefiniert 2"),
+        ("white_balance.custom_3", "Benutzerdefiniert 3"),
+        ("picture_style.auto", "Automatisch"),
+        ("picture_style.standard", "Standard"),
+        ("picture_style.portrait", "Porträt"),
+        ("picture_style.landscape", "Landschaft"),
+        ("picture_style.fine_detail", "Feindetail"),
+        ("picture_style.neutral", "Neutral"),
+        ("picture_style.faithful", "Natürlich"),
+        ("picture_style.monochrome", "Monochrom"),
+        ("picture_style.user_defined_1", "Benutzerdefiniert 1"),
+        ("picture_style.user_defined_2", "Benutzerdefiniert 2"),
+        ("picture_style.user_defined_3", "Benutzerdefiniert 3"),
+        ("power_line.disabled", "Deaktiviert"),
+        ("power_line.auto", "Automatisch"),
+    ],
+};
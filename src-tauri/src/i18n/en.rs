@@ -0,0 +1,91 @@
+//! English catalogue.
+
+use super::Catalogue;
+
+pub(super) static CATALOGUE: Catalogue = Catalogue {
+    controls: &[
+        ("pan", "Pan"),
+        ("tilt", "Tilt"),
+        ("roll", "Roll"),
+        ("zoom", "Zoom"),
+        ("exposure", "Exposure"),
+        ("iris", "Iris"),
+        ("focus", "Focus"),
+        ("brightness", "Brightness"),
+        ("contrast", "Contrast"),
+        ("hue", "Hue"),
+        ("saturation", "Saturation"),
+        ("sharpness", "Sharpness"),
+        ("gamma", "Gamma"),
+        ("color_enable", "Colour Enable"),
+        ("white_balance", "White Balance"),
+        ("backlight_compensation", "Backlight Compensation"),
+        ("gain", "Gain"),
+        ("power_line_frequency", "Power Line Frequency"),
+        ("tally_light", "Tally Light"),
+        ("canon_iso", "ISO"),
+        ("canon_aperture", "Aperture"),
+        ("canon_shutter_speed", "Shutter Speed"),
+        ("canon_white_balance", "White Balance"),
+        ("canon_exposure_compensation", "Exposure Compensation"),
+        ("canon_picture_style", "Picture Style"),
+        ("canon_image_quality", "Image Quality"),
+    ],
+    groups: &[
+        ("image", "Image"),
+        ("exposure", "Exposure"),
+        ("focus", "Focus"),
+        ("advanced", "Advanced"),
+        ("camera", "Camera"),
+    ],
+    errors: &[
+        ("device_busy", "Camera is in use by another application"),
+        (
+            "access_denied",
+            "Access denied — close other camera apps and retry",
+        ),
+        ("device_failed", "Camera returned an unspecified error"),
+        (
+            "canon_busy",
+            "Canon camera is busy — wait a moment and try again",
+        ),
+        (
+            "canon_session_not_open",
+            "Canon camera session is not open — reconnect the camera",
+        ),
+        ("canon_disconnected", "Canon camera was disconnected"),
+    ],
+    tray: &[
+        ("show_hide", "Show/Hide"),
+        ("app_settings", "App Settings"),
+        ("quit", "Exit"),
+        ("settings_window_title", "App Settings"),
+    ],
+    values: &[
+        ("white_balance.auto", "Auto"),
+        ("white_balance.daylight", "Daylight"),
+        ("white_balance.cloudy", "Cloudy"),
+        ("white_balance.tungsten", "Tungsten"),
+        ("white_balance.fluorescent", "Fluorescent"),
+        ("white_balance.flash", "Flash"),
+        ("white_balance.manual", "Manual"),
+        ("white_balance.shade", "Shade"),
+        ("white_balance.colour_temperature", "Colour Temperature"),
+        ("white_balance.custom_1", "Custom 1"),
+        ("white_balance.custom_2", "Custom 2"),
+        ("white_balance.custom_3", "Custom 3"),
+        ("picture_style.auto", "Auto"),
+        ("picture_style.standard", "Standard"),
+        ("picture_style.portrait", "Portrait"),
+        ("picture_style.landscape", "Landscape"),
+        ("picture_style.fine_detail", "Fine Detail"),
+        ("picture_style.neutral", "Neutral"),
+        ("picture_style.faithful", "Faithful"),
+        ("picture_style.monochrome", "Monochrome"),
+        ("picture_style.user_defined_1", "User Defined 1"),
+        ("picture_style.user_defined_2", "User Defined 2"),
+        ("picture_style.user_defined_3", "User Defined 3"),
+        ("power_line.disabled", "Disabled"),
+        ("power_line.auto", "Auto"),
+    ],
+};
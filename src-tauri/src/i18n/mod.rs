@@ -0,0 +1,262 @@
+// Localisation — message catalogues for backend-produced user-facing text.
+//
+// Callers look text up by a stable key (a control's IPC id, a group id, an
+// error key); keys never change between locales, only the text does. Every
+// locale ships the same keys, English is the fallback, and lookups are a
+// scan over a static table returning `&'static str`, so nothing allocates.
+
+#[cfg(feature = "app")]
+pub mod commands;
+mod de;
+mod en;
+mod nl;
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// A catalogue section: key and text pairs.
+type Entries = &'static [(&'static str, &'static str)];
+
+/// All of one locale's text, by domain.
+pub struct Catalogue {
+    controls: Entries,
+    groups: Entries,
+    errors: Entries,
+    tray: Entries,
+    values: Entries,
+}
+
+impl Catalogue {
+    fn entries(&self, domain: Domain) -> Entries {
+        match domain {
+            Domain::Control => self.controls,
+            Domain::Group => self.groups,
+            Domain::Error => self.errors,
+            Domain::Tray => self.tray,
+            Domain::Value => self.values,
+        }
+    }
+
+    fn get(&self, domain: Domain, key: &str) -> Option<&'static str> {
+        self.entries(domain)
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|&(_, text)| text)
+    }
+}
+
+/// What a key names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    /// Control display names, keyed by IPC id.
+    Control,
+    /// Control group labels, keyed by group id.
+    Group,
+    /// Default messages for error codes, keyed by code.
+    Error,
+    /// Tray menu and window text.
+    Tray,
+    /// Labels for control values, like white balance presets.
+    Value,
+}
+
+impl Domain {
+    pub const ALL: [Self; 5] = [
+        Self::Control,
+        Self::Group,
+        Self::Error,
+        Self::Tray,
+        Self::Value,
+    ];
+}
+
+/// A locale the backend ships text for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+    Nl,
+}
+
+impl Locale {
+    pub const ALL: [Self; 3] = [Self::En, Self::De, Self::Nl];
+
+    /// Language code, as `set_locale` takes and returns it.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+            Self::Nl => "nl",
+        }
+    }
+
+    /// Locale for a language tag such as `de`, `nl-BE` or `en_GB`. Only the
+    /// language subtag counts; `None` if no catalogue ships for it.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(language))
+    }
+
+    /// Separator between the whole and fractional part of a number.
+    pub fn decimal_separator(self) -> char {
+        match self {
+            Self::En => '.',
+            Self::De | Self::Nl => ',',
+        }
+    }
+
+    fn catalogue(self) -> &'static Catalogue {
+        match self {
+            Self::En => &en::CATALOGUE,
+            Self::De => &de::CATALOGUE,
+            Self::Nl => &nl::CATALOGUE,
+        }
+    }
+
+    fn from_index(index: u8) -> Self {
+        Self::ALL
+            .get(usize::from(index))
+            .copied()
+            .unwrap_or(Self::En)
+    }
+
+    fn index(self) -> u8 {
+        match self {
+            Self::En => 0,
+            Self::De => 1,
+            Self::Nl => 2,
+        }
+    }
+}
+
+/// The active locale, as an index into `Locale::ALL`.
+static ACTIVE: AtomicU8 = AtomicU8::new(0);
+
+/// The locale text is currently looked up in.
+pub fn active() -> Locale {
+    Locale::from_index(ACTIVE.load(Ordering::Relaxed))
+}
+
+/// Switch the locale text is looked up in.
+pub fn set_active(locale: Locale) {
+    ACTIVE.store(locale.index(), Ordering::Relaxed);
+}
+
+/// Text for `key` in the active locale.
+pub fn text(domain: Domain, key: &'static str) -> &'static str {
+    text_in(active(), domain, key)
+}
+
+/// Text for `key` in `locale`, falling back to English and then to the key
+/// itself.
+pub fn text_in(locale: Locale, domain: Domain, key: &'static str) -> &'static str {
+    locale
+        .catalogue()
+        .get(domain, key)
+        .or_else(|| Locale::En.catalogue().get(domain, key))
+        .unwrap_or(key)
+}
+
+/// Text for `key` in the active locale, falling back to English; `None` if
+/// no catalogue has it.
+pub fn lookup(domain: Domain, key: &str) -> Option<&'static str> {
+    active()
+        .catalogue()
+        .get(domain, key)
+        .or_else(|| Locale::En.catalogue().get(domain, key))
+}
+
+/// `label` with its decimal points swapped for the active locale's
+/// separator, for numeric labels like `f/5.6` or `0.8"`.
+pub fn localise_decimals(label: String) -> String {
+    localise_decimals_in(active(), label)
+}
+
+/// `label` with its decimal points swapped for `locale`'s separator.
+pub fn localise_decimals_in(locale: Locale, label: String) -> String {
+    match locale.decimal_separator() {
+        '.' => label,
+        separator => label.replace('.', &separator.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn keys(locale: Locale, domain: Domain) -> BTreeSet<&'static str> {
+        locale
+            .catalogue()
+            .entries(domain)
+            .iter()
+            .map(|&(key, _)| key)
+            .collect()
+    }
+
+    #[test]
+    fn every_locale_has_every_english_key() {
+        for domain in Domain::ALL {
+            let english = keys(Locale::En, domain);
+            for locale in Locale::ALL {
+                assert_eq!(
+                    keys(locale, domain),
+                    english,
+                    "{domain:?} keys differ in {}",
+                    locale.code()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_catalogue_repeats_a_key() {
+        for locale in Locale::ALL {
+            for domain in Domain::ALL {
+                let entries = locale.catalogue().entries(domain);
+                assert_eq!(
+                    keys(locale, domain).len(),
+                    entries.len(),
+                    "{domain:?} repeats a key in {}",
+                    locale.code()
+                );
+                assert!(entries.iter().all(|(_, text)| !text.is_empty()));
+            }
+        }
+    }
+
+    #[test]
+    fn lookups_fall_back_to_the_key() {
+        assert_eq!(
+            text_in(Locale::De, Domain::Control, "white_balance"),
+            "Weißabgleich"
+        );
+        assert_eq!(text_in(Locale::Nl, Domain::Group, "image"), "Beeld");
+        assert_eq!(text_in(Locale::De, Domain::Control, "unknown"), "unknown");
+    }
+
+    #[test]
+    fn locales_parse_from_language_tags() {
+        assert_eq!(Locale::parse("de"), Some(Locale::De));
+        assert_eq!(Locale::parse("nl-BE"), Some(Locale::Nl));
+        assert_eq!(Locale::parse("EN_gb"), Some(Locale::En));
+        assert_eq!(Locale::parse("fr-FR"), None);
+        assert_eq!(Locale::parse(""), None);
+    }
+
+    #[test]
+    fn locale_indices_roundtrip() {
+        for locale in Locale::ALL {
+            assert_eq!(Locale::from_index(locale.index()), locale);
+        }
+        assert_eq!(Locale::from_index(u8::MAX), Locale::En);
+    }
+
+    #[test]
+    fn decimals_use_the_locale_separator() {
+        assert_eq!(localise_decimals_in(Locale::En, "f/5.6".into()), "f/5.6");
+        assert_eq!(localise_decimals_in(Locale::De, "f/5.6".into()), "f/5,6");
+        assert_eq!(localise_decimals_in(Locale::Nl, "+1.3".into()), "+1,3");
+    }
+}
@@ -0,0 +1,94 @@
+//! Dutch catalogue.
+
+use super::Catalogue;
+
+pub(super) static CATALOGUE: Catalogue = Catalogue {
+    controls: &[
+        ("pan", "Pannen"),
+        ("tilt", "Kantelen"),
+        ("roll", "Rollen"),
+        ("zoom", "Zoom"),
+        ("exposure", "Belichting"),
+        ("iris", "Iris"),
+        ("focus", "Focus"),
+        ("brightness", "Helderheid"),
+        ("contrast", "Contrast"),
+        ("hue", "Tint"),
+        ("saturation", "Verzadiging"),
+        ("sharpness", "Scherpte"),
+        ("gamma", "Gamma"),
+        ("color_enable", "Kleur inschakelen"),
+        ("white_balance", "Witbalans"),
+        ("backlight_compensation", "Tegenlichtcompensatie"),
+        ("gain", "Versterking"),
+        ("power_line_frequency", "Netfrequentie"),
+        ("tally_light", "Tallylampje"),
+        ("canon_iso", "ISO"),
+        ("canon_aperture", "Diafragma"),
+        ("canon_shutter_speed", "Sluitertijd"),
+        ("canon_white_balance", "Witbalans"),
+        ("canon_exposure_compensation", "Belichtingscorrectie"),
+        ("canon_picture_style", "Beeldstijl"),
+        ("canon_image_quality", "Beeldkwaliteit"),
+    ],
+    groups: &[
+        ("image", "Beeld"),
+        ("exposure", "Belichting"),
+        ("focus", "Focus"),
+        ("advanced", "Geavanceerd"),
+        ("camera", "Camera"),
+    ],
+    errors: &[
+        (
+            "device_busy",
+            "De camera wordt gebruikt door een andere toepassing",
+        ),
+        (
+            "access_denied",
+            "Toegang geweigerd — sluit andere camera-apps en probeer het opnieuw",
+        ),
+        ("device_failed", "De camera gaf een onbekende fout"),
+        (
+            "canon_busy",
+            "De Canon-camera is bezet — wacht even en probeer het opnieuw",
+        ),
+        (
+            "canon_session_not_open",
+            "De sessie met de Canon-camera is niet geopend — sluit de camera opnieuw aan",
+        ),
+        ("canon_disconnected", "De Canon-camera is losgekoppeld"),
+    ],
+    tray: &[
+        ("show_hide", "Tonen/Verbergen"),
+        ("app_settings", "App-instellingen"),
+        ("quit", "Afsluiten"),
+        ("settings_window_title", "App-instellingen"),
+    ],
+    values: &[
+        ("white_balance.auto", "Automatisch"),
+        ("white_balance.daylight", "Daglicht"),
+        ("white_balance.cloudy", "Bewolkt"),
+        ("white_balance.tungsten", "Gloeilamp"),
+        ("white_balance.fluorescent", "TL-licht"),
+        ("white_balance.flash", "Flits"),
+        ("white_balance.manual", "Handmatig"),
+        ("white_balance.shade", "Schaduw"),
+        ("white_balance.colour_temperature", "Kleurtemperatuur"),
+        ("white_balance.custom_1", "Aangepast 1"),
+        ("white_balance.custom_2", "Aangepast 2"),
+        ("white_balance.custom_3", "Aangepast 3"),
+        ("picture_style.auto", "Automatisch"),
+        ("picture_style.standard", "Standaard"),
+        ("picture_style.portrait", "Portret"),
+        ("picture_style.landscape", "Landschap"),
+        ("picture_style.fine_detail", "Fijn detail"),
+        ("picture_style.neutral", "Neutraal"),
+        ("picture_style.faithful", "Natuurgetrouw"),
+        ("picture_style.monochrome", "Monochroom"),
+        ("picture_style.user_defined_1", "Aangepast 1"),
+        ("picture_style.user_defined_2", "Aangepast 2"),
+        ("picture_style.user_defined_3", "Aangepast 3"),
+        ("power_line.disabled", "Uitgeschakeld"),
+        ("power_line.auto", "Automatisch"),
+    ],
+};
@@ -0,0 +1,191 @@
+// Tauri commands and the background thread for keyboard control.
+//
+// Preview windows forward raw key presses and releases to `key_input`; the
+// thread keeps each device's held keys and writes the nudges they make,
+// including the repeats while a key is held, so every window nudges the
+// same way.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Instant;
+
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::keys::{find_binding, KeyBinding, KeyHold, Nudge};
+use crate::camera::commands::CameraState;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::{code, AppError};
+use crate::settings::apply::{nudge_control, parse_control_id};
+use crate::settings::commands::SettingsState;
+
+/// A key event forwarded from a preview window.
+enum KeyEvent {
+    Press {
+        device_id: String,
+        key: String,
+        binding: KeyBinding,
+    },
+    Release {
+        device_id: String,
+        key: String,
+    },
+}
+
+/// Where `key_input` sends key events for the keyboard thread.
+pub struct KeyInputState {
+    events: Sender<KeyEvent>,
+}
+
+/// Start the keyboard thread, returning the state `key_input` sends to it.
+pub fn start_key_input(app: &AppHandle) -> std::io::Result<KeyInputState> {
+    let (events, received) = mpsc::channel();
+    let app = app.clone();
+    std::thread::Builder::new()
+        .name("key-input".to_string())
+        .spawn(move || run(&app, received))?;
+    Ok(KeyInputState { events })
+}
+
+/// Track held keys until every sender is gone, writing nudges as presses
+/// arrive and repeats fall due.
+fn run(app: &AppHandle, events: Receiver<KeyEvent>) {
+    let mut holds: HashMap<String, KeyHold> = HashMap::new();
+    loop {
+        let next_tick = holds.values().filter_map(KeyHold::next_tick).min();
+        let received = match next_tick {
+            Some(at) => events.recv_timeout(at.saturating_duration_since(Instant::now())),
+            None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let now = Instant::now();
+        match received {
+            Ok(KeyEvent::Press {
+                device_id,
+                key,
+                binding,
+            }) => {
+                let hold = holds.entry(device_id.clone()).or_default();
+                if let Some(nudge) = hold.press(&key, &binding, now) {
+                    write(app, &device_id, &nudge);
+                }
+            }
+            Ok(KeyEvent::Release { device_id, key }) => {
+                if let Some(hold) = holds.get_mut(&device_id) {
+                    hold.release(&key, now);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        for (device_id, hold) in &mut holds {
+            for nudge in hold.tick(now) {
+                write(app, device_id, &nudge);
+            }
+        }
+        holds.retain(|_, hold| !hold.is_idle());
+    }
+    tracing::debug!("Key input thread stopped");
+}
+
+fn write(app: &AppHandle, device_id: &str, nudge: &Nudge) {
+    let store = &app.state::<SettingsState>().store;
+    let camera_name = store
+        .get_camera(device_id)
+        .map(|camera| camera.name)
+        .unwrap_or_else(|| device_id.to_string());
+    let result = parse_control_id(&nudge.control_id).and_then(|control| {
+        nudge_control(
+            &app.state::<CameraState>().backend,
+            store,
+            &app.state::<ControlLatencyState>(),
+            device_id,
+            &camera_name,
+            control,
+            nudge.steps,
+        )
+    });
+    match result {
+        Ok(written) => {
+            let _ = app.emit(
+                "control-nudged",
+                serde_json::json!({
+                    "deviceId": device_id,
+                    "controlId": nudge.control_id,
+                    "value": written.value.value(),
+                }),
+            );
+        }
+        // Expected whenever the camera has no such control, e.g. no pan
+        Err(e) if e.code == code::CONTROL_UNAVAILABLE => tracing::debug!("{e}"),
+        Err(e) => tracing::warn!("Nudging {} on {device_id} failed: {e}", nudge.control_id),
+    }
+}
+
+/// A key pressed or released while a preview of `device_id` has focus.
+/// `key` is the event's `KeyboardEvent.key`.
+///
+/// Returns whether the key is bound to a control, so the window can keep
+/// the key from doing anything else. A window losing focus should release
+/// the keys it has sent presses for.
+#[tauri::command]
+pub async fn key_input(
+    settings_state: State<'_, SettingsState>,
+    key_state: State<'_, KeyInputState>,
+    device_id: String,
+    key: String,
+    pressed: bool,
+) -> Result<bool, AppError> {
+    let bindings = settings_state.store.key_bindings();
+    let Some(binding) = find_binding(&bindings, &key).cloned() else {
+        return Ok(false);
+    };
+    let event = if pressed {
+        KeyEvent::Press {
+            device_id,
+            key,
+            binding,
+        }
+    } else {
+        KeyEvent::Release { device_id, key }
+    };
+    key_state
+        .events
+        .send(event)
+        .map_err(|_| AppError::new(code::INTERNAL, "keyboard control has stopped"))?;
+    Ok(true)
+}
+
+/// Keys that nudge controls.
+#[tauri::command]
+pub async fn get_key_bindings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<Vec<KeyBinding>, AppError> {
+    Ok(settings_state.store.key_bindings())
+}
+
+/// Replace the keys that nudge controls, or go back to the built-in ones
+/// with `null`. Each key may only be bound once, to a known control.
+#[tauri::command]
+pub async fn set_key_bindings(
+    settings_state: State<'_, SettingsState>,
+    bindings: Option<Vec<KeyBinding>>,
+) -> Result<Vec<KeyBinding>, AppError> {
+    if let Some(bindings) = &bindings {
+        for (i, binding) in bindings.iter().enumerate() {
+            if binding.key.is_empty() {
+                return Err(AppError::new(
+                    code::INVALID_ARGUMENT,
+                    "Key bindings need a key",
+                ));
+            }
+            parse_control_id(&binding.control_id)?;
+            if bindings[..i].iter().any(|other| other.key == binding.key) {
+                return Err(AppError::new(
+                    code::INVALID_ARGUMENT,
+                    format!("'{}' is bound more than once", binding.key),
+                ));
+            }
+        }
+    }
+    settings_state.store.set_key_bindings(bindings);
+    Ok(settings_state.store.key_bindings())
+}
@@ -0,0 +1,345 @@
+// Turning held keys into camera control nudges.
+//
+// Everything here is pure and takes the time as an argument: the key
+// bindings and the per-device hold state machine. A press nudges its
+// control one step straight away; after `REPEAT_DELAY` the hold repeats
+// every `REPEAT_TICK`, by a step more every `ACCELERATE_EVERY` repeats up to
+// `MAX_STEPS`, until the key is released. The timer and the control writes
+// live in `commands`; the frontend releases held keys when it loses focus.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// How long a key is held before it starts repeating.
+pub const REPEAT_DELAY: Duration = Duration::from_millis(300);
+
+/// Time between repeats of a held key.
+pub const REPEAT_TICK: Duration = Duration::from_millis(50);
+
+/// Repeats after which each nudge grows by a step.
+pub const ACCELERATE_EVERY: u32 = 5;
+
+/// Most steps a single repeat nudges by.
+pub const MAX_STEPS: i32 = 8;
+
+/// A key nudging a control, as stored in the settings file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBinding {
+    /// `KeyboardEvent.key` of the key, e.g. "ArrowLeft" or "+".
+    pub key: String,
+    pub control_id: String,
+    /// Nudge up (towards the control's maximum) or down.
+    pub up: bool,
+}
+
+impl KeyBinding {
+    fn new(key: &str, control_id: &str, up: bool) -> Self {
+        Self {
+            key: key.to_string(),
+            control_id: control_id.to_string(),
+            up,
+        }
+    }
+}
+
+/// Arrow keys pan and tilt, plus and minus zoom. "=" zooms in too, as it's
+/// "+" without shift on most layouts.
+pub fn default_bindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding::new("ArrowLeft", "pan", false),
+        KeyBinding::new("ArrowRight", "pan", true),
+        KeyBinding::new("ArrowUp", "tilt", true),
+        KeyBinding::new("ArrowDown", "tilt", false),
+        KeyBinding::new("+", "zoom", true),
+        KeyBinding::new("=", "zoom", true),
+        KeyBinding::new("-", "zoom", false),
+    ]
+}
+
+/// The binding for `key`, if it has one.
+pub fn find_binding<'a>(bindings: &'a [KeyBinding], key: &str) -> Option<&'a KeyBinding> {
+    bindings.iter().find(|binding| binding.key == key)
+}
+
+/// Move a control by `steps` of its step size, negative for down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nudge {
+    pub control_id: String,
+    pub steps: i32,
+}
+
+/// A key being held.
+#[derive(Debug)]
+struct HeldKey {
+    control_id: String,
+    direction: i32,
+}
+
+/// A control with keys held on it that don't cancel out.
+#[derive(Debug)]
+struct Repeat {
+    direction: i32,
+    repeats: u32,
+    next: Instant,
+}
+
+impl Repeat {
+    fn new(direction: i32, now: Instant) -> Self {
+        Self {
+            direction,
+            repeats: 0,
+            next: now + REPEAT_DELAY,
+        }
+    }
+
+    /// Steps the next repeat nudges by.
+    fn steps(&self) -> i32 {
+        let steps = 1 + (self.repeats / ACCELERATE_EVERY) as i32;
+        self.direction * steps.min(MAX_STEPS)
+    }
+}
+
+/// One device's held keys and the repeats they drive.
+///
+/// Keys on the same control add up, so holding both directions at once
+/// cancels out until one is released; the other then starts over as though
+/// it had just been held.
+#[derive(Debug, Default)]
+pub struct KeyHold {
+    held: HashMap<String, HeldKey>,
+    repeats: HashMap<String, Repeat>,
+}
+
+impl KeyHold {
+    /// A press of `key`, bound to `binding`, at `now`. Returns the nudge to
+    /// make straight away, if the press starts the control moving.
+    ///
+    /// A press of a key already held is the browser's key repeat, which is
+    /// ignored: repeats come from [`tick`](Self::tick).
+    pub fn press(&mut self, key: &str, binding: &KeyBinding, now: Instant) -> Option<Nudge> {
+        if self.held.contains_key(key) {
+            return None;
+        }
+        self.held.insert(
+            key.to_string(),
+            HeldKey {
+                control_id: binding.control_id.clone(),
+                direction: if binding.up { 1 } else { -1 },
+            },
+        );
+        let direction = self.update(&binding.control_id, now)?;
+        Some(Nudge {
+            control_id: binding.control_id.clone(),
+            steps: direction,
+        })
+    }
+
+    /// A release of `key` at `now`. Releasing a key that isn't held does
+    /// nothing.
+    pub fn release(&mut self, key: &str, now: Instant) {
+        if let Some(held) = self.held.remove(key) {
+            self.update(&held.control_id, now);
+        }
+    }
+
+    /// Repeats due by `now`, at most one per control.
+    pub fn tick(&mut self, now: Instant) -> Vec<Nudge> {
+        let mut nudges = Vec::new();
+        for (control_id, repeat) in &mut self.repeats {
+            if repeat.next > now {
+                continue;
+            }
+            nudges.push(Nudge {
+                control_id: control_id.clone(),
+                steps: repeat.steps(),
+            });
+            repeat.repeats += 1;
+            // Counted from now, so a late tick doesn't make up the ones it
+            // missed
+            repeat.next = now + REPEAT_TICK;
+        }
+        nudges.sort_by(|a, b| a.control_id.cmp(&b.control_id));
+        nudges
+    }
+
+    /// When [`tick`](Self::tick) next has a repeat to make, if any.
+    pub fn next_tick(&self) -> Option<Instant> {
+        self.repeats.values().map(|repeat| repeat.next).min()
+    }
+
+    /// Whether no keys are held.
+    pub fn is_idle(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Recompute `control_id`'s direction after a key on it changed,
+    /// starting its repeats over if the direction changed. Returns the new
+    /// direction if the control moves.
+    fn update(&mut self, control_id: &str, now: Instant) -> Option<i32> {
+        let direction: i32 = self
+            .held
+            .values()
+            .filter(|held| held.control_id == control_id)
+            .map(|held| held.direction)
+            .sum::<i32>()
+            .signum();
+        if direction == 0 {
+            self.repeats.remove(control_id);
+            return None;
+        }
+        if self.repeats.get(control_id).map(|r| r.direction) != Some(direction) {
+            self.repeats
+                .insert(control_id.to_string(), Repeat::new(direction, now));
+        }
+        Some(direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(key: &str) -> KeyBinding {
+        find_binding(&default_bindings(), key).unwrap().clone()
+    }
+
+    fn nudge(control_id: &str, steps: i32) -> Nudge {
+        Nudge {
+            control_id: control_id.to_string(),
+            steps,
+        }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Tick every `REPEAT_TICK` from `from` up to and including `to`,
+    /// collecting the steps nudged.
+    fn ticks(hold: &mut KeyHold, start: Instant, from: Duration, to: Duration) -> Vec<i32> {
+        let mut steps = Vec::new();
+        let mut at = from;
+        while at <= to {
+            steps.extend(hold.tick(start + at).into_iter().map(|n| n.steps));
+            at += REPEAT_TICK;
+        }
+        steps
+    }
+
+    #[test]
+    fn default_bindings_cover_pan_tilt_and_zoom() {
+        let bindings = default_bindings();
+        assert_eq!(
+            find_binding(&bindings, "ArrowLeft").unwrap().control_id,
+            "pan"
+        );
+        assert!(!find_binding(&bindings, "ArrowLeft").unwrap().up);
+        assert_eq!(
+            find_binding(&bindings, "ArrowUp").unwrap().control_id,
+            "tilt"
+        );
+        assert!(find_binding(&bindings, "=").unwrap().up);
+        assert!(find_binding(&bindings, "a").is_none());
+    }
+
+    #[test]
+    fn a_quick_tap_nudges_once() {
+        let start = Instant::now();
+        let mut hold = KeyHold::default();
+
+        assert_eq!(
+            hold.press("ArrowRight", &binding("ArrowRight"), start),
+            Some(nudge("pan", 1))
+        );
+        hold.release("ArrowRight", start + ms(80));
+
+        assert!(hold.is_idle());
+        assert_eq!(hold.next_tick(), None);
+        assert!(ticks(&mut hold, start, ms(0), ms(1000)).is_empty());
+    }
+
+    #[test]
+    fn a_hold_repeats_after_the_delay_and_accelerates() {
+        let start = Instant::now();
+        let mut hold = KeyHold::default();
+        hold.press("ArrowLeft", &binding("ArrowLeft"), start);
+
+        // Nothing until the repeat delay
+        assert!(hold.tick(start + REPEAT_DELAY - ms(1)).is_empty());
+        assert_eq!(hold.next_tick(), Some(start + REPEAT_DELAY));
+
+        let steps = ticks(
+            &mut hold,
+            start,
+            REPEAT_DELAY,
+            REPEAT_DELAY + REPEAT_TICK * 11,
+        );
+        assert_eq!(steps, [-1, -1, -1, -1, -1, -2, -2, -2, -2, -2, -3, -3]);
+
+        hold.release("ArrowLeft", start + ms(2000));
+        assert!(hold.tick(start + ms(2100)).is_empty());
+    }
+
+    #[test]
+    fn a_long_hold_levels_off_at_the_max_rate() {
+        let start = Instant::now();
+        let mut hold = KeyHold::default();
+        hold.press("+", &binding("+"), start);
+
+        // The browser's own key repeat is ignored
+        assert_eq!(hold.press("+", &binding("+"), start + ms(500)), None);
+        let steps = ticks(&mut hold, start, ms(0), ms(5000));
+
+        let reached = (MAX_STEPS as u32 - 1) * ACCELERATE_EVERY;
+        assert_eq!(steps[reached as usize - 1], MAX_STEPS - 1);
+        assert!(steps[reached as usize..].iter().all(|&s| s == MAX_STEPS));
+        assert!(steps.len() > reached as usize + 10);
+    }
+
+    #[test]
+    fn opposing_keys_cancel_until_one_is_released() {
+        let start = Instant::now();
+        let mut hold = KeyHold::default();
+        hold.press("ArrowLeft", &binding("ArrowLeft"), start);
+        assert_eq!(
+            hold.press("ArrowRight", &binding("ArrowRight"), start + ms(100)),
+            None
+        );
+
+        // Both held: no repeats
+        assert!(ticks(&mut hold, start, ms(100), ms(900)).is_empty());
+
+        // Releasing left leaves right, which starts over
+        hold.release("ArrowLeft", start + ms(900));
+        assert!(hold.tick(start + ms(900) + REPEAT_DELAY - ms(1)).is_empty());
+        assert_eq!(hold.tick(start + ms(900) + REPEAT_DELAY), [nudge("pan", 1)]);
+    }
+
+    #[test]
+    fn keys_on_different_controls_repeat_independently() {
+        let start = Instant::now();
+        let mut hold = KeyHold::default();
+        hold.press("ArrowRight", &binding("ArrowRight"), start);
+        hold.press("ArrowUp", &binding("ArrowUp"), start);
+
+        assert_eq!(
+            hold.tick(start + REPEAT_DELAY),
+            [nudge("pan", 1), nudge("tilt", 1)]
+        );
+    }
+
+    #[test]
+    fn a_late_tick_does_not_catch_up() {
+        let start = Instant::now();
+        let mut hold = KeyHold::default();
+        hold.press("ArrowDown", &binding("ArrowDown"), start);
+
+        let late = start + REPEAT_DELAY + REPEAT_TICK * 5;
+        assert_eq!(hold.tick(late), [nudge("tilt", -1)]);
+        assert!(hold.tick(late).is_empty());
+        assert_eq!(hold.next_tick(), Some(late + REPEAT_TICK));
+    }
+}
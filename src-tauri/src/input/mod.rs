@@ -1 +1,5 @@
-// Input devices — MIDI controllers, Stream Deck.
+// Input devices — the keyboard, MIDI controllers, Stream Deck.
+
+#[cfg(feature = "app")]
+pub mod commands;
+pub mod keys;
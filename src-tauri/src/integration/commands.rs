@@ -0,0 +1,148 @@
+// Tauri commands and lifecycle for the localhost control API.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::commands::CameraState;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::{code, AppError};
+use crate::integration::control_api::{bind, generate_token, serve, ControlContext, DEFAULT_PORT};
+use crate::preset::commands::queue_preset_apply;
+use crate::preset::types::PresetApplyResult;
+use crate::settings::commands::SettingsState;
+use crate::settings::store::SettingsStore;
+
+/// The running control API server, if any.
+#[derive(Default)]
+pub struct ControlApiState {
+    server: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Control API settings as shown in the settings window.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    /// Bearer token; absent until the API is first enabled.
+    pub token: Option<String>,
+}
+
+/// Serves the API from the app's managed state.
+struct AppContext(AppHandle);
+
+impl ControlContext for AppContext {
+    fn backend(&self) -> &dyn CameraBackend {
+        &self.0.state::<CameraState>().inner().backend
+    }
+
+    fn store(&self) -> &SettingsStore {
+        &self.0.state::<SettingsState>().inner().store
+    }
+
+    fn latency(&self) -> &ControlLatencyState {
+        self.0.state::<ControlLatencyState>().inner()
+    }
+
+    fn apply_preset(
+        &self,
+        device_id: &str,
+        preset_id: &str,
+        camera_name: &str,
+    ) -> impl Future<Output = Result<PresetApplyResult, AppError>> + Send {
+        queue_preset_apply(&self.0, device_id, preset_id, camera_name)
+    }
+}
+
+fn status(app: &AppHandle) -> ControlApiStatus {
+    let store = &app.state::<SettingsState>().store;
+    ControlApiStatus {
+        enabled: store.control_api_enabled(),
+        running: app.state::<ControlApiState>().server.lock().is_some(),
+        port: store.control_api_port().unwrap_or(DEFAULT_PORT),
+        token: store.control_api_token(),
+    }
+}
+
+/// Start (or restart) the control API with the saved port and token,
+/// generating the token if there isn't one yet.
+pub async fn start_control_api(app: &AppHandle) -> Result<(), String> {
+    stop_control_api(app).await;
+
+    let store = &app.state::<SettingsState>().store;
+    let token = store.control_api_token_or(generate_token);
+    let listener = bind(store.control_api_port().unwrap_or(DEFAULT_PORT)).await?;
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!("Control API listening on http://{addr}/api");
+    }
+
+    let server = tauri::async_runtime::spawn(serve(
+        listener,
+        Arc::new(AppContext(app.clone())),
+        Arc::from(token),
+    ));
+    *app.state::<ControlApiState>().server.lock() = Some(server);
+    Ok(())
+}
+
+/// Stop the control API, waiting until its port is released. Idempotent.
+pub async fn stop_control_api(app: &AppHandle) {
+    let server = app.state::<ControlApiState>().server.lock().take();
+    if let Some(server) = server {
+        server.abort();
+        // Resolves once the task, and with it the listener, is dropped
+        let _ = server.await;
+        tracing::info!("Control API stopped");
+    }
+}
+
+/// Whether the control API is enabled and running, its port and its token.
+#[tauri::command]
+pub async fn get_control_api(app: AppHandle) -> Result<ControlApiStatus, AppError> {
+    Ok(status(&app))
+}
+
+/// Enable or disable the localhost control API. Enabling it the first time
+/// generates its token.
+///
+/// The preference is saved even if the server fails to start.
+#[tauri::command]
+pub async fn set_control_api_enabled(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<ControlApiStatus, AppError> {
+    settings_state.store.set_control_api_enabled(enabled);
+    if enabled {
+        start_control_api(&app)
+            .await
+            .map_err(AppError::with_code(code::INTEGRATION_FAILED))?;
+    } else {
+        stop_control_api(&app).await;
+    }
+    Ok(status(&app))
+}
+
+/// Replace the control API's token, restarting the server if it's running
+/// so the old token stops working straight away.
+#[tauri::command]
+pub async fn regenerate_control_api_token(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+) -> Result<ControlApiStatus, AppError> {
+    settings_state
+        .store
+        .set_control_api_token(&generate_token());
+    if settings_state.store.control_api_enabled() {
+        start_control_api(&app)
+            .await
+            .map_err(AppError::with_code(code::INTEGRATION_FAILED))?;
+    }
+    Ok(status(&app))
+}
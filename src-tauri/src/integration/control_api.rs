@@ -0,0 +1,643 @@
+// Localhost control API — lets home automation (Home Assistant and the like)
+// read and set camera controls and apply presets over HTTP.
+//
+// Listens on 127.0.0.1 only and requires a bearer token on every request.
+// Each route goes through the same `settings::apply` functions the Tauri
+// commands use, so a value set over HTTP is clamped, timed and saved exactly
+// like one set from the UI. Errors come back as RFC 9457 problem documents
+// carrying the same humanised message the UI would show.
+
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::siblings::group_siblings;
+use crate::camera::types::{CameraDevice, ControlId, DeviceId};
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::AppError;
+use crate::integration::http::{read_request, write_response, ReadError, Request, Response};
+use crate::preset::types::PresetApplyResult;
+use crate::settings::apply::{self, find_descriptor, write_control, write_control_auto};
+use crate::settings::control_cache::lookup_controls;
+use crate::settings::store::SettingsStore;
+
+/// Port the API listens on unless the settings file says otherwise.
+pub const DEFAULT_PORT: u16 = 47_820;
+
+/// How long a client gets to send its request before the connection is
+/// dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const JSON: &str = "application/json";
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// What the API needs from the app: the camera backend, the settings store
+/// and latency tracking, plus a way to apply presets.
+pub trait ControlContext: Send + Sync + 'static {
+    fn backend(&self) -> &dyn CameraBackend;
+    fn store(&self) -> &SettingsStore;
+    fn latency(&self) -> &ControlLatencyState;
+
+    /// Apply a preset to a camera, returning the controls written and
+    /// skipped.
+    ///
+    /// Applies directly by default; the app routes it through the device
+    /// queue like the `apply_preset` command.
+    fn apply_preset(
+        &self,
+        device_id: &str,
+        preset_id: &str,
+        camera_name: &str,
+    ) -> impl Future<Output = Result<PresetApplyResult, AppError>> + Send {
+        std::future::ready(apply::apply_preset(
+            self.backend(),
+            self.store(),
+            self.latency(),
+            device_id,
+            preset_id,
+            camera_name,
+        ))
+    }
+}
+
+/// Generate a fresh bearer token: 128 bits as 32 hex characters.
+///
+/// Built from std's randomly keyed SipHash, which is seeded from the OS
+/// random source, rather than pulling in a random number crate.
+pub fn generate_token() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    (0..2u8)
+        .map(|half| {
+            let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u8(half);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Compare tokens in time independent of where they differ.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// An RFC 9457 problem document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+}
+
+impl Problem {
+    fn new(status: u16, title: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            kind: "about:blank",
+            title,
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(400, "Bad request", detail)
+    }
+
+    fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(404, "Not found", detail)
+    }
+
+    /// The camera rejected or failed the change.
+    fn not_applied(detail: impl Into<String>) -> Self {
+        Self::new(422, "Change not applied", detail)
+    }
+
+    /// The camera couldn't be read.
+    fn camera_error(detail: impl Into<String>) -> Self {
+        Self::new(500, "Camera error", detail)
+    }
+
+    fn into_response(self) -> Response {
+        Response::json(self.status, PROBLEM_JSON, &self)
+    }
+}
+
+/// Body of `PUT /api/cameras/{id}/controls/{control}`. With both fields the
+/// mode is switched first, so a value written with `auto: false` sticks.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ControlUpdate {
+    value: Option<i32>,
+    auto: Option<bool>,
+}
+
+/// Response to `POST /api/cameras/{id}/presets/{name}/apply`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PresetApplied {
+    preset_id: String,
+    controls_written: usize,
+}
+
+/// Answer one request.
+pub async fn handle<C: ControlContext>(ctx: &C, token: &str, request: &Request) -> Response {
+    let authorised = request
+        .header("authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|given| tokens_match(given.trim(), token));
+    if !authorised {
+        return Problem::new(401, "Unauthorised", "A valid bearer token is required")
+            .into_response()
+            .with_header("WWW-Authenticate", "Bearer");
+    }
+
+    match route(ctx, request).await {
+        Ok(response) => response,
+        Err(problem) => problem.into_response(),
+    }
+}
+
+async fn route<C: ControlContext>(ctx: &C, request: &Request) -> Result<Response, Problem> {
+    let segments = request.segments().map_err(Problem::bad_request)?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+
+    let allowed = match segments.as_slice() {
+        ["api", "cameras"] => "GET",
+        ["api", "cameras", _, "controls"] => "GET",
+        ["api", "cameras", _, "controls", _] => "PUT",
+        ["api", "cameras", _, "presets", _, "apply"] => "POST",
+        _ => {
+            return Err(Problem::not_found(format!(
+                "No such endpoint: {}",
+                request.path
+            )))
+        }
+    };
+    if method != allowed {
+        return Ok(Problem::new(
+            405,
+            "Method not allowed",
+            format!("{} only accepts {allowed}", request.path),
+        )
+        .into_response()
+        .with_header("Allow", allowed));
+    }
+
+    match segments.as_slice() {
+        ["api", "cameras"] => Ok(Response::json(200, JSON, &list_cameras(ctx)?)),
+        ["api", "cameras", id, "controls"] => {
+            let device = find_camera(ctx, id)?;
+            let controls = lookup_controls(ctx.backend(), ctx.store(), &device.id, None, 0)
+                .map_err(|e| Problem::camera_error(AppError::from(e)))?;
+            Ok(Response::json(200, JSON, &controls.controls))
+        }
+        ["api", "cameras", id, "controls", control] => {
+            let device = find_camera(ctx, id)?;
+            let control = apply::parse_control_id(control).map_err(Problem::not_found)?;
+            let update: ControlUpdate = serde_json::from_slice(&request.body)
+                .map_err(|e| Problem::bad_request(format!("Invalid request body: {e}")))?;
+            set_control(ctx, &device, control, update)
+        }
+        ["api", "cameras", id, "presets", name, "apply"] => {
+            let device = find_camera(ctx, id)?;
+            let preset_id = ctx
+                .store()
+                .find_preset_id(name)
+                .ok_or_else(|| Problem::not_found(format!("No preset named '{name}'")))?;
+            let controls_written = ctx
+                .apply_preset(device.id.as_str(), &preset_id, &device.name)
+                .await
+                .map_err(Problem::not_applied)?
+                .applied
+                .len();
+            Ok(Response::json(
+                200,
+                JSON,
+                &PresetApplied {
+                    preset_id,
+                    controls_written,
+                },
+            ))
+        }
+        _ => unreachable!("routes are matched above"),
+    }
+}
+
+/// Connected cameras, as the `list_cameras` command returns them.
+fn list_cameras<C: ControlContext>(ctx: &C) -> Result<Vec<CameraDevice>, Problem> {
+    ctx.backend()
+        .enumerate_devices()
+        .map(group_siblings)
+        .map_err(|e| Problem::camera_error(AppError::from(e)))
+}
+
+fn find_camera<C: ControlContext>(ctx: &C, id: &str) -> Result<CameraDevice, Problem> {
+    let device_id = DeviceId::new(id);
+    list_cameras(ctx)?
+        .into_iter()
+        .find(|d| d.id == device_id)
+        .ok_or_else(|| Problem::not_found(format!("No connected camera with ID '{id}'")))
+}
+
+fn set_control<C: ControlContext>(
+    ctx: &C,
+    device: &CameraDevice,
+    control: ControlId,
+    update: ControlUpdate,
+) -> Result<Response, Problem> {
+    if update.value.is_none() && update.auto.is_none() {
+        return Err(Problem::bad_request(
+            "Request body needs a 'value', an 'auto' flag or both",
+        ));
+    }
+    let device_id = device.id.as_str();
+    if let Some(auto) = update.auto {
+        write_control_auto(
+            ctx.backend(),
+            ctx.store(),
+            device_id,
+            &device.name,
+            control,
+            auto,
+        )
+        .map_err(Problem::not_applied)?;
+    }
+    if let Some(value) = update.value {
+        write_control(
+            ctx.backend(),
+            ctx.store(),
+            ctx.latency(),
+            device_id,
+            &device.name,
+            control,
+            value,
+        )
+        .map_err(Problem::not_applied)?;
+    }
+
+    let descriptor =
+        find_descriptor(ctx.backend(), &device.id, &control).map_err(Problem::camera_error)?;
+    Ok(Response::json(200, JSON, &descriptor))
+}
+
+/// Accept connections on `listener` until the returned future is dropped,
+/// answering each with [`handle`].
+pub async fn serve<C: ControlContext>(listener: TcpListener, ctx: Arc<C>, token: Arc<str>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("Control API failed to accept a connection: {e}");
+                continue;
+            }
+        };
+        let ctx = Arc::clone(&ctx);
+        let token = Arc::clone(&token);
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, &*ctx, &token).await {
+                tracing::debug!("Control API connection from {peer} failed: {e}");
+            }
+        });
+    }
+}
+
+async fn serve_connection<C: ControlContext>(
+    stream: TcpStream,
+    ctx: &C,
+    token: &str,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let response = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut reader)).await {
+        Ok(Ok(request)) => handle(ctx, token, &request).await,
+        Ok(Err(ReadError { status, detail })) => {
+            Problem::new(status, "Unreadable request", detail).into_response()
+        }
+        Err(_) => {
+            Problem::new(408, "Request timeout", "Request took too long to arrive").into_response()
+        }
+    };
+    write_response(&mut writer, &response).await
+}
+
+/// Bind the API's listener on the loopback interface.
+pub async fn bind(port: u16) -> Result<TcpListener, String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Couldn't listen on {addr}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::dummy::DummyBackend;
+    use crate::preset::types::Preset;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+    struct TestContext {
+        backend: DummyBackend,
+        store: SettingsStore,
+        latency: ControlLatencyState,
+        _dir: TempDir,
+    }
+
+    impl ControlContext for TestContext {
+        fn backend(&self) -> &dyn CameraBackend {
+            &self.backend
+        }
+        fn store(&self) -> &SettingsStore {
+            &self.store
+        }
+        fn latency(&self) -> &ControlLatencyState {
+            &self.latency
+        }
+    }
+
+    /// Serve a fresh dummy-backed context on an ephemeral port.
+    async fn start() -> (SocketAddr, Arc<TestContext>) {
+        let dir = TempDir::new().unwrap();
+        let ctx = Arc::new(TestContext {
+            backend: DummyBackend::new(),
+            store: SettingsStore::new(dir.path().join("cameras.json")),
+            latency: ControlLatencyState::default(),
+            _dir: dir,
+        });
+        let listener = bind(0).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::clone(&ctx), Arc::from(TOKEN)));
+        (addr, ctx)
+    }
+
+    /// Send a raw request and return the status, headers and parsed body.
+    async fn send(addr: SocketAddr, raw: String) -> (u16, String, Value) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(raw.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse().unwrap();
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(body).unwrap()
+        };
+        (status, head.to_string(), body)
+    }
+
+    async fn request(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> (u16, String, Value) {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        send(
+            addr,
+            format!(
+                "{method} {path} HTTP/1.1\r\nHost: localhost\r\n\
+                 Authorization: Bearer {TOKEN}\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+        )
+        .await
+    }
+
+    fn camera_path(rest: &str) -> String {
+        format!("/api/cameras/{}{rest}", DummyBackend::device_id())
+    }
+
+    #[tokio::test]
+    async fn lists_cameras() {
+        let (addr, _ctx) = start().await;
+        let (status, head, body) = request(addr, "GET", "/api/cameras", None).await;
+        assert_eq!(status, 200);
+        assert!(head.contains("Content-Type: application/json"));
+        assert_eq!(body[0]["id"], DummyBackend::device_id().as_str());
+        assert_eq!(body[0]["name"], "Dummy Test Camera");
+    }
+
+    #[tokio::test]
+    async fn lists_controls() {
+        let (addr, _ctx) = start().await;
+        let (status, _, body) = request(addr, "GET", &camera_path("/controls"), None).await;
+        assert_eq!(status, 200);
+        let brightness = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == "brightness")
+            .unwrap();
+        assert_eq!(brightness["current"], 128);
+    }
+
+    #[tokio::test]
+    async fn sets_a_control_through_the_shared_write_path() {
+        let (addr, ctx) = start().await;
+        let (status, _, body) = request(
+            addr,
+            "PUT",
+            &camera_path("/controls/brightness"),
+            Some(json!({ "value": 300 })),
+        )
+        .await;
+        // Clamped to the range and saved, like a write from the UI
+        assert_eq!(status, 200);
+        assert_eq!(body["current"], 255);
+        let saved = ctx
+            .store
+            .get_camera(DummyBackend::device_id().as_str())
+            .unwrap();
+        assert_eq!(saved.name, "Dummy Test Camera");
+        assert_eq!(saved.controls["brightness"].value, 255);
+        assert_eq!(
+            ctx.latency
+                .stats_for_device(DummyBackend::device_id().as_str())
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn applies_a_preset_by_name() {
+        let (addr, ctx) = start().await;
+        ctx.store.save_preset(
+            "p1",
+            Preset {
+                name: "Evening light".to_string(),
+                controls: HashMap::from([("contrast".to_string(), 70)]),
+                ..Preset::default()
+            },
+        );
+        let (status, _, body) = request(
+            addr,
+            "POST",
+            &camera_path("/presets/evening%20light/apply"),
+            None,
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(body, json!({ "presetId": "p1", "controlsWritten": 1 }));
+        let contrast = ctx
+            .backend
+            .get_control(&DummyBackend::device_id(), &ControlId::Contrast)
+            .unwrap();
+        assert_eq!(contrast.value(), 70);
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_token_are_rejected() {
+        let (addr, ctx) = start().await;
+        let (status, head, body) = send(
+            addr,
+            "GET /api/cameras HTTP/1.1\r\nHost: localhost\r\n\r\n".to_string(),
+        )
+        .await;
+        assert_eq!(status, 401);
+        assert!(head.contains("WWW-Authenticate: Bearer"));
+        assert!(head.contains("Content-Type: application/problem+json"));
+        assert_eq!(body["status"], 401);
+
+        let wrong = send(
+            addr,
+            format!(
+                "PUT {} HTTP/1.1\r\nAuthorization: Bearer {}\r\n\
+                 Content-Length: 13\r\n\r\n{{\"value\":200}}",
+                camera_path("/controls/brightness"),
+                TOKEN.replace('0', "1")
+            ),
+        )
+        .await;
+        assert_eq!(wrong.0, 401);
+        // Nothing was written
+        assert!(ctx
+            .store
+            .get_camera(DummyBackend::device_id().as_str())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_and_methods_are_reported() {
+        let (addr, _ctx) = start().await;
+        let (status, _, body) = request(addr, "GET", "/api/lights", None).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["type"], "about:blank");
+
+        let (status, head, _) = request(addr, "DELETE", "/api/cameras", None).await;
+        assert_eq!(status, 405);
+        assert!(head.contains("Allow: GET"));
+    }
+
+    #[tokio::test]
+    async fn lookups_that_miss_are_not_found() {
+        let (addr, _ctx) = start().await;
+        let (status, _, body) = request(addr, "GET", "/api/cameras/nope/controls", None).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["detail"], "No connected camera with ID 'nope'");
+
+        let (status, _, body) = request(
+            addr,
+            "PUT",
+            &camera_path("/controls/warp_speed"),
+            Some(json!({ "value": 1 })),
+        )
+        .await;
+        assert_eq!(status, 404);
+        assert_eq!(body["detail"], "Unknown control: 'warp_speed'");
+
+        let (status, _, _) =
+            request(addr, "POST", &camera_path("/presets/missing/apply"), None).await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn bad_bodies_are_bad_requests() {
+        let (addr, _ctx) = start().await;
+        let path = camera_path("/controls/brightness");
+        for body in [json!({}), json!({ "value": "high" }), json!({ "level": 3 })] {
+            let (status, _, problem) = request(addr, "PUT", &path, Some(body.clone())).await;
+            assert_eq!(status, 400, "body {body}");
+            assert_eq!(problem["title"], "Bad request");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_changes_carry_the_humanised_error() {
+        let (addr, _ctx) = start().await;
+        // The dummy camera has no automatic modes
+        let (status, head, body) = request(
+            addr,
+            "PUT",
+            &camera_path("/controls/brightness"),
+            Some(json!({ "auto": true })),
+        )
+        .await;
+        assert_eq!(status, 422);
+        assert!(head.contains("application/problem+json"));
+        assert_eq!(body["detail"], "Control 'Brightness' has no automatic mode");
+
+        // Focus isn't simulated at all
+        let (status, _, body) = request(
+            addr,
+            "PUT",
+            &camera_path("/controls/focus"),
+            Some(json!({ "value": 10 })),
+        )
+        .await;
+        assert_eq!(status, 422);
+        assert_eq!(
+            body["detail"],
+            "Control 'Focus' not supported on this device"
+        );
+    }
+
+    #[tokio::test]
+    async fn unreadable_requests_get_a_problem_document() {
+        let (addr, _ctx) = start().await;
+        let (status, _, body) = send(addr, "NONSENSE\r\n\r\n".to_string()).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["title"], "Unreadable request");
+    }
+
+    #[test]
+    fn token_comparison_needs_an_exact_match() {
+        assert!(tokens_match(TOKEN, TOKEN));
+        assert!(!tokens_match(&TOKEN[..31], TOKEN));
+        assert!(!tokens_match(&TOKEN.to_uppercase(), TOKEN));
+        assert!(!tokens_match("", TOKEN));
+    }
+
+    #[test]
+    fn generated_tokens_are_long_and_distinct() {
+        let a = generate_token();
+        let b = generate_token();
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,347 @@
+// Just enough HTTP/1.1 for the localhost control API: one request per
+// connection, `Content-Length` bodies only, no keep-alive or chunking.
+// Home-automation clients send small JSON requests, so the limits are tight.
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest request line plus headers accepted, in bytes.
+pub const MAX_HEAD_BYTES: usize = 8 * 1024;
+
+/// Largest request body accepted, in bytes.
+pub const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// A parsed request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// Request target without the query string, still percent-encoded.
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The first header named `name` (lowercase).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The path split on `/` with each segment percent-decoded. Empty
+    /// segments are dropped, so `/api/cameras/` and `/api/cameras` match.
+    pub fn segments(&self) -> Result<Vec<String>, String> {
+        self.path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect()
+    }
+}
+
+/// A request that couldn't be read, with the status to answer it with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadError {
+    pub status: u16,
+    pub detail: String,
+}
+
+impl ReadError {
+    fn bad_request(detail: impl Into<String>) -> Self {
+        Self {
+            status: 400,
+            detail: detail.into(),
+        }
+    }
+
+    fn too_large(detail: impl Into<String>) -> Self {
+        Self {
+            status: 413,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Read one request from `reader`.
+pub async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Request, ReadError> {
+    let mut head_bytes = 0;
+    let request_line = read_line(reader, &mut head_bytes).await?;
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ReadError::bad_request("Malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(ReadError::bad_request(format!(
+            "Unsupported protocol '{version}'"
+        )));
+    }
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader, &mut head_bytes).await?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| ReadError::bad_request("Malformed header"))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let mut request = Request {
+        method: method.to_string(),
+        path: target.split('?').next().unwrap_or_default().to_string(),
+        headers,
+        body: Vec::new(),
+    };
+    if request.header("transfer-encoding").is_some() {
+        return Err(ReadError::bad_request(
+            "Chunked request bodies aren't supported; send Content-Length",
+        ));
+    }
+    let length = match request.header("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| ReadError::bad_request("Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(ReadError::too_large(format!(
+            "Request body is over the {MAX_BODY_BYTES} byte limit"
+        )));
+    }
+    request.body = vec![0; length];
+    reader
+        .read_exact(&mut request.body)
+        .await
+        .map_err(|_| ReadError::bad_request("Request body ended early"))?;
+    Ok(request)
+}
+
+/// Read a CRLF- or LF-terminated line, counting it against the head limit.
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    head_bytes: &mut usize,
+) -> Result<String, ReadError> {
+    let mut line = Vec::new();
+    let limit = (MAX_HEAD_BYTES - *head_bytes + 1) as u64;
+    let read = (&mut *reader)
+        .take(limit)
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|e| ReadError::bad_request(e.to_string()))?;
+    *head_bytes += read;
+    if *head_bytes > MAX_HEAD_BYTES {
+        return Err(ReadError::too_large(format!(
+            "Request headers are over the {MAX_HEAD_BYTES} byte limit"
+        )));
+    }
+    if line.pop() != Some(b'\n') {
+        return Err(ReadError::bad_request("Request ended early"));
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line).map_err(|_| ReadError::bad_request("Request head isn't UTF-8"))
+}
+
+/// Decode `%XX` escapes in a path segment.
+fn percent_decode(segment: &str) -> Result<String, String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("Invalid escape in path segment '{segment}'"))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("Path segment '{segment}' isn't UTF-8"))
+}
+
+/// A response to write back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// A response with `body` serialised as JSON under `content_type`.
+    pub fn json(status: u16, content_type: &str, body: &impl serde::Serialize) -> Self {
+        match serde_json::to_vec(body) {
+            Ok(body) => Self {
+                status,
+                headers: vec![("Content-Type", content_type.to_string())],
+                body,
+            },
+            Err(e) => {
+                tracing::warn!("Failed to serialise response: {e}");
+                Self {
+                    status: 500,
+                    headers: Vec::new(),
+                    body: Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// Add a header.
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+/// Standard reason phrase for the statuses the API uses.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Content Too Large",
+        422 => "Unprocessable Content",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Write `response` and close the exchange.
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &Response,
+) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&response.body).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(raw: &str) -> Result<Request, ReadError> {
+        read_request(&mut raw.as_bytes()).await
+    }
+
+    #[tokio::test]
+    async fn reads_a_request_with_a_body() {
+        let request = parse(
+            "PUT /api/cameras/a/controls/brightness?x=1 HTTP/1.1\r\n\
+             Host: localhost\r\nContent-Length: 13\r\n\r\n{\"value\":100}",
+        )
+        .await
+        .unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/api/cameras/a/controls/brightness");
+        assert_eq!(request.header("host"), Some("localhost"));
+        assert_eq!(request.body, b"{\"value\":100}");
+    }
+
+    #[tokio::test]
+    async fn accepts_bare_line_feeds() {
+        let request = parse("GET /api/cameras HTTP/1.1\nHost: x\n\n")
+            .await
+            .unwrap();
+        assert_eq!(request.header("host"), Some("x"));
+        assert!(request.body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_requests() {
+        assert_eq!(parse("GET\r\n\r\n").await.unwrap_err().status, 400);
+        assert_eq!(parse("GET / SPDY/3\r\n\r\n").await.unwrap_err().status, 400);
+        assert_eq!(
+            parse("GET / HTTP/1.1\r\nno colon\r\n\r\n")
+                .await
+                .unwrap_err()
+                .status,
+            400
+        );
+        // Body shorter than announced
+        assert_eq!(
+            parse("PUT / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}")
+                .await
+                .unwrap_err()
+                .status,
+            400
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_heads_and_bodies() {
+        let long_header = format!(
+            "GET / HTTP/1.1\r\nX-Filler: {}\r\n\r\n",
+            "a".repeat(MAX_HEAD_BYTES)
+        );
+        assert_eq!(parse(&long_header).await.unwrap_err().status, 413);
+
+        let big_body = format!(
+            "PUT / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert_eq!(parse(&big_body).await.unwrap_err().status, 413);
+    }
+
+    #[test]
+    fn segments_are_percent_decoded() {
+        let request = Request {
+            method: "GET".to_string(),
+            path: "/api/cameras/usb%23vid%3A046d//controls/".to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+        assert_eq!(
+            request.segments().unwrap(),
+            vec!["api", "cameras", "usb#vid:046d", "controls"]
+        );
+    }
+
+    #[test]
+    fn bad_escapes_are_rejected() {
+        assert!(percent_decode("%zz").is_err());
+        assert!(percent_decode("abc%4").is_err());
+        assert!(percent_decode("%ff").is_err());
+        assert_eq!(percent_decode("Evening%20light").unwrap(), "Evening light");
+    }
+
+    #[tokio::test]
+    async fn responses_carry_length_and_close() {
+        let response = Response::json(200, "application/json", &serde_json::json!({ "a": 1 }))
+            .with_header("Allow", "GET");
+        let mut out = Vec::new();
+        write_response(&mut out, &response).await.unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Allow: GET\r\n"));
+        assert!(text.contains("Content-Length: 7\r\n"));
+        assert!(text.contains("Connection: close\r\n"));
+        assert!(text.ends_with("\r\n\r\n{\"a\":1}"));
+    }
+}
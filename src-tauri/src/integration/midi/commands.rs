@@ -0,0 +1,341 @@
+// Tauri commands and the background listener for MIDI control surfaces.
+//
+// Without the `midi` feature the mapping commands still work, but there's
+// no input to open, so listing inputs and learning report that MIDI isn't
+// built in.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use super::mapping::{LearnTarget, MidiLearn, MidiMapping, CC_MAX};
+use crate::camera::commands::CameraState;
+use crate::camera::types::DeviceId;
+use crate::error::{code, AppError};
+use crate::settings::apply::{find_descriptor, parse_control_id};
+use crate::settings::commands::SettingsState;
+
+#[cfg(feature = "midi")]
+use super::port::{self, MidiConnection};
+
+const NOT_BUILT: &str = "MIDI support isn't built into this version";
+
+/// A failure talking to MIDI inputs, which without the `midi` feature is
+/// always [`NOT_BUILT`].
+fn midi_error(message: String) -> AppError {
+    let code = if cfg!(feature = "midi") {
+        code::INTEGRATION_FAILED
+    } else {
+        code::UNSUPPORTED
+    };
+    AppError::new(code, message)
+}
+
+/// The open MIDI input and learn mode.
+#[derive(Default)]
+pub struct MidiState {
+    #[cfg(feature = "midi")]
+    connection: Mutex<Option<MidiConnection>>,
+    learn: Mutex<MidiLearn>,
+}
+
+impl MidiState {
+    /// Name of the open input, if any.
+    #[cfg(feature = "midi")]
+    fn connected_input(&self) -> Option<String> {
+        self.connection.lock().as_ref().map(|c| c.name.clone())
+    }
+
+    #[cfg(not(feature = "midi"))]
+    fn connected_input(&self) -> Option<String> {
+        None
+    }
+}
+
+/// MIDI settings as shown in the settings window.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MidiStatus {
+    /// Whether this build can open MIDI inputs.
+    pub available: bool,
+    /// The chosen input, connected or not.
+    pub input: Option<String>,
+    pub connected: bool,
+    /// The control learn mode is waiting to map, if any.
+    pub learning: Option<LearnTarget>,
+    pub mappings: Vec<MidiMapping>,
+}
+
+fn status(app: &AppHandle) -> MidiStatus {
+    let store = &app.state::<SettingsState>().store;
+    let midi = app.state::<MidiState>();
+    let learning = match &*midi.learn.lock() {
+        MidiLearn::Waiting(target) => Some(target.clone()),
+        MidiLearn::Idle => None,
+    };
+    MidiStatus {
+        available: cfg!(feature = "midi"),
+        input: store.midi_input(),
+        connected: midi.connected_input().is_some(),
+        learning,
+        mappings: store.midi_mappings(),
+    }
+}
+
+/// Open the MIDI input `name` and start listening to it, closing any input
+/// already open.
+#[cfg(feature = "midi")]
+pub fn open_midi_input(app: &AppHandle, name: &str) -> Result<(), String> {
+    close_midi_input(app);
+    let (sender, changes) = std::sync::mpsc::channel();
+    let connection = port::open(name, sender)?;
+    let handle = app.clone();
+    std::thread::Builder::new()
+        .name("midi-listener".to_string())
+        .spawn(move || listener::run(&handle, changes))
+        .map_err(|e| format!("Couldn't start the MIDI listener: {e}"))?;
+    tracing::info!("Listening to MIDI input '{name}'");
+    *app.state::<MidiState>().connection.lock() = Some(connection);
+    Ok(())
+}
+
+#[cfg(not(feature = "midi"))]
+pub fn open_midi_input(_app: &AppHandle, _name: &str) -> Result<(), String> {
+    Err(NOT_BUILT.to_string())
+}
+
+/// Close the open MIDI input, if any. Its listener exits once the port has
+/// closed.
+#[cfg(feature = "midi")]
+pub fn close_midi_input(app: &AppHandle) {
+    if let Some(connection) = app.state::<MidiState>().connection.lock().take() {
+        tracing::info!("Closed MIDI input '{}'", connection.name);
+    }
+}
+
+#[cfg(not(feature = "midi"))]
+pub fn close_midi_input(_app: &AppHandle) {}
+
+#[cfg(feature = "midi")]
+mod listener {
+    use std::sync::mpsc::{Receiver, RecvTimeoutError};
+    use std::time::Instant;
+
+    use tauri::{AppHandle, Emitter, Manager};
+
+    use super::{CameraState, MidiState};
+    use crate::diagnostics::control_latency::ControlLatencyState;
+    use crate::integration::midi::mapping::{
+        find_mapping, ControlChange, ControlKey, WriteThrottle,
+    };
+    use crate::settings::apply::{parse_control_id, write_control};
+    use crate::settings::commands::SettingsState;
+
+    /// Act on control changes until the input closes: learn mappings while
+    /// learn mode waits, otherwise write mapped controls through the
+    /// throttle.
+    pub(super) fn run(app: &AppHandle, changes: Receiver<ControlChange>) {
+        let mut throttle = WriteThrottle::default();
+        loop {
+            let received = match throttle.next_due(Instant::now()) {
+                Some(wait) => changes.recv_timeout(wait),
+                None => changes.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(change) => on_change(app, &mut throttle, change),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            for (key, value) in throttle.due(Instant::now()) {
+                write(app, &key, value);
+            }
+        }
+        tracing::debug!("MIDI listener stopped");
+    }
+
+    fn on_change(app: &AppHandle, throttle: &mut WriteThrottle, change: ControlChange) {
+        let store = &app.state::<SettingsState>().store;
+        let learned = app
+            .state::<MidiState>()
+            .learn
+            .lock()
+            .on_control_change(change);
+        if let Some(mapping) = learned {
+            tracing::info!(
+                "Mapped MIDI channel {} CC {} to {} on {}",
+                mapping.midi_channel + 1,
+                mapping.cc_number,
+                mapping.control_id,
+                mapping.device_id
+            );
+            store.set_midi_mapping(mapping.clone());
+            let _ = app.emit("midi-learned", mapping);
+            return;
+        }
+
+        let mappings = store.midi_mappings();
+        let Some(mapping) = find_mapping(&mappings, change.channel, change.cc) else {
+            return;
+        };
+        let key = (mapping.device_id.clone(), mapping.control_id.clone());
+        let value = mapping.scale(change.value);
+        if let Some(value) = throttle.offer(key.clone(), value, Instant::now()) {
+            write(app, &key, value);
+        }
+    }
+
+    fn write(app: &AppHandle, (device_id, control_id): &ControlKey, value: i32) {
+        let store = &app.state::<SettingsState>().store;
+        let camera_name = store
+            .get_camera(device_id)
+            .map(|camera| camera.name)
+            .unwrap_or_else(|| device_id.clone());
+        let result = parse_control_id(control_id).and_then(|control| {
+            write_control(
+                &app.state::<CameraState>().backend,
+                store,
+                &app.state::<ControlLatencyState>(),
+                device_id,
+                &camera_name,
+                control,
+                value,
+            )
+        });
+        match result {
+            Ok(written) => {
+                let _ = app.emit(
+                    "midi-control-changed",
+                    serde_json::json!({
+                        "deviceId": device_id,
+                        "controlId": control_id,
+                        "value": written.value.value(),
+                    }),
+                );
+            }
+            Err(e) => tracing::warn!("MIDI write of {control_id} on {device_id} failed: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "midi")]
+fn input_names() -> Result<Vec<String>, String> {
+    port::input_names()
+}
+
+#[cfg(not(feature = "midi"))]
+fn input_names() -> Result<Vec<String>, String> {
+    Err(NOT_BUILT.to_string())
+}
+
+/// Names of the MIDI inputs currently available.
+#[tauri::command]
+pub async fn list_midi_inputs() -> Result<Vec<String>, AppError> {
+    input_names().map_err(midi_error)
+}
+
+/// The chosen MIDI input, whether it's connected, learn mode and the
+/// mapping table.
+#[tauri::command]
+pub async fn get_midi(app: AppHandle) -> Result<MidiStatus, AppError> {
+    Ok(status(&app))
+}
+
+/// Choose the MIDI input to listen to, or stop listening with `None`.
+///
+/// The choice is saved even if the input fails to open.
+#[tauri::command]
+pub async fn set_midi_input(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+    name: Option<String>,
+) -> Result<MidiStatus, AppError> {
+    settings_state.store.set_midi_input(name.as_deref());
+    match name {
+        Some(name) => open_midi_input(&app, &name).map_err(midi_error)?,
+        None => close_midi_input(&app),
+    }
+    Ok(status(&app))
+}
+
+/// Map a knob or fader to a camera control, replacing any mapping on the
+/// same channel and CC. Returns the updated mapping table.
+#[tauri::command]
+pub async fn set_midi_mapping(
+    settings_state: State<'_, SettingsState>,
+    mapping: MidiMapping,
+) -> Result<Vec<MidiMapping>, AppError> {
+    if mapping.midi_channel > 15 {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("MIDI channel must be 0–15, got {}", mapping.midi_channel),
+        ));
+    }
+    if mapping.cc_number > CC_MAX {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("CC number must be 0–{CC_MAX}, got {}", mapping.cc_number),
+        ));
+    }
+    parse_control_id(&mapping.control_id)?;
+    settings_state.store.set_midi_mapping(mapping);
+    Ok(settings_state.store.midi_mappings())
+}
+
+/// Remove the mapping on a channel and CC. Returns the updated mapping
+/// table.
+#[tauri::command]
+pub async fn remove_midi_mapping(
+    settings_state: State<'_, SettingsState>,
+    midi_channel: u8,
+    cc_number: u8,
+) -> Result<Vec<MidiMapping>, AppError> {
+    settings_state
+        .store
+        .remove_midi_mapping(midi_channel, cc_number);
+    Ok(settings_state.store.midi_mappings())
+}
+
+/// Map the next knob or fader moved to a control, over the control's full
+/// range. The mapping is saved and announced with a `midi-learned` event.
+#[tauri::command]
+pub async fn start_midi_learn(
+    state: State<'_, CameraState>,
+    midi_state: State<'_, MidiState>,
+    device_id: String,
+    control_id: String,
+) -> Result<LearnTarget, AppError> {
+    if midi_state.connected_input().is_none() {
+        let reason = if cfg!(feature = "midi") {
+            "Choose a MIDI input before learning a mapping"
+        } else {
+            NOT_BUILT
+        };
+        return Err(midi_error(reason.to_string()));
+    }
+    let control = parse_control_id(&control_id)?;
+    let desc = find_descriptor(&state.backend, &DeviceId::new(&device_id), &control)?;
+    let (Some(min), Some(max)) = (desc.min, desc.max) else {
+        return Err(AppError::new(
+            code::CONTROL_UNAVAILABLE,
+            format!(
+                "'{}' has no range to map a knob onto",
+                control.display_name()
+            ),
+        ));
+    };
+    let target = LearnTarget {
+        device_id,
+        control_id,
+        min,
+        max,
+    };
+    midi_state.learn.lock().start(target.clone());
+    Ok(target)
+}
+
+/// Leave learn mode without mapping anything. Returns whether it was
+/// waiting.
+#[tauri::command]
+pub async fn cancel_midi_learn(midi_state: State<'_, MidiState>) -> Result<bool, AppError> {
+    Ok(midi_state.learn.lock().cancel())
+}
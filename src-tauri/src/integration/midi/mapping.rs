@@ -0,0 +1,463 @@
+// Turning MIDI control changes into camera control writes.
+//
+// Everything here is pure: message parsing, the mapping table, scaling a
+// 7-bit CC value into a control's range, learn mode and the per-control
+// throttle. Port I/O lives in `port`, behind the `midi` feature.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Largest value a control change carries.
+pub const CC_MAX: u8 = 127;
+
+/// Shortest gap between writes to one control. A knob turned quickly sends
+/// dozens of changes a second; only the latest in each interval is written.
+pub const WRITE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A control change (CC) message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlChange {
+    /// Channel, 0–15.
+    pub channel: u8,
+    /// Controller number, 0–127.
+    pub cc: u8,
+    /// Value, 0–127.
+    pub value: u8,
+}
+
+/// Parse a raw MIDI message, returning `None` for anything that isn't a
+/// control change.
+pub fn parse_control_change(message: &[u8]) -> Option<ControlChange> {
+    match *message {
+        [status, cc, value, ..] if status & 0xF0 == 0xB0 && cc <= CC_MAX && value <= CC_MAX => {
+            Some(ControlChange {
+                channel: status & 0x0F,
+                cc,
+                value,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A knob or fader mapped to a camera control.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MidiMapping {
+    pub midi_channel: u8,
+    pub cc_number: u8,
+    pub device_id: String,
+    pub control_id: String,
+    /// Control value at CC 0.
+    pub min: i32,
+    /// Control value at CC 127. May be below `min` to turn the knob around.
+    pub max: i32,
+}
+
+impl MidiMapping {
+    /// Whether this mapping listens to `channel` and `cc`.
+    pub fn matches(&self, channel: u8, cc: u8) -> bool {
+        self.midi_channel == channel && self.cc_number == cc
+    }
+
+    /// The control value for a CC value. See [`scale_cc`].
+    pub fn scale(&self, value: u8) -> i32 {
+        scale_cc(value, self.min, self.max)
+    }
+}
+
+/// The mapping for `channel` and `cc`, if there is one.
+pub fn find_mapping(mappings: &[MidiMapping], channel: u8, cc: u8) -> Option<&MidiMapping> {
+    mappings.iter().find(|m| m.matches(channel, cc))
+}
+
+/// Add `mapping`, replacing any mapping on the same channel and CC so one
+/// knob never drives two controls.
+pub fn upsert_mapping(mappings: &mut Vec<MidiMapping>, mapping: MidiMapping) {
+    mappings.retain(|m| !m.matches(mapping.midi_channel, mapping.cc_number));
+    mappings.push(mapping);
+}
+
+/// Scale a CC value linearly from 0–127 onto `min`–`max`, rounding to the
+/// nearest integer. Values above 127 count as 127.
+pub fn scale_cc(value: u8, min: i32, max: i32) -> i32 {
+    let value = i64::from(value.min(CC_MAX));
+    let span = i64::from(max) - i64::from(min);
+    let cc_max = i64::from(CC_MAX);
+    // Round half away from zero, so inverted ranges mirror normal ones
+    let offset = (2 * span * value + span.signum() * cc_max) / (2 * cc_max);
+    (i64::from(min) + offset) as i32
+}
+
+/// The control learn mode is waiting to map.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearnTarget {
+    pub device_id: String,
+    pub control_id: String,
+    pub min: i32,
+    pub max: i32,
+}
+
+/// Learn mode: the next control change received is mapped to a waiting
+/// target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MidiLearn {
+    #[default]
+    Idle,
+    Waiting(LearnTarget),
+}
+
+impl MidiLearn {
+    /// Wait for a control change to map to `target`, replacing any target
+    /// already waiting.
+    pub fn start(&mut self, target: LearnTarget) {
+        *self = Self::Waiting(target);
+    }
+
+    /// Stop waiting. Returns whether a target was waiting.
+    pub fn cancel(&mut self) -> bool {
+        matches!(std::mem::take(self), Self::Waiting(_))
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        matches!(self, Self::Waiting(_))
+    }
+
+    /// Offer a control change. While waiting, it becomes the returned
+    /// mapping and learn mode goes back to idle; otherwise it's ignored.
+    pub fn on_control_change(&mut self, change: ControlChange) -> Option<MidiMapping> {
+        let Self::Waiting(target) = std::mem::take(self) else {
+            return None;
+        };
+        Some(MidiMapping {
+            midi_channel: change.channel,
+            cc_number: change.cc,
+            device_id: target.device_id,
+            control_id: target.control_id,
+            min: target.min,
+            max: target.max,
+        })
+    }
+}
+
+/// Identifies a control for throttling: device ID and control ID.
+pub type ControlKey = (String, String);
+
+/// Limits writes to each control to one per interval, keeping the latest
+/// value seen in between so the control always ends where the knob stopped.
+#[derive(Debug)]
+pub struct WriteThrottle {
+    interval: Duration,
+    last_write: HashMap<ControlKey, Instant>,
+    pending: HashMap<ControlKey, i32>,
+}
+
+impl WriteThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_write: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Offer a value at `now`. Returns it if it should be written straight
+    /// away; otherwise it's held until [`due`](Self::due) releases it.
+    pub fn offer(&mut self, key: ControlKey, value: i32, now: Instant) -> Option<i32> {
+        let ready = self
+            .last_write
+            .get(&key)
+            .map_or(true, |&last| now.duration_since(last) >= self.interval);
+        if ready {
+            self.pending.remove(&key);
+            self.last_write.insert(key, now);
+            Some(value)
+        } else {
+            self.pending.insert(key, value);
+            None
+        }
+    }
+
+    /// Held values whose interval has passed by `now`, marked as written.
+    pub fn due(&mut self, now: Instant) -> Vec<(ControlKey, i32)> {
+        let interval = self.interval;
+        let last_write = &self.last_write;
+        let ready: Vec<ControlKey> = self
+            .pending
+            .keys()
+            .filter(|key| {
+                last_write
+                    .get(*key)
+                    .map_or(true, |&last| now.duration_since(last) >= interval)
+            })
+            .cloned()
+            .collect();
+        ready
+            .into_iter()
+            .map(|key| {
+                let value = self.pending.remove(&key).expect("key comes from pending");
+                self.last_write.insert(key.clone(), now);
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// How long until the next held value is due, if any are held.
+    pub fn next_due(&self, now: Instant) -> Option<Duration> {
+        self.pending
+            .keys()
+            .map(|key| {
+                self.last_write.get(key).map_or(Duration::ZERO, |&last| {
+                    self.interval.saturating_sub(now.duration_since(last))
+                })
+            })
+            .min()
+    }
+}
+
+impl Default for WriteThrottle {
+    fn default() -> Self {
+        Self::new(WRITE_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(channel: u8, cc: u8, control_id: &str) -> MidiMapping {
+        MidiMapping {
+            midi_channel: channel,
+            cc_number: cc,
+            device_id: "cam-1".to_string(),
+            control_id: control_id.to_string(),
+            min: 0,
+            max: 255,
+        }
+    }
+
+    fn key(control_id: &str) -> ControlKey {
+        ("cam-1".to_string(), control_id.to_string())
+    }
+
+    // --- Parsing ---
+
+    #[test]
+    fn parses_control_changes_on_any_channel() {
+        assert_eq!(
+            parse_control_change(&[0xB0, 7, 100]),
+            Some(ControlChange {
+                channel: 0,
+                cc: 7,
+                value: 100
+            })
+        );
+        assert_eq!(parse_control_change(&[0xBF, 1, 0]).unwrap().channel, 15);
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        // Note on, pitch bend, clock, truncated CC, data byte out of range
+        assert!(parse_control_change(&[0x90, 60, 100]).is_none());
+        assert!(parse_control_change(&[0xE0, 0, 64]).is_none());
+        assert!(parse_control_change(&[0xF8]).is_none());
+        assert!(parse_control_change(&[0xB0, 7]).is_none());
+        assert!(parse_control_change(&[0xB0, 0x80, 1]).is_none());
+        assert!(parse_control_change(&[]).is_none());
+    }
+
+    // --- Scaling ---
+
+    #[test]
+    fn scale_hits_both_ends_of_the_range() {
+        assert_eq!(scale_cc(0, 0, 255), 0);
+        assert_eq!(scale_cc(127, 0, 255), 255);
+        assert_eq!(scale_cc(0, -13, -1), -13);
+        assert_eq!(scale_cc(127, -13, -1), -1);
+    }
+
+    #[test]
+    fn scale_rounds_to_the_nearest_value() {
+        // 64 / 127 * 255 = 128.5
+        assert_eq!(scale_cc(64, 0, 255), 129);
+        assert_eq!(scale_cc(63, 0, 255), 126);
+        // Narrower than the CC range: several CC values share a step
+        assert_eq!(scale_cc(1, 0, 10), 0);
+        assert_eq!(scale_cc(7, 0, 10), 1);
+    }
+
+    #[test]
+    fn scale_mirrors_inverted_ranges() {
+        assert_eq!(scale_cc(0, 255, 0), 255);
+        assert_eq!(scale_cc(127, 255, 0), 0);
+        assert_eq!(scale_cc(64, 255, 0), 255 - scale_cc(64, 0, 255));
+    }
+
+    #[test]
+    fn scale_handles_empty_and_extreme_ranges() {
+        assert_eq!(scale_cc(90, 5, 5), 5);
+        assert_eq!(scale_cc(200, 0, 100), 100);
+        assert_eq!(scale_cc(127, i32::MIN, i32::MAX), i32::MAX);
+        assert_eq!(scale_cc(0, i32::MIN, i32::MAX), i32::MIN);
+    }
+
+    #[test]
+    fn mapping_scales_with_its_own_range() {
+        let m = MidiMapping {
+            min: -10,
+            max: 10,
+            ..mapping(0, 1, "zoom")
+        };
+        assert_eq!(m.scale(0), -10);
+        assert_eq!(m.scale(127), 10);
+    }
+
+    // --- Mapping table ---
+
+    #[test]
+    fn lookup_matches_channel_and_cc() {
+        let mappings = vec![mapping(0, 7, "exposure"), mapping(1, 7, "zoom")];
+        assert_eq!(find_mapping(&mappings, 1, 7).unwrap().control_id, "zoom");
+        assert!(find_mapping(&mappings, 0, 8).is_none());
+        assert!(find_mapping(&mappings, 2, 7).is_none());
+    }
+
+    #[test]
+    fn upsert_replaces_the_mapping_on_the_same_knob() {
+        let mut mappings = vec![mapping(0, 7, "exposure"), mapping(0, 8, "focus")];
+        upsert_mapping(&mut mappings, mapping(0, 7, "zoom"));
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(find_mapping(&mappings, 0, 7).unwrap().control_id, "zoom");
+    }
+
+    // --- Learn mode ---
+
+    fn target(control_id: &str) -> LearnTarget {
+        LearnTarget {
+            device_id: "cam-1".to_string(),
+            control_id: control_id.to_string(),
+            min: -13,
+            max: -1,
+        }
+    }
+
+    #[test]
+    fn learn_maps_the_next_control_change_and_goes_idle() {
+        let mut learn = MidiLearn::default();
+        learn.start(target("exposure"));
+        assert!(learn.is_waiting());
+
+        let learned = learn
+            .on_control_change(ControlChange {
+                channel: 2,
+                cc: 21,
+                value: 64,
+            })
+            .unwrap();
+        assert_eq!(
+            learned,
+            MidiMapping {
+                midi_channel: 2,
+                cc_number: 21,
+                device_id: "cam-1".to_string(),
+                control_id: "exposure".to_string(),
+                min: -13,
+                max: -1,
+            }
+        );
+        assert_eq!(learn, MidiLearn::Idle);
+    }
+
+    #[test]
+    fn idle_learn_ignores_control_changes() {
+        let mut learn = MidiLearn::default();
+        let change = ControlChange {
+            channel: 0,
+            cc: 1,
+            value: 1,
+        };
+        assert!(learn.on_control_change(change).is_none());
+    }
+
+    #[test]
+    fn starting_again_replaces_the_target() {
+        let mut learn = MidiLearn::default();
+        learn.start(target("exposure"));
+        learn.start(target("zoom"));
+        let change = ControlChange {
+            channel: 0,
+            cc: 1,
+            value: 1,
+        };
+        assert_eq!(learn.on_control_change(change).unwrap().control_id, "zoom");
+    }
+
+    #[test]
+    fn cancel_reports_whether_learn_was_waiting() {
+        let mut learn = MidiLearn::default();
+        assert!(!learn.cancel());
+        learn.start(target("exposure"));
+        assert!(learn.cancel());
+        assert!(!learn.is_waiting());
+    }
+
+    // --- Throttle ---
+
+    #[test]
+    fn first_value_is_written_straight_away() {
+        let mut throttle = WriteThrottle::new(Duration::from_millis(50));
+        let now = Instant::now();
+        assert_eq!(throttle.offer(key("zoom"), 10, now), Some(10));
+        assert!(throttle.next_due(now).is_none());
+    }
+
+    #[test]
+    fn a_burst_keeps_only_the_latest_value() {
+        let mut throttle = WriteThrottle::new(Duration::from_millis(50));
+        let start = Instant::now();
+        throttle.offer(key("zoom"), 10, start);
+        for (ms, value) in [(5, 11), (10, 12), (20, 13)] {
+            let at = start + Duration::from_millis(ms);
+            assert_eq!(throttle.offer(key("zoom"), value, at), None);
+        }
+
+        let at = start + Duration::from_millis(20);
+        assert_eq!(throttle.next_due(at), Some(Duration::from_millis(30)));
+        assert!(throttle.due(at).is_empty());
+
+        let at = start + Duration::from_millis(50);
+        assert_eq!(throttle.due(at), vec![(key("zoom"), 13)]);
+        assert!(throttle.next_due(at).is_none());
+    }
+
+    #[test]
+    fn controls_are_throttled_independently() {
+        let mut throttle = WriteThrottle::new(Duration::from_millis(50));
+        let now = Instant::now();
+        assert_eq!(throttle.offer(key("zoom"), 1, now), Some(1));
+        assert_eq!(throttle.offer(key("focus"), 2, now), Some(2));
+        assert_eq!(throttle.offer(key("zoom"), 3, now), None);
+    }
+
+    #[test]
+    fn a_write_after_the_interval_supersedes_a_held_value() {
+        let mut throttle = WriteThrottle::new(Duration::from_millis(50));
+        let start = Instant::now();
+        throttle.offer(key("zoom"), 1, start);
+        throttle.offer(key("zoom"), 2, start + Duration::from_millis(10));
+        let later = start + Duration::from_millis(60);
+        assert_eq!(throttle.offer(key("zoom"), 3, later), Some(3));
+        assert!(throttle.due(later + Duration::from_millis(100)).is_empty());
+    }
+
+    #[test]
+    fn mappings_serialise_camel_case() {
+        let json = serde_json::to_value(mapping(3, 74, "zoom")).unwrap();
+        assert_eq!(json["midiChannel"], 3);
+        assert_eq!(json["ccNumber"], 74);
+        assert_eq!(json["controlId"], "zoom");
+    }
+}
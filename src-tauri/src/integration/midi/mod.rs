@@ -0,0 +1,7 @@
+// MIDI control surfaces — knobs and faders mapped to camera controls.
+
+#[cfg(feature = "app")]
+pub mod commands;
+pub mod mapping;
+#[cfg(feature = "midi")]
+pub mod port;
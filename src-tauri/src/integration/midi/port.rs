@@ -0,0 +1,63 @@
+// MIDI input ports through midir. Only control changes are passed on; the
+// listener that acts on them lives in `commands`.
+
+use std::sync::mpsc::Sender;
+
+use midir::{Ignore, MidiInput, MidiInputConnection};
+
+use super::mapping::{parse_control_change, ControlChange};
+
+/// Client name the app registers with the system's MIDI service.
+const CLIENT_NAME: &str = "Cameras";
+
+/// An open MIDI input. Dropping it closes the port, which in turn ends the
+/// listener receiving its control changes.
+pub struct MidiConnection {
+    pub name: String,
+    _connection: MidiInputConnection<()>,
+}
+
+fn midi_input() -> Result<MidiInput, String> {
+    let mut input = MidiInput::new(CLIENT_NAME).map_err(|e| format!("MIDI unavailable: {e}"))?;
+    // Clock, active sensing and SysEx are never mapped
+    input.ignore(Ignore::All);
+    Ok(input)
+}
+
+/// Names of the MIDI inputs currently available.
+pub fn input_names() -> Result<Vec<String>, String> {
+    let input = midi_input()?;
+    Ok(input
+        .ports()
+        .iter()
+        .filter_map(|port| input.port_name(port).ok())
+        .collect())
+}
+
+/// Open the input named `name`, sending every control change it receives
+/// to `changes`.
+pub fn open(name: &str, changes: Sender<ControlChange>) -> Result<MidiConnection, String> {
+    let input = midi_input()?;
+    let port = input
+        .ports()
+        .into_iter()
+        .find(|port| input.port_name(port).is_ok_and(|n| n == name))
+        .ok_or_else(|| format!("MIDI input '{name}' isn't connected"))?;
+    let connection = input
+        .connect(
+            &port,
+            "camera-controls",
+            move |_timestamp, message, _| {
+                if let Some(change) = parse_control_change(message) {
+                    // The listener has gone; the connection is about to close
+                    let _ = changes.send(change);
+                }
+            },
+            (),
+        )
+        .map_err(|e| format!("Couldn't open MIDI input '{name}': {e}"))?;
+    Ok(MidiConnection {
+        name: name.to_string(),
+        _connection: connection,
+    })
+}
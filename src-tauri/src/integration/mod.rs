@@ -1 +1,7 @@
-// External integrations — OBS, RTSP, virtual camera.
+// External integrations — OBS, RTSP, virtual camera, home automation, MIDI.
+
+#[cfg(feature = "app")]
+pub mod commands;
+pub mod control_api;
+pub mod http;
+pub mod midi;
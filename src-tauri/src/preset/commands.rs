@@ -0,0 +1,122 @@
+use tauri::{AppHandle, Manager, State};
+
+use crate::camera::commands::CameraState;
+use crate::camera::queue::{DeviceQueue, OpKind};
+use crate::camera::types::DeviceId;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::{code, AppError};
+use crate::preset::types::{Preset, PresetApplyResult, SavedPreset};
+use crate::settings::apply;
+use crate::settings::commands::SettingsState;
+
+/// Capture a camera's current control values as a preset and save it under
+/// `preset_id`, replacing any preset with that ID. Refused if another
+/// preset has the same name. Only succeeds once the preset is written to
+/// disk.
+#[tauri::command]
+pub async fn save_preset(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    preset_id: String,
+    name: String,
+    normalised: bool,
+) -> Result<Preset, AppError> {
+    let preset = apply::save_preset(
+        &camera_state.backend,
+        &settings_state.store,
+        &device_id,
+        &preset_id,
+        &name,
+        normalised,
+    )?;
+    settings_state
+        .store
+        .flush()
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+    Ok(preset)
+}
+
+/// Saved presets, sorted by ID. With `device_id`, only those with at least
+/// one control that camera has.
+#[tauri::command]
+pub async fn list_presets(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: Option<String>,
+) -> Result<Vec<SavedPreset>, AppError> {
+    let store = &settings_state.store;
+    match device_id {
+        Some(device_id) => apply::presets_for_device(&camera_state.backend, store, &device_id),
+        None => Ok(store
+            .presets()
+            .into_iter()
+            .map(|(id, preset)| SavedPreset { id, preset })
+            .collect()),
+    }
+}
+
+/// Delete a saved preset. Returns whether it existed.
+///
+/// Schedules and scenes naming it stay as they are, and report the preset
+/// as missing when they run.
+#[tauri::command]
+pub async fn delete_preset(
+    settings_state: State<'_, SettingsState>,
+    preset_id: String,
+) -> Result<bool, AppError> {
+    let deleted = settings_state.store.delete_preset(&preset_id);
+    settings_state
+        .store
+        .flush()
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+    Ok(deleted)
+}
+
+/// Apply a saved preset to a camera and remember it for drift and revert.
+///
+/// Runs through the device queue, so the controls aren't written while the
+/// camera's preview is being restarted.
+///
+/// Returns the controls written and the preset's controls the camera
+/// doesn't have or refused.
+#[tauri::command]
+pub async fn apply_preset(
+    app: AppHandle,
+    device_id: String,
+    preset_id: String,
+    camera_name: String,
+) -> Result<PresetApplyResult, AppError> {
+    queue_preset_apply(&app, &device_id, &preset_id, &camera_name).await
+}
+
+/// Apply a preset through the device queue, as `apply_preset` does. Also
+/// used by the preset scheduler.
+pub async fn queue_preset_apply(
+    app: &AppHandle,
+    device_id: &str,
+    preset_id: &str,
+    camera_name: &str,
+) -> Result<PresetApplyResult, AppError> {
+    let handle = app.clone();
+    let op_device = device_id.to_string();
+    let preset_id = preset_id.to_string();
+    let camera_name = camera_name.to_string();
+    app.state::<DeviceQueue>()
+        .run(
+            &DeviceId::new(device_id),
+            OpKind::PresetApply,
+            move |_| async move {
+                apply::apply_preset(
+                    &handle.state::<CameraState>().backend,
+                    &handle.state::<SettingsState>().store,
+                    &handle.state::<ControlLatencyState>(),
+                    &op_device,
+                    &preset_id,
+                    &camera_name,
+                )
+            },
+        )
+        .await
+        .map_err(AppError::from)
+}
@@ -1 +1,5 @@
 // Preset management — JSON preset storage and retrieval.
+
+#[cfg(feature = "app")]
+pub mod commands;
+pub mod types;
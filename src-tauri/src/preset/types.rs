@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::camera::types::ControlDescriptor;
+use crate::camera::units;
+
+/// Control id for absolute exposure (log2 seconds on DirectShow).
+const EXPOSURE: &str = "exposure";
+/// Control id for absolute focus (device-specific scale).
+const FOCUS: &str = "focus";
+
+/// A named set of control values that can be applied to a camera.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preset {
+    pub name: String,
+    /// Native control values, keyed by control id.
+    pub controls: HashMap<String, i32>,
+    /// When set, exposure and focus are applied from the device-independent
+    /// fields below instead of `controls`, so the preset carries over to
+    /// cameras with different native scales.
+    #[serde(default)]
+    pub normalised: bool,
+    /// Exposure time in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exposure_seconds: Option<f64>,
+    /// Focus position in `[0, 1]` across the device's range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus: Option<f64>,
+}
+
+/// A saved preset with the ID it's stored under.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedPreset {
+    pub id: String,
+    #[serde(flatten)]
+    pub preset: Preset,
+}
+
+/// What applying a preset to a camera did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetApplyResult {
+    /// Controls written, sorted by id.
+    pub applied: Vec<String>,
+    /// The preset's other controls, sorted by id: ones the camera doesn't
+    /// have or can't write, and ones it refused.
+    pub skipped: Vec<String>,
+}
+
+impl Preset {
+    /// Capture the current values of a camera's writable controls.
+    ///
+    /// With `normalised`, exposure and focus are also recorded in
+    /// device-independent units where the device reports a usable range.
+    pub fn capture(name: &str, descriptors: &[ControlDescriptor], normalised: bool) -> Self {
+        let writable = || {
+            descriptors
+                .iter()
+                .filter(|d| d.supported && !d.flags.is_read_only)
+        };
+        let controls = writable().map(|d| (d.id.clone(), d.current)).collect();
+
+        let find = |id: &str| writable().find(|d| d.id == id);
+        let (exposure_seconds, focus) = if normalised {
+            (
+                find(EXPOSURE).map(|d| units::exposure_native_to_seconds(d.current)),
+                find(FOCUS).and_then(|d| units::native_to_normalised(d.current, d.min, d.max).ok()),
+            )
+        } else {
+            (None, None)
+        };
+
+        Self {
+            name: name.to_string(),
+            controls,
+            normalised,
+            exposure_seconds,
+            focus,
+        }
+    }
+
+    /// Native values to write to a camera described by `descriptors`,
+    /// sorted by control id.
+    ///
+    /// Values are clamped to each control's range and snapped to its step.
+    /// Controls the camera does not support are skipped. For normalised
+    /// presets, exposure and focus are converted into the camera's native
+    /// scale.
+    pub fn resolve(&self, descriptors: &[ControlDescriptor]) -> Vec<(String, i32)> {
+        let mut resolved: Vec<(String, i32)> = descriptors
+            .iter()
+            .filter(|d| d.supported && !d.flags.is_read_only)
+            .filter_map(|d| self.resolve_control(d).map(|value| (d.id.clone(), value)))
+            .collect();
+        resolved.sort_by(|a, b| a.0.cmp(&b.0));
+        resolved
+    }
+
+    fn resolve_control(&self, desc: &ControlDescriptor) -> Option<i32> {
+        if self.normalised {
+            let converted = match desc.id.as_str() {
+                EXPOSURE => self
+                    .exposure_seconds
+                    .and_then(|s| units::exposure_seconds_to_native(s, desc.min, desc.max).ok()),
+                FOCUS => self
+                    .focus
+                    .and_then(|f| units::normalised_to_native(f, desc.min, desc.max).ok()),
+                _ => None,
+            };
+            if let Some(native) = converted {
+                return Some(desc.clamp(native.value).value.value());
+            }
+        }
+
+        self.controls
+            .get(&desc.id)
+            .map(|&v| desc.clamp(v).value.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{ControlFlags, ControlType};
+
+    fn slider(id: &str, min: i32, max: i32, current: i32) -> ControlDescriptor {
+        ControlDescriptor {
+            id: id.to_string(),
+            name: id.to_string(),
+            control_type: ControlType::Slider,
+            group: "test".to_string(),
+            min: Some(min),
+            max: Some(max),
+            step: Some(1),
+            default: Some(min),
+            default_auto: false,
+            current,
+            flags: ControlFlags {
+                supports_auto: false,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    /// Camera A: exposure -13..-1, focus 0..250.
+    fn camera_a() -> Vec<ControlDescriptor> {
+        vec![
+            slider("brightness", 0, 255, 140),
+            slider("exposure", -13, -1, -6),
+            slider("focus", 0, 250, 125),
+        ]
+    }
+
+    /// Camera B: exposure -11..-3, focus 0..1023.
+    fn camera_b() -> Vec<ControlDescriptor> {
+        vec![
+            slider("brightness", 0, 100, 50),
+            slider("exposure", -11, -3, -5),
+            slider("focus", 0, 1023, 0),
+        ]
+    }
+
+    fn value_of(resolved: &[(String, i32)], id: &str) -> Option<i32> {
+        resolved.iter().find(|(c, _)| c == id).map(|(_, v)| *v)
+    }
+
+    #[test]
+    fn capture_records_native_values_only_by_default() {
+        let preset = Preset::capture("Desk", &camera_a(), false);
+        assert!(!preset.normalised);
+        assert_eq!(preset.controls["exposure"], -6);
+        assert_eq!(preset.controls["focus"], 125);
+        assert_eq!(preset.exposure_seconds, None);
+        assert_eq!(preset.focus, None);
+    }
+
+    #[test]
+    fn capture_records_normalised_values_when_flagged() {
+        let preset = Preset::capture("Desk", &camera_a(), true);
+        assert!(preset.normalised);
+        assert_eq!(preset.exposure_seconds, Some(1.0 / 64.0));
+        assert_eq!(preset.focus, Some(0.5));
+    }
+
+    #[test]
+    fn normalised_preset_converts_for_a_different_camera() {
+        let preset = Preset::capture("Desk", &camera_a(), true);
+        let resolved = preset.resolve(&camera_b());
+
+        // 1/64s is -6, inside camera B's -11..-3 range
+        assert_eq!(value_of(&resolved, "exposure"), Some(-6));
+        // Half of 0..1023
+        assert_eq!(value_of(&resolved, "focus"), Some(512));
+        // Non-normalised controls are clamped natively
+        assert_eq!(value_of(&resolved, "brightness"), Some(100));
+    }
+
+    #[test]
+    fn native_preset_copies_raw_values() {
+        let preset = Preset::capture("Desk", &camera_a(), false);
+        let resolved = preset.resolve(&camera_b());
+        assert_eq!(value_of(&resolved, "exposure"), Some(-6));
+        // Raw 125 means something different on camera B
+        assert_eq!(value_of(&resolved, "focus"), Some(125));
+    }
+
+    #[test]
+    fn native_values_snap_to_the_target_step() {
+        let preset = Preset {
+            name: "Desk".to_string(),
+            controls: HashMap::from([("brightness".to_string(), 133)]),
+            ..Preset::default()
+        };
+        let mut brightness = slider("brightness", 0, 255, 128);
+        brightness.step = Some(5);
+        let resolved = preset.resolve(&[brightness]);
+        assert_eq!(value_of(&resolved, "brightness"), Some(135));
+    }
+
+    #[test]
+    fn normalised_exposure_clamps_to_target_range() {
+        let preset = Preset {
+            name: "Night".to_string(),
+            normalised: true,
+            exposure_seconds: Some(1.0),
+            ..Preset::default()
+        };
+        let resolved = preset.resolve(&camera_b());
+        assert_eq!(value_of(&resolved, "exposure"), Some(-3));
+    }
+
+    #[test]
+    fn resolve_skips_unsupported_and_missing_controls() {
+        let mut descriptors = camera_b();
+        descriptors[2].supported = false;
+        descriptors.push(slider("zoom", 100, 500, 100));
+
+        let preset = Preset::capture("Desk", &camera_a(), true);
+        let resolved = preset.resolve(&descriptors);
+        let ids: Vec<&str> = resolved.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(ids, vec!["brightness", "exposure"]);
+    }
+
+    #[test]
+    fn preset_serialises_normalised_fields_only_when_present() {
+        let native = Preset::capture("Desk", &camera_a(), false);
+        let json = serde_json::to_value(&native).unwrap();
+        assert_eq!(json["normalised"], false);
+        assert!(json.get("exposureSeconds").is_none());
+
+        let normalised = Preset::capture("Desk", &camera_a(), true);
+        let json = serde_json::to_value(&normalised).unwrap();
+        assert_eq!(json["exposureSeconds"], 1.0 / 64.0);
+        assert_eq!(json["focus"], 0.5);
+
+        let restored: Preset = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, normalised);
+    }
+}
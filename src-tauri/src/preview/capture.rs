@@ -1,21 +1,66 @@
 use parking_lot::Mutex;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
-#[cfg(target_os = "windows")]
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::camera::canon::api::{CameraHandle, EdsSdkApi};
-use crate::camera::canon::live_view::LiveViewSession;
+use crate::camera::canon::focus;
+use crate::camera::canon::live_view::live_view_runner;
+use crate::camera::canon::types::EdsPoint;
+use crate::camera::types::short_tag;
+use crate::diagnostics::accounting::{CpuMeter, CpuTimes};
+use crate::diagnostics::content::ContentHealth;
+use crate::diagnostics::delivery::{DeliveryTracker, FrameDelivery};
 use crate::diagnostics::stats::{DiagnosticSnapshot, DiagnosticStats};
+use crate::error::{code, AppError};
+use crate::preview::colour::{ColourSpace, SharedColourSpace};
 use crate::preview::encode_worker::{
-    EncodeWorker, EncodingSnapshot, JpegFrameBuffer, WorkerConfig,
+    EncodeWorker, EncodingSnapshot, FrameSender, JpegFrame, JpegFrameBuffer, WorkerConfig,
 };
 use crate::preview::gpu::GpuContext;
-
-/// Callback type for reporting capture errors to the frontend.
-/// Arguments: (device_id, error_message).
-pub type ErrorCallback = Arc<dyn Fn(&str, &str) + Send + Sync>;
+use crate::preview::mode::{NegotiatedFormat, PreviewFormat, SessionMode};
+use crate::preview::pool::BufferPool;
+use crate::preview::render::{Orientation, SharedOrientation};
+use crate::preview::shm::ShmExport;
+use crate::preview::sources::{SourcePreference, VideoSource};
+use crate::preview::tap::{FrameTap, FrameTaps, TapId};
+use crate::preview::transform::{PostProcessing, SharedPostProcessing};
+use crate::preview::zoom::{CropRect, SharedCrop};
+use crate::supervisor::RestartPolicy;
+
+/// Callback type for reporting capture errors to the frontend. Receives
+/// the payload for the `preview-error` event, already enriched by
+/// [`ErrorReporter`].
+pub type ErrorCallback = Arc<dyn Fn(PreviewErrorPayload) + Send + Sync>;
+
+/// Callback type for reporting black or frozen frames to the frontend.
+/// Arguments: (device_id, new classification).
+pub type ContentCallback = Arc<dyn Fn(&str, ContentHealth) + Send + Sync>;
+
+/// Callback type for telling the frontend what format a session's capture
+/// graph settled on. Receives the payload for the `preview-started` event.
+pub type StartedCallback = Arc<dyn Fn(PreviewStartedPayload) + Send + Sync>;
+
+/// Callback type for telling the frontend a failed capture graph is about
+/// to be rebuilt. Receives the payload for the `preview-recovering` event.
+pub type RecoveringCallback = Arc<dyn Fn(PreviewRecoveringPayload) + Send + Sync>;
+
+/// Builds and runs one capture graph until `running` is cleared, returning
+/// an error if it fails. Injectable so restarts can be tested without a
+/// camera.
+pub type GraphRunner = Arc<dyn Fn(&GraphContext) -> Result<(), String> + Send + Sync>;
+
+/// Error reported to the frontend when the capture path panics.
+pub const INTERNAL_CAPTURE_ERROR: &str = "internal capture error";
+
+/// How a capture session rebuilds a graph that failed: a few quick retries
+/// ride out a USB reset or a device briefly held by another app.
+pub const CAPTURE_RESTART: RestartPolicy = RestartPolicy {
+    max_attempts: 4,
+    initial_backoff: std::time::Duration::from_millis(500),
+    max_backoff: std::time::Duration::from_secs(4),
+};
 
 /// A single captured frame from the camera.
 pub struct Frame {
@@ -25,50 +70,174 @@ pub struct Frame {
     pub width: u32,
     /// Frame height in pixels.
     pub height: u32,
-    /// Capture timestamp in microseconds.
+    /// Capture timestamp in microseconds, never earlier than the previous
+    /// frame's in the same session. Consumers should use this one.
     pub timestamp_us: u64,
+    /// Timestamp as the device reported it, which can go backwards. For
+    /// diagnostics only.
+    pub device_timestamp_us: u64,
 }
 
 /// Thread-safe ring buffer for camera frames.
 ///
 /// Stores up to `capacity` frames, overwriting the oldest when full.
 /// Frames are wrapped in `Arc` so consumers get a cheap reference-counted
-/// pointer instead of cloning multi-megabyte pixel buffers.
+/// pointer instead of cloning multi-megabyte pixel buffers. Overwritten
+/// frames go to the buffer's [`BufferPool`] for the capture callback to
+/// convert the next ones into.
 pub struct FrameBuffer {
-    frames: Mutex<Vec<Option<Arc<Frame>>>>,
-    capacity: usize,
-    write_idx: Mutex<usize>,
+    /// Slots, write position and sequence, only ever changed together so a
+    /// reader can't see one advanced without the others.
+    ring: Mutex<Ring>,
     /// Monotonic counter incremented on each push — used for cache
     /// invalidation even when camera timestamps are unreliable (e.g. OBS
     /// Virtual Camera reports sample_time = 0 for every frame).
+    ///
+    /// Mirrors the ring's sequence, published after the slot is written, so
+    /// `sequence()` doesn't take the lock.
     sequence: AtomicU64,
+    /// Which frames each consumer has used, for end-to-end drop accounting.
+    delivery: Mutex<DeliveryTracker>,
+    /// Streaming consumers that get every pushed frame.
+    taps: FrameTaps,
+    /// Shared-memory export written with every pushed frame, if enabled.
+    export: Mutex<Option<Arc<ShmExport>>>,
+    /// Pixel buffers of overwritten frames, for the next ones.
+    pool: BufferPool,
+}
+
+/// The frames of a [`FrameBuffer`] and where the next one goes.
+struct Ring {
+    slots: Vec<Option<Arc<Frame>>>,
+    write_idx: usize,
+    /// Sequence number of the frame in the slot before `write_idx`.
+    sequence: u64,
+}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity).map(|_| None).collect(),
+            write_idx: 0,
+            sequence: 0,
+        }
+    }
+
+    /// Store `frame` over the oldest slot, returning its sequence number
+    /// and the frame it overwrote.
+    fn push(&mut self, frame: Arc<Frame>) -> (u64, Option<Arc<Frame>>) {
+        let mut overwritten = None;
+        if !self.slots.is_empty() {
+            overwritten = self.slots[self.write_idx].replace(frame);
+            self.write_idx = (self.write_idx + 1) % self.slots.len();
+        }
+        self.sequence += 1;
+        (self.sequence, overwritten)
+    }
+
+    fn latest(&self) -> Option<(Arc<Frame>, u64)> {
+        let capacity = self.slots.len();
+        if capacity == 0 {
+            return None;
+        }
+        let idx = (self.write_idx + capacity - 1) % capacity;
+        let frame = self.slots[idx].clone()?;
+        Some((frame, self.sequence))
+    }
 }
 
 impl FrameBuffer {
     /// Create a new ring buffer with the given capacity.
     pub fn new(capacity: usize) -> Self {
-        let frames = (0..capacity).map(|_| None).collect();
         Self {
-            frames: Mutex::new(frames),
-            capacity,
-            write_idx: Mutex::new(0),
+            ring: Mutex::new(Ring::new(capacity)),
             sequence: AtomicU64::new(0),
+            delivery: Mutex::new(DeliveryTracker::new(capacity)),
+            taps: FrameTaps::default(),
+            export: Mutex::new(None),
+            pool: BufferPool::default(),
+        }
+    }
+
+    /// Push a new frame into the buffer, overwriting the oldest if full,
+    /// write it to the shared-memory export if there is one, and queue it
+    /// to every registered tap without blocking. The overwritten frame's
+    /// pixel buffer goes back to the pool.
+    ///
+    /// Returns the sequence number assigned to the frame.
+    pub fn push(&self, frame: Frame) -> u64 {
+        let frame = Arc::new(frame);
+        let (sequence, overwritten) = {
+            let mut ring = self.ring.lock();
+            let (sequence, overwritten) = ring.push(Arc::clone(&frame));
+            // Stored under the lock, so the counter never runs backwards
+            // and never gets ahead of a frame `latest()` can return
+            self.sequence.store(sequence, Ordering::Release);
+            (sequence, overwritten)
+        };
+        if let Some(overwritten) = overwritten {
+            self.pool.recycle(overwritten);
+        }
+        let export = self.export.lock().clone();
+        if let Some(export) = export {
+            export.write(&frame);
         }
+        self.taps.send(&frame);
+        sequence
+    }
+
+    /// Bytes of pixel data held across the ring.
+    pub fn bytes(&self) -> usize {
+        self.ring
+            .lock()
+            .slots
+            .iter()
+            .flatten()
+            .map(|f| f.data.len())
+            .sum()
+    }
+
+    /// Pool of pixel buffers to convert the next frame into.
+    pub fn pool(&self) -> &BufferPool {
+        &self.pool
+    }
+
+    /// The shared-memory export fed by this buffer, if any.
+    pub fn export(&self) -> Option<Arc<ShmExport>> {
+        self.export.lock().clone()
+    }
+
+    /// Feed `export` from now on, or stop exporting with `None`. Returns the
+    /// export it replaces, which the caller should close.
+    pub fn set_export(&self, export: Option<Arc<ShmExport>>) -> Option<Arc<ShmExport>> {
+        std::mem::replace(&mut *self.export.lock(), export)
     }
 
-    /// Push a new frame into the buffer, overwriting the oldest if full.
-    pub fn push(&self, frame: Frame) {
-        let mut frames = self.frames.lock();
-        let mut idx = self.write_idx.lock();
-        frames[*idx] = Some(Arc::new(frame));
-        *idx = (*idx + 1) % self.capacity;
-        self.sequence.fetch_add(1, Ordering::Relaxed);
+    /// Register a tap that receives every frame pushed from now on,
+    /// queueing up to `capacity` and dropping the oldest beyond that.
+    pub fn register_tap(&self, capacity: usize) -> FrameTap {
+        self.taps.register(capacity)
+    }
+
+    /// Unregister a tap, returning whether it was registered.
+    pub fn unregister_tap(&self, id: TapId) -> bool {
+        self.taps.unregister(id)
+    }
+
+    /// Frames each registered tap has dropped on overflow.
+    pub fn tap_overflows(&self) -> Vec<(TapId, u64)> {
+        self.taps.overflows()
+    }
+
+    /// Unregister every tap, ending their streams.
+    pub fn close_taps(&self) {
+        self.taps.close_all();
     }
 
     /// Return the monotonic sequence number. Increases by 1 for each
     /// pushed frame, regardless of the frame's own timestamp.
     pub fn sequence(&self) -> u64 {
-        self.sequence.load(Ordering::Relaxed)
+        self.sequence.load(Ordering::Acquire)
     }
 
     /// Get the most recently pushed frame, if any.
@@ -76,41 +245,460 @@ impl FrameBuffer {
     /// Returns an `Arc<Frame>` — a cheap clone of a reference-counted pointer
     /// rather than copying the entire pixel buffer.
     pub fn latest(&self) -> Option<Arc<Frame>> {
-        let frames = self.frames.lock();
-        let idx = self.write_idx.lock();
-        if self.capacity == 0 {
-            return None;
+        self.latest_with_sequence().map(|(frame, _)| frame)
+    }
+
+    /// Get the most recently pushed frame together with its sequence number.
+    ///
+    /// Both are read under the buffer lock, so a concurrent push can't pair
+    /// a frame with the wrong sequence.
+    pub fn latest_with_sequence(&self) -> Option<(Arc<Frame>, u64)> {
+        self.ring.lock().latest()
+    }
+
+    /// Report that `consumer` has used the frame with `sequence`.
+    pub fn record_consumed(&self, consumer: &'static str, sequence: u64) {
+        self.delivery
+            .lock()
+            .record_consumed(consumer, sequence, self.sequence());
+    }
+
+    /// Produced / consumed / overwritten-unseen counts per consumer.
+    pub fn delivery(&self) -> Vec<FrameDelivery> {
+        self.delivery.lock().snapshot(self.sequence())
+    }
+}
+
+/// State of a session while waiting for its first frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameProbe {
+    /// No frame yet.
+    Pending,
+    /// At least one frame has arrived at the given size.
+    Ready { width: u32, height: u32 },
+    /// The session failed before delivering a frame.
+    Failed(String),
+}
+
+/// Result of waiting for a session's first frame, for IPC serialisation.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum FirstFrameOutcome {
+    /// Video is flowing at the negotiated resolution.
+    Ready { width: u32, height: u32 },
+    /// No frame arrived within the timeout.
+    Timeout,
+    /// The session failed before delivering a frame.
+    SessionError { message: String },
+}
+
+/// Probe a raw frame buffer for its first frame.
+pub fn probe_frame_buffer(buffer: &FrameBuffer) -> FrameProbe {
+    if buffer.sequence() == 0 {
+        return FrameProbe::Pending;
+    }
+    match buffer.latest() {
+        Some(frame) => FrameProbe::Ready {
+            width: frame.width,
+            height: frame.height,
+        },
+        None => FrameProbe::Pending,
+    }
+}
+
+/// Poll `probe` every `poll_interval` until it reports a frame, an error,
+/// or `timeout` elapses.
+///
+/// Blocks the calling thread; run it on a blocking task. The probe should
+/// only hold locks for the duration of a single call.
+pub fn poll_first_frame(
+    mut probe: impl FnMut() -> FrameProbe,
+    timeout: std::time::Duration,
+    poll_interval: std::time::Duration,
+) -> FirstFrameOutcome {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        match probe() {
+            FrameProbe::Ready { width, height } => {
+                return FirstFrameOutcome::Ready { width, height };
+            }
+            FrameProbe::Failed(message) => return FirstFrameOutcome::SessionError { message },
+            FrameProbe::Pending => {}
         }
-        let latest_idx = if *idx == 0 {
-            self.capacity - 1
-        } else {
-            *idx - 1
-        };
-        frames[latest_idx].clone()
+
+        let now = std::time::Instant::now();
+        if now >= deadline {
+            return FirstFrameOutcome::Timeout;
+        }
+        std::thread::sleep(poll_interval.min(deadline - now));
     }
 }
 
 /// Active capture session for a single camera.
 pub struct CaptureSession {
     device_id: String,
+    /// Path the capture graph was built from, for recognising another
+    /// session on the same camera.
+    device_path: String,
     buffer: Arc<FrameBuffer>,
     running: Arc<AtomicBool>,
     /// Signals the watchdog to exit early during teardown.
     shutdown: Arc<AtomicBool>,
+    /// Set when the capture graph exits with an error or panics.
+    failed: Arc<AtomicBool>,
+    /// Error message recorded alongside `failed`.
+    last_error: Arc<Mutex<Option<String>>>,
+    /// Orientation applied by the encode worker.
+    orientation: SharedOrientation,
+    /// Digital zoom crop applied by the encode worker.
+    crop: SharedCrop,
+    /// Sharpening and denoise applied by the encode worker.
+    post_processing: SharedPostProcessing,
+    /// Colour space override read by the capture callback; `None` detects it.
+    colour: SharedColourSpace,
+    /// CPU time spent on this session's frames, by the capture callback and
+    /// the encode worker.
+    cpu: CpuMeter,
     thread: Option<JoinHandle<()>>,
     watchdog: Option<JoinHandle<()>>,
     stats: Arc<Mutex<DiagnosticStats>>,
     /// Async JPEG encode worker — produces JPEG frames from raw RGB input.
+    /// Not started for thumbnail-only sessions.
     encode_worker: Option<EncodeWorker>,
+    mode: SessionMode,
+    /// Frame size and rate the session asked for.
+    format: PreviewFormat,
+    /// What the capture graph settled on; `None` until it's connected.
+    negotiated: Arc<Mutex<Option<NegotiatedFormat>>>,
+    /// Video sources the capture graph found on the device; empty until the
+    /// graph is built, and for single-input devices it couldn't enumerate.
+    sources: Arc<Mutex<Vec<VideoSource>>>,
 }
 
 /// Payload emitted via the `preview-error` Tauri event when a capture
 /// graph fails.
-#[derive(Clone, serde::Serialize)]
+///
+/// The context fields say where in the session the error happened, for bug
+/// reports. They're omitted when unknown.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PreviewErrorPayload {
     pub device_id: String,
-    pub error: String,
+    pub error: AppError,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frames_delivered: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_uptime_ms: Option<u64>,
+    /// Absent before the first frame as well as when stats are unavailable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_frame_age_ms: Option<u64>,
+    /// Resolution frames were delivered at, e.g. `1920x1080`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// 1 for the session's first error, 2 for the next, and so on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_ordinal: Option<u32>,
+}
+
+/// Counters read from a session's stats when it reports an error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorStats {
+    pub frames_delivered: u64,
+    pub uptime: std::time::Duration,
+    pub since_last_frame: Option<std::time::Duration>,
+}
+
+impl ErrorStats {
+    pub fn read(stats: &DiagnosticStats) -> Self {
+        Self {
+            frames_delivered: stats.frame_count(),
+            uptime: stats.uptime(),
+            since_last_frame: stats.since_last_frame(),
+        }
+    }
+}
+
+/// What a session knows about itself when it reports an error.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorSessionInfo {
+    /// Resolution frames were delivered at, if any arrived.
+    pub format: Option<String>,
+    pub error_ordinal: Option<u32>,
+}
+
+impl PreviewErrorPayload {
+    /// Build the payload for an error, coding and humanising it and adding
+    /// whatever context is known. `stats` is `None` when none could be read.
+    pub fn enriched(
+        device_id: &str,
+        error: &str,
+        stats: Option<ErrorStats>,
+        session: ErrorSessionInfo,
+    ) -> Self {
+        let millis = |d: std::time::Duration| d.as_millis() as u64;
+        Self {
+            device_id: device_id.to_string(),
+            error: AppError::classify(code::PREVIEW_FAILED, error),
+            frames_delivered: stats.map(|s| s.frames_delivered),
+            session_uptime_ms: stats.map(|s| millis(s.uptime)),
+            last_frame_age_ms: stats.and_then(|s| s.since_last_frame).map(millis),
+            format: session.format,
+            error_ordinal: session.error_ordinal,
+        }
+    }
+}
+
+/// Reports a session's errors through its [`ErrorCallback`], enriching each
+/// with the session's stats, delivered format and error count. Shared by the
+/// capture thread and the watchdog so both number errors in one sequence.
+pub struct ErrorReporter {
+    device_id: String,
+    callback: ErrorCallback,
+    stats: Arc<Mutex<DiagnosticStats>>,
+    buffer: Arc<FrameBuffer>,
+    errors: AtomicU32,
+}
+
+impl ErrorReporter {
+    pub fn new(
+        device_id: String,
+        callback: ErrorCallback,
+        stats: Arc<Mutex<DiagnosticStats>>,
+        buffer: Arc<FrameBuffer>,
+    ) -> Self {
+        Self {
+            device_id,
+            callback,
+            stats,
+            buffer,
+            errors: AtomicU32::new(0),
+        }
+    }
+
+    /// Report `error` with the session's current context.
+    pub fn report(&self, error: &str) {
+        // A wedged capture thread may hold the stats; report without them
+        // rather than block the error path
+        let stats = self
+            .stats
+            .try_lock_for(std::time::Duration::from_millis(50))
+            .map(|stats| ErrorStats::read(&stats));
+        let session = ErrorSessionInfo {
+            format: self
+                .buffer
+                .latest()
+                .map(|frame| format!("{}x{}", frame.width, frame.height)),
+            error_ordinal: Some(self.errors.fetch_add(1, Ordering::Relaxed) + 1),
+        };
+        (self.callback)(PreviewErrorPayload::enriched(
+            &self.device_id,
+            error,
+            stats,
+            session,
+        ));
+    }
+}
+
+/// Payload emitted via the `preview-started` Tauri event once a capture
+/// graph is connected, with the resolution and pixel format the camera
+/// actually delivers rather than the ones requested.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewStartedPayload {
+    pub device_id: String,
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: String,
+}
+
+impl PreviewStartedPayload {
+    pub fn new(device_id: &str, format: NegotiatedFormat) -> Self {
+        Self {
+            device_id: device_id.to_string(),
+            width: format.width,
+            height: format.height,
+            pixel_format: format.pixel_format,
+        }
+    }
+}
+
+/// Payload emitted via the `preview-recovering` Tauri event when a session's
+/// capture graph failed and is about to be rebuilt.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewRecoveringPayload {
+    pub device_id: String,
+    /// The attempt about to be made; the first retry is attempt 2.
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Why the previous attempt failed.
+    pub error: AppError,
+    pub retry_in_ms: u64,
+}
+
+/// Everything a [`GraphRunner`] needs to build one capture graph, shared
+/// across the restarts of a session.
+pub struct GraphContext {
+    pub device_id: String,
+    pub device_path: String,
+    pub friendly_name: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+    pub pixel_format: Option<String>,
+    pub mode: SessionMode,
+    pub video_source: Option<SourcePreference>,
+    pub sources: Arc<Mutex<Vec<VideoSource>>>,
+    pub buffer: Arc<FrameBuffer>,
+    pub running: Arc<AtomicBool>,
+    pub stats: Arc<Mutex<DiagnosticStats>>,
+    pub gpu: Option<Arc<GpuContext>>,
+    pub frame_sender: Option<FrameSender>,
+    pub on_content: Option<ContentCallback>,
+    pub on_started: Option<StartedCallback>,
+    pub negotiated: Arc<Mutex<Option<NegotiatedFormat>>>,
+    pub colour: SharedColourSpace,
+    pub cpu: CpuMeter,
+}
+
+/// Run one DirectShow capture graph for `ctx`.
+#[cfg(target_os = "windows")]
+fn run_directshow_graph(ctx: &GraphContext) -> Result<(), String> {
+    use super::graph::directshow::{run_capture_graph, ContentHook, StartedHook};
+
+    let on_content = ctx.on_content.clone().map(|cb| {
+        let device_id = ctx.device_id.clone();
+        Box::new(move |health: ContentHealth| cb(&device_id, health)) as ContentHook
+    });
+    let on_started = {
+        let device_id = ctx.device_id.clone();
+        let negotiated = Arc::clone(&ctx.negotiated);
+        let callback = ctx.on_started.clone();
+        Box::new(move |format: NegotiatedFormat| {
+            *negotiated.lock() = Some(format.clone());
+            if let Some(cb) = callback {
+                cb(PreviewStartedPayload::new(&device_id, format));
+            }
+        }) as StartedHook
+    };
+    run_capture_graph(
+        &ctx.device_path,
+        &ctx.friendly_name,
+        ctx.width,
+        ctx.height,
+        ctx.fps,
+        ctx.pixel_format.as_deref(),
+        ctx.mode,
+        ctx.video_source.clone(),
+        Arc::clone(&ctx.sources),
+        Arc::clone(&ctx.buffer),
+        Arc::clone(&ctx.running),
+        Arc::clone(&ctx.stats),
+        ctx.gpu.clone(),
+        ctx.frame_sender.clone(),
+        on_content,
+        on_started,
+        Arc::clone(&ctx.colour),
+        ctx.cpu.clone(),
+    )
+}
+
+/// The platform's capture graph, or `None` where there is none.
+fn default_graph_runner() -> Option<GraphRunner> {
+    #[cfg(target_os = "windows")]
+    {
+        Some(Arc::new(run_directshow_graph))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+/// Run `runner` until it succeeds, `shutdown` is set or `restart` runs out
+/// of attempts, calling `on_recovering` before each retry.
+///
+/// Returns the error that ended the session, or `None` if the graph exited
+/// cleanly or the session was stopped while waiting to retry.
+fn supervise_graph(
+    ctx: &GraphContext,
+    runner: &(dyn Fn(&GraphContext) -> Result<(), String> + Send + Sync),
+    restart: RestartPolicy,
+    shutdown: &AtomicBool,
+    on_recovering: Option<&RecoveringCallback>,
+) -> Option<String> {
+    let device_id = &ctx.device_id;
+    let mut attempt = 1;
+    loop {
+        let delivered = ctx.buffer.sequence();
+        let error = match crate::supervisor::catch_panic(|| runner(ctx)) {
+            Ok(Ok(())) => return None,
+            Ok(Err(e)) => {
+                error!("capture graph failed for {device_id}: {e}");
+                e
+            }
+            Err(panic_msg) => {
+                error!("capture thread panicked for {device_id}: {panic_msg}");
+                ctx.stats.lock().record_panic();
+                INTERNAL_CAPTURE_ERROR.to_string()
+            }
+        };
+        ctx.running.store(false, Ordering::Relaxed);
+        // A graph that delivered frames recovered, so this failure starts a
+        // fresh run of attempts
+        if ctx.buffer.sequence() != delivered {
+            attempt = 1;
+        }
+        if shutdown.load(Ordering::Relaxed) || attempt >= restart.max_attempts {
+            return Some(error);
+        }
+
+        let delay = restart.backoff_for(attempt);
+        attempt += 1;
+        warn!(
+            "restarting capture for {device_id} in {delay:?} (attempt {attempt} of {})",
+            restart.max_attempts
+        );
+        if let Some(callback) = on_recovering {
+            callback(PreviewRecoveringPayload {
+                device_id: device_id.clone(),
+                attempt,
+                max_attempts: restart.max_attempts,
+                error: AppError::classify(code::PREVIEW_FAILED, &error),
+                retry_in_ms: delay.as_millis() as u64,
+            });
+        }
+        if !sleep_unless_shutdown(delay, shutdown) {
+            info!("capture for {device_id} stopped while waiting to restart");
+            return None;
+        }
+    }
+}
+
+/// Sleep for `delay` in short steps, returning `false` early if `shutdown`
+/// is set.
+fn sleep_unless_shutdown(delay: std::time::Duration, shutdown: &AtomicBool) -> bool {
+    const STEP: std::time::Duration = std::time::Duration::from_millis(20);
+    let deadline = std::time::Instant::now() + delay;
+    loop {
+        if shutdown.load(Ordering::Relaxed) {
+            return false;
+        }
+        let now = std::time::Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        std::thread::sleep(STEP.min(deadline - now));
+    }
+}
+
+/// Payload emitted via the `preview-content-warning` Tauri event when a
+/// running session's frames turn black or frozen, or recover.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewContentPayload {
+    pub device_id: String,
+    pub health: ContentHealth,
 }
 
 /// Configuration for the frame watchdog timer.
@@ -143,90 +731,224 @@ impl CaptureSession {
     /// An async encode worker thread compresses frames to JPEG in the
     /// background, storing results in a `JpegFrameBuffer`.
     ///
+    /// `device_id` keys logs, thread names and error callbacks; `device_path`
+    /// finds the DirectShow filter.
+    ///
     /// If `on_error` is provided, it is called with `(device_id, error_msg)`
-    /// when the capture graph fails, allowing the caller to surface errors
-    /// to the frontend.
+    /// when the capture graph fails for good, allowing the caller to surface errors
+    /// to the frontend. `on_content` is called with the new classification
+    /// when sampled frames turn black or frozen, and again when they recover.
+    /// `on_started` is called once the graph is connected, with the format
+    /// the camera settled on.
     ///
     /// If `gpu` is provided, colour conversion runs on the GPU; otherwise
     /// the CPU fallback is used.
+    ///
+    /// `pixel_format` is a FourCC such as `MJPG`, as `get_camera_formats`
+    /// lists them; the camera's formats in it are preferred when one exists.
+    ///
+    /// A `ThumbnailOnly` session requests the smallest adequate resolution
+    /// instead of `width`x`height`, keeps a single raw frame and starts no
+    /// encode worker, so it can only feed thumbnails.
+    ///
+    /// On a device with several video inputs, `video_source` is the one to
+    /// capture; see [`choose_source`](crate::preview::sources::choose_source).
+    ///
+    /// When the graph fails to build or exits with an error, it is rebuilt
+    /// under `restart`, calling `on_recovering` before each retry; `on_error`
+    /// is only called once the last attempt has failed.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         device_id: String,
+        device_path: String,
         friendly_name: String,
         width: u32,
         height: u32,
-        _fps: f32,
+        fps: f32,
+        pixel_format: Option<String>,
+        mode: SessionMode,
+        video_source: Option<SourcePreference>,
+        restart: RestartPolicy,
         on_error: Option<ErrorCallback>,
+        on_content: Option<ContentCallback>,
+        on_started: Option<StartedCallback>,
+        on_recovering: Option<RecoveringCallback>,
         gpu: Option<Arc<GpuContext>>,
         jpeg_quality: u8,
     ) -> Self {
-        let buffer = Arc::new(FrameBuffer::new(3));
+        Self::start(
+            device_id,
+            device_path,
+            friendly_name,
+            width,
+            height,
+            fps,
+            pixel_format,
+            mode,
+            video_source,
+            restart,
+            on_error,
+            on_content,
+            on_started,
+            on_recovering,
+            gpu,
+            jpeg_quality,
+            default_graph_runner(),
+        )
+    }
+
+    /// A full preview session whose frames come from `runner` rather than
+    /// the platform's capture graph, such as Canon live view. It's encoded,
+    /// watched and restarted like any other session.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_runner(
+        device_id: String,
+        device_path: String,
+        friendly_name: String,
+        format: PreviewFormat,
+        restart: RestartPolicy,
+        on_error: Option<ErrorCallback>,
+        on_started: Option<StartedCallback>,
+        jpeg_quality: u8,
+        runner: GraphRunner,
+    ) -> Self {
+        Self::start(
+            device_id,
+            device_path,
+            friendly_name,
+            format.width,
+            format.height,
+            format.fps,
+            None,
+            SessionMode::Full,
+            None,
+            restart,
+            on_error,
+            None,
+            on_started,
+            None,
+            None,
+            jpeg_quality,
+            Some(runner),
+        )
+    }
+
+    /// [`new`](Self::new) with the capture graph supplied by `runner`; no
+    /// capture thread is started when it's `None`.
+    #[allow(clippy::too_many_arguments)]
+    fn start(
+        device_id: String,
+        device_path: String,
+        friendly_name: String,
+        width: u32,
+        height: u32,
+        fps: f32,
+        pixel_format: Option<String>,
+        mode: SessionMode,
+        video_source: Option<SourcePreference>,
+        restart: RestartPolicy,
+        on_error: Option<ErrorCallback>,
+        on_content: Option<ContentCallback>,
+        on_started: Option<StartedCallback>,
+        on_recovering: Option<RecoveringCallback>,
+        gpu: Option<Arc<GpuContext>>,
+        jpeg_quality: u8,
+        runner: Option<GraphRunner>,
+    ) -> Self {
+        let buffer = Arc::new(FrameBuffer::new(mode.frame_buffer_capacity()));
         let running = Arc::new(AtomicBool::new(false));
         let shutdown = Arc::new(AtomicBool::new(false));
+        let failed = Arc::new(AtomicBool::new(false));
+        let last_error = Arc::new(Mutex::new(None));
         let stats = Arc::new(Mutex::new(DiagnosticStats::new()));
+        let sources = Arc::new(Mutex::new(Vec::new()));
+        let negotiated = Arc::new(Mutex::new(None));
+        let orientation = SharedOrientation::default();
+        let crop = SharedCrop::default();
+        let post_processing = SharedPostProcessing::default();
+        let colour = SharedColourSpace::default();
+        let cpu = CpuMeter::default();
 
         // Spawn the JPEG encode worker
-        let (encode_worker, frame_sender) = EncodeWorker::spawn(WorkerConfig {
-            quality: jpeg_quality,
-            ..WorkerConfig::default()
-        });
+        let (encode_worker, frame_sender) = if mode.encodes_frames() {
+            let (worker, sender) = EncodeWorker::spawn(WorkerConfig {
+                quality: jpeg_quality,
+                orientation: Arc::clone(&orientation),
+                crop: Arc::clone(&crop),
+                post_processing: Arc::clone(&post_processing),
+                cpu: cpu.clone(),
+                ..WorkerConfig::default()
+            });
+            (Some(worker), Some(sender))
+        } else {
+            (None, None)
+        };
 
-        // Clone on_error for the watchdog — the capture thread gets the original
+        // Shared by the capture thread and the watchdog
+        let on_error = on_error.map(|callback| {
+            Arc::new(ErrorReporter::new(
+                device_id.clone(),
+                callback,
+                Arc::clone(&stats),
+                Arc::clone(&buffer),
+            ))
+        });
         let on_error_wd = on_error.clone();
 
-        let thread = {
-            let device_id_clone = device_id.clone();
-            let friendly_name_clone = friendly_name;
-            let buffer_clone = Arc::clone(&buffer);
-            let running_clone = Arc::clone(&running);
-            let stats_clone = Arc::clone(&stats);
-
-            #[cfg(target_os = "windows")]
-            {
-                Some(
-                    std::thread::Builder::new()
-                        .name(format!("capture-{}", &device_id))
-                        .spawn(move || {
-                            info!("capture thread starting for {device_id_clone}");
-                            if let Err(e) = super::graph::directshow::run_capture_graph(
-                                &device_id_clone,
-                                &friendly_name_clone,
-                                width,
-                                height,
-                                buffer_clone,
-                                running_clone,
-                                stats_clone,
-                                gpu,
-                                Some(frame_sender),
-                            ) {
-                                error!("capture graph failed for {device_id_clone}: {e}");
-                                if let Some(cb) = &on_error {
-                                    cb(&device_id_clone, &e);
-                                }
-                            }
-                            info!("capture thread exiting for {device_id_clone}");
-                        })
-                        .expect("failed to spawn capture thread"),
-                )
-            }
-
-            #[cfg(not(target_os = "windows"))]
-            {
-                let _ = (
-                    device_id_clone,
-                    friendly_name_clone,
-                    buffer_clone,
-                    running_clone,
-                    stats_clone,
-                    width,
-                    height,
-                    on_error,
-                    gpu,
-                    frame_sender,
-                );
-                None
-            }
-        };
+        // Device IDs can be long; thread names have length limits
+        let tag = short_tag(&device_id);
+        let session_path = device_path.clone();
+
+        let thread = runner.map(|runner| {
+            let ctx = GraphContext {
+                device_id: device_id.clone(),
+                device_path,
+                friendly_name,
+                width,
+                height,
+                fps,
+                pixel_format,
+                mode,
+                video_source,
+                sources: Arc::clone(&sources),
+                buffer: Arc::clone(&buffer),
+                running: Arc::clone(&running),
+                stats: Arc::clone(&stats),
+                gpu,
+                frame_sender,
+                on_content,
+                on_started,
+                negotiated: Arc::clone(&negotiated),
+                colour: Arc::clone(&colour),
+                cpu: cpu.clone(),
+            };
+            let shutdown = Arc::clone(&shutdown);
+            let failed = Arc::clone(&failed);
+            let last_error = Arc::clone(&last_error);
+
+            std::thread::Builder::new()
+                .name(format!("capture-{tag}"))
+                .spawn(move || {
+                    info!("capture thread starting for {}", ctx.device_id);
+                    let error = supervise_graph(
+                        &ctx,
+                        runner.as_ref(),
+                        restart,
+                        &shutdown,
+                        on_recovering.as_ref(),
+                    );
+                    if let Some(e) = error {
+                        ctx.running.store(false, Ordering::Relaxed);
+                        *last_error.lock() = Some(e.clone());
+                        failed.store(true, Ordering::Relaxed);
+                        if let Some(reporter) = &on_error {
+                            reporter.report(&e);
+                        }
+                    }
+                    info!("capture thread exiting for {}", ctx.device_id);
+                })
+                .expect("failed to spawn capture thread")
+        });
 
         let watchdog = {
             let device_id_wd = device_id.clone();
@@ -236,14 +958,14 @@ impl CaptureSession {
 
             Some(
                 std::thread::Builder::new()
-                    .name(format!("watchdog-{}", &device_id))
+                    .name(format!("watchdog-{tag}"))
                     .spawn(move || {
                         Self::run_watchdog(
                             &device_id_wd,
                             &buffer_wd,
                             &running_wd,
                             &shutdown_wd,
-                            on_error_wd.as_ref(),
+                            on_error_wd.as_deref(),
                         );
                     })
                     .expect("failed to spawn watchdog thread"),
@@ -252,13 +974,25 @@ impl CaptureSession {
 
         Self {
             device_id,
+            device_path: session_path,
             buffer,
             running,
             shutdown,
+            failed,
+            last_error,
+            orientation,
+            crop,
+            post_processing,
+            colour,
+            cpu,
             thread,
             watchdog,
             stats,
-            encode_worker: Some(encode_worker),
+            encode_worker,
+            mode,
+            format: PreviewFormat { width, height, fps },
+            negotiated,
+            sources,
         }
     }
 
@@ -267,14 +1001,47 @@ impl CaptureSession {
         &self.buffer
     }
 
+    /// Register a tap that receives every raw frame this session captures.
+    /// See `FrameBuffer::register_tap`.
+    pub fn register_tap(&self, capacity: usize) -> FrameTap {
+        self.buffer.register_tap(capacity)
+    }
+
+    /// Unregister a tap, returning whether it was registered.
+    pub fn unregister_tap(&self, id: TapId) -> bool {
+        self.buffer.unregister_tap(id)
+    }
+
     /// Get a reference to the JPEG output buffer from the encode worker.
     ///
-    /// Returns `None` if the encode worker was never started (shouldn't
-    /// happen in normal operation).
+    /// Returns `None` if the encode worker was never started, as in
+    /// thumbnail-only sessions.
     pub fn jpeg_buffer(&self) -> Option<&Arc<JpegFrameBuffer>> {
         self.encode_worker.as_ref().map(|w| w.jpeg_buffer())
     }
 
+    /// Serve `frame` until this session encodes its own first frame, so a
+    /// restarted preview keeps its last picture instead of going blank.
+    ///
+    /// Ignored once the session has produced output.
+    pub fn seed_frame(&self, frame: &JpegFrame) {
+        let Some(buffer) = self.jpeg_buffer() else {
+            return;
+        };
+        if buffer.sequence() > 0 {
+            return;
+        }
+        buffer.update(JpegFrame {
+            jpeg_bytes: frame.jpeg_bytes.clone(),
+            width: frame.width,
+            height: frame.height,
+            encoder_kind: frame.encoder_kind,
+            orientation: frame.orientation,
+            // Not from this session's raw buffer
+            source_sequence: 0,
+        });
+    }
+
     /// Check if the capture session is currently running.
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
@@ -285,9 +1052,109 @@ impl CaptureSession {
         &self.device_id
     }
 
+    /// Device path the session captures from.
+    pub fn device_path(&self) -> &str {
+        &self.device_path
+    }
+
+    /// What this session was started for.
+    pub fn mode(&self) -> SessionMode {
+        self.mode
+    }
+
+    /// Frame size and rate the session asked for; the camera delivers the
+    /// nearest it has, and thumbnail-only sessions ignore it.
+    pub fn requested_format(&self) -> PreviewFormat {
+        self.format
+    }
+
+    /// Frame size and pixel format the capture graph settled on, or `None`
+    /// until it's connected.
+    pub fn negotiated_format(&self) -> Option<NegotiatedFormat> {
+        self.negotiated.lock().clone()
+    }
+
+    /// Video sources the capture graph found on the device.
+    pub fn video_sources(&self) -> Vec<VideoSource> {
+        self.sources.lock().clone()
+    }
+
+    /// Whether the capture graph exited with an error or panicked.
+    pub fn is_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// The error that failed the session, if any.
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().clone()
+    }
+
     /// Take a snapshot of diagnostic stats for this session.
     pub fn diagnostics(&self) -> DiagnosticSnapshot {
-        self.stats.lock().snapshot()
+        DiagnosticSnapshot {
+            negotiated_format: self.negotiated_format(),
+            ..self.stats.lock().snapshot()
+        }
+    }
+
+    /// Time since the capture graph last delivered a frame, or `None`
+    /// before the first.
+    pub fn since_last_frame(&self) -> Option<std::time::Duration> {
+        self.stats.lock().since_last_frame()
+    }
+
+    /// Count a still written to disk from this session.
+    pub fn record_snapshot(&self) {
+        self.stats.lock().record_snapshot();
+    }
+
+    /// Orientation currently applied to delivered frames.
+    pub fn orientation(&self) -> Orientation {
+        *self.orientation.lock()
+    }
+
+    /// Change the orientation; takes effect from the next encoded frame.
+    pub fn set_orientation(&self, orientation: Orientation) {
+        *self.orientation.lock() = orientation;
+    }
+
+    /// Digital zoom crop currently applied to encoded frames.
+    pub fn crop(&self) -> CropRect {
+        *self.crop.lock()
+    }
+
+    /// Change the digital zoom crop; takes effect from the next encoded
+    /// frame.
+    pub fn set_crop(&self, crop: CropRect) {
+        *self.crop.lock() = crop;
+    }
+
+    /// Sharpening and denoise currently applied to encoded frames.
+    pub fn post_processing(&self) -> PostProcessing {
+        *self.post_processing.lock()
+    }
+
+    /// Change sharpening and denoise; takes effect from the next encoded
+    /// frame.
+    pub fn set_post_processing(&self, settings: PostProcessing) {
+        *self.post_processing.lock() = settings;
+    }
+
+    /// The colour space override, or `None` when it's detected per frame.
+    pub fn colour_space(&self) -> Option<ColourSpace> {
+        *self.colour.lock()
+    }
+
+    /// Override how YUV frames are decoded, or go back to detecting it
+    /// with `None`. Takes effect from the next captured frame.
+    pub fn set_colour_space(&self, colour: Option<ColourSpace>) {
+        *self.colour.lock() = colour;
+    }
+
+    /// CPU time spent capturing and compressing this session's frames so
+    /// far.
+    pub fn cpu_times(&self) -> CpuTimes {
+        self.cpu.times()
     }
 
     /// Take a snapshot of encoding performance stats for this session.
@@ -298,14 +1165,14 @@ impl CaptureSession {
     }
 
     /// Watchdog: waits for the graph to start running, then checks that frames
-    /// arrive within `FRAME_TIMEOUT`. Fires `on_error` and stops the session
+    /// arrive within `FRAME_TIMEOUT`. Reports an error and stops the session
     /// if the camera produces no frames.
     fn run_watchdog(
         device_id: &str,
         buffer: &FrameBuffer,
         running: &AtomicBool,
         shutdown: &AtomicBool,
-        on_error: Option<&ErrorCallback>,
+        on_error: Option<&ErrorReporter>,
     ) {
         Self::run_watchdog_with_config(
             device_id,
@@ -323,7 +1190,7 @@ impl CaptureSession {
         buffer: &FrameBuffer,
         running: &AtomicBool,
         shutdown: &AtomicBool,
-        on_error: Option<&ErrorCallback>,
+        on_error: Option<&ErrorReporter>,
         config: WatchdogConfig,
     ) {
         let WatchdogConfig {
@@ -362,14 +1229,11 @@ impl CaptureSession {
                     "watchdog: no frames received within {}s for {device_id}",
                     frame_timeout.as_secs()
                 );
-                if let Some(cb) = on_error {
-                    cb(
-                        device_id,
-                        &format!(
-                            "Camera produces no frames ({}s timeout)",
-                            frame_timeout.as_secs()
-                        ),
-                    );
+                if let Some(reporter) = on_error {
+                    reporter.report(&format!(
+                        "Camera produces no frames ({}s timeout)",
+                        frame_timeout.as_secs()
+                    ));
                 }
                 running.store(false, Ordering::Relaxed);
                 return;
@@ -383,6 +1247,13 @@ impl CaptureSession {
         self.shutdown.store(true, Ordering::Relaxed);
         self.running.store(false, Ordering::Relaxed);
         if let Some(handle) = self.thread.take() {
+            // A graph that was mid-build, or rebuilding after a failure,
+            // sets `running` once it's up; keep clearing it until the
+            // thread notices
+            while !handle.is_finished() {
+                self.running.store(false, Ordering::Relaxed);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
             let _ = handle.join();
         }
         if let Some(handle) = self.watchdog.take() {
@@ -391,140 +1262,260 @@ impl CaptureSession {
         if let Some(mut worker) = self.encode_worker.take() {
             worker.stop();
         }
+        // No more frames will arrive; let streaming consumers finish
+        self.buffer.close_taps();
+        if let Some(export) = self.buffer.set_export(None) {
+            export.close();
+        }
     }
 }
 
 /// Canon live view capture session.
 ///
-/// Wraps a `LiveViewSession` that polls JPEG frames from the Canon SDK
-/// and pushes them directly into a `JpegFrameBuffer`. No encoding step
-/// is needed since Canon delivers JPEG natively.
+/// Live view runs as the capture graph of an ordinary [`CaptureSession`]
+/// (see [`live_view_runner`]), so its frames are buffered, encoded, watched
+/// and counted like any other camera's. The SDK and camera handle are kept
+/// for focus control while it runs.
 pub struct CanonCaptureSession {
-    device_id: String,
-    live_view: Option<LiveViewSession>,
-    jpeg_buffer: Arc<JpegFrameBuffer>,
-    /// Type-erased SDK reference for stopping the live view session.
-    /// Stored as a closure that calls `stop()` with the correct types.
-    stop_fn: Option<Box<dyn FnOnce(LiveViewSession) + Send>>,
+    capture: CaptureSession,
+    sdk: Arc<dyn EdsSdkApi>,
+    camera: CameraHandle,
 }
 
 impl CanonCaptureSession {
-    /// Create and start a Canon capture session for the given device.
+    /// Create and start a Canon capture session for the given device,
+    /// polling live view at `format`'s frame rate. The size is whatever the
+    /// camera's live view delivers.
     ///
     /// The caller must ensure that a camera session is already open (managed
-    /// by `CanonBackend::enumerate_devices`). This method only starts live
-    /// view and begins polling JPEG frames into the JPEG buffer.
+    /// by `CanonBackend::enumerate_devices`). Live view is started on the
+    /// capture thread; `on_error` is called if it can't be, or fails for
+    /// good, and `on_started` once the first frame's size is known.
+    #[allow(clippy::too_many_arguments)]
     pub fn new<S: EdsSdkApi + 'static>(
         device_id: String,
+        device_path: String,
         sdk: Arc<S>,
         camera: CameraHandle,
-    ) -> Result<Self, String> {
-        let jpeg_buffer = Arc::new(JpegFrameBuffer::new());
-
-        let live_view = LiveViewSession::start(Arc::clone(&sdk), camera, Arc::clone(&jpeg_buffer))
-            .map_err(|e| format!("failed to start Canon live view: {e}"))?;
-
-        tracing::info!("Started Canon live view for {device_id}");
-
-        // Capture the SDK and camera handle in a closure for clean shutdown
-        let stop_fn: Box<dyn FnOnce(LiveViewSession) + Send> =
-            Box::new(move |session: LiveViewSession| {
-                session.stop(&*sdk, camera);
-            });
-
-        Ok(Self {
+        format: PreviewFormat,
+        on_error: Option<ErrorCallback>,
+        on_started: Option<StartedCallback>,
+        jpeg_quality: u8,
+    ) -> Self {
+        let capture = CaptureSession::with_runner(
             device_id,
-            live_view: Some(live_view),
-            jpeg_buffer,
-            stop_fn: Some(stop_fn),
-        })
+            device_path,
+            String::new(),
+            format,
+            CAPTURE_RESTART,
+            on_error,
+            on_started,
+            jpeg_quality,
+            live_view_runner(Arc::clone(&sdk), camera),
+        );
+        Self {
+            capture,
+            sdk,
+            camera,
+        }
     }
 
-    /// Get a reference to the JPEG output buffer.
-    pub fn jpeg_buffer(&self) -> &Arc<JpegFrameBuffer> {
-        &self.jpeg_buffer
+    /// The capture session live view runs in.
+    pub fn capture(&self) -> &CaptureSession {
+        &self.capture
     }
 
-    /// Check if the live view session is currently running.
+    /// Check if live view is currently running.
     pub fn is_running(&self) -> bool {
-        self.live_view
-            .as_ref()
-            .map(|lv| lv.is_running())
-            .unwrap_or(false)
+        self.capture.is_running()
     }
 
     /// Return the device ID for this session.
     pub fn device_id(&self) -> &str {
-        &self.device_id
+        self.capture.device_id()
     }
 
-    /// Stop the Canon capture session. Idempotent.
-    pub fn stop(&mut self) {
-        if let (Some(live_view), Some(stop_fn)) = (self.live_view.take(), self.stop_fn.take()) {
-            stop_fn(live_view);
-            tracing::info!("Stopped Canon live view for {}", self.device_id);
+    /// Move the AF point to a normalised `[0, 1]` position in the delivered
+    /// (oriented) frame. Returns the zoom position written to the camera.
+    pub fn set_af_point(&self, x: f32, y: f32) -> Result<EdsPoint, String> {
+        self.ensure_live_view()?;
+        let (sx, sy) = self.capture.orientation().source_point(x, y);
+        focus::set_af_point(&*self.sdk, self.camera, sx, sy).map_err(|e| e.to_string())
+    }
+
+    /// Run one autofocus pass at the current AF point.
+    pub fn trigger_af(&self) -> Result<(), String> {
+        self.ensure_live_view()?;
+        focus::trigger_af(&*self.sdk, self.camera).map_err(|e| e.to_string())
+    }
+
+    fn ensure_live_view(&self) -> Result<(), String> {
+        if self.is_running() {
+            Ok(())
+        } else {
+            Err("live view is not active — start the preview first".to_string())
         }
     }
+
+    /// Stop live view and the capture session. Idempotent.
+    pub fn stop(&mut self) {
+        self.capture.stop();
+    }
 }
 
 /// Unified preview session wrapping either a DirectShow or Canon capture.
 ///
 /// The preview commands layer stores these in the sessions map so that
-/// `get_frame` can read JPEG data regardless of the capture backend.
+/// `get_frame` can read JPEG data regardless of the capture backend. Both
+/// run in a [`CaptureSession`], so frames, stats and encoding are the same
+/// for either; only how the device is addressed and controlled differs.
 pub enum PreviewSession {
-    /// DirectShow capture session (RGB frames encoded to JPEG).
+    /// DirectShow capture session.
     DirectShow(CaptureSession),
-    /// Canon live view session (native JPEG passthrough).
+    /// Canon live view session.
     Canon(CanonCaptureSession),
 }
 
 impl PreviewSession {
-    /// Get the JPEG buffer for this session, if available.
-    pub fn jpeg_buffer(&self) -> Option<&Arc<JpegFrameBuffer>> {
+    /// The capture session behind either kind.
+    fn capture(&self) -> &CaptureSession {
         match self {
-            Self::DirectShow(session) => session.jpeg_buffer(),
-            Self::Canon(session) => Some(session.jpeg_buffer()),
+            Self::DirectShow(session) => session,
+            Self::Canon(session) => session.capture(),
         }
     }
 
-    /// Get the raw frame buffer (DirectShow only).
-    pub fn buffer(&self) -> Option<&Arc<FrameBuffer>> {
-        match self {
-            Self::DirectShow(session) => Some(session.buffer()),
-            Self::Canon(_) => None,
-        }
+    /// Get the JPEG buffer for this session, if available.
+    pub fn jpeg_buffer(&self) -> Option<&Arc<JpegFrameBuffer>> {
+        self.capture().jpeg_buffer()
+    }
+
+    /// Get the raw frame buffer.
+    pub fn buffer(&self) -> &Arc<FrameBuffer> {
+        self.capture().buffer()
     }
 
     /// Check if the session is running.
     pub fn is_running(&self) -> bool {
-        match self {
-            Self::DirectShow(session) => session.is_running(),
-            Self::Canon(session) => session.is_running(),
-        }
+        self.capture().is_running()
+    }
+
+    /// Whether the session stopped on an error.
+    pub fn is_failed(&self) -> bool {
+        self.capture().is_failed()
+    }
+
+    /// Time since the session last delivered a frame.
+    pub fn since_last_frame(&self) -> Option<std::time::Duration> {
+        self.capture().since_last_frame()
+    }
+
+    /// CPU time spent on the session's frames so far.
+    pub fn cpu_times(&self) -> CpuTimes {
+        self.capture().cpu_times()
+    }
+
+    /// Bytes of frames held by the session's raw and JPEG buffers.
+    pub fn buffered_bytes(&self) -> usize {
+        let jpeg = self.jpeg_buffer().map_or(0, |buffer| buffer.bytes());
+        self.buffer().bytes() + jpeg
     }
 
     /// Return the device ID for this session.
     pub fn device_id(&self) -> &str {
+        self.capture().device_id()
+    }
+
+    /// Device path the session captures from. Canon sessions are addressed
+    /// by handle and report `None`.
+    pub fn device_path(&self) -> Option<&str> {
         match self {
-            Self::DirectShow(session) => session.device_id(),
-            Self::Canon(session) => session.device_id(),
+            Self::DirectShow(session) => Some(session.device_path()),
+            Self::Canon(_) => None,
+        }
+    }
+
+    /// Video sources the session's device offers. Canon live view has one
+    /// and reports none.
+    pub fn video_sources(&self) -> Vec<VideoSource> {
+        self.capture().video_sources()
+    }
+
+    /// What this session was started for. Canon live view is always `Full`.
+    pub fn mode(&self) -> SessionMode {
+        self.capture().mode()
+    }
+
+    /// Orientation currently applied to delivered frames.
+    pub fn orientation(&self) -> Orientation {
+        self.capture().orientation()
+    }
+
+    /// Change the orientation applied to delivered frames.
+    pub fn set_orientation(&self, orientation: Orientation) {
+        self.capture().set_orientation(orientation);
+    }
+
+    /// Digital zoom crop currently applied to delivered frames.
+    pub fn crop(&self) -> CropRect {
+        self.capture().crop()
+    }
+
+    /// Change the digital zoom crop.
+    pub fn set_crop(&self, crop: CropRect) {
+        self.capture().set_crop(crop);
+    }
+
+    /// Change sharpening and denoise.
+    pub fn set_post_processing(&self, settings: PostProcessing) {
+        self.capture().set_post_processing(settings);
+    }
+
+    /// Override how YUV frames are decoded, from the next captured frame.
+    /// Canon sessions keep it like any other, but live view delivers JPEG,
+    /// so it makes no difference to their frames.
+    pub fn set_colour_space(&self, colour: Option<ColourSpace>) {
+        self.capture().set_colour_space(colour);
+    }
+
+    /// Check whether the session has delivered its first frame.
+    pub fn probe_first_frame(&self) -> FrameProbe {
+        let session = self.capture();
+        if session.is_failed() {
+            let message = session
+                .last_error()
+                .unwrap_or_else(|| "capture session failed".to_string());
+            return FrameProbe::Failed(message);
         }
+        probe_frame_buffer(session.buffer())
+    }
+
+    /// Report that `consumer` has used the raw frame with `sequence`.
+    pub fn record_consumed(&self, consumer: &'static str, sequence: u64) {
+        self.buffer().record_consumed(consumer, sequence);
+    }
+
+    /// Count a still written to disk from this session.
+    pub fn record_snapshot(&self) {
+        self.capture().record_snapshot();
+    }
+
+    /// Produced / consumed / overwritten-unseen counts per consumer.
+    pub fn delivery(&self) -> Vec<FrameDelivery> {
+        self.buffer().delivery()
     }
 
-    /// Take a snapshot of diagnostic stats (DirectShow only).
+    /// Take a snapshot of diagnostic stats, with frame delivery.
     pub fn diagnostics(&self) -> DiagnosticSnapshot {
-        match self {
-            Self::DirectShow(session) => session.diagnostics(),
-            Self::Canon(_) => DiagnosticSnapshot::default(),
+        DiagnosticSnapshot {
+            frame_delivery: self.delivery(),
+            ..self.capture().diagnostics()
         }
     }
 
     /// Take a snapshot of encoding stats.
     pub fn encoding_snapshot(&self) -> Option<EncodingSnapshot> {
-        match self {
-            Self::DirectShow(session) => session.encoding_snapshot(),
-            Self::Canon(_) => None,
-        }
+        self.capture().encoding_snapshot()
     }
 
     /// Stop the session. Idempotent.
@@ -546,6 +1537,7 @@ mod tests {
             width: 10,
             height: 10,
             timestamp_us: timestamp,
+            device_timestamp_us: timestamp,
         }
     }
 
@@ -580,6 +1572,84 @@ mod tests {
         assert_eq!(latest.timestamp_us, 400);
     }
 
+    #[test]
+    fn frame_buffer_bytes_count_the_frames_in_the_ring() {
+        let buf = FrameBuffer::new(2);
+        assert_eq!(buf.bytes(), 0);
+        buf.push(make_frame(1, 100));
+        assert_eq!(buf.bytes(), 100);
+        buf.push(make_frame(2, 200));
+        buf.push(make_frame(3, 300));
+        assert_eq!(buf.bytes(), 200);
+    }
+
+    #[test]
+    fn frame_buffer_push_writes_to_the_export_until_removed() {
+        let buf = FrameBuffer::new(2);
+        let export = Arc::new(ShmExport::create("cam", 2, 2).unwrap());
+        assert!(buf.set_export(Some(Arc::clone(&export))).is_none());
+
+        let frame = |value| Frame {
+            data: vec![value; 12],
+            width: 2,
+            height: 2,
+            timestamp_us: 0,
+            device_timestamp_us: 0,
+        };
+        buf.push(frame(1));
+        buf.push(frame(2));
+        assert_eq!(export.frames_written(), 2);
+
+        assert!(buf.set_export(None).is_some());
+        buf.push(frame(3));
+        assert_eq!(export.frames_written(), 2);
+    }
+
+    #[test]
+    fn frame_buffer_push_feeds_taps_beyond_ring_capacity() {
+        let buf = FrameBuffer::new(3);
+        let tap = buf.register_tap(8);
+        for n in 1..=5 {
+            buf.push(make_frame(n as u8, n * 100));
+        }
+        assert!(buf.unregister_tap(tap.id()));
+        buf.push(make_frame(6, 600));
+
+        // The ring only kept three, but the tap saw all five before unregistering
+        let received: Vec<u64> = std::iter::from_fn(|| tap.try_recv())
+            .map(|f| f.timestamp_us)
+            .collect();
+        assert_eq!(received, vec![100, 200, 300, 400, 500]);
+        assert!(buf.tap_overflows().is_empty());
+    }
+
+    #[test]
+    fn frame_buffer_taps_churn_while_frames_are_pushed() {
+        let buf = Arc::new(FrameBuffer::new(3));
+        let producer = {
+            let buf = Arc::clone(&buf);
+            std::thread::spawn(move || {
+                for n in 1..=10_000 {
+                    buf.push(make_frame(0, n));
+                }
+            })
+        };
+
+        while !producer.is_finished() {
+            let tap = buf.register_tap(64);
+            std::thread::yield_now();
+            buf.unregister_tap(tap.id());
+            let received: Vec<u64> = std::iter::from_fn(|| tap.try_recv())
+                .map(|f| f.timestamp_us)
+                .collect();
+            assert!(received.len() <= 64);
+            assert!(received.windows(2).all(|w| w[1] == w[0] + 1));
+            assert!(tap.try_recv().is_none(), "no frame after unregister");
+        }
+        producer.join().unwrap();
+        assert!(buf.tap_overflows().is_empty());
+    }
+
     #[test]
     fn frame_buffer_is_send_and_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -599,15 +1669,252 @@ mod tests {
         assert_eq!(a.data[0], 42);
     }
 
+    #[test]
+    fn frame_buffer_push_returns_sequence_and_latest_pairs_it() {
+        let buf = FrameBuffer::new(3);
+        assert!(buf.latest_with_sequence().is_none());
+        assert_eq!(buf.push(make_frame(1, 100)), 1);
+        assert_eq!(buf.push(make_frame(2, 200)), 2);
+
+        let (frame, seq) = buf.latest_with_sequence().unwrap();
+        assert_eq!((frame.data[0], seq), (2, 2));
+    }
+
+    #[test]
+    fn frame_buffer_without_slots_still_counts_frames() {
+        let buf = FrameBuffer::new(0);
+        assert_eq!(buf.push(make_frame(1, 100)), 1);
+        assert_eq!(buf.sequence(), 1);
+        assert!(buf.latest().is_none());
+    }
+
+    #[test]
+    fn frame_buffer_readers_never_see_a_frame_behind_the_sequence() {
+        const CAPACITY: usize = 3;
+        const FRAMES: u64 = 50_000;
+        let buf = Arc::new(FrameBuffer::new(CAPACITY));
+        // Each frame's timestamp is the sequence number push will give it
+        let producer = {
+            let buf = Arc::clone(&buf);
+            std::thread::spawn(move || {
+                for n in 1..=FRAMES {
+                    assert_eq!(buf.push(make_frame(0, n)), n);
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let buf = Arc::clone(&buf);
+                std::thread::spawn(move || {
+                    let mut newest = 0;
+                    while newest < FRAMES {
+                        let published = buf.sequence();
+                        let Some(frame) = buf.latest() else {
+                            assert_eq!(published, 0, "no frame after {published} pushes");
+                            continue;
+                        };
+                        // At least as new as the sequence read before it,
+                        // let alone the oldest frame still in the ring
+                        assert!(
+                            frame.timestamp_us >= published,
+                            "frame {} behind sequence {published}",
+                            frame.timestamp_us
+                        );
+                        assert!(frame.timestamp_us + CAPACITY as u64 > published);
+                        assert!(frame.timestamp_us >= newest, "latest went backwards");
+                        newest = frame.timestamp_us;
+
+                        let (frame, seq) = buf.latest_with_sequence().unwrap();
+                        assert_eq!(frame.timestamp_us, seq);
+                    }
+                })
+            })
+            .collect();
+
+        producer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(buf.sequence(), FRAMES);
+    }
+
+    /// A frame of `value`s in a buffer taken from `buf`'s pool.
+    fn pooled_frame(buf: &FrameBuffer, value: u8, timestamp: u64) -> Frame {
+        let mut data = buf.pool().take(100);
+        data.resize(100, value);
+        Frame {
+            data,
+            ..make_frame(value, timestamp)
+        }
+    }
+
+    #[test]
+    fn frame_buffer_reuses_buffers_once_their_frames_are_dropped() {
+        let buf = FrameBuffer::new(2);
+        buf.push(pooled_frame(&buf, 1, 100));
+        let held = buf.latest().unwrap();
+        let ptr = held.data.as_ptr();
+
+        // Overwritten while held: not reused yet
+        buf.push(pooled_frame(&buf, 2, 200));
+        buf.push(pooled_frame(&buf, 3, 300));
+        assert_eq!(buf.pool().lent_frames(), 1);
+        assert_eq!(held.data, vec![1; 100]);
+
+        drop(held);
+        let reused = pooled_frame(&buf, 4, 400);
+        assert_eq!(reused.data.as_ptr(), ptr);
+        buf.push(reused);
+        assert_eq!(buf.latest().unwrap().data, vec![4; 100]);
+    }
+
+    #[test]
+    fn frame_buffer_pool_is_bounded() {
+        let buf = FrameBuffer::new(2);
+        for n in 0..50 {
+            buf.push(make_frame(n, u64::from(n)));
+        }
+        assert!(buf.pool().free_buffers() <= crate::preview::pool::DEFAULT_MAX_BUFFERS);
+
+        // Steady state: one buffer in, one out, per frame
+        for n in 0..50 {
+            buf.push(pooled_frame(&buf, n, u64::from(n)));
+        }
+        assert!(buf.pool().free_buffers() <= crate::preview::pool::DEFAULT_MAX_BUFFERS);
+        assert_eq!(buf.pool().lent_frames(), 0);
+    }
+
+    #[test]
+    fn frame_buffer_readers_never_see_a_recycled_buffer_change() {
+        const FRAMES: u64 = 20_000;
+        let buf = Arc::new(FrameBuffer::new(3));
+        let producer = {
+            let buf = Arc::clone(&buf);
+            std::thread::spawn(move || {
+                for n in 1..=FRAMES {
+                    buf.push(pooled_frame(&buf, (n % 251) as u8, n));
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let buf = Arc::clone(&buf);
+                std::thread::spawn(move || {
+                    while buf.sequence() < FRAMES {
+                        let Some(frame) = buf.latest() else {
+                            continue;
+                        };
+                        let value = (frame.timestamp_us % 251) as u8;
+                        // Hold it across a few pushes
+                        std::thread::yield_now();
+                        assert!(
+                            frame.data.iter().all(|&b| b == value),
+                            "frame {} changed while held",
+                            frame.timestamp_us
+                        );
+                    }
+                })
+            })
+            .collect();
+
+        producer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert!(buf.pool().free_buffers() <= crate::preview::pool::DEFAULT_MAX_BUFFERS);
+    }
+
+    /// Simulate a consumer reading whatever frame is latest.
+    fn poll_latest(buf: &FrameBuffer, consumer: &'static str) {
+        let (_, seq) = buf.latest_with_sequence().unwrap();
+        buf.record_consumed(consumer, seq);
+    }
+
+    #[test]
+    fn frame_buffer_accounts_each_consumer_independently() {
+        let buf = FrameBuffer::new(3);
+        for i in 1..=30u64 {
+            buf.push(make_frame(i as u8, i));
+            // Keeps up with every frame
+            poll_latest(&buf, "fast");
+            // Polls at half rate, like 15Hz against a 30fps camera
+            if i % 2 == 0 {
+                poll_latest(&buf, "half");
+            }
+            // Polls once per 10 frames, well past a ring wrap
+            if i % 10 == 0 {
+                poll_latest(&buf, "slow");
+            }
+        }
+
+        let delivery = buf.delivery();
+        let names: Vec<_> = delivery.iter().map(|d| d.consumer.as_str()).collect();
+        assert_eq!(names, ["fast", "half", "slow"]);
+        assert!(delivery.iter().all(|d| d.produced == 30));
+
+        let counts: Vec<_> = delivery
+            .iter()
+            .map(|d| (d.consumed, d.overwritten_unseen))
+            .collect();
+        // Frames 1..=27 have been overwritten. `half` missed the 14 odd ones
+        // among them (1, 3, ..., 27); `slow` missed all but 10 and 20.
+        assert_eq!(counts, [(30, 0), (15, 14), (3, 25)]);
+        assert!((delivery[1].seen_percent - 50.0).abs() < 1e-9);
+        assert!((delivery[2].seen_percent - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn frame_buffer_repeat_polls_of_same_frame_count_once() {
+        let buf = FrameBuffer::new(3);
+        buf.push(make_frame(1, 100));
+        for _ in 0..5 {
+            poll_latest(&buf, "preview");
+        }
+        buf.push(make_frame(2, 200));
+        buf.push(make_frame(3, 300));
+        buf.push(make_frame(4, 400));
+
+        let d = &buf.delivery()[0];
+        assert_eq!((d.produced, d.consumed, d.overwritten_unseen), (4, 1, 0));
+    }
+
+    #[test]
+    fn frame_buffer_unseen_frames_count_only_after_wrap() {
+        let buf = FrameBuffer::new(3);
+        buf.push(make_frame(1, 100));
+        poll_latest(&buf, "preview");
+        buf.push(make_frame(2, 200));
+        buf.push(make_frame(3, 300));
+
+        // 2 and 3 are unseen but still buffered
+        assert_eq!(buf.delivery()[0].overwritten_unseen, 0);
+
+        buf.push(make_frame(4, 400)); // evicts 1 (seen)
+        assert_eq!(buf.delivery()[0].overwritten_unseen, 0);
+        buf.push(make_frame(5, 500)); // evicts 2 (unseen)
+        buf.push(make_frame(6, 600)); // evicts 3 (unseen)
+        assert_eq!(buf.delivery()[0].overwritten_unseen, 2);
+    }
+
     #[test]
     fn capture_session_can_be_created() {
         let session = CaptureSession::new(
             "test-device".to_string(),
             String::new(),
+            String::new(),
             1920,
             1080,
             30.0,
             None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
             None,
             75,
         );
@@ -615,15 +1922,52 @@ mod tests {
         assert!(session.buffer().latest().is_none());
     }
 
+    #[test]
+    fn thumbnail_only_session_keeps_one_frame_and_no_encoder() {
+        let session = CaptureSession::new(
+            "test-device".to_string(),
+            String::new(),
+            String::new(),
+            640,
+            480,
+            30.0,
+            None,
+            SessionMode::ThumbnailOnly,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
+            None,
+            75,
+        );
+        assert_eq!(session.mode(), SessionMode::ThumbnailOnly);
+        assert!(session.jpeg_buffer().is_none());
+        assert!(session.encoding_snapshot().is_none());
+        assert_eq!(
+            PreviewSession::DirectShow(session).mode(),
+            SessionMode::ThumbnailOnly
+        );
+    }
+
     #[test]
     fn capture_session_stop_is_idempotent() {
         let mut session = CaptureSession::new(
             "test-device".to_string(),
             String::new(),
+            String::new(),
             640,
             480,
             30.0,
             None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
             None,
             75,
         );
@@ -632,15 +1976,188 @@ mod tests {
         assert!(!session.is_running());
     }
 
+    fn seed(bytes: &[u8], source_sequence: u64) -> JpegFrame {
+        JpegFrame {
+            jpeg_bytes: bytes.to_vec(),
+            width: 160,
+            height: 120,
+            encoder_kind: crate::preview::mf_jpeg::encoder::EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence,
+        }
+    }
+
+    #[test]
+    fn seed_frame_is_served_until_the_session_encodes() {
+        let session = CaptureSession::new(
+            "test-device".to_string(),
+            String::new(),
+            String::new(),
+            640,
+            480,
+            30.0,
+            None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
+            None,
+            75,
+        );
+        session.seed_frame(&seed(&[0xFF, 0xD8, 1], 42));
+
+        let jpeg = session.jpeg_buffer().unwrap();
+        let seeded = jpeg.latest().unwrap();
+        assert_eq!(seeded.jpeg_bytes, [0xFF, 0xD8, 1]);
+        assert_eq!(seeded.width, 160);
+        // The old session's raw sequence means nothing to this one
+        assert_eq!(seeded.source_sequence, 0);
+        // Raw capture hasn't started, so first-frame checks still wait
+        assert_eq!(session.buffer().sequence(), 0);
+
+        // A second seed doesn't replace output the session already has
+        session.seed_frame(&seed(&[0xFF, 0xD8, 2], 0));
+        assert_eq!(jpeg.latest().unwrap().jpeg_bytes, [0xFF, 0xD8, 1]);
+    }
+
     #[test]
     fn preview_error_payload_serialises_correctly() {
-        let payload = PreviewErrorPayload {
-            device_id: "test-device".to_string(),
-            error: "capture graph failed: 0x800705AA".to_string(),
-        };
+        let payload = PreviewErrorPayload::enriched(
+            "test-device",
+            "capture graph failed: 0x800705AA",
+            Some(ErrorStats {
+                frames_delivered: 2_000_000,
+                uptime: std::time::Duration::from_secs(3600),
+                since_last_frame: Some(std::time::Duration::from_millis(40)),
+            }),
+            ErrorSessionInfo {
+                format: Some("1920x1080".to_string()),
+                error_ordinal: Some(2),
+            },
+        );
         let json = serde_json::to_value(&payload).unwrap();
         assert_eq!(json["deviceId"], "test-device");
-        assert_eq!(json["error"], "capture graph failed: 0x800705AA");
+        assert_eq!(json["error"]["code"], "device_busy");
+        assert_eq!(
+            json["error"]["message"],
+            "Camera is in use by another application"
+        );
+        assert_eq!(json["framesDelivered"], 2_000_000);
+        assert_eq!(json["sessionUptimeMs"], 3_600_000);
+        assert_eq!(json["lastFrameAgeMs"], 40);
+        assert_eq!(json["format"], "1920x1080");
+        assert_eq!(json["errorOrdinal"], 2);
+    }
+
+    #[test]
+    fn preview_started_payload_serialises_correctly() {
+        let payload = PreviewStartedPayload::new(
+            "test-device",
+            NegotiatedFormat {
+                width: 1920,
+                height: 1080,
+                pixel_format: "MJPG".to_string(),
+            },
+        );
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "deviceId": "test-device",
+                "width": 1920,
+                "height": 1080,
+                "pixelFormat": "MJPG",
+            })
+        );
+    }
+
+    #[test]
+    fn sessions_report_no_negotiated_format_before_the_graph_connects() {
+        let session = CaptureSession::new(
+            "test-device".to_string(),
+            String::new(),
+            String::new(),
+            640,
+            480,
+            30.0,
+            None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
+            None,
+            75,
+        );
+        assert_eq!(session.negotiated_format(), None);
+        assert_eq!(session.diagnostics().negotiated_format, None);
+    }
+
+    #[test]
+    fn preview_error_payload_without_context_keeps_the_original_shape() {
+        let payload = PreviewErrorPayload::enriched(
+            "test-device",
+            "0x800700AA",
+            None,
+            ErrorSessionInfo::default(),
+        );
+        assert_eq!(payload.frames_delivered, None);
+        assert_eq!(payload.session_uptime_ms, None);
+        assert_eq!(payload.last_frame_age_ms, None);
+        assert_eq!(payload.format, None);
+        assert_eq!(payload.error_ordinal, None);
+        // The error is coded like every other surfaced camera error
+        assert_eq!(
+            payload.error,
+            AppError::classify(code::PREVIEW_FAILED, "0x800700AA")
+        );
+
+        let json = serde_json::to_value(&payload).unwrap();
+        let keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(keys, vec!["deviceId", "error"]);
+    }
+
+    #[test]
+    fn preview_error_payload_omits_frame_age_before_the_first_frame() {
+        let stats = ErrorStats::read(&DiagnosticStats::new());
+        let payload = PreviewErrorPayload::enriched(
+            "test-device",
+            "boom",
+            Some(stats),
+            ErrorSessionInfo::default(),
+        );
+        assert_eq!(payload.frames_delivered, Some(0));
+        assert!(payload.session_uptime_ms.is_some());
+        assert_eq!(payload.last_frame_age_ms, None);
+    }
+
+    #[test]
+    fn reporter_numbers_errors_and_reads_the_delivered_format() {
+        let buffer = Arc::new(FrameBuffer::new(3));
+        let payloads = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&payloads);
+        let reporter = reporter(&buffer, move |payload| sink.lock().push(payload));
+
+        reporter.report("first");
+        buffer.push(make_frame(1, 100));
+        reporter.report("second");
+
+        let payloads = payloads.lock();
+        assert_eq!(payloads[0].error_ordinal, Some(1));
+        assert_eq!(payloads[0].format, None);
+        assert_eq!(payloads[0].frames_delivered, Some(0));
+        assert_eq!(payloads[1].error_ordinal, Some(2));
+        assert_eq!(payloads[1].device_id, "test");
+        let frame = buffer.latest().unwrap();
+        assert_eq!(
+            payloads[1].format.as_deref(),
+            Some(format!("{}x{}", frame.width, frame.height).as_str())
+        );
     }
 
     #[test]
@@ -653,17 +2170,25 @@ mod tests {
     fn capture_session_with_error_callback() {
         let called = Arc::new(std::sync::atomic::AtomicBool::new(false));
         let called_clone = Arc::clone(&called);
-        let on_error: ErrorCallback = Arc::new(move |_device_id, _error| {
+        let on_error: ErrorCallback = Arc::new(move |_payload| {
             called_clone.store(true, Ordering::Relaxed);
         });
         let session = CaptureSession::new(
             "test-device".to_string(),
             String::new(),
+            String::new(),
             640,
             480,
             30.0,
+            None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
             Some(on_error),
             None,
+            None,
+            None,
+            None,
             75,
         );
         // On non-Windows, no capture thread spawns, so callback won't fire
@@ -671,6 +2196,19 @@ mod tests {
         assert!(!session.is_running());
     }
 
+    /// Reporter for a test session with fresh stats.
+    fn reporter(
+        buffer: &Arc<FrameBuffer>,
+        callback: impl Fn(PreviewErrorPayload) + Send + Sync + 'static,
+    ) -> ErrorReporter {
+        ErrorReporter::new(
+            "test".to_string(),
+            Arc::new(callback),
+            Arc::new(Mutex::new(DiagnosticStats::new())),
+            Arc::clone(buffer),
+        )
+    }
+
     /// Short durations for watchdog tests — keeps tests under 200ms.
     fn fast_watchdog() -> WatchdogConfig {
         WatchdogConfig {
@@ -682,12 +2220,12 @@ mod tests {
 
     #[test]
     fn watchdog_does_not_fire_when_frames_arrive() {
-        let buffer = FrameBuffer::new(3);
+        let buffer = Arc::new(FrameBuffer::new(3));
         let running = AtomicBool::new(true);
         let shutdown = AtomicBool::new(false);
         let called = Arc::new(AtomicBool::new(false));
         let called_clone = Arc::clone(&called);
-        let on_error: ErrorCallback = Arc::new(move |_, _| {
+        let on_error = reporter(&buffer, move |_| {
             called_clone.store(true, Ordering::Relaxed);
         });
 
@@ -709,12 +2247,12 @@ mod tests {
 
     #[test]
     fn watchdog_fires_when_no_frames_arrive() {
-        let buffer = FrameBuffer::new(3);
+        let buffer = Arc::new(FrameBuffer::new(3));
         let running = AtomicBool::new(true);
         let shutdown = AtomicBool::new(false);
         let called = Arc::new(AtomicBool::new(false));
         let called_clone = Arc::clone(&called);
-        let on_error: ErrorCallback = Arc::new(move |_, _| {
+        let on_error = reporter(&buffer, move |_| {
             called_clone.store(true, Ordering::Relaxed);
         });
 
@@ -733,12 +2271,12 @@ mod tests {
 
     #[test]
     fn watchdog_exits_early_when_shutdown_signalled() {
-        let buffer = FrameBuffer::new(3);
+        let buffer = Arc::new(FrameBuffer::new(3));
         let running = AtomicBool::new(false);
         let shutdown = AtomicBool::new(true);
         let called = Arc::new(AtomicBool::new(false));
         let called_clone = Arc::clone(&called);
-        let on_error: ErrorCallback = Arc::new(move |_, _| {
+        let on_error = reporter(&buffer, move |_| {
             called_clone.store(true, Ordering::Relaxed);
         });
 
@@ -756,12 +2294,12 @@ mod tests {
 
     #[test]
     fn watchdog_exits_if_graph_never_starts() {
-        let buffer = FrameBuffer::new(3);
+        let buffer = Arc::new(FrameBuffer::new(3));
         let running = AtomicBool::new(false);
         let shutdown = AtomicBool::new(false);
         let called = Arc::new(AtomicBool::new(false));
         let called_clone = Arc::clone(&called);
-        let on_error: ErrorCallback = Arc::new(move |_, _| {
+        let on_error = reporter(&buffer, move |_| {
             called_clone.store(true, Ordering::Relaxed);
         });
 
@@ -777,4 +2315,308 @@ mod tests {
         // Graph never ran — watchdog exits via startup timeout, not via error
         assert!(!called.load(Ordering::Relaxed));
     }
+
+    fn fast_restart(max_attempts: u32) -> RestartPolicy {
+        RestartPolicy {
+            max_attempts,
+            initial_backoff: std::time::Duration::from_millis(1),
+            max_backoff: std::time::Duration::from_millis(5),
+        }
+    }
+
+    /// Stand-in for a graph that starts and runs until the session stops.
+    fn run_until_stopped(ctx: &GraphContext) -> Result<(), String> {
+        ctx.running.store(true, Ordering::Relaxed);
+        while ctx.running.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    /// Poll `condition` for up to two seconds.
+    fn wait_for(condition: impl Fn() -> bool) -> bool {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while std::time::Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        false
+    }
+
+    type Recorded<T> = Arc<Mutex<Vec<T>>>;
+
+    /// Session capturing with `runner`, recording the error and recovering
+    /// payloads it reports.
+    fn session_with_runner(
+        restart: RestartPolicy,
+        runner: impl Fn(&GraphContext) -> Result<(), String> + Send + Sync + 'static,
+    ) -> (
+        CaptureSession,
+        Recorded<PreviewErrorPayload>,
+        Recorded<PreviewRecoveringPayload>,
+    ) {
+        let errors: Recorded<PreviewErrorPayload> = Arc::default();
+        let recovering: Recorded<PreviewRecoveringPayload> = Arc::default();
+        let on_error: ErrorCallback = {
+            let errors = Arc::clone(&errors);
+            Arc::new(move |payload| errors.lock().push(payload))
+        };
+        let on_recovering: RecoveringCallback = {
+            let recovering = Arc::clone(&recovering);
+            Arc::new(move |payload| recovering.lock().push(payload))
+        };
+        let session = CaptureSession::start(
+            "test-device".to_string(),
+            String::new(),
+            String::new(),
+            640,
+            480,
+            30.0,
+            None,
+            SessionMode::ThumbnailOnly,
+            None,
+            restart,
+            Some(on_error),
+            None,
+            None,
+            Some(on_recovering),
+            None,
+            75,
+            Some(Arc::new(runner)),
+        );
+        (session, errors, recovering)
+    }
+
+    #[test]
+    fn failed_graph_is_restarted_until_it_starts() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&attempts);
+        let (mut session, errors, recovering) = session_with_runner(fast_restart(4), move |ctx| {
+            if counted.fetch_add(1, Ordering::Relaxed) < 2 {
+                return Err("device busy".to_string());
+            }
+            run_until_stopped(ctx)
+        });
+
+        assert!(wait_for(|| session.is_running()));
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        let retries: Vec<_> = recovering
+            .lock()
+            .iter()
+            .map(|p| (p.attempt, p.max_attempts))
+            .collect();
+        assert_eq!(retries, vec![(2, 4), (3, 4)]);
+        assert!(!session.is_failed());
+
+        session.stop();
+        assert!(!session.is_running());
+        assert!(errors.lock().is_empty());
+    }
+
+    #[test]
+    fn error_is_reported_once_restarts_are_exhausted() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&attempts);
+        let (mut session, errors, recovering) = session_with_runner(fast_restart(3), move |_| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            Err("device lost".to_string())
+        });
+
+        assert!(wait_for(|| session.is_failed()));
+        session.stop();
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        assert_eq!(recovering.lock().len(), 2);
+        assert_eq!(errors.lock().len(), 1);
+        assert_eq!(errors.lock()[0].error_ordinal, Some(1));
+        assert_eq!(session.last_error().as_deref(), Some("device lost"));
+    }
+
+    #[test]
+    fn attempts_start_again_once_a_restarted_graph_delivers_frames() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&attempts);
+        let (mut session, errors, recovering) = session_with_runner(fast_restart(3), move |ctx| {
+            match counted.fetch_add(1, Ordering::Relaxed) {
+                0 => Err("device busy".to_string()),
+                // Recovers and runs for a while before the device drops out
+                1 => {
+                    ctx.running.store(true, Ordering::Relaxed);
+                    ctx.buffer.push(make_frame(1, 100));
+                    Err("device lost".to_string())
+                }
+                _ => Err("device lost".to_string()),
+            }
+        });
+
+        assert!(wait_for(|| session.is_failed()));
+        session.stop();
+        // The first failure, then a full run of attempts from the one that
+        // delivered frames
+        assert_eq!(attempts.load(Ordering::Relaxed), 1 + 3);
+        let retries: Vec<_> = recovering.lock().iter().map(|p| p.attempt).collect();
+        assert_eq!(retries, vec![2, 2, 3]);
+        assert_eq!(errors.lock().len(), 1);
+    }
+
+    #[test]
+    fn panicking_graph_is_restarted() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&attempts);
+        let (mut session, errors, recovering) = session_with_runner(fast_restart(2), move |ctx| {
+            if counted.fetch_add(1, Ordering::Relaxed) == 0 {
+                panic!("callback blew up");
+            }
+            run_until_stopped(ctx)
+        });
+
+        assert!(wait_for(|| session.is_running()));
+        session.stop();
+        assert_eq!(recovering.lock().len(), 1);
+        assert!(errors.lock().is_empty());
+        assert!(!session.is_failed());
+    }
+
+    #[test]
+    fn stop_during_restart_backoff_returns_promptly_without_an_error() {
+        let restart = RestartPolicy {
+            max_attempts: 3,
+            initial_backoff: std::time::Duration::from_secs(60),
+            max_backoff: std::time::Duration::from_secs(60),
+        };
+        let (mut session, errors, recovering) =
+            session_with_runner(restart, |_| Err("device busy".to_string()));
+        assert!(wait_for(|| !recovering.lock().is_empty()));
+        assert_eq!(recovering.lock()[0].retry_in_ms, 60_000);
+
+        let started = std::time::Instant::now();
+        session.stop();
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(errors.lock().is_empty());
+        assert!(!session.is_failed());
+    }
+
+    #[test]
+    fn stop_wins_against_a_graph_that_starts_late() {
+        let (mut session, errors, _) = session_with_runner(fast_restart(1), |ctx| {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            run_until_stopped(ctx)
+        });
+        // Stopped before the graph is up; it must still see the stop
+        session.stop();
+        assert!(!session.is_running());
+        assert!(errors.lock().is_empty());
+    }
+
+    #[test]
+    fn preview_recovering_payload_serialises_correctly() {
+        let payload = PreviewRecoveringPayload {
+            device_id: "test-device".to_string(),
+            attempt: 2,
+            max_attempts: 4,
+            error: AppError::new(code::PREVIEW_FAILED, "device busy"),
+            retry_in_ms: 500,
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "deviceId": "test-device",
+                "attempt": 2,
+                "maxAttempts": 4,
+                "error": { "code": code::PREVIEW_FAILED, "message": "device busy" },
+                "retryInMs": 500,
+            })
+        );
+    }
+
+    #[test]
+    fn poll_first_frame_ready_when_frame_pushed_from_another_thread() {
+        let buffer = Arc::new(FrameBuffer::new(3));
+        let producer = {
+            let buffer = Arc::clone(&buffer);
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(30));
+                buffer.push(make_frame(1, 100));
+            })
+        };
+
+        let outcome = poll_first_frame(
+            || probe_frame_buffer(&buffer),
+            std::time::Duration::from_secs(2),
+            std::time::Duration::from_millis(5),
+        );
+        producer.join().unwrap();
+
+        assert_eq!(
+            outcome,
+            FirstFrameOutcome::Ready {
+                width: 10,
+                height: 10
+            }
+        );
+    }
+
+    #[test]
+    fn poll_first_frame_times_out_without_frames() {
+        let buffer = FrameBuffer::new(3);
+        let outcome = poll_first_frame(
+            || probe_frame_buffer(&buffer),
+            std::time::Duration::from_millis(30),
+            std::time::Duration::from_millis(5),
+        );
+        assert_eq!(outcome, FirstFrameOutcome::Timeout);
+    }
+
+    #[test]
+    fn poll_first_frame_reports_session_error_when_flag_flips() {
+        let buffer = FrameBuffer::new(3);
+        let failed = Arc::new(AtomicBool::new(false));
+        let flipper = {
+            let failed = Arc::clone(&failed);
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(30));
+                failed.store(true, Ordering::Relaxed);
+            })
+        };
+
+        let outcome = poll_first_frame(
+            || {
+                if failed.load(Ordering::Relaxed) {
+                    FrameProbe::Failed("graph failed".to_string())
+                } else {
+                    probe_frame_buffer(&buffer)
+                }
+            },
+            std::time::Duration::from_secs(2),
+            std::time::Duration::from_millis(5),
+        );
+        flipper.join().unwrap();
+
+        assert_eq!(
+            outcome,
+            FirstFrameOutcome::SessionError {
+                message: "graph failed".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn first_frame_outcome_serialises_with_status_tag() {
+        let json = serde_json::to_value(FirstFrameOutcome::Ready {
+            width: 640,
+            height: 480,
+        })
+        .unwrap();
+        assert_eq!(json["status"], "ready");
+        assert_eq!(json["width"], 640);
+
+        let json = serde_json::to_value(FirstFrameOutcome::SessionError {
+            message: "boom".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json["status"], "sessionError");
+        assert_eq!(json["message"], "boom");
+    }
 }
@@ -0,0 +1,374 @@
+//! YUV colour space selection for preview conversion.
+//!
+//! YUY2 and NV12 frames only make sense with the matrix and range the
+//! camera encoded them with. SD cameras use BT.601, HD cameras almost
+//! always BT.709, and both normally keep luma within 16–235 ("limited"
+//! range). Decoding HD video as full-range BT.601 — what the converters
+//! used to do — leaves blacks grey, whites dim and hues shifted.
+//!
+//! A session's colour space comes from, in order: the per-device override
+//! in the camera's settings, the hints a `VIDEOINFOHEADER2` carries, and a
+//! default from the frame height. [`Coefficients`] turns the choice into
+//! the fixed-point factors the CPU converters and GPU shaders share.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Frames at least this tall are assumed to be HD, and so BT.709.
+pub const HD_MIN_HEIGHT: u32 = 720;
+
+/// Fractional bits of [`Coefficients`].
+pub const SHIFT: u32 = 16;
+
+/// Added before shifting down so results round to nearest.
+const ROUND: i32 = 1 << (SHIFT - 1);
+
+/// `AMCONTROL_COLORINFO_PRESENT`: the upper bits of `dwControlFlags` hold a
+/// `DXVA_ExtendedFormat`.
+const COLORINFO_PRESENT: u32 = 0x80;
+
+/// The matrix relating Y'CbCr to R'G'B'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColourMatrix {
+    /// SD video.
+    Bt601,
+    /// HD video.
+    Bt709,
+}
+
+impl ColourMatrix {
+    /// The red and blue luma weights (Kr, Kb).
+    fn weights(self) -> (f64, f64) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+/// Which code values black and white sit at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColourRange {
+    /// Luma 16–235, chroma 16–240. What video cameras send.
+    Limited,
+    /// Every code value, as in JPEG.
+    Full,
+}
+
+/// How a camera's YUV frames are to be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColourSpace {
+    pub matrix: ColourMatrix,
+    pub range: ColourRange,
+}
+
+/// A session's colour space override, shared with its capture callback.
+/// `None` means detect it.
+pub type SharedColourSpace = Arc<Mutex<Option<ColourSpace>>>;
+
+impl ColourSpace {
+    pub const BT601_LIMITED: Self = Self {
+        matrix: ColourMatrix::Bt601,
+        range: ColourRange::Limited,
+    };
+    pub const BT601_FULL: Self = Self {
+        matrix: ColourMatrix::Bt601,
+        range: ColourRange::Full,
+    };
+    pub const BT709_LIMITED: Self = Self {
+        matrix: ColourMatrix::Bt709,
+        range: ColourRange::Limited,
+    };
+    pub const BT709_FULL: Self = Self {
+        matrix: ColourMatrix::Bt709,
+        range: ColourRange::Full,
+    };
+
+    /// The usual colour space for frames of this size: BT.709 from 720p
+    /// up, BT.601 below, limited range either way.
+    pub fn default_for(height: u32) -> Self {
+        if height >= HD_MIN_HEIGHT {
+            Self::BT709_LIMITED
+        } else {
+            Self::BT601_LIMITED
+        }
+    }
+
+    pub fn coefficients(self) -> Coefficients {
+        Coefficients::new(self)
+    }
+}
+
+/// What a media type says about its colour space. Either part may be
+/// missing, and the rest is filled in from the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColourHints {
+    pub matrix: Option<ColourMatrix>,
+    pub range: Option<ColourRange>,
+}
+
+impl ColourHints {
+    /// Read the `DXVA_ExtendedFormat` packed into a `VIDEOINFOHEADER2`'s
+    /// `dwControlFlags`. Unknown and unsupported values give no hint.
+    pub fn from_control_flags(flags: u32) -> Self {
+        if flags & COLORINFO_PRESENT == 0 {
+            return Self::default();
+        }
+        // DXVA2_NominalRange: 1 is 0–255, 2 is 16–235
+        let range = match (flags >> 12) & 0x7 {
+            1 => Some(ColourRange::Full),
+            2 => Some(ColourRange::Limited),
+            _ => None,
+        };
+        // DXVA2_VideoTransferMatrix: 1 is BT.709, 2 is BT.601
+        let matrix = match (flags >> 15) & 0x7 {
+            1 => Some(ColourMatrix::Bt709),
+            2 => Some(ColourMatrix::Bt601),
+            _ => None,
+        };
+        Self { matrix, range }
+    }
+}
+
+/// The colour space to decode a `height`-line frame with: the override if
+/// there is one, else the media type's hints over the default for the size.
+pub fn resolve(override_: Option<ColourSpace>, hints: ColourHints, height: u32) -> ColourSpace {
+    if let Some(space) = override_ {
+        return space;
+    }
+    let default = ColourSpace::default_for(height);
+    ColourSpace {
+        matrix: hints.matrix.unwrap_or(default.matrix),
+        range: hints.range.unwrap_or(default.range),
+    }
+}
+
+/// Fixed-point YUV to RGB factors, scaled by `1 << SHIFT`. Range expansion
+/// is folded in, so limited and full range share one conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coefficients {
+    /// Luma code value of black.
+    pub y_offset: i32,
+    pub y_gain: i32,
+    /// V's contribution to red.
+    pub rv: i32,
+    /// U's and V's (subtracted) contributions to green.
+    pub gu: i32,
+    pub gv: i32,
+    /// U's contribution to blue.
+    pub bu: i32,
+}
+
+impl Coefficients {
+    pub fn new(space: ColourSpace) -> Self {
+        let (kr, kb) = space.matrix.weights();
+        let kg = 1.0 - kr - kb;
+        let (y_offset, y_scale, c_scale) = match space.range {
+            ColourRange::Limited => (16, 255.0 / 219.0, 255.0 / 224.0),
+            ColourRange::Full => (0, 1.0, 1.0),
+        };
+        let fixed = |x: f64| (x * f64::from(1 << SHIFT)).round() as i32;
+        Self {
+            y_offset,
+            y_gain: fixed(y_scale),
+            rv: fixed(2.0 * (1.0 - kr) * c_scale),
+            gu: fixed(2.0 * kb * (1.0 - kb) / kg * c_scale),
+            gv: fixed(2.0 * kr * (1.0 - kr) / kg * c_scale),
+            bu: fixed(2.0 * (1.0 - kb) * c_scale),
+        }
+    }
+
+    /// Convert one pixel. `u` and `v` are the raw chroma samples.
+    #[inline]
+    pub fn to_rgb(self, y: u8, u: u8, v: u8) -> [u8; 3] {
+        let y = (i32::from(y) - self.y_offset) * self.y_gain + ROUND;
+        let u = i32::from(u) - 128;
+        let v = i32::from(v) - 128;
+        let channel = |x: i32| (x >> SHIFT).clamp(0, 255) as u8;
+        [
+            channel(y + self.rv * v),
+            channel(y - self.gu * u - self.gv * v),
+            channel(y + self.bu * u),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [ColourSpace; 4] = [
+        ColourSpace::BT601_LIMITED,
+        ColourSpace::BT601_FULL,
+        ColourSpace::BT709_LIMITED,
+        ColourSpace::BT709_FULL,
+    ];
+
+    fn assert_near(actual: [u8; 3], expected: [u8; 3], what: &str) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                a.abs_diff(e) <= 1,
+                "{what}: got {actual:?}, expected {expected:?} ±1"
+            );
+        }
+    }
+
+    #[test]
+    fn white_and_black_map_to_the_ends_of_the_range() {
+        for space in ALL {
+            let c = space.coefficients();
+            let (black, white) = match space.range {
+                ColourRange::Limited => (16, 235),
+                ColourRange::Full => (0, 255),
+            };
+            assert_near(
+                c.to_rgb(white, 128, 128),
+                [255; 3],
+                &format!("{space:?} white"),
+            );
+            assert_near(
+                c.to_rgb(black, 128, 128),
+                [0; 3],
+                &format!("{space:?} black"),
+            );
+        }
+    }
+
+    #[test]
+    fn primaries_decode_within_one() {
+        // Y'CbCr of pure red, green and blue as each standard encodes them
+        let cases = [
+            (
+                ColourSpace::BT601_LIMITED,
+                [(81, 90, 240), (145, 54, 34), (41, 240, 110)],
+            ),
+            (
+                ColourSpace::BT709_LIMITED,
+                [(63, 102, 240), (173, 42, 26), (32, 240, 118)],
+            ),
+            (
+                ColourSpace::BT601_FULL,
+                [(76, 85, 255), (150, 44, 21), (29, 255, 107)],
+            ),
+            (
+                ColourSpace::BT709_FULL,
+                [(54, 99, 255), (182, 30, 12), (18, 255, 116)],
+            ),
+        ];
+        let expected = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        for (space, primaries) in cases {
+            let c = space.coefficients();
+            for ((y, u, v), rgb) in primaries.into_iter().zip(expected) {
+                assert_near(c.to_rgb(y, u, v), rgb, &format!("{space:?} {rgb:?}"));
+            }
+        }
+    }
+
+    #[test]
+    fn full_range_bt601_matches_the_old_conversion() {
+        // The previous converters: (y*256 + 359v) >> 8 and so on
+        let c = ColourSpace::BT601_FULL.coefficients();
+        for (y, u, v) in [
+            (235, 128, 128),
+            (128, 128, 128),
+            (81, 90, 240),
+            (200, 30, 60),
+        ] {
+            let (yi, ui, vi) = (y as i32, u as i32 - 128, v as i32 - 128);
+            let old = [
+                ((yi * 256 + 359 * vi) >> 8).clamp(0, 255) as u8,
+                ((yi * 256 - 88 * ui - 183 * vi) >> 8).clamp(0, 255) as u8,
+                ((yi * 256 + 454 * ui) >> 8).clamp(0, 255) as u8,
+            ];
+            assert_near(c.to_rgb(y, u, v), old, &format!("({y}, {u}, {v})"));
+        }
+    }
+
+    #[test]
+    fn limited_range_stretches_what_full_range_leaves_washed_out() {
+        let full = ColourSpace::BT709_FULL.coefficients();
+        let limited = ColourSpace::BT709_LIMITED.coefficients();
+        assert_eq!(full.to_rgb(16, 128, 128), [16; 3]);
+        assert_eq!(limited.to_rgb(16, 128, 128), [0; 3]);
+        assert_eq!(full.to_rgb(235, 128, 128), [235; 3]);
+        assert_eq!(limited.to_rgb(235, 128, 128), [255; 3]);
+    }
+
+    #[test]
+    fn hd_defaults_to_bt709_and_sd_to_bt601() {
+        assert_eq!(ColourSpace::default_for(1080), ColourSpace::BT709_LIMITED);
+        assert_eq!(ColourSpace::default_for(720), ColourSpace::BT709_LIMITED);
+        assert_eq!(ColourSpace::default_for(576), ColourSpace::BT601_LIMITED);
+        assert_eq!(ColourSpace::default_for(480), ColourSpace::BT601_LIMITED);
+    }
+
+    #[test]
+    fn control_flags_carry_matrix_and_range() {
+        // COLORINFO_PRESENT, nominal range 1 (full), transfer matrix 2 (BT.601)
+        let flags = COLORINFO_PRESENT | (1 << 12) | (2 << 15);
+        assert_eq!(
+            ColourHints::from_control_flags(flags),
+            ColourHints {
+                matrix: Some(ColourMatrix::Bt601),
+                range: Some(ColourRange::Full),
+            }
+        );
+
+        let flags = COLORINFO_PRESENT | (2 << 12) | (1 << 15);
+        assert_eq!(
+            ColourHints::from_control_flags(flags),
+            ColourHints {
+                matrix: Some(ColourMatrix::Bt709),
+                range: Some(ColourRange::Limited),
+            }
+        );
+
+        // The same bits without COLORINFO_PRESENT mean nothing
+        assert_eq!(
+            ColourHints::from_control_flags((1 << 12) | (2 << 15)),
+            ColourHints::default()
+        );
+        // SMPTE 240M and the 48–208 range aren't supported
+        assert_eq!(
+            ColourHints::from_control_flags(COLORINFO_PRESENT | (3 << 12) | (3 << 15)),
+            ColourHints::default()
+        );
+    }
+
+    #[test]
+    fn override_beats_hints_which_beat_the_default() {
+        let hints = ColourHints {
+            matrix: Some(ColourMatrix::Bt601),
+            range: None,
+        };
+        assert_eq!(
+            resolve(None, hints, 1080),
+            ColourSpace {
+                matrix: ColourMatrix::Bt601,
+                range: ColourRange::Limited,
+            }
+        );
+        assert_eq!(
+            resolve(Some(ColourSpace::BT709_FULL), hints, 480),
+            ColourSpace::BT709_FULL
+        );
+        assert_eq!(
+            resolve(None, ColourHints::default(), 480),
+            ColourSpace::BT601_LIMITED
+        );
+    }
+
+    #[test]
+    fn serialises_in_camel_case() {
+        let json = serde_json::to_value(ColourSpace::BT709_LIMITED).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "matrix": "bt709", "range": "limited" })
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! Lifetime of the COM objects we hand to DirectShow.
+//!
+//! The SampleGrabber holds our frame callback through a raw COM pointer and
+//! may AddRef or Release it from its streaming thread while the capture
+//! thread tears the graph down. The object layout, its reference count and
+//! the handle we own it through live here, apart from the DirectShow
+//! bindings, so they can be tested without a capture graph.
+
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{fence, AtomicU32, Ordering};
+
+/// A reference-counted COM object: a vtable pointer, as every COM interface
+/// pointer starts with, followed by the count and the object's own data.
+#[repr(C)]
+pub struct ComObject<V: 'static, T> {
+    vtbl: &'static V,
+    refs: AtomicU32,
+    inner: T,
+}
+
+impl<V: 'static, T> ComObject<V, T> {
+    /// Allocate an object with a single reference, held by the returned
+    /// handle. Whoever else is given the pointer takes their own reference.
+    pub fn create(vtbl: &'static V, inner: T) -> ComHandle<V, T> {
+        let object = Box::new(Self {
+            vtbl,
+            refs: AtomicU32::new(1),
+            inner,
+        });
+        ComHandle {
+            ptr: NonNull::from(Box::leak(object)),
+            _owns: PhantomData,
+        }
+    }
+}
+
+/// `IUnknown::AddRef`. Returns the new count.
+///
+/// # Safety
+///
+/// `this` must point to a live `ComObject<V, T>` the caller holds a
+/// reference to.
+pub unsafe fn add_ref<V: 'static, T>(this: *mut core::ffi::c_void) -> u32 {
+    let object = &*(this as *const ComObject<V, T>);
+    // A new reference can only be made from an existing one, so nothing
+    // needs ordering here; `release` orders the accesses before freeing.
+    object.refs.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// `IUnknown::Release`. Returns the new count, freeing the object when it
+/// reaches zero.
+///
+/// # Safety
+///
+/// `this` must point to a live `ComObject<V, T>`, and the caller gives up
+/// the reference it held.
+pub unsafe fn release<V: 'static, T>(this: *mut core::ffi::c_void) -> u32 {
+    let object = &*(this as *const ComObject<V, T>);
+    // Release publishes this thread's use of the object to whichever thread
+    // drops the last reference; that thread's Acquire fence sees all of it
+    // before the object is freed.
+    let prev = object.refs.fetch_sub(1, Ordering::Release);
+    debug_assert!(prev != 0, "released a COM object with no references");
+    if prev != 1 {
+        return prev - 1;
+    }
+    fence(Ordering::Acquire);
+    drop(Box::from_raw(this as *mut ComObject<V, T>));
+    0
+}
+
+/// The object's data.
+///
+/// # Safety
+///
+/// `this` must point to a live `ComObject<V, T>`, and stay live for `'a`.
+pub unsafe fn inner<'a, V: 'static, T>(this: *mut core::ffi::c_void) -> &'a T {
+    &(*(this as *const ComObject<V, T>)).inner
+}
+
+/// Our own reference to a COM object, released on drop. While it's held
+/// the count can't fall below one, whatever DirectShow does with the
+/// pointers it was given.
+pub struct ComHandle<V: 'static, T> {
+    ptr: NonNull<ComObject<V, T>>,
+    _owns: PhantomData<ComObject<V, T>>,
+}
+
+// Like `Arc`: the data is shared between threads, and freed by whichever
+// thread drops the last reference.
+unsafe impl<V: 'static + Sync, T: Send + Sync> Send for ComHandle<V, T> {}
+unsafe impl<V: 'static + Sync, T: Send + Sync> Sync for ComHandle<V, T> {}
+
+impl<V: 'static, T> ComHandle<V, T> {
+    /// The interface pointer, to hand to COM. Doesn't add a reference.
+    pub fn as_raw(&self) -> *mut core::ffi::c_void {
+        self.ptr.as_ptr().cast()
+    }
+
+    /// Current reference count, ours included.
+    pub fn ref_count(&self) -> u32 {
+        unsafe { self.ptr.as_ref() }.refs.load(Ordering::Acquire)
+    }
+
+    pub fn get(&self) -> &T {
+        &unsafe { self.ptr.as_ref() }.inner
+    }
+}
+
+impl<V: 'static, T> Drop for ComHandle<V, T> {
+    fn drop(&mut self) {
+        unsafe {
+            release::<V, T>(self.as_raw());
+        }
+    }
+}
+
+/// Receives the samples a BufferCB callback is given.
+pub trait SampleSink {
+    /// Whether samples are wanted; ones arriving otherwise are ignored.
+    fn is_running(&self) -> bool;
+    /// Handle one sample. May panic; the panic is caught before it reaches
+    /// the caller.
+    fn on_sample(&self, sample_time: f64, data: &[u8]);
+    /// A null or empty buffer was delivered.
+    fn on_invalid(&self, buffer_len: i32);
+    /// `on_sample` panicked.
+    fn on_panic(&self, message: String);
+}
+
+/// `ISampleGrabberCB::BufferCB` for an object whose data is a
+/// [`SampleSink`]. Checks the buffer before borrowing it, and keeps panics
+/// from unwinding into the caller, which would abort the process.
+///
+/// # Safety
+///
+/// `this` must point to a live `ComObject<V, T>`. `buffer`, if not null,
+/// must be valid for reads of `buffer_len` bytes for the duration of the
+/// call.
+pub unsafe fn buffer_cb<V: 'static, T: SampleSink>(
+    this: *mut core::ffi::c_void,
+    sample_time: f64,
+    buffer: *const u8,
+    buffer_len: i32,
+) {
+    let sink = inner::<V, T>(this);
+    if !sink.is_running() {
+        return;
+    }
+    if buffer.is_null() || buffer_len <= 0 {
+        sink.on_invalid(buffer_len);
+        return;
+    }
+    let data = std::slice::from_raw_parts(buffer, buffer_len as usize);
+    if let Err(message) = crate::supervisor::catch_panic(|| sink.on_sample(sample_time, data)) {
+        sink.on_panic(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicBool, AtomicUsize};
+    use std::sync::Arc;
+
+    struct TestVtbl {
+        add_ref: unsafe fn(*mut core::ffi::c_void) -> u32,
+        release: unsafe fn(*mut core::ffi::c_void) -> u32,
+        buffer_cb: unsafe fn(*mut core::ffi::c_void, f64, *const u8, i32),
+    }
+
+    static TEST_VTBL: TestVtbl = TestVtbl {
+        add_ref: add_ref::<TestVtbl, TestSink>,
+        release: release::<TestVtbl, TestSink>,
+        buffer_cb: buffer_cb::<TestVtbl, TestSink>,
+    };
+
+    /// Records what it's given, and counts its own drops.
+    struct TestSink {
+        running: AtomicBool,
+        samples: Mutex<Vec<(f64, Vec<u8>)>>,
+        invalid: Mutex<Vec<i32>>,
+        panics: Mutex<Vec<String>>,
+        dropped: Arc<AtomicUsize>,
+    }
+
+    impl SampleSink for TestSink {
+        fn is_running(&self) -> bool {
+            self.running.load(Ordering::Relaxed)
+        }
+
+        fn on_sample(&self, sample_time: f64, data: &[u8]) {
+            if data == b"panic" {
+                panic!("bad frame");
+            }
+            self.samples.lock().push((sample_time, data.to_vec()));
+        }
+
+        fn on_invalid(&self, buffer_len: i32) {
+            self.invalid.lock().push(buffer_len);
+        }
+
+        fn on_panic(&self, message: String) {
+            self.panics.lock().push(message);
+        }
+    }
+
+    impl Drop for TestSink {
+        fn drop(&mut self) {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn object() -> (ComHandle<TestVtbl, TestSink>, Arc<AtomicUsize>) {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let sink = TestSink {
+            running: AtomicBool::new(true),
+            samples: Mutex::default(),
+            invalid: Mutex::default(),
+            panics: Mutex::default(),
+            dropped: Arc::clone(&dropped),
+        };
+        (ComObject::create(&TEST_VTBL, sink), dropped)
+    }
+
+    /// Calls through the vtable, as COM would.
+    fn vtbl(this: *mut core::ffi::c_void) -> &'static TestVtbl {
+        unsafe { *(this as *const &'static TestVtbl) }
+    }
+
+    #[test]
+    fn new_objects_hold_one_reference() {
+        let (handle, dropped) = object();
+        assert_eq!(handle.ref_count(), 1);
+        drop(handle);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn add_ref_and_release_return_the_new_count() {
+        let (handle, dropped) = object();
+        let this = handle.as_raw();
+        unsafe {
+            assert_eq!((vtbl(this).add_ref)(this), 2);
+            assert_eq!((vtbl(this).add_ref)(this), 3);
+            assert_eq!((vtbl(this).release)(this), 2);
+            assert_eq!((vtbl(this).release)(this), 1);
+        }
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+        drop(handle);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn object_outlives_our_handle_while_com_holds_it() {
+        let (handle, dropped) = object();
+        let this = handle.as_raw();
+        // The grabber takes its own reference in SetCallback
+        unsafe { (vtbl(this).add_ref)(this) };
+        drop(handle);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+        // A frame arriving after our teardown still finds the object
+        unsafe { (vtbl(this).buffer_cb)(this, 0.5, [7u8].as_ptr(), 1) };
+        assert_eq!(
+            unsafe { inner::<TestVtbl, TestSink>(this) }
+                .samples
+                .lock()
+                .len(),
+            1
+        );
+
+        assert_eq!(unsafe { (vtbl(this).release)(this) }, 0);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn concurrent_add_ref_and_release_free_exactly_once() {
+        const THREADS: usize = 8;
+        const ROUNDS: usize = 10_000;
+
+        let (handle, dropped) = object();
+        let this = handle.as_raw() as usize;
+        std::thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(move || {
+                    let this = this as *mut core::ffi::c_void;
+                    for _ in 0..ROUNDS {
+                        unsafe {
+                            (vtbl(this).add_ref)(this);
+                            (vtbl(this).release)(this);
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(handle.ref_count(), 1);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+        drop(handle);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn last_release_on_another_thread_frees_once() {
+        const THREADS: usize = 8;
+
+        for _ in 0..100 {
+            let (handle, dropped) = object();
+            let this = handle.as_raw();
+            for _ in 0..THREADS {
+                unsafe { (vtbl(this).add_ref)(this) };
+            }
+            let this = this as usize;
+            std::thread::scope(|scope| {
+                for _ in 0..THREADS {
+                    scope.spawn(move || {
+                        let this = this as *mut core::ffi::c_void;
+                        unsafe { (vtbl(this).release)(this) };
+                    });
+                }
+                scope.spawn(move || drop(handle));
+            });
+            assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn valid_buffers_are_delivered() {
+        let (handle, _) = object();
+        let this = handle.as_raw();
+        let frame = [1u8, 2, 3, 4];
+        unsafe { (vtbl(this).buffer_cb)(this, 1.25, frame.as_ptr(), frame.len() as i32) };
+        assert_eq!(*handle.get().samples.lock(), vec![(1.25, frame.to_vec())]);
+        assert!(handle.get().invalid.lock().is_empty());
+    }
+
+    #[test]
+    fn null_and_empty_buffers_are_reported_not_read() {
+        let (handle, _) = object();
+        let this = handle.as_raw();
+        let frame = [1u8];
+        unsafe {
+            (vtbl(this).buffer_cb)(this, 0.0, std::ptr::null(), 16);
+            (vtbl(this).buffer_cb)(this, 0.0, frame.as_ptr(), 0);
+            (vtbl(this).buffer_cb)(this, 0.0, frame.as_ptr(), -1);
+        }
+        assert!(handle.get().samples.lock().is_empty());
+        assert_eq!(*handle.get().invalid.lock(), vec![16, 0, -1]);
+    }
+
+    #[test]
+    fn buffers_are_ignored_once_stopped() {
+        let (handle, _) = object();
+        let this = handle.as_raw();
+        handle.get().running.store(false, Ordering::Relaxed);
+        let frame = [1u8];
+        unsafe {
+            (vtbl(this).buffer_cb)(this, 0.0, frame.as_ptr(), 1);
+            (vtbl(this).buffer_cb)(this, 0.0, std::ptr::null(), 0);
+        }
+        assert!(handle.get().samples.lock().is_empty());
+        assert!(handle.get().invalid.lock().is_empty());
+    }
+
+    #[test]
+    fn panics_in_the_sink_are_caught() {
+        let (handle, _) = object();
+        let this = handle.as_raw();
+        unsafe { (vtbl(this).buffer_cb)(this, 0.0, b"panic".as_ptr(), 5) };
+        assert_eq!(*handle.get().panics.lock(), vec!["bad frame".to_string()]);
+
+        // The object carries on after a caught panic
+        unsafe { (vtbl(this).buffer_cb)(this, 0.0, [9u8].as_ptr(), 1) };
+        assert_eq!(handle.get().samples.lock().len(), 1);
+        assert_eq!(handle.ref_count(), 1);
+    }
+}
@@ -1,38 +1,642 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use parking_lot::Mutex;
+use tauri::ipc::{Channel, Response};
 use tauri::{AppHandle, Emitter, Manager, State};
 
-use super::capture::{CaptureSession, PreviewErrorPayload, PreviewSession};
-use super::compress;
-use super::gpu::{GpuAdapterInfo, GpuState};
+use super::capture::{
+    poll_first_frame, CaptureSession, ContentCallback, ErrorCallback, FirstFrameOutcome, Frame,
+    FrameBuffer, FrameProbe, PreviewContentPayload, PreviewErrorPayload, PreviewRecoveringPayload,
+    PreviewSession, PreviewStartedPayload, RecoveringCallback, StartedCallback, CAPTURE_RESTART,
+};
+use super::colour::ColourSpace;
+use super::compress::{self, FrameFormat, PixelRect};
+use super::gpu::{GpuAdapterInfo, GpuContext, GpuState};
+use super::identity::{canonical_device_id, sessions_for_device};
+use super::jpeg_cache::{CachedJpeg, FrameVariant, JpegCache, DEFAULT_LIMIT_BYTES};
+use super::limits::{self, check_capacity, ResourceUsage, DEFAULT_MAX_SESSIONS};
+use super::mode::{PreviewFormat, SessionMode};
+use super::output::{self, validate_chain, OutputTarget, Processor};
+use super::payload::{self, fit_to_budget, FrameChunk, PayloadStats, DEFAULT_MAX_RESPONSE_BYTES};
+use super::placeholder::{placeholder_state, render_placeholder, PlaceholderCache, SignalState};
+use super::quality::{AdaptiveQuality, QualityProfile};
+use super::render::{self, Orientation};
+use super::shm::{ShmExport, ShmLayout};
+use super::snapshot;
+use super::sources::{SourcePreference, VideoSource};
+use super::subscription::{self, PreviewFramePayload, Subscriptions};
+use super::tap::TapId;
+use super::thumbnail::{
+    plan_thumbnail, ThumbnailConfig, ThumbnailFit, ThumbnailSizes, ThumbnailSpec,
+};
+use super::timelapse_task::latest_still;
+use super::transform::PostProcessing;
+use super::warm::{default_device, Tier, WarmAction, WarmEvent, WarmMachine};
+use super::watermark::WatermarkContext;
+use super::zebra::{self, ExposureMask, MaskCache};
+use super::zoom::{plan_zoom, HardwareZoom, ZoomSplit};
+use crate::camera::backend::CameraBackend;
 use crate::camera::commands::CameraState;
-use crate::camera::error::humanise_error;
-use crate::camera::types::{CameraDevice, DeviceId};
+use crate::camera::queue::{DeviceQueue, OpKind};
+use crate::camera::siblings::should_auto_start;
+use crate::camera::types::{CameraDevice, ControlId, DeviceId};
+use crate::diagnostics::accounting::{ResourceAccounting, ResourceUsagePerDevice};
+use crate::diagnostics::benchmark::{self, BenchmarkReport};
+use crate::diagnostics::content::ContentHealth;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::diagnostics::delivery::{PREVIEW_CONSUMER, THUMBNAIL_CONSUMER};
 use crate::diagnostics::stats::DiagnosticSnapshot;
-use crate::preview::encode_worker::EncodingSnapshot;
+use crate::diagnostics::stream::DiagnosticsUpdate;
+use crate::error::{code, AppError};
+use crate::preview::encode_worker::{EncodingSnapshot, JpegFrame};
+use crate::settings::commands::SettingsState;
+use crate::settings::persist::SaveHealth;
+use crate::settings::store::SettingsStore;
 use crate::CanonSdkState;
 
-/// Cached JPEG result for a single device, keyed by frame sequence number.
-struct JpegCache {
-    sequence: u64,
-    base64: String,
+/// JPEG quality used by the encode worker.
+pub(crate) const FRAME_JPEG_QUALITY: u8 = 75;
+
+/// How often the resource sampler reads each session's CPU meter.
+const RESOURCE_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Capture size and frame rate for previews the backend starts itself.
+pub(crate) const AUTO_START_SIZE: (u32, u32) = (640, 480);
+pub(crate) const AUTO_START_FPS: f32 = 30.0;
+
+/// Format a full preview starts at when none has been chosen for the camera.
+const AUTO_START_FORMAT: PreviewFormat = PreviewFormat {
+    width: AUTO_START_SIZE.0,
+    height: AUTO_START_SIZE.1,
+    fps: AUTO_START_FPS,
+};
+
+/// Latest frame chosen for delivery, before compression.
+pub(super) enum FrameSource {
+    /// JPEG already encoded with the session's current orientation.
+    Encoded(Arc<JpegFrame>),
+    /// Raw RGB frame, rendered and compressed on demand.
+    Raw(Arc<Frame>),
+    /// JPEG with no raw frame behind it, such as one seeded from the
+    /// previous session, that still needs orienting.
+    Passthrough(Arc<JpegFrame>),
+}
+
+/// Pick the frame to deliver for a session as `variant`, with the sequence
+/// number used for caching.
+///
+/// For the whole frame as JPEG, pre-encoded JPEG is preferred, but only if
+/// it was rendered with `orientation` — right after an orientation change
+/// the raw frame is rendered instead so every endpoint reflects the change
+/// immediately. PNG and crops are made from the raw frame, so they don't
+/// carry JPEG's artefacts.
+fn select_frame_source(
+    session: &PreviewSession,
+    orientation: Orientation,
+    variant: FrameVariant,
+) -> Option<(FrameSource, u64)> {
+    let encoded = session
+        .jpeg_buffer()
+        .and_then(|buf| buf.latest().map(|frame| (frame, buf.sequence())));
+
+    if let Some((frame, seq)) = &encoded {
+        if variant == FrameVariant::default() && frame.orientation == orientation {
+            return Some((FrameSource::Encoded(Arc::clone(frame)), *seq));
+        }
+    }
+
+    if let Some((frame, seq)) = session.buffer().latest_with_sequence() {
+        return Some((FrameSource::Raw(frame), seq));
+    }
+
+    encoded.map(|(frame, seq)| (FrameSource::Passthrough(frame), seq))
+}
+
+impl FrameSource {
+    /// Sequence to report to the session's delivery accounting, given the
+    /// cache sequence `select_frame_source` returned.
+    ///
+    /// Encoded frames are numbered by the JPEG buffer, so they report the raw
+    /// frame they came from when there is one.
+    fn delivered_sequence(&self, seq: u64) -> u64 {
+        match self {
+            Self::Encoded(frame) if frame.source_sequence > 0 => frame.source_sequence,
+            _ => seq,
+        }
+    }
+
+    /// Whether delivering this source means compressing on the calling thread.
+    fn needs_compression(&self, orientation: Orientation) -> bool {
+        match self {
+            Self::Encoded(_) => false,
+            Self::Passthrough(_) => !orientation.is_identity(),
+            Self::Raw(_) => true,
+        }
+    }
+}
+
+/// Produce JPEG bytes for a frame source through the shared render pipeline.
+///
+/// `quality` only applies when the source has to be compressed here.
+pub(super) fn encode_frame_source(
+    source: &FrameSource,
+    orientation: Orientation,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let rendered = match source {
+        FrameSource::Encoded(frame) => return Ok(frame.jpeg_bytes.clone()),
+        FrameSource::Passthrough(frame) if orientation.is_identity() => {
+            return Ok(frame.jpeg_bytes.clone());
+        }
+        source => render_frame_source(source, orientation)?,
+    };
+    Ok(compress::compress_jpeg(
+        &rendered.data,
+        rendered.width,
+        rendered.height,
+        quality,
+    ))
+}
+
+/// Render a frame source to oriented RGB, decoding it if it's JPEG.
+fn render_frame_source(
+    source: &FrameSource,
+    orientation: Orientation,
+) -> Result<render::RenderedFrame, String> {
+    match source {
+        // Already rendered with the session's orientation
+        FrameSource::Encoded(frame) => {
+            render::render_jpeg(&frame.jpeg_bytes, Orientation::default())
+        }
+        FrameSource::Passthrough(frame) => render::render_jpeg(&frame.jpeg_bytes, orientation),
+        FrameSource::Raw(frame) => Ok(render::render_frame(frame, orientation)),
+    }
+}
+
+/// Produce JPEG bytes for a frame source as `chain` wants it for one
+/// output. Without processors this is [`encode_frame_source`]; with them the
+/// frame is rendered, processed and compressed at `quality`.
+pub(super) fn encode_for_output(
+    source: &FrameSource,
+    orientation: Orientation,
+    quality: u8,
+    chain: &[Processor],
+    context: &WatermarkContext,
+) -> Result<Vec<u8>, String> {
+    if chain.is_empty() {
+        return encode_frame_source(source, orientation, quality);
+    }
+    let mut rendered = render_frame_source(source, orientation)?;
+    output::apply_chain(chain, &mut rendered, context);
+    Ok(compress::compress_jpeg(
+        &rendered.data,
+        rendered.width,
+        rendered.height,
+        quality,
+    ))
+}
+
+/// Placeholder values for a camera's frames delivered now.
+pub(super) fn watermark_context(camera: Option<String>) -> WatermarkContext {
+    WatermarkContext {
+        camera,
+        time: chrono::Local::now().naive_local(),
+    }
+}
+
+/// Render the latest raw frame as a thumbnail in `format`, returning it with
+/// the sequence of the frame it was rendered from.
+///
+/// The frame is oriented first and then fitted inside `config`'s box, so a
+/// 90° rotation yields a portrait thumbnail.
+fn render_thumbnail(
+    buffer: &FrameBuffer,
+    orientation: Orientation,
+    config: &ThumbnailConfig,
+    spec: Option<ThumbnailSpec>,
+    format: FrameFormat,
+) -> Option<(Vec<u8>, u64)> {
+    let (rendered, seq) = render::render_latest(buffer, orientation)?;
+    let (thumb_box, fit) = spec.map_or((config.physical_box(), ThumbnailFit::Contain), |spec| {
+        ((spec.width, spec.height), spec.fit)
+    });
+    let plan = plan_thumbnail(thumb_box, fit, (rendered.width, rendered.height));
+    let thumb = compress::compress_thumbnail(
+        &rendered.data,
+        rendered.width,
+        rendered.height,
+        plan.crop,
+        plan.width,
+        plan.height,
+        format,
+    );
+    Some((thumb, seq))
+}
+
+/// Saved orientation for a device, or the identity if none is stored.
+fn saved_orientation(app: &AppHandle, device_id: &str) -> Orientation {
+    app.try_state::<SettingsState>()
+        .and_then(|s| s.store.get_camera(device_id))
+        .map(|c| c.orientation)
+        .unwrap_or_default()
+}
+
+/// Saved sharpening and denoise for a device, or off if none is stored.
+fn saved_post_processing(app: &AppHandle, device_id: &str) -> PostProcessing {
+    app.try_state::<SettingsState>()
+        .and_then(|s| s.store.get_camera(device_id))
+        .map(|c| c.post_processing)
+        .unwrap_or_default()
+}
+
+/// Colour space override saved for a device, if one was set.
+fn saved_colour_space(app: &AppHandle, device_id: &str) -> Option<ColourSpace> {
+    app.try_state::<SettingsState>()
+        .and_then(|s| s.store.colour_space(device_id))
+}
+
+/// Video source saved for a multi-input device, if one was picked.
+fn saved_video_source(app: &AppHandle, device_id: &str) -> Option<SourcePreference> {
+    app.try_state::<SettingsState>()
+        .and_then(|s| s.store.video_source(device_id))
+}
+
+/// Format last chosen for a device's preview, or the auto-start default.
+pub(crate) fn saved_preview_format(store: &SettingsStore, device_id: &str) -> PreviewFormat {
+    store.preview_format(device_id).unwrap_or(AUTO_START_FORMAT)
+}
+
+/// Format a preview started without being asked for one captures at; see
+/// [`saved_preview_format`].
+fn auto_start_format(app: &AppHandle, device_id: &str) -> PreviewFormat {
+    app.try_state::<SettingsState>()
+        .map_or(AUTO_START_FORMAT, |s| {
+            saved_preview_format(&s.store, device_id)
+        })
+}
+
+/// Mode an auto-started preview runs in: thumbnail-only unless the camera
+/// is set to auto-start at full resolution.
+fn auto_start_mode(app: &AppHandle, device_id: &str) -> SessionMode {
+    let full = app
+        .try_state::<SettingsState>()
+        .is_some_and(|s| s.store.full_resolution_autostart(device_id));
+    if full {
+        SessionMode::Full
+    } else {
+        SessionMode::ThumbnailOnly
+    }
+}
+
+/// Whether the user opted in to auto-starting IR/depth sibling devices.
+fn auto_start_non_primary(app: &AppHandle) -> bool {
+    app.try_state::<SettingsState>()
+        .is_some_and(|s| s.store.auto_start_non_primary())
+}
+
+/// Saved JPEG quality profile for a device, or the defaults if none is stored.
+fn saved_quality_profile(settings: &SettingsState, device_id: &str) -> QualityProfile {
+    settings
+        .store
+        .get_camera(device_id)
+        .map(|c| c.jpeg_quality)
+        .unwrap_or_default()
 }
 
 /// Managed state holding active preview sessions.
 pub struct PreviewState {
     pub sessions: Mutex<HashMap<String, PreviewSession>>,
-    /// Per-device JPEG cache to avoid recompressing unchanged frames.
-    jpeg_cache: Mutex<HashMap<String, JpegCache>>,
+    /// Per-device JPEG caches to avoid recompressing unchanged frames and
+    /// thumbnails.
+    jpeg_cache: Mutex<JpegCache>,
+    thumbnail_cache: Mutex<JpegCache>,
+    /// Per-device "no signal" cards served by `get_frame`.
+    placeholders: Mutex<PlaceholderCache>,
+    /// Per-device zebra masks served by `get_exposure_mask`.
+    exposure_masks: Mutex<MaskCache>,
+    /// Size thumbnails are drawn at, as configured by the frontend.
+    thumbnails: Mutex<ThumbnailSizes>,
+    /// Per-device quality controllers for frames compressed in `get_frame`.
+    /// Kept across session restarts so a device doesn't relearn its quality.
+    quality: Mutex<HashMap<String, AdaptiveQuality>>,
+    /// Most sessions that may exist at once.
+    max_sessions: AtomicUsize,
+    /// Largest `get_frame` response, in bytes of base64, before quality is
+    /// stepped down to fit.
+    max_response_bytes: AtomicUsize,
+    /// Sizes of frame responses sent, for diagnostics.
+    payloads: PayloadStats,
+    /// Per-device frame being fetched with `get_frame_chunked`: its
+    /// sequence number and base64.
+    chunked: Mutex<HashMap<String, (u64, String)>>,
+    /// Samples of each session's CPU time, for per-device diagnostics.
+    accounting: Mutex<ResourceAccounting>,
+    /// Devices whose frames are pushed as `preview-frame` events.
+    subscriptions: Mutex<Subscriptions>,
 }
 
 impl PreviewState {
     pub fn new() -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
-            jpeg_cache: Mutex::new(HashMap::new()),
+            jpeg_cache: Mutex::new(JpegCache::new(DEFAULT_LIMIT_BYTES)),
+            thumbnail_cache: Mutex::new(JpegCache::new(DEFAULT_LIMIT_BYTES)),
+            placeholders: Mutex::new(PlaceholderCache::default()),
+            exposure_masks: Mutex::new(MaskCache::default()),
+            thumbnails: Mutex::new(ThumbnailSizes::default()),
+            quality: Mutex::new(HashMap::new()),
+            max_sessions: AtomicUsize::new(DEFAULT_MAX_SESSIONS),
+            max_response_bytes: AtomicUsize::new(DEFAULT_MAX_RESPONSE_BYTES),
+            payloads: PayloadStats::default(),
+            chunked: Mutex::new(HashMap::new()),
+            accounting: Mutex::new(ResourceAccounting::default()),
+            subscriptions: Mutex::new(Subscriptions::default()),
+        }
+    }
+
+    /// Limit how many sessions may exist at once. Running sessions beyond
+    /// a lowered limit are left alone; new starts are refused until enough
+    /// have stopped.
+    pub fn set_max_sessions(&self, max: usize) {
+        self.max_sessions.store(max, Ordering::Relaxed);
+    }
+
+    /// Limit the size of a `get_frame` response, in bytes of base64.
+    /// Larger frames are recompressed at lower quality until they fit.
+    pub fn set_max_response_bytes(&self, bytes: usize) {
+        self.max_response_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Whether a session for `device_id` may be added to `sessions`, the
+    /// locked sessions map.
+    fn admit(
+        &self,
+        sessions: &HashMap<String, PreviewSession>,
+        device_id: &str,
+    ) -> Result<(), AppError> {
+        check_capacity(
+            sessions,
+            device_id,
+            self.max_sessions.load(Ordering::Relaxed),
+        )
+        .map_err(AppError::with_code(code::PREVIEW_LIMIT))
+    }
+
+    /// Make the session `create` starts the only one capturing `device`,
+    /// keyed by its ID. Sessions already capturing it, under its ID or
+    /// another spelling of it, are stopped first; otherwise the start must
+    /// fit within the session limit. `create` runs with the sessions lock held, so two
+    /// starts for one camera can't both build a capture graph.
+    fn replace_for_device(
+        &self,
+        device: &CameraDevice,
+        create: impl FnOnce() -> Result<PreviewSession, AppError>,
+    ) -> Result<(), AppError> {
+        let device_id = device.id.as_str();
+        let mut sessions = self.sessions.lock();
+        let existing = existing_sessions(&sessions, device);
+        if existing.is_empty() {
+            self.admit(&sessions, device_id)?;
+        }
+        for key in existing {
+            if let Some(mut session) = sessions.remove(&key) {
+                session.stop();
+            }
+            if key != device_id {
+                tracing::warn!("Stopped duplicate preview session '{key}' for {device_id}");
+                self.forget_cached(&key);
+            }
+        }
+
+        let session = create()?;
+        sessions.insert(device_id.to_string(), session);
+        Ok(())
+    }
+
+    /// Sessions, buffered frames, caches and threads, for diagnostics.
+    fn resource_usage(&self, settings: SaveHealth) -> ResourceUsage {
+        let (session_count, running_sessions, frame_buffer_bytes) = {
+            let sessions = self.sessions.lock();
+            (
+                sessions.len(),
+                sessions.values().filter(|s| s.is_running()).count(),
+                sessions.values().map(|s| s.buffered_bytes()).sum(),
+            )
+        };
+        ResourceUsage {
+            session_count,
+            running_sessions,
+            max_sessions: self.max_sessions.load(Ordering::Relaxed),
+            frame_buffer_bytes,
+            cache_bytes: self.cache_bytes(),
+            process_threads: limits::process_thread_count(),
+            settings,
+            frame_payloads: self.payloads.snapshot(),
+        }
+    }
+
+    /// Sample every session's CPU time, forgetting devices without one.
+    fn sample_resources(&self, now: Instant) {
+        let sessions = self.sessions.lock();
+        let mut accounting = self.accounting.lock();
+        for (device_id, session) in sessions.iter() {
+            accounting.sample(device_id, session.cpu_times(), now);
+        }
+        accounting.retain(|id| sessions.contains_key(id));
+    }
+
+    /// CPU, buffered frames and cached bytes for each session's device.
+    fn resource_usage_per_device(&self) -> ResourceUsagePerDevice {
+        let sessions = self.sessions.lock();
+        let accounting = self.accounting.lock();
+        let jpeg_cache = self.jpeg_cache.lock();
+        let thumbnail_cache = self.thumbnail_cache.lock();
+        let devices = sessions
+            .iter()
+            .map(|(device_id, session)| {
+                let cache_bytes =
+                    jpeg_cache.bytes_for(device_id) + thumbnail_cache.bytes_for(device_id);
+                let resources = accounting.device_resources(
+                    device_id,
+                    session.cpu_times(),
+                    session.buffered_bytes(),
+                    cache_bytes,
+                );
+                (device_id.clone(), resources)
+            })
+            .collect();
+        ResourceUsagePerDevice::new(devices)
+    }
+
+    /// Cache a delivered JPEG, sweeping out entries for devices whose
+    /// session has gone.
+    fn cache_jpeg(&self, cache: &Mutex<JpegCache>, device_id: &str, jpeg: CachedJpeg) {
+        let sessions = self.sessions.lock();
+        let mut cache = cache.lock();
+        cache.insert(device_id, jpeg);
+        cache.retain(|id| sessions.contains_key(id));
+    }
+
+    /// Stop and remove `device_id`'s session, ending its `preview-frame`
+    /// subscription and dropping its cached frames. Returns whether it had a
+    /// session.
+    fn stop_session(&self, device_id: &str) -> bool {
+        let session = self.sessions.lock().remove(device_id);
+        let had_session = session.is_some();
+        if let Some(mut session) = session {
+            session.stop();
+        }
+        self.subscriptions.lock().unsubscribe(device_id);
+        self.forget_cached(device_id);
+        had_session
+    }
+
+    /// Drop a device's cached frame, thumbnail, "no signal" card and zebra
+    /// mask.
+    fn forget_cached(&self, device_id: &str) {
+        self.jpeg_cache.lock().remove(device_id);
+        self.thumbnail_cache.lock().remove(device_id);
+        self.placeholders.lock().remove(device_id);
+        self.exposure_masks.lock().remove(device_id);
+        self.chunked.lock().remove(device_id);
+    }
+
+    /// Base64 "no signal" card for a device, drawn on first use and again
+    /// only when the state or name changes.
+    fn placeholder_frame(&self, device_id: &str, signal: SignalState, name: &str) -> String {
+        self.placeholders
+            .lock()
+            .get_or_render(device_id, signal, name, || {
+                let card = render_placeholder(name, signal);
+                let jpeg = compress::compress_jpeg(
+                    &card.data,
+                    card.width,
+                    card.height,
+                    FRAME_JPEG_QUALITY,
+                );
+                base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg)
+            })
+    }
+
+    /// Configure thumbnail size for one device, or with `None` for every
+    /// device without its own size. Cached thumbnails drawn at the old size
+    /// are dropped.
+    fn configure_thumbnails(&self, device_id: Option<&str>, config: ThumbnailConfig) {
+        let mut sizes = self.thumbnails.lock();
+        if !sizes.set(device_id, config) {
+            return;
+        }
+        let mut cache = self.thumbnail_cache.lock();
+        match device_id {
+            Some(id) => cache.remove(id),
+            None => cache.retain(|id| sizes.has_own(id)),
+        }
+    }
+
+    /// Thumbnail configuration for a device.
+    fn thumbnail_config(&self, device_id: &str) -> ThumbnailConfig {
+        self.thumbnails.lock().config_for(device_id)
+    }
+
+    /// Limit the bytes each of the frame and thumbnail caches may hold.
+    pub fn set_cache_limit(&self, bytes: usize) {
+        self.jpeg_cache.lock().set_limit(bytes);
+        self.thumbnail_cache.lock().set_limit(bytes);
+    }
+
+    /// Bytes held by the frame and thumbnail caches across all devices.
+    pub(crate) fn cache_bytes(&self) -> usize {
+        self.jpeg_cache.lock().total_bytes() + self.thumbnail_cache.lock().total_bytes()
+    }
+
+    /// Most bytes the frame and thumbnail caches may hold between them.
+    pub(crate) fn cache_limit(&self) -> usize {
+        self.jpeg_cache.lock().limit() + self.thumbnail_cache.lock().limit()
+    }
+
+    /// Names of this state's locks that couldn't be taken within `timeout`,
+    /// as a deadlock would leave them.
+    pub(crate) fn stuck_locks(&self, timeout: Duration) -> Vec<&'static str> {
+        fn is_free<T>(lock: &Mutex<T>, timeout: Duration) -> bool {
+            lock.try_lock_for(timeout).is_some()
+        }
+        [
+            ("sessions", is_free(&self.sessions, timeout)),
+            ("frame cache", is_free(&self.jpeg_cache, timeout)),
+            ("thumbnail cache", is_free(&self.thumbnail_cache, timeout)),
+            ("placeholders", is_free(&self.placeholders, timeout)),
+            ("exposure masks", is_free(&self.exposure_masks, timeout)),
+            ("thumbnail sizes", is_free(&self.thumbnails, timeout)),
+            ("quality", is_free(&self.quality, timeout)),
+            ("chunked frames", is_free(&self.chunked, timeout)),
+            ("accounting", is_free(&self.accounting, timeout)),
+        ]
+        .into_iter()
+        .filter(|(_, free)| !free)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// Quality for the next on-demand encode, creating the device's controller
+    /// from `profile` on first use.
+    fn frame_quality(&self, device_id: &str, profile: impl FnOnce() -> QualityProfile) -> u8 {
+        self.quality
+            .lock()
+            .entry(device_id.to_string())
+            .or_insert_with(|| AdaptiveQuality::new(profile()))
+            .quality()
+    }
+
+    /// Feed an encode duration back to the device's controller.
+    fn record_encode(&self, device_id: &str, encode_time: Duration) {
+        if let Some(controller) = self.quality.lock().get_mut(device_id) {
+            let before = controller.quality();
+            let after = controller.record(encode_time);
+            if after != before {
+                tracing::debug!("JPEG quality for {device_id}: {before} -> {after}");
+            }
+        }
+    }
+
+    /// Replace a device's quality profile, taking effect from the next encode.
+    fn set_quality_profile(&self, device_id: &str, profile: QualityProfile) {
+        self.quality
+            .lock()
+            .entry(device_id.to_string())
+            .and_modify(|c| c.set_profile(profile))
+            .or_insert_with(|| AdaptiveQuality::new(profile));
+    }
+
+    /// Add the device's quality controller state to a diagnostics snapshot.
+    fn with_quality_stats(
+        &self,
+        device_id: &str,
+        mut snapshot: DiagnosticSnapshot,
+    ) -> DiagnosticSnapshot {
+        if let Some(controller) = self.quality.lock().get(device_id) {
+            snapshot.effective_jpeg_quality = Some(controller.quality());
+            snapshot.recent_encode_ms = controller.recent_encode_ms();
         }
+        snapshot
+    }
+
+    /// Diagnostics of every active session, by device ID, as
+    /// `get_diagnostics` reports each.
+    pub(crate) fn diagnostics_by_device(&self) -> DiagnosticsUpdate {
+        let cache_bytes = self.cache_bytes() as u64;
+        self.sessions
+            .lock()
+            .iter()
+            .map(|(device_id, session)| {
+                let mut snapshot = self.with_quality_stats(device_id, session.diagnostics());
+                snapshot.jpeg_cache_bytes = cache_bytes;
+                (device_id.clone(), snapshot)
+            })
+            .collect()
     }
 }
 
@@ -42,65 +646,300 @@ impl Default for PreviewState {
     }
 }
 
-/// Resolve device_id to (device_path, friendly_name) via the camera backend.
-fn resolve_device_info(
-    camera_state: &CameraState,
-    device_id: &str,
-) -> Result<(String, String), String> {
-    let devices = camera_state
-        .backend
-        .enumerate_devices()
-        .map_err(|e| humanise_error(&format!("failed to enumerate devices: {e}")))?;
+/// Default timeout when `start_preview` is asked to wait for the first frame.
+const DEFAULT_FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(5);
 
-    let target_id = DeviceId::new(device_id);
+/// How often the first-frame wait re-checks the session.
+const FIRST_FRAME_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Resolve `device_id`, in any spelling `canonical_device_id` accepts, to
+/// the enumerated device.
+fn resolve_device(camera_state: &CameraState, device_id: &str) -> Result<CameraDevice, AppError> {
+    let devices = camera_state.backend.enumerate_devices()?;
+
+    let id = DeviceId::new(resolve_device_id(device_id, &devices)?);
     devices
-        .iter()
-        .find(|d| d.id == target_id)
-        .map(|d| (d.device_path.clone(), d.name.clone()))
-        .ok_or_else(|| format!("device not found: {device_id}"))
+        .into_iter()
+        .find(|d| d.id == id)
+        .ok_or_else(|| not_found(device_id))
+}
+
+/// The enumerated ID of the device `device_id` names among `devices`; see
+/// [`canonical_device_id`].
+fn resolve_device_id(device_id: &str, devices: &[CameraDevice]) -> Result<String, AppError> {
+    canonical_device_id(device_id, devices).map_err(AppError::with_code(code::DEVICE_NOT_FOUND))
+}
+
+fn not_found(device_id: &str) -> AppError {
+    AppError::new(
+        code::DEVICE_NOT_FOUND,
+        format!("device not found: {device_id}"),
+    )
+}
+
+/// Error for a device with no preview session.
+fn no_preview() -> AppError {
+    AppError::new(
+        code::PREVIEW_NOT_RUNNING,
+        "no active preview for this device",
+    )
+}
+
+/// Error for a session with nothing captured to serve yet.
+fn no_frame() -> AppError {
+    AppError::new(code::FRAME_UNAVAILABLE, "no frame available")
+}
+
+/// The cameras connected now: those the backend is tracking, or a fresh
+/// enumeration if it hasn't found any yet.
+fn current_devices(camera_state: &CameraState) -> Result<Vec<CameraDevice>, AppError> {
+    let devices = camera_state.backend.known_devices();
+    if !devices.is_empty() {
+        return Ok(devices);
+    }
+    Ok(camera_state.backend.enumerate_devices()?)
+}
+
+/// Keys of the sessions capturing `device`, under its ID or another
+/// spelling of it.
+fn existing_sessions(
+    sessions: &HashMap<String, PreviewSession>,
+    device: &CameraDevice,
+) -> Vec<String> {
+    sessions_for_device(
+        sessions
+            .iter()
+            .map(|(key, session)| (key.as_str(), session.device_path())),
+        device,
+    )
+}
+
+/// Replace any preview session for `device_id` with a fresh one, keyed by
+/// the camera's enumerated ID whichever spelling `device_id` is in.
+fn replace_session(
+    app: &AppHandle,
+    device_id: &str,
+    width: u32,
+    height: u32,
+    fps: f32,
+    pixel_format: Option<String>,
+    mode: SessionMode,
+) -> Result<(), AppError> {
+    let state = app.state::<PreviewState>();
+
+    // Resolve device_id to the actual device path and name needed by DirectShow
+    let device = resolve_device(&app.state::<CameraState>(), device_id)?;
+
+    state.replace_for_device(&device, || {
+        create_preview_session(
+            app,
+            &app.state::<CanonSdkState>(),
+            &app.state::<GpuState>(),
+            device.id.as_str(),
+            &device.device_path,
+            &device.name,
+            width,
+            height,
+            fps,
+            pixel_format,
+            mode,
+        )
+    })
+}
+
+/// Make sure `device_id` has a running full-resolution preview, starting
+/// one at its saved format if it has none, is thumbnail-only or has
+/// failed. A healthy full session is left alone.
+///
+/// Callers run this inside a `PreviewStart` device-queue op.
+pub fn resume_preview_session(app: &AppHandle, device_id: &str) -> Result<(), AppError> {
+    let running_full = app
+        .state::<PreviewState>()
+        .sessions
+        .lock()
+        .get(device_id)
+        .is_some_and(|s| s.mode() == SessionMode::Full && !s.is_failed());
+    if running_full {
+        return Ok(());
+    }
+    let format = auto_start_format(app, device_id);
+    replace_session(
+        app,
+        device_id,
+        format.width,
+        format.height,
+        format.fps,
+        None,
+        SessionMode::Full,
+    )
+}
+
+/// Drop `device_id`'s preview back to thumbnail-only capture, keeping the
+/// camera open without encoding full frames. Does nothing if it has no
+/// session or is already thumbnail-only; Canon live view can't be paused.
+///
+/// Callers run this inside a `PreviewRestart` device-queue op.
+pub fn pause_preview_session(app: &AppHandle, device_id: &str) -> Result<(), AppError> {
+    match app.state::<PreviewState>().sessions.lock().get(device_id) {
+        None => return Ok(()),
+        Some(PreviewSession::Canon(_)) => {
+            return Err(AppError::new(
+                code::UNSUPPORTED,
+                "Canon live view can't be paused",
+            ))
+        }
+        Some(session) if session.mode() == SessionMode::ThumbnailOnly => return Ok(()),
+        Some(_) => {}
+    }
+    replace_session(
+        app,
+        device_id,
+        AUTO_START_SIZE.0,
+        AUTO_START_SIZE.1,
+        AUTO_START_FPS,
+        None,
+        SessionMode::ThumbnailOnly,
+    )
+}
+
+/// Stop `device_id`'s preview session and its `preview-frame` subscription,
+/// and drop its cached frames. Idempotent.
+///
+/// Callers run this inside a `PreviewStop` device-queue op.
+pub fn stop_preview_session(app: &AppHandle, device_id: &str) {
+    app.state::<PreviewState>().stop_session(device_id);
 }
 
 /// Start a camera preview session.
+///
+/// The session is (re)started through the device queue, so it never races a
+/// preset being applied or another restart on the same camera. `device_id`
+/// must name a connected camera, but may be padded, differently cased or
+/// the camera's device path: it's resolved to the enumerated ID first, so
+/// every spelling shares one session and one queue. A session already
+/// capturing the camera is replaced, and a start for a new camera is
+/// refused if the session limit has been reached. The format is saved for
+/// the camera, and previews started without one reuse it.
+///
+/// `pixel_format` is one of the FourCCs `get_camera_formats` lists, such as
+/// `MJPG`. The camera's formats in it are preferred; if it has none, the
+/// format is chosen by resolution as usual.
 #[tauri::command]
-#[allow(clippy::too_many_arguments)]
 pub async fn start_preview(
     app: AppHandle,
-    state: State<'_, PreviewState>,
+    queue: State<'_, DeviceQueue>,
     camera_state: State<'_, CameraState>,
-    canon_state: State<'_, CanonSdkState>,
-    gpu_state: State<'_, GpuState>,
     device_id: String,
     width: u32,
     height: u32,
     fps: f32,
-) -> Result<(), String> {
-    if device_id.is_empty() {
-        return Err("device_id must not be empty".to_string());
+    pixel_format: Option<String>,
+    wait_for_frame: Option<bool>,
+) -> Result<(), AppError> {
+    let devices = current_devices(&camera_state)?;
+    let device_id = resolve_device_id(&device_id, &devices)?;
+
+    let op_app = app.clone();
+    let op_device = device_id.clone();
+    queue
+        .run(
+            &DeviceId::new(&device_id),
+            OpKind::PreviewStart,
+            move |_| async move {
+                replace_session(
+                    &op_app,
+                    &op_device,
+                    width,
+                    height,
+                    fps,
+                    pixel_format,
+                    SessionMode::Full,
+                )
+            },
+        )
+        .await?;
+    crate::tray::notify_activity(&app);
+
+    // Auto-started previews come back at this format
+    if let Some(device) = devices.iter().find(|d| d.id.as_str() == device_id) {
+        let format = PreviewFormat { width, height, fps };
+        app.state::<SettingsState>()
+            .store
+            .set_preview_format(&device_id, &device.name, format);
     }
 
-    // Resolve device_id to the actual device path and name needed by DirectShow
-    let (device_path, friendly_name) = resolve_device_info(&camera_state, &device_id)?;
+    if !wait_for_frame.unwrap_or(false) {
+        return Ok(());
+    }
 
-    let mut sessions = state.sessions.lock();
-    if sessions.contains_key(&device_id) {
-        if let Some(mut existing) = sessions.remove(&device_id) {
-            existing.stop();
+    match await_first_frame(&app, &device_id, DEFAULT_FIRST_FRAME_TIMEOUT).await {
+        FirstFrameOutcome::Ready { .. } => Ok(()),
+        FirstFrameOutcome::Timeout => Err(AppError::new(
+            code::PREVIEW_FAILED,
+            format!(
+                "no frames received from {device_id} within {}s",
+                DEFAULT_FIRST_FRAME_TIMEOUT.as_secs()
+            ),
+        )),
+        FirstFrameOutcome::SessionError { message } => {
+            Err(AppError::classify(code::PREVIEW_FAILED, message))
         }
     }
+}
 
-    let session = create_preview_session(
-        &app,
-        &canon_state,
-        &gpu_state,
-        &device_id,
-        &device_path,
-        &friendly_name,
-        width,
-        height,
-        fps,
-    )?;
-    sessions.insert(device_id, session);
-    Ok(())
+/// Wait until the preview session for `device_id` delivers its first frame.
+///
+/// Polls on a blocking task, taking the sessions lock only for each
+/// individual check so other commands are never held up by the wait.
+async fn await_first_frame(
+    app: &AppHandle,
+    device_id: &str,
+    timeout: Duration,
+) -> FirstFrameOutcome {
+    let app = app.clone();
+    let device_id = device_id.to_string();
+    let task = tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<PreviewState>();
+        poll_first_frame(
+            || match state.sessions.lock().get(&device_id) {
+                Some(session) => session.probe_first_frame(),
+                None => FrameProbe::Failed("no active preview for this device".to_string()),
+            },
+            timeout,
+            FIRST_FRAME_POLL_INTERVAL,
+        )
+    });
+
+    task.await
+        .unwrap_or_else(|e| FirstFrameOutcome::SessionError {
+            message: format!("first-frame wait failed: {e}"),
+        })
+}
+
+/// Block until video is flowing for a started preview, or `timeout_ms`
+/// elapses. Returns the negotiated frame size on success.
+#[tauri::command]
+pub async fn wait_for_first_frame(
+    app: AppHandle,
+    device_id: String,
+    timeout_ms: u64,
+) -> Result<FirstFrameOutcome, AppError> {
+    Ok(await_first_frame(&app, &device_id, Duration::from_millis(timeout_ms)).await)
+}
+
+/// Whether the preview for `device_id` delivers a frame within `timeout`,
+/// or `None` if it has no session to probe.
+pub async fn probe_preview(app: &AppHandle, device_id: &str, timeout: Duration) -> Option<bool> {
+    if !app
+        .state::<PreviewState>()
+        .sessions
+        .lock()
+        .contains_key(device_id)
+    {
+        return None;
+    }
+    let outcome = await_first_frame(app, device_id, timeout).await;
+    Some(matches!(outcome, FirstFrameOutcome::Ready { .. }))
 }
 
 /// Create a `PreviewSession` for the given device.
@@ -118,68 +957,180 @@ fn create_preview_session(
     width: u32,
     height: u32,
     fps: f32,
-) -> Result<PreviewSession, String> {
+    pixel_format: Option<String>,
+    mode: SessionMode,
+) -> Result<PreviewSession, AppError> {
     // Canon live view: device_path starts with "edsdk://"
-    if device_path.starts_with("edsdk://") {
-        return create_canon_session(canon_state, device_id, device_path);
-    }
+    let session = if device_path.starts_with("edsdk://") {
+        let format = PreviewFormat { width, height, fps };
+        create_canon_session(app, canon_state, device_id, device_path, format)?
+    } else {
+        let on_error = make_error_callback(app);
+        let gpu = gpu_state.context();
+        PreviewSession::DirectShow(CaptureSession::new(
+            device_id.to_string(),
+            device_path.to_string(),
+            friendly_name.to_string(),
+            width,
+            height,
+            fps,
+            pixel_format,
+            mode,
+            saved_video_source(app, device_id),
+            CAPTURE_RESTART,
+            Some(on_error),
+            Some(make_content_callback(app)),
+            Some(make_started_callback(app)),
+            Some(make_recovering_callback(app)),
+            gpu,
+            FRAME_JPEG_QUALITY,
+        ))
+    };
+    session.set_orientation(saved_orientation(app, device_id));
+    session.set_post_processing(saved_post_processing(app, device_id));
+    session.set_colour_space(saved_colour_space(app, device_id));
+    Ok(session)
+}
 
-    let on_error = make_error_callback(app);
-    let gpu = gpu_state.context();
+/// Create the capture session auto-started for a DirectShow `device` at
+/// launch, at its saved format and in its saved mode, with its saved
+/// video source and processing.
+pub(crate) fn startup_capture_session(
+    store: &SettingsStore,
+    device: &CameraDevice,
+    gpu: Option<Arc<GpuContext>>,
+    on_error: Option<ErrorCallback>,
+    on_content: Option<ContentCallback>,
+    on_started: Option<StartedCallback>,
+    on_recovering: Option<RecoveringCallback>,
+) -> CaptureSession {
+    let device_id = device.id.as_str();
+    // Thumbnail-only unless the camera is set otherwise
+    let mode = if store.full_resolution_autostart(device_id) {
+        SessionMode::Full
+    } else {
+        SessionMode::ThumbnailOnly
+    };
+    let format = saved_preview_format(store, device_id);
     let session = CaptureSession::new(
-        device_path.to_string(),
-        friendly_name.to_string(),
-        width,
-        height,
-        fps,
-        Some(on_error),
+        device_id.to_string(),
+        device.device_path.clone(),
+        device.name.clone(),
+        format.width,
+        format.height,
+        format.fps,
+        None,
+        mode,
+        store.video_source(device_id),
+        CAPTURE_RESTART,
+        on_error,
+        on_content,
+        on_started,
+        on_recovering,
         gpu,
-        75,
+        FRAME_JPEG_QUALITY,
     );
-    Ok(PreviewSession::DirectShow(session))
+    if let Some(camera) = store.get_camera(device_id) {
+        session.set_post_processing(camera.post_processing);
+        session.set_colour_space(camera.colour_space);
+    }
+    session
 }
 
-/// Create a Canon live view capture session.
+/// Create a Canon live view capture session, polling at `format`'s frame
+/// rate.
 ///
-/// Resolves the CameraHandle from the shared handle map and starts live view.
+/// Resolves the CameraHandle from the shared handle map and starts live view
+/// on the session's capture thread, which reports failures like any other.
 fn create_canon_session(
+    app: &AppHandle,
     canon_state: &CanonSdkState,
     device_id: &str,
     device_path: &str,
-) -> Result<PreviewSession, String> {
+    format: PreviewFormat,
+) -> Result<PreviewSession, AppError> {
     #[cfg(all(feature = "canon", target_os = "windows"))]
     {
         let sdk = canon_state
             .sdk()
-            .ok_or_else(|| "Canon SDK not available".to_string())?;
-        let handle = canon_state
-            .find_handle(device_path)
-            .ok_or_else(|| format!("Canon camera not found: {device_path}"))?;
+            .ok_or_else(|| AppError::new(code::CANON_SDK, "Canon SDK not available"))?;
+        let handle = canon_state.find_handle(device_path).ok_or_else(|| {
+            AppError::new(
+                code::DEVICE_NOT_FOUND,
+                format!("Canon camera not found: {device_path}"),
+            )
+        })?;
 
         let session = super::capture::CanonCaptureSession::new(
             device_id.to_string(),
-            Arc::clone(sdk),
+            device_path.to_string(),
+            sdk,
             handle,
-        )?;
+            format,
+            Some(make_error_callback(app)),
+            Some(make_started_callback(app)),
+            FRAME_JPEG_QUALITY,
+        );
         Ok(PreviewSession::Canon(session))
     }
 
     #[cfg(not(all(feature = "canon", target_os = "windows")))]
     {
-        let _ = (canon_state, device_id, device_path);
-        Err("Canon support not available in this build".to_string())
+        let _ = (app, canon_state, device_id, device_path, format);
+        Err(AppError::new(
+            code::UNSUPPORTED,
+            "Canon support not available in this build",
+        ))
     }
 }
 
 /// Build a standard error callback that emits `preview-error` events.
 fn make_error_callback(app: &AppHandle) -> super::capture::ErrorCallback {
     let app = app.clone();
-    Arc::new(move |dev_id: &str, error: &str| {
+    Arc::new(move |payload: PreviewErrorPayload| {
+        let _ = app.emit("preview-error", payload);
+        crate::tray::notify_activity(&app);
+    })
+}
+
+/// Build a callback that emits `preview-started` events with the format a
+/// session's capture graph settled on.
+fn make_started_callback(app: &AppHandle) -> super::capture::StartedCallback {
+    let app = app.clone();
+    Arc::new(move |payload: PreviewStartedPayload| {
+        tracing::info!(
+            "Preview for {} started at {}x{} {}",
+            payload.device_id,
+            payload.width,
+            payload.height,
+            payload.pixel_format
+        );
+        let _ = app.emit("preview-started", payload);
+    })
+}
+
+/// Build a callback that emits `preview-recovering` events when a session's
+/// capture graph failed and is about to be rebuilt.
+fn make_recovering_callback(app: &AppHandle) -> super::capture::RecoveringCallback {
+    let app = app.clone();
+    Arc::new(move |payload: PreviewRecoveringPayload| {
+        let _ = app.emit("preview-recovering", payload);
+    })
+}
+
+/// Build a callback that emits `preview-content-warning` events when a
+/// session's frames turn black or frozen, or recover.
+fn make_content_callback(app: &AppHandle) -> super::capture::ContentCallback {
+    let app = app.clone();
+    Arc::new(move |dev_id: &str, health| {
+        if health != ContentHealth::Ok {
+            tracing::warn!("Preview content for {dev_id} is {health:?}");
+        }
         let _ = app.emit(
-            "preview-error",
-            PreviewErrorPayload {
+            "preview-content-warning",
+            PreviewContentPayload {
                 device_id: dev_id.to_string(),
-                error: humanise_error(error),
+                health,
             },
         );
     })
@@ -187,8 +1138,12 @@ fn make_error_callback(app: &AppHandle) -> super::capture::ErrorCallback {
 
 /// Start capture sessions for all currently connected cameras.
 ///
-/// Skips devices that already have an active session. Uses sensible defaults
-/// (640x480, 30fps) — the frontend can reconfigure individual sessions later.
+/// Skips devices that already have an active session, and IR/depth sibling
+/// devices unless the user opted in. Sessions start thumbnail-only unless
+/// the camera is set to auto-start at full resolution; `upgrade_preview`
+/// (or `start_preview`) brings one up to full resolution when it's opened.
+/// Full sessions capture at the format `start_preview` last used for the
+/// camera, or 640x480 at 30 fps.
 #[tauri::command]
 pub async fn start_all_previews(
     app: AppHandle,
@@ -196,22 +1151,35 @@ pub async fn start_all_previews(
     camera_state: State<'_, CameraState>,
     canon_state: State<'_, CanonSdkState>,
     gpu_state: State<'_, GpuState>,
-) -> Result<(), String> {
-    let devices = camera_state
-        .backend
-        .enumerate_devices()
-        .map_err(|e| humanise_error(&format!("failed to enumerate devices: {e}")))?;
+) -> Result<(), AppError> {
+    let devices = camera_state.backend.enumerate_devices()?;
 
+    let include_non_primary = auto_start_non_primary(&app);
     let mut sessions = state.sessions.lock();
 
     for device in &devices {
         let device_id = device.id.as_str().to_string();
 
-        // Skip if session already exists
-        if sessions.contains_key(&device_id) {
+        // Skip if session already exists, under this ID or another spelling
+        if !existing_sessions(&sessions, device).is_empty() {
             continue;
         }
 
+        if !should_auto_start(device, include_non_primary) {
+            tracing::debug!(
+                "Not auto-starting {:?} device '{}'",
+                device.kind,
+                device.name
+            );
+            continue;
+        }
+
+        if let Err(e) = state.admit(&sessions, &device_id) {
+            tracing::warn!("Not auto-starting '{}': {e}", device.name);
+            break;
+        }
+
+        let format = auto_start_format(&app, &device_id);
         match create_preview_session(
             &app,
             &canon_state,
@@ -219,9 +1187,11 @@ pub async fn start_all_previews(
             &device_id,
             &device.device_path,
             &device.name,
-            640,
-            480,
-            30.0,
+            format.width,
+            format.height,
+            format.fps,
+            None,
+            auto_start_mode(&app, &device_id),
         ) {
             Ok(session) => {
                 sessions.insert(device_id.clone(), session);
@@ -237,7 +1207,8 @@ pub async fn start_all_previews(
 }
 
 /// Start a capture session for a single device by ID. Used by the hotplug
-/// bridge when a new camera is connected.
+/// bridge when a new camera is connected; starts thumbnail-only like
+/// `start_all_previews`.
 pub fn start_preview_for_device(app: &AppHandle, device_id: &str) {
     let preview_state = match app.try_state::<PreviewState>() {
         Some(s) => s,
@@ -266,16 +1237,39 @@ pub fn start_preview_for_device(app: &AppHandle, device_id: &str) {
         }
     };
 
+    if !should_auto_start(device, auto_start_non_primary(app)) {
+        tracing::debug!(
+            "Not auto-starting {:?} device '{}'",
+            device.kind,
+            device.name
+        );
+        return;
+    }
+
     let mut sessions = preview_state.sessions.lock();
-    if sessions.contains_key(device_id) {
+    if !existing_sessions(&sessions, device).is_empty() {
+        return;
+    }
+    if let Err(e) = preview_state.admit(&sessions, device_id) {
+        tracing::warn!("Not auto-starting '{}' on hotplug: {e}", device.name);
         return;
     }
 
     // Canon live view: device_path starts with "edsdk://"
     if device.device_path.starts_with("edsdk://") {
         if let Some(canon_state) = app.try_state::<CanonSdkState>() {
-            match create_canon_session(canon_state.inner(), device_id, &device.device_path) {
+            let format = auto_start_format(app, device_id);
+            let canon = create_canon_session(
+                app,
+                canon_state.inner(),
+                device_id,
+                &device.device_path,
+                format,
+            );
+            match canon {
                 Ok(session) => {
+                    session.set_orientation(saved_orientation(app, device_id));
+                    session.set_post_processing(saved_post_processing(app, device_id));
                     sessions.insert(device_id.to_string(), session);
                     tracing::info!(
                         "Auto-started Canon preview for '{}' on hotplug",
@@ -297,16 +1291,28 @@ pub fn start_preview_for_device(app: &AppHandle, device_id: &str) {
     let on_error = make_error_callback(app);
     let gpu = app.try_state::<GpuState>().and_then(|s| s.context());
 
+    let format = auto_start_format(app, device_id);
     let session = CaptureSession::new(
+        device_id.to_string(),
         device.device_path.clone(),
         device.name.clone(),
-        640,
-        480,
-        30.0,
+        format.width,
+        format.height,
+        format.fps,
+        None,
+        auto_start_mode(app, device_id),
+        saved_video_source(app, device_id),
+        CAPTURE_RESTART,
         Some(on_error),
+        Some(make_content_callback(app)),
+        Some(make_started_callback(app)),
+        Some(make_recovering_callback(app)),
         gpu,
-        75,
+        FRAME_JPEG_QUALITY,
     );
+    session.set_orientation(saved_orientation(app, device_id));
+    session.set_post_processing(saved_post_processing(app, device_id));
+    session.set_colour_space(saved_colour_space(app, device_id));
     sessions.insert(device_id.to_string(), PreviewSession::DirectShow(session));
     tracing::info!(
         "Auto-started preview session for '{}' on hotplug",
@@ -321,590 +1327,3471 @@ pub fn stop_preview_for_device(app: &AppHandle, device_id: &str) {
         None => return,
     };
 
-    let mut sessions = preview_state.sessions.lock();
-    if let Some(mut session) = sessions.remove(device_id) {
-        session.stop();
+    if preview_state.stop_session(device_id) {
         tracing::info!("Stopped preview session for disconnected device: {device_id}");
     }
-    preview_state.jpeg_cache.lock().remove(device_id);
 }
 
-/// Stop a camera preview session. Idempotent.
-#[tauri::command]
-pub async fn stop_preview(state: State<'_, PreviewState>, device_id: String) -> Result<(), String> {
-    let mut sessions = state.sessions.lock();
-    if let Some(mut session) = sessions.remove(&device_id) {
-        session.stop();
+/// Whether the user opted in to keeping the default camera warm.
+fn keep_default_warm(app: &AppHandle) -> bool {
+    app.try_state::<SettingsState>()
+        .is_some_and(|s| s.store.keep_default_warm())
+}
+
+/// Cameras to prefer as the default, in order: the one the user chose (or
+/// that was suggested on first run), then the one they last selected.
+fn preferred_default_cameras(app: &AppHandle) -> Vec<String> {
+    let Some(state) = app.try_state::<SettingsState>() else {
+        return Vec::new();
+    };
+    [
+        state.store.default_camera(),
+        state.ui_state.last_selected_camera(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Managed state for keeping the default camera warm.
+pub struct WarmState {
+    machine: Mutex<WarmMachine>,
+    /// Window visibility changes, applied in order off the UI thread.
+    window_events: std::sync::mpsc::Sender<WarmEvent>,
+}
+
+/// Manage the keep-warm state and start its worker.
+///
+/// Runs before previews auto-start, so a camera that should be warm starts
+/// straight at thumbnail size and the auto-start skips it.
+pub fn setup_warm(
+    app: &AppHandle,
+    devices: &[CameraDevice],
+    window_visible: bool,
+) -> std::io::Result<()> {
+    let mut machine = WarmMachine::new(window_visible);
+    let preferred = preferred_default_cameras(app);
+    machine.handle(WarmEvent::DefaultDeviceChanged(
+        default_device(devices, &preferred).map(|d| d.id.as_str().to_string()),
+    ));
+    for action in machine.handle(WarmEvent::PreferenceChanged(keep_default_warm(app))) {
+        apply_warm_action(app, &action);
     }
-    // Remove cached JPEG for this device
-    state.jpeg_cache.lock().remove(&device_id);
+
+    let (window_events, events) = std::sync::mpsc::channel();
+    app.manage(WarmState {
+        machine: Mutex::new(machine),
+        window_events,
+    });
+
+    let app = app.clone();
+    std::thread::Builder::new()
+        .name("preview-warm".to_string())
+        .spawn(move || {
+            for event in events {
+                update_warm(&app, event);
+            }
+        })?;
     Ok(())
 }
 
-/// Get the latest frame as base64-encoded JPEG.
+/// Feed `event` to the keep-warm machine and carry out the restarts it
+/// calls for. Blocks while sessions restart.
+pub fn update_warm(app: &AppHandle, event: WarmEvent) {
+    let warm = match app.try_state::<WarmState>() {
+        Some(s) => s,
+        None => return,
+    };
+    // Held across the restarts so they apply in the order they were decided
+    let mut machine = warm.machine.lock();
+    for action in machine.handle(event) {
+        apply_warm_action(app, &action);
+    }
+}
+
+/// Tell the keep-warm machine the main window was shown or hidden.
 ///
-/// Reads pre-encoded JPEG from the async encode worker's output buffer.
-/// Caches the base64 result per device — if the sequence hasn't changed
-/// since the last call, the cached string is returned immediately.
-#[tauri::command]
-pub async fn get_frame(
-    state: State<'_, PreviewState>,
-    device_id: String,
-) -> Result<String, String> {
-    let (jpeg_frame, seq) = {
-        let sessions = state.sessions.lock();
-        let session = sessions
-            .get(&device_id)
-            .ok_or_else(|| "no active preview for this device".to_string())?;
-
-        // Try the JPEG buffer first (from the encode worker)
-        if let Some(jpeg_buf) = session.jpeg_buffer() {
-            let seq = jpeg_buf.sequence();
-            if let Some(frame) = jpeg_buf.latest() {
-                (Some(frame), seq)
-            } else {
-                (None, 0)
-            }
-        } else {
-            (None, 0)
+/// Called from window events, so the restart happens on the keep-warm
+/// worker rather than the UI thread.
+pub fn main_window_visibility_changed(app: &AppHandle, visible: bool) {
+    let event = if visible {
+        WarmEvent::WindowShown
+    } else {
+        WarmEvent::WindowHidden
+    };
+    if let Some(warm) = app.try_state::<WarmState>() {
+        let _ = warm.window_events.send(event);
+    }
+}
+
+/// Re-derive the default camera, e.g. after a hotplug event.
+///
+/// Enumerates rather than reading the known device list, so a camera that
+/// reconnects keeps its place in the system's order. Skipped while the
+/// preference is off; enabling it refreshes the default first.
+pub fn refresh_warm_default(app: &AppHandle) {
+    if !keep_default_warm(app) {
+        return;
+    }
+    let camera_state = match app.try_state::<CameraState>() {
+        Some(s) => s,
+        None => return,
+    };
+    let devices = match camera_state.backend.enumerate_devices() {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::warn!("Failed to enumerate devices for keep-warm: {e}");
+            return;
         }
     };
+    refresh_warm_default_from(app, &devices);
+}
+
+/// Re-derive the default camera from devices already enumerated, e.g. the
+/// ones found so far at startup. Skipped while the preference is off.
+pub fn refresh_warm_default_from(app: &AppHandle, devices: &[CameraDevice]) {
+    if !keep_default_warm(app) {
+        return;
+    }
+    let preferred = preferred_default_cameras(app);
+    let default = default_device(devices, &preferred).map(|d| d.id.as_str().to_string());
+    update_warm(app, WarmEvent::DefaultDeviceChanged(default));
+}
+
+/// Restart the session a keep-warm action targets at the action's size.
+fn apply_warm_action(app: &AppHandle, action: &WarmAction) {
+    let device_id = action.device_id();
+    let (width, height) = match action.tier() {
+        // The graph picks the closest mode it has to the thumbnail box
+        Tier::Thumbnail => app
+            .try_state::<PreviewState>()
+            .map(|s| s.thumbnail_config(device_id).physical_box())
+            .unwrap_or_else(|| ThumbnailConfig::default().physical_box()),
+        Tier::Full => AUTO_START_SIZE,
+    };
+    if let Err(e) = restart_session_at(app, device_id, width, height, action.starts_session()) {
+        tracing::warn!("Failed to restart preview for {device_id} at {width}x{height}: {e}");
+    }
+}
 
-    // If the encode worker has a JPEG frame, use it directly
-    if let Some(jpeg_frame) = jpeg_frame {
-        // Check cache — return early if the frame hasn't changed
-        {
-            let cache = state.jpeg_cache.lock();
-            if let Some(cached) = cache.get(&device_id) {
-                if cached.sequence == seq {
-                    return Ok(cached.base64.clone());
-                }
-            }
+/// Replace the DirectShow session for `device_id` with one capturing at
+/// `width`x`height`, seeding it with the old session's last frame so the
+/// preview doesn't flash black while the new graph starts.
+///
+/// A device with no session is only started when `create` is set.
+fn restart_session_at(
+    app: &AppHandle,
+    device_id: &str,
+    width: u32,
+    height: u32,
+    create: bool,
+) -> Result<(), AppError> {
+    let preview_state = app
+        .try_state::<PreviewState>()
+        .ok_or_else(|| AppError::new(code::INTERNAL, "preview state not available"))?;
+    let camera_state = app
+        .try_state::<CameraState>()
+        .ok_or_else(|| AppError::new(code::INTERNAL, "camera state not available"))?;
+    let device = camera_state
+        .backend
+        .known_device(&DeviceId::new(device_id))
+        .ok_or_else(|| not_found(device_id))?;
+    let gpu = app.try_state::<GpuState>().and_then(|s| s.context());
+
+    let mut sessions = preview_state.sessions.lock();
+    preview_state.admit(&sessions, device_id)?;
+    let last_frame = match sessions.remove(device_id) {
+        Some(mut previous) => {
+            let last = previous.jpeg_buffer().and_then(|buffer| buffer.latest());
+            previous.stop();
+            last
         }
+        None if create => None,
+        None => return Ok(()),
+    };
 
-        let base64 = base64::Engine::encode(
-            &base64::engine::general_purpose::STANDARD,
-            &jpeg_frame.jpeg_bytes,
-        );
+    let session = CaptureSession::new(
+        device_id.to_string(),
+        device.device_path.clone(),
+        device.name.clone(),
+        width,
+        height,
+        AUTO_START_FPS,
+        None,
+        SessionMode::Full,
+        saved_video_source(app, device_id),
+        CAPTURE_RESTART,
+        Some(make_error_callback(app)),
+        Some(make_content_callback(app)),
+        Some(make_started_callback(app)),
+        Some(make_recovering_callback(app)),
+        gpu,
+        FRAME_JPEG_QUALITY,
+    );
+    session.set_orientation(saved_orientation(app, device_id));
+    session.set_post_processing(saved_post_processing(app, device_id));
+    session.set_colour_space(saved_colour_space(app, device_id));
+    if let Some(frame) = last_frame {
+        session.seed_frame(&frame);
+    }
+    sessions.insert(device_id.to_string(), PreviewSession::DirectShow(session));
+    preview_state.forget_cached(device_id);
+    drop(sessions);
 
-        let mut cache = state.jpeg_cache.lock();
-        cache.insert(
-            device_id,
-            JpegCache {
-                sequence: seq,
-                base64: base64.clone(),
+    crate::tray::notify_activity(app);
+    tracing::info!(
+        "Restarted preview for '{}' at {width}x{height}",
+        device.name
+    );
+    Ok(())
+}
+
+/// Whether the default camera is kept warm while the window is hidden.
+#[tauri::command]
+pub async fn get_keep_default_warm(
+    settings_state: State<'_, SettingsState>,
+) -> Result<bool, AppError> {
+    Ok(settings_state.store.keep_default_warm())
+}
+
+/// Opt in to (or out of) keeping the default camera's capture graph running
+/// at thumbnail size while the main window is hidden, so showing the window
+/// has video straight away.
+#[tauri::command]
+pub async fn set_keep_default_warm(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_state.store.set_keep_default_warm(enabled);
+    // The default isn't tracked while the preference is off
+    refresh_warm_default(&app);
+    update_warm(&app, WarmEvent::PreferenceChanged(enabled));
+    Ok(())
+}
+
+/// Stop a camera preview session. Idempotent.
+#[tauri::command]
+pub async fn stop_preview(
+    app: AppHandle,
+    queue: State<'_, DeviceQueue>,
+    device_id: String,
+) -> Result<(), AppError> {
+    // Not checked against the enumeration, so an unplugged camera's session
+    // can still be stopped
+    let device_id = device_id.trim().to_string();
+    let op_device = device_id.clone();
+    queue
+        .run(
+            &DeviceId::new(&device_id),
+            OpKind::PreviewStop,
+            move |_| async move {
+                stop_preview_session(&app, &op_device);
+                Ok(())
             },
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// The latest preview frame of a device, as `get_frame` serves it.
+enum LatestFrame {
+    /// Base64 "no signal" card.
+    Placeholder(String),
+    Source {
+        source: FrameSource,
+        sequence: u64,
+        orientation: Orientation,
+    },
+}
+
+/// Pick the frame `get_frame` serves for `device_id` as `variant`, counting
+/// it as consumed: the "no signal" card when that's on and due, otherwise
+/// the latest frame.
+fn latest_preview_frame(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+    variant: FrameVariant,
+) -> Result<LatestFrame, AppError> {
+    let placeholder_name = settings_state.store.placeholder_name(device_id);
+    let sessions = state.sessions.lock();
+    let session = sessions.get(device_id).ok_or_else(no_preview)?;
+    if session.mode() == SessionMode::ThumbnailOnly {
+        return Err(AppError::new(
+            code::PREVIEW_THUMBNAIL_ONLY,
+            THUMBNAIL_ONLY_ERROR,
+        ));
+    }
+    let signal = placeholder_state(
+        placeholder_name.is_some(),
+        session.is_failed(),
+        session.since_last_frame(),
+    );
+    if let (Some(signal), Some(name)) = (signal, &placeholder_name) {
+        drop(sessions);
+        return Ok(LatestFrame::Placeholder(
+            state.placeholder_frame(device_id, signal, name),
+        ));
+    }
+    let orientation = session.orientation();
+    let (source, sequence) =
+        select_frame_source(session, orientation, variant).ok_or_else(no_frame)?;
+    session.record_consumed(PREVIEW_CONSUMER, source.delivered_sequence(sequence));
+    Ok(LatestFrame::Source {
+        source,
+        sequence,
+        orientation,
+    })
+}
+
+/// Encode a preview frame the way `get_frame` serves it, through the
+/// preview's processor chain, cropped to `crop` if given. Returns the JPEG
+/// and the quality it was compressed at.
+fn encode_preview_frame(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+    source: &FrameSource,
+    orientation: Orientation,
+    crop: Option<PixelRect>,
+) -> Result<(Vec<u8>, u8), AppError> {
+    if let Some(crop) = crop {
+        let rendered = render_preview(settings_state, device_id, source, orientation, Some(crop))?;
+        let quality = state.frame_quality(device_id, || {
+            saved_quality_profile(settings_state, device_id)
+        });
+        let started = Instant::now();
+        let jpeg =
+            compress::compress_jpeg(&rendered.data, rendered.width, rendered.height, quality);
+        state.record_encode(device_id, started.elapsed());
+        return Ok((jpeg, quality));
+    }
+    let (chain, camera) = settings_state
+        .store
+        .output_chain(device_id, OutputTarget::Preview);
+    if chain.is_empty() && !source.needs_compression(orientation) {
+        let jpeg = encode_frame_source(source, orientation, FRAME_JPEG_QUALITY)
+            .map_err(AppError::with_code(code::PREVIEW_FAILED))?;
+        return Ok((jpeg, FRAME_JPEG_QUALITY));
+    }
+    let quality = state.frame_quality(device_id, || {
+        saved_quality_profile(settings_state, device_id)
+    });
+    let started = Instant::now();
+    let jpeg = encode_for_output(
+        source,
+        orientation,
+        quality,
+        &chain,
+        &watermark_context(camera),
+    )
+    .map_err(AppError::with_code(code::PREVIEW_FAILED))?;
+    state.record_encode(device_id, started.elapsed());
+    Ok((jpeg, quality))
+}
+
+/// Encode a preview frame as PNG, through the preview's processor chain,
+/// cropped to `crop` if given. Lossless, so neither the adaptive quality nor
+/// its encode timings apply.
+fn encode_preview_png(
+    settings_state: &SettingsState,
+    device_id: &str,
+    source: &FrameSource,
+    orientation: Orientation,
+    crop: Option<PixelRect>,
+) -> Result<Vec<u8>, AppError> {
+    let rendered = render_preview(settings_state, device_id, source, orientation, crop)?;
+    Ok(compress::compress_png(
+        &rendered.data,
+        rendered.width,
+        rendered.height,
+    ))
+}
+
+/// Render a preview frame to RGB through the preview's processor chain,
+/// cropped to `crop` first if given. A crop is clamped to the oriented
+/// frame; one entirely outside it is an error.
+fn render_preview(
+    settings_state: &SettingsState,
+    device_id: &str,
+    source: &FrameSource,
+    orientation: Orientation,
+    crop: Option<PixelRect>,
+) -> Result<render::RenderedFrame, AppError> {
+    let (chain, camera) = settings_state
+        .store
+        .output_chain(device_id, OutputTarget::Preview);
+    let mut rendered = render_frame_source(source, orientation)
+        .map_err(AppError::with_code(code::PREVIEW_FAILED))?;
+    if let Some(crop) = crop {
+        let clamped = crop
+            .clamp_to(rendered.width, rendered.height)
+            .ok_or_else(|| {
+                AppError::new(
+                    code::INVALID_ARGUMENT,
+                    format!(
+                        "crop {}x{} at ({}, {}) is outside the {}x{} frame",
+                        crop.width, crop.height, crop.x, crop.y, rendered.width, rendered.height
+                    ),
+                )
+            })?;
+        rendered.data =
+            compress::crop_rgb(&rendered.data, rendered.width, rendered.height, clamped);
+        rendered.width = clamped.width;
+        rendered.height = clamped.height;
+    }
+    output::apply_chain(&chain, &mut rendered, &watermark_context(camera));
+    Ok(rendered)
+}
+
+/// `jpeg`, compressed at `quality`, recompressed as needed to fit the
+/// response budget. The frame is rendered once and then compressed at
+/// stepped-down qualities; see [`fit_to_budget`].
+fn fit_preview_frame(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+    source: &FrameSource,
+    orientation: Orientation,
+    crop: Option<PixelRect>,
+    (jpeg, quality): (Vec<u8>, u8),
+) -> Result<Vec<u8>, AppError> {
+    let max_bytes = state.max_response_bytes.load(Ordering::Relaxed);
+    if payload::base64_len(jpeg.len()) <= max_bytes {
+        return Ok(jpeg);
+    }
+    let rendered = render_preview(settings_state, device_id, source, orientation, crop)?;
+    let fitted = fit_to_budget(jpeg, quality, max_bytes, |quality| {
+        Ok::<_, AppError>(compress::compress_jpeg(
+            &rendered.data,
+            rendered.width,
+            rendered.height,
+            quality,
+        ))
+    })?;
+    state.payloads.record_fit(&fitted);
+    if !fitted.fits {
+        tracing::debug!(
+            "Frame for {device_id} is still {} bytes at quality {}",
+            fitted.jpeg.len(),
+            fitted.quality
         );
+    }
+    Ok(fitted.jpeg)
+}
+
+/// Get the latest frame as base64-encoded JPEG.
+///
+/// Prefers pre-encoded JPEG from the async encode worker, falling back to
+/// rendering the raw frame. Caches the base64 result per device — if the
+/// sequence and orientation haven't changed since the last call, the cached
+/// string is returned immediately.
+///
+/// A frame whose base64 would be over the response budget (2MB unless the
+/// settings file says otherwise) is recompressed at lower quality until it
+/// fits; `get_frame_chunked` serves it at full quality instead.
+///
+/// `format` is `"jpeg"` (the default) or `"png"`. PNG is lossless, for
+/// checking focus on fine detail; it's made from the raw frame, cached
+/// alongside the JPEG, and served whatever its size, as it has no quality
+/// to lower.
+///
+/// `crop` (`{x, y, width, height}` in pixels of the oriented frame) serves
+/// just that region, at full resolution, for inspecting detail without
+/// sending the whole frame. It's clamped to the frame; an empty one, or one
+/// entirely outside the frame, is rejected. The last crop asked for is
+/// cached beside the whole frame.
+///
+/// With the camera's `placeholder_on_error` setting on, a "no signal" card is
+/// returned instead while the session has failed or stalled. The card is
+/// always JPEG, and never cropped.
+#[tauri::command]
+pub async fn get_frame(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    format: Option<String>,
+    crop: Option<PixelRect>,
+) -> Result<String, AppError> {
+    let variant = FrameVariant {
+        format: parse_frame_format(format.as_deref())?,
+        crop: check_crop(crop)?,
+        thumbnail: None,
+    };
+    let (_, base64) = preview_frame_base64(&state, &settings_state, &device_id, variant, None)?
+        .ok_or_else(no_frame)?;
+    Ok(base64)
+}
+
+/// The [`FrameFormat`] a command was asked for, or JPEG if none.
+fn parse_frame_format(format: Option<&str>) -> Result<FrameFormat, AppError> {
+    FrameFormat::parse(format).map_err(AppError::with_code(code::INVALID_ARGUMENT))
+}
 
-        return Ok(base64);
+/// `crop` if it has an area. Whether it overlaps the frame is only known
+/// once there is one; see [`render_preview`].
+fn check_crop(crop: Option<PixelRect>) -> Result<Option<PixelRect>, AppError> {
+    match crop {
+        Some(rect) if rect.width == 0 || rect.height == 0 => Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("crop must not be empty, got {}x{}", rect.width, rect.height),
+        )),
+        crop => Ok(crop),
     }
+}
 
-    // Fallback: read raw frame and compress on the fly (legacy path)
-    // Canon sessions have no raw buffer, so this path is DirectShow-only.
-    let (frame, seq) = {
-        let sessions = state.sessions.lock();
-        let session = sessions
-            .get(&device_id)
-            .ok_or_else(|| "no active preview for this device".to_string())?;
+/// A device's latest preview frame, once encoded.
+enum PreviewFrame {
+    /// Base64 "no signal" card.
+    Placeholder(String),
+    /// A frame stored in the frame cache under its sequence and orientation.
+    Cached {
+        sequence: u64,
+        orientation: Orientation,
+        jpeg: Arc<Vec<u8>>,
+    },
+}
 
-        let buf = session
-            .buffer()
-            .ok_or_else(|| "no frame available".to_string())?;
-        let f = buf
-            .latest()
-            .ok_or_else(|| "no frame available".to_string())?;
-        (f, buf.sequence())
+/// The latest preview frame of `device_id` as `variant`, compressed once
+/// per sequence and kept in the frame cache, which `get_frame`,
+/// `get_frame_raw` and `preview-frame` subscriptions all serve from.
+/// Returns `None` if the latest frame is still `unless_sequence`; the "no
+/// signal" card counts as sequence 0.
+fn cached_preview_frame(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+    variant: FrameVariant,
+    unless_sequence: Option<u64>,
+) -> Result<Option<PreviewFrame>, AppError> {
+    let latest = latest_preview_frame(state, settings_state, device_id, variant)?;
+    let (source, seq, orientation) = match latest {
+        LatestFrame::Placeholder(_) if unless_sequence == Some(0) => return Ok(None),
+        LatestFrame::Placeholder(card) => return Ok(Some(PreviewFrame::Placeholder(card))),
+        LatestFrame::Source {
+            source,
+            sequence,
+            orientation,
+        } => (source, sequence, orientation),
     };
+    if unless_sequence == Some(seq) {
+        return Ok(None);
+    }
 
-    {
-        let cache = state.jpeg_cache.lock();
-        if let Some(cached) = cache.get(&device_id) {
-            if cached.sequence == seq {
-                return Ok(cached.base64.clone());
-            }
+    // Check cache — return early if the frame hasn't changed
+    let cached = state
+        .jpeg_cache
+        .lock()
+        .get(device_id, variant, seq, orientation);
+    let jpeg = match cached {
+        Some(jpeg) => jpeg,
+        None => {
+            let image = match variant.format {
+                FrameFormat::Jpeg => {
+                    let encoded = encode_preview_frame(
+                        state,
+                        settings_state,
+                        device_id,
+                        &source,
+                        orientation,
+                        variant.crop,
+                    )?;
+                    fit_preview_frame(
+                        state,
+                        settings_state,
+                        device_id,
+                        &source,
+                        orientation,
+                        variant.crop,
+                        encoded,
+                    )?
+                }
+                FrameFormat::Png => encode_preview_png(
+                    settings_state,
+                    device_id,
+                    &source,
+                    orientation,
+                    variant.crop,
+                )?,
+            };
+            let jpeg = Arc::new(image);
+            state.cache_jpeg(
+                &state.jpeg_cache,
+                device_id,
+                CachedJpeg::new(seq, orientation, Arc::clone(&jpeg)).with_variant(variant),
+            );
+            jpeg
         }
-    }
+    };
+    Ok(Some(PreviewFrame::Cached {
+        sequence: seq,
+        orientation,
+        jpeg,
+    }))
+}
 
-    let jpeg = compress::compress_jpeg(&frame.data, frame.width, frame.height, 75);
-    let base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg);
+/// The latest preview frame of `device_id` as `get_frame` serves it as
+/// `variant`, with its sequence number; the "no signal" card has sequence 0.
+/// Returns `None` if the latest frame is still `unless_sequence`.
+///
+/// The base64 is made from the cached image once and kept with it.
+fn preview_frame_base64(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+    variant: FrameVariant,
+    unless_sequence: Option<u64>,
+) -> Result<Option<(u64, String)>, AppError> {
+    let frame = cached_preview_frame(state, settings_state, device_id, variant, unless_sequence)?;
+    let (sequence, base64) = match frame {
+        None => return Ok(None),
+        Some(PreviewFrame::Placeholder(card)) => (0, card),
+        Some(PreviewFrame::Cached {
+            sequence,
+            orientation,
+            jpeg,
+        }) => {
+            let cached =
+                state
+                    .jpeg_cache
+                    .lock()
+                    .get_base64(device_id, variant, sequence, orientation);
+            // Not cached when it's bigger than the whole cache
+            let base64 = cached.unwrap_or_else(|| {
+                base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &*jpeg)
+            });
+            (sequence, base64)
+        }
+    };
+    state.payloads.record_response(base64.len());
+    Ok(Some((sequence, base64)))
+}
 
-    let mut cache = state.jpeg_cache.lock();
-    cache.insert(
+/// The latest preview frame of `device_id` as `get_frame_raw` serves it:
+/// JPEG bytes and the frame's sequence number.
+fn preview_frame_bytes(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+) -> Result<(u64, Arc<Vec<u8>>), AppError> {
+    let frame = cached_preview_frame(
+        state,
+        settings_state,
         device_id,
-        JpegCache {
-            sequence: seq,
-            base64: base64.clone(),
-        },
-    );
-
-    Ok(base64)
+        FrameVariant::default(),
+        None,
+    )?
+    .ok_or_else(no_frame)?;
+    let (sequence, jpeg) = match frame {
+        PreviewFrame::Placeholder(card) => (0, Arc::new(placeholder_bytes(&card)?)),
+        PreviewFrame::Cached { sequence, jpeg, .. } => (sequence, jpeg),
+    };
+    state.payloads.record_response(jpeg.len());
+    Ok((sequence, jpeg))
 }
 
-/// Get a thumbnail (160x120) as base64-encoded JPEG.
+/// Get the latest frame as raw JPEG bytes, without `get_frame`'s base64:
+/// a third smaller, and no string to build or parse.
+///
+/// Served from the same cache as `get_frame`, and kept under the same
+/// response budget. `get_frame_meta` describes the frame.
 #[tauri::command]
-pub async fn get_thumbnail(
+pub async fn get_frame_raw(
     state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
     device_id: String,
-) -> Result<String, String> {
-    let frame = {
-        let sessions = state.sessions.lock();
-        let session = sessions
-            .get(&device_id)
-            .ok_or_else(|| "no active preview for this device".to_string())?;
-
-        let buf = session
-            .buffer()
-            .ok_or_else(|| "thumbnails not available for Canon live view".to_string())?;
-        buf.latest()
-            .ok_or_else(|| "no frame available".to_string())?
-    };
+) -> Result<Response, AppError> {
+    let (_, jpeg) = preview_frame_bytes(&state, &settings_state, &device_id)?;
+    Ok(Response::new(Arc::unwrap_or_clone(jpeg)))
+}
 
-    let thumb = compress::compress_thumbnail(&frame.data, frame.width, frame.height, 160, 120);
-    Ok(base64::Engine::encode(
-        &base64::engine::general_purpose::STANDARD,
-        &thumb,
-    ))
+/// Size and sequence number of a preview frame, for `get_frame_raw`
+/// consumers, which get bare bytes.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameMeta {
+    /// 0 for the "no signal" card.
+    pub sequence: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the JPEG in bytes.
+    pub byte_len: usize,
 }
 
-/// Get diagnostic stats for a camera preview session.
+/// Describe the latest preview frame, encoding and caching it if it isn't
+/// already, so a `get_frame_raw` straight after serves the same frame from
+/// the cache unless a newer one has arrived in between.
 #[tauri::command]
-pub async fn get_diagnostics(
+pub async fn get_frame_meta(
     state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
     device_id: String,
-) -> Result<DiagnosticSnapshot, String> {
-    let sessions = state.sessions.lock();
-    let session = sessions
-        .get(&device_id)
-        .ok_or_else(|| "no active preview for this device".to_string())?;
+) -> Result<FrameMeta, AppError> {
+    let frame = cached_preview_frame(
+        &state,
+        &settings_state,
+        &device_id,
+        FrameVariant::default(),
+        None,
+    )?
+    .ok_or_else(no_frame)?;
+    match frame {
+        PreviewFrame::Placeholder(card) => frame_meta(0, &placeholder_bytes(&card)?),
+        PreviewFrame::Cached { sequence, jpeg, .. } => frame_meta(sequence, &jpeg),
+    }
+}
 
-    Ok(session.diagnostics())
+/// JPEG bytes of a base64 "no signal" card.
+fn placeholder_bytes(card: &str) -> Result<Vec<u8>, AppError> {
+    base64::Engine::decode(&base64::engine::general_purpose::STANDARD, card)
+        .map_err(AppError::with_code(code::INTERNAL))
 }
 
-/// Get encoding performance stats for a camera preview session.
+/// [`FrameMeta`] of `jpeg`, read from its header.
+fn frame_meta(sequence: u64, jpeg: &[u8]) -> Result<FrameMeta, AppError> {
+    let (width, height) =
+        compress::jpeg_dimensions(jpeg).map_err(AppError::with_code(code::INTERNAL))?;
+    Ok(FrameMeta {
+        sequence,
+        width,
+        height,
+        byte_len: jpeg.len(),
+    })
+}
+
+/// Push `device_id`'s preview frames to the webview as `preview-frame`
+/// events instead of having it poll `get_frame`. A new frame is checked
+/// for at most `max_fps` times a second and sent only when it has changed.
 ///
-/// Returns encoder type (hardware/software/CPU), frame counts, and timing.
+/// Frames are the ones `get_frame` would return, from the same cache. A
+/// device has one subscription; subscribing again replaces it. It ends with
+/// `unsubscribe_preview` or when the preview is stopped, and carries on
+/// across restarts of the session in between.
 #[tauri::command]
-pub async fn get_encoding_stats(
+pub async fn subscribe_preview(
+    app: AppHandle,
     state: State<'_, PreviewState>,
     device_id: String,
-) -> Result<EncodingSnapshot, String> {
-    let sessions = state.sessions.lock();
-    let session = sessions
-        .get(&device_id)
-        .ok_or_else(|| "no active preview for this device".to_string())?;
+    max_fps: f32,
+) -> Result<(), AppError> {
+    let interval = subscription::frame_interval(max_fps)
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    if !state.sessions.lock().contains_key(&device_id) {
+        return Err(no_preview());
+    }
+    let stopped = state.subscriptions.lock().subscribe(&device_id);
 
-    session
-        .encoding_snapshot()
-        .ok_or_else(|| "encode worker not active for this device".to_string())
+    tauri::async_runtime::spawn(async move {
+        let latest = |last: Option<u64>| {
+            let app = app.clone();
+            let device_id = device_id.clone();
+            async move {
+                let frame = tauri::async_runtime::spawn_blocking({
+                    let device_id = device_id.clone();
+                    move || {
+                        preview_frame_base64(
+                            &app.state::<PreviewState>(),
+                            &app.state::<SettingsState>(),
+                            &device_id,
+                            FrameVariant::default(),
+                            last,
+                        )
+                    }
+                })
+                .await;
+                match frame {
+                    Ok(Ok(frame)) => frame,
+                    // No frame yet, thumbnail-only, or mid-restart
+                    Ok(Err(_)) => None,
+                    Err(e) => {
+                        tracing::warn!("Preview subscription frame for {device_id} failed: {e}");
+                        None
+                    }
+                }
+            }
+        };
+        let emit = |sequence: u64, jpeg: String| {
+            let _ = app.emit(
+                "preview-frame",
+                PreviewFramePayload {
+                    device_id: device_id.clone(),
+                    sequence,
+                    jpeg,
+                },
+            );
+        };
+        subscription::run(interval, stopped, latest, emit).await;
+        tracing::debug!("Preview subscription for {device_id} ended");
+    });
+
+    Ok(())
 }
 
-/// List all available GPU adapters on the system.
+/// Stop the `preview-frame` events `subscribe_preview` started. Returns
+/// whether the device had a subscription.
 #[tauri::command]
-pub async fn list_gpu_adapters() -> Vec<GpuAdapterInfo> {
-    super::gpu::GpuContext::enumerate_adapters()
+pub async fn unsubscribe_preview(
+    state: State<'_, PreviewState>,
+    device_id: String,
+) -> Result<bool, AppError> {
+    Ok(state.subscriptions.lock().unsubscribe(&device_id))
 }
 
-/// Get information about the currently active GPU adapter.
+/// Get the latest frame at full quality in chunks, for consumers that
+/// can't take `get_frame`'s size-reduced frame.
 ///
-/// Returns `null` if GPU acceleration is disabled (CPU-only mode).
+/// Chunk 0 captures the latest frame and returns its first slice; later
+/// chunks are slices of that same frame, in order, until one comes back
+/// with `last` set. Concatenating the `data` of every chunk gives the
+/// frame's base64; each chunk also decodes on its own. Asking for chunk 0
+/// again starts over with a newer frame, and chunks of different
+/// `sequence`s must not be mixed.
 #[tauri::command]
-pub async fn get_active_gpu(state: State<'_, GpuState>) -> Result<Option<GpuAdapterInfo>, String> {
-    Ok(state.context().map(|ctx| ctx.adapter_info()))
+pub async fn get_frame_chunked(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    chunk: u32,
+) -> Result<FrameChunk, AppError> {
+    if chunk == 0 {
+        let latest =
+            latest_preview_frame(&state, &settings_state, &device_id, FrameVariant::default())?;
+        let (sequence, base64) = match latest {
+            LatestFrame::Placeholder(card) => (0, card),
+            LatestFrame::Source {
+                source,
+                sequence,
+                orientation,
+            } => {
+                let (jpeg, _) = encode_preview_frame(
+                    &state,
+                    &settings_state,
+                    &device_id,
+                    &source,
+                    orientation,
+                )?;
+                let base64 =
+                    base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg);
+                (sequence, base64)
+            }
+        };
+        state
+            .chunked
+            .lock()
+            .insert(device_id.clone(), (sequence, base64));
+    }
+
+    let mut chunked = state.chunked.lock();
+    let (sequence, base64) = chunked.get(&device_id).ok_or_else(|| {
+        AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("no chunked frame in progress for {device_id}; request chunk 0 first"),
+        )
+    })?;
+    let sequence = *sequence;
+    let chunk_count = payload::chunk_count(base64.len());
+    let data = payload::chunk(base64, chunk)
+        .ok_or_else(|| {
+            AppError::new(
+                code::INVALID_ARGUMENT,
+                format!("chunk {chunk} is past the last of {chunk_count}"),
+            )
+        })?
+        .to_string();
+    let last = chunk + 1 == chunk_count;
+    if last {
+        chunked.remove(&device_id);
+    }
+    drop(chunked);
+
+    state.payloads.record_chunk();
+    Ok(FrameChunk {
+        sequence,
+        chunk,
+        chunk_count,
+        data,
+        last,
+    })
 }
 
-/// Switch the active GPU adapter, or disable GPU acceleration.
+/// Message of the `preview_thumbnail_only` error `get_frame` returns for a
+/// thumbnail-only session, on which the frontend calls `upgrade_preview`.
+pub const THUMBNAIL_ONLY_ERROR: &str =
+    "preview is thumbnail-only; call upgrade_preview for full frames";
+
+/// Restart a thumbnail-only preview at full resolution so `get_frame` can
+/// serve it. Does nothing if the session is already full or gone.
 ///
-/// Pass `adapterIndex: null` to switch to CPU-only mode.
-/// Returns the name of the newly selected adapter, or `null` if disabled.
+/// Runs through the device queue like `start_preview`.
 #[tauri::command]
-pub async fn set_gpu_adapter(
-    state: State<'_, GpuState>,
-    adapter_index: Option<usize>,
-) -> Result<Option<String>, String> {
-    Ok(state.set_adapter(adapter_index))
+pub async fn upgrade_preview(
+    app: AppHandle,
+    queue: State<'_, DeviceQueue>,
+    camera_state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<(), AppError> {
+    let device_id = resolve_device_id(&device_id, &current_devices(&camera_state)?)?;
+    let op_app = app.clone();
+    let op_device = device_id.clone();
+    queue
+        .run(
+            &DeviceId::new(&device_id),
+            OpKind::PreviewStart,
+            move |_| async move {
+                let thumbnail_only = op_app
+                    .state::<PreviewState>()
+                    .sessions
+                    .lock()
+                    .get(&op_device)
+                    .is_some_and(|s| s.mode() == SessionMode::ThumbnailOnly);
+                if !thumbnail_only {
+                    return Ok(());
+                }
+                let format = auto_start_format(&op_app, &op_device);
+                replace_session(
+                    &op_app,
+                    &op_device,
+                    format.width,
+                    format.height,
+                    format.fps,
+                    None,
+                    SessionMode::Full,
+                )?;
+                tracing::info!("Upgraded preview for {op_device} to full resolution");
+                Ok(())
+            },
+        )
+        .await?;
+    crate::tray::notify_activity(&app);
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::preview::capture::Frame;
-
-    fn make_preview_state() -> PreviewState {
-        PreviewState::new()
-    }
+/// Frames a `stream_frames` consumer can fall behind by before the oldest
+/// are dropped.
+const STREAM_TAP_CAPACITY: usize = 8;
 
-    fn make_rgb_frame(width: u32, height: u32) -> Frame {
-        let data = vec![128u8; (width * height * 3) as usize];
-        Frame {
-            data,
-            width,
-            height,
-            timestamp_us: 1000,
-        }
-    }
+/// A frame pushed over a `stream_frames` channel.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamedFrame {
+    /// Base64-encoded JPEG, oriented like `get_frame` and run through the
+    /// stream's processor chain.
+    pub jpeg: String,
+    pub width: u32,
+    pub height: u32,
+    pub timestamp_us: u64,
+    /// Frames this stream has dropped so far because it fell behind.
+    pub dropped: u64,
+}
+
+/// Stream every raw frame a camera captures over `channel`, returning a
+/// stream ID for `stop_frame_stream`.
+///
+/// Unlike polling `get_frame`, the consumer sees each frame unless it falls
+/// more than a few behind, in which case the oldest are dropped and
+/// counted. The stream ends when stopped, when the channel closes or when
+/// the preview session stops.
+#[tauri::command]
+pub async fn stream_frames(
+    app: AppHandle,
+    state: State<'_, PreviewState>,
+    device_id: String,
+    channel: Channel<StreamedFrame>,
+) -> Result<u64, AppError> {
+    let tap = {
+        let sessions = state.sessions.lock();
+        let session = sessions.get(&device_id).ok_or_else(no_preview)?;
+        session.buffer().register_tap(STREAM_TAP_CAPACITY)
+    };
+    let stream_id = tap.id().get();
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(frame) = tap.recv().await {
+            let orientation = app
+                .state::<PreviewState>()
+                .sessions
+                .lock()
+                .get(&device_id)
+                .map(|session| session.orientation())
+                .unwrap_or_default();
+            let (chain, camera) = app
+                .state::<SettingsState>()
+                .store
+                .output_chain(&device_id, OutputTarget::Stream);
+            let dropped = tap.overflowed();
+            let encoded = tauri::async_runtime::spawn_blocking(move || {
+                let mut rendered = render::render_frame(&frame, orientation);
+                output::apply_chain(&chain, &mut rendered, &watermark_context(camera));
+                let jpeg = compress::compress_jpeg(
+                    &rendered.data,
+                    rendered.width,
+                    rendered.height,
+                    FRAME_JPEG_QUALITY,
+                );
+                StreamedFrame {
+                    jpeg: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg),
+                    width: rendered.width,
+                    height: rendered.height,
+                    timestamp_us: frame.timestamp_us,
+                    dropped,
+                }
+            })
+            .await;
+            let sent = match encoded {
+                Ok(frame) => channel.send(frame),
+                Err(e) => {
+                    tracing::warn!("Frame stream {stream_id} for {device_id} failed: {e}");
+                    break;
+                }
+            };
+            if sent.is_err() {
+                break;
+            }
+        }
+        tracing::debug!("Frame stream {stream_id} for {device_id} ended");
+    });
+
+    Ok(stream_id)
+}
+
+/// Stop a stream started by `stream_frames`. Returns whether it was still
+/// running.
+#[tauri::command]
+pub async fn stop_frame_stream(
+    state: State<'_, PreviewState>,
+    device_id: String,
+    stream_id: u64,
+) -> Result<bool, AppError> {
+    let sessions = state.sessions.lock();
+    Ok(sessions
+        .get(&device_id)
+        .is_some_and(|session| session.buffer().unregister_tap(TapId::new(stream_id))))
+}
+
+/// Start exporting a camera's raw frames to shared memory for a companion
+/// process, returning the mapping's name and layout. The region is sized
+/// for the resolution the preview is capturing at, so the preview must
+/// have delivered a frame. Calling it again returns the running export.
+///
+/// The export ends with `disable_shm_export` or when the preview stops or
+/// restarts.
+#[tauri::command]
+pub async fn enable_shm_export(
+    state: State<'_, PreviewState>,
+    device_id: String,
+) -> Result<ShmLayout, AppError> {
+    let sessions = state.sessions.lock();
+    let buffer = sessions.get(&device_id).ok_or_else(no_preview)?.buffer();
+    if let Some(export) = buffer.export() {
+        return Ok(export.layout().clone());
+    }
+    let frame = buffer.latest().ok_or_else(|| {
+        AppError::new(
+            code::FRAME_UNAVAILABLE,
+            "no frames captured yet; try again once the preview is running",
+        )
+    })?;
+    let export = Arc::new(
+        ShmExport::create(&device_id, frame.width, frame.height)
+            .map_err(AppError::with_code(code::INTERNAL))?,
+    );
+    let layout = export.layout().clone();
+    buffer.set_export(Some(export));
+    tracing::info!(
+        "Exporting {device_id} frames to shared memory '{}' at {}x{}",
+        layout.name,
+        layout.width,
+        layout.height
+    );
+    Ok(layout)
+}
+
+/// Stop a shared-memory export, unmapping it once any frame being written
+/// is finished. Returns whether one was running.
+#[tauri::command]
+pub async fn disable_shm_export(
+    state: State<'_, PreviewState>,
+    device_id: String,
+) -> Result<bool, AppError> {
+    let export = state
+        .sessions
+        .lock()
+        .get(&device_id)
+        .and_then(|session| session.buffer().set_export(None));
+    Ok(match export {
+        Some(export) => {
+            export.close();
+            tracing::info!(
+                "Stopped shared-memory export for {device_id} after {} frames",
+                export.frames_written()
+            );
+            true
+        }
+        None => false,
+    })
+}
+
+/// The box and fit a `get_thumbnail` call asks for, or `None` to draw at the
+/// configured size.
+fn thumbnail_spec(
+    state: &PreviewState,
+    device_id: &str,
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: Option<ThumbnailFit>,
+) -> Result<Option<ThumbnailSpec>, AppError> {
+    if (width, height, fit) == (None, None, None) {
+        return Ok(None);
+    }
+    let (box_width, box_height) = state.thumbnail_config(device_id).physical_box();
+    ThumbnailSpec::new(
+        width.unwrap_or(box_width),
+        height.unwrap_or(box_height),
+        fit.unwrap_or_default(),
+    )
+    .map(Some)
+    .map_err(AppError::with_code(code::INVALID_ARGUMENT))
+}
+
+/// Get a thumbnail as base64-encoded JPEG, sized by `configure_thumbnails`
+/// (160x120 by default). Cached per device like `get_frame`, and like it
+/// takes a `format` of `"jpeg"` (the default) or `"png"`.
+///
+/// `width` and `height` ask for another box, in physical pixels, for this
+/// call only; a side left out is the configured one. `fit` is how the frame
+/// fills the box: `"contain"` (the default) fits all of it inside, `"cover"`
+/// crops the middle to fill the box and `"stretch"` scales it to the box.
+/// Thumbnails are never larger than the frame.
+#[tauri::command]
+pub async fn get_thumbnail(
+    state: State<'_, PreviewState>,
+    device_id: String,
+    format: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: Option<ThumbnailFit>,
+) -> Result<String, AppError> {
+    let variant = FrameVariant {
+        format: parse_frame_format(format.as_deref())?,
+        crop: None,
+        thumbnail: thumbnail_spec(&state, &device_id, width, height, fit)?,
+    };
+    let (buffer, orientation) = {
+        let sessions = state.sessions.lock();
+        let session = sessions.get(&device_id).ok_or_else(no_preview)?;
+
+        (Arc::clone(session.buffer()), session.orientation())
+    };
+
+    let latest = buffer.sequence();
+    if let Some(cached) =
+        state
+            .thumbnail_cache
+            .lock()
+            .get_base64(&device_id, variant, latest, orientation)
+    {
+        buffer.record_consumed(THUMBNAIL_CONSUMER, latest);
+        return Ok(cached);
+    }
+
+    let config = state.thumbnail_config(&device_id);
+    let (thumb, seq) = render_thumbnail(
+        &buffer,
+        orientation,
+        &config,
+        variant.thumbnail,
+        variant.format,
+    )
+    .ok_or_else(no_frame)?;
+    buffer.record_consumed(THUMBNAIL_CONSUMER, seq);
+    let thumb = Arc::new(thumb);
+
+    state.cache_jpeg(
+        &state.thumbnail_cache,
+        &device_id,
+        CachedJpeg::new(seq, orientation, Arc::clone(&thumb)).with_variant(variant),
+    );
+    let cached = state
+        .thumbnail_cache
+        .lock()
+        .get_base64(&device_id, variant, seq, orientation);
+    Ok(cached.unwrap_or_else(|| {
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &*thumb)
+    }))
+}
+
+/// Write a camera's latest frame to `path` as a JPEG at `quality`, with the
+/// recording output chain applied, and count it in the session's stats.
+///
+/// The frame is taken under the sessions lock and encoded after it's
+/// released, so neither the capture thread nor other commands wait on the
+/// encode.
+fn write_session_snapshot(
+    state: &PreviewState,
+    settings_state: &SettingsState,
+    device_id: &str,
+    path: &Path,
+    quality: u8,
+) -> Result<(), AppError> {
+    let (source, orientation) = {
+        let sessions = state.sessions.lock();
+        let session = sessions.get(device_id).ok_or_else(no_preview)?;
+        if session.mode() == SessionMode::ThumbnailOnly {
+            return Err(AppError::new(
+                code::PREVIEW_THUMBNAIL_ONLY,
+                THUMBNAIL_ONLY_ERROR,
+            ));
+        }
+        let (source, _) = latest_still(session).ok_or_else(no_frame)?;
+        (source, session.orientation())
+    };
+
+    // Snapshots are kept, so they carry the recording chain's watermark
+    // like time-lapse stills do
+    let (chain, camera) = settings_state
+        .store
+        .output_chain(device_id, OutputTarget::Recording);
+    let jpeg = encode_for_output(
+        &source,
+        orientation,
+        quality,
+        &chain,
+        &watermark_context(camera),
+    )
+    .map_err(AppError::with_code(code::INTERNAL))?;
+    snapshot::write_snapshot(path, &jpeg).map_err(AppError::with_code(code::SETTINGS_IO))?;
+
+    if let Some(session) = state.sessions.lock().get(device_id) {
+        session.record_snapshot();
+    }
+    Ok(())
+}
+
+/// Save a camera's latest frame as a full-resolution JPEG still, returning
+/// where it was written.
+///
+/// Writes to `path` if given, replacing any file there, or to
+/// `Pictures/cameras/<device>-<time>.jpg`. `quality` defaults to
+/// [`snapshot::DEFAULT_SNAPSHOT_QUALITY`], above the preview's, since the still is
+/// kept.
+#[tauri::command]
+pub async fn capture_snapshot(
+    app: AppHandle,
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    path: Option<String>,
+    quality: Option<u8>,
+) -> Result<String, AppError> {
+    let quality =
+        snapshot::snapshot_quality(quality).map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let pictures = app
+                .path()
+                .picture_dir()
+                .map_err(AppError::with_code(code::SETTINGS_IO))?;
+            snapshot::default_snapshot_path(
+                &pictures,
+                &device_id,
+                chrono::Local::now().naive_local(),
+            )
+        }
+    };
+    write_session_snapshot(&state, &settings_state, &device_id, &path, quality)?;
+    tracing::info!("Saved snapshot of {device_id} to {}", path.display());
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Zebra mask of a camera's latest frame: the cells brighter than
+/// `threshold`, laid out like the displayed frame, for the frontend to draw
+/// over the video. Computed again only when a new frame arrives or the
+/// threshold changes.
+#[tauri::command]
+pub async fn get_exposure_mask(
+    state: State<'_, PreviewState>,
+    device_id: String,
+    threshold: u8,
+) -> Result<ExposureMask, AppError> {
+    let (frame, seq, orientation, crop) = {
+        let sessions = state.sessions.lock();
+        let session = sessions.get(&device_id).ok_or_else(no_preview)?;
+        let (frame, seq) = session
+            .buffer()
+            .latest_with_sequence()
+            .ok_or_else(no_frame)?;
+        (frame, seq, session.orientation(), session.crop())
+    };
+
+    if let Some(mask) = state.exposure_masks.lock().get(&device_id, seq, threshold) {
+        return Ok(mask);
+    }
+    let mask =
+        zebra::exposure_mask(&frame, seq, threshold, orientation, crop).ok_or_else(|| {
+            AppError::new(
+                code::PREVIEW_FAILED,
+                "frame is smaller than its reported size",
+            )
+        })?;
+    state.exposure_masks.lock().insert(&device_id, mask.clone());
+    Ok(mask)
+}
+
+/// Set the size thumbnails are drawn at from the CSS box they're shown in
+/// and the display's device pixel ratio. Applies to `device_id`, or without
+/// one to every device that hasn't been configured on its own.
+///
+/// Thumbnails keep their frame's aspect ratio inside the box and are never
+/// larger than the frame. Cached thumbnails are redrawn at the new size.
+#[tauri::command]
+pub async fn configure_thumbnails(
+    state: State<'_, PreviewState>,
+    width: u32,
+    height: u32,
+    dpr: f32,
+    device_id: Option<String>,
+) -> Result<(), AppError> {
+    let config = ThumbnailConfig::new(width, height, dpr)
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    state.configure_thumbnails(device_id.as_deref(), config);
+    Ok(())
+}
+
+/// Set the orientation applied to a camera's delivered frames and persist it.
+///
+/// Takes effect from the next frame for every endpoint; no restart needed.
+#[tauri::command]
+pub async fn set_preview_orientation(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    orientation: Orientation,
+) -> Result<(), AppError> {
+    if let Some(session) = state.sessions.lock().get(&device_id) {
+        session.set_orientation(orientation);
+    }
+    state.forget_cached(&device_id);
+    settings_state
+        .store
+        .set_orientation(&device_id, &camera_name, orientation);
+    Ok(())
+}
+
+/// Set the sharpening and denoise applied to a camera's encoded frames and
+/// persist them. Strengths run from 0 (off) to 100.
+///
+/// Takes effect from the next encoded frame.
+#[tauri::command]
+pub async fn set_post_processing(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    settings: PostProcessing,
+) -> Result<(), AppError> {
+    if settings.sharpen > 100 || settings.denoise > 100 {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!(
+                "Sharpen and denoise must be 0–100, got {} and {}",
+                settings.sharpen, settings.denoise
+            ),
+        ));
+    }
+    if let Some(session) = state.sessions.lock().get(&device_id) {
+        session.set_post_processing(settings);
+    }
+    state.forget_cached(&device_id);
+    settings_state
+        .store
+        .set_post_processing(&device_id, &camera_name, settings);
+    Ok(())
+}
+
+/// Override how a camera's YUV frames are decoded — BT.601 or BT.709,
+/// limited or full range — and persist it. `None` goes back to detecting
+/// it from the media type and frame size.
+///
+/// Takes effect from the next captured frame. Canon live view arrives as
+/// JPEG and is unaffected.
+#[tauri::command]
+pub async fn set_colour_space(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    colour_space: Option<ColourSpace>,
+) -> Result<(), AppError> {
+    if let Some(session) = state.sessions.lock().get(&device_id) {
+        session.set_colour_space(colour_space);
+    }
+    state.forget_cached(&device_id);
+    settings_state
+        .store
+        .set_colour_space(&device_id, &camera_name, colour_space);
+    Ok(())
+}
+
+/// Give one of a camera's outputs its own processor chain, such as a
+/// watermark on recordings only, and persist it. `None` goes back to the
+/// preview's chain.
+///
+/// `get_frame` runs the preview chain, `stream_frames` the stream chain and
+/// time-lapse stills the recording chain, from the next frame each delivers.
+#[tauri::command]
+pub async fn set_output_processors(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    target: OutputTarget,
+    config: Option<Vec<Processor>>,
+) -> Result<(), AppError> {
+    if let Some(chain) = &config {
+        validate_chain(chain).map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    }
+    if target == OutputTarget::Preview {
+        state.forget_cached(&device_id);
+    }
+    settings_state
+        .store
+        .set_output_processors(&device_id, &camera_name, target, config);
+    Ok(())
+}
+
+/// Video sources of `device`: the ones its running session's graph found,
+/// or, with no session, the ones a throwaway graph finds. Canon cameras and
+/// platforms without DirectShow have a single source and list none.
+async fn video_sources_for(
+    app: &AppHandle,
+    device: &CameraDevice,
+) -> Result<Vec<VideoSource>, AppError> {
+    if device.device_path.starts_with("edsdk://") {
+        return Ok(Vec::new());
+    }
+    if let Some(session) = app
+        .state::<PreviewState>()
+        .sessions
+        .lock()
+        .get(device.id.as_str())
+    {
+        return Ok(session.video_sources());
+    }
+    probe_video_sources(device.device_path.clone(), device.name.clone()).await
+}
+
+#[cfg(target_os = "windows")]
+async fn probe_video_sources(
+    device_path: String,
+    friendly_name: String,
+) -> Result<Vec<VideoSource>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        super::graph::directshow::probe_video_sources(&device_path, &friendly_name)
+    })
+    .await
+    .map_err(|e| AppError::new(code::INTERNAL, e.to_string()))?
+    .map_err(AppError::with_code(code::PREVIEW_FAILED))
+}
+
+#[cfg(not(target_os = "windows"))]
+async fn probe_video_sources(
+    _device_path: String,
+    _friendly_name: String,
+) -> Result<Vec<VideoSource>, AppError> {
+    Ok(Vec::new())
+}
+
+/// List the video inputs of a multi-input device — a capture card's HDMI
+/// and SDI inputs behind a crossbar, or its separate capture pins — for
+/// `set_video_source`. Empty for devices with a single source.
+#[tauri::command]
+pub async fn get_video_sources(
+    app: AppHandle,
+    camera_state: State<'_, CameraState>,
+    device_id: String,
+) -> Result<Vec<VideoSource>, AppError> {
+    let device = resolve_device(&camera_state, &device_id)?;
+    video_sources_for(&app, &device).await
+}
+
+/// Capture `index` of the device's video sources from now on, saving the
+/// choice with the camera's settings.
+///
+/// A running preview is restarted through the device queue at the
+/// auto-start size to pick the new source up; the choice is otherwise
+/// applied when the next session starts. A saved source the device no
+/// longer has falls back to its first.
+#[tauri::command]
+pub async fn set_video_source(
+    app: AppHandle,
+    queue: State<'_, DeviceQueue>,
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    index: u32,
+) -> Result<(), AppError> {
+    let device = resolve_device(&camera_state, &device_id)?;
+    let sources = video_sources_for(&app, &device).await?;
+    let source = sources.iter().find(|s| s.index == index).ok_or_else(|| {
+        AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("{} has no video source {index}", device.name),
+        )
+    })?;
+    let device_id = device.id.as_str().to_string();
+    settings_state.store.set_video_source(
+        &device_id,
+        &camera_name,
+        Some(SourcePreference::from(source)),
+    );
+    tracing::info!("Video source for {device_id} set to {:?}", source.name);
+
+    let op_app = app.clone();
+    let op_device = device_id.clone();
+    queue
+        .run(
+            &DeviceId::new(&device_id),
+            OpKind::PreviewRestart,
+            move |_| async move {
+                let mode = match op_app
+                    .state::<PreviewState>()
+                    .sessions
+                    .lock()
+                    .get(&op_device)
+                {
+                    Some(PreviewSession::DirectShow(session)) => session.mode(),
+                    _ => return Ok(()),
+                };
+                replace_session(
+                    &op_app,
+                    &op_device,
+                    AUTO_START_SIZE.0,
+                    AUTO_START_SIZE.1,
+                    AUTO_START_FPS,
+                    None,
+                    mode,
+                )
+            },
+        )
+        .await?;
+    crate::tray::notify_activity(&app);
+    Ok(())
+}
+
+/// Zoom a camera to `factor` around (`center_x`, `center_y`), a point in
+/// the displayed frame from `(0, 0)` top left to `(1, 1)` bottom right. The
+/// camera's Zoom control goes as far as it can and a digital crop of the
+/// preview makes up the rest; see [`plan_zoom`] for the split.
+///
+/// Runs through the device queue, so the control and the crop change
+/// together and never while the preview restarts. Neither is saved, and a
+/// restarted session starts uncropped. Returns the split applied.
+#[tauri::command]
+pub async fn set_combined_zoom(
+    app: AppHandle,
+    device_id: String,
+    factor: f32,
+    center_x: f32,
+    center_y: f32,
+) -> Result<ZoomSplit, AppError> {
+    queue_combined_zoom(app, device_id, Some((factor, center_x, center_y))).await
+}
+
+/// Undo a combined zoom: the Zoom control back to its minimum and the crop
+/// removed. Returns the split applied.
+#[tauri::command]
+pub async fn reset_combined_zoom(app: AppHandle, device_id: String) -> Result<ZoomSplit, AppError> {
+    queue_combined_zoom(app, device_id, None).await
+}
+
+/// Apply a combined zoom, or with `None` reset it, as a queued operation.
+async fn queue_combined_zoom(
+    app: AppHandle,
+    device_id: String,
+    target: Option<(f32, f32, f32)>,
+) -> Result<ZoomSplit, AppError> {
+    let device_id = device_id.trim().to_string();
+    let op_device = device_id.clone();
+    let handle = app.clone();
+    app.state::<DeviceQueue>()
+        .run(
+            &DeviceId::new(&device_id),
+            OpKind::ZoomChange,
+            move |_| async move { apply_combined_zoom(&handle, &op_device, target) },
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+fn apply_combined_zoom(
+    app: &AppHandle,
+    device_id: &str,
+    target: Option<(f32, f32, f32)>,
+) -> Result<ZoomSplit, AppError> {
+    let id = DeviceId::new(device_id);
+    let backend = &app.state::<CameraState>().backend;
+    let zoom = backend
+        .get_controls(&id)?
+        .into_iter()
+        .find(|d| d.id == ControlId::Zoom.as_id_str());
+    let hardware = zoom.as_ref().and_then(HardwareZoom::from_descriptor);
+    let split = match target {
+        Some((factor, center_x, center_y)) => plan_zoom(factor, center_x, center_y, hardware)
+            .map_err(AppError::with_code(code::INVALID_ARGUMENT))?,
+        None => ZoomSplit::neutral(hardware),
+    };
+
+    // Check the crop can be applied before moving the lens
+    let preview = app.state::<PreviewState>();
+    if !split.crop.is_full() && !preview.sessions.lock().contains_key(device_id) {
+        return Err(no_preview());
+    }
+
+    if let (Some(desc), Some(value)) = (&zoom, split.hardware_value) {
+        let snapped = desc.clamp(value);
+        app.state::<ControlLatencyState>()
+            .time_write(device_id, desc.id.as_str(), || {
+                backend.set_control(&id, &ControlId::Zoom, snapped.value)
+            })?;
+    }
+    if let Some(session) = preview.sessions.lock().get(device_id) {
+        session.set_crop(split.crop);
+    }
+    preview.forget_cached(device_id);
+    Ok(split)
+}
+
+/// Look up the running Canon session for a device and run `f` on it.
+fn with_canon_session<T>(
+    state: &PreviewState,
+    device_id: &str,
+    f: impl FnOnce(&super::capture::CanonCaptureSession) -> Result<T, String>,
+) -> Result<T, AppError> {
+    match state.sessions.lock().get(device_id) {
+        Some(PreviewSession::Canon(session)) => {
+            f(session).map_err(|e| AppError::classify(code::CANON_SDK, e))
+        }
+        Some(PreviewSession::DirectShow(_)) => Err(AppError::new(
+            code::UNSUPPORTED,
+            format!("tap-to-focus is only supported on Canon cameras: {device_id}"),
+        )),
+        None => Err(AppError::new(
+            code::PREVIEW_NOT_RUNNING,
+            "live view is not active — start the preview first",
+        )),
+    }
+}
+
+/// Move a Canon camera's AF point to a normalised `[0, 1]` position in the
+/// preview frame, as seen by the user (after orientation).
+///
+/// Positions outside the frame are clamped to the nearest edge.
+#[tauri::command]
+pub async fn canon_set_af_point(
+    state: State<'_, PreviewState>,
+    device_id: String,
+    x: f32,
+    y: f32,
+) -> Result<(), AppError> {
+    with_canon_session(&state, &device_id, |session| {
+        session.set_af_point(x, y).map(|_| ())
+    })
+}
+
+/// Run one autofocus pass on a Canon camera at its current AF point.
+#[tauri::command]
+pub async fn canon_trigger_af(
+    state: State<'_, PreviewState>,
+    device_id: String,
+) -> Result<(), AppError> {
+    with_canon_session(&state, &device_id, |session| session.trigger_af())
+}
+
+/// Set the JPEG quality profile for frames compressed by `get_frame` and
+/// persist it.
+///
+/// Takes effect from the next compressed frame. Adaptation restarts from the
+/// profile's starting quality.
+#[tauri::command]
+pub async fn set_jpeg_quality_profile(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    profile: QualityProfile,
+) -> Result<(), AppError> {
+    profile
+        .validate()
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    state.set_quality_profile(&device_id, profile);
+    settings_state
+        .store
+        .set_jpeg_quality(&device_id, &camera_name, profile);
+    Ok(())
+}
+
+/// Turn the "no signal" card for a camera on or off and persist it.
+///
+/// Takes effect from the next `get_frame`.
+#[tauri::command]
+pub async fn set_placeholder_on_error(
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_state
+        .store
+        .set_placeholder_on_error(&device_id, &camera_name, enabled);
+    Ok(())
+}
+
+/// Choose whether a camera's preview auto-starts at full resolution rather
+/// than thumbnail-only, and persist it.
+///
+/// Takes effect the next time the preview is auto-started.
+#[tauri::command]
+pub async fn set_full_resolution_autostart(
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    camera_name: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_state
+        .store
+        .set_full_resolution_autostart(&device_id, &camera_name, enabled);
+    Ok(())
+}
+
+/// Get diagnostic stats for a camera preview session.
+#[tauri::command]
+pub async fn get_diagnostics(
+    state: State<'_, PreviewState>,
+    device_id: String,
+) -> Result<DiagnosticSnapshot, AppError> {
+    let sessions = state.sessions.lock();
+    let session = sessions.get(&device_id).ok_or_else(no_preview)?;
+
+    let mut snapshot = state.with_quality_stats(&device_id, session.diagnostics());
+    snapshot.jpeg_cache_bytes = state.cache_bytes() as u64;
+    Ok(snapshot)
+}
+
+/// Get encoding performance stats for a camera preview session.
+///
+/// Returns encoder type (hardware/software/CPU), frame counts, and timing.
+#[tauri::command]
+pub async fn get_encoding_stats(
+    state: State<'_, PreviewState>,
+    device_id: String,
+) -> Result<EncodingSnapshot, AppError> {
+    let sessions = state.sessions.lock();
+    let session = sessions.get(&device_id).ok_or_else(no_preview)?;
+
+    session.encoding_snapshot().ok_or_else(|| {
+        AppError::new(
+            code::UNSUPPORTED,
+            "encode worker not active for this device",
+        )
+    })
+}
+
+/// Session count, buffered frame and cache bytes, thread count and how
+/// settings saves are going, for the diagnostics view.
+#[tauri::command]
+pub async fn get_resource_usage(
+    state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+) -> Result<ResourceUsage, AppError> {
+    Ok(state.resource_usage(settings_state.store.save_health()))
+}
+
+/// CPU time, buffered frame and cache bytes for each device with a
+/// session, plus their totals. CPU percentages come from the resource
+/// sampler, so they read zero for a few seconds after a session starts.
+#[tauri::command]
+pub async fn get_resource_usage_per_device(
+    state: State<'_, PreviewState>,
+) -> Result<ResourceUsagePerDevice, AppError> {
+    Ok(state.resource_usage_per_device())
+}
+
+/// Sample each session's CPU time every few seconds until the app exits,
+/// for `get_resource_usage_per_device`. Spawn once `PreviewState` is
+/// managed.
+pub async fn run_resource_sampler(app: AppHandle) {
+    loop {
+        app.state::<PreviewState>().sample_resources(Instant::now());
+        tokio::time::sleep(RESOURCE_SAMPLE_INTERVAL).await;
+    }
+}
+
+/// Time each stage of the frame pipeline on synthesised `width`x`height`
+/// frames, without a camera, to see which stage costs the CPU on this
+/// machine. Stops early after five seconds.
+#[tauri::command]
+pub async fn run_pipeline_benchmark(
+    width: u32,
+    height: u32,
+    iterations: u32,
+) -> Result<BenchmarkReport, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        benchmark::run_pipeline_benchmark(width, height, iterations)
+    })
+    .await
+    .map_err(|e| AppError::new(code::INTERNAL, format!("benchmark failed: {e}")))?
+    .map_err(AppError::with_code(code::INVALID_ARGUMENT))
+}
+
+/// List all available GPU adapters on the system.
+#[tauri::command]
+pub async fn list_gpu_adapters() -> Vec<GpuAdapterInfo> {
+    super::gpu::GpuContext::enumerate_adapters()
+}
+
+/// Get information about the currently active GPU adapter.
+///
+/// Returns `null` if GPU acceleration is disabled (CPU-only mode).
+#[tauri::command]
+pub async fn get_active_gpu(
+    state: State<'_, GpuState>,
+) -> Result<Option<GpuAdapterInfo>, AppError> {
+    Ok(state.context().map(|ctx| ctx.adapter_info()))
+}
+
+/// Switch the active GPU adapter, or disable GPU acceleration.
+///
+/// Pass `adapterIndex: null` to switch to CPU-only mode.
+/// Returns the name of the newly selected adapter, or `null` if disabled.
+#[tauri::command]
+pub async fn set_gpu_adapter(
+    state: State<'_, GpuState>,
+    adapter_index: Option<usize>,
+) -> Result<Option<String>, AppError> {
+    Ok(state.set_adapter(adapter_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::capture::{ErrorSessionInfo, Frame};
+
+    fn make_preview_state() -> PreviewState {
+        PreviewState::new()
+    }
+
+    fn make_rgb_frame(width: u32, height: u32) -> Frame {
+        let data = vec![128u8; (width * height * 3) as usize];
+        Frame {
+            data,
+            width,
+            height,
+            timestamp_us: 1000,
+            device_timestamp_us: 1000,
+        }
+    }
 
     /// Helper: create a DirectShow capture session for testing.
     fn make_ds_session(device_id: &str, w: u32, h: u32) -> CaptureSession {
         CaptureSession::new(
             device_id.to_string(),
             String::new(),
+            String::new(),
             w,
             h,
             30.0,
             None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
+            None,
+            75,
+        )
+    }
+
+    #[test]
+    fn sessions_beyond_the_limit_are_not_admitted() {
+        let state = make_preview_state();
+        state.set_max_sessions(0);
+        let sessions = state.sessions.lock();
+        assert!(state.admit(&sessions, "dev-1").is_err());
+        drop(sessions);
+
+        state.set_max_sessions(1);
+        assert!(state.admit(&state.sessions.lock(), "dev-1").is_ok());
+        assert_eq!(state.resource_usage().max_sessions, 1);
+    }
+
+    #[test]
+    fn start_preview_with_empty_device_id_fails() {
+        let state = make_preview_state();
+        let device_id = "".to_string();
+        assert!(device_id.is_empty());
+        let sessions = state.sessions.lock();
+        assert!(sessions.is_empty());
+    }
+
+    #[test]
+    fn start_preview_creates_session() {
+        let state = make_preview_state();
+        {
+            let mut sessions = state.sessions.lock();
+            let session = make_ds_session("test-device", 640, 480);
+            sessions.insert(
+                "test-device".to_string(),
+                PreviewSession::DirectShow(session),
+            );
+        }
+        let sessions = state.sessions.lock();
+        assert!(sessions.contains_key("test-device"));
+    }
+
+    #[test]
+    fn stop_preview_removes_session() {
+        let state = make_preview_state();
+        {
+            let mut sessions = state.sessions.lock();
+            let session = make_ds_session("test-device", 640, 480);
+            sessions.insert(
+                "test-device".to_string(),
+                PreviewSession::DirectShow(session),
+            );
+        }
+        {
+            let mut sessions = state.sessions.lock();
+            if let Some(mut s) = sessions.remove("test-device") {
+                s.stop();
+            }
+        }
+        let sessions = state.sessions.lock();
+        assert!(!sessions.contains_key("test-device"));
+    }
+
+    #[test]
+    fn diagnostics_cover_every_session() {
+        let state = make_preview_state();
+        assert!(state.diagnostics_by_device().is_empty());
+        {
+            let mut sessions = state.sessions.lock();
+            for id in ["cam-1", "cam-2"] {
+                let session = make_ds_session(id, 640, 480);
+                sessions.insert(id.to_string(), PreviewSession::DirectShow(session));
+            }
+        }
+        state.set_quality_profile("cam-2", QualityProfile::default());
+        state
+            .quality
+            .lock()
+            .get_mut("cam-2")
+            .unwrap()
+            .record(Duration::from_millis(4));
+
+        let all = state.diagnostics_by_device();
+        assert_eq!(
+            all.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["cam-1", "cam-2"]
+        );
+        assert_eq!(all["cam-1"].effective_jpeg_quality, None);
+        assert!(all["cam-2"].effective_jpeg_quality.is_some());
+        assert_eq!(all["cam-2"].recent_encode_ms.len(), 1);
+
+        // An unplugged camera drops out of the next update
+        state.sessions.lock().remove("cam-1");
+        assert_eq!(
+            state.diagnostics_by_device().keys().collect::<Vec<_>>(),
+            ["cam-2"]
+        );
+    }
+
+    #[test]
+    fn stop_preview_without_session_is_ok() {
+        let state = make_preview_state();
+        let sessions = state.sessions.lock();
+        assert!(sessions.get("nonexistent").is_none());
+    }
+
+    #[test]
+    fn get_frame_returns_base64_jpeg_when_active() {
+        let state = make_preview_state();
+        let frame = make_rgb_frame(10, 10);
+        {
+            let mut sessions = state.sessions.lock();
+            let session = make_ds_session("test-device", 10, 10);
+            session.buffer().push(frame);
+            sessions.insert(
+                "test-device".to_string(),
+                PreviewSession::DirectShow(session),
+            );
+        }
+
+        let sessions = state.sessions.lock();
+        let session = sessions.get("test-device").unwrap();
+        let buf = session.buffer();
+        let latest = buf.latest().unwrap();
+        let jpeg = compress::compress_jpeg(&latest.data, latest.width, latest.height, 85);
+        let b64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg);
+        assert!(!b64.is_empty());
+
+        let decoded =
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &b64).unwrap();
+        assert_eq!(decoded[0], 0xFF);
+        assert_eq!(decoded[1], 0xD8);
+    }
+
+    #[test]
+    fn get_frame_returns_error_when_no_preview() {
+        let state = make_preview_state();
+        let sessions = state.sessions.lock();
+        let result = sessions.get("nonexistent");
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn preview_error_payload_has_camel_case_keys() {
+        let payload = PreviewErrorPayload::enriched(
+            "cam-1",
+            "resource busy",
+            None,
+            ErrorSessionInfo::default(),
+        );
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.contains("deviceId"), "expected camelCase key: {json}");
+        assert!(
+            !json.contains("device_id"),
+            "expected no snake_case key: {json}"
+        );
+    }
+
+    #[test]
+    fn jpeg_cache_returns_same_result_for_same_sequence() {
+        let state = make_preview_state();
+
+        let session = make_ds_session("dev-1", 10, 10);
+        session.buffer().push(make_rgb_frame(10, 10));
+        let seq = session.buffer().sequence();
+        state
+            .sessions
+            .lock()
+            .insert("dev-1".to_string(), PreviewSession::DirectShow(session));
+
+        let frame1 = {
+            let sessions = state.sessions.lock();
+            sessions
+                .get("dev-1")
+                .unwrap()
+                .buffer()
+                .unwrap()
+                .latest()
+                .unwrap()
+        };
+        let jpeg = compress::compress_jpeg(&frame1.data, frame1.width, frame1.height, 85);
+        let b64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg);
+        state.cache_jpeg(
+            &state.jpeg_cache,
+            "dev-1",
+            CachedJpeg::new(seq, Orientation::default(), Arc::new(jpeg.clone())),
+        );
+
+        let mut cache = state.jpeg_cache.lock();
+        assert_eq!(seq, 1);
+        assert_eq!(
+            cache
+                .get(
+                    "dev-1",
+                    FrameVariant::default(),
+                    seq,
+                    Orientation::default()
+                )
+                .as_deref(),
+            Some(&jpeg)
+        );
+        assert_eq!(
+            cache.get_base64(
+                "dev-1",
+                FrameVariant::default(),
+                seq,
+                Orientation::default()
+            ),
+            Some(b64)
+        );
+    }
+
+    #[test]
+    fn jpeg_cache_invalidates_on_new_frame() {
+        let state = make_preview_state();
+
+        state.jpeg_cache.lock().insert(
+            "dev-1",
+            CachedJpeg::new(1, Orientation::default(), Arc::new(b"old-data".to_vec())),
+        );
+
+        let session = make_ds_session("dev-1", 10, 10);
+        session.buffer().push(make_rgb_frame(10, 10));
+        session.buffer().push(make_rgb_frame(10, 10));
+        let new_seq = session.buffer().sequence();
+
+        let mut cache = state.jpeg_cache.lock();
+        assert!(cache
+            .get(
+                "dev-1",
+                FrameVariant::default(),
+                new_seq,
+                Orientation::default()
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn stop_preview_clears_jpeg_cache() {
+        let state = make_preview_state();
+
+        let session = make_ds_session("dev-1", 10, 10);
+        state
+            .sessions
+            .lock()
+            .insert("dev-1".to_string(), PreviewSession::DirectShow(session));
+        state.jpeg_cache.lock().insert("dev-1", cached_jpeg(1));
+        state.thumbnail_cache.lock().insert("dev-1", cached_jpeg(1));
+
+        {
+            let mut sessions = state.sessions.lock();
+            if let Some(mut s) = sessions.remove("dev-1") {
+                s.stop();
+            }
+        }
+        state.forget_cached("dev-1");
+
+        assert!(state.jpeg_cache.lock().is_empty());
+        assert!(state.thumbnail_cache.lock().is_empty());
+        assert_eq!(state.cache_bytes(), 0);
+    }
+
+    fn cached_jpeg(sequence: u64) -> CachedJpeg {
+        CachedJpeg::new(
+            sequence,
+            Orientation::default(),
+            Arc::new(b"cached".to_vec()),
+        )
+    }
+
+    #[test]
+    fn caching_a_frame_sweeps_devices_without_a_session() {
+        let state = make_preview_state();
+        let session = make_ds_session("cam-1", 10, 10);
+        state
+            .sessions
+            .lock()
+            .insert("cam-1".to_string(), PreviewSession::DirectShow(session));
+
+        // cam-2 errored out and its session was dropped without stop_preview
+        state.jpeg_cache.lock().insert("cam-2", cached_jpeg(5));
+        state.cache_jpeg(&state.jpeg_cache, "cam-1", cached_jpeg(1));
+
+        let mut cache = state.jpeg_cache.lock();
+        assert_eq!(cache.len(), 1);
+        assert!(cache
+            .get("cam-1", FrameVariant::default(), 1, Orientation::default())
+            .is_some());
+    }
+
+    #[test]
+    fn cache_limit_applies_to_frames_and_thumbnails() {
+        let state = make_preview_state();
+        state.jpeg_cache.lock().insert("cam-1", cached_jpeg(1));
+        state.thumbnail_cache.lock().insert("cam-1", cached_jpeg(1));
+        assert_eq!(state.cache_bytes(), 12);
+
+        state.set_cache_limit(4);
+        assert_eq!(state.cache_bytes(), 0);
+    }
+
+    #[test]
+    fn start_all_previews_creates_sessions_for_all_devices() {
+        let state = make_preview_state();
+
+        let device_ids = vec!["cam-1", "cam-2", "cam-3"];
+        {
+            let mut sessions = state.sessions.lock();
+            for id in &device_ids {
+                let session = make_ds_session(id, 640, 480);
+                sessions.insert(id.to_string(), PreviewSession::DirectShow(session));
+            }
+        }
+
+        let sessions = state.sessions.lock();
+        assert_eq!(sessions.len(), 3);
+        assert!(sessions.contains_key("cam-1"));
+        assert!(sessions.contains_key("cam-2"));
+        assert!(sessions.contains_key("cam-3"));
+    }
+
+    #[test]
+    fn start_all_previews_skips_existing_sessions() {
+        let state = make_preview_state();
+
+        {
+            let mut sessions = state.sessions.lock();
+            let session = CaptureSession::new(
+                "cam-1".to_string(),
+                String::new(),
+                "Camera 1".to_string(),
+                640,
+                480,
+                30.0,
+                None,
+                SessionMode::Full,
+                None,
+                CAPTURE_RESTART,
+                None,
+                None,
+                None,
+                None,
+                None,
+                75,
+            );
+            sessions.insert("cam-1".to_string(), PreviewSession::DirectShow(session));
+        }
+
+        {
+            let mut sessions = state.sessions.lock();
+            for id in &["cam-1", "cam-2"] {
+                if !sessions.contains_key(*id) {
+                    let session = make_ds_session(id, 640, 480);
+                    sessions.insert(id.to_string(), PreviewSession::DirectShow(session));
+                }
+            }
+        }
+
+        let sessions = state.sessions.lock();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.contains_key("cam-1"));
+        assert!(sessions.contains_key("cam-2"));
+    }
+
+    #[test]
+    fn auto_start_falls_back_to_the_default_format() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+
+        assert_eq!(saved_preview_format(&store, "cam-1"), AUTO_START_FORMAT);
+        assert_eq!(
+            (AUTO_START_FORMAT.width, AUTO_START_FORMAT.height),
+            (640, 480)
+        );
+    }
+
+    #[test]
+    fn auto_started_session_captures_at_the_saved_format() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+        let hd = PreviewFormat {
+            width: 1920,
+            height: 1080,
+            fps: 30.0,
+        };
+        let device = brio();
+        let id = device.id.as_str();
+        store.set_preview_format(id, &device.name, hd);
+        store.set_full_resolution_autostart(id, &device.name, true);
+
+        // As the app starts it at launch
+        let mut session = startup_capture_session(&store, &device, None, None, None, None, None);
+        assert_eq!(session.requested_format(), hd);
+        assert_eq!(session.mode(), SessionMode::Full);
+        session.stop();
+
+        // A camera without a saved format starts at the default
+        let other = CameraDevice {
+            id: DeviceId::new("cam-2"),
+            ..brio()
+        };
+        let mut session = startup_capture_session(&store, &other, None, None, None, None, None);
+        assert_eq!(session.requested_format(), AUTO_START_FORMAT);
+        assert_eq!(session.mode(), SessionMode::ThumbnailOnly);
+        session.stop();
+    }
+
+    #[test]
+    fn stop_preview_for_disconnected_device_cleans_up() {
+        let state = make_preview_state();
+
+        {
+            let mut sessions = state.sessions.lock();
+            let session = make_ds_session("cam-1", 640, 480);
+            sessions.insert("cam-1".to_string(), PreviewSession::DirectShow(session));
+        }
+        state.jpeg_cache.lock().insert("cam-1", cached_jpeg(1));
+
+        {
+            let mut sessions = state.sessions.lock();
+            if let Some(mut s) = sessions.remove("cam-1") {
+                s.stop();
+            }
+        }
+        state.forget_cached("cam-1");
+
+        assert!(!state.sessions.lock().contains_key("cam-1"));
+        assert!(state.jpeg_cache.lock().is_empty());
+    }
+
+    #[test]
+    fn sessions_for_long_device_paths_are_keyed_by_device_id() {
+        let state = make_preview_state();
+        let dock = |port: u32| {
+            let path = format!(
+                r"\\?\usb#vid_046d&pid_085e&mi_00#9&{}&{port:04}#{{65e8773d-8f56-11d0-a3b9-00a0c9223196}}",
+                "2f1b3c4d&0&".repeat(25)
+            );
+            format!("{path}{}", "x".repeat(400 - path.len()))
+        };
+        let ids: Vec<DeviceId> = [1, 2]
+            .iter()
+            .map(|&port| DeviceId::from_device_path(&dock(port)))
+            .collect();
+        assert_ne!(ids[0], ids[1]);
+
+        {
+            let mut sessions = state.sessions.lock();
+            for (id, port) in ids.iter().zip([1, 2]) {
+                let session = CaptureSession::new(
+                    id.as_str().to_string(),
+                    dock(port),
+                    "Logitech BRIO".to_string(),
+                    640,
+                    480,
+                    30.0,
+                    None,
+                    SessionMode::Full,
+                    None,
+                    CAPTURE_RESTART,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    75,
+                );
+                assert_eq!(session.device_id(), id.as_str());
+                sessions.insert(id.as_str().to_string(), PreviewSession::DirectShow(session));
+            }
+        }
+        state
+            .jpeg_cache
+            .lock()
+            .insert(ids[0].as_str(), cached_jpeg(1));
+
+        if let Some(mut session) = state.sessions.lock().remove(ids[0].as_str()) {
+            session.stop();
+        }
+        state.forget_cached(ids[0].as_str());
+
+        let sessions = state.sessions.lock();
+        assert!(!sessions.contains_key(ids[0].as_str()));
+        assert!(sessions.contains_key(ids[1].as_str()));
+        assert!(state.jpeg_cache.lock().is_empty());
+    }
+
+    const BRIO_PATH: &str =
+        r"\\?\usb#vid_046d&pid_085e&mi_00#7&1a2b3c4d&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}";
+
+    fn brio() -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new("046d:085e:A1"),
+            name: "Logitech BRIO".to_string(),
+            device_path: BRIO_PATH.to_string(),
+            is_connected: true,
+            kind: crate::camera::types::DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    /// A session as `replace_session` would create it, on the dummy capture
+    /// path.
+    fn start_for(device: &CameraDevice) -> Result<PreviewSession, AppError> {
+        Ok(PreviewSession::DirectShow(CaptureSession::new(
+            device.id.as_str().to_string(),
+            device.device_path.clone(),
+            device.name.clone(),
+            640,
+            480,
+            30.0,
+            None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
             None,
             75,
-        )
+        )))
+    }
+
+    fn session_keys(state: &PreviewState) -> Vec<String> {
+        let mut keys: Vec<String> = state.sessions.lock().keys().cloned().collect();
+        keys.sort();
+        keys
     }
 
     #[test]
-    fn start_preview_with_empty_device_id_fails() {
+    fn a_session_under_a_stale_id_is_replaced_not_duplicated() {
         let state = make_preview_state();
-        let device_id = "".to_string();
-        assert!(device_id.is_empty());
-        let sessions = state.sessions.lock();
-        assert!(sessions.is_empty());
+        let device = brio();
+        let stale = PreviewSession::DirectShow(CaptureSession::new(
+            BRIO_PATH.to_uppercase(),
+            BRIO_PATH.to_uppercase(),
+            device.name.clone(),
+            640,
+            480,
+            30.0,
+            None,
+            SessionMode::Full,
+            None,
+            CAPTURE_RESTART,
+            None,
+            None,
+            None,
+            None,
+            None,
+            75,
+        ));
+        state
+            .sessions
+            .lock()
+            .insert(BRIO_PATH.to_uppercase(), stale);
+        state
+            .jpeg_cache
+            .lock()
+            .insert(&BRIO_PATH.to_uppercase(), cached_jpeg(1));
+        assert_eq!(
+            existing_sessions(&state.sessions.lock(), &device),
+            vec![BRIO_PATH.to_uppercase()]
+        );
+
+        state
+            .replace_for_device(&device, || start_for(&device))
+            .unwrap();
+        assert_eq!(session_keys(&state), vec!["046d:085e:A1".to_string()]);
+        assert!(state.jpeg_cache.lock().is_empty());
     }
 
     #[test]
-    fn start_preview_creates_session() {
+    fn differently_cased_ids_share_one_session() {
         let state = make_preview_state();
-        {
-            let mut sessions = state.sessions.lock();
-            let session = make_ds_session("test-device", 640, 480);
-            sessions.insert(
-                "test-device".to_string(),
-                PreviewSession::DirectShow(session),
-            );
+        let devices = vec![brio()];
+        for raw in ["046d:085e:A1", "046D:085E:A1", " 046d:085e:a1 ", BRIO_PATH] {
+            let id = canonical_device_id(raw, &devices).unwrap();
+            let device = devices.iter().find(|d| d.id.as_str() == id).unwrap();
+            state
+                .replace_for_device(device, || start_for(device))
+                .unwrap();
         }
+        assert_eq!(session_keys(&state), vec!["046d:085e:A1".to_string()]);
+    }
+
+    #[test]
+    fn concurrent_starts_for_one_camera_leave_one_session() {
+        let state = make_preview_state();
+        let devices = vec![brio()];
+        let created = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for raw in ["046d:085e:A1", "046D:085E:A1", BRIO_PATH, "046d:085e:a1"] {
+                let (state, devices, created) = (&state, &devices, &created);
+                scope.spawn(move || {
+                    let id = canonical_device_id(raw, devices).unwrap();
+                    let device = devices.iter().find(|d| d.id.as_str() == id).unwrap();
+                    state
+                        .replace_for_device(device, || {
+                            created.fetch_add(1, Ordering::Relaxed);
+                            start_for(device)
+                        })
+                        .unwrap();
+                });
+            }
+        });
+        // Each start replaced the one before it rather than running beside it
+        assert_eq!(created.load(Ordering::Relaxed), 4);
+        assert_eq!(session_keys(&state), vec!["046d:085e:A1".to_string()]);
+    }
+
+    #[test]
+    fn replacing_a_camera_session_is_allowed_at_the_limit() {
+        let state = make_preview_state();
+        let device = brio();
+        state
+            .replace_for_device(&device, || start_for(&device))
+            .unwrap();
+        state.set_max_sessions(1);
+        state
+            .replace_for_device(&device, || start_for(&device))
+            .unwrap();
+
+        let mut other = brio();
+        other.id = DeviceId::new("046d:085e:B2");
+        other.device_path = BRIO_PATH.replace("0000", "0001");
+        assert!(state
+            .replace_for_device(&other, || start_for(&other))
+            .is_err());
+        assert_eq!(session_keys(&state), vec!["046d:085e:A1".to_string()]);
+    }
+
+    #[test]
+    fn frame_buffer_latest_returns_arc() {
+        let state = make_preview_state();
+        let session = make_ds_session("dev-1", 10, 10);
+        session.buffer().push(make_rgb_frame(10, 10));
+        state
+            .sessions
+            .lock()
+            .insert("dev-1".to_string(), PreviewSession::DirectShow(session));
+
         let sessions = state.sessions.lock();
-        assert!(sessions.contains_key("test-device"));
+        let session = sessions.get("dev-1").unwrap();
+        let buf = session.buffer();
+        let frame1 = buf.latest().unwrap();
+        let frame2 = buf.latest().unwrap();
+
+        assert!(std::sync::Arc::ptr_eq(&frame1, &frame2));
     }
 
     #[test]
-    fn stop_preview_removes_session() {
+    fn preview_session_routes_edsdk_to_canon() {
+        // Verify that the edsdk:// prefix detection works
+        let device_path = "edsdk://Canon EOS R5";
+        assert!(device_path.starts_with("edsdk://"));
+
+        let device_path = "\\\\?\\usb#vid_046d";
+        assert!(!device_path.starts_with("edsdk://"));
+    }
+
+    /// A running Canon session on a mock camera whose live view delivers
+    /// 48x32 frames, once the first has arrived.
+    fn canon_session(
+        geometry: Option<crate::camera::canon::types::EvfGeometry>,
+    ) -> (
+        crate::preview::capture::CanonCaptureSession,
+        Arc<crate::camera::canon::mock::MockEdsSdk>,
+    ) {
+        use crate::camera::canon::api::CameraHandle;
+        use crate::camera::canon::mock::MockEdsSdk;
+        use crate::preview::capture::CanonCaptureSession;
+
+        let jpeg = compress::compress_jpeg(&[200; 48 * 32 * 3], 48, 32, 90);
+        let mut mock = MockEdsSdk::new().with_cameras(1).with_live_view_frame(jpeg);
+        if let Some(geometry) = geometry {
+            mock = mock.with_evf_geometry(0, geometry);
+        }
+        let mock = Arc::new(mock);
+        let session = CanonCaptureSession::new(
+            "canon:MOCK0001".to_string(),
+            "edsdk://MOCK0001".to_string(),
+            Arc::clone(&mock),
+            CameraHandle(0),
+            AUTO_START_FORMAT,
+            None,
+            None,
+            FRAME_JPEG_QUALITY,
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while session.capture().buffer().sequence() == 0 {
+            assert!(Instant::now() < deadline, "no live view frames arrived");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        (session, mock)
+    }
+
+    #[test]
+    fn canon_live_view_frames_are_served_like_any_other() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
         let state = make_preview_state();
+        let (session, _mock) = canon_session(None);
+        state
+            .sessions
+            .lock()
+            .insert("canon:MOCK0001".to_string(), PreviewSession::Canon(session));
+
         {
-            let mut sessions = state.sessions.lock();
-            let session = make_ds_session("test-device", 640, 480);
-            sessions.insert(
-                "test-device".to_string(),
-                PreviewSession::DirectShow(session),
-            );
+            let sessions = state.sessions.lock();
+            let preview = &sessions["canon:MOCK0001"];
+            let frame = preview.buffer().latest().unwrap();
+            assert_eq!((frame.width, frame.height), (48, 32));
+            assert!(preview.diagnostics().frame_count > 0);
+            assert!(preview.since_last_frame().is_some());
+            assert!(matches!(
+                preview.probe_first_frame(),
+                FrameProbe::Ready {
+                    width: 48,
+                    height: 32
+                }
+            ));
+        }
+
+        let (sequence, jpeg) =
+            preview_frame_bytes(&state, &settings_state, "canon:MOCK0001").unwrap();
+        assert!(sequence > 0);
+        assert_eq!(jpeg_size(&jpeg), (48, 32));
+        let (_, base64) = preview_frame_base64(
+            &state,
+            &settings_state,
+            "canon:MOCK0001",
+            FrameVariant::default(),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        let decoded =
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &base64).unwrap();
+        assert_eq!(jpeg_size(&decoded), (48, 32));
+
+        assert!(state.stop_session("canon:MOCK0001"));
+    }
+
+    fn canon_focus_state(
+        geometry: Option<crate::camera::canon::types::EvfGeometry>,
+    ) -> (PreviewState, Arc<crate::camera::canon::mock::MockEdsSdk>) {
+        let (session, mock) = canon_session(geometry);
+        let state = PreviewState::new();
+        state
+            .sessions
+            .lock()
+            .insert("canon:MOCK0001".to_string(), PreviewSession::Canon(session));
+        (state, mock)
+    }
+
+    fn evf_geometry() -> crate::camera::canon::types::EvfGeometry {
+        use crate::camera::canon::types::{EdsPoint, EdsRect, EdsSize, EvfGeometry};
+        EvfGeometry {
+            coordinate_system: EdsSize {
+                width: 6000,
+                height: 4000,
+            },
+            zoom_rect: EdsRect {
+                point: EdsPoint { x: 0, y: 0 },
+                size: EdsSize {
+                    width: 1200,
+                    height: 800,
+                },
+            },
         }
+    }
+
+    #[test]
+    fn canon_af_point_maps_through_orientation() {
+        use crate::camera::canon::types::{EdsPoint, PROP_ID_EVF_ZOOM_POSITION};
+        use crate::preview::render::Rotation;
+
+        let (state, mock) = canon_focus_state(Some(evf_geometry()));
         {
-            let mut sessions = state.sessions.lock();
-            if let Some(mut s) = sessions.remove("test-device") {
-                s.stop();
-            }
+            let sessions = state.sessions.lock();
+            sessions["canon:MOCK0001"].set_orientation(Orientation {
+                rotation: Rotation::Cw180,
+                mirror: false,
+            });
         }
-        let sessions = state.sessions.lock();
-        assert!(!sessions.contains_key("test-device"));
+
+        // Top-left of a 180°-rotated preview is bottom-right of the sensor
+        with_canon_session(&state, "canon:MOCK0001", |s| s.set_af_point(0.0, 0.0)).unwrap();
+        assert_eq!(
+            mock.point_writes(0, PROP_ID_EVF_ZOOM_POSITION),
+            vec![EdsPoint { x: 4800, y: 3200 }]
+        );
+        state
+            .sessions
+            .lock()
+            .get_mut("canon:MOCK0001")
+            .unwrap()
+            .stop();
+    }
+
+    #[test]
+    fn canon_focus_rejected_without_session_or_live_view() {
+        let state = PreviewState::new();
+        let err = with_canon_session(&state, "canon:MOCK0001", |s| s.trigger_af()).unwrap_err();
+        assert_eq!(err.code, code::PREVIEW_NOT_RUNNING);
+        assert!(err.message.contains("live view is not active"));
+
+        let (state, mock) = canon_focus_state(Some(evf_geometry()));
+        state
+            .sessions
+            .lock()
+            .get_mut("canon:MOCK0001")
+            .unwrap()
+            .stop();
+        assert!(with_canon_session(&state, "canon:MOCK0001", |s| s.trigger_af()).is_err());
+        assert!(mock.commands_sent().is_empty());
+    }
+
+    #[test]
+    fn canon_focus_rejected_for_directshow_sessions() {
+        let state = PreviewState::new();
+        state.sessions.lock().insert(
+            "dev-1".to_string(),
+            PreviewSession::DirectShow(make_ds_session("dev-1", 10, 10)),
+        );
+        let err = with_canon_session(&state, "dev-1", |s| s.trigger_af()).unwrap_err();
+        assert_eq!(err.code, code::UNSUPPORTED);
+        assert!(err.message.contains("only supported on Canon"));
+    }
+
+    #[test]
+    fn resource_usage_per_device_covers_sessions_and_their_caches() {
+        let state = PreviewState::new();
+        state.sessions.lock().insert(
+            "dev-1".to_string(),
+            PreviewSession::DirectShow(make_ds_session("dev-1", 10, 10)),
+        );
+        state.jpeg_cache.lock().insert(
+            "dev-1",
+            CachedJpeg::new(1, Orientation::default(), Arc::new(vec![b'A'; 40])),
+        );
+        state.sample_resources(Instant::now());
+
+        let usage = state.resource_usage_per_device();
+        assert_eq!(usage.devices.len(), 1);
+        let dev = &usage.devices["dev-1"];
+        assert_eq!(dev.cache_bytes, 40);
+        assert_eq!(usage.totals, *dev);
+
+        let mut session = state.sessions.lock().remove("dev-1").unwrap();
+        session.stop();
+        state.sample_resources(Instant::now());
+        assert!(state.resource_usage_per_device().devices.is_empty());
+    }
+
+    /// Decoded dimensions of a JPEG.
+    fn jpeg_size(jpeg: &[u8]) -> (u32, u32) {
+        let img = image::load_from_memory(jpeg).unwrap();
+        (img.width(), img.height())
+    }
+
+    fn gradient_frame(width: u32, height: u32) -> Frame {
+        let data = (0..width * height * 3).map(|i| (i % 251) as u8).collect();
+        Frame {
+            data,
+            width,
+            height,
+            timestamp_us: 1000,
+            device_timestamp_us: 1000,
+        }
+    }
+
+    fn quarter_turn() -> Orientation {
+        Orientation {
+            rotation: render::Rotation::Cw90,
+            mirror: false,
+        }
+    }
+
+    // The encode worker only uses the CPU encoder off Windows, so its output
+    // is byte-comparable with the on-demand path there.
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn encode_worker_and_raw_path_produce_identical_jpeg() {
+        use crate::preview::encode_worker::{EncodeWorker, WorkerConfig};
+
+        let orientation = Orientation {
+            rotation: render::Rotation::Cw270,
+            mirror: true,
+        };
+        let config = WorkerConfig::default();
+        *config.orientation.lock() = orientation;
+        let (mut worker, sender) = EncodeWorker::spawn(config);
+        assert!(sender.send(gradient_frame(48, 32)));
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while worker.jpeg_buffer().latest().is_none() {
+            assert!(std::time::Instant::now() < deadline, "no frame encoded");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let encoded = worker.jpeg_buffer().latest().unwrap();
+        worker.stop();
+
+        let raw = encode_frame_source(
+            &FrameSource::Raw(Arc::new(gradient_frame(48, 32))),
+            orientation,
+            FRAME_JPEG_QUALITY,
+        )
+        .unwrap();
+        assert_eq!(encoded.jpeg_bytes, raw);
+        assert_eq!((encoded.width, encoded.height), jpeg_size(&raw));
+    }
+
+    #[test]
+    fn quarter_turn_swaps_dimensions_for_frame_and_thumbnail() {
+        let session = PreviewSession::DirectShow(make_ds_session("dev-1", 64, 32));
+        session.buffer().push(gradient_frame(64, 32));
+        session.set_orientation(quarter_turn());
+
+        let orientation = session.orientation();
+        let (source, seq) =
+            select_frame_source(&session, orientation, FrameVariant::default()).unwrap();
+        assert!(matches!(source, FrameSource::Raw(_)));
+        assert_eq!(seq, 1);
+
+        let frame = encode_frame_source(&source, orientation, FRAME_JPEG_QUALITY).unwrap();
+        assert_eq!(jpeg_size(&frame), (32, 64));
+
+        let config = ThumbnailConfig::default();
+        let (thumb, _) = render_thumbnail(
+            session.buffer().unwrap(),
+            orientation,
+            &config,
+            None,
+            FrameFormat::Jpeg,
+        )
+        .unwrap();
+        assert_eq!(jpeg_size(&thumb), (32, 64));
+
+        let mut session = session;
+        session.stop();
+    }
+
+    #[test]
+    fn thumbnails_are_drawn_at_the_configured_physical_size() {
+        let buffer = FrameBuffer::new(3);
+        buffer.push(gradient_frame(640, 360));
+        let at_2x = ThumbnailConfig::new(80, 60, 2.0).unwrap();
+
+        let (thumb, _) = render_thumbnail(
+            &buffer,
+            Orientation::default(),
+            &at_2x,
+            None,
+            FrameFormat::Jpeg,
+        )
+        .unwrap();
+        assert_eq!(jpeg_size(&thumb), (160, 90));
+
+        let (thumb, _) =
+            render_thumbnail(&buffer, quarter_turn(), &at_2x, None, FrameFormat::Jpeg).unwrap();
+        assert_eq!(jpeg_size(&thumb), (68, 120));
+    }
+
+    #[test]
+    fn thumbnails_can_ask_for_their_own_box_and_fit() {
+        let buffer = FrameBuffer::new(3);
+        buffer.push(gradient_frame(640, 360));
+        let config = ThumbnailConfig::default();
+        let draw = |fit| {
+            let spec = ThumbnailSpec::new(320, 240, fit).unwrap();
+            let (thumb, _) = render_thumbnail(
+                &buffer,
+                Orientation::default(),
+                &config,
+                Some(spec),
+                FrameFormat::Jpeg,
+            )
+            .unwrap();
+            jpeg_size(&thumb)
+        };
+
+        assert_eq!(draw(ThumbnailFit::Contain), (320, 180));
+        assert_eq!(draw(ThumbnailFit::Cover), (320, 240));
+        assert_eq!(draw(ThumbnailFit::Stretch), (320, 240));
+    }
+
+    #[test]
+    fn a_single_pixel_frame_has_a_thumbnail() {
+        let buffer = FrameBuffer::new(3);
+        buffer.push(gradient_frame(1, 1));
+        for fit in [
+            ThumbnailFit::Contain,
+            ThumbnailFit::Cover,
+            ThumbnailFit::Stretch,
+        ] {
+            let spec = ThumbnailSpec::new(160, 120, fit).unwrap();
+            let (thumb, _) = render_thumbnail(
+                &buffer,
+                Orientation::default(),
+                &ThumbnailConfig::default(),
+                Some(spec),
+                FrameFormat::Jpeg,
+            )
+            .unwrap();
+            assert_eq!(jpeg_size(&thumb), (1, 1));
+        }
+    }
+
+    #[test]
+    fn thumbnail_specs_fill_in_the_configured_box() {
+        let state = make_preview_state();
+        assert_eq!(
+            thumbnail_spec(&state, "cam-1", None, None, None).unwrap(),
+            None
+        );
+        assert_eq!(
+            thumbnail_spec(&state, "cam-1", Some(320), None, None).unwrap(),
+            Some(ThumbnailSpec::new(320, 120, ThumbnailFit::Contain).unwrap())
+        );
+        assert_eq!(
+            thumbnail_spec(&state, "cam-1", None, None, Some(ThumbnailFit::Cover)).unwrap(),
+            Some(ThumbnailSpec::new(160, 120, ThumbnailFit::Cover).unwrap())
+        );
+        let err = thumbnail_spec(&state, "cam-1", Some(0), Some(120), None).unwrap_err();
+        assert_eq!(err.code, code::INVALID_ARGUMENT);
     }
 
     #[test]
-    fn stop_preview_without_session_is_ok() {
+    fn configuring_thumbnails_drops_thumbnails_at_the_old_size() {
         let state = make_preview_state();
-        let sessions = state.sessions.lock();
-        assert!(sessions.get("nonexistent").is_none());
+        let at_2x = ThumbnailConfig::new(80, 60, 2.0).unwrap();
+        let fill = |state: &PreviewState| {
+            let mut cache = state.thumbnail_cache.lock();
+            cache.insert("cam-1", cached_jpeg(1));
+            cache.insert("cam-2", cached_jpeg(1));
+        };
+
+        fill(&state);
+        state.configure_thumbnails(Some("cam-1"), at_2x);
+        assert_eq!(state.thumbnail_config("cam-1"), at_2x);
+        assert_eq!(state.thumbnail_config("cam-2"), ThumbnailConfig::default());
+        assert!(state
+            .thumbnail_cache
+            .lock()
+            .get("cam-1", FrameVariant::default(), 1, Orientation::default())
+            .is_none());
+        assert!(state
+            .thumbnail_cache
+            .lock()
+            .get("cam-2", FrameVariant::default(), 1, Orientation::default())
+            .is_some());
+
+        // A global change leaves devices with their own size alone
+        fill(&state);
+        state.configure_thumbnails(None, at_2x);
+        assert!(state
+            .thumbnail_cache
+            .lock()
+            .get("cam-1", FrameVariant::default(), 1, Orientation::default())
+            .is_some());
+        assert!(state
+            .thumbnail_cache
+            .lock()
+            .get("cam-2", FrameVariant::default(), 1, Orientation::default())
+            .is_none());
+
+        // Repeating the same configuration keeps the cache
+        fill(&state);
+        state.configure_thumbnails(None, at_2x);
+        assert_eq!(state.thumbnail_cache.lock().len(), 2);
     }
 
     #[test]
-    fn get_frame_returns_base64_jpeg_when_active() {
-        let state = make_preview_state();
-        let frame = make_rgb_frame(10, 10);
-        {
-            let mut sessions = state.sessions.lock();
-            let session = make_ds_session("test-device", 10, 10);
-            session.buffer().push(frame);
-            sessions.insert(
-                "test-device".to_string(),
-                PreviewSession::DirectShow(session),
-            );
-        }
+    fn identity_orientation_keeps_landscape_dimensions() {
+        let buffer = FrameBuffer::new(3);
+        buffer.push(gradient_frame(64, 32));
 
-        let sessions = state.sessions.lock();
-        let session = sessions.get("test-device").unwrap();
-        let buf = session.buffer().unwrap();
-        let latest = buf.latest().unwrap();
-        let jpeg = compress::compress_jpeg(&latest.data, latest.width, latest.height, 85);
-        let b64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg);
-        assert!(!b64.is_empty());
+        let frame = encode_frame_source(
+            &FrameSource::Raw(buffer.latest().unwrap()),
+            Orientation::default(),
+            FRAME_JPEG_QUALITY,
+        )
+        .unwrap();
+        assert_eq!(jpeg_size(&frame), (64, 32));
 
-        let decoded =
-            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &b64).unwrap();
-        assert_eq!(decoded[0], 0xFF);
-        assert_eq!(decoded[1], 0xD8);
+        let config = ThumbnailConfig::default();
+        let (thumb, _) = render_thumbnail(
+            &buffer,
+            Orientation::default(),
+            &config,
+            None,
+            FrameFormat::Jpeg,
+        )
+        .unwrap();
+        assert_eq!(jpeg_size(&thumb), (64, 32));
     }
 
     #[test]
-    fn get_frame_returns_error_when_no_preview() {
-        let state = make_preview_state();
-        let sessions = state.sessions.lock();
-        let result = sessions.get("nonexistent");
-        assert!(result.is_none());
+    fn output_chains_only_change_the_frames_they_run_on() {
+        use crate::preview::watermark::Watermark;
+
+        let source = FrameSource::Raw(Arc::new(make_rgb_frame(320, 120)));
+        let context = watermark_context(Some("Desk".to_string()));
+        let plain = encode_frame_source(&source, Orientation::default(), 90).unwrap();
+
+        let unprocessed =
+            encode_for_output(&source, Orientation::default(), 90, &[], &context).unwrap();
+        assert_eq!(unprocessed, plain);
+
+        let chain = [Processor::Watermark(Watermark::default())];
+        let stamped =
+            encode_for_output(&source, Orientation::default(), 90, &chain, &context).unwrap();
+        assert_ne!(stamped, plain);
+        assert_eq!(jpeg_size(&stamped), (320, 120));
     }
 
-    #[test]
-    fn preview_error_payload_has_camel_case_keys() {
-        let payload = PreviewErrorPayload {
-            device_id: "cam-1".to_string(),
-            error: "resource busy".to_string(),
-        };
-        let json = serde_json::to_string(&payload).unwrap();
-        assert!(json.contains("deviceId"), "expected camelCase key: {json}");
-        assert!(
-            !json.contains("device_id"),
-            "expected no snake_case key: {json}"
-        );
+    /// Settings with nothing saved, kept in `dir`.
+    fn make_settings_state(dir: &tempfile::TempDir) -> SettingsState {
+        SettingsState {
+            store: Arc::new(SettingsStore::new(dir.path().join("settings.json"))),
+            ui_state: Arc::new(crate::settings::ui_state::UiStateStore::new(
+                dir.path().join("ui-state.json"),
+            )),
+        }
     }
 
-    #[test]
-    fn jpeg_cache_returns_same_result_for_same_sequence() {
+    /// Preview state with a full-resolution session for `dev-1` that has
+    /// captured one raw frame.
+    fn state_with_raw_frame() -> PreviewState {
         let state = make_preview_state();
-
-        let session = make_ds_session("dev-1", 10, 10);
-        session.buffer().push(make_rgb_frame(10, 10));
-        let seq = session.buffer().sequence();
+        let session = make_ds_session("dev-1", 64, 32);
+        session.buffer().push(gradient_frame(64, 32));
         state
             .sessions
             .lock()
             .insert("dev-1".to_string(), PreviewSession::DirectShow(session));
-
-        let frame1 = {
-            let sessions = state.sessions.lock();
-            sessions
-                .get("dev-1")
-                .unwrap()
-                .buffer()
-                .unwrap()
-                .latest()
-                .unwrap()
-        };
-        let jpeg = compress::compress_jpeg(&frame1.data, frame1.width, frame1.height, 85);
-        let b64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &jpeg);
-        state.jpeg_cache.lock().insert(
-            "dev-1".to_string(),
-            JpegCache {
-                sequence: seq,
-                base64: b64.clone(),
-            },
-        );
-
-        let cache = state.jpeg_cache.lock();
-        let cached = cache.get("dev-1").unwrap();
-        assert_eq!(cached.sequence, 1);
-        assert_eq!(cached.base64, b64);
+        state
     }
 
     #[test]
-    fn jpeg_cache_invalidates_on_new_frame() {
-        let state = make_preview_state();
+    fn raw_frames_are_jpeg_bytes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
 
-        state.jpeg_cache.lock().insert(
-            "dev-1".to_string(),
-            JpegCache {
+        let (sequence, jpeg) = preview_frame_bytes(&state, &settings_state, "dev-1").unwrap();
+        assert_eq!(sequence, 1);
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+        assert_eq!(
+            frame_meta(sequence, &jpeg).unwrap(),
+            FrameMeta {
                 sequence: 1,
-                base64: "old-data".to_string(),
-            },
+                width: 64,
+                height: 32,
+                byte_len: jpeg.len(),
+            }
         );
+    }
 
-        let session = make_ds_session("dev-1", 10, 10);
-        session.buffer().push(make_rgb_frame(10, 10));
-        session.buffer().push(make_rgb_frame(10, 10));
-        let new_seq = session.buffer().sequence();
+    #[test]
+    fn raw_and_base64_frames_share_one_compression() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
+
+        let (_, raw) = preview_frame_bytes(&state, &settings_state, "dev-1").unwrap();
+        let (_, again) = preview_frame_bytes(&state, &settings_state, "dev-1").unwrap();
+        // Served from the cache, not compressed a second time
+        assert!(Arc::ptr_eq(&raw, &again));
+
+        let (_, base64) = preview_frame_base64(
+            &state,
+            &settings_state,
+            "dev-1",
+            FrameVariant::default(),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            base64,
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &*raw)
+        );
+        let cached = state.jpeg_cache.lock().get(
+            "dev-1",
+            FrameVariant::default(),
+            1,
+            Orientation::default(),
+        );
+        assert!(cached.is_some_and(|cached| Arc::ptr_eq(&cached, &raw)));
+        assert_eq!(state.cache_bytes(), raw.len() + base64.len());
 
-        let cache = state.jpeg_cache.lock();
-        let cached = cache.get("dev-1").unwrap();
-        assert_ne!(cached.sequence, new_seq);
+        assert!(state.stop_session("dev-1"));
+        assert!(state.jpeg_cache.lock().is_empty());
+        assert_eq!(state.cache_bytes(), 0);
     }
 
     #[test]
-    fn stop_preview_clears_jpeg_cache() {
+    fn get_frame_and_subscriptions_share_one_encoded_frame() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
         let state = make_preview_state();
-
-        let session = make_ds_session("dev-1", 10, 10);
+        let session = make_ds_session("dev-1", 640, 480);
+        let test_frame = crate::camera::dummy::DummyBackend::test_frame();
+        session.seed_frame(&JpegFrame {
+            jpeg_bytes: test_frame.clone(),
+            width: 1,
+            height: 1,
+            encoder_kind: crate::preview::mf_jpeg::encoder::EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
+        });
         state
             .sessions
             .lock()
             .insert("dev-1".to_string(), PreviewSession::DirectShow(session));
-        state.jpeg_cache.lock().insert(
-            "dev-1".to_string(),
-            JpegCache {
-                sequence: 1,
-                base64: "cached".to_string(),
-            },
+
+        let (sequence, base64) = preview_frame_base64(
+            &state,
+            &settings_state,
+            "dev-1",
+            FrameVariant::default(),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            base64,
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &test_frame)
+        );
+        assert_eq!(
+            state.jpeg_cache.lock().get_base64(
+                "dev-1",
+                FrameVariant::default(),
+                sequence,
+                Orientation::default()
+            ),
+            Some(base64)
         );
+        // A subscriber that already sent this frame isn't given it again
+        assert_eq!(
+            preview_frame_base64(
+                &state,
+                &settings_state,
+                "dev-1",
+                FrameVariant::default(),
+                Some(sequence)
+            )
+            .unwrap(),
+            None
+        );
+    }
 
-        {
-            let mut sessions = state.sessions.lock();
-            if let Some(mut s) = sessions.remove("dev-1") {
-                s.stop();
-            }
+    const PNG: FrameVariant = FrameVariant {
+        format: FrameFormat::Png,
+        crop: None,
+        thumbnail: None,
+    };
+
+    fn cropped(x: u32, y: u32, width: u32, height: u32) -> FrameVariant {
+        FrameVariant {
+            format: FrameFormat::Jpeg,
+            crop: Some(PixelRect {
+                x,
+                y,
+                width,
+                height,
+            }),
+            thumbnail: None,
         }
-        state.jpeg_cache.lock().remove("dev-1");
+    }
+
+    #[test]
+    fn png_frames_decode_to_the_raw_pixels() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
 
-        assert!(state.jpeg_cache.lock().get("dev-1").is_none());
+        let (_, base64) = preview_frame_base64(&state, &settings_state, "dev-1", PNG, None)
+            .unwrap()
+            .unwrap();
+        let png =
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64).unwrap();
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.dimensions(), (64, 32));
+        assert_eq!(decoded.into_raw(), gradient_frame(64, 32).data);
     }
 
     #[test]
-    fn start_all_previews_creates_sessions_for_all_devices() {
-        let state = make_preview_state();
+    fn switching_formats_keeps_both_frames_cached() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
 
-        let device_ids = vec!["cam-1", "cam-2", "cam-3"];
-        {
-            let mut sessions = state.sessions.lock();
-            for id in &device_ids {
-                let session = make_ds_session(id, 640, 480);
-                sessions.insert(id.to_string(), PreviewSession::DirectShow(session));
-            }
+        let (_, jpeg) = preview_frame_bytes(&state, &settings_state, "dev-1").unwrap();
+        preview_frame_base64(&state, &settings_state, "dev-1", PNG, None).unwrap();
+        let (_, again) = preview_frame_bytes(&state, &settings_state, "dev-1").unwrap();
+        assert!(Arc::ptr_eq(&jpeg, &again));
+
+        let mut cache = state.jpeg_cache.lock();
+        assert_eq!(cache.len(), 2);
+        let png = cache.get("dev-1", PNG, 1, Orientation::default()).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected_with_the_accepted_ones() {
+        assert_eq!(parse_frame_format(None).unwrap(), FrameFormat::Jpeg);
+        assert_eq!(parse_frame_format(Some("png")).unwrap(), FrameFormat::Png);
+
+        let err = parse_frame_format(Some("webp")).unwrap_err();
+        assert_eq!(err.code, code::INVALID_ARGUMENT);
+        assert!(err.message.contains("jpeg, png"), "{}", err.message);
+    }
+
+    #[test]
+    fn crops_are_served_at_their_size_and_cached() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
+
+        let jpeg =
+            |variant| match cached_preview_frame(&state, &settings_state, "dev-1", variant, None)
+                .unwrap()
+                .unwrap()
+            {
+                PreviewFrame::Cached { jpeg, .. } => jpeg,
+                PreviewFrame::Placeholder(_) => panic!("expected a frame"),
+            };
+        let crop = jpeg(cropped(8, 4, 16, 8));
+        assert_eq!(jpeg_size(&crop), (16, 8));
+        // Same crop of the same frame comes from the cache
+        assert!(Arc::ptr_eq(&crop, &jpeg(cropped(8, 4, 16, 8))));
+        // Clamped to the 64x32 frame
+        assert_eq!(jpeg_size(&jpeg(cropped(48, 0, 100, 100))), (16, 32));
+        assert_eq!(jpeg_size(&jpeg(FrameVariant::default())), (64, 32));
+    }
+
+    #[test]
+    fn empty_crops_and_crops_outside_the_frame_are_rejected() {
+        let rect = |width, height| PixelRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        assert_eq!(check_crop(None).unwrap(), None);
+        assert_eq!(check_crop(Some(rect(1, 1))).unwrap(), Some(rect(1, 1)));
+        for empty in [rect(0, 10), rect(10, 0)] {
+            let err = check_crop(Some(empty)).unwrap_err();
+            assert_eq!(err.code, code::INVALID_ARGUMENT);
         }
 
-        let sessions = state.sessions.lock();
-        assert_eq!(sessions.len(), 3);
-        assert!(sessions.contains_key("cam-1"));
-        assert!(sessions.contains_key("cam-2"));
-        assert!(sessions.contains_key("cam-3"));
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
+        let err = match cached_preview_frame(
+            &state,
+            &settings_state,
+            "dev-1",
+            cropped(64, 0, 8, 8),
+            None,
+        ) {
+            Err(err) => err,
+            Ok(_) => panic!("expected the crop to be rejected"),
+        };
+        assert_eq!(err.code, code::INVALID_ARGUMENT);
+        assert!(state.jpeg_cache.lock().is_empty());
     }
 
     #[test]
-    fn start_all_previews_skips_existing_sessions() {
+    fn stopping_a_preview_ends_its_subscription() {
         let state = make_preview_state();
+        state.sessions.lock().insert(
+            "dev-1".to_string(),
+            PreviewSession::DirectShow(make_ds_session("dev-1", 640, 480)),
+        );
+        let mut stopped = state.subscriptions.lock().subscribe("dev-1");
 
-        {
-            let mut sessions = state.sessions.lock();
-            let session = CaptureSession::new(
-                "cam-1".to_string(),
-                "Camera 1".to_string(),
-                640,
-                480,
-                30.0,
-                None,
-                None,
-                75,
-            );
-            sessions.insert("cam-1".to_string(), PreviewSession::DirectShow(session));
-        }
+        assert!(state.stop_session("dev-1"));
+        assert!(state.sessions.lock().is_empty());
+        assert!(!state.subscriptions.lock().is_subscribed("dev-1"));
+        // The subscription's task sees the stop
+        assert!(stopped.try_recv().is_err());
+        assert!(!state.stop_session("dev-1"));
+    }
 
-        {
-            let mut sessions = state.sessions.lock();
-            for id in &["cam-1", "cam-2"] {
-                if !sessions.contains_key(*id) {
-                    let session = make_ds_session(id, 640, 480);
-                    sessions.insert(id.to_string(), PreviewSession::DirectShow(session));
-                }
-            }
-        }
+    #[test]
+    fn snapshots_are_full_size_jpeg_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let state = state_with_raw_frame();
+        let path = dir.path().join("stills").join("dev-1.jpg");
+
+        write_session_snapshot(&state, &settings_state, "dev-1", &path, 92).unwrap();
+        let jpeg = std::fs::read(&path).unwrap();
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+        assert_eq!(compress::jpeg_dimensions(&jpeg), Ok((64, 32)));
 
+        write_session_snapshot(&state, &settings_state, "dev-1", &path, 92).unwrap();
         let sessions = state.sessions.lock();
-        assert_eq!(sessions.len(), 2);
-        assert!(sessions.contains_key("cam-1"));
-        assert!(sessions.contains_key("cam-2"));
+        assert_eq!(sessions["dev-1"].diagnostics().snapshot_count, 2);
     }
 
     #[test]
-    fn stop_preview_for_disconnected_device_cleans_up() {
+    fn snapshots_need_a_session_with_a_frame() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings_state = make_settings_state(&dir);
+        let path = dir.path().join("still.jpg");
         let state = make_preview_state();
 
-        {
-            let mut sessions = state.sessions.lock();
-            let session = make_ds_session("cam-1", 640, 480);
-            sessions.insert("cam-1".to_string(), PreviewSession::DirectShow(session));
-        }
-        state.jpeg_cache.lock().insert(
-            "cam-1".to_string(),
-            JpegCache {
-                sequence: 1,
-                base64: "cached".to_string(),
-            },
+        let err = write_session_snapshot(&state, &settings_state, "dev-1", &path, 92).unwrap_err();
+        assert_eq!(err.code, code::PREVIEW_NOT_RUNNING);
+
+        state.sessions.lock().insert(
+            "dev-1".to_string(),
+            PreviewSession::DirectShow(make_ds_session("dev-1", 64, 32)),
         );
+        let err = write_session_snapshot(&state, &settings_state, "dev-1", &path, 92).unwrap_err();
+        assert_eq!(err.code, code::FRAME_UNAVAILABLE);
+        assert!(!path.exists());
+        let sessions = state.sessions.lock();
+        assert_eq!(sessions["dev-1"].diagnostics().snapshot_count, 0);
+    }
 
-        {
-            let mut sessions = state.sessions.lock();
-            if let Some(mut s) = sessions.remove("cam-1") {
-                s.stop();
-            }
-        }
-        state.jpeg_cache.lock().remove("cam-1");
+    #[test]
+    fn passthrough_jpeg_is_rotated_when_orientation_set() {
+        let rgb = gradient_frame(64, 32);
+        let jpeg = compress::compress_jpeg(&rgb.data, 64, 32, 90);
+        let source = FrameSource::Passthrough(Arc::new(JpegFrame {
+            jpeg_bytes: jpeg.clone(),
+            width: 64,
+            height: 32,
+            encoder_kind: crate::preview::mf_jpeg::encoder::EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
+        }));
 
-        assert!(!state.sessions.lock().contains_key("cam-1"));
-        assert!(state.jpeg_cache.lock().get("cam-1").is_none());
+        assert_eq!(
+            encode_frame_source(&source, Orientation::default(), FRAME_JPEG_QUALITY).unwrap(),
+            jpeg
+        );
+        let rotated = encode_frame_source(&source, quarter_turn(), FRAME_JPEG_QUALITY).unwrap();
+        assert_eq!(jpeg_size(&rotated), (32, 64));
     }
 
     #[test]
-    fn frame_buffer_latest_returns_arc() {
-        let state = make_preview_state();
-        let session = make_ds_session("dev-1", 10, 10);
-        session.buffer().push(make_rgb_frame(10, 10));
-        state
-            .sessions
-            .lock()
-            .insert("dev-1".to_string(), PreviewSession::DirectShow(session));
+    fn encoded_frames_report_their_raw_source_sequence() {
+        let session = PreviewSession::DirectShow(make_ds_session("dev-1", 64, 32));
+        let raw = session.buffer().unwrap();
+        for _ in 0..4 {
+            raw.push(gradient_frame(64, 32));
+        }
+        // The worker encoded frame 2 and has not caught up since
+        session.jpeg_buffer().unwrap().update(JpegFrame {
+            jpeg_bytes: vec![0xFF, 0xD8],
+            width: 64,
+            height: 32,
+            encoder_kind: crate::preview::mf_jpeg::encoder::EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 2,
+        });
 
-        let sessions = state.sessions.lock();
-        let session = sessions.get("dev-1").unwrap();
-        let buf = session.buffer().unwrap();
-        let frame1 = buf.latest().unwrap();
-        let frame2 = buf.latest().unwrap();
+        let (source, seq) =
+            select_frame_source(&session, Orientation::default(), FrameVariant::default()).unwrap();
+        assert_eq!(seq, 1, "cache key stays on the JPEG buffer");
+        session.record_consumed(PREVIEW_CONSUMER, source.delivered_sequence(seq));
 
-        assert!(std::sync::Arc::ptr_eq(&frame1, &frame2));
+        let delivery = session.diagnostics().frame_delivery;
+        assert_eq!(delivery.len(), 1);
+        assert_eq!(delivery[0].consumer, PREVIEW_CONSUMER);
+        assert_eq!((delivery[0].produced, delivery[0].consumed), (4, 1));
+        // Frame 1 was overwritten unseen; 3 and 4 are still buffered
+        assert_eq!(delivery[0].overwritten_unseen, 1);
     }
 
     #[test]
-    fn preview_session_routes_edsdk_to_canon() {
-        // Verify that the edsdk:// prefix detection works
-        let device_path = "edsdk://Canon EOS R5";
-        assert!(device_path.starts_with("edsdk://"));
+    fn stale_encoded_frame_is_bypassed_after_orientation_change() {
+        let session = PreviewSession::DirectShow(make_ds_session("dev-1", 64, 32));
+        session.buffer().push(gradient_frame(64, 32));
+        session.jpeg_buffer().unwrap().update(JpegFrame {
+            jpeg_bytes: vec![0xFF, 0xD8],
+            width: 64,
+            height: 32,
+            encoder_kind: crate::preview::mf_jpeg::encoder::EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
+        });
 
-        let device_path = "\\\\?\\usb#vid_046d";
-        assert!(!device_path.starts_with("edsdk://"));
+        let (source, _) =
+            select_frame_source(&session, Orientation::default(), FrameVariant::default()).unwrap();
+        assert!(matches!(source, FrameSource::Encoded(_)));
+
+        let (source, _) =
+            select_frame_source(&session, quarter_turn(), FrameVariant::default()).unwrap();
+        assert!(matches!(source, FrameSource::Raw(_)));
+
+        let mut session = session;
+        session.stop();
     }
 
     #[test]
-    fn preview_session_canon_has_no_raw_buffer() {
-        use crate::camera::canon::api::CameraHandle;
-        use crate::camera::canon::mock::MockEdsSdk;
-        use crate::preview::capture::CanonCaptureSession;
+    fn jpeg_cache_misses_when_orientation_changes() {
+        let cache = cached_jpeg(3);
+        assert!(cache.matches(3, Orientation::default()));
+        assert!(!cache.matches(3, quarter_turn()));
+        assert!(!cache.matches(4, Orientation::default()));
+    }
 
-        let mock = Arc::new(
-            MockEdsSdk::new()
-                .with_cameras(1)
-                .with_live_view_frame(vec![0xFF, 0xD8, 0xFF, 0xD9]),
+    #[test]
+    fn frame_quality_seeds_controller_from_profile_once() {
+        let state = make_preview_state();
+        let pinned = QualityProfile {
+            adaptive: false,
+            quality: 60,
+            ..QualityProfile::default()
+        };
+        assert_eq!(state.frame_quality("dev-1", || pinned), 60);
+        // Later calls reuse the existing controller
+        assert_eq!(
+            state.frame_quality("dev-1", || unreachable!("controller already exists")),
+            60
         );
-        let camera = CameraHandle(0);
+    }
 
-        let session = CanonCaptureSession::new("canon:MOCK0001".to_string(), mock, camera).unwrap();
+    #[test]
+    fn slow_encodes_lower_quality_and_show_in_diagnostics() {
+        let state = make_preview_state();
+        let start = state.frame_quality("dev-1", QualityProfile::default);
+        for _ in 0..3 {
+            state.record_encode("dev-1", Duration::from_millis(45));
+        }
+        let lowered = state.frame_quality("dev-1", QualityProfile::default);
+        assert!(lowered < start);
 
-        let preview = PreviewSession::Canon(session);
-        assert!(
-            preview.buffer().is_none(),
-            "Canon sessions have no raw buffer"
-        );
-        assert!(
-            preview.jpeg_buffer().is_some(),
-            "Canon sessions have a JPEG buffer"
-        );
+        let snapshot = state.with_quality_stats("dev-1", DiagnosticSnapshot::default());
+        assert_eq!(snapshot.effective_jpeg_quality, Some(lowered));
+        assert_eq!(snapshot.recent_encode_ms.len(), 3);
+
+        let untouched = state.with_quality_stats("dev-2", DiagnosticSnapshot::default());
+        assert_eq!(untouched.effective_jpeg_quality, None);
     }
 
     #[test]
-    fn preview_session_directshow_has_raw_buffer() {
-        let session = make_ds_session("dev-1", 10, 10);
-        let preview = PreviewSession::DirectShow(session);
-        assert!(
-            preview.buffer().is_some(),
-            "DirectShow sessions have a raw buffer"
+    fn set_quality_profile_applies_to_next_encode() {
+        let state = make_preview_state();
+        state.frame_quality("dev-1", QualityProfile::default);
+        state.set_quality_profile(
+            "dev-1",
+            QualityProfile {
+                adaptive: false,
+                quality: 95,
+                ..QualityProfile::default()
+            },
         );
+        state.record_encode("dev-1", Duration::from_millis(45));
+        assert_eq!(state.frame_quality("dev-1", QualityProfile::default), 95);
     }
 
     #[test]
-    fn canon_thumbnail_returns_error() {
-        // Canon live view doesn't support thumbnails (no raw buffer)
-        let device_path = "edsdk://Canon EOS R5";
-        assert!(
-            device_path.starts_with("edsdk://"),
-            "Canon devices should be detected by edsdk:// prefix"
-        );
-        // The get_thumbnail command calls session.buffer() which returns None for Canon,
-        // resulting in "thumbnails not available for Canon live view" error.
+    fn only_raw_and_reoriented_passthrough_frames_need_compression() {
+        let jpeg = Arc::new(JpegFrame {
+            jpeg_bytes: vec![],
+            width: 1,
+            height: 1,
+            encoder_kind: crate::preview::mf_jpeg::encoder::EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
+        });
+        let identity = Orientation::default();
+
+        assert!(!FrameSource::Encoded(Arc::clone(&jpeg)).needs_compression(identity));
+        assert!(!FrameSource::Passthrough(Arc::clone(&jpeg)).needs_compression(identity));
+        assert!(FrameSource::Passthrough(jpeg).needs_compression(quarter_turn()));
+        assert!(FrameSource::Raw(Arc::new(make_rgb_frame(2, 2))).needs_compression(identity));
     }
 }
@@ -1,5 +1,58 @@
-use image::codecs::jpeg::JpegEncoder;
-use image::{ImageBuffer, Rgb};
+use image::codecs::jpeg::{JpegDecoder, JpegEncoder};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageBuffer, ImageDecoder, Rgb};
+use serde::{Deserialize, Serialize};
+
+/// Bytes per pixel of the RGB24 data compressed here.
+const CHANNELS: usize = 3;
+
+/// Image format a frame or thumbnail is delivered in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FrameFormat {
+    #[default]
+    Jpeg,
+    /// Lossless, for checking focus on fine detail. Several times the size
+    /// of the JPEG.
+    Png,
+}
+
+impl FrameFormat {
+    pub const ALL: [Self; 2] = [Self::Jpeg, Self::Png];
+
+    /// Name of the format, as `get_frame` and `get_thumbnail` take it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpeg",
+            Self::Png => "png",
+        }
+    }
+
+    /// The format called `name`, or JPEG when none is given.
+    pub fn parse(name: Option<&str>) -> Result<Self, String> {
+        let Some(name) = name else {
+            return Ok(Self::default());
+        };
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                let accepted: Vec<_> = Self::ALL.iter().map(|format| format.name()).collect();
+                format!(
+                    "unknown format {name:?}; expected one of: {}",
+                    accepted.join(", ")
+                )
+            })
+    }
+
+    /// Compress raw RGB pixel data in this format. `quality` (1-100) only
+    /// applies to JPEG.
+    pub fn compress(self, data: &[u8], width: u32, height: u32, quality: u8) -> Vec<u8> {
+        match self {
+            Self::Jpeg => compress_jpeg(data, width, height, quality),
+            Self::Png => compress_png(data, width, height),
+        }
+    }
+}
 
 /// Compress raw RGB pixel data to JPEG at the given quality (1-100).
 pub fn compress_jpeg(data: &[u8], width: u32, height: u32, quality: u8) -> Vec<u8> {
@@ -13,34 +66,132 @@ pub fn compress_jpeg(data: &[u8], width: u32, height: u32, quality: u8) -> Vec<u
     buf
 }
 
+/// Compress raw RGB pixel data to PNG. Uses fast compression, since frames
+/// are compressed on every poll rather than stored.
+pub fn compress_png(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let img: ImageBuffer<Rgb<u8>, _> =
+        ImageBuffer::from_raw(width, height, data).expect("invalid buffer dimensions");
+
+    let mut buf = Vec::new();
+    let encoder =
+        PngEncoder::new_with_quality(&mut buf, CompressionType::Fast, FilterType::Adaptive);
+    img.write_with_encoder(encoder)
+        .expect("PNG encoding failed");
+    buf
+}
+
+/// A rectangle of a frame in pixels, `(0, 0)` being the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    /// The part of this rectangle inside a `width`x`height` frame, or `None`
+    /// if none of it is.
+    pub fn clamp_to(self, width: u32, height: u32) -> Option<Self> {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        let clamped = Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        };
+        (clamped.width > 0 && clamped.height > 0).then_some(clamped)
+    }
+}
+
+/// Cut `rect` out of RGB24 data `width`x`height`, clamping it to the frame
+/// first. Empty when none of `rect` is inside the frame.
+pub fn crop_rgb(frame: &[u8], width: u32, height: u32, rect: PixelRect) -> Vec<u8> {
+    let Some(rect) = rect.clamp_to(width, height) else {
+        return Vec::new();
+    };
+    let stride = width as usize * CHANNELS;
+    let row_len = rect.width as usize * CHANNELS;
+    let mut out = Vec::with_capacity(row_len * rect.height as usize);
+    for y in rect.y..rect.y + rect.height {
+        let start = y as usize * stride + rect.x as usize * CHANNELS;
+        out.extend_from_slice(&frame[start..start + row_len]);
+    }
+    out
+}
+
+/// Width and height of a JPEG, read from its header without decoding it.
+pub fn jpeg_dimensions(jpeg: &[u8]) -> Result<(u32, u32), String> {
+    let decoder = JpegDecoder::new(std::io::Cursor::new(jpeg)).map_err(|e| e.to_string())?;
+    Ok(decoder.dimensions())
+}
+
 /// Compress and downscale raw RGB data for sidebar thumbnails.
 ///
-/// Uses `fast_image_resize` for SIMD-accelerated resizing, then encodes to JPEG.
+/// Resizes `crop` of the frame, or all of it, with [`downscale_region`],
+/// then encodes in `format`.
 pub fn compress_thumbnail(
     data: &[u8],
     width: u32,
     height: u32,
+    crop: Option<PixelRect>,
     thumb_width: u32,
     thumb_height: u32,
+    format: FrameFormat,
 ) -> Vec<u8> {
-    use fast_image_resize as fr;
-    use fr::images::Image;
+    let resized_data = downscale_region(data, width, height, crop, thumb_width, thumb_height);
+    format.compress(&resized_data, thumb_width, thumb_height, 70)
+}
+
+/// Resize raw RGB data to `thumb_width`x`thumb_height`.
+pub fn downscale_rgb(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    thumb_width: u32,
+    thumb_height: u32,
+) -> Vec<u8> {
+    downscale_region(data, width, height, None, thumb_width, thumb_height)
+}
 
-    // Create source image
-    let src_image = Image::from_vec_u8(width, height, data.to_vec(), fr::PixelType::U8x3).unwrap();
+/// Resize `crop` of raw RGB data, or all of it, to
+/// `thumb_width`x`thumb_height`, reading the frame in place.
+///
+/// Uses `fast_image_resize` for SIMD-accelerated resizing, with a Lanczos
+/// filter so fine detail averages out rather than aliasing when a large
+/// frame is shrunk to a thumbnail.
+pub fn downscale_region(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    crop: Option<PixelRect>,
+    thumb_width: u32,
+    thumb_height: u32,
+) -> Vec<u8> {
+    use fast_image_resize as fr;
+    use fr::images::{Image, ImageRef};
 
-    // Create destination image
+    let src_image = ImageRef::new(width, height, data, fr::PixelType::U8x3).unwrap();
     let mut dst_image = Image::new(thumb_width, thumb_height, fr::PixelType::U8x3);
 
-    // Resize
+    let mut options =
+        fr::ResizeOptions::new().resize_alg(fr::ResizeAlg::Convolution(fr::FilterType::Lanczos3));
+    if let Some(rect) = crop.and_then(|rect| rect.clamp_to(width, height)) {
+        options = options.crop(
+            f64::from(rect.x),
+            f64::from(rect.y),
+            f64::from(rect.width),
+            f64::from(rect.height),
+        );
+    }
     let mut resizer = fr::Resizer::new();
     resizer
-        .resize(&src_image, &mut dst_image, None)
+        .resize(&src_image, &mut dst_image, &options)
         .expect("resize failed");
 
-    // Encode the resized image as JPEG
-    let resized_data = dst_image.into_vec();
-    compress_jpeg(&resized_data, thumb_width, thumb_height, 70)
+    dst_image.into_vec()
 }
 
 #[cfg(test)]
@@ -60,6 +211,14 @@ mod tests {
         data
     }
 
+    #[test]
+    fn jpeg_dimensions_come_from_the_header() {
+        let jpeg = compress_jpeg(&make_test_rgb(64, 32), 64, 32, 80);
+        assert_eq!(jpeg_dimensions(&jpeg), Ok((64, 32)));
+        assert!(jpeg_dimensions(&jpeg[..4]).is_err());
+        assert!(jpeg_dimensions(b"not a jpeg").is_err());
+    }
+
     #[test]
     fn compress_jpeg_produces_valid_jpeg_bytes() {
         let rgb = make_test_rgb(640, 480);
@@ -96,7 +255,7 @@ mod tests {
     #[test]
     fn compress_thumbnail_produces_reduced_resolution() {
         let rgb = make_test_rgb(1920, 1080);
-        let thumb = compress_thumbnail(&rgb, 1920, 1080, 160, 120);
+        let thumb = compress_thumbnail(&rgb, 1920, 1080, None, 160, 120, FrameFormat::Jpeg);
         // Should be valid JPEG
         assert_eq!(thumb[0], 0xFF);
         assert_eq!(thumb[1], 0xD8);
@@ -105,11 +264,175 @@ mod tests {
     #[test]
     fn compress_thumbnail_output_under_10kb() {
         let rgb = make_test_rgb(1920, 1080);
-        let thumb = compress_thumbnail(&rgb, 1920, 1080, 160, 120);
+        let thumb = compress_thumbnail(&rgb, 1920, 1080, None, 160, 120, FrameFormat::Jpeg);
         assert!(
             thumb.len() < 10_000,
             "thumbnail size {} exceeds 10KB",
             thumb.len()
         );
     }
+
+    #[test]
+    fn compress_png_decodes_to_the_exact_input_pixels() {
+        let rgb = make_test_rgb(37, 23);
+        let png = compress_png(&rgb, 37, 23);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.dimensions(), (37, 23));
+        assert_eq!(decoded.into_raw(), rgb);
+    }
+
+    #[test]
+    fn png_thumbnails_are_lossless_at_the_target_size() {
+        let rgb = make_test_rgb(320, 240);
+        let thumb = compress_thumbnail(&rgb, 320, 240, None, 160, 120, FrameFormat::Png);
+        let decoded = image::load_from_memory_with_format(&thumb, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.into_raw(), downscale_rgb(&rgb, 320, 240, 160, 120));
+    }
+
+    #[test]
+    fn formats_parse_by_name_and_default_to_jpeg() {
+        assert_eq!(FrameFormat::parse(None), Ok(FrameFormat::Jpeg));
+        assert_eq!(FrameFormat::parse(Some("jpeg")), Ok(FrameFormat::Jpeg));
+        assert_eq!(FrameFormat::parse(Some("PNG")), Ok(FrameFormat::Png));
+
+        let err = FrameFormat::parse(Some("gif")).unwrap_err();
+        assert_eq!(err, "unknown format \"gif\"; expected one of: jpeg, png");
+    }
+
+    /// The pixel of `make_test_rgb` data at (`x`, `y`).
+    fn test_pixel(x: u32, y: u32) -> [u8; 3] {
+        [(x % 256) as u8, (y % 256) as u8, 128]
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> PixelRect {
+        PixelRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn crop_rgb_keeps_the_rows_and_columns_of_the_rect() {
+        let rgb = make_test_rgb(8, 6);
+        let cropped = crop_rgb(&rgb, 8, 6, rect(2, 1, 3, 2));
+        let expected: Vec<u8> = [(2, 1), (3, 1), (4, 1), (2, 2), (3, 2), (4, 2)]
+            .into_iter()
+            .flat_map(|(x, y)| test_pixel(x, y))
+            .collect();
+        assert_eq!(cropped, expected);
+    }
+
+    #[test]
+    fn crop_rgb_of_a_single_pixel() {
+        let rgb = make_test_rgb(8, 6);
+        assert_eq!(crop_rgb(&rgb, 8, 6, rect(5, 4, 1, 1)), test_pixel(5, 4));
+    }
+
+    #[test]
+    fn crop_rgb_larger_than_the_frame_is_the_whole_frame() {
+        let rgb = make_test_rgb(8, 6);
+        assert_eq!(crop_rgb(&rgb, 8, 6, rect(0, 0, 100, 100)), rgb);
+        assert_eq!(rect(0, 0, 100, 100).clamp_to(8, 6), Some(rect(0, 0, 8, 6)));
+    }
+
+    #[test]
+    fn crop_rgb_at_the_right_and_bottom_edges_is_clamped() {
+        let rgb = make_test_rgb(8, 6);
+        assert_eq!(rect(6, 4, 5, 5).clamp_to(8, 6), Some(rect(6, 4, 2, 2)));
+        let expected: Vec<u8> = [(6, 4), (7, 4), (6, 5), (7, 5)]
+            .into_iter()
+            .flat_map(|(x, y)| test_pixel(x, y))
+            .collect();
+        assert_eq!(crop_rgb(&rgb, 8, 6, rect(6, 4, 5, 5)), expected);
+        assert_eq!(crop_rgb(&rgb, 8, 6, rect(7, 5, 1, 1)), test_pixel(7, 5));
+    }
+
+    #[test]
+    fn crops_outside_the_frame_or_without_area_are_empty() {
+        let rgb = make_test_rgb(8, 6);
+        assert_eq!(rect(8, 0, 2, 2).clamp_to(8, 6), None);
+        assert_eq!(rect(0, 6, 2, 2).clamp_to(8, 6), None);
+        assert_eq!(rect(1, 1, 0, 2).clamp_to(8, 6), None);
+        assert!(crop_rgb(&rgb, 8, 6, rect(20, 20, 2, 2)).is_empty());
+    }
+
+    #[test]
+    fn pixel_rects_use_camel_case_over_ipc() {
+        let parsed: PixelRect =
+            serde_json::from_str(r#"{"x":1,"y":2,"width":3,"height":4}"#).unwrap();
+        assert_eq!(parsed, rect(1, 2, 3, 4));
+    }
+
+    #[test]
+    fn downscale_rgb_produces_packed_rgb_at_the_target_size() {
+        let rgb = make_test_rgb(1920, 1080);
+        let small = downscale_rgb(&rgb, 1920, 1080, 160, 90);
+        assert_eq!(small.len(), 160 * 90 * 3);
+    }
+
+    #[test]
+    fn downscaling_fine_detail_averages_it_out() {
+        // A one-pixel checkerboard, which nearest-neighbour sampling would
+        // turn into solid black or white
+        let (width, height) = (1920, 1080);
+        let rgb: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let v = if (i % width + i / width) % 2 == 0 {
+                    0
+                } else {
+                    255
+                };
+                [v; 3]
+            })
+            .collect();
+        let small = downscale_rgb(&rgb, width, height, 160, 90);
+        let (min, max) = (small.iter().min(), small.iter().max());
+        assert!(
+            small.iter().all(|&v| (120..=135).contains(&v)),
+            "{min:?}..{max:?}"
+        );
+    }
+
+    #[test]
+    fn regions_are_downscaled_without_the_rest_of_the_frame() {
+        // Left half black, right half white
+        let (width, height) = (64, 32);
+        let rgb: Vec<u8> = (0..width * height)
+            .flat_map(|i| [if i % width < 32 { 0 } else { 255 }; 3])
+            .collect();
+        let right = rect(32, 0, 32, 32);
+        let small = downscale_region(&rgb, width, height, Some(right), 8, 8);
+        // The filter reaches a pixel or two past the region's edge
+        assert!(small.iter().all(|&v| v >= 224), "{small:?}");
+
+        let jpeg = compress_thumbnail(&rgb, width, height, Some(right), 8, 8, FrameFormat::Jpeg);
+        assert_eq!(jpeg_dimensions(&jpeg), Ok((8, 8)));
+    }
+
+    #[test]
+    fn odd_and_single_pixel_frames_downscale() {
+        for (width, height, thumb) in [(1, 1, (1, 1)), (333, 7, (160, 3)), (7, 333, (3, 120))] {
+            let rgb = make_test_rgb(width, height);
+            let small = downscale_rgb(&rgb, width, height, thumb.0, thumb.1);
+            assert_eq!(small.len(), (thumb.0 * thumb.1 * 3) as usize);
+            let jpeg = compress_thumbnail(
+                &rgb,
+                width,
+                height,
+                None,
+                thumb.0,
+                thumb.1,
+                FrameFormat::Jpeg,
+            );
+            assert_eq!(jpeg_dimensions(&jpeg), Ok(thumb));
+        }
+    }
 }
@@ -14,8 +14,13 @@ use parking_lot::Mutex;
 use serde::Serialize;
 use tracing::{debug, info, trace, warn};
 
+use crate::diagnostics::accounting::{CpuMeter, CpuTimer, CpuWork};
+use crate::diagnostics::delivery::{DeliveryTracker, FrameDelivery};
 use crate::preview::capture::Frame;
 use crate::preview::mf_jpeg::encoder::EncoderKind;
+use crate::preview::render::{self, Orientation, SharedOrientation};
+use crate::preview::transform::{PostProcessing, SharedPostProcessing};
+use crate::preview::zoom::{CropRect, SharedCrop};
 
 /// A single JPEG-encoded frame ready for IPC delivery.
 pub struct JpegFrame {
@@ -27,6 +32,12 @@ pub struct JpegFrame {
     pub height: u32,
     /// Which encoder produced this frame.
     pub encoder_kind: EncoderKind,
+    /// Orientation the frame was rendered with, so readers can detect
+    /// frames encoded before an orientation change.
+    pub orientation: Orientation,
+    /// Raw `FrameBuffer` sequence this frame was encoded from, or 0 when
+    /// there is no raw source (a frame seeded from a previous session).
+    pub source_sequence: u64,
 }
 
 /// Thread-safe buffer holding the latest JPEG frame for a camera.
@@ -38,6 +49,8 @@ pub struct JpegFrameBuffer {
     /// Monotonic counter incremented on each update, used for cache
     /// invalidation in the IPC layer.
     sequence: AtomicU64,
+    /// Delivery accounting for sessions where this is the source buffer.
+    delivery: Mutex<DeliveryTracker>,
 }
 
 impl JpegFrameBuffer {
@@ -45,6 +58,7 @@ impl JpegFrameBuffer {
         Self {
             frame: Mutex::new(None),
             sequence: AtomicU64::new(0),
+            delivery: Mutex::new(DeliveryTracker::new(1)),
         }
     }
 
@@ -59,10 +73,33 @@ impl JpegFrameBuffer {
         self.frame.lock().clone()
     }
 
+    /// Bytes of the JPEG held, or 0 before the first frame.
+    pub fn bytes(&self) -> usize {
+        self.frame.lock().as_ref().map_or(0, |f| f.jpeg_bytes.len())
+    }
+
     /// Monotonic sequence number — increases by 1 for each update.
     pub fn sequence(&self) -> u64 {
         self.sequence.load(Ordering::Relaxed)
     }
+
+    /// Report that `consumer` has used the frame with `sequence`.
+    pub fn record_consumed(&self, consumer: &'static str, sequence: u64) {
+        self.delivery
+            .lock()
+            .record_consumed(consumer, sequence, self.sequence());
+    }
+
+    /// Produced / consumed / overwritten-unseen counts per consumer.
+    pub fn delivery(&self) -> Vec<FrameDelivery> {
+        self.delivery.lock().snapshot(self.sequence())
+    }
+}
+
+impl Default for JpegFrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Tracks encoding performance metrics for a single camera session.
@@ -73,6 +110,12 @@ struct EncodingStats {
     total_encode_time_us: u64,
     /// Duration of the most recent encode operation.
     last_encode_us: u64,
+    /// Frames that went through sharpening or denoise.
+    frames_post_processed: u64,
+    /// Cumulative post-processing time across those frames.
+    total_post_process_us: u64,
+    /// Post-processing time of the most recent frame; 0 when it was off.
+    last_post_process_us: u64,
 }
 
 impl EncodingStats {
@@ -81,6 +124,9 @@ impl EncodingStats {
             frames_encoded: 0,
             total_encode_time_us: 0,
             last_encode_us: 0,
+            frames_post_processed: 0,
+            total_post_process_us: 0,
+            last_post_process_us: 0,
         }
     }
 
@@ -90,6 +136,15 @@ impl EncodingStats {
         self.last_encode_us = duration_us;
     }
 
+    /// Record a frame's post-processing time, or `None` when it was off.
+    fn record_post_process(&mut self, duration_us: Option<u64>) {
+        if let Some(duration_us) = duration_us {
+            self.frames_post_processed += 1;
+            self.total_post_process_us += duration_us;
+        }
+        self.last_post_process_us = duration_us.unwrap_or(0);
+    }
+
     /// Average encode time per frame in microseconds.
     fn avg_encode_us(&self) -> f64 {
         if self.frames_encoded == 0 {
@@ -97,6 +152,14 @@ impl EncodingStats {
         }
         self.total_encode_time_us as f64 / self.frames_encoded as f64
     }
+
+    /// Average post-processing time per processed frame in microseconds.
+    fn avg_post_process_us(&self) -> f64 {
+        if self.frames_post_processed == 0 {
+            return 0.0;
+        }
+        self.total_post_process_us as f64 / self.frames_post_processed as f64
+    }
 }
 
 /// Serialisable snapshot of encoding stats for IPC delivery.
@@ -113,6 +176,11 @@ pub struct EncodingSnapshot {
     pub avg_encode_ms: f64,
     /// Most recent encode time in milliseconds.
     pub last_encode_ms: f64,
+    /// Average sharpen/denoise time per processed frame in microseconds.
+    pub avg_post_process_us: f64,
+    /// Sharpen/denoise time of the most recent frame in microseconds; 0 when
+    /// post-processing is off.
+    pub last_post_process_us: u64,
 }
 
 /// Configuration for the encode worker.
@@ -122,6 +190,14 @@ pub struct WorkerConfig {
     /// Maximum pending frames in the channel before dropping.
     /// Keeps memory bounded and avoids encoding stale frames.
     pub channel_capacity: usize,
+    /// Orientation applied to each frame before encoding.
+    pub orientation: SharedOrientation,
+    /// Digital zoom crop applied after orientation.
+    pub crop: SharedCrop,
+    /// Sharpening and denoise applied after orientation.
+    pub post_processing: SharedPostProcessing,
+    /// Meter that rendering and encoding time is recorded against.
+    pub cpu: CpuMeter,
 }
 
 impl Default for WorkerConfig {
@@ -129,6 +205,10 @@ impl Default for WorkerConfig {
         Self {
             quality: 75,
             channel_capacity: 2,
+            orientation: SharedOrientation::default(),
+            crop: SharedCrop::default(),
+            post_processing: SharedPostProcessing::default(),
+            cpu: CpuMeter::default(),
         }
     }
 }
@@ -139,17 +219,18 @@ impl Default for WorkerConfig {
 /// Sending is non-blocking — if the channel is full, the frame is dropped.
 #[derive(Clone)]
 pub struct FrameSender {
-    tx: mpsc::SyncSender<Frame>,
+    tx: mpsc::SyncSender<(Frame, u64)>,
     drop_count: Arc<AtomicU64>,
 }
 
 impl FrameSender {
-    /// Send a raw RGB frame to the worker for encoding.
+    /// Send a raw RGB frame to the worker for encoding, tagged with its
+    /// `FrameBuffer` sequence number.
     ///
     /// Returns `true` if the frame was enqueued, `false` if it was dropped
     /// because the worker is busy (channel full).
-    pub fn send(&self, frame: Frame) -> bool {
-        match self.tx.try_send(frame) {
+    pub fn send(&self, frame: Frame, sequence: u64) -> bool {
+        match self.tx.try_send((frame, sequence)) {
             Ok(()) => true,
             Err(mpsc::TrySendError::Full(_)) => {
                 self.drop_count.fetch_add(1, Ordering::Relaxed);
@@ -188,7 +269,7 @@ impl EncodeWorker {
     /// Returns `(worker, sender)` — the sender should be given to the capture
     /// callback; the worker owns the JPEG output buffer.
     pub fn spawn(config: WorkerConfig) -> (Self, FrameSender) {
-        let (tx, rx) = mpsc::sync_channel::<Frame>(config.channel_capacity);
+        let (tx, rx) = mpsc::sync_channel::<(Frame, u64)>(config.channel_capacity);
         let jpeg_buffer = Arc::new(JpegFrameBuffer::new());
         let running = Arc::new(AtomicBool::new(true));
         let encoder_kind = Arc::new(Mutex::new(EncoderKind::CpuFallback));
@@ -210,7 +291,11 @@ impl EncodeWorker {
                         &running,
                         &encoder_kind,
                         &stats,
+                        &config.orientation,
+                        &config.crop,
+                        &config.post_processing,
                         config.quality,
+                        &config.cpu,
                     );
                 })
                 .expect("failed to spawn encode worker thread")
@@ -249,6 +334,8 @@ impl EncodeWorker {
             frames_dropped: self.drop_count.load(Ordering::Relaxed),
             avg_encode_ms: stats.avg_encode_us() / 1000.0,
             last_encode_ms: stats.last_encode_us as f64 / 1000.0,
+            avg_post_process_us: stats.avg_post_process_us(),
+            last_post_process_us: stats.last_post_process_us,
         }
     }
 
@@ -261,13 +348,18 @@ impl EncodeWorker {
     }
 
     /// Worker thread main loop.
+    #[allow(clippy::too_many_arguments)]
     fn run(
-        rx: mpsc::Receiver<Frame>,
+        rx: mpsc::Receiver<(Frame, u64)>,
         jpeg_buffer: &JpegFrameBuffer,
         running: &AtomicBool,
         encoder_kind: &Mutex<EncoderKind>,
         stats: &Mutex<EncodingStats>,
+        orientation: &Mutex<Orientation>,
+        crop: &Mutex<CropRect>,
+        post_processing: &Mutex<PostProcessing>,
         quality: u8,
+        cpu: &CpuMeter,
     ) {
         info!("encode worker started (quality={quality})");
 
@@ -289,10 +381,11 @@ impl EncodeWorker {
         #[cfg(not(target_os = "windows"))]
         let mf_encoder = None::<()>;
 
-        // Will be initialised on the first frame (we need width/height)
+        // Will be initialised on the first frame (we need width/height), and
+        // re-initialised whenever the rendered size changes (e.g. rotation)
         #[allow(unused_mut)]
         let mut mf_encoder = mf_encoder;
-        let mut encoder_initialised = false;
+        let mut encoder_size: Option<(u32, u32)> = None;
 
         while running.load(Ordering::Relaxed) {
             // Block up to 100ms waiting for a frame, then recheck `running`
@@ -306,11 +399,33 @@ impl EncodeWorker {
             };
 
             // Drain any stale frames — only encode the freshest
-            let frame = drain_to_latest(frame, &rx);
+            let (frame, source_sequence) = drain_to_latest(frame, &rx);
+            let timer = CpuTimer::start();
+
+            let frame_orientation = *orientation.lock();
+            let rendered = render::render_frame(&frame, frame_orientation);
+            let mut frame = Frame {
+                data: rendered.data,
+                width: rendered.width,
+                height: rendered.height,
+                timestamp_us: frame.timestamp_us,
+                device_timestamp_us: frame.device_timestamp_us,
+            };
+            if let Some(cropped) = crop.lock().apply(&frame.data, frame.width, frame.height) {
+                frame.data = cropped;
+            }
+
+            let processing = *post_processing.lock();
+            let post_process_us = (!processing.is_default()).then(|| {
+                let t0 = Instant::now();
+                processing.apply(&mut frame.data, frame.width, frame.height);
+                t0.elapsed().as_micros() as u64
+            });
+            stats.lock().record_post_process(post_process_us);
 
-            // Lazily initialise the MF encoder on the first frame
+            // Lazily initialise the MF encoder for the rendered frame size
             #[cfg(target_os = "windows")]
-            if !encoder_initialised {
+            if encoder_size != Some((frame.width, frame.height)) {
                 match crate::preview::mf_jpeg::encoder::JpegEncoder::new(
                     frame.width,
                     frame.height,
@@ -329,15 +444,16 @@ impl EncodeWorker {
                     None => {
                         info!("encode worker: no MF encoder, using CPU fallback");
                         *encoder_kind.lock() = EncoderKind::CpuFallback;
+                        mf_encoder = None;
                     }
                 }
-                encoder_initialised = true;
+                encoder_size = Some((frame.width, frame.height));
             }
 
             #[cfg(not(target_os = "windows"))]
-            if !encoder_initialised {
+            if encoder_size.is_none() {
                 *encoder_kind.lock() = EncoderKind::CpuFallback;
-                encoder_initialised = true;
+                encoder_size = Some((frame.width, frame.height));
                 let _ = &mf_encoder; // suppress unused warning
             }
 
@@ -347,12 +463,15 @@ impl EncodeWorker {
             let encode_us = t0.elapsed().as_micros() as u64;
 
             stats.lock().record_encode(encode_us);
+            cpu.record(CpuWork::Compress, timer.elapsed());
 
             jpeg_buffer.update(JpegFrame {
                 jpeg_bytes,
                 width: frame.width,
                 height: frame.height,
                 encoder_kind: kind,
+                orientation: frame_orientation,
+                source_sequence,
             });
         }
 
@@ -370,7 +489,7 @@ impl EncodeWorker {
 /// When the encoder is slower than the capture rate, multiple frames
 /// can queue up. We only care about the latest one — encoding stale
 /// frames wastes CPU/GPU time.
-fn drain_to_latest(initial: Frame, rx: &mpsc::Receiver<Frame>) -> Frame {
+fn drain_to_latest<T>(initial: T, rx: &mpsc::Receiver<T>) -> T {
     let mut latest = initial;
     loop {
         match rx.try_recv() {
@@ -419,6 +538,7 @@ mod tests {
             width,
             height,
             timestamp_us: 1000,
+            device_timestamp_us: 1000,
         }
     }
 
@@ -437,6 +557,7 @@ mod tests {
             width,
             height,
             timestamp_us: 1000,
+            device_timestamp_us: 1000,
         }
     }
 
@@ -455,6 +576,8 @@ mod tests {
             width: 640,
             height: 480,
             encoder_kind: EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
         });
 
         let latest = buf.latest().unwrap();
@@ -471,12 +594,16 @@ mod tests {
             width: 10,
             height: 10,
             encoder_kind: EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
         });
         buf.update(JpegFrame {
             jpeg_bytes: vec![2],
             width: 20,
             height: 20,
             encoder_kind: EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
         });
 
         let latest = buf.latest().unwrap();
@@ -493,6 +620,8 @@ mod tests {
             width: 10,
             height: 10,
             encoder_kind: EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
         });
 
         let a = buf.latest().unwrap();
@@ -500,7 +629,7 @@ mod tests {
         assert!(Arc::ptr_eq(&a, &b));
     }
 
-    fn make_sender(tx: mpsc::SyncSender<Frame>) -> FrameSender {
+    fn make_sender(tx: mpsc::SyncSender<(Frame, u64)>) -> FrameSender {
         FrameSender {
             tx,
             drop_count: Arc::new(AtomicU64::new(0)),
@@ -509,36 +638,36 @@ mod tests {
 
     #[test]
     fn frame_sender_send_returns_true_when_space() {
-        let (tx, _rx) = mpsc::sync_channel::<Frame>(2);
+        let (tx, _rx) = mpsc::sync_channel::<(Frame, u64)>(2);
         let sender = make_sender(tx);
-        assert!(sender.send(make_frame(10, 10, 128)));
+        assert!(sender.send(make_frame(10, 10, 128), 1));
     }
 
     #[test]
     fn frame_sender_send_returns_false_when_full() {
-        let (tx, _rx) = mpsc::sync_channel::<Frame>(1);
+        let (tx, _rx) = mpsc::sync_channel::<(Frame, u64)>(1);
         let sender = make_sender(tx);
-        assert!(sender.send(make_frame(10, 10, 1)));
+        assert!(sender.send(make_frame(10, 10, 1), 1));
         // Channel is full — second send should return false
-        assert!(!sender.send(make_frame(10, 10, 2)));
+        assert!(!sender.send(make_frame(10, 10, 2), 2));
     }
 
     #[test]
     fn frame_sender_send_returns_false_when_disconnected() {
-        let (tx, rx) = mpsc::sync_channel::<Frame>(2);
+        let (tx, rx) = mpsc::sync_channel::<(Frame, u64)>(2);
         let sender = make_sender(tx);
         drop(rx);
-        assert!(!sender.send(make_frame(10, 10, 128)));
+        assert!(!sender.send(make_frame(10, 10, 128), 1));
     }
 
     #[test]
     fn frame_sender_tracks_drop_count() {
-        let (tx, _rx) = mpsc::sync_channel::<Frame>(1);
+        let (tx, _rx) = mpsc::sync_channel::<(Frame, u64)>(1);
         let sender = make_sender(tx);
-        assert!(sender.send(make_frame(10, 10, 1)));
+        assert!(sender.send(make_frame(10, 10, 1), 1));
         // Channel is full — drop counter should increment
-        assert!(!sender.send(make_frame(10, 10, 2)));
-        assert!(!sender.send(make_frame(10, 10, 3)));
+        assert!(!sender.send(make_frame(10, 10, 2), 2));
+        assert!(!sender.send(make_frame(10, 10, 3), 3));
         assert_eq!(sender.drop_count.load(Ordering::Relaxed), 2);
     }
 
@@ -574,10 +703,11 @@ mod tests {
         let (mut worker, sender) = EncodeWorker::spawn(WorkerConfig {
             quality: 75,
             channel_capacity: 2,
+            ..WorkerConfig::default()
         });
 
         let frame = make_rgb_frame(64, 64);
-        assert!(sender.send(frame));
+        assert!(sender.send(frame, 7));
 
         // Wait for the worker to encode the frame
         let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
@@ -593,6 +723,33 @@ mod tests {
         assert_eq!(jpeg_frame.jpeg_bytes[1], 0xD8, "missing JPEG SOI");
         assert_eq!(jpeg_frame.width, 64);
         assert_eq!(jpeg_frame.height, 64);
+        assert_eq!(jpeg_frame.source_sequence, 7);
+
+        worker.stop();
+    }
+
+    #[test]
+    fn encode_worker_applies_orientation_before_encoding() {
+        let config = WorkerConfig::default();
+        *config.orientation.lock() = Orientation {
+            rotation: render::Rotation::Cw90,
+            mirror: false,
+        };
+        let (mut worker, sender) = EncodeWorker::spawn(config);
+
+        assert!(sender.send(make_rgb_frame(64, 32), 1));
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while worker.jpeg_buffer().latest().is_none() {
+            if std::time::Instant::now() > deadline {
+                panic!("encode worker did not produce a frame within 5s");
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let jpeg_frame = worker.jpeg_buffer().latest().unwrap();
+        assert_eq!((jpeg_frame.width, jpeg_frame.height), (32, 64));
+        assert_eq!(jpeg_frame.orientation.rotation, render::Rotation::Cw90);
 
         worker.stop();
     }
@@ -602,12 +759,13 @@ mod tests {
         let (mut worker, sender) = EncodeWorker::spawn(WorkerConfig {
             quality: 75,
             channel_capacity: 4,
+            ..WorkerConfig::default()
         });
 
         // Send frames with a delay so the worker processes each one
         // individually rather than draining them all at once.
-        for _ in 0..3 {
-            sender.send(make_rgb_frame(32, 32));
+        for seq in 1..=3 {
+            sender.send(make_rgb_frame(32, 32), seq);
             // Wait for the worker to process before sending the next
             let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
             let target_seq = worker.jpeg_buffer().sequence() + 1;
@@ -648,6 +806,23 @@ mod tests {
         assert!((stats.avg_encode_us() - 2000.0).abs() < 0.1);
     }
 
+    #[test]
+    fn encoding_stats_averages_post_processing_over_processed_frames() {
+        let mut stats = EncodingStats::new();
+        assert_eq!(stats.avg_post_process_us(), 0.0);
+
+        stats.record_post_process(Some(400));
+        stats.record_post_process(None);
+        stats.record_post_process(Some(600));
+        assert_eq!(stats.last_post_process_us, 600);
+        assert!((stats.avg_post_process_us() - 500.0).abs() < 0.1);
+
+        // Turning it off shows 0 for the latest frame but keeps the average
+        stats.record_post_process(None);
+        assert_eq!(stats.last_post_process_us, 0);
+        assert!((stats.avg_post_process_us() - 500.0).abs() < 0.1);
+    }
+
     #[test]
     fn encoding_snapshot_serialises_to_camel_case() {
         let snap = EncodingSnapshot {
@@ -656,6 +831,8 @@ mod tests {
             frames_dropped: 5,
             avg_encode_ms: 1.5,
             last_encode_ms: 1.2,
+            avg_post_process_us: 850.0,
+            last_post_process_us: 900,
         };
         let json = serde_json::to_value(&snap).unwrap();
         assert!(json["encoderKind"].is_string());
@@ -663,6 +840,8 @@ mod tests {
         assert_eq!(json["framesDropped"], 5);
         assert!(json["avgEncodeMs"].is_number());
         assert!(json["lastEncodeMs"].is_number());
+        assert_eq!(json["avgPostProcessUs"], 850.0);
+        assert_eq!(json["lastPostProcessUs"], 900);
     }
 
     #[test]
@@ -670,6 +849,7 @@ mod tests {
         let (mut worker, sender) = EncodeWorker::spawn(WorkerConfig {
             quality: 75,
             channel_capacity: 2,
+            ..WorkerConfig::default()
         });
 
         // Initial snapshot — no frames yet
@@ -678,7 +858,7 @@ mod tests {
         assert_eq!(snap.frames_dropped, 0);
 
         // Send a frame and wait for it to be encoded
-        sender.send(make_rgb_frame(32, 32));
+        sender.send(make_rgb_frame(32, 32), 1);
         let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
         while worker.jpeg_buffer().sequence() < 1 {
             if std::time::Instant::now() > deadline {
@@ -0,0 +1,223 @@
+// Minimal 5x7 bitmap font and RGB24 canvas for generated frames.
+//
+// Covers upper-case letters, digits and a little punctuation; lower case is
+// drawn as upper case and anything else as '?'. Glyphs are scaled up by
+// whole pixels, so output is identical on every machine.
+
+/// Glyph size in font pixels.
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// Blank font pixels between adjacent glyphs.
+const SPACING: u32 = 1;
+
+/// Rows of a glyph, top first; bit 4 is the leftmost column.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        ' ' => [0x00; 7],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// Width in pixels of `text` drawn at `scale`, without trailing spacing.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    let chars = text.chars().count() as u32;
+    (chars * (GLYPH_WIDTH + SPACING)).saturating_sub(SPACING) * scale
+}
+
+/// An RGB24 image being drawn into, top row first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Canvas {
+    /// A canvas filled with `background`.
+    pub fn new(width: u32, height: u32, background: [u8; 3]) -> Self {
+        let data = background.repeat((width * height) as usize);
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Fill a rectangle, clipped to the canvas.
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, colour: [u8; 3]) {
+        let x_end = (x + width).min(self.width);
+        let y_end = (y + height).min(self.height);
+        for row in y.min(y_end)..y_end {
+            for col in x.min(x_end)..x_end {
+                let i = ((row * self.width + col) * 3) as usize;
+                self.data[i..i + 3].copy_from_slice(&colour);
+            }
+        }
+    }
+
+    /// Draw `text` with its top-left corner at `(x, y)`, each font pixel
+    /// `scale` pixels square. Clipped to the canvas.
+    pub fn draw_text(&mut self, x: u32, y: u32, scale: u32, text: &str, colour: [u8; 3]) {
+        let advance = (GLYPH_WIDTH + SPACING) * scale;
+        for (i, c) in text.chars().enumerate() {
+            let left = x + i as u32 * advance;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (0x10 >> col) != 0 {
+                        self.fill_rect(
+                            left + col * scale,
+                            y + row as u32 * scale,
+                            scale,
+                            scale,
+                            colour,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw `text` horizontally centred with its top edge at `y`.
+    pub fn draw_text_centred(&mut self, y: u32, scale: u32, text: &str, colour: [u8; 3]) {
+        let x = self.width.saturating_sub(text_width(text, scale)) / 2;
+        self.draw_text(x, y, scale, text, colour);
+    }
+
+    /// Colour of the pixel at `(x, y)`.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let i = ((y * self.width + x) * 3) as usize;
+        [self.data[i], self.data[i + 1], self.data[i + 2]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: [u8; 3] = [0, 0, 0];
+    const WHITE: [u8; 3] = [255, 255, 255];
+
+    /// Render `text` at scale 1 as rows of '#' and '.'.
+    fn ascii(text: &str) -> Vec<String> {
+        let width = text_width(text, 1);
+        let mut canvas = Canvas::new(width, GLYPH_HEIGHT, BLACK);
+        canvas.draw_text(0, 0, 1, text, WHITE);
+        (0..GLYPH_HEIGHT)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        if canvas.pixel(x, y) == WHITE {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_glyph_bits_left_to_right() {
+        assert_eq!(
+            ascii("L1"),
+            [
+                "#.......#..",
+                "#......##..",
+                "#.......#..",
+                "#.......#..",
+                "#.......#..",
+                "#.......#..",
+                "#####..###.",
+            ]
+        );
+    }
+
+    #[test]
+    fn lower_case_matches_upper_case_and_unknowns_are_marked() {
+        assert_eq!(ascii("cam"), ascii("CAM"));
+        assert_eq!(ascii("é"), ascii("?"));
+    }
+
+    #[test]
+    fn text_width_excludes_trailing_spacing() {
+        assert_eq!(text_width("", 3), 0);
+        assert_eq!(text_width("A", 1), 5);
+        assert_eq!(text_width("AB", 2), 22);
+    }
+
+    #[test]
+    fn scaling_repeats_each_font_pixel() {
+        let mut canvas = Canvas::new(10, 14, BLACK);
+        canvas.draw_text(0, 0, 2, "-", WHITE);
+        // The dash is row 3 of the glyph, so rows 6 and 7 at scale 2
+        assert_eq!(canvas.pixel(0, 5), BLACK);
+        assert_eq!(canvas.pixel(0, 6), WHITE);
+        assert_eq!(canvas.pixel(9, 7), WHITE);
+        assert_eq!(canvas.pixel(9, 8), BLACK);
+    }
+
+    #[test]
+    fn drawing_past_the_edge_is_clipped() {
+        let mut canvas = Canvas::new(8, 4, BLACK);
+        canvas.draw_text(4, 1, 3, "W", WHITE);
+        canvas.fill_rect(6, 3, 10, 10, WHITE);
+        assert_eq!(canvas.data.len(), 8 * 4 * 3);
+        assert_eq!(canvas.pixel(7, 3), WHITE);
+    }
+
+    #[test]
+    fn centred_text_has_equal_margins() {
+        let mut canvas = Canvas::new(21, GLYPH_HEIGHT, BLACK);
+        canvas.draw_text_centred(0, 1, "HH", WHITE);
+        // "HH" is 11 pixels wide, leaving 5 on each side
+        assert_eq!(canvas.pixel(4, 0), BLACK);
+        assert_eq!(canvas.pixel(5, 0), WHITE);
+        assert_eq!(canvas.pixel(15, 0), WHITE);
+        assert_eq!(canvas.pixel(16, 0), BLACK);
+    }
+}
@@ -12,6 +12,8 @@ use bytemuck::{Pod, Zeroable};
 use parking_lot::RwLock;
 use tracing::{info, warn};
 
+use super::colour::{Coefficients, ColourSpace};
+
 /// Information about a GPU adapter available on the system.
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,13 +29,20 @@ pub struct GpuAdapterInfo {
 }
 
 /// Uniform parameters passed to compute shaders via a uniform buffer.
+///
+/// The YUV shaders read the fixed-point colour [`Coefficients`] after the
+/// size; the BGR shader declares only the size and ignores the rest.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct ConvertParams {
     width: u32,
     height: u32,
-    _pad0: u32,
-    _pad1: u32,
+    y_offset: i32,
+    y_gain: i32,
+    rv: i32,
+    gu: i32,
+    gv: i32,
+    bu: i32,
 }
 
 /// Holds the WGPU device, queue, and pre-compiled pipelines for colour conversion.
@@ -52,14 +61,20 @@ pub struct GpuContext {
 /// WGSL compute shader for NV12 -> RGBA conversion.
 ///
 /// NV12 is a 4:2:0 planar format: full-resolution Y plane followed by
-/// interleaved UV at half resolution in both dimensions. Uses BT.601
-/// coefficients matching the existing CPU path.
+/// interleaved UV at half resolution in both dimensions. The colour
+/// coefficients come from the uniform, matching the CPU path.
 ///
 /// Output is RGBA (1 u32 per pixel) to avoid byte-alignment issues.
 const NV12_SHADER: &str = r#"
 struct Params {
     width: u32,
     height: u32,
+    y_offset: i32,
+    y_gain: i32,
+    rv: i32,
+    gu: i32,
+    gv: i32,
+    bu: i32,
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
@@ -72,6 +87,15 @@ fn read_byte(offset: u32) -> u32 {
     return (input[word_idx] >> (byte_idx * 8u)) & 0xFFu;
 }
 
+// Fixed-point YUV -> packed RGBA, 16 fractional bits, rounded
+fn yuv_to_rgba(y: i32, u: i32, v: i32) -> u32 {
+    let luma = (y - params.y_offset) * params.y_gain + 32768;
+    let r = u32(clamp((luma + params.rv * v) >> 16u, 0, 255));
+    let g = u32(clamp((luma - params.gu * u - params.gv * v) >> 16u, 0, 255));
+    let b = u32(clamp((luma + params.bu * u) >> 16u, 0, 255));
+    return r | (g << 8u) | (b << 16u) | (255u << 24u);
+}
+
 @compute @workgroup_size(16, 16)
 fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let col = gid.x;
@@ -87,12 +111,8 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let u_val = i32(read_byte(uv_offset + uv_row * params.width + uv_col)) - 128;
     let v_val = i32(read_byte(uv_offset + uv_row * params.width + uv_col + 1u)) - 128;
 
-    let r = u32(clamp((y_val * 256 + 359 * v_val) >> 8, 0, 255));
-    let g = u32(clamp((y_val * 256 - 88 * u_val - 183 * v_val) >> 8, 0, 255));
-    let b = u32(clamp((y_val * 256 + 454 * u_val) >> 8, 0, 255));
-
     let pixel_idx = row * params.width + col;
-    output[pixel_idx] = r | (g << 8u) | (b << 16u) | (255u << 24u);
+    output[pixel_idx] = yuv_to_rgba(y_val, u_val, v_val);
 }
 "#;
 
@@ -104,6 +124,12 @@ const YUY2_SHADER: &str = r#"
 struct Params {
     width: u32,
     height: u32,
+    y_offset: i32,
+    y_gain: i32,
+    rv: i32,
+    gu: i32,
+    gv: i32,
+    bu: i32,
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
@@ -116,6 +142,15 @@ fn read_byte(offset: u32) -> u32 {
     return (input[word_idx] >> (byte_idx * 8u)) & 0xFFu;
 }
 
+// Fixed-point YUV -> packed RGBA, 16 fractional bits, rounded
+fn yuv_to_rgba(y: i32, u: i32, v: i32) -> u32 {
+    let luma = (y - params.y_offset) * params.y_gain + 32768;
+    let r = u32(clamp((luma + params.rv * v) >> 16u, 0, 255));
+    let g = u32(clamp((luma - params.gu * u - params.gv * v) >> 16u, 0, 255));
+    let b = u32(clamp((luma + params.bu * u) >> 16u, 0, 255));
+    return r | (g << 8u) | (b << 16u) | (255u << 24u);
+}
+
 @compute @workgroup_size(256)
 fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let macro_idx = gid.x;
@@ -130,17 +165,9 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let y1 = i32(read_byte(src + 2u));
     let v_val = i32(read_byte(src + 3u)) - 128;
 
-    let r0 = u32(clamp((y0 * 256 + 359 * v_val) >> 8, 0, 255));
-    let g0 = u32(clamp((y0 * 256 - 88 * u_val - 183 * v_val) >> 8, 0, 255));
-    let b0 = u32(clamp((y0 * 256 + 454 * u_val) >> 8, 0, 255));
-
-    let r1 = u32(clamp((y1 * 256 + 359 * v_val) >> 8, 0, 255));
-    let g1 = u32(clamp((y1 * 256 - 88 * u_val - 183 * v_val) >> 8, 0, 255));
-    let b1 = u32(clamp((y1 * 256 + 454 * u_val) >> 8, 0, 255));
-
     let out_idx = macro_idx * 2u;
-    output[out_idx] = r0 | (g0 << 8u) | (b0 << 16u) | (255u << 24u);
-    output[out_idx + 1u] = r1 | (g1 << 8u) | (b1 << 16u) | (255u << 24u);
+    output[out_idx] = yuv_to_rgba(y0, u_val, v_val);
+    output[out_idx + 1u] = yuv_to_rgba(y1, u_val, v_val);
 }
 "#;
 
@@ -307,40 +334,99 @@ impl GpuContext {
     }
 
     /// Convert NV12 frame data to RGB24 using the GPU.
-    pub fn convert_nv12_to_rgb(&self, data: &[u8], width: usize, height: usize) -> Option<Vec<u8>> {
-        self.run_conversion(&self.nv12_pipeline, data, width, height)
+    pub fn convert_nv12_to_rgb(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        colour: ColourSpace,
+    ) -> Option<Vec<u8>> {
+        let mut rgb = Vec::new();
+        self.convert_into(PixelFormat::Nv12, data, width, height, colour, &mut rgb)?;
+        Some(rgb)
     }
 
     /// Convert YUY2 frame data to RGB24 using the GPU.
-    pub fn convert_yuy2_to_rgb(&self, data: &[u8], width: usize, height: usize) -> Option<Vec<u8>> {
-        self.run_conversion(&self.yuy2_pipeline, data, width, height)
+    pub fn convert_yuy2_to_rgb(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        colour: ColourSpace,
+    ) -> Option<Vec<u8>> {
+        let mut rgb = Vec::new();
+        self.convert_into(PixelFormat::Yuy2, data, width, height, colour, &mut rgb)?;
+        Some(rgb)
     }
 
     /// Convert BGR24 bottom-up frame data to RGB24 top-down using the GPU.
     pub fn convert_bgr_to_rgb(&self, data: &[u8], width: usize, height: usize) -> Option<Vec<u8>> {
-        self.run_conversion(&self.bgr_pipeline, data, width, height)
+        // The colour space only matters to the YUV shaders
+        let mut rgb = Vec::new();
+        self.convert_into(
+            PixelFormat::Bgr24BottomUp,
+            data,
+            width,
+            height,
+            ColourSpace::BT601_FULL,
+            &mut rgb,
+        )?;
+        Some(rgb)
+    }
+
+    /// Convert a frame in `format` to RGB24 into `rgb`, reusing its
+    /// allocation. `None` if the GPU work failed.
+    pub fn convert_into(
+        &self,
+        format: PixelFormat,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        colour: ColourSpace,
+        rgb: &mut Vec<u8>,
+    ) -> Option<()> {
+        let pipeline = match format {
+            PixelFormat::Nv12 => &self.nv12_pipeline,
+            PixelFormat::Yuy2 => &self.yuy2_pipeline,
+            PixelFormat::Bgr24BottomUp => &self.bgr_pipeline,
+        };
+        self.run_conversion(pipeline, data, width, height, colour, rgb)
     }
 
     /// Run a colour conversion compute shader.
     ///
     /// The shader outputs RGBA (1 u32 per pixel). This method strips the
-    /// alpha channel on readback, returning RGB24 data.
+    /// alpha channel on readback, writing RGB24 data into `rgb`.
     fn run_conversion(
         &self,
         pipeline: &wgpu::ComputePipeline,
         input_data: &[u8],
         width: usize,
         height: usize,
-    ) -> Option<Vec<u8>> {
+        colour: ColourSpace,
+        rgb: &mut Vec<u8>,
+    ) -> Option<()> {
         use wgpu::util::DeviceExt;
 
         let pixel_count = width * height;
 
+        let Coefficients {
+            y_offset,
+            y_gain,
+            rv,
+            gu,
+            gv,
+            bu,
+        } = colour.coefficients();
         let params = ConvertParams {
             width: width as u32,
             height: height as u32,
-            _pad0: 0,
-            _pad1: 0,
+            y_offset,
+            y_gain,
+            rv,
+            gu,
+            gv,
+            bu,
         };
 
         let uniform_buf = self
@@ -440,7 +526,8 @@ impl GpuContext {
 
         // Convert RGBA -> RGB by stripping the alpha channel
         let rgba = &mapped[..];
-        let mut rgb = Vec::with_capacity(pixel_count * 3);
+        rgb.clear();
+        rgb.reserve(pixel_count * 3);
         for pixel in rgba.chunks_exact(4) {
             rgb.push(pixel[0]); // R
             rgb.push(pixel[1]); // G
@@ -450,7 +537,7 @@ impl GpuContext {
         drop(mapped);
         staging_buf.unmap();
 
-        Some(rgb)
+        Some(())
     }
 }
 
@@ -551,23 +638,40 @@ pub enum PixelFormat {
 
 /// Convert a frame using GPU if available, otherwise fall back to CPU.
 ///
-/// This is the main entry point called from the capture callback.
+/// This is the main entry point called from the capture callback. RGB24
+/// rows must be tightly packed and bottom-up; the callback converts other
+/// layouts on the CPU itself. `colour` is how YUV formats are decoded.
 pub fn convert_frame(
     gpu: Option<&Arc<GpuContext>>,
     format: PixelFormat,
     data: &[u8],
     width: usize,
     height: usize,
+    colour: ColourSpace,
 ) -> Vec<u8> {
+    let mut rgb = Vec::new();
+    convert_frame_into(gpu, format, data, width, height, colour, &mut rgb);
+    rgb
+}
+
+/// [`convert_frame`] into `rgb`, reusing its allocation; the capture
+/// callback passes a buffer from its frame buffer's pool.
+pub fn convert_frame_into(
+    gpu: Option<&Arc<GpuContext>>,
+    format: PixelFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    colour: ColourSpace,
+    rgb: &mut Vec<u8>,
+) {
     // Try GPU path first
     if let Some(ctx) = gpu {
-        let result = match format {
-            PixelFormat::Nv12 => ctx.convert_nv12_to_rgb(data, width, height),
-            PixelFormat::Yuy2 => ctx.convert_yuy2_to_rgb(data, width, height),
-            PixelFormat::Bgr24BottomUp => ctx.convert_bgr_to_rgb(data, width, height),
-        };
-        if let Some(rgb) = result {
-            return rgb;
+        if ctx
+            .convert_into(format, data, width, height, colour, rgb)
+            .is_some()
+        {
+            return;
         }
         // GPU conversion failed — fall through to CPU
         warn!(
@@ -578,10 +682,14 @@ pub fn convert_frame(
 
     // CPU fallback
     match format {
-        PixelFormat::Nv12 => super::graph::convert_nv12_to_rgb(data, width, height),
-        PixelFormat::Yuy2 => super::graph::convert_yuy2_to_rgb(data, width, height),
+        PixelFormat::Nv12 => {
+            super::graph::convert_nv12_to_rgb_into(data, width, height, colour, rgb);
+        }
+        PixelFormat::Yuy2 => {
+            super::graph::convert_yuy2_to_rgb_into(data, width, height, colour, rgb);
+        }
         PixelFormat::Bgr24BottomUp => {
-            super::graph::convert_bgr_bottom_up_to_rgb(data, width, height)
+            super::graph::convert_bgr_bottom_up_to_rgb_into(data, width, height, width * 3, rgb);
         }
     }
 }
@@ -630,7 +738,14 @@ mod tests {
         // 2x2 BGR bottom-up: blue pixels in row 0, red pixels in row 1
         let bgr = vec![255u8, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255];
 
-        let rgb = convert_frame(None, PixelFormat::Bgr24BottomUp, &bgr, 2, 2);
+        let rgb = convert_frame(
+            None,
+            PixelFormat::Bgr24BottomUp,
+            &bgr,
+            2,
+            2,
+            ColourSpace::BT601_FULL,
+        );
 
         // Row 0 of output = row 1 of input (flipped), BGR(0,0,255) -> RGB(255,0,0)
         assert_eq!(rgb[0], 255); // R
@@ -642,7 +757,14 @@ mod tests {
     fn convert_frame_yuy2_cpu_fallback() {
         // Single macro-pixel: Y0=128, U=128, Y1=128, V=128 -> mid-grey
         let yuy2 = vec![128u8, 128, 128, 128];
-        let rgb = convert_frame(None, PixelFormat::Yuy2, &yuy2, 2, 1);
+        let rgb = convert_frame(
+            None,
+            PixelFormat::Yuy2,
+            &yuy2,
+            2,
+            1,
+            ColourSpace::BT601_FULL,
+        );
         assert_eq!(rgb.len(), 6);
         // With Y=128, U=0 (128-128), V=0 (128-128) -> R=128, G=128, B=128
         assert_eq!(rgb[0], 128);
@@ -655,13 +777,36 @@ mod tests {
         // 2x2 NV12: all luma=128, U=128, V=128 -> mid-grey
         let mut nv12 = vec![128u8; 4]; // Y plane
         nv12.extend_from_slice(&[128, 128]); // UV plane
-        let rgb = convert_frame(None, PixelFormat::Nv12, &nv12, 2, 2);
+        let rgb = convert_frame(
+            None,
+            PixelFormat::Nv12,
+            &nv12,
+            2,
+            2,
+            ColourSpace::BT601_FULL,
+        );
         assert_eq!(rgb.len(), 12);
         assert_eq!(rgb[0], 128);
         assert_eq!(rgb[1], 128);
         assert_eq!(rgb[2], 128);
     }
 
+    #[test]
+    fn convert_frame_cpu_fallback_honours_the_colour_space() {
+        // Limited-range white and black stretch to the full range
+        let nv12 = vec![235u8, 235, 16, 16, 128, 128];
+        let rgb = convert_frame(
+            None,
+            PixelFormat::Nv12,
+            &nv12,
+            2,
+            2,
+            ColourSpace::BT709_LIMITED,
+        );
+        assert_eq!(&rgb[..3], [255, 255, 255]);
+        assert_eq!(&rgb[6..9], [0, 0, 0]);
+    }
+
     #[test]
     fn pad_to_alignment_pads_correctly() {
         let data = vec![1u8, 2, 3];
@@ -3,21 +3,30 @@
 // Builds a Source -> SampleGrabber -> NullRenderer pipeline and delivers
 // raw RGB24 frames via a callback into the shared FrameBuffer.
 
+use std::time::Duration;
+
+use crate::diagnostics::accounting::{record_offloaded, CpuTimer};
+use crate::preview::colour::ColourSpace;
+
 #[cfg(target_os = "windows")]
 pub mod directshow {
     use parking_lot::Mutex;
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
+    use std::time::Instant;
 
     use tracing::{debug, error, info, warn};
     use windows::core::{Interface, GUID, HRESULT};
     use windows::Win32::Media::DirectShow::{
-        IAMStreamConfig, IBaseFilter, ICreateDevEnum, IFilterGraph2, IGraphBuilder, IMediaControl,
-        IMediaFilter, IPin,
+        IAMCrossbar, IAMStreamConfig, IBaseFilter, ICaptureGraphBuilder2, ICreateDevEnum,
+        IFilterGraph2, IGraphBuilder, IMediaControl, IMediaFilter, IPin, AMPROPERTY_PIN_CATEGORY,
+        PIN_INFO,
     };
+    use windows::Win32::Media::KernelStreaming::IKsPropertySet;
     use windows::Win32::Media::MediaFoundation::VIDEOINFOHEADER;
     use windows::Win32::Media::MediaFoundation::{
-        CLSID_SystemDeviceEnum, CLSID_VideoInputDeviceCategory,
+        AMPROPSETID_Pin, CLSID_CaptureGraphBuilder2, CLSID_SystemDeviceEnum,
+        CLSID_VideoInputDeviceCategory, LOOK_UPSTREAM_ONLY, PIN_CATEGORY_CAPTURE,
     };
     use windows::Win32::System::Com::StructuredStorage::IPropertyBag;
     use windows::Win32::System::Com::{
@@ -26,10 +35,29 @@ pub mod directshow {
     };
     use windows::Win32::System::Variant::VARIANT;
 
-    use crate::diagnostics::stats::DiagnosticStats;
-    use crate::preview::capture::{Frame, FrameBuffer};
+    use crate::camera::platform::stream_caps::{frame_interval_to_fps, parse_stream_config_caps};
+    use crate::camera::types::{abbreviate_path, same_device_path};
+    use crate::diagnostics::accounting::{CpuMeter, CpuTimer, CpuWork};
+    use crate::diagnostics::content::{ContentHealth, ContentMonitor};
+    use crate::diagnostics::stats::{DiagnosticStats, DropReason};
+    use crate::preview::capture::{Frame, FrameBuffer, INTERNAL_CAPTURE_ERROR};
+    use crate::preview::colour::{self, ColourHints, SharedColourSpace};
+    use crate::preview::com_object::{self, ComHandle, ComObject, SampleSink};
     use crate::preview::gpu::{self, GpuContext, PixelFormat};
-    use crate::preview::graph::is_obs_virtual_camera;
+    use crate::preview::mode::{
+        fourcc_name, parse_fourcc, select_capability, Capability, NegotiatedFormat, SessionMode,
+    };
+    use crate::preview::quirks;
+    use crate::preview::sources::{
+        choose_source, list_sources, Crossbar, CrossbarInput, OutputPin, SourcePreference,
+        SourceRoute, VideoSource,
+    };
+    use crate::preview::timestamp::MonotonicClock;
+
+    use super::{
+        convert_bgr_bottom_up_to_rgb_into, convert_bgr_top_down_to_rgb_into, convert_mjpg_to_rgb,
+        teardown_graph, TeardownGraph, TeardownStep,
+    };
 
     // --- Manually defined types not in windows-rs metadata ---
 
@@ -97,6 +125,9 @@ pub mod directshow {
     // MEDIASUBTYPE_NV12: {3231564E-0000-0010-8000-00AA00389B71}
     const MEDIASUBTYPE_NV12: GUID = GUID::from_u128(0x3231564E_0000_0010_8000_00AA00389B71);
 
+    // MEDIASUBTYPE_MJPG: {47504A4D-0000-0010-8000-00AA00389B71}
+    const MEDIASUBTYPE_MJPG: GUID = GUID::from_u128(0x47504A4D_0000_0010_8000_00AA00389B71);
+
     // FORMAT_VideoInfo: {05589F80-C356-11CE-BF01-00AA0055595A}
     const FORMAT_VIDEOINFO: GUID = GUID::from_u128(0x05589f80_c356_11ce_bf01_00aa0055595a);
 
@@ -219,14 +250,15 @@ pub mod directshow {
         buffer_cb: unsafe extern "system" fn(*mut core::ffi::c_void, f64, *mut u8, i32) -> HRESULT,
     }
 
-    /// COM object data for our ISampleGrabberCB implementation.
-    #[repr(C)]
+    /// Data of our ISampleGrabberCB implementation.
     struct FrameCallbackData {
-        vtbl: *const ISampleGrabberCBVtbl,
-        ref_count: std::sync::atomic::AtomicU32,
         buffer: Arc<FrameBuffer>,
         width: u32,
         height: u32,
+        /// Bytes per source row for RGB24, DWORD-aligned from `biWidth`.
+        stride: usize,
+        /// RGB24 rows are stored top-down (negative `biHeight`).
+        top_down: bool,
         sub_type: GUID,
         running: Arc<AtomicBool>,
         stats: Arc<Mutex<DiagnosticStats>>,
@@ -234,8 +266,37 @@ pub mod directshow {
         gpu: Option<Arc<GpuContext>>,
         /// Optional sender for async JPEG encoding via the encode worker.
         frame_sender: Option<crate::preview::encode_worker::FrameSender>,
+        /// Resolutions advertised by the source pin, used to reinterpret
+        /// mis-sized frames when `tolerate_size_mismatch` is set.
+        capabilities: Vec<(u32, u32)>,
+        /// Accept frames sized for a different advertised capability.
+        tolerate_size_mismatch: bool,
+        /// Set if frame handling panicked; the graph then stops and reports
+        /// an internal capture error.
+        panicked: Arc<AtomicBool>,
+        /// Keeps delivered timestamps from going backwards.
+        clock: Mutex<MonotonicClock>,
+        /// Samples delivered frames for black or frozen content.
+        content: Mutex<ContentMonitor>,
+        /// Called when the content classification changes.
+        on_content: Option<ContentHook>,
+        /// The device's colour space override, changeable while running.
+        colour: SharedColourSpace,
+        /// What the source's media type says about its colour space.
+        colour_hints: ColourHints,
+        /// The session's CPU meter; frame handling counts as capture time.
+        cpu: CpuMeter,
     }
 
+    /// Reports content classification changes for one session.
+    pub type ContentHook = Box<dyn Fn(ContentHealth) + Send + Sync>;
+
+    /// Told once what format the connected graph settled on.
+    pub type StartedHook = Box<dyn FnOnce(NegotiatedFormat) + Send>;
+
+    /// Our reference to the frame callback handed to the SampleGrabber.
+    type FrameCallback = ComHandle<ISampleGrabberCBVtbl, FrameCallbackData>;
+
     static FRAME_CALLBACK_VTBL: ISampleGrabberCBVtbl = ISampleGrabberCBVtbl {
         query_interface: frame_cb_query_interface,
         add_ref: frame_cb_add_ref,
@@ -261,20 +322,11 @@ pub mod directshow {
     }
 
     unsafe extern "system" fn frame_cb_add_ref(this: *mut core::ffi::c_void) -> u32 {
-        let data = &*(this as *const FrameCallbackData);
-        data.ref_count
-            .fetch_add(1, Ordering::Relaxed)
-            .wrapping_add(1)
+        com_object::add_ref::<ISampleGrabberCBVtbl, FrameCallbackData>(this)
     }
 
     unsafe extern "system" fn frame_cb_release(this: *mut core::ffi::c_void) -> u32 {
-        let data = &*(this as *const FrameCallbackData);
-        let prev = data.ref_count.fetch_sub(1, Ordering::Relaxed);
-        if prev == 1 {
-            drop(Box::from_raw(this as *mut FrameCallbackData));
-            return 0;
-        }
-        prev - 1
+        com_object::release::<ISampleGrabberCBVtbl, FrameCallbackData>(this)
     }
 
     unsafe extern "system" fn frame_cb_sample_cb(
@@ -292,55 +344,75 @@ pub mod directshow {
         buffer: *mut u8,
         buffer_len: i32,
     ) -> HRESULT {
-        let data = &*(this as *const FrameCallbackData);
+        com_object::buffer_cb::<ISampleGrabberCBVtbl, FrameCallbackData>(
+            this,
+            sample_time,
+            buffer,
+            buffer_len,
+        );
+        HRESULT(0)
+    }
 
-        if !data.running.load(Ordering::Relaxed) {
-            return HRESULT(0);
+    impl SampleSink for FrameCallbackData {
+        fn is_running(&self) -> bool {
+            self.running.load(Ordering::Relaxed)
         }
 
-        if buffer.is_null() || buffer_len <= 0 {
+        fn on_sample(&self, sample_time: f64, raw: &[u8]) {
+            let timer = CpuTimer::start();
+            handle_buffer(self, sample_time, raw);
+            self.cpu.record(CpuWork::Capture, timer.elapsed());
+        }
+
+        fn on_invalid(&self, buffer_len: i32) {
             warn!("frame callback received null/empty buffer (len={buffer_len})");
-            data.stats.lock().record_drop();
-            return HRESULT(0);
+            self.stats.lock().record_drop(DropReason::NullBuffer);
         }
 
-        let len = buffer_len as usize;
-        let raw = std::slice::from_raw_parts(buffer, len);
-        let timestamp_us = (sample_time * 1_000_000.0) as u64;
+        // A panic must not unwind across the FFI boundary (it would abort the
+        // process). Convert it into a stopped graph and a session error.
+        fn on_panic(&self, message: String) {
+            error!("frame callback panicked: {message}");
+            self.stats.lock().record_panic();
+            self.panicked.store(true, Ordering::Relaxed);
+            self.running.store(false, Ordering::Relaxed);
+        }
+    }
 
-        let width = data.width as usize;
-        let height = data.height as usize;
+    /// Validate, convert and deliver a single sample from BufferCB.
+    fn handle_buffer(data: &FrameCallbackData, sample_time: f64, raw: &[u8]) {
+        let started = Instant::now();
+        let len = raw.len();
+        let device_timestamp_us = (sample_time * 1_000_000.0) as u64;
+
+        // MJPG is decoded on the CPU; a corrupt or truncated frame is a drop
+        if data.sub_type == MEDIASUBTYPE_MJPG {
+            let (width, height) = (data.width as usize, data.height as usize);
+            match convert_mjpg_to_rgb(raw, width, height) {
+                Ok(rgb) => deliver_frame(
+                    data,
+                    rgb,
+                    data.width,
+                    data.height,
+                    device_timestamp_us,
+                    started,
+                ),
+                // Counted rather than warned about: a camera sending corrupt
+                // MJPG would otherwise log every frame
+                Err(e) => {
+                    debug!("dropping MJPG frame ({len} bytes): {e}");
+                    data.stats.lock().record_drop(DropReason::DecodeFailed);
+                }
+            }
+            return;
+        }
 
-        // Determine pixel format and validate buffer size
+        // Determine pixel format
         let format = if data.sub_type == MEDIASUBTYPE_RGB24 {
-            let expected = width * 3 * height;
-            if len < expected {
-                warn!(
-                    "frame size mismatch: got {len} bytes, expected {expected} ({width}x{height})"
-                );
-                data.stats.lock().record_drop();
-                return HRESULT(0);
-            }
             PixelFormat::Bgr24BottomUp
         } else if data.sub_type == MEDIASUBTYPE_YUY2 {
-            let expected = width * height * 2;
-            if len < expected {
-                warn!(
-                    "YUY2 frame size mismatch: got {len} bytes, expected {expected} ({width}x{height})"
-                );
-                data.stats.lock().record_drop();
-                return HRESULT(0);
-            }
             PixelFormat::Yuy2
         } else if data.sub_type == MEDIASUBTYPE_NV12 {
-            let expected = width * height * 3 / 2;
-            if len < expected {
-                warn!(
-                    "NV12 frame size mismatch: got {len} bytes, expected {expected} ({width}x{height})"
-                );
-                data.stats.lock().record_drop();
-                return HRESULT(0);
-            }
             PixelFormat::Nv12
         } else {
             // Unsupported format — drop the frame to prevent panics in
@@ -349,44 +421,138 @@ pub mod directshow {
                 "unsupported sub_type {:?}, dropping frame ({len} bytes)",
                 data.sub_type
             );
-            data.stats.lock().record_drop();
-            return HRESULT(0);
+            data.stats.lock().record_drop(DropReason::UnsupportedFormat);
+            return;
         };
 
-        // Convert using GPU if available, otherwise CPU fallback
-        let rgb = gpu::convert_frame(data.gpu.as_ref(), format, raw, width, height);
+        // Validate buffer size, reinterpreting against other advertised
+        // capabilities for cameras with the size-mismatch quirk.
+        let Some((frame_width, frame_height)) = quirks::resolve_frame_size(
+            format,
+            len,
+            data.width,
+            data.height,
+            &data.capabilities,
+            data.tolerate_size_mismatch,
+        ) else {
+            let expected = quirks::expected_frame_len(format, data.width, data.height);
+            warn!(
+                "{format:?} frame size mismatch: got {len} bytes, expected {expected} ({}x{}, \
+                 stride {})",
+                data.width, data.height, data.stride
+            );
+            data.stats.lock().record_drop(DropReason::SizeMismatch);
+            return;
+        };
 
-        let frame_bytes = rgb.len();
+        let width = frame_width as usize;
+        let height = frame_height as usize;
 
-        // Send to the async JPEG encode worker (non-blocking)
-        if let Some(sender) = &data.frame_sender {
-            sender.send(Frame {
-                data: rgb.clone(),
-                width: data.width,
-                height: data.height,
-                timestamp_us,
-            });
+        // Convert using GPU if available, otherwise CPU fallback. The GPU
+        // shader assumes tightly packed bottom-up rows, so other RGB24
+        // layouts are converted on the CPU.
+        let stride = if (frame_width, frame_height) == (data.width, data.height) {
+            data.stride
+        } else {
+            quirks::bgr24_stride(frame_width)
+        };
+        let stride = quirks::bgr24_row_stride(len, frame_width, frame_height, stride);
+        let mut rgb = data.buffer.pool().take(width * height * 3);
+        match format {
+            PixelFormat::Bgr24BottomUp if data.top_down => {
+                convert_bgr_top_down_to_rgb_into(raw, width, height, stride, &mut rgb);
+            }
+            PixelFormat::Bgr24BottomUp if stride != width * 3 => {
+                convert_bgr_bottom_up_to_rgb_into(raw, width, height, stride, &mut rgb);
+            }
+            _ => {
+                let colour = colour::resolve(*data.colour.lock(), data.colour_hints, frame_height);
+                gpu::convert_frame_into(
+                    data.gpu.as_ref(),
+                    format,
+                    raw,
+                    width,
+                    height,
+                    colour,
+                    &mut rgb,
+                );
+            }
         }
+        deliver_frame(
+            data,
+            rgb,
+            frame_width,
+            frame_height,
+            device_timestamp_us,
+            started,
+        );
+    }
 
-        data.buffer.push(Frame {
+    /// Push a converted RGB24 frame into the buffer and on to the encode
+    /// worker, and update the stats and content health. `started` is when
+    /// the callback got the sample, for the conversion time.
+    fn deliver_frame(
+        data: &FrameCallbackData,
+        rgb: Vec<u8>,
+        frame_width: u32,
+        frame_height: u32,
+        device_timestamp_us: u64,
+        started: Instant,
+    ) {
+        let frame_bytes = rgb.len();
+        let content_change = data
+            .content
+            .lock()
+            .on_frame(&rgb, frame_width, frame_height);
+        let timestamp_us = data.clock.lock().stamp(device_timestamp_us);
+
+        // The encode worker gets its own copy; the ring's frame goes back
+        // to the pool once overwritten
+        let worker_rgb = data.frame_sender.as_ref().map(|_| rgb.clone());
+        let sequence = data.buffer.push(Frame {
             data: rgb,
-            width: data.width,
-            height: data.height,
+            width: frame_width,
+            height: frame_height,
             timestamp_us,
+            device_timestamp_us,
         });
-        data.stats.lock().record_frame(frame_bytes, timestamp_us);
+
+        // Send to the async JPEG encode worker (non-blocking)
+        if let (Some(sender), Some(worker_rgb)) = (&data.frame_sender, worker_rgb) {
+            sender.send(
+                Frame {
+                    data: worker_rgb,
+                    width: frame_width,
+                    height: frame_height,
+                    timestamp_us,
+                    device_timestamp_us,
+                },
+                sequence,
+            );
+        }
+        // One lock for everything, so a snapshot never sees the frame
+        // counted without its conversion time
+        let frame_count = {
+            let mut stats = data.stats.lock();
+            stats.record_frame(frame_bytes, device_timestamp_us);
+            stats.record_conversion(started.elapsed());
+            if let Some(health) = content_change {
+                stats.set_content_health(health);
+            }
+            stats.frame_count()
+        };
+        if let (Some(health), Some(hook)) = (content_change, &data.on_content) {
+            hook(health);
+        }
 
         // Log early frames at debug level to confirm delivery
-        let snapshot = data.stats.lock().snapshot();
-        if snapshot.frame_count <= 3 {
+        if frame_count <= 3 {
             debug!(
-                "frame #{} delivered: {width}x{height}, {frame_bytes} bytes, \
-                 sub_type={:?}",
-                snapshot.frame_count, data.sub_type
+                "frame #{frame_count} delivered: {frame_width}x{frame_height}, {frame_bytes} \
+                 bytes, sub_type={:?}",
+                data.sub_type
             );
         }
-
-        HRESULT(0)
     }
 
     /// Create a new ISampleGrabberCB implementation that pushes frames
@@ -396,25 +562,43 @@ pub mod directshow {
         buffer: Arc<FrameBuffer>,
         width: u32,
         height: u32,
+        stride: usize,
+        top_down: bool,
         sub_type: GUID,
         running: Arc<AtomicBool>,
         stats: Arc<Mutex<DiagnosticStats>>,
         gpu: Option<Arc<GpuContext>>,
         frame_sender: Option<crate::preview::encode_worker::FrameSender>,
-    ) -> *mut core::ffi::c_void {
-        let data = Box::new(FrameCallbackData {
-            vtbl: &FRAME_CALLBACK_VTBL,
-            ref_count: std::sync::atomic::AtomicU32::new(1),
+        capabilities: Vec<(u32, u32)>,
+        tolerate_size_mismatch: bool,
+        panicked: Arc<AtomicBool>,
+        on_content: Option<ContentHook>,
+        colour: SharedColourSpace,
+        colour_hints: ColourHints,
+        cpu: CpuMeter,
+    ) -> FrameCallback {
+        let data = FrameCallbackData {
             buffer,
             width,
             height,
+            stride,
+            top_down,
             sub_type,
             running,
             stats,
             gpu,
             frame_sender,
-        });
-        Box::into_raw(data) as *mut core::ffi::c_void
+            capabilities,
+            tolerate_size_mismatch,
+            panicked,
+            clock: Mutex::new(MonotonicClock::default()),
+            content: Mutex::new(ContentMonitor::default()),
+            on_content,
+            colour,
+            colour_hints,
+            cpu,
+        };
+        ComObject::create(&FRAME_CALLBACK_VTBL, data)
     }
 
     /// COM guard for per-thread initialisation.
@@ -442,14 +626,40 @@ pub mod directshow {
 
     /// Configure the source filter's output pin to request a specific resolution.
     ///
-    /// Enumerates the pin's stream capabilities via IAMStreamConfig, picks the
-    /// best match for the requested width/height, and calls SetFormat. If no
-    /// suitable format is found or the pin doesn't support IAMStreamConfig, the
-    /// function logs a warning and returns without error — the graph will fall
-    /// back to the camera's default resolution.
-    unsafe fn configure_source_resolution(source: &IBaseFilter, width: u32, height: u32) {
+    /// Enumerates the pin's stream capabilities via IAMStreamConfig, picks one
+    /// with [`select_capability`] — the best match for the requested
+    /// width/height, or the smallest adequate one in thumbnail-only `mode` —
+    /// and calls SetFormat. AvgTimePerFrame is written for `fps` first,
+    /// clamped to the capability's frame-interval range; if the driver
+    /// rejects it, SetFormat is retried at the capability's default rate.
+    /// If no suitable format is found or the pin doesn't support
+    /// IAMStreamConfig, the function logs a warning and returns without error —
+    /// the graph will fall back to the camera's default resolution.
+    ///
+    /// A full session asking for `pixel_format` prefers capabilities in that
+    /// format, and falls back to choosing by resolution alone, with a warning,
+    /// when the pin has none.
+    ///
+    /// With `pin_position`, only that output pin is configured.
+    unsafe fn configure_source_resolution(
+        source: &IBaseFilter,
+        pin_position: Option<usize>,
+        width: u32,
+        height: u32,
+        fps: f32,
+        pixel_format: Option<&str>,
+        mode: SessionMode,
+    ) {
         use windows::Win32::Media::MediaFoundation::FORMAT_VideoInfo;
 
+        let fourcc = pixel_format.and_then(|f| {
+            let fourcc = parse_fourcc(f);
+            if fourcc.is_none() {
+                warn!("ignoring unrecognised pixel format {f:?}");
+            }
+            fourcc
+        });
+
         let pin_enum = match source.EnumPins() {
             Ok(e) => e,
             Err(e) => {
@@ -459,8 +669,9 @@ pub mod directshow {
         };
 
         let mut pin_array = [None; 1];
+        let mut output_position = 0;
 
-        // Find the first output pin with IAMStreamConfig
+        // Find the first (or the chosen) output pin with IAMStreamConfig
         loop {
             let hr = pin_enum.Next(&mut pin_array, None);
             if hr.is_err() {
@@ -480,6 +691,11 @@ pub mod directshow {
             if dir.0 != 1 {
                 continue;
             }
+            let position = output_position;
+            output_position += 1;
+            if pin_position.is_some_and(|p| p != position) {
+                continue;
+            }
 
             let Ok(stream_config) = pin.cast::<IAMStreamConfig>() else {
                 continue;
@@ -494,9 +710,8 @@ pub mod directshow {
                 continue;
             }
 
-            let target_pixels = (width as u64) * (height as u64);
-            let mut best_index: Option<i32> = None;
-            let mut best_diff: u64 = u64::MAX;
+            let mut indices = Vec::new();
+            let mut caps = Vec::new();
 
             for i in 0..count {
                 let mut scc = vec![0u8; size as usize];
@@ -513,8 +728,12 @@ pub mod directshow {
                 }
 
                 let mt_ref = &*mt_ptr;
+                let cap_fourcc = mt_ref.subtype.data1;
                 let mut cap_w = 0u32;
                 let mut cap_h = 0u32;
+                let supports_fps = parse_stream_config_caps(&scc)
+                    .and_then(|c| c.frame_interval_for_fps(fps))
+                    .is_some();
 
                 if mt_ref.formattype == FORMAT_VideoInfo
                     && !mt_ref.pbFormat.is_null()
@@ -537,14 +756,25 @@ pub mod directshow {
                     continue;
                 }
 
-                let cap_pixels = (cap_w as u64) * (cap_h as u64);
-                let diff = cap_pixels.abs_diff(target_pixels);
-                if diff < best_diff {
-                    best_diff = diff;
-                    best_index = Some(i);
-                }
+                indices.push(i);
+                caps.push(Capability {
+                    width: cap_w,
+                    height: cap_h,
+                    supports_fps,
+                    fourcc: cap_fourcc,
+                });
             }
 
+            let best = select_capability(&caps, width, height, fourcc, mode);
+            if let (SessionMode::Full, Some(wanted), Some(best)) = (mode, fourcc, best) {
+                if !caps[best].has_fourcc(wanted) {
+                    warn!(
+                        "no {} format on this camera, choosing by resolution only",
+                        pixel_format.unwrap_or_default()
+                    );
+                }
+            }
+            let best_index = best.map(|c| indices[c]);
             if let Some(idx) = best_index {
                 let mut scc = vec![0u8; size as usize];
                 let mut mt_ptr = std::ptr::null_mut();
@@ -556,20 +786,47 @@ pub mod directshow {
                     let mt_ref = &*mt_ptr;
                     let mut fmt_w = 0u32;
                     let mut fmt_h = 0u32;
+                    // The capability's own rate, and the one written over it
+                    let mut default_interval = None;
                     if mt_ref.formattype == FORMAT_VideoInfo
                         && !mt_ref.pbFormat.is_null()
                         && mt_ref.cbFormat as usize >= std::mem::size_of::<VIDEOINFOHEADER>()
                     {
-                        let vih: &VIDEOINFOHEADER = &*(mt_ref.pbFormat as *const VIDEOINFOHEADER);
+                        let vih = &mut *(mt_ref.pbFormat as *mut VIDEOINFOHEADER);
                         fmt_w = vih.bmiHeader.biWidth as u32;
                         fmt_h = vih.bmiHeader.biHeight.unsigned_abs();
+
+                        // Request the desired frame rate, or the nearest one
+                        // the capability's frame-interval range allows
+                        match parse_stream_config_caps(&scc)
+                            .and_then(|c| c.clamped_frame_interval(fps))
+                        {
+                            Some(interval) if interval != vih.AvgTimePerFrame => {
+                                default_interval = Some(vih.AvgTimePerFrame);
+                                vih.AvgTimePerFrame = interval;
+                                info!("requesting {fps}fps (AvgTimePerFrame={interval})");
+                            }
+                            _ => debug!("using the default rate for {fps}fps"),
+                        }
                     }
 
-                    match stream_config.SetFormat(mt_ptr) {
+                    let mut result = stream_config.SetFormat(mt_ptr);
+                    if let (Err(e), Some(default)) = (&result, default_interval) {
+                        // Some drivers only take the rates they list; the
+                        // resolution matters more
+                        warn!(
+                            "SetFormat({fmt_w}x{fmt_h} at {fps}fps) failed: {e}, \
+                             retrying at the default rate"
+                        );
+                        let vih = &mut *(mt_ref.pbFormat as *mut VIDEOINFOHEADER);
+                        vih.AvgTimePerFrame = default;
+                        result = stream_config.SetFormat(mt_ptr);
+                    }
+                    match result {
                         Ok(()) => {
                             info!(
                                 "configured source resolution to {fmt_w}x{fmt_h} \
-                                 (requested {width}x{height})"
+                                 (requested {width}x{height}, {mode:?})"
                             );
                         }
                         Err(e) => {
@@ -598,14 +855,174 @@ pub mod directshow {
         warn!("no output pin with IAMStreamConfig found, using camera default resolution");
     }
 
-    /// Force NV12 on the source pin via IAMStreamConfig.
+    /// Colour hints for the negotiated format, from a `VIDEOINFOHEADER2`
+    /// capability of `pin` with the same subtype and size. Empty when the
+    /// pin only advertises plain `VIDEOINFOHEADER`s.
+    unsafe fn colour_hints_for(pin: &IPin, sub_type: GUID, width: u32, height: u32) -> ColourHints {
+        use windows::Win32::Media::MediaFoundation::{FORMAT_VideoInfo2, VIDEOINFOHEADER2};
+
+        let Ok(stream_config) = pin.cast::<IAMStreamConfig>() else {
+            return ColourHints::default();
+        };
+        let mut count = 0i32;
+        let mut size = 0i32;
+        if stream_config
+            .GetNumberOfCapabilities(&mut count, &mut size)
+            .is_err()
+        {
+            return ColourHints::default();
+        }
+
+        for i in 0..count {
+            let mut scc = vec![0u8; size as usize];
+            let mut mt_ptr = std::ptr::null_mut();
+            if stream_config
+                .GetStreamCaps(i, &mut mt_ptr, scc.as_mut_ptr())
+                .is_err()
+                || mt_ptr.is_null()
+            {
+                continue;
+            }
+
+            let mt_ref = &*mt_ptr;
+            let mut hints = None;
+            if mt_ref.formattype == FORMAT_VideoInfo2
+                && mt_ref.subtype == sub_type
+                && !mt_ref.pbFormat.is_null()
+                && mt_ref.cbFormat as usize >= std::mem::size_of::<VIDEOINFOHEADER2>()
+            {
+                let vih2 = &*(mt_ref.pbFormat as *const VIDEOINFOHEADER2);
+                if vih2.bmiHeader.biWidth as u32 == width
+                    && vih2.bmiHeader.biHeight.unsigned_abs() == height
+                {
+                    hints = Some(ColourHints::from_control_flags(
+                        vih2.Anonymous.dwControlFlags,
+                    ));
+                }
+            }
+
+            // Free the AM_MEDIA_TYPE
+            if !mt_ref.pbFormat.is_null() {
+                windows::Win32::System::Com::CoTaskMemFree(Some(mt_ref.pbFormat.cast()));
+            }
+            windows::Win32::System::Com::CoTaskMemFree(Some(
+                (mt_ptr as *mut core::ffi::c_void).cast(),
+            ));
+
+            if let Some(hints) = hints {
+                return hints;
+            }
+        }
+        ColourHints::default()
+    }
+
+    /// Map a quirk's forced pixel format to its DirectShow subtype GUID.
+    fn pixel_format_subtype(format: PixelFormat) -> GUID {
+        match format {
+            PixelFormat::Nv12 => MEDIASUBTYPE_NV12,
+            PixelFormat::Yuy2 => MEDIASUBTYPE_YUY2,
+            PixelFormat::Bgr24BottomUp => MEDIASUBTYPE_RGB24,
+        }
+    }
+
+    /// List the resolutions advertised by the source output pin.
     ///
-    /// Enumerates stream capabilities to find an NV12 format and calls
+    /// Used by the size-mismatch quirk to reinterpret frames that arrive
+    /// sized for a different capability. Returns an empty list if the pin
+    /// doesn't support IAMStreamConfig.
+    unsafe fn enumerate_source_capabilities(source: &IBaseFilter) -> Vec<(u32, u32)> {
+        use windows::Win32::Media::MediaFoundation::FORMAT_VideoInfo;
+
+        let mut caps = Vec::new();
+        let Ok(pin_enum) = source.EnumPins() else {
+            return caps;
+        };
+
+        let mut pin_array = [None; 1];
+
+        loop {
+            let hr = pin_enum.Next(&mut pin_array, None);
+            if hr.is_err() {
+                break;
+            }
+
+            let Some(pin) = pin_array[0].take() else {
+                break;
+            };
+
+            // PINDIR_OUTPUT = 1
+            if !matches!(pin.QueryDirection(), Ok(d) if d.0 == 1) {
+                continue;
+            }
+
+            let Ok(stream_config) = pin.cast::<IAMStreamConfig>() else {
+                continue;
+            };
+
+            let mut count = 0i32;
+            let mut size = 0i32;
+            if stream_config
+                .GetNumberOfCapabilities(&mut count, &mut size)
+                .is_err()
+            {
+                continue;
+            }
+
+            for i in 0..count {
+                let mut scc = vec![0u8; size as usize];
+                let mut mt_ptr = std::ptr::null_mut();
+                if stream_config
+                    .GetStreamCaps(i, &mut mt_ptr, scc.as_mut_ptr())
+                    .is_err()
+                    || mt_ptr.is_null()
+                {
+                    continue;
+                }
+
+                let mt_ref = &*mt_ptr;
+                if mt_ref.formattype == FORMAT_VideoInfo
+                    && !mt_ref.pbFormat.is_null()
+                    && mt_ref.cbFormat as usize >= std::mem::size_of::<VIDEOINFOHEADER>()
+                {
+                    let vih: &VIDEOINFOHEADER = &*(mt_ref.pbFormat as *const VIDEOINFOHEADER);
+                    let dims = (
+                        vih.bmiHeader.biWidth as u32,
+                        vih.bmiHeader.biHeight.unsigned_abs(),
+                    );
+                    if dims.0 > 0 && dims.1 > 0 && !caps.contains(&dims) {
+                        caps.push(dims);
+                    }
+                }
+
+                // Free the AM_MEDIA_TYPE
+                if !mt_ref.pbFormat.is_null() {
+                    windows::Win32::System::Com::CoTaskMemFree(Some(mt_ref.pbFormat.cast()));
+                }
+                windows::Win32::System::Com::CoTaskMemFree(Some(
+                    (mt_ptr as *mut core::ffi::c_void).cast(),
+                ));
+            }
+
+            break;
+        }
+
+        caps
+    }
+
+    /// Force a specific subtype on the source pin via IAMStreamConfig.
+    ///
+    /// Enumerates stream capabilities to find a matching format and calls
     /// SetFormat with the full media type (including proper format_type,
     /// width, height). This is how OpenCV handles OBS Virtual Camera —
     /// forcing the entire pipeline to NV12 from the source rather than
     /// relying on SampleGrabber hints.
-    unsafe fn force_nv12_on_source_pin(source: &IBaseFilter) -> Result<(), String> {
+    ///
+    /// With `pin_position`, only that output pin is considered.
+    unsafe fn force_subtype_on_source_pin(
+        source: &IBaseFilter,
+        pin_position: Option<usize>,
+        subtype: GUID,
+    ) -> Result<(), String> {
         use windows::Win32::Media::MediaFoundation::FORMAT_VideoInfo;
 
         let pin_enum = source
@@ -613,6 +1030,7 @@ pub mod directshow {
             .map_err(|e| format!("EnumPins failed: {e}"))?;
 
         let mut pin_array = [None; 1];
+        let mut output_position = 0;
 
         loop {
             let hr = pin_enum.Next(&mut pin_array, None);
@@ -633,6 +1051,11 @@ pub mod directshow {
             if dir.0 != 1 {
                 continue;
             }
+            let position = output_position;
+            output_position += 1;
+            if pin_position.is_some_and(|p| p != position) {
+                continue;
+            }
 
             let Ok(stream_config) = pin.cast::<IAMStreamConfig>() else {
                 continue;
@@ -647,7 +1070,7 @@ pub mod directshow {
                 continue;
             }
 
-            // Find an NV12 capability
+            // Find a capability with the requested subtype
             for i in 0..count {
                 let mut scc = vec![0u8; size as usize];
                 let mut mt_ptr = std::ptr::null_mut();
@@ -664,10 +1087,9 @@ pub mod directshow {
 
                 let mt_ref = &*mt_ptr;
 
-                let is_nv12 =
-                    mt_ref.subtype == MEDIASUBTYPE_NV12 && mt_ref.formattype == FORMAT_VideoInfo;
+                let is_match = mt_ref.subtype == subtype && mt_ref.formattype == FORMAT_VideoInfo;
 
-                if is_nv12 {
+                if is_match {
                     // Extract dimensions for logging
                     let mut w = 0u32;
                     let mut h = 0u32;
@@ -681,7 +1103,7 @@ pub mod directshow {
 
                     match stream_config.SetFormat(mt_ptr) {
                         Ok(()) => {
-                            info!("set source pin to NV12 successfully ({w}x{h})");
+                            info!("set source pin to {subtype:?} successfully ({w}x{h})");
 
                             // Free the AM_MEDIA_TYPE
                             if !mt_ref.pbFormat.is_null() {
@@ -696,7 +1118,10 @@ pub mod directshow {
                             return Ok(());
                         }
                         Err(e) => {
-                            warn!("SetFormat(NV12, cap {i}) failed: {e}, trying next capability");
+                            warn!(
+                                "SetFormat({subtype:?}, cap {i}) failed: {e}, \
+                                 trying next capability"
+                            );
                         }
                     }
                 }
@@ -710,7 +1135,7 @@ pub mod directshow {
                 ));
             }
 
-            return Err("no NV12 capability found on source pin".to_string());
+            return Err(format!("no {subtype:?} capability found on source pin"));
         }
 
         Err("no output pin with IAMStreamConfig found".to_string())
@@ -762,7 +1187,7 @@ pub mod directshow {
 
             // Match by device path first, fall back to friendly name for
             // virtual cameras that may not have a DevicePath property.
-            let matched = if !device_path.is_empty() && path == device_path {
+            let matched = if !device_path.is_empty() && same_device_path(&path, device_path) {
                 true
             } else if !friendly_name.is_empty() && path.is_empty() && name == friendly_name {
                 info!("matched virtual camera by FriendlyName: {name}");
@@ -786,7 +1211,8 @@ pub mod directshow {
         }
 
         Err(format!(
-            "device not found: path={device_path}, name={friendly_name}"
+            "device not found: path={}, name={friendly_name}",
+            abbreviate_path(device_path)
         ))
     }
 
@@ -823,23 +1249,38 @@ pub mod directshow {
     /// This function blocks the calling thread, running the filter graph
     /// until `running` is set to false. Should be called from a dedicated
     /// capture thread.
+    ///
+    /// The device's video sources are published to `sources` once found,
+    /// and the one `video_source` names is routed or captured.
+    /// `on_started` is told the negotiated resolution and pixel format once
+    /// the graph is connected, before it runs.
     #[allow(clippy::too_many_arguments)]
     pub fn run_capture_graph(
         device_path: &str,
         friendly_name: &str,
         width: u32,
         height: u32,
+        fps: f32,
+        pixel_format: Option<&str>,
+        mode: SessionMode,
+        video_source: Option<SourcePreference>,
+        sources: Arc<Mutex<Vec<VideoSource>>>,
         buffer: Arc<FrameBuffer>,
         running: Arc<AtomicBool>,
         stats: Arc<Mutex<DiagnosticStats>>,
         gpu: Option<Arc<GpuContext>>,
         frame_sender: Option<crate::preview::encode_worker::FrameSender>,
+        on_content: Option<ContentHook>,
+        on_started: StartedHook,
+        colour: SharedColourSpace,
+        cpu: CpuMeter,
     ) -> Result<(), String> {
         unsafe {
             let _guard = ComGuard::init()?;
 
             // 1. Create filter graph
-            info!("creating capture graph for {device_path}");
+            let logged_path = abbreviate_path(device_path);
+            info!("creating capture graph for {logged_path}");
             let graph: IGraphBuilder =
                 CoCreateInstance(&CLSID_FILTER_GRAPH, None, CLSCTX_INPROC_SERVER).map_err(|e| {
                     error!("failed to create filter graph: {e}");
@@ -853,7 +1294,7 @@ pub mod directshow {
 
             // 2. Find and add source filter
             let source = find_source_filter(device_path, friendly_name).map_err(|e| {
-                error!("failed to find source filter for {device_path}: {e}");
+                error!("failed to find source filter for {logged_path}: {e}");
                 e
             })?;
             graph2
@@ -863,15 +1304,60 @@ pub mod directshow {
                     format!("failed to add source filter: {e}")
                 })?;
 
+            // 2a. Multi-input devices: find the inputs behind a crossbar or
+            //     the separate capture pins, and select the saved one.
+            let discovered = discover_sources(&graph, &source);
+            let choice = choose_source(&discovered.sources, video_source.as_ref());
+            if choice.preference_missing {
+                warn!(
+                    "saved video source {video_source:?} not found on {logged_path}, \
+                     using {:?}",
+                    choice.source.map(|s| &s.name)
+                );
+            }
+            let source_pin = match choice.source.map(|s| (s.route, &s.name)) {
+                Some((SourceRoute::Pin(position), name)) => {
+                    info!("capturing video source {name:?} (output pin {position})");
+                    Some(position)
+                }
+                Some((SourceRoute::Crossbar { output, input }, name)) => {
+                    match discovered.crossbar.as_ref().map(|c| c.Route(output, input)) {
+                        Some(Ok(())) => info!("routed crossbar to video source {name:?}"),
+                        Some(Err(e)) => warn!("crossbar route to {name:?} failed: {e}"),
+                        None => {}
+                    }
+                    None
+                }
+                None => None,
+            };
+            *sources.lock() = discovered.sources;
+
             // 2b. Configure the source output pin resolution via IAMStreamConfig.
             //     This requests the camera to output at the desired resolution
             //     rather than defaulting to its maximum (e.g. 1920x1080).
-            //     Skip for OBS Virtual Camera — its SetFormat returns S_OK for
-            //     anything but silently breaks the pipeline. NV12 is forced
+            //     Skipped for cameras with the skip_set_format quirk (e.g. OBS
+            //     Virtual Camera, whose SetFormat returns S_OK for anything but
+            //     silently breaks the pipeline). Forced subtypes are applied
             //     separately in step 6.
-            info!("checking camera: friendly_name={friendly_name:?}");
-            if width > 0 && height > 0 && !is_obs_virtual_camera(friendly_name) {
-                configure_source_resolution(&source, width, height);
+            let quirk = quirks::quirk_profile_for(friendly_name);
+            info!(
+                "checking camera: friendly_name={friendly_name:?}, quirks={:?}",
+                quirk.map(|q| q.label)
+            );
+            let should_configure = match quirk {
+                Some(q) => q.should_configure_resolution(width, height),
+                None => width > 0 && height > 0,
+            };
+            if should_configure {
+                configure_source_resolution(
+                    &source,
+                    source_pin,
+                    width,
+                    height,
+                    fps,
+                    pixel_format,
+                    mode,
+                );
             }
 
             // 3. Create and add SampleGrabber filter
@@ -925,51 +1411,59 @@ pub mod directshow {
                 })?;
 
             // 6. Connect: Source -> SampleGrabber -> NullRenderer
-            //    OBS Virtual Camera lies about supporting RGB24 (SetFormat returns
-            //    S_OK for anything) but only delivers NV12 frames reliably. When
-            //    detected, request NV12 directly to avoid the 1-frame issue.
-            //    For all other cameras, try RGB24 first then fall back to any subtype.
-            let source_out = find_unconnected_pin(&source, 1)?;
+            //    Some virtual cameras (OBS, NDI) lie about supporting RGB24
+            //    (SetFormat returns S_OK for anything) but only deliver their
+            //    native subtype reliably. Their quirk profile forces that
+            //    subtype directly to avoid the 1-frame issue. For all other
+            //    cameras, try RGB24 first then fall back to any subtype.
+            let source_out = match source_pin {
+                Some(position) => output_pin_at(&source, position)?,
+                None => find_unconnected_pin(&source, 1)?,
+            };
             let grabber_in = find_unconnected_pin(&grabber_filter, 0)?;
 
-            if is_obs_virtual_camera(friendly_name) {
+            let forced = quirk.and_then(|q| q.force_subtype.map(|f| (q.label, f)));
+
+            if let Some((label, forced_format)) = forced {
+                let subtype = pixel_format_subtype(forced_format);
                 info!(
-                    "OBS Virtual Camera detected — skipping RGB24 SetFormat, \
-                     forcing NV12 on source pin"
+                    "{label} detected — skipping RGB24 SetFormat, \
+                     forcing {forced_format:?} on source pin"
                 );
 
-                // Force NV12 on the source pin via IAMStreamConfig so the
-                // entire pipeline negotiates NV12 from the start. This is
+                // Force the subtype on the source pin via IAMStreamConfig so
+                // the entire pipeline negotiates it from the start. This is
                 // how OpenCV handles OBS — setting the grabber alone is
                 // just a hint that DirectShow may ignore.
-                if let Err(e) = force_nv12_on_source_pin(&source) {
+                if let Err(e) = force_subtype_on_source_pin(&source, source_pin, subtype) {
                     warn!(
-                        "could not force NV12 on source pin: {e}, attempting graph-level connect"
+                        "could not force {forced_format:?} on source pin: {e}, \
+                         attempting graph-level connect"
                     );
                 }
 
-                // Set the SampleGrabber to accept NV12 with a proper
+                // Set the SampleGrabber to accept the subtype with a proper
                 // FORMAT_VideoInfo so DirectShow treats it as a real
                 // constraint rather than a wildcard hint.
-                let nv12_mt = AmMediaType {
+                let forced_mt = AmMediaType {
                     major_type: MEDIATYPE_VIDEO,
-                    sub_type: MEDIASUBTYPE_NV12,
+                    sub_type: subtype,
                     format_type: FORMAT_VIDEOINFO,
                     ..AmMediaType::default()
                 };
 
-                let hr = grabber.set_media_type(&nv12_mt);
+                let hr = grabber.set_media_type(&forced_mt);
                 if hr.is_err() {
-                    error!("SetMediaType(NV12) failed: {hr:?}");
-                    return Err(format!("SetMediaType(NV12) failed: {hr:?}"));
+                    error!("SetMediaType({forced_format:?}) failed: {hr:?}");
+                    return Err(format!("SetMediaType({forced_format:?}) failed: {hr:?}"));
                 }
 
                 graph2.Connect(&source_out, &grabber_in).map_err(|e| {
-                    error!("failed to connect OBS source -> grabber (NV12): {e}");
+                    error!("failed to connect {label} source -> grabber ({forced_format:?}): {e}");
                     format!("failed to connect source -> grabber: {e}")
                 })?;
 
-                info!("connected OBS Virtual Camera with NV12");
+                info!("connected {label} with {forced_format:?}");
             } else {
                 let rgb24_mt = AmMediaType {
                     major_type: MEDIATYPE_VIDEO,
@@ -1036,22 +1530,44 @@ pub mod directshow {
                 connected_mt.sub_type, connected_mt.format_type
             );
 
-            let (actual_width, actual_height, actual_sub_type) = if hr.is_ok()
+            let (actual_width, actual_height, stride, top_down, actual_sub_type) = if hr.is_ok()
                 && !connected_mt.pb_format.is_null()
                 && connected_mt.cb_format as usize >= std::mem::size_of::<VIDEOINFOHEADER>()
             {
                 let vih = &*(connected_mt.pb_format as *const VIDEOINFOHEADER);
                 let w = vih.bmiHeader.biWidth as u32;
                 let h = vih.bmiHeader.biHeight.unsigned_abs();
+                // RGB DIB rows are DWORD-aligned, and stored top-down when
+                // the height is negative.
+                let stride = quirks::bgr24_stride(w);
+                let top_down = vih.bmiHeader.biHeight < 0;
                 let sub = connected_mt.sub_type;
-                info!("negotiated resolution: {w}x{h}");
-                (w, h, sub)
+                let negotiated_fps = frame_interval_to_fps(vih.AvgTimePerFrame);
+                if let Some(fps) = negotiated_fps {
+                    info!("negotiated frame rate: {fps:.2}fps");
+                }
+                stats.lock().set_negotiated_fps(negotiated_fps);
+                if sub == MEDIASUBTYPE_RGB24 {
+                    info!(
+                        "negotiated resolution: {w}x{h}, stride {stride}{}",
+                        if top_down { ", top-down" } else { "" }
+                    );
+                } else {
+                    info!("negotiated resolution: {w}x{h}");
+                }
+                (w, h, stride, top_down, sub)
             } else {
                 warn!(
                     "could not query connected media type (hr={hr:?}), \
                      falling back to requested {width}x{height}"
                 );
-                (width, height, MEDIASUBTYPE_RGB24)
+                (
+                    width,
+                    height,
+                    quirks::bgr24_stride(width),
+                    false,
+                    MEDIASUBTYPE_RGB24,
+                )
             };
 
             // Free the format block if allocated
@@ -1061,68 +1577,438 @@ pub mod directshow {
                 ));
             }
 
+            on_started(NegotiatedFormat {
+                width: actual_width,
+                height: actual_height,
+                pixel_format: fourcc_name(actual_sub_type.data1),
+            });
+
+            // Colour hints only come with VIDEOINFOHEADER2, which the
+            // grabber doesn't accept; look for them among the source's caps
+            let colour_hints =
+                colour_hints_for(&source_out, actual_sub_type, actual_width, actual_height);
+            if colour_hints != ColourHints::default() {
+                info!("source colour hints: {colour_hints:?}");
+            }
+
             // 9. Set up callback (mode 1 = BufferCB) with actual resolution
+            let panicked = Arc::new(AtomicBool::new(false));
+            let tolerate_size_mismatch = quirk.is_some_and(|q| q.tolerate_size_mismatch);
+            let capabilities = if tolerate_size_mismatch {
+                enumerate_source_capabilities(&source)
+            } else {
+                Vec::new()
+            };
             let callback = create_frame_callback(
                 buffer,
                 actual_width,
                 actual_height,
+                stride,
+                top_down,
                 actual_sub_type,
                 Arc::clone(&running),
                 stats,
                 gpu,
                 frame_sender,
+                capabilities,
+                tolerate_size_mismatch,
+                Arc::clone(&panicked),
+                on_content,
+                colour,
+                colour_hints,
+                cpu,
             );
 
-            let hr = grabber.set_callback(callback, 1);
+            // Pin references would otherwise outlive the teardown
+            drop((source_out, grabber_in, grabber_out, null_in));
+
+            let callback_ptr = callback.as_raw();
+            let components = GraphComponents {
+                graph,
+                graph2,
+                filters: vec![source, grabber_filter, null_renderer],
+                grabber,
+                callback: Some(callback),
+            };
+
+            // The grabber takes its own reference; ours is kept until teardown
+            let hr = components.grabber.set_callback(callback_ptr, 1);
             if hr.is_err() {
                 error!("SetCallback failed: {hr:?}");
+                log_teardown_failures(&logged_path, teardown_graph(components));
                 return Err(format!("SetCallback failed: {hr:?}"));
             }
 
-            // 10. Accept frames BEFORE running the graph to avoid dropping
-            //     the first few frames
-            running.store(true, Ordering::Relaxed);
+            let outcome = run_until_stopped(
+                &components,
+                quirk,
+                &running,
+                &logged_path,
+                actual_width,
+                actual_height,
+            );
 
-            let media_control: IMediaControl = graph.cast().map_err(|e| {
-                error!("failed to get IMediaControl: {e}");
-                format!("failed to get IMediaControl: {e}")
-            })?;
+            // 12. Cleanup, before the COM guard uninitialises
+            debug!("tearing down capture graph for {logged_path}");
+            log_teardown_failures(&logged_path, teardown_graph(components));
+            outcome?;
 
-            // OBS Virtual Camera doesn't handle reference clock timing correctly
-            // (OBS issue #4929, #8057). Remove the clock so the NullRenderer
-            // delivers every sample immediately instead of scheduling by timestamp.
-            if is_obs_virtual_camera(friendly_name) {
-                let media_filter: IMediaFilter = graph.cast().map_err(|e| {
-                    error!("failed to get IMediaFilter: {e}");
-                    format!("failed to get IMediaFilter: {e}")
-                })?;
-                media_filter
-                    .SetSyncSource(None)
-                    .map_err(|e| format!("SetSyncSource(NULL) failed: {e}"))?;
-                info!("disabled reference clock for OBS Virtual Camera");
+            if panicked.load(Ordering::Relaxed) {
+                return Err(INTERNAL_CAPTURE_ERROR.to_string());
             }
 
-            media_control.Run().map_err(|e| {
-                error!("failed to run graph: {e}");
-                running.store(false, Ordering::Relaxed);
-                format!("failed to run graph: {e}")
+            Ok(())
+        }
+    }
+
+    /// Run a built graph, blocking until `running` is cleared. The graph is
+    /// left for [`teardown_graph`] to stop.
+    unsafe fn run_until_stopped(
+        components: &GraphComponents,
+        quirk: Option<quirks::QuirkProfile>,
+        running: &AtomicBool,
+        logged_path: &str,
+        actual_width: u32,
+        actual_height: u32,
+    ) -> Result<(), String> {
+        // 10. Accept frames BEFORE running the graph to avoid dropping
+        //     the first few frames
+        running.store(true, Ordering::Relaxed);
+
+        let media_control: IMediaControl = components.graph.cast().map_err(|e| {
+            error!("failed to get IMediaControl: {e}");
+            format!("failed to get IMediaControl: {e}")
+        })?;
+
+        // Some virtual cameras (e.g. OBS, issues #4929 and #8057) don't
+        // handle reference clock timing correctly. Remove the clock so the
+        // NullRenderer delivers every sample immediately instead of
+        // scheduling by timestamp.
+        if let Some(q) = quirk.filter(|q| q.disable_reference_clock) {
+            let media_filter: IMediaFilter = components.graph.cast().map_err(|e| {
+                error!("failed to get IMediaFilter: {e}");
+                format!("failed to get IMediaFilter: {e}")
             })?;
+            media_filter
+                .SetSyncSource(None)
+                .map_err(|e| format!("SetSyncSource(NULL) failed: {e}"))?;
+            info!("disabled reference clock for {}", q.label);
+        }
+
+        media_control.Run().map_err(|e| {
+            error!("failed to run graph: {e}");
+            running.store(false, Ordering::Relaxed);
+            format!("failed to run graph: {e}")
+        })?;
+
+        info!("capture graph running for {logged_path} at {actual_width}x{actual_height}");
+
+        // 11. Block until stopped
+        while running.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        Ok(())
+    }
+
+    /// Everything a built capture graph holds on to, so it can be torn down
+    /// explicitly rather than whenever the bindings happen to drop it.
+    struct GraphComponents {
+        graph: IGraphBuilder,
+        graph2: IFilterGraph2,
+        /// Source, SampleGrabber and NullRenderer, in the order they were added.
+        filters: Vec<IBaseFilter>,
+        grabber: SampleGrabber,
+        /// Our own reference to the frame callback, so it outlives the
+        /// grabber's use of it; `None` once released.
+        callback: Option<FrameCallback>,
+    }
+
+    impl TeardownGraph for GraphComponents {
+        fn filter_count(&self) -> usize {
+            self.filters.len()
+        }
+
+        fn stop(&mut self) -> Result<(), String> {
+            unsafe {
+                let media_control: IMediaControl = self.graph.cast().map_err(|e| e.to_string())?;
+                media_control.Stop().map_err(|e| e.to_string())
+            }
+        }
+
+        fn clear_callback(&mut self) -> Result<(), String> {
+            let hr = unsafe { self.grabber.set_callback(std::ptr::null_mut(), 1) };
+            hr.ok().map_err(|e| e.to_string())
+        }
 
-            info!("capture graph running for {device_path} at {actual_width}x{actual_height}");
+        fn disconnect_pins(&mut self, filter: usize) -> Result<(), String> {
+            unsafe {
+                let pins = self.filters[filter]
+                    .EnumPins()
+                    .map_err(|e| format!("EnumPins failed: {e}"))?;
+                let mut pin_array = [None; 1];
+                while pins.Next(&mut pin_array, None).is_ok() {
+                    let Some(pin) = pin_array[0].take() else {
+                        break;
+                    };
+                    if pin.ConnectedTo().is_ok() {
+                        self.graph2.Disconnect(&pin).map_err(|e| e.to_string())?;
+                    }
+                }
+                Ok(())
+            }
+        }
 
-            // 11. Block until stopped
-            while running.load(Ordering::Relaxed) {
-                std::thread::sleep(std::time::Duration::from_millis(50));
+        fn remove_filter(&mut self, filter: usize) -> Result<(), String> {
+            unsafe {
+                self.graph2
+                    .RemoveFilter(&self.filters[filter])
+                    .map_err(|e| e.to_string())
             }
+        }
+
+        fn release_callback(&mut self) {
+            self.callback = None;
+        }
 
-            // 12. Cleanup
-            debug!("stopping capture graph for {device_path}");
-            if let Err(e) = media_control.Stop() {
-                warn!("IMediaControl::Stop failed: {e}");
+        fn release(self) {
+            let Self {
+                graph,
+                graph2,
+                mut filters,
+                grabber,
+                ..
+            } = self;
+            drop(grabber);
+            while let Some(filter) = filters.pop() {
+                drop(filter);
             }
+            drop(graph2);
+            drop(graph);
+        }
+    }
 
-            Ok(())
+    /// Log teardown steps that failed. A device left half torn down tends to
+    /// stay busy, so these explain a following "device in use" error.
+    fn log_teardown_failures(logged_path: &str, failures: Vec<(TeardownStep, String)>) {
+        for (step, e) in failures {
+            warn!("capture graph teardown for {logged_path}: {step:?} failed: {e}");
+        }
+    }
+
+    /// What [`discover_sources`] found on a device.
+    struct DiscoveredSources {
+        sources: Vec<VideoSource>,
+        /// The crossbar the sources route through, if they're its inputs.
+        crossbar: Option<IAMCrossbar>,
+    }
+
+    /// Enumerate the video sources of `source`, which must already be in
+    /// `graph`: a WDM device's crossbar is only added to the graph when
+    /// looked for upstream of it. Anything that can't be queried is left out
+    /// rather than failing the graph.
+    unsafe fn discover_sources(graph: &IGraphBuilder, source: &IBaseFilter) -> DiscoveredSources {
+        let pins = capture_pins(source);
+        let crossbar = find_crossbar(graph, source);
+        let layout = crossbar.as_ref().and_then(|c| read_crossbar(c));
+        let sources = list_sources(&pins, layout.as_ref());
+        debug!(
+            "found {} video source(s): {} capture pin(s), crossbar {}",
+            sources.len(),
+            pins.len(),
+            if layout.is_some() {
+                "present"
+            } else {
+                "absent"
+            }
+        );
+        let routes_crossbar = sources
+            .iter()
+            .any(|s| matches!(s.route, SourceRoute::Crossbar { .. }));
+        DiscoveredSources {
+            sources,
+            crossbar: crossbar.filter(|_| routes_crossbar),
+        }
+    }
+
+    /// Enumerate the video sources of a device without capturing from it,
+    /// for a device with no running session.
+    pub fn probe_video_sources(
+        device_path: &str,
+        friendly_name: &str,
+    ) -> Result<Vec<VideoSource>, String> {
+        unsafe {
+            let _guard = ComGuard::init()?;
+            let graph: IGraphBuilder =
+                CoCreateInstance(&CLSID_FILTER_GRAPH, None, CLSCTX_INPROC_SERVER)
+                    .map_err(|e| format!("failed to create filter graph: {e}"))?;
+            let source = find_source_filter(device_path, friendly_name)?;
+            graph
+                .AddFilter(&source, windows::core::w!("Source"))
+                .map_err(|e| format!("failed to add source filter: {e}"))?;
+            let discovered = discover_sources(&graph, &source);
+            let _ = graph.RemoveFilter(&source);
+            Ok(discovered.sources)
+        }
+    }
+
+    /// The source filter's output pins that capture video: those in the
+    /// capture category, or with no category to ask about. Preview and
+    /// still pins are left out.
+    unsafe fn capture_pins(filter: &IBaseFilter) -> Vec<OutputPin> {
+        let Ok(pin_enum) = filter.EnumPins() else {
+            return Vec::new();
+        };
+        let mut pins = Vec::new();
+        let mut pin_array = [None; 1];
+        let mut output_position = 0;
+        loop {
+            let hr = pin_enum.Next(&mut pin_array, None);
+            if hr.is_err() {
+                break;
+            }
+            let Some(pin) = pin_array[0].take() else {
+                break;
+            };
+            // PINDIR_OUTPUT = 1
+            if !pin.QueryDirection().is_ok_and(|d| d.0 == 1) {
+                continue;
+            }
+            let position = output_position;
+            output_position += 1;
+            if pin_category(&pin).is_some_and(|c| c != PIN_CATEGORY_CAPTURE) {
+                continue;
+            }
+            pins.push(OutputPin {
+                position,
+                name: pin_name(&pin),
+            });
+        }
+        pins
+    }
+
+    /// A pin's `AMPROPERTY_PIN_CATEGORY`, if it reports one.
+    unsafe fn pin_category(pin: &IPin) -> Option<GUID> {
+        let properties = pin.cast::<IKsPropertySet>().ok()?;
+        let mut category = GUID::zeroed();
+        let mut returned = 0u32;
+        properties
+            .Get(
+                &AMPROPSETID_Pin,
+                AMPROPERTY_PIN_CATEGORY.0 as u32,
+                std::ptr::null(),
+                0,
+                (&mut category as *mut GUID).cast(),
+                std::mem::size_of::<GUID>() as u32,
+                &mut returned,
+            )
+            .ok()?;
+        Some(category)
+    }
+
+    /// A pin's name, or empty if it has none.
+    unsafe fn pin_name(pin: &IPin) -> String {
+        let mut info = PIN_INFO::default();
+        if pin.QueryPinInfo(&mut info).is_err() {
+            return String::new();
+        }
+        // QueryPinInfo adds a reference to the owning filter
+        std::mem::ManuallyDrop::drop(&mut info.pFilter);
+        let len = info
+            .achName
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(info.achName.len());
+        String::from_utf16_lossy(&info.achName[..len])
+    }
+
+    /// The crossbar in front of `source`: the filter itself if it is one,
+    /// otherwise the one the capture graph builder finds upstream.
+    unsafe fn find_crossbar(graph: &IGraphBuilder, source: &IBaseFilter) -> Option<IAMCrossbar> {
+        if let Ok(crossbar) = source.cast::<IAMCrossbar>() {
+            return Some(crossbar);
+        }
+        let builder: ICaptureGraphBuilder2 =
+            CoCreateInstance(&CLSID_CaptureGraphBuilder2, None, CLSCTX_INPROC_SERVER).ok()?;
+        builder.SetFiltergraph(graph).ok()?;
+        let mut raw = std::ptr::null_mut();
+        builder
+            .FindInterface(
+                Some(&LOOK_UPSTREAM_ONLY as *const GUID),
+                None,
+                source,
+                &IAMCrossbar::IID,
+                &mut raw,
+            )
+            .ok()?;
+        (!raw.is_null()).then(|| IAMCrossbar::from_raw(raw))
+    }
+
+    /// The crossbar's video output and its inputs. The output is the one
+    /// feeding the video decoder, or failing that the first video output.
+    unsafe fn read_crossbar(crossbar: &IAMCrossbar) -> Option<Crossbar> {
+        // PhysConn_Video_VideoDecoder; audio types start at 0x1000
+        const VIDEO_DECODER: i32 = 12;
+        const FIRST_AUDIO: i32 = 0x1000;
+
+        let (mut output_count, mut input_count) = (0i32, 0i32);
+        crossbar
+            .get_PinCounts(&mut output_count, &mut input_count)
+            .ok()?;
+        let physical_type = |input: bool, index: i32| {
+            let (mut related, mut physical) = (0i32, 0i32);
+            crossbar
+                .get_CrossbarPinInfo(input, index, &mut related, &mut physical)
+                .ok()
+                .map(|()| physical)
+        };
+        let outputs: Vec<(i32, i32)> = (0..output_count)
+            .filter_map(|i| physical_type(false, i).map(|t| (i, t)))
+            .collect();
+        let video_output = outputs
+            .iter()
+            .find(|&&(_, t)| t == VIDEO_DECODER)
+            .or_else(|| outputs.iter().find(|&&(_, t)| t < FIRST_AUDIO))
+            .map(|&(i, _)| i)?;
+        let inputs = (0..input_count)
+            .filter_map(|index| {
+                Some(CrossbarInput {
+                    index,
+                    physical_type: physical_type(true, index)?,
+                    routable: crossbar.CanRoute(video_output, index).is_ok(),
+                })
+            })
+            .collect();
+        Some(Crossbar {
+            video_output,
+            inputs,
+        })
+    }
+
+    /// The output pin at `position` among a filter's output pins.
+    unsafe fn output_pin_at(filter: &IBaseFilter, position: usize) -> Result<IPin, String> {
+        let pin_enum = filter
+            .EnumPins()
+            .map_err(|e| format!("EnumPins failed: {e}"))?;
+        let mut pin_array = [None; 1];
+        let mut output_position = 0;
+        loop {
+            let hr = pin_enum.Next(&mut pin_array, None);
+            if hr.is_err() {
+                break;
+            }
+            let Some(pin) = pin_array[0].take() else {
+                break;
+            };
+            // PINDIR_OUTPUT = 1
+            if !pin.QueryDirection().is_ok_and(|d| d.0 == 1) {
+                continue;
+            }
+            if output_position == position {
+                return Ok(pin);
+            }
+            output_position += 1;
         }
+        Err(format!("no output pin at position {position}"))
     }
 
     /// Find an unconnected pin on a filter by direction.
@@ -1164,103 +2050,362 @@ pub mod directshow {
     }
 }
 
+/// A step of tearing down a capture graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeardownStep {
+    Stop,
+    ClearCallback,
+    DisconnectPins(usize),
+    RemoveFilter(usize),
+    ReleaseCallback,
+    Release,
+}
+
+/// A built capture graph, as far as tearing it down is concerned. Filters are
+/// indexed in the order they were added.
+pub trait TeardownGraph {
+    fn filter_count(&self) -> usize;
+    /// Stop the graph running.
+    fn stop(&mut self) -> Result<(), String>;
+    /// Unregister the frame callback from the grabber.
+    fn clear_callback(&mut self) -> Result<(), String>;
+    /// Disconnect every connected pin on a filter.
+    fn disconnect_pins(&mut self, filter: usize) -> Result<(), String>;
+    /// Remove a filter from the graph.
+    fn remove_filter(&mut self, filter: usize) -> Result<(), String>;
+    /// Drop our own reference to the frame callback.
+    fn release_callback(&mut self);
+    /// Release the graph and filter interfaces.
+    fn release(self);
+}
+
+/// Tear a capture graph down explicitly, so the driver frees the device
+/// straight away instead of when the interfaces happen to be released.
+///
+/// The graph is stopped, the callback unregistered so no frame arrives
+/// mid-teardown, every pin disconnected, and the filters removed from the
+/// last added to the first before anything is released. Our reference to the
+/// callback is dropped only once the grabber has been told to let go of its
+/// own. A failed step doesn't stop the rest; the failures are returned for
+/// logging.
+pub fn teardown_graph<G: TeardownGraph>(mut graph: G) -> Vec<(TeardownStep, String)> {
+    let mut failures = Vec::new();
+    let mut check = |step: TeardownStep, result: Result<(), String>| {
+        if let Err(e) = result {
+            failures.push((step, e));
+        }
+    };
+
+    check(TeardownStep::Stop, graph.stop());
+    check(TeardownStep::ClearCallback, graph.clear_callback());
+    let filters = graph.filter_count();
+    for filter in (0..filters).rev() {
+        check(
+            TeardownStep::DisconnectPins(filter),
+            graph.disconnect_pins(filter),
+        );
+    }
+    for filter in (0..filters).rev() {
+        check(
+            TeardownStep::RemoveFilter(filter),
+            graph.remove_filter(filter),
+        );
+    }
+    graph.release_callback();
+    graph.release();
+    failures
+}
+
 /// Returns `true` if the friendly name looks like an OBS Virtual Camera.
 ///
-/// OBS Virtual Camera lies about supporting RGB24 format — `SetFormat()`
-/// returns `S_OK` for anything but it only delivers NV12 frames reliably.
-/// When detected, the capture graph should request NV12 directly instead
-/// of relying on DirectShow's colour converter + RGB24.
+/// Thin wrapper over the quirks table; the capture graph consults the
+/// full [`QuirkProfile`](crate::preview::quirks::QuirkProfile) instead.
 pub fn is_obs_virtual_camera(friendly_name: &str) -> bool {
-    let lower = friendly_name.to_ascii_lowercase();
-    lower.contains("obs") && lower.contains("virtual")
+    crate::preview::quirks::quirk_profile_for(friendly_name)
+        .is_some_and(|q| q.label == "OBS Virtual Camera")
 }
 
 /// Convert BGR24 bottom-up data to RGB24 top-down.
 ///
 /// DirectShow delivers frames in BGR colour order with rows stored
-/// bottom-to-top. This function flips rows vertically and swaps
-/// blue/red channels.
-pub fn convert_bgr_bottom_up_to_rgb(bgr: &[u8], width: usize, height: usize) -> Vec<u8> {
-    let stride = width * 3;
-    let expected = stride * height;
-    if bgr.len() < expected {
-        return Vec::new();
+/// bottom-to-top, each `stride` bytes apart. This function flips rows
+/// vertically, swaps blue/red channels and drops any row padding.
+pub fn convert_bgr_bottom_up_to_rgb(
+    bgr: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+) -> Vec<u8> {
+    let mut rgb = Vec::new();
+    convert_bgr_bottom_up_to_rgb_into(bgr, width, height, stride, &mut rgb);
+    rgb
+}
+
+/// [`convert_bgr_bottom_up_to_rgb`] into `rgb`, reusing its allocation.
+pub fn convert_bgr_bottom_up_to_rgb_into(
+    bgr: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    rgb: &mut Vec<u8>,
+) {
+    convert_bgr_rows(bgr, width, height, stride, true, rgb);
+}
+
+/// Convert BGR24 top-down data (a negative `biHeight`) to RGB24.
+///
+/// Like [`convert_bgr_bottom_up_to_rgb`] without the vertical flip.
+pub fn convert_bgr_top_down_to_rgb(
+    bgr: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+) -> Vec<u8> {
+    let mut rgb = Vec::new();
+    convert_bgr_top_down_to_rgb_into(bgr, width, height, stride, &mut rgb);
+    rgb
+}
+
+/// [`convert_bgr_top_down_to_rgb`] into `rgb`, reusing its allocation.
+pub fn convert_bgr_top_down_to_rgb_into(
+    bgr: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    rgb: &mut Vec<u8>,
+) {
+    convert_bgr_rows(bgr, width, height, stride, false, rgb);
+}
+
+/// Copy BGR24 rows `stride` bytes apart into `rgb` as tightly packed RGB24.
+///
+/// Leaves `rgb` empty if `bgr` is too short. The last row may omit its
+/// padding.
+fn convert_bgr_rows(
+    bgr: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    bottom_up: bool,
+    rgb: &mut Vec<u8>,
+) {
+    rgb.clear();
+    let row_len = width * 3;
+    if width == 0 || height == 0 || stride < row_len {
+        return;
+    }
+    let needed = stride
+        .checked_mul(height - 1)
+        .and_then(|rows| rows.checked_add(row_len));
+    if !needed.is_some_and(|needed| bgr.len() >= needed) {
+        return;
     }
 
-    let mut rgb = vec![0u8; expected];
-    for y in 0..height {
-        let src_row = &bgr[(height - 1 - y) * stride..(height - y) * stride];
-        let dst_row = &mut rgb[y * stride..(y + 1) * stride];
-        for x in 0..width {
-            dst_row[x * 3] = src_row[x * 3 + 2]; // R
-            dst_row[x * 3 + 1] = src_row[x * 3 + 1]; // G
-            dst_row[x * 3 + 2] = src_row[x * 3]; // B
+    rgb.resize(row_len * height, 0);
+    for (y, dst_row) in rgb.chunks_exact_mut(row_len).enumerate() {
+        let src_y = if bottom_up { height - 1 - y } else { y };
+        let src_row = &bgr[src_y * stride..src_y * stride + row_len];
+        for (dst, src) in dst_row.chunks_exact_mut(3).zip(src_row.chunks_exact(3)) {
+            dst[0] = src[2]; // R
+            dst[1] = src[1]; // G
+            dst[2] = src[0]; // B
         }
     }
-    rgb
+}
+
+/// Frames with fewer pixels than this are converted on the calling thread;
+/// for smaller ones, starting threads costs more than it saves.
+const PARALLEL_MIN_PIXELS: usize = 640 * 480;
+
+/// Most threads one frame's conversion is split across. Several cameras
+/// convert at once, each on its own capture thread.
+const MAX_CONVERT_THREADS: usize = 4;
+
+/// Fill `rgb`, `width` pixels by `height` rows, by calling
+/// `convert(first_row, band)` on bands of rows, in parallel for frames of
+/// at least [`PARALLEL_MIN_PIXELS`]. Bands have an even number of rows,
+/// except the last, so neither a YUY2 pixel pair of an odd width nor an
+/// NV12 chroma row straddles two of them.
+fn convert_row_bands(
+    rgb: &mut [u8],
+    width: usize,
+    height: usize,
+    convert: impl Fn(usize, &mut [u8]) + Sync,
+) {
+    let threads = if width * height < PARALLEL_MIN_PIXELS {
+        1
+    } else {
+        std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(MAX_CONVERT_THREADS)
+    };
+    convert_in_bands(rgb, width, height, threads, convert);
+}
+
+/// [`convert_row_bands`] across `threads` threads. The helper threads' CPU
+/// time is recorded as offloaded by the calling thread, so the capture
+/// callback's timer still counts it.
+fn convert_in_bands(
+    rgb: &mut [u8],
+    width: usize,
+    height: usize,
+    threads: usize,
+    convert: impl Fn(usize, &mut [u8]) + Sync,
+) {
+    if threads <= 1 {
+        convert(0, rgb);
+        return;
+    }
+
+    let band_rows = height.div_ceil(threads).next_multiple_of(2);
+    let convert = &convert;
+    let helpers: Duration = std::thread::scope(|scope| {
+        let mut bands = rgb.chunks_mut(band_rows * width * 3).enumerate();
+        let first = bands.next();
+        let helpers: Vec<_> = bands
+            .map(|(i, band)| {
+                scope.spawn(move || {
+                    let timer = CpuTimer::start();
+                    convert(i * band_rows, band);
+                    timer.elapsed()
+                })
+            })
+            .collect();
+        // The calling thread takes the first band rather than idling
+        if let Some((_, band)) = first {
+            convert(0, band);
+        }
+        helpers
+            .into_iter()
+            .map(|helper| {
+                helper
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .sum()
+    });
+    record_offloaded(helpers);
 }
 
 /// Convert YUY2 (YUYV) packed data to RGB24.
 ///
 /// YUY2 stores two pixels per 4-byte macro-pixel: [Y0, U, Y1, V].
-/// Uses BT.601 conversion with fixed-point integer arithmetic (<<8)
-/// for performance on the DirectShow capture thread. Width must be even.
-pub fn convert_yuy2_to_rgb(yuy2: &[u8], width: usize, height: usize) -> Vec<u8> {
+/// `colour` picks the matrix and range; the maths is fixed-point integer
+/// arithmetic for performance on the DirectShow capture thread, and large
+/// frames are converted a band of rows per thread. Width must be even.
+pub fn convert_yuy2_to_rgb(
+    yuy2: &[u8],
+    width: usize,
+    height: usize,
+    colour: ColourSpace,
+) -> Vec<u8> {
+    let mut rgb = Vec::new();
+    convert_yuy2_to_rgb_into(yuy2, width, height, colour, &mut rgb);
+    rgb
+}
+
+/// [`convert_yuy2_to_rgb`] into `rgb`, reusing its allocation. Leaves it
+/// empty if `yuy2` is too short.
+pub fn convert_yuy2_to_rgb_into(
+    yuy2: &[u8],
+    width: usize,
+    height: usize,
+    colour: ColourSpace,
+    rgb: &mut Vec<u8>,
+) {
+    rgb.clear();
     let expected = width * height * 2;
     if yuy2.len() < expected || width == 0 || height == 0 {
-        return Vec::new();
+        return;
     }
 
-    let mut rgb = vec![0u8; width * height * 3];
-    for i in 0..(width * height / 2) {
-        let y0 = yuy2[i * 4] as i32;
-        let u = yuy2[i * 4 + 1] as i32 - 128;
-        let y1 = yuy2[i * 4 + 2] as i32;
-        let v = yuy2[i * 4 + 3] as i32 - 128;
-
-        let base = i * 6;
-        rgb[base] = ((y0 * 256 + 359 * v) >> 8).clamp(0, 255) as u8;
-        rgb[base + 1] = ((y0 * 256 - 88 * u - 183 * v) >> 8).clamp(0, 255) as u8;
-        rgb[base + 2] = ((y0 * 256 + 454 * u) >> 8).clamp(0, 255) as u8;
-        rgb[base + 3] = ((y1 * 256 + 359 * v) >> 8).clamp(0, 255) as u8;
-        rgb[base + 4] = ((y1 * 256 - 88 * u - 183 * v) >> 8).clamp(0, 255) as u8;
-        rgb[base + 5] = ((y1 * 256 + 454 * u) >> 8).clamp(0, 255) as u8;
-    }
-    rgb
+    let coefficients = colour.coefficients();
+    rgb.resize(width * height * 3, 0);
+    convert_row_bands(rgb, width, height, |first_row, band| {
+        let start = first_row * width * 2;
+        let src = &yuy2[start..start + band.len() / 3 * 2];
+        for (dst, src) in band.chunks_exact_mut(6).zip(src.chunks_exact(4)) {
+            let [y0, u, y1, v] = [src[0], src[1], src[2], src[3]];
+            dst[..3].copy_from_slice(&coefficients.to_rgb(y0, u, v));
+            dst[3..].copy_from_slice(&coefficients.to_rgb(y1, u, v));
+        }
+    });
 }
 
 /// Convert NV12 planar data to RGB24.
 ///
 /// NV12 stores a full-resolution Y plane followed by an interleaved UV plane
 /// at half resolution in both dimensions (4:2:0 subsampling). Each 2x2 block
-/// of pixels shares one U,V pair. Uses BT.601 conversion with fixed-point
-/// integer arithmetic (<<8) for performance on the DirectShow capture thread.
-pub fn convert_nv12_to_rgb(nv12: &[u8], width: usize, height: usize) -> Vec<u8> {
-    let expected = width * height * 3 / 2;
+/// of pixels shares one U,V pair; an odd last row has a chroma row of its
+/// own. `colour` picks the matrix and range; the maths is fixed-point
+/// integer arithmetic for performance on the DirectShow capture thread, and
+/// large frames are converted a band of rows per thread.
+pub fn convert_nv12_to_rgb(
+    nv12: &[u8],
+    width: usize,
+    height: usize,
+    colour: ColourSpace,
+) -> Vec<u8> {
+    let mut rgb = Vec::new();
+    convert_nv12_to_rgb_into(nv12, width, height, colour, &mut rgb);
+    rgb
+}
+
+/// [`convert_nv12_to_rgb`] into `rgb`, reusing its allocation. Leaves it
+/// empty if `nv12` is too short.
+pub fn convert_nv12_to_rgb_into(
+    nv12: &[u8],
+    width: usize,
+    height: usize,
+    colour: ColourSpace,
+    rgb: &mut Vec<u8>,
+) {
+    rgb.clear();
+    let expected = width * height + width * height.div_ceil(2);
     if nv12.len() < expected || width == 0 || height == 0 {
-        return Vec::new();
+        return;
     }
 
     let y_plane = &nv12[..width * height];
     let uv_plane = &nv12[width * height..];
 
-    let mut rgb = vec![0u8; width * height * 3];
-
-    for row in 0..height {
-        for col in 0..width {
-            let y = y_plane[row * width + col] as i32;
-            let uv_index = (row / 2) * width + (col / 2) * 2;
-            let u = uv_plane[uv_index] as i32 - 128;
-            let v = uv_plane[uv_index + 1] as i32 - 128;
-
-            let base = (row * width + col) * 3;
-            rgb[base] = ((y * 256 + 359 * v) >> 8).clamp(0, 255) as u8;
-            rgb[base + 1] = ((y * 256 - 88 * u - 183 * v) >> 8).clamp(0, 255) as u8;
-            rgb[base + 2] = ((y * 256 + 454 * u) >> 8).clamp(0, 255) as u8;
+    let coefficients = colour.coefficients();
+    rgb.resize(width * height * 3, 0);
+    convert_row_bands(rgb, width, height, |first_row, band| {
+        for (row, dst_row) in (first_row..).zip(band.chunks_exact_mut(width * 3)) {
+            let y_row = &y_plane[row * width..(row + 1) * width];
+            // Runs on into the next chroma row, as the last pixel of an odd
+            // width reads one byte past its own
+            let uv_row = &uv_plane[(row / 2) * width..];
+            for ((dst, ys), uv) in dst_row
+                .chunks_mut(6)
+                .zip(y_row.chunks(2))
+                .zip(uv_row.chunks_exact(2))
+            {
+                for (dst, &y) in dst.chunks_exact_mut(3).zip(ys) {
+                    dst.copy_from_slice(&coefficients.to_rgb(y, uv[0], uv[1]));
+                }
+            }
         }
-    }
+    });
+}
 
-    rgb
+/// Decode an MJPG frame to RGB24.
+///
+/// Each MJPG sample is a complete JPEG. Fails for a corrupt or truncated
+/// one, or one whose size isn't the negotiated `width`x`height`, so the
+/// frame can be dropped rather than delivered at the wrong size.
+pub fn convert_mjpg_to_rgb(mjpg: &[u8], width: usize, height: usize) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory_with_format(mjpg, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("failed to decode MJPG frame: {e}"))?
+        .into_rgb8();
+    let (decoded_width, decoded_height) = image.dimensions();
+    if (decoded_width as usize, decoded_height as usize) != (width, height) {
+        return Err(format!(
+            "MJPG frame is {decoded_width}x{decoded_height}, expected {width}x{height}"
+        ));
+    }
+    Ok(image.into_raw())
 }
 
 #[cfg(test)]
@@ -1269,6 +2414,153 @@ mod tests {
     use crate::preview::capture::{Frame, FrameBuffer};
     use std::sync::Arc;
 
+    /// Records teardown calls in order, failing the steps it's told to.
+    struct MockGraph {
+        filters: usize,
+        steps: Arc<parking_lot::Mutex<Vec<TeardownStep>>>,
+        failing: Vec<TeardownStep>,
+    }
+
+    impl MockGraph {
+        fn record(&self, step: TeardownStep) -> Result<(), String> {
+            self.steps.lock().push(step);
+            if self.failing.contains(&step) {
+                Err(format!("{step:?} refused"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl TeardownGraph for MockGraph {
+        fn filter_count(&self) -> usize {
+            self.filters
+        }
+
+        fn stop(&mut self) -> Result<(), String> {
+            self.record(TeardownStep::Stop)
+        }
+
+        fn clear_callback(&mut self) -> Result<(), String> {
+            self.record(TeardownStep::ClearCallback)
+        }
+
+        fn disconnect_pins(&mut self, filter: usize) -> Result<(), String> {
+            self.record(TeardownStep::DisconnectPins(filter))
+        }
+
+        fn remove_filter(&mut self, filter: usize) -> Result<(), String> {
+            self.record(TeardownStep::RemoveFilter(filter))
+        }
+
+        fn release_callback(&mut self) {
+            let _ = self.record(TeardownStep::ReleaseCallback);
+        }
+
+        fn release(self) {
+            let _ = self.record(TeardownStep::Release);
+        }
+    }
+
+    fn tear_down(failing: Vec<TeardownStep>) -> (Vec<TeardownStep>, Vec<(TeardownStep, String)>) {
+        let steps = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let failures = teardown_graph(MockGraph {
+            filters: 3,
+            steps: Arc::clone(&steps),
+            failing,
+        });
+        let steps = steps.lock().clone();
+        (steps, failures)
+    }
+
+    #[test]
+    fn work_on_helper_threads_counts_towards_the_callers_timer() {
+        const SPIN: Duration = Duration::from_millis(100);
+        let (width, height) = (640, 480);
+        let mut rgb = vec![0u8; width * height * 3];
+
+        let timer = CpuTimer::start();
+        convert_in_bands(&mut rgb, width, height, 4, |first_row, band| {
+            // Busy, so it's CPU time wherever the OS reports that
+            if first_row > 0 {
+                let started = std::time::Instant::now();
+                while started.elapsed() < SPIN {
+                    std::hint::black_box(&band);
+                }
+            }
+            band.fill(1);
+        });
+
+        assert!(rgb.iter().all(|&b| b == 1));
+        // Three helper bands, each busy for SPIN
+        assert!(timer.elapsed() >= SPIN * 2, "{:?}", timer.elapsed());
+    }
+
+    #[test]
+    fn teardown_runs_steps_in_order() {
+        use TeardownStep::*;
+
+        let (steps, failures) = tear_down(vec![]);
+
+        assert_eq!(
+            steps,
+            [
+                Stop,
+                ClearCallback,
+                DisconnectPins(2),
+                DisconnectPins(1),
+                DisconnectPins(0),
+                RemoveFilter(2),
+                RemoveFilter(1),
+                RemoveFilter(0),
+                ReleaseCallback,
+                Release,
+            ]
+        );
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn teardown_carries_on_past_failed_steps() {
+        use TeardownStep::*;
+
+        let (steps, failures) = tear_down(vec![Stop, DisconnectPins(1), RemoveFilter(0)]);
+
+        assert_eq!(steps.len(), 10);
+        assert_eq!(steps.last(), Some(&Release));
+        let failed: Vec<_> = failures.iter().map(|(step, _)| *step).collect();
+        assert_eq!(failed, [Stop, DisconnectPins(1), RemoveFilter(0)]);
+        assert_eq!(failures[0].1, "Stop refused");
+    }
+
+    #[test]
+    fn callback_is_cleared_before_any_pin_is_disconnected() {
+        let (steps, _) = tear_down(vec![]);
+        let cleared = steps
+            .iter()
+            .position(|s| *s == TeardownStep::ClearCallback)
+            .unwrap();
+        let first_disconnect = steps
+            .iter()
+            .position(|s| matches!(s, TeardownStep::DisconnectPins(_)))
+            .unwrap();
+        assert!(cleared < first_disconnect);
+    }
+
+    #[test]
+    fn every_pin_is_disconnected_before_any_filter_is_removed() {
+        let (steps, _) = tear_down(vec![]);
+        let last_disconnect = steps
+            .iter()
+            .rposition(|s| matches!(s, TeardownStep::DisconnectPins(_)))
+            .unwrap();
+        let first_removal = steps
+            .iter()
+            .position(|s| matches!(s, TeardownStep::RemoveFilter(_)))
+            .unwrap();
+        assert!(last_disconnect < first_removal);
+    }
+
     #[test]
     fn converts_bgr_bottom_up_to_rgb_top_down() {
         // 2x2 BGR24 bottom-up image
@@ -1286,7 +2578,7 @@ mod tests {
         bgr[8] = 255;
         bgr[11] = 255;
 
-        let rgb = convert_bgr_bottom_up_to_rgb(&bgr, width, height);
+        let rgb = convert_bgr_bottom_up_to_rgb(&bgr, width, height, stride);
 
         // After flip: output row 0 = input row 1 (top of image)
         // BGR(0,0,255) -> RGB(255,0,0) = red
@@ -1303,14 +2595,14 @@ mod tests {
 
     #[test]
     fn handles_undersized_buffer_gracefully() {
-        let result = convert_bgr_bottom_up_to_rgb(&[0u8; 5], 2, 2);
+        let result = convert_bgr_bottom_up_to_rgb(&[0u8; 5], 2, 2, 6);
         assert!(result.is_empty());
     }
 
     #[test]
     fn handles_1x1_pixel() {
         let bgr = vec![100u8, 150, 200]; // B=100, G=150, R=200
-        let rgb = convert_bgr_bottom_up_to_rgb(&bgr, 1, 1);
+        let rgb = convert_bgr_bottom_up_to_rgb(&bgr, 1, 1, 3);
         assert_eq!(rgb, vec![200, 150, 100]); // R=200, G=150, B=100
     }
 
@@ -1319,13 +2611,14 @@ mod tests {
         let buffer = Arc::new(FrameBuffer::new(3));
 
         let bgr = vec![50u8, 100, 150]; // B=50, G=100, R=150
-        let rgb = convert_bgr_bottom_up_to_rgb(&bgr, 1, 1);
+        let rgb = convert_bgr_bottom_up_to_rgb(&bgr, 1, 1, 3);
 
         buffer.push(Frame {
             data: rgb,
             width: 1,
             height: 1,
             timestamp_us: 42,
+            device_timestamp_us: 42,
         });
 
         let frame = buffer.latest().unwrap();
@@ -1335,15 +2628,100 @@ mod tests {
 
     #[test]
     fn empty_input_returns_empty() {
-        let result = convert_bgr_bottom_up_to_rgb(&[], 0, 0);
+        let result = convert_bgr_bottom_up_to_rgb(&[], 0, 0, 0);
         assert!(result.is_empty());
     }
 
+    /// A bottom-up BGR24 frame whose rows are padded to `stride`, with padding
+    /// bytes set to 0xEE so they show up if read as pixels.
+    fn padded_bgr_fixture(width: usize, height: usize, stride: usize) -> Vec<u8> {
+        let mut bgr = vec![0xEEu8; stride * height];
+        for row in 0..height {
+            for x in 0..width {
+                let offset = row * stride + x * 3;
+                bgr[offset] = (x % 200) as u8; // B
+                bgr[offset + 1] = row as u8; // G
+                bgr[offset + 2] = (x % 7) as u8; // R
+            }
+        }
+        bgr
+    }
+
+    #[test]
+    fn converts_padded_bgr_rows_at_odd_widths() {
+        let height = 3;
+        for width in [1365usize, 1366, 1367] {
+            let stride = crate::preview::quirks::bgr24_stride(width as u32);
+            assert_eq!(stride % 4, 0);
+            assert!(stride > width * 3);
+            let bgr = padded_bgr_fixture(width, height, stride);
+
+            let rgb = convert_bgr_bottom_up_to_rgb(&bgr, width, height, stride);
+
+            assert_eq!(rgb.len(), width * height * 3, "width {width}");
+            for y in 0..height {
+                let src_row = height - 1 - y;
+                for x in 0..width {
+                    let px = &rgb[(y * width + x) * 3..][..3];
+                    assert_eq!(
+                        px,
+                        [(x % 7) as u8, src_row as u8, (x % 200) as u8],
+                        "width {width}, pixel ({x}, {y})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn converts_padded_top_down_bgr_rows() {
+        let (width, height) = (1366, 2);
+        let stride = crate::preview::quirks::bgr24_stride(width as u32);
+        let bgr = padded_bgr_fixture(width, height, stride);
+
+        let rgb = convert_bgr_top_down_to_rgb(&bgr, width, height, stride);
+
+        assert_eq!(rgb.len(), width * height * 3);
+        assert!(!rgb.contains(&0xEE));
+        assert_eq!(&rgb[..3], [0, 0, 0]);
+        assert_eq!(&rgb[width * 3..][..3], [0, 1, 0]);
+    }
+
+    #[test]
+    fn padded_bgr_conversion_stays_in_bounds() {
+        for width in [1365usize, 1366, 1367] {
+            let height = 2;
+            let stride = crate::preview::quirks::bgr24_stride(width as u32);
+            let bgr = padded_bgr_fixture(width, height, stride);
+
+            // The final row's padding may be missing.
+            let unpadded_tail = &bgr[..stride * (height - 1) + width * 3];
+            assert_eq!(
+                convert_bgr_bottom_up_to_rgb(unpadded_tail, width, height, stride).len(),
+                width * height * 3
+            );
+            assert_eq!(
+                convert_bgr_top_down_to_rgb(unpadded_tail, width, height, stride).len(),
+                width * height * 3
+            );
+
+            // Anything shorter is rejected rather than read past the end.
+            let truncated = &unpadded_tail[..unpadded_tail.len() - 1];
+            assert!(convert_bgr_bottom_up_to_rgb(truncated, width, height, stride).is_empty());
+            assert!(convert_bgr_top_down_to_rgb(truncated, width, height, stride).is_empty());
+        }
+    }
+
+    #[test]
+    fn rejects_stride_shorter_than_a_row() {
+        assert!(convert_bgr_bottom_up_to_rgb(&[0u8; 64], 4, 2, 8).is_empty());
+    }
+
     #[test]
     fn converts_yuy2_white_pixel_pair() {
         // White in YUY2: Y=235, U=128, V=128 (no chroma)
         let yuy2 = vec![235, 128, 235, 128];
-        let rgb = convert_yuy2_to_rgb(&yuy2, 2, 1);
+        let rgb = convert_yuy2_to_rgb(&yuy2, 2, 1, ColourSpace::BT601_FULL);
         assert_eq!(rgb.len(), 6);
         // Y=235, U=0, V=0 => R=235, G=235, B=235
         assert_eq!(rgb[0], 235);
@@ -1358,19 +2736,19 @@ mod tests {
     fn converts_yuy2_black_pixel_pair() {
         // Black in YUY2: Y=0, U=128, V=128
         let yuy2 = vec![0, 128, 0, 128];
-        let rgb = convert_yuy2_to_rgb(&yuy2, 2, 1);
+        let rgb = convert_yuy2_to_rgb(&yuy2, 2, 1, ColourSpace::BT601_FULL);
         assert_eq!(rgb, vec![0, 0, 0, 0, 0, 0]);
     }
 
     #[test]
     fn yuy2_undersized_buffer_returns_empty() {
-        let result = convert_yuy2_to_rgb(&[0u8; 3], 2, 1);
+        let result = convert_yuy2_to_rgb(&[0u8; 3], 2, 1, ColourSpace::BT601_FULL);
         assert!(result.is_empty());
     }
 
     #[test]
     fn yuy2_zero_dimensions_returns_empty() {
-        let result = convert_yuy2_to_rgb(&[], 0, 0);
+        let result = convert_yuy2_to_rgb(&[], 0, 0, ColourSpace::BT601_FULL);
         assert!(result.is_empty());
     }
 
@@ -1381,7 +2759,7 @@ mod tests {
             128, 128, 128, 128, // row 0: grey pair
             128, 128, 128, 128, // row 1: grey pair
         ];
-        let rgb = convert_yuy2_to_rgb(&yuy2, 2, 2);
+        let rgb = convert_yuy2_to_rgb(&yuy2, 2, 2, ColourSpace::BT601_FULL);
         assert_eq!(rgb.len(), 2 * 2 * 3); // 12 bytes
     }
 
@@ -1393,7 +2771,7 @@ mod tests {
             128, 128, 128, 128, // Y plane (2x2)
             128, 128, // UV plane (1 pair for the 2x2 block)
         ];
-        let rgb = convert_nv12_to_rgb(&nv12, 2, 2);
+        let rgb = convert_nv12_to_rgb(&nv12, 2, 2, ColourSpace::BT601_FULL);
         assert_eq!(rgb.len(), 2 * 2 * 3);
         // Y=128, U=0, V=0 => R=128, G=128, B=128
         for pixel in rgb.chunks(3) {
@@ -1404,7 +2782,7 @@ mod tests {
     #[test]
     fn nv12_undersized_buffer_returns_empty() {
         // NV12 for 2x2 should be 6 bytes (4 Y + 2 UV), pass only 5
-        let result = convert_nv12_to_rgb(&[0u8; 5], 2, 2);
+        let result = convert_nv12_to_rgb(&[0u8; 5], 2, 2, ColourSpace::BT601_FULL);
         assert!(result.is_empty());
     }
 
@@ -1414,7 +2792,7 @@ mod tests {
         // All grey: Y=200, U=128, V=128
         let mut nv12 = vec![200u8; 8]; // Y plane
         nv12.extend_from_slice(&[128, 128, 128, 128]); // UV plane
-        let rgb = convert_nv12_to_rgb(&nv12, 4, 2);
+        let rgb = convert_nv12_to_rgb(&nv12, 4, 2, ColourSpace::BT601_FULL);
         assert_eq!(rgb.len(), 4 * 2 * 3);
         // Y=200, U=0, V=0 => R=200, G=200, B=200
         for pixel in rgb.chunks(3) {
@@ -1424,7 +2802,7 @@ mod tests {
 
     #[test]
     fn nv12_zero_dimensions_returns_empty() {
-        let result = convert_nv12_to_rgb(&[], 0, 0);
+        let result = convert_nv12_to_rgb(&[], 0, 0, ColourSpace::BT601_FULL);
         assert!(result.is_empty());
     }
 
@@ -1435,7 +2813,7 @@ mod tests {
             0, 0, 0, 0, // Y plane (2x2)
             128, 128, // UV plane
         ];
-        let rgb = convert_nv12_to_rgb(&nv12, 2, 2);
+        let rgb = convert_nv12_to_rgb(&nv12, 2, 2, ColourSpace::BT601_FULL);
         assert_eq!(rgb.len(), 12);
         for pixel in rgb.chunks(3) {
             assert_eq!(pixel, [0, 0, 0]);
@@ -1449,13 +2827,232 @@ mod tests {
             235, 235, 235, 235, // Y plane (2x2)
             128, 128, // UV plane
         ];
-        let rgb = convert_nv12_to_rgb(&nv12, 2, 2);
+        let rgb = convert_nv12_to_rgb(&nv12, 2, 2, ColourSpace::BT601_FULL);
         assert_eq!(rgb.len(), 12);
         for pixel in rgb.chunks(3) {
             assert_eq!(pixel, [235, 235, 235]);
         }
     }
 
+    /// The per-pixel YUY2 conversion the banded one replaced, to check
+    /// against.
+    fn scalar_yuy2_to_rgb(
+        yuy2: &[u8],
+        width: usize,
+        height: usize,
+        colour: ColourSpace,
+    ) -> Vec<u8> {
+        let coefficients = colour.coefficients();
+        let mut rgb = vec![0u8; width * height * 3];
+        for (dst, src) in rgb
+            .chunks_exact_mut(6)
+            .zip(yuy2[..width * height * 2].chunks_exact(4))
+        {
+            dst[..3].copy_from_slice(&coefficients.to_rgb(src[0], src[1], src[3]));
+            dst[3..].copy_from_slice(&coefficients.to_rgb(src[2], src[1], src[3]));
+        }
+        rgb
+    }
+
+    /// The per-pixel NV12 conversion the banded one replaced.
+    fn scalar_nv12_to_rgb(
+        nv12: &[u8],
+        width: usize,
+        height: usize,
+        colour: ColourSpace,
+    ) -> Vec<u8> {
+        let (y_plane, uv_plane) = nv12.split_at(width * height);
+        let coefficients = colour.coefficients();
+        let mut rgb = vec![0u8; width * height * 3];
+        for row in 0..height {
+            for col in 0..width {
+                let y = y_plane[row * width + col];
+                let uv_index = (row / 2) * width + (col / 2) * 2;
+                let (u, v) = (uv_plane[uv_index], uv_plane[uv_index + 1]);
+                let base = (row * width + col) * 3;
+                rgb[base..base + 3].copy_from_slice(&coefficients.to_rgb(y, u, v));
+            }
+        }
+        rgb
+    }
+
+    /// `len` bytes of xorshift noise, the same for the same `seed`.
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    /// Sizes on both sides of the threshold for converting in parallel.
+    const SIZES: [(usize, usize); 6] = [
+        (2, 1),
+        (16, 9),
+        (320, 240),
+        (640, 480),
+        (642, 481),
+        (1920, 1080),
+    ];
+
+    const COLOURS: [ColourSpace; 2] = [ColourSpace::BT601_FULL, ColourSpace::BT709_LIMITED];
+
+    #[test]
+    fn yuy2_conversion_matches_the_scalar_one() {
+        for (i, &(width, height)) in SIZES.iter().enumerate() {
+            let yuy2 = noise(width * height * 2, i as u64);
+            for colour in COLOURS {
+                assert!(
+                    convert_yuy2_to_rgb(&yuy2, width, height, colour)
+                        == scalar_yuy2_to_rgb(&yuy2, width, height, colour),
+                    "{width}x{height} {colour:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn yuy2_conversion_of_an_odd_width_matches_the_scalar_one() {
+        // Pixel pairs run across rows; bands must not split one
+        let (width, height) = (641, 500);
+        let yuy2 = noise(width * height * 2, 7);
+        assert!(
+            convert_yuy2_to_rgb(&yuy2, width, height, ColourSpace::BT601_FULL)
+                == scalar_yuy2_to_rgb(&yuy2, width, height, ColourSpace::BT601_FULL)
+        );
+    }
+
+    #[test]
+    fn nv12_conversion_matches_the_scalar_one() {
+        let sizes = SIZES.iter().chain(&[(4, 3), (1280, 721)]);
+        for (i, &(width, height)) in sizes.enumerate() {
+            // Odd heights have a chroma row for their last row alone
+            let nv12 = noise(width * height + width * height.div_ceil(2), i as u64);
+            for colour in COLOURS {
+                assert!(
+                    convert_nv12_to_rgb(&nv12, width, height, colour)
+                        == scalar_nv12_to_rgb(&nv12, width, height, colour),
+                    "{width}x{height} {colour:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nv12_without_the_last_chroma_row_of_an_odd_height_returns_empty() {
+        // 4x3 needs two chroma rows; the scalar conversion read past the
+        // end of this one
+        assert!(convert_nv12_to_rgb(&[0u8; 18], 4, 3, ColourSpace::BT601_FULL).is_empty());
+        assert_eq!(
+            convert_nv12_to_rgb(&[0u8; 20], 4, 3, ColourSpace::BT601_FULL).len(),
+            4 * 3 * 3
+        );
+    }
+
+    #[test]
+    fn converting_into_a_buffer_reuses_it() {
+        let (width, height) = (16, 8);
+        let yuy2 = noise(width * height * 2, 3);
+        let nv12 = noise(width * height * 3 / 2, 4);
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        let ptr = rgb.as_ptr();
+
+        convert_yuy2_to_rgb_into(&yuy2, width, height, ColourSpace::BT601_FULL, &mut rgb);
+        assert_eq!(
+            rgb,
+            convert_yuy2_to_rgb(&yuy2, width, height, ColourSpace::BT601_FULL)
+        );
+        convert_nv12_to_rgb_into(&nv12, width, height, ColourSpace::BT601_FULL, &mut rgb);
+        assert_eq!(
+            rgb,
+            convert_nv12_to_rgb(&nv12, width, height, ColourSpace::BT601_FULL)
+        );
+        assert_eq!(rgb.as_ptr(), ptr);
+
+        // A frame too short leaves it empty, not holding the last one
+        convert_nv12_to_rgb_into(
+            &nv12[..10],
+            width,
+            height,
+            ColourSpace::BT601_FULL,
+            &mut rgb,
+        );
+        assert!(rgb.is_empty());
+        convert_bgr_bottom_up_to_rgb_into(&[0u8; 5], 2, 2, 6, &mut rgb);
+        assert!(rgb.is_empty());
+    }
+
+    /// Timings of 1080p conversions against the scalar ones. Run with
+    /// `cargo test --release -- --ignored --nocapture conversion_timings`.
+    #[test]
+    #[ignore = "timing, not a check"]
+    fn conversion_timings() {
+        use std::time::Instant;
+
+        let (width, height) = (1920, 1080);
+        let colour = ColourSpace::BT709_LIMITED;
+        let yuy2 = noise(width * height * 2, 1);
+        let nv12 = noise(width * height * 3 / 2, 2);
+        let time = |name: &str, convert: &dyn Fn() -> Vec<u8>| {
+            const RUNS: u32 = 50;
+            let started = Instant::now();
+            for _ in 0..RUNS {
+                std::hint::black_box(convert());
+            }
+            println!("{name}: {:?} per frame", started.elapsed() / RUNS);
+        };
+        time("yuy2 scalar", &|| {
+            scalar_yuy2_to_rgb(&yuy2, width, height, colour)
+        });
+        time("yuy2", &|| {
+            convert_yuy2_to_rgb(&yuy2, width, height, colour)
+        });
+        time("nv12 scalar", &|| {
+            scalar_nv12_to_rgb(&nv12, width, height, colour)
+        });
+        time("nv12", &|| {
+            convert_nv12_to_rgb(&nv12, width, height, colour)
+        });
+    }
+
+    /// A `width`x`height` JPEG of one flat colour.
+    fn flat_jpeg(width: usize, height: usize, colour: [u8; 3]) -> Vec<u8> {
+        let rgb = colour.repeat(width * height);
+        crate::preview::compress::compress_jpeg(&rgb, width as u32, height as u32, 95)
+    }
+
+    #[test]
+    fn converts_mjpg_to_rgb() {
+        let jpeg = flat_jpeg(16, 8, [200, 40, 40]);
+        let rgb = convert_mjpg_to_rgb(&jpeg, 16, 8).unwrap();
+        assert_eq!(rgb.len(), 16 * 8 * 3);
+        for pixel in rgb.chunks(3) {
+            // JPEG is lossy; a flat colour comes back within a few levels
+            for (got, want) in pixel.iter().zip([200u8, 40, 40]) {
+                assert!(got.abs_diff(want) <= 4, "{pixel:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn mjpg_of_the_wrong_size_is_rejected() {
+        let jpeg = flat_jpeg(16, 8, [0, 0, 0]);
+        let err = convert_mjpg_to_rgb(&jpeg, 32, 24).unwrap_err();
+        assert!(err.contains("16x8"), "{err}");
+    }
+
+    #[test]
+    fn corrupt_or_truncated_mjpg_is_an_error() {
+        let jpeg = flat_jpeg(16, 8, [0, 0, 0]);
+        assert!(convert_mjpg_to_rgb(&jpeg[..jpeg.len() / 3], 16, 8).is_err());
+        assert!(convert_mjpg_to_rgb(&[0xFF, 0xD8, 0x00, 0x01], 16, 8).is_err());
+        assert!(convert_mjpg_to_rgb(&[], 16, 8).is_err());
+    }
+
     #[test]
     fn detects_obs_virtual_camera() {
         assert!(is_obs_virtual_camera("OBS Virtual Camera"));
@@ -0,0 +1,190 @@
+//! Canonical identity of preview sessions.
+//!
+//! Sessions are keyed by the enumerated `DeviceId`, but callers may hold the
+//! ID in another spelling: padded with whitespace, in different case, or
+//! the camera's device path itself. Every start resolves what it was given
+//! onto the enumerated ID first, so one camera never ends up with two
+//! capture graphs under two keys.
+
+use crate::camera::types::{same_device_path, CameraDevice};
+
+/// The enumerated ID of the device `raw` names among `devices`.
+///
+/// `raw` is trimmed, then matched against each device's ID exactly, then
+/// ignoring case (if only one device matches that way), then against each
+/// device's path however it's spelt.
+pub fn canonical_device_id(raw: &str, devices: &[CameraDevice]) -> Result<String, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("device_id must not be empty".to_string());
+    }
+    if let Some(device) = devices.iter().find(|d| d.id.as_str() == trimmed) {
+        return Ok(device.id.as_str().to_string());
+    }
+
+    let by_case: Vec<&CameraDevice> = devices
+        .iter()
+        .filter(|d| d.id.as_str().eq_ignore_ascii_case(trimmed))
+        .collect();
+    match by_case.as_slice() {
+        [device] => return Ok(device.id.as_str().to_string()),
+        [] => {}
+        _ => return Err(format!("device id is ambiguous: {trimmed}")),
+    }
+
+    devices
+        .iter()
+        .find(|d| !d.device_path.is_empty() && same_device_path(&d.device_path, trimmed))
+        .map(|d| d.id.as_str().to_string())
+        .ok_or_else(|| format!("device not found: {trimmed}"))
+}
+
+/// Keys of the sessions capturing `device`: its own, and any left under
+/// another spelling of its ID or recorded with its device path. `sessions`
+/// yields each session's key and device path, where it has one.
+pub fn sessions_for_device<'a>(
+    sessions: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+    device: &CameraDevice,
+) -> Vec<String> {
+    let id = device.id.as_str();
+    sessions
+        .into_iter()
+        .filter(|(key, path)| {
+            key.trim().eq_ignore_ascii_case(id)
+                || (!device.device_path.is_empty()
+                    && path.is_some_and(|path| same_device_path(path, &device.device_path)))
+        })
+        .map(|(key, _)| key.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{DeviceId, DeviceKind};
+    use crate::preview::limits::check_capacity;
+    use std::collections::HashMap;
+
+    const BRIO_PATH: &str =
+        r"\\?\usb#vid_046d&pid_085e&mi_00#7&1a2b3c4d&0&0000#{65e8773d-8f56-11d0-a3b9-00a0c9223196}";
+
+    fn device(id: &str, path: &str) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: "Camera".to_string(),
+            device_path: path.to_string(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn known() -> Vec<CameraDevice> {
+        vec![device("046d:085e:A1", BRIO_PATH), device("obs-virtual", "")]
+    }
+
+    #[test]
+    fn device_ids_are_trimmed_onto_known_devices() {
+        let known = known();
+        assert_eq!(
+            canonical_device_id("  046d:085e:A1\n", &known).unwrap(),
+            "046d:085e:A1"
+        );
+        assert_eq!(
+            canonical_device_id("obs-virtual", &known).unwrap(),
+            "obs-virtual"
+        );
+    }
+
+    #[test]
+    fn unknown_and_empty_device_ids_are_rejected() {
+        let known = known();
+        assert_eq!(
+            canonical_device_id("046d:085e:B2", &known).unwrap_err(),
+            "device not found: 046d:085e:B2"
+        );
+        assert!(canonical_device_id("   ", &known)
+            .unwrap_err()
+            .contains("empty"));
+    }
+
+    #[test]
+    fn differently_cased_ids_resolve_to_the_enumerated_one() {
+        let known = known();
+        assert_eq!(
+            canonical_device_id("046D:085E:a1", &known).unwrap(),
+            "046d:085e:A1"
+        );
+        assert_eq!(
+            canonical_device_id("OBS-Virtual", &known).unwrap(),
+            "obs-virtual"
+        );
+    }
+
+    #[test]
+    fn exact_matches_win_over_case_insensitive_ones() {
+        let known = vec![device("cam-a", ""), device("CAM-A", "")];
+        assert_eq!(canonical_device_id("CAM-A", &known).unwrap(), "CAM-A");
+        assert!(canonical_device_id("Cam-A", &known)
+            .unwrap_err()
+            .contains("ambiguous"));
+    }
+
+    #[test]
+    fn stale_device_paths_resolve_to_the_enumerated_id() {
+        let known = known();
+        let stale = format!("{}\\GLOBAL", BRIO_PATH.to_uppercase());
+        assert_eq!(canonical_device_id(&stale, &known).unwrap(), "046d:085e:A1");
+    }
+
+    #[test]
+    fn whitespace_variants_share_one_session_slot() {
+        let known = known();
+        let mut sessions = HashMap::new();
+        for raw in [
+            "046d:085e:A1",
+            " 046d:085e:A1",
+            "046d:085e:A1\t",
+            "046D:085E:A1",
+        ] {
+            let id = canonical_device_id(raw, &known).unwrap();
+            check_capacity(&sessions, &id, 1).unwrap();
+            sessions.insert(id, ());
+        }
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn sessions_under_other_spellings_are_found() {
+        let known = known();
+        let brio = &known[0];
+        let sessions = [
+            ("046d:085e:A1", Some(BRIO_PATH)),
+            ("046D:085E:A1", Some(BRIO_PATH)),
+            (r"\\?\USB#VID_046D&PID_085E", Some(BRIO_PATH)),
+            ("obs-virtual", None),
+            ("046d:085e:B2", Some(r"\\?\usb#vid_046d&pid_085e&mi_00#8&0")),
+        ];
+        assert_eq!(
+            sessions_for_device(sessions, brio),
+            vec![
+                "046d:085e:A1".to_string(),
+                "046D:085E:A1".to_string(),
+                r"\\?\USB#VID_046D&PID_085E".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn devices_without_a_path_match_by_id_only() {
+        let known = known();
+        let obs = &known[1];
+        let sessions = [("obs-virtual", None), ("other", Some(""))];
+        assert_eq!(
+            sessions_for_device(sessions, obs),
+            vec!["obs-virtual".to_string()]
+        );
+    }
+}
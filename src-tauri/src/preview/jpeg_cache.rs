@@ -0,0 +1,560 @@
+// Size-bounded cache of JPEGs, a few entries per device.
+//
+// get_frame, get_frame_raw and get_thumbnail keep the last JPEG they produced
+// for each device so repeated polls of an unchanged frame skip compression.
+// PNG and cropped frames are kept alongside in their own slots, so a view
+// asking for one doesn't evict the full JPEG every other view is polling,
+// or vice versa. A device has one slot per format for the whole frame and
+// one for a crop, which a poll for a different crop replaces. Thumbnails
+// at a size asked for in the call likewise have a slot beside those at the
+// configured size.
+// The bytes are stored once; the base64 the string-returning commands send
+// is made from them the first time it's asked for and kept alongside.
+// Entries are accounted by size, base64 included once made: once the total
+// passes the limit, the least recently used ones are dropped. Entries for
+// devices without a session are swept out whenever something is inserted,
+// so a session that failed or was torn down without stop_preview doesn't
+// pin its last frame forever.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::compress::{FrameFormat, PixelRect};
+use super::render::Orientation;
+use super::thumbnail::ThumbnailSpec;
+
+/// Default limit on the bytes one cache may hold.
+pub const DEFAULT_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+
+/// How an image was made from its frame, besides orientation: the format it
+/// was compressed in, the part of the frame it shows and, for thumbnails,
+/// the size it was drawn at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FrameVariant {
+    pub format: FrameFormat,
+    /// Region of the oriented frame, or `None` for all of it.
+    pub crop: Option<PixelRect>,
+    /// Thumbnail size and fit asked for in the call, or `None` for the
+    /// configured size.
+    pub thumbnail: Option<ThumbnailSpec>,
+}
+
+impl FrameVariant {
+    /// Slot of a device's entries this variant is kept in.
+    fn slot(self) -> (FrameFormat, bool, bool) {
+        (self.format, self.crop.is_some(), self.thumbnail.is_some())
+    }
+}
+
+/// A JPEG, tagged with the frame sequence number and orientation it was
+/// rendered from. Holds a PNG or a crop instead when `variant` says so.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedJpeg {
+    pub sequence: u64,
+    pub orientation: Orientation,
+    pub variant: FrameVariant,
+    pub jpeg: Arc<Vec<u8>>,
+    /// Base64 of `jpeg`, once something has asked for it.
+    base64: Option<String>,
+}
+
+impl CachedJpeg {
+    pub fn new(sequence: u64, orientation: Orientation, jpeg: Arc<Vec<u8>>) -> Self {
+        Self {
+            sequence,
+            orientation,
+            variant: FrameVariant::default(),
+            jpeg,
+            base64: None,
+        }
+    }
+
+    /// The same image, cached as `variant` rather than the whole frame as
+    /// JPEG.
+    pub fn with_variant(self, variant: FrameVariant) -> Self {
+        Self { variant, ..self }
+    }
+
+    pub fn matches(&self, sequence: u64, orientation: Orientation) -> bool {
+        self.sequence == sequence && self.orientation == orientation
+    }
+
+    fn size(&self) -> usize {
+        self.jpeg.len() + self.base64.as_ref().map_or(0, String::len)
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    jpeg: CachedJpeg,
+    /// Value of the cache's clock when the entry was last inserted or hit.
+    last_used: u64,
+}
+
+/// Per-device JPEG cache with LRU eviction beyond a byte limit.
+#[derive(Debug)]
+pub struct JpegCache {
+    limit: usize,
+    total: usize,
+    clock: u64,
+    /// Entries by device ID, then slot.
+    entries: HashMap<String, HashMap<(FrameFormat, bool, bool), Entry>>,
+}
+
+impl JpegCache {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            total: 0,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Change the byte limit, evicting straight away if it shrank.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.evict_to_limit();
+    }
+
+    /// The cached image of `variant` for `device_id` if it was rendered from
+    /// `sequence` at `orientation`. A hit marks the entry as recently used.
+    pub fn get(
+        &mut self,
+        device_id: &str,
+        variant: FrameVariant,
+        sequence: u64,
+        orientation: Orientation,
+    ) -> Option<Arc<Vec<u8>>> {
+        let now = self.tick();
+        let entry = self.entries.get_mut(device_id)?.get_mut(&variant.slot())?;
+        if entry.jpeg.variant != variant || !entry.jpeg.matches(sequence, orientation) {
+            return None;
+        }
+        entry.last_used = now;
+        Some(Arc::clone(&entry.jpeg.jpeg))
+    }
+
+    /// [`get`](Self::get) as base64, encoding it on the first call for the
+    /// entry and keeping the string for later ones.
+    pub fn get_base64(
+        &mut self,
+        device_id: &str,
+        variant: FrameVariant,
+        sequence: u64,
+        orientation: Orientation,
+    ) -> Option<String> {
+        self.get(device_id, variant, sequence, orientation)?;
+        let entry = self.entries.get_mut(device_id)?.get_mut(&variant.slot())?;
+        if let Some(base64) = &entry.jpeg.base64 {
+            return Some(base64.clone());
+        }
+        let base64 = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            &*entry.jpeg.jpeg,
+        );
+        self.total += base64.len();
+        entry.jpeg.base64 = Some(base64.clone());
+        self.evict_to_limit();
+        Some(base64)
+    }
+
+    /// Cache `jpeg` for `device_id`, replacing its previous entry in the
+    /// same slot, then evict least recently used entries until the total
+    /// fits the limit.
+    ///
+    /// A JPEG larger than the whole limit isn't kept.
+    pub fn insert(&mut self, device_id: &str, jpeg: CachedJpeg) {
+        let slot = jpeg.variant.slot();
+        self.remove_slot(device_id, slot);
+        if jpeg.size() > self.limit {
+            return;
+        }
+        let last_used = self.tick();
+        self.total += jpeg.size();
+        self.entries
+            .entry(device_id.to_string())
+            .or_default()
+            .insert(slot, Entry { jpeg, last_used });
+        self.evict_to_limit();
+    }
+
+    /// Drop every entry for `device_id`.
+    pub fn remove(&mut self, device_id: &str) {
+        if let Some(slots) = self.entries.remove(device_id) {
+            self.total -= slots.values().map(|e| e.jpeg.size()).sum::<usize>();
+        }
+    }
+
+    /// Drop the entry in `device_id`'s `slot`, if any.
+    fn remove_slot(&mut self, device_id: &str, slot: (FrameFormat, bool, bool)) {
+        let Some(slots) = self.entries.get_mut(device_id) else {
+            return;
+        };
+        if let Some(entry) = slots.remove(&slot) {
+            self.total -= entry.jpeg.size();
+        }
+        if slots.is_empty() {
+            self.entries.remove(device_id);
+        }
+    }
+
+    /// Drop every entry whose device `is_live` rejects.
+    pub fn retain(&mut self, mut is_live: impl FnMut(&str) -> bool) {
+        let orphans: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|id| !is_live(id))
+            .cloned()
+            .collect();
+        for id in orphans {
+            self.remove(&id);
+        }
+    }
+
+    /// Most bytes the cache may hold.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Bytes currently held.
+    pub fn total_bytes(&self) -> usize {
+        self.total
+    }
+
+    /// Bytes held for `device_id`, across its slots.
+    pub fn bytes_for(&self, device_id: &str) -> usize {
+        self.entries.get(device_id).map_or(0, |slots| {
+            slots.values().map(|entry| entry.jpeg.size()).sum()
+        })
+    }
+
+    /// Entries held, counting each slot of a device separately.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn evict_to_limit(&mut self) {
+        while self.total > self.limit {
+            let Some((oldest, slot)) = self
+                .entries
+                .iter()
+                .flat_map(|(id, slots)| {
+                    slots
+                        .iter()
+                        .map(move |(slot, entry)| (id, *slot, entry.last_used))
+                })
+                .min_by_key(|&(_, _, last_used)| last_used)
+                .map(|(id, slot, _)| (id.clone(), slot))
+            else {
+                return;
+            };
+            self.remove_slot(&oldest, slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::render::Rotation;
+
+    const PNG: FrameVariant = FrameVariant {
+        format: FrameFormat::Png,
+        crop: None,
+        thumbnail: None,
+    };
+
+    fn crop(x: u32) -> FrameVariant {
+        FrameVariant {
+            format: FrameFormat::Jpeg,
+            crop: Some(PixelRect {
+                x,
+                y: 0,
+                width: 10,
+                height: 10,
+            }),
+            thumbnail: None,
+        }
+    }
+
+    /// A JPEG of `size` bytes rendered from `sequence`.
+    fn jpeg(sequence: u64, size: usize) -> CachedJpeg {
+        CachedJpeg::new(sequence, Orientation::default(), Arc::new(vec![0xAB; size]))
+    }
+
+    fn hit(cache: &mut JpegCache, device_id: &str, sequence: u64) -> bool {
+        cache
+            .get(
+                device_id,
+                FrameVariant::default(),
+                sequence,
+                Orientation::default(),
+            )
+            .is_some()
+    }
+
+    #[test]
+    fn returns_entry_only_for_matching_frame() {
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(7, 10));
+
+        assert!(hit(&mut cache, "cam-a", 7));
+        assert!(!hit(&mut cache, "cam-a", 8));
+        assert!(!hit(&mut cache, "cam-b", 7));
+
+        let rotated = Orientation {
+            rotation: Rotation::Cw90,
+            mirror: false,
+        };
+        assert!(cache
+            .get("cam-a", FrameVariant::default(), 7, rotated)
+            .is_none());
+    }
+
+    #[test]
+    fn tracks_total_bytes_across_replace_and_remove() {
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(1, 100));
+        cache.insert("cam-b", jpeg(1, 50));
+        assert_eq!(cache.total_bytes(), 150);
+
+        cache.insert("cam-a", jpeg(2, 30));
+        assert_eq!(cache.total_bytes(), 80);
+        assert_eq!(cache.len(), 2);
+
+        assert_eq!(cache.bytes_for("cam-a"), 30);
+        assert_eq!(cache.bytes_for("cam-b"), 50);
+
+        cache.remove("cam-b");
+        cache.remove("cam-b");
+        assert_eq!(cache.total_bytes(), 30);
+        assert_eq!(cache.bytes_for("cam-b"), 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_inserted_beyond_limit() {
+        let mut cache = JpegCache::new(100);
+        cache.insert("cam-a", jpeg(1, 40));
+        cache.insert("cam-b", jpeg(1, 40));
+        cache.insert("cam-c", jpeg(1, 40));
+
+        assert!(!hit(&mut cache, "cam-a", 1));
+        assert!(hit(&mut cache, "cam-b", 1));
+        assert!(hit(&mut cache, "cam-c", 1));
+        assert_eq!(cache.total_bytes(), 80);
+    }
+
+    #[test]
+    fn hits_count_as_use() {
+        let mut cache = JpegCache::new(100);
+        cache.insert("cam-a", jpeg(1, 40));
+        cache.insert("cam-b", jpeg(1, 40));
+
+        // Reading cam-a makes cam-b the oldest
+        assert!(hit(&mut cache, "cam-a", 1));
+        cache.insert("cam-c", jpeg(1, 40));
+
+        assert!(hit(&mut cache, "cam-a", 1));
+        assert!(!hit(&mut cache, "cam-b", 1));
+    }
+
+    #[test]
+    fn evicts_as_many_entries_as_needed() {
+        let mut cache = JpegCache::new(100);
+        for id in ["cam-a", "cam-b", "cam-c", "cam-d"] {
+            cache.insert(id, jpeg(1, 25));
+        }
+        cache.insert("cam-e", jpeg(1, 70));
+
+        assert_eq!(cache.len(), 2);
+        assert!(hit(&mut cache, "cam-d", 1));
+        assert!(hit(&mut cache, "cam-e", 1));
+        assert!(cache.total_bytes() <= 100);
+    }
+
+    #[test]
+    fn does_not_keep_a_jpeg_larger_than_the_limit() {
+        let mut cache = JpegCache::new(100);
+        cache.insert("cam-a", jpeg(1, 40));
+        cache.insert("cam-b", jpeg(1, 40));
+
+        // The stale cam-a entry goes, the others stay
+        cache.insert("cam-a", jpeg(2, 150));
+        assert!(!hit(&mut cache, "cam-a", 1));
+        assert!(!hit(&mut cache, "cam-a", 2));
+        assert!(hit(&mut cache, "cam-b", 1));
+        assert_eq!(cache.total_bytes(), 40);
+    }
+
+    #[test]
+    fn shrinking_the_limit_evicts_immediately() {
+        let mut cache = JpegCache::new(DEFAULT_LIMIT_BYTES);
+        cache.insert("cam-a", jpeg(1, 60));
+        cache.insert("cam-b", jpeg(1, 60));
+
+        cache.set_limit(100);
+        assert_eq!(cache.len(), 1);
+        assert!(hit(&mut cache, "cam-b", 1));
+    }
+
+    #[test]
+    fn base64_is_made_once_and_accounted() {
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(1, 30));
+        assert_eq!(cache.total_bytes(), 30);
+
+        let base64 = cache
+            .get_base64("cam-a", FrameVariant::default(), 1, Orientation::default())
+            .unwrap();
+        assert_eq!(base64, "q6urq6ur".repeat(5));
+        assert_eq!(cache.total_bytes(), 70);
+        assert_eq!(cache.bytes_for("cam-a"), 70);
+
+        assert_eq!(
+            cache.get_base64("cam-a", FrameVariant::default(), 1, Orientation::default()),
+            Some(base64)
+        );
+        assert_eq!(cache.total_bytes(), 70);
+        assert_eq!(
+            cache.get_base64("cam-a", FrameVariant::default(), 2, Orientation::default()),
+            None
+        );
+
+        cache.remove("cam-a");
+        assert_eq!(cache.total_bytes(), 0);
+    }
+
+    #[test]
+    fn base64_that_overflows_the_limit_evicts_older_entries() {
+        let mut cache = JpegCache::new(100);
+        cache.insert("cam-a", jpeg(1, 30));
+        cache.insert("cam-b", jpeg(1, 30));
+
+        // cam-b's 40 bytes of base64 take the total to 100, then cam-a's to 140
+        cache
+            .get_base64("cam-b", FrameVariant::default(), 1, Orientation::default())
+            .unwrap();
+        cache
+            .get_base64("cam-a", FrameVariant::default(), 1, Orientation::default())
+            .unwrap();
+        assert!(!hit(&mut cache, "cam-b", 1));
+        assert!(hit(&mut cache, "cam-a", 1));
+        assert_eq!(cache.total_bytes(), 70);
+    }
+
+    #[test]
+    fn formats_are_cached_side_by_side() {
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(1, 10));
+        cache.insert("cam-a", jpeg(1, 40).with_variant(PNG));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.bytes_for("cam-a"), 50);
+
+        let get = |cache: &mut JpegCache, variant| {
+            cache
+                .get("cam-a", variant, 1, Orientation::default())
+                .map(|image| image.len())
+        };
+        assert_eq!(get(&mut cache, FrameVariant::default()), Some(10));
+        assert_eq!(get(&mut cache, PNG), Some(40));
+
+        // A new JPEG replaces only the JPEG
+        cache.insert("cam-a", jpeg(2, 20));
+        assert_eq!(get(&mut cache, PNG), Some(40));
+        assert_eq!(cache.total_bytes(), 60);
+
+        cache.remove("cam-a");
+        assert!(cache.is_empty());
+        assert_eq!(cache.total_bytes(), 0);
+    }
+
+    #[test]
+    fn crops_are_cached_beside_the_whole_frame() {
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(1, 10));
+        cache.insert("cam-a", jpeg(1, 20).with_variant(crop(0)));
+
+        let get = |cache: &mut JpegCache, variant| {
+            cache
+                .get("cam-a", variant, 1, Orientation::default())
+                .map(|image| image.len())
+        };
+        assert_eq!(get(&mut cache, FrameVariant::default()), Some(10));
+        assert_eq!(get(&mut cache, crop(0)), Some(20));
+        // Same slot, different crop
+        assert_eq!(get(&mut cache, crop(5)), None);
+
+        // A new crop replaces the old one, not the whole frame
+        cache.insert("cam-a", jpeg(1, 30).with_variant(crop(5)));
+        assert_eq!(get(&mut cache, crop(0)), None);
+        assert_eq!(get(&mut cache, crop(5)), Some(30));
+        assert_eq!(get(&mut cache, FrameVariant::default()), Some(10));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.total_bytes(), 40);
+    }
+
+    #[test]
+    fn sized_thumbnails_are_cached_beside_configured_ones() {
+        use crate::preview::thumbnail::ThumbnailFit;
+
+        let sized = |width: u32, fit: ThumbnailFit| FrameVariant {
+            thumbnail: Some(ThumbnailSpec::new(width, 180, fit).unwrap()),
+            ..FrameVariant::default()
+        };
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(1, 10));
+        let cover = sized(320, ThumbnailFit::Cover);
+        cache.insert("cam-a", jpeg(1, 20).with_variant(cover));
+
+        let get = |cache: &mut JpegCache, variant| {
+            cache
+                .get("cam-a", variant, 1, Orientation::default())
+                .map(|image| image.len())
+        };
+        assert_eq!(get(&mut cache, FrameVariant::default()), Some(10));
+        assert_eq!(get(&mut cache, cover), Some(20));
+        // Same slot, but another size or fit isn't served
+        assert_eq!(get(&mut cache, sized(240, ThumbnailFit::Cover)), None);
+        assert_eq!(get(&mut cache, sized(320, ThumbnailFit::Contain)), None);
+    }
+
+    #[test]
+    fn eviction_drops_the_oldest_format_first() {
+        let mut cache = JpegCache::new(100);
+        cache.insert("cam-a", jpeg(1, 40).with_variant(PNG));
+        cache.insert("cam-a", jpeg(1, 40));
+        cache.insert("cam-b", jpeg(1, 40));
+
+        assert!(cache.get("cam-a", PNG, 1, Orientation::default()).is_none());
+        assert!(hit(&mut cache, "cam-a", 1));
+        assert!(hit(&mut cache, "cam-b", 1));
+        assert_eq!(cache.total_bytes(), 80);
+    }
+
+    #[test]
+    fn retain_removes_orphaned_devices() {
+        let mut cache = JpegCache::new(1000);
+        cache.insert("cam-a", jpeg(1, 10));
+        cache.insert("cam-b", jpeg(1, 20));
+        cache.insert("cam-c", jpeg(1, 30));
+
+        cache.retain(|id| id != "cam-b");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.total_bytes(), 40);
+        assert!(!hit(&mut cache, "cam-b", 1));
+
+        cache.retain(|_| false);
+        assert!(cache.is_empty());
+        assert_eq!(cache.total_bytes(), 0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Guardrails against runaway preview sessions.
+//!
+//! Every session holds a camera open, a capture graph, an encode worker and
+//! a few frames of RGB, so a frontend bug that keeps starting sessions can
+//! grind the machine to a halt. Starts are capped at a configurable number
+//! of concurrent sessions; see `identity` for how session keys are kept
+//! to one per camera.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::preview::payload::PayloadSnapshot;
+use crate::settings::persist::SaveHealth;
+
+/// Concurrent sessions allowed unless the settings file says otherwise.
+pub const DEFAULT_MAX_SESSIONS: usize = 16;
+
+/// Whether a session for `device_id` may start alongside `sessions`, given
+/// at most `max` may run at once. Replacing a device's own session is
+/// always allowed.
+pub fn check_capacity<S>(
+    sessions: &HashMap<String, S>,
+    device_id: &str,
+    max: usize,
+) -> Result<(), String> {
+    if sessions.contains_key(device_id) || sessions.len() < max {
+        return Ok(());
+    }
+    Err(format!(
+        "Too many previews: {} are already running (limit {max}). Stop one before starting another",
+        sessions.len()
+    ))
+}
+
+/// Resources held by preview sessions, for the diagnostics view.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub session_count: usize,
+    pub running_sessions: usize,
+    pub max_sessions: usize,
+    /// Bytes of frames held in session frame buffers.
+    pub frame_buffer_bytes: usize,
+    /// Bytes held by the frame and thumbnail JPEG caches.
+    pub cache_bytes: usize,
+    /// Threads in the whole process, where the OS reports it.
+    pub process_threads: Option<usize>,
+    /// When settings were last written and how many changes are waiting.
+    pub settings: SaveHealth,
+    /// Sizes of the frames sent to the webview.
+    pub frame_payloads: PayloadSnapshot,
+}
+
+/// Threads in this process, from `/proc/self/status`.
+#[cfg(target_os = "linux")]
+pub fn process_thread_count() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+/// Threads in this process, from a Toolhelp snapshot.
+#[cfg(target_os = "windows")]
+pub fn process_thread_count() -> Option<usize> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+
+    let pid = std::process::id();
+    // SAFETY: the snapshot handle is closed below and `entry` is a
+    // correctly sized THREADENTRY32 that outlives every call using it.
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0).ok()?;
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+        let mut count = 0;
+        let mut more = Thread32First(snapshot, &mut entry).is_ok();
+        while more {
+            if entry.th32OwnerProcessID == pid {
+                count += 1;
+            }
+            more = Thread32Next(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+        Some(count)
+    }
+}
+
+/// Other platforms don't report a thread count.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn process_thread_count() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_beyond_the_limit_are_rejected() {
+        let sessions: HashMap<String, ()> = (0..3).map(|i| (format!("cam-{i}"), ())).collect();
+        let err = check_capacity(&sessions, "cam-3", 3).unwrap_err();
+        assert!(err.contains("limit 3"), "{err}");
+        assert!(check_capacity(&sessions, "cam-3", 4).is_ok());
+    }
+
+    #[test]
+    fn restarting_a_session_at_the_limit_is_allowed() {
+        let sessions: HashMap<String, ()> = (0..3).map(|i| (format!("cam-{i}"), ())).collect();
+        assert!(check_capacity(&sessions, "cam-1", 3).is_ok());
+    }
+
+    #[test]
+    fn stopping_a_session_frees_headroom_immediately() {
+        let mut sessions: HashMap<String, ()> = (0..3).map(|i| (format!("cam-{i}"), ())).collect();
+        assert!(check_capacity(&sessions, "cam-3", 3).is_err());
+
+        sessions.remove("cam-0");
+        assert!(check_capacity(&sessions, "cam-3", 3).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn the_process_thread_count_is_reported() {
+        assert!(process_thread_count().is_some_and(|n| n >= 1));
+    }
+}
@@ -1,9 +1,39 @@
 // Preview pipeline — frame capture, compression, and IPC delivery.
 
 pub mod capture;
+pub mod colour;
+pub mod com_object;
+#[cfg(feature = "app")]
 pub mod commands;
 pub mod compress;
 pub mod encode_worker;
+pub mod font;
 pub mod gpu;
 pub mod graph;
+pub mod identity;
+pub mod jpeg_cache;
+pub mod limits;
 pub mod mf_jpeg;
+pub mod mode;
+pub mod output;
+pub mod payload;
+pub mod placeholder;
+pub mod pool;
+pub mod quality;
+pub mod quirks;
+pub mod render;
+pub mod shm;
+pub mod snapshot;
+pub mod sources;
+pub mod subscription;
+pub mod tap;
+pub mod thumbnail;
+pub mod timelapse;
+#[cfg(feature = "app")]
+pub mod timelapse_task;
+pub mod timestamp;
+pub mod transform;
+pub mod warm;
+pub mod watermark;
+pub mod zebra;
+pub mod zoom;
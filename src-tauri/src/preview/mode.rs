@@ -0,0 +1,361 @@
+// Capture session modes.
+//
+// Grid views only need thumbnails, so auto-started sessions run in
+// `ThumbnailOnly` mode: the capture graph requests the smallest format that
+// still makes a decent thumbnail rather than the one nearest the requested
+// size, and frames are neither kept nor JPEG-encoded beyond what thumbnails
+// need. Opening a camera's full preview upgrades its session to `Full`.
+//
+// Capability selection is pure; the graph feeds it what the source pin
+// advertises.
+
+use serde::{Deserialize, Serialize};
+
+/// What a capture session is for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionMode {
+    /// Full previews via `get_frame`, at the requested resolution.
+    #[default]
+    Full,
+    /// Thumbnails only, at the smallest adequate resolution.
+    ThumbnailOnly,
+}
+
+impl SessionMode {
+    /// Raw frames the session's ring buffer keeps. Thumbnails only ever read
+    /// the latest.
+    pub fn frame_buffer_capacity(self) -> usize {
+        match self {
+            Self::Full => 3,
+            Self::ThumbnailOnly => 1,
+        }
+    }
+
+    /// Whether frames are JPEG-encoded at full size for `get_frame`.
+    pub fn encodes_frames(self) -> bool {
+        self == Self::Full
+    }
+}
+
+/// Smallest frame size a thumbnail-only session requests.
+pub const THUMBNAIL_MIN_SIZE: (u32, u32) = (320, 240);
+
+/// Turn a pixel format name into the FourCC the source pin reports in its
+/// subtype, or `None` if it is neither. Takes four characters ("MJPG") or
+/// the eight hex digits device capabilities show for subtypes that aren't
+/// printable ("E436EB7D").
+pub fn parse_fourcc(format: &str) -> Option<u32> {
+    let bytes = format.as_bytes();
+    match bytes.len() {
+        4 if bytes.iter().all(u8::is_ascii_graphic) => {
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        }
+        8 => u32::from_str_radix(format, 16).ok(),
+        _ => None,
+    }
+}
+
+/// Name of a subtype FourCC, the inverse of [`parse_fourcc`]: four
+/// characters when they're printable, eight hex digits otherwise.
+pub fn fourcc_name(fourcc: u32) -> String {
+    let bytes = fourcc.to_le_bytes();
+    if bytes.iter().all(u8::is_ascii_graphic) {
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        format!("{fourcc:08X}")
+    }
+}
+
+/// Frame size and rate a full preview asks the camera for. The graph
+/// settles on the nearest capability it has.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PreviewFormat {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+}
+
+/// Frame size and pixel format a capture graph settled on, which may not be
+/// what it asked for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiatedFormat {
+    pub width: u32,
+    pub height: u32,
+    /// FourCC name, e.g. `MJPG`, or hex digits for subtypes without one.
+    pub pixel_format: String,
+}
+
+/// A format advertised by the source pin, as far as selection cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capability {
+    pub width: u32,
+    pub height: u32,
+    /// Whether the frame-interval range covers the requested rate.
+    pub supports_fps: bool,
+    /// First four bytes of the media subtype GUID.
+    pub fourcc: u32,
+}
+
+impl Capability {
+    fn pixels(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    /// Whether the capability is in pixel format `fourcc`, ignoring ASCII
+    /// case ("mjpg" matches "MJPG").
+    pub fn has_fourcc(&self, fourcc: u32) -> bool {
+        self.fourcc
+            .to_le_bytes()
+            .eq_ignore_ascii_case(&fourcc.to_le_bytes())
+    }
+}
+
+/// Index of the capability a session in `mode` should request, or `None`
+/// if none has a usable size.
+///
+/// `Full` picks the capability whose pixel count is nearest
+/// `width`x`height`. `ThumbnailOnly` ignores the requested size and picks the
+/// smallest capability at least [`THUMBNAIL_MIN_SIZE`] in both dimensions,
+/// falling back to the one nearest that size. Either way, ties go to a
+/// capability that supports the requested frame rate, then to the first.
+///
+/// A `Full` session asking for pixel format `fourcc` only considers
+/// capabilities in that format, unless there are none with a usable size;
+/// then it picks from all of them as if none was asked for.
+/// `ThumbnailOnly` always picks by size.
+pub fn select_capability(
+    caps: &[Capability],
+    width: u32,
+    height: u32,
+    fourcc: Option<u32>,
+    mode: SessionMode,
+) -> Option<usize> {
+    match mode {
+        SessionMode::Full => fourcc
+            .and_then(|fourcc| nearest_where(caps, width, height, |cap| cap.has_fourcc(fourcc)))
+            .or_else(|| nearest(caps, width, height)),
+        SessionMode::ThumbnailOnly => smallest_adequate(caps)
+            .or_else(|| nearest(caps, THUMBNAIL_MIN_SIZE.0, THUMBNAIL_MIN_SIZE.1)),
+    }
+}
+
+fn usable(caps: &[Capability]) -> impl Iterator<Item = (usize, &Capability)> {
+    caps.iter()
+        .enumerate()
+        .filter(|(_, cap)| cap.width > 0 && cap.height > 0)
+}
+
+fn nearest(caps: &[Capability], width: u32, height: u32) -> Option<usize> {
+    nearest_where(caps, width, height, |_| true)
+}
+
+fn nearest_where(
+    caps: &[Capability],
+    width: u32,
+    height: u32,
+    keep: impl Fn(&Capability) -> bool,
+) -> Option<usize> {
+    let target = u64::from(width) * u64::from(height);
+    usable(caps)
+        .filter(|(_, cap)| keep(cap))
+        .min_by_key(|(_, cap)| (cap.pixels().abs_diff(target), !cap.supports_fps))
+        .map(|(index, _)| index)
+}
+
+fn smallest_adequate(caps: &[Capability]) -> Option<usize> {
+    let (min_width, min_height) = THUMBNAIL_MIN_SIZE;
+    usable(caps)
+        .filter(|(_, cap)| cap.width >= min_width && cap.height >= min_height)
+        .min_by_key(|(_, cap)| (cap.pixels(), !cap.supports_fps))
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YUY2: u32 = u32::from_le_bytes(*b"YUY2");
+    const MJPG: u32 = u32::from_le_bytes(*b"MJPG");
+
+    fn cap(width: u32, height: u32) -> Capability {
+        Capability {
+            width,
+            height,
+            supports_fps: true,
+            fourcc: YUY2,
+        }
+    }
+
+    fn mjpg(width: u32, height: u32) -> Capability {
+        Capability {
+            fourcc: MJPG,
+            ..cap(width, height)
+        }
+    }
+
+    fn without_fps(width: u32, height: u32) -> Capability {
+        Capability {
+            supports_fps: false,
+            ..cap(width, height)
+        }
+    }
+
+    #[test]
+    fn selects_by_mode() {
+        let webcam = [
+            cap(1920, 1080),
+            cap(1280, 720),
+            cap(640, 480),
+            cap(320, 240),
+        ];
+        let no_small_mode = [cap(3840, 2160), cap(1920, 1080), cap(1280, 720)];
+        let tiny_modes = [cap(160, 120), cap(176, 144), cap(640, 480)];
+        let only_tiny = [cap(160, 120), cap(176, 144)];
+        let wide_only = [cap(1280, 200), cap(400, 300), cap(1920, 1080)];
+        let fps_tie = [without_fps(640, 480), cap(640, 480)];
+        let unusable = [cap(0, 0), cap(640, 0)];
+
+        // (capabilities, requested size, mode, expected index)
+        type Case<'a> = (&'a [Capability], (u32, u32), SessionMode, Option<usize>);
+        let cases: &[Case] = &[
+            (&webcam, (640, 480), SessionMode::Full, Some(2)),
+            (&webcam, (1920, 1080), SessionMode::Full, Some(0)),
+            (&webcam, (640, 480), SessionMode::ThumbnailOnly, Some(3)),
+            // Nothing near the minimum: both take the smallest there is.
+            (&no_small_mode, (640, 480), SessionMode::Full, Some(2)),
+            (
+                &no_small_mode,
+                (640, 480),
+                SessionMode::ThumbnailOnly,
+                Some(2),
+            ),
+            (&tiny_modes, (640, 480), SessionMode::Full, Some(2)),
+            // Modes below the minimum are never picked while one reaches it.
+            (&tiny_modes, (640, 480), SessionMode::ThumbnailOnly, Some(2)),
+            // Nothing big enough: fall back to nearest 320x240.
+            (&only_tiny, (640, 480), SessionMode::ThumbnailOnly, Some(1)),
+            (&only_tiny, (640, 480), SessionMode::Full, Some(1)),
+            // Both dimensions must reach the minimum.
+            (&wide_only, (640, 480), SessionMode::ThumbnailOnly, Some(1)),
+            (&fps_tie, (640, 480), SessionMode::Full, Some(1)),
+            (&fps_tie, (640, 480), SessionMode::ThumbnailOnly, Some(1)),
+            (&unusable, (640, 480), SessionMode::Full, None),
+            (&unusable, (640, 480), SessionMode::ThumbnailOnly, None),
+            (&[], (640, 480), SessionMode::Full, None),
+        ];
+
+        for (i, &(caps, (width, height), mode, expected)) in cases.iter().enumerate() {
+            assert_eq!(
+                select_capability(caps, width, height, None, mode),
+                expected,
+                "case {i}: {mode:?} {width}x{height}"
+            );
+        }
+    }
+
+    #[test]
+    fn equal_candidates_keep_the_first() {
+        let caps = [cap(640, 480), cap(480, 640)];
+        assert_eq!(
+            select_capability(&caps, 640, 480, None, SessionMode::Full),
+            Some(0)
+        );
+        assert_eq!(
+            select_capability(&caps, 640, 480, None, SessionMode::ThumbnailOnly),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn prefers_the_requested_pixel_format() {
+        let caps = [cap(1920, 1080), mjpg(1920, 1080), mjpg(640, 480)];
+        assert_eq!(
+            select_capability(&caps, 1920, 1080, None, SessionMode::Full),
+            Some(0)
+        );
+        assert_eq!(
+            select_capability(&caps, 1920, 1080, Some(MJPG), SessionMode::Full),
+            Some(1)
+        );
+        // The format wins over a nearer size in another format.
+        let caps = [cap(1920, 1080), mjpg(1280, 720)];
+        assert_eq!(
+            select_capability(&caps, 1920, 1080, Some(MJPG), SessionMode::Full),
+            Some(1)
+        );
+        // Case doesn't matter.
+        assert_eq!(
+            select_capability(&caps, 1920, 1080, parse_fourcc("mjpg"), SessionMode::Full),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn missing_pixel_format_falls_back_to_size() {
+        let caps = [cap(1920, 1080), cap(640, 480), mjpg(0, 0)];
+        let nv12 = parse_fourcc("NV12");
+        assert_eq!(
+            select_capability(&caps, 640, 480, nv12, SessionMode::Full),
+            Some(1)
+        );
+        // A format with no usable size counts as missing.
+        assert_eq!(
+            select_capability(&caps, 640, 480, Some(MJPG), SessionMode::Full),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn thumbnails_ignore_the_pixel_format() {
+        let caps = [cap(320, 240), mjpg(640, 480)];
+        assert_eq!(
+            select_capability(&caps, 1920, 1080, Some(MJPG), SessionMode::ThumbnailOnly),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn parses_pixel_formats() {
+        assert_eq!(parse_fourcc("MJPG"), Some(MJPG));
+        assert_eq!(parse_fourcc("YUY2"), Some(YUY2));
+        // Subtypes without a printable FourCC, as capabilities list them
+        assert_eq!(parse_fourcc("E436EB7D"), Some(0xE436_EB7D));
+        assert_eq!(parse_fourcc("e436eb7d"), Some(0xE436_EB7D));
+        assert_eq!(parse_fourcc(""), None);
+        assert_eq!(parse_fourcc("MJP"), None);
+        assert_eq!(parse_fourcc("MJ G"), None);
+        assert_eq!(parse_fourcc("NOTHEX!!"), None);
+    }
+
+    #[test]
+    fn names_pixel_formats() {
+        assert_eq!(fourcc_name(MJPG), "MJPG");
+        assert_eq!(fourcc_name(0xE436_EB7D), "E436EB7D");
+        for name in ["YUY2", "NV12", "E436EB7D"] {
+            assert_eq!(parse_fourcc(name).map(fourcc_name).as_deref(), Some(name));
+        }
+    }
+
+    #[test]
+    fn negotiated_format_serialises_in_camel_case() {
+        let format = NegotiatedFormat {
+            width: 1920,
+            height: 1080,
+            pixel_format: "MJPG".to_string(),
+        };
+        let json = serde_json::to_value(&format).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"width": 1920, "height": 1080, "pixelFormat": "MJPG"})
+        );
+    }
+
+    #[test]
+    fn thumbnail_sessions_keep_one_frame_and_skip_encoding() {
+        assert_eq!(SessionMode::Full.frame_buffer_capacity(), 3);
+        assert!(SessionMode::Full.encodes_frames());
+        assert_eq!(SessionMode::ThumbnailOnly.frame_buffer_capacity(), 1);
+        assert!(!SessionMode::ThumbnailOnly.encodes_frames());
+    }
+}
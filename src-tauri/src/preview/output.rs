@@ -0,0 +1,193 @@
+//! Per-output processing: which processors each kind of output runs.
+//!
+//! A camera's frames leave the app through several outputs — the live
+//! preview, snapshots, recordings and the stream — and each can run its own
+//! ordered chain of processors on the rendered frame, so a recording can
+//! carry a watermark the preview doesn't. An output without a chain of its
+//! own runs the preview's, so configuring the preview alone changes every
+//! output alike.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::render::RenderedFrame;
+use super::watermark::{Watermark, WatermarkContext};
+
+/// Most processors in one chain.
+pub const MAX_CHAIN_LEN: usize = 8;
+
+/// Where rendered frames go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputTarget {
+    /// Live preview frames (`get_frame`).
+    Preview,
+    /// Single stills taken on request.
+    Snapshot,
+    /// Frames written to disk over time (time-lapse stills).
+    Recording,
+    /// Frames served to other applications.
+    Stream,
+}
+
+/// One step of a chain, run on the rendered RGB frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Processor {
+    Watermark(Watermark),
+}
+
+impl Processor {
+    /// Why this processor can't be used, if it can't.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Watermark(watermark) => watermark.validate(),
+        }
+    }
+
+    /// Run the processor on `frame`.
+    pub fn apply(&self, frame: &mut RenderedFrame, context: &WatermarkContext) {
+        match self {
+            Self::Watermark(watermark) => watermark.apply(frame, context),
+        }
+    }
+}
+
+/// The chains a camera's outputs run, by target.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OutputProcessors(BTreeMap<OutputTarget, Vec<Processor>>);
+
+impl OutputProcessors {
+    /// Whether no output has a chain.
+    pub fn is_default(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The chain `target` runs: its own if it has one, even an empty one,
+    /// otherwise the preview's.
+    pub fn chain(&self, target: OutputTarget) -> &[Processor] {
+        self.0
+            .get(&target)
+            .or_else(|| self.0.get(&OutputTarget::Preview))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Give `target` its own chain, or with `None` go back to running the
+    /// preview's (or nothing, for the preview itself).
+    pub fn set(&mut self, target: OutputTarget, chain: Option<Vec<Processor>>) {
+        match chain {
+            Some(chain) => self.0.insert(target, chain),
+            None => self.0.remove(&target),
+        };
+    }
+}
+
+/// Why `chain` can't be used, if it can't.
+pub fn validate_chain(chain: &[Processor]) -> Result<(), String> {
+    if chain.len() > MAX_CHAIN_LEN {
+        return Err(format!("an output runs at most {MAX_CHAIN_LEN} processors"));
+    }
+    chain.iter().try_for_each(Processor::validate)
+}
+
+/// Run each processor of `chain` on `frame` in order.
+pub fn apply_chain(chain: &[Processor], frame: &mut RenderedFrame, context: &WatermarkContext) {
+    for processor in chain {
+        processor.apply(frame, context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::watermark::Corner;
+
+    fn watermark(template: &str) -> Processor {
+        Processor::Watermark(Watermark {
+            template: template.to_string(),
+            ..Watermark::default()
+        })
+    }
+
+    #[test]
+    fn outputs_without_a_chain_run_the_previews() {
+        let mut outputs = OutputProcessors::default();
+        assert!(outputs.chain(OutputTarget::Recording).is_empty());
+
+        outputs.set(OutputTarget::Preview, Some(vec![watermark("live")]));
+        for target in [
+            OutputTarget::Preview,
+            OutputTarget::Snapshot,
+            OutputTarget::Recording,
+            OutputTarget::Stream,
+        ] {
+            assert_eq!(outputs.chain(target), [watermark("live")], "{target:?}");
+        }
+    }
+
+    #[test]
+    fn an_outputs_own_chain_wins_even_when_empty() {
+        let mut outputs = OutputProcessors::default();
+        outputs.set(OutputTarget::Preview, Some(vec![watermark("live")]));
+        outputs.set(OutputTarget::Recording, Some(vec![watermark("rec")]));
+        outputs.set(OutputTarget::Stream, Some(vec![]));
+
+        assert_eq!(outputs.chain(OutputTarget::Recording), [watermark("rec")]);
+        assert!(outputs.chain(OutputTarget::Stream).is_empty());
+        assert_eq!(outputs.chain(OutputTarget::Snapshot), [watermark("live")]);
+
+        // Clearing the override goes back to the preview's chain
+        outputs.set(OutputTarget::Stream, None);
+        assert_eq!(outputs.chain(OutputTarget::Stream), [watermark("live")]);
+    }
+
+    #[test]
+    fn only_recordings_carry_a_recording_watermark() {
+        let mut outputs = OutputProcessors::default();
+        outputs.set(OutputTarget::Recording, Some(vec![watermark("rec")]));
+
+        assert!(outputs.chain(OutputTarget::Preview).is_empty());
+        assert!(outputs.chain(OutputTarget::Snapshot).is_empty());
+        assert_eq!(outputs.chain(OutputTarget::Recording), [watermark("rec")]);
+    }
+
+    #[test]
+    fn chains_are_validated() {
+        assert!(validate_chain(&[watermark("ok")]).is_ok());
+        assert!(validate_chain(&vec![watermark("x"); MAX_CHAIN_LEN + 1]).is_err());
+        let bad = Processor::Watermark(Watermark {
+            scale: 0,
+            ..Watermark::default()
+        });
+        assert!(validate_chain(&[watermark("ok"), bad]).is_err());
+    }
+
+    #[test]
+    fn serialises_as_a_map_of_tagged_processors() {
+        let mut outputs = OutputProcessors::default();
+        outputs.set(
+            OutputTarget::Recording,
+            Some(vec![Processor::Watermark(Watermark {
+                corner: Corner::TopLeft,
+                ..Watermark::default()
+            })]),
+        );
+
+        let json = serde_json::to_value(&outputs).unwrap();
+        let step = &json["recording"][0];
+        assert_eq!(step["kind"], "watermark");
+        assert_eq!(step["template"], "{camera} {timestamp}");
+        assert_eq!(step["corner"], "topLeft");
+        assert_eq!(step["backgroundOpacity"], 50);
+
+        // Missing fields take their defaults
+        let parsed: OutputProcessors =
+            serde_json::from_str(r#"{"stream":[{"kind":"watermark"}]}"#).unwrap();
+        assert_eq!(
+            parsed.chain(OutputTarget::Stream),
+            [Processor::Watermark(Watermark::default())]
+        );
+    }
+}
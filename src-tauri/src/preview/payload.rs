@@ -0,0 +1,297 @@
+//! Size management for frames sent to the webview.
+//!
+//! Frames cross IPC as base64 strings, and a big one — a 4K frame at high
+//! quality is ~4MB encoded — stalls the webview while it's marshalled.
+//! `get_frame` keeps each response under a budget by stepping the JPEG
+//! quality down, and consumers that need the full-quality image fetch it
+//! in chunks instead. The decisions here are pure; the counters are plain
+//! atomics the command layer bumps per response.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+/// Largest single frame response by default, in bytes of base64.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Quality dropped per step while fitting a frame to the budget.
+pub const QUALITY_STEP: u8 = 10;
+
+/// Lowest quality a frame is stepped down to. A frame still over the
+/// budget here is sent anyway.
+pub const MIN_FIT_QUALITY: u8 = 30;
+
+/// Base64 characters per chunk of a chunked frame. A multiple of four, so
+/// every chunk decodes on its own.
+pub const CHUNK_CHARS: usize = 512 * 1024;
+
+/// Length of `bytes` bytes once base64-encoded with padding.
+pub fn base64_len(bytes: usize) -> usize {
+    bytes.div_ceil(3) * 4
+}
+
+/// The quality to try after `quality` failed to fit, or `None` at the floor.
+pub fn next_quality(quality: u8) -> Option<u8> {
+    (quality > MIN_FIT_QUALITY).then(|| quality.saturating_sub(QUALITY_STEP).max(MIN_FIT_QUALITY))
+}
+
+/// A frame encoded to fit the budget, or as close as it got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fitted {
+    pub jpeg: Vec<u8>,
+    pub quality: u8,
+    /// Whether the base64 of `jpeg` is within the budget.
+    pub fits: bool,
+}
+
+/// Fit a frame to `max_bytes` of base64. `first` is the frame as encoded
+/// at `quality`; while it's over the budget, `encode` is asked for it again
+/// [`QUALITY_STEP`] lower, down to [`MIN_FIT_QUALITY`].
+pub fn fit_to_budget<E>(
+    first: Vec<u8>,
+    quality: u8,
+    max_bytes: usize,
+    mut encode: impl FnMut(u8) -> Result<Vec<u8>, E>,
+) -> Result<Fitted, E> {
+    let mut fitted = Fitted {
+        jpeg: first,
+        quality,
+        fits: false,
+    };
+    loop {
+        fitted.fits = base64_len(fitted.jpeg.len()) <= max_bytes;
+        if fitted.fits {
+            return Ok(fitted);
+        }
+        let Some(next) = next_quality(fitted.quality) else {
+            return Ok(fitted);
+        };
+        fitted.jpeg = encode(next)?;
+        fitted.quality = next;
+    }
+}
+
+/// Chunks a payload of `len` characters is split into. Never zero, so an
+/// empty payload is still one (empty) chunk.
+pub fn chunk_count(len: usize) -> u32 {
+    len.div_ceil(CHUNK_CHARS).max(1) as u32
+}
+
+/// Chunk `index` of `payload`, or `None` past the end. Concatenating every
+/// chunk in order gives `payload` back.
+pub fn chunk(payload: &str, index: u32) -> Option<&str> {
+    if index >= chunk_count(payload.len()) {
+        return None;
+    }
+    let start = index as usize * CHUNK_CHARS;
+    let end = (start + CHUNK_CHARS).min(payload.len());
+    payload.get(start..end)
+}
+
+/// One chunk of a frame fetched with `get_frame_chunked`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameChunk {
+    /// Sequence number of the frame the chunks belong to. Chunks of
+    /// different frames must not be mixed.
+    pub sequence: u64,
+    pub chunk: u32,
+    pub chunk_count: u32,
+    /// Base64 slice of the JPEG.
+    pub data: String,
+    /// Set on the final chunk.
+    pub last: bool,
+}
+
+/// Counters of frame responses sent, for diagnostics.
+#[derive(Debug, Default)]
+pub struct PayloadStats {
+    responses: AtomicU64,
+    total_bytes: AtomicU64,
+    largest_bytes: AtomicU64,
+    reduced: AtomicU64,
+    over_budget: AtomicU64,
+    chunks: AtomicU64,
+}
+
+/// What [`PayloadStats`] has counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadSnapshot {
+    /// Whole-frame responses sent.
+    pub responses: u64,
+    /// Bytes of base64 those responses carried.
+    pub total_bytes: u64,
+    pub largest_bytes: u64,
+    /// Responses whose quality was stepped down to fit the budget.
+    pub reduced: u64,
+    /// Responses still over the budget at the lowest quality.
+    pub over_budget: u64,
+    /// Chunks served by `get_frame_chunked`.
+    pub chunks: u64,
+}
+
+impl PayloadStats {
+    /// Count a whole-frame response of `bytes` bytes.
+    pub fn record_response(&self, bytes: usize) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        self.total_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.largest_bytes.fetch_max(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count a frame fitted to the budget.
+    pub fn record_fit(&self, fitted: &Fitted) {
+        self.reduced.fetch_add(1, Ordering::Relaxed);
+        if !fitted.fits {
+            self.over_budget.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_chunk(&self) {
+        self.chunks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> PayloadSnapshot {
+        PayloadSnapshot {
+            responses: self.responses.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes.load(Ordering::Relaxed),
+            largest_bytes: self.largest_bytes.load(Ordering::Relaxed),
+            reduced: self.reduced.load(Ordering::Relaxed),
+            over_budget: self.over_budget.load(Ordering::Relaxed),
+            chunks: self.chunks.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    /// A stand-in encoder whose output shrinks by 40KB per quality point.
+    fn synthetic(quality: u8) -> Result<Vec<u8>, String> {
+        Ok(vec![0; quality as usize * 40 * 1024])
+    }
+
+    #[test]
+    fn base64_length_includes_padding() {
+        assert_eq!(base64_len(0), 0);
+        assert_eq!(base64_len(1), 4);
+        assert_eq!(base64_len(3), 4);
+        assert_eq!(base64_len(4), 8);
+        assert_eq!(base64_len(3 * 1024 * 1024), 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn quality_steps_down_to_the_floor() {
+        assert_eq!(next_quality(95), Some(85));
+        assert_eq!(next_quality(35), Some(MIN_FIT_QUALITY));
+        assert_eq!(next_quality(MIN_FIT_QUALITY), None);
+        assert_eq!(next_quality(10), None);
+    }
+
+    #[test]
+    fn a_frame_within_budget_is_left_alone() {
+        let first = vec![0; 1000];
+        let fitted = fit_to_budget(first.clone(), 95, DEFAULT_MAX_RESPONSE_BYTES, |_| {
+            Err::<Vec<u8>, _>("should not re-encode")
+        })
+        .unwrap();
+        assert_eq!(
+            fitted,
+            Fitted {
+                jpeg: first,
+                quality: 95,
+                fits: true
+            }
+        );
+    }
+
+    #[test]
+    fn a_big_frame_steps_quality_down_until_it_fits() {
+        // 95 is 3.8MB (~5MB as base64); 2MB of base64 holds 1.5MB, quality 38 or less
+        let mut tried = Vec::new();
+        let fitted = fit_to_budget(synthetic(95).unwrap(), 95, DEFAULT_MAX_RESPONSE_BYTES, |q| {
+            tried.push(q);
+            synthetic(q)
+        })
+        .unwrap();
+        assert_eq!(tried, [85, 75, 65, 55, 45, 35]);
+        assert_eq!(fitted.quality, 35);
+        assert!(fitted.fits);
+        assert!(base64_len(fitted.jpeg.len()) <= DEFAULT_MAX_RESPONSE_BYTES);
+    }
+
+    #[test]
+    fn a_frame_too_big_even_at_the_floor_is_sent_over_budget() {
+        let fitted = fit_to_budget(synthetic(95).unwrap(), 95, 1024, synthetic).unwrap();
+        assert_eq!(fitted.quality, MIN_FIT_QUALITY);
+        assert!(!fitted.fits);
+    }
+
+    #[test]
+    fn encode_errors_stop_the_fit() {
+        let result = fit_to_budget(vec![0; 4096], 80, 16, |_| Err("encoder failed"));
+        assert_eq!(result, Err("encoder failed"));
+    }
+
+    #[test]
+    fn chunks_reassemble_to_the_payload_and_decode_alone() {
+        let jpeg: Vec<u8> = (0..CHUNK_CHARS * 2).map(|i| (i % 251) as u8).collect();
+        let payload = base64::engine::general_purpose::STANDARD.encode(&jpeg);
+        let count = chunk_count(payload.len());
+        assert_eq!(count, 3, "2×CHUNK_CHARS bytes is 8/3 chunks of base64");
+
+        let chunks: Vec<&str> = (0..count).map(|i| chunk(&payload, i).unwrap()).collect();
+        assert_eq!(chunks.concat(), payload);
+        assert!(chunks[..2].iter().all(|c| c.len() == CHUNK_CHARS));
+        assert_eq!(chunk(&payload, count), None);
+
+        // Each chunk is whole base64 quads, so decoding piecewise works too
+        let decoded: Vec<u8> = chunks
+            .iter()
+            .flat_map(|c| base64::engine::general_purpose::STANDARD.decode(c).unwrap())
+            .collect();
+        assert_eq!(decoded, jpeg);
+    }
+
+    #[test]
+    fn small_and_empty_payloads_are_one_chunk() {
+        assert_eq!(chunk_count(0), 1);
+        assert_eq!(chunk("", 0), Some(""));
+        assert_eq!(chunk_count(10), 1);
+        assert_eq!(chunk("abcd", 0), Some("abcd"));
+        assert_eq!(chunk_count(CHUNK_CHARS), 1);
+        assert_eq!(chunk_count(CHUNK_CHARS + 1), 2);
+    }
+
+    #[test]
+    fn stats_count_sizes_reductions_and_chunks() {
+        let stats = PayloadStats::default();
+        stats.record_response(100);
+        stats.record_response(300);
+        stats.record_fit(&Fitted {
+            jpeg: Vec::new(),
+            quality: 60,
+            fits: true,
+        });
+        stats.record_fit(&Fitted {
+            jpeg: Vec::new(),
+            quality: MIN_FIT_QUALITY,
+            fits: false,
+        });
+        stats.record_chunk();
+
+        assert_eq!(
+            stats.snapshot(),
+            PayloadSnapshot {
+                responses: 2,
+                total_bytes: 400,
+                largest_bytes: 300,
+                reduced: 2,
+                over_budget: 1,
+                chunks: 1,
+            }
+        );
+    }
+}
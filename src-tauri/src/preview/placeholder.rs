@@ -0,0 +1,261 @@
+// "No signal" cards served in place of a camera's frames.
+//
+// With a camera's placeholder_on_error setting on, get_frame returns a
+// generated card naming the camera and what's wrong with it while the
+// session has failed or stopped delivering frames, instead of an error or
+// the last frozen frame. Cards are only handed to get_frame's caller; they
+// are never written into a session's buffers, so anything reading frames
+// from the session itself can't mistake one for video.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::font::{text_width, Canvas, GLYPH_HEIGHT};
+
+/// Size of a generated card.
+pub const PLACEHOLDER_SIZE: (u32, u32) = (640, 480);
+
+/// How long a running session can go without a frame before it counts as
+/// stalled.
+pub const STALL_AFTER: Duration = Duration::from_secs(3);
+
+const BACKGROUND: [u8; 3] = [24, 24, 28];
+const TEXT: [u8; 3] = [235, 235, 235];
+const LABEL_SCALE: u32 = 6;
+const NAME_SCALE: u32 = 3;
+const MARGIN: u32 = 32;
+
+/// Why a session has no picture to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalState {
+    /// Running, but no frame has arrived for `STALL_AFTER`.
+    Stalled,
+    /// The capture graph failed.
+    Error,
+}
+
+impl SignalState {
+    /// Heading drawn on the card.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Stalled => "NO SIGNAL",
+            Self::Error => "CAMERA ERROR",
+        }
+    }
+
+    fn accent(self) -> [u8; 3] {
+        match self {
+            Self::Stalled => [230, 160, 0],
+            Self::Error => [200, 40, 40],
+        }
+    }
+}
+
+/// The card to serve instead of a frame, if any.
+///
+/// `enabled` is the camera's placeholder setting; with it off the session's
+/// frames (or errors) are always passed through. `since_last_frame` is
+/// `None` until the session has delivered a frame.
+pub fn placeholder_state(
+    enabled: bool,
+    failed: bool,
+    since_last_frame: Option<Duration>,
+) -> Option<SignalState> {
+    if !enabled {
+        None
+    } else if failed {
+        Some(SignalState::Error)
+    } else if since_last_frame.is_some_and(|age| age >= STALL_AFTER) {
+        Some(SignalState::Stalled)
+    } else {
+        None
+    }
+}
+
+/// Draw the card for a camera called `name` in `state`.
+///
+/// Names too wide for the card are cut short with "...".
+pub fn render_placeholder(name: &str, state: SignalState) -> Canvas {
+    let (width, height) = PLACEHOLDER_SIZE;
+    let mut canvas = Canvas::new(width, height, BACKGROUND);
+    let accent = state.accent();
+
+    // Accent bars top and bottom
+    canvas.fill_rect(0, 0, width, MARGIN / 2, accent);
+    canvas.fill_rect(0, height - MARGIN / 2, width, MARGIN / 2, accent);
+
+    let label_height = GLYPH_HEIGHT * LABEL_SCALE;
+    let name_height = GLYPH_HEIGHT * NAME_SCALE;
+    let gap = MARGIN;
+    let top = (height - label_height - gap - name_height) / 2;
+
+    canvas.draw_text_centred(top, LABEL_SCALE, state.label(), accent);
+    canvas.draw_text_centred(
+        top + label_height + gap,
+        NAME_SCALE,
+        &fit_name(name, width - 2 * MARGIN),
+        TEXT,
+    );
+    canvas
+}
+
+/// `name`, shortened with "..." until it fits in `max_width` at the name
+/// scale.
+fn fit_name(name: &str, max_width: u32) -> String {
+    let name = name.trim();
+    if text_width(name, NAME_SCALE) <= max_width {
+        return name.to_string();
+    }
+    let mut chars: Vec<char> = name.chars().collect();
+    loop {
+        chars.pop();
+        let shortened = format!("{}...", chars.iter().collect::<String>().trim_end());
+        if chars.is_empty() || text_width(&shortened, NAME_SCALE) <= max_width {
+            return shortened;
+        }
+    }
+}
+
+struct CachedCard {
+    state: SignalState,
+    name: String,
+    base64: String,
+}
+
+/// Encoded cards per device, re-rendered only when the state or the camera
+/// name changes.
+#[derive(Default)]
+pub struct PlaceholderCache {
+    cards: HashMap<String, CachedCard>,
+}
+
+impl PlaceholderCache {
+    /// The card for `device_id`, calling `render` only if the cached one
+    /// was drawn for a different state or name.
+    pub fn get_or_render(
+        &mut self,
+        device_id: &str,
+        state: SignalState,
+        name: &str,
+        render: impl FnOnce() -> String,
+    ) -> String {
+        if let Some(card) = self.cards.get(device_id) {
+            if card.state == state && card.name == name {
+                return card.base64.clone();
+            }
+        }
+        let base64 = render();
+        self.cards.insert(
+            device_id.to_string(),
+            CachedCard {
+                state,
+                name: name.to_string(),
+                base64: base64.clone(),
+            },
+        );
+        base64
+    }
+
+    /// Drop the card for `device_id`, if any.
+    pub fn remove(&mut self, device_id: &str) {
+        self.cards.remove(device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRESH: Option<Duration> = Some(Duration::from_millis(40));
+    const OLD: Option<Duration> = Some(STALL_AFTER);
+
+    #[test]
+    fn disabled_never_substitutes() {
+        assert_eq!(placeholder_state(false, true, OLD), None);
+        assert_eq!(placeholder_state(false, false, OLD), None);
+    }
+
+    #[test]
+    fn failed_session_shows_error_card() {
+        assert_eq!(
+            placeholder_state(true, true, FRESH),
+            Some(SignalState::Error)
+        );
+        assert_eq!(
+            placeholder_state(true, true, None),
+            Some(SignalState::Error)
+        );
+    }
+
+    #[test]
+    fn stalled_after_threshold_without_frames() {
+        assert_eq!(placeholder_state(true, false, FRESH), None);
+        assert_eq!(
+            placeholder_state(true, false, OLD),
+            Some(SignalState::Stalled)
+        );
+    }
+
+    #[test]
+    fn starting_session_is_not_stalled() {
+        assert_eq!(placeholder_state(true, false, None), None);
+    }
+
+    #[test]
+    fn rendering_is_deterministic() {
+        let a = render_placeholder("Camera 2", SignalState::Stalled);
+        let b = render_placeholder("Camera 2", SignalState::Stalled);
+        assert_eq!(a, b);
+        assert_eq!((a.width, a.height), PLACEHOLDER_SIZE);
+        assert_eq!(a.data.len(), (640 * 480 * 3) as usize);
+    }
+
+    #[test]
+    fn state_and_name_change_the_card() {
+        let stalled = render_placeholder("Camera 2", SignalState::Stalled);
+        assert_ne!(stalled, render_placeholder("Camera 2", SignalState::Error));
+        assert_ne!(
+            stalled,
+            render_placeholder("Camera 3", SignalState::Stalled)
+        );
+    }
+
+    #[test]
+    fn accent_colour_follows_state() {
+        let error = render_placeholder("Cam", SignalState::Error);
+        assert_eq!(error.pixel(0, 0), SignalState::Error.accent());
+        assert_eq!(error.pixel(320, 240 - 60), BACKGROUND);
+    }
+
+    #[test]
+    fn long_names_are_shortened_to_fit() {
+        let max = PLACEHOLDER_SIZE.0 - 2 * MARGIN;
+        assert_eq!(fit_name("  Camera 2 ", max), "Camera 2");
+
+        let long = "Logitech BRIO Ultra HD Webcam for Streaming (Left)";
+        let fitted = fit_name(long, max);
+        assert!(fitted.ends_with("..."));
+        assert!(text_width(&fitted, NAME_SCALE) <= max);
+        assert!(long.starts_with(fitted.trim_end_matches("...")));
+    }
+
+    #[test]
+    fn cache_rerenders_only_on_state_or_name_change() {
+        let mut cache = PlaceholderCache::default();
+        let mut renders = 0;
+        let mut get = |cache: &mut PlaceholderCache, state, name: &str| {
+            cache.get_or_render("cam-1", state, name, || {
+                renders += 1;
+                format!("{state:?}:{name}")
+            })
+        };
+
+        assert_eq!(get(&mut cache, SignalState::Stalled, "Cam"), "Stalled:Cam");
+        assert_eq!(get(&mut cache, SignalState::Stalled, "Cam"), "Stalled:Cam");
+        assert_eq!(get(&mut cache, SignalState::Error, "Cam"), "Error:Cam");
+        assert_eq!(get(&mut cache, SignalState::Error, "Desk"), "Error:Desk");
+        cache.remove("cam-1");
+        get(&mut cache, SignalState::Error, "Desk");
+        assert_eq!(renders, 4);
+    }
+}
@@ -0,0 +1,236 @@
+//! Recycled pixel buffers for the capture path.
+//!
+//! Converting every frame into a freshly allocated multi-megabyte `Vec`
+//! keeps the allocator busy with a few cameras running. Each frame buffer
+//! has a pool that takes back the frames its ring overwrites and hands
+//! their pixel buffers to the converters for the next ones.
+//!
+//! A frame overwritten while a consumer (a tap queue, an in-flight command)
+//! still holds it is kept aside and reclaimed once the consumer lets go, so
+//! consumers never see a buffer change under them.
+//!
+//! Buffers too small for the latest frame are dropped rather than kept, so
+//! after a resolution change the pool fills up with buffers of the new size.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use super::capture::Frame;
+
+/// Buffers a pool keeps by default: enough for the one the capture
+/// callback is converting into and a few overwritten frames still held.
+pub const DEFAULT_MAX_BUFFERS: usize = 4;
+
+/// Bounded pool of pixel buffers.
+pub struct BufferPool {
+    max_buffers: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Buffers ready to be written into.
+    free: Vec<Vec<u8>>,
+    /// Overwritten frames something else still holds, oldest first.
+    lent: VecDeque<Arc<Frame>>,
+    /// Size of the latest buffer taken; smaller buffers are no use.
+    frame_len: usize,
+}
+
+impl BufferPool {
+    /// A pool holding at most `max_buffers` free buffers and waiting on at
+    /// most as many held frames.
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            max_buffers,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// An empty buffer with room for `len` bytes: a recycled one if one is
+    /// big enough, otherwise a new one. Free buffers smaller than `len` are
+    /// dropped.
+    pub fn take(&self, len: usize) -> Vec<u8> {
+        let mut inner = self.inner.lock();
+        inner.frame_len = len;
+        inner.free.retain(|buf| buf.capacity() >= len);
+        inner.reclaim(self.max_buffers);
+        match inner.free.iter().position(|buf| buf.capacity() >= len) {
+            Some(i) => {
+                let mut buf = inner.free.swap_remove(i);
+                buf.clear();
+                buf
+            }
+            None => Vec::with_capacity(len),
+        }
+    }
+
+    /// Take back a frame the ring has overwritten. Its buffer is free now
+    /// if nothing else holds the frame, otherwise once the last holder
+    /// drops it.
+    ///
+    /// Beyond `max_buffers` held frames the oldest is forgotten, and its
+    /// buffer freed as usual when its holder is done with it.
+    pub fn recycle(&self, frame: Arc<Frame>) {
+        let mut inner = self.inner.lock();
+        inner.lent.push_back(frame);
+        if inner.lent.len() > self.max_buffers {
+            inner.lent.pop_front();
+        }
+        inner.reclaim(self.max_buffers);
+    }
+
+    /// Buffers ready to be taken.
+    pub fn free_buffers(&self) -> usize {
+        self.inner.lock().free.len()
+    }
+
+    /// Overwritten frames waiting for their holders to drop them.
+    pub fn lent_frames(&self) -> usize {
+        self.inner.lock().lent.len()
+    }
+
+    /// Bytes allocated for free buffers.
+    pub fn free_bytes(&self) -> usize {
+        self.inner.lock().free.iter().map(Vec::capacity).sum()
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BUFFERS)
+    }
+}
+
+impl Inner {
+    /// Move the buffers of lent frames nothing else holds any more to the
+    /// free list, dropping those too small for the latest frame.
+    fn reclaim(&mut self, max_buffers: usize) {
+        for frame in std::mem::take(&mut self.lent) {
+            match Arc::try_unwrap(frame) {
+                Ok(frame) => {
+                    if self.free.len() < max_buffers && frame.data.capacity() >= self.frame_len {
+                        self.free.push(frame.data);
+                    }
+                }
+                Err(frame) => self.lent.push_back(frame),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(data: Vec<u8>) -> Arc<Frame> {
+        Arc::new(Frame {
+            data,
+            width: 0,
+            height: 0,
+            timestamp_us: 0,
+            device_timestamp_us: 0,
+        })
+    }
+
+    #[test]
+    fn recycled_buffers_are_taken_again() {
+        let pool = BufferPool::new(2);
+        let mut buf = pool.take(64);
+        buf.extend_from_slice(&[7; 64]);
+        let ptr = buf.as_ptr();
+
+        pool.recycle(frame(buf));
+        assert_eq!(pool.free_buffers(), 1);
+        let again = pool.take(64);
+        assert_eq!(again.as_ptr(), ptr);
+        assert!(again.is_empty());
+        assert_eq!(pool.free_buffers(), 0);
+    }
+
+    #[test]
+    fn buffers_too_small_are_dropped() {
+        let pool = BufferPool::new(2);
+        pool.recycle(frame(Vec::with_capacity(16)));
+        assert!(pool.take(64).capacity() >= 64);
+        assert_eq!(pool.free_buffers(), 0);
+
+        // Nor kept when reclaimed after the frames grew
+        pool.recycle(frame(Vec::with_capacity(16)));
+        pool.take(64);
+        assert_eq!(pool.free_buffers(), 0);
+    }
+
+    #[test]
+    fn buffers_are_reused_after_the_resolution_grows() {
+        const VGA: usize = 640 * 480 * 3;
+        const FULL_HD: usize = 1920 * 1080 * 3;
+        let pool = BufferPool::new(2);
+        let small: Vec<_> = (0..2).map(|_| pool.take(VGA)).collect();
+        for buf in small {
+            pool.recycle(frame(buf));
+        }
+        assert_eq!(pool.free_buffers(), 2);
+
+        // The camera switches to 1920x1080
+        let first = pool.take(FULL_HD);
+        assert_eq!(pool.free_buffers(), 0);
+        let mut ptr = first.as_ptr();
+        pool.recycle(frame(first));
+        for _ in 0..3 {
+            let buf = pool.take(FULL_HD);
+            assert_eq!(buf.as_ptr(), ptr);
+            ptr = buf.as_ptr();
+            pool.recycle(frame(buf));
+        }
+        assert_eq!(pool.free_buffers(), 1);
+        assert!(pool.free_bytes() >= FULL_HD);
+    }
+
+    #[test]
+    fn free_buffers_are_bounded() {
+        let pool = BufferPool::new(2);
+        for _ in 0..5 {
+            pool.recycle(frame(vec![0; 32]));
+        }
+        assert_eq!(pool.free_buffers(), 2);
+        assert_eq!(pool.free_bytes(), 64);
+    }
+
+    #[test]
+    fn held_frames_are_reclaimed_once_dropped() {
+        let pool = BufferPool::new(2);
+        let held = frame(vec![1; 32]);
+        let ptr = held.data.as_ptr();
+
+        pool.recycle(Arc::clone(&held));
+        assert_eq!(pool.free_buffers(), 0);
+        assert_eq!(pool.lent_frames(), 1);
+        // The holder still sees its pixels
+        assert_eq!(held.data, vec![1; 32]);
+
+        drop(held);
+        let reused = pool.take(32);
+        assert_eq!(reused.as_ptr(), ptr);
+        assert_eq!(pool.lent_frames(), 0);
+    }
+
+    #[test]
+    fn held_frames_are_bounded() {
+        let pool = BufferPool::new(2);
+        let held: Vec<_> = (0..5).map(|_| frame(vec![0; 8])).collect();
+        for frame in &held {
+            pool.recycle(Arc::clone(frame));
+        }
+        assert_eq!(pool.lent_frames(), 2);
+
+        drop(held);
+        // Reclaims them and takes one
+        pool.take(8);
+        assert_eq!(pool.lent_frames(), 0);
+        // Only the frames still tracked come back
+        assert_eq!(pool.free_buffers(), 1);
+    }
+}
@@ -0,0 +1,382 @@
+// Adaptive JPEG quality for on-demand frame compression.
+//
+// Encoding a 4K frame at quality 75 can take 45ms on a weak machine, which is
+// more than a 30fps poll can afford. The controller here watches how long each
+// encode takes and walks quality down (or back up) to keep encode time within
+// a per-device budget, with hysteresis so it settles instead of oscillating.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Consecutive over-budget encodes before quality is lowered.
+const STEP_DOWN_AFTER: u32 = 3;
+
+/// Consecutive encodes under the headroom threshold before quality is raised.
+const STEP_UP_AFTER: u32 = 15;
+
+/// Quality decrease per step. Larger than the increase so overload clears fast.
+const STEP_DOWN: u8 = 5;
+
+/// Quality increase per step.
+const STEP_UP: u8 = 2;
+
+/// Fraction of the budget an encode must stay under to count towards raising
+/// quality. Encodes between this and the full budget leave quality unchanged.
+const HEADROOM: f64 = 0.7;
+
+/// Encode durations kept for diagnostics.
+const RECENT_SAMPLES: usize = 16;
+
+/// Per-device JPEG quality settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QualityProfile {
+    /// Adjust quality to hold the encode budget. When off, `quality` is used as-is.
+    pub adaptive: bool,
+    /// Pinned quality when not adaptive, and the starting point when adaptive.
+    pub quality: u8,
+    /// Lowest quality adaptation may choose.
+    pub min_quality: u8,
+    /// Highest quality adaptation may choose.
+    pub max_quality: u8,
+    /// Target encode time per frame, in milliseconds.
+    pub budget_ms: u32,
+}
+
+impl Default for QualityProfile {
+    fn default() -> Self {
+        Self {
+            adaptive: true,
+            quality: 75,
+            min_quality: 40,
+            max_quality: 90,
+            budget_ms: 15,
+        }
+    }
+}
+
+impl QualityProfile {
+    /// Whether this profile matches the defaults.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check that qualities are in 1-100, the bounds are ordered and the
+    /// budget is non-zero.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("quality", self.quality),
+            ("minQuality", self.min_quality),
+            ("maxQuality", self.max_quality),
+        ] {
+            if !(1..=100).contains(&value) {
+                return Err(format!("{name} must be between 1 and 100, got {value}"));
+            }
+        }
+        if self.min_quality > self.max_quality {
+            return Err(format!(
+                "minQuality ({}) must not exceed maxQuality ({})",
+                self.min_quality, self.max_quality
+            ));
+        }
+        if self.budget_ms == 0 {
+            return Err("budgetMs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// Quality for stills, which have no encode budget to hold: the pinned
+    /// quality, or the highest adaptation may choose.
+    pub fn still_quality(&self) -> u8 {
+        if self.adaptive {
+            self.max_quality
+        } else {
+            self.quality
+        }
+    }
+}
+
+/// Chooses the JPEG quality for the next encode from measured encode times.
+#[derive(Debug, Clone)]
+pub struct AdaptiveQuality {
+    profile: QualityProfile,
+    quality: u8,
+    over_budget: u32,
+    under_headroom: u32,
+    recent: VecDeque<Duration>,
+}
+
+impl AdaptiveQuality {
+    pub fn new(profile: QualityProfile) -> Self {
+        Self {
+            profile,
+            quality: Self::initial_quality(&profile),
+            over_budget: 0,
+            under_headroom: 0,
+            recent: VecDeque::with_capacity(RECENT_SAMPLES),
+        }
+    }
+
+    fn initial_quality(profile: &QualityProfile) -> u8 {
+        if profile.adaptive {
+            profile
+                .quality
+                .clamp(profile.min_quality, profile.max_quality)
+        } else {
+            profile.quality
+        }
+    }
+
+    /// Replace the profile, restarting adaptation from its starting quality.
+    pub fn set_profile(&mut self, profile: QualityProfile) {
+        *self = Self {
+            recent: std::mem::take(&mut self.recent),
+            ..Self::new(profile)
+        };
+    }
+
+    pub fn profile(&self) -> QualityProfile {
+        self.profile
+    }
+
+    /// Quality to use for the next encode.
+    pub fn quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Record how long an encode at the current quality took, returning the
+    /// quality for the next one.
+    pub fn record(&mut self, encode_time: Duration) -> u8 {
+        if self.recent.len() == RECENT_SAMPLES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(encode_time);
+
+        if !self.profile.adaptive {
+            return self.quality;
+        }
+
+        let budget = Duration::from_millis(u64::from(self.profile.budget_ms));
+        if encode_time > budget {
+            self.over_budget += 1;
+            self.under_headroom = 0;
+        } else if encode_time.as_secs_f64() < budget.as_secs_f64() * HEADROOM {
+            self.under_headroom += 1;
+            self.over_budget = 0;
+        } else {
+            self.over_budget = 0;
+            self.under_headroom = 0;
+        }
+
+        if self.over_budget >= STEP_DOWN_AFTER {
+            self.quality = self
+                .quality
+                .saturating_sub(STEP_DOWN)
+                .max(self.profile.min_quality);
+            self.over_budget = 0;
+        } else if self.under_headroom >= STEP_UP_AFTER {
+            self.quality = self
+                .quality
+                .saturating_add(STEP_UP)
+                .min(self.profile.max_quality);
+            self.under_headroom = 0;
+        }
+
+        self.quality
+    }
+
+    /// Recent encode durations in milliseconds, oldest first.
+    pub fn recent_encode_ms(&self) -> Vec<f64> {
+        self.recent
+            .iter()
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: f64) -> Duration {
+        Duration::from_secs_f64(millis / 1000.0)
+    }
+
+    fn profile(quality: u8, min_quality: u8, max_quality: u8) -> QualityProfile {
+        QualityProfile {
+            adaptive: true,
+            quality,
+            min_quality,
+            max_quality,
+            budget_ms: 15,
+        }
+    }
+
+    /// Feed `frames` encodes whose duration is `cost(quality)`, returning the
+    /// quality after each one.
+    fn simulate(
+        controller: &mut AdaptiveQuality,
+        frames: usize,
+        cost: impl Fn(u8) -> f64,
+    ) -> Vec<u8> {
+        (0..frames)
+            .map(|_| {
+                let q = controller.quality();
+                controller.record(ms(cost(q)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn converges_below_budget_on_slow_machine_and_settles() {
+        // 4ms at q40, +0.4ms per quality step: q75 ≈ 18ms, q70 = 16ms, q65 = 14ms
+        let cost = |q: u8| 4.0 + (f64::from(q) - 40.0) * 0.4;
+        let mut controller = AdaptiveQuality::new(profile(90, 20, 95));
+
+        let history = simulate(&mut controller, 300, cost);
+
+        assert_eq!(controller.quality(), 65);
+        assert!(cost(controller.quality()) <= 15.0);
+        // Once settled it stays put
+        assert!(history[100..].iter().all(|&q| q == 65));
+    }
+
+    #[test]
+    fn climbs_to_max_on_fast_machine() {
+        let mut controller = AdaptiveQuality::new(profile(50, 30, 90));
+        simulate(&mut controller, 1000, |q| f64::from(q) * 0.05);
+        assert_eq!(controller.quality(), 90);
+    }
+
+    #[test]
+    fn never_drops_below_min_quality() {
+        let mut controller = AdaptiveQuality::new(profile(75, 60, 90));
+        let history = simulate(&mut controller, 200, |_| 45.0);
+        assert_eq!(controller.quality(), 60);
+        assert!(history.iter().all(|&q| q >= 60));
+    }
+
+    #[test]
+    fn never_rises_above_max_quality() {
+        let mut controller = AdaptiveQuality::new(profile(75, 40, 80));
+        let history = simulate(&mut controller, 500, |_| 1.0);
+        assert_eq!(controller.quality(), 80);
+        assert!(history.iter().all(|&q| q <= 80));
+    }
+
+    #[test]
+    fn starting_quality_is_clamped_into_bounds() {
+        assert_eq!(AdaptiveQuality::new(profile(95, 40, 80)).quality(), 80);
+        assert_eq!(AdaptiveQuality::new(profile(10, 40, 80)).quality(), 40);
+    }
+
+    #[test]
+    fn occasional_spikes_do_not_lower_quality() {
+        let mut controller = AdaptiveQuality::new(profile(75, 40, 90));
+        for i in 0..100 {
+            let sample = if i % 3 == 0 { 40.0 } else { 12.0 };
+            controller.record(ms(sample));
+        }
+        assert_eq!(controller.quality(), 75);
+    }
+
+    #[test]
+    fn samples_within_hysteresis_band_hold_quality() {
+        let mut controller = AdaptiveQuality::new(profile(75, 40, 90));
+        // Alternating just over and comfortably under budget never builds a streak
+        for i in 0..200 {
+            let sample = if i % 2 == 0 { 16.0 } else { 9.0 };
+            controller.record(ms(sample));
+        }
+        assert_eq!(controller.quality(), 75);
+
+        // Anything between 70% and 100% of budget leaves quality alone
+        for _ in 0..200 {
+            controller.record(ms(12.0));
+        }
+        assert_eq!(controller.quality(), 75);
+    }
+
+    #[test]
+    fn steps_down_after_consecutive_overruns_only() {
+        let mut controller = AdaptiveQuality::new(profile(75, 40, 90));
+        assert_eq!(controller.record(ms(30.0)), 75);
+        assert_eq!(controller.record(ms(30.0)), 75);
+        assert_eq!(controller.record(ms(30.0)), 70);
+    }
+
+    #[test]
+    fn disabled_adaptation_pins_quality() {
+        let pinned = QualityProfile {
+            adaptive: false,
+            quality: 95,
+            ..QualityProfile::default()
+        };
+        let mut controller = AdaptiveQuality::new(pinned);
+        for _ in 0..100 {
+            assert_eq!(controller.record(ms(50.0)), 95);
+        }
+        assert_eq!(controller.recent_encode_ms().len(), RECENT_SAMPLES);
+    }
+
+    #[test]
+    fn recent_encode_ms_keeps_latest_samples_in_order() {
+        let mut controller = AdaptiveQuality::new(QualityProfile::default());
+        for i in 1..=20 {
+            controller.record(ms(f64::from(i)));
+        }
+        let recent = controller.recent_encode_ms();
+        assert_eq!(recent.len(), RECENT_SAMPLES);
+        assert!((recent[0] - 5.0).abs() < 1e-9);
+        assert!((recent[RECENT_SAMPLES - 1] - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn set_profile_restarts_from_new_starting_quality() {
+        let mut controller = AdaptiveQuality::new(profile(75, 40, 90));
+        simulate(&mut controller, 50, |_| 45.0);
+        assert_eq!(controller.quality(), 40);
+
+        controller.set_profile(profile(85, 50, 95));
+        assert_eq!(controller.quality(), 85);
+        assert!(!controller.recent_encode_ms().is_empty());
+    }
+
+    #[test]
+    fn validate_rejects_bad_profiles() {
+        assert!(QualityProfile::default().validate().is_ok());
+        assert!(profile(75, 80, 60).validate().is_err());
+        assert!(profile(0, 40, 90).validate().is_err());
+        assert!(profile(75, 40, 101).validate().is_err());
+        let no_budget = QualityProfile {
+            budget_ms: 0,
+            ..QualityProfile::default()
+        };
+        assert!(no_budget.validate().is_err());
+    }
+
+    #[test]
+    fn profile_deserialises_with_defaults_for_missing_fields() {
+        let parsed: QualityProfile = serde_json::from_str(r#"{"adaptive":false}"#).unwrap();
+        assert!(!parsed.adaptive);
+        assert_eq!(parsed.quality, 75);
+        assert_eq!(parsed.budget_ms, 15);
+
+        let json = serde_json::to_value(QualityProfile::default()).unwrap();
+        assert_eq!(json["minQuality"], 40);
+        assert_eq!(json["budgetMs"], 15);
+    }
+
+    #[test]
+    fn stills_use_the_best_quality_the_profile_allows() {
+        assert_eq!(profile(75, 40, 90).still_quality(), 90);
+        let pinned = QualityProfile {
+            adaptive: false,
+            quality: 60,
+            ..QualityProfile::default()
+        };
+        assert_eq!(pinned.still_quality(), 60);
+    }
+}
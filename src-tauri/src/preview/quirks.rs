@@ -0,0 +1,341 @@
+// Virtual camera quirks table.
+//
+// Several virtual cameras misreport their DirectShow capabilities. Rather
+// than scattering name checks through the capture graph, each known camera
+// gets a `QuirkProfile` looked up by friendly-name pattern.
+
+use crate::preview::gpu::PixelFormat;
+
+/// Capture graph workarounds for a misbehaving (usually virtual) camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuirkProfile {
+    /// Human-readable name used in logs.
+    pub label: &'static str,
+    /// Force this subtype on the source pin and SampleGrabber instead of
+    /// negotiating RGB24. Mapped to the DirectShow subtype GUID by the graph.
+    pub force_subtype: Option<PixelFormat>,
+    /// Remove the graph reference clock so samples are delivered immediately
+    /// rather than scheduled by their (unreliable) timestamps.
+    pub disable_reference_clock: bool,
+    /// Skip the IAMStreamConfig resolution request.
+    pub skip_set_format: bool,
+    /// Accept frames whose length matches a different advertised capability
+    /// instead of dropping them as size mismatches.
+    pub tolerate_size_mismatch: bool,
+}
+
+impl QuirkProfile {
+    /// Whether the graph should request a resolution via IAMStreamConfig.
+    pub fn should_configure_resolution(&self, width: u32, height: u32) -> bool {
+        width > 0 && height > 0 && !self.skip_set_format
+    }
+}
+
+/// A quirks table entry: all `patterns` must appear (case-insensitively) in
+/// the friendly name for the profile to apply.
+struct QuirkEntry {
+    patterns: &'static [&'static str],
+    profile: QuirkProfile,
+}
+
+/// Known misbehaving cameras.
+///
+/// - OBS Virtual Camera returns `S_OK` from `SetFormat()` for anything but
+///   only delivers NV12 reliably, and mishandles reference clock timing
+///   (OBS issues #4929, #8057).
+/// - NDI Virtual Input also advertises RGB24 it cannot deliver; its native
+///   output is packed 4:2:2, so YUY2 is requested directly.
+/// - Snap Camera intermittently delivers buffers sized for a different
+///   capability than the one negotiated.
+/// - XSplit VCam ignores resolution requests and stalls when its output
+///   pin is reconfigured before connection.
+const QUIRKS: &[QuirkEntry] = &[
+    QuirkEntry {
+        patterns: &["obs", "virtual"],
+        profile: QuirkProfile {
+            label: "OBS Virtual Camera",
+            force_subtype: Some(PixelFormat::Nv12),
+            disable_reference_clock: true,
+            skip_set_format: true,
+            tolerate_size_mismatch: false,
+        },
+    },
+    QuirkEntry {
+        patterns: &["ndi", "virtual input"],
+        profile: QuirkProfile {
+            label: "NDI Virtual Input",
+            force_subtype: Some(PixelFormat::Yuy2),
+            disable_reference_clock: false,
+            skip_set_format: false,
+            tolerate_size_mismatch: false,
+        },
+    },
+    QuirkEntry {
+        patterns: &["snap camera"],
+        profile: QuirkProfile {
+            label: "Snap Camera",
+            force_subtype: None,
+            disable_reference_clock: false,
+            skip_set_format: false,
+            tolerate_size_mismatch: true,
+        },
+    },
+    QuirkEntry {
+        patterns: &["xsplit", "vcam"],
+        profile: QuirkProfile {
+            label: "XSplit VCam",
+            force_subtype: None,
+            disable_reference_clock: false,
+            skip_set_format: true,
+            tolerate_size_mismatch: false,
+        },
+    },
+];
+
+/// Look up the quirk profile for a camera by its friendly name.
+///
+/// Returns `None` for cameras that need no special handling.
+pub fn quirk_profile_for(friendly_name: &str) -> Option<QuirkProfile> {
+    let lower = friendly_name.to_ascii_lowercase();
+    QUIRKS
+        .iter()
+        .find(|entry| entry.patterns.iter().all(|p| lower.contains(p)))
+        .map(|entry| entry.profile)
+}
+
+/// Row stride of an RGB24 frame: DIB rows are padded to a DWORD boundary.
+pub fn bgr24_stride(width: u32) -> usize {
+    (width as usize * 3 + 3) & !3
+}
+
+/// Source row stride of an RGB24 buffer of `len` bytes.
+///
+/// Most drivers pad rows to `stride` as DIBs require, but a few deliver
+/// them tightly packed, which only differs when `width * 3` isn't a
+/// multiple of four.
+pub fn bgr24_row_stride(len: usize, width: u32, height: u32, stride: usize) -> usize {
+    let packed = width as usize * 3;
+    if len == packed * height as usize {
+        packed
+    } else {
+        stride
+    }
+}
+
+/// Expected buffer length for a frame of the given format and dimensions.
+///
+/// RGB24 rows are DWORD-aligned; see [`bgr24_stride`].
+pub fn expected_frame_len(format: PixelFormat, width: u32, height: u32) -> usize {
+    let (w, h) = (width as usize, height as usize);
+    match format {
+        PixelFormat::Bgr24BottomUp => bgr24_stride(width) * h,
+        PixelFormat::Yuy2 => w * h * 2,
+        PixelFormat::Nv12 => w * h * 3 / 2,
+    }
+}
+
+/// Whether `len` is exactly the size of a frame, allowing tightly packed
+/// RGB24 rows as well as DWORD-aligned ones.
+fn is_frame_len(format: PixelFormat, len: usize, width: u32, height: u32) -> bool {
+    len == expected_frame_len(format, width, height)
+        || (format == PixelFormat::Bgr24BottomUp && len == width as usize * 3 * height as usize)
+}
+
+/// Decide which dimensions a delivered buffer should be interpreted with.
+///
+/// Buffers at least as large as the negotiated size are accepted as-is
+/// (drivers may pad). Undersized buffers are dropped (`None`) unless
+/// `tolerate_size_mismatch` is set and the length exactly matches another
+/// advertised capability, in which case that capability's size is used.
+pub fn resolve_frame_size(
+    format: PixelFormat,
+    len: usize,
+    width: u32,
+    height: u32,
+    capabilities: &[(u32, u32)],
+    tolerate_size_mismatch: bool,
+) -> Option<(u32, u32)> {
+    if is_frame_len(format, len, width, height) {
+        return Some((width, height));
+    }
+
+    if tolerate_size_mismatch {
+        if let Some(&(w, h)) = capabilities
+            .iter()
+            .find(|&&(w, h)| (w, h) != (width, height) && is_frame_len(format, len, w, h))
+        {
+            return Some((w, h));
+        }
+    }
+
+    if len > expected_frame_len(format, width, height) {
+        Some((width, height))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_obs_profile() {
+        let q = quirk_profile_for("OBS Virtual Camera").unwrap();
+        assert_eq!(q.label, "OBS Virtual Camera");
+        assert_eq!(q.force_subtype, Some(PixelFormat::Nv12));
+        assert!(q.disable_reference_clock);
+        assert!(q.skip_set_format);
+        assert!(!q.tolerate_size_mismatch);
+    }
+
+    #[test]
+    fn looks_up_ndi_profile() {
+        let q = quirk_profile_for("NDI Virtual Input").unwrap();
+        assert_eq!(q.label, "NDI Virtual Input");
+        assert_eq!(q.force_subtype, Some(PixelFormat::Yuy2));
+        assert!(!q.skip_set_format);
+    }
+
+    #[test]
+    fn looks_up_snap_profile() {
+        let q = quirk_profile_for("Snap Camera").unwrap();
+        assert_eq!(q.label, "Snap Camera");
+        assert!(q.tolerate_size_mismatch);
+        assert_eq!(q.force_subtype, None);
+    }
+
+    #[test]
+    fn looks_up_xsplit_profile() {
+        let q = quirk_profile_for("XSplit VCam").unwrap();
+        assert_eq!(q.label, "XSplit VCam");
+        assert!(q.skip_set_format);
+        assert!(!q.disable_reference_clock);
+    }
+
+    #[test]
+    fn lookup_is_case_insensitive() {
+        assert!(quirk_profile_for("ndi virtual input (2)").is_some());
+        assert!(quirk_profile_for("SNAP CAMERA").is_some());
+        assert!(quirk_profile_for("xsplit vcam").is_some());
+    }
+
+    #[test]
+    fn lookup_requires_all_patterns() {
+        assert!(quirk_profile_for("Logitech C920").is_none());
+        assert!(quirk_profile_for("NDI Webcam Input").is_none());
+        assert!(quirk_profile_for("XSplit Broadcaster").is_none());
+        assert!(quirk_profile_for("Virtual Camera").is_none());
+    }
+
+    #[test]
+    fn skip_set_format_disables_resolution_request() {
+        let obs = quirk_profile_for("OBS Virtual Camera").unwrap();
+        assert!(!obs.should_configure_resolution(640, 480));
+
+        let snap = quirk_profile_for("Snap Camera").unwrap();
+        assert!(snap.should_configure_resolution(640, 480));
+        assert!(!snap.should_configure_resolution(0, 480));
+    }
+
+    #[test]
+    fn expected_frame_len_per_format() {
+        assert_eq!(expected_frame_len(PixelFormat::Bgr24BottomUp, 4, 2), 24);
+        assert_eq!(expected_frame_len(PixelFormat::Yuy2, 4, 2), 16);
+        assert_eq!(expected_frame_len(PixelFormat::Nv12, 4, 2), 12);
+    }
+
+    #[test]
+    fn rgb24_rows_are_dword_aligned() {
+        assert_eq!(bgr24_stride(1364), 4092);
+        assert_eq!(bgr24_stride(1365), 4096);
+        assert_eq!(bgr24_stride(1366), 4100);
+        assert_eq!(bgr24_stride(1367), 4104);
+        assert_eq!(
+            expected_frame_len(PixelFormat::Bgr24BottomUp, 1366, 768),
+            4100 * 768
+        );
+    }
+
+    #[test]
+    fn row_stride_follows_the_delivered_layout() {
+        let stride = bgr24_stride(1366);
+        assert_eq!(bgr24_row_stride(stride * 768, 1366, 768, stride), stride);
+        assert_eq!(bgr24_row_stride(4098 * 768, 1366, 768, stride), 4098);
+    }
+
+    #[test]
+    fn resolve_accepts_padded_and_packed_rgb24_frames() {
+        let caps = [(1366, 768)];
+        for len in [4100 * 768, 4098 * 768] {
+            assert_eq!(
+                resolve_frame_size(PixelFormat::Bgr24BottomUp, len, 1366, 768, &caps, false),
+                Some((1366, 768))
+            );
+        }
+        assert_eq!(
+            resolve_frame_size(
+                PixelFormat::Bgr24BottomUp,
+                4098 * 768 - 1,
+                1366,
+                768,
+                &caps,
+                false
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn resolve_matches_padded_rgb24_capabilities_with_tolerance() {
+        let caps = [(1920, 1080), (1366, 768)];
+        let len = expected_frame_len(PixelFormat::Bgr24BottomUp, 1366, 768);
+        assert_eq!(
+            resolve_frame_size(PixelFormat::Bgr24BottomUp, len, 1920, 1080, &caps, true),
+            Some((1366, 768))
+        );
+    }
+
+    #[test]
+    fn resolve_accepts_exact_and_padded_frames() {
+        let caps = [(640, 480)];
+        let exact = expected_frame_len(PixelFormat::Nv12, 640, 480);
+        assert_eq!(
+            resolve_frame_size(PixelFormat::Nv12, exact, 640, 480, &caps, false),
+            Some((640, 480))
+        );
+        assert_eq!(
+            resolve_frame_size(PixelFormat::Nv12, exact + 64, 640, 480, &caps, false),
+            Some((640, 480))
+        );
+    }
+
+    #[test]
+    fn resolve_drops_undersized_frames_without_tolerance() {
+        let caps = [(640, 480), (320, 240)];
+        let small = expected_frame_len(PixelFormat::Yuy2, 320, 240);
+        assert_eq!(
+            resolve_frame_size(PixelFormat::Yuy2, small, 640, 480, &caps, false),
+            None
+        );
+    }
+
+    #[test]
+    fn resolve_uses_matching_capability_with_tolerance() {
+        let caps = [(640, 480), (320, 240)];
+        let small = expected_frame_len(PixelFormat::Yuy2, 320, 240);
+        assert_eq!(
+            resolve_frame_size(PixelFormat::Yuy2, small, 640, 480, &caps, true),
+            Some((320, 240))
+        );
+    }
+
+    #[test]
+    fn resolve_drops_unmatched_sizes_even_with_tolerance() {
+        let caps = [(640, 480), (320, 240)];
+        assert_eq!(
+            resolve_frame_size(PixelFormat::Yuy2, 1000, 640, 480, &caps, true),
+            None
+        );
+    }
+}
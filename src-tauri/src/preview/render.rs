@@ -0,0 +1,331 @@
+// Shared render pipeline for frame delivery.
+//
+// Every consumer of camera output (encode worker, get_frame fallback,
+// thumbnails) renders frames through here so orientation is applied once,
+// in the backend, and all paths agree on pixels and reported dimensions.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::preview::capture::{Frame, FrameBuffer};
+
+/// Clockwise rotation applied to delivered frames.
+///
+/// Serialised as degrees (`0`, `90`, `180`, `270`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Self::None),
+            90 => Ok(Self::Cw90),
+            180 => Ok(Self::Cw180),
+            270 => Ok(Self::Cw270),
+            other => Err(format!("unsupported rotation: {other} degrees")),
+        }
+    }
+}
+
+impl From<Rotation> for u16 {
+    fn from(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::None => 0,
+            Rotation::Cw90 => 90,
+            Rotation::Cw180 => 180,
+            Rotation::Cw270 => 270,
+        }
+    }
+}
+
+/// Per-device orientation applied before compression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Orientation {
+    /// Clockwise rotation, applied after mirroring.
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// Mirror the image horizontally (as seen by the camera).
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+impl Orientation {
+    /// Whether this orientation leaves frames untouched.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Output dimensions for a `width` x `height` input.
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.rotation {
+            Rotation::Cw90 | Rotation::Cw270 => (height, width),
+            Rotation::None | Rotation::Cw180 => (width, height),
+        }
+    }
+
+    /// Map a normalised `[0, 1]` point in the delivered frame back to the
+    /// same point in the unoriented source frame.
+    pub fn source_point(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sx, sy) = match self.rotation {
+            Rotation::None => (x, y),
+            Rotation::Cw90 => (y, 1.0 - x),
+            Rotation::Cw180 => (1.0 - x, 1.0 - y),
+            Rotation::Cw270 => (1.0 - y, x),
+        };
+        if self.mirror {
+            (1.0 - sx, sy)
+        } else {
+            (sx, sy)
+        }
+    }
+}
+
+/// Orientation shared between a session and its encode worker, so changes
+/// apply from the next frame without restarting capture.
+pub type SharedOrientation = Arc<Mutex<Orientation>>;
+
+/// RGB24 frame after the render pipeline has been applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedFrame {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Apply `orientation` to tightly packed RGB24 pixel data.
+pub fn apply_orientation(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    orientation: Orientation,
+) -> RenderedFrame {
+    if orientation.is_identity() {
+        return RenderedFrame {
+            data: data.to_vec(),
+            width,
+            height,
+        };
+    }
+
+    let (w, h) = (width as usize, height as usize);
+    let (out_w, out_h) = orientation.output_size(width, height);
+    let mut out = vec![0u8; w * h * 3];
+
+    for oy in 0..out_h as usize {
+        for ox in 0..out_w as usize {
+            let (x, y) = match orientation.rotation {
+                Rotation::None => (ox, oy),
+                Rotation::Cw90 => (oy, h - 1 - ox),
+                Rotation::Cw180 => (w - 1 - ox, h - 1 - oy),
+                Rotation::Cw270 => (w - 1 - oy, ox),
+            };
+            let x = if orientation.mirror { w - 1 - x } else { x };
+
+            let src = (y * w + x) * 3;
+            let dst = (oy * out_w as usize + ox) * 3;
+            out[dst..dst + 3].copy_from_slice(&data[src..src + 3]);
+        }
+    }
+
+    RenderedFrame {
+        data: out,
+        width: out_w,
+        height: out_h,
+    }
+}
+
+/// Render a captured frame for delivery.
+pub fn render_frame(frame: &Frame, orientation: Orientation) -> RenderedFrame {
+    apply_orientation(&frame.data, frame.width, frame.height, orientation)
+}
+
+/// Fetch the latest frame from `buffer` and render it.
+///
+/// Returns the rendered frame and the buffer sequence it was taken at, or
+/// `None` if no frame has arrived yet.
+pub fn render_latest(
+    buffer: &FrameBuffer,
+    orientation: Orientation,
+) -> Option<(RenderedFrame, u64)> {
+    let (frame, sequence) = buffer.latest_with_sequence()?;
+    Some((render_frame(&frame, orientation), sequence))
+}
+
+/// Decode a JPEG to RGB24 and render it.
+///
+/// Used for JPEG frames with no raw frame behind them when a non-identity
+/// orientation is set, and to decode encoded frames for further processing.
+pub fn render_jpeg(jpeg: &[u8], orientation: Orientation) -> Result<RenderedFrame, String> {
+    let img = image::load_from_memory_with_format(jpeg, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("failed to decode JPEG frame: {e}"))?
+        .into_rgb8();
+    let (width, height) = img.dimensions();
+    Ok(apply_orientation(img.as_raw(), width, height, orientation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::compress;
+
+    /// 3x2 image with a unique colour per pixel:
+    /// row 0: A B C, row 1: D E F
+    fn fixture() -> Vec<u8> {
+        (0..6u8)
+            .flat_map(|i| [i * 10, i * 10 + 1, i * 10 + 2])
+            .collect()
+    }
+
+    /// Pixel index (into the 3x2 fixture) at each output position.
+    fn pixel_ids(frame: &RenderedFrame) -> Vec<u8> {
+        frame.data.chunks(3).map(|px| px[0] / 10).collect()
+    }
+
+    fn orientation(rotation: Rotation, mirror: bool) -> Orientation {
+        Orientation { rotation, mirror }
+    }
+
+    #[test]
+    fn identity_returns_input_unchanged() {
+        let out = apply_orientation(&fixture(), 3, 2, Orientation::default());
+        assert_eq!(out.data, fixture());
+        assert_eq!((out.width, out.height), (3, 2));
+    }
+
+    #[test]
+    fn rotate_90_swaps_dimensions_and_pixels() {
+        let out = apply_orientation(&fixture(), 3, 2, orientation(Rotation::Cw90, false));
+        assert_eq!((out.width, out.height), (2, 3));
+        // D A / E B / F C
+        assert_eq!(pixel_ids(&out), vec![3, 0, 4, 1, 5, 2]);
+    }
+
+    #[test]
+    fn rotate_180_reverses_pixels() {
+        let out = apply_orientation(&fixture(), 3, 2, orientation(Rotation::Cw180, false));
+        assert_eq!((out.width, out.height), (3, 2));
+        assert_eq!(pixel_ids(&out), vec![5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn rotate_270_swaps_dimensions_and_pixels() {
+        let out = apply_orientation(&fixture(), 3, 2, orientation(Rotation::Cw270, false));
+        assert_eq!((out.width, out.height), (2, 3));
+        // C F / B E / A D
+        assert_eq!(pixel_ids(&out), vec![2, 5, 1, 4, 0, 3]);
+    }
+
+    #[test]
+    fn mirror_flips_horizontally() {
+        let out = apply_orientation(&fixture(), 3, 2, orientation(Rotation::None, true));
+        assert_eq!(pixel_ids(&out), vec![2, 1, 0, 5, 4, 3]);
+    }
+
+    #[test]
+    fn mirror_is_applied_before_rotation() {
+        let out = apply_orientation(&fixture(), 3, 2, orientation(Rotation::Cw90, true));
+        assert_eq!((out.width, out.height), (2, 3));
+        // Mirrored: C B A / F E D, then rotated: F C / E B / D A
+        assert_eq!(pixel_ids(&out), vec![5, 2, 4, 1, 3, 0]);
+    }
+
+    #[test]
+    fn source_point_agrees_with_pixel_mapping() {
+        // Pixel centres of the 3x2 fixture, normalised
+        let centre = |i: usize, n: u32| (i as f32 + 0.5) / n as f32;
+        for rotation in [
+            Rotation::None,
+            Rotation::Cw90,
+            Rotation::Cw180,
+            Rotation::Cw270,
+        ] {
+            for mirror in [false, true] {
+                let o = orientation(rotation, mirror);
+                let out = apply_orientation(&fixture(), 3, 2, o);
+                let ids = pixel_ids(&out);
+                for oy in 0..out.height as usize {
+                    for ox in 0..out.width as usize {
+                        let (sx, sy) =
+                            o.source_point(centre(ox, out.width), centre(oy, out.height));
+                        let src = (sy * 2.0) as usize * 3 + (sx * 3.0) as usize;
+                        assert_eq!(
+                            ids[oy * out.width as usize + ox] as usize,
+                            src,
+                            "{o:?} at ({ox}, {oy})"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_are_identity() {
+        let mut frame = apply_orientation(&fixture(), 3, 2, Orientation::default());
+        for _ in 0..4 {
+            frame = apply_orientation(
+                &frame.data,
+                frame.width,
+                frame.height,
+                orientation(Rotation::Cw90, false),
+            );
+        }
+        assert_eq!(frame.data, fixture());
+        assert_eq!((frame.width, frame.height), (3, 2));
+    }
+
+    #[test]
+    fn render_latest_matches_render_frame() {
+        let buffer = FrameBuffer::new(3);
+        assert!(render_latest(&buffer, Orientation::default()).is_none());
+
+        buffer.push(Frame {
+            data: fixture(),
+            width: 3,
+            height: 2,
+            timestamp_us: 0,
+            device_timestamp_us: 0,
+        });
+        let o = orientation(Rotation::Cw90, true);
+        let (rendered, seq) = render_latest(&buffer, o).unwrap();
+        assert_eq!(seq, 1);
+        assert_eq!(rendered, render_frame(&buffer.latest().unwrap(), o));
+    }
+
+    #[test]
+    fn jpeg_path_reports_same_dimensions_as_raw_path() {
+        // 16x8 so JPEG chroma subsampling doesn't affect dimensions
+        let data: Vec<u8> = (0..16 * 8 * 3).map(|i| (i % 251) as u8).collect();
+        let jpeg = compress::compress_jpeg(&data, 16, 8, 90);
+        let o = orientation(Rotation::Cw90, false);
+
+        let raw = apply_orientation(&data, 16, 8, o);
+        let decoded = render_jpeg(&jpeg, o).unwrap();
+        assert_eq!((decoded.width, decoded.height), (raw.width, raw.height));
+        assert_eq!((raw.width, raw.height), (8, 16));
+    }
+
+    #[test]
+    fn orientation_serialises_rotation_as_degrees() {
+        let json = serde_json::to_value(orientation(Rotation::Cw270, true)).unwrap();
+        assert_eq!(json["rotation"], 270);
+        assert_eq!(json["mirror"], true);
+
+        let parsed: Orientation = serde_json::from_str(r#"{"rotation":90}"#).unwrap();
+        assert_eq!(parsed, orientation(Rotation::Cw90, false));
+
+        assert!(serde_json::from_str::<Orientation>(r#"{"rotation":45}"#).is_err());
+    }
+}
@@ -0,0 +1,566 @@
+//! Shared-memory frame export — zero-copy access to raw frames for
+//! companion processes.
+//!
+//! Base64 over IPC or MJPEG over HTTP costs a full encode and decode per
+//! frame, which adds up at 1080p30. An export instead copies each RGB frame
+//! into a named shared memory region (a Windows file mapping) that another
+//! process maps read-only.
+//!
+//! # Layout
+//!
+//! The region starts with a [`HEADER_SIZE`]-byte header, followed by
+//! [`BUFFER_COUNT`] frame buffers of `buffer_size` bytes each, back to back.
+//! All fields are little-endian and naturally aligned:
+//!
+//! | Offset | Type | Field |
+//! |-------:|------|-------|
+//! | 0  | u32 | `magic`, [`MAGIC`] (`"CSHM"`) |
+//! | 4  | u32 | `version`, [`VERSION`] |
+//! | 8  | u32 | `width` in pixels |
+//! | 12 | u32 | `height` in pixels |
+//! | 16 | u32 | `stride`, bytes per row (`width * 3`) |
+//! | 20 | u32 | `pixel_format`, [`PIXEL_FORMAT_RGB24`]: 8-bit R, G, B, top row first |
+//! | 24 | u32 | `buffer_count` |
+//! | 28 | u32 | `buffer_size`, bytes per buffer (`stride * height`) |
+//! | 32 | u32 | `data_offset`, where buffer 0 starts ([`HEADER_SIZE`]) |
+//! | 36 | u32 | `flags`; bit 0 ([`FLAG_CLOSED`]) is set when the export ends |
+//! | 40 | u64 | `sequence`, the last published frame (0 before the first) |
+//! | 48 | u64 | `writing`, the frame being written (equal to `sequence` between frames) |
+//! | 56 | u64 × `buffer_count` | capture timestamp in µs of the frame in each buffer |
+//!
+//! # Protocol
+//!
+//! Frames are numbered from 1, and frame `n` always goes into buffer
+//! `n % buffer_count`, so the latest frame's buffer is `sequence %
+//! buffer_count`. To write frame `n`, the writer stores `n` in `writing`,
+//! issues a release fence, copies the pixels and timestamp into the buffer
+//! the reader isn't on, then stores `n` in `sequence` with release ordering.
+//!
+//! A reader loads `sequence` (acquire), copies buffer `sequence %
+//! buffer_count`, issues an acquire fence and loads `writing`. The copy is
+//! intact if `writing < sequence + buffer_count`: the buffer is only reused
+//! for frame `sequence + buffer_count`. Otherwise it was overwritten while
+//! being read, and the reader tries again. An unchanged `sequence` means
+//! no new frame; a jump of more than 1 means frames were missed.
+//!
+//! The export is tied to its preview session: stopping or restarting the
+//! preview, or disabling the export, sets [`FLAG_CLOSED`] and the writer
+//! stops. The mapping stays valid for readers until they unmap it.
+
+use std::ptr::NonNull;
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use super::capture::Frame;
+
+/// `"CSHM"` read as a little-endian u32.
+pub const MAGIC: u32 = u32::from_le_bytes(*b"CSHM");
+
+/// Bumped whenever the layout or protocol changes.
+pub const VERSION: u32 = 1;
+
+/// Packed 8-bit RGB, top row first.
+pub const PIXEL_FORMAT_RGB24: u32 = 1;
+
+/// Frames are double-buffered.
+pub const BUFFER_COUNT: usize = 2;
+
+/// Bytes before buffer 0. Leaves room for the header to grow and keeps the
+/// buffers cache-line aligned.
+pub const HEADER_SIZE: usize = 128;
+
+/// Set in `flags` once the export has ended.
+pub const FLAG_CLOSED: u32 = 1;
+
+/// The header at the start of the region. See the module docs.
+#[repr(C)]
+struct ShmHeader {
+    magic: u32,
+    version: u32,
+    width: u32,
+    height: u32,
+    stride: u32,
+    pixel_format: u32,
+    buffer_count: u32,
+    buffer_size: u32,
+    data_offset: u32,
+    flags: AtomicU32,
+    sequence: AtomicU64,
+    writing: AtomicU64,
+    timestamps_us: [AtomicU64; BUFFER_COUNT],
+}
+
+const _: () = assert!(std::mem::size_of::<ShmHeader>() <= HEADER_SIZE);
+
+/// Where an export lives and how it's laid out, for the process mapping it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShmLayout {
+    /// Name to open the mapping by, e.g. with `OpenFileMappingW`.
+    pub name: String,
+    /// Total size of the region in bytes.
+    pub size: usize,
+    pub version: u32,
+    pub header_size: usize,
+    pub buffer_count: usize,
+    pub buffer_size: usize,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub pixel_format: &'static str,
+}
+
+impl ShmLayout {
+    fn new(name: String, width: u32, height: u32) -> Result<Self, String> {
+        let stride = width
+            .checked_mul(3)
+            .ok_or_else(|| format!("{width}x{height} is too large to export"))?;
+        let buffer_size = (stride as usize)
+            .checked_mul(height as usize)
+            .filter(|&size| size > 0 && u32::try_from(size).is_ok())
+            .ok_or_else(|| format!("{width}x{height} can't be exported"))?;
+        Ok(Self {
+            name,
+            size: HEADER_SIZE + BUFFER_COUNT * buffer_size,
+            version: VERSION,
+            header_size: HEADER_SIZE,
+            buffer_count: BUFFER_COUNT,
+            buffer_size,
+            width,
+            height,
+            stride,
+            pixel_format: "rgb24",
+        })
+    }
+
+    /// Byte offset of buffer `index` from the start of the region.
+    pub fn buffer_offset(&self, index: usize) -> usize {
+        HEADER_SIZE + index * self.buffer_size
+    }
+}
+
+/// Mapping name for an export of `device_id`. Includes `generation` so a
+/// reader still holding an old export open can't make a new one reuse it
+/// at the old size.
+fn mapping_name(device_id: &str, generation: u64) -> String {
+    let safe: String = device_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!(r"Local\cameras-frames-{safe}-{generation}")
+}
+
+/// A mapped view of a shared memory region.
+struct Mapping {
+    view: NonNull<u8>,
+    #[cfg(windows)]
+    handle: windows::Win32::Foundation::HANDLE,
+    /// Off Windows the region is process-local memory, which is only good
+    /// for tests. Atomics so it can be shared with an in-process reader.
+    #[cfg(not(windows))]
+    memory: std::sync::Arc<[AtomicU64]>,
+}
+
+// The view is only written through the header's atomics and by the single
+// writer, under the export's lock.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    /// Create a zeroed region of `size` bytes named `name`.
+    #[cfg(windows)]
+    fn create(name: &str, size: usize) -> Result<Self, String> {
+        use windows::core::PCWSTR;
+        use windows::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+        use windows::Win32::System::Memory::{
+            CreateFileMappingW, MapViewOfFile, FILE_MAP_ALL_ACCESS, PAGE_READWRITE,
+        };
+
+        let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+        let size_u64 = size as u64;
+        let handle = unsafe {
+            CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                None,
+                PAGE_READWRITE,
+                (size_u64 >> 32) as u32,
+                size_u64 as u32,
+                PCWSTR(wide.as_ptr()),
+            )
+        }
+        .map_err(|e| format!("failed to create shared memory '{name}': {e}"))?;
+        let view = unsafe { MapViewOfFile(handle, FILE_MAP_ALL_ACCESS, 0, 0, size) };
+        match NonNull::new(view.Value.cast::<u8>()) {
+            Some(view) => Ok(Self { view, handle }),
+            None => {
+                unsafe {
+                    let _ = CloseHandle(handle);
+                }
+                Err(format!("failed to map shared memory '{name}'"))
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn create(_name: &str, size: usize) -> Result<Self, String> {
+        let memory: std::sync::Arc<[AtomicU64]> =
+            (0..size.div_ceil(8)).map(|_| AtomicU64::new(0)).collect();
+        let view = NonNull::new(memory.as_ptr().cast::<u8>().cast_mut())
+            .ok_or_else(|| "failed to allocate frame export".to_string())?;
+        Ok(Self { view, memory })
+    }
+
+    fn header(&self) -> &ShmHeader {
+        // The region is at least HEADER_SIZE bytes and page (or u64) aligned
+        unsafe { self.view.cast::<ShmHeader>().as_ref() }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Memory::{UnmapViewOfFile, MEMORY_MAPPED_VIEW_ADDRESS};
+
+        unsafe {
+            let _ = UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
+                Value: self.view.as_ptr().cast(),
+            });
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Source of export generations; see `mapping_name`.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// A running shared-memory export of one camera's frames.
+///
+/// Frames come from a single writer, the capture thread, via `write`.
+pub struct ShmExport {
+    layout: ShmLayout,
+    /// `None` once closed. Writes hold the read lock for the whole copy, so
+    /// `close` waits for an in-flight frame before unmapping.
+    mapping: RwLock<Option<Mapping>>,
+    written: AtomicU64,
+    skipped: AtomicU64,
+}
+
+impl ShmExport {
+    /// Create an export of `device_id`'s frames at `width`x`height`.
+    pub fn create(device_id: &str, width: u32, height: u32) -> Result<Self, String> {
+        let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+        let layout = ShmLayout::new(mapping_name(device_id, generation), width, height)?;
+        let mapping = Mapping::create(&layout.name, layout.size)?;
+        let header = ShmHeader {
+            magic: MAGIC,
+            version: VERSION,
+            width,
+            height,
+            stride: layout.stride,
+            pixel_format: PIXEL_FORMAT_RGB24,
+            buffer_count: BUFFER_COUNT as u32,
+            buffer_size: layout.buffer_size as u32,
+            data_offset: HEADER_SIZE as u32,
+            flags: AtomicU32::new(0),
+            sequence: AtomicU64::new(0),
+            writing: AtomicU64::new(0),
+            timestamps_us: Default::default(),
+        };
+        // Readers can't rely on the header until a frame is published
+        unsafe { mapping.view.cast::<ShmHeader>().as_ptr().write(header) };
+        Ok(Self {
+            layout,
+            mapping: RwLock::new(Some(mapping)),
+            written: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        })
+    }
+
+    pub fn layout(&self) -> &ShmLayout {
+        &self.layout
+    }
+
+    /// Frames published so far.
+    pub fn frames_written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Frames not published because they didn't match the export's size.
+    pub fn frames_skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Publish `frame` into the inactive buffer. Returns whether it was
+    /// written; closed exports and frames of another size are skipped.
+    pub fn write(&self, frame: &Frame) -> bool {
+        let guard = self.mapping.read();
+        let Some(mapping) = guard.as_ref() else {
+            return false;
+        };
+        let layout = &self.layout;
+        if frame.width != layout.width
+            || frame.height != layout.height
+            || frame.data.len() != layout.buffer_size
+        {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        let header = mapping.header();
+        // Only this writer changes `sequence`
+        let number = header.sequence.load(Ordering::Relaxed) + 1;
+        let slot = (number % BUFFER_COUNT as u64) as usize;
+        header.writing.store(number, Ordering::Relaxed);
+        fence(Ordering::Release);
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                frame.data.as_ptr(),
+                mapping.view.as_ptr().add(layout.buffer_offset(slot)),
+                layout.buffer_size,
+            );
+        }
+        header.timestamps_us[slot].store(frame.timestamp_us, Ordering::Relaxed);
+        header.sequence.store(number, Ordering::Release);
+        self.written.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// End the export: mark it closed for readers and unmap it. Waits for a
+    /// frame being written to finish first. Idempotent.
+    pub fn close(&self) {
+        if let Some(mapping) = self.mapping.write().take() {
+            mapping
+                .header()
+                .flags
+                .fetch_or(FLAG_CLOSED, Ordering::Release);
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.mapping.read().is_none()
+    }
+}
+
+impl Drop for ShmExport {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A read-only view of an export, as a companion process would map it.
+    struct Reader {
+        view: *const u8,
+        #[cfg(windows)]
+        _mapping: ReadOnlyMapping,
+        #[cfg(not(windows))]
+        _memory: std::sync::Arc<[AtomicU64]>,
+    }
+
+    #[cfg(windows)]
+    struct ReadOnlyMapping {
+        view: windows::Win32::System::Memory::MEMORY_MAPPED_VIEW_ADDRESS,
+        handle: windows::Win32::Foundation::HANDLE,
+    }
+
+    #[cfg(windows)]
+    impl Drop for ReadOnlyMapping {
+        fn drop(&mut self) {
+            use windows::Win32::Foundation::CloseHandle;
+            use windows::Win32::System::Memory::UnmapViewOfFile;
+            unsafe {
+                let _ = UnmapViewOfFile(self.view);
+                let _ = CloseHandle(self.handle);
+            }
+        }
+    }
+
+    /// Open the export by name with read-only access.
+    #[cfg(windows)]
+    fn map_reader(export: &ShmExport) -> Reader {
+        use windows::core::PCWSTR;
+        use windows::Win32::System::Memory::{MapViewOfFile, OpenFileMappingW, FILE_MAP_READ};
+
+        let wide: Vec<u16> = export
+            .layout()
+            .name
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let handle = unsafe { OpenFileMappingW(FILE_MAP_READ.0, false, PCWSTR(wide.as_ptr())) }
+            .expect("open mapping");
+        let view = unsafe { MapViewOfFile(handle, FILE_MAP_READ, 0, 0, 0) };
+        assert!(!view.Value.is_null());
+        Reader {
+            view: view.Value.cast(),
+            _mapping: ReadOnlyMapping { view, handle },
+        }
+    }
+
+    /// Off Windows the region is process-local, so share it in place.
+    #[cfg(not(windows))]
+    fn map_reader(export: &ShmExport) -> Reader {
+        let guard = export.mapping.read();
+        let mapping = guard.as_ref().expect("open export");
+        Reader {
+            view: mapping.view.as_ptr(),
+            _memory: std::sync::Arc::clone(&mapping.memory),
+        }
+    }
+
+    /// A frame as read back: sequence, timestamp and pixels.
+    type ReadFrame = (u64, u64, Vec<u8>);
+
+    impl Reader {
+        fn u32_at(&self, offset: usize) -> u32 {
+            unsafe { self.view.add(offset).cast::<u32>().read() }
+        }
+
+        fn header(&self) -> &ShmHeader {
+            unsafe { &*self.view.cast::<ShmHeader>() }
+        }
+
+        /// Read the latest frame following the documented protocol, or
+        /// `None` before the first one.
+        fn latest(&self) -> Option<ReadFrame> {
+            let header = self.header();
+            let count = u64::from(self.u32_at(24));
+            let size = self.u32_at(28) as usize;
+            let offset = self.u32_at(32) as usize;
+            loop {
+                let sequence = header.sequence.load(Ordering::Acquire);
+                if sequence == 0 {
+                    return None;
+                }
+                let slot = (sequence % count) as usize;
+                let mut pixels = vec![0; size];
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        self.view.add(offset + slot * size),
+                        pixels.as_mut_ptr(),
+                        size,
+                    );
+                }
+                let timestamp = header.timestamps_us[slot].load(Ordering::Relaxed);
+                fence(Ordering::Acquire);
+                if header.writing.load(Ordering::Relaxed) < sequence + count {
+                    return Some((sequence, timestamp, pixels));
+                }
+            }
+        }
+
+        fn is_closed(&self) -> bool {
+            self.header().flags.load(Ordering::Acquire) & FLAG_CLOSED != 0
+        }
+    }
+
+    fn rgb_frame(width: u32, height: u32, value: u8, timestamp_us: u64) -> Frame {
+        Frame {
+            data: vec![value; (width * height * 3) as usize],
+            width,
+            height,
+            timestamp_us,
+            device_timestamp_us: timestamp_us,
+        }
+    }
+
+    #[test]
+    fn header_describes_the_layout() {
+        let export = ShmExport::create("usb:046d:085e", 4, 2).unwrap();
+        let layout = export.layout().clone();
+        assert_eq!(layout.stride, 12);
+        assert_eq!(layout.buffer_size, 24);
+        assert_eq!(layout.size, HEADER_SIZE + 48);
+        assert_eq!(layout.buffer_offset(1), HEADER_SIZE + 24);
+        assert!(layout
+            .name
+            .starts_with(r"Local\cameras-frames-usb-046d-085e-"));
+
+        let reader = map_reader(&export);
+        assert_eq!(reader.u32_at(0), MAGIC);
+        assert_eq!(reader.u32_at(4), VERSION);
+        assert_eq!((reader.u32_at(8), reader.u32_at(12)), (4, 2));
+        assert_eq!(reader.u32_at(16), 12);
+        assert_eq!(reader.u32_at(20), PIXEL_FORMAT_RGB24);
+        assert_eq!(reader.u32_at(24), BUFFER_COUNT as u32);
+        assert_eq!(reader.u32_at(32), HEADER_SIZE as u32);
+        assert_eq!(reader.latest(), None);
+    }
+
+    #[test]
+    fn frames_alternate_buffers_and_are_read_in_order() {
+        let export = ShmExport::create("cam", 4, 2).unwrap();
+        let reader = map_reader(&export);
+
+        for n in 1..=5u8 {
+            assert!(export.write(&rgb_frame(4, 2, n, u64::from(n) * 1000)));
+            let (sequence, timestamp, pixels) = reader.latest().unwrap();
+            assert_eq!(sequence, u64::from(n));
+            assert_eq!(timestamp, u64::from(n) * 1000);
+            assert!(pixels.iter().all(|&p| p == n));
+        }
+        assert_eq!(export.frames_written(), 5);
+
+        // The previous frame is still intact in the other buffer
+        let previous = reader.view.wrapping_add(export.layout().buffer_offset(0));
+        assert_eq!(unsafe { previous.read() }, 4);
+    }
+
+    #[test]
+    fn frames_of_another_size_are_skipped() {
+        let export = ShmExport::create("cam", 4, 2).unwrap();
+        let reader = map_reader(&export);
+
+        assert!(!export.write(&rgb_frame(8, 2, 1, 0)));
+        assert_eq!(export.frames_skipped(), 1);
+        assert_eq!(reader.latest(), None);
+    }
+
+    #[test]
+    fn a_reader_sees_increasing_sequences_while_frames_are_written() {
+        let export = std::sync::Arc::new(ShmExport::create("cam", 64, 48).unwrap());
+        let reader = map_reader(&export);
+
+        let writer = {
+            let export = std::sync::Arc::clone(&export);
+            std::thread::spawn(move || {
+                for n in 1..=200u64 {
+                    export.write(&rgb_frame(64, 48, (n % 251) as u8, n));
+                }
+            })
+        };
+        let mut last = 0;
+        while last < 200 {
+            if let Some((sequence, timestamp, pixels)) = reader.latest() {
+                assert!(sequence >= last, "sequence went backwards");
+                assert_eq!(timestamp, sequence);
+                let value = (sequence % 251) as u8;
+                assert!(pixels.iter().all(|&p| p == value), "torn frame {sequence}");
+                last = sequence;
+            }
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn closing_stops_writes_and_flags_readers() {
+        let export = ShmExport::create("cam", 4, 2).unwrap();
+        let reader = map_reader(&export);
+        export.write(&rgb_frame(4, 2, 7, 1));
+
+        export.close();
+        export.close();
+
+        assert!(export.is_closed());
+        assert!(!export.write(&rgb_frame(4, 2, 8, 2)));
+        // Readers keep their mapping and still see the last frame
+        assert!(reader.is_closed());
+        assert_eq!(reader.latest().unwrap().0, 1);
+    }
+}
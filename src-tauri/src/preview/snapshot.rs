@@ -0,0 +1,126 @@
+//! Still snapshots: a camera's latest frame written to disk as a JPEG at a
+//! higher quality than the preview's, named after the camera and the time
+//! unless the caller picks a path.
+
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+/// JPEG quality snapshots are taken at unless one is asked for. Higher than
+/// the preview's, since a snapshot is kept.
+pub const DEFAULT_SNAPSHOT_QUALITY: u8 = 92;
+
+/// Folder under the user's Pictures folder that snapshots go to by default.
+pub const SNAPSHOT_DIR: &str = "cameras";
+
+/// The quality to take a snapshot at: `quality` if given, which must be
+/// between 1 and 100, or [`DEFAULT_SNAPSHOT_QUALITY`].
+pub fn snapshot_quality(quality: Option<u8>) -> Result<u8, String> {
+    match quality {
+        None => Ok(DEFAULT_SNAPSHOT_QUALITY),
+        Some(q @ 1..=100) => Ok(q),
+        Some(q) => Err(format!("quality must be between 1 and 100, got {q}")),
+    }
+}
+
+/// File name of a snapshot of `device_id` taken at `time`, such as
+/// `cam-1-20260314-091502-123.jpg`. Characters that aren't safe in file
+/// names everywhere (device IDs contain `\`, `?` and `#`) become `_`.
+pub fn snapshot_file_name(device_id: &str, time: NaiveDateTime) -> String {
+    let device: String = device_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{device}-{}.jpg", time.format("%Y%m%d-%H%M%S-%3f"))
+}
+
+/// Where a snapshot of `device_id` taken at `time` goes by default, given
+/// the user's Pictures folder.
+pub fn default_snapshot_path(pictures: &Path, device_id: &str, time: NaiveDateTime) -> PathBuf {
+    pictures
+        .join(SNAPSHOT_DIR)
+        .join(snapshot_file_name(device_id, time))
+}
+
+/// Write `jpeg` to `path`, creating its folder if needed and replacing any
+/// file already there.
+pub fn write_snapshot(path: &Path, jpeg: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    std::fs::write(path, jpeg).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 14)
+            .unwrap()
+            .and_hms_milli_opt(9, 15, 2, 123)
+            .unwrap()
+    }
+
+    #[test]
+    fn quality_defaults_above_the_preview_and_is_range_checked() {
+        assert_eq!(snapshot_quality(None), Ok(DEFAULT_SNAPSHOT_QUALITY));
+        assert_eq!(snapshot_quality(Some(1)), Ok(1));
+        assert_eq!(snapshot_quality(Some(100)), Ok(100));
+        assert!(snapshot_quality(Some(0)).is_err());
+        assert!(snapshot_quality(Some(101)).is_err());
+    }
+
+    #[test]
+    fn file_names_carry_the_device_and_time() {
+        assert_eq!(
+            snapshot_file_name("cam-1", time()),
+            "cam-1-20260314-091502-123.jpg"
+        );
+    }
+
+    #[test]
+    fn unsafe_characters_in_device_ids_are_replaced() {
+        assert_eq!(
+            snapshot_file_name(r"\\?\usb#vid_046d&pid_085e", time()),
+            "____usb_vid_046d_pid_085e-20260314-091502-123.jpg"
+        );
+    }
+
+    #[test]
+    fn default_path_is_under_the_pictures_folder() {
+        assert_eq!(
+            default_snapshot_path(Path::new("pictures"), "cam-1", time()),
+            Path::new("pictures")
+                .join("cameras")
+                .join("cam-1-20260314-091502-123.jpg")
+        );
+    }
+
+    #[test]
+    fn writing_creates_the_folder_and_replaces_the_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("still.jpg");
+
+        write_snapshot(&path, b"first").unwrap();
+        write_snapshot(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+    }
+
+    #[test]
+    fn writing_into_a_file_fails() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+
+        assert!(write_snapshot(&file.join("still.jpg"), b"jpeg").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Video sources of capture devices with more than one input.
+//!
+//! Capture cards often expose several inputs behind one device: a crossbar
+//! filter routing HDMI, SDI or composite into the capture pin, or several
+//! capture pins on the source filter itself. Graph setup discovers both and
+//! this module turns what it found into the list the frontend picks from,
+//! and decides which source a session captures given the saved choice. The
+//! COM side lives in `graph::directshow`; everything here is pure.
+
+use serde::{Deserialize, Serialize};
+
+/// First `PhysicalConnectorType` value of an audio connector. Crossbar
+/// pins at or above it carry sound, not video.
+const PHYS_CONN_AUDIO: i32 = 0x1000;
+
+/// What kind of input a video source is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceKind {
+    /// A separate capture pin on the source filter.
+    Pin,
+    Tuner,
+    Composite,
+    SVideo,
+    Rgb,
+    Component,
+    /// Serial digital: SDI, and HDMI on many cards.
+    Sdi,
+    /// Parallel digital: HDMI or DVI on most cards.
+    Digital,
+    Usb,
+    Other,
+}
+
+impl SourceKind {
+    /// Kind of a crossbar input from its `PhysicalConnectorType`, or `None`
+    /// for audio and decoder pins that aren't video inputs.
+    pub fn from_physical_type(physical_type: i32) -> Option<Self> {
+        Some(match physical_type {
+            1 => Self::Tuner,
+            2 => Self::Composite,
+            3 => Self::SVideo,
+            4 => Self::Rgb,
+            5 => Self::Component,
+            6 => Self::Sdi,
+            7 => Self::Digital,
+            11 => Self::Usb,
+            // Decoder and encoder pins sit between inputs and the capture
+            // pin; they aren't something to pick.
+            12 | 13 => return None,
+            t if t >= PHYS_CONN_AUDIO || t <= 0 => return None,
+            _ => Self::Other,
+        })
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pin => "Capture",
+            Self::Tuner => "Tuner",
+            Self::Composite => "Composite",
+            Self::SVideo => "S-Video",
+            Self::Rgb => "RGB",
+            Self::Component => "Component",
+            Self::Sdi => "SDI",
+            Self::Digital => "HDMI/DVI",
+            Self::Usb => "USB",
+            Self::Other => "Input",
+        }
+    }
+}
+
+/// How graph setup selects a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceRoute {
+    /// Capture from the source filter's output pin at this position, in
+    /// `EnumPins` order among output pins.
+    Pin(usize),
+    /// Route crossbar input pin `input` to video output pin `output`.
+    Crossbar { output: i32, input: i32 },
+}
+
+/// A source the frontend can pick.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoSource {
+    pub index: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: SourceKind,
+    #[serde(skip)]
+    pub route: SourceRoute,
+}
+
+/// A capture-capable output pin of the source filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputPin {
+    /// Position among the filter's output pins.
+    pub position: usize,
+    pub name: String,
+}
+
+/// A crossbar input pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrossbarInput {
+    pub index: i32,
+    pub physical_type: i32,
+    /// Whether the crossbar can route it to the video output.
+    pub routable: bool,
+}
+
+/// What graph setup found on a device's crossbar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crossbar {
+    /// Output pin feeding the capture filter.
+    pub video_output: i32,
+    pub inputs: Vec<CrossbarInput>,
+}
+
+/// The saved source choice for a camera. The name is kept alongside the
+/// index so a device whose inputs moved around still finds the same one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcePreference {
+    pub index: u32,
+    pub name: String,
+}
+
+impl From<&VideoSource> for SourcePreference {
+    fn from(source: &VideoSource) -> Self {
+        Self {
+            index: source.index,
+            name: source.name.clone(),
+        }
+    }
+}
+
+/// The sources a device offers, indexed from 0.
+///
+/// A crossbar with routable video inputs wins: its inputs are the sources
+/// and capture stays on the first pin. Otherwise every capture pin is a
+/// source. Repeated input kinds are numbered ("SDI 1", "SDI 2"); pins keep
+/// the driver's names, falling back to "Capture N" when a pin has none.
+pub fn list_sources(pins: &[OutputPin], crossbar: Option<&Crossbar>) -> Vec<VideoSource> {
+    let crossbar_sources: Vec<(SourceKind, SourceRoute)> = crossbar
+        .map(|crossbar| {
+            crossbar
+                .inputs
+                .iter()
+                .filter(|input| input.routable)
+                .filter_map(|input| {
+                    let kind = SourceKind::from_physical_type(input.physical_type)?;
+                    let route = SourceRoute::Crossbar {
+                        output: crossbar.video_output,
+                        input: input.index,
+                    };
+                    Some((kind, route))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !crossbar_sources.is_empty() {
+        let count = |kind| crossbar_sources.iter().filter(|(k, _)| *k == kind).count();
+        let mut seen = Vec::new();
+        return crossbar_sources
+            .iter()
+            .enumerate()
+            .map(|(i, &(kind, route))| {
+                seen.push(kind);
+                let name = if count(kind) > 1 {
+                    let n = seen.iter().filter(|&&k| k == kind).count();
+                    format!("{} {n}", kind.label())
+                } else {
+                    kind.label().to_string()
+                };
+                VideoSource {
+                    index: i as u32,
+                    name,
+                    kind,
+                    route,
+                }
+            })
+            .collect();
+    }
+
+    pins.iter()
+        .enumerate()
+        .map(|(i, pin)| {
+            let name = pin.name.trim();
+            VideoSource {
+                index: i as u32,
+                name: if name.is_empty() {
+                    format!("{} {}", SourceKind::Pin.label(), i + 1)
+                } else {
+                    name.to_string()
+                },
+                kind: SourceKind::Pin,
+                route: SourceRoute::Pin(pin.position),
+            }
+        })
+        .collect()
+}
+
+/// Which source a session captures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceChoice<'a> {
+    /// The source to select, or `None` to leave the device as it is.
+    pub source: Option<&'a VideoSource>,
+    /// Set when a saved choice matched none of the sources, so the caller
+    /// can say the device fell back to its first.
+    pub preference_missing: bool,
+}
+
+/// Pick the source to capture from `sources` given the saved `preference`.
+///
+/// The saved index wins while its name still matches; otherwise the first
+/// source with the saved name, so a reordered device keeps its input. A
+/// preference matching neither falls back to the first source and is
+/// flagged. Without a preference the device is left on whatever it was
+/// routed to, except that multi-pin devices capture their first pin.
+pub fn choose_source<'a>(
+    sources: &'a [VideoSource],
+    preference: Option<&SourcePreference>,
+) -> SourceChoice<'a> {
+    let Some(preference) = preference else {
+        return SourceChoice {
+            source: sources.first().filter(|s| s.kind == SourceKind::Pin),
+            preference_missing: false,
+        };
+    };
+    let by_index = sources
+        .iter()
+        .find(|s| s.index == preference.index && s.name == preference.name);
+    let by_name = || sources.iter().find(|s| s.name == preference.name);
+    match by_index.or_else(by_name) {
+        Some(source) => SourceChoice {
+            source: Some(source),
+            preference_missing: false,
+        },
+        None => SourceChoice {
+            source: sources.first(),
+            preference_missing: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(position: usize, name: &str) -> OutputPin {
+        OutputPin {
+            position,
+            name: name.to_string(),
+        }
+    }
+
+    fn input(index: i32, physical_type: i32) -> CrossbarInput {
+        CrossbarInput {
+            index,
+            physical_type,
+            routable: true,
+        }
+    }
+
+    fn crossbar(inputs: Vec<CrossbarInput>) -> Crossbar {
+        Crossbar {
+            video_output: 0,
+            inputs,
+        }
+    }
+
+    fn preference(index: u32, name: &str) -> SourcePreference {
+        SourcePreference {
+            index,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn crossbar_video_inputs_become_numbered_sources() {
+        let bar = crossbar(vec![
+            input(0, 6),
+            input(1, 0x1001), // audio
+            input(2, 6),
+            input(3, 2),
+            input(4, 12), // decoder
+            CrossbarInput {
+                routable: false,
+                ..input(5, 3)
+            },
+        ]);
+        let sources = list_sources(&[pin(0, "Capture")], Some(&bar));
+
+        let names: Vec<_> = sources.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["SDI 1", "SDI 2", "Composite"]);
+        assert_eq!(
+            sources.iter().map(|s| s.index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        assert_eq!(
+            sources[1].route,
+            SourceRoute::Crossbar {
+                output: 0,
+                input: 2
+            }
+        );
+        assert_eq!(sources[2].kind, SourceKind::Composite);
+    }
+
+    #[test]
+    fn without_crossbar_inputs_each_capture_pin_is_a_source() {
+        let pins = [pin(0, "Capture 1"), pin(2, ""), pin(3, " Program ")];
+        for bar in [None, Some(crossbar(vec![input(0, 0x1000)]))] {
+            let sources = list_sources(&pins, bar.as_ref());
+            let names: Vec<_> = sources.iter().map(|s| s.name.as_str()).collect();
+            assert_eq!(names, ["Capture 1", "Capture 2", "Program"]);
+            assert_eq!(sources[1].route, SourceRoute::Pin(2));
+            assert!(sources.iter().all(|s| s.kind == SourceKind::Pin));
+        }
+    }
+
+    #[test]
+    fn saved_choice_is_found_by_index_then_by_name() {
+        let sources = list_sources(&[], Some(&crossbar(vec![input(0, 7), input(1, 6)])));
+        assert_eq!(sources[0].name, "HDMI/DVI");
+
+        let choice = choose_source(&sources, Some(&preference(1, "SDI")));
+        assert_eq!(choice.source, Some(&sources[1]));
+        assert!(!choice.preference_missing);
+
+        // The inputs moved: the name still finds it
+        let choice = choose_source(&sources, Some(&preference(0, "SDI")));
+        assert_eq!(choice.source, Some(&sources[1]));
+        assert!(!choice.preference_missing);
+    }
+
+    #[test]
+    fn a_missing_saved_choice_falls_back_to_the_first_source() {
+        let sources = list_sources(&[pin(0, "A"), pin(1, "B")], None);
+        let choice = choose_source(&sources, Some(&preference(4, "Gone")));
+        assert_eq!(choice.source, Some(&sources[0]));
+        assert!(choice.preference_missing);
+
+        // A device that now has no sources at all
+        let choice = choose_source(&[], Some(&preference(0, "A")));
+        assert_eq!(choice.source, None);
+        assert!(choice.preference_missing);
+    }
+
+    #[test]
+    fn without_a_saved_choice_crossbars_are_left_alone() {
+        let pins = list_sources(&[pin(1, "A"), pin(2, "B")], None);
+        assert_eq!(choose_source(&pins, None).source, Some(&pins[0]));
+
+        let inputs = list_sources(&[], Some(&crossbar(vec![input(0, 2), input(1, 3)])));
+        let choice = choose_source(&inputs, None);
+        assert_eq!(choice.source, None);
+        assert!(!choice.preference_missing);
+    }
+
+    #[test]
+    fn serialises_with_a_type_and_no_route() {
+        let sources = list_sources(&[], Some(&crossbar(vec![input(3, 3)])));
+        let json = serde_json::to_value(&sources[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "index": 0, "name": "S-Video", "type": "sVideo" })
+        );
+    }
+}
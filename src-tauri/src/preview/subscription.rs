@@ -0,0 +1,304 @@
+//! Preview frames pushed to the webview as events, instead of the frontend
+//! polling `get_frame`: a task per subscribed camera checks for a new frame
+//! at most `max_fps` times a second and emits it only when it has changed.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+use tokio::time::MissedTickBehavior;
+
+/// Fastest a subscription is checked, whatever rate it asks for.
+pub const MAX_SUBSCRIPTION_FPS: f32 = 120.0;
+
+/// Payload emitted via the `preview-frame` Tauri event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewFramePayload {
+    pub device_id: String,
+    /// Sequence number of the frame; 0 for the "no signal" card.
+    pub sequence: u64,
+    /// Base64-encoded JPEG, exactly as `get_frame` would return it.
+    pub jpeg: String,
+}
+
+/// Time between a subscription's checks for a new frame at `max_fps`,
+/// capped at [`MAX_SUBSCRIPTION_FPS`].
+pub fn frame_interval(max_fps: f32) -> Result<Duration, String> {
+    if !max_fps.is_finite() || max_fps <= 0.0 {
+        return Err(format!("max_fps must be a positive number, got {max_fps}"));
+    }
+    Ok(Duration::from_secs_f64(
+        1.0 / f64::from(max_fps.min(MAX_SUBSCRIPTION_FPS)),
+    ))
+}
+
+/// The active subscriptions, by device ID. Each holds the sender whose
+/// drop stops its task.
+#[derive(Default)]
+pub struct Subscriptions {
+    active: HashMap<String, oneshot::Sender<()>>,
+}
+
+impl Subscriptions {
+    /// Register a subscription for `device_id`, stopping any it already
+    /// had. Returns the signal to pass to [`run`].
+    pub fn subscribe(&mut self, device_id: &str) -> oneshot::Receiver<()> {
+        let (stop, stopped) = oneshot::channel();
+        self.active.insert(device_id.to_string(), stop);
+        stopped
+    }
+
+    /// Stop `device_id`'s subscription, returning whether it had one.
+    pub fn unsubscribe(&mut self, device_id: &str) -> bool {
+        self.active.remove(device_id).is_some()
+    }
+
+    /// Whether `device_id` has a subscription.
+    pub fn is_subscribed(&self, device_id: &str) -> bool {
+        self.active
+            .get(device_id)
+            .is_some_and(|stop| !stop.is_closed())
+    }
+}
+
+/// Check for a new frame every `interval` until `stopped` fires or its
+/// sender is dropped, passing each frame `latest` returns to `emit`.
+///
+/// `latest` is given the sequence last emitted and returns `None` when
+/// there's no newer frame, so unchanged frames are never re-encoded. Ticks
+/// missed while a frame is being encoded are skipped rather than bunched.
+pub async fn run<F, Fut>(
+    interval: Duration,
+    mut stopped: oneshot::Receiver<()>,
+    mut latest: F,
+    mut emit: impl FnMut(u64, String),
+) where
+    F: FnMut(Option<u64>) -> Fut,
+    Fut: Future<Output = Option<(u64, String)>>,
+{
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut last = None;
+    loop {
+        tokio::select! {
+            _ = &mut stopped => break,
+            _ = ticks.tick() => {}
+        }
+        let Some((sequence, jpeg)) = latest(last).await else {
+            continue;
+        };
+        // Unsubscribed while the frame was being encoded
+        if !matches!(stopped.try_recv(), Err(oneshot::error::TryRecvError::Empty)) {
+            break;
+        }
+        last = Some(sequence);
+        emit(sequence, jpeg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::dummy::DummyBackend;
+    use crate::preview::encode_worker::{JpegFrame, JpegFrameBuffer};
+    use crate::preview::mf_jpeg::encoder::EncoderKind;
+    use crate::preview::render::Orientation;
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    /// The dummy camera's test frame, as the encode worker would store it.
+    fn dummy_frame() -> JpegFrame {
+        JpegFrame {
+            jpeg_bytes: DummyBackend::test_frame(),
+            width: 1,
+            height: 1,
+            encoder_kind: EncoderKind::CpuFallback,
+            orientation: Orientation::default(),
+            source_sequence: 0,
+        }
+    }
+
+    /// Push the dummy camera's test frame into `buffer` every millisecond
+    /// until `done` is set, returning how many were pushed.
+    fn push_dummy_frames(
+        buffer: Arc<JpegFrameBuffer>,
+        done: Arc<AtomicBool>,
+    ) -> std::thread::JoinHandle<u64> {
+        std::thread::spawn(move || {
+            let mut pushed = 0;
+            while !done.load(Ordering::Relaxed) {
+                buffer.update(dummy_frame());
+                pushed += 1;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            pushed
+        })
+    }
+
+    /// The buffer's latest frame as base64, unless it's still `last`.
+    fn latest_from(
+        buffer: &JpegFrameBuffer,
+        last: Option<u64>,
+    ) -> std::future::Ready<Option<(u64, String)>> {
+        let sequence = buffer.sequence();
+        let frame = buffer.latest().filter(|_| Some(sequence) != last);
+        std::future::ready(frame.map(|frame| {
+            let jpeg = base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                &frame.jpeg_bytes,
+            );
+            (sequence, jpeg)
+        }))
+    }
+
+    #[test]
+    fn frame_interval_is_the_period_of_max_fps() {
+        assert_eq!(frame_interval(20.0), Ok(Duration::from_millis(50)));
+        assert_eq!(frame_interval(1000.0), frame_interval(MAX_SUBSCRIPTION_FPS));
+    }
+
+    #[test]
+    fn frame_interval_rejects_rates_that_are_not_positive() {
+        for fps in [0.0, -5.0, f32::NAN, f32::INFINITY] {
+            assert!(frame_interval(fps).is_err(), "{fps}");
+        }
+    }
+
+    #[test]
+    fn subscribing_again_stops_the_previous_subscription() {
+        let mut subscriptions = Subscriptions::default();
+        let mut first = subscriptions.subscribe("cam-1");
+        let _second = subscriptions.subscribe("cam-1");
+        assert!(first.try_recv().is_err());
+        assert!(subscriptions.is_subscribed("cam-1"));
+
+        assert!(subscriptions.unsubscribe("cam-1"));
+        assert!(!subscriptions.unsubscribe("cam-1"));
+        assert!(!subscriptions.is_subscribed("cam-1"));
+    }
+
+    #[tokio::test]
+    async fn emissions_are_throttled_to_max_fps() {
+        let buffer = Arc::new(JpegFrameBuffer::new());
+        let done = Arc::new(AtomicBool::new(false));
+        let pusher = push_dummy_frames(Arc::clone(&buffer), Arc::clone(&done));
+
+        let mut subscriptions = Subscriptions::default();
+        let stopped = subscriptions.subscribe("cam-1");
+        let emitted = Arc::new(Mutex::new(Vec::new()));
+        let task = {
+            let buffer = Arc::clone(&buffer);
+            let emitted = Arc::clone(&emitted);
+            tokio::spawn(async move {
+                run(
+                    frame_interval(20.0).unwrap(),
+                    stopped,
+                    |last| latest_from(&buffer, last),
+                    |sequence, jpeg| emitted.lock().push((sequence, jpeg)),
+                )
+                .await
+            })
+        };
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        subscriptions.unsubscribe("cam-1");
+        task.await.unwrap();
+        done.store(true, Ordering::Relaxed);
+        let pushed = pusher.join().unwrap();
+
+        let emitted = emitted.lock();
+        // One at the first tick, then one per 50ms at most
+        assert!(!emitted.is_empty());
+        assert!(emitted.len() <= 8, "{} emitted", emitted.len());
+        assert!(pushed > emitted.len() as u64 * 2, "{pushed} pushed");
+        assert!(emitted.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        let expected = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            DummyBackend::test_frame(),
+        );
+        assert_eq!(emitted[0].1, expected);
+    }
+
+    #[tokio::test]
+    async fn unchanged_frames_are_not_emitted_again() {
+        let buffer = Arc::new(JpegFrameBuffer::new());
+        buffer.update(dummy_frame());
+
+        let mut subscriptions = Subscriptions::default();
+        let stopped = subscriptions.subscribe("cam-1");
+        let emitted = Arc::new(Mutex::new(0));
+        let task = {
+            let emitted = Arc::clone(&emitted);
+            tokio::spawn(async move {
+                run(
+                    Duration::from_millis(5),
+                    stopped,
+                    |last| latest_from(&buffer, last),
+                    |_, _| *emitted.lock() += 1,
+                )
+                .await
+            })
+        };
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        subscriptions.unsubscribe("cam-1");
+        task.await.unwrap();
+        assert_eq!(*emitted.lock(), 1);
+    }
+
+    #[tokio::test]
+    async fn unsubscribing_ends_the_task_and_its_emissions() {
+        let buffer = Arc::new(JpegFrameBuffer::new());
+        let done = Arc::new(AtomicBool::new(false));
+        let pusher = push_dummy_frames(Arc::clone(&buffer), Arc::clone(&done));
+
+        let mut subscriptions = Subscriptions::default();
+        let stopped = subscriptions.subscribe("cam-1");
+        let emitted = Arc::new(Mutex::new(0));
+        let task = {
+            let buffer = Arc::clone(&buffer);
+            let emitted = Arc::clone(&emitted);
+            tokio::spawn(async move {
+                run(
+                    Duration::from_millis(10),
+                    stopped,
+                    |last| latest_from(&buffer, last),
+                    |_, _| *emitted.lock() += 1,
+                )
+                .await
+            })
+        };
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(subscriptions.unsubscribe("cam-1"));
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("task should end once unsubscribed")
+            .unwrap();
+        let after_unsubscribe = *emitted.lock();
+        assert!(after_unsubscribe > 0);
+
+        // Frames keep arriving, but nothing is emitted for them
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        done.store(true, Ordering::Relaxed);
+        pusher.join().unwrap();
+        assert_eq!(*emitted.lock(), after_unsubscribe);
+    }
+
+    #[test]
+    fn preview_frame_payload_serialises_correctly() {
+        let payload = PreviewFramePayload {
+            device_id: "cam-1".to_string(),
+            sequence: 42,
+            jpeg: "abc".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({ "deviceId": "cam-1", "sequence": 42, "jpeg": "abc" })
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! Frame taps — push delivery of every raw frame to streaming consumers.
+//!
+//! Polling `FrameBuffer::latest()` misses any frame that is overwritten
+//! between two polls. A tap instead receives each frame pushed into the
+//! buffer through its own bounded queue. Sending never blocks the capture
+//! callback: when a tap's queue is full its oldest frame is dropped and
+//! counted against that tap, so one slow consumer can't stall the camera or
+//! the other taps.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use super::capture::Frame;
+
+/// Identifies a registered tap within one frame buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct TapId(u64);
+
+impl TapId {
+    /// Wrap a raw id, e.g. one sent back from the frontend.
+    pub fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// The raw id.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+/// Queue shared between the registry (sending side) and a `FrameTap`.
+struct TapQueue {
+    frames: Mutex<VecDeque<Arc<Frame>>>,
+    capacity: usize,
+    overflowed: AtomicU64,
+    closed: AtomicBool,
+    notify: Notify,
+}
+
+impl TapQueue {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            frames: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            overflowed: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Queue `frame`, dropping the oldest queued frame if full.
+    fn offer(&self, frame: &Arc<Frame>) {
+        {
+            let mut frames = self.frames.lock();
+            if frames.len() >= self.capacity {
+                frames.pop_front();
+                self.overflowed.fetch_add(1, Ordering::Relaxed);
+            }
+            frames.push_back(Arc::clone(frame));
+        }
+        self.notify.notify_one();
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+}
+
+/// Receiving end of a tap. Dropping it unregisters the tap.
+pub struct FrameTap {
+    id: TapId,
+    queue: Arc<TapQueue>,
+}
+
+impl FrameTap {
+    /// This tap's id, for `FrameTaps::unregister`.
+    pub fn id(&self) -> TapId {
+        self.id
+    }
+
+    /// Take the oldest queued frame without waiting.
+    pub fn try_recv(&self) -> Option<Arc<Frame>> {
+        self.queue.frames.lock().pop_front()
+    }
+
+    /// Wait for the next frame. Returns `None` once the tap has been
+    /// unregistered or its session stopped, after queued frames are drained.
+    pub async fn recv(&self) -> Option<Arc<Frame>> {
+        loop {
+            if let Some(frame) = self.try_recv() {
+                return Some(frame);
+            }
+            if self.queue.is_closed() {
+                // A frame may have been queued just before the close
+                return self.try_recv();
+            }
+            // `notify_one` stores a permit, so a frame offered between the
+            // checks above and this await still wakes us
+            self.queue.notify.notified().await;
+        }
+    }
+
+    /// Frames dropped from this tap's queue because it was full.
+    pub fn overflowed(&self) -> u64 {
+        self.queue.overflowed.load(Ordering::Relaxed)
+    }
+
+    /// Whether the tap has been unregistered or its session stopped.
+    pub fn is_closed(&self) -> bool {
+        self.queue.is_closed()
+    }
+}
+
+impl Drop for FrameTap {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+/// The taps registered on one frame buffer.
+#[derive(Default)]
+pub struct FrameTaps {
+    next_id: AtomicU64,
+    taps: Mutex<Vec<(TapId, Arc<TapQueue>)>>,
+}
+
+impl FrameTaps {
+    /// Register a tap that queues up to `capacity` frames (at least one).
+    pub fn register(&self, capacity: usize) -> FrameTap {
+        let id = TapId(self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let queue = Arc::new(TapQueue::new(capacity));
+        self.taps.lock().push((id, Arc::clone(&queue)));
+        FrameTap { id, queue }
+    }
+
+    /// Remove a tap, returning whether it was registered. No frame is
+    /// queued to it once this returns; frames already queued can still be
+    /// received.
+    pub fn unregister(&self, id: TapId) -> bool {
+        let mut taps = self.taps.lock();
+        let Some(index) = taps.iter().position(|(tap_id, _)| *tap_id == id) else {
+            return false;
+        };
+        let (_, queue) = taps.swap_remove(index);
+        queue.close();
+        true
+    }
+
+    /// Queue `frame` to every registered tap without blocking.
+    ///
+    /// Taps whose receiver was dropped are removed here.
+    pub fn send(&self, frame: &Arc<Frame>) {
+        let mut taps = self.taps.lock();
+        taps.retain(|(_, queue)| !queue.is_closed());
+        for (_, queue) in taps.iter() {
+            queue.offer(frame);
+        }
+    }
+
+    /// Unregister every tap, ending their streams.
+    pub fn close_all(&self) {
+        for (_, queue) in self.taps.lock().drain(..) {
+            queue.close();
+        }
+    }
+
+    /// Frames dropped on overflow, per registered tap.
+    pub fn overflows(&self) -> Vec<(TapId, u64)> {
+        self.taps
+            .lock()
+            .iter()
+            .map(|(id, queue)| (*id, queue.overflowed.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Number of registered taps, including any whose receiver was dropped
+    /// since the last send.
+    pub fn len(&self) -> usize {
+        self.taps.lock().len()
+    }
+
+    /// Whether no taps are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn frame(n: u64) -> Arc<Frame> {
+        Arc::new(Frame {
+            data: vec![0; 3],
+            width: 1,
+            height: 1,
+            timestamp_us: n,
+            device_timestamp_us: n,
+        })
+    }
+
+    fn drain(tap: &FrameTap) -> Vec<u64> {
+        std::iter::from_fn(|| tap.try_recv())
+            .map(|f| f.timestamp_us)
+            .collect()
+    }
+
+    fn assert_consecutive(received: &[u64]) {
+        for pair in received.windows(2) {
+            assert_eq!(pair[1], pair[0] + 1, "gap or reorder in {received:?}");
+        }
+    }
+
+    #[test]
+    fn every_tap_gets_every_frame_in_order() {
+        let taps = FrameTaps::default();
+        let a = taps.register(8);
+        let b = taps.register(8);
+        for n in 1..=5 {
+            taps.send(&frame(n));
+        }
+        assert_eq!(drain(&a), vec![1, 2, 3, 4, 5]);
+        assert_eq!(drain(&b), vec![1, 2, 3, 4, 5]);
+        assert_eq!(a.overflowed(), 0);
+    }
+
+    #[test]
+    fn overflow_drops_the_oldest_and_counts_per_tap() {
+        let taps = FrameTaps::default();
+        let small = taps.register(2);
+        let large = taps.register(10);
+        for n in 1..=5 {
+            taps.send(&frame(n));
+        }
+
+        assert_eq!(drain(&small), vec![4, 5]);
+        assert_eq!(small.overflowed(), 3);
+        assert_eq!(drain(&large), vec![1, 2, 3, 4, 5]);
+        assert_eq!(large.overflowed(), 0);
+
+        let overflows = taps.overflows();
+        assert!(overflows.contains(&(small.id(), 3)));
+        assert!(overflows.contains(&(large.id(), 0)));
+    }
+
+    #[test]
+    fn unregistered_tap_keeps_queued_frames_but_gets_no_more() {
+        let taps = FrameTaps::default();
+        let tap = taps.register(8);
+        taps.send(&frame(1));
+
+        assert!(taps.unregister(tap.id()));
+        assert!(!taps.unregister(tap.id()));
+        taps.send(&frame(2));
+
+        assert!(tap.is_closed());
+        assert_eq!(drain(&tap), vec![1]);
+        assert!(taps.is_empty());
+    }
+
+    #[test]
+    fn dropped_receivers_are_pruned_on_send() {
+        let taps = FrameTaps::default();
+        let kept = taps.register(4);
+        drop(taps.register(4));
+        assert_eq!(taps.len(), 2);
+
+        taps.send(&frame(1));
+        assert_eq!(taps.len(), 1);
+        assert_eq!(drain(&kept), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn recv_waits_for_frames_and_ends_when_closed() {
+        let taps = Arc::new(FrameTaps::default());
+        let tap = taps.register(4);
+
+        let sender = Arc::clone(&taps);
+        let producer = tokio::spawn(async move {
+            for n in 1..=3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                sender.send(&frame(n));
+            }
+            sender.close_all();
+        });
+
+        let mut received = Vec::new();
+        while let Some(frame) = tap.recv().await {
+            received.push(frame.timestamp_us);
+        }
+        producer.await.unwrap();
+        assert_eq!(received, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn taps_churning_while_frames_flow() {
+        const FRAMES: u64 = 20_000;
+        let taps = Arc::new(FrameTaps::default());
+        let done = Arc::new(AtomicBool::new(false));
+
+        let producer = {
+            let taps = Arc::clone(&taps);
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                for n in 1..=FRAMES {
+                    taps.send(&frame(n));
+                }
+                done.store(true, Ordering::Release);
+            })
+        };
+
+        let mut checked = 0;
+        while !done.load(Ordering::Acquire) {
+            let tap = taps.register(4096);
+            std::thread::yield_now();
+            assert!(taps.unregister(tap.id()));
+            let received = drain(&tap);
+
+            // Nothing arrives after unregister
+            std::thread::yield_now();
+            assert!(tap.try_recv().is_none());
+
+            // Overflow only trims the front, so what's left is unbroken
+            assert_consecutive(&received);
+            checked += 1;
+        }
+        producer.join().unwrap();
+
+        assert!(checked > 0);
+        assert!(taps.is_empty());
+    }
+
+    #[test]
+    fn overflow_accounting_holds_under_concurrent_sends() {
+        const FRAMES: u64 = 10_000;
+        let taps = Arc::new(FrameTaps::default());
+        let tap = taps.register(16);
+
+        let producer = {
+            let taps = Arc::clone(&taps);
+            std::thread::spawn(move || {
+                for n in 1..=FRAMES {
+                    taps.send(&frame(n));
+                }
+            })
+        };
+
+        let mut received = Vec::new();
+        while !producer.is_finished() {
+            received.extend(tap.try_recv().map(|f| f.timestamp_us));
+        }
+        producer.join().unwrap();
+        received.extend(drain(&tap));
+
+        assert!(
+            received.windows(2).all(|w| w[0] < w[1]),
+            "frames arrive in order"
+        );
+        assert_eq!(received.last(), Some(&FRAMES));
+
+        // Every frame was either received or counted as dropped
+        assert_eq!(received.len() as u64 + tap.overflowed(), FRAMES);
+    }
+}
@@ -0,0 +1,448 @@
+// Thumbnail sizing negotiated with the frontend.
+//
+// The frontend reports the CSS box a thumbnail is shown in and the display's
+// device pixel ratio, globally or for one device. Thumbnails are drawn at the
+// physical size of that box, with the frame's aspect ratio fitted inside it
+// and never larger than the frame itself, so they are sharp on high-DPI
+// screens without wasting bandwidth on low-DPI ones.
+//
+// A single get_thumbnail call may also ask for its own box, in physical
+// pixels, and how the frame fills it: fitted inside (the default), cropped
+// to cover it or stretched to it.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::compress::PixelRect;
+
+/// CSS box used until the frontend configures one.
+pub const DEFAULT_THUMBNAIL_BOX: (u32, u32) = (160, 120);
+
+/// Bounds on each side of the physical box, in pixels.
+const MIN_THUMBNAIL_SIDE: u32 = 16;
+const MAX_THUMBNAIL_SIDE: u32 = 1280;
+
+/// Bounds on the device pixel ratio. Browsers report anything from zoomed-out
+/// fractions to 5+ on some phones.
+const MIN_DPR: f32 = 0.5;
+const MAX_DPR: f32 = 4.0;
+
+/// The CSS box a thumbnail is shown in and the display's pixel ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThumbnailConfig {
+    css_width: u32,
+    css_height: u32,
+    dpr: f32,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            css_width: DEFAULT_THUMBNAIL_BOX.0,
+            css_height: DEFAULT_THUMBNAIL_BOX.1,
+            dpr: 1.0,
+        }
+    }
+}
+
+impl ThumbnailConfig {
+    /// Validate a configuration from the frontend.
+    pub fn new(css_width: u32, css_height: u32, dpr: f32) -> Result<Self, String> {
+        if css_width == 0 || css_height == 0 {
+            return Err(format!(
+                "Thumbnail size must be positive, got {css_width}x{css_height}"
+            ));
+        }
+        if !dpr.is_finite() || dpr <= 0.0 {
+            return Err(format!("Device pixel ratio must be positive, got {dpr}"));
+        }
+        Ok(Self {
+            css_width,
+            css_height,
+            dpr,
+        })
+    }
+
+    /// The box in physical pixels: the CSS size times the (clamped) pixel
+    /// ratio, each side clamped to sane bounds.
+    pub fn physical_box(&self) -> (u32, u32) {
+        let dpr = self.dpr.clamp(MIN_DPR, MAX_DPR);
+        let side = |css: u32| {
+            ((css as f32 * dpr).round() as u32).clamp(MIN_THUMBNAIL_SIDE, MAX_THUMBNAIL_SIDE)
+        };
+        (side(self.css_width), side(self.css_height))
+    }
+}
+
+/// How a thumbnail fills its box when the frame's aspect ratio differs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFit {
+    /// Scaled to the box exactly, distorting the frame.
+    Stretch,
+    /// The whole frame, fitted inside the box. Comes out smaller than the
+    /// box on one side; the frontend letterboxes it.
+    #[default]
+    Contain,
+    /// The middle of the frame, cropped to the box's aspect ratio so it
+    /// fills the box.
+    Cover,
+}
+
+/// A box and fit asked for by a single `get_thumbnail` call, in physical
+/// pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThumbnailSpec {
+    pub width: u32,
+    pub height: u32,
+    pub fit: ThumbnailFit,
+}
+
+impl ThumbnailSpec {
+    /// Validate a box from the frontend.
+    pub fn new(width: u32, height: u32, fit: ThumbnailFit) -> Result<Self, String> {
+        let valid = MIN_THUMBNAIL_SIDE..=MAX_THUMBNAIL_SIDE;
+        if !valid.contains(&width) || !valid.contains(&height) {
+            return Err(format!(
+                "Thumbnail sides must be between {MIN_THUMBNAIL_SIDE} and \
+                 {MAX_THUMBNAIL_SIDE}, got {width}x{height}"
+            ));
+        }
+        Ok(Self { width, height, fit })
+    }
+}
+
+/// The part of a frame a thumbnail shows and the size to draw it at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailPlan {
+    /// Region of the frame to draw, or `None` for all of it.
+    pub crop: Option<PixelRect>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Plan a thumbnail of a `source` frame for a `thumb_box` filled as `fit`
+/// says. Never larger than the frame, so small frames aren't upscaled.
+pub fn plan_thumbnail(
+    thumb_box: (u32, u32),
+    fit: ThumbnailFit,
+    source: (u32, u32),
+) -> ThumbnailPlan {
+    let (width, height) = (source.0.max(1), source.1.max(1));
+    let (box_width, box_height) = (thumb_box.0.max(1), thumb_box.1.max(1));
+    match fit {
+        ThumbnailFit::Stretch => ThumbnailPlan {
+            crop: None,
+            width: box_width.min(width),
+            height: box_height.min(height),
+        },
+        ThumbnailFit::Contain => {
+            let (width, height) = fit_inside(thumb_box, source);
+            ThumbnailPlan {
+                crop: None,
+                width,
+                height,
+            }
+        }
+        ThumbnailFit::Cover => {
+            // The largest centred region with the box's aspect ratio
+            let box_aspect = box_width as f64 / box_height as f64;
+            let (crop_width, crop_height) = if width as f64 / height as f64 > box_aspect {
+                let crop_width = (height as f64 * box_aspect).round() as u32;
+                (crop_width.clamp(1, width), height)
+            } else {
+                let crop_height = (width as f64 / box_aspect).round() as u32;
+                (width, crop_height.clamp(1, height))
+            };
+            let crop = (crop_width, crop_height) != (width, height);
+            let (thumb_width, thumb_height) = fit_inside(thumb_box, (crop_width, crop_height));
+            ThumbnailPlan {
+                crop: crop.then_some(PixelRect {
+                    x: (width - crop_width) / 2,
+                    y: (height - crop_height) / 2,
+                    width: crop_width,
+                    height: crop_height,
+                }),
+                width: thumb_width,
+                height: thumb_height,
+            }
+        }
+    }
+}
+
+/// Size to draw a thumbnail of a `source` frame at: the frame's aspect ratio
+/// fitted inside the configured box, never larger than the frame.
+pub fn thumbnail_size(config: &ThumbnailConfig, source: (u32, u32)) -> (u32, u32) {
+    fit_inside(config.physical_box(), source)
+}
+
+/// `source` scaled to fit inside `thumb_box`, keeping its aspect ratio and
+/// never growing.
+fn fit_inside(thumb_box: (u32, u32), source: (u32, u32)) -> (u32, u32) {
+    let (box_width, box_height) = thumb_box;
+    let (width, height) = (source.0.max(1), source.1.max(1));
+    let scale = (box_width as f64 / width as f64)
+        .min(box_height as f64 / height as f64)
+        .min(1.0);
+    let fit = |side: u32| ((side as f64 * scale).round() as u32).max(1);
+    (fit(width), fit(height))
+}
+
+/// Thumbnail configuration for every device: a global one, plus any
+/// configured for a single device.
+#[derive(Debug, Default)]
+pub struct ThumbnailSizes {
+    global: ThumbnailConfig,
+    devices: HashMap<String, ThumbnailConfig>,
+}
+
+impl ThumbnailSizes {
+    /// The configuration thumbnails of `device_id` are drawn with.
+    pub fn config_for(&self, device_id: &str) -> ThumbnailConfig {
+        self.devices.get(device_id).copied().unwrap_or(self.global)
+    }
+
+    /// Whether `device_id` has a configuration of its own.
+    pub fn has_own(&self, device_id: &str) -> bool {
+        self.devices.contains_key(device_id)
+    }
+
+    /// Configure one device, or with `None` every device without its own
+    /// configuration. Returns whether anything changed.
+    pub fn set(&mut self, device_id: Option<&str>, config: ThumbnailConfig) -> bool {
+        match device_id {
+            Some(id) => self.devices.insert(id.to_string(), config) != Some(config),
+            None => std::mem::replace(&mut self.global, config) != config,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(width: u32, height: u32, dpr: f32) -> ThumbnailConfig {
+        ThumbnailConfig::new(width, height, dpr).unwrap()
+    }
+
+    #[test]
+    fn default_box_is_160_by_120_at_1x() {
+        assert_eq!(ThumbnailConfig::default().physical_box(), (160, 120));
+    }
+
+    #[test]
+    fn physical_box_scales_by_pixel_ratio() {
+        assert_eq!(config(80, 60, 1.0).physical_box(), (80, 60));
+        assert_eq!(config(80, 60, 2.0).physical_box(), (160, 120));
+        assert_eq!(config(80, 60, 1.25).physical_box(), (100, 75));
+        assert_eq!(config(80, 60, 1.5).physical_box(), (120, 90));
+    }
+
+    #[test]
+    fn extreme_pixel_ratios_are_clamped() {
+        assert_eq!(config(80, 60, 10.0).physical_box(), (320, 240));
+        assert_eq!(config(80, 60, 0.1).physical_box(), (40, 30));
+    }
+
+    #[test]
+    fn physical_box_sides_are_clamped() {
+        assert_eq!(config(4000, 3000, 4.0).physical_box(), (1280, 1280));
+        assert_eq!(config(1, 1, 0.5).physical_box(), (16, 16));
+    }
+
+    #[test]
+    fn invalid_configurations_are_rejected() {
+        assert!(ThumbnailConfig::new(0, 60, 1.0).is_err());
+        assert!(ThumbnailConfig::new(80, 0, 1.0).is_err());
+        assert!(ThumbnailConfig::new(80, 60, 0.0).is_err());
+        assert!(ThumbnailConfig::new(80, 60, -2.0).is_err());
+        assert!(ThumbnailConfig::new(80, 60, f32::NAN).is_err());
+        assert!(ThumbnailConfig::new(80, 60, f32::INFINITY).is_err());
+    }
+
+    #[test]
+    fn matching_aspect_fills_the_box() {
+        let at_2x = config(80, 60, 2.0);
+        assert_eq!(thumbnail_size(&at_2x, (640, 480)), (160, 120));
+        assert_eq!(thumbnail_size(&at_2x, (1920, 1440)), (160, 120));
+    }
+
+    #[test]
+    fn wide_source_is_fitted_by_width() {
+        let at_1x = config(160, 120, 1.0);
+        assert_eq!(thumbnail_size(&at_1x, (1920, 1080)), (160, 90));
+        assert_eq!(thumbnail_size(&at_1x, (3840, 1080)), (160, 45));
+    }
+
+    #[test]
+    fn portrait_source_is_fitted_by_height() {
+        let at_1x = config(160, 120, 1.0);
+        assert_eq!(thumbnail_size(&at_1x, (480, 640)), (90, 120));
+        assert_eq!(thumbnail_size(&at_1x, (1080, 1920)), (68, 120));
+    }
+
+    #[test]
+    fn portrait_source_at_high_pixel_ratio() {
+        let at_3x = config(80, 60, 3.0);
+        assert_eq!(thumbnail_size(&at_3x, (1080, 1920)), (101, 180));
+    }
+
+    #[test]
+    fn small_sources_are_never_upscaled() {
+        let at_4x = config(160, 120, 4.0);
+        assert_eq!(thumbnail_size(&at_4x, (320, 240)), (320, 240));
+        assert_eq!(thumbnail_size(&at_4x, (64, 32)), (64, 32));
+    }
+
+    #[test]
+    fn extreme_aspect_keeps_at_least_one_pixel() {
+        let at_1x = config(160, 120, 1.0);
+        assert_eq!(thumbnail_size(&at_1x, (10_000, 2)), (160, 1));
+        assert_eq!(thumbnail_size(&at_1x, (2, 10_000)), (1, 120));
+    }
+
+    fn plan(thumb_box: (u32, u32), fit: ThumbnailFit, source: (u32, u32)) -> (u32, u32) {
+        let plan = plan_thumbnail(thumb_box, fit, source);
+        (plan.width, plan.height)
+    }
+
+    #[test]
+    fn contain_fits_the_whole_frame_inside_the_box() {
+        assert_eq!(
+            plan((160, 120), ThumbnailFit::Contain, (1920, 1080)),
+            (160, 90)
+        );
+        assert_eq!(
+            plan((160, 120), ThumbnailFit::Contain, (1080, 1920)),
+            (68, 120)
+        );
+        assert_eq!(
+            plan_thumbnail((160, 120), ThumbnailFit::Contain, (1920, 1080)).crop,
+            None
+        );
+    }
+
+    #[test]
+    fn cover_fills_the_box_with_the_middle_of_the_frame() {
+        let wide = plan_thumbnail((160, 120), ThumbnailFit::Cover, (1920, 1080));
+        assert_eq!((wide.width, wide.height), (160, 120));
+        assert_eq!(
+            wide.crop,
+            Some(PixelRect {
+                x: 240,
+                y: 0,
+                width: 1440,
+                height: 1080,
+            })
+        );
+
+        let tall = plan_thumbnail((160, 120), ThumbnailFit::Cover, (1080, 1920));
+        assert_eq!((tall.width, tall.height), (160, 120));
+        assert_eq!(
+            tall.crop,
+            Some(PixelRect {
+                x: 0,
+                y: 555,
+                width: 1080,
+                height: 810,
+            })
+        );
+
+        // Already the box's shape: nothing to crop
+        assert_eq!(
+            plan_thumbnail((160, 120), ThumbnailFit::Cover, (640, 480)).crop,
+            None
+        );
+    }
+
+    #[test]
+    fn stretch_fills_the_box_exactly() {
+        assert_eq!(
+            plan((160, 120), ThumbnailFit::Stretch, (1920, 1080)),
+            (160, 120)
+        );
+        assert_eq!(
+            plan((320, 90), ThumbnailFit::Stretch, (640, 480)),
+            (320, 90)
+        );
+    }
+
+    #[test]
+    fn odd_sources_are_planned_inside_the_frame() {
+        for fit in [
+            ThumbnailFit::Stretch,
+            ThumbnailFit::Contain,
+            ThumbnailFit::Cover,
+        ] {
+            for source in [(1921, 1081), (333, 7), (7, 333), (99, 101)] {
+                let plan = plan_thumbnail((160, 120), fit, source);
+                assert!(
+                    plan.width >= 1 && plan.width <= 160.min(source.0),
+                    "{fit:?} {source:?}"
+                );
+                assert!(
+                    plan.height >= 1 && plan.height <= 120.min(source.1),
+                    "{fit:?} {source:?}"
+                );
+                if let Some(crop) = plan.crop {
+                    assert_eq!(
+                        crop.clamp_to(source.0, source.1),
+                        Some(crop),
+                        "{fit:?} {source:?}"
+                    );
+                }
+            }
+        }
+        let cover = plan_thumbnail((160, 120), ThumbnailFit::Cover, (1921, 1081));
+        assert_eq!((cover.width, cover.height), (160, 120));
+    }
+
+    #[test]
+    fn a_single_pixel_frame_gives_a_single_pixel_thumbnail() {
+        for fit in [
+            ThumbnailFit::Stretch,
+            ThumbnailFit::Contain,
+            ThumbnailFit::Cover,
+        ] {
+            let plan = plan_thumbnail((160, 120), fit, (1, 1));
+            assert_eq!((plan.width, plan.height), (1, 1), "{fit:?}");
+            assert_eq!(plan.crop, None, "{fit:?}");
+        }
+    }
+
+    #[test]
+    fn specs_are_range_checked_and_fits_parse_lowercase() {
+        assert!(ThumbnailSpec::new(320, 180, ThumbnailFit::Cover).is_ok());
+        assert!(ThumbnailSpec::new(0, 180, ThumbnailFit::Cover).is_err());
+        assert!(ThumbnailSpec::new(320, 4000, ThumbnailFit::Cover).is_err());
+        assert_eq!(
+            serde_json::from_str::<ThumbnailFit>("\"cover\"").unwrap(),
+            ThumbnailFit::Cover
+        );
+        assert!(serde_json::from_str::<ThumbnailFit>("\"fill\"").is_err());
+    }
+
+    #[test]
+    fn device_configuration_overrides_the_global_one() {
+        let mut sizes = ThumbnailSizes::default();
+        assert_eq!(sizes.config_for("cam-1"), ThumbnailConfig::default());
+
+        assert!(sizes.set(None, config(80, 60, 2.0)));
+        assert!(sizes.set(Some("cam-1"), config(320, 240, 1.0)));
+
+        assert_eq!(sizes.config_for("cam-1"), config(320, 240, 1.0));
+        assert_eq!(sizes.config_for("cam-2"), config(80, 60, 2.0));
+        assert!(sizes.has_own("cam-1"));
+        assert!(!sizes.has_own("cam-2"));
+    }
+
+    #[test]
+    fn setting_the_same_configuration_is_not_a_change() {
+        let mut sizes = ThumbnailSizes::default();
+        assert!(!sizes.set(None, ThumbnailConfig::default()));
+        assert!(sizes.set(Some("cam-1"), config(80, 60, 2.0)));
+        assert!(!sizes.set(Some("cam-1"), config(80, 60, 2.0)));
+        assert!(sizes.set(Some("cam-1"), config(80, 60, 1.0)));
+    }
+}
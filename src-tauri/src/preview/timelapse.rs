@@ -0,0 +1,728 @@
+//! Time-lapse capture: a still from a preview session at a fixed interval.
+//!
+//! A recorder ticks on a fixed schedule. At each tick it takes the
+//! session's latest frame if a new one arrived since the last still, writes
+//! it as a numbered JPEG and notes it in an index manifest. Ticks with no
+//! fresh frame are skipped, and ticks while the session is stalled or
+//! restarting are paused; both are recorded in the manifest. Time, frames
+//! and storage are passed in, so the schedule and accounting run the same
+//! under test as in the app, which drives one recorder per camera from a
+//! worker thread.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the index manifest in the output folder.
+pub const MANIFEST_FILE: &str = "timelapse.json";
+
+/// Free space that must remain on the output drive after each still.
+pub const DEFAULT_MIN_FREE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Stills between progress reports (and manifest saves).
+pub const PROGRESS_EVERY: u32 = 10;
+
+/// Shortest interval between stills.
+pub const MIN_INTERVAL_SECS: u32 = 1;
+
+/// How a time-lapse is captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimelapseConfig {
+    pub interval: Duration,
+    /// Stop after this many stills; `None` runs until stopped.
+    pub max_frames: Option<u32>,
+    pub min_free_bytes: u64,
+    pub progress_every: u32,
+}
+
+impl TimelapseConfig {
+    /// A config with the default disk floor and progress cadence.
+    pub fn new(interval_secs: u32, max_frames: Option<u32>) -> Result<Self, String> {
+        if interval_secs < MIN_INTERVAL_SECS {
+            return Err(format!(
+                "interval must be at least {MIN_INTERVAL_SECS} second, got {interval_secs}"
+            ));
+        }
+        if max_frames == Some(0) {
+            return Err("maxFrames must be greater than zero".to_string());
+        }
+        Ok(Self {
+            interval: Duration::from_secs(u64::from(interval_secs)),
+            max_frames,
+            min_free_bytes: DEFAULT_MIN_FREE_BYTES,
+            progress_every: PROGRESS_EVERY,
+        })
+    }
+}
+
+/// Fixed-interval ticks from a start time, in Unix milliseconds. Tick `n`
+/// is due at `start + n * interval`; the first is due straight away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickSchedule {
+    start_ms: u64,
+    interval_ms: u64,
+    next_tick: u64,
+}
+
+impl TickSchedule {
+    pub fn new(start_ms: u64, interval: Duration) -> Self {
+        Self {
+            start_ms,
+            interval_ms: (interval.as_millis() as u64).max(1),
+            next_tick: 0,
+        }
+    }
+
+    /// When tick `tick` is due.
+    pub fn due_at(&self, tick: u64) -> u64 {
+        self.start_ms + tick * self.interval_ms
+    }
+
+    /// Take the ticks that came due by `now_ms` since the last call. More
+    /// than one means the caller was held up past a tick.
+    pub fn take_due(&mut self, now_ms: u64) -> Range<u64> {
+        let reached = match now_ms.checked_sub(self.start_ms) {
+            Some(elapsed) => elapsed / self.interval_ms + 1,
+            None => 0,
+        };
+        let due = self.next_tick..reached.max(self.next_tick);
+        self.next_tick = due.end;
+        due
+    }
+
+    /// Time from `now_ms` until the next tick is due.
+    pub fn until_next(&self, now_ms: u64) -> Duration {
+        Duration::from_millis(self.due_at(self.next_tick).saturating_sub(now_ms))
+    }
+}
+
+/// What a frame source had at a tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Still {
+    /// A frame that arrived since the last still, encoded as JPEG.
+    Fresh(Vec<u8>),
+    /// The session is running, but no frame arrived since the last still.
+    Unchanged,
+    /// The session is stalled, failed or restarting.
+    Unavailable,
+}
+
+/// Where stills come from.
+pub trait StillSource {
+    fn poll(&mut self) -> Still;
+}
+
+/// Where stills and the manifest go.
+pub trait StillStorage {
+    /// Free space on the output drive, where the OS reports it.
+    fn free_bytes(&self) -> Option<u64>;
+    /// Write `name` in full, replacing any earlier copy.
+    fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), String>;
+}
+
+/// Why a tick has no still.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    /// No frame arrived since the last still.
+    NoFreshFrame,
+    /// The session was stalled or restarting; capture was paused.
+    Stalled,
+    /// The recorder was held up past the tick.
+    Missed,
+}
+
+/// A still in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFrame {
+    /// File name in the output folder.
+    pub file: String,
+    pub tick: u64,
+    /// Unix milliseconds the still was taken.
+    pub captured_at: u64,
+    pub bytes: u64,
+}
+
+/// A tick without a still.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedTick {
+    pub tick: u64,
+    /// Unix milliseconds the tick was due.
+    pub due_at: u64,
+    pub reason: SkipReason,
+}
+
+/// Why a time-lapse ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    /// `stop_timelapse` was called.
+    Requested,
+    /// `max_frames` stills were written.
+    Completed,
+    /// The camera was unplugged.
+    Disconnected,
+    /// A still couldn't be written; see the manifest's `error`.
+    Failed,
+}
+
+/// Index of a time-lapse, written next to its stills.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub device_id: String,
+    pub interval_ms: u64,
+    pub started_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<StopReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub frames: Vec<ManifestFrame>,
+    pub skipped: Vec<SkippedTick>,
+}
+
+impl Manifest {
+    fn skipped_for(&self, reason: SkipReason) -> usize {
+        self.skipped.iter().filter(|s| s.reason == reason).count()
+    }
+}
+
+/// Payload emitted via the `timelapse-progress` and `timelapse-stopped`
+/// Tauri events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelapseProgress {
+    pub device_id: String,
+    pub frames_written: usize,
+    /// Ticks skipped for want of a fresh frame, including missed ones.
+    pub skipped_ticks: usize,
+    /// Ticks skipped while the session was stalled or restarting.
+    pub paused_ticks: usize,
+    pub bytes_written: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<StopReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What a call to [`Recorder::tick`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickOutcome {
+    /// No tick was due.
+    Idle,
+    /// A still was written; `report` is set every `progress_every` stills.
+    Captured {
+        report: bool,
+    },
+    Skipped(SkipReason),
+    /// The last still was written; the time-lapse is done.
+    Completed,
+}
+
+/// File name of the `index`th still (counting from 1).
+pub fn frame_file_name(index: usize) -> String {
+    format!("frame_{index:06}.jpg")
+}
+
+/// Drives one time-lapse.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    config: TimelapseConfig,
+    schedule: TickSchedule,
+    manifest: Manifest,
+}
+
+impl Recorder {
+    pub fn new(device_id: &str, config: TimelapseConfig, start_ms: u64) -> Self {
+        Self {
+            config,
+            schedule: TickSchedule::new(start_ms, config.interval),
+            manifest: Manifest {
+                device_id: device_id.to_string(),
+                interval_ms: config.interval.as_millis() as u64,
+                started_at: start_ms,
+                finished_at: None,
+                stop_reason: None,
+                error: None,
+                frames: Vec::new(),
+                skipped: Vec::new(),
+            },
+        }
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Time from `now_ms` until the next tick.
+    pub fn until_next(&self, now_ms: u64) -> Duration {
+        self.schedule.until_next(now_ms)
+    }
+
+    /// Handle the ticks due by `now_ms`. Ticks the recorder was held up
+    /// past are skipped as missed, and the latest one takes a still.
+    ///
+    /// Fails, writing nothing, if the still would leave less free space
+    /// than the floor, or if it can't be written.
+    pub fn tick(
+        &mut self,
+        now_ms: u64,
+        source: &mut impl StillSource,
+        storage: &mut impl StillStorage,
+    ) -> Result<TickOutcome, String> {
+        let due = self.schedule.take_due(now_ms);
+        let Some(tick) = due.end.checked_sub(1).filter(|_| !due.is_empty()) else {
+            return Ok(TickOutcome::Idle);
+        };
+        for missed in due.start..tick {
+            self.skip(missed, SkipReason::Missed);
+        }
+
+        let jpeg = match source.poll() {
+            Still::Fresh(jpeg) => jpeg,
+            Still::Unchanged => return Ok(self.skip(tick, SkipReason::NoFreshFrame)),
+            Still::Unavailable => return Ok(self.skip(tick, SkipReason::Stalled)),
+        };
+
+        if let Some(free) = storage.free_bytes() {
+            let needed = self.config.min_free_bytes + jpeg.len() as u64;
+            if free < needed {
+                return Err(format!(
+                    "Only {} MB free on the output drive; time-lapse stops below {} MB",
+                    free / (1024 * 1024),
+                    self.config.min_free_bytes / (1024 * 1024)
+                ));
+            }
+        }
+        let file = frame_file_name(self.manifest.frames.len() + 1);
+        storage.write(&file, &jpeg)?;
+        self.manifest.frames.push(ManifestFrame {
+            file,
+            tick,
+            captured_at: now_ms,
+            bytes: jpeg.len() as u64,
+        });
+
+        let written = self.manifest.frames.len();
+        if self
+            .config
+            .max_frames
+            .is_some_and(|max| written >= max as usize)
+        {
+            return Ok(TickOutcome::Completed);
+        }
+        let report = written % self.config.progress_every.max(1) as usize == 0;
+        if report {
+            self.save_manifest(storage)?;
+        }
+        Ok(TickOutcome::Captured { report })
+    }
+
+    fn skip(&mut self, tick: u64, reason: SkipReason) -> TickOutcome {
+        self.manifest.skipped.push(SkippedTick {
+            tick,
+            due_at: self.schedule.due_at(tick),
+            reason,
+        });
+        TickOutcome::Skipped(reason)
+    }
+
+    /// Write the manifest as it stands.
+    pub fn save_manifest(&self, storage: &mut impl StillStorage) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(&self.manifest).map_err(|e| e.to_string())?;
+        storage.write(MANIFEST_FILE, &json)
+    }
+
+    /// Record why and when the time-lapse ended and write the final
+    /// manifest.
+    pub fn finish(
+        &mut self,
+        now_ms: u64,
+        reason: StopReason,
+        error: Option<String>,
+        storage: &mut impl StillStorage,
+    ) -> Result<(), String> {
+        self.manifest.finished_at = Some(now_ms);
+        self.manifest.stop_reason = Some(reason);
+        self.manifest.error = error;
+        self.save_manifest(storage)
+    }
+
+    pub fn progress(&self) -> TimelapseProgress {
+        let manifest = &self.manifest;
+        TimelapseProgress {
+            device_id: manifest.device_id.clone(),
+            frames_written: manifest.frames.len(),
+            skipped_ticks: manifest.skipped_for(SkipReason::NoFreshFrame)
+                + manifest.skipped_for(SkipReason::Missed),
+            paused_ticks: manifest.skipped_for(SkipReason::Stalled),
+            bytes_written: manifest.frames.iter().map(|f| f.bytes).sum(),
+            last_file: manifest.frames.last().map(|f| f.file.clone()),
+            stop_reason: manifest.stop_reason,
+            error: manifest.error.clone(),
+        }
+    }
+}
+
+/// Stills written to a folder on disk.
+pub struct DirStorage {
+    dir: PathBuf,
+}
+
+impl DirStorage {
+    /// Use `dir`, creating it if needed. A folder already holding a
+    /// time-lapse is refused, so stills are never overwritten.
+    pub fn create(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Can't create {}: {e}", dir.display()))?;
+        if dir.join(MANIFEST_FILE).exists() {
+            return Err(format!(
+                "{} already holds a time-lapse; choose another folder",
+                dir.display()
+            ));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+impl StillStorage for DirStorage {
+    fn free_bytes(&self) -> Option<u64> {
+        free_disk_bytes(&self.dir)
+    }
+
+    /// Written through a temporary file, so a crash never leaves a
+    /// truncated still or manifest.
+    fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), String> {
+        let path = self.dir.join(name);
+        let tmp = self.dir.join(format!("{name}.tmp"));
+        std::fs::write(&tmp, bytes).map_err(|e| format!("Can't write {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("Can't write {}: {e}", path.display()))
+    }
+}
+
+/// Free space available to this user on the drive holding `dir`.
+#[cfg(target_os = "windows")]
+pub fn free_disk_bytes(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and outlives the call; `available`
+    // is a valid out pointer.
+    unsafe {
+        GetDiskFreeSpaceExW(
+            PCWSTR(wide.as_ptr()),
+            Some(&mut available as *mut u64),
+            None,
+            None,
+        )
+    }
+    .ok()?;
+    Some(available)
+}
+
+/// Other platforms don't report free space, so no floor is enforced.
+#[cfg(not(target_os = "windows"))]
+pub fn free_disk_bytes(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+
+    const START: u64 = 1_700_000_000_000;
+    const SECOND: u64 = 1000;
+
+    /// Replays scripted stills; `Unchanged` once the script runs out.
+    struct Script(VecDeque<Still>);
+
+    impl Script {
+        fn new(stills: impl IntoIterator<Item = Still>) -> Self {
+            Self(stills.into_iter().collect())
+        }
+    }
+
+    impl StillSource for Script {
+        fn poll(&mut self) -> Still {
+            self.0.pop_front().unwrap_or(Still::Unchanged)
+        }
+    }
+
+    #[derive(Default)]
+    struct Memory {
+        files: HashMap<String, Vec<u8>>,
+        free: Option<u64>,
+    }
+
+    impl StillStorage for Memory {
+        fn free_bytes(&self) -> Option<u64> {
+            self.free
+        }
+
+        fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), String> {
+            self.files.insert(name.to_string(), bytes.to_vec());
+            Ok(())
+        }
+    }
+
+    impl Memory {
+        fn manifest(&self) -> Manifest {
+            serde_json::from_slice(&self.files[MANIFEST_FILE]).unwrap()
+        }
+    }
+
+    fn fresh(byte: u8) -> Still {
+        Still::Fresh(vec![byte; 4])
+    }
+
+    fn config(interval_secs: u32, max_frames: Option<u32>) -> TimelapseConfig {
+        TimelapseConfig::new(interval_secs, max_frames).unwrap()
+    }
+
+    #[test]
+    fn ticks_fall_due_at_fixed_intervals() {
+        let mut schedule = TickSchedule::new(START, Duration::from_secs(5));
+        assert_eq!(schedule.take_due(START), 0..1);
+        assert_eq!(schedule.take_due(START + 4 * SECOND), 1..1);
+        assert_eq!(
+            schedule.until_next(START + 4 * SECOND),
+            Duration::from_secs(1)
+        );
+        assert_eq!(schedule.take_due(START + 5 * SECOND), 1..2);
+        // Held up past two ticks
+        assert_eq!(schedule.take_due(START + 17 * SECOND), 2..4);
+        assert_eq!(schedule.due_at(4), START + 20 * SECOND);
+    }
+
+    #[test]
+    fn a_clock_before_the_start_has_nothing_due() {
+        let mut schedule = TickSchedule::new(START, Duration::from_secs(5));
+        assert!(schedule.take_due(START - SECOND).is_empty());
+        assert_eq!(schedule.until_next(START - SECOND), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn configs_are_validated() {
+        assert!(TimelapseConfig::new(0, None).is_err());
+        assert!(TimelapseConfig::new(5, Some(0)).is_err());
+        let config = config(5, Some(100));
+        assert_eq!(config.interval, Duration::from_secs(5));
+        assert_eq!(config.min_free_bytes, DEFAULT_MIN_FREE_BYTES);
+    }
+
+    #[test]
+    fn stills_are_numbered_and_indexed() {
+        let mut recorder = Recorder::new("cam-1", config(5, None), START);
+        let mut source = Script::new([fresh(1), fresh(2)]);
+        let mut storage = Memory::default();
+
+        for now in [START, START + 5 * SECOND] {
+            let outcome = recorder.tick(now, &mut source, &mut storage).unwrap();
+            assert_eq!(outcome, TickOutcome::Captured { report: false });
+        }
+        assert_eq!(storage.files["frame_000001.jpg"], vec![1; 4]);
+        assert_eq!(storage.files["frame_000002.jpg"], vec![2; 4]);
+
+        let frames = &recorder.manifest().frames;
+        assert_eq!(frames[1].tick, 1);
+        assert_eq!(frames[1].captured_at, START + 5 * SECOND);
+        assert_eq!(recorder.progress().bytes_written, 8);
+        assert_eq!(
+            recorder.progress().last_file.as_deref(),
+            Some("frame_000002.jpg")
+        );
+    }
+
+    #[test]
+    fn calls_between_ticks_do_nothing() {
+        let mut recorder = Recorder::new("cam-1", config(5, None), START);
+        let mut source = Script::new([fresh(1), fresh(2)]);
+        let mut storage = Memory::default();
+        recorder.tick(START, &mut source, &mut storage).unwrap();
+
+        let outcome = recorder
+            .tick(START + 2 * SECOND, &mut source, &mut storage)
+            .unwrap();
+        assert_eq!(outcome, TickOutcome::Idle);
+        assert_eq!(recorder.manifest().frames.len(), 1);
+        assert!(recorder.manifest().skipped.is_empty());
+    }
+
+    #[test]
+    fn ticks_without_a_fresh_frame_are_skipped_and_counted() {
+        let mut recorder = Recorder::new("cam-1", config(1, None), START);
+        let mut source = Script::new([fresh(1), Still::Unchanged, fresh(2)]);
+        let mut storage = Memory::default();
+
+        for i in 0..3 {
+            recorder
+                .tick(START + i * SECOND, &mut source, &mut storage)
+                .unwrap();
+        }
+        let manifest = recorder.manifest();
+        assert_eq!(manifest.frames.len(), 2);
+        assert_eq!(
+            manifest.skipped,
+            vec![SkippedTick {
+                tick: 1,
+                due_at: START + SECOND,
+                reason: SkipReason::NoFreshFrame,
+            }]
+        );
+        assert_eq!(recorder.progress().skipped_ticks, 1);
+    }
+
+    #[test]
+    fn ticks_the_recorder_was_held_up_past_are_missed() {
+        let mut recorder = Recorder::new("cam-1", config(1, None), START);
+        let mut source = Script::new([fresh(1), fresh(2)]);
+        let mut storage = Memory::default();
+        recorder.tick(START, &mut source, &mut storage).unwrap();
+
+        recorder
+            .tick(START + 3 * SECOND + 500, &mut source, &mut storage)
+            .unwrap();
+        let manifest = recorder.manifest();
+        assert_eq!(manifest.frames[1].tick, 3);
+        let missed: Vec<u64> = manifest.skipped.iter().map(|s| s.tick).collect();
+        assert_eq!(missed, vec![1, 2]);
+        assert!(manifest
+            .skipped
+            .iter()
+            .all(|s| s.reason == SkipReason::Missed));
+    }
+
+    #[test]
+    fn capture_pauses_while_stalled_and_resumes() {
+        let mut recorder = Recorder::new("cam-1", config(1, None), START);
+        let mut source = Script::new([fresh(1), Still::Unavailable, Still::Unavailable, fresh(2)]);
+        let mut storage = Memory::default();
+
+        let outcomes: Vec<TickOutcome> = (0..4)
+            .map(|i| {
+                recorder
+                    .tick(START + i * SECOND, &mut source, &mut storage)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(outcomes[1], TickOutcome::Skipped(SkipReason::Stalled));
+        assert_eq!(outcomes[3], TickOutcome::Captured { report: false });
+        let progress = recorder.progress();
+        assert_eq!(progress.paused_ticks, 2);
+        assert_eq!(progress.skipped_ticks, 0);
+        assert_eq!(progress.frames_written, 2);
+    }
+
+    #[test]
+    fn progress_is_reported_and_saved_every_few_stills() {
+        let mut config = config(1, None);
+        config.progress_every = 3;
+        let mut recorder = Recorder::new("cam-1", config, START);
+        let mut source = Script::new((1..=3).map(fresh));
+        let mut storage = Memory::default();
+
+        let reports: Vec<bool> = (0..3)
+            .map(|i| {
+                match recorder
+                    .tick(START + i * SECOND, &mut source, &mut storage)
+                    .unwrap()
+                {
+                    TickOutcome::Captured { report } => report,
+                    other => panic!("unexpected {other:?}"),
+                }
+            })
+            .collect();
+        assert_eq!(reports, vec![false, false, true]);
+        assert_eq!(storage.manifest().frames.len(), 3);
+    }
+
+    #[test]
+    fn the_last_still_completes_the_time_lapse() {
+        let mut recorder = Recorder::new("cam-1", config(1, Some(2)), START);
+        let mut source = Script::new([fresh(1), fresh(2)]);
+        let mut storage = Memory::default();
+
+        recorder.tick(START, &mut source, &mut storage).unwrap();
+        let outcome = recorder
+            .tick(START + SECOND, &mut source, &mut storage)
+            .unwrap();
+        assert_eq!(outcome, TickOutcome::Completed);
+    }
+
+    #[test]
+    fn stills_stop_at_the_free_space_floor() {
+        let mut config = config(1, None);
+        config.min_free_bytes = 1024 * 1024;
+        let mut recorder = Recorder::new("cam-1", config, START);
+        let mut source = Script::new([fresh(1), fresh(2)]);
+        let mut storage = Memory {
+            free: Some(1024 * 1024 + 4),
+            ..Memory::default()
+        };
+        recorder.tick(START, &mut source, &mut storage).unwrap();
+
+        storage.free = Some(1024 * 1024 + 3);
+        let err = recorder
+            .tick(START + SECOND, &mut source, &mut storage)
+            .unwrap_err();
+        assert!(err.contains("below 1 MB"), "{err}");
+        assert!(!storage.files.contains_key("frame_000002.jpg"));
+        assert_eq!(recorder.manifest().frames.len(), 1);
+    }
+
+    #[test]
+    fn finishing_writes_the_final_manifest() {
+        let mut recorder = Recorder::new("cam-1", config(1, None), START);
+        let mut source = Script::new([fresh(1), Still::Unchanged]);
+        let mut storage = Memory::default();
+        recorder.tick(START, &mut source, &mut storage).unwrap();
+        recorder
+            .tick(START + SECOND, &mut source, &mut storage)
+            .unwrap();
+
+        recorder
+            .finish(START + 1500, StopReason::Disconnected, None, &mut storage)
+            .unwrap();
+        let manifest = storage.manifest();
+        assert_eq!(manifest, *recorder.manifest());
+        assert_eq!(manifest.finished_at, Some(START + 1500));
+        assert_eq!(manifest.stop_reason, Some(StopReason::Disconnected));
+        assert_eq!(manifest.frames.len(), 1);
+        assert_eq!(manifest.skipped.len(), 1);
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["intervalMs"], 1000);
+        assert_eq!(json["skipped"][0]["reason"], "noFreshFrame");
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn folders_holding_a_time_lapse_are_refused() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let out = dir.path().join("lapse");
+        let mut storage = DirStorage::create(&out).unwrap();
+        storage.write("frame_000001.jpg", &[1, 2, 3]).unwrap();
+        storage.write(MANIFEST_FILE, b"{}").unwrap();
+
+        assert_eq!(
+            std::fs::read(out.join("frame_000001.jpg")).unwrap(),
+            [1, 2, 3]
+        );
+        assert!(!out.join("frame_000001.jpg.tmp").exists());
+        let err = DirStorage::create(&out).err().unwrap();
+        assert!(err.contains("already holds a time-lapse"), "{err}");
+    }
+}
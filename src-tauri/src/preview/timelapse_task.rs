@@ -0,0 +1,280 @@
+//! Running time-lapses: a worker thread per camera drives a
+//! [`Recorder`](super::timelapse::Recorder) from the camera's preview
+//! session, alongside the preview itself.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::capture::PreviewSession;
+use super::commands::{
+    encode_for_output, watermark_context, FrameSource, PreviewState, THUMBNAIL_ONLY_ERROR,
+};
+use super::mode::SessionMode;
+use super::output::OutputTarget;
+use super::placeholder::STALL_AFTER;
+use super::timelapse::{
+    DirStorage, Recorder, Still, StillSource, StopReason, TickOutcome, TimelapseConfig,
+    TimelapseProgress,
+};
+use crate::diagnostics::delivery::TIMELAPSE_CONSUMER;
+use crate::error::{code, AppError};
+use crate::settings::commands::SettingsState;
+
+struct RunningTimelapse {
+    stop: Sender<StopReason>,
+    worker: JoinHandle<TimelapseProgress>,
+}
+
+/// Tauri-managed state holding the running time-lapses, by device ID.
+#[derive(Default)]
+pub struct TimelapseState {
+    running: Mutex<HashMap<String, RunningTimelapse>>,
+}
+
+impl TimelapseState {
+    /// Tell `device_id`'s time-lapse to stop, returning its worker to join.
+    fn signal_stop(
+        &self,
+        device_id: &str,
+        reason: StopReason,
+    ) -> Option<JoinHandle<TimelapseProgress>> {
+        let running = self.running.lock().remove(device_id)?;
+        // A worker that already finished has dropped its receiver
+        let _ = running.stop.send(reason);
+        Some(running.worker)
+    }
+}
+
+/// Stills from the camera's current preview session. The session is looked
+/// up at every tick, so one restarted by recovery or keep-warm is picked up
+/// where the old one left off.
+struct SessionStills {
+    app: AppHandle,
+    device_id: String,
+    quality: u8,
+    /// Buffer and sequence of the last frame taken.
+    last: Option<(usize, u64)>,
+}
+
+impl StillSource for SessionStills {
+    fn poll(&mut self) -> Still {
+        let preview = self.app.state::<PreviewState>();
+        let (source, key, orientation) = {
+            let sessions = preview.sessions.lock();
+            let Some(session) = sessions.get(&self.device_id) else {
+                return Still::Unavailable;
+            };
+            let stalled = session
+                .since_last_frame()
+                .map_or(true, |age| age >= STALL_AFTER);
+            if session.is_failed() || stalled {
+                return Still::Unavailable;
+            }
+            let Some((source, key)) = latest_still(session) else {
+                return Still::Unavailable;
+            };
+            if self.last == Some(key) {
+                return Still::Unchanged;
+            }
+            session.record_consumed(TIMELAPSE_CONSUMER, key.1);
+            (source, key, session.orientation())
+        };
+
+        // Stills are recordings, so they carry the recording chain's
+        // watermark rather than the preview's
+        let (chain, camera) = self
+            .app
+            .state::<SettingsState>()
+            .store
+            .output_chain(&self.device_id, OutputTarget::Recording);
+        match encode_for_output(
+            &source,
+            orientation,
+            self.quality,
+            &chain,
+            &watermark_context(camera),
+        ) {
+            Ok(jpeg) => {
+                self.last = Some(key);
+                Still::Fresh(jpeg)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to encode time-lapse still for {}: {e}",
+                    self.device_id
+                );
+                Still::Unchanged
+            }
+        }
+    }
+}
+
+/// The session's newest frame, keyed by its buffer and sequence so frames
+/// from a restarted session count as new. Raw frames are preferred, so the
+/// still is encoded at the still quality rather than the preview's.
+pub(super) fn latest_still(session: &PreviewSession) -> Option<(FrameSource, (usize, u64))> {
+    let buf = session.buffer();
+    if let Some((frame, seq)) = buf.latest_with_sequence() {
+        return Some((FrameSource::Raw(frame), (Arc::as_ptr(buf) as usize, seq)));
+    }
+    let buf = session.jpeg_buffer()?;
+    let frame = buf.latest()?;
+    Some((
+        FrameSource::Passthrough(frame),
+        (Arc::as_ptr(buf) as usize, buf.sequence()),
+    ))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Tick until stopped, the last still is written or a still can't be
+/// written, then write the final manifest and emit `timelapse-stopped`.
+fn run_worker(
+    app: AppHandle,
+    mut recorder: Recorder,
+    mut source: SessionStills,
+    mut storage: DirStorage,
+    stop: Receiver<StopReason>,
+) -> TimelapseProgress {
+    let (reason, error) = loop {
+        match stop.recv_timeout(recorder.until_next(now_ms())) {
+            Ok(reason) => break (reason, None),
+            Err(RecvTimeoutError::Disconnected) => break (StopReason::Requested, None),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        match recorder.tick(now_ms(), &mut source, &mut storage) {
+            Ok(TickOutcome::Captured { report: true }) => {
+                let _ = app.emit("timelapse-progress", recorder.progress());
+            }
+            Ok(TickOutcome::Completed) => break (StopReason::Completed, None),
+            Ok(_) => {}
+            Err(e) => break (StopReason::Failed, Some(e)),
+        }
+    };
+
+    let device_id = &recorder.manifest().device_id;
+    match &error {
+        Some(e) => tracing::warn!("Time-lapse for {device_id} stopped: {e}"),
+        None => tracing::info!("Time-lapse for {device_id} stopped ({reason:?})"),
+    }
+    if let Err(e) = recorder.finish(now_ms(), reason, error, &mut storage) {
+        tracing::warn!("Failed to write time-lapse manifest for {device_id}: {e}");
+    }
+    let progress = recorder.progress();
+    if let Err(e) = app.emit("timelapse-stopped", &progress) {
+        tracing::warn!("Failed to emit timelapse-stopped event: {e}");
+    }
+    progress
+}
+
+/// Stop a camera's time-lapse because it was unplugged. Doesn't wait for
+/// the worker to finish.
+pub fn stop_timelapse_for_device(app: &AppHandle, device_id: &str) {
+    if let Some(state) = app.try_state::<TimelapseState>() {
+        state.signal_stop(device_id, StopReason::Disconnected);
+    }
+}
+
+/// Capture a still from a camera's preview every `interval_secs` seconds
+/// into `output_dir` (created if needed; it mustn't already hold a
+/// time-lapse), stopping after `max_frames` stills if given.
+///
+/// Stills are numbered JPEGs at the camera's quality profile, indexed in a
+/// `timelapse.json` manifest. Progress is emitted as `timelapse-progress`
+/// every few stills and the outcome as `timelapse-stopped`. Capture pauses
+/// while the preview is stalled or stopped and resumes with it; unplugging
+/// the camera ends the time-lapse.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_timelapse(
+    app: AppHandle,
+    state: State<'_, TimelapseState>,
+    preview_state: State<'_, PreviewState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    interval_secs: u32,
+    output_dir: String,
+    max_frames: Option<u32>,
+) -> Result<(), AppError> {
+    let device_id = device_id.trim().to_string();
+    let config = TimelapseConfig::new(interval_secs, max_frames)
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    {
+        let sessions = preview_state.sessions.lock();
+        let session = sessions.get(&device_id).ok_or_else(|| {
+            AppError::new(
+                code::PREVIEW_NOT_RUNNING,
+                "no active preview for this device",
+            )
+        })?;
+        if session.mode() == SessionMode::ThumbnailOnly {
+            return Err(AppError::new(
+                code::PREVIEW_THUMBNAIL_ONLY,
+                THUMBNAIL_ONLY_ERROR,
+            ));
+        }
+    }
+
+    let mut running = state.running.lock();
+    if running
+        .get(&device_id)
+        .is_some_and(|r| !r.worker.is_finished())
+    {
+        return Err(AppError::new(
+            code::ALREADY_RUNNING,
+            "A time-lapse is already running for this camera",
+        ));
+    }
+    let storage = DirStorage::create(Path::new(&output_dir))
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+    let quality = settings_state
+        .store
+        .get_camera(&device_id)
+        .map(|c| c.jpeg_quality)
+        .unwrap_or_default()
+        .still_quality();
+    let recorder = Recorder::new(&device_id, config, now_ms());
+    let source = SessionStills {
+        app: app.clone(),
+        device_id: device_id.clone(),
+        quality,
+        last: None,
+    };
+
+    let (stop, stopped) = mpsc::channel();
+    let worker = std::thread::Builder::new()
+        .name("timelapse".to_string())
+        .spawn(move || run_worker(app, recorder, source, storage, stopped))
+        .map_err(|e| AppError::new(code::INTERNAL, format!("Failed to start time-lapse: {e}")))?;
+    tracing::info!("Started time-lapse for {device_id} every {interval_secs}s into {output_dir}");
+    running.insert(device_id, RunningTimelapse { stop, worker });
+    Ok(())
+}
+
+/// Stop a camera's time-lapse, returning its final progress once the
+/// manifest is written. `None` if none was running.
+#[tauri::command]
+pub async fn stop_timelapse(
+    state: State<'_, TimelapseState>,
+    device_id: String,
+) -> Result<Option<TimelapseProgress>, AppError> {
+    let Some(worker) = state.signal_stop(device_id.trim(), StopReason::Requested) else {
+        return Ok(None);
+    };
+    let progress = tauri::async_runtime::spawn_blocking(move || worker.join())
+        .await
+        .map_err(AppError::with_code(code::INTERNAL))?
+        .map_err(|_| AppError::new(code::INTERNAL, "time-lapse worker panicked"))?;
+    Ok(Some(progress))
+}
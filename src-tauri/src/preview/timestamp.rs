@@ -0,0 +1,77 @@
+// Monotonic frame timestamps.
+//
+// Some capture cards occasionally stamp a burst of frames seconds earlier
+// than the frames before them. Each session runs its device timestamps
+// through a `MonotonicClock` so frame consumers never see time go
+// backwards; the device's own value is kept alongside for diagnostics.
+
+/// Keeps one session's frame timestamps from going backwards.
+#[derive(Debug, Default)]
+pub struct MonotonicClock {
+    last_us: Option<u64>,
+}
+
+impl MonotonicClock {
+    /// Timestamp to store for a frame the device stamped `device_us`: the
+    /// device value, or the previous frame's if the device went backwards.
+    ///
+    /// Forward jumps pass through unchanged. After a regression, frames
+    /// share the previous timestamp until the device catches up.
+    pub fn stamp(&mut self, device_us: u64) -> u64 {
+        let stamped = self.last_us.map_or(device_us, |last| device_us.max(last));
+        self.last_us = Some(stamped);
+        stamped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp_all(device: &[u64]) -> Vec<u64> {
+        let mut clock = MonotonicClock::default();
+        device.iter().map(|&us| clock.stamp(us)).collect()
+    }
+
+    #[test]
+    fn increasing_timestamps_pass_through() {
+        assert_eq!(
+            stamp_all(&[0, 33_333, 66_666, 100_000]),
+            [0, 33_333, 66_666, 100_000]
+        );
+    }
+
+    #[test]
+    fn regression_burst_holds_the_last_timestamp() {
+        // Three frames stamped ~2s early, then back on the timeline
+        let device = [
+            10_000_000, 10_033_333, 8_066_666, 8_100_000, 8_133_333, 10_066_666,
+        ];
+        let held = 10_033_333;
+        assert_eq!(
+            stamp_all(&device),
+            [10_000_000, held, held, held, held, 10_066_666]
+        );
+    }
+
+    #[test]
+    fn duplicates_are_kept() {
+        assert_eq!(stamp_all(&[500, 500, 500, 533]), [500, 500, 500, 533]);
+    }
+
+    #[test]
+    fn large_forward_jumps_pass_through() {
+        assert_eq!(
+            stamp_all(&[0, 33_333, 3_600_000_000, 3_600_033_333]),
+            [0, 33_333, 3_600_000_000, 3_600_033_333]
+        );
+    }
+
+    #[test]
+    fn never_decreases() {
+        let device = [5, 3, 9, 1, 9, 0, 12, 11, 12, 2];
+        let stamped = stamp_all(&device);
+        assert!(stamped.windows(2).all(|w| w[0] <= w[1]), "{stamped:?}");
+        assert_eq!(stamped.last(), Some(&12));
+    }
+}
@@ -0,0 +1,368 @@
+// Software post-processing of RGB frames: unsharp-mask sharpening and a
+// light box-blur denoise, for cameras whose output is soft or noisy even
+// at their strongest hardware setting.
+//
+// Both are built on one box blur run as separable passes, horizontal then
+// vertical, each a running sum along the line. The cost per pixel doesn't
+// depend on the radius, which keeps 1080p affordable on the encode worker.
+// Reads past a frame edge clamp to the edge pixel, so no frame size can
+// index outside the buffer.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Blur radius the unsharp mask subtracts, in pixels.
+pub const SHARPEN_RADIUS: usize = 2;
+
+/// Blur radius of the denoise pass, in pixels.
+pub const DENOISE_RADIUS: usize = 1;
+
+/// Unsharp-mask amount at sharpen strength 100: each pixel moves 1.5 times
+/// its difference from the blurred image further away from it.
+const MAX_SHARPEN_AMOUNT: f32 = 1.5;
+
+/// Bytes per pixel of the RGB24 frames the pipeline carries.
+const CHANNELS: usize = 3;
+
+/// Software post-processing for one camera. Strengths run from 0 (off) to
+/// 100; larger values are treated as 100.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostProcessing {
+    #[serde(default)]
+    pub sharpen: u8,
+    #[serde(default)]
+    pub denoise: u8,
+}
+
+/// Post-processing shared between a session and its encode worker.
+pub type SharedPostProcessing = Arc<Mutex<PostProcessing>>;
+
+impl PostProcessing {
+    /// Whether both passes are off.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Denoise then sharpen `data` in place, so sharpening doesn't amplify
+    /// the noise. Does nothing when both strengths are 0 or `data` is too
+    /// short for `width`x`height`.
+    pub fn apply(&self, data: &mut [u8], width: u32, height: u32) {
+        denoise(data, width, height, self.denoise);
+        sharpen(data, width, height, self.sharpen);
+    }
+}
+
+/// Dimensions as `usize`, or `None` if the frame is empty or `data` doesn't
+/// hold `width`x`height` RGB pixels.
+fn dimensions(data: &[u8], width: u32, height: u32) -> Option<(usize, usize)> {
+    let (width, height) = (width as usize, height as usize);
+    let len = width.checked_mul(height)?.checked_mul(CHANNELS)?;
+    (len > 0 && data.len() >= len).then_some((width, height))
+}
+
+/// Blur one channel along one line of `len` samples, `stride` bytes apart
+/// starting at `start`, averaging each sample with `radius` neighbours on
+/// either side.
+fn blur_line(src: &[u8], dst: &mut [u8], start: usize, stride: usize, len: usize, radius: usize) {
+    let last = len - 1;
+    let at = |i: isize| u32::from(src[start + (i.clamp(0, last as isize) as usize) * stride]);
+    let r = radius as isize;
+    let taps = 2 * radius as u32 + 1;
+
+    let mut sum: u32 = (-r..=r).map(at).sum();
+    for i in 0..len {
+        dst[start + i * stride] = ((sum + taps / 2) / taps) as u8;
+        let i = i as isize;
+        sum = sum + at(i + r + 1) - at(i - r);
+    }
+}
+
+/// Box-blur an RGB frame with a `(2 * radius + 1)` square kernel, as a
+/// horizontal pass followed by a vertical one. Returns `data` unchanged if
+/// it doesn't hold `width`x`height` pixels.
+pub fn box_blur(data: &[u8], width: u32, height: u32, radius: usize) -> Vec<u8> {
+    let Some((width, height)) = dimensions(data, width, height) else {
+        return data.to_vec();
+    };
+    let row = width * CHANNELS;
+    let mut horizontal = data.to_vec();
+    for y in 0..height {
+        for channel in 0..CHANNELS {
+            blur_line(
+                data,
+                &mut horizontal,
+                y * row + channel,
+                CHANNELS,
+                width,
+                radius,
+            );
+        }
+    }
+    let mut blurred = horizontal.clone();
+    for x in 0..width {
+        for channel in 0..CHANNELS {
+            blur_line(
+                &horizontal,
+                &mut blurred,
+                x * CHANNELS + channel,
+                row,
+                height,
+                radius,
+            );
+        }
+    }
+    blurred
+}
+
+/// Fraction of the full effect for a 0–100 strength.
+fn fraction(strength: u8) -> f32 {
+    f32::from(strength.min(100)) / 100.0
+}
+
+/// Move each byte of `data` by `f(original, blurred)`, rounding and
+/// clamping to a byte.
+fn mix(data: &mut [u8], blurred: &[u8], f: impl Fn(f32, f32) -> f32) {
+    for (pixel, &blur) in data.iter_mut().zip(blurred) {
+        let value = f(f32::from(*pixel), f32::from(blur));
+        *pixel = value.round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Sharpen with an unsharp mask: push each pixel away from a blurred copy
+/// of the frame. Strength 0 leaves `data` untouched.
+pub fn sharpen(data: &mut [u8], width: u32, height: u32, strength: u8) {
+    if strength == 0 || dimensions(data, width, height).is_none() {
+        return;
+    }
+    let amount = fraction(strength) * MAX_SHARPEN_AMOUNT;
+    let blurred = box_blur(data, width, height, SHARPEN_RADIUS);
+    mix(data, &blurred, |pixel, blur| {
+        pixel + amount * (pixel - blur)
+    });
+}
+
+/// Denoise by blending towards a box-blurred copy of the frame; strength
+/// 100 is the full blur. Strength 0 leaves `data` untouched.
+pub fn denoise(data: &mut [u8], width: u32, height: u32, strength: u8) {
+    if strength == 0 || dimensions(data, width, height).is_none() {
+        return;
+    }
+    let amount = fraction(strength);
+    let blurred = box_blur(data, width, height, DENOISE_RADIUS);
+    mix(data, &blurred, |pixel, blur| {
+        pixel + amount * (blur - pixel)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An RGB frame from grey levels, one per pixel.
+    fn grey(levels: &[u8]) -> Vec<u8> {
+        levels.iter().flat_map(|&v| [v, v, v]).collect()
+    }
+
+    /// Grey levels of an RGB frame whose channels agree.
+    fn levels(data: &[u8]) -> Vec<u8> {
+        data.chunks(3)
+            .map(|px| {
+                assert!(px[0] == px[1] && px[1] == px[2], "channels differ: {px:?}");
+                px[0]
+            })
+            .collect()
+    }
+
+    /// Deterministic pseudo-random bytes (64-bit LCG).
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    // --- Box blur ---
+
+    #[test]
+    fn horizontal_pass_clamps_at_the_edges() {
+        // (0+0+30)/3, (0+30+60)/3, (30+60+60)/3
+        let blurred = box_blur(&grey(&[0, 30, 60]), 3, 1, 1);
+        assert_eq!(levels(&blurred), [10, 30, 50]);
+    }
+
+    #[test]
+    fn vertical_pass_clamps_at_the_edges() {
+        let blurred = box_blur(&grey(&[0, 90, 0]), 1, 3, 1);
+        assert_eq!(levels(&blurred), [30, 30, 30]);
+    }
+
+    #[test]
+    fn both_passes_spread_a_point_over_the_kernel() {
+        #[rustfmt::skip]
+        let image = grey(&[
+            0, 0, 0,
+            0, 90, 0,
+            0, 0, 0,
+        ]);
+        // Each output averages a 3x3 window that always holds the 90
+        assert_eq!(levels(&box_blur(&image, 3, 3, 1)), [10; 9]);
+    }
+
+    #[test]
+    fn averages_round_to_nearest() {
+        // (0+0+1)/3 = 0.33, (0+1+1)/3 = 0.67, (1+1+1)/3 = 1
+        let blurred = box_blur(&grey(&[0, 1, 1]), 3, 1, 1);
+        assert_eq!(levels(&blurred), [0, 1, 1]);
+    }
+
+    #[test]
+    fn channels_blur_independently() {
+        let image = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+        // Each channel is a spike at a different pixel
+        assert_eq!(
+            box_blur(&image, 3, 1, 1),
+            [170, 85, 0, 85, 85, 85, 0, 85, 170]
+        );
+    }
+
+    #[test]
+    fn radius_larger_than_the_frame_stays_in_bounds() {
+        for (width, height) in [(1, 1), (1, 7), (7, 1), (2, 2), (3, 5)] {
+            let image = noise(width * height * 3, 7);
+            let blurred = box_blur(&image, width as u32, height as u32, 4);
+            assert_eq!(blurred.len(), image.len());
+        }
+        // A flat 1x1 frame blurs to itself whatever the radius
+        assert_eq!(box_blur(&[9, 8, 7], 1, 1, 10), [9, 8, 7]);
+    }
+
+    #[test]
+    fn short_buffers_are_returned_unchanged() {
+        let image = grey(&[1, 2, 3]);
+        assert_eq!(box_blur(&image, 4, 1, 1), image);
+        assert_eq!(box_blur(&image, 0, 1, 1), image);
+    }
+
+    // --- Sharpen ---
+
+    #[test]
+    fn sharpen_boosts_a_spike_against_its_blur() {
+        // Radius 2 spreads the spike to 20 everywhere; strength 100 adds
+        // 1.5 times the difference: 100 + 1.5 * 80 = 220, 0 - 1.5 * 20 -> 0
+        let mut image = grey(&[0, 0, 100, 0, 0]);
+        sharpen(&mut image, 5, 1, 100);
+        assert_eq!(levels(&image), [0, 0, 220, 0, 0]);
+
+        let mut image = grey(&[50, 50, 100, 50, 50]);
+        sharpen(&mut image, 5, 1, 50);
+        // Blur is 60; 100 + 0.75 * 40 = 130, 50 - 0.75 * 10 = 42.5 -> 43
+        assert_eq!(levels(&image), [43, 43, 130, 43, 43]);
+    }
+
+    #[test]
+    fn sharpen_leaves_flat_areas_alone() {
+        let mut image = grey(&[77; 12]);
+        sharpen(&mut image, 4, 3, 100);
+        assert_eq!(levels(&image), [77; 12]);
+    }
+
+    #[test]
+    fn sharpen_clamps_to_the_byte_range() {
+        let mut image = grey(&[255, 255, 0, 255, 255]);
+        sharpen(&mut image, 5, 1, 100);
+        let out = levels(&image);
+        assert_eq!(out[2], 0);
+        assert_eq!(out[0], 255);
+    }
+
+    // --- Denoise ---
+
+    #[test]
+    fn full_denoise_is_the_box_blur() {
+        let image = noise(6 * 4 * 3, 3);
+        let mut denoised = image.clone();
+        denoise(&mut denoised, 6, 4, 100);
+        assert_eq!(denoised, box_blur(&image, 6, 4, DENOISE_RADIUS));
+    }
+
+    #[test]
+    fn partial_denoise_blends_towards_the_blur() {
+        // Blur is [10, 30, 50]; halfway from [0, 30, 60] is [5, 30, 55]
+        let mut image = grey(&[0, 30, 60]);
+        denoise(&mut image, 3, 1, 50);
+        assert_eq!(levels(&image), [5, 30, 55]);
+    }
+
+    // --- Strength 0 and limits ---
+
+    #[test]
+    fn strength_zero_is_byte_identical() {
+        let sizes = [(1, 1), (2, 1), (1, 2), (3, 3), (5, 2), (16, 9), (33, 17)];
+        for (seed, &(width, height)) in sizes.iter().enumerate() {
+            let image = noise(width * height * 3, seed as u64 + 1);
+            let mut out = image.clone();
+            PostProcessing::default().apply(&mut out, width as u32, height as u32);
+            assert_eq!(out, image, "{width}x{height}");
+
+            let mut out = image.clone();
+            sharpen(&mut out, width as u32, height as u32, 0);
+            denoise(&mut out, width as u32, height as u32, 0);
+            assert_eq!(out, image, "{width}x{height}");
+        }
+    }
+
+    #[test]
+    fn strengths_above_100_act_as_100() {
+        let image = noise(8 * 8 * 3, 11);
+        let (mut at_100, mut at_255) = (image.clone(), image);
+        sharpen(&mut at_100, 8, 8, 100);
+        sharpen(&mut at_255, 8, 8, 255);
+        assert_eq!(at_100, at_255);
+    }
+
+    #[test]
+    fn every_strength_and_small_size_stays_in_bounds() {
+        for width in 1..=5u32 {
+            for height in 1..=5u32 {
+                let image = noise((width * height * 3) as usize, u64::from(width * height));
+                for strength in [1, 50, 100] {
+                    let mut out = image.clone();
+                    PostProcessing {
+                        sharpen: strength,
+                        denoise: strength,
+                    }
+                    .apply(&mut out, width, height);
+                    assert_eq!(out.len(), image.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn post_processing_serialises_camel_case_and_defaults_missing_fields() {
+        let json = serde_json::to_value(PostProcessing {
+            sharpen: 40,
+            denoise: 10,
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({ "sharpen": 40, "denoise": 10 }));
+
+        let parsed: PostProcessing = serde_json::from_str(r#"{ "sharpen": 25 }"#).unwrap();
+        assert_eq!(
+            parsed,
+            PostProcessing {
+                sharpen: 25,
+                denoise: 0
+            }
+        );
+        assert!(PostProcessing::default().is_default());
+    }
+}
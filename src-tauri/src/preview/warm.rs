@@ -0,0 +1,372 @@
+// Keep-warm orchestration for the default camera.
+//
+// With the preference on, the default camera's capture graph keeps running
+// at thumbnail resolution while the main window is hidden, and is restarted
+// at full preview resolution when the window is shown, so opening the window
+// shows video straight away. Hiding the window cools it back down.
+//
+// The machine here is pure; commands.rs feeds it events and turns its
+// actions into session restarts.
+
+use crate::camera::types::{CameraDevice, DeviceKind};
+
+/// Resolution a kept-warm session captures at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// Thumbnail resolution, while the window is hidden.
+    Thumbnail,
+    /// The resolution every auto-started preview runs at.
+    Full,
+}
+
+/// Something that can change which session is kept warm, or how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarmEvent {
+    PreferenceChanged(bool),
+    WindowShown,
+    WindowHidden,
+    /// The default camera changed, disconnected, or reconnected.
+    DefaultDeviceChanged(Option<String>),
+}
+
+/// Session change the machine asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarmAction {
+    /// Start the device at thumbnail resolution, replacing any session it has.
+    Warm(String),
+    /// Restart the device's session at full resolution.
+    Upgrade(String),
+    /// Restart the device's session at thumbnail resolution.
+    CoolDown(String),
+}
+
+impl WarmAction {
+    pub fn device_id(&self) -> &str {
+        match self {
+            Self::Warm(id) | Self::Upgrade(id) | Self::CoolDown(id) => id,
+        }
+    }
+
+    /// Resolution the device's session should capture at afterwards.
+    pub fn tier(&self) -> Tier {
+        match self {
+            Self::Upgrade(_) => Tier::Full,
+            Self::Warm(_) | Self::CoolDown(_) => Tier::Thumbnail,
+        }
+    }
+
+    /// Whether the action starts a session for a device that has none.
+    /// Resizes leave a device without a session alone — it has gone away.
+    pub fn starts_session(&self) -> bool {
+        matches!(self, Self::Warm(_))
+    }
+}
+
+/// The camera to keep warm: the first connected camera in `preferred`
+/// (the chosen default, then the last selected camera), otherwise the first
+/// colour camera in enumeration order.
+///
+/// Canon bodies are skipped — only DirectShow graphs are kept warm.
+pub fn default_device<'a>(
+    devices: &'a [CameraDevice],
+    preferred: &[String],
+) -> Option<&'a CameraDevice> {
+    let eligible =
+        |d: &&CameraDevice| d.kind == DeviceKind::Primary && !d.device_path.starts_with("edsdk://");
+    preferred
+        .iter()
+        .find_map(|id| {
+            devices
+                .iter()
+                .filter(eligible)
+                .find(|d| d.id.as_str() == id)
+        })
+        .or_else(|| devices.iter().find(eligible))
+}
+
+/// Tracks the preference, window visibility and default camera, and works
+/// out which session restarts each change calls for.
+#[derive(Debug)]
+pub struct WarmMachine {
+    enabled: bool,
+    window_visible: bool,
+    default_device: Option<String>,
+    /// The device being kept warm and the tier its session was left at.
+    managed: Option<(String, Tier)>,
+}
+
+impl WarmMachine {
+    /// A machine with the preference off.
+    pub fn new(window_visible: bool) -> Self {
+        Self {
+            enabled: false,
+            window_visible,
+            default_device: None,
+            managed: None,
+        }
+    }
+
+    /// Apply `event`, returning the session changes it calls for in order.
+    pub fn handle(&mut self, event: WarmEvent) -> Vec<WarmAction> {
+        match event {
+            WarmEvent::PreferenceChanged(enabled) => self.enabled = enabled,
+            WarmEvent::WindowShown => self.window_visible = true,
+            WarmEvent::WindowHidden => self.window_visible = false,
+            WarmEvent::DefaultDeviceChanged(device) => self.default_device = device,
+        }
+        self.reconcile()
+    }
+
+    /// The device being kept warm and its current tier, if any.
+    pub fn managed(&self) -> Option<(&str, Tier)> {
+        self.managed.as_ref().map(|(id, tier)| (id.as_str(), *tier))
+    }
+
+    fn desired(&self) -> Option<(String, Tier)> {
+        let device = self.default_device.clone().filter(|_| self.enabled)?;
+        let tier = if self.window_visible {
+            Tier::Full
+        } else {
+            Tier::Thumbnail
+        };
+        Some((device, tier))
+    }
+
+    fn reconcile(&mut self) -> Vec<WarmAction> {
+        let desired = self.desired();
+        let mut actions = Vec::new();
+        match (self.managed.take(), &desired) {
+            (Some((device, from)), Some((target, to))) if device == *target => match (from, to) {
+                (Tier::Thumbnail, Tier::Full) => actions.push(WarmAction::Upgrade(device)),
+                (Tier::Full, Tier::Thumbnail) => actions.push(WarmAction::CoolDown(device)),
+                _ => {}
+            },
+            (previous, _) => {
+                // Hand the old device back at the resolution other sessions use
+                if let Some((device, Tier::Thumbnail)) = previous {
+                    actions.push(WarmAction::Upgrade(device));
+                }
+                // Sessions not kept warm already run at full resolution
+                if let Some((device, Tier::Thumbnail)) = &desired {
+                    actions.push(WarmAction::Warm(device.clone()));
+                }
+            }
+        }
+        self.managed = desired;
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::DeviceId;
+
+    fn changed(id: &str) -> WarmEvent {
+        WarmEvent::DefaultDeviceChanged(Some(id.to_string()))
+    }
+
+    fn warm(id: &str) -> WarmAction {
+        WarmAction::Warm(id.to_string())
+    }
+
+    fn upgrade(id: &str) -> WarmAction {
+        WarmAction::Upgrade(id.to_string())
+    }
+
+    fn cool_down(id: &str) -> WarmAction {
+        WarmAction::CoolDown(id.to_string())
+    }
+
+    /// Machine with the preference on and `cam-a` as the default camera.
+    fn enabled(window_visible: bool) -> WarmMachine {
+        let mut machine = WarmMachine::new(window_visible);
+        machine.handle(changed("cam-a"));
+        machine.handle(WarmEvent::PreferenceChanged(true));
+        machine
+    }
+
+    #[test]
+    fn does_nothing_while_the_preference_is_off() {
+        let mut machine = WarmMachine::new(false);
+        assert!(machine.handle(changed("cam-a")).is_empty());
+        assert!(machine.handle(WarmEvent::WindowShown).is_empty());
+        assert!(machine.handle(WarmEvent::WindowHidden).is_empty());
+        assert_eq!(machine.managed(), None);
+    }
+
+    #[test]
+    fn enabling_while_hidden_warms_the_default_camera() {
+        let mut machine = WarmMachine::new(false);
+        machine.handle(changed("cam-a"));
+        assert_eq!(
+            machine.handle(WarmEvent::PreferenceChanged(true)),
+            [warm("cam-a")]
+        );
+        assert_eq!(machine.managed(), Some(("cam-a", Tier::Thumbnail)));
+    }
+
+    #[test]
+    fn enabling_while_visible_leaves_the_running_session_alone() {
+        let mut machine = WarmMachine::new(true);
+        machine.handle(changed("cam-a"));
+        assert!(machine
+            .handle(WarmEvent::PreferenceChanged(true))
+            .is_empty());
+        assert_eq!(machine.managed(), Some(("cam-a", Tier::Full)));
+    }
+
+    #[test]
+    fn showing_upgrades_and_hiding_cools_down() {
+        let mut machine = enabled(false);
+        assert_eq!(machine.handle(WarmEvent::WindowShown), [upgrade("cam-a")]);
+        assert_eq!(
+            machine.handle(WarmEvent::WindowHidden),
+            [cool_down("cam-a")]
+        );
+        assert_eq!(machine.managed(), Some(("cam-a", Tier::Thumbnail)));
+    }
+
+    #[test]
+    fn repeated_window_events_are_ignored() {
+        let mut machine = enabled(true);
+        assert!(machine.handle(WarmEvent::WindowShown).is_empty());
+        machine.handle(WarmEvent::WindowHidden);
+        assert!(machine.handle(WarmEvent::WindowHidden).is_empty());
+    }
+
+    #[test]
+    fn disabling_while_hidden_restores_full_resolution() {
+        let mut machine = enabled(false);
+        assert_eq!(
+            machine.handle(WarmEvent::PreferenceChanged(false)),
+            [upgrade("cam-a")]
+        );
+        assert_eq!(machine.managed(), None);
+        assert!(machine.handle(WarmEvent::WindowShown).is_empty());
+    }
+
+    #[test]
+    fn disabling_while_visible_changes_nothing() {
+        let mut machine = enabled(true);
+        assert!(machine
+            .handle(WarmEvent::PreferenceChanged(false))
+            .is_empty());
+        assert!(machine.handle(WarmEvent::WindowHidden).is_empty());
+    }
+
+    #[test]
+    fn new_default_takes_over_and_the_old_one_is_handed_back() {
+        let mut machine = enabled(false);
+        assert_eq!(
+            machine.handle(changed("cam-b")),
+            [upgrade("cam-a"), warm("cam-b")]
+        );
+        assert_eq!(machine.managed(), Some(("cam-b", Tier::Thumbnail)));
+    }
+
+    #[test]
+    fn default_change_while_visible_needs_no_restart() {
+        let mut machine = enabled(true);
+        assert!(machine.handle(changed("cam-b")).is_empty());
+        assert_eq!(machine.managed(), Some(("cam-b", Tier::Full)));
+    }
+
+    #[test]
+    fn reconnecting_default_is_warmed_again() {
+        let mut machine = enabled(false);
+
+        // Upgrading a disconnected device finds no session and does nothing
+        let gone = machine.handle(WarmEvent::DefaultDeviceChanged(None));
+        assert_eq!(gone, [upgrade("cam-a")]);
+        assert!(!gone[0].starts_session());
+        assert_eq!(machine.managed(), None);
+
+        let back = machine.handle(changed("cam-a"));
+        assert_eq!(back, [warm("cam-a")]);
+        assert!(back[0].starts_session());
+    }
+
+    #[test]
+    fn no_default_camera_means_nothing_to_warm() {
+        let mut machine = WarmMachine::new(false);
+        assert!(machine
+            .handle(WarmEvent::PreferenceChanged(true))
+            .is_empty());
+        assert!(machine.handle(WarmEvent::WindowShown).is_empty());
+        assert_eq!(machine.managed(), None);
+    }
+
+    #[test]
+    fn actions_report_their_target() {
+        assert_eq!(warm("cam-a").device_id(), "cam-a");
+        assert_eq!(warm("cam-a").tier(), Tier::Thumbnail);
+        assert_eq!(cool_down("cam-a").tier(), Tier::Thumbnail);
+        assert_eq!(upgrade("cam-a").tier(), Tier::Full);
+        assert!(!cool_down("cam-a").starts_session());
+    }
+
+    fn device(id: &str, path: &str, kind: DeviceKind) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: id.to_string(),
+            device_path: path.to_string(),
+            is_connected: true,
+            kind,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    #[test]
+    fn default_device_is_the_first_colour_directshow_camera() {
+        let devices = [
+            device("canon", "edsdk://EOS R5", DeviceKind::Primary),
+            device("hello-ir", r"\\?\usb#ir", DeviceKind::Infrared),
+            device("brio", r"\\?\usb#brio", DeviceKind::Primary),
+            device("c920", r"\\?\usb#c920", DeviceKind::Primary),
+        ];
+        assert_eq!(default_device(&devices, &[]).unwrap().id.as_str(), "brio");
+    }
+
+    #[test]
+    fn default_device_prefers_the_chosen_camera_when_connected() {
+        let devices = [
+            device("brio", r"\\?\usb#brio", DeviceKind::Primary),
+            device("c920", r"\\?\usb#c920", DeviceKind::Primary),
+            device("brio-ir", r"\\?\usb#ir", DeviceKind::Infrared),
+        ];
+        let pick = |preferred: &str| {
+            default_device(&devices, &[preferred.to_string()])
+                .unwrap()
+                .id
+                .as_str()
+        };
+        assert_eq!(pick("c920"), "c920");
+        // Unplugged or not keepable warm: fall back to enumeration order
+        assert_eq!(pick("razer"), "brio");
+        assert_eq!(pick("brio-ir"), "brio");
+    }
+
+    #[test]
+    fn default_device_falls_back_through_the_preference_list() {
+        let devices = [
+            device("brio", r"\\?\usb#brio", DeviceKind::Primary),
+            device("c920", r"\\?\usb#c920", DeviceKind::Primary),
+        ];
+        // Chosen default unplugged: the last selected camera wins
+        let preferred = ["razer".to_string(), "c920".to_string()];
+        assert_eq!(
+            default_device(&devices, &preferred).unwrap().id.as_str(),
+            "c920"
+        );
+    }
+
+    #[test]
+    fn default_device_is_none_without_a_directshow_camera() {
+        assert!(default_device(&[], &[]).is_none());
+        let devices = [device("canon", "edsdk://EOS R5", DeviceKind::Primary)];
+        assert!(default_device(&devices, &["canon".to_string()]).is_none());
+    }
+}
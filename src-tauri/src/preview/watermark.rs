@@ -0,0 +1,344 @@
+//! Text watermarks stamped into delivered frames.
+//!
+//! A watermark is a short line of text — by default the camera name and
+//! the time — drawn with the bitmap font into a corner of the frame over a
+//! semi-transparent box, so it stays legible on any background. The text
+//! comes from a template whose placeholders are expanded per frame; the
+//! expansion and drawing are pure so they run the same under test.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use super::font::{text_width, Canvas, GLYPH_HEIGHT};
+use super::render::RenderedFrame;
+
+/// Template used when none is given.
+pub const DEFAULT_TEMPLATE: &str = "{camera} {timestamp}";
+
+/// Longest template accepted, in characters.
+pub const MAX_TEMPLATE_CHARS: usize = 128;
+
+/// Longest expanded text drawn, in characters. Longer text is cut short.
+pub const MAX_TEXT_CHARS: usize = 64;
+
+/// `{camera}` for a camera without a saved name.
+pub const UNNAMED_CAMERA: &str = "Camera";
+
+/// Largest font scale accepted.
+pub const MAX_SCALE: u8 = 8;
+
+/// Gap between the box and the frame edge, in font pixels.
+const MARGIN: u32 = 2;
+
+/// Gap between the text and the edge of its box, in font pixels.
+const PADDING: u32 = 1;
+
+const TEXT_COLOUR: [u8; 3] = [255, 255, 255];
+const BOX_COLOUR: [u8; 3] = [0, 0, 0];
+
+/// Corner of the frame a watermark sits in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// A text watermark.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Watermark {
+    /// Text to draw. `{camera}`, `{date}`, `{time}` and `{timestamp}` are
+    /// expanded; anything else is drawn as written.
+    #[serde(default = "default_template")]
+    pub template: String,
+    #[serde(default)]
+    pub corner: Corner,
+    /// Size of a font pixel in frame pixels, 1 to [`MAX_SCALE`].
+    #[serde(default = "default_scale")]
+    pub scale: u8,
+    /// Opacity of the box behind the text, 0 (none) to 100 (solid). Larger
+    /// values are treated as 100.
+    #[serde(default = "default_opacity")]
+    pub background_opacity: u8,
+}
+
+fn default_template() -> String {
+    DEFAULT_TEMPLATE.to_string()
+}
+
+fn default_scale() -> u8 {
+    2
+}
+
+fn default_opacity() -> u8 {
+    50
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            template: default_template(),
+            corner: Corner::default(),
+            scale: default_scale(),
+            background_opacity: default_opacity(),
+        }
+    }
+}
+
+/// What a template's placeholders expand to for one frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatermarkContext {
+    /// Saved name of the camera, if it has one.
+    pub camera: Option<String>,
+    /// Local time the frame is delivered.
+    pub time: NaiveDateTime,
+}
+
+impl Watermark {
+    /// Why this watermark can't be used, if it can't.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.template.chars().count() > MAX_TEMPLATE_CHARS {
+            return Err(format!(
+                "watermark template is longer than {MAX_TEMPLATE_CHARS} characters"
+            ));
+        }
+        if !(1..=MAX_SCALE).contains(&self.scale) {
+            return Err(format!("watermark scale must be 1 to {MAX_SCALE}"));
+        }
+        Ok(())
+    }
+
+    /// Draw the watermark into `frame`. Text that doesn't fit the frame is
+    /// clipped; a frame too small for the box is left alone.
+    pub fn apply(&self, frame: &mut RenderedFrame, context: &WatermarkContext) {
+        let text = expand_template(&self.template, context);
+        if text.is_empty() {
+            return;
+        }
+        let scale = u32::from(self.scale.clamp(1, MAX_SCALE));
+        let (margin, padding) = (MARGIN * scale, PADDING * scale);
+        let box_width = text_width(&text, scale) + 2 * padding;
+        let box_height = GLYPH_HEIGHT * scale + 2 * padding;
+        if box_width + margin > frame.width || box_height + margin > frame.height {
+            return;
+        }
+
+        let (x, y) = match self.corner {
+            Corner::TopLeft => (margin, margin),
+            Corner::TopRight => (frame.width - margin - box_width, margin),
+            Corner::BottomLeft => (margin, frame.height - margin - box_height),
+            Corner::BottomRight => (
+                frame.width - margin - box_width,
+                frame.height - margin - box_height,
+            ),
+        };
+        blend_rect(
+            frame,
+            (x, y, box_width, box_height),
+            BOX_COLOUR,
+            self.background_opacity,
+        );
+
+        let mut canvas = Canvas {
+            width: frame.width,
+            height: frame.height,
+            data: std::mem::take(&mut frame.data),
+        };
+        canvas.draw_text(x + padding, y + padding, scale, &text, TEXT_COLOUR);
+        frame.data = canvas.data;
+    }
+}
+
+/// `template` with its placeholders expanded, cut to [`MAX_TEXT_CHARS`].
+///
+/// `{camera}` falls back to [`UNNAMED_CAMERA`] when the camera has no
+/// saved name. Unknown placeholders and unmatched braces are kept as
+/// written, so a typo shows up in the frame rather than vanishing.
+pub fn expand_template(template: &str, context: &WatermarkContext) -> String {
+    let camera = context
+        .camera
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(UNNAMED_CAMERA);
+
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find('}') else {
+            out.push_str(after);
+            rest = "";
+            break;
+        };
+        match &after[1..end] {
+            "camera" => out.push_str(camera),
+            "date" => out.push_str(&context.time.format("%Y-%m-%d").to_string()),
+            "time" => out.push_str(&context.time.format("%H:%M:%S").to_string()),
+            "timestamp" => out.push_str(&context.time.format("%Y-%m-%d %H:%M:%S").to_string()),
+            _ => out.push_str(&after[..=end]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out.chars().take(MAX_TEXT_CHARS).collect()
+}
+
+/// `over` laid on `under` at `opacity` percent (0–100, clamped), rounded to
+/// the nearest level.
+pub fn blend(under: u8, over: u8, opacity: u8) -> u8 {
+    let alpha = u32::from(opacity.min(100));
+    ((u32::from(under) * (100 - alpha) + u32::from(over) * alpha + 50) / 100) as u8
+}
+
+/// Lay `colour` over a rectangle of `frame` at `opacity` percent, clipped
+/// to the frame.
+fn blend_rect(frame: &mut RenderedFrame, rect: (u32, u32, u32, u32), colour: [u8; 3], opacity: u8) {
+    let (x, y, width, height) = rect;
+    let x_end = (x + width).min(frame.width) as usize;
+    let y_end = (y + height).min(frame.height) as usize;
+    let stride = frame.width as usize * 3;
+    if frame.data.len() < stride * frame.height as usize {
+        return;
+    }
+    for row in y as usize..y_end {
+        for col in x as usize..x_end {
+            let i = row * stride + col * 3;
+            for (channel, &over) in colour.iter().enumerate() {
+                frame.data[i + channel] = blend(frame.data[i + channel], over, opacity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn context(camera: Option<&str>) -> WatermarkContext {
+        WatermarkContext {
+            camera: camera.map(str::to_string),
+            time: NaiveDate::from_ymd_opt(2026, 3, 9)
+                .unwrap()
+                .and_hms_opt(7, 5, 30)
+                .unwrap(),
+        }
+    }
+
+    fn grey_frame(width: u32, height: u32, level: u8) -> RenderedFrame {
+        RenderedFrame {
+            data: vec![level; (width * height * 3) as usize],
+            width,
+            height,
+        }
+    }
+
+    fn pixel(frame: &RenderedFrame, x: u32, y: u32) -> [u8; 3] {
+        let i = ((y * frame.width + x) * 3) as usize;
+        [frame.data[i], frame.data[i + 1], frame.data[i + 2]]
+    }
+
+    #[test]
+    fn expands_every_placeholder() {
+        let ctx = context(Some("Desk Cam"));
+        assert_eq!(
+            expand_template(DEFAULT_TEMPLATE, &ctx),
+            "Desk Cam 2026-03-09 07:05:30"
+        );
+        assert_eq!(
+            expand_template("{date}|{time}|{camera}", &ctx),
+            "2026-03-09|07:05:30|Desk Cam"
+        );
+    }
+
+    #[test]
+    fn unnamed_cameras_fall_back_to_a_generic_name() {
+        assert_eq!(expand_template("{camera}", &context(None)), UNNAMED_CAMERA);
+        assert_eq!(
+            expand_template("{camera}", &context(Some("  "))),
+            UNNAMED_CAMERA
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_and_stray_braces_are_kept() {
+        let ctx = context(Some("Cam"));
+        assert_eq!(expand_template("{serial} {camera}", &ctx), "{serial} Cam");
+        assert_eq!(expand_template("open { brace", &ctx), "open { brace");
+        assert_eq!(expand_template("}{camera}", &ctx), "}Cam");
+    }
+
+    #[test]
+    fn expanded_text_is_cut_to_the_maximum() {
+        let long_name = "N".repeat(100);
+        let text = expand_template("{camera} {time}", &context(Some(&long_name)));
+        assert_eq!(text.chars().count(), MAX_TEXT_CHARS);
+        assert!(text.chars().all(|c| c == 'N'));
+    }
+
+    #[test]
+    fn templates_over_the_limit_and_bad_scales_are_rejected() {
+        let long = Watermark {
+            template: "x".repeat(MAX_TEMPLATE_CHARS + 1),
+            ..Watermark::default()
+        };
+        assert!(long.validate().is_err());
+        for scale in [0, MAX_SCALE + 1] {
+            let watermark = Watermark {
+                scale,
+                ..Watermark::default()
+            };
+            assert!(watermark.validate().is_err());
+        }
+        assert!(Watermark::default().validate().is_ok());
+    }
+
+    #[test]
+    fn blend_mixes_by_opacity_and_rounds() {
+        assert_eq!(blend(200, 0, 0), 200);
+        assert_eq!(blend(200, 0, 100), 0);
+        assert_eq!(blend(200, 0, 50), 100);
+        assert_eq!(blend(201, 0, 50), 101);
+        assert_eq!(blend(0, 255, 25), 64);
+        // Opacity over 100 is solid
+        assert_eq!(blend(200, 10, 255), 10);
+    }
+
+    #[test]
+    fn draws_a_translucent_box_with_text_in_the_chosen_corner() {
+        let mut frame = grey_frame(200, 60, 200);
+        let watermark = Watermark {
+            template: "A".to_string(),
+            corner: Corner::BottomRight,
+            scale: 1,
+            background_opacity: 50,
+        };
+        watermark.apply(&mut frame, &context(None));
+
+        // Box: glyph plus padding, inset by the margin from the corner
+        let (box_width, box_height) = (5 + 2, 7 + 2);
+        let (x, y) = (200 - 2 - box_width, 60 - 2 - box_height);
+        assert_eq!(pixel(&frame, x, y), [100; 3], "box corner is half-dimmed");
+        assert_eq!(pixel(&frame, x - 1, y), [200; 3], "left of the box");
+        assert_eq!(pixel(&frame, 199, 59), [200; 3], "margin");
+        // Top row of 'A' has its middle three columns lit
+        assert_eq!(pixel(&frame, x + 1 + 2, y + 1), TEXT_COLOUR);
+        assert_eq!(pixel(&frame, x + 1, y + 1), [100; 3]);
+        // The opposite corner is untouched
+        assert_eq!(pixel(&frame, 0, 0), [200; 3]);
+    }
+
+    #[test]
+    fn frames_too_small_for_the_box_are_left_alone() {
+        let mut frame = grey_frame(20, 8, 90);
+        let before = frame.clone();
+        Watermark::default().apply(&mut frame, &context(Some("Cam")));
+        assert_eq!(frame, before);
+    }
+}
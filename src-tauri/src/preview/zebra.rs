@@ -0,0 +1,335 @@
+//! Zebra stripes — where a frame is at or close to clipping.
+//!
+//! Rather than drawing stripes into the preview, which would end up in
+//! snapshots and time-lapses, the frontend draws them on an overlay canvas
+//! from a coarse mask computed here: one cell per `MASK_SCALE`x`MASK_SCALE`
+//! block of pixels, set when any pixel in the block is brighter than the
+//! threshold, so a small specular highlight still shows. The mask is laid
+//! out like the displayed frame, oriented and cropped the same way, and
+//! run-length encoded since highlights come in patches.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::capture::Frame;
+use super::render::Orientation;
+use super::zoom::CropRect;
+
+/// Source pixels per mask cell along each axis.
+pub const MASK_SCALE: u32 = 4;
+
+/// A zebra mask for one frame, as `get_exposure_mask` returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureMask {
+    /// Mask cells across the displayed frame.
+    pub width: u32,
+    /// Mask cells down the displayed frame.
+    pub height: u32,
+    pub threshold: u8,
+    /// Buffer sequence of the frame the mask was computed from.
+    pub sequence: u64,
+    /// Row-major run lengths, alternating between cells at or below the
+    /// threshold and cells above it, starting with the former — so the
+    /// first run is 0 when the top-left cell is set. They add up to
+    /// `width * height`.
+    pub runs: Vec<u32>,
+}
+
+/// BT.601 luma of an RGB pixel, in integer arithmetic.
+pub fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((77 * u32::from(r) + 150 * u32::from(g) + 29 * u32::from(b) + 128) >> 8) as u8
+}
+
+/// Cells of a tightly packed RGB24 frame with a pixel brighter than
+/// `threshold`, one per `MASK_SCALE` block in row-major order, with the
+/// grid's columns and rows. Blocks on the right and bottom edges may be
+/// partial.
+///
+/// Returns `None` if `rgb` is too short for `width`x`height`.
+pub fn threshold_cells(
+    rgb: &[u8],
+    width: u32,
+    height: u32,
+    threshold: u8,
+) -> Option<(Vec<bool>, u32, u32)> {
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || rgb.len() < w * h * 3 {
+        return None;
+    }
+    let scale = MASK_SCALE as usize;
+    let (cols, rows) = (w.div_ceil(scale), h.div_ceil(scale));
+    let mut cells = vec![false; cols * rows];
+    for (y, row) in rgb[..w * h * 3].chunks_exact(w * 3).enumerate() {
+        let cell_row = &mut cells[y / scale * cols..][..cols];
+        for (x, pixel) in row.chunks_exact(3).enumerate() {
+            if luma(pixel[0], pixel[1], pixel[2]) > threshold {
+                cell_row[x / scale] = true;
+            }
+        }
+    }
+    Some((cells, cols as u32, rows as u32))
+}
+
+/// Resample `cells` (`cols`x`rows`, laid out like the source frame) into
+/// the layout of the displayed frame: `orientation` applied, then `crop`.
+/// A crop keeps the cells it covers rather than scaling them back up.
+fn display_cells(
+    cells: Vec<bool>,
+    cols: u32,
+    rows: u32,
+    orientation: Orientation,
+    crop: CropRect,
+) -> (Vec<bool>, u32, u32) {
+    if orientation.is_identity() && crop.is_full() {
+        return (cells, cols, rows);
+    }
+    let (out_cols, out_rows) = orientation.output_size(cols, rows);
+    let width = ((out_cols as f32 * crop.width).round() as u32).max(1);
+    let height = ((out_rows as f32 * crop.height).round() as u32).max(1);
+    let mut out = Vec::with_capacity((width * height) as usize);
+    for oy in 0..height {
+        for ox in 0..width {
+            // Sample each cell at its centre
+            let x = crop.x + (ox as f32 + 0.5) / width as f32 * crop.width;
+            let y = crop.y + (oy as f32 + 0.5) / height as f32 * crop.height;
+            let (sx, sy) = orientation.source_point(x, y);
+            let cx = ((sx * cols as f32) as u32).min(cols - 1);
+            let cy = ((sy * rows as f32) as u32).min(rows - 1);
+            out.push(cells[(cy * cols + cx) as usize]);
+        }
+    }
+    (out, width, height)
+}
+
+/// Run-length encode `cells` as [`ExposureMask::runs`] describes.
+pub fn encode_runs(cells: &[bool]) -> Vec<u32> {
+    let mut runs = Vec::new();
+    let mut current = false;
+    let mut length = 0;
+    for &cell in cells {
+        if cell != current {
+            runs.push(length);
+            current = cell;
+            length = 0;
+        }
+        length += 1;
+    }
+    runs.push(length);
+    runs
+}
+
+/// Zebra mask of `frame`, pushed at `sequence`, as it's displayed with
+/// `orientation` and `crop`.
+///
+/// Returns `None` if the frame's data is too short for its size.
+pub fn exposure_mask(
+    frame: &Frame,
+    sequence: u64,
+    threshold: u8,
+    orientation: Orientation,
+    crop: CropRect,
+) -> Option<ExposureMask> {
+    let (cells, cols, rows) = threshold_cells(&frame.data, frame.width, frame.height, threshold)?;
+    let (cells, width, height) = display_cells(cells, cols, rows, orientation, crop);
+    Some(ExposureMask {
+        width,
+        height,
+        threshold,
+        sequence,
+        runs: encode_runs(&cells),
+    })
+}
+
+/// Each device's last mask, reused while the frame and threshold are
+/// unchanged.
+#[derive(Debug, Default)]
+pub struct MaskCache {
+    masks: HashMap<String, ExposureMask>,
+}
+
+impl MaskCache {
+    /// The cached mask for `device_id`, if it was computed from the frame
+    /// at `sequence` with `threshold`.
+    pub fn get(&self, device_id: &str, sequence: u64, threshold: u8) -> Option<ExposureMask> {
+        self.masks
+            .get(device_id)
+            .filter(|mask| mask.sequence == sequence && mask.threshold == threshold)
+            .cloned()
+    }
+
+    pub fn insert(&mut self, device_id: &str, mask: ExposureMask) {
+        self.masks.insert(device_id.to_string(), mask);
+    }
+
+    pub fn remove(&mut self, device_id: &str) {
+        self.masks.remove(device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::render::Rotation;
+
+    /// Expand run lengths back into cells.
+    fn decode_runs(runs: &[u32]) -> Vec<bool> {
+        runs.iter()
+            .enumerate()
+            .flat_map(|(i, &length)| std::iter::repeat(i % 2 == 1).take(length as usize))
+            .collect()
+    }
+
+    /// Grey frame whose luma rises left to right, one step per column.
+    fn horizontal_gradient(width: u32, height: u32) -> Frame {
+        let data = (0..height)
+            .flat_map(|_| (0..width).flat_map(|x| [x as u8; 3]))
+            .collect();
+        Frame {
+            data,
+            width,
+            height,
+            timestamp_us: 0,
+            device_timestamp_us: 0,
+        }
+    }
+
+    fn full(frame: &Frame, threshold: u8) -> ExposureMask {
+        exposure_mask(frame, 1, threshold, Orientation::default(), CropRect::FULL).unwrap()
+    }
+
+    #[test]
+    fn luma_spans_the_full_range() {
+        assert_eq!(luma(0, 0, 0), 0);
+        assert_eq!(luma(255, 255, 255), 255);
+        assert_eq!(luma(128, 128, 128), 128);
+        // Green counts for most, blue for least
+        assert!(luma(0, 255, 0) > luma(255, 0, 0));
+        assert!(luma(255, 0, 0) > luma(0, 0, 255));
+    }
+
+    #[test]
+    fn gradient_mask_sets_the_cells_past_the_threshold() {
+        // 64 columns of luma 0–63 make 16 cells of 4; above 39 starts at
+        // column 40, the start of cell 10
+        let mask = full(&horizontal_gradient(64, 8), 39);
+        assert_eq!((mask.width, mask.height), (16, 2));
+        let row: Vec<bool> = (0..16).map(|cell| cell >= 10).collect();
+        assert_eq!(decode_runs(&mask.runs), [row.clone(), row].concat());
+        assert_eq!(mask.runs, [10, 6, 10, 6]);
+    }
+
+    #[test]
+    fn one_bright_pixel_sets_its_whole_cell() {
+        // Above 40 only at column 41, the middle of cell 10
+        let mask = full(&horizontal_gradient(42, 4), 40);
+        assert_eq!((mask.width, mask.height), (11, 1));
+        assert_eq!(mask.runs, [10, 1]);
+    }
+
+    #[test]
+    fn partial_edge_blocks_get_their_own_cells() {
+        let (cells, cols, rows) = threshold_cells(&[255; 5 * 6 * 3], 5, 6, 0).unwrap();
+        assert_eq!((cols, rows), (2, 2));
+        assert!(cells.iter().all(|&c| c));
+    }
+
+    #[test]
+    fn thresholds_at_the_extremes() {
+        let frame = horizontal_gradient(16, 4);
+        assert_eq!(full(&frame, 255).runs, [4]);
+        // Every cell but a lone black pixel has something above 0
+        assert_eq!(full(&frame, 0).runs, [0, 4]);
+        assert_eq!(full(&horizontal_gradient(1, 1), 0).runs, [1]);
+    }
+
+    #[test]
+    fn short_frames_have_no_mask() {
+        assert!(threshold_cells(&[0; 10], 4, 4, 128).is_none());
+        assert!(threshold_cells(&[], 0, 0, 128).is_none());
+    }
+
+    #[test]
+    fn rotation_lays_the_mask_out_like_the_displayed_frame() {
+        // Bright on the right; rotated 90° clockwise that's the bottom
+        let frame = horizontal_gradient(16, 8);
+        let orientation = Orientation {
+            rotation: Rotation::Cw90,
+            mirror: false,
+        };
+        let mask = exposure_mask(&frame, 1, 7, orientation, CropRect::FULL).unwrap();
+        assert_eq!((mask.width, mask.height), (2, 4));
+        assert_eq!(
+            decode_runs(&mask.runs),
+            [false, false, false, false, true, true, true, true]
+        );
+
+        let mirrored = Orientation {
+            rotation: Rotation::None,
+            mirror: true,
+        };
+        let mask = exposure_mask(&frame, 1, 7, mirrored, CropRect::FULL).unwrap();
+        assert_eq!(mask.runs, [0, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn crop_keeps_the_cells_it_covers() {
+        let frame = horizontal_gradient(64, 8);
+        let crop = CropRect {
+            x: 0.5,
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        };
+        let mask = exposure_mask(&frame, 1, 39, Orientation::default(), crop).unwrap();
+        // Cells 8–15 of each row; 10 and up are set
+        assert_eq!((mask.width, mask.height), (8, 2));
+        assert_eq!(mask.runs, [2, 6, 2, 6]);
+    }
+
+    #[test]
+    fn runs_round_trip() {
+        for cells in [
+            vec![],
+            vec![true],
+            vec![false],
+            vec![true, true, false, true, false, false],
+            (0..1000).map(|i| i % 7 < 3).collect(),
+        ] {
+            let runs = encode_runs(&cells);
+            assert_eq!(runs.iter().sum::<u32>() as usize, cells.len());
+            assert_eq!(decode_runs(&runs), cells);
+        }
+    }
+
+    #[test]
+    fn cache_hits_only_for_the_same_frame_and_threshold() {
+        let mut cache = MaskCache::default();
+        let mask = full(&horizontal_gradient(8, 4), 3);
+        cache.insert("cam", mask.clone());
+
+        assert_eq!(cache.get("cam", 1, 3), Some(mask));
+        assert_eq!(cache.get("cam", 2, 3), None);
+        assert_eq!(cache.get("cam", 1, 4), None);
+        assert_eq!(cache.get("other", 1, 3), None);
+
+        cache.remove("cam");
+        assert_eq!(cache.get("cam", 1, 3), None);
+    }
+
+    #[test]
+    fn mask_serialises_to_camel_case() {
+        let json = serde_json::to_value(full(&horizontal_gradient(8, 4), 3)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "width": 2,
+                "height": 1,
+                "threshold": 3,
+                "sequence": 1,
+                "runs": [1, 1],
+            })
+        );
+    }
+}
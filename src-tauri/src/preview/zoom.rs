@@ -0,0 +1,490 @@
+//! Combined zoom: the camera's optical zoom, topped up with a digital crop.
+//!
+//! A factor the hardware can't reach on its own is split in two: the Zoom
+//! control is driven as far as the request allows and the remainder is
+//! cropped from the delivered frames and scaled back up to full size. The
+//! optical zoom is always centred, so it's held back when the requested
+//! region would fall outside what it leaves in view; the crop then does
+//! more of the work. Everything here is plain arithmetic on normalised frame
+//! coordinates, `(0, 0)` top left to `(1, 1)` bottom right, as the frame is
+//! displayed.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::camera::types::ControlDescriptor;
+
+/// Zoom control units per 1x of magnification. UVC leaves the units to the
+/// camera; most report hundredths, with the minimum as 1x (100 to 500 for a
+/// 5x zoom), and the split assumes that.
+pub const ZOOM_UNITS_PER_X: f32 = 100.0;
+
+/// Largest magnification the digital crop adds on top of the optical zoom.
+/// Beyond this the upscaled frame is too soft to be useful.
+pub const MAX_DIGITAL_ZOOM: f32 = 4.0;
+
+/// Bytes per pixel of the RGB24 frames the pipeline carries.
+const CHANNELS: usize = 3;
+
+/// Range of a camera's Zoom control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HardwareZoom {
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+}
+
+impl HardwareZoom {
+    /// The range from the Zoom control's descriptor, or `None` if it can't
+    /// zoom in (no range, or a read-only control).
+    pub fn from_descriptor(desc: &ControlDescriptor) -> Option<Self> {
+        let (min, max) = (desc.min?, desc.max?);
+        if max <= min || desc.flags.is_read_only {
+            return None;
+        }
+        Some(Self {
+            min,
+            max,
+            step: desc.step.unwrap_or(1).max(1),
+        })
+    }
+
+    /// Magnification at control value `value`.
+    pub fn factor_at(&self, value: i32) -> f32 {
+        1.0 + (value - self.min) as f32 / ZOOM_UNITS_PER_X
+    }
+
+    /// The largest step-aligned value that magnifies no more than `factor`.
+    pub fn value_for(&self, factor: f32) -> i32 {
+        let units = ((factor - 1.0) * ZOOM_UNITS_PER_X).max(0.0);
+        let steps = (units + 1e-3) as i64 / i64::from(self.step);
+        let value = i64::from(self.min) + steps * i64::from(self.step);
+        let max = self.max - (self.max - self.min) % self.step;
+        value.min(i64::from(max)) as i32
+    }
+}
+
+/// A region of the frame, in normalised coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CropRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Digital crop shared between a session and its encode worker.
+pub type SharedCrop = Arc<Mutex<CropRect>>;
+
+impl Default for CropRect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl CropRect {
+    /// The whole frame: no crop.
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn is_full(&self) -> bool {
+        *self == Self::FULL
+    }
+
+    /// Crop `data` (RGB24, `width`x`height`) to this region and scale it back
+    /// up to `width`x`height`, bilinearly. `None` when there's nothing to
+    /// crop or `data` is too short.
+    pub fn apply(&self, data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+        if self.is_full() || width == 0 || height == 0 {
+            return None;
+        }
+        let (w, h) = (width as usize, height as usize);
+        if data.len() < w * h * CHANNELS {
+            return None;
+        }
+        let columns = samples(self.x, self.width, w);
+        let rows = samples(self.y, self.height, h);
+
+        let mut out = Vec::with_capacity(w * h * CHANNELS);
+        for &(y0, y1, fy) in &rows {
+            let top = &data[y0 * w * CHANNELS..(y0 + 1) * w * CHANNELS];
+            let bottom = &data[y1 * w * CHANNELS..(y1 + 1) * w * CHANNELS];
+            for &(x0, x1, fx) in &columns {
+                for c in 0..CHANNELS {
+                    let upper = lerp(top[x0 * CHANNELS + c], top[x1 * CHANNELS + c], fx);
+                    let lower = lerp(bottom[x0 * CHANNELS + c], bottom[x1 * CHANNELS + c], fx);
+                    out.push(((upper * (256 - fy) + lower * fy + (1 << 15)) >> 16) as u8);
+                }
+            }
+        }
+        Some(out)
+    }
+}
+
+/// For each of `len` output positions, the two source positions it blends
+/// and the weight of the second, in 256ths. Source positions sample the
+/// span `[start, start + size)` of a line `len` long, clamped to the line.
+fn samples(start: f32, size: f32, len: usize) -> Vec<(usize, usize, u32)> {
+    let last = (len - 1) as f32;
+    (0..len)
+        .map(|i| {
+            let src = (start * len as f32 + (i as f32 + 0.5) * size - 0.5).clamp(0.0, last);
+            let lo = src.floor();
+            let weight = ((src - lo) * 256.0).round() as u32;
+            let lo = lo as usize;
+            (lo, (lo + 1).min(len - 1), weight)
+        })
+        .collect()
+}
+
+/// `a` to `b` by `t` 256ths, scaled up by 256.
+fn lerp(a: u8, b: u8, t: u32) -> u32 {
+    u32::from(a) * (256 - t) + u32::from(b) * t
+}
+
+/// How a combined zoom is carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoomSplit {
+    /// Magnification requested.
+    pub factor: f32,
+    /// Value for the Zoom control, or `None` when the camera has no zoom.
+    pub hardware_value: Option<i32>,
+    /// Magnification from the optical zoom.
+    pub hardware_factor: f32,
+    /// Magnification from the digital crop.
+    pub digital_factor: f32,
+    /// Region of the optically zoomed frame kept by the digital crop.
+    pub crop: CropRect,
+}
+
+impl ZoomSplit {
+    /// No zoom: the optical zoom at its minimum and no crop.
+    pub fn neutral(hardware: Option<HardwareZoom>) -> Self {
+        Self {
+            factor: 1.0,
+            hardware_value: hardware.map(|hw| hw.min),
+            hardware_factor: 1.0,
+            digital_factor: 1.0,
+            crop: CropRect::FULL,
+        }
+    }
+}
+
+/// Split a zoom of `factor` around (`center_x`, `center_y`) between the
+/// `hardware` zoom, if the camera has one, and a digital crop.
+///
+/// The region shown is `1 / factor` of the frame each way, centred on the
+/// point but moved inside the frame where the point is near an edge.
+/// The optical zoom takes as much of the factor as its range, its step and
+/// the region's distance from the centre allow; the crop does the rest.
+pub fn plan_zoom(
+    factor: f32,
+    center_x: f32,
+    center_y: f32,
+    hardware: Option<HardwareZoom>,
+) -> Result<ZoomSplit, String> {
+    if !factor.is_finite() || factor < 1.0 {
+        return Err(format!("Zoom must be at least 1x, got {factor}"));
+    }
+    for (axis, center) in [("x", center_x), ("y", center_y)] {
+        if !(0.0..=1.0).contains(&center) {
+            return Err(format!("Zoom centre {axis} must be 0–1, got {center}"));
+        }
+    }
+
+    let size = 1.0 / factor;
+    let x = (center_x - size / 2.0).clamp(0.0, 1.0 - size);
+    let y = (center_y - size / 2.0).clamp(0.0, 1.0 - size);
+
+    // The centred optical view keeps [0.5 - 0.5 / f, 0.5 + 0.5 / f]; it
+    // must still hold the region
+    let reach = [x, x + size, y, y + size]
+        .into_iter()
+        .map(|edge| (edge - 0.5).abs())
+        .fold(0.0f32, f32::max);
+    let optical_limit = if reach > 0.0 {
+        (0.5 / reach).min(factor)
+    } else {
+        factor
+    };
+    let hardware_value = hardware.map(|hw| hw.value_for(optical_limit));
+    let hardware_factor = match (hardware, hardware_value) {
+        (Some(hw), Some(value)) => hw.factor_at(value),
+        _ => 1.0,
+    };
+
+    let digital_factor = factor / hardware_factor;
+    if digital_factor > MAX_DIGITAL_ZOOM + 1e-4 {
+        return Err(format!(
+            "Zoom of {factor}x is beyond this camera: {hardware_factor}x optical and at most \
+             {MAX_DIGITAL_ZOOM}x digital"
+        ));
+    }
+
+    // The region in the optically zoomed frame's coordinates
+    let crop_size = (size * hardware_factor).min(1.0);
+    let to_zoomed = |edge: f32| (0.5 + (edge - 0.5) * hardware_factor).clamp(0.0, 1.0 - crop_size);
+    let crop = if digital_factor > 1.0 + 1e-4 {
+        CropRect {
+            x: to_zoomed(x),
+            y: to_zoomed(y),
+            width: crop_size,
+            height: crop_size,
+        }
+    } else {
+        CropRect::FULL
+    };
+
+    Ok(ZoomSplit {
+        factor,
+        hardware_value,
+        hardware_factor,
+        digital_factor,
+        crop,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{ControlFlags, ControlType};
+
+    /// 2x optical zoom in steps of 10.
+    const ZOOM_2X: HardwareZoom = HardwareZoom {
+        min: 100,
+        max: 200,
+        step: 10,
+    };
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn assert_rect(rect: CropRect, x: f32, y: f32, size: f32) {
+        assert!(
+            close(rect.x, x)
+                && close(rect.y, y)
+                && close(rect.width, size)
+                && close(rect.height, size),
+            "{rect:?} != ({x}, {y}, {size})"
+        );
+    }
+
+    fn descriptor(min: Option<i32>, max: Option<i32>, step: Option<i32>) -> ControlDescriptor {
+        ControlDescriptor {
+            id: "zoom".to_string(),
+            name: "Zoom".to_string(),
+            control_type: ControlType::Slider,
+            group: "focus".to_string(),
+            min,
+            max,
+            step,
+            default: min,
+            default_auto: false,
+            current: min.unwrap_or(0),
+            flags: ControlFlags {
+                supports_auto: false,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    #[test]
+    fn hardware_range_comes_from_the_descriptor() {
+        assert_eq!(
+            HardwareZoom::from_descriptor(&descriptor(Some(100), Some(500), Some(1))),
+            Some(HardwareZoom {
+                min: 100,
+                max: 500,
+                step: 1
+            })
+        );
+        // A missing or zero step counts as 1
+        assert_eq!(
+            HardwareZoom::from_descriptor(&descriptor(Some(0), Some(10), Some(0)))
+                .map(|hw| hw.step),
+            Some(1)
+        );
+        assert_eq!(
+            HardwareZoom::from_descriptor(&descriptor(Some(100), Some(100), None)),
+            None
+        );
+        assert_eq!(
+            HardwareZoom::from_descriptor(&descriptor(None, Some(500), None)),
+            None
+        );
+
+        let mut read_only = descriptor(Some(100), Some(500), Some(1));
+        read_only.flags.is_read_only = true;
+        assert_eq!(HardwareZoom::from_descriptor(&read_only), None);
+    }
+
+    #[test]
+    fn hardware_values_snap_down_to_the_step() {
+        assert_eq!(ZOOM_2X.value_for(1.0), 100);
+        assert_eq!(ZOOM_2X.value_for(1.5), 150);
+        assert_eq!(ZOOM_2X.value_for(1.57), 150);
+        assert_eq!(ZOOM_2X.value_for(4.0), 200);
+        assert!(close(ZOOM_2X.factor_at(150), 1.5));
+
+        // A range that doesn't end on a step stops at the last one
+        let ragged = HardwareZoom {
+            min: 100,
+            max: 195,
+            step: 10,
+        };
+        assert_eq!(ragged.value_for(3.0), 190);
+    }
+
+    #[test]
+    fn factors_below_one_are_rejected() {
+        for factor in [0.5, 0.999, 0.0, -2.0, f32::NAN, f32::INFINITY] {
+            assert!(
+                plan_zoom(factor, 0.5, 0.5, Some(ZOOM_2X)).is_err(),
+                "{factor}"
+            );
+        }
+    }
+
+    #[test]
+    fn centres_outside_the_frame_are_rejected() {
+        assert!(plan_zoom(2.0, -0.1, 0.5, None)
+            .unwrap_err()
+            .contains("centre x"));
+        assert!(plan_zoom(2.0, 0.5, 1.5, None)
+            .unwrap_err()
+            .contains("centre y"));
+        assert!(plan_zoom(2.0, f32::NAN, 0.5, None).is_err());
+    }
+
+    #[test]
+    fn one_x_is_neutral() {
+        let split = plan_zoom(1.0, 0.3, 0.8, Some(ZOOM_2X)).unwrap();
+        assert_eq!(split.hardware_value, Some(100));
+        assert!(close(split.hardware_factor, 1.0));
+        assert!(close(split.digital_factor, 1.0));
+        assert!(split.crop.is_full());
+        assert_eq!(ZoomSplit::neutral(Some(ZOOM_2X)).hardware_value, Some(100));
+    }
+
+    #[test]
+    fn centred_zoom_within_range_is_all_optical() {
+        let split = plan_zoom(1.5, 0.5, 0.5, Some(ZOOM_2X)).unwrap();
+        assert_eq!(split.hardware_value, Some(150));
+        assert!(close(split.digital_factor, 1.0));
+        assert!(split.crop.is_full());
+    }
+
+    #[test]
+    fn centred_zoom_past_the_range_maxes_hardware_then_crops() {
+        let split = plan_zoom(4.0, 0.5, 0.5, Some(ZOOM_2X)).unwrap();
+        assert_eq!(split.hardware_value, Some(200));
+        assert!(close(split.hardware_factor, 2.0));
+        assert!(close(split.digital_factor, 2.0));
+        assert_rect(split.crop, 0.25, 0.25, 0.5);
+    }
+
+    #[test]
+    fn step_remainder_goes_to_the_crop() {
+        let split = plan_zoom(1.55, 0.5, 0.5, Some(ZOOM_2X)).unwrap();
+        assert_eq!(split.hardware_value, Some(150));
+        assert!(close(split.hardware_factor * split.digital_factor, 1.55));
+        assert!(close(split.crop.width, 1.5 / 1.55));
+    }
+
+    #[test]
+    fn cameras_without_zoom_crop_everything() {
+        let split = plan_zoom(2.0, 0.25, 0.75, None).unwrap();
+        assert_eq!(split.hardware_value, None);
+        assert!(close(split.hardware_factor, 1.0));
+        assert!(close(split.digital_factor, 2.0));
+        assert_rect(split.crop, 0.0, 0.5, 0.5);
+    }
+
+    #[test]
+    fn off_centre_regions_hold_the_optical_zoom_back() {
+        // Region [0.6, 0.85] wide reaches 0.35 from the centre, so the
+        // optical zoom can go to 0.5 / 0.35 = 1.43x, snapped to 1.4x
+        let split = plan_zoom(4.0, 0.725, 0.5, Some(ZOOM_2X)).unwrap();
+        assert_eq!(split.hardware_value, Some(140));
+        assert!(close(split.digital_factor, 4.0 / 1.4));
+        // In the 1.4x view, x = 0.5 + 0.1 * 1.4 and the width 0.25 * 1.4
+        assert!(close(split.crop.x, 0.64));
+        assert!(close(split.crop.width, 0.35));
+        assert!(close(split.crop.y, 0.5 - 0.35 / 2.0));
+        // The region still lies within the optically zoomed frame
+        assert!(split.crop.x + split.crop.width <= 1.0 + 1e-4);
+    }
+
+    #[test]
+    fn centres_near_an_edge_clamp_the_region_inside_the_frame() {
+        let split = plan_zoom(2.0, 0.05, 0.98, Some(ZOOM_2X)).unwrap();
+        // The region is pushed into the corner, where the optical zoom
+        // would lose it
+        assert_eq!(split.hardware_value, Some(100));
+        assert_rect(split.crop, 0.0, 0.5, 0.5);
+
+        let split = plan_zoom(4.0, 1.0, 0.0, None).unwrap();
+        assert_rect(split.crop, 0.75, 0.0, 0.25);
+    }
+
+    #[test]
+    fn zoom_beyond_the_digital_limit_is_rejected() {
+        assert!(plan_zoom(8.0, 0.5, 0.5, Some(ZOOM_2X)).is_ok());
+        assert!(plan_zoom(8.5, 0.5, 0.5, Some(ZOOM_2X))
+            .unwrap_err()
+            .contains("beyond"));
+        assert!(plan_zoom(4.5, 0.5, 0.5, None).is_err());
+    }
+
+    #[test]
+    fn full_crop_leaves_frames_alone() {
+        assert_eq!(CropRect::FULL.apply(&[0; 12], 2, 2), None);
+        let half = CropRect {
+            x: 0.0,
+            y: 0.0,
+            width: 0.5,
+            height: 0.5,
+        };
+        // Too short for 2x2
+        assert_eq!(half.apply(&[0; 6], 2, 2), None);
+    }
+
+    #[test]
+    fn crop_scales_the_region_back_to_full_size() {
+        // 4x1 grey ramp; the right half stretched over the whole width
+        let row = [0u8, 80, 160, 240];
+        let data: Vec<u8> = row.iter().flat_map(|&v| [v; 3]).collect();
+        let right = CropRect {
+            x: 0.5,
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        };
+        let out = right.apply(&data, 4, 1).unwrap();
+        assert_eq!(out.len(), data.len());
+        let greys: Vec<u8> = out.chunks(3).map(|px| px[0]).collect();
+        // Samples at 1.75, 2.25, 2.75 and 3.25, clamped to the last pixel
+        assert_eq!(greys, vec![140, 180, 220, 240]);
+        assert!(greys.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn crop_of_a_flat_frame_stays_flat() {
+        let data = vec![77u8; 16 * 9 * 3];
+        let split = plan_zoom(3.0, 0.9, 0.1, None).unwrap();
+        let out = split.crop.apply(&data, 16, 9).unwrap();
+        assert!(out.iter().all(|&v| v == 77));
+    }
+}
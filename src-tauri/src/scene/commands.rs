@@ -0,0 +1,173 @@
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::commands::{follow_tally, CameraState};
+use crate::camera::queue::{DeviceQueue, OpKind};
+use crate::camera::tally::TallyTrigger;
+use crate::camera::types::DeviceId;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::{code, AppError};
+use crate::preset::commands::queue_preset_apply;
+use crate::preview::commands::{
+    pause_preview_session, resume_preview_session, stop_preview_session,
+};
+use crate::scene::types::{
+    run_actions, summarise_activation, validate_scene, CameraOutcome, Scene, SceneAction,
+    SceneActivation, SceneCamera,
+};
+use crate::settings::apply::{self, parse_control_id};
+use crate::settings::commands::SettingsState;
+
+/// Validate and save a scene, replacing any with the same name. Only
+/// succeeds once the scene is written to disk.
+///
+/// Cameras count as existing if they're connected now or have saved
+/// settings, so a scene can include a camera that's unplugged at the time.
+#[tauri::command]
+pub async fn save_scene(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    scene: Scene,
+) -> Result<Scene, AppError> {
+    let connected = camera_state.backend.enumerate_devices()?;
+    let store = &settings_state.store;
+    validate_scene(
+        &scene,
+        |device_id| {
+            let id = DeviceId::new(device_id);
+            connected.iter().any(|d| d.id == id) || store.get_camera(device_id).is_some()
+        },
+        |preset_id| store.preset(preset_id).is_some(),
+    )
+    .map_err(AppError::with_code(code::INVALID_ARGUMENT))?;
+    store.save_scene(scene.clone());
+    store
+        .flush()
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+    Ok(scene)
+}
+
+/// All saved scenes.
+#[tauri::command]
+pub async fn list_scenes(settings_state: State<'_, SettingsState>) -> Result<Vec<Scene>, AppError> {
+    Ok(settings_state.store.scenes())
+}
+
+/// Delete a saved scene. Returns whether it existed.
+#[tauri::command]
+pub async fn delete_scene(
+    settings_state: State<'_, SettingsState>,
+    name: String,
+) -> Result<bool, AppError> {
+    Ok(settings_state.store.delete_scene(&name))
+}
+
+/// Activate a saved scene.
+///
+/// Each camera's actions run in order through its device queue, with
+/// cameras running in parallel. A camera stops at its first failed action;
+/// the others carry on. Emits `scene-activated` with the per-camera results
+/// once every camera has finished, after pointing the tally lights at the
+/// scene's live cameras if they're automatic.
+#[tauri::command]
+pub async fn activate_scene(app: AppHandle, name: String) -> Result<SceneActivation, AppError> {
+    let scene = app
+        .state::<SettingsState>()
+        .store
+        .scene(&name)
+        .ok_or_else(|| AppError::new(code::NOT_FOUND, format!("Scene '{name}' doesn't exist")))?;
+
+    let tasks: Vec<_> = scene
+        .cameras
+        .iter()
+        .cloned()
+        .map(|camera| {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move { activate_camera(&app, &camera).await })
+        })
+        .collect();
+    let mut outcomes = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        match task.await {
+            Ok(outcome) => outcomes.push(outcome),
+            Err(e) => tracing::warn!("Scene '{}' camera task failed: {e}", scene.name),
+        }
+    }
+
+    let activation = summarise_activation(&scene, outcomes);
+    tracing::info!(
+        "Activated scene '{}': {:?}",
+        activation.scene,
+        activation.status
+    );
+    follow_tally(&app, TallyTrigger::Scene(&scene));
+    let _ = app.emit("scene-activated", &activation);
+    crate::tray::notify_activity(&app);
+    Ok(activation)
+}
+
+/// Run one camera's part of a scene.
+async fn activate_camera(app: &AppHandle, camera: &SceneCamera) -> CameraOutcome {
+    let device_id = camera.device_id.clone();
+    let camera_name = app
+        .state::<SettingsState>()
+        .store
+        .get_camera(&device_id)
+        .map(|c| c.name)
+        .unwrap_or_else(|| device_id.clone());
+    run_actions(camera, |action| {
+        run_action(app.clone(), device_id.clone(), camera_name.clone(), action)
+    })
+    .await
+}
+
+/// Run a single scene action on a camera through its device queue.
+async fn run_action(
+    app: AppHandle,
+    device_id: String,
+    camera_name: String,
+    action: SceneAction,
+) -> Result<(), String> {
+    let kind = match &action {
+        SceneAction::ApplyPreset { preset_id } => {
+            return queue_preset_apply(&app, &device_id, preset_id, &camera_name)
+                .await
+                .map(|_| ())
+                .map_err(String::from);
+        }
+        SceneAction::SetControls { .. } => OpKind::PresetApply,
+        SceneAction::StartPreview => OpKind::PreviewStart,
+        SceneAction::StopPreview => OpKind::PreviewStop,
+        SceneAction::PausePreview => OpKind::PreviewRestart,
+    };
+    let handle = app.clone();
+    let op_device = device_id.clone();
+    app.state::<DeviceQueue>()
+        .run(&DeviceId::new(&device_id), kind, move |_| async move {
+            match action {
+                SceneAction::SetControls { controls } => {
+                    for (control_id, value) in controls {
+                        apply::write_control(
+                            &handle.state::<CameraState>().backend,
+                            &handle.state::<SettingsState>().store,
+                            &handle.state::<ControlLatencyState>(),
+                            &op_device,
+                            &camera_name,
+                            parse_control_id(&control_id)?,
+                            value,
+                        )?;
+                    }
+                    Ok(())
+                }
+                SceneAction::StartPreview => resume_preview_session(&handle, &op_device),
+                SceneAction::StopPreview => {
+                    stop_preview_session(&handle, &op_device);
+                    Ok(())
+                }
+                SceneAction::PausePreview => pause_preview_session(&handle, &op_device),
+                SceneAction::ApplyPreset { .. } => unreachable!("applied above"),
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+}
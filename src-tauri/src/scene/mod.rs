@@ -0,0 +1,5 @@
+// Scenes — coordinated actions across a group of cameras, activated at once.
+
+#[cfg(feature = "app")]
+pub mod commands;
+pub mod types;
@@ -0,0 +1,492 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use crate::settings::apply::parse_control_id;
+
+/// A named set of per-camera actions, activated together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    pub name: String,
+    /// One entry per camera, in the order results are reported.
+    pub cameras: Vec<SceneCamera>,
+}
+
+/// What a scene does to one camera.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneCamera {
+    pub device_id: String,
+    /// Run in order; the first failure skips the rest.
+    pub actions: Vec<SceneAction>,
+}
+
+/// One step of a scene on a camera.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SceneAction {
+    ApplyPreset {
+        preset_id: String,
+    },
+    /// Native values by control id, written in id order.
+    SetControls {
+        controls: BTreeMap<String, i32>,
+    },
+    /// Run the preview at full resolution, starting it if needed.
+    StartPreview,
+    StopPreview,
+    /// Drop the preview to thumbnails only, keeping the camera open so it
+    /// starts again quickly.
+    PausePreview,
+}
+
+/// Check a scene before saving it: it needs a name and at least one
+/// camera, every camera and preset it names must exist, every control id
+/// must be known, and no camera may appear twice.
+pub fn validate_scene(
+    scene: &Scene,
+    device_exists: impl Fn(&str) -> bool,
+    preset_exists: impl Fn(&str) -> bool,
+) -> Result<(), String> {
+    if scene.name.trim().is_empty() {
+        return Err("Scene name must not be empty".to_string());
+    }
+    if scene.cameras.is_empty() {
+        return Err(format!("Scene '{}' has no cameras", scene.name));
+    }
+    for (index, camera) in scene.cameras.iter().enumerate() {
+        let device_id = &camera.device_id;
+        if scene.cameras[..index]
+            .iter()
+            .any(|earlier| earlier.device_id == *device_id)
+        {
+            return Err(format!("Camera '{device_id}' appears more than once"));
+        }
+        if !device_exists(device_id) {
+            return Err(format!("Camera '{device_id}' doesn't exist"));
+        }
+        if camera.actions.is_empty() {
+            return Err(format!("Camera '{device_id}' has no actions"));
+        }
+        for action in &camera.actions {
+            match action {
+                SceneAction::ApplyPreset { preset_id } if !preset_exists(preset_id) => {
+                    return Err(format!("Preset '{preset_id}' doesn't exist"));
+                }
+                SceneAction::SetControls { controls } => {
+                    for control_id in controls.keys() {
+                        parse_control_id(control_id)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What happened to one camera when a scene was activated.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraOutcome {
+    pub device_id: String,
+    /// Actions that succeeded, counted from the start of the list.
+    pub completed: usize,
+    pub total: usize,
+    /// Why the action after the completed ones failed. The rest were
+    /// skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CameraOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Run a camera's actions in order with `run`, stopping at the first
+/// failure.
+pub async fn run_actions<F, Fut>(camera: &SceneCamera, mut run: F) -> CameraOutcome
+where
+    F: FnMut(SceneAction) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let mut outcome = CameraOutcome {
+        device_id: camera.device_id.clone(),
+        completed: 0,
+        total: camera.actions.len(),
+        error: None,
+    };
+    for action in &camera.actions {
+        if let Err(e) = run(action.clone()).await {
+            outcome.error = Some(e);
+            break;
+        }
+        outcome.completed += 1;
+    }
+    outcome
+}
+
+/// How a scene activation went overall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivationStatus {
+    /// Every camera ran all of its actions.
+    Succeeded,
+    /// Some cameras failed.
+    Partial,
+    /// Every camera failed.
+    Failed,
+}
+
+/// Result of activating a scene, as returned by `activate_scene` and
+/// emitted as `scene-activated`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneActivation {
+    pub scene: String,
+    pub status: ActivationStatus,
+    /// In the scene's camera order.
+    pub cameras: Vec<CameraOutcome>,
+}
+
+/// Combine the cameras' outcomes, in whatever order they finished, into the
+/// scene's result. A camera without an outcome is reported as failed.
+pub fn summarise_activation(scene: &Scene, mut outcomes: Vec<CameraOutcome>) -> SceneActivation {
+    let cameras: Vec<CameraOutcome> = scene
+        .cameras
+        .iter()
+        .map(|camera| {
+            match outcomes
+                .iter()
+                .position(|o| o.device_id == camera.device_id)
+            {
+                Some(index) => outcomes.swap_remove(index),
+                None => CameraOutcome {
+                    device_id: camera.device_id.clone(),
+                    completed: 0,
+                    total: camera.actions.len(),
+                    error: Some("Didn't report a result".to_string()),
+                },
+            }
+        })
+        .collect();
+
+    let failed = cameras.iter().filter(|c| !c.succeeded()).count();
+    let status = match failed {
+        0 => ActivationStatus::Succeeded,
+        n if n == cameras.len() => ActivationStatus::Failed,
+        _ => ActivationStatus::Partial,
+    };
+    SceneActivation {
+        scene: scene.name.clone(),
+        status,
+        cameras,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::backend::CameraBackend;
+    use crate::camera::dummy::DummyBackend;
+    use crate::camera::types::{ControlId, ControlValue};
+
+    fn camera(device_id: &str, actions: Vec<SceneAction>) -> SceneCamera {
+        SceneCamera {
+            device_id: device_id.to_string(),
+            actions,
+        }
+    }
+
+    fn preset(preset_id: &str) -> SceneAction {
+        SceneAction::ApplyPreset {
+            preset_id: preset_id.to_string(),
+        }
+    }
+
+    fn controls(values: &[(&str, i32)]) -> SceneAction {
+        SceneAction::SetControls {
+            controls: values.iter().map(|&(id, v)| (id.to_string(), v)).collect(),
+        }
+    }
+
+    fn interview() -> Scene {
+        Scene {
+            name: "Interview".to_string(),
+            cameras: vec![
+                camera("cam-a", vec![preset("host")]),
+                camera("cam-b", vec![SceneAction::StartPreview, preset("guest")]),
+                camera("cam-c", vec![SceneAction::PausePreview]),
+            ],
+        }
+    }
+
+    fn reported(
+        device_id: &str,
+        completed: usize,
+        total: usize,
+        error: Option<&str>,
+    ) -> CameraOutcome {
+        CameraOutcome {
+            device_id: device_id.to_string(),
+            completed,
+            total,
+            error: error.map(str::to_string),
+        }
+    }
+
+    /// Validate against the Dummy backend's cameras plus `cam-a` to `cam-c`,
+    /// and the presets `host` and `guest`.
+    fn validate(scene: &Scene) -> Result<(), String> {
+        let devices = DummyBackend::new().enumerate_devices().unwrap();
+        validate_scene(
+            scene,
+            |id| {
+                ["cam-a", "cam-b", "cam-c"].contains(&id)
+                    || devices.iter().any(|d| d.id.as_str() == id)
+            },
+            |id| ["host", "guest"].contains(&id),
+        )
+    }
+
+    // --- Validation ---
+
+    #[test]
+    fn valid_scene_passes() {
+        assert_eq!(validate(&interview()), Ok(()));
+
+        let dummy = Scene {
+            name: "Dummy".to_string(),
+            cameras: vec![camera(
+                DummyBackend::device_id().as_str(),
+                vec![controls(&[("brightness", 10)])],
+            )],
+        };
+        assert_eq!(validate(&dummy), Ok(()));
+    }
+
+    #[test]
+    fn scene_needs_a_name_and_cameras() {
+        let mut scene = interview();
+        scene.name = "  ".to_string();
+        assert!(validate(&scene).unwrap_err().contains("name"));
+
+        let empty = Scene {
+            name: "Empty".to_string(),
+            cameras: vec![],
+        };
+        assert_eq!(
+            validate(&empty).unwrap_err(),
+            "Scene 'Empty' has no cameras"
+        );
+    }
+
+    #[test]
+    fn duplicate_cameras_are_rejected() {
+        let mut scene = interview();
+        scene
+            .cameras
+            .push(camera("cam-a", vec![SceneAction::StopPreview]));
+        assert_eq!(
+            validate(&scene).unwrap_err(),
+            "Camera 'cam-a' appears more than once"
+        );
+    }
+
+    #[test]
+    fn unknown_cameras_presets_and_controls_are_rejected() {
+        let mut scene = interview();
+        scene.cameras[0].device_id = "cam-z".to_string();
+        assert_eq!(
+            validate(&scene).unwrap_err(),
+            "Camera 'cam-z' doesn't exist"
+        );
+
+        let mut scene = interview();
+        scene.cameras[1].actions.push(preset("outro"));
+        assert_eq!(
+            validate(&scene).unwrap_err(),
+            "Preset 'outro' doesn't exist"
+        );
+
+        let mut scene = interview();
+        scene.cameras[2].actions.push(controls(&[("warp", 1)]));
+        assert_eq!(validate(&scene).unwrap_err(), "Unknown control: 'warp'");
+    }
+
+    #[test]
+    fn camera_without_actions_is_rejected() {
+        let mut scene = interview();
+        scene.cameras[2].actions.clear();
+        assert_eq!(
+            validate(&scene).unwrap_err(),
+            "Camera 'cam-c' has no actions"
+        );
+    }
+
+    // --- Running actions ---
+
+    #[tokio::test]
+    async fn actions_run_in_order_against_the_dummy_backend() {
+        let backend = DummyBackend::new();
+        let id = DummyBackend::device_id();
+        let scene_camera = camera(
+            id.as_str(),
+            vec![
+                controls(&[("brightness", 10), ("contrast", 20)]),
+                controls(&[("brightness", 30)]),
+            ],
+        );
+
+        let outcome = run_actions(&scene_camera, |action| {
+            let result = match action {
+                SceneAction::SetControls { controls } => {
+                    controls.iter().try_for_each(|(control, &value)| {
+                        let control = parse_control_id(control)?;
+                        backend
+                            .set_control(&id, &control, ControlValue::new(value, None, None))
+                            .map_err(|e| e.to_string())
+                    })
+                }
+                other => Err(format!("unexpected {other:?}")),
+            };
+            async move { result }
+        })
+        .await;
+
+        assert_eq!(outcome, reported(id.as_str(), 2, 2, None));
+        let value = |control| backend.get_control(&id, &control).unwrap().value();
+        assert_eq!(value(ControlId::Brightness), 30);
+        assert_eq!(value(ControlId::Contrast), 20);
+    }
+
+    #[tokio::test]
+    async fn first_failure_skips_the_remaining_actions() {
+        let scene_camera = camera(
+            "cam-b",
+            vec![
+                SceneAction::StartPreview,
+                preset("guest"),
+                SceneAction::StopPreview,
+            ],
+        );
+        let mut ran = Vec::new();
+
+        let outcome = run_actions(&scene_camera, |action| {
+            ran.push(action.clone());
+            let result = match action {
+                SceneAction::ApplyPreset { .. } => Err("Camera is in use".to_string()),
+                _ => Ok(()),
+            };
+            async move { result }
+        })
+        .await;
+
+        assert_eq!(outcome, reported("cam-b", 1, 3, Some("Camera is in use")));
+        assert_eq!(ran, [SceneAction::StartPreview, preset("guest")]);
+    }
+
+    // --- Aggregation ---
+
+    #[test]
+    fn all_cameras_succeeding_is_a_success_in_scene_order() {
+        let activation = summarise_activation(
+            &interview(),
+            vec![
+                reported("cam-c", 1, 1, None),
+                reported("cam-a", 1, 1, None),
+                reported("cam-b", 2, 2, None),
+            ],
+        );
+        assert_eq!(activation.status, ActivationStatus::Succeeded);
+        let order: Vec<_> = activation
+            .cameras
+            .iter()
+            .map(|c| c.device_id.as_str())
+            .collect();
+        assert_eq!(order, ["cam-a", "cam-b", "cam-c"]);
+    }
+
+    #[test]
+    fn some_failures_are_partial_and_all_failures_failed() {
+        let partial = summarise_activation(
+            &interview(),
+            vec![
+                reported("cam-a", 1, 1, None),
+                reported("cam-b", 1, 2, Some("Preset 'guest' no longer exists")),
+                reported("cam-c", 1, 1, None),
+            ],
+        );
+        assert_eq!(partial.status, ActivationStatus::Partial);
+        assert_eq!(
+            partial.cameras[1].error.as_deref(),
+            Some("Preset 'guest' no longer exists")
+        );
+
+        let failed = summarise_activation(
+            &interview(),
+            vec![
+                reported("cam-a", 0, 1, Some("a")),
+                reported("cam-b", 0, 2, Some("b")),
+                reported("cam-c", 0, 1, Some("c")),
+            ],
+        );
+        assert_eq!(failed.status, ActivationStatus::Failed);
+    }
+
+    #[test]
+    fn missing_outcomes_count_as_failures() {
+        let activation = summarise_activation(&interview(), vec![reported("cam-a", 1, 1, None)]);
+        assert_eq!(activation.status, ActivationStatus::Partial);
+        assert_eq!(
+            activation.cameras[1],
+            reported("cam-b", 0, 2, Some("Didn't report a result"))
+        );
+        assert!(!activation.cameras[2].succeeded());
+    }
+
+    // --- Serialisation ---
+
+    #[test]
+    fn scenes_round_trip_through_camel_case_json() {
+        let scene = Scene {
+            name: "Interview".to_string(),
+            cameras: vec![camera(
+                "cam-a",
+                vec![
+                    preset("host"),
+                    controls(&[("zoom", 120)]),
+                    SceneAction::PausePreview,
+                ],
+            )],
+        };
+        let json = serde_json::to_value(&scene).unwrap();
+        assert_eq!(json["cameras"][0]["deviceId"], "cam-a");
+        assert_eq!(
+            json["cameras"][0]["actions"],
+            serde_json::json!([
+                { "type": "applyPreset", "presetId": "host" },
+                { "type": "setControls", "controls": { "zoom": 120 } },
+                { "type": "pausePreview" },
+            ])
+        );
+        assert_eq!(serde_json::from_value::<Scene>(json).unwrap(), scene);
+    }
+
+    #[test]
+    fn activation_serialises_camel_case_without_empty_errors() {
+        let activation = summarise_activation(&interview(), vec![reported("cam-a", 1, 1, None)]);
+        let json = serde_json::to_value(&activation).unwrap();
+        assert_eq!(json["scene"], "Interview");
+        assert_eq!(json["status"], "partial");
+        assert!(json["cameras"][0].get("error").is_none());
+        assert_eq!(json["cameras"][1]["error"], "Didn't report a result");
+    }
+}
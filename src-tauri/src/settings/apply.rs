@@ -0,0 +1,1700 @@
+//! Writing saved settings and presets to a camera.
+//!
+//! Everything here works on a `CameraBackend` and a `SettingsStore`
+//! directly; the Tauri commands are thin wrappers that pull these out of
+//! managed state.
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::types::{
+    ControlDescriptor, ControlId, ControlValue, ControlValueResponse, DeviceId, SnappedValue,
+};
+use crate::diagnostics::control_latency::{order_by_latency, ControlLatencyState};
+use crate::error::{code, AppError};
+use crate::preset::types::{Preset, PresetApplyResult, SavedPreset};
+use crate::settings::drift::{settings_drift, ControlDrift};
+use crate::settings::reconcile::{
+    reconcile_control, store_reconciled, Reconciliation, SettingsReconciled,
+};
+use crate::settings::store::SettingsStore;
+use crate::settings::types::{CameraSettings, ControlSource, ResetResult, SavedControl};
+
+/// What `apply_saved_settings` restored, and what it had to adjust.
+#[derive(Debug, Default)]
+pub struct RestoredSettings {
+    /// Controls written or put back in auto mode, with their saved entries.
+    pub applied: Vec<(String, SavedControl)>,
+    /// Saved values that couldn't be restored as they were.
+    pub reconciled: Vec<Reconciliation>,
+    /// Whether the saved values were updated to the ones written.
+    pub updated: bool,
+}
+
+impl RestoredSettings {
+    /// The `settings-reconciled` payload for this restore, if anything had
+    /// to be adjusted.
+    pub fn reconciled_event(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+    ) -> Option<SettingsReconciled> {
+        (!self.reconciled.is_empty()).then(|| SettingsReconciled {
+            device_id: device_id.to_string(),
+            camera_name: camera_name.to_string(),
+            adjustments: self.reconciled.clone(),
+            updated: self.updated,
+        })
+    }
+}
+
+/// Apply saved settings to a connected camera.
+///
+/// Modes are restored first: a control left in auto mode would otherwise
+/// override the manual value written after it. Values are then written for
+/// manual controls only, clamped to the descriptor's range and ordered
+/// slowest-first using recorded latency stats. Controls saved in auto mode
+/// only get their mode back. Logs and skips individual failures.
+///
+/// Controls are matched to the camera's descriptors by ID, so ones without
+/// a `ControlId`, like Canon's picture style, are restored too.
+///
+/// Values that had to be clamped or snapped, and controls the camera no
+/// longer has, are reported in `reconciled`; see [`store_reconciled`] for
+/// how the saved values are updated.
+pub fn apply_saved_settings(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+) -> RestoredSettings {
+    let saved = match store.get_camera(device_id) {
+        Some(s) => s,
+        None => return RestoredSettings::default(),
+    };
+
+    let id = DeviceId::new(device_id);
+    let descriptors = match backend.get_controls(&id) {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::warn!("Failed to get controls for {device_id}: {e}");
+            return RestoredSettings::default();
+        }
+    };
+
+    let saved_ids: Vec<String> = saved.controls.keys().cloned().collect();
+    let ordered = order_by_latency(&latency.stats_for_device(device_id), &saved_ids);
+
+    let mut restored = RestoredSettings::default();
+    let known: Vec<_> = ordered
+        .iter()
+        .filter_map(|control_str| {
+            let entry = &saved.controls[control_str];
+            let Some(desc) = descriptors.iter().find(|d| d.id == *control_str) else {
+                tracing::warn!(
+                    "Control '{control_str}' not available on device {device_id}, skipping"
+                );
+                restored
+                    .reconciled
+                    .extend(reconcile_control(control_str, entry, None));
+                return None;
+            };
+            Some((control_str, desc, entry.clone()))
+        })
+        .collect();
+
+    // Mode first, so auto mode can't override the value written next
+    let mut mode_restored = Vec::new();
+    for (control_str, desc, entry) in &known {
+        if !desc.flags.supports_auto {
+            mode_restored.push(*control_str);
+            continue;
+        }
+        let Some(control) = ControlId::from_str_id(control_str) else {
+            tracing::warn!("Can't switch the mode of '{control_str}' on {device_id}, skipping");
+            continue;
+        };
+        match backend.set_control_auto(&id, &control, entry.auto) {
+            Ok(()) => mode_restored.push(*control_str),
+            Err(e) => {
+                let mode = if entry.auto { "auto" } else { "manual" };
+                tracing::warn!("Failed to set '{control_str}' to {mode} on {device_id}: {e}");
+            }
+        }
+    }
+
+    for (control_str, desc, entry) in known {
+        if !mode_restored.contains(&control_str) {
+            continue;
+        }
+        if entry.auto {
+            restored.applied.push((control_str.clone(), entry));
+            continue;
+        }
+
+        let value = entry.value;
+        let adjustment = reconcile_control(control_str, &entry, Some(desc));
+        let clamped = desc.clamp(value).value;
+        if adjustment.is_some() {
+            tracing::info!(
+                "Saved '{control_str}' = {value} isn't valid on {device_id}, writing {}",
+                clamped.value()
+            );
+        }
+        match latency.time_write(device_id, control_str, || {
+            backend.set_control_by_str(&id, control_str, clamped)
+        }) {
+            Ok(()) => {
+                restored.applied.push((control_str.clone(), entry));
+                restored.reconciled.extend(adjustment);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to apply '{control_str}' = {value} on {device_id}: {e}");
+            }
+        }
+    }
+
+    restored.updated = store_reconciled(store, device_id, &restored.reconciled);
+    restored
+}
+
+/// Parse a string control ID to a `ControlId`, returning a human-readable
+/// error on failure.
+pub fn parse_control_id(s: &str) -> Result<ControlId, AppError> {
+    ControlId::from_str_id(s)
+        .ok_or_else(|| AppError::new(code::INVALID_ARGUMENT, format!("Unknown control: '{s}'")))
+}
+
+/// Look up the descriptor for a control, failing if the device doesn't
+/// support it.
+pub fn find_descriptor(
+    backend: &dyn CameraBackend,
+    id: &DeviceId,
+    control: &ControlId,
+) -> Result<ControlDescriptor, AppError> {
+    let descriptors = backend.get_controls(id)?;
+    descriptors
+        .into_iter()
+        .find(|d| d.id == control.as_id_str())
+        .ok_or_else(|| {
+            AppError::new(
+                code::CONTROL_UNAVAILABLE,
+                format!(
+                    "Control '{}' not supported on this device",
+                    control.display_name()
+                ),
+            )
+        })
+}
+
+/// Look up the descriptor for a control by its ID, failing if the device
+/// doesn't offer it. Unlike [`find_descriptor`] this finds controls without
+/// a `ControlId` too.
+pub fn find_descriptor_by_str(
+    backend: &dyn CameraBackend,
+    id: &DeviceId,
+    control_id: &str,
+) -> Result<ControlDescriptor, AppError> {
+    let descriptors = backend.get_controls(id)?;
+    descriptors
+        .into_iter()
+        .find(|d| d.id == control_id)
+        .ok_or_else(|| {
+            AppError::new(
+                code::CONTROL_UNAVAILABLE,
+                format!("Control '{control_id}' not supported on this device"),
+            )
+        })
+}
+
+/// Read one control's value, range and mode from its descriptor.
+///
+/// The descriptor already carries the current value, so the camera is only
+/// queried once.
+pub fn read_control(
+    backend: &dyn CameraBackend,
+    device_id: &str,
+    control: ControlId,
+) -> Result<ControlValueResponse, AppError> {
+    let desc = find_descriptor(backend, &DeviceId::new(device_id), &control)?;
+    Ok(ControlValueResponse {
+        value: desc.current,
+        is_auto_enabled: desc.flags.is_auto_enabled,
+        min: desc.min,
+        max: desc.max,
+    })
+}
+
+/// Clamp a native control value to the control's range and step, write it
+/// and persist it.
+///
+/// Returns the value actually written and whether it differs from `value`.
+pub fn write_control(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    camera_name: &str,
+    control: ControlId,
+    value: i32,
+) -> Result<SnappedValue, AppError> {
+    // Look up the descriptor to know the valid range
+    let desc = find_descriptor(backend, &DeviceId::new(device_id), &control)?;
+    write_within(
+        backend,
+        store,
+        latency,
+        device_id,
+        camera_name,
+        &desc,
+        value,
+    )
+}
+
+/// [`write_control`] for a control named by its descriptor ID, which needn't
+/// have a `ControlId`.
+pub fn write_control_by_str(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    camera_name: &str,
+    control_id: &str,
+    value: i32,
+) -> Result<SnappedValue, AppError> {
+    let desc = find_descriptor_by_str(backend, &DeviceId::new(device_id), control_id)?;
+    write_within(
+        backend,
+        store,
+        latency,
+        device_id,
+        camera_name,
+        &desc,
+        value,
+    )
+}
+
+/// Move a control `steps` of its step size up from its current value, or
+/// down for negative `steps`, then clamp, write and persist it as
+/// [`write_control`] does. For nudges from held keys.
+pub fn nudge_control(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    camera_name: &str,
+    control: ControlId,
+    steps: i32,
+) -> Result<SnappedValue, AppError> {
+    let desc = find_descriptor(backend, &DeviceId::new(device_id), &control)?;
+    let delta = steps.saturating_mul(desc.step.unwrap_or(1).max(1));
+    let value = desc.current.saturating_add(delta);
+    write_within(
+        backend,
+        store,
+        latency,
+        device_id,
+        camera_name,
+        &desc,
+        value,
+    )
+}
+
+/// Clamp `value` to `desc`, write it and persist it.
+fn write_within(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    camera_name: &str,
+    desc: &ControlDescriptor,
+    value: i32,
+) -> Result<SnappedValue, AppError> {
+    let id = DeviceId::new(device_id);
+    let control_id = desc.id.as_str();
+    if desc.flags.is_read_only {
+        return Err(AppError::new(
+            code::CONTROL_REJECTED,
+            format!("Control '{}' is read-only", desc.name),
+        ));
+    }
+
+    let clamped = desc.clamp(value);
+    latency.time_write(device_id, control_id, || {
+        backend.set_control_by_str(&id, control_id, clamped.value)
+    })?;
+
+    store.set_control(device_id, camera_name, control_id, clamped.value.value());
+    Ok(clamped)
+}
+
+/// Switch a control between automatic and manual mode and persist the mode.
+///
+/// Switching to manual keeps the value the control currently holds.
+pub fn write_control_auto(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    device_id: &str,
+    camera_name: &str,
+    control: ControlId,
+    auto: bool,
+) -> Result<(), AppError> {
+    let id = DeviceId::new(device_id);
+    let desc = find_descriptor(backend, &id, &control)?;
+    if !desc.flags.supports_auto {
+        return Err(AppError::new(
+            code::CONTROL_REJECTED,
+            format!("Control '{}' has no automatic mode", control.display_name()),
+        ));
+    }
+
+    backend.set_control_auto(&id, &control, auto)?;
+
+    store.set_control_auto(
+        device_id,
+        camera_name,
+        control.as_id_str(),
+        auto,
+        desc.current,
+    );
+    Ok(())
+}
+
+/// Write native control values to a camera, slowest first, saving each one
+/// written with `source`. Values must already be in the control's range.
+/// Logs and skips individual failures; returns the values written.
+pub fn write_tracked_values(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    camera_name: &str,
+    values: &[(String, i32)],
+    source: &ControlSource,
+) -> Vec<(String, i32)> {
+    let id = DeviceId::new(device_id);
+    let control_ids: Vec<String> = values.iter().map(|(c, _)| c.clone()).collect();
+    let ordered = order_by_latency(&latency.stats_for_device(device_id), &control_ids);
+
+    let mut written = Vec::new();
+    for control_str in ordered {
+        let Some(control) = ControlId::from_str_id(&control_str) else {
+            tracing::warn!("Skipping unknown control '{control_str}' for {device_id}");
+            continue;
+        };
+        let value = values
+            .iter()
+            .find(|(c, _)| *c == control_str)
+            .map(|(_, v)| *v)
+            .expect("ordered ids come from values");
+        match latency.time_write(device_id, &control_str, || {
+            backend.set_control(&id, &control, ControlValue::new(value, None, None))
+        }) {
+            Ok(()) => {
+                store.record_control(device_id, camera_name, &control_str, value, source.clone());
+                written.push((control_str, value));
+            }
+            Err(e) => {
+                tracing::warn!("Failed to write '{control_str}' = {value} on {device_id}: {e}");
+            }
+        }
+    }
+    written
+}
+
+/// Saved settings for a camera and the preset last applied to it.
+pub fn applied_preset(
+    store: &SettingsStore,
+    device_id: &str,
+) -> Result<(CameraSettings, Preset), AppError> {
+    let saved = store
+        .get_camera(device_id)
+        .ok_or_else(|| AppError::new(code::NOT_FOUND, "No settings saved for this camera"))?;
+    let preset_id = saved.preset.clone().ok_or_else(|| {
+        AppError::new(code::NOT_FOUND, "No preset has been applied to this camera")
+    })?;
+    let preset = store.preset(&preset_id).ok_or_else(|| {
+        AppError::new(
+            code::NOT_FOUND,
+            format!("Preset '{preset_id}' no longer exists"),
+        )
+    })?;
+    Ok((saved, preset))
+}
+
+/// Capture a camera's current control values as a preset named `name` and
+/// save it under `preset_id`, replacing any preset with that ID.
+///
+/// Refused if another preset already has the name (ignoring case), since
+/// presets are also looked up by name.
+pub fn save_preset(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    device_id: &str,
+    preset_id: &str,
+    name: &str,
+    normalised: bool,
+) -> Result<Preset, AppError> {
+    let taken = store
+        .presets()
+        .into_iter()
+        .any(|(id, preset)| id != preset_id && preset.name.eq_ignore_ascii_case(name));
+    if taken {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("A preset named '{name}' already exists"),
+        ));
+    }
+    let descriptors = backend.get_controls(&DeviceId::new(device_id))?;
+    let preset = Preset::capture(name, &descriptors, normalised);
+    store.save_preset(preset_id, preset.clone());
+    Ok(preset)
+}
+
+/// Saved presets with at least one control `device_id` can take, sorted by
+/// ID.
+pub fn presets_for_device(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    device_id: &str,
+) -> Result<Vec<SavedPreset>, AppError> {
+    let descriptors = backend.get_controls(&DeviceId::new(device_id))?;
+    Ok(store
+        .presets()
+        .into_iter()
+        .filter(|(_, preset)| !preset.resolve(&descriptors).is_empty())
+        .map(|(id, preset)| SavedPreset { id, preset })
+        .collect())
+}
+
+/// Apply a saved preset to a camera and remember it as the camera's applied
+/// preset, for drift and revert.
+///
+/// Values are clamped to the camera's ranges, and controls it doesn't have
+/// are skipped. Each control written is saved with the preset as its
+/// source.
+pub fn apply_preset(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    preset_id: &str,
+    camera_name: &str,
+) -> Result<PresetApplyResult, AppError> {
+    let preset = store.preset(preset_id).ok_or_else(|| {
+        AppError::new(
+            code::NOT_FOUND,
+            format!("Preset '{preset_id}' no longer exists"),
+        )
+    })?;
+    let descriptors = backend.get_controls(&DeviceId::new(device_id))?;
+
+    store.set_applied_preset(device_id, camera_name, preset_id);
+    let written = write_tracked_values(
+        backend,
+        store,
+        latency,
+        device_id,
+        camera_name,
+        &preset.resolve(&descriptors),
+        &ControlSource::Preset(preset_id.to_string()),
+    );
+
+    let mut applied: Vec<String> = written.into_iter().map(|(id, _)| id).collect();
+    applied.sort();
+    let mut skipped: Vec<String> = preset
+        .controls
+        .into_keys()
+        .filter(|id| !applied.contains(id))
+        .collect();
+    skipped.sort();
+    Ok(PresetApplyResult { applied, skipped })
+}
+
+/// Controls whose saved value differs from the preset last applied to the
+/// camera, with both values.
+pub fn preset_drift(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    device_id: &str,
+) -> Result<Vec<ControlDrift>, AppError> {
+    let (saved, preset) = applied_preset(store, device_id)?;
+    let descriptors = backend.get_controls(&DeviceId::new(device_id))?;
+    Ok(settings_drift(&preset, &descriptors, &saved.controls))
+}
+
+/// Re-apply the preset last applied to the camera, writing only the
+/// controls that have drifted from it.
+///
+/// Returns the drift that was reverted.
+pub fn revert_drift(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    latency: &ControlLatencyState,
+    device_id: &str,
+) -> Result<Vec<ControlDrift>, AppError> {
+    let (saved, preset) = applied_preset(store, device_id)?;
+    let descriptors = backend.get_controls(&DeviceId::new(device_id))?;
+
+    let drift = settings_drift(&preset, &descriptors, &saved.controls);
+    let values: Vec<(String, i32)> = drift
+        .iter()
+        .map(|d| (d.control_id.clone(), d.preset_value))
+        .collect();
+    let written = write_tracked_values(
+        backend,
+        store,
+        latency,
+        device_id,
+        &saved.name,
+        &values,
+        &ControlSource::Restore,
+    );
+    Ok(drift
+        .into_iter()
+        .filter(|d| written.iter().any(|(c, _)| *c == d.control_id))
+        .collect())
+}
+
+/// Reset each control on a camera to its power-on state (see
+/// [`reset_control`]). Controls with nothing to reset to are skipped; the
+/// first failed write aborts.
+pub fn reset_controls(
+    backend: &dyn CameraBackend,
+    latency: &ControlLatencyState,
+    device_id: &str,
+) -> Result<Vec<ResetResult>, AppError> {
+    let id = DeviceId::new(device_id);
+    let descriptors = backend.get_controls(&id)?;
+
+    let mut reset_values = Vec::new();
+
+    for desc in &descriptors {
+        if let Some(result) = reset_control(backend, latency, device_id, desc)? {
+            reset_values.push(result);
+        }
+    }
+
+    Ok(reset_values)
+}
+
+/// Reset one control to its power-on state.
+///
+/// Controls whose factory default is automatic mode get auto re-enabled and
+/// keep their current value; the rest get their numeric default written in
+/// manual mode. Returns `None` for a control with neither to go back to,
+/// such as Canon's, which report no defaults.
+pub fn reset_control(
+    backend: &dyn CameraBackend,
+    latency: &ControlLatencyState,
+    device_id: &str,
+    desc: &ControlDescriptor,
+) -> Result<Option<ResetResult>, AppError> {
+    let id = DeviceId::new(device_id);
+
+    // Only controls with a `ControlId` have a mode to switch
+    let auto_control = ControlId::from_str_id(&desc.id).filter(|_| desc.resets_to_auto());
+    if let Some(control) = auto_control {
+        latency.time_write(device_id, &desc.id, || {
+            backend.set_control_auto(&id, &control, true)
+        })?;
+        return Ok(Some(ResetResult {
+            control_id: desc.id.clone(),
+            value: desc.current,
+            auto: true,
+        }));
+    }
+
+    let default_val = match desc.default {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    let clamped = desc.clamp(default_val).value;
+    latency.time_write(device_id, &desc.id, || {
+        backend.set_control_by_str(&id, &desc.id, clamped)
+    })?;
+
+    Ok(Some(ResetResult {
+        control_id: desc.id.clone(),
+        value: clamped.value(),
+        auto: false,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::backend::CameraBackend;
+    use crate::camera::composite::CompositeBackend;
+    use crate::camera::dummy::DummyBackend;
+    use crate::camera::error::{CameraError, Result as CamResult};
+    use crate::camera::types::{
+        CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue,
+        DeviceId, DeviceKind, FormatDescriptor, HotplugEvent,
+    };
+    use crate::settings::reconcile::ReconcileReason;
+    use crate::settings::store::SettingsStore;
+    use crate::settings::types::ResetResult;
+    use std::sync::Mutex;
+
+    /// A write made through the mock, in the order it happened.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Write {
+        Mode(String, bool),
+        Value(String, i32),
+    }
+
+    /// Mock backend that tracks set_control calls for verification.
+    struct MockBackend {
+        devices: Vec<CameraDevice>,
+        controls: Vec<ControlDescriptor>,
+        set_calls: Mutex<Vec<(String, String, i32)>>,
+        /// Mode and value writes interleaved in call order.
+        writes: Mutex<Vec<Write>>,
+        /// Controls that should fail when set (control_id strings).
+        fail_controls: Vec<String>,
+    }
+
+    impl MockBackend {
+        fn new(controls: Vec<ControlDescriptor>) -> Self {
+            Self {
+                devices: vec![CameraDevice {
+                    id: DeviceId::new("test-device"),
+                    name: "Test Camera".to_string(),
+                    device_path: "test-path".to_string(),
+                    is_connected: true,
+                    kind: DeviceKind::Primary,
+                    primary_id: None,
+                    identity: None,
+                    suppressed_by: None,
+                }],
+                controls,
+                set_calls: Mutex::new(Vec::new()),
+                writes: Mutex::new(Vec::new()),
+                fail_controls: Vec::new(),
+            }
+        }
+
+        fn with_failing_controls(mut self, fails: Vec<String>) -> Self {
+            self.fail_controls = fails;
+            self
+        }
+    }
+
+    impl CameraBackend for MockBackend {
+        fn enumerate_devices(&self) -> CamResult<Vec<CameraDevice>> {
+            Ok(self.devices.clone())
+        }
+
+        fn watch_hotplug(&self, _callback: Box<dyn Fn(HotplugEvent) + Send>) -> CamResult<()> {
+            Ok(())
+        }
+
+        fn get_controls(&self, id: &DeviceId) -> CamResult<Vec<ControlDescriptor>> {
+            if self.devices.iter().any(|d| &d.id == id) {
+                Ok(self.controls.clone())
+            } else {
+                Err(CameraError::DeviceNotFound(id.to_string()))
+            }
+        }
+
+        fn get_control(&self, _id: &DeviceId, _control: &ControlId) -> CamResult<ControlValue> {
+            Ok(ControlValue::new(128, Some(0), Some(255)))
+        }
+
+        fn set_control(
+            &self,
+            id: &DeviceId,
+            control: &ControlId,
+            value: ControlValue,
+        ) -> CamResult<()> {
+            if !self.devices.iter().any(|d| &d.id == id) {
+                return Err(CameraError::DeviceNotFound(id.to_string()));
+            }
+            let control_str = control.as_id_str().to_string();
+            if self.fail_controls.contains(&control_str) {
+                return Err(CameraError::ControlWrite(format!(
+                    "simulated failure for {control_str}"
+                )));
+            }
+            self.writes
+                .lock()
+                .unwrap()
+                .push(Write::Value(control_str.clone(), value.value()));
+            self.set_calls.lock().unwrap().push((
+                id.as_str().to_string(),
+                control_str,
+                value.value(),
+            ));
+            Ok(())
+        }
+
+        fn set_control_auto(
+            &self,
+            id: &DeviceId,
+            control: &ControlId,
+            auto: bool,
+        ) -> CamResult<()> {
+            if !self.devices.iter().any(|d| &d.id == id) {
+                return Err(CameraError::DeviceNotFound(id.to_string()));
+            }
+            self.writes
+                .lock()
+                .unwrap()
+                .push(Write::Mode(control.as_id_str().to_string(), auto));
+            Ok(())
+        }
+
+        /// Controls without a `ControlId` are written as they come, like a
+        /// backend with controls of its own.
+        fn set_control_by_str(
+            &self,
+            id: &DeviceId,
+            control: &str,
+            value: ControlValue,
+        ) -> CamResult<()> {
+            match ControlId::from_str_id(control) {
+                Some(control) => self.set_control(id, &control, value),
+                None => {
+                    self.writes
+                        .lock()
+                        .unwrap()
+                        .push(Write::Value(control.to_string(), value.value()));
+                    Ok(())
+                }
+            }
+        }
+
+        fn get_formats(&self, _id: &DeviceId) -> CamResult<Vec<FormatDescriptor>> {
+            Ok(vec![])
+        }
+    }
+
+    /// A control only known by its descriptor ID, with values 0 to 5.
+    fn make_style_control(default: Option<i32>) -> ControlDescriptor {
+        ControlDescriptor {
+            id: "style".to_string(),
+            name: "Style".to_string(),
+            min: Some(0),
+            max: Some(5),
+            default,
+            current: 0,
+            ..make_brightness_control(None)
+        }
+    }
+
+    fn make_brightness_control(default: Option<i32>) -> ControlDescriptor {
+        ControlDescriptor {
+            id: "brightness".to_string(),
+            name: "Brightness".to_string(),
+            control_type: ControlType::Slider,
+            group: "image".to_string(),
+            min: Some(0),
+            max: Some(255),
+            step: Some(1),
+            default,
+            default_auto: false,
+            current: 128,
+            flags: ControlFlags {
+                supports_auto: false,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    fn make_contrast_control(default: Option<i32>) -> ControlDescriptor {
+        ControlDescriptor {
+            id: "contrast".to_string(),
+            name: "Contrast".to_string(),
+            control_type: ControlType::Slider,
+            group: "image".to_string(),
+            min: Some(0),
+            max: Some(100),
+            step: Some(1),
+            default,
+            default_auto: false,
+            current: 50,
+            flags: ControlFlags {
+                supports_auto: false,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    fn temp_store() -> (SettingsStore, tempfile::TempDir) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        (SettingsStore::new(path), dir)
+    }
+
+    // --- read_control tests ---
+
+    #[test]
+    fn read_control_reads_the_dummy_camera() {
+        let backend = DummyBackend::new();
+        let id = DummyBackend::device_id();
+        backend
+            .set_control(&id, &ControlId::Contrast, ControlValue::new(70, None, None))
+            .unwrap();
+
+        let reading = read_control(&backend, id.as_str(), ControlId::Contrast).unwrap();
+        assert_eq!(
+            reading,
+            ControlValueResponse {
+                value: 70,
+                is_auto_enabled: false,
+                min: Some(0),
+                max: Some(100),
+            }
+        );
+    }
+
+    #[test]
+    fn read_control_routes_through_the_composite() {
+        let mut brightness = make_brightness_control(Some(128));
+        brightness.flags.supports_auto = true;
+        brightness.flags.is_auto_enabled = true;
+        let composite = CompositeBackend::new(vec![
+            Box::new(DummyBackend::new()),
+            Box::new(MockBackend::new(vec![brightness])),
+        ]);
+
+        let mock = read_control(&composite, "test-device", ControlId::Brightness).unwrap();
+        assert_eq!(mock.value, 128);
+        assert!(mock.is_auto_enabled);
+
+        let dummy_id = DummyBackend::device_id();
+        let dummy = read_control(&composite, dummy_id.as_str(), ControlId::Contrast).unwrap();
+        assert_eq!((dummy.value, dummy.max), (50, Some(100)));
+        assert!(!dummy.is_auto_enabled);
+    }
+
+    #[test]
+    fn read_control_takes_the_value_from_the_descriptor() {
+        let mut brightness = make_brightness_control(Some(128));
+        brightness.current = 90;
+        let backend = MockBackend::new(vec![brightness]);
+
+        // The mock's `get_control` always answers 128
+        let reading = read_control(&backend, "test-device", ControlId::Brightness).unwrap();
+        assert_eq!(reading.value, 90);
+    }
+
+    #[test]
+    fn read_control_errors_name_the_problem() {
+        let backend = DummyBackend::new();
+
+        let err = read_control(&backend, "usb:unknown", ControlId::Brightness).unwrap_err();
+        assert_eq!(err.code, code::DEVICE_NOT_FOUND);
+        assert!(err.message.contains("usb:unknown"), "{}", err.message);
+
+        let id = DummyBackend::device_id();
+        let err = read_control(&backend, id.as_str(), ControlId::Exposure).unwrap_err();
+        assert_eq!(err.code, code::CONTROL_UNAVAILABLE);
+        assert!(err.message.contains("not supported"), "{}", err.message);
+    }
+
+    // --- Apply saved settings tests (Step 5) ---
+
+    #[test]
+    fn apply_saved_settings_calls_set_control_for_each_saved_value() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control("test-device", "Camera", "contrast", 80);
+
+        let latency = ControlLatencyState::default();
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        assert_eq!(applied.len(), 2);
+
+        let calls = backend.set_calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+    }
+
+    #[test]
+    fn apply_saved_settings_snaps_onto_a_coarser_step() {
+        let mut brightness = make_brightness_control(Some(128));
+        brightness.step = Some(10);
+        let backend = MockBackend::new(vec![brightness]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 203);
+
+        let latency = ControlLatencyState::default();
+        apply_saved_settings(&backend, &store, &latency, "test-device");
+        let calls = backend.set_calls.lock().unwrap();
+        assert_eq!(calls[0].2, 200);
+    }
+
+    #[test]
+    fn apply_saved_settings_reconciles_values_the_camera_no_longer_accepts() {
+        let mut brightness = make_brightness_control(Some(128));
+        brightness.max = Some(150);
+        let backend = MockBackend::new(vec![brightness]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control("test-device", "Camera", "zoom", 3);
+
+        let latency = ControlLatencyState::default();
+        let restored = apply_saved_settings(&backend, &store, &latency, "test-device");
+        let reasons: Vec<_> = restored
+            .reconciled
+            .iter()
+            .map(|r| (r.control_id.as_str(), r.applied, r.reason))
+            .collect();
+        assert!(reasons.contains(&("brightness", Some(150), ReconcileReason::OutOfRange)));
+        assert!(reasons.contains(&("zoom", None, ReconcileReason::ControlMissing)));
+        assert!(restored.updated);
+
+        let saved = store.get_camera("test-device").unwrap();
+        assert_eq!(saved.controls["brightness"].value, 150);
+        assert_eq!(saved.controls["zoom"].value, 3);
+
+        // Once reconciled, the next restore has nothing to adjust but zoom
+        let again = apply_saved_settings(&backend, &store, &latency, "test-device");
+        assert_eq!(again.reconciled.len(), 1);
+    }
+
+    #[test]
+    fn nudge_control_moves_by_steps_from_the_current_value() {
+        let mut contrast = make_contrast_control(Some(50));
+        contrast.step = Some(5);
+        contrast.current = 90;
+        let backend = MockBackend::new(vec![contrast]);
+        let (store, _dir) = temp_store();
+        let latency = ControlLatencyState::default();
+        let nudge = |steps| {
+            nudge_control(
+                &backend,
+                &store,
+                &latency,
+                "test-device",
+                "Camera",
+                ControlId::Contrast,
+                steps,
+            )
+            .unwrap()
+        };
+
+        let down = nudge(-3);
+        assert_eq!(down.value.value(), 75);
+        assert!(!down.snapped);
+
+        // Clamped at the top of the range
+        let up = nudge(4);
+        assert_eq!(up.value.value(), 100);
+        assert!(up.snapped);
+        assert_eq!(
+            store.get_camera("test-device").unwrap().controls["contrast"].value,
+            100
+        );
+    }
+
+    #[test]
+    fn write_control_reports_the_snapped_value_and_saves_it() {
+        let mut brightness = make_brightness_control(Some(128));
+        brightness.step = Some(4);
+        let backend = MockBackend::new(vec![brightness]);
+        let (store, _dir) = temp_store();
+        let latency = ControlLatencyState::default();
+
+        let written = write_control(
+            &backend,
+            &store,
+            &latency,
+            "test-device",
+            "Camera",
+            ControlId::Brightness,
+            131,
+        )
+        .unwrap();
+        assert_eq!(written.value.value(), 132);
+        assert!(written.snapped);
+        assert_eq!(backend.set_calls.lock().unwrap()[0].2, 132);
+        let saved = store.get_camera("test-device").unwrap();
+        assert_eq!(saved.controls["brightness"].value, 132);
+
+        let exact = write_control(
+            &backend,
+            &store,
+            &latency,
+            "test-device",
+            "Camera",
+            ControlId::Brightness,
+            128,
+        )
+        .unwrap();
+        assert!(!exact.snapped);
+    }
+
+    #[test]
+    fn apply_saved_settings_skips_unknown_controls() {
+        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control("test-device", "Camera", "nonexistent_control", 42);
+
+        let latency = ControlLatencyState::default();
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        // Only brightness should be applied, nonexistent_control skipped
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].0, "brightness");
+    }
+
+    #[test]
+    fn apply_saved_settings_restores_controls_without_a_control_id() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_style_control(None),
+        ]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "style", 9);
+
+        let latency = ControlLatencyState::default();
+        let restored = apply_saved_settings(&backend, &store, &latency, "test-device");
+        assert_eq!(restored.applied.len(), 1);
+        assert_eq!(restored.applied[0].0, "style");
+        // Clamped to the descriptor's range like any other control
+        assert_eq!(
+            *backend.writes.lock().unwrap(),
+            [Write::Value("style".to_string(), 5)]
+        );
+    }
+
+    #[test]
+    fn write_control_by_str_writes_and_saves_controls_without_a_control_id() {
+        let backend = MockBackend::new(vec![make_style_control(None)]);
+        let (store, _dir) = temp_store();
+        let latency = ControlLatencyState::default();
+
+        let written = write_control_by_str(
+            &backend,
+            &store,
+            &latency,
+            "test-device",
+            "Camera",
+            "style",
+            3,
+        )
+        .unwrap();
+        assert_eq!(written.value.value(), 3);
+        assert_eq!(
+            *backend.writes.lock().unwrap(),
+            [Write::Value("style".to_string(), 3)]
+        );
+        assert_eq!(
+            store.get_camera("test-device").unwrap().controls["style"].value,
+            3
+        );
+
+        let err = write_control_by_str(
+            &backend,
+            &store,
+            &latency,
+            "test-device",
+            "Camera",
+            "missing",
+            1,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, code::CONTROL_UNAVAILABLE);
+    }
+
+    #[cfg(feature = "canon")]
+    #[test]
+    fn canon_controls_are_restored_after_a_restart() {
+        use crate::camera::canon::api::{CameraHandle, EdsSdkApi};
+        use crate::camera::canon::backend::CanonBackend;
+        use crate::camera::canon::mock::MockEdsSdk;
+        use crate::camera::canon::types::{PROP_ID_ISO_SPEED, PROP_ID_PICTURE_STYLE};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        /// A freshly connected EOS R5 at ISO 100 and the standard style.
+        fn camera() -> Arc<MockEdsSdk> {
+            Arc::new(
+                MockEdsSdk::new()
+                    .with_camera("Canon EOS R5", Some("SER001"))
+                    .with_property(0, PROP_ID_ISO_SPEED, 0x48)
+                    .with_property_desc(0, PROP_ID_ISO_SPEED, vec![0x48, 0x50, 0x58])
+                    .with_property(0, PROP_ID_PICTURE_STYLE, 0x81)
+                    .with_property_desc(0, PROP_ID_PICTURE_STYLE, vec![0x81, 0x86]),
+            )
+        }
+
+        fn composite(sdk: &Arc<MockEdsSdk>) -> CompositeBackend {
+            let canon = CanonBackend::new(Arc::clone(sdk), Arc::new(Mutex::new(HashMap::new())));
+            let composite =
+                CompositeBackend::new(vec![Box::new(DummyBackend::new()), Box::new(canon)]);
+            composite.enumerate_devices().unwrap();
+            composite
+        }
+
+        let (store, dir) = temp_store();
+        let latency = ControlLatencyState::default();
+        let sdk = camera();
+        let backend = composite(&sdk);
+        for (control, value) in [("canon_iso", 0x50), ("canon_picture_style", 0x86)] {
+            write_control_by_str(
+                &backend,
+                &store,
+                &latency,
+                "canon:SER001",
+                "Canon EOS R5",
+                control,
+                value,
+            )
+            .unwrap();
+        }
+        store.save().unwrap();
+        drop(backend);
+
+        // The app restarts and the camera comes back at its own settings
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+        let sdk = camera();
+        let backend = composite(&sdk);
+        let restored = apply_saved_settings(&backend, &store, &latency, "canon:SER001");
+
+        let mut applied: Vec<&str> = restored.applied.iter().map(|(c, _)| c.as_str()).collect();
+        applied.sort_unstable();
+        assert_eq!(applied, ["canon_iso", "canon_picture_style"]);
+        assert!(restored.reconciled.is_empty());
+        let camera = CameraHandle(0);
+        assert_eq!(sdk.get_property(camera, PROP_ID_ISO_SPEED).unwrap(), 0x50);
+        assert_eq!(
+            sdk.get_property(camera, PROP_ID_PICTURE_STYLE).unwrap(),
+            0x86
+        );
+    }
+
+    #[test]
+    fn apply_saved_settings_does_nothing_when_no_saved_settings() {
+        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
+        let (store, _dir) = temp_store();
+
+        let latency = ControlLatencyState::default();
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        assert!(applied.is_empty());
+        assert!(backend.set_calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn apply_saved_settings_continues_on_individual_control_failure() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ])
+        .with_failing_controls(vec!["brightness".to_string()]);
+
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control("test-device", "Camera", "contrast", 80);
+
+        let latency = ControlLatencyState::default();
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        // brightness fails but contrast should still be applied
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].0, "contrast");
+    }
+
+    #[test]
+    fn apply_saved_settings_writes_slowest_controls_first() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control("test-device", "Camera", "contrast", 80);
+
+        let latency = ControlLatencyState::default();
+        latency.tracker.lock().record(
+            "test-device",
+            "contrast",
+            std::time::Duration::from_millis(300),
+        );
+
+        apply_saved_settings(&backend, &store, &latency, "test-device");
+
+        let calls = backend.set_calls.lock().unwrap();
+        assert_eq!(calls[0].1, "contrast");
+        assert_eq!(calls[1].1, "brightness");
+        drop(calls);
+
+        let stats = latency.stats_for_device("test-device");
+        assert_eq!(stats.iter().map(|s| s.count).sum::<u64>(), 3);
+    }
+
+    fn make_exposure_control() -> ControlDescriptor {
+        ControlDescriptor {
+            id: "exposure".to_string(),
+            name: "Exposure".to_string(),
+            control_type: ControlType::Slider,
+            group: "exposure".to_string(),
+            min: Some(-11),
+            max: Some(-2),
+            step: Some(1),
+            default: Some(-6),
+            default_auto: false,
+            current: -6,
+            flags: ControlFlags {
+                supports_auto: true,
+                is_auto_enabled: true,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    #[test]
+    fn apply_saved_settings_restores_mode_before_value() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_exposure_control(),
+        ]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "exposure", -8);
+        store.set_control("test-device", "Camera", "brightness", 200);
+
+        // Exposure is the slowest write, but its mode still goes out first
+        let latency = ControlLatencyState::default();
+        latency.tracker.lock().record(
+            "test-device",
+            "exposure",
+            std::time::Duration::from_millis(300),
+        );
+
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        assert_eq!(applied.len(), 2);
+        assert_eq!(
+            *backend.writes.lock().unwrap(),
+            [
+                Write::Mode("exposure".to_string(), false),
+                Write::Value("exposure".to_string(), -8),
+                Write::Value("brightness".to_string(), 200),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_saved_settings_restores_only_the_flag_for_auto_controls() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_exposure_control(),
+        ]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control_auto("test-device", "Camera", "exposure", true, -5);
+
+        let latency = ControlLatencyState::default();
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        assert_eq!(applied.len(), 2);
+        assert_eq!(
+            *backend.writes.lock().unwrap(),
+            [
+                Write::Mode("exposure".to_string(), true),
+                Write::Value("brightness".to_string(), 200),
+            ]
+        );
+    }
+
+    /// Settings file written before modes were persisted.
+    const LEGACY_SETTINGS: &str = r#"{
+        "cameras": {
+            "test-device": {
+                "name": "Camera",
+                "controls": { "brightness": 200, "exposure": -8 }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn apply_saved_settings_treats_legacy_values_as_manual() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        std::fs::write(&path, LEGACY_SETTINGS).unwrap();
+        let store = SettingsStore::new(path);
+
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_exposure_control(),
+        ]);
+        let latency = ControlLatencyState::default();
+        let applied = apply_saved_settings(&backend, &store, &latency, "test-device").applied;
+        assert_eq!(applied.len(), 2);
+
+        // Exposure comes back manual rather than at the camera's auto default
+        let writes = backend.writes.lock().unwrap();
+        let mode = writes
+            .iter()
+            .position(|w| *w == Write::Mode("exposure".to_string(), false))
+            .expect("exposure mode restored");
+        let value = writes
+            .iter()
+            .position(|w| *w == Write::Value("exposure".to_string(), -8))
+            .expect("exposure value restored");
+        assert!(mode < value);
+        assert!(writes.contains(&Write::Value("brightness".to_string(), 200)));
+    }
+
+    #[test]
+    fn write_tracked_values_saves_source_of_written_controls_only() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ])
+        .with_failing_controls(vec!["contrast".to_string()]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "contrast", 80);
+
+        let latency = ControlLatencyState::default();
+        let source = ControlSource::Preset("desk".to_string());
+        let written = write_tracked_values(
+            &backend,
+            &store,
+            &latency,
+            "test-device",
+            "Camera",
+            &[
+                ("brightness".to_string(), 150),
+                ("contrast".to_string(), 60),
+            ],
+            &source,
+        );
+        assert_eq!(written, [("brightness".to_string(), 150)]);
+
+        let cam = store.get_camera("test-device").unwrap();
+        assert_eq!(cam.controls["brightness"].value, 150);
+        assert_eq!(cam.controls["brightness"].source, source);
+        // The failed write leaves the manual value alone
+        assert_eq!(cam.controls["contrast"].value, 80);
+        assert_eq!(cam.controls["contrast"].source, ControlSource::Manual);
+    }
+
+    #[test]
+    fn applying_a_preset_skips_controls_the_camera_lacks() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ])
+        .with_failing_controls(vec!["contrast".to_string()]);
+        let (store, _dir) = temp_store();
+        let preset = Preset {
+            name: "Other model".to_string(),
+            controls: [
+                ("brightness".to_string(), 300),
+                ("contrast".to_string(), 60),
+                ("zoom".to_string(), 200),
+            ]
+            .into(),
+            ..Preset::default()
+        };
+        store.save_preset("other", preset);
+        let latency = ControlLatencyState::default();
+
+        let result =
+            apply_preset(&backend, &store, &latency, "test-device", "other", "Camera").unwrap();
+        assert_eq!(result.applied, ["brightness"]);
+        // Missing on this camera, and refused by it
+        assert_eq!(result.skipped, ["contrast", "zoom"]);
+        // Clamped to this camera's range
+        assert_eq!(
+            *backend.set_calls.lock().unwrap(),
+            [("test-device".to_string(), "brightness".to_string(), 255)]
+        );
+
+        let err = apply_preset(&backend, &store, &latency, "test-device", "gone", "Camera");
+        assert_eq!(err.unwrap_err().code, code::NOT_FOUND);
+    }
+
+    #[test]
+    fn saving_a_preset_refuses_a_name_another_preset_has() {
+        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
+        let (store, _dir) = temp_store();
+
+        let desk = save_preset(&backend, &store, "test-device", "desk", "Desk", false).unwrap();
+        assert_eq!(desk.controls["brightness"], 128);
+
+        let err = save_preset(&backend, &store, "test-device", "desk-2", "DESK", false);
+        assert_eq!(
+            err.unwrap_err(),
+            AppError::new(
+                code::INVALID_ARGUMENT,
+                "A preset named 'DESK' already exists"
+            )
+        );
+        assert!(store.preset("desk-2").is_none());
+
+        // Saving over the same ID keeps its name
+        assert!(save_preset(&backend, &store, "test-device", "desk", "Desk", true).is_ok());
+        assert!(store.preset("desk").unwrap().normalised);
+    }
+
+    #[test]
+    fn presets_for_a_device_leave_out_ones_it_cannot_take() {
+        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
+        let (store, _dir) = temp_store();
+        let preset = |id: &str| Preset {
+            name: id.to_string(),
+            controls: [(id.to_string(), 1)].into(),
+            ..Preset::default()
+        };
+        store.save_preset("zoom", preset("zoom"));
+        store.save_preset("brightness", preset("brightness"));
+
+        let presets = presets_for_device(&backend, &store, "test-device").unwrap();
+        let ids: Vec<_> = presets.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["brightness"]);
+
+        let json = serde_json::to_value(&presets[0]).unwrap();
+        assert_eq!(json["id"], "brightness");
+        assert_eq!(json["name"], "brightness");
+        assert_eq!(json["controls"]["brightness"], 1);
+    }
+
+    #[test]
+    fn reverting_drift_writes_only_drifted_controls() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ]);
+        let (store, _dir) = temp_store();
+        let preset = Preset {
+            name: "Desk".to_string(),
+            controls: [
+                ("brightness".to_string(), 150),
+                ("contrast".to_string(), 60),
+            ]
+            .into(),
+            ..Preset::default()
+        };
+        store.save_preset("desk", preset);
+        let latency = ControlLatencyState::default();
+
+        let result = apply_preset(&backend, &store, &latency, "test-device", "desk", "Camera");
+        assert_eq!(result.unwrap().applied, ["brightness", "contrast"]);
+        assert!(preset_drift(&backend, &store, "test-device")
+            .unwrap()
+            .is_empty());
+
+        store.set_control("test-device", "Camera", "contrast", 90);
+        let drift = preset_drift(&backend, &store, "test-device").unwrap();
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].preset_value, 60);
+        assert_eq!(drift[0].current.as_ref().map(|c| c.value), Some(90));
+
+        backend.set_calls.lock().unwrap().clear();
+        let reverted = revert_drift(&backend, &store, &latency, "test-device").unwrap();
+        assert_eq!(reverted, drift);
+
+        let calls = backend.set_calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].1.as_str(), calls[0].2), ("contrast", 60));
+        let saved = store.get_camera("test-device").unwrap();
+        assert_eq!(saved.controls["contrast"].source, ControlSource::Restore);
+        assert_eq!(
+            saved.controls["brightness"].source,
+            ControlSource::Preset("desk".to_string())
+        );
+        drop(calls);
+        assert!(preset_drift(&backend, &store, "test-device")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn applied_preset_explains_what_is_missing() {
+        let (store, _dir) = temp_store();
+        let err = applied_preset(&store, "test-device").unwrap_err();
+        assert_eq!(
+            err,
+            AppError::new(code::NOT_FOUND, "No settings saved for this camera")
+        );
+
+        store.set_control("test-device", "Camera", "brightness", 100);
+        let err = applied_preset(&store, "test-device").unwrap_err();
+        assert_eq!(
+            err,
+            AppError::new(code::NOT_FOUND, "No preset has been applied to this camera")
+        );
+
+        store.set_applied_preset("test-device", "Camera", "gone");
+        let err = applied_preset(&store, "test-device").unwrap_err();
+        assert_eq!(
+            err,
+            AppError::new(code::NOT_FOUND, "Preset 'gone' no longer exists")
+        );
+    }
+
+    // --- reset_to_defaults tests (Step 6) ---
+    // These test the logic directly using the mock backend, not through Tauri IPC
+
+    #[test]
+    fn reset_to_defaults_sets_all_controls_to_hardware_defaults() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(Some(50)),
+        ]);
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        store.set_control("test-device", "Camera", "contrast", 80);
+
+        // Directly test the reset logic
+        let id = DeviceId::new("test-device");
+        let descriptors = backend.get_controls(&id).unwrap();
+
+        let mut reset_values = Vec::new();
+        for desc in &descriptors {
+            if let Some(default_val) = desc.default {
+                let control = ControlId::from_str_id(&desc.id).unwrap();
+                let clamped = ControlValue::new(default_val, desc.min, desc.max);
+                backend.set_control(&id, &control, clamped).unwrap();
+                reset_values.push(ResetResult {
+                    control_id: desc.id.clone(),
+                    value: default_val,
+                    auto: false,
+                });
+            }
+        }
+        store.remove_camera("test-device");
+
+        assert_eq!(reset_values.len(), 2);
+        let calls = backend.set_calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        // Verify default values were applied
+        assert!(calls.iter().any(|(_, c, v)| c == "brightness" && *v == 128));
+        assert!(calls.iter().any(|(_, c, v)| c == "contrast" && *v == 50));
+        // Verify settings cleared
+        assert!(store.get_camera("test-device").is_none());
+    }
+
+    #[test]
+    fn reset_to_defaults_returns_error_for_unknown_device() {
+        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
+        let id = DeviceId::new("nonexistent");
+        let result = backend.get_controls(&id);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reset_to_defaults_skips_controls_without_defaults() {
+        let backend = MockBackend::new(vec![
+            make_brightness_control(Some(128)),
+            make_contrast_control(None), // no default
+        ]);
+
+        let id = DeviceId::new("test-device");
+        let descriptors = backend.get_controls(&id).unwrap();
+
+        let mut reset_count = 0;
+        for desc in &descriptors {
+            if let Some(default_val) = desc.default {
+                let control = ControlId::from_str_id(&desc.id).unwrap();
+                let clamped = ControlValue::new(default_val, desc.min, desc.max);
+                backend.set_control(&id, &control, clamped).unwrap();
+                reset_count += 1;
+            }
+        }
+
+        assert_eq!(reset_count, 1); // Only brightness has a default
+    }
+
+    #[test]
+    fn reset_to_defaults_clears_saved_settings_for_device() {
+        let (store, _dir) = temp_store();
+        store.set_control("test-device", "Camera", "brightness", 200);
+        assert!(store.get_camera("test-device").is_some());
+
+        store.remove_camera("test-device");
+        assert!(store.get_camera("test-device").is_none());
+    }
+
+    fn exposure_with(supports_auto: bool, default_auto: bool) -> ControlDescriptor {
+        ControlDescriptor {
+            default_auto,
+            current: -8,
+            flags: ControlFlags {
+                supports_auto,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            ..make_exposure_control()
+        }
+    }
+
+    fn reset_exposure(supports_auto: bool, default_auto: bool) -> (Vec<ResetResult>, Vec<Write>) {
+        let backend = MockBackend::new(vec![exposure_with(supports_auto, default_auto)]);
+        let latency = ControlLatencyState::default();
+        let results = reset_controls(&backend, &latency, "test-device").unwrap();
+        let writes = backend.writes.lock().unwrap().clone();
+        (results, writes)
+    }
+
+    #[test]
+    fn reset_re_enables_auto_when_auto_is_the_supported_default() {
+        let (results, writes) = reset_exposure(true, true);
+        assert_eq!(writes, [Write::Mode("exposure".to_string(), true)]);
+        assert_eq!(
+            results,
+            [ResetResult {
+                control_id: "exposure".to_string(),
+                value: -8,
+                auto: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn reset_writes_the_default_when_auto_is_supported_but_not_the_default() {
+        let (results, writes) = reset_exposure(true, false);
+        assert_eq!(writes, [Write::Value("exposure".to_string(), -6)]);
+        assert!(!results[0].auto);
+        assert_eq!(results[0].value, -6);
+    }
+
+    #[test]
+    fn reset_writes_the_default_when_auto_is_the_default_but_unsupported() {
+        let (results, writes) = reset_exposure(false, true);
+        assert_eq!(writes, [Write::Value("exposure".to_string(), -6)]);
+        assert!(!results[0].auto);
+    }
+
+    #[test]
+    fn reset_writes_the_default_when_auto_is_neither_supported_nor_the_default() {
+        let (results, writes) = reset_exposure(false, false);
+        assert_eq!(writes, [Write::Value("exposure".to_string(), -6)]);
+        assert!(!results[0].auto);
+    }
+
+    #[test]
+    fn reset_writes_defaults_of_controls_without_a_control_id() {
+        let backend = MockBackend::new(vec![make_style_control(Some(2))]);
+        let latency = ControlLatencyState::default();
+        let results = reset_controls(&backend, &latency, "test-device").unwrap();
+        assert_eq!(
+            *backend.writes.lock().unwrap(),
+            [Write::Value("style".to_string(), 2)]
+        );
+        assert_eq!(results[0].control_id, "style");
+    }
+
+    #[test]
+    fn reset_restores_auto_even_without_a_numeric_default() {
+        let backend = MockBackend::new(vec![ControlDescriptor {
+            default: None,
+            ..exposure_with(true, true)
+        }]);
+        let latency = ControlLatencyState::default();
+        let results = reset_controls(&backend, &latency, "test-device").unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].auto);
+    }
+}
@@ -1,72 +1,57 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 use crate::camera::backend::CameraBackend;
 use crate::camera::commands::CameraState;
-use crate::camera::types::{ControlId, ControlValue, DeviceId};
+use crate::camera::queue::{DeviceQueue, OpKind};
+use crate::camera::types::CameraDevice;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::{code, AppError};
+use crate::settings::apply::{apply_saved_settings, preset_drift, reset_controls, revert_drift};
+use crate::settings::control_cache::unix_now;
+use crate::settings::drift::ControlDrift;
+use crate::settings::export::default_export_path;
+use crate::settings::housekeeping::{known_cameras, KnownCamera};
 use crate::settings::store::SettingsStore;
 use crate::settings::types::ResetResult;
+use crate::settings::ui_state::UiStateStore;
 
-/// Tauri-managed state wrapping the settings store.
+/// Tauri-managed state wrapping the settings and UI state stores.
 pub struct SettingsState {
     pub store: Arc<SettingsStore>,
+    pub ui_state: Arc<UiStateStore>,
 }
 
-/// Apply saved settings to a connected camera.
-///
-/// For each saved control value, looks up the descriptor (for range clamping)
-/// and calls `set_control`. Logs and skips individual failures.
-pub fn apply_saved_settings(
-    backend: &dyn CameraBackend,
-    store: &SettingsStore,
-    device_id: &str,
-) -> Vec<(String, i32)> {
-    let saved = match store.get_camera(device_id) {
-        Some(s) => s,
-        None => return vec![],
-    };
-
-    let id = DeviceId::new(device_id);
-    let descriptors = match backend.get_controls(&id) {
-        Ok(d) => d,
-        Err(e) => {
-            tracing::warn!("Failed to get controls for {device_id}: {e}");
-            return vec![];
-        }
-    };
-
-    let mut applied = Vec::new();
-
-    for (control_str, &value) in &saved.controls {
-        let control = match ControlId::from_str_id(control_str) {
-            Some(c) => c,
-            None => {
-                tracing::warn!("Skipping unknown control '{control_str}' for {device_id}");
-                continue;
-            }
-        };
-
-        let desc = match descriptors.iter().find(|d| d.id == *control_str) {
-            Some(d) => d,
-            None => {
-                tracing::warn!(
-                    "Control '{control_str}' not available on device {device_id}, skipping"
-                );
-                continue;
-            }
-        };
-
-        let clamped = ControlValue::new(value, desc.min, desc.max);
-        match backend.set_control(&id, &control, clamped) {
-            Ok(()) => applied.push((control_str.clone(), value)),
-            Err(e) => {
-                tracing::warn!("Failed to apply '{control_str}' = {value} on {device_id}: {e}");
-            }
-        }
-    }
+/// Controls whose saved value differs from the preset last applied to the
+/// camera, with both values.
+#[tauri::command]
+pub async fn get_settings_drift(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+) -> Result<Vec<ControlDrift>, AppError> {
+    preset_drift(&camera_state.backend, &settings_state.store, &device_id)
+}
 
-    applied
+/// Re-apply the preset last applied to the camera, writing only the
+/// controls that have drifted from it.
+///
+/// Returns the drift that was reverted.
+#[tauri::command]
+pub async fn revert_to_preset(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    latency_state: State<'_, ControlLatencyState>,
+    device_id: String,
+) -> Result<Vec<ControlDrift>, AppError> {
+    revert_drift(
+        &camera_state.backend,
+        &settings_state.store,
+        &latency_state,
+        &device_id,
+    )
 }
 
 /// Reset all controls to their hardware defaults and clear saved settings.
@@ -74,41 +59,11 @@ pub fn apply_saved_settings(
 pub async fn reset_to_defaults(
     camera_state: State<'_, CameraState>,
     settings_state: State<'_, SettingsState>,
+    latency_state: State<'_, ControlLatencyState>,
     device_id: String,
-) -> Result<Vec<ResetResult>, String> {
-    let id = DeviceId::new(&device_id);
-    let descriptors = camera_state
-        .backend
-        .get_controls(&id)
-        .map_err(|e| e.to_string())?;
-
-    let mut reset_values = Vec::new();
-
-    for desc in &descriptors {
-        let default_val = match desc.default {
-            Some(v) => v,
-            None => continue,
-        };
-
-        let control = match ControlId::from_str_id(&desc.id) {
-            Some(c) => c,
-            None => continue,
-        };
-
-        let clamped = ControlValue::new(default_val, desc.min, desc.max);
-        camera_state
-            .backend
-            .set_control(&id, &control, clamped)
-            .map_err(|e| e.to_string())?;
-
-        reset_values.push(ResetResult {
-            control_id: desc.id.clone(),
-            value: default_val,
-        });
-    }
-
+) -> Result<Vec<ResetResult>, AppError> {
+    let reset_values = reset_controls(&camera_state.backend, &latency_state, &device_id)?;
     settings_state.store.remove_camera(&device_id);
-
     Ok(reset_values)
 }
 
@@ -117,289 +72,214 @@ pub async fn reset_to_defaults(
 pub async fn get_saved_settings(
     settings_state: State<'_, SettingsState>,
     device_id: String,
-) -> Result<Option<crate::settings::types::CameraSettings>, String> {
+) -> Result<Option<crate::settings::types::CameraSettings>, AppError> {
     Ok(settings_state.store.get_camera(&device_id))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::camera::backend::CameraBackend;
-    use crate::camera::error::{CameraError, Result as CamResult};
-    use crate::camera::types::{
-        CameraDevice, ControlDescriptor, ControlFlags, ControlId, ControlType, ControlValue,
-        DeviceId, FormatDescriptor, HotplugEvent,
-    };
-    use crate::settings::store::SettingsStore;
-    use crate::settings::types::ResetResult;
-    use std::sync::Mutex;
-
-    /// Mock backend that tracks set_control calls for verification.
-    struct MockBackend {
-        devices: Vec<CameraDevice>,
-        controls: Vec<ControlDescriptor>,
-        set_calls: Mutex<Vec<(String, String, i32)>>,
-        /// Controls that should fail when set (control_id strings).
-        fail_controls: Vec<String>,
-    }
-
-    impl MockBackend {
-        fn new(controls: Vec<ControlDescriptor>) -> Self {
-            Self {
-                devices: vec![CameraDevice {
-                    id: DeviceId::new("test-device"),
-                    name: "Test Camera".to_string(),
-                    device_path: "test-path".to_string(),
-                    is_connected: true,
-                }],
-                controls,
-                set_calls: Mutex::new(Vec::new()),
-                fail_controls: Vec::new(),
-            }
-        }
-
-        fn with_failing_controls(mut self, fails: Vec<String>) -> Self {
-            self.fail_controls = fails;
-            self
-        }
-    }
-
-    impl CameraBackend for MockBackend {
-        fn enumerate_devices(&self) -> CamResult<Vec<CameraDevice>> {
-            Ok(self.devices.clone())
-        }
-
-        fn watch_hotplug(&self, _callback: Box<dyn Fn(HotplugEvent) + Send>) -> CamResult<()> {
-            Ok(())
-        }
-
-        fn get_controls(&self, id: &DeviceId) -> CamResult<Vec<ControlDescriptor>> {
-            if self.devices.iter().any(|d| &d.id == id) {
-                Ok(self.controls.clone())
-            } else {
-                Err(CameraError::DeviceNotFound(id.to_string()))
-            }
-        }
+fn connected_devices(camera_state: &CameraState) -> Result<Vec<CameraDevice>, AppError> {
+    camera_state
+        .backend
+        .enumerate_devices()
+        .map_err(AppError::from)
+}
 
-        fn get_control(&self, _id: &DeviceId, _control: &ControlId) -> CamResult<ControlValue> {
-            Ok(ControlValue::new(128, Some(0), Some(255)))
-        }
+/// Export every camera's settings, the presets and the app-wide options to
+/// `path`, or to a dated file beside the settings file without one. Returns
+/// the path written.
+#[tauri::command]
+pub async fn export_settings(
+    settings_state: State<'_, SettingsState>,
+    path: Option<String>,
+) -> Result<String, AppError> {
+    let store = &settings_state.store;
+    let path = match path.map(|path| path.trim().to_string()) {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => default_export_path(store.dir(), unix_now()),
+    };
+    store.export_to(&path).map_err(|e| {
+        AppError::new(
+            code::SETTINGS_IO,
+            format!("Can't export settings to {}: {e}", path.display()),
+        )
+    })?;
+    Ok(path.display().to_string())
+}
 
-        fn set_control(
-            &self,
-            id: &DeviceId,
-            control: &ControlId,
-            value: ControlValue,
-        ) -> CamResult<()> {
-            if !self.devices.iter().any(|d| &d.id == id) {
-                return Err(CameraError::DeviceNotFound(id.to_string()));
-            }
-            let control_str = control.as_id_str().to_string();
-            if self.fail_controls.contains(&control_str) {
-                return Err(CameraError::ControlWrite(format!(
-                    "simulated failure for {control_str}"
-                )));
+/// Import settings exported by `export_settings`, merging them into the
+/// current ones or replacing them, and apply them straight away to the
+/// imported cameras that are connected. A file that can't be read, isn't an
+/// export or has another version is refused without changing anything.
+///
+/// Each camera's settings are applied through its device queue, as a preset
+/// is, so they aren't written while its preview is being restarted.
+///
+/// Returns the connected cameras the settings were applied to.
+#[tauri::command]
+pub async fn import_settings(
+    app: AppHandle,
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    path: String,
+    merge: bool,
+) -> Result<Vec<String>, AppError> {
+    let store = &settings_state.store;
+    let imported = store
+        .import_from(Path::new(&path), merge)
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+    store
+        .flush()
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+
+    let connected = connected_devices(&camera_state)?;
+    let mut applied = Vec::new();
+    for device in connected
+        .iter()
+        .filter(|d| imported.iter().any(|id| id == d.id.as_str()))
+    {
+        let handle = app.clone();
+        let op_device = device.id.as_str().to_string();
+        let restored = app
+            .state::<DeviceQueue>()
+            .run(&device.id, OpKind::PresetApply, move |_| async move {
+                Ok(apply_saved_settings(
+                    &handle.state::<CameraState>().backend,
+                    &handle.state::<SettingsState>().store,
+                    &handle.state::<ControlLatencyState>(),
+                    &op_device,
+                ))
+            })
+            .await;
+        let restored = match restored {
+            Ok(restored) => restored,
+            Err(e) => {
+                tracing::warn!("Can't apply imported settings to '{}': {e}", device.name);
+                continue;
             }
-            self.set_calls.lock().unwrap().push((
-                id.as_str().to_string(),
-                control_str,
-                value.value(),
-            ));
-            Ok(())
-        }
-
-        fn get_formats(&self, _id: &DeviceId) -> CamResult<Vec<FormatDescriptor>> {
-            Ok(vec![])
-        }
-    }
-
-    fn make_brightness_control(default: Option<i32>) -> ControlDescriptor {
-        ControlDescriptor {
-            id: "brightness".to_string(),
-            name: "Brightness".to_string(),
-            control_type: ControlType::Slider,
-            group: "image".to_string(),
-            min: Some(0),
-            max: Some(255),
-            step: Some(1),
-            default,
-            current: 128,
-            flags: ControlFlags {
-                supports_auto: false,
-                is_auto_enabled: false,
-                is_read_only: false,
-            },
-            options: None,
-            supported: true,
-        }
-    }
-
-    fn make_contrast_control(default: Option<i32>) -> ControlDescriptor {
-        ControlDescriptor {
-            id: "contrast".to_string(),
-            name: "Contrast".to_string(),
-            control_type: ControlType::Slider,
-            group: "image".to_string(),
-            min: Some(0),
-            max: Some(100),
-            step: Some(1),
-            default,
-            current: 50,
-            flags: ControlFlags {
-                supports_auto: false,
-                is_auto_enabled: false,
-                is_read_only: false,
-            },
-            options: None,
-            supported: true,
-        }
-    }
-
-    fn temp_store() -> (SettingsStore, tempfile::TempDir) {
-        let dir = tempfile::TempDir::new().unwrap();
-        let path = dir.path().join("cameras.json");
-        (SettingsStore::new(path), dir)
-    }
-
-    // --- Apply saved settings tests (Step 5) ---
-
-    #[test]
-    fn apply_saved_settings_calls_set_control_for_each_saved_value() {
-        let backend = MockBackend::new(vec![
-            make_brightness_control(Some(128)),
-            make_contrast_control(Some(50)),
-        ]);
-        let (store, _dir) = temp_store();
-        store.set_control("test-device", "Camera", "brightness", 200);
-        store.set_control("test-device", "Camera", "contrast", 80);
-
-        let applied = apply_saved_settings(&backend, &store, "test-device");
-        assert_eq!(applied.len(), 2);
-
-        let calls = backend.set_calls.lock().unwrap();
-        assert_eq!(calls.len(), 2);
-    }
-
-    #[test]
-    fn apply_saved_settings_skips_unknown_controls() {
-        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
-        let (store, _dir) = temp_store();
-        store.set_control("test-device", "Camera", "brightness", 200);
-        store.set_control("test-device", "Camera", "nonexistent_control", 42);
-
-        let applied = apply_saved_settings(&backend, &store, "test-device");
-        // Only brightness should be applied, nonexistent_control skipped
-        assert_eq!(applied.len(), 1);
-        assert_eq!(applied[0].0, "brightness");
-    }
-
-    #[test]
-    fn apply_saved_settings_does_nothing_when_no_saved_settings() {
-        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
-        let (store, _dir) = temp_store();
-
-        let applied = apply_saved_settings(&backend, &store, "test-device");
-        assert!(applied.is_empty());
-        assert!(backend.set_calls.lock().unwrap().is_empty());
+        };
+        tracing::info!(
+            "Applied {} imported settings to '{}'",
+            restored.applied.len(),
+            device.name
+        );
+        applied.push(device.id.as_str().to_string());
     }
+    Ok(applied)
+}
 
-    #[test]
-    fn apply_saved_settings_continues_on_individual_control_failure() {
-        let backend = MockBackend::new(vec![
-            make_brightness_control(Some(128)),
-            make_contrast_control(Some(50)),
-        ])
-        .with_failing_controls(vec!["brightness".to_string()]);
-
-        let (store, _dir) = temp_store();
-        store.set_control("test-device", "Camera", "brightness", 200);
-        store.set_control("test-device", "Camera", "contrast", 80);
+/// Every camera with saved settings, whether it's connected, and when it
+/// was last seen. Connected cameras come first.
+#[tauri::command]
+pub async fn list_known_cameras(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+) -> Result<Vec<KnownCamera>, AppError> {
+    let connected = connected_devices(&camera_state)?;
+    Ok(known_cameras(&settings_state.store.cameras(), &connected))
+}
 
-        let applied = apply_saved_settings(&backend, &store, "test-device");
-        // brightness fails but contrast should still be applied
-        assert_eq!(applied.len(), 1);
-        assert_eq!(applied[0].0, "contrast");
+/// Forget a camera's saved settings, cached controls and synced snapshot.
+/// Refused for a connected camera unless `force` is set. Returns whether
+/// there was anything to forget.
+#[tauri::command]
+pub async fn forget_camera(
+    camera_state: State<'_, CameraState>,
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+    force: bool,
+) -> Result<bool, AppError> {
+    if !force
+        && connected_devices(&camera_state)?
+            .iter()
+            .any(|d| d.id.as_str() == device_id)
+    {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            format!("Camera {device_id} is connected; reset it instead, or force forgetting it"),
+        ));
     }
+    Ok(settings_state.store.forget_camera(&device_id).is_some())
+}
 
-    // --- reset_to_defaults tests (Step 6) ---
-    // These test the logic directly using the mock backend, not through Tauri IPC
-
-    #[test]
-    fn reset_to_defaults_sets_all_controls_to_hardware_defaults() {
-        let backend = MockBackend::new(vec![
-            make_brightness_control(Some(128)),
-            make_contrast_control(Some(50)),
-        ]);
-        let (store, _dir) = temp_store();
-        store.set_control("test-device", "Camera", "brightness", 200);
-        store.set_control("test-device", "Camera", "contrast", 80);
-
-        // Directly test the reset logic
-        let id = DeviceId::new("test-device");
-        let descriptors = backend.get_controls(&id).unwrap();
-
-        let mut reset_values = Vec::new();
-        for desc in &descriptors {
-            if let Some(default_val) = desc.default {
-                let control = ControlId::from_str_id(&desc.id).unwrap();
-                let clamped = ControlValue::new(default_val, desc.min, desc.max);
-                backend.set_control(&id, &control, clamped).unwrap();
-                reset_values.push(ResetResult {
-                    control_id: desc.id.clone(),
-                    value: default_val,
-                });
-            }
-        }
-        store.remove_camera("test-device");
-
-        assert_eq!(reset_values.len(), 2);
-        let calls = backend.set_calls.lock().unwrap();
-        assert_eq!(calls.len(), 2);
-        // Verify default values were applied
-        assert!(calls.iter().any(|(_, c, v)| c == "brightness" && *v == 128));
-        assert!(calls.iter().any(|(_, c, v)| c == "contrast" && *v == 50));
-        // Verify settings cleared
-        assert!(store.get_camera("test-device").is_none());
-    }
+/// Days a camera can go unseen before its settings are pruned at startup,
+/// if pruning is on.
+#[tauri::command]
+pub async fn get_prune_after_days(
+    settings_state: State<'_, SettingsState>,
+) -> Result<Option<u32>, AppError> {
+    Ok(settings_state.store.prune_after_days())
+}
 
-    #[test]
-    fn reset_to_defaults_returns_error_for_unknown_device() {
-        let backend = MockBackend::new(vec![make_brightness_control(Some(128))]);
-        let id = DeviceId::new("nonexistent");
-        let result = backend.get_controls(&id);
-        assert!(result.is_err());
+/// Turn pruning of unseen cameras on with a number of days, or off with
+/// `None`.
+#[tauri::command]
+pub async fn set_prune_after_days(
+    settings_state: State<'_, SettingsState>,
+    days: Option<u32>,
+) -> Result<(), AppError> {
+    if days == Some(0) {
+        return Err(AppError::new(
+            code::INVALID_ARGUMENT,
+            "days must be at least 1",
+        ));
     }
+    settings_state.store.set_prune_after_days(days);
+    Ok(())
+}
 
-    #[test]
-    fn reset_to_defaults_skips_controls_without_defaults() {
-        let backend = MockBackend::new(vec![
-            make_brightness_control(Some(128)),
-            make_contrast_control(None), // no default
-        ]);
+/// Whether IR/depth sibling devices get previews auto-started.
+#[tauri::command]
+pub async fn get_auto_start_non_primary(
+    settings_state: State<'_, SettingsState>,
+) -> Result<bool, AppError> {
+    Ok(settings_state.store.auto_start_non_primary())
+}
 
-        let id = DeviceId::new("test-device");
-        let descriptors = backend.get_controls(&id).unwrap();
+/// Opt in to (or out of) auto-starting previews for IR/depth sibling devices.
+#[tauri::command]
+pub async fn set_auto_start_non_primary(
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_state.store.set_auto_start_non_primary(enabled);
+    Ok(())
+}
 
-        let mut reset_count = 0;
-        for desc in &descriptors {
-            if let Some(default_val) = desc.default {
-                let control = ControlId::from_str_id(&desc.id).unwrap();
-                let clamped = ControlValue::new(default_val, desc.min, desc.max);
-                backend.set_control(&id, &control, clamped).unwrap();
-                reset_count += 1;
-            }
-        }
+/// Whether restore updates saved values it had to clamp or snap.
+#[tauri::command]
+pub async fn get_reconcile_saved_settings(
+    settings_state: State<'_, SettingsState>,
+) -> Result<bool, AppError> {
+    Ok(settings_state.store.reconcile_saved_settings())
+}
 
-        assert_eq!(reset_count, 1); // Only brightness has a default
-    }
+/// Turn updating of saved values restore had to clamp or snap on or off.
+/// With it off they're still reported, but kept as saved.
+#[tauri::command]
+pub async fn set_reconcile_saved_settings(
+    settings_state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    settings_state.store.set_reconcile_saved_settings(enabled);
+    Ok(())
+}
 
-    #[test]
-    fn reset_to_defaults_clears_saved_settings_for_device() {
-        let (store, _dir) = temp_store();
-        store.set_control("test-device", "Camera", "brightness", 200);
-        assert!(store.get_camera("test-device").is_some());
+/// The persisted frontend UI state document.
+#[tauri::command]
+pub async fn get_ui_state(
+    settings_state: State<'_, SettingsState>,
+) -> Result<serde_json::Value, AppError> {
+    Ok(settings_state.ui_state.get())
+}
 
-        store.remove_camera("test-device");
-        assert!(store.get_camera("test-device").is_none());
-    }
+/// Merge a JSON merge patch into the persisted UI state and return the
+/// merged document. `null` deletes a key; arrays replace wholesale.
+#[tauri::command]
+pub async fn set_ui_state(
+    settings_state: State<'_, SettingsState>,
+    patch: serde_json::Value,
+) -> Result<serde_json::Value, AppError> {
+    settings_state
+        .ui_state
+        .apply_patch(patch)
+        .map_err(AppError::with_code(code::INVALID_ARGUMENT))
 }
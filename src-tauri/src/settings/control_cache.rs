@@ -0,0 +1,433 @@
+//! Write-through cache of control descriptors.
+//!
+//! Querying controls on Windows means several slow COM calls per camera,
+//! while a device's control list and ranges almost never change. The last
+//! list fetched for each device is kept in the settings file, served straight
+//! away on the next launch, and refreshed from the camera in the background.
+
+use serde::Serialize;
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::error::Result;
+use crate::camera::types::{CameraDevice, ControlDescriptor, DeviceId};
+use crate::settings::store::SettingsStore;
+
+/// Cached lists older than this are queried again before being served.
+pub const CONTROL_CACHE_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Controls returned by `get_camera_controls`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraControls {
+    pub controls: Vec<ControlDescriptor>,
+    /// Served from the cache; a `controls-refreshed` event follows once the
+    /// camera has been queried.
+    pub cached: bool,
+}
+
+/// Payload of the `controls-refreshed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlsRefreshed {
+    pub device_id: String,
+    pub controls: Vec<ControlDescriptor>,
+    /// IDs of controls the cached list didn't have.
+    pub added: Vec<String>,
+    /// IDs of cached controls the camera no longer reports.
+    pub removed: Vec<String>,
+    /// Whether anything differs from the cached list, including ranges and
+    /// current values.
+    pub changed: bool,
+}
+
+/// Current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Controls for `id`, from the cache when possible.
+///
+/// `device` is the last enumerated entry for `id`; without it the cache
+/// can't be validated, so the camera is queried directly. Fresh lists are
+/// written through to the cache.
+pub fn lookup_controls(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    id: &DeviceId,
+    device: Option<&CameraDevice>,
+    now: u64,
+) -> Result<CameraControls> {
+    if let Some(device) = device {
+        if let Some(entry) = store.cached_controls(id.as_str()) {
+            if entry.is_valid_for(device, now, CONTROL_CACHE_MAX_AGE_SECS) {
+                return Ok(CameraControls {
+                    controls: entry.controls,
+                    cached: true,
+                });
+            }
+        }
+    }
+
+    let controls = backend.get_controls(id)?;
+    if let Some(device) = device {
+        store.cache_controls(device, controls.clone(), now);
+    }
+    Ok(CameraControls {
+        controls,
+        cached: false,
+    })
+}
+
+/// Query `device`'s controls and write them through to the cache, reporting
+/// how they differ from what was cached before.
+pub fn refresh_controls(
+    backend: &dyn CameraBackend,
+    store: &SettingsStore,
+    device: &CameraDevice,
+    now: u64,
+) -> Result<ControlsRefreshed> {
+    let controls = backend.get_controls(&device.id)?;
+    let previous = store
+        .cache_controls(device, controls.clone(), now)
+        .map(|entry| entry.controls)
+        .unwrap_or_default();
+
+    let added = missing_ids(&controls, &previous);
+    let removed = missing_ids(&previous, &controls);
+    Ok(ControlsRefreshed {
+        device_id: device.id.as_str().to_string(),
+        changed: controls != previous,
+        controls,
+        added,
+        removed,
+    })
+}
+
+/// IDs in `from` that aren't in `other`, in `from` order.
+fn missing_ids(from: &[ControlDescriptor], other: &[ControlDescriptor]) -> Vec<String> {
+    from.iter()
+        .filter(|c| !other.iter().any(|o| o.id == c.id))
+        .map(|c| c.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::error::CameraError;
+    use crate::camera::types::{
+        ControlFlags, ControlId, ControlType, ControlValue, DeviceKind, FormatDescriptor,
+        HotplugEvent,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    const NOW: u64 = 1_700_000_000;
+
+    /// Backend reporting a settable control list and counting queries.
+    struct MockBackend {
+        controls: Mutex<Vec<ControlDescriptor>>,
+        queries: AtomicUsize,
+    }
+
+    impl MockBackend {
+        fn new(controls: Vec<ControlDescriptor>) -> Self {
+            Self {
+                controls: Mutex::new(controls),
+                queries: AtomicUsize::new(0),
+            }
+        }
+
+        fn set_controls(&self, controls: Vec<ControlDescriptor>) {
+            *self.controls.lock().unwrap() = controls;
+        }
+
+        fn queries(&self) -> usize {
+            self.queries.load(Ordering::SeqCst)
+        }
+    }
+
+    impl CameraBackend for MockBackend {
+        fn enumerate_devices(&self) -> Result<Vec<CameraDevice>> {
+            Ok(vec![device()])
+        }
+
+        fn watch_hotplug(&self, _callback: Box<dyn Fn(HotplugEvent) + Send>) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_controls(&self, id: &DeviceId) -> Result<Vec<ControlDescriptor>> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            if id.as_str() != "cam-1" {
+                return Err(CameraError::DeviceNotFound(id.to_string()));
+            }
+            Ok(self.controls.lock().unwrap().clone())
+        }
+
+        fn get_control(&self, id: &DeviceId, _control: &ControlId) -> Result<ControlValue> {
+            Err(CameraError::DeviceNotFound(id.to_string()))
+        }
+
+        fn set_control(
+            &self,
+            _id: &DeviceId,
+            _control: &ControlId,
+            _value: ControlValue,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_control_auto(
+            &self,
+            _id: &DeviceId,
+            _control: &ControlId,
+            _auto: bool,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_formats(&self, _id: &DeviceId) -> Result<Vec<FormatDescriptor>> {
+            Ok(vec![])
+        }
+    }
+
+    fn temp_store() -> (SettingsStore, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+        (store, dir)
+    }
+
+    fn device() -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new("cam-1"),
+            name: "Logitech BRIO".to_string(),
+            device_path: "\\\\?\\usb#vid_046d&pid_085e".to_string(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn slider(id: &str, max: i32, current: i32) -> ControlDescriptor {
+        ControlDescriptor {
+            id: id.to_string(),
+            name: id.to_string(),
+            control_type: ControlType::Slider,
+            group: "image".to_string(),
+            min: Some(0),
+            max: Some(max),
+            step: Some(1),
+            default: Some(max / 2),
+            default_auto: false,
+            current,
+            flags: ControlFlags {
+                supports_auto: false,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    fn ids(controls: &[ControlDescriptor]) -> Vec<&str> {
+        controls.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[test]
+    fn first_lookup_queries_camera_and_fills_cache() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+
+        let result = lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        assert!(!result.cached);
+        assert_eq!(backend.queries(), 1);
+        let entry = store.cached_controls("cam-1").unwrap();
+        assert_eq!(entry.controls, result.controls);
+        assert_eq!(entry.cached_at, NOW);
+    }
+
+    #[test]
+    fn valid_cache_is_served_without_querying() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        let result =
+            lookup_controls(&backend, &store, &device().id, Some(&device()), NOW + 60).unwrap();
+
+        assert!(result.cached);
+        assert_eq!(ids(&result.controls), ["brightness"]);
+        assert_eq!(backend.queries(), 1);
+    }
+
+    #[test]
+    fn cache_survives_a_restart() {
+        let (store, dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+        store.save().unwrap();
+
+        let reopened = SettingsStore::new(dir.path().join("cameras.json"));
+        let result =
+            lookup_controls(&backend, &reopened, &device().id, Some(&device()), NOW).unwrap();
+        assert!(result.cached);
+    }
+
+    #[test]
+    fn cache_is_served_when_only_the_path_spelling_changes() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        let mut replugged = device();
+        replugged.device_path = r"\\?\USB#VID_046D&PID_085E\global".to_string();
+        let result =
+            lookup_controls(&backend, &store, &replugged.id, Some(&replugged), NOW).unwrap();
+        assert!(result.cached);
+        assert_eq!(backend.queries(), 1);
+    }
+
+    #[test]
+    fn cache_is_invalidated_when_name_or_path_changes() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        let mut renamed = device();
+        renamed.name = "Logitech BRIO 4K".to_string();
+        let result = lookup_controls(&backend, &store, &renamed.id, Some(&renamed), NOW).unwrap();
+        assert!(!result.cached);
+
+        let mut moved = renamed.clone();
+        moved.device_path = "\\\\?\\usb#vid_046d&pid_085e&mi_02".to_string();
+        let result = lookup_controls(&backend, &store, &moved.id, Some(&moved), NOW).unwrap();
+        assert!(!result.cached);
+        assert_eq!(backend.queries(), 3);
+    }
+
+    #[test]
+    fn cache_expires_after_max_age() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        let at_limit = NOW + CONTROL_CACHE_MAX_AGE_SECS;
+        let result =
+            lookup_controls(&backend, &store, &device().id, Some(&device()), at_limit).unwrap();
+        assert!(result.cached);
+
+        let expired = at_limit + 1;
+        let result =
+            lookup_controls(&backend, &store, &device().id, Some(&device()), expired).unwrap();
+        assert!(!result.cached);
+        assert_eq!(store.cached_controls("cam-1").unwrap().cached_at, expired);
+    }
+
+    #[test]
+    fn unknown_device_bypasses_cache() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        let result = lookup_controls(&backend, &store, &device().id, None, NOW).unwrap();
+        assert!(!result.cached);
+        assert_eq!(backend.queries(), 2);
+    }
+
+    #[test]
+    fn query_errors_leave_cache_untouched() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![]);
+        let mut missing = device();
+        missing.id = DeviceId::new("cam-2");
+
+        assert!(lookup_controls(&backend, &store, &missing.id, Some(&missing), NOW).is_err());
+        assert!(store.cached_controls("cam-2").is_none());
+    }
+
+    #[test]
+    fn refresh_reports_unchanged_list() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        let refreshed = refresh_controls(&backend, &store, &device(), NOW + 5).unwrap();
+
+        assert!(!refreshed.changed);
+        assert!(refreshed.added.is_empty() && refreshed.removed.is_empty());
+        assert_eq!(store.cached_controls("cam-1").unwrap().cached_at, NOW + 5);
+    }
+
+    #[test]
+    fn refresh_reports_added_and_removed_controls_and_updates_cache() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![
+            slider("brightness", 255, 128),
+            slider("contrast", 255, 128),
+        ]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        // Firmware update swapped contrast for sharpness
+        backend.set_controls(vec![
+            slider("brightness", 255, 128),
+            slider("sharpness", 7, 3),
+        ]);
+        let refreshed = refresh_controls(&backend, &store, &device(), NOW + 5).unwrap();
+
+        assert!(refreshed.changed);
+        assert_eq!(refreshed.device_id, "cam-1");
+        assert_eq!(refreshed.added, ["sharpness"]);
+        assert_eq!(refreshed.removed, ["contrast"]);
+        assert_eq!(
+            ids(&store.cached_controls("cam-1").unwrap().controls),
+            ["brightness", "sharpness"]
+        );
+    }
+
+    #[test]
+    fn refresh_flags_range_and_value_changes() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+        lookup_controls(&backend, &store, &device().id, Some(&device()), NOW).unwrap();
+
+        backend.set_controls(vec![slider("brightness", 100, 40)]);
+        let refreshed = refresh_controls(&backend, &store, &device(), NOW).unwrap();
+
+        assert!(refreshed.changed);
+        assert!(refreshed.added.is_empty() && refreshed.removed.is_empty());
+        assert_eq!(refreshed.controls[0].max, Some(100));
+    }
+
+    #[test]
+    fn refresh_without_cache_reports_everything_added() {
+        let (store, _dir) = temp_store();
+        let backend = MockBackend::new(vec![slider("brightness", 255, 128)]);
+
+        let refreshed = refresh_controls(&backend, &store, &device(), NOW).unwrap();
+
+        assert!(refreshed.changed);
+        assert_eq!(refreshed.added, ["brightness"]);
+    }
+
+    #[test]
+    fn refreshed_payload_uses_camel_case() {
+        let refreshed = ControlsRefreshed {
+            device_id: "cam-1".to_string(),
+            controls: vec![],
+            added: vec![],
+            removed: vec![],
+            changed: false,
+        };
+        let json = serde_json::to_value(&refreshed).unwrap();
+        assert_eq!(json["deviceId"], "cam-1");
+        assert!(json.get("device_id").is_none());
+    }
+}
@@ -0,0 +1,212 @@
+//! Drift between a camera's saved controls and the preset last applied.
+//!
+//! Applying a preset saves each control it writes with a preset source;
+//! tweaking a slider afterwards saves over it as a manual value. A control
+//! has drifted when its saved value no longer matches what the preset would
+//! write today. The preset is resolved against the camera's current
+//! descriptors, so controls added to the preset since it was applied count
+//! as drift, controls removed from it don't, and controls the camera no
+//! longer supports are left out.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::camera::types::ControlDescriptor;
+use crate::preset::types::Preset;
+use crate::settings::types::SavedControl;
+
+/// A control whose saved value differs from the applied preset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlDrift {
+    pub control_id: String,
+    /// Value the preset writes, in the camera's native scale.
+    pub preset_value: i32,
+    /// What's saved now, or `None` if the control was never saved.
+    pub current: Option<SavedControl>,
+}
+
+/// Controls in `saved` that no longer match `preset` on a camera described
+/// by `descriptors`, sorted by control id.
+///
+/// A control saved in auto mode has drifted, since the preset sets a fixed
+/// value.
+pub fn settings_drift(
+    preset: &Preset,
+    descriptors: &[ControlDescriptor],
+    saved: &HashMap<String, SavedControl>,
+) -> Vec<ControlDrift> {
+    preset
+        .resolve(descriptors)
+        .into_iter()
+        .filter_map(|(control_id, preset_value)| {
+            let current = saved.get(&control_id);
+            let matches = current.is_some_and(|c| !c.auto && c.value == preset_value);
+            (!matches).then(|| ControlDrift {
+                current: current.cloned(),
+                control_id,
+                preset_value,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{ControlFlags, ControlType};
+    use crate::settings::types::ControlSource;
+
+    fn slider(id: &str, min: i32, max: i32) -> ControlDescriptor {
+        ControlDescriptor {
+            id: id.to_string(),
+            name: id.to_string(),
+            control_type: ControlType::Slider,
+            group: "test".to_string(),
+            min: Some(min),
+            max: Some(max),
+            step: Some(1),
+            default: Some(min),
+            default_auto: false,
+            current: min,
+            flags: ControlFlags {
+                supports_auto: false,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    fn camera() -> Vec<ControlDescriptor> {
+        vec![
+            slider("brightness", 0, 255),
+            slider("contrast", 0, 100),
+            slider("saturation", 0, 100),
+        ]
+    }
+
+    fn preset(controls: &[(&str, i32)]) -> Preset {
+        Preset {
+            name: "Desk".to_string(),
+            controls: controls
+                .iter()
+                .map(|(id, v)| (id.to_string(), *v))
+                .collect(),
+            ..Preset::default()
+        }
+    }
+
+    fn from_preset(value: i32) -> SavedControl {
+        SavedControl {
+            value,
+            source: ControlSource::Preset("desk".to_string()),
+            changed_at: 100,
+            ..SavedControl::default()
+        }
+    }
+
+    fn saved(controls: &[(&str, SavedControl)]) -> HashMap<String, SavedControl> {
+        controls
+            .iter()
+            .map(|(id, c)| (id.to_string(), c.clone()))
+            .collect()
+    }
+
+    fn ids(drift: &[ControlDrift]) -> Vec<&str> {
+        drift.iter().map(|d| d.control_id.as_str()).collect()
+    }
+
+    #[test]
+    fn untouched_preset_has_no_drift() {
+        let preset = preset(&[("brightness", 150), ("contrast", 60)]);
+        let saved = saved(&[
+            ("brightness", from_preset(150)),
+            ("contrast", from_preset(60)),
+        ]);
+        assert!(settings_drift(&preset, &camera(), &saved).is_empty());
+    }
+
+    #[test]
+    fn manual_tweaks_are_reported_with_both_values() {
+        let preset = preset(&[("brightness", 150), ("contrast", 60)]);
+        let saved = saved(&[
+            ("brightness", SavedControl::manual(170)),
+            ("contrast", from_preset(60)),
+        ]);
+
+        let drift = settings_drift(&preset, &camera(), &saved);
+        assert_eq!(
+            drift,
+            [ControlDrift {
+                control_id: "brightness".to_string(),
+                preset_value: 150,
+                current: Some(SavedControl::manual(170)),
+            }]
+        );
+    }
+
+    #[test]
+    fn tweak_back_to_the_preset_value_is_not_drift() {
+        let preset = preset(&[("brightness", 150)]);
+        let saved = saved(&[("brightness", SavedControl::manual(150))]);
+        assert!(settings_drift(&preset, &camera(), &saved).is_empty());
+    }
+
+    #[test]
+    fn control_switched_to_auto_has_drifted() {
+        let preset = preset(&[("brightness", 150)]);
+        let auto = SavedControl {
+            auto: true,
+            ..from_preset(150)
+        };
+        let drift = settings_drift(&preset, &camera(), &saved(&[("brightness", auto)]));
+        assert_eq!(ids(&drift), ["brightness"]);
+    }
+
+    #[test]
+    fn control_added_to_preset_since_applying_has_drifted() {
+        let preset = preset(&[("brightness", 150), ("saturation", 40)]);
+        let saved = saved(&[("brightness", from_preset(150))]);
+
+        let drift = settings_drift(&preset, &camera(), &saved);
+        assert_eq!(ids(&drift), ["saturation"]);
+        assert_eq!(drift[0].preset_value, 40);
+        assert_eq!(drift[0].current, None);
+    }
+
+    #[test]
+    fn control_removed_from_preset_since_applying_is_ignored() {
+        let preset = preset(&[("brightness", 150)]);
+        let saved = saved(&[
+            ("brightness", from_preset(150)),
+            ("contrast", SavedControl::manual(90)),
+        ]);
+        assert!(settings_drift(&preset, &camera(), &saved).is_empty());
+    }
+
+    #[test]
+    fn controls_the_camera_no_longer_supports_are_ignored() {
+        let preset = preset(&[("brightness", 150), ("contrast", 60), ("zoom", 200)]);
+        let saved = saved(&[
+            ("brightness", SavedControl::manual(10)),
+            ("contrast", SavedControl::manual(10)),
+            ("zoom", SavedControl::manual(100)),
+        ]);
+        let mut descriptors = camera();
+        descriptors[1].supported = false;
+
+        let drift = settings_drift(&preset, &descriptors, &saved);
+        assert_eq!(ids(&drift), ["brightness"]);
+    }
+
+    #[test]
+    fn preset_values_are_compared_after_clamping() {
+        // Saved contrast was clamped to the camera's 0..100 when applied
+        let preset = preset(&[("contrast", 180)]);
+        let saved = saved(&[("contrast", from_preset(100))]);
+        assert!(settings_drift(&preset, &camera(), &saved).is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! Exporting settings to a file and importing them again, to back them up or
+//! move them to another machine.
+//!
+//! An export is the settings file wrapped with a schema version, so a file
+//! written by a newer version of the app is refused rather than half-read.
+//! Entries that only make sense on the machine that wrote them (cached
+//! control descriptors, sync state and the control API token) are left out
+//! of an export and kept as they are on import.
+//!
+//! Importing either replaces the settings outright or merges the file's
+//! cameras, presets, schedules, guards and scenes into the current ones,
+//! the file winning for entries both have. A merge leaves the app-wide
+//! options alone.
+
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::types::SettingsFile;
+
+/// Schema version written to exports, and the only one imports accept.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Folder beside the settings file that exports go in when no path is given.
+pub const EXPORT_DIR: &str = "exports";
+
+/// An exported settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsExport {
+    pub version: u32,
+    /// When the export was written, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub exported_at: u64,
+    pub settings: SettingsFile,
+}
+
+impl SettingsExport {
+    /// An export of `settings` stamped `now`, without this machine's own
+    /// entries.
+    pub fn new(settings: &SettingsFile, now: u64) -> Self {
+        let mut settings = settings.clone();
+        take_machine_local(&mut settings);
+        Self {
+            version: EXPORT_VERSION,
+            exported_at: now,
+            settings,
+        }
+    }
+}
+
+/// Where to export to at `now` when no path is given:
+/// `exports/cameras-export-<date>.json` under `dir`, numbered when that day
+/// already has one.
+pub fn default_export_path(dir: &Path, now: u64) -> PathBuf {
+    let date = DateTime::from_timestamp(now as i64, 0)
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| now.to_string());
+    let dir = dir.join(EXPORT_DIR);
+    let mut path = dir.join(format!("cameras-export-{date}.json"));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("cameras-export-{date}-{n}.json"));
+        n += 1;
+    }
+    path
+}
+
+/// Read an export's settings from `contents`, checking its version first.
+pub fn parse_export(contents: &str) -> Result<SettingsFile, String> {
+    let value: serde_json::Value = serde_json::from_str(contents)
+        .map_err(|e| format!("Not a settings export, it isn't valid JSON: {e}"))?;
+    let Some(version) = value.get("version") else {
+        return Err("Not a settings export, it has no version".to_string());
+    };
+    let Some(version) = version.as_u64() else {
+        return Err(format!(
+            "Not a settings export, its version {version} isn't a number"
+        ));
+    };
+    if version != u64::from(EXPORT_VERSION) {
+        return Err(format!(
+            "Settings export version {version} isn't supported, only version {EXPORT_VERSION} \
+             is"
+        ));
+    }
+    let export: SettingsExport = serde_json::from_value(value)
+        .map_err(|e| format!("Settings export version {version} is malformed: {e}"))?;
+    Ok(export.settings)
+}
+
+/// Bring `imported` settings into `current`, merging or replacing them.
+/// This machine's own entries are kept either way.
+pub fn import_into(current: &mut SettingsFile, mut imported: SettingsFile, merge: bool) {
+    if !merge {
+        imported.control_cache = std::mem::take(&mut current.control_cache);
+        imported.sync_dir = current.sync_dir.take();
+        imported.sync_machine_id = current.sync_machine_id.take();
+        imported.sync_snapshots = std::mem::take(&mut current.sync_snapshots);
+        imported.control_api_token = current.control_api_token.take();
+        *current = imported;
+        return;
+    }
+    current.cameras.extend(imported.cameras);
+    current.presets.extend(imported.presets);
+    current.schedules.extend(imported.schedules);
+    current.control_guards.extend(imported.control_guards);
+    for scene in imported.scenes {
+        match current
+            .scenes
+            .iter_mut()
+            .find(|existing| existing.name.eq_ignore_ascii_case(&scene.name))
+        {
+            Some(existing) => *existing = scene,
+            None => current.scenes.push(scene),
+        }
+    }
+}
+
+/// Clear the entries that only apply to the machine that wrote them.
+fn take_machine_local(settings: &mut SettingsFile) {
+    settings.control_cache.clear();
+    settings.sync_dir = None;
+    settings.sync_machine_id = None;
+    settings.sync_snapshots.clear();
+    settings.control_api_token = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(json: serde_json::Value) -> SettingsFile {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn local() -> SettingsFile {
+        settings(serde_json::json!({
+            "cameras": {
+                "cam-1": {"name": "Desk", "controls": {"brightness": 100}},
+                "cam-2": {"name": "Overhead", "controls": {"zoom": 120}},
+            },
+            "presets": {"day": {"name": "Day", "controls": {"brightness": 140}}},
+            "scenes": [{"name": "Interview", "cameras": []}],
+            "tally_auto": true,
+            "sync_dir": "C:/Sync",
+            "sync_machine_id": "machine-a",
+            "control_api_token": "secret",
+        }))
+    }
+
+    fn incoming() -> SettingsFile {
+        settings(serde_json::json!({
+            "cameras": {
+                "cam-2": {"name": "Overhead", "controls": {"zoom": 200}},
+                "cam-3": {"name": "Wide", "controls": {"focus": 30}},
+            },
+            "presets": {"night": {"name": "Night", "controls": {"brightness": 60}}},
+            "scenes": [
+                {"name": "interview", "cameras": [{"deviceId": "cam-3", "actions": []}]},
+                {"name": "Outro", "cameras": []},
+            ],
+        }))
+    }
+
+    #[test]
+    fn exports_leave_out_machine_local_entries() {
+        let export = SettingsExport::new(&local(), 1_700_000_000);
+        assert_eq!(export.version, EXPORT_VERSION);
+        assert_eq!(export.exported_at, 1_700_000_000);
+        assert_eq!(export.settings.cameras.len(), 2);
+        assert!(export.settings.tally_auto);
+        assert_eq!(export.settings.sync_dir, None);
+        assert_eq!(export.settings.sync_machine_id, None);
+        assert_eq!(export.settings.control_api_token, None);
+    }
+
+    #[test]
+    fn default_export_paths_are_dated_and_numbered() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // 2023-11-14
+        let first = default_export_path(dir.path(), 1_700_000_000);
+        assert_eq!(
+            first,
+            dir.path()
+                .join(EXPORT_DIR)
+                .join("cameras-export-2023-11-14.json")
+        );
+        std::fs::create_dir_all(first.parent().unwrap()).unwrap();
+        std::fs::write(&first, "{}").unwrap();
+        assert_eq!(
+            default_export_path(dir.path(), 1_700_000_000),
+            dir.path()
+                .join(EXPORT_DIR)
+                .join("cameras-export-2023-11-14-2.json")
+        );
+    }
+
+    #[test]
+    fn exports_parse_back_to_their_settings() {
+        let export = SettingsExport::new(&local(), 0);
+        let json = serde_json::to_string_pretty(&export).unwrap();
+        assert_eq!(parse_export(&json).unwrap(), export.settings);
+    }
+
+    #[test]
+    fn malformed_exports_are_rejected() {
+        let not_json = parse_export("{\"version\": 1, \"settings\": {").unwrap_err();
+        assert!(not_json.contains("isn't valid JSON"), "{not_json}");
+
+        let unversioned = parse_export(r#"{"cameras": {}}"#).unwrap_err();
+        assert!(unversioned.contains("has no version"), "{unversioned}");
+
+        let not_a_number = parse_export(r#"{"version": "one"}"#).unwrap_err();
+        assert!(not_a_number.contains("isn't a number"), "{not_a_number}");
+
+        let missing = parse_export(r#"{"version": 1}"#).unwrap_err();
+        assert!(missing.contains("malformed"), "{missing}");
+
+        let wrong_shape =
+            parse_export(r#"{"version": 1, "settings": {"cameras": []}}"#).unwrap_err();
+        assert!(wrong_shape.contains("malformed"), "{wrong_shape}");
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let err = parse_export(r#"{"version": 2, "settings": {"cameras": {}}}"#).unwrap_err();
+        assert!(err.contains("version 2 isn't supported"), "{err}");
+    }
+
+    #[test]
+    fn replacing_drops_what_the_file_does_not_have() {
+        let mut current = local();
+        import_into(&mut current, incoming(), false);
+
+        let mut ids: Vec<_> = current.cameras.keys().cloned().collect();
+        ids.sort();
+        assert_eq!(ids, ["cam-2", "cam-3"]);
+        assert_eq!(current.cameras["cam-2"].controls["zoom"].value, 200);
+        assert_eq!(current.presets.keys().collect::<Vec<_>>(), ["night"]);
+        assert_eq!(current.scenes.len(), 2);
+        assert!(!current.tally_auto);
+        // This machine's own entries survive
+        assert_eq!(current.sync_dir.as_deref(), Some("C:/Sync"));
+        assert_eq!(current.sync_machine_id.as_deref(), Some("machine-a"));
+        assert_eq!(current.control_api_token.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn merging_keeps_what_the_file_does_not_have() {
+        let mut current = local();
+        import_into(&mut current, incoming(), true);
+
+        let mut ids: Vec<_> = current.cameras.keys().cloned().collect();
+        ids.sort();
+        assert_eq!(ids, ["cam-1", "cam-2", "cam-3"]);
+        assert_eq!(current.cameras["cam-1"].controls["brightness"].value, 100);
+        // The file wins for cameras both have
+        assert_eq!(current.cameras["cam-2"].controls["zoom"].value, 200);
+        let mut presets: Vec<_> = current.presets.keys().cloned().collect();
+        presets.sort();
+        assert_eq!(presets, ["day", "night"]);
+        // Scenes are matched by name, ignoring case
+        let scenes: Vec<_> = current.scenes.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(scenes, ["interview", "Outro"]);
+        assert_eq!(current.scenes[0].cameras.len(), 1);
+        // App-wide options stay as they were
+        assert!(current.tally_auto);
+        assert_eq!(current.sync_dir.as_deref(), Some("C:/Sync"));
+    }
+}
@@ -0,0 +1,375 @@
+//! Control guards — ceilings that auto modes aren't allowed to exceed.
+//!
+//! A guard names a control and a maximum. While the control is in auto mode
+//! and reads above the maximum, the monitor switches it to manual at the
+//! maximum; if the guard has a cool-down, auto mode is handed back once it
+//! has passed, and the guard watches again. A guard that has switched a
+//! control to manual lets go without restoring auto if anyone else changes
+//! the control meanwhile — auto turned back on, a new manual value, or the
+//! control losing its auto mode — so it never fights the user.
+//!
+//! Everything here is pure and takes the time as an argument; the polling
+//! and the control writes live in `guard_monitor`.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::types::ControlDescriptor;
+
+/// A ceiling on one of a camera's controls, as stored in the settings file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlGuard {
+    pub control_id: String,
+    /// Highest value auto mode may take the control to, in native units.
+    pub max_value: i32,
+    /// Seconds to hold the control at the ceiling before handing it back
+    /// to auto mode. Without one, it stays manual until changed by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u32>,
+}
+
+impl ControlGuard {
+    fn cooldown(&self) -> Option<Duration> {
+        self.cooldown_secs.map(|secs| Duration::from_secs(secs.into()))
+    }
+}
+
+/// A guarded control as the camera last reported it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardReading {
+    pub value: i32,
+    pub auto: bool,
+    pub supports_auto: bool,
+    pub min: Option<i32>,
+    pub step: Option<i32>,
+}
+
+impl From<&ControlDescriptor> for GuardReading {
+    fn from(desc: &ControlDescriptor) -> Self {
+        Self {
+            value: desc.current,
+            auto: desc.flags.is_auto_enabled,
+            supports_auto: desc.flags.supports_auto,
+            min: desc.min,
+            step: desc.step,
+        }
+    }
+}
+
+/// What the monitor has to do to a guarded control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardAction {
+    /// Switch to manual and write `to`, the ceiling on the control's step
+    /// grid. `from` is the value auto mode had reached.
+    Clamp { from: i32, to: i32 },
+    /// The cool-down has passed: switch back to auto.
+    Restore,
+}
+
+/// The ceiling snapped down onto the control's step grid, so writing it
+/// can't land above the maximum. Never below the control's minimum.
+fn ceiling(max_value: i32, reading: &GuardReading) -> i32 {
+    let Some(min) = reading.min else {
+        return max_value;
+    };
+    let step = reading.step.unwrap_or(1).max(1);
+    if max_value <= min {
+        return min;
+    }
+    let steps = (i64::from(max_value) - i64::from(min)) / i64::from(step);
+    (i64::from(min) + steps * i64::from(step)) as i32
+}
+
+/// A control the guard has switched to manual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Held {
+    since: Instant,
+    value: i32,
+}
+
+/// Which guarded controls are being held at their ceiling, by device.
+#[derive(Debug, Default)]
+pub struct GuardTracker {
+    held: HashMap<(String, String), Held>,
+}
+
+impl GuardTracker {
+    /// Evaluate `guard` on `device_id` against the latest `reading`, or
+    /// `None` when the camera no longer has the control.
+    pub fn evaluate(
+        &mut self,
+        device_id: &str,
+        guard: &ControlGuard,
+        reading: Option<GuardReading>,
+        now: Instant,
+    ) -> Option<GuardAction> {
+        let key = (device_id.to_string(), guard.control_id.clone());
+        let Some(reading) = reading else {
+            self.held.remove(&key);
+            return None;
+        };
+
+        if let Some(held) = self.held.get(&key).copied() {
+            // Anything but our own manual value means someone else took
+            // over; a control that lost auto mode can't be handed back.
+            if reading.auto || !reading.supports_auto || reading.value != held.value {
+                self.held.remove(&key);
+            } else if guard
+                .cooldown()
+                .is_some_and(|cooldown| now.duration_since(held.since) >= cooldown)
+            {
+                self.held.remove(&key);
+                return Some(GuardAction::Restore);
+            } else {
+                return None;
+            }
+        }
+
+        if !(reading.supports_auto && reading.auto && reading.value > guard.max_value) {
+            return None;
+        }
+        let to = ceiling(guard.max_value, &reading);
+        self.held.insert(key, Held { since: now, value: to });
+        Some(GuardAction::Clamp {
+            from: reading.value,
+            to,
+        })
+    }
+
+    /// Whether the guard on `control_id` is holding it at its ceiling.
+    pub fn is_holding(&self, device_id: &str, control_id: &str) -> bool {
+        self.held
+            .contains_key(&(device_id.to_string(), control_id.to_string()))
+    }
+
+    /// Forget a held control, e.g. because its guard was removed. Auto mode
+    /// isn't restored.
+    pub fn forget(&mut self, device_id: &str, control_id: &str) {
+        self.held
+            .remove(&(device_id.to_string(), control_id.to_string()));
+    }
+
+    /// Forget every control held on a device that has gone away.
+    pub fn forget_device(&mut self, device_id: &str) {
+        self.held.retain(|(device, _), _| device != device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAIN: &str = "gain";
+
+    fn guard(max_value: i32, cooldown_secs: Option<u32>) -> ControlGuard {
+        ControlGuard {
+            control_id: GAIN.to_string(),
+            max_value,
+            cooldown_secs,
+        }
+    }
+
+    fn auto(value: i32) -> Option<GuardReading> {
+        Some(GuardReading {
+            value,
+            auto: true,
+            supports_auto: true,
+            min: Some(0),
+            step: Some(1),
+        })
+    }
+
+    fn manual(value: i32) -> Option<GuardReading> {
+        auto(value).map(|r| GuardReading { auto: false, ..r })
+    }
+
+    /// A fake clock: the instant `secs` after a fixed start.
+    fn clock() -> impl Fn(u64) -> Instant {
+        let start = Instant::now();
+        move |secs| start + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn auto_mode_over_the_ceiling_is_clamped_to_it() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let guard = guard(100, None);
+
+        assert_eq!(tracker.evaluate("cam", &guard, auto(80), at(0)), None);
+        assert_eq!(tracker.evaluate("cam", &guard, auto(100), at(1)), None);
+        assert_eq!(
+            tracker.evaluate("cam", &guard, auto(180), at(2)),
+            Some(GuardAction::Clamp { from: 180, to: 100 })
+        );
+        assert!(tracker.is_holding("cam", GAIN));
+    }
+
+    #[test]
+    fn manual_values_over_the_ceiling_are_left_alone() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        assert_eq!(
+            tracker.evaluate("cam", &guard(100, None), manual(200), at(0)),
+            None
+        );
+        assert!(!tracker.is_holding("cam", GAIN));
+    }
+
+    #[test]
+    fn flapping_around_the_ceiling_clamps_once_per_cooldown() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let guard = guard(100, Some(30));
+
+        // Auto mode wobbling either side of the ceiling
+        assert_eq!(tracker.evaluate("cam", &guard, auto(99), at(0)), None);
+        assert_eq!(
+            tracker.evaluate("cam", &guard, auto(101), at(2)),
+            Some(GuardAction::Clamp { from: 101, to: 100 })
+        );
+        // Held at the ceiling in manual until the cool-down passes
+        for secs in [4, 10, 20, 31] {
+            assert_eq!(
+                tracker.evaluate("cam", &guard, manual(100), at(secs)),
+                None
+            );
+        }
+        assert_eq!(
+            tracker.evaluate("cam", &guard, manual(100), at(32)),
+            Some(GuardAction::Restore)
+        );
+        assert!(!tracker.is_holding("cam", GAIN));
+
+        // Back in auto: under the ceiling is fine, over it clamps again
+        assert_eq!(tracker.evaluate("cam", &guard, auto(99), at(34)), None);
+        assert_eq!(tracker.evaluate("cam", &guard, auto(100), at(36)), None);
+        assert_eq!(
+            tracker.evaluate("cam", &guard, auto(102), at(38)),
+            Some(GuardAction::Clamp { from: 102, to: 100 })
+        );
+    }
+
+    #[test]
+    fn without_a_cooldown_the_control_stays_manual() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let guard = guard(100, None);
+        tracker.evaluate("cam", &guard, auto(150), at(0));
+        assert_eq!(
+            tracker.evaluate("cam", &guard, manual(100), at(86_400)),
+            None
+        );
+        assert!(tracker.is_holding("cam", GAIN));
+    }
+
+    #[test]
+    fn user_changes_while_held_release_the_guard_without_restoring() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let guard = guard(100, Some(10));
+
+        // A new manual value
+        tracker.evaluate("cam", &guard, auto(150), at(0));
+        assert_eq!(tracker.evaluate("cam", &guard, manual(60), at(20)), None);
+        assert!(!tracker.is_holding("cam", GAIN));
+
+        // Auto turned back on by hand, still over the ceiling: clamped anew
+        tracker.evaluate("cam", &guard, auto(150), at(30));
+        assert_eq!(
+            tracker.evaluate("cam", &guard, auto(140), at(31)),
+            Some(GuardAction::Clamp { from: 140, to: 100 })
+        );
+    }
+
+    #[test]
+    fn a_control_losing_auto_support_is_let_go() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let guard = guard(100, Some(10));
+        let no_auto = |value| {
+            manual(value).map(|r| GuardReading {
+                supports_auto: false,
+                ..r
+            })
+        };
+
+        tracker.evaluate("cam", &guard, auto(150), at(0));
+        // Past the cool-down, but there's no auto mode to hand back to
+        assert_eq!(tracker.evaluate("cam", &guard, no_auto(100), at(20)), None);
+        assert!(!tracker.is_holding("cam", GAIN));
+        // Nor is a control without auto mode ever clamped
+        assert_eq!(tracker.evaluate("cam", &guard, no_auto(500), at(21)), None);
+
+        // A control that disappears entirely is forgotten too
+        tracker.evaluate("cam", &guard, auto(150), at(30));
+        assert_eq!(tracker.evaluate("cam", &guard, None, at(31)), None);
+        assert!(!tracker.is_holding("cam", GAIN));
+    }
+
+    #[test]
+    fn the_ceiling_snaps_down_onto_the_step_grid() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let stepped = |value| {
+            auto(value).map(|r| GuardReading {
+                min: Some(4),
+                step: Some(8),
+                ..r
+            })
+        };
+        // On the grid 4 + 8n, 100 is a step and 99 rounds down to 92
+        assert_eq!(
+            tracker.evaluate("cam", &guard(100, None), stepped(180), at(0)),
+            Some(GuardAction::Clamp { from: 180, to: 100 })
+        );
+        assert_eq!(
+            tracker.evaluate("cam", &guard(99, None), stepped(180), at(0)),
+            Some(GuardAction::Clamp { from: 180, to: 92 })
+        );
+        // Below the minimum, the minimum is as low as it goes
+        assert_eq!(
+            tracker.evaluate("cam2", &guard(-10, None), stepped(20), at(0)),
+            Some(GuardAction::Clamp { from: 20, to: 4 })
+        );
+    }
+
+    #[test]
+    fn devices_and_controls_are_tracked_separately() {
+        let at = clock();
+        let mut tracker = GuardTracker::default();
+        let gain = guard(100, None);
+        let exposure = ControlGuard {
+            control_id: "exposure".to_string(),
+            ..guard(-5, None)
+        };
+
+        tracker.evaluate("a", &gain, auto(150), at(0));
+        tracker.evaluate("b", &gain, auto(150), at(0));
+        tracker.evaluate("a", &exposure, auto(-2), at(0));
+        assert!(tracker.is_holding("a", GAIN) && tracker.is_holding("b", GAIN));
+
+        tracker.forget("a", "exposure");
+        assert!(!tracker.is_holding("a", "exposure"));
+        tracker.forget_device("a");
+        assert!(!tracker.is_holding("a", GAIN));
+        assert!(tracker.is_holding("b", GAIN));
+    }
+
+    #[test]
+    fn guards_serialise_in_camel_case_without_an_unset_cooldown() {
+        let json = serde_json::to_value(guard(100, None)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "controlId": "gain", "maxValue": 100 })
+        );
+        let parsed: ControlGuard =
+            serde_json::from_value(serde_json::json!({
+                "controlId": "gain", "maxValue": 80, "cooldownSecs": 60
+            }))
+            .unwrap();
+        assert_eq!(parsed, guard(80, Some(60)));
+    }
+}
@@ -0,0 +1,195 @@
+//! Background task that enforces control guards.
+//!
+//! Cameras with guards have their controls read every couple of seconds,
+//! and straight away when a guard changes. A guarded control that auto mode
+//! has taken over its ceiling is switched to manual at the ceiling, and
+//! handed back to auto after the guard's cool-down. These writes are
+//! temporary, so unlike a user's changes they aren't saved to the camera's
+//! settings.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::camera::backend::CameraBackend;
+use crate::camera::commands::CameraState;
+use crate::camera::types::{ControlValue, DeviceId};
+use crate::error::AppError;
+use crate::settings::apply::parse_control_id;
+use crate::settings::commands::SettingsState;
+use crate::settings::guard::{ControlGuard, GuardAction, GuardReading, GuardTracker};
+
+/// How often guarded controls are read when nothing wakes the monitor.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Tauri-managed state shared with the guard monitor task.
+#[derive(Default)]
+pub struct GuardMonitorState {
+    tracker: Mutex<GuardTracker>,
+    wake: Notify,
+}
+
+impl GuardMonitorState {
+    /// Read guarded controls now instead of at the next poll.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+/// Payload emitted via the `guard-triggered` Tauri event when a guard
+/// switches a control to manual at its ceiling.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardTriggered {
+    pub device_id: String,
+    pub control_id: String,
+    /// The value auto mode had taken the control to.
+    pub before: i32,
+    /// The ceiling it was set to.
+    pub after: i32,
+}
+
+/// Enforce guards until the app exits. Spawn once the settings, camera and
+/// guard monitor state are managed.
+pub async fn run_guard_monitor(app: AppHandle) {
+    loop {
+        let handle = app.clone();
+        if let Err(e) = tauri::async_runtime::spawn_blocking(move || check_guards(&handle)).await {
+            tracing::warn!("Control guard check failed: {e}");
+        }
+        let monitor = app.state::<GuardMonitorState>();
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = monitor.wake.notified() => {}
+        }
+    }
+}
+
+/// Read each guarded camera's controls once and act on what the guards
+/// decide. A camera that can't be read (usually disconnected) has its held
+/// controls forgotten.
+fn check_guards(app: &AppHandle) {
+    let store = Arc::clone(&app.state::<SettingsState>().store);
+    let camera_state = app.state::<CameraState>();
+    let backend = &camera_state.backend;
+    let monitor = app.state::<GuardMonitorState>();
+
+    for (device_id, guards) in store.all_control_guards() {
+        let id = DeviceId::new(&device_id);
+        let descriptors = match backend.get_controls(&id) {
+            Ok(descriptors) => descriptors,
+            Err(e) => {
+                tracing::debug!("Control guards skipped for {device_id}: {e}");
+                monitor.tracker.lock().forget_device(&device_id);
+                continue;
+            }
+        };
+        let now = Instant::now();
+        for guard in &guards {
+            let reading = descriptors
+                .iter()
+                .find(|d| d.id == guard.control_id)
+                .map(GuardReading::from);
+            let action = monitor
+                .tracker
+                .lock()
+                .evaluate(&device_id, guard, reading, now);
+            if let Some(action) = action {
+                act(app, backend, &id, guard, action);
+            }
+        }
+    }
+}
+
+/// Carry out a guard's decision on the camera.
+fn act(
+    app: &AppHandle,
+    backend: &dyn CameraBackend,
+    id: &DeviceId,
+    guard: &ControlGuard,
+    action: GuardAction,
+) {
+    let Ok(control) = parse_control_id(&guard.control_id) else {
+        return;
+    };
+    match action {
+        GuardAction::Clamp { from, to } => {
+            let result = backend
+                .set_control_auto(id, &control, false)
+                .and_then(|()| {
+                    backend.set_control(id, &control, ControlValue::new(to, None, None))
+                });
+            match result {
+                Ok(()) => {
+                    tracing::info!(
+                        "Guard held {} on {id} at {to} (auto mode reached {from})",
+                        guard.control_id
+                    );
+                    let payload = GuardTriggered {
+                        device_id: id.to_string(),
+                        control_id: guard.control_id.clone(),
+                        before: from,
+                        after: to,
+                    };
+                    if let Err(e) = app.emit("guard-triggered", &payload) {
+                        tracing::warn!("Failed to emit guard-triggered event: {e}");
+                    }
+                }
+                Err(e) => tracing::warn!("Guard couldn't hold {} on {id}: {e}", guard.control_id),
+            }
+        }
+        GuardAction::Restore => match backend.set_control_auto(id, &control, true) {
+            Ok(()) => tracing::info!(
+                "Guard cool-down over, {} on {id} back in auto mode",
+                guard.control_id
+            ),
+            Err(e) => tracing::warn!(
+                "Guard couldn't restore auto mode for {} on {id}: {e}",
+                guard.control_id
+            ),
+        },
+    }
+}
+
+/// A camera's control guards.
+#[tauri::command]
+pub async fn get_control_guards(
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+) -> Result<Vec<ControlGuard>, AppError> {
+    Ok(settings_state.store.control_guards(&device_id))
+}
+
+/// Guard a control, replacing any guard already on it, and check it
+/// straight away.
+#[tauri::command]
+pub async fn set_control_guard(
+    settings_state: State<'_, SettingsState>,
+    monitor: State<'_, GuardMonitorState>,
+    device_id: String,
+    guard: ControlGuard,
+) -> Result<(), AppError> {
+    parse_control_id(&guard.control_id)?;
+    settings_state.store.set_control_guard(&device_id, guard);
+    monitor.wake();
+    Ok(())
+}
+
+/// Remove the guard on a control. A control the guard is holding at its
+/// ceiling stays in manual mode. Returns whether there was a guard.
+#[tauri::command]
+pub async fn remove_control_guard(
+    settings_state: State<'_, SettingsState>,
+    monitor: State<'_, GuardMonitorState>,
+    device_id: String,
+    control_id: String,
+) -> Result<bool, AppError> {
+    monitor.tracker.lock().forget(&device_id, &control_id);
+    Ok(settings_state
+        .store
+        .remove_control_guard(&device_id, &control_id))
+}
@@ -0,0 +1,386 @@
+//! Housekeeping of saved cameras.
+//!
+//! Every device ever plugged in keeps an entry in the settings file. Each
+//! entry records when its camera was last enumerated, so the UI can list
+//! them all with their status, and cameras unseen for a set number of days
+//! can be pruned at startup. Pruned entries are written to a dated archive
+//! beside the settings file first, so they can be restored by hand.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::types::CameraDevice;
+use crate::settings::persist::write_json_atomic;
+use crate::settings::store::SettingsStore;
+use crate::settings::types::CameraSettings;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Folder beside the settings file that pruned entries are archived in.
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// A camera with saved settings, as listed by `list_known_cameras`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownCamera {
+    pub device_id: String,
+    pub name: String,
+    pub connected: bool,
+    /// Unix time (seconds) the camera was last enumerated, if recorded.
+    pub last_seen: Option<u64>,
+    pub saved_controls: usize,
+}
+
+/// Every saved camera: connected ones first, then the most recently seen,
+/// then by name.
+pub fn known_cameras(
+    cameras: &HashMap<String, CameraSettings>,
+    connected: &[CameraDevice],
+) -> Vec<KnownCamera> {
+    let mut known: Vec<KnownCamera> = cameras
+        .iter()
+        .map(|(device_id, camera)| KnownCamera {
+            device_id: device_id.clone(),
+            name: camera.name.clone(),
+            connected: is_connected(device_id, connected),
+            last_seen: (camera.last_seen != 0).then_some(camera.last_seen),
+            saved_controls: camera.controls.len(),
+        })
+        .collect();
+    known.sort_by(|a, b| {
+        b.connected
+            .cmp(&a.connected)
+            .then(b.last_seen.cmp(&a.last_seen))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.device_id.cmp(&b.device_id))
+    });
+    known
+}
+
+fn is_connected(device_id: &str, connected: &[CameraDevice]) -> bool {
+    connected.iter().any(|d| d.id.as_str() == device_id)
+}
+
+/// Device IDs of the saved cameras last seen more than `days` days before
+/// `now`, sorted. Connected cameras and ones never marked seen are kept.
+pub fn select_unseen(
+    cameras: &HashMap<String, CameraSettings>,
+    connected: &[CameraDevice],
+    now: u64,
+    days: u32,
+) -> Vec<String> {
+    let max_age = u64::from(days) * SECS_PER_DAY;
+    let mut unseen: Vec<String> = cameras
+        .iter()
+        .filter(|(device_id, camera)| {
+            camera.last_seen != 0
+                && now.saturating_sub(camera.last_seen) > max_age
+                && !is_connected(device_id, connected)
+        })
+        .map(|(device_id, _)| device_id.clone())
+        .collect();
+    unseen.sort();
+    unseen
+}
+
+/// Contents of an archive file: the pruned cameras' entries, as they were
+/// in the settings file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PruneArchive {
+    /// Unix time (seconds) of the prune.
+    pub pruned_at: u64,
+    /// The age limit the cameras were pruned at.
+    pub after_days: u32,
+    pub cameras: HashMap<String, CameraSettings>,
+}
+
+/// Where to archive a prune made at `now`: `archive/cameras-pruned-<date>.json`
+/// under `dir`, numbered when that day already has one.
+pub fn archive_path(dir: &Path, now: u64) -> PathBuf {
+    let date = DateTime::from_timestamp(now as i64, 0)
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| now.to_string());
+    let dir = dir.join(ARCHIVE_DIR);
+    let mut path = dir.join(format!("cameras-pruned-{date}.json"));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("cameras-pruned-{date}-{n}.json"));
+        n += 1;
+    }
+    path
+}
+
+/// Write `archive` to a new file under `dir`, returning its path.
+pub fn write_archive(dir: &Path, archive: &PruneArchive) -> Result<PathBuf, String> {
+    let path = archive_path(dir, archive.pruned_at);
+    write_json_atomic(&path, archive)
+        .map_err(|e| format!("Can't archive pruned cameras to {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Outcome of a prune.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    /// Device IDs removed, sorted.
+    pub pruned: Vec<String>,
+    /// Archive file they were written to.
+    pub archive: PathBuf,
+}
+
+/// Prune the saved cameras unseen for the configured number of days, if
+/// pruning is on. Cameras never marked seen are stamped with `now` first,
+/// so they age out from here. The archive is written before anything is
+/// removed; if it can't be, nothing is.
+pub fn prune_unseen(
+    store: &SettingsStore,
+    connected: &[CameraDevice],
+    now: u64,
+) -> Result<Option<PruneReport>, String> {
+    let Some(days) = store.prune_after_days() else {
+        return Ok(None);
+    };
+    store.stamp_unrecorded(now);
+    let mut cameras = store.cameras();
+    let pruned = select_unseen(&cameras, connected, now, days);
+    if pruned.is_empty() {
+        return Ok(None);
+    }
+    cameras.retain(|device_id, _| pruned.contains(device_id));
+
+    let archive = write_archive(
+        store.dir(),
+        &PruneArchive {
+            pruned_at: now,
+            after_days: days,
+            cameras,
+        },
+    )?;
+    for device_id in &pruned {
+        store.forget_camera(device_id);
+    }
+    Ok(Some(PruneReport { pruned, archive }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{DeviceId, DeviceKind};
+    use crate::settings::persist::load_json;
+    use crate::settings::types::SavedControl;
+    use tempfile::TempDir;
+
+    /// 2026-10-18 12:00 UTC.
+    const NOW: u64 = 1_792_324_800;
+
+    fn days_ago(days: u64) -> u64 {
+        NOW - days * SECS_PER_DAY
+    }
+
+    fn device(id: &str) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: id.to_string(),
+            device_path: String::new(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn camera(name: &str, last_seen: u64) -> CameraSettings {
+        CameraSettings {
+            name: name.to_string(),
+            last_seen,
+            ..CameraSettings::default()
+        }
+    }
+
+    fn cameras(entries: &[(&str, u64)]) -> HashMap<String, CameraSettings> {
+        entries
+            .iter()
+            .map(|&(id, last_seen)| (id.to_string(), camera(id, last_seen)))
+            .collect()
+    }
+
+    fn temp_store(entries: &[(&str, u64)]) -> (SettingsStore, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+        for (id, camera) in cameras(entries) {
+            store.set_control(&id, &camera.name, "brightness", 100);
+        }
+        let devices: Vec<_> = entries.iter().map(|(id, _)| device(id)).collect();
+        for (device, &(_, last_seen)) in devices.iter().zip(entries) {
+            if last_seen != 0 {
+                store.mark_seen(std::slice::from_ref(device), last_seen);
+            }
+        }
+        (store, dir)
+    }
+
+    #[test]
+    fn unseen_cameras_are_selected_past_the_limit() {
+        let saved = cameras(&[
+            ("old", days_ago(91)),
+            ("recent", days_ago(10)),
+            ("edge", days_ago(90)),
+            ("older", days_ago(400)),
+        ]);
+        assert_eq!(
+            select_unseen(&saved, &[], NOW, 90),
+            vec!["old".to_string(), "older".to_string()]
+        );
+        assert!(select_unseen(&saved, &[], NOW, 500).is_empty());
+    }
+
+    #[test]
+    fn connected_and_unrecorded_cameras_are_never_selected() {
+        let saved = cameras(&[("plugged", days_ago(200)), ("legacy", 0)]);
+        assert!(select_unseen(&saved, &[device("plugged")], NOW, 30).is_empty());
+        assert_eq!(select_unseen(&saved, &[], NOW, 30), vec!["plugged"]);
+    }
+
+    #[test]
+    fn clocks_behind_the_last_sighting_select_nothing() {
+        let saved = cameras(&[("future", NOW + SECS_PER_DAY)]);
+        assert!(select_unseen(&saved, &[], NOW, 0).is_empty());
+    }
+
+    #[test]
+    fn known_cameras_list_connected_then_recent() {
+        let mut saved = cameras(&[
+            ("a", days_ago(5)),
+            ("b", days_ago(1)),
+            ("c", 0),
+            ("d", days_ago(30)),
+        ]);
+        saved
+            .get_mut("a")
+            .unwrap()
+            .controls
+            .insert("zoom".to_string(), SavedControl::manual(100));
+        let known = known_cameras(&saved, &[device("d")]);
+        let order: Vec<&str> = known.iter().map(|k| k.device_id.as_str()).collect();
+        assert_eq!(order, ["d", "b", "a", "c"]);
+        assert!(known[0].connected && !known[1].connected);
+        assert_eq!(known[2].saved_controls, 1);
+        assert_eq!(known[3].last_seen, None);
+    }
+
+    #[test]
+    fn marking_seen_stamps_saved_cameras_only() {
+        let (store, _dir) = temp_store(&[("saved", 0)]);
+        store.mark_seen(&[device("saved"), device("unsaved")], NOW);
+        assert_eq!(store.get_camera("saved").unwrap().last_seen, NOW);
+        assert!(store.get_camera("unsaved").is_none());
+    }
+
+    #[test]
+    fn last_seen_is_omitted_until_recorded_and_round_trips() {
+        let json = serde_json::to_value(camera("Cam", 0)).unwrap();
+        assert!(json.get("last_seen").is_none());
+
+        let seen = camera("Cam", NOW);
+        let json = serde_json::to_string(&seen).unwrap();
+        assert_eq!(serde_json::from_str::<CameraSettings>(&json).unwrap(), seen);
+    }
+
+    #[test]
+    fn archive_paths_are_dated_and_never_overwrite() {
+        let dir = TempDir::new().unwrap();
+        let first = archive_path(dir.path(), NOW);
+        assert_eq!(
+            first,
+            dir.path()
+                .join("archive")
+                .join("cameras-pruned-2026-10-18.json")
+        );
+        std::fs::create_dir_all(first.parent().unwrap()).unwrap();
+        std::fs::write(&first, "{}").unwrap();
+        assert_eq!(
+            archive_path(dir.path(), NOW),
+            dir.path()
+                .join("archive")
+                .join("cameras-pruned-2026-10-18-2.json")
+        );
+    }
+
+    #[test]
+    fn prune_archives_then_forgets_unseen_cameras() {
+        let (store, dir) = temp_store(&[
+            ("gone", days_ago(120)),
+            ("here", days_ago(120)),
+            ("recent", days_ago(3)),
+        ]);
+        store.set_prune_after_days(Some(90));
+
+        let report = prune_unseen(&store, &[device("here")], NOW)
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.pruned, vec!["gone".to_string()]);
+        assert!(store.get_camera("gone").is_none());
+        assert!(store.get_camera("here").is_some());
+        assert!(store.get_camera("recent").is_some());
+
+        assert!(report.archive.starts_with(dir.path().join("archive")));
+        let archive: PruneArchive = load_json(&report.archive).unwrap();
+        assert_eq!(archive.pruned_at, NOW);
+        assert_eq!(archive.after_days, 90);
+        assert_eq!(archive.cameras.len(), 1);
+        assert_eq!(archive.cameras["gone"].controls["brightness"].value, 100);
+        assert_eq!(archive.cameras["gone"].last_seen, days_ago(120));
+    }
+
+    #[test]
+    fn prune_does_nothing_when_off_or_nothing_is_stale() {
+        let (store, dir) = temp_store(&[("gone", days_ago(120))]);
+        assert_eq!(prune_unseen(&store, &[], NOW), Ok(None));
+        assert!(store.get_camera("gone").is_some());
+
+        store.set_prune_after_days(Some(365));
+        assert_eq!(prune_unseen(&store, &[], NOW), Ok(None));
+        assert!(!dir.path().join("archive").exists());
+    }
+
+    #[test]
+    fn unrecorded_cameras_start_ageing_at_the_first_prune() {
+        let (store, _dir) = temp_store(&[("legacy", 0)]);
+        store.set_prune_after_days(Some(30));
+
+        assert_eq!(prune_unseen(&store, &[], NOW), Ok(None));
+        assert_eq!(store.get_camera("legacy").unwrap().last_seen, NOW);
+
+        let later = NOW + 31 * SECS_PER_DAY;
+        let report = prune_unseen(&store, &[], later).unwrap().unwrap();
+        assert_eq!(report.pruned, vec!["legacy".to_string()]);
+    }
+
+    #[test]
+    fn failed_archive_writes_keep_the_cameras() {
+        let (store, dir) = temp_store(&[("gone", days_ago(120))]);
+        store.set_prune_after_days(Some(90));
+        // A file where the archive folder should be
+        std::fs::write(dir.path().join("archive"), "").unwrap();
+
+        assert!(prune_unseen(&store, &[], NOW)
+            .unwrap_err()
+            .contains("archive"));
+        assert!(store.get_camera("gone").is_some());
+    }
+
+    #[test]
+    fn forgetting_removes_the_cached_controls_too() {
+        let (store, _dir) = temp_store(&[("gone", NOW)]);
+        store.cache_controls(&device("gone"), Vec::new(), NOW);
+
+        assert_eq!(store.forget_camera("gone").unwrap().name, "gone");
+        assert!(store.get_camera("gone").is_none());
+        assert!(store.cached_controls("gone").is_none());
+        assert!(store.forget_camera("gone").is_none());
+    }
+}
@@ -1,5 +1,25 @@
 // Settings domain — persistence, auto-apply, and restore.
 
+pub mod apply;
+#[cfg(feature = "app")]
 pub mod commands;
+pub mod control_cache;
+pub mod drift;
+pub mod export;
+pub mod guard;
+#[cfg(feature = "app")]
+pub mod guard_monitor;
+pub mod housekeeping;
+pub mod persist;
+pub mod reconcile;
+pub mod rename;
+pub mod schedule;
+#[cfg(feature = "app")]
+pub mod scheduler;
 pub mod store;
+pub mod sync;
+pub mod sync_dir;
+#[cfg(feature = "app")]
+pub mod sync_watch;
 pub mod types;
+pub mod ui_state;
@@ -0,0 +1,345 @@
+// JSON persistence shared by the settings and UI-state stores: loading,
+// atomic writes and debounced saving.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use tokio::sync::Notify;
+
+use crate::settings::control_cache::unix_now;
+use crate::supervisor::RestartPolicy;
+
+/// How long a save waits for further changes before writing.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Panics after which a save loop reports itself degraded.
+pub const DEGRADED_AFTER_PANICS: u32 = 3;
+
+/// How a save loop that panicked is restarted: indefinitely, since giving
+/// up would leave every later change unsaved.
+pub const SAVE_LOOP_RESTART: RestartPolicy = RestartPolicy {
+    max_attempts: u32::MAX,
+    initial_backoff: Duration::from_millis(500),
+    max_backoff: Duration::from_secs(30),
+};
+
+/// Load a JSON file, returning the default on a missing file.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Write `value` as pretty JSON atomically (write `.tmp` then rename),
+/// creating parent directories as needed.
+pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, &json).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// `serialize_with` for maps of saved entries: an entry that fails to
+/// serialise is logged and left out, rather than failing the whole write.
+pub fn skip_unserialisable_entries<T, S>(
+    map: &HashMap<String, T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let mut out = serializer.serialize_map(None)?;
+    for (key, value) in map {
+        match serde_json::to_value(value) {
+            Ok(value) => out.serialize_entry(key, &value)?,
+            Err(e) => tracing::error!("Not saving {key:?}, it failed to serialise: {e}"),
+        }
+    }
+    out.end()
+}
+
+/// `serialize_with` for lists of saved entries, as
+/// [`skip_unserialisable_entries`] is for maps.
+pub fn skip_unserialisable_items<T, S>(items: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let mut out = serializer.serialize_seq(None)?;
+    for (i, item) in items.iter().enumerate() {
+        match serde_json::to_value(item) {
+            Ok(item) => out.serialize_element(&item)?,
+            Err(e) => tracing::error!("Not saving entry {i}, it failed to serialise: {e}"),
+        }
+    }
+    out.end()
+}
+
+/// How a store's saves are going, for the diagnostics view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveHealth {
+    /// Unix seconds of the last successful write since startup.
+    pub last_saved: Option<u64>,
+    /// Changes made since the last successful write.
+    pub unsaved_changes: u64,
+}
+
+/// Payload of the `settings-degraded` event: a store's save loop keeps
+/// panicking, so changes may not be reaching the disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveDegraded {
+    /// Which store, e.g. "settings".
+    pub store: &'static str,
+    /// Times the loop has panicked.
+    pub panics: u32,
+}
+
+/// Debounced save requests for one store.
+#[derive(Default)]
+pub struct SaveScheduler {
+    notify: Notify,
+    is_dirty: AtomicBool,
+    /// Changes requested since the last successful write.
+    unsaved: AtomicU64,
+    /// Unix seconds of the last successful write, 0 before the first.
+    last_saved: AtomicU64,
+    /// Held while writing, so a flush and the save loop never interleave.
+    writing: Mutex<()>,
+}
+
+impl SaveScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the store changed and wake the save loop.
+    pub fn request(&self) {
+        self.unsaved.fetch_add(1, Ordering::AcqRel);
+        self.is_dirty.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+
+    /// Whether a change is waiting to be saved.
+    pub fn is_pending(&self) -> bool {
+        self.is_dirty.load(Ordering::Acquire)
+    }
+
+    /// Clear the pending flag, returning whether a change was waiting.
+    pub fn take_pending(&self) -> bool {
+        self.is_dirty.swap(false, Ordering::AcqRel)
+    }
+
+    /// Saves since startup and changes not yet written.
+    pub fn health(&self) -> SaveHealth {
+        let last_saved = self.last_saved.load(Ordering::Acquire);
+        SaveHealth {
+            last_saved: (last_saved != 0).then_some(last_saved),
+            unsaved_changes: self.unsaved.load(Ordering::Acquire),
+        }
+    }
+
+    /// Call `save`, recording the changes it wrote if it succeeds.
+    fn write(&self, save: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+        let _writing = self.writing.lock();
+        let unsaved = self.unsaved.load(Ordering::Acquire);
+        save()?;
+        self.unsaved.fetch_sub(unsaved, Ordering::AcqRel);
+        self.last_saved.store(unix_now(), Ordering::Release);
+        Ok(())
+    }
+
+    /// Write any unsaved changes now, without waiting for the debounce,
+    /// for callers that must know their change reached the disk.
+    ///
+    /// If the write fails, the save loop is woken to try again.
+    pub fn flush(&self, save: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+        self.take_pending();
+        if self.unsaved.load(Ordering::Acquire) == 0 {
+            return Ok(());
+        }
+        self.write(save).inspect_err(|_| {
+            self.is_dirty.store(true, Ordering::Release);
+            self.notify.notify_one();
+        })
+    }
+
+    /// Wait for save requests, sleep 500ms, then call `save`. Never
+    /// finishes; run it from a task spawned on the async runtime, restarted
+    /// with [`SAVE_LOOP_RESTART`] if it panics.
+    ///
+    /// Uses an `AtomicBool` dirty flag to avoid losing requests that arrive
+    /// between a save completing and `notified().await` re-registering. The
+    /// inner `while` loop drains all pending changes so a request during
+    /// `save` is never lost. Changes a previous run didn't write — because
+    /// it panicked, or its last write failed — are saved straight away.
+    pub async fn run(&self, what: &str, save: impl Fn() -> Result<(), String>) {
+        if self.unsaved.load(Ordering::Acquire) > 0 {
+            self.is_dirty.store(true, Ordering::Release);
+            self.notify.notify_one();
+        }
+        loop {
+            self.notify.notified().await;
+            tokio::time::sleep(SAVE_DEBOUNCE).await;
+
+            // Drain: keep saving until no more changes arrive during save
+            while self.take_pending() {
+                if let Err(e) = self.write(&save) {
+                    tracing::warn!("Failed to save {what}: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    use crate::supervisor::run_task_with_restart;
+
+    /// An entry that can refuse to be saved.
+    #[derive(Debug)]
+    enum Entry {
+        Fine(u32),
+        Broken,
+    }
+
+    impl Serialize for Entry {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Entry::Fine(value) => serializer.serialize_u32(*value),
+                Entry::Broken => Err(serde::ser::Error::custom("refuses to serialise")),
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    struct File {
+        #[serde(serialize_with = "skip_unserialisable_entries")]
+        map: HashMap<String, Entry>,
+        #[serde(serialize_with = "skip_unserialisable_items")]
+        list: Vec<Entry>,
+    }
+
+    #[test]
+    fn an_entry_that_fails_to_serialise_is_left_out() {
+        let file = File {
+            map: HashMap::from([
+                ("good".to_string(), Entry::Fine(1)),
+                ("bad".to_string(), Entry::Broken),
+            ]),
+            list: vec![Entry::Fine(2), Entry::Broken, Entry::Fine(3)],
+        };
+        // Without the skipping, the whole file would fail
+        assert!(serde_json::to_string(&Entry::Broken).is_err());
+        assert_eq!(
+            serde_json::to_value(&file).unwrap(),
+            serde_json::json!({ "map": { "good": 1 }, "list": [2, 3] })
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file.json");
+        write_json_atomic(&path, &file).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["list"], serde_json::json!([2, 3]));
+    }
+
+    #[test]
+    fn flush_writes_unsaved_changes_and_records_health() {
+        let saves = SaveScheduler::new();
+        assert_eq!(saves.health(), SaveHealth::default());
+
+        saves.request();
+        saves.request();
+        assert_eq!(saves.health().unsaved_changes, 2);
+
+        saves.flush(|| Ok(())).unwrap();
+        let health = saves.health();
+        assert_eq!(health.unsaved_changes, 0);
+        assert!(health.last_saved.is_some());
+        assert!(!saves.is_pending());
+
+        // Nothing to write
+        saves.flush(|| panic!("nothing should be written")).unwrap();
+    }
+
+    #[test]
+    fn a_failed_flush_keeps_the_changes_for_the_save_loop() {
+        let saves = SaveScheduler::new();
+        saves.request();
+
+        assert_eq!(
+            saves.flush(|| Err("disk full".to_string())),
+            Err("disk full".to_string())
+        );
+        assert_eq!(saves.health().unsaved_changes, 1);
+        assert_eq!(saves.health().last_saved, None);
+        assert!(saves.is_pending());
+    }
+
+    #[tokio::test]
+    async fn a_restarted_save_loop_writes_what_the_panicked_one_did_not() {
+        let saves = Arc::new(SaveScheduler::new());
+        let writes = Arc::new(AtomicU32::new(0));
+
+        let loop_saves = Arc::clone(&saves);
+        let loop_writes = Arc::clone(&writes);
+        let policy = RestartPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..SAVE_LOOP_RESTART
+        };
+        let supervisor = tokio::spawn(run_task_with_restart(
+            policy,
+            move |_| {
+                let saves = Arc::clone(&loop_saves);
+                let writes = Arc::clone(&loop_writes);
+                async move {
+                    saves
+                        .run("test", || {
+                            if writes.fetch_add(1, Ordering::SeqCst) == 0 {
+                                panic!("failed to serialise");
+                            }
+                            Ok(())
+                        })
+                        .await
+                }
+            },
+            |_, _| {},
+        ));
+
+        saves.request();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while saves.health().unsaved_changes > 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("the change should be saved after the restart");
+        supervisor.abort();
+
+        assert_eq!(writes.load(Ordering::SeqCst), 2);
+        assert!(saves.health().last_saved.is_some());
+    }
+}
@@ -0,0 +1,238 @@
+//! Reconciling saved settings with what a camera accepts today.
+//!
+//! A firmware or driver update can shrink a control's range, coarsen its
+//! step or drop it altogether. Restore then writes a clamped or snapped
+//! value, or nothing, and the saved value no longer matches the camera.
+//! Each adjustment is recorded here; with reconciliation on (the default)
+//! the saved value is updated to what was actually written, so the
+//! mismatch is reported once rather than on every launch. Controls the
+//! camera no longer has are reported but kept, in case they come back.
+
+use serde::Serialize;
+
+use crate::camera::types::ControlDescriptor;
+use crate::settings::store::SettingsStore;
+use crate::settings::types::SavedControl;
+
+/// Why restore couldn't write a saved value as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReconcileReason {
+    /// Outside the range the camera advertises; clamped to it.
+    OutOfRange,
+    /// Inside the range but off the step grid; snapped onto it.
+    StepSnapped,
+    /// The camera doesn't have the control any more.
+    ControlMissing,
+}
+
+/// A saved value restore had to adjust.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reconciliation {
+    pub control_id: String,
+    pub stored: i32,
+    /// What was written instead, or `None` if nothing was.
+    pub applied: Option<i32>,
+    pub reason: ReconcileReason,
+}
+
+/// Payload emitted by the `settings-reconciled` Tauri event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsReconciled {
+    pub device_id: String,
+    pub camera_name: String,
+    pub adjustments: Vec<Reconciliation>,
+    /// Whether the saved values were updated to the applied ones.
+    pub updated: bool,
+}
+
+/// How restoring `entry` for `control_id` onto a control described by
+/// `descriptor` (or `None` if the camera lacks it) differs from the saved
+/// value, if it does.
+///
+/// Controls saved in auto mode only have their mode restored, so their
+/// value is never adjusted.
+pub fn reconcile_control(
+    control_id: &str,
+    entry: &SavedControl,
+    descriptor: Option<&ControlDescriptor>,
+) -> Option<Reconciliation> {
+    let Some(descriptor) = descriptor else {
+        return Some(Reconciliation {
+            control_id: control_id.to_string(),
+            stored: entry.value,
+            applied: None,
+            reason: ReconcileReason::ControlMissing,
+        });
+    };
+    if entry.auto {
+        return None;
+    }
+    let clamped = descriptor.clamp(entry.value);
+    if !clamped.snapped {
+        return None;
+    }
+    let out_of_range = descriptor.min.is_some_and(|min| entry.value < min)
+        || descriptor.max.is_some_and(|max| entry.value > max);
+    Some(Reconciliation {
+        control_id: control_id.to_string(),
+        stored: entry.value,
+        applied: Some(clamped.value.value()),
+        reason: if out_of_range {
+            ReconcileReason::OutOfRange
+        } else {
+            ReconcileReason::StepSnapped
+        },
+    })
+}
+
+/// Update `device_id`'s saved values to the applied ones in `adjustments`,
+/// if the user hasn't turned reconciliation off. Returns whether anything
+/// was updated.
+pub fn store_reconciled(
+    store: &SettingsStore,
+    device_id: &str,
+    adjustments: &[Reconciliation],
+) -> bool {
+    if !store.reconcile_saved_settings() {
+        return false;
+    }
+    let mut updated = false;
+    for adjustment in adjustments {
+        if let Some(applied) = adjustment.applied {
+            updated |= store.adjust_saved_value(device_id, &adjustment.control_id, applied);
+        }
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{ControlFlags, ControlType};
+    use crate::settings::types::ControlSource;
+    use tempfile::TempDir;
+
+    fn white_balance(min: i32, max: i32, step: i32) -> ControlDescriptor {
+        ControlDescriptor {
+            id: "white_balance".to_string(),
+            name: "White Balance".to_string(),
+            control_type: ControlType::Slider,
+            group: "image".to_string(),
+            min: Some(min),
+            max: Some(max),
+            step: Some(step),
+            default: Some(4600),
+            default_auto: false,
+            current: 4600,
+            flags: ControlFlags {
+                supports_auto: true,
+                is_auto_enabled: false,
+                is_read_only: false,
+            },
+            options: None,
+            supported: true,
+        }
+    }
+
+    fn saved(value: i32, auto: bool) -> SavedControl {
+        SavedControl {
+            value,
+            auto,
+            source: ControlSource::Manual,
+            changed_at: 0,
+        }
+    }
+
+    #[test]
+    fn a_shrunk_range_clamps_and_reports_out_of_range() {
+        let shrunk = white_balance(2800, 6500, 1);
+        let low = reconcile_control("white_balance", &saved(2700, false), Some(&shrunk)).unwrap();
+        assert_eq!(low.applied, Some(2800));
+        assert_eq!(low.reason, ReconcileReason::OutOfRange);
+
+        let high = reconcile_control("white_balance", &saved(7500, false), Some(&shrunk)).unwrap();
+        assert_eq!(high.stored, 7500);
+        assert_eq!(high.applied, Some(6500));
+    }
+
+    #[test]
+    fn a_grown_range_needs_no_reconciliation() {
+        let grown = white_balance(1500, 9000, 1);
+        assert_eq!(
+            reconcile_control("white_balance", &saved(2700, false), Some(&grown)),
+            None
+        );
+    }
+
+    #[test]
+    fn a_coarser_step_reports_step_snapped() {
+        let coarse = white_balance(2000, 7500, 100);
+        let snapped =
+            reconcile_control("white_balance", &saved(2740, false), Some(&coarse)).unwrap();
+        assert_eq!(snapped.applied, Some(2700));
+        assert_eq!(snapped.reason, ReconcileReason::StepSnapped);
+    }
+
+    #[test]
+    fn a_missing_control_is_reported_without_an_applied_value() {
+        let missing = reconcile_control("white_balance", &saved(2700, true), None).unwrap();
+        assert_eq!(missing.applied, None);
+        assert_eq!(missing.reason, ReconcileReason::ControlMissing);
+    }
+
+    #[test]
+    fn auto_controls_are_left_alone() {
+        let shrunk = white_balance(2800, 6500, 1);
+        assert_eq!(
+            reconcile_control("white_balance", &saved(2700, true), Some(&shrunk)),
+            None
+        );
+    }
+
+    fn store_with_white_balance(value: i32) -> (SettingsStore, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let store = SettingsStore::new(dir.path().join("cameras.json"));
+        store.set_control("dev-1", "Cam", "white_balance", value);
+        (store, dir)
+    }
+
+    fn stored_white_balance(store: &SettingsStore) -> i32 {
+        store.get_camera("dev-1").unwrap().controls["white_balance"].value
+    }
+
+    #[test]
+    fn applied_values_replace_stored_ones_by_default() {
+        let (store, _dir) = store_with_white_balance(2700);
+        let shrunk = white_balance(2800, 6500, 1);
+        let adjustments: Vec<_> = [
+            reconcile_control("white_balance", &saved(2700, false), Some(&shrunk)),
+            reconcile_control("zoom", &saved(3, false), None),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        assert!(store_reconciled(&store, "dev-1", &adjustments));
+        assert_eq!(stored_white_balance(&store), 2800);
+        assert!(!store
+            .get_camera("dev-1")
+            .unwrap()
+            .controls
+            .contains_key("zoom"));
+    }
+
+    #[test]
+    fn stored_values_are_kept_with_reconciliation_off() {
+        let (store, _dir) = store_with_white_balance(2700);
+        store.set_reconcile_saved_settings(false);
+        let shrunk = white_balance(2800, 6500, 1);
+        let adjustment =
+            reconcile_control("white_balance", &saved(2700, false), Some(&shrunk)).unwrap();
+
+        assert!(!store_reconciled(&store, "dev-1", &[adjustment]));
+        assert_eq!(stored_white_balance(&store), 2700);
+    }
+}
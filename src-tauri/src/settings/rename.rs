@@ -0,0 +1,134 @@
+//! Friendly-name refresh for cameras whose name changed under the same id.
+//!
+//! Driver updates sometimes change the friendly name Windows reports while
+//! the device path, and so the `DeviceId`, stays the same. Saved settings
+//! keep the name they were last written with, so without a refresh the
+//! tray, the placeholder card and the settings file show the old name until
+//! a control happens to be saved again.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::camera::types::CameraDevice;
+use crate::settings::types::CameraSettings;
+
+/// A saved camera whose enumerated name no longer matches the stored one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraRename {
+    pub device_id: String,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// Saved cameras in `stored` whose name differs from the one `devices`
+/// reports for the same id, sorted by device id.
+///
+/// Devices without saved settings and devices reporting an empty name are
+/// skipped, as are saved cameras that aren't enumerated right now.
+pub fn detect_renames(
+    stored: &HashMap<String, CameraSettings>,
+    devices: &[CameraDevice],
+) -> Vec<CameraRename> {
+    let mut renames: Vec<CameraRename> = devices
+        .iter()
+        .filter(|d| !d.name.is_empty())
+        .filter_map(|d| {
+            let saved = stored.get(d.id.as_str())?;
+            (saved.name != d.name).then(|| CameraRename {
+                device_id: d.id.as_str().to_string(),
+                old_name: saved.name.clone(),
+                new_name: d.name.clone(),
+            })
+        })
+        .collect();
+    renames.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+    renames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::types::{DeviceId, DeviceKind};
+
+    fn device(id: &str, name: &str) -> CameraDevice {
+        CameraDevice {
+            id: DeviceId::new(id),
+            name: name.to_string(),
+            device_path: format!("path-{id}"),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        }
+    }
+
+    fn stored(entries: &[(&str, &str)]) -> HashMap<String, CameraSettings> {
+        entries
+            .iter()
+            .map(|(id, name)| {
+                let settings = CameraSettings {
+                    name: name.to_string(),
+                    ..CameraSettings::default()
+                };
+                (id.to_string(), settings)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_a_renamed_device() {
+        let stored = stored(&[("dev-1", "USB Camera")]);
+        let renames = detect_renames(&stored, &[device("dev-1", "Logitech BRIO")]);
+        assert_eq!(
+            renames,
+            vec![CameraRename {
+                device_id: "dev-1".to_string(),
+                old_name: "USB Camera".to_string(),
+                new_name: "Logitech BRIO".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn unchanged_names_report_nothing() {
+        let stored = stored(&[("dev-1", "Logitech BRIO"), ("dev-2", "Desk Cam")]);
+        let devices = [
+            device("dev-1", "Logitech BRIO"),
+            device("dev-2", "Desk Cam"),
+        ];
+        assert!(detect_renames(&stored, &devices).is_empty());
+    }
+
+    #[test]
+    fn names_swapped_between_two_devices_follow_the_id() {
+        let stored = stored(&[("dev-1", "Left"), ("dev-2", "Right")]);
+        let devices = [device("dev-2", "Left"), device("dev-1", "Right")];
+        let renames = detect_renames(&stored, &devices);
+
+        let pairs: Vec<(&str, &str, &str)> = renames
+            .iter()
+            .map(|r| {
+                (
+                    r.device_id.as_str(),
+                    r.old_name.as_str(),
+                    r.new_name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("dev-1", "Left", "Right"), ("dev-2", "Right", "Left")]
+        );
+    }
+
+    #[test]
+    fn skips_unsaved_disconnected_and_nameless_devices() {
+        let stored = stored(&[("dev-1", "Desk Cam"), ("dev-2", "Old Name")]);
+        // dev-2 isn't enumerated, dev-3 has no saved settings, dev-1 reports no name
+        let devices = [device("dev-1", ""), device("dev-3", "New Cam")];
+        assert!(detect_renames(&stored, &devices).is_empty());
+    }
+}
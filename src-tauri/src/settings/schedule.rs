@@ -0,0 +1,525 @@
+//! Scheduled presets — which preset a camera's schedule calls for right now.
+//!
+//! A schedule is an ordered list of rules, each naming a preset, a local
+//! start time and the days it starts on. A rule stays active from its start
+//! until another rule starts, so "night at 20:00, day at 07:00" covers the
+//! whole day and night runs across midnight. When two rules start at the
+//! same moment, the one listed first wins.
+//!
+//! Start times are local wall-clock times. They are turned into instants
+//! through a `LocalZone`, so a start skipped by a forward DST jump happens
+//! when the clocks change, and one repeated by a backward jump happens the
+//! first time round only.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// Day of the week a rule starts on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        match day {
+            chrono::Weekday::Mon => Self::Monday,
+            chrono::Weekday::Tue => Self::Tuesday,
+            chrono::Weekday::Wed => Self::Wednesday,
+            chrono::Weekday::Thu => Self::Thursday,
+            chrono::Weekday::Fri => Self::Friday,
+            chrono::Weekday::Sat => Self::Saturday,
+            chrono::Weekday::Sun => Self::Sunday,
+        }
+    }
+}
+
+/// A local time of day, serialised as `"HH:MM"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    /// A time of day, or an error if `hour` or `minute` is out of range.
+    pub fn new(hour: u8, minute: u8) -> Result<Self, String> {
+        if hour > 23 || minute > 59 {
+            return Err(format!("{hour:02}:{minute:02} is not a valid time of day"));
+        }
+        Ok(Self { hour, minute })
+    }
+
+    fn to_naive(self) -> NaiveTime {
+        NaiveTime::from_hms_opt(self.hour.into(), self.minute.into(), 0)
+            .expect("hour and minute are validated")
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("'{value}' is not a time of day (expected HH:MM)");
+        let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+        if hour.is_empty() || hour.len() > 2 || minute.len() != 2 {
+            return Err(invalid());
+        }
+        let hour = hour.parse().map_err(|_| invalid())?;
+        let minute = minute.parse().map_err(|_| invalid())?;
+        Self::new(hour, minute)
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Apply `preset_id` from `start_time` on each of `days_of_week`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRule {
+    pub preset_id: String,
+    pub start_time: TimeOfDay,
+    /// Days the rule starts on. Empty means every day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days_of_week: Vec<Weekday>,
+}
+
+impl ScheduleRule {
+    fn starts_on(&self, date: NaiveDate) -> bool {
+        self.days_of_week.is_empty() || self.days_of_week.contains(&date.weekday().into())
+    }
+}
+
+/// Conversion between instants (Unix seconds) and local wall-clock time.
+pub trait LocalZone {
+    /// Wall-clock time at `instant`.
+    fn local(&self, instant: i64) -> NaiveDateTime;
+
+    /// The instant the wall clock shows `local`: the first of two in a
+    /// backward jump, and the moment of the jump for a time it skips.
+    fn instant(&self, local: NaiveDateTime) -> i64;
+}
+
+/// Any chrono time zone, such as `chrono::Local`.
+impl<Tz: TimeZone> LocalZone for Tz {
+    fn local(&self, instant: i64) -> NaiveDateTime {
+        self.timestamp_opt(instant, 0)
+            .earliest()
+            .map(|t| t.naive_local())
+            .unwrap_or_default()
+    }
+
+    fn instant(&self, local: NaiveDateTime) -> i64 {
+        if let Some(t) = self.from_local_datetime(&local).earliest() {
+            return t.timestamp();
+        }
+        // Skipped by a forward jump: find the first valid minute after it,
+        // which is when the clocks changed
+        (1..=24 * 60)
+            .find_map(|m| {
+                self.from_local_datetime(&(local + Duration::minutes(m)))
+                    .earliest()
+            })
+            .map_or_else(|| local.and_utc().timestamp(), |t| t.timestamp())
+    }
+}
+
+/// The rule a schedule has active, identified by position and preset so an
+/// edited schedule doesn't match a stale one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveRule {
+    pub index: usize,
+    pub preset_id: String,
+}
+
+/// The rule in `rules` active at `now` (Unix seconds) in `zone`: the one
+/// that started most recently, up to a week back. Ties go to the rule
+/// listed first. `None` for an empty schedule.
+pub fn active_rule(rules: &[ScheduleRule], now: i64, zone: &impl LocalZone) -> Option<ActiveRule> {
+    let today = zone.local(now).date();
+    // A rule that starts on one weekday last started up to 7 days ago
+    let mut latest: Option<(i64, usize)> = None;
+    for days_back in 0..=7 {
+        let date = today - Duration::days(days_back);
+        for (index, rule) in rules.iter().enumerate() {
+            if !rule.starts_on(date) {
+                continue;
+            }
+            let started = zone.instant(date.and_time(rule.start_time.to_naive()));
+            if started > now {
+                continue;
+            }
+            let later = match latest {
+                None => true,
+                Some((best, best_index)) => {
+                    started > best || (started == best && index < best_index)
+                }
+            };
+            if later {
+                latest = Some((started, index));
+            }
+        }
+    }
+    latest.map(|(_, index)| ActiveRule {
+        index,
+        preset_id: rules[index].preset_id.clone(),
+    })
+}
+
+/// The rule last applied to each camera, so a schedule's preset is only
+/// applied when its active rule changes rather than on every evaluation.
+#[derive(Debug, Default)]
+pub struct ScheduleTracker {
+    applied: HashMap<String, ActiveRule>,
+}
+
+impl ScheduleTracker {
+    /// Whether `active` differs from the rule last applied to `device_id`.
+    pub fn is_due(&self, device_id: &str, active: &ActiveRule) -> bool {
+        self.applied.get(device_id) != Some(active)
+    }
+
+    /// Record `active` as applied to `device_id`.
+    pub fn record(&mut self, device_id: &str, active: ActiveRule) {
+        self.applied.insert(device_id.to_string(), active);
+    }
+
+    /// Forget what was applied to `device_id`, so its active rule is applied
+    /// again on the next evaluation.
+    pub fn forget(&mut self, device_id: &str) {
+        self.applied.remove(device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveDate, Utc};
+
+    fn at(hour: u8, minute: u8) -> TimeOfDay {
+        TimeOfDay::new(hour, minute).unwrap()
+    }
+
+    fn rule(preset_id: &str, start: TimeOfDay, days: &[Weekday]) -> ScheduleRule {
+        ScheduleRule {
+            preset_id: preset_id.to_string(),
+            start_time: start,
+            days_of_week: days.to_vec(),
+        }
+    }
+
+    fn day_night() -> Vec<ScheduleRule> {
+        vec![rule("night", at(20, 0), &[]), rule("day", at(7, 0), &[])]
+    }
+
+    /// Unix seconds for a wall-clock time in `zone`.
+    fn local(zone: &impl LocalZone, date: &str, time: &str) -> i64 {
+        let local =
+            NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M").unwrap();
+        zone.instant(local)
+    }
+
+    fn active(rules: &[ScheduleRule], now: i64, zone: &impl LocalZone) -> Option<String> {
+        active_rule(rules, now, zone).map(|r| r.preset_id)
+    }
+
+    /// Central European time with 2026's transitions: clocks go forward
+    /// from 02:00 to 03:00 on 29 March and back from 03:00 to 02:00 on
+    /// 25 October, both at 01:00 UTC.
+    struct Cet;
+
+    impl Cet {
+        const SPRING: i64 = 1_774_746_000; // 2026-03-29T01:00Z
+        const AUTUMN: i64 = 1_792_890_000; // 2026-10-25T01:00Z
+
+        fn offset_at(instant: i64) -> i64 {
+            if (Self::SPRING..Self::AUTUMN).contains(&instant) {
+                2 * 3600
+            } else {
+                3600
+            }
+        }
+    }
+
+    impl LocalZone for Cet {
+        fn local(&self, instant: i64) -> NaiveDateTime {
+            let local = instant + Self::offset_at(instant);
+            chrono::DateTime::from_timestamp(local, 0)
+                .unwrap()
+                .naive_utc()
+        }
+
+        fn instant(&self, local: NaiveDateTime) -> i64 {
+            let wall = local.and_utc().timestamp();
+            // Earliest offset that round-trips, as for a repeated hour
+            [2 * 3600, 3600]
+                .into_iter()
+                .map(|offset| wall - offset)
+                .find(|&instant| instant + Self::offset_at(instant) == wall)
+                // Skipped hour: the rule starts at the jump
+                .unwrap_or(Self::SPRING)
+        }
+    }
+
+    #[test]
+    fn time_of_day_parses_and_serialises_as_hh_mm() {
+        let time: TimeOfDay = serde_json::from_str("\"07:05\"").unwrap();
+        assert_eq!(time, at(7, 5));
+        assert_eq!(serde_json::to_string(&at(20, 0)).unwrap(), "\"20:00\"");
+        assert_eq!(
+            serde_json::from_str::<TimeOfDay>("\"9:30\"").unwrap(),
+            at(9, 30)
+        );
+
+        for bad in [
+            "\"24:00\"",
+            "\"12:60\"",
+            "\"1200\"",
+            "\"12:5\"",
+            "\"ab:cd\"",
+        ] {
+            assert!(serde_json::from_str::<TimeOfDay>(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn rule_serialises_with_camel_case_and_lowercase_days() {
+        let rule = rule("night", at(20, 0), &[Weekday::Friday, Weekday::Saturday]);
+        let json = serde_json::to_value(&rule).unwrap();
+        assert_eq!(json["presetId"], "night");
+        assert_eq!(json["startTime"], "20:00");
+        assert_eq!(
+            json["daysOfWeek"],
+            serde_json::json!(["friday", "saturday"])
+        );
+
+        let every_day = ScheduleRule {
+            days_of_week: vec![],
+            ..rule
+        };
+        let json = serde_json::to_value(&every_day).unwrap();
+        assert!(json.get("daysOfWeek").is_none());
+        let restored: ScheduleRule = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, every_day);
+    }
+
+    #[test]
+    fn empty_schedule_has_no_active_rule() {
+        assert_eq!(active(&[], 1_000_000, &Utc), None);
+    }
+
+    #[test]
+    fn day_and_night_split_the_clock() {
+        let rules = day_night();
+        let cases = [
+            ("06:59", "night"),
+            ("07:00", "day"),
+            ("12:00", "day"),
+            ("19:59", "day"),
+            ("20:00", "night"),
+            ("23:59", "night"),
+            ("00:00", "night"),
+        ];
+        for (time, expected) in cases {
+            let now = local(&Utc, "2026-06-10", time);
+            assert_eq!(
+                active(&rules, now, &Utc).as_deref(),
+                Some(expected),
+                "{time}"
+            );
+        }
+    }
+
+    #[test]
+    fn night_started_yesterday_is_still_active_after_midnight() {
+        let rules = vec![rule("night", at(20, 0), &[Weekday::Friday])];
+        // Saturday 03:00 after a Friday-only night rule
+        let now = local(&Utc, "2026-06-13", "03:00");
+        assert_eq!(active(&rules, now, &Utc).as_deref(), Some("night"));
+    }
+
+    #[test]
+    fn single_weekday_rule_stays_active_all_week() {
+        let rules = vec![rule("weekly", at(9, 0), &[Weekday::Wednesday])];
+        // Wednesday 08:00: last started the Wednesday before
+        let now = local(&Utc, "2026-06-10", "08:00");
+        assert_eq!(active(&rules, now, &Utc).as_deref(), Some("weekly"));
+    }
+
+    #[test]
+    fn weekday_rules_only_start_on_their_days() {
+        let rules = vec![
+            rule("day", at(7, 0), &[]),
+            rule("weekend", at(9, 0), &[Weekday::Saturday, Weekday::Sunday]),
+        ];
+        // Wednesday 10:00
+        let now = local(&Utc, "2026-06-10", "10:00");
+        assert_eq!(active(&rules, now, &Utc).as_deref(), Some("day"));
+        // Saturday 10:00
+        let now = local(&Utc, "2026-06-13", "10:00");
+        assert_eq!(active(&rules, now, &Utc).as_deref(), Some("weekend"));
+        // Monday 06:00: the weekend rule ran on, since day hasn't started yet
+        let now = local(&Utc, "2026-06-15", "06:00");
+        assert_eq!(active(&rules, now, &Utc).as_deref(), Some("weekend"));
+    }
+
+    #[test]
+    fn rules_starting_together_go_to_the_first_listed() {
+        let rules = vec![
+            rule("first", at(8, 0), &[Weekday::Wednesday]),
+            rule("second", at(8, 0), &[]),
+        ];
+        let wednesday = local(&Utc, "2026-06-10", "09:00");
+        let active_rule = active_rule(&rules, wednesday, &Utc).unwrap();
+        assert_eq!(active_rule.index, 0);
+        assert_eq!(active_rule.preset_id, "first");
+
+        let thursday = local(&Utc, "2026-06-11", "09:00");
+        assert_eq!(active(&rules, thursday, &Utc).as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn local_offset_shifts_the_boundaries() {
+        let rules = day_night();
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        // 22:30 UTC is 07:30 the next morning in Tokyo
+        let now = local(&Utc, "2026-06-10", "22:30");
+        assert_eq!(active(&rules, now, &tokyo).as_deref(), Some("day"));
+        assert_eq!(active(&rules, now, &Utc).as_deref(), Some("night"));
+    }
+
+    #[test]
+    fn start_skipped_by_spring_forward_happens_at_the_jump() {
+        let rules = vec![rule("day", at(7, 0), &[]), rule("early", at(2, 30), &[])];
+        // The clock never shows 02:30 on 29 March; it jumps 02:00 -> 03:00
+        assert_eq!(
+            active(&rules, Cet::SPRING - 60, &Cet).as_deref(),
+            Some("day")
+        );
+        assert_eq!(active(&rules, Cet::SPRING, &Cet).as_deref(), Some("early"));
+        let now = local(&Cet, "2026-03-29", "03:30");
+        assert_eq!(active(&rules, now, &Cet).as_deref(), Some("early"));
+    }
+
+    #[test]
+    fn start_repeated_by_fall_back_happens_once() {
+        let rules = vec![rule("day", at(7, 0), &[]), rule("late", at(2, 30), &[])];
+        // 02:30 occurs twice on 25 October; the rule starts the first time
+        let first = local(&Cet, "2026-10-25", "02:30");
+        assert_eq!(active(&rules, first - 60, &Cet).as_deref(), Some("day"));
+        assert_eq!(active(&rules, first, &Cet).as_deref(), Some("late"));
+
+        // Back at 02:00 after the jump, the rule has still already started
+        let after_jump = Cet::AUTUMN;
+        assert_eq!(Cet.local(after_jump).format("%H:%M").to_string(), "02:00");
+        assert_eq!(active(&rules, after_jump, &Cet).as_deref(), Some("late"));
+        // And it isn't restarted the second time 02:30 comes round
+        let second = first + 3600;
+        let active_second = active_rule(&rules, second, &Cet).unwrap();
+        assert_eq!(active_second.preset_id, "late");
+    }
+
+    #[test]
+    fn day_night_boundaries_hold_across_dst_changes() {
+        let rules = day_night();
+        for date in [
+            "2026-03-28",
+            "2026-03-29",
+            "2026-03-30",
+            "2026-10-25",
+            "2026-10-26",
+        ] {
+            let morning = local(&Cet, date, "07:00");
+            let evening = local(&Cet, date, "20:00");
+            assert_eq!(
+                active(&rules, morning - 60, &Cet).as_deref(),
+                Some("night"),
+                "{date}"
+            );
+            assert_eq!(
+                active(&rules, morning, &Cet).as_deref(),
+                Some("day"),
+                "{date}"
+            );
+            assert_eq!(
+                active(&rules, evening - 60, &Cet).as_deref(),
+                Some("day"),
+                "{date}"
+            );
+            assert_eq!(
+                active(&rules, evening, &Cet).as_deref(),
+                Some("night"),
+                "{date}"
+            );
+        }
+    }
+
+    #[test]
+    fn chrono_zones_resolve_through_local_zone() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 10).unwrap();
+        let local = date.and_hms_opt(7, 0, 0).unwrap();
+        let instant = LocalZone::instant(&Utc, local);
+        assert_eq!(LocalZone::local(&Utc, instant), local);
+    }
+
+    #[test]
+    fn tracker_is_due_only_when_the_active_rule_changes() {
+        let mut tracker = ScheduleTracker::default();
+        let night = ActiveRule {
+            index: 0,
+            preset_id: "night".to_string(),
+        };
+        let day = ActiveRule {
+            index: 1,
+            preset_id: "day".to_string(),
+        };
+
+        assert!(tracker.is_due("dev-1", &night));
+        tracker.record("dev-1", night.clone());
+        assert!(!tracker.is_due("dev-1", &night));
+        assert!(tracker.is_due("dev-2", &night));
+        assert!(tracker.is_due("dev-1", &day));
+
+        // A reconnected camera gets its rule applied again
+        tracker.forget("dev-1");
+        assert!(tracker.is_due("dev-1", &night));
+    }
+
+    #[test]
+    fn tracker_treats_an_edited_rule_at_the_same_position_as_new() {
+        let mut tracker = ScheduleTracker::default();
+        tracker.record(
+            "dev-1",
+            ActiveRule {
+                index: 0,
+                preset_id: "night".to_string(),
+            },
+        );
+        let edited = ActiveRule {
+            index: 0,
+            preset_id: "dim".to_string(),
+        };
+        assert!(tracker.is_due("dev-1", &edited));
+    }
+}
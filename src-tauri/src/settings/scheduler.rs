@@ -0,0 +1,151 @@
+//! Background task that applies scheduled presets.
+//!
+//! Every camera's schedule is evaluated once a minute, and straight away
+//! when the scheduler is woken on startup, a reconnect or a schedule change.
+//! A preset is applied through the device queue only when the camera's
+//! active rule changes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Local;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::error::{code, AppError};
+use crate::preset::commands::queue_preset_apply;
+use crate::settings::commands::SettingsState;
+use crate::settings::schedule::{active_rule, ScheduleRule, ScheduleTracker};
+
+/// How often schedules are evaluated when nothing wakes the scheduler.
+const EVALUATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tauri-managed state shared with the scheduler task.
+#[derive(Default)]
+pub struct SchedulerState {
+    tracker: Mutex<ScheduleTracker>,
+    wake: Notify,
+}
+
+impl SchedulerState {
+    /// Evaluate schedules now instead of at the next tick. With `device_id`,
+    /// that camera's active rule is applied again even if it hasn't changed.
+    pub fn wake(&self, device_id: Option<&str>) {
+        if let Some(device_id) = device_id {
+            self.tracker.lock().forget(device_id);
+        }
+        self.wake.notify_one();
+    }
+}
+
+/// Payload emitted via the `schedule-applied` Tauri event.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleApplied {
+    pub device_id: String,
+    pub preset_id: String,
+    /// Position of the rule in the camera's schedule.
+    pub rule_index: usize,
+    pub controls_written: usize,
+}
+
+/// Evaluate schedules until the app exits. Spawn once the settings, camera
+/// and scheduler state are managed.
+pub async fn run_scheduler(app: AppHandle) {
+    loop {
+        evaluate_schedules(&app).await;
+        let scheduler = app.state::<SchedulerState>();
+        tokio::select! {
+            _ = tokio::time::sleep(EVALUATE_INTERVAL) => {}
+            _ = scheduler.wake.notified() => {}
+        }
+    }
+}
+
+/// Apply the active rule's preset to each camera whose active rule changed.
+///
+/// Failures (usually a disconnected camera) aren't recorded, so the rule is
+/// tried again on the next evaluation.
+async fn evaluate_schedules(app: &AppHandle) {
+    let store = Arc::clone(&app.state::<SettingsState>().store);
+    let now = Local::now().timestamp();
+
+    for (device_id, rules) in store.schedules() {
+        let Some(active) = active_rule(&rules, now, &Local) else {
+            continue;
+        };
+        if !app
+            .state::<SchedulerState>()
+            .tracker
+            .lock()
+            .is_due(&device_id, &active)
+        {
+            continue;
+        }
+
+        let camera_name = store
+            .get_camera(&device_id)
+            .map_or_else(|| device_id.clone(), |camera| camera.name);
+        match queue_preset_apply(app, &device_id, &active.preset_id, &camera_name).await {
+            Ok(result) => {
+                tracing::info!(
+                    "Applied scheduled preset '{}' to '{camera_name}'",
+                    active.preset_id
+                );
+                let payload = ScheduleApplied {
+                    device_id: device_id.clone(),
+                    preset_id: active.preset_id.clone(),
+                    rule_index: active.index,
+                    controls_written: result.applied.len(),
+                };
+                app.state::<SchedulerState>()
+                    .tracker
+                    .lock()
+                    .record(&device_id, active);
+                if let Err(e) = app.emit("schedule-applied", &payload) {
+                    tracing::warn!("Failed to emit schedule-applied event: {e}");
+                }
+            }
+            Err(e) => tracing::debug!(
+                "Scheduled preset '{}' not applied to '{camera_name}': {e}",
+                active.preset_id
+            ),
+        }
+    }
+}
+
+/// A camera's scheduled presets, in priority order.
+#[tauri::command]
+pub async fn get_schedule(
+    settings_state: State<'_, SettingsState>,
+    device_id: String,
+) -> Result<Vec<ScheduleRule>, AppError> {
+    Ok(settings_state.store.schedule(&device_id))
+}
+
+/// Replace a camera's schedule and apply its active rule straight away. An
+/// empty list removes the schedule.
+///
+/// Every rule must name a saved preset.
+#[tauri::command]
+pub async fn set_schedule(
+    settings_state: State<'_, SettingsState>,
+    scheduler: State<'_, SchedulerState>,
+    device_id: String,
+    rules: Vec<ScheduleRule>,
+) -> Result<(), AppError> {
+    if let Some(rule) = rules
+        .iter()
+        .find(|rule| settings_state.store.preset(&rule.preset_id).is_none())
+    {
+        return Err(AppError::new(
+            code::NOT_FOUND,
+            format!("Preset '{}' doesn't exist", rule.preset_id),
+        ));
+    }
+    settings_state.store.set_schedule(&device_id, rules);
+    scheduler.wake(Some(&device_id));
+    Ok(())
+}
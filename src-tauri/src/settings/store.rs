@@ -1,18 +1,37 @@
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use parking_lot::Mutex;
-use tokio::sync::Notify;
 
-use crate::settings::types::SettingsFile;
+use crate::camera::types::{CameraDevice, ControlDescriptor};
+use crate::input::keys::{default_bindings, KeyBinding};
+use crate::integration::midi::mapping::{upsert_mapping, MidiMapping};
+use crate::preset::types::Preset;
+use crate::preview::colour::ColourSpace;
+use crate::preview::mode::PreviewFormat;
+use crate::preview::output::{OutputTarget, Processor};
+use crate::preview::quality::QualityProfile;
+use crate::preview::render::Orientation;
+use crate::preview::sources::SourcePreference;
+use crate::preview::transform::PostProcessing;
+use crate::scene::types::Scene;
+use crate::settings::control_cache::unix_now;
+use crate::settings::export::{import_into, parse_export, SettingsExport};
+use crate::settings::guard::ControlGuard;
+use crate::settings::persist::{load_json, write_json_atomic, SaveHealth, SaveScheduler};
+use crate::settings::rename::{detect_renames, CameraRename};
+use crate::settings::schedule::ScheduleRule;
+use crate::settings::sync::{DeviceSnapshot, InboundChange};
+use crate::settings::types::{
+    CachedControls, CameraSettings, ControlSource, SavedControl, SettingsFile,
+};
 
 /// Persistent settings store with debounced saving.
 pub struct SettingsStore {
     path: PathBuf,
     data: Mutex<SettingsFile>,
-    save_notify: Notify,
-    is_dirty: AtomicBool,
+    saves: SaveScheduler,
 }
 
 impl SettingsStore {
@@ -22,247 +41,1590 @@ impl SettingsStore {
         Self {
             path,
             data: Mutex::new(data),
-            save_notify: Notify::new(),
-            is_dirty: AtomicBool::new(false),
+            saves: SaveScheduler::new(),
         }
     }
 
     /// Load settings from a JSON file, returning default on missing file.
     pub fn load(path: &std::path::Path) -> Result<SettingsFile, String> {
-        if !path.exists() {
-            return Ok(SettingsFile::default());
-        }
-        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&contents).map_err(|e| e.to_string())
+        load_json(path)
     }
 
     /// Save current settings to disk atomically (write .tmp then rename).
     pub fn save(&self) -> Result<(), String> {
         let data = self.data.lock().clone();
-        let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+        write_json_atomic(&self.path, &data)
+    }
+
+    /// Write any unsaved changes now rather than after the debounce. For
+    /// commands that should only report success once the change is on disk.
+    pub fn flush(&self) -> Result<(), String> {
+        self.saves.flush(|| self.save())
+    }
+
+    /// When settings were last written and how many changes are waiting.
+    pub fn save_health(&self) -> SaveHealth {
+        self.saves.health()
+    }
+
+    /// Write the settings to `path` as a versioned export, without the
+    /// entries that only apply to this machine.
+    pub fn export_to(&self, path: &Path) -> Result<(), String> {
+        let export = SettingsExport::new(&self.data.lock(), unix_now());
+        write_json_atomic(path, &export)
+    }
+
+    /// Import an export written by [`Self::export_to`], merging it into the
+    /// current settings or replacing them. The file is checked in full
+    /// before anything changes. Returns the device IDs it had settings for,
+    /// sorted; triggers a debounced save.
+    pub fn import_from(&self, path: &Path, merge: bool) -> Result<Vec<String>, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Can't read {}: {e}", path.display()))?;
+        let imported = parse_export(&contents)?;
+        let mut device_ids: Vec<String> = imported.cameras.keys().cloned().collect();
+        device_ids.sort();
+        import_into(&mut self.data.lock(), imported, merge);
+        self.saves.request();
+        Ok(device_ids)
+    }
+
+    /// Folder the settings file lives in.
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Get saved settings for a camera by device ID.
+    pub fn get_camera(&self, device_id: &str) -> Option<CameraSettings> {
+        self.data.lock().cameras.get(device_id).cloned()
+    }
+
+    /// Saved settings of every camera, by device ID.
+    pub fn cameras(&self) -> HashMap<String, CameraSettings> {
+        self.data.lock().cameras.clone()
+    }
+
+    /// Set a control value changed by hand, creating the camera entry if
+    /// needed. Triggers a debounced save.
+    pub fn set_control(&self, device_id: &str, camera_name: &str, control_id: &str, value: i32) {
+        self.record_control(
+            device_id,
+            camera_name,
+            control_id,
+            value,
+            ControlSource::Manual,
+        );
+    }
+
+    /// Set a manual control value written by `source`, timestamped now.
+    /// Creates the camera entry if needed and triggers a debounced save.
+    pub fn record_control(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+        control_id: &str,
+        value: i32,
+        source: ControlSource,
+    ) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.controls.insert(
+                control_id.to_string(),
+                SavedControl {
+                    value,
+                    auto: false,
+                    source,
+                    changed_at: unix_now(),
+                },
+            );
+        }
+        self.saves.request();
+    }
+
+    /// Record whether a control is in auto mode, along with the value it
+    /// currently holds. Creates the camera entry if needed and triggers a
+    /// debounced save.
+    pub fn set_control_auto(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+        control_id: &str,
+        auto: bool,
+        value: i32,
+    ) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.controls.insert(
+                control_id.to_string(),
+                SavedControl {
+                    value,
+                    auto,
+                    source: ControlSource::Manual,
+                    changed_at: unix_now(),
+                },
+            );
+        }
+        self.saves.request();
+    }
+
+    /// Replace the value of an existing saved control, keeping its mode,
+    /// source and timestamp. Used when restore had to adjust the value, so
+    /// it isn't a change by the user. Returns whether the control was saved;
+    /// triggers a debounced save if it was.
+    pub fn adjust_saved_value(&self, device_id: &str, control_id: &str, value: i32) -> bool {
+        let adjusted = {
+            let mut data = self.data.lock();
+            match data
+                .cameras
+                .get_mut(device_id)
+                .and_then(|camera| camera.controls.get_mut(control_id))
+            {
+                Some(entry) => {
+                    entry.value = value;
+                    true
+                }
+                None => false,
+            }
+        };
+        if adjusted {
+            self.saves.request();
+        }
+        adjusted
+    }
+
+    /// Set the preview orientation, creating the camera entry if needed.
+    /// Triggers a debounced save.
+    pub fn set_orientation(&self, device_id: &str, camera_name: &str, orientation: Orientation) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.orientation = orientation;
+        }
+        self.saves.request();
+    }
+
+    /// Set the JPEG quality profile, creating the camera entry if needed.
+    /// Triggers a debounced save.
+    pub fn set_jpeg_quality(&self, device_id: &str, camera_name: &str, profile: QualityProfile) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.jpeg_quality = profile;
+        }
+        self.saves.request();
+    }
+
+    /// Set sharpening and denoise, creating the camera entry if needed.
+    /// Triggers a debounced save.
+    pub fn set_post_processing(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+        settings: PostProcessing,
+    ) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.post_processing = settings;
+        }
+        self.saves.request();
+    }
+
+    /// Give one of a camera's outputs its own processor chain, or with
+    /// `None` go back to the preview's, creating the camera entry if needed.
+    /// Triggers a debounced save.
+    pub fn set_output_processors(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+        target: OutputTarget,
+        chain: Option<Vec<Processor>>,
+    ) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.output_processors.set(target, chain);
+        }
+        self.saves.request();
+    }
+
+    /// The processor chain `target` runs for a camera, with the camera's
+    /// saved name. Empty, and no name, for cameras without an entry.
+    pub fn output_chain(
+        &self,
+        device_id: &str,
+        target: OutputTarget,
+    ) -> (Vec<Processor>, Option<String>) {
+        let data = self.data.lock();
+        match data.cameras.get(device_id) {
+            Some(camera) => (
+                camera.output_processors.chain(target).to_vec(),
+                Some(camera.name.clone()),
+            ),
+            None => (Vec::new(), None),
+        }
+    }
+
+    /// Save the video source picked for a multi-input camera, or with
+    /// `None` forget it, creating the camera entry if needed. Triggers a
+    /// debounced save.
+    pub fn set_video_source(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+        source: Option<SourcePreference>,
+    ) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.video_source = source;
+        }
+        self.saves.request();
+    }
+
+    /// The video source saved for a camera, if one was picked.
+    pub fn video_source(&self, device_id: &str) -> Option<SourcePreference> {
+        self.data
+            .lock()
+            .cameras
+            .get(device_id)
+            .and_then(|camera| camera.video_source.clone())
+    }
+
+    /// Override how a camera's YUV frames are decoded, or with `None` go
+    /// back to detecting it, creating the camera entry if needed. Triggers
+    /// a debounced save.
+    pub fn set_colour_space(
+        &self,
+        device_id: &str,
+        camera_name: &str,
+        colour_space: Option<ColourSpace>,
+    ) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.colour_space = colour_space;
+        }
+        self.saves.request();
+    }
+
+    /// The colour space override saved for a camera, if any.
+    pub fn colour_space(&self, device_id: &str) -> Option<ColourSpace> {
+        self.data
+            .lock()
+            .cameras
+            .get(device_id)
+            .and_then(|camera| camera.colour_space)
+    }
+
+    /// Turn the "no signal" card on or off for a camera, creating the camera
+    /// entry if needed. Triggers a debounced save.
+    pub fn set_placeholder_on_error(&self, device_id: &str, camera_name: &str, enabled: bool) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.placeholder_on_error = enabled;
+        }
+        self.saves.request();
+    }
+
+    /// Name to draw on a camera's "no signal" card, or `None` if the camera
+    /// doesn't have the card turned on.
+    pub fn placeholder_name(&self, device_id: &str) -> Option<String> {
+        self.data
+            .lock()
+            .cameras
+            .get(device_id)
+            .filter(|c| c.placeholder_on_error)
+            .map(|c| c.name.clone())
+    }
+
+    /// Choose whether a camera's preview auto-starts at full resolution
+    /// rather than thumbnail-only, creating the camera entry if needed.
+    /// Triggers a debounced save.
+    pub fn set_full_resolution_autostart(&self, device_id: &str, camera_name: &str, enabled: bool) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.full_resolution_autostart = enabled;
+        }
+        self.saves.request();
+    }
+
+    /// Whether a camera's preview auto-starts at full resolution.
+    pub fn full_resolution_autostart(&self, device_id: &str) -> bool {
+        self.data
+            .lock()
+            .cameras
+            .get(device_id)
+            .is_some_and(|c| c.full_resolution_autostart)
+    }
+
+    /// Remember the frame size and rate chosen for a camera's preview,
+    /// creating the camera entry if needed. Triggers a debounced save.
+    pub fn set_preview_format(&self, device_id: &str, camera_name: &str, format: PreviewFormat) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.preview_format = Some(format);
+        }
+        self.saves.request();
+    }
+
+    /// The frame size and rate last chosen for a camera's preview, if any.
+    pub fn preview_format(&self, device_id: &str) -> Option<PreviewFormat> {
+        self.data
+            .lock()
+            .cameras
+            .get(device_id)
+            .and_then(|c| c.preview_format)
+    }
+
+    /// Record `preset_id` as the preset last applied to a camera, creating
+    /// the camera entry if needed. Triggers a debounced save.
+    pub fn set_applied_preset(&self, device_id: &str, camera_name: &str, preset_id: &str) {
+        {
+            let mut data = self.data.lock();
+            let entry = data.cameras.entry(device_id.to_string()).or_default();
+            entry.name = camera_name.to_string();
+            entry.preset = Some(preset_id.to_string());
+        }
+        self.saves.request();
+    }
+
+    /// A saved preset by ID.
+    pub fn preset(&self, preset_id: &str) -> Option<Preset> {
+        self.data.lock().presets.get(preset_id).cloned()
+    }
+
+    /// ID of the preset with ID `key` or, failing that, the one named `key`
+    /// (ignoring case).
+    pub fn find_preset_id(&self, key: &str) -> Option<String> {
+        let data = self.data.lock();
+        if data.presets.contains_key(key) {
+            return Some(key.to_string());
+        }
+        data.presets
+            .iter()
+            .find(|(_, preset)| preset.name.eq_ignore_ascii_case(key))
+            .map(|(id, _)| id.clone())
+    }
+
+    /// Every saved preset with its ID, sorted by ID.
+    pub fn presets(&self) -> Vec<(String, Preset)> {
+        let mut presets: Vec<_> = self
+            .data
+            .lock()
+            .presets
+            .iter()
+            .map(|(id, preset)| (id.clone(), preset.clone()))
+            .collect();
+        presets.sort_by(|a, b| a.0.cmp(&b.0));
+        presets
+    }
+
+    /// Save a preset, replacing any with the same ID. Triggers a debounced
+    /// save.
+    pub fn save_preset(&self, preset_id: &str, preset: Preset) {
+        self.data
+            .lock()
+            .presets
+            .insert(preset_id.to_string(), preset);
+        self.saves.request();
+    }
+
+    /// Delete a preset, returning whether it existed. Triggers a debounced
+    /// save if it did.
+    pub fn delete_preset(&self, preset_id: &str) -> bool {
+        let removed = self.data.lock().presets.remove(preset_id).is_some();
+        if removed {
+            self.saves.request();
+        }
+        removed
+    }
+
+    /// A camera's scheduled presets, in priority order. Empty if it has no
+    /// schedule.
+    pub fn schedule(&self, device_id: &str) -> Vec<ScheduleRule> {
+        self.data
+            .lock()
+            .schedules
+            .get(device_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Every camera's schedule, by device ID.
+    pub fn schedules(&self) -> Vec<(String, Vec<ScheduleRule>)> {
+        self.data
+            .lock()
+            .schedules
+            .iter()
+            .map(|(id, rules)| (id.clone(), rules.clone()))
+            .collect()
+    }
+
+    /// Replace a camera's schedule; an empty list removes it. Triggers a
+    /// debounced save.
+    pub fn set_schedule(&self, device_id: &str, rules: Vec<ScheduleRule>) {
+        {
+            let mut data = self.data.lock();
+            if rules.is_empty() {
+                data.schedules.remove(device_id);
+            } else {
+                data.schedules.insert(device_id.to_string(), rules);
+            }
+        }
+        self.saves.request();
+    }
+
+    /// A camera's control guards. Empty if it has none.
+    pub fn control_guards(&self, device_id: &str) -> Vec<ControlGuard> {
+        self.data
+            .lock()
+            .control_guards
+            .get(device_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Every camera's control guards, by device ID.
+    pub fn all_control_guards(&self) -> Vec<(String, Vec<ControlGuard>)> {
+        self.data
+            .lock()
+            .control_guards
+            .iter()
+            .map(|(id, guards)| (id.clone(), guards.clone()))
+            .collect()
+    }
+
+    /// Add a control guard, replacing any on the same control. Triggers a
+    /// debounced save.
+    pub fn set_control_guard(&self, device_id: &str, guard: ControlGuard) {
+        {
+            let mut data = self.data.lock();
+            let guards = data
+                .control_guards
+                .entry(device_id.to_string())
+                .or_default();
+            match guards.iter_mut().find(|g| g.control_id == guard.control_id) {
+                Some(existing) => *existing = guard,
+                None => guards.push(guard),
+            }
+        }
+        self.saves.request();
+    }
+
+    /// Remove the guard on one of a camera's controls. Returns whether there
+    /// was one; triggers a debounced save if so.
+    pub fn remove_control_guard(&self, device_id: &str, control_id: &str) -> bool {
+        let removed = {
+            let mut data = self.data.lock();
+            let Some(guards) = data.control_guards.get_mut(device_id) else {
+                return false;
+            };
+            let before = guards.len();
+            guards.retain(|g| g.control_id != control_id);
+            let removed = guards.len() != before;
+            if guards.is_empty() {
+                data.control_guards.remove(device_id);
+            }
+            removed
+        };
+        if removed {
+            self.saves.request();
+        }
+        removed
+    }
+
+    /// Saved scenes, in the order they were first saved.
+    pub fn scenes(&self) -> Vec<Scene> {
+        self.data.lock().scenes.clone()
+    }
+
+    /// The saved scene named `name`, ignoring case.
+    pub fn scene(&self, name: &str) -> Option<Scene> {
+        self.data
+            .lock()
+            .scenes
+            .iter()
+            .find(|scene| scene.name.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// Save a scene, replacing any with the same name (ignoring case) in
+    /// place. Triggers a debounced save.
+    pub fn save_scene(&self, scene: Scene) {
+        {
+            let mut data = self.data.lock();
+            match data
+                .scenes
+                .iter_mut()
+                .find(|existing| existing.name.eq_ignore_ascii_case(&scene.name))
+            {
+                Some(existing) => *existing = scene,
+                None => data.scenes.push(scene),
+            }
+        }
+        self.saves.request();
+    }
+
+    /// Delete the scene named `name`, ignoring case. Returns whether it
+    /// existed.
+    pub fn delete_scene(&self, name: &str) -> bool {
+        let removed = {
+            let mut data = self.data.lock();
+            let before = data.scenes.len();
+            data.scenes
+                .retain(|scene| !scene.name.eq_ignore_ascii_case(name));
+            data.scenes.len() != before
+        };
+        if removed {
+            self.saves.request();
+        }
+        removed
+    }
+
+    /// Whether IR/depth sibling devices get previews auto-started.
+    pub fn auto_start_non_primary(&self) -> bool {
+        self.data.lock().auto_start_non_primary
+    }
+
+    /// Set whether IR/depth sibling devices get previews auto-started.
+    /// Triggers a debounced save.
+    pub fn set_auto_start_non_primary(&self, enabled: bool) {
+        self.data.lock().auto_start_non_primary = enabled;
+        self.saves.request();
+    }
+
+    /// Whether cameras hidden as duplicates of another backend's device are
+    /// listed.
+    pub fn show_suppressed_devices(&self) -> bool {
+        self.data.lock().show_suppressed_devices
+    }
+
+    /// Set whether suppressed duplicate cameras are listed. Triggers a
+    /// debounced save.
+    pub fn set_show_suppressed_devices(&self, enabled: bool) {
+        self.data.lock().show_suppressed_devices = enabled;
+        self.saves.request();
+    }
+
+    /// Whether the Canon EDSDK backend should be loaded.
+    pub fn canon_enabled(&self) -> bool {
+        !self.data.lock().disable_canon
+    }
+
+    /// Enable or disable the Canon EDSDK backend. Triggers a debounced save.
+    pub fn set_canon_enabled(&self, enabled: bool) {
+        self.data.lock().disable_canon = !enabled;
+        self.saves.request();
+    }
+
+    /// Whether saved values restore had to clamp or snap are updated to
+    /// what was written.
+    pub fn reconcile_saved_settings(&self) -> bool {
+        !self.data.lock().keep_unreconciled_settings
+    }
+
+    /// Turn reconciliation of saved values on or off. Triggers a debounced
+    /// save.
+    pub fn set_reconcile_saved_settings(&self, enabled: bool) {
+        self.data.lock().keep_unreconciled_settings = !enabled;
+        self.saves.request();
+    }
+
+    /// Language code of the backend's user-facing text, if one was chosen.
+    pub fn locale(&self) -> Option<String> {
+        self.data.lock().locale.clone()
+    }
+
+    /// Remember the language code of the backend's user-facing text.
+    /// Triggers a debounced save.
+    pub fn set_locale(&self, locale: &str) {
+        self.data.lock().locale = Some(locale.to_string());
+        self.saves.request();
+    }
+
+    /// Whether the default camera is kept warm while the window is hidden.
+    pub fn keep_default_warm(&self) -> bool {
+        self.data.lock().keep_default_warm
+    }
+
+    /// Set whether the default camera is kept warm. Triggers a debounced save.
+    pub fn set_keep_default_warm(&self, enabled: bool) {
+        self.data.lock().keep_default_warm = enabled;
+        self.saves.request();
+    }
+
+    /// Whether tally lights follow the default camera and scenes.
+    pub fn tally_auto(&self) -> bool {
+        self.data.lock().tally_auto
+    }
+
+    /// Set whether tally lights follow the default camera and scenes.
+    /// Triggers a debounced save.
+    pub fn set_tally_auto(&self, enabled: bool) {
+        self.data.lock().tally_auto = enabled;
+        self.saves.request();
+    }
+
+    /// Days after which unseen cameras are pruned at startup, if set.
+    pub fn prune_after_days(&self) -> Option<u32> {
+        self.data.lock().prune_after_days
+    }
+
+    /// Prune cameras unseen for `days` at startup, or never with `None`.
+    /// Triggers a debounced save.
+    pub fn set_prune_after_days(&self, days: Option<u32>) {
+        self.data.lock().prune_after_days = days;
+        self.saves.request();
+    }
+
+    /// Device ID of the default camera, if one has been chosen.
+    pub fn default_camera(&self) -> Option<String> {
+        self.data.lock().default_camera.clone()
+    }
+
+    /// Set the default camera. Triggers a debounced save.
+    pub fn set_default_camera(&self, device_id: &str) {
+        self.data.lock().default_camera = Some(device_id.to_string());
+        self.saves.request();
+    }
+
+    /// Configured limit on each preview JPEG cache in megabytes, if set.
+    pub fn jpeg_cache_limit_mb(&self) -> Option<u32> {
+        self.data.lock().jpeg_cache_limit_mb
+    }
+
+    /// Configured limit on concurrent preview sessions, if set.
+    pub fn max_preview_sessions(&self) -> Option<u32> {
+        self.data.lock().max_preview_sessions
+    }
+
+    /// Configured limit on a frame response in kilobytes, if set.
+    pub fn max_frame_payload_kb(&self) -> Option<u32> {
+        self.data.lock().max_frame_payload_kb
+    }
+
+    /// Whether the localhost control API is served.
+    pub fn control_api_enabled(&self) -> bool {
+        self.data.lock().control_api_enabled
+    }
+
+    /// Set whether the localhost control API is served. Triggers a debounced
+    /// save.
+    pub fn set_control_api_enabled(&self, enabled: bool) {
+        self.data.lock().control_api_enabled = enabled;
+        self.saves.request();
+    }
+
+    /// The control API's bearer token, if one has been generated.
+    pub fn control_api_token(&self) -> Option<String> {
+        self.data.lock().control_api_token.clone()
+    }
+
+    /// The control API's bearer token, generating and saving one with
+    /// `generate` if none exists yet.
+    pub fn control_api_token_or(&self, generate: impl FnOnce() -> String) -> String {
+        let token = {
+            let mut data = self.data.lock();
+            if let Some(token) = &data.control_api_token {
+                return token.clone();
+            }
+            data.control_api_token.insert(generate()).clone()
+        };
+        self.saves.request();
+        token
+    }
+
+    /// Replace the control API's bearer token. Triggers a debounced save.
+    pub fn set_control_api_token(&self, token: &str) {
+        self.data.lock().control_api_token = Some(token.to_string());
+        self.saves.request();
+    }
+
+    /// Configured control API port, if set.
+    pub fn control_api_port(&self) -> Option<u16> {
+        self.data.lock().control_api_port
+    }
+
+    /// Name of the MIDI input to listen to, if one has been chosen.
+    pub fn midi_input(&self) -> Option<String> {
+        self.data.lock().midi_input.clone()
+    }
+
+    /// Set or clear the MIDI input to listen to. Triggers a debounced save.
+    pub fn set_midi_input(&self, name: Option<&str>) {
+        self.data.lock().midi_input = name.map(str::to_string);
+        self.saves.request();
+    }
+
+    /// MIDI control changes mapped to camera controls.
+    pub fn midi_mappings(&self) -> Vec<MidiMapping> {
+        self.data.lock().midi_mappings.clone()
+    }
+
+    /// Add a MIDI mapping, replacing any on the same channel and CC.
+    /// Triggers a debounced save.
+    pub fn set_midi_mapping(&self, mapping: MidiMapping) {
+        upsert_mapping(&mut self.data.lock().midi_mappings, mapping);
+        self.saves.request();
+    }
+
+    /// Remove the MIDI mapping on `channel` and `cc`. Returns whether one
+    /// was removed; triggers a debounced save if so.
+    pub fn remove_midi_mapping(&self, channel: u8, cc: u8) -> bool {
+        let removed = {
+            let mut data = self.data.lock();
+            let before = data.midi_mappings.len();
+            data.midi_mappings.retain(|m| !m.matches(channel, cc));
+            data.midi_mappings.len() != before
+        };
+        if removed {
+            self.saves.request();
+        }
+        removed
+    }
+
+    /// Keys that nudge controls, the built-in ones unless changed.
+    pub fn key_bindings(&self) -> Vec<KeyBinding> {
+        self.data
+            .lock()
+            .key_bindings
+            .clone()
+            .unwrap_or_else(default_bindings)
+    }
+
+    /// Replace the key bindings, or go back to the built-in ones with
+    /// `None`. Triggers a debounced save.
+    pub fn set_key_bindings(&self, bindings: Option<Vec<KeyBinding>>) {
+        self.data.lock().key_bindings = bindings;
+        self.saves.request();
+    }
+
+    /// Cached control descriptors for a camera, if any were saved.
+    ///
+    /// Returned whether or not the entry is still valid for the device.
+    pub fn cached_controls(&self, device_id: &str) -> Option<CachedControls> {
+        self.data.lock().control_cache.get(device_id).cloned()
+    }
+
+    /// Replace the cached control descriptors for `device`, returning the
+    /// previous entry. Triggers a debounced save.
+    pub fn cache_controls(
+        &self,
+        device: &CameraDevice,
+        controls: Vec<ControlDescriptor>,
+        now: u64,
+    ) -> Option<CachedControls> {
+        let previous = self.data.lock().control_cache.insert(
+            device.id.as_str().to_string(),
+            CachedControls {
+                name: device.name.clone(),
+                device_path: device.device_path.clone(),
+                cached_at: now,
+                controls,
+            },
+        );
+        self.saves.request();
+        previous
+    }
+
+    /// Bring saved camera names in line with a fresh enumeration, returning
+    /// the renames applied. Triggers a debounced save only when a name
+    /// changed.
+    pub fn reconcile_names(&self, devices: &[CameraDevice]) -> Vec<CameraRename> {
+        let renames = {
+            let mut data = self.data.lock();
+            let renames = detect_renames(&data.cameras, devices);
+            for rename in &renames {
+                if let Some(camera) = data.cameras.get_mut(&rename.device_id) {
+                    camera.name = rename.new_name.clone();
+                }
+            }
+            renames
+        };
+        if renames.is_empty() {
+            return renames;
+        }
+        self.saves.request();
+        renames
+    }
+
+    /// Folder settings are synced through, if sync is on.
+    pub fn sync_dir(&self) -> Option<String> {
+        self.data.lock().sync_dir.clone()
+    }
 
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-        }
+    /// Turn sync on with a folder, or off with `None`. Triggers a debounced
+    /// save.
+    pub fn set_sync_dir(&self, dir: Option<&str>) {
+        self.data.lock().sync_dir = dir.map(str::to_string);
+        self.saves.request();
+    }
 
-        let tmp_path = self.path.with_extension("json.tmp");
-        std::fs::write(&tmp_path, &json).map_err(|e| e.to_string())?;
-        std::fs::rename(&tmp_path, &self.path).map_err(|e| e.to_string())?;
+    /// This machine's sync ID, generating and saving one with `generate` if
+    /// none exists yet.
+    pub fn sync_machine_id_or(&self, generate: impl FnOnce() -> String) -> String {
+        let id = {
+            let mut data = self.data.lock();
+            if let Some(id) = &data.sync_machine_id {
+                return id.clone();
+            }
+            data.sync_machine_id.insert(generate()).clone()
+        };
+        self.saves.request();
+        id
+    }
 
-        Ok(())
+    /// This machine's last synced snapshot of a camera.
+    pub fn sync_snapshot(&self, device_id: &str) -> Option<DeviceSnapshot> {
+        self.data.lock().sync_snapshots.get(device_id).cloned()
     }
 
-    /// Get saved settings for a camera by device ID.
-    pub fn get_camera(&self, device_id: &str) -> Option<crate::settings::types::CameraSettings> {
-        self.data.lock().cameras.get(device_id).cloned()
+    /// Device IDs with saved settings or a synced snapshot.
+    pub fn synced_device_ids(&self) -> Vec<String> {
+        let data = self.data.lock();
+        let mut ids: Vec<String> = data
+            .cameras
+            .keys()
+            .chain(data.sync_snapshots.keys())
+            .cloned()
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
     }
 
-    /// Set a control value, creating the camera entry if needed.
-    /// Triggers a debounced save.
-    pub fn set_control(&self, device_id: &str, camera_name: &str, control_id: &str, value: i32) {
+    /// Record `snapshot` as the camera's synced state and apply `inbound`
+    /// changes from other machines to its saved controls, creating the
+    /// camera entry if needed. Triggers a debounced save.
+    pub fn apply_synced(&self, snapshot: DeviceSnapshot, inbound: &[InboundChange]) {
         {
             let mut data = self.data.lock();
-            let entry = data.cameras.entry(device_id.to_string()).or_default();
-            entry.name = camera_name.to_string();
-            entry.controls.insert(control_id.to_string(), value);
+            if !inbound.is_empty() {
+                let entry = data.cameras.entry(snapshot.device_id.clone()).or_default();
+                if entry.name.is_empty() {
+                    entry.name = snapshot.camera_name.clone();
+                }
+                for change in inbound {
+                    match &change.saved {
+                        Some(saved) => {
+                            entry
+                                .controls
+                                .insert(change.control_id.clone(), saved.clone());
+                        }
+                        None => {
+                            entry.controls.remove(&change.control_id);
+                        }
+                    }
+                }
+            }
+            data.sync_snapshots
+                .insert(snapshot.device_id.clone(), snapshot);
         }
-        self.is_dirty.store(true, Ordering::Release);
-        self.save_notify.notify_one();
+        self.saves.request();
     }
 
     /// Remove all saved settings for a camera.
     pub fn remove_camera(&self, device_id: &str) {
         self.data.lock().cameras.remove(device_id);
-        self.is_dirty.store(true, Ordering::Release);
-        self.save_notify.notify_one();
+        self.saves.request();
     }
 
-    /// Start the debounce task — waits for dirty notification, sleeps 500ms, then saves.
-    ///
-    /// Uses an `AtomicBool` dirty flag to avoid losing notifications that arrive
-    /// between `save()` completing and `notified().await` re-registering. The
-    /// inner `while` loop drains all pending changes so a notification during
-    /// `save()` is never lost.
-    pub fn start_debounce_task(self: &Arc<Self>) {
-        let store = Arc::clone(self);
-        tauri::async_runtime::spawn(async move {
-            loop {
-                store.save_notify.notified().await;
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-                // Drain: keep saving until no more changes arrive during save
-                while store.is_dirty.swap(false, Ordering::AcqRel) {
-                    if let Err(e) = store.save() {
-                        tracing::warn!("Failed to save settings: {e}");
-                    }
+    /// Forget a camera altogether: its saved settings, cached controls and
+    /// synced snapshot. Its schedule is kept, as on a reset. Returns the
+    /// settings removed, if it had any; triggers a debounced save if so.
+    pub fn forget_camera(&self, device_id: &str) -> Option<CameraSettings> {
+        let removed = {
+            let mut data = self.data.lock();
+            data.control_cache.remove(device_id);
+            data.sync_snapshots.remove(device_id);
+            data.cameras.remove(device_id)
+        };
+        if removed.is_some() {
+            self.saves.request();
+        }
+        removed
+    }
+
+    /// Record that `devices` were enumerated at `now`. Only cameras with
+    /// saved settings are stamped. Triggers a debounced save if any were.
+    pub fn mark_seen(&self, devices: &[CameraDevice], now: u64) {
+        let marked = {
+            let mut data = self.data.lock();
+            let mut marked = false;
+            for device in devices {
+                if let Some(camera) = data.cameras.get_mut(device.id.as_str()) {
+                    marked |= camera.last_seen != now;
+                    camera.last_seen = now;
                 }
             }
-        });
+            marked
+        };
+        if marked {
+            self.saves.request();
+        }
+    }
+
+    /// Stamp cameras never marked seen with `now`, so ones saved before
+    /// sightings were recorded start ageing from today rather than never.
+    /// Triggers a debounced save if any were stamped.
+    pub fn stamp_unrecorded(&self, now: u64) {
+        let stamped = {
+            let mut data = self.data.lock();
+            let mut stamped = false;
+            for camera in data.cameras.values_mut().filter(|c| c.last_seen == 0) {
+                camera.last_seen = now;
+                stamped = true;
+            }
+            stamped
+        };
+        if stamped {
+            self.saves.request();
+        }
+    }
+
+    /// The debounce task — waits for changes, sleeps 500ms, then saves.
+    /// Never finishes; spawn it on the async runtime. See
+    /// [`SaveScheduler::run`].
+    pub fn debounce_task(self: &Arc<Self>) -> impl std::future::Future<Output = ()> + Send {
+        let store = Arc::clone(self);
+        async move { store.saves.run("settings", || store.save()).await }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::preview::output::OutputProcessors;
+    use crate::preview::watermark::Watermark;
     use crate::settings::types::CameraSettings;
     use std::collections::HashMap;
     use tempfile::TempDir;
 
-    /// Helper: create a store backed by a temp directory.
-    fn temp_store() -> (SettingsStore, TempDir) {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("cameras.json");
-        let store = SettingsStore::new(path);
-        (store, dir)
+    /// Helper: create a store backed by a temp directory.
+    fn temp_store() -> (SettingsStore, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        let store = SettingsStore::new(path);
+        (store, dir)
+    }
+
+    // --- File I/O tests (Step 3) ---
+
+    #[test]
+    fn load_returns_default_when_file_missing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nonexistent.json");
+        let result = SettingsStore::load(&path).unwrap();
+        assert_eq!(result, SettingsFile::default());
+    }
+
+    #[test]
+    fn load_parses_valid_json_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        let json = r#"{"cameras":{"dev-1":{"name":"Cam","controls":{"brightness":100}}}}"#;
+        std::fs::write(&path, json).unwrap();
+
+        let result = SettingsStore::load(&path).unwrap();
+        assert_eq!(result.cameras.len(), 1);
+        assert_eq!(result.cameras["dev-1"].name, "Cam");
+        assert_eq!(result.cameras["dev-1"].controls["brightness"].value, 100);
+    }
+
+    #[test]
+    fn load_returns_error_for_invalid_json() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        std::fs::write(&path, "not valid json!!!").unwrap();
+
+        let result = SettingsStore::load(&path);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn save_creates_file_on_disk() {
+        let (store, dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 150);
+        store.save().unwrap();
+
+        let path = dir.path().join("cameras.json");
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn save_writes_valid_json() {
+        let (store, dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 150);
+        store.save().unwrap();
+
+        let path = dir.path().join("cameras.json");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let parsed: SettingsFile = serde_json::from_str(&contents).unwrap();
+        assert_eq!(parsed.cameras["dev-1"].controls["brightness"].value, 150);
+    }
+
+    #[test]
+    fn save_round_trips_through_load() {
+        let (store, dir) = temp_store();
+        store.set_control("dev-1", "Camera One", "brightness", 200);
+        store.set_control("dev-2", "Camera Two", "contrast", 50);
+        store.save().unwrap();
+
+        let path = dir.path().join("cameras.json");
+        let loaded = SettingsStore::load(&path).unwrap();
+        assert_eq!(loaded.cameras.len(), 2);
+        assert_eq!(loaded.cameras["dev-1"].name, "Camera One");
+        assert_eq!(loaded.cameras["dev-1"].controls["brightness"].value, 200);
+        assert_eq!(loaded.cameras["dev-2"].name, "Camera Two");
+        assert_eq!(loaded.cameras["dev-2"].controls["contrast"].value, 50);
+    }
+
+    #[test]
+    fn save_creates_parent_directories() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("deep").join("cameras.json");
+        let store = SettingsStore::new(path.clone());
+        store.set_control("dev-1", "Camera", "brightness", 100);
+        store.save().unwrap();
+
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn save_is_atomic() {
+        let (store, dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 100);
+        store.save().unwrap();
+
+        // After a successful save, no .tmp file should remain
+        let tmp_path = dir.path().join("cameras.json.tmp");
+        assert!(
+            !tmp_path.exists(),
+            ".tmp file should be cleaned up after rename"
+        );
+    }
+
+    #[test]
+    fn new_loads_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+
+        // Write a settings file manually
+        let mut controls = HashMap::new();
+        controls.insert("brightness".to_string(), SavedControl::manual(200));
+        let mut cameras = HashMap::new();
+        cameras.insert(
+            "dev-1".to_string(),
+            CameraSettings {
+                name: "Pre-existing".to_string(),
+                controls,
+                orientation: Orientation::default(),
+                jpeg_quality: QualityProfile::default(),
+                post_processing: PostProcessing::default(),
+                output_processors: OutputProcessors::default(),
+                video_source: None,
+                colour_space: None,
+                placeholder_on_error: false,
+                full_resolution_autostart: false,
+                preview_format: None,
+                last_seen: 0,
+                preset: None,
+            },
+        );
+        let file = SettingsFile {
+            cameras,
+            ..Default::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        // SettingsStore::new should load it
+        let store = SettingsStore::new(path);
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.name, "Pre-existing");
+        assert_eq!(cam.controls["brightness"].value, 200);
+    }
+
+    // --- In-memory operation tests (Step 4) ---
+
+    #[test]
+    fn get_camera_returns_none_for_unknown() {
+        let (store, _dir) = temp_store();
+        assert!(store.get_camera("nonexistent").is_none());
+    }
+
+    #[test]
+    fn get_camera_returns_saved_settings() {
+        let (store, _dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 128);
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.name, "Camera");
+        assert_eq!(cam.controls["brightness"].value, 128);
+    }
+
+    #[test]
+    fn set_control_creates_new_camera_entry() {
+        let (store, _dir) = temp_store();
+        assert!(store.get_camera("new-device").is_none());
+        store.set_control("new-device", "New Camera", "exposure", 50);
+        let cam = store.get_camera("new-device").unwrap();
+        assert_eq!(cam.name, "New Camera");
+        assert_eq!(cam.controls["exposure"].value, 50);
+    }
+
+    #[test]
+    fn set_orientation_keeps_saved_controls() {
+        let (store, _dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 100);
+        let orientation = Orientation {
+            rotation: crate::preview::render::Rotation::Cw180,
+            mirror: false,
+        };
+        store.set_orientation("dev-1", "Camera", orientation);
+
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.orientation, orientation);
+        assert_eq!(cam.controls["brightness"].value, 100);
+    }
+
+    #[test]
+    fn set_jpeg_quality_keeps_saved_controls() {
+        let (store, _dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 100);
+        let profile = QualityProfile {
+            adaptive: false,
+            quality: 90,
+            ..QualityProfile::default()
+        };
+        store.set_jpeg_quality("dev-1", "Camera", profile);
+
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.jpeg_quality, profile);
+        assert_eq!(cam.controls["brightness"].value, 100);
+    }
+
+    #[test]
+    fn set_post_processing_keeps_saved_controls() {
+        let (store, _dir) = temp_store();
+        store.set_control("dev-1", "Camera", "brightness", 100);
+        let settings = PostProcessing {
+            sharpen: 40,
+            denoise: 20,
+        };
+        store.set_post_processing("dev-1", "Camera", settings);
+
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.post_processing, settings);
+        assert_eq!(cam.controls["brightness"].value, 100);
+    }
+
+    #[test]
+    fn output_chains_fall_back_to_the_preview_and_persist() {
+        let (store, dir) = temp_store();
+        assert_eq!(
+            store.output_chain("dev-1", OutputTarget::Recording),
+            (Vec::new(), None)
+        );
+
+        let chain = vec![Processor::Watermark(Watermark::default())];
+        store.set_output_processors(
+            "dev-1",
+            "Camera",
+            OutputTarget::Recording,
+            Some(chain.clone()),
+        );
+        assert_eq!(
+            store.output_chain("dev-1", OutputTarget::Recording),
+            (chain.clone(), Some("Camera".to_string()))
+        );
+        assert!(store
+            .output_chain("dev-1", OutputTarget::Preview)
+            .0
+            .is_empty());
+
+        store.save().unwrap();
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(
+            loaded.cameras["dev-1"]
+                .output_processors
+                .chain(OutputTarget::Recording),
+            chain
+        );
+    }
+
+    #[test]
+    fn video_source_choice_persists_and_can_be_forgotten() {
+        let (store, dir) = temp_store();
+        assert_eq!(store.video_source("dev-1"), None);
+
+        let choice = SourcePreference {
+            index: 1,
+            name: "SDI".to_string(),
+        };
+        store.set_video_source("dev-1", "Capture Card", Some(choice.clone()));
+        assert_eq!(store.video_source("dev-1"), Some(choice.clone()));
+
+        store.save().unwrap();
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(loaded.cameras["dev-1"].video_source, Some(choice));
+        assert_eq!(loaded.cameras["dev-1"].name, "Capture Card");
+
+        store.set_video_source("dev-1", "Capture Card", None);
+        assert_eq!(store.video_source("dev-1"), None);
+    }
+
+    #[test]
+    fn colour_space_override_persists_and_can_be_cleared() {
+        let (store, dir) = temp_store();
+        assert_eq!(store.colour_space("dev-1"), None);
+
+        store.set_colour_space("dev-1", "HD Cam", Some(ColourSpace::BT709_FULL));
+        assert_eq!(store.colour_space("dev-1"), Some(ColourSpace::BT709_FULL));
+
+        store.save().unwrap();
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(
+            loaded.cameras["dev-1"].colour_space,
+            Some(ColourSpace::BT709_FULL)
+        );
+
+        store.set_colour_space("dev-1", "HD Cam", None);
+        assert_eq!(store.colour_space("dev-1"), None);
+    }
+
+    #[test]
+    fn control_guards_replace_per_control_and_persist() {
+        let (store, dir) = temp_store();
+        let gain = |max_value| ControlGuard {
+            control_id: "gain".to_string(),
+            max_value,
+            cooldown_secs: Some(60),
+        };
+        let exposure = ControlGuard {
+            control_id: "exposure".to_string(),
+            max_value: -5,
+            cooldown_secs: None,
+        };
+
+        store.set_control_guard("dev-1", gain(100));
+        store.set_control_guard("dev-1", exposure.clone());
+        store.set_control_guard("dev-1", gain(80));
+        assert_eq!(
+            store.control_guards("dev-1"),
+            vec![gain(80), exposure.clone()]
+        );
+        assert!(store.control_guards("dev-2").is_empty());
+
+        store.save().unwrap();
+        let loaded = SettingsStore::new(dir.path().join("cameras.json"));
+        assert_eq!(
+            loaded.all_control_guards(),
+            vec![("dev-1".to_string(), vec![gain(80), exposure])]
+        );
+
+        assert!(loaded.remove_control_guard("dev-1", "gain"));
+        assert!(!loaded.remove_control_guard("dev-1", "gain"));
+        assert!(loaded.remove_control_guard("dev-1", "exposure"));
+        assert!(loaded.all_control_guards().is_empty());
+    }
+
+    #[test]
+    fn auto_start_non_primary_defaults_off_and_persists() {
+        let (store, dir) = temp_store();
+        assert!(!store.auto_start_non_primary());
+
+        store.set_auto_start_non_primary(true);
+        store.save().unwrap();
+
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert!(loaded.auto_start_non_primary);
+    }
+
+    #[test]
+    fn show_suppressed_devices_defaults_off_and_persists() {
+        let (store, dir) = temp_store();
+        assert!(!store.show_suppressed_devices());
+
+        store.set_show_suppressed_devices(true);
+        store.save().unwrap();
+
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert!(loaded.show_suppressed_devices);
+    }
+
+    #[test]
+    fn canon_defaults_on_and_persists_when_disabled() {
+        let (store, dir) = temp_store();
+        assert!(store.canon_enabled());
+
+        store.set_canon_enabled(false);
+        store.save().unwrap();
+
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert!(loaded.disable_canon);
     }
 
-    // --- File I/O tests (Step 3) ---
+    #[test]
+    fn reconciliation_defaults_on_and_persists_when_disabled() {
+        let (store, dir) = temp_store();
+        assert!(store.reconcile_saved_settings());
+
+        store.set_reconcile_saved_settings(false);
+        store.save().unwrap();
+
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert!(loaded.keep_unreconciled_settings);
+    }
 
     #[test]
-    fn load_returns_default_when_file_missing() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("nonexistent.json");
-        let result = SettingsStore::load(&path).unwrap();
-        assert_eq!(result, SettingsFile::default());
+    fn locale_is_unset_until_chosen_and_persists() {
+        let (store, dir) = temp_store();
+        assert_eq!(store.locale(), None);
+
+        store.set_locale("de");
+        store.save().unwrap();
+
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(loaded.locale.as_deref(), Some("de"));
     }
 
     #[test]
-    fn load_parses_valid_json_file() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("cameras.json");
-        let json = r#"{"cameras":{"dev-1":{"name":"Cam","controls":{"brightness":100}}}}"#;
-        std::fs::write(&path, json).unwrap();
+    fn synced_changes_update_controls_and_the_snapshot() {
+        let (store, _dir) = temp_store();
+        store.set_control("dev-1", "Cam", "zoom", 3);
+        store.set_control("dev-1", "Cam", "focus", 40);
+        let mut snapshot = DeviceSnapshot::new("dev-1", "Cam", "laptop");
+        snapshot.revision = 9;
 
-        let result = SettingsStore::load(&path).unwrap();
-        assert_eq!(result.cameras.len(), 1);
-        assert_eq!(result.cameras["dev-1"].name, "Cam");
-        assert_eq!(result.cameras["dev-1"].controls["brightness"], 100);
+        store.apply_synced(
+            snapshot.clone(),
+            &[
+                InboundChange {
+                    control_id: "zoom".to_string(),
+                    saved: Some(SavedControl::manual(7)),
+                },
+                InboundChange {
+                    control_id: "focus".to_string(),
+                    saved: None,
+                },
+            ],
+        );
+
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.controls["zoom"].value, 7);
+        assert!(!cam.controls.contains_key("focus"));
+        assert_eq!(store.sync_snapshot("dev-1"), Some(snapshot));
     }
 
     #[test]
-    fn load_returns_error_for_invalid_json() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("cameras.json");
-        std::fs::write(&path, "not valid json!!!").unwrap();
+    fn synced_changes_create_unknown_cameras_with_the_snapshot_name() {
+        let (store, _dir) = temp_store();
+        store.apply_synced(
+            DeviceSnapshot::new("dev-2", "Laptop Cam", "laptop"),
+            &[InboundChange {
+                control_id: "zoom".to_string(),
+                saved: Some(SavedControl::manual(2)),
+            }],
+        );
+        assert_eq!(store.get_camera("dev-2").unwrap().name, "Laptop Cam");
+        assert_eq!(store.synced_device_ids(), vec!["dev-2".to_string()]);
+    }
 
-        let result = SettingsStore::load(&path);
-        assert!(result.is_err());
+    #[test]
+    fn the_sync_machine_id_is_generated_once() {
+        let (store, _dir) = temp_store();
+        assert_eq!(store.sync_machine_id_or(|| "abc".to_string()), "abc");
+        assert_eq!(store.sync_machine_id_or(|| "def".to_string()), "abc");
     }
 
     #[test]
-    fn save_creates_file_on_disk() {
-        let (store, dir) = temp_store();
-        store.set_control("dev-1", "Camera", "brightness", 150);
-        store.save().unwrap();
+    fn adjusting_a_saved_value_keeps_its_source() {
+        let (store, _dir) = temp_store();
+        store.record_control("dev-1", "Cam", "zoom", 7, ControlSource::Preset("p".into()));
+        let before = store.get_camera("dev-1").unwrap().controls["zoom"].clone();
 
-        let path = dir.path().join("cameras.json");
-        assert!(path.exists());
+        assert!(store.adjust_saved_value("dev-1", "zoom", 5));
+        assert!(!store.adjust_saved_value("dev-1", "focus", 5));
+
+        let after = store.get_camera("dev-1").unwrap().controls["zoom"].clone();
+        assert_eq!(after.value, 5);
+        assert_eq!(after.source, before.source);
+        assert_eq!(after.changed_at, before.changed_at);
     }
 
     #[test]
-    fn save_writes_valid_json() {
+    fn keep_default_warm_defaults_off_and_persists() {
         let (store, dir) = temp_store();
-        store.set_control("dev-1", "Camera", "brightness", 150);
+        assert!(!store.keep_default_warm());
+
+        store.set_keep_default_warm(true);
         store.save().unwrap();
 
-        let path = dir.path().join("cameras.json");
-        let contents = std::fs::read_to_string(&path).unwrap();
-        let parsed: SettingsFile = serde_json::from_str(&contents).unwrap();
-        assert_eq!(parsed.cameras["dev-1"].controls["brightness"], 150);
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert!(loaded.keep_default_warm);
     }
 
     #[test]
-    fn save_round_trips_through_load() {
+    fn tally_auto_defaults_off_and_persists() {
         let (store, dir) = temp_store();
-        store.set_control("dev-1", "Camera One", "brightness", 200);
-        store.set_control("dev-2", "Camera Two", "contrast", 50);
+        assert!(!store.tally_auto());
+
+        store.set_tally_auto(true);
         store.save().unwrap();
 
-        let path = dir.path().join("cameras.json");
-        let loaded = SettingsStore::load(&path).unwrap();
-        assert_eq!(loaded.cameras.len(), 2);
-        assert_eq!(loaded.cameras["dev-1"].name, "Camera One");
-        assert_eq!(loaded.cameras["dev-1"].controls["brightness"], 200);
-        assert_eq!(loaded.cameras["dev-2"].name, "Camera Two");
-        assert_eq!(loaded.cameras["dev-2"].controls["contrast"], 50);
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert!(loaded.tally_auto);
     }
 
     #[test]
-    fn save_creates_parent_directories() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("nested").join("deep").join("cameras.json");
-        let store = SettingsStore::new(path.clone());
-        store.set_control("dev-1", "Camera", "brightness", 100);
+    fn long_device_paths_persist_under_short_device_id_keys() {
+        use crate::camera::types::{CameraDevice, DeviceId, DeviceKind};
+
+        let dock = |port: u32| {
+            let path = format!(
+                r"\\?\usb#vid_046d&pid_085e&mi_00#9&{}&{port:04}#{{65e8773d-8f56-11d0-a3b9-00a0c9223196}}",
+                "2f1b3c4d&0&".repeat(25)
+            );
+            format!("{path}{}", "x".repeat(400 - path.len()))
+        };
+        let devices: Vec<CameraDevice> = [1, 2]
+            .iter()
+            .map(|&port| CameraDevice {
+                id: DeviceId::from_device_path(&dock(port)),
+                name: "Logitech BRIO".to_string(),
+                device_path: dock(port),
+                is_connected: true,
+                kind: DeviceKind::Primary,
+                primary_id: None,
+                identity: None,
+                suppressed_by: None,
+            })
+            .collect();
+
+        let (store, dir) = temp_store();
+        for (device, brightness) in devices.iter().zip([100, 200]) {
+            store.set_control(device.id.as_str(), &device.name, "brightness", brightness);
+            store.cache_controls(device, vec![], 1_000);
+        }
         store.save().unwrap();
 
-        assert!(path.exists());
+        let json = std::fs::read_to_string(dir.path().join("cameras.json")).unwrap();
+        let file: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let keys: Vec<&String> = file["cameras"].as_object().unwrap().keys().collect();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.iter().all(|k| k.len() < 32), "keys: {keys:?}");
+
+        let loaded = SettingsStore::new(dir.path().join("cameras.json"));
+        for (device, brightness) in devices.iter().zip([100, 200]) {
+            let camera = loaded.get_camera(device.id.as_str()).unwrap();
+            assert_eq!(camera.controls["brightness"].value, brightness);
+            // The full path survives as a value for cache validation
+            let cached = loaded.cached_controls(device.id.as_str()).unwrap();
+            assert_eq!(cached.device_path, device.device_path);
+        }
     }
 
     #[test]
-    fn save_is_atomic() {
+    fn default_camera_is_unset_until_chosen_and_persists() {
         let (store, dir) = temp_store();
-        store.set_control("dev-1", "Camera", "brightness", 100);
+        assert_eq!(store.default_camera(), None);
+
+        store.set_default_camera("brio");
         store.save().unwrap();
 
-        // After a successful save, no .tmp file should remain
-        let tmp_path = dir.path().join("cameras.json.tmp");
-        assert!(
-            !tmp_path.exists(),
-            ".tmp file should be cleaned up after rename"
-        );
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(loaded.default_camera.as_deref(), Some("brio"));
     }
 
     #[test]
-    fn new_loads_existing_file() {
+    fn jpeg_cache_limit_is_read_from_the_file() {
         let dir = TempDir::new().unwrap();
         let path = dir.path().join("cameras.json");
+        std::fs::write(&path, r#"{"cameras":{},"jpeg_cache_limit_mb":16}"#).unwrap();
 
-        // Write a settings file manually
-        let mut controls = HashMap::new();
-        controls.insert("brightness".to_string(), 200);
-        let mut cameras = HashMap::new();
-        cameras.insert(
-            "dev-1".to_string(),
-            CameraSettings {
-                name: "Pre-existing".to_string(),
-                controls,
-            },
-        );
-        let file = SettingsFile { cameras };
-        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
-
-        // SettingsStore::new should load it
         let store = SettingsStore::new(path);
-        let cam = store.get_camera("dev-1").unwrap();
-        assert_eq!(cam.name, "Pre-existing");
-        assert_eq!(cam.controls["brightness"], 200);
-    }
+        assert_eq!(store.jpeg_cache_limit_mb(), Some(16));
 
-    // --- In-memory operation tests (Step 4) ---
+        let (store, _dir) = temp_store();
+        assert_eq!(store.jpeg_cache_limit_mb(), None);
+    }
 
     #[test]
-    fn get_camera_returns_none_for_unknown() {
+    fn frame_payload_limit_is_read_from_the_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        std::fs::write(&path, r#"{"cameras":{},"max_frame_payload_kb":512}"#).unwrap();
+
+        let store = SettingsStore::new(path);
+        assert_eq!(store.max_frame_payload_kb(), Some(512));
+
         let (store, _dir) = temp_store();
-        assert!(store.get_camera("nonexistent").is_none());
+        assert_eq!(store.max_frame_payload_kb(), None);
     }
 
     #[test]
-    fn get_camera_returns_saved_settings() {
+    fn placeholder_name_only_when_enabled() {
         let (store, _dir) = temp_store();
-        store.set_control("dev-1", "Camera", "brightness", 128);
-        let cam = store.get_camera("dev-1").unwrap();
-        assert_eq!(cam.name, "Camera");
-        assert_eq!(cam.controls["brightness"], 128);
+        store.set_control("dev-1", "Desk Cam", "brightness", 100);
+        assert_eq!(store.placeholder_name("dev-1"), None);
+        assert_eq!(store.placeholder_name("dev-2"), None);
+
+        store.set_placeholder_on_error("dev-1", "Desk Cam", true);
+        assert_eq!(store.placeholder_name("dev-1").as_deref(), Some("Desk Cam"));
+        assert_eq!(store.get_camera("dev-1").unwrap().controls.len(), 1);
+
+        store.set_placeholder_on_error("dev-1", "Desk Cam", false);
+        assert_eq!(store.placeholder_name("dev-1"), None);
     }
 
     #[test]
-    fn set_control_creates_new_camera_entry() {
+    fn full_resolution_autostart_is_per_camera() {
         let (store, _dir) = temp_store();
-        assert!(store.get_camera("new-device").is_none());
-        store.set_control("new-device", "New Camera", "exposure", 50);
-        let cam = store.get_camera("new-device").unwrap();
-        assert_eq!(cam.name, "New Camera");
-        assert_eq!(cam.controls["exposure"], 50);
+        assert!(!store.full_resolution_autostart("dev-1"));
+
+        store.set_full_resolution_autostart("dev-1", "Desk Cam", true);
+        assert!(store.full_resolution_autostart("dev-1"));
+        assert!(!store.full_resolution_autostart("dev-2"));
+        assert_eq!(store.get_camera("dev-1").unwrap().name, "Desk Cam");
+
+        store.set_full_resolution_autostart("dev-1", "Desk Cam", false);
+        assert!(!store.full_resolution_autostart("dev-1"));
+    }
+
+    #[test]
+    fn preview_format_persists_per_camera() {
+        let (store, dir) = temp_store();
+        assert_eq!(store.preview_format("dev-1"), None);
+
+        let hd = PreviewFormat {
+            width: 1920,
+            height: 1080,
+            fps: 60.0,
+        };
+        store.set_preview_format("dev-1", "Desk Cam", hd);
+        assert_eq!(store.preview_format("dev-1"), Some(hd));
+        assert_eq!(store.preview_format("dev-2"), None);
+
+        store.save().unwrap();
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(loaded.cameras["dev-1"].preview_format, Some(hd));
+        assert_eq!(loaded.cameras["dev-1"].name, "Desk Cam");
     }
 
     #[test]
@@ -271,7 +1633,7 @@ mod tests {
         store.set_control("dev-1", "Camera", "brightness", 100);
         store.set_control("dev-1", "Camera", "brightness", 200);
         let cam = store.get_camera("dev-1").unwrap();
-        assert_eq!(cam.controls["brightness"], 200);
+        assert_eq!(cam.controls["brightness"].value, 200);
     }
 
     #[test]
@@ -285,7 +1647,7 @@ mod tests {
 
         let cam2 = store.get_camera("dev-2").unwrap();
         assert_eq!(cam2.name, "Camera Two");
-        assert_eq!(cam2.controls["contrast"], 50);
+        assert_eq!(cam2.controls["contrast"].value, 50);
     }
 
     #[test]
@@ -295,8 +1657,222 @@ mod tests {
         store.set_control("dev-1", "Camera", "contrast", 50);
 
         let cam = store.get_camera("dev-1").unwrap();
-        assert_eq!(cam.controls["brightness"], 100);
-        assert_eq!(cam.controls["contrast"], 50);
+        assert_eq!(cam.controls["brightness"].value, 100);
+        assert_eq!(cam.controls["contrast"].value, 50);
+    }
+
+    #[test]
+    fn set_control_auto_records_mode_and_value() {
+        let (store, _dir) = temp_store();
+        store.set_control_auto("dev-1", "Camera", "exposure", true, -6);
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.controls["exposure"].value, -6);
+        assert!(cam.controls["exposure"].auto);
+
+        // Writing a value puts the control back in manual mode
+        store.set_control("dev-1", "Camera", "exposure", -8);
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.controls["exposure"].value, -8);
+        assert!(!cam.controls["exposure"].auto);
+    }
+
+    #[test]
+    fn controls_record_their_source_and_time() {
+        let (store, _dir) = temp_store();
+        let before = unix_now();
+        store.set_applied_preset("dev-1", "Camera", "desk");
+        store.record_control(
+            "dev-1",
+            "Camera",
+            "brightness",
+            150,
+            ControlSource::Preset("desk".to_string()),
+        );
+        store.set_control("dev-1", "Camera", "contrast", 60);
+
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.preset.as_deref(), Some("desk"));
+        let brightness = &cam.controls["brightness"];
+        assert_eq!(brightness.source, ControlSource::Preset("desk".to_string()));
+        assert!(brightness.changed_at >= before);
+        assert_eq!(cam.controls["contrast"].source, ControlSource::Manual);
+
+        // A later tweak becomes manual again
+        store.set_control("dev-1", "Camera", "brightness", 160);
+        let cam = store.get_camera("dev-1").unwrap();
+        assert_eq!(cam.controls["brightness"].source, ControlSource::Manual);
+    }
+
+    #[test]
+    fn presets_persist_by_id() {
+        let (store, dir) = temp_store();
+        assert!(store.preset("desk").is_none());
+
+        let preset = Preset {
+            name: "Desk".to_string(),
+            controls: HashMap::from([("brightness".to_string(), 150)]),
+            ..Preset::default()
+        };
+        store.save_preset("desk", preset.clone());
+        store.save().unwrap();
+
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(loaded.presets["desk"], preset);
+        assert_eq!(store.preset("desk"), Some(preset));
+    }
+
+    #[test]
+    fn scenes_are_replaced_by_name_and_persist() {
+        use crate::scene::types::{SceneAction, SceneCamera};
+
+        let (store, dir) = temp_store();
+        let scene = |name: &str, action: SceneAction| Scene {
+            name: name.to_string(),
+            cameras: vec![SceneCamera {
+                device_id: "dev-1".to_string(),
+                actions: vec![action],
+            }],
+        };
+        store.save_scene(scene("Interview", SceneAction::StartPreview));
+        store.save_scene(scene("Break", SceneAction::PausePreview));
+        store.save_scene(scene("interview", SceneAction::StopPreview));
+
+        let names: Vec<_> = store.scenes().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["interview", "Break"]);
+        assert_eq!(
+            store.scene("INTERVIEW").unwrap().cameras[0].actions,
+            [SceneAction::StopPreview]
+        );
+
+        store.save().unwrap();
+        let reopened = SettingsStore::new(dir.path().join("cameras.json"));
+        assert_eq!(reopened.scenes(), store.scenes());
+
+        assert!(store.delete_scene("break"));
+        assert!(!store.delete_scene("break"));
+        assert!(store.scene("Break").is_none());
+    }
+
+    #[test]
+    fn presets_are_listed_by_id_and_deleted() {
+        let (store, dir) = temp_store();
+        let named = |name: &str| Preset {
+            name: name.to_string(),
+            ..Preset::default()
+        };
+        store.save_preset("night", named("Night"));
+        store.save_preset("desk", named("Desk"));
+
+        let ids: Vec<_> = store.presets().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["desk", "night"]);
+
+        assert!(store.delete_preset("desk"));
+        assert!(!store.delete_preset("desk"));
+        assert!(store.preset("desk").is_none());
+
+        store.save().unwrap();
+        let loaded = SettingsStore::load(&dir.path().join("cameras.json")).unwrap();
+        assert_eq!(loaded.presets.len(), 1);
+        assert!(loaded.presets.contains_key("night"));
+    }
+
+    #[test]
+    fn presets_are_found_by_id_then_name() {
+        let (store, _dir) = temp_store();
+        let named = |name: &str| Preset {
+            name: name.to_string(),
+            ..Preset::default()
+        };
+        store.save_preset("desk", named("Evening"));
+        store.save_preset("evening", named("Desk"));
+
+        // An ID match wins over a name match
+        assert_eq!(store.find_preset_id("desk").as_deref(), Some("desk"));
+        assert_eq!(store.find_preset_id("EVENING").as_deref(), Some("desk"));
+        assert!(store.find_preset_id("night").is_none());
+    }
+
+    #[test]
+    fn key_bindings_default_until_changed_and_persist() {
+        let (store, dir) = temp_store();
+        assert_eq!(store.key_bindings(), default_bindings());
+
+        let bindings = vec![KeyBinding {
+            key: "]".to_string(),
+            control_id: "focus".to_string(),
+            up: true,
+        }];
+        store.set_key_bindings(Some(bindings.clone()));
+        store.save().unwrap();
+        let reopened = SettingsStore::new(dir.path().join("cameras.json"));
+        assert_eq!(reopened.key_bindings(), bindings);
+
+        reopened.set_key_bindings(None);
+        assert_eq!(reopened.key_bindings(), default_bindings());
+    }
+
+    #[test]
+    fn control_api_token_is_generated_once() {
+        let (store, _dir) = temp_store();
+        let first = store.control_api_token_or(|| "first".to_string());
+        assert!(store.saves.take_pending());
+
+        let second = store.control_api_token_or(|| "second".to_string());
+        assert_eq!(first, "first");
+        assert_eq!(second, "first");
+        assert!(!store.saves.is_pending());
+    }
+
+    #[test]
+    fn midi_mappings_replace_by_channel_and_cc() {
+        let (store, _dir) = temp_store();
+        let mapping = |channel: u8, control_id: &str| MidiMapping {
+            midi_channel: channel,
+            cc_number: 7,
+            device_id: "dev-1".to_string(),
+            control_id: control_id.to_string(),
+            min: 0,
+            max: 255,
+        };
+        store.set_midi_mapping(mapping(0, "brightness"));
+        store.set_midi_mapping(mapping(1, "contrast"));
+        store.set_midi_mapping(mapping(0, "zoom"));
+        assert!(store.saves.take_pending());
+
+        let mut controls: Vec<_> = store
+            .midi_mappings()
+            .into_iter()
+            .map(|m| (m.midi_channel, m.control_id))
+            .collect();
+        controls.sort();
+        assert_eq!(
+            controls,
+            vec![(0, "zoom".to_string()), (1, "contrast".to_string())]
+        );
+
+        assert!(store.remove_midi_mapping(1, 7));
+        assert!(store.saves.take_pending());
+        assert!(!store.remove_midi_mapping(1, 7));
+        assert!(!store.saves.is_pending());
+        assert_eq!(store.midi_mappings().len(), 1);
+    }
+
+    #[test]
+    fn legacy_file_is_rewritten_with_modes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cameras.json");
+        let legacy = r#"{"cameras":{"dev-1":{"name":"Cam","controls":{"exposure":-8}}}}"#;
+        std::fs::write(&path, legacy).unwrap();
+
+        let store = SettingsStore::new(path.clone());
+        store.save().unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            json["cameras"]["dev-1"]["controls"]["exposure"],
+            serde_json::json!({ "value": -8, "auto": false })
+        );
     }
 
     #[test]
@@ -331,18 +1907,72 @@ mod tests {
     #[test]
     fn set_control_sets_dirty_flag() {
         let (store, _dir) = temp_store();
-        assert!(!store.is_dirty.load(Ordering::Acquire));
+        assert!(!store.saves.is_pending());
+        store.set_control("dev-1", "Camera", "brightness", 100);
+        assert!(store.saves.is_pending());
+    }
+
+    #[test]
+    fn schedule_persists_and_survives_a_reset() {
+        let (store, dir) = temp_store();
+        let rules: Vec<ScheduleRule> = serde_json::from_str(
+            r#"[{"presetId":"night","startTime":"20:00"},{"presetId":"day","startTime":"07:00"}]"#,
+        )
+        .unwrap();
         store.set_control("dev-1", "Camera", "brightness", 100);
-        assert!(store.is_dirty.load(Ordering::Acquire));
+        store.set_schedule("dev-1", rules.clone());
+        assert!(store.schedule("dev-2").is_empty());
+
+        // Resetting a camera clears its controls, not its schedule
+        store.remove_camera("dev-1");
+        store.save().unwrap();
+        let loaded = SettingsStore::new(dir.path().join("cameras.json"));
+        assert_eq!(loaded.schedule("dev-1"), rules);
+        assert_eq!(loaded.schedules(), vec![("dev-1".to_string(), rules)]);
+
+        loaded.set_schedule("dev-1", vec![]);
+        assert!(loaded.schedules().is_empty());
+    }
+
+    #[test]
+    fn reconcile_names_renames_saved_cameras_and_keeps_settings() {
+        use crate::camera::types::{DeviceId, DeviceKind};
+
+        let (store, _dir) = temp_store();
+        store.set_control("dev-1", "USB Camera", "brightness", 100);
+        store.saves.take_pending();
+
+        let device = CameraDevice {
+            id: DeviceId::new("dev-1"),
+            name: "Logitech BRIO".to_string(),
+            device_path: "path-1".to_string(),
+            is_connected: true,
+            kind: DeviceKind::Primary,
+            primary_id: None,
+            identity: None,
+            suppressed_by: None,
+        };
+        let renames = store.reconcile_names(std::slice::from_ref(&device));
+        assert_eq!(renames.len(), 1);
+        assert_eq!(renames[0].old_name, "USB Camera");
+        assert!(store.saves.take_pending());
+
+        let camera = store.get_camera("dev-1").unwrap();
+        assert_eq!(camera.name, "Logitech BRIO");
+        assert_eq!(camera.controls["brightness"].value, 100);
+
+        // A second pass has nothing to do and leaves the store clean
+        assert!(store.reconcile_names(&[device]).is_empty());
+        assert!(!store.saves.is_pending());
     }
 
     #[test]
     fn remove_camera_sets_dirty_flag() {
         let (store, _dir) = temp_store();
         store.set_control("dev-1", "Camera", "brightness", 100);
-        store.is_dirty.store(false, Ordering::Release);
+        store.saves.take_pending();
         store.remove_camera("dev-1");
-        assert!(store.is_dirty.load(Ordering::Acquire));
+        assert!(store.saves.is_pending());
     }
 
     #[test]
@@ -352,15 +1982,15 @@ mod tests {
         // Simulate the debounce drain loop: set dirty, swap-and-save, then
         // make a new change before the loop re-checks the flag.
         store.set_control("dev-1", "Camera", "brightness", 100);
-        assert!(store.is_dirty.swap(false, Ordering::AcqRel));
+        assert!(store.saves.take_pending());
         store.save().unwrap();
 
         // Simulate a change arriving during the save-to-recheck gap
         store.set_control("dev-1", "Camera", "brightness", 200);
 
-        // The drain loop re-checks is_dirty — it must still be true
+        // The drain loop re-checks the flag — it must still be set
         assert!(
-            store.is_dirty.swap(false, Ordering::AcqRel),
+            store.saves.take_pending(),
             "change during save must keep dirty flag set"
         );
         store.save().unwrap();
@@ -368,6 +1998,94 @@ mod tests {
         // Verify the latest value was persisted
         let path = dir.path().join("cameras.json");
         let loaded = SettingsStore::load(&path).unwrap();
-        assert_eq!(loaded.cameras["dev-1"].controls["brightness"], 200);
+        assert_eq!(loaded.cameras["dev-1"].controls["brightness"].value, 200);
+    }
+
+    // --- Export and import ---
+
+    fn day_preset() -> Preset {
+        Preset {
+            name: "Day".to_string(),
+            controls: HashMap::from([("brightness".to_string(), 140)]),
+            ..Preset::default()
+        }
+    }
+
+    #[test]
+    fn exported_settings_import_into_another_store() {
+        let (store, dir) = temp_store();
+        store.set_control("cam-1", "Desk", "brightness", 150);
+        store.set_control("cam-2", "Overhead", "zoom", 200);
+        store.save_preset("day", day_preset());
+        let path = dir.path().join("backup").join("export.json");
+        store.export_to(&path).unwrap();
+
+        let exported: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(exported["version"], crate::settings::export::EXPORT_VERSION);
+
+        let (other, _other_dir) = temp_store();
+        let imported = other.import_from(&path, false).unwrap();
+        assert_eq!(imported, ["cam-1", "cam-2"]);
+        assert_eq!(other.cameras(), store.cameras());
+        assert_eq!(other.presets(), store.presets());
+    }
+
+    #[test]
+    fn importing_replaces_or_merges() {
+        let (source, dir) = temp_store();
+        source.set_control("cam-2", "Overhead", "zoom", 250);
+        let path = dir.path().join("export.json");
+        source.export_to(&path).unwrap();
+
+        let seeded = || {
+            let (store, dir) = temp_store();
+            store.set_control("cam-1", "Desk", "brightness", 150);
+            store.set_control("cam-2", "Overhead", "zoom", 100);
+            store.save_preset("day", day_preset());
+            (store, dir)
+        };
+
+        let (merged, _merged_dir) = seeded();
+        merged.import_from(&path, true).unwrap();
+        assert_eq!(
+            merged.get_camera("cam-1").unwrap().controls["brightness"].value,
+            150
+        );
+        assert_eq!(
+            merged.get_camera("cam-2").unwrap().controls["zoom"].value,
+            250
+        );
+        assert_eq!(merged.presets().len(), 1);
+
+        let (replaced, _replaced_dir) = seeded();
+        replaced.import_from(&path, false).unwrap();
+        assert!(replaced.get_camera("cam-1").is_none());
+        assert_eq!(
+            replaced.get_camera("cam-2").unwrap().controls["zoom"].value,
+            250
+        );
+        assert!(replaced.presets().is_empty());
+    }
+
+    #[test]
+    fn rejected_imports_leave_the_settings_alone() {
+        let (store, dir) = temp_store();
+        store.set_control("cam-1", "Desk", "brightness", 150);
+        let before = store.cameras();
+
+        let corrupt = dir.path().join("corrupt.json");
+        std::fs::write(&corrupt, "{\"version\": 1, \"settings\": {\"cameras\"").unwrap();
+        let err = store.import_from(&corrupt, false).unwrap_err();
+        assert!(err.contains("isn't valid JSON"), "{err}");
+
+        let newer = dir.path().join("newer.json");
+        std::fs::write(&newer, r#"{"version": 99, "settings": {"cameras": {}}}"#).unwrap();
+        let err = store.import_from(&newer, false).unwrap_err();
+        assert!(err.contains("version 99"), "{err}");
+
+        let missing = dir.path().join("missing.json");
+        assert!(store.import_from(&missing, true).is_err());
+        assert_eq!(store.cameras(), before);
     }
 }
@@ -0,0 +1,622 @@
+//! Merging per-camera settings between machines that share a folder.
+//!
+//! Each machine keeps a snapshot of every camera's saved controls, stamped
+//! per control with a Lamport revision and the machine that wrote it. A
+//! local change takes the next revision after the highest the machine has
+//! seen; a deleted control becomes a tombstone, so the deletion can win over
+//! older values elsewhere. Merging keeps, for every control, the entry with
+//! the higher revision, breaking ties on machine ID so both sides pick the
+//! same winner. Ties between different values are concurrent edits and are
+//! reported as conflicts.
+//!
+//! Everything here is pure; reading and writing the shared folder lives in
+//! `sync_dir`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::types::{CameraSettings, SavedControl};
+
+/// One control's synced state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedControl {
+    pub revision: u64,
+    /// Machine that made the change.
+    pub machine_id: String,
+    /// The saved control, or `None` once it was deleted (a tombstone).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved: Option<SavedControl>,
+}
+
+impl SyncedControl {
+    /// Whether this entry wins a merge against `other`.
+    pub fn wins_over(&self, other: &Self) -> bool {
+        (self.revision, &self.machine_id) > (other.revision, &other.machine_id)
+    }
+}
+
+/// A camera's synced controls, as one machine last knew them. Written to the
+/// shared folder as one file per camera.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSnapshot {
+    pub device_id: String,
+    pub camera_name: String,
+    /// Machine that wrote the snapshot.
+    pub machine_id: String,
+    /// Lamport clock: the highest revision seen for this camera.
+    pub revision: u64,
+    pub controls: BTreeMap<String, SyncedControl>,
+}
+
+impl DeviceSnapshot {
+    pub fn new(device_id: &str, camera_name: &str, machine_id: &str) -> Self {
+        Self {
+            device_id: device_id.to_string(),
+            camera_name: camera_name.to_string(),
+            machine_id: machine_id.to_string(),
+            revision: 0,
+            controls: BTreeMap::new(),
+        }
+    }
+
+    /// Saved controls, without tombstones.
+    pub fn saved_controls(&self) -> impl Iterator<Item = (&String, &SavedControl)> {
+        self.controls
+            .iter()
+            .filter_map(|(id, entry)| entry.saved.as_ref().map(|saved| (id, saved)))
+    }
+}
+
+/// Bring `previous` (the machine's last snapshot, if any) up to date with the
+/// camera's saved `settings` (`None` if it has none any more).
+///
+/// Every control that differs from the snapshot gets the next revision,
+/// and controls that disappeared become tombstones. Returns `None` if
+/// nothing changed.
+pub fn record_local(
+    previous: Option<&DeviceSnapshot>,
+    device_id: &str,
+    settings: Option<&CameraSettings>,
+    machine_id: &str,
+) -> Option<DeviceSnapshot> {
+    let mut snapshot = match previous {
+        Some(previous) => previous.clone(),
+        None => DeviceSnapshot::new(device_id, "", machine_id),
+    };
+    let mut changed = previous.is_none() && settings.is_some();
+
+    if let Some(settings) = settings {
+        if snapshot.camera_name != settings.name {
+            snapshot.camera_name = settings.name.clone();
+            changed = true;
+        }
+        // Sorted so revisions are assigned in a stable order
+        let sorted: BTreeMap<_, _> = settings.controls.iter().collect();
+        for (control_id, saved) in sorted {
+            let current = snapshot.controls.get(control_id);
+            if current.and_then(|c| c.saved.as_ref()) == Some(saved) {
+                continue;
+            }
+            snapshot.revision += 1;
+            snapshot.controls.insert(
+                control_id.clone(),
+                SyncedControl {
+                    revision: snapshot.revision,
+                    machine_id: machine_id.to_string(),
+                    saved: Some(saved.clone()),
+                },
+            );
+            changed = true;
+        }
+    }
+
+    let removed: Vec<String> = snapshot
+        .saved_controls()
+        .map(|(id, _)| id)
+        .filter(|id| settings.map_or(true, |s| !s.controls.contains_key(*id)))
+        .cloned()
+        .collect();
+    for control_id in removed {
+        snapshot.revision += 1;
+        snapshot.controls.insert(
+            control_id,
+            SyncedControl {
+                revision: snapshot.revision,
+                machine_id: machine_id.to_string(),
+                saved: None,
+            },
+        );
+        changed = true;
+    }
+
+    if !changed {
+        return None;
+    }
+    snapshot.machine_id = machine_id.to_string();
+    Some(snapshot)
+}
+
+/// A control the remote side changed, to be applied locally: the new saved
+/// control, or `None` to delete it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundChange {
+    pub control_id: String,
+    pub saved: Option<SavedControl>,
+}
+
+/// Two machines changed a control at the same revision. `kept` won on
+/// machine ID; `discarded` is lost.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub control_id: String,
+    pub kept: SyncedControl,
+    pub discarded: SyncedControl,
+}
+
+/// The result of merging a remote snapshot into the local one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    /// The merged snapshot: the new local state.
+    pub snapshot: DeviceSnapshot,
+    /// Remote changes to apply to the local settings.
+    pub inbound: Vec<InboundChange>,
+    pub conflicts: Vec<SyncConflict>,
+    /// Whether the local side had changes the remote lacks, so the merged
+    /// snapshot should be written back.
+    pub outbound: bool,
+}
+
+/// Merge `remote` into `local`, keeping the winning entry for every control.
+///
+/// The merged clock is the higher of the two, so the next local change is
+/// ordered after everything seen. The result is the same whichever side
+/// runs the merge.
+pub fn merge(local: &DeviceSnapshot, remote: &DeviceSnapshot) -> Merge {
+    let mut merged = local.clone();
+    merged.revision = local.revision.max(remote.revision);
+    let mut inbound = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut outbound = local
+        .controls
+        .keys()
+        .any(|id| !remote.controls.contains_key(id));
+
+    for (control_id, theirs) in &remote.controls {
+        let Some(ours) = local.controls.get(control_id) else {
+            merged.controls.insert(control_id.clone(), theirs.clone());
+            // A tombstone for a control never seen here has nothing to delete
+            if theirs.saved.is_some() {
+                inbound.push(InboundChange {
+                    control_id: control_id.clone(),
+                    saved: theirs.saved.clone(),
+                });
+            }
+            continue;
+        };
+        if ours == theirs {
+            continue;
+        }
+
+        let remote_wins = theirs.wins_over(ours);
+        let (kept, discarded) = if remote_wins {
+            (theirs, ours)
+        } else {
+            (ours, theirs)
+        };
+        if ours.revision == theirs.revision && ours.saved != theirs.saved {
+            conflicts.push(SyncConflict {
+                control_id: control_id.clone(),
+                kept: kept.clone(),
+                discarded: discarded.clone(),
+            });
+        }
+        if remote_wins {
+            merged.controls.insert(control_id.clone(), theirs.clone());
+            if ours.saved != theirs.saved {
+                inbound.push(InboundChange {
+                    control_id: control_id.clone(),
+                    saved: theirs.saved.clone(),
+                });
+            }
+        } else {
+            outbound = true;
+        }
+    }
+
+    // The name follows whichever side has seen more changes
+    if remote.revision > local.revision && !remote.camera_name.is_empty() {
+        merged.camera_name = remote.camera_name.clone();
+    }
+    outbound |= merged.revision != remote.revision || merged.camera_name != remote.camera_name;
+
+    Merge {
+        snapshot: merged,
+        inbound,
+        conflicts,
+        outbound,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::types::ControlSource;
+    use std::collections::HashMap;
+
+    fn saved(value: i32) -> SavedControl {
+        SavedControl {
+            value,
+            auto: false,
+            source: ControlSource::Manual,
+            changed_at: 0,
+        }
+    }
+
+    fn settings(controls: &[(&str, i32)]) -> CameraSettings {
+        CameraSettings {
+            name: "Brio".to_string(),
+            controls: controls
+                .iter()
+                .map(|&(id, value)| (id.to_string(), saved(value)))
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    fn local(machine: &str, controls: &[(&str, i32)]) -> DeviceSnapshot {
+        record_local(None, "dev-1", Some(&settings(controls)), machine).unwrap()
+    }
+
+    fn value_of(snapshot: &DeviceSnapshot, control_id: &str) -> Option<i32> {
+        snapshot.controls[control_id]
+            .saved
+            .as_ref()
+            .map(|s| s.value)
+    }
+
+    #[test]
+    fn first_recording_numbers_controls_in_order() {
+        let snapshot = local("desk", &[("zoom", 3), ("brightness", 120)]);
+        assert_eq!(snapshot.revision, 2);
+        assert_eq!(snapshot.controls["brightness"].revision, 1);
+        assert_eq!(snapshot.controls["zoom"].revision, 2);
+        assert_eq!(snapshot.camera_name, "Brio");
+        assert!(snapshot.controls.values().all(|c| c.machine_id == "desk"));
+    }
+
+    #[test]
+    fn unchanged_settings_record_nothing() {
+        let snapshot = local("desk", &[("zoom", 3)]);
+        let same = settings(&[("zoom", 3)]);
+        assert_eq!(
+            record_local(Some(&snapshot), "dev-1", Some(&same), "desk"),
+            None
+        );
+        assert_eq!(record_local(None, "dev-1", None, "desk"), None);
+    }
+
+    #[test]
+    fn only_changed_controls_take_a_new_revision() {
+        let before = local("desk", &[("zoom", 3), ("brightness", 120)]);
+        let after = record_local(
+            Some(&before),
+            "dev-1",
+            Some(&settings(&[("zoom", 4), ("brightness", 120)])),
+            "desk",
+        )
+        .unwrap();
+        assert_eq!(after.revision, 3);
+        assert_eq!(after.controls["zoom"].revision, 3);
+        assert_eq!(after.controls["brightness"].revision, 1);
+    }
+
+    #[test]
+    fn a_switch_to_auto_is_a_change() {
+        let before = local("desk", &[("focus", 40)]);
+        let mut auto = settings(&[("focus", 40)]);
+        auto.controls.get_mut("focus").unwrap().auto = true;
+        let after = record_local(Some(&before), "dev-1", Some(&auto), "desk").unwrap();
+        assert!(after.controls["focus"].saved.as_ref().unwrap().auto);
+    }
+
+    #[test]
+    fn removed_controls_become_tombstones() {
+        let before = local("desk", &[("zoom", 3), ("brightness", 120)]);
+        let after = record_local(
+            Some(&before),
+            "dev-1",
+            Some(&settings(&[("brightness", 120)])),
+            "desk",
+        )
+        .unwrap();
+        assert_eq!(after.controls["zoom"].saved, None);
+        assert_eq!(after.controls["zoom"].revision, 3);
+
+        // A tombstone isn't deleted again
+        let again = record_local(
+            Some(&after),
+            "dev-1",
+            Some(&settings(&[("brightness", 120)])),
+            "desk",
+        );
+        assert_eq!(again, None);
+    }
+
+    #[test]
+    fn a_removed_camera_tombstones_every_control() {
+        let before = local("desk", &[("zoom", 3), ("brightness", 120)]);
+        let after = record_local(Some(&before), "dev-1", None, "desk").unwrap();
+        assert_eq!(after.saved_controls().count(), 0);
+        assert_eq!(after.controls.len(), 2);
+    }
+
+    #[test]
+    fn a_control_recreated_after_deletion_revives() {
+        let before = local("desk", &[("zoom", 3)]);
+        let deleted = record_local(Some(&before), "dev-1", Some(&settings(&[])), "desk").unwrap();
+        let revived = record_local(
+            Some(&deleted),
+            "dev-1",
+            Some(&settings(&[("zoom", 5)])),
+            "desk",
+        )
+        .unwrap();
+        assert_eq!(value_of(&revived, "zoom"), Some(5));
+        assert_eq!(revived.controls["zoom"].revision, 3);
+    }
+
+    #[test]
+    fn newer_remote_values_come_in() {
+        let ours = local("desk", &[("zoom", 3)]);
+        let theirs = record_local(
+            Some(&ours),
+            "dev-1",
+            Some(&settings(&[("zoom", 7)])),
+            "laptop",
+        )
+        .unwrap();
+
+        let merge = merge(&ours, &theirs);
+        assert_eq!(
+            merge.inbound,
+            vec![InboundChange {
+                control_id: "zoom".to_string(),
+                saved: Some(saved(7)),
+            }]
+        );
+        assert_eq!(value_of(&merge.snapshot, "zoom"), Some(7));
+        assert!(merge.conflicts.is_empty());
+        assert!(!merge.outbound);
+    }
+
+    #[test]
+    fn newer_local_values_go_out() {
+        let theirs = local("laptop", &[("zoom", 3)]);
+        let ours = record_local(
+            Some(&theirs),
+            "dev-1",
+            Some(&settings(&[("zoom", 9)])),
+            "desk",
+        )
+        .unwrap();
+
+        let merge = merge(&ours, &theirs);
+        assert!(merge.inbound.is_empty());
+        assert!(merge.outbound);
+        assert_eq!(value_of(&merge.snapshot, "zoom"), Some(9));
+    }
+
+    #[test]
+    fn controls_merge_independently() {
+        let base = local("desk", &[("zoom", 3), ("brightness", 120)]);
+        let ours = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 4), ("brightness", 120)])),
+            "desk",
+        )
+        .unwrap();
+        let theirs = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 3), ("brightness", 150)])),
+            "laptop",
+        )
+        .unwrap();
+        // Both changes sit at revision 3, but on different controls
+        let merge = merge(&ours, &theirs);
+        assert_eq!(value_of(&merge.snapshot, "zoom"), Some(4));
+        assert_eq!(value_of(&merge.snapshot, "brightness"), Some(150));
+        assert!(merge.conflicts.is_empty());
+        assert!(merge.outbound);
+    }
+
+    #[test]
+    fn concurrent_edits_conflict_and_resolve_on_machine_id() {
+        let base = local("desk", &[("zoom", 3)]);
+        let desk = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 4)])),
+            "desk",
+        )
+        .unwrap();
+        let laptop = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 5)])),
+            "laptop",
+        )
+        .unwrap();
+
+        let at_desk = merge(&desk, &laptop);
+        let at_laptop = merge(&laptop, &desk);
+        // "laptop" sorts after "desk", so both sides keep the laptop's value
+        assert_eq!(value_of(&at_desk.snapshot, "zoom"), Some(5));
+        assert_eq!(value_of(&at_laptop.snapshot, "zoom"), Some(5));
+        assert_eq!(at_desk.conflicts.len(), 1);
+        assert_eq!(at_desk.conflicts[0].kept.machine_id, "laptop");
+        assert_eq!(
+            at_desk.conflicts[0].discarded.saved.as_ref().unwrap().value,
+            4
+        );
+        assert_eq!(at_laptop.conflicts.len(), 1);
+        assert!(at_laptop.inbound.is_empty());
+    }
+
+    #[test]
+    fn identical_concurrent_edits_are_not_conflicts() {
+        let base = local("desk", &[("zoom", 3)]);
+        let desk = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 4)])),
+            "desk",
+        )
+        .unwrap();
+        let laptop = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 4)])),
+            "laptop",
+        )
+        .unwrap();
+
+        let merge = merge(&desk, &laptop);
+        assert!(merge.conflicts.is_empty());
+        assert!(merge.inbound.is_empty());
+    }
+
+    #[test]
+    fn remote_tombstones_delete_older_values() {
+        let ours = local("desk", &[("zoom", 3), ("brightness", 120)]);
+        let theirs = record_local(
+            Some(&ours),
+            "dev-1",
+            Some(&settings(&[("brightness", 120)])),
+            "laptop",
+        )
+        .unwrap();
+
+        let merge = merge(&ours, &theirs);
+        assert_eq!(
+            merge.inbound,
+            vec![InboundChange {
+                control_id: "zoom".to_string(),
+                saved: None,
+            }]
+        );
+        assert_eq!(merge.snapshot.saved_controls().count(), 1);
+    }
+
+    #[test]
+    fn newer_values_beat_older_tombstones() {
+        let base = local("desk", &[("zoom", 3)]);
+        let deleted = record_local(Some(&base), "dev-1", Some(&settings(&[])), "laptop").unwrap();
+        let changed_twice = record_local(
+            Some(
+                &record_local(
+                    Some(&base),
+                    "dev-1",
+                    Some(&settings(&[("zoom", 4)])),
+                    "desk",
+                )
+                .unwrap(),
+            ),
+            "dev-1",
+            Some(&settings(&[("zoom", 5)])),
+            "desk",
+        )
+        .unwrap();
+
+        let merge = merge(&changed_twice, &deleted);
+        assert_eq!(value_of(&merge.snapshot, "zoom"), Some(5));
+        assert!(merge.inbound.is_empty());
+        assert!(merge.outbound);
+    }
+
+    #[test]
+    fn controls_only_the_remote_has_come_in() {
+        let ours = local("desk", &[("zoom", 3)]);
+        let theirs = local("laptop", &[("zoom", 3), ("focus", 40)]);
+
+        let merge = merge(&ours, &theirs);
+        assert!(merge
+            .inbound
+            .iter()
+            .any(|c| c.control_id == "focus" && c.saved == Some(saved(40))));
+        assert!(merge.snapshot.controls.contains_key("focus"));
+    }
+
+    #[test]
+    fn merging_advances_the_clock() {
+        let ours = local("desk", &[("zoom", 3)]);
+        let mut theirs = local("laptop", &[("zoom", 3)]);
+        theirs.revision = 40;
+        theirs.controls.get_mut("zoom").unwrap().revision = 40;
+
+        let merged = merge(&ours, &theirs).snapshot;
+        assert_eq!(merged.revision, 40);
+        let next = record_local(
+            Some(&merged),
+            "dev-1",
+            Some(&settings(&[("zoom", 8)])),
+            "desk",
+        )
+        .unwrap();
+        assert_eq!(next.controls["zoom"].revision, 41);
+    }
+
+    #[test]
+    fn merging_an_identical_snapshot_changes_nothing() {
+        let ours = local("desk", &[("zoom", 3)]);
+        let merge = merge(&ours, &ours.clone());
+        assert_eq!(merge.snapshot, ours);
+        assert!(merge.inbound.is_empty());
+        assert!(merge.conflicts.is_empty());
+        assert!(!merge.outbound);
+    }
+
+    #[test]
+    fn merging_is_commutative_on_the_result() {
+        let base = local("desk", &[("zoom", 3), ("brightness", 120), ("focus", 10)]);
+        let desk = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 4), ("brightness", 120)])),
+            "desk",
+        )
+        .unwrap();
+        let laptop = record_local(
+            Some(&base),
+            "dev-1",
+            Some(&settings(&[("zoom", 3), ("brightness", 90), ("focus", 12)])),
+            "laptop",
+        )
+        .unwrap();
+
+        let a = merge(&desk, &laptop).snapshot;
+        let b = merge(&laptop, &desk).snapshot;
+        assert_eq!(a.controls, b.controls);
+        assert_eq!(a.revision, b.revision);
+    }
+
+    #[test]
+    fn snapshots_roundtrip_through_json() {
+        let snapshot = record_local(
+            Some(&local("desk", &[("zoom", 3)])),
+            "dev-1",
+            Some(&settings(&[])),
+            "desk",
+        )
+        .unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("\"machineId\""));
+        let restored: DeviceSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, snapshot);
+    }
+}
@@ -0,0 +1,312 @@
+//! Syncing the settings store through a shared folder.
+//!
+//! The folder holds one snapshot file per camera, written atomically by
+//! whichever machine last had something to add. A sync pass records local
+//! changes, merges each camera's file into the local snapshot (see `sync`),
+//! applies what other machines changed and writes the file back if this
+//! machine had changes the file lacks.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::settings::persist::write_json_atomic;
+use crate::settings::store::SettingsStore;
+use crate::settings::sync::{merge, record_local, DeviceSnapshot, SyncConflict};
+
+/// Extension of snapshot files. Atomic writes go through a `.json.tmp` file
+/// first, which doesn't match.
+pub const SNAPSHOT_EXTENSION: &str = "json";
+
+/// What a sync pass did for one camera.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSync {
+    pub device_id: String,
+    pub camera_name: String,
+    /// Controls changed by another machine and applied here.
+    pub inbound: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+    /// Whether the camera's snapshot file was written.
+    pub written: bool,
+}
+
+/// File name of a camera's snapshot. Characters that aren't safe in file
+/// names everywhere (device IDs contain `:`) are percent-encoded.
+pub fn snapshot_file_name(device_id: &str) -> String {
+    let mut name = String::with_capacity(device_id.len() + 5);
+    for byte in device_id.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.') {
+            name.push(char::from(byte));
+        } else {
+            name.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    name.push('.');
+    name.push_str(SNAPSHOT_EXTENSION);
+    name
+}
+
+/// Whether `path` is a camera snapshot file.
+pub fn is_snapshot_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(SNAPSHOT_EXTENSION))
+}
+
+/// Every readable snapshot in `dir`, by device ID. Unreadable files (a sync
+/// client's half-written copy, say) are logged and skipped; they're read
+/// again on the next pass.
+pub fn read_snapshots(dir: &Path) -> Result<HashMap<String, DeviceSnapshot>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let mut snapshots = HashMap::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if !is_snapshot_file(&path) {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                serde_json::from_str::<DeviceSnapshot>(&json).map_err(|e| e.to_string())
+            });
+        match parsed {
+            Ok(snapshot) => {
+                snapshots.insert(snapshot.device_id.clone(), snapshot);
+            }
+            Err(e) => tracing::warn!("Skipping sync file {}: {e}", path.display()),
+        }
+    }
+    Ok(snapshots)
+}
+
+/// Write a camera's snapshot into `dir` atomically.
+pub fn write_snapshot(dir: &Path, snapshot: &DeviceSnapshot) -> Result<PathBuf, String> {
+    let path = dir.join(snapshot_file_name(&snapshot.device_id));
+    write_json_atomic(&path, snapshot)?;
+    Ok(path)
+}
+
+/// Sync one camera against its file's snapshot (`None` if it has none).
+/// Returns `None` if neither side knows the camera.
+pub fn sync_device(
+    store: &SettingsStore,
+    dir: &Path,
+    device_id: &str,
+    remote: Option<&DeviceSnapshot>,
+    machine_id: &str,
+) -> Result<Option<DeviceSync>, String> {
+    let previous = store.sync_snapshot(device_id);
+    let recorded = record_local(
+        previous.as_ref(),
+        device_id,
+        store.get_camera(device_id).as_ref(),
+        machine_id,
+    );
+    let local = match recorded.or(previous.clone()) {
+        Some(local) => local,
+        None if remote.is_some() => DeviceSnapshot::new(device_id, "", machine_id),
+        None => return Ok(None),
+    };
+
+    let (mut snapshot, inbound, conflicts, outbound) = match remote {
+        Some(remote) => {
+            let merged = merge(&local, remote);
+            (
+                merged.snapshot,
+                merged.inbound,
+                merged.conflicts,
+                merged.outbound,
+            )
+        }
+        None => (local, Vec::new(), Vec::new(), true),
+    };
+
+    for conflict in &conflicts {
+        tracing::warn!(
+            "Sync conflict on '{}' for {device_id}: kept {:?} from {}, discarded {:?} from {}",
+            conflict.control_id,
+            conflict.kept.saved.as_ref().map(|s| s.value),
+            conflict.kept.machine_id,
+            conflict.discarded.saved.as_ref().map(|s| s.value),
+            conflict.discarded.machine_id,
+        );
+    }
+
+    let written = outbound;
+    if written {
+        snapshot.machine_id = machine_id.to_string();
+        write_snapshot(dir, &snapshot)?;
+    }
+    let camera_name = snapshot.camera_name.clone();
+    if previous.as_ref() != Some(&snapshot) || !inbound.is_empty() {
+        store.apply_synced(snapshot, &inbound);
+    }
+
+    Ok(Some(DeviceSync {
+        device_id: device_id.to_string(),
+        camera_name,
+        inbound: inbound.into_iter().map(|c| c.control_id).collect(),
+        conflicts,
+        written,
+    }))
+}
+
+/// Sync every camera known locally or in `dir`. Returns the cameras where
+/// something happened; failures are logged and retried on the next pass.
+pub fn sync_all(store: &SettingsStore, dir: &Path, machine_id: &str) -> Vec<DeviceSync> {
+    let remote = match read_snapshots(dir) {
+        Ok(remote) => remote,
+        Err(e) => {
+            tracing::warn!("Can't read sync folder: {e}");
+            return Vec::new();
+        }
+    };
+    let mut device_ids = store.synced_device_ids();
+    device_ids.extend(remote.keys().cloned());
+    device_ids.sort();
+    device_ids.dedup();
+
+    device_ids
+        .iter()
+        .filter_map(|device_id| {
+            sync_device(store, dir, device_id, remote.get(device_id), machine_id).unwrap_or_else(
+                |e| {
+                    tracing::warn!("Failed to sync {device_id}: {e}");
+                    None
+                },
+            )
+        })
+        .filter(|sync| sync.written || !sync.inbound.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Two machines sharing one sync folder.
+    struct Rig {
+        desk: SettingsStore,
+        laptop: SettingsStore,
+        shared: TempDir,
+        _local: TempDir,
+    }
+
+    fn rig() -> Rig {
+        let local = TempDir::new().unwrap();
+        Rig {
+            desk: SettingsStore::new(local.path().join("desk.json")),
+            laptop: SettingsStore::new(local.path().join("laptop.json")),
+            shared: TempDir::new().unwrap(),
+            _local: local,
+        }
+    }
+
+    impl Rig {
+        fn sync_desk(&self) -> Vec<DeviceSync> {
+            sync_all(&self.desk, self.shared.path(), "desk")
+        }
+
+        fn sync_laptop(&self) -> Vec<DeviceSync> {
+            sync_all(&self.laptop, self.shared.path(), "laptop")
+        }
+    }
+
+    fn value(store: &SettingsStore, control_id: &str) -> Option<i32> {
+        store
+            .get_camera("046d:085e:A1")
+            .and_then(|c| c.controls.get(control_id).map(|s| s.value))
+    }
+
+    #[test]
+    fn file_names_escape_unsafe_characters() {
+        assert_eq!(snapshot_file_name("046d:085e:A1"), "046d%3A085e%3AA1.json");
+        assert_eq!(snapshot_file_name("obs-virtual_1"), "obs-virtual_1.json");
+        assert!(is_snapshot_file(Path::new("a%3Ab.json")));
+        assert!(!is_snapshot_file(Path::new("a%3Ab.json.tmp")));
+    }
+
+    #[test]
+    fn settings_follow_a_camera_to_the_other_machine() {
+        let rig = rig();
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 3);
+
+        let out = rig.sync_desk();
+        assert!(out[0].written);
+        let inbound = rig.sync_laptop();
+        assert_eq!(inbound[0].inbound, vec!["zoom".to_string()]);
+        assert_eq!(value(&rig.laptop, "zoom"), Some(3));
+        assert_eq!(rig.laptop.get_camera("046d:085e:A1").unwrap().name, "Brio");
+    }
+
+    #[test]
+    fn changes_flow_back_and_settle() {
+        let rig = rig();
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 3);
+        rig.sync_desk();
+        rig.sync_laptop();
+
+        rig.laptop.set_control("046d:085e:A1", "Brio", "zoom", 5);
+        assert!(rig.sync_laptop()[0].written);
+        assert_eq!(rig.sync_desk()[0].inbound, vec!["zoom".to_string()]);
+        assert_eq!(value(&rig.desk, "zoom"), Some(5));
+
+        // Nothing left to do on either side
+        assert!(rig.sync_desk().is_empty());
+        assert!(rig.sync_laptop().is_empty());
+    }
+
+    #[test]
+    fn deletions_propagate() {
+        let rig = rig();
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 3);
+        rig.desk.set_control("046d:085e:A1", "Brio", "focus", 40);
+        rig.sync_desk();
+        rig.sync_laptop();
+
+        rig.desk.remove_camera("046d:085e:A1");
+        rig.sync_desk();
+        rig.sync_laptop();
+        assert_eq!(value(&rig.laptop, "zoom"), None);
+        assert_eq!(value(&rig.laptop, "focus"), None);
+    }
+
+    #[test]
+    fn concurrent_edits_are_reported_and_converge() {
+        let rig = rig();
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 3);
+        rig.sync_desk();
+        rig.sync_laptop();
+
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 4);
+        rig.laptop.set_control("046d:085e:A1", "Brio", "zoom", 6);
+        rig.sync_desk();
+        let at_laptop = rig.sync_laptop();
+        assert_eq!(at_laptop[0].conflicts.len(), 1);
+        rig.sync_desk();
+
+        assert_eq!(value(&rig.desk, "zoom"), Some(6));
+        assert_eq!(value(&rig.laptop, "zoom"), Some(6));
+    }
+
+    #[test]
+    fn unreadable_files_are_skipped() {
+        let rig = rig();
+        std::fs::write(rig.shared.path().join("broken.json"), "{ half").unwrap();
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 3);
+        assert_eq!(rig.sync_desk().len(), 1);
+        assert_eq!(read_snapshots(rig.shared.path()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn an_emptied_folder_is_filled_again() {
+        let rig = rig();
+        rig.desk.set_control("046d:085e:A1", "Brio", "zoom", 3);
+        rig.sync_desk();
+        std::fs::remove_file(rig.shared.path().join(snapshot_file_name("046d:085e:A1"))).unwrap();
+
+        assert!(rig.sync_desk()[0].written);
+        assert_eq!(read_snapshots(rig.shared.path()).unwrap().len(), 1);
+    }
+}
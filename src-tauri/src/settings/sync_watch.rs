@@ -0,0 +1,168 @@
+//! Background task that syncs settings through the shared folder.
+//!
+//! A sync pass runs every few seconds to pick up local changes, and straight
+//! away (after a short settle) when another machine's file changes in the
+//! folder. Controls changed elsewhere are applied to connected cameras
+//! through the normal restore path.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::camera::commands::CameraState;
+use crate::camera::types::DeviceId;
+use crate::diagnostics::control_latency::ControlLatencyState;
+use crate::error::{code, AppError};
+use crate::integration::control_api::generate_token;
+use crate::settings::apply::apply_saved_settings;
+use crate::settings::commands::SettingsState;
+use crate::settings::sync_dir::{is_snapshot_file, sync_all, DeviceSync};
+
+/// How often local changes are synced when nothing wakes the task.
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long to wait after a folder change before syncing, so a sync client
+/// writing several files is picked up in one pass.
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Tauri-managed state shared with the sync task.
+#[derive(Default)]
+pub struct SyncState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    wake: Notify,
+}
+
+impl SyncState {
+    /// Sync now instead of at the next tick.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+/// Watch `dir` for snapshot changes, replacing any previous watch. `None`
+/// stops watching.
+pub fn watch_sync_dir(app: &AppHandle, dir: Option<&Path>) -> Result<(), String> {
+    let state = app.state::<SyncState>();
+    let mut watcher = state.watcher.lock();
+    *watcher = None;
+    let Some(dir) = dir else {
+        return Ok(());
+    };
+
+    let handle = app.clone();
+    let mut new_watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if event.paths.iter().any(|p| is_snapshot_file(p)) => {
+                handle.state::<SyncState>().wake();
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Sync folder watch error: {e}"),
+        })
+        .map_err(|e| e.to_string())?;
+    new_watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Can't watch {}: {e}", dir.display()))?;
+    *watcher = Some(new_watcher);
+    Ok(())
+}
+
+/// Sync until the app exits. Spawn once the settings, camera and sync state
+/// are managed.
+pub async fn run_sync(app: AppHandle) {
+    loop {
+        let handle = app.clone();
+        match tauri::async_runtime::spawn_blocking(move || sync_once(&handle)).await {
+            Ok(synced) => emit_synced(&app, synced),
+            Err(e) => tracing::warn!("Settings sync task failed: {e}"),
+        }
+
+        let state = app.state::<SyncState>();
+        tokio::select! {
+            _ = tokio::time::sleep(SYNC_INTERVAL) => {}
+            _ = state.wake.notified() => tokio::time::sleep(SETTLE_DELAY).await,
+        }
+    }
+}
+
+/// Run a sync pass if a folder is set, then apply inbound changes to the
+/// cameras that are connected. Disconnected cameras get them on reconnect.
+fn sync_once(app: &AppHandle) -> Vec<DeviceSync> {
+    let store = Arc::clone(&app.state::<SettingsState>().store);
+    let Some(dir) = store.sync_dir() else {
+        return Vec::new();
+    };
+    let machine_id = store.sync_machine_id_or(generate_token);
+    let synced = sync_all(&store, Path::new(&dir), &machine_id);
+
+    let camera = app.state::<CameraState>();
+    let latency = app.state::<ControlLatencyState>();
+    for sync in synced.iter().filter(|sync| !sync.inbound.is_empty()) {
+        if camera
+            .backend
+            .known_device(&DeviceId::new(sync.device_id.clone()))
+            .is_none()
+        {
+            continue;
+        }
+        let restored = apply_saved_settings(&camera.backend, &store, &latency, &sync.device_id);
+        tracing::info!(
+            "Applied {} synced settings to '{}'",
+            restored.applied.len(),
+            sync.camera_name
+        );
+        if let Some(reconciled) = restored.reconciled_event(&sync.device_id, &sync.camera_name) {
+            let _ = app.emit("settings-reconciled", reconciled);
+        }
+    }
+    synced
+}
+
+fn emit_synced(app: &AppHandle, synced: Vec<DeviceSync>) {
+    let changed: Vec<DeviceSync> = synced
+        .into_iter()
+        .filter(|sync| !sync.inbound.is_empty() || !sync.conflicts.is_empty())
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+    if let Err(e) = app.emit("settings-synced", &changed) {
+        tracing::warn!("Failed to emit settings-synced event: {e}");
+    }
+}
+
+/// Folder settings are synced through, or `None` if sync is off.
+#[tauri::command]
+pub async fn get_sync_dir(
+    settings_state: State<'_, SettingsState>,
+) -> Result<Option<String>, AppError> {
+    Ok(settings_state.store.sync_dir())
+}
+
+/// Sync settings through `dir`, creating it if needed, or turn sync off
+/// with `None`. Syncs straight away.
+#[tauri::command]
+pub async fn set_sync_dir(
+    app: AppHandle,
+    settings_state: State<'_, SettingsState>,
+    sync_state: State<'_, SyncState>,
+    dir: Option<String>,
+) -> Result<(), AppError> {
+    let dir = dir
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty());
+    if let Some(dir) = &dir {
+        std::fs::create_dir_all(dir).map_err(|e| {
+            AppError::new(code::SETTINGS_IO, format!("Can't use {dir} for sync: {e}"))
+        })?;
+    }
+    watch_sync_dir(&app, dir.as_deref().map(Path::new))
+        .map_err(AppError::with_code(code::SETTINGS_IO))?;
+    settings_state.store.set_sync_dir(dir.as_deref());
+    sync_state.wake();
+    Ok(())
+}
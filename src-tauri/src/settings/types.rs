@@ -1,11 +1,158 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// Settings for a single camera — name and control values.
+use crate::camera::types::{same_device_path, CameraDevice, ControlDescriptor};
+use crate::input::keys::KeyBinding;
+use crate::integration::midi::mapping::MidiMapping;
+use crate::preset::types::Preset;
+use crate::preview::colour::ColourSpace;
+use crate::preview::mode::PreviewFormat;
+use crate::preview::output::OutputProcessors;
+use crate::preview::quality::QualityProfile;
+use crate::preview::render::Orientation;
+use crate::preview::sources::SourcePreference;
+use crate::preview::transform::PostProcessing;
+use crate::scene::types::Scene;
+use crate::settings::guard::ControlGuard;
+use crate::settings::persist::{skip_unserialisable_entries, skip_unserialisable_items};
+use crate::settings::schedule::ScheduleRule;
+use crate::settings::sync::DeviceSnapshot;
+
+/// Settings for a single camera — name, control values and modes, preview
+/// orientation, JPEG quality profile, post-processing, the processors each
+/// output runs, on multi-input devices the video source captured, and any
+/// colour space override.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct CameraSettings {
     pub name: String,
-    pub controls: HashMap<String, i32>,
+    pub controls: HashMap<String, SavedControl>,
+    /// Omitted from the file when unrotated, so older files load unchanged.
+    #[serde(default, skip_serializing_if = "Orientation::is_identity")]
+    pub orientation: Orientation,
+    /// Omitted from the file when left at the defaults.
+    #[serde(default, skip_serializing_if = "QualityProfile::is_default")]
+    pub jpeg_quality: QualityProfile,
+    /// Software sharpening and denoise. Omitted from the file when off.
+    #[serde(default, skip_serializing_if = "PostProcessing::is_default")]
+    pub post_processing: PostProcessing,
+    /// Processor chains by output, such as a watermark on recordings.
+    /// Omitted from the file when no output has one.
+    #[serde(default, skip_serializing_if = "OutputProcessors::is_default")]
+    pub output_processors: OutputProcessors,
+    /// Video source picked on a device with several inputs. Omitted when
+    /// none has been picked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_source: Option<SourcePreference>,
+    /// How the camera's YUV frames are decoded, overriding detection.
+    /// Omitted when detected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colour_space: Option<ColourSpace>,
+    /// Serve a "no signal" card instead of an error while the camera has
+    /// failed or stopped delivering frames. Omitted when off.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub placeholder_on_error: bool,
+    /// Auto-start this camera's preview at full resolution rather than
+    /// thumbnail-only. Omitted when off.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub full_resolution_autostart: bool,
+    /// Frame size and rate last chosen for the preview, which auto-started
+    /// previews reuse. Omitted until one has been chosen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_format: Option<PreviewFormat>,
+    /// Unix time (seconds) the camera was last enumerated. Zero, and
+    /// omitted, for cameras not seen since this was first recorded.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub last_seen: u64,
+    /// ID of the preset last applied, which drift is measured against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+}
+
+/// A saved control: its value, whether the camera drives it itself, and
+/// where the value came from.
+///
+/// `value` is only written back for manual controls; for auto controls it
+/// records what the camera had chosen when the mode was saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "SavedControlRepr")]
+pub struct SavedControl {
+    pub value: i32,
+    pub auto: bool,
+    /// Omitted for manual changes.
+    #[serde(skip_serializing_if = "ControlSource::is_manual")]
+    pub source: ControlSource,
+    /// Unix time (seconds) the value was saved. Zero, and omitted, for
+    /// values saved before changes were timestamped.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub changed_at: u64,
+}
+
+impl SavedControl {
+    pub fn manual(value: i32) -> Self {
+        Self {
+            value,
+            ..Self::default()
+        }
+    }
+}
+
+/// What last set a saved control.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlSource {
+    /// Changed by hand.
+    #[default]
+    Manual,
+    /// Written by applying the preset with this ID.
+    Preset(String),
+    /// Put back to the applied preset's value by `revert_to_preset`.
+    Restore,
+}
+
+impl ControlSource {
+    pub fn is_manual(&self) -> bool {
+        *self == Self::Manual
+    }
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+/// On-disk forms of a saved control. Files written before modes were saved
+/// store a bare value, which loads as a manual control.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedControlRepr {
+    Legacy(i32),
+    Current {
+        value: i32,
+        #[serde(default)]
+        auto: bool,
+        #[serde(default)]
+        source: ControlSource,
+        #[serde(default)]
+        changed_at: u64,
+    },
+}
+
+impl From<SavedControlRepr> for SavedControl {
+    fn from(repr: SavedControlRepr) -> Self {
+        match repr {
+            SavedControlRepr::Legacy(value) => Self::manual(value),
+            SavedControlRepr::Current {
+                value,
+                auto,
+                source,
+                changed_at,
+            } => Self {
+                value,
+                auto,
+                source,
+                changed_at,
+            },
+        }
+    }
 }
 
 /// Result of resetting a single control to its hardware default.
@@ -14,27 +161,183 @@ pub struct CameraSettings {
 pub struct ResetResult {
     pub control_id: String,
     pub value: i32,
+    /// Whether the control went back to automatic mode rather than having
+    /// `value` written.
+    #[serde(default)]
+    pub auto: bool,
+}
+
+/// Last-known control descriptors for a camera.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedControls {
+    /// Name and path the device reported when the list was cached. If
+    /// either changes, the entry is treated as belonging to another device.
+    pub name: String,
+    pub device_path: String,
+    /// Unix time (seconds) the list was last fetched from the camera.
+    pub cached_at: u64,
+    pub controls: Vec<ControlDescriptor>,
+}
+
+impl CachedControls {
+    /// Whether this entry can be served for `device` at `now`, given entries
+    /// older than `max_age` seconds are discarded.
+    pub fn is_valid_for(&self, device: &CameraDevice, now: u64, max_age: u64) -> bool {
+        self.name == device.name
+            && same_device_path(&self.device_path, &device.device_path)
+            && now.saturating_sub(self.cached_at) <= max_age
+    }
 }
 
 /// Top-level settings file structure — maps device IDs to camera settings.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct SettingsFile {
+    #[serde(serialize_with = "skip_unserialisable_entries")]
     pub cameras: HashMap<String, CameraSettings>,
+    /// Also auto-start previews for IR/depth sibling filters.
+    #[serde(default)]
+    pub auto_start_non_primary: bool,
+    /// Canon EDSDK integration switched off by the user. Stored negated so
+    /// files written before the toggle existed keep it on.
+    #[serde(default)]
+    pub disable_canon: bool,
+    /// Keep the default camera's capture graph running at thumbnail size
+    /// while the main window is hidden.
+    #[serde(default)]
+    pub keep_default_warm: bool,
+    /// Forget cameras not seen for this many days at startup, archiving
+    /// their settings first. Unset keeps them indefinitely.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune_after_days: Option<u32>,
+    /// Drive camera tally lights from the default camera and scene
+    /// activations.
+    #[serde(default)]
+    pub tally_auto: bool,
+    /// List cameras hidden as duplicates of another backend's device.
+    #[serde(default)]
+    pub show_suppressed_devices: bool,
+    /// Keep saved values restore had to clamp or snap instead of updating
+    /// them to what was written. Stored negated so reconciliation is on by
+    /// default.
+    #[serde(default)]
+    pub keep_unreconciled_settings: bool,
+    /// Language code of the backend's user-facing text, as `set_locale`
+    /// stored it. Unset uses English.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Device ID of the default camera. Seeded from the suggested camera on
+    /// first run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_camera: Option<String>,
+    /// Limit on each preview JPEG cache, in megabytes. Unset uses the
+    /// built-in default; only set by editing the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jpeg_cache_limit_mb: Option<u32>,
+    /// Most preview sessions that may run at once. Unset uses the built-in
+    /// default; only set by editing the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_preview_sessions: Option<u32>,
+    /// Largest `get_frame` response in kilobytes before quality is stepped
+    /// down to fit. Unset uses the built-in default; only set by editing the
+    /// file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_frame_payload_kb: Option<u32>,
+    /// Serve the localhost control API.
+    #[serde(default)]
+    pub control_api_enabled: bool,
+    /// Bearer token the control API requires. Generated the first time the
+    /// API is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_api_token: Option<String>,
+    /// Port the control API listens on. Unset uses the built-in default;
+    /// only set by editing the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_api_port: Option<u16>,
+    /// Name of the MIDI input to listen to for mapped knobs and faders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub midi_input: Option<String>,
+    /// Keys that nudge controls while a preview window is focused. Unset
+    /// uses the built-in bindings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_bindings: Option<Vec<KeyBinding>>,
+    /// MIDI control changes mapped to camera controls.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "skip_unserialisable_items"
+    )]
+    pub midi_mappings: Vec<MidiMapping>,
+    /// Control descriptors by device ID, served while the camera is queried.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "skip_unserialisable_entries"
+    )]
+    pub control_cache: HashMap<String, CachedControls>,
+    /// Saved presets by ID.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "skip_unserialisable_entries"
+    )]
+    pub presets: HashMap<String, Preset>,
+    /// Scheduled presets by device ID. Kept apart from `cameras` so a reset
+    /// to defaults leaves the schedule in place.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "skip_unserialisable_entries"
+    )]
+    pub schedules: HashMap<String, Vec<ScheduleRule>>,
+    /// Ceilings on auto-mode controls by device ID. Kept apart from
+    /// `cameras` for the same reason as `schedules`.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "skip_unserialisable_entries"
+    )]
+    pub control_guards: HashMap<String, Vec<ControlGuard>>,
+    /// Saved scenes, in the order they were first saved.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "skip_unserialisable_items"
+    )]
+    pub scenes: Vec<Scene>,
+    /// Folder shared with other machines to sync camera settings through.
+    /// Sync is off while unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_dir: Option<String>,
+    /// This machine's ID in synced snapshots. Generated when sync is first
+    /// used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_machine_id: Option<String>,
+    /// This machine's last synced snapshot of each camera, by device ID.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "skip_unserialisable_entries"
+    )]
+    pub sync_snapshots: HashMap<String, DeviceSnapshot>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::preview::output::{OutputTarget, Processor};
+    use crate::preview::watermark::Watermark;
 
     #[test]
     fn reset_result_serialises_to_camel_case_json() {
         let result = ResetResult {
             control_id: "brightness".to_string(),
             value: 128,
+            auto: false,
         };
         let json = serde_json::to_value(&result).unwrap();
         assert_eq!(json["controlId"], "brightness");
         assert_eq!(json["value"], 128);
+        assert_eq!(json["auto"], false);
         // Must not use snake_case field names
         assert!(json.get("control_id").is_none());
     }
@@ -44,6 +347,7 @@ mod tests {
         let result = ResetResult {
             control_id: "contrast".to_string(),
             value: 50,
+            auto: true,
         };
         let json = serde_json::to_string(&result).unwrap();
         let restored: ResetResult = serde_json::from_str(&json).unwrap();
@@ -60,8 +364,8 @@ mod tests {
     #[test]
     fn settings_file_serialises_to_json() {
         let mut controls = HashMap::new();
-        controls.insert("brightness".to_string(), 150);
-        controls.insert("contrast".to_string(), 60);
+        controls.insert("brightness".to_string(), SavedControl::manual(150));
+        controls.insert("contrast".to_string(), SavedControl::manual(60));
 
         let mut cameras = HashMap::new();
         cameras.insert(
@@ -69,20 +373,34 @@ mod tests {
             CameraSettings {
                 name: "Logitech BRIO".to_string(),
                 controls,
+                orientation: Orientation::default(),
+                jpeg_quality: QualityProfile::default(),
+                post_processing: PostProcessing::default(),
+                output_processors: OutputProcessors::default(),
+                video_source: None,
+                colour_space: None,
+                placeholder_on_error: false,
+                full_resolution_autostart: false,
+                preview_format: None,
+                last_seen: 0,
+                preset: None,
             },
         );
 
-        let file = SettingsFile { cameras };
+        let file = SettingsFile {
+            cameras,
+            ..Default::default()
+        };
         let json = serde_json::to_value(&file).unwrap();
 
         assert!(json["cameras"]["046d:085e:serial"].is_object());
         assert_eq!(json["cameras"]["046d:085e:serial"]["name"], "Logitech BRIO");
         assert_eq!(
             json["cameras"]["046d:085e:serial"]["controls"]["brightness"],
-            150
+            serde_json::json!({ "value": 150, "auto": false })
         );
         assert_eq!(
-            json["cameras"]["046d:085e:serial"]["controls"]["contrast"],
+            json["cameras"]["046d:085e:serial"]["controls"]["contrast"]["value"],
             60
         );
     }
@@ -104,16 +422,72 @@ mod tests {
         let file: SettingsFile = serde_json::from_str(json).unwrap();
         assert_eq!(file.cameras.len(), 1);
 
+        assert!(!file.auto_start_non_primary);
+        assert!(!file.disable_canon);
+        assert!(!file.keep_default_warm);
+        assert_eq!(file.jpeg_cache_limit_mb, None);
+
         let cam = &file.cameras["device-001"];
         assert_eq!(cam.name, "Test Camera");
-        assert_eq!(cam.controls["brightness"], 200);
-        assert_eq!(cam.controls["saturation"], 100);
+        assert_eq!(cam.controls["brightness"].value, 200);
+        assert_eq!(cam.controls["saturation"].value, 100);
+    }
+
+    #[test]
+    fn saved_controls_load_from_old_and_new_forms() {
+        let json = r#"{
+            "cameras": {
+                "dev-1": {
+                    "name": "Cam",
+                    "controls": {
+                        "brightness": 200,
+                        "exposure": { "value": -6, "auto": true },
+                        "focus": { "value": 40 }
+                    }
+                }
+            }
+        }"#;
+        let file: SettingsFile = serde_json::from_str(json).unwrap();
+        let controls = &file.cameras["dev-1"].controls;
+        assert_eq!(controls["brightness"], SavedControl::manual(200));
+        assert_eq!(
+            controls["exposure"],
+            SavedControl {
+                value: -6,
+                auto: true,
+                ..SavedControl::default()
+            }
+        );
+        assert_eq!(controls["focus"], SavedControl::manual(40));
+    }
+
+    #[test]
+    fn saved_control_provenance_round_trips_and_is_omitted_when_manual() {
+        let manual = SavedControl::manual(10);
+        let json = serde_json::to_value(&manual).unwrap();
+        assert_eq!(json, serde_json::json!({ "value": 10, "auto": false }));
+
+        let from_preset = SavedControl {
+            value: 10,
+            auto: false,
+            source: ControlSource::Preset("desk".to_string()),
+            changed_at: 1_700_000_000,
+        };
+        let json = serde_json::to_value(&from_preset).unwrap();
+        assert_eq!(json["source"], serde_json::json!({ "preset": "desk" }));
+        assert_eq!(json["changed_at"], 1_700_000_000);
+        let restored: SavedControl = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, from_preset);
+
+        let reverted: SavedControl =
+            serde_json::from_str(r#"{"value":10,"source":"restore","changed_at":5}"#).unwrap();
+        assert_eq!(reverted.source, ControlSource::Restore);
     }
 
     #[test]
     fn settings_file_round_trips_through_json() {
         let mut controls = HashMap::new();
-        controls.insert("brightness".to_string(), 128);
+        controls.insert("brightness".to_string(), SavedControl::manual(128));
 
         let mut cameras = HashMap::new();
         cameras.insert(
@@ -121,10 +495,24 @@ mod tests {
             CameraSettings {
                 name: "Camera".to_string(),
                 controls,
+                orientation: Orientation::default(),
+                jpeg_quality: QualityProfile::default(),
+                post_processing: PostProcessing::default(),
+                output_processors: OutputProcessors::default(),
+                video_source: None,
+                colour_space: None,
+                placeholder_on_error: false,
+                full_resolution_autostart: false,
+                preview_format: None,
+                last_seen: 0,
+                preset: None,
             },
         );
 
-        let original = SettingsFile { cameras };
+        let original = SettingsFile {
+            cameras,
+            ..Default::default()
+        };
         let json = serde_json::to_string(&original).unwrap();
         let restored: SettingsFile = serde_json::from_str(&json).unwrap();
 
@@ -140,9 +528,20 @@ mod tests {
                 name: "Camera One".to_string(),
                 controls: {
                     let mut c = HashMap::new();
-                    c.insert("brightness".to_string(), 100);
+                    c.insert("brightness".to_string(), SavedControl::manual(100));
                     c
                 },
+                orientation: Orientation::default(),
+                jpeg_quality: QualityProfile::default(),
+                post_processing: PostProcessing::default(),
+                output_processors: OutputProcessors::default(),
+                video_source: None,
+                colour_space: None,
+                placeholder_on_error: false,
+                full_resolution_autostart: false,
+                preview_format: None,
+                last_seen: 0,
+                preset: None,
             },
         );
         cameras.insert(
@@ -151,13 +550,27 @@ mod tests {
                 name: "Camera Two".to_string(),
                 controls: {
                     let mut c = HashMap::new();
-                    c.insert("contrast".to_string(), 50);
+                    c.insert("contrast".to_string(), SavedControl::manual(50));
                     c
                 },
+                orientation: Orientation::default(),
+                jpeg_quality: QualityProfile::default(),
+                post_processing: PostProcessing::default(),
+                output_processors: OutputProcessors::default(),
+                video_source: None,
+                colour_space: None,
+                placeholder_on_error: false,
+                full_resolution_autostart: false,
+                preview_format: None,
+                last_seen: 0,
+                preset: None,
             },
         );
 
-        let file = SettingsFile { cameras };
+        let file = SettingsFile {
+            cameras,
+            ..Default::default()
+        };
         let json = serde_json::to_string(&file).unwrap();
         let restored: SettingsFile = serde_json::from_str(&json).unwrap();
 
@@ -165,4 +578,159 @@ mod tests {
         assert_eq!(restored.cameras["cam-1"].name, "Camera One");
         assert_eq!(restored.cameras["cam-2"].name, "Camera Two");
     }
+
+    #[test]
+    fn orientation_is_omitted_when_identity_and_defaults_when_missing() {
+        let settings = CameraSettings::default();
+        let json = serde_json::to_value(&settings).unwrap();
+        assert!(json.get("orientation").is_none());
+
+        let parsed: CameraSettings =
+            serde_json::from_str(r#"{"name":"Cam","controls":{}}"#).unwrap();
+        assert!(parsed.orientation.is_identity());
+    }
+
+    #[test]
+    fn placeholder_on_error_defaults_off_and_is_omitted_when_off() {
+        let parsed: CameraSettings =
+            serde_json::from_str(r#"{"name":"Cam","controls":{}}"#).unwrap();
+        assert!(!parsed.placeholder_on_error);
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert!(json.get("placeholder_on_error").is_none());
+
+        let enabled = CameraSettings {
+            placeholder_on_error: true,
+            ..parsed
+        };
+        let json = serde_json::to_value(&enabled).unwrap();
+        assert_eq!(json["placeholder_on_error"], true);
+    }
+
+    #[test]
+    fn full_resolution_autostart_defaults_off_and_is_omitted_when_off() {
+        let parsed: CameraSettings =
+            serde_json::from_str(r#"{"name":"Cam","controls":{}}"#).unwrap();
+        assert!(!parsed.full_resolution_autostart);
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert!(json.get("full_resolution_autostart").is_none());
+
+        let enabled = CameraSettings {
+            full_resolution_autostart: true,
+            last_seen: 0,
+            ..parsed
+        };
+        let json = serde_json::to_value(&enabled).unwrap();
+        assert_eq!(json["full_resolution_autostart"], true);
+    }
+
+    #[test]
+    fn preview_format_is_omitted_until_chosen_and_round_trips() {
+        let parsed: CameraSettings =
+            serde_json::from_str(r#"{"name":"Cam","controls":{}}"#).unwrap();
+        assert_eq!(parsed.preview_format, None);
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert!(json.get("preview_format").is_none());
+
+        let chosen = CameraSettings {
+            preview_format: Some(PreviewFormat {
+                width: 1920,
+                height: 1080,
+                fps: 30.0,
+            }),
+            ..parsed
+        };
+        let json = serde_json::to_string(&chosen).unwrap();
+        assert!(json.contains(r#""preview_format":{"width":1920,"height":1080,"fps":30.0}"#));
+        let back: CameraSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, chosen);
+    }
+
+    #[test]
+    fn jpeg_quality_is_omitted_when_default_and_round_trips() {
+        let mut settings = CameraSettings {
+            name: "Cam".to_string(),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        assert!(json.get("jpeg_quality").is_none());
+
+        settings.jpeg_quality.adaptive = false;
+        settings.jpeg_quality.quality = 90;
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["jpeg_quality"]["adaptive"], false);
+
+        let restored: CameraSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, settings);
+    }
+
+    #[test]
+    fn post_processing_is_omitted_when_off_and_round_trips() {
+        let mut settings = CameraSettings {
+            name: "Cam".to_string(),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        assert!(json.get("post_processing").is_none());
+
+        settings.post_processing = PostProcessing {
+            sharpen: 60,
+            denoise: 0,
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["post_processing"]["sharpen"], 60);
+
+        let restored: CameraSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, settings);
+    }
+
+    #[test]
+    fn output_processors_are_omitted_when_unset_and_round_trip() {
+        let mut settings = CameraSettings {
+            name: "Cam".to_string(),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        assert!(json.get("output_processors").is_none());
+
+        settings.output_processors.set(
+            OutputTarget::Recording,
+            Some(vec![Processor::Watermark(Watermark::default())]),
+        );
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(
+            json["output_processors"]["recording"][0]["kind"],
+            "watermark"
+        );
+
+        let restored: CameraSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, settings);
+    }
+
+    #[test]
+    fn orientation_round_trips_through_json() {
+        let settings = CameraSettings {
+            name: "Cam".to_string(),
+            controls: HashMap::new(),
+            orientation: Orientation {
+                rotation: crate::preview::render::Rotation::Cw90,
+                mirror: true,
+            },
+            jpeg_quality: QualityProfile::default(),
+            post_processing: PostProcessing::default(),
+            output_processors: OutputProcessors::default(),
+            video_source: None,
+            colour_space: None,
+            placeholder_on_error: false,
+            full_resolution_autostart: false,
+            preview_format: None,
+            last_seen: 0,
+            preset: None,
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["orientation"]["rotation"], 90);
+        assert_eq!(json["orientation"]["mirror"], true);
+
+        let restored: CameraSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, settings);
+    }
 }
@@ -0,0 +1,257 @@
+// Frontend UI state persisted across restarts — sidebar width, expanded
+// panels, last selected camera. The frontend owns the shape; the backend
+// only merges patches, enforces a size cap and reads the few keys it needs.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+
+use crate::settings::persist::{load_json, write_json_atomic, SaveScheduler};
+
+/// Largest serialised UI state accepted, in bytes. UI state is small by
+/// nature; anything bigger is a frontend bug, not something to write to disk.
+pub const MAX_UI_STATE_BYTES: usize = 64 * 1024;
+
+/// Key the frontend stores the last selected camera's device ID under.
+pub const LAST_SELECTED_CAMERA_KEY: &str = "lastSelectedCamera";
+
+/// Persistent UI state store with debounced saving.
+pub struct UiStateStore {
+    path: PathBuf,
+    data: Mutex<Map<String, Value>>,
+    saves: SaveScheduler,
+}
+
+impl UiStateStore {
+    /// Create a new store, loading from disk if the file exists.
+    pub fn new(path: PathBuf) -> Self {
+        let data = Self::load(&path).unwrap_or_default();
+        Self {
+            path,
+            data: Mutex::new(data),
+            saves: SaveScheduler::new(),
+        }
+    }
+
+    /// Load UI state from a JSON file, returning an empty object on a
+    /// missing file.
+    pub fn load(path: &std::path::Path) -> Result<Map<String, Value>, String> {
+        load_json(path)
+    }
+
+    /// Save the current UI state to disk atomically.
+    pub fn save(&self) -> Result<(), String> {
+        let data = self.data.lock().clone();
+        write_json_atomic(&self.path, &data)
+    }
+
+    /// The whole UI state document.
+    pub fn get(&self) -> Value {
+        Value::Object(self.data.lock().clone())
+    }
+
+    /// Merge `patch` into the UI state (RFC 7386 JSON merge patch) and
+    /// return the merged document.
+    ///
+    /// Objects merge key by key, recursively; `null` deletes a key; arrays
+    /// and scalars replace the old value wholesale. The patch must be an
+    /// object, and a result larger than [`MAX_UI_STATE_BYTES`] is rejected
+    /// without changing the stored state.
+    pub fn apply_patch(&self, patch: Value) -> Result<Value, String> {
+        let Value::Object(patch) = patch else {
+            return Err("UI state patch must be a JSON object".to_string());
+        };
+
+        let mut data = self.data.lock();
+        let mut merged = data.clone();
+        merge_object(&mut merged, patch);
+
+        let size = serde_json::to_vec(&merged)
+            .map_err(|e| e.to_string())?
+            .len();
+        if size > MAX_UI_STATE_BYTES {
+            return Err(format!(
+                "UI state would be {size} bytes, over the {MAX_UI_STATE_BYTES} byte limit"
+            ));
+        }
+
+        let changed = *data != merged;
+        *data = merged;
+        let result = Value::Object(data.clone());
+        drop(data);
+
+        if changed {
+            self.saves.request();
+        }
+        Ok(result)
+    }
+
+    /// The camera the user last selected, if the frontend recorded one.
+    pub fn last_selected_camera(&self) -> Option<String> {
+        self.data
+            .lock()
+            .get(LAST_SELECTED_CAMERA_KEY)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    }
+
+    /// The debounce task — waits for changes, sleeps 500ms, then saves.
+    /// Never finishes; spawn it on the async runtime. See
+    /// [`SaveScheduler::run`].
+    pub fn debounce_task(self: &Arc<Self>) -> impl std::future::Future<Output = ()> + Send {
+        let store = Arc::clone(self);
+        async move { store.saves.run("UI state", || store.save()).await }
+    }
+}
+
+/// Merge `patch` into `target` key by key.
+fn merge_object(target: &mut Map<String, Value>, patch: Map<String, Value>) {
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(&key);
+            }
+            Value::Object(inner) => match target.get_mut(&key) {
+                Some(Value::Object(existing)) => merge_object(existing, inner),
+                _ => {
+                    // Replacing a non-object: merge into an empty object so
+                    // nulls inside the patch don't get stored
+                    let mut fresh = Map::new();
+                    merge_object(&mut fresh, inner);
+                    target.insert(key, Value::Object(fresh));
+                }
+            },
+            other => {
+                target.insert(key, other);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn temp_store() -> (UiStateStore, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let store = UiStateStore::new(dir.path().join("ui-state.json"));
+        (store, dir)
+    }
+
+    #[test]
+    fn starts_empty_without_a_file() {
+        let (store, _dir) = temp_store();
+        assert_eq!(store.get(), json!({}));
+        assert!(store.last_selected_camera().is_none());
+    }
+
+    #[test]
+    fn nested_objects_merge_key_by_key() {
+        let (store, _dir) = temp_store();
+        store
+            .apply_patch(json!({ "sidebar": { "width": 280, "collapsed": false } }))
+            .unwrap();
+        let merged = store
+            .apply_patch(json!({ "sidebar": { "collapsed": true } }))
+            .unwrap();
+        assert_eq!(
+            merged,
+            json!({ "sidebar": { "width": 280, "collapsed": true } })
+        );
+    }
+
+    #[test]
+    fn null_deletes_a_key() {
+        let (store, _dir) = temp_store();
+        store
+            .apply_patch(json!({ "sidebar": { "width": 280 }, "theme": "dark" }))
+            .unwrap();
+        let merged = store
+            .apply_patch(json!({ "sidebar": { "width": null }, "theme": null }))
+            .unwrap();
+        assert_eq!(merged, json!({ "sidebar": {} }));
+    }
+
+    #[test]
+    fn nulls_inside_a_new_object_are_not_stored() {
+        let (store, _dir) = temp_store();
+        store.apply_patch(json!({ "panels": 3 })).unwrap();
+        let merged = store
+            .apply_patch(json!({ "panels": { "open": true, "pinned": null } }))
+            .unwrap();
+        assert_eq!(merged, json!({ "panels": { "open": true } }));
+    }
+
+    #[test]
+    fn arrays_are_replaced_not_merged() {
+        let (store, _dir) = temp_store();
+        store
+            .apply_patch(json!({ "expanded": ["image", "focus"] }))
+            .unwrap();
+        let merged = store.apply_patch(json!({ "expanded": ["audio"] })).unwrap();
+        assert_eq!(merged, json!({ "expanded": ["audio"] }));
+    }
+
+    #[test]
+    fn non_object_patch_is_rejected() {
+        let (store, _dir) = temp_store();
+        assert!(store.apply_patch(json!(["sidebar"])).is_err());
+        assert!(store.apply_patch(Value::Null).is_err());
+        assert!(!store.saves.is_pending());
+    }
+
+    #[test]
+    fn oversized_result_is_rejected_without_changing_state() {
+        let (store, _dir) = temp_store();
+        store.apply_patch(json!({ "theme": "dark" })).unwrap();
+        store.saves.take_pending();
+
+        let big = "x".repeat(MAX_UI_STATE_BYTES);
+        let err = store.apply_patch(json!({ "notes": big })).unwrap_err();
+        assert!(err.contains("limit"), "got: {err}");
+        assert_eq!(store.get(), json!({ "theme": "dark" }));
+        assert!(!store.saves.is_pending());
+    }
+
+    #[test]
+    fn unchanged_patch_does_not_request_a_save() {
+        let (store, _dir) = temp_store();
+        store.apply_patch(json!({ "theme": "dark" })).unwrap();
+        assert!(store.saves.take_pending());
+
+        store.apply_patch(json!({ "theme": "dark" })).unwrap();
+        assert!(!store.saves.is_pending());
+    }
+
+    #[test]
+    fn last_selected_camera_reads_the_string_key() {
+        let (store, _dir) = temp_store();
+        store
+            .apply_patch(json!({ LAST_SELECTED_CAMERA_KEY: "dev-1" }))
+            .unwrap();
+        assert_eq!(store.last_selected_camera().as_deref(), Some("dev-1"));
+
+        // A value of the wrong type is ignored rather than misread
+        store
+            .apply_patch(json!({ LAST_SELECTED_CAMERA_KEY: 42 }))
+            .unwrap();
+        assert!(store.last_selected_camera().is_none());
+    }
+
+    #[test]
+    fn saved_state_round_trips() {
+        let (store, dir) = temp_store();
+        store
+            .apply_patch(json!({ "sidebar": { "width": 300 }, LAST_SELECTED_CAMERA_KEY: "dev-2" }))
+            .unwrap();
+        store.save().unwrap();
+
+        let reloaded = UiStateStore::new(dir.path().join("ui-state.json"));
+        assert_eq!(reloaded.get(), store.get());
+        assert!(!dir.path().join("ui-state.json.tmp").exists());
+    }
+}
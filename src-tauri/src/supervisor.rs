@@ -0,0 +1,306 @@
+// Panic supervision for long-lived worker threads and tasks.
+//
+// Capture and hotplug threads drive unsafe FFI; a panic there would otherwise
+// kill the thread silently. These helpers catch panics, count them, and
+// optionally restart the work with bounded exponential backoff.
+
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Process-wide count of panics caught by the supervisor.
+static PANIC_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Total number of panics caught since startup.
+pub fn panic_count() -> u64 {
+    PANIC_COUNT.load(Ordering::Relaxed)
+}
+
+/// Restart policy for supervised work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Maximum number of runs, including the first.
+    pub max_attempts: u32,
+    /// Delay before the first restart.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between restarts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay before restarting after the given (1-based) failed attempt.
+    /// Doubles each time, capped at `max_backoff`.
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Result of running work under supervision.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SupervisedOutcome<T> {
+    /// The work returned normally (possibly after restarts).
+    Completed(T),
+    /// Every attempt panicked; carries the last panic message.
+    GaveUp { attempts: u32, last_panic: String },
+}
+
+/// Extract a readable message from a panic payload.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Run `f` once, converting a panic into `Err(message)`.
+///
+/// Every caught panic increments the process-wide panic counter.
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        PANIC_COUNT.fetch_add(1, Ordering::Relaxed);
+        panic_message(payload.as_ref())
+    })
+}
+
+/// Run `f` under supervision, restarting it after a panic.
+///
+/// `f` receives the 1-based attempt number. `on_panic` is called with the
+/// attempt number and panic message before any backoff sleep, and is the
+/// place to repair shared state before the next attempt.
+pub fn run_with_restart<T>(
+    policy: RestartPolicy,
+    mut f: impl FnMut(u32) -> T,
+    mut on_panic: impl FnMut(u32, &str),
+) -> SupervisedOutcome<T> {
+    let mut last_panic = String::new();
+
+    for attempt in 1..=policy.max_attempts {
+        match catch_panic(|| f(attempt)) {
+            Ok(value) => return SupervisedOutcome::Completed(value),
+            Err(message) => {
+                tracing::error!("supervised work panicked (attempt {attempt}): {message}");
+                on_panic(attempt, &message);
+                last_panic = message;
+                if attempt < policy.max_attempts {
+                    std::thread::sleep(policy.backoff_for(attempt));
+                }
+            }
+        }
+    }
+
+    SupervisedOutcome::GaveUp {
+        attempts: policy.max_attempts,
+        last_panic,
+    }
+}
+
+/// Run the future `f` builds as a task on the Tokio runtime, building and
+/// running a new one after a panic — the async counterpart of
+/// [`run_with_restart`].
+///
+/// A task cancelled by the runtime shutting down isn't restarted; that
+/// counts as completing.
+pub async fn run_task_with_restart<F, Fut>(
+    policy: RestartPolicy,
+    mut f: F,
+    mut on_panic: impl FnMut(u32, &str),
+) -> SupervisedOutcome<()>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut last_panic = String::new();
+
+    for attempt in 1..=policy.max_attempts {
+        match tokio::spawn(f(attempt)).await {
+            Err(e) if e.is_panic() => {
+                PANIC_COUNT.fetch_add(1, Ordering::Relaxed);
+                let message = panic_message(e.into_panic().as_ref());
+                tracing::error!("supervised task panicked (attempt {attempt}): {message}");
+                on_panic(attempt, &message);
+                last_panic = message;
+                if attempt < policy.max_attempts {
+                    tokio::time::sleep(policy.backoff_for(attempt)).await;
+                }
+            }
+            _ => return SupervisedOutcome::Completed(()),
+        }
+    }
+
+    SupervisedOutcome::GaveUp {
+        attempts: policy.max_attempts,
+        last_panic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn fast_policy(max_attempts: u32) -> RestartPolicy {
+        RestartPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        }
+    }
+
+    #[test]
+    fn catch_panic_returns_value_without_panic() {
+        assert_eq!(catch_panic(|| 7), Ok(7));
+    }
+
+    #[test]
+    fn catch_panic_converts_str_and_string_payloads() {
+        assert_eq!(
+            catch_panic(|| panic!("boom")),
+            Err::<(), _>("boom".to_string())
+        );
+        let n = 3;
+        assert_eq!(
+            catch_panic(|| panic!("boom {n}")),
+            Err::<(), _>("boom 3".to_string())
+        );
+    }
+
+    #[test]
+    fn catch_panic_increments_counter() {
+        let before = panic_count();
+        let _ = catch_panic(|| panic!("counted"));
+        assert!(panic_count() > before);
+    }
+
+    #[test]
+    fn panic_message_handles_unknown_payload() {
+        let payload: Box<dyn Any + Send> = Box::new(42u32);
+        assert_eq!(panic_message(payload.as_ref()), "unknown panic");
+    }
+
+    #[test]
+    fn run_with_restart_completes_without_restart() {
+        let outcome = run_with_restart(fast_policy(3), |attempt| attempt, |_, _| {});
+        assert_eq!(outcome, SupervisedOutcome::Completed(1));
+    }
+
+    #[test]
+    fn run_with_restart_recovers_after_panics() {
+        let repairs = Cell::new(0);
+        let outcome = run_with_restart(
+            fast_policy(5),
+            |attempt| {
+                if attempt < 3 {
+                    panic!("attempt {attempt} failed");
+                }
+                attempt
+            },
+            |_, _| repairs.set(repairs.get() + 1),
+        );
+        assert_eq!(outcome, SupervisedOutcome::Completed(3));
+        assert_eq!(repairs.get(), 2);
+    }
+
+    #[test]
+    fn run_with_restart_gives_up_after_max_attempts() {
+        let runs = Cell::new(0);
+        let outcome: SupervisedOutcome<()> = run_with_restart(
+            fast_policy(3),
+            |_| {
+                runs.set(runs.get() + 1);
+                panic!("always");
+            },
+            |_, _| {},
+        );
+        assert_eq!(
+            outcome,
+            SupervisedOutcome::GaveUp {
+                attempts: 3,
+                last_panic: "always".to_string()
+            }
+        );
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn on_panic_receives_attempt_and_message() {
+        let mut seen = Vec::new();
+        let _: SupervisedOutcome<()> = run_with_restart(
+            fast_policy(2),
+            |a| panic!("p{a}"),
+            |a, m| seen.push((a, m.to_string())),
+        );
+        assert_eq!(seen, vec![(1, "p1".to_string()), (2, "p2".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn run_task_with_restart_recovers_after_panics() {
+        let before = panic_count();
+        let mut seen = Vec::new();
+        let outcome = run_task_with_restart(
+            fast_policy(5),
+            |attempt| async move {
+                if attempt < 3 {
+                    panic!("task {attempt} failed");
+                }
+            },
+            |a, m| seen.push((a, m.to_string())),
+        )
+        .await;
+        assert_eq!(outcome, SupervisedOutcome::Completed(()));
+        assert_eq!(
+            seen,
+            vec![
+                (1, "task 1 failed".to_string()),
+                (2, "task 2 failed".to_string())
+            ]
+        );
+        assert!(panic_count() >= before + 2);
+    }
+
+    #[tokio::test]
+    async fn run_task_with_restart_gives_up_after_max_attempts() {
+        let outcome =
+            run_task_with_restart(fast_policy(2), |_| async { panic!("always") }, |_, _| {}).await;
+        assert_eq!(
+            outcome,
+            SupervisedOutcome::GaveUp {
+                attempts: 2,
+                last_panic: "always".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let policy = RestartPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff_for(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff_for(40), Duration::from_millis(500));
+    }
+}
@@ -1,13 +1,76 @@
-use tauri::menu::{MenuBuilder, MenuItemBuilder};
-use tauri::tray::TrayIconBuilder;
+mod icon;
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use tauri::menu::{MenuBuilder, MenuItem, MenuItemBuilder};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
 use tauri::webview::WebviewWindowBuilder;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::camera::commands::CameraState;
+use crate::i18n::{self, Domain, Locale};
+use crate::preview::commands::PreviewState;
+use icon::{
+    animation_frame, resolve_icon, until_next_frame, ActivityState, IconDebouncer, IconId,
+    TrayTheme, ICON_SETTLE,
+};
 
 /// Identifiers for tray menu items.
 const MENU_ID_SHOW_HIDE: &str = "show-hide";
 const MENU_ID_APP_SETTINGS: &str = "app-settings";
 const MENU_ID_QUIT: &str = "quit";
 
+/// Tray menu items in order, as (menu id, catalogue key).
+const MENU_ITEMS: [(&str, &str); 3] = [
+    (MENU_ID_SHOW_HIDE, "show_hide"),
+    (MENU_ID_APP_SETTINGS, "app_settings"),
+    (MENU_ID_QUIT, "quit"),
+];
+
+/// Longest the icon updater sleeps without a notification, so changes
+/// nothing reports (a session ending, the taskbar theme) still show up.
+const ICON_IDLE_POLL: Duration = Duration::from_secs(1);
+
+/// Wakes the tray icon updater when camera activity changes.
+pub struct TrayActivity {
+    notify: Sender<()>,
+}
+
+/// The tray's menu items, kept so their labels can follow the locale.
+pub struct TrayMenu {
+    items: Vec<(&'static str, MenuItem<Wry>)>,
+}
+
+/// Menu items as (id, label) in `locale`.
+fn menu_labels(locale: Locale) -> [(&'static str, &'static str); 3] {
+    MENU_ITEMS.map(|(id, key)| (id, i18n::text_in(locale, Domain::Tray, key)))
+}
+
+/// Relabel the tray menu in the active locale, after it changed.
+pub fn refresh_labels(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    for (id, label) in menu_labels(i18n::active()) {
+        if let Some((_, item)) = menu.items.iter().find(|(item_id, _)| *item_id == id) {
+            if let Err(e) = item.set_text(label) {
+                tracing::warn!("Failed to relabel tray item '{id}': {e}");
+            }
+        }
+    }
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.set_title(i18n::text(Domain::Tray, "settings_window_title"));
+    }
+}
+
+/// Tell the tray icon that camera activity may have changed.
+pub fn notify_activity(app: &AppHandle) {
+    if let Some(activity) = app.try_state::<TrayActivity>() {
+        let _ = activity.notify.send(());
+    }
+}
+
 /// Show the main window and give it focus.
 fn show_main_window(app: &AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
@@ -21,6 +84,7 @@ fn show_main_window(app: &AppHandle) {
 fn hide_main_window(app: &AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.hide();
+        crate::preview::commands::main_window_visibility_changed(app, false);
     }
 }
 
@@ -47,7 +111,7 @@ fn open_settings_window(app: &AppHandle) {
         "settings",
         tauri::WebviewUrl::App("index.html#settings".into()),
     )
-    .title("App Settings")
+    .title(i18n::text(Domain::Tray, "settings_window_title"))
     .inner_size(500.0, 400.0)
     .resizable(true)
     .center();
@@ -59,17 +123,23 @@ fn open_settings_window(app: &AppHandle) {
 
 /// Build and register the system tray for the application.
 pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
-    let show_hide = MenuItemBuilder::with_id(MENU_ID_SHOW_HIDE, "Show/Hide").build(app)?;
-    let app_settings = MenuItemBuilder::with_id(MENU_ID_APP_SETTINGS, "App Settings").build(app)?;
-    let quit = MenuItemBuilder::with_id(MENU_ID_QUIT, "Exit").build(app)?;
+    let items = menu_labels(i18n::active())
+        .into_iter()
+        .map(|(id, label)| {
+            MenuItemBuilder::with_id(id, label)
+                .build(app)
+                .map(|item| (id, item))
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
 
-    let menu = MenuBuilder::new(app)
-        .item(&show_hide)
-        .item(&app_settings)
-        .item(&quit)
-        .build()?;
+    let mut menu = MenuBuilder::new(app);
+    for (_, item) in &items {
+        menu = menu.item(item);
+    }
+    let menu = menu.build()?;
+    app.manage(TrayMenu { items });
 
-    let _tray = TrayIconBuilder::new()
+    let tray = TrayIconBuilder::new()
         .icon(app.default_window_icon().cloned().unwrap())
         .menu(&menu)
         .show_menu_on_left_click(false)
@@ -94,25 +164,118 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
         })
         .build(app)?;
 
+    let (notify, wake) = mpsc::channel();
+    app.manage(TrayActivity { notify });
+    let app = app.clone();
+    std::thread::Builder::new()
+        .name("tray-icon".to_string())
+        .spawn(move || run_icon_updater(app, tray, wake))?;
+
     Ok(())
 }
 
+/// Keep the tray icon in line with camera activity until the app exits.
+fn run_icon_updater(app: AppHandle, tray: TrayIcon, wake: Receiver<()>) {
+    let started = Instant::now();
+    let mut debouncer = IconDebouncer::new(ICON_SETTLE);
+    loop {
+        let state = gather_activity(&app);
+        let now = Instant::now();
+        let elapsed = now.duration_since(started);
+        let id = resolve_icon(&state, system_theme(), animation_frame(elapsed));
+        if let Some(id) = debouncer.offer(id, now) {
+            set_icon(&tray, id);
+        }
+
+        // Sleep until the pending icon is due, the animation flips, or the
+        // next poll — whichever comes first
+        let mut timeout = ICON_IDLE_POLL;
+        if let Some(due) = debouncer.next_due() {
+            timeout = timeout.min(due.saturating_duration_since(now));
+        }
+        if state.any_streaming {
+            timeout = timeout.min(until_next_frame(elapsed));
+        }
+        if let Err(RecvTimeoutError::Disconnected) = wake.recv_timeout(timeout) {
+            return;
+        }
+    }
+}
+
+/// Aggregate device and session state for the icon.
+fn gather_activity(app: &AppHandle) -> ActivityState {
+    let device_count = app
+        .try_state::<CameraState>()
+        .map_or(0, |camera| camera.backend.known_devices().len());
+    let (any_streaming, any_error) =
+        app.try_state::<PreviewState>()
+            .map_or((false, false), |preview| {
+                let sessions = preview.sessions.lock();
+                (
+                    sessions.values().any(|s| s.is_running()),
+                    sessions.values().any(|s| s.is_failed()),
+                )
+            });
+    ActivityState {
+        device_count,
+        any_streaming,
+        // Nothing records yet
+        any_recording: false,
+        any_error,
+    }
+}
+
+fn set_icon(tray: &TrayIcon, id: IconId) {
+    let result =
+        tauri::image::Image::from_bytes(id.png()).and_then(|image| tray.set_icon(Some(image)));
+    if let Err(e) = result {
+        tracing::warn!("Failed to set tray icon {id:?}: {e}");
+    }
+}
+
+/// Theme of the Windows taskbar, from `SystemUsesLightTheme`. Defaults to
+/// dark when the value is missing (Windows 10 before 1903).
+#[cfg(target_os = "windows")]
+fn system_theme() -> TrayTheme {
+    use windows::core::w;
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    // SAFETY: `value` and `size` outlive the call and `size` matches the
+    // buffer; RRF_RT_REG_DWORD guarantees at most four bytes are written.
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("SystemUsesLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+    };
+    if status == ERROR_SUCCESS && value != 0 {
+        TrayTheme::Light
+    } else {
+        TrayTheme::Dark
+    }
+}
+
+/// Non-Windows trays are assumed dark.
+#[cfg(not(target_os = "windows"))]
+fn system_theme() -> TrayTheme {
+    TrayTheme::Dark
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    /// Testable representation of menu items (id, label).
-    fn menu_item_defs() -> Vec<(&'static str, &'static str)> {
-        vec![
-            (MENU_ID_SHOW_HIDE, "Show/Hide"),
-            (MENU_ID_APP_SETTINGS, "App Settings"),
-            (MENU_ID_QUIT, "Exit"),
-        ]
-    }
-
     #[test]
     fn menu_item_defs_contains_correct_ids_and_labels() {
-        let defs = menu_item_defs();
+        let defs = menu_labels(Locale::En);
 
         assert_eq!(defs.len(), 3);
         assert_eq!(defs[0], (MENU_ID_SHOW_HIDE, "Show/Hide"));
@@ -122,7 +285,7 @@ mod tests {
 
     #[test]
     fn menu_has_show_hide_item() {
-        let defs = menu_item_defs();
+        let defs = menu_labels(Locale::En);
         let item = defs.iter().find(|(id, _)| *id == MENU_ID_SHOW_HIDE);
 
         assert!(item.is_some());
@@ -131,7 +294,7 @@ mod tests {
 
     #[test]
     fn menu_has_app_settings_item() {
-        let defs = menu_item_defs();
+        let defs = menu_labels(Locale::En);
         let item = defs.iter().find(|(id, _)| *id == MENU_ID_APP_SETTINGS);
 
         assert!(item.is_some());
@@ -140,10 +303,17 @@ mod tests {
 
     #[test]
     fn menu_has_quit_item() {
-        let defs = menu_item_defs();
+        let defs = menu_labels(Locale::En);
         let quit = defs.iter().find(|(id, _)| *id == MENU_ID_QUIT);
 
         assert!(quit.is_some());
         assert_eq!(quit.unwrap().1, "Exit");
     }
+
+    #[test]
+    fn menu_labels_follow_the_locale() {
+        let defs = menu_labels(Locale::De);
+
+        assert_eq!(defs[2], (MENU_ID_QUIT, "Beenden"));
+    }
 }
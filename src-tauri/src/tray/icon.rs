@@ -0,0 +1,360 @@
+// Tray icon selection.
+//
+// The tray icon reflects what the cameras are doing: grey with nothing
+// connected, a plain glyph when idle, a two-frame pulse while previews
+// stream, and a dot overlay for recording or errors. Each icon comes in a
+// light and a dark variant to match the taskbar theme.
+//
+// Everything here is pure; tray.rs gathers the state and calls SetIcon.
+
+use std::time::{Duration, Instant};
+
+/// Time between the two frames of the streaming animation.
+pub const ANIMATION_INTERVAL: Duration = Duration::from_millis(600);
+
+/// How long a new icon must stay wanted before it's shown.
+pub const ICON_SETTLE: Duration = Duration::from_millis(150);
+
+/// Aggregate camera state the icon is chosen from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActivityState {
+    pub device_count: usize,
+    pub any_streaming: bool,
+    pub any_recording: bool,
+    pub any_error: bool,
+}
+
+/// Theme of the taskbar the icon is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrayTheme {
+    /// Light taskbar — needs a dark glyph.
+    Light,
+    /// Dark taskbar — needs a light glyph.
+    Dark,
+}
+
+/// What the icon shows, independent of theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconKind {
+    NoCameras,
+    Idle,
+    /// Second frame of the streaming animation; the first is `Idle`.
+    Streaming,
+    Recording,
+    Error,
+}
+
+/// One of the embedded tray icon assets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IconId {
+    pub kind: IconKind,
+    pub theme: TrayTheme,
+}
+
+impl IconId {
+    /// PNG bytes of the asset.
+    pub fn png(self) -> &'static [u8] {
+        use IconKind::*;
+        use TrayTheme::*;
+        match (self.kind, self.theme) {
+            (NoCameras, Light) => include_bytes!("../../icons/tray/no-cameras-light.png"),
+            (NoCameras, Dark) => include_bytes!("../../icons/tray/no-cameras-dark.png"),
+            (Idle, Light) => include_bytes!("../../icons/tray/idle-light.png"),
+            (Idle, Dark) => include_bytes!("../../icons/tray/idle-dark.png"),
+            (Streaming, Light) => include_bytes!("../../icons/tray/streaming-light.png"),
+            (Streaming, Dark) => include_bytes!("../../icons/tray/streaming-dark.png"),
+            (Recording, Light) => include_bytes!("../../icons/tray/recording-light.png"),
+            (Recording, Dark) => include_bytes!("../../icons/tray/recording-dark.png"),
+            (Error, Light) => include_bytes!("../../icons/tray/error-light.png"),
+            (Error, Dark) => include_bytes!("../../icons/tray/error-dark.png"),
+        }
+    }
+}
+
+/// Pick the icon for `state`.
+///
+/// Recording outranks errors, which outrank streaming — a failed camera
+/// matters more than an animation. `frame` is the animation frame, only
+/// used while streaming.
+pub fn resolve_icon(state: &ActivityState, theme: TrayTheme, frame: bool) -> IconId {
+    let kind = if state.device_count == 0 {
+        IconKind::NoCameras
+    } else if state.any_recording {
+        IconKind::Recording
+    } else if state.any_error {
+        IconKind::Error
+    } else if state.any_streaming && frame {
+        IconKind::Streaming
+    } else {
+        IconKind::Idle
+    };
+    IconId { kind, theme }
+}
+
+/// Animation frame at `elapsed` since the animation started.
+pub fn animation_frame(elapsed: Duration) -> bool {
+    (elapsed.as_millis() / ANIMATION_INTERVAL.as_millis()) % 2 == 1
+}
+
+/// Time from `elapsed` until the animation next changes frame.
+pub fn until_next_frame(elapsed: Duration) -> Duration {
+    let interval = ANIMATION_INTERVAL.as_millis();
+    Duration::from_millis((interval - elapsed.as_millis() % interval) as u64)
+}
+
+/// Holds back icon changes until they've been wanted for `settle`, so a
+/// burst of state changes produces one SetIcon rather than many.
+#[derive(Debug)]
+pub struct IconDebouncer {
+    settle: Duration,
+    shown: Option<IconId>,
+    pending: Option<(IconId, Instant)>,
+}
+
+impl IconDebouncer {
+    pub fn new(settle: Duration) -> Self {
+        Self {
+            settle,
+            shown: None,
+            pending: None,
+        }
+    }
+
+    /// Offer the icon the current state resolves to.
+    ///
+    /// Returns the icon to display once it has been offered continuously for
+    /// `settle` and differs from what's shown. Offering something else
+    /// restarts the wait.
+    pub fn offer(&mut self, icon: IconId, now: Instant) -> Option<IconId> {
+        if self.shown == Some(icon) {
+            self.pending = None;
+            return None;
+        }
+        let since = match self.pending {
+            Some((pending, since)) if pending == icon => since,
+            _ => {
+                self.pending = Some((icon, now));
+                now
+            }
+        };
+        if now.duration_since(since) < self.settle {
+            return None;
+        }
+        self.pending = None;
+        self.shown = Some(icon);
+        Some(icon)
+    }
+
+    /// When the pending icon becomes due, if one is waiting.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.map(|(_, since)| since + self.settle)
+    }
+
+    /// The icon last returned by `offer`.
+    pub fn shown(&self) -> Option<IconId> {
+        self.shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_KINDS: [IconKind; 5] = [
+        IconKind::NoCameras,
+        IconKind::Idle,
+        IconKind::Streaming,
+        IconKind::Recording,
+        IconKind::Error,
+    ];
+
+    fn state(devices: usize, streaming: bool, recording: bool, error: bool) -> ActivityState {
+        ActivityState {
+            device_count: devices,
+            any_streaming: streaming,
+            any_recording: recording,
+            any_error: error,
+        }
+    }
+
+    fn kind(state: ActivityState, frame: bool) -> IconKind {
+        resolve_icon(&state, TrayTheme::Dark, frame).kind
+    }
+
+    #[test]
+    fn no_devices_is_grey_whatever_else_is_set() {
+        assert_eq!(
+            kind(state(0, false, false, false), false),
+            IconKind::NoCameras
+        );
+        assert_eq!(kind(state(0, true, true, true), true), IconKind::NoCameras);
+    }
+
+    #[test]
+    fn connected_but_idle_is_plain() {
+        assert_eq!(kind(state(2, false, false, false), true), IconKind::Idle);
+    }
+
+    #[test]
+    fn streaming_alternates_between_two_frames() {
+        let s = state(1, true, false, false);
+        assert_eq!(kind(s, false), IconKind::Idle);
+        assert_eq!(kind(s, true), IconKind::Streaming);
+    }
+
+    #[test]
+    fn recording_outranks_error_and_streaming() {
+        assert_eq!(kind(state(1, true, true, true), true), IconKind::Recording);
+        assert_eq!(
+            kind(state(1, true, true, false), false),
+            IconKind::Recording
+        );
+    }
+
+    #[test]
+    fn error_outranks_streaming_and_does_not_animate() {
+        let s = state(1, true, false, true);
+        assert_eq!(kind(s, false), IconKind::Error);
+        assert_eq!(kind(s, true), IconKind::Error);
+    }
+
+    #[test]
+    fn theme_selects_the_asset_variant() {
+        let s = state(1, false, false, false);
+        assert_eq!(
+            resolve_icon(&s, TrayTheme::Light, false).theme,
+            TrayTheme::Light
+        );
+        assert_eq!(
+            resolve_icon(&s, TrayTheme::Dark, false).theme,
+            TrayTheme::Dark
+        );
+    }
+
+    #[test]
+    fn every_asset_is_a_distinct_png() {
+        let mut seen = Vec::new();
+        for kind in ALL_KINDS {
+            for theme in [TrayTheme::Light, TrayTheme::Dark] {
+                let png = IconId { kind, theme }.png();
+                assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"), "{kind:?}/{theme:?}");
+                assert!(
+                    !seen.contains(&png),
+                    "{kind:?}/{theme:?} duplicates another asset"
+                );
+                seen.push(png);
+            }
+        }
+    }
+
+    #[test]
+    fn animation_frame_flips_each_interval() {
+        let step = ANIMATION_INTERVAL;
+        assert!(!animation_frame(Duration::ZERO));
+        assert!(!animation_frame(step - Duration::from_millis(1)));
+        assert!(animation_frame(step));
+        assert!(!animation_frame(step * 2));
+        assert!(animation_frame(step * 3 + Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn until_next_frame_reaches_the_boundary() {
+        let step = ANIMATION_INTERVAL;
+        assert_eq!(until_next_frame(Duration::ZERO), step);
+        let elapsed = step + Duration::from_millis(100);
+        assert_eq!(until_next_frame(elapsed), step - Duration::from_millis(100));
+        assert_ne!(
+            animation_frame(elapsed),
+            animation_frame(elapsed + until_next_frame(elapsed))
+        );
+    }
+
+    fn icon(kind: IconKind) -> IconId {
+        IconId {
+            kind,
+            theme: TrayTheme::Dark,
+        }
+    }
+
+    #[test]
+    fn debouncer_waits_for_settle_before_showing() {
+        let start = Instant::now();
+        let mut debouncer = IconDebouncer::new(ICON_SETTLE);
+
+        assert_eq!(debouncer.offer(icon(IconKind::Idle), start), None);
+        assert_eq!(debouncer.next_due(), Some(start + ICON_SETTLE));
+        assert_eq!(
+            debouncer.offer(icon(IconKind::Idle), start + ICON_SETTLE),
+            Some(icon(IconKind::Idle))
+        );
+        assert_eq!(debouncer.next_due(), None);
+        assert_eq!(debouncer.shown(), Some(icon(IconKind::Idle)));
+    }
+
+    #[test]
+    fn debouncer_collapses_a_burst_into_one_change() {
+        let start = Instant::now();
+        let mut debouncer = IconDebouncer::new(ICON_SETTLE);
+        let ms = Duration::from_millis;
+
+        // Flapping between states faster than the settle time shows nothing
+        let burst = [
+            IconKind::NoCameras,
+            IconKind::Idle,
+            IconKind::Error,
+            IconKind::Idle,
+            IconKind::Error,
+        ];
+        for (i, k) in burst.into_iter().enumerate() {
+            assert_eq!(debouncer.offer(icon(k), start + ms(i as u64 * 20)), None);
+        }
+
+        // Only the state that stuck is applied
+        let settled = start + ms(80) + ICON_SETTLE;
+        assert_eq!(
+            debouncer.offer(icon(IconKind::Error), settled),
+            Some(icon(IconKind::Error))
+        );
+    }
+
+    #[test]
+    fn debouncer_ignores_the_icon_already_shown() {
+        let start = Instant::now();
+        let mut debouncer = IconDebouncer::new(ICON_SETTLE);
+        debouncer.offer(icon(IconKind::Idle), start);
+        debouncer.offer(icon(IconKind::Idle), start + ICON_SETTLE);
+
+        // A brief excursion that returns to the shown icon changes nothing
+        let later = start + ICON_SETTLE * 2;
+        assert_eq!(debouncer.offer(icon(IconKind::Error), later), None);
+        assert_eq!(debouncer.offer(icon(IconKind::Idle), later), None);
+        assert_eq!(debouncer.next_due(), None);
+        assert_eq!(
+            debouncer.offer(icon(IconKind::Idle), later + ICON_SETTLE * 2),
+            None
+        );
+    }
+
+    #[test]
+    fn animation_passes_through_the_debouncer() {
+        let start = Instant::now();
+        let mut debouncer = IconDebouncer::new(ICON_SETTLE);
+        let streaming = state(1, true, false, false);
+        let mut applied = Vec::new();
+
+        // Sample every 50ms for three animation intervals
+        let mut t = Duration::ZERO;
+        while t < ANIMATION_INTERVAL * 3 {
+            let id = resolve_icon(&streaming, TrayTheme::Dark, animation_frame(t));
+            if let Some(shown) = debouncer.offer(id, start + t) {
+                applied.push(shown.kind);
+            }
+            t += Duration::from_millis(50);
+        }
+
+        assert_eq!(
+            applied,
+            [IconKind::Idle, IconKind::Streaming, IconKind::Idle]
+        );
+    }
+}
@@ -12,23 +12,32 @@ vi.mock('@tauri-apps/api/event', () => ({
 }))
 
 vi.mock('./features/controls/api', () => ({
-  getCameraControls: vi.fn().mockResolvedValue([]),
+  getCameraControls: vi.fn().mockResolvedValue({ controls: [], cached: false }),
+  onControlsRefreshed: vi.fn().mockResolvedValue(vi.fn()),
+  onControlNudged: vi.fn().mockResolvedValue(vi.fn()),
+  getKeyBindings: vi.fn().mockResolvedValue([]),
+  keyInput: vi.fn().mockResolvedValue(false),
   setCameraControl: vi.fn().mockResolvedValue(undefined),
   resetCameraControl: vi.fn().mockResolvedValue(0),
 }))
 
 vi.mock('./features/camera-sidebar/api', () => ({
   listCameras: vi.fn().mockResolvedValue([]),
+  getDefaultCamera: vi.fn().mockResolvedValue(null),
   onCameraHotplug: vi.fn().mockResolvedValue(vi.fn()),
+  onCamerasChanged: vi.fn().mockResolvedValue(vi.fn()),
+  onCameraRenamed: vi.fn().mockResolvedValue(vi.fn()),
 }))
 
 import App from './App'
+import { getDefaultCamera, listCameras } from './features/camera-sidebar/api'
 
 const cam1: CameraDevice = {
   id: 'cam-1',
   name: 'Logitech C920',
   devicePath: '/dev/video0',
   isConnected: true,
+  kind: 'primary',
 }
 
 const cam2: CameraDevice = {
@@ -36,6 +45,7 @@ const cam2: CameraDevice = {
   name: 'Razer Kiyo',
   devicePath: '/dev/video1',
   isConnected: true,
+  kind: 'primary',
 }
 
 describe('App', () => {
@@ -86,6 +96,30 @@ describe('App', () => {
     expect(screen.getByRole('region', { name: 'Camera controls' })).toBeInTheDocument()
   })
 
+  it('selects the default camera on mount', async () => {
+    vi.mocked(listCameras).mockResolvedValueOnce([cam1, cam2])
+    vi.mocked(getDefaultCamera).mockResolvedValueOnce('cam-2')
+
+    render(<App />)
+
+    await vi.waitFor(() => {
+      expect(useCameraStore.getState().selectedId).toBe('cam-2')
+    })
+  })
+
+  it('keeps an existing selection over the default camera', async () => {
+    useCameraStore.setState({ cameras: [cam1, cam2], selectedId: 'cam-1' })
+    vi.mocked(listCameras).mockResolvedValueOnce([cam1, cam2])
+    vi.mocked(getDefaultCamera).mockResolvedValueOnce('cam-2')
+
+    render(<App />)
+
+    await vi.waitFor(() => {
+      expect(getDefaultCamera).toHaveBeenCalled()
+    })
+    expect(useCameraStore.getState().selectedId).toBe('cam-1')
+  })
+
   it('calls start_all_previews on mount', async () => {
     const { invoke } = await import('@tauri-apps/api/core')
     const mockInvoke = vi.mocked(invoke)
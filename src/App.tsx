@@ -1,8 +1,15 @@
 import { useEffect, useRef } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { useShallow } from 'zustand/react/shallow'
-import { CameraSidebar, listCameras, useCameraStore, useHotplug } from './features/camera-sidebar'
+import {
+  CameraSidebar,
+  getDefaultCamera,
+  listCameras,
+  useCameraStore,
+  useHotplug,
+} from './features/camera-sidebar'
 import { ControlsPanel } from './features/controls/ControlsPanel'
+import { useKeyboardControl } from './features/controls/useKeyboardControl'
 import { ToastContainer } from './features/notifications'
 import { PreviewCanvas } from './features/preview/PreviewCanvas'
 import { usePreview } from './features/preview/usePreview'
@@ -17,7 +24,15 @@ function App() {
   // List cameras and start all backend capture sessions on mount
   useEffect(() => {
     listCameras()
-      .then(setCameras)
+      .then(async (cameras) => {
+        setCameras(cameras)
+        // Open on the default camera unless one was picked already
+        const defaultId = await getDefaultCamera().catch(() => null)
+        const { selectedId, selectCamera } = useCameraStore.getState()
+        if (selectedId === null && defaultId && cameras.some((c) => c.id === defaultId)) {
+          selectCamera(defaultId)
+        }
+      })
       .catch((err: unknown) => {
         console.error('Failed to list cameras:', err)
       })
@@ -30,6 +45,7 @@ function App() {
   useHotplug()
 
   const preview = usePreview(selectedCamera?.id ?? null)
+  useKeyboardControl(selectedCamera?.id ?? null)
 
   // Keep a ref to the latest start/stop so the effect only re-fires on
   // camera ID changes, not when the callback references are recreated.
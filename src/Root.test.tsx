@@ -10,14 +10,21 @@ vi.mock('@tauri-apps/api/event', () => ({
 }))
 
 vi.mock('./features/controls/api', () => ({
-  getCameraControls: vi.fn().mockResolvedValue([]),
+  getCameraControls: vi.fn().mockResolvedValue({ controls: [], cached: false }),
+  onControlsRefreshed: vi.fn().mockResolvedValue(vi.fn()),
+  onControlNudged: vi.fn().mockResolvedValue(vi.fn()),
+  getKeyBindings: vi.fn().mockResolvedValue([]),
+  keyInput: vi.fn().mockResolvedValue(false),
   setCameraControl: vi.fn().mockResolvedValue(undefined),
   resetCameraControl: vi.fn().mockResolvedValue(0),
 }))
 
 vi.mock('./features/camera-sidebar/api', () => ({
   listCameras: vi.fn().mockResolvedValue([]),
+  getDefaultCamera: vi.fn().mockResolvedValue(null),
   onCameraHotplug: vi.fn().mockResolvedValue(vi.fn()),
+  onCamerasChanged: vi.fn().mockResolvedValue(vi.fn()),
+  onCameraRenamed: vi.fn().mockResolvedValue(vi.fn()),
 }))
 
 import { Root } from './Root'
@@ -8,6 +8,7 @@ const device: CameraDevice = {
   name: 'Logitech C920',
   devicePath: '/dev/video0',
   isConnected: true,
+  kind: 'primary',
 }
 
 describe('CameraEntry', () => {
@@ -10,6 +10,7 @@ const cam1: CameraDevice = {
   name: 'Logitech C920',
   devicePath: '/dev/video0',
   isConnected: true,
+  kind: 'primary',
 }
 
 const cam2: CameraDevice = {
@@ -17,6 +18,7 @@ const cam2: CameraDevice = {
   name: 'Razer Kiyo',
   devicePath: '/dev/video1',
   isConnected: true,
+  kind: 'primary',
 }
 
 describe('CameraSidebar', () => {
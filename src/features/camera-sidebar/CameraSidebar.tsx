@@ -1,10 +1,15 @@
 import type { CameraDevice } from '../../types/camera'
 import { useThumbnail } from '../preview/useThumbnail'
+import { useThumbnailSize } from '../preview/useThumbnailSize'
 import { CameraEntry } from './CameraEntry'
 import './CameraSidebar.css'
 import { EmptyState } from './EmptyState'
 import { useCameraStore } from './store'
 
+/** CSS size of the thumbnail box in CameraEntry.css. */
+const THUMBNAIL_WIDTH = 80
+const THUMBNAIL_HEIGHT = 60
+
 function CameraEntryWithThumbnail({
   device,
   isSelected,
@@ -29,6 +34,7 @@ export function CameraSidebar() {
   const cameras = useCameraStore((s) => s.cameras)
   const selectedId = useCameraStore((s) => s.selectedId)
   const selectCamera = useCameraStore((s) => s.selectCamera)
+  useThumbnailSize(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)
 
   if (cameras.length === 0) {
     return (
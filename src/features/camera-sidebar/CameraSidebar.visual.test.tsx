@@ -6,9 +6,27 @@ import './CameraSidebar.css'
 import './CameraEntry.css'
 
 const devices: CameraDevice[] = [
-  { id: 'cam-1', name: 'HD Webcam C920', devicePath: '/dev/video0', isConnected: true },
-  { id: 'cam-2', name: 'USB Camera', devicePath: '/dev/video1', isConnected: true },
-  { id: 'cam-3', name: 'FaceTime HD', devicePath: '/dev/video2', isConnected: true },
+  {
+    id: 'cam-1',
+    name: 'HD Webcam C920',
+    devicePath: '/dev/video0',
+    isConnected: true,
+    kind: 'primary',
+  },
+  {
+    id: 'cam-2',
+    name: 'USB Camera',
+    devicePath: '/dev/video1',
+    isConnected: true,
+    kind: 'primary',
+  },
+  {
+    id: 'cam-3',
+    name: 'FaceTime HD',
+    devicePath: '/dev/video2',
+    isConnected: true,
+    kind: 'primary',
+  },
 ]
 
 test('sidebar with camera entries matches baseline', async () => {
@@ -1,5 +1,11 @@
 import { type Mock, beforeEach, describe, expect, it, vi } from 'vitest'
-import type { CameraDevice } from '../../types/camera'
+import type {
+  CameraDevice,
+  CameraRename,
+  CameraSuggestion,
+  DeviceOperationProgress,
+  StartupProgress,
+} from '../../types/camera'
 
 vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn(),
@@ -11,12 +17,32 @@ vi.mock('@tauri-apps/api/event', () => ({
 
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
-import { listCameras, onCameraHotplug } from './api'
+import {
+  cancelDeviceOperation,
+  getCamera,
+  getDefaultCamera,
+  getShowSuppressedDevices,
+  listCameras,
+  onCameraHotplug,
+  onCameraRenamed,
+  onCamerasChanged,
+  onDeviceOperation,
+  onStartupProgress,
+  setDefaultCamera,
+  setShowSuppressedDevices,
+  suggestDefaultCamera,
+} from './api'
 
 describe('listCameras', () => {
   it('calls invoke with list_cameras', async () => {
     const cameras: CameraDevice[] = [
-      { id: 'cam-1', name: 'Webcam', devicePath: '/dev/video0', isConnected: true },
+      {
+        id: 'cam-1',
+        name: 'Webcam',
+        devicePath: '/dev/video0',
+        isConnected: true,
+        kind: 'primary',
+      },
     ]
     ;(invoke as Mock).mockResolvedValue(cameras)
 
@@ -27,6 +53,74 @@ describe('listCameras', () => {
   })
 })
 
+describe('getCamera', () => {
+  it('calls invoke with get_camera and the device ID', async () => {
+    const camera: CameraDevice = {
+      id: 'cam-1',
+      name: 'Webcam',
+      devicePath: '/dev/video0',
+      isConnected: true,
+      kind: 'primary',
+    }
+    ;(invoke as Mock).mockResolvedValue(camera)
+
+    const result = await getCamera('cam-1')
+
+    expect(invoke).toHaveBeenCalledWith('get_camera', { deviceId: 'cam-1' })
+    expect(result).toEqual(camera)
+  })
+
+  it('returns null for a camera that is not connected', async () => {
+    ;(invoke as Mock).mockResolvedValue(null)
+
+    expect(await getCamera('gone')).toBeNull()
+  })
+})
+
+describe('suggestDefaultCamera', () => {
+  it('calls invoke without probing by default', async () => {
+    const ranked: CameraSuggestion[] = [
+      {
+        deviceId: 'brio',
+        name: 'Logitech BRIO',
+        score: 68,
+        breakdown: { virtualCamera: 0, sibling: 0, controls: 28, resolution: 40, probe: 0 },
+      },
+    ]
+    ;(invoke as Mock).mockResolvedValue(ranked)
+
+    const result = await suggestDefaultCamera()
+
+    expect(invoke).toHaveBeenCalledWith('suggest_default_camera', { probe: false })
+    expect(result).toEqual(ranked)
+  })
+
+  it('passes the probe flag', async () => {
+    ;(invoke as Mock).mockResolvedValue([])
+
+    await suggestDefaultCamera(true)
+
+    expect(invoke).toHaveBeenCalledWith('suggest_default_camera', { probe: true })
+  })
+})
+
+describe('default camera', () => {
+  it('gets the default camera', async () => {
+    ;(invoke as Mock).mockResolvedValue('brio')
+
+    expect(await getDefaultCamera()).toBe('brio')
+    expect(invoke).toHaveBeenCalledWith('get_default_camera')
+  })
+
+  it('sets the default camera', async () => {
+    ;(invoke as Mock).mockResolvedValue(undefined)
+
+    await setDefaultCamera('c920')
+
+    expect(invoke).toHaveBeenCalledWith('set_default_camera', { deviceId: 'c920' })
+  })
+})
+
 describe('onCameraHotplug', () => {
   beforeEach(() => {
     vi.clearAllMocks()
@@ -63,3 +157,143 @@ describe('onCameraHotplug', () => {
     expect(callback).toHaveBeenCalledWith({ type: 'connected', id: 'cam-1', name: 'Webcam' })
   })
 })
+
+describe('onCamerasChanged', () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it('forwards the camera list to callback', async () => {
+    const cameras: CameraDevice[] = [
+      {
+        id: 'canon:r5',
+        name: 'Canon EOS R5',
+        devicePath: 'edsdk://Canon EOS R5',
+        isConnected: true,
+        kind: 'primary',
+      },
+    ]
+    ;(listen as Mock).mockImplementation((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload: cameras })
+      return Promise.resolve(vi.fn())
+    })
+    const callback = vi.fn()
+
+    await onCamerasChanged(callback)
+
+    expect(listen).toHaveBeenCalledWith('cameras-changed', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(cameras)
+  })
+})
+
+describe('onCameraRenamed', () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it('forwards the rename to callback', async () => {
+    const rename: CameraRename = {
+      deviceId: 'cam-1',
+      oldName: 'USB Camera',
+      newName: 'Logitech BRIO',
+    }
+    ;(listen as Mock).mockImplementation((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload: rename })
+      return Promise.resolve(vi.fn())
+    })
+    const callback = vi.fn()
+
+    await onCameraRenamed(callback)
+
+    expect(listen).toHaveBeenCalledWith('camera-renamed', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(rename)
+  })
+})
+
+describe('onDeviceOperation', () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it('forwards operation progress to callback', async () => {
+    const progress: DeviceOperationProgress = {
+      opId: 3,
+      deviceId: 'cam-1',
+      kind: 'preset_apply',
+      progress: 0.5,
+    }
+    ;(listen as Mock).mockImplementation((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload: progress })
+      return Promise.resolve(vi.fn())
+    })
+    const callback = vi.fn()
+
+    await onDeviceOperation(callback)
+
+    expect(listen).toHaveBeenCalledWith('device-operation', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(progress)
+  })
+})
+
+describe('onStartupProgress', () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it('forwards startup progress to callback', async () => {
+    const progress: StartupProgress = {
+      phase: 'ready',
+      devicesFound: 2,
+      pendingBackends: 1,
+    }
+    ;(listen as Mock).mockImplementation((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload: progress })
+      return Promise.resolve(vi.fn())
+    })
+    const callback = vi.fn()
+
+    await onStartupProgress(callback)
+
+    expect(listen).toHaveBeenCalledWith('startup-progress', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(progress)
+  })
+})
+
+describe('cancelDeviceOperation', () => {
+  it('calls invoke with the operation ID', async () => {
+    ;(invoke as Mock).mockResolvedValue(true)
+
+    const result = await cancelDeviceOperation(3)
+
+    expect(invoke).toHaveBeenCalledWith('cancel_device_operation', { opId: 3 })
+    expect(result).toBe(true)
+  })
+})
+
+describe('suppressed devices', () => {
+  it('reads the preference', async () => {
+    ;(invoke as Mock).mockResolvedValue(false)
+
+    expect(await getShowSuppressedDevices()).toBe(false)
+    expect(invoke).toHaveBeenCalledWith('get_show_suppressed_devices')
+  })
+
+  it('sets the preference and returns the new list', async () => {
+    const cameras: CameraDevice[] = [
+      {
+        id: 'ds:eos',
+        name: 'EOS R5',
+        devicePath: '\\\\?\\usb#vid_04a9&pid_32f4#1#{guid}',
+        isConnected: true,
+        kind: 'primary',
+        suppressedBy: 'canon:usb1',
+      },
+    ]
+    ;(invoke as Mock).mockResolvedValue(cameras)
+
+    const result = await setShowSuppressedDevices(true)
+
+    expect(invoke).toHaveBeenCalledWith('set_show_suppressed_devices', { enabled: true })
+    expect(result).toEqual(cameras)
+  })
+})
@@ -1,12 +1,55 @@
 import { invoke } from '@tauri-apps/api/core'
 import { type UnlistenFn, listen } from '@tauri-apps/api/event'
-import type { CameraDevice, HotplugEvent } from '../../types/camera'
+import type {
+  CameraDevice,
+  CameraRename,
+  CameraSuggestion,
+  DeviceOperationProgress,
+  HotplugEvent,
+  StartupProgress,
+} from '../../types/camera'
 
 /** Fetch the current list of cameras from the Rust backend. */
 export async function listCameras(): Promise<CameraDevice[]> {
   return invoke<CameraDevice[]>('list_cameras')
 }
 
+/** Look up one camera, or `null` if it isn't connected. */
+export async function getCamera(deviceId: string): Promise<CameraDevice | null> {
+  return invoke<CameraDevice | null>('get_camera', { deviceId })
+}
+
+/**
+ * Rank connected cameras for use as the default, best first. With `probe`,
+ * running previews are also scored on whether they deliver a frame.
+ */
+export async function suggestDefaultCamera(probe = false): Promise<CameraSuggestion[]> {
+  return invoke<CameraSuggestion[]>('suggest_default_camera', { probe })
+}
+
+/** Device ID of the default camera, suggested on first run if never chosen. */
+export async function getDefaultCamera(): Promise<string | null> {
+  return invoke<string | null>('get_default_camera')
+}
+
+/** Choose the default camera. */
+export async function setDefaultCamera(deviceId: string): Promise<void> {
+  return invoke('set_default_camera', { deviceId })
+}
+
+/** Whether cameras hidden as duplicates of another backend's device are listed. */
+export async function getShowSuppressedDevices(): Promise<boolean> {
+  return invoke<boolean>('get_show_suppressed_devices')
+}
+
+/**
+ * List or hide cameras suppressed as duplicates of another backend's device
+ * (listed ones carry `suppressedBy`). Resolves to the new camera list.
+ */
+export async function setShowSuppressedDevices(enabled: boolean): Promise<CameraDevice[]> {
+  return invoke<CameraDevice[]>('set_show_suppressed_devices', { enabled })
+}
+
 /** Subscribe to camera hot-plug events. Returns an unlisten function. */
 export async function onCameraHotplug(
   callback: (event: HotplugEvent) => void,
@@ -15,3 +58,57 @@ export async function onCameraHotplug(
     callback(event.payload)
   })
 }
+
+/**
+ * Subscribe to full camera list replacements, emitted when the backend is
+ * rebuilt (e.g. the Canon integration is toggled). Returns an unlisten function.
+ */
+export async function onCamerasChanged(
+  callback: (cameras: CameraDevice[]) => void,
+): Promise<UnlistenFn> {
+  return listen<CameraDevice[]>('cameras-changed', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Subscribe to cameras whose reported name changed under the same device ID,
+ * e.g. after a driver update. Returns an unlisten function.
+ */
+export async function onCameraRenamed(
+  callback: (rename: CameraRename) => void,
+): Promise<UnlistenFn> {
+  return listen<CameraRename>('camera-renamed', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Subscribe to progress of queued device operations (preview restarts,
+ * preset applies, ...). Returns an unlisten function.
+ */
+export async function onDeviceOperation(
+  callback: (progress: DeviceOperationProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<DeviceOperationProgress>('device-operation', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Subscribe to startup discovery progress. Cameras found at startup arrive
+ * over several seconds when a backend is slow, so the UI can show a loading
+ * state until the phase leaves `enumerating`. Returns an unlisten function.
+ */
+export async function onStartupProgress(
+  callback: (progress: StartupProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<StartupProgress>('startup-progress', (event) => {
+    callback(event.payload)
+  })
+}
+
+/** Cancel a queued or running device operation. Resolves to whether it was still pending. */
+export async function cancelDeviceOperation(opId: number): Promise<boolean> {
+  return invoke<boolean>('cancel_device_operation', { opId })
+}
@@ -1,4 +1,10 @@
 export { CameraSidebar } from './CameraSidebar'
 export { useCameraStore } from './store'
 export { useHotplug } from './useHotplug'
-export { listCameras } from './api'
+export {
+  getCamera,
+  getDefaultCamera,
+  listCameras,
+  setDefaultCamera,
+  suggestDefaultCamera,
+} from './api'
@@ -7,6 +7,7 @@ const cam1: CameraDevice = {
   name: 'Logitech C920',
   devicePath: '/dev/video0',
   isConnected: true,
+  kind: 'primary',
 }
 
 const cam2: CameraDevice = {
@@ -14,6 +15,7 @@ const cam2: CameraDevice = {
   name: 'Razer Kiyo',
   devicePath: '/dev/video1',
   isConnected: true,
+  kind: 'primary',
 }
 
 const cam3: CameraDevice = {
@@ -21,6 +23,7 @@ const cam3: CameraDevice = {
   name: 'Elgato Facecam',
   devicePath: '/dev/video2',
   isConnected: true,
+  kind: 'primary',
 }
 
 describe('useCameraStore', () => {
@@ -84,6 +87,14 @@ describe('useCameraStore', () => {
     expect(useCameraStore.getState().selectedId).toBe('cam-1')
   })
 
+  it('renames a camera in place via renameCamera', () => {
+    useCameraStore.getState().setCameras([cam1, cam2])
+    useCameraStore.getState().renameCamera('cam-1', 'Logitech C920e')
+    const { cameras } = useCameraStore.getState()
+    expect(cameras.map((c) => c.name)).toEqual(['Logitech C920e', 'Razer Kiyo'])
+    expect(cameras[0]).toEqual({ ...cam1, name: 'Logitech C920e' })
+  })
+
   it('returns the selected camera via selectedCamera', () => {
     useCameraStore.getState().setCameras([cam1, cam2])
     useCameraStore.getState().selectCamera('cam-1')
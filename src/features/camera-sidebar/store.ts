@@ -8,6 +8,7 @@ interface CameraStore {
   selectCamera: (id: string) => void
   addCamera: (device: CameraDevice) => void
   removeCamera: (id: string) => void
+  renameCamera: (id: string, name: string) => void
   selectedCamera: () => CameraDevice | undefined
 }
 
@@ -42,6 +43,11 @@ export const useCameraStore = create<CameraStore>((set, get) => ({
       return { cameras: remaining, selectedId: nextId }
     }),
 
+  renameCamera: (id, name) =>
+    set((state) => ({
+      cameras: state.cameras.map((c) => (c.id === id ? { ...c, name } : c)),
+    })),
+
   selectedCamera: () => {
     const { cameras, selectedId } = get()
     return cameras.find((c) => c.id === selectedId)
@@ -6,9 +6,15 @@ import { useCameraStore } from './store'
 const mockUnlisten = vi.fn()
 const mockOnCameraHotplug = vi.fn()
 const mockUnlistenSettings = vi.fn()
+const mockOnCamerasChanged = vi.fn()
+const mockUnlistenCameras = vi.fn()
+const mockOnCameraRenamed = vi.fn()
+const mockUnlistenRenamed = vi.fn()
 
 vi.mock('./api', () => ({
   onCameraHotplug: (...args: unknown[]) => mockOnCameraHotplug(...args),
+  onCamerasChanged: (...args: unknown[]) => mockOnCamerasChanged(...args),
+  onCameraRenamed: (...args: unknown[]) => mockOnCameraRenamed(...args),
 }))
 
 vi.mock('@tauri-apps/api/event', () => ({
@@ -24,6 +30,8 @@ describe('useHotplug', () => {
   beforeEach(() => {
     vi.clearAllMocks()
     mockOnCameraHotplug.mockResolvedValue(mockUnlisten)
+    mockOnCamerasChanged.mockResolvedValue(mockUnlistenCameras)
+    mockOnCameraRenamed.mockResolvedValue(mockUnlistenRenamed)
     mockListen.mockResolvedValue(mockUnlistenSettings)
     useCameraStore.setState({ cameras: [], selectedId: null })
     useToastStore.setState({ toasts: [] })
@@ -61,13 +69,27 @@ describe('useHotplug', () => {
     renderHook(() => useHotplug())
 
     expect(useCameraStore.getState().cameras).toEqual([
-      { id: 'cam-1', name: 'Webcam', devicePath: '/dev/video0', isConnected: true },
+      {
+        id: 'cam-1',
+        name: 'Webcam',
+        devicePath: '/dev/video0',
+        isConnected: true,
+        kind: 'primary',
+      },
     ])
   })
 
   it('calls removeCamera on disconnected event', () => {
     useCameraStore.setState({
-      cameras: [{ id: 'cam-1', name: 'Webcam', devicePath: '/dev/video0', isConnected: true }],
+      cameras: [
+        {
+          id: 'cam-1',
+          name: 'Webcam',
+          devicePath: '/dev/video0',
+          isConnected: true,
+          kind: 'primary',
+        },
+      ],
     })
 
     mockOnCameraHotplug.mockImplementation((callback: (event: unknown) => void) => {
@@ -105,7 +127,13 @@ describe('useHotplug', () => {
   it('shows info toast on camera disconnected with camera name', () => {
     useCameraStore.setState({
       cameras: [
-        { id: 'cam-1', name: 'Logitech C920', devicePath: '/dev/video0', isConnected: true },
+        {
+          id: 'cam-1',
+          name: 'Logitech C920',
+          devicePath: '/dev/video0',
+          isConnected: true,
+          kind: 'primary',
+        },
       ],
     })
 
@@ -140,6 +168,92 @@ describe('useHotplug', () => {
     })
   })
 
+  // --- Cameras changed ---
+
+  it('replaces the camera list on cameras-changed', () => {
+    useCameraStore.setState({
+      cameras: [
+        {
+          id: 'cam-1',
+          name: 'Webcam',
+          devicePath: '/dev/video0',
+          isConnected: true,
+          kind: 'primary',
+        },
+      ],
+    })
+    const rebuilt = [
+      {
+        id: 'cam-1',
+        name: 'Webcam',
+        devicePath: '/dev/video0',
+        isConnected: true,
+        kind: 'primary',
+      },
+      {
+        id: 'canon:r5',
+        name: 'Canon EOS R5',
+        devicePath: 'edsdk://Canon EOS R5',
+        isConnected: true,
+        kind: 'primary',
+      },
+    ]
+    mockOnCamerasChanged.mockImplementation((callback: (cameras: unknown) => void) => {
+      callback(rebuilt)
+      return Promise.resolve(mockUnlistenCameras)
+    })
+
+    renderHook(() => useHotplug())
+
+    expect(useCameraStore.getState().cameras).toEqual(rebuilt)
+  })
+
+  it('unsubscribes from cameras-changed on unmount', async () => {
+    const { unmount } = renderHook(() => useHotplug())
+
+    await vi.waitFor(() => {
+      expect(mockOnCamerasChanged).toHaveBeenCalled()
+    })
+
+    unmount()
+    expect(mockUnlistenCameras).toHaveBeenCalled()
+  })
+
+  // --- Camera renamed ---
+
+  it('updates the camera name on camera-renamed', () => {
+    useCameraStore.setState({
+      cameras: [
+        {
+          id: 'cam-1',
+          name: 'USB Camera',
+          devicePath: '/dev/video0',
+          isConnected: true,
+          kind: 'primary',
+        },
+      ],
+    })
+    mockOnCameraRenamed.mockImplementation((callback: (rename: unknown) => void) => {
+      callback({ deviceId: 'cam-1', oldName: 'USB Camera', newName: 'Logitech BRIO' })
+      return Promise.resolve(mockUnlistenRenamed)
+    })
+
+    renderHook(() => useHotplug())
+
+    expect(useCameraStore.getState().cameras[0].name).toBe('Logitech BRIO')
+  })
+
+  it('unsubscribes from camera-renamed on unmount', async () => {
+    const { unmount } = renderHook(() => useHotplug())
+
+    await vi.waitFor(() => {
+      expect(mockOnCameraRenamed).toHaveBeenCalled()
+    })
+
+    unmount()
+    expect(mockUnlistenRenamed).toHaveBeenCalled()
+  })
+
   // --- Settings restored ---
 
   it('subscribes to settings-restored events on mount', () => {
@@ -2,13 +2,18 @@ import { listen } from '@tauri-apps/api/event'
 import { useEffect } from 'react'
 import type { HotplugEvent, SettingsRestoredPayload } from '../../types/camera'
 import { useToastStore } from '../notifications/useToast'
-import { onCameraHotplug } from './api'
+import { onCameraHotplug, onCameraRenamed, onCamerasChanged } from './api'
 import { useCameraStore } from './store'
 
-/** Subscribes to camera hot-plug events and settings-restored events. */
+/**
+ * Subscribes to camera hot-plug, cameras-changed, camera-renamed and
+ * settings-restored events.
+ */
 export function useHotplug() {
   useEffect(() => {
     let unlistenHotplug: (() => void) | undefined
+    let unlistenCameras: (() => void) | undefined
+    let unlistenRenamed: (() => void) | undefined
     let unlistenSettings: (() => void) | undefined
 
     onCameraHotplug((event: HotplugEvent) => {
@@ -19,6 +24,8 @@ export function useHotplug() {
           name,
           devicePath: event.devicePath ?? '',
           isConnected: event.isConnected ?? true,
+          kind: event.kind ?? 'primary',
+          primaryId: event.primaryId,
         })
         useToastStore.getState().addToast(`${name} connected`, 'success')
       } else if (event.type === 'disconnected') {
@@ -33,6 +40,20 @@ export function useHotplug() {
       unlistenHotplug = fn
     })
 
+    // Individual changes already arrived as hotplug events; this replaces the
+    // list with the backend's grouped view (sibling kinds included)
+    onCamerasChanged((cameras) => {
+      useCameraStore.getState().setCameras(cameras)
+    }).then((fn) => {
+      unlistenCameras = fn
+    })
+
+    onCameraRenamed((rename) => {
+      useCameraStore.getState().renameCamera(rename.deviceId, rename.newName)
+    }).then((fn) => {
+      unlistenRenamed = fn
+    })
+
     listen<SettingsRestoredPayload>('settings-restored', (event) => {
       if (event.payload.controlsApplied > 0) {
         useToastStore
@@ -45,6 +66,8 @@ export function useHotplug() {
 
     return () => {
       unlistenHotplug?.()
+      unlistenCameras?.()
+      unlistenRenamed?.()
       unlistenSettings?.()
     }
   }, [])
@@ -13,6 +13,7 @@ const slider: ControlDescriptor = {
   step: 1,
   default: 128,
   current: 128,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
@@ -27,6 +28,7 @@ const toggle: ControlDescriptor = {
   step: 1,
   default: 1,
   current: 1,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
@@ -41,6 +43,7 @@ const select: ControlDescriptor = {
   step: 1,
   default: 1,
   current: 1,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
@@ -14,6 +14,7 @@ const brightness: ControlDescriptor = {
   step: 1,
   default: 128,
   current: 150,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
@@ -14,6 +14,7 @@ const unsupportedControl: ControlDescriptor = {
   step: 1,
   default: 128,
   current: 64,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: false,
 }
@@ -14,6 +14,7 @@ const colorEnable: ControlDescriptor = {
   step: 1,
   default: 1,
   current: 1,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
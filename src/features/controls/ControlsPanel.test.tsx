@@ -1,20 +1,34 @@
-import { render, screen, waitFor } from '@testing-library/react'
+import { act, render, screen, waitFor } from '@testing-library/react'
 import userEvent from '@testing-library/user-event'
 import { beforeEach, describe, expect, it, vi } from 'vitest'
-import type { ControlDescriptor } from '../../types/camera'
+import type {
+  ControlDescriptor,
+  ControlNudged,
+  ControlsRefreshedPayload,
+} from '../../types/camera'
 import { useToastStore } from '../notifications/useToast'
 import { ControlsPanel } from './ControlsPanel'
 
 vi.mock('./api', () => ({
   getCameraControls: vi.fn(),
+  onControlNudged: vi.fn(),
+  onControlsRefreshed: vi.fn(),
   setCameraControl: vi.fn(),
   resetCameraControl: vi.fn(),
   resetAllToDefaults: vi.fn(),
   getSavedSettings: vi.fn(),
 }))
 
-const { getCameraControls, setCameraControl, resetCameraControl } = await import('./api')
+const {
+  getCameraControls,
+  onControlNudged,
+  onControlsRefreshed,
+  setCameraControl,
+  resetCameraControl,
+} = await import('./api')
 const mockGetControls = vi.mocked(getCameraControls)
+const mockOnRefreshed = vi.mocked(onControlsRefreshed)
+const mockOnNudged = vi.mocked(onControlNudged)
 const mockSetControl = vi.mocked(setCameraControl)
 const mockResetControl = vi.mocked(resetCameraControl)
 
@@ -28,6 +42,7 @@ const brightness: ControlDescriptor = {
   step: 1,
   default: 128,
   current: 150,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
@@ -50,6 +65,7 @@ const exposure: ControlDescriptor = {
   step: 1,
   default: -6,
   current: -6,
+  defaultAuto: false,
   flags: { supportsAuto: true, isAutoEnabled: true, isReadOnly: false },
   supported: true,
 }
@@ -64,6 +80,7 @@ const pan: ControlDescriptor = {
   step: 1,
   default: 0,
   current: 0,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: false,
 }
@@ -73,6 +90,10 @@ const allControls = [brightness, contrast, exposure, pan]
 describe('ControlsPanel', () => {
   beforeEach(() => {
     mockGetControls.mockReset()
+    mockOnRefreshed.mockReset()
+    mockOnRefreshed.mockResolvedValue(vi.fn())
+    mockOnNudged.mockReset()
+    mockOnNudged.mockResolvedValue(vi.fn())
     mockSetControl.mockReset()
     mockResetControl.mockReset()
   })
@@ -80,7 +101,7 @@ describe('ControlsPanel', () => {
   // --- Loading ---
 
   it('fetches controls when selectedCameraId changes', async () => {
-    mockGetControls.mockResolvedValue(allControls)
+    mockGetControls.mockResolvedValue({ controls: allControls, cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(mockGetControls).toHaveBeenCalledWith('cam-1')
@@ -96,7 +117,7 @@ describe('ControlsPanel', () => {
   // --- Grouping ---
 
   it('groups controls by group field into accordion sections', async () => {
-    mockGetControls.mockResolvedValue(allControls)
+    mockGetControls.mockResolvedValue({ controls: allControls, cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(screen.getByText('Image')).toBeInTheDocument()
@@ -106,7 +127,7 @@ describe('ControlsPanel', () => {
   })
 
   it('expands "Image" group by default', async () => {
-    mockGetControls.mockResolvedValue(allControls)
+    mockGetControls.mockResolvedValue({ controls: allControls, cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(screen.getByText('Brightness')).toBeVisible()
@@ -114,7 +135,7 @@ describe('ControlsPanel', () => {
   })
 
   it('shows single expanded section when camera has <= 3 controls', async () => {
-    mockGetControls.mockResolvedValue([brightness])
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(screen.getByText('Brightness')).toBeVisible()
@@ -125,8 +146,8 @@ describe('ControlsPanel', () => {
 
   it('calls setCameraControl IPC on slider change', async () => {
     const user = userEvent.setup()
-    mockGetControls.mockResolvedValue([brightness])
-    mockSetControl.mockResolvedValue(undefined)
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
+    mockSetControl.mockResolvedValue({ value: 200, snapped: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
 
     await waitFor(() => {
@@ -145,9 +166,32 @@ describe('ControlsPanel', () => {
     })
   })
 
+  it('moves the slider to the snapped value', async () => {
+    const user = userEvent.setup()
+    mockGetControls.mockResolvedValue({ controls: [{ ...brightness, step: 10 }], cached: false })
+    mockSetControl.mockResolvedValue({ value: 200, snapped: true })
+    render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
+
+    await waitFor(() => {
+      expect(screen.getByRole('slider')).toBeInTheDocument()
+    })
+
+    await user.click(screen.getByText('150'))
+    const input = screen.getByRole('spinbutton')
+    await user.clear(input)
+    await user.type(input, '203')
+    await user.keyboard('{Enter}')
+
+    // The readout shows the written value, not the one typed
+    await waitFor(() => {
+      expect(screen.getByText('200')).toBeInTheDocument()
+    })
+    expect(screen.queryByText('203')).not.toBeInTheDocument()
+  })
+
   it('reverts slider on backend rejection', async () => {
     const user = userEvent.setup()
-    mockGetControls.mockResolvedValue([brightness])
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
     mockSetControl.mockRejectedValue(new Error('Hardware rejected'))
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
 
@@ -168,7 +212,7 @@ describe('ControlsPanel', () => {
 
   it('calls resetCameraControl on reset button click', async () => {
     const user = userEvent.setup()
-    mockGetControls.mockResolvedValue([brightness])
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
     mockResetControl.mockResolvedValue(128)
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
 
@@ -185,7 +229,7 @@ describe('ControlsPanel', () => {
 
   it('updates slider to returned default value after reset', async () => {
     const user = userEvent.setup()
-    mockGetControls.mockResolvedValue([brightness])
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
     mockResetControl.mockResolvedValue(128)
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
 
@@ -203,7 +247,7 @@ describe('ControlsPanel', () => {
   // --- Empty controls ---
 
   it('shows "No adjustable controls" when camera returns empty controls', async () => {
-    mockGetControls.mockResolvedValue([])
+    mockGetControls.mockResolvedValue({ controls: [], cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(screen.getByText(/no adjustable controls/i)).toBeInTheDocument()
@@ -220,7 +264,7 @@ describe('ControlsPanel', () => {
   // --- Accessibility ---
 
   it('panel has aria-label "Camera controls"', async () => {
-    mockGetControls.mockResolvedValue([brightness])
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(screen.getByRole('region', { name: 'Camera controls' })).toBeInTheDocument()
@@ -230,7 +274,7 @@ describe('ControlsPanel', () => {
   // --- Reset all ---
 
   it('renders "Reset all to defaults" button when controls are loaded', async () => {
-    mockGetControls.mockResolvedValue(allControls)
+    mockGetControls.mockResolvedValue({ controls: allControls, cached: false })
     render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
     await waitFor(() => {
       expect(screen.getByRole('button', { name: /reset all to defaults/i })).toBeInTheDocument()
@@ -263,4 +307,109 @@ describe('ControlsPanel', () => {
     })
     addToastSpy.mockRestore()
   })
+
+  // --- Keyboard nudges ---
+
+  it('moves the slider to nudged values for this camera only', async () => {
+    let emit: ((payload: ControlNudged) => void) | undefined
+    mockOnNudged.mockImplementation((callback) => {
+      emit = callback
+      return Promise.resolve(vi.fn())
+    })
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: false })
+    render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
+    await waitFor(() => {
+      expect(screen.getByText('150')).toBeInTheDocument()
+    })
+
+    act(() => emit?.({ deviceId: 'cam-2', controlId: 'brightness', value: 10 }))
+    expect(screen.getByText('150')).toBeInTheDocument()
+
+    act(() => emit?.({ deviceId: 'cam-1', controlId: 'brightness', value: 158 }))
+    expect(screen.getByText('158')).toBeInTheDocument()
+  })
+
+  // --- Cached controls ---
+
+  /** Capture the controls-refreshed listener so tests can fire it. */
+  function captureRefresh() {
+    let emit: ((payload: ControlsRefreshedPayload) => void) | undefined
+    mockOnRefreshed.mockImplementation((callback) => {
+      emit = callback
+      return Promise.resolve(vi.fn())
+    })
+    return (payload: Omit<ControlsRefreshedPayload, 'changed'>) =>
+      act(() => emit?.({ ...payload, changed: true }))
+  }
+
+  it('replaces cached controls with the refreshed list', async () => {
+    const refresh = captureRefresh()
+    mockGetControls.mockResolvedValue({ controls: [brightness, contrast], cached: true })
+    render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
+    await waitFor(() => {
+      expect(screen.getByText('Contrast')).toBeInTheDocument()
+    })
+
+    refresh({
+      deviceId: 'cam-1',
+      controls: [brightness, exposure],
+      added: ['exposure'],
+      removed: ['contrast'],
+    })
+
+    expect(screen.queryByText('Contrast')).not.toBeInTheDocument()
+    expect(screen.getByText('Exposure')).toBeInTheDocument()
+  })
+
+  it('ignores refreshes for other cameras', async () => {
+    const refresh = captureRefresh()
+    mockGetControls.mockResolvedValue({ controls: [brightness], cached: true })
+    render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
+    await waitFor(() => {
+      expect(screen.getByText('150')).toBeInTheDocument()
+    })
+
+    refresh({
+      deviceId: 'cam-2',
+      controls: [{ ...brightness, current: 10 }],
+      added: [],
+      removed: [],
+    })
+
+    expect(screen.getByText('150')).toBeInTheDocument()
+  })
+
+  it('keeps values changed before the refresh arrives', async () => {
+    const user = userEvent.setup()
+    const refresh = captureRefresh()
+    mockGetControls.mockResolvedValue({ controls: [brightness, contrast], cached: true })
+    mockSetControl.mockResolvedValue({ value: 200, snapped: false })
+    render(<ControlsPanel cameraId="cam-1" cameraName="Test Cam" />)
+    await waitFor(() => {
+      expect(screen.getByText('150')).toBeInTheDocument()
+    })
+
+    await user.click(screen.getByText('150'))
+    const input = screen.getByRole('spinbutton')
+    await user.clear(input)
+    await user.type(input, '200')
+    await user.keyboard('{Enter}')
+    await waitFor(() => {
+      expect(mockSetControl).toHaveBeenCalled()
+    })
+
+    refresh({
+      deviceId: 'cam-1',
+      controls: [
+        { ...brightness, current: 90 },
+        { ...contrast, current: 40 },
+      ],
+      added: [],
+      removed: [],
+    })
+
+    // Brightness keeps the user's value, contrast takes the live one
+    expect(screen.getByText('200')).toBeInTheDocument()
+    expect(screen.getByText('40')).toBeInTheDocument()
+  })
 })
@@ -5,7 +5,14 @@ import { AccordionSection } from './AccordionSection'
 import { ControlRenderer } from './ControlRenderer'
 import './ControlsPanel.css'
 import { ResetAllButton } from './ResetAllButton'
-import { getCameraControls, resetCameraControl, setCameraControl } from './api'
+import {
+  getCameraControls,
+  onControlNudged,
+  onControlsRefreshed,
+  resetCameraControl,
+  setCameraControl,
+} from './api'
+import { errorMessage } from '../../types/error'
 
 /** Display labels for control groups. */
 const GROUP_LABELS: Record<ControlGroup, string> = {
@@ -32,29 +39,48 @@ interface PanelState {
   descriptors: ControlDescriptor[]
   values: Record<string, ControlValue>
   loading: boolean
+  /** Controls changed in this panel since loading, kept across a refresh. */
+  edited: Record<string, true>
 }
 
 type PanelAction =
   | { type: 'fetch_start' }
   | { type: 'fetch_success'; controls: ControlDescriptor[] }
+  | { type: 'refresh'; controls: ControlDescriptor[] }
   | { type: 'fetch_error' }
   | { type: 'set_value'; controlId: string; value: number }
   | { type: 'set_error'; controlId: string; value: number; error: string }
   | { type: 'reset_value'; controlId: string; value: number }
   | { type: 'reset_all'; results: ResetResult[] }
 
-const initialState: PanelState = { descriptors: [], values: {}, loading: false }
+const initialState: PanelState = { descriptors: [], values: {}, loading: false, edited: {} }
 
 function panelReducer(state: PanelState, action: PanelAction): PanelState {
   switch (action.type) {
     case 'fetch_start':
-      return { descriptors: [], values: {}, loading: true }
+      return { descriptors: [], values: {}, loading: true, edited: {} }
     case 'fetch_success': {
+      // A refresh that beat the fetch result already holds the live list
+      if (!state.loading) return state
       const values: Record<string, ControlValue> = {}
       for (const c of action.controls) {
         values[c.id] = { value: c.current }
       }
-      return { descriptors: action.controls, values, loading: false }
+      return { descriptors: action.controls, values, loading: false, edited: {} }
+    }
+    case 'refresh': {
+      // Take the live list, but keep values the user has changed meanwhile
+      const values: Record<string, ControlValue> = {}
+      const edited: Record<string, true> = {}
+      for (const c of action.controls) {
+        if (state.edited[c.id] && state.values[c.id]) {
+          values[c.id] = state.values[c.id]
+          edited[c.id] = true
+        } else {
+          values[c.id] = { value: c.current }
+        }
+      }
+      return { descriptors: action.controls, values, loading: false, edited }
     }
     case 'fetch_error':
       return { ...state, loading: false }
@@ -62,6 +88,7 @@ function panelReducer(state: PanelState, action: PanelAction): PanelState {
       return {
         ...state,
         values: { ...state.values, [action.controlId]: { value: action.value } },
+        edited: { ...state.edited, [action.controlId]: true },
       }
     case 'set_error':
       return {
@@ -75,13 +102,16 @@ function panelReducer(state: PanelState, action: PanelAction): PanelState {
       return {
         ...state,
         values: { ...state.values, [action.controlId]: { value: action.value } },
+        edited: { ...state.edited, [action.controlId]: true },
       }
     case 'reset_all': {
       const values: Record<string, ControlValue> = { ...state.values }
+      const edited: Record<string, true> = { ...state.edited }
       for (const r of action.results) {
         values[r.controlId] = { value: r.value }
+        edited[r.controlId] = true
       }
-      return { ...state, values }
+      return { ...state, values, edited }
     }
   }
 }
@@ -93,23 +123,45 @@ export function ControlsPanel({ cameraId, cameraName }: ControlsPanelProps) {
     if (!cameraId) return
 
     let cancelled = false
+    let unlisten: (() => void) | undefined
+    let unlistenNudged: (() => void) | undefined
     dispatch({ type: 'fetch_start' })
 
+    // Cached lists are followed by the live one once the camera answers
+    onControlsRefreshed((payload) => {
+      if (cancelled || payload.deviceId !== cameraId || !payload.changed) return
+      dispatch({ type: 'refresh', controls: payload.controls })
+    }).then((fn) => {
+      if (cancelled) fn()
+      else unlisten = fn
+    })
+
+    // Follow controls nudged from the keyboard
+    onControlNudged((payload) => {
+      if (cancelled || payload.deviceId !== cameraId) return
+      dispatch({ type: 'set_value', controlId: payload.controlId, value: payload.value })
+    }).then((fn) => {
+      if (cancelled) fn()
+      else unlistenNudged = fn
+    })
+
     getCameraControls(cameraId).then(
-      (controls) => {
+      ({ controls }) => {
         if (cancelled) return
         dispatch({ type: 'fetch_success', controls })
       },
       (err: unknown) => {
         if (cancelled) return
         dispatch({ type: 'fetch_error' })
-        const message = err instanceof Error ? err.message : String(err)
+        const message = errorMessage(err)
         useToastStore.getState().addToast(`Failed to load controls: ${message}`, 'error')
       },
     )
 
     return () => {
       cancelled = true
+      unlisten?.()
+      unlistenNudged?.()
     }
   }, [cameraId])
 
@@ -121,15 +173,23 @@ export function ControlsPanel({ cameraId, cameraName }: ControlsPanelProps) {
 
       dispatch({ type: 'set_value', controlId, value: newValue })
 
-      setCameraControl(cameraId, controlId, newValue, cameraName).catch((err: unknown) => {
-        const message = err instanceof Error ? err.message : 'Control rejected by hardware'
-        dispatch({
-          type: 'set_error',
-          controlId,
-          value: previousValue ?? newValue,
-          error: message,
-        })
-      })
+      setCameraControl(cameraId, controlId, newValue, cameraName).then(
+        (written) => {
+          // Move the slider to the value the camera actually took
+          if (written.snapped) {
+            dispatch({ type: 'set_value', controlId, value: written.value })
+          }
+        },
+        (err: unknown) => {
+          const message = errorMessage(err, 'Control rejected by hardware')
+          dispatch({
+            type: 'set_error',
+            controlId,
+            value: previousValue ?? newValue,
+            error: message,
+          })
+        },
+      )
     },
     [cameraId, cameraName, values],
   )
@@ -147,7 +207,7 @@ export function ControlsPanel({ cameraId, cameraName }: ControlsPanelProps) {
           dispatch({ type: 'reset_value', controlId, value: defaultValue })
         },
         (err: unknown) => {
-          const message = err instanceof Error ? err.message : 'Reset failed'
+          const message = errorMessage(err, 'Reset failed')
           dispatch({
             type: 'set_error',
             controlId,
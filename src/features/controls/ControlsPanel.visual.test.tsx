@@ -20,6 +20,7 @@ const imageControls: ControlDescriptor[] = [
     step: 1,
     default: 128,
     current: 128,
+    defaultAuto: false,
     flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
     supported: true,
   },
@@ -33,6 +34,7 @@ const imageControls: ControlDescriptor[] = [
     step: 1,
     default: 128,
     current: 100,
+    defaultAuto: false,
     flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
     supported: true,
   },
@@ -49,6 +51,7 @@ const exposureControls: ControlDescriptor[] = [
     step: 1,
     default: 1,
     current: 1,
+    defaultAuto: false,
     flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
     supported: true,
   },
@@ -62,6 +65,7 @@ const exposureControls: ControlDescriptor[] = [
     step: 1,
     default: 0,
     current: 2,
+    defaultAuto: false,
     flags: { supportsAuto: true, isAutoEnabled: false, isReadOnly: false },
     supported: true,
   },
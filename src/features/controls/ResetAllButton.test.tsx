@@ -1,6 +1,7 @@
 import { render, screen, waitFor } from '@testing-library/react'
 import userEvent from '@testing-library/user-event'
 import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { ResetResult } from '../../types/camera'
 import { useToastStore } from '../notifications/useToast'
 import { ResetAllButton } from './ResetAllButton'
 
@@ -51,7 +52,7 @@ describe('ResetAllButton', () => {
 
   it('calls resetAllToDefaults IPC on confirm', async () => {
     const user = userEvent.setup()
-    const results = [{ controlId: 'brightness', value: 128 }]
+    const results = [{ controlId: 'brightness', value: 128, auto: false }]
     mockResetAll.mockResolvedValueOnce(results)
     render(<ResetAllButton {...defaultProps} />)
 
@@ -65,7 +66,7 @@ describe('ResetAllButton', () => {
 
   it('calls onReset with results after successful IPC', async () => {
     const user = userEvent.setup()
-    const results = [{ controlId: 'brightness', value: 128 }]
+    const results = [{ controlId: 'brightness', value: 128, auto: false }]
     mockResetAll.mockResolvedValueOnce(results)
     render(<ResetAllButton {...defaultProps} />)
 
@@ -79,7 +80,7 @@ describe('ResetAllButton', () => {
 
   it('shows success toast after reset', async () => {
     const user = userEvent.setup()
-    const results = [{ controlId: 'brightness', value: 128 }]
+    const results = [{ controlId: 'brightness', value: 128, auto: false }]
     mockResetAll.mockResolvedValueOnce(results)
     render(<ResetAllButton {...defaultProps} />)
 
@@ -128,7 +129,7 @@ describe('ResetAllButton', () => {
   it('disables confirm button while resetting to prevent double-submit', async () => {
     const user = userEvent.setup()
     // Use a promise that never resolves to keep the resetting state active
-    let resolveReset: (value: { controlId: string; value: number }[]) => void = () => {}
+    let resolveReset: (value: ResetResult[]) => void = () => {}
     mockResetAll.mockReturnValue(
       new Promise((resolve) => {
         resolveReset = resolve
@@ -148,6 +149,6 @@ describe('ResetAllButton', () => {
     })
 
     // Clean up by resolving the promise
-    resolveReset([{ controlId: 'brightness', value: 128 }])
+    resolveReset([{ controlId: 'brightness', value: 128, auto: false }])
   })
 })
@@ -4,6 +4,7 @@ import { useToastStore } from '../notifications/useToast'
 import { ConfirmModal } from './ConfirmModal'
 import './ResetAllButton.css'
 import { resetAllToDefaults } from './api'
+import { errorMessage } from '../../types/error'
 
 interface ResetAllButtonProps {
   cameraId: string
@@ -23,7 +24,7 @@ export function ResetAllButton({ cameraId, cameraName, onReset }: ResetAllButton
       useToastStore.getState().addToast('All controls reset to defaults', 'success')
       setModalOpen(false)
     } catch (err: unknown) {
-      const message = err instanceof Error ? err.message : 'Reset failed'
+      const message = errorMessage(err, 'Reset failed')
       useToastStore.getState().addToast(message, 'error')
     } finally {
       setResetting(false)
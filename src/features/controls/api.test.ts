@@ -1,19 +1,60 @@
 import { beforeEach, describe, expect, it, vi } from 'vitest'
 import type { ControlDescriptor } from '../../types/camera'
 import {
+  activateScene,
+  applyPreset,
+  deletePreset,
+  deleteScene,
+  getCameraControl,
   getCameraControls,
+  getCameraFormatsGrouped,
+  getControlGuards,
+  getDeviceCapabilities,
   getSavedSettings,
+  getSchedule,
+  getSettingsDrift,
+  getTallyAuto,
+  getVideoSources,
+  listPresets,
+  listScenes,
+  onControlsRefreshed,
+  onGuardTriggered,
+  onSceneActivated,
+  onScheduleApplied,
+  onSettingsDegraded,
+  onSettingsReconciled,
+  removeControlGuard,
   resetAllToDefaults,
   resetCameraControl,
+  resetCombinedZoom,
+  revertToPreset,
+  saveScene,
   setCameraControl,
+  setCameraControlAuto,
+  setColourSpace,
+  setCombinedZoom,
+  setControlGuard,
+  setOutputProcessors,
+  setPostProcessing,
+  setSchedule,
+  setTally,
+  setTallyAuto,
+  setVideoSource,
+  suggestPowerlineFrequency,
 } from './api'
 
 vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn(),
 }))
 
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(),
+}))
+
 const { invoke } = await import('@tauri-apps/api/core')
 const mockInvoke = vi.mocked(invoke)
+const { listen } = await import('@tauri-apps/api/event')
+const mockListen = vi.mocked(listen)
 
 const brightness: ControlDescriptor = {
   id: 'brightness',
@@ -25,6 +66,7 @@ const brightness: ControlDescriptor = {
   step: 1,
   default: 128,
   current: 128,
+  defaultAuto: false,
   flags: { supportsAuto: false, isAutoEnabled: false, isReadOnly: false },
   supported: true,
 }
@@ -35,15 +77,159 @@ describe('controls API', () => {
   })
 
   it('fetches controls for a given device id', async () => {
-    mockInvoke.mockResolvedValueOnce([brightness])
+    mockInvoke.mockResolvedValueOnce({ controls: [brightness], cached: true })
     const result = await getCameraControls('cam-1')
     expect(mockInvoke).toHaveBeenCalledWith('get_camera_controls', { deviceId: 'cam-1' })
-    expect(result).toEqual([brightness])
+    expect(result).toEqual({ controls: [brightness], cached: true })
   })
 
-  it('calls set_camera_control with correct IPC args', async () => {
+  it('reads a single control', async () => {
+    const reading = { value: 140, isAutoEnabled: false, min: 0, max: 255 }
+    mockInvoke.mockResolvedValueOnce(reading)
+    const result = await getCameraControl('cam-1', 'brightness')
+    expect(mockInvoke).toHaveBeenCalledWith('get_camera_control', {
+      deviceId: 'cam-1',
+      controlId: 'brightness',
+    })
+    expect(result).toEqual(reading)
+  })
+
+  it('fetches formats grouped by resolution', async () => {
+    const groups = [
+      {
+        width: 1920,
+        height: 1080,
+        formats: [{ pixelFormat: 'MJPG', fps: [60, 30], minFps: 5, maxFps: 60 }],
+      },
+    ]
+    mockInvoke.mockResolvedValueOnce(groups)
+    const result = await getCameraFormatsGrouped('cam-1')
+    expect(mockInvoke).toHaveBeenCalledWith('get_camera_formats_grouped', { deviceId: 'cam-1' })
+    expect(result).toEqual(groups)
+  })
+
+  it('lists and selects video sources', async () => {
+    const sources = [
+      { index: 0, name: 'SDI 1', type: 'sdi' },
+      { index: 1, name: 'HDMI/DVI', type: 'digital' },
+    ]
+    mockInvoke.mockResolvedValueOnce(sources)
+    await expect(getVideoSources('cam-1')).resolves.toEqual(sources)
+    expect(mockInvoke).toHaveBeenCalledWith('get_video_sources', { deviceId: 'cam-1' })
+
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setVideoSource('cam-1', 'Capture Card', 1)
+    expect(mockInvoke).toHaveBeenLastCalledWith('set_video_source', {
+      deviceId: 'cam-1',
+      cameraName: 'Capture Card',
+      index: 1,
+    })
+  })
+
+  it('overrides and clears the colour space', async () => {
+    mockInvoke.mockResolvedValueOnce(undefined)
+    const colourSpace = { matrix: 'bt709' as const, range: 'limited' as const }
+    await setColourSpace('cam-1', 'HD Cam', colourSpace)
+    expect(mockInvoke).toHaveBeenLastCalledWith('set_colour_space', {
+      deviceId: 'cam-1',
+      cameraName: 'HD Cam',
+      colourSpace,
+    })
+
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setColourSpace('cam-1', 'HD Cam', null)
+    expect(mockInvoke).toHaveBeenLastCalledWith('set_colour_space', {
+      deviceId: 'cam-1',
+      cameraName: 'HD Cam',
+      colourSpace: null,
+    })
+  })
+
+  it('sets an output processor chain', async () => {
+    mockInvoke.mockResolvedValueOnce(undefined)
+    const watermark = {
+      kind: 'watermark' as const,
+      template: '{camera} {timestamp}',
+      corner: 'bottomRight' as const,
+      scale: 2,
+      backgroundOpacity: 50,
+    }
+    await setOutputProcessors('cam-1', 'Test Camera', 'recording', [watermark])
+    expect(mockInvoke).toHaveBeenCalledWith('set_output_processors', {
+      deviceId: 'cam-1',
+      cameraName: 'Test Camera',
+      target: 'recording',
+      config: [watermark],
+    })
+
     mockInvoke.mockResolvedValueOnce(undefined)
-    await setCameraControl('cam-1', 'brightness', 200, 'Test Camera')
+    await setOutputProcessors('cam-1', 'Test Camera', 'recording', null)
+    expect(mockInvoke).toHaveBeenLastCalledWith('set_output_processors', {
+      deviceId: 'cam-1',
+      cameraName: 'Test Camera',
+      target: 'recording',
+      config: null,
+    })
+  })
+
+  it('fetches the device capabilities', async () => {
+    const capabilities = {
+      supportsPreview: true,
+      supportsFormats: false,
+      supportsAutoToggle: false,
+      supportsHotplug: true,
+      supportsRelativePtz: false,
+    }
+    mockInvoke.mockResolvedValueOnce(capabilities)
+    const result = await getDeviceCapabilities('canon:SER001')
+    expect(mockInvoke).toHaveBeenCalledWith('get_device_capabilities', { deviceId: 'canon:SER001' })
+    expect(result).toEqual(capabilities)
+  })
+
+  it('calls set_post_processing with the strengths', async () => {
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setPostProcessing('cam-1', 'Test Camera', { sharpen: 40, denoise: 10 })
+    expect(mockInvoke).toHaveBeenCalledWith('set_post_processing', {
+      deviceId: 'cam-1',
+      cameraName: 'Test Camera',
+      settings: { sharpen: 40, denoise: 10 },
+    })
+  })
+
+  it('calls set_combined_zoom and returns the split', async () => {
+    const split = {
+      factor: 4,
+      hardwareValue: 200,
+      hardwareFactor: 2,
+      digitalFactor: 2,
+      crop: { x: 0.25, y: 0.25, width: 0.5, height: 0.5 },
+    }
+    mockInvoke.mockResolvedValueOnce(split)
+    expect(await setCombinedZoom('cam-1', 4, 0.5, 0.5)).toEqual(split)
+    expect(mockInvoke).toHaveBeenCalledWith('set_combined_zoom', {
+      deviceId: 'cam-1',
+      factor: 4,
+      centerX: 0.5,
+      centerY: 0.5,
+    })
+  })
+
+  it('calls reset_combined_zoom', async () => {
+    mockInvoke.mockResolvedValueOnce({
+      factor: 1,
+      hardwareValue: 100,
+      hardwareFactor: 1,
+      digitalFactor: 1,
+      crop: { x: 0, y: 0, width: 1, height: 1 },
+    })
+    await resetCombinedZoom('cam-1')
+    expect(mockInvoke).toHaveBeenCalledWith('reset_combined_zoom', { deviceId: 'cam-1' })
+  })
+
+  it('calls set_camera_control with correct IPC args', async () => {
+    mockInvoke.mockResolvedValueOnce({ value: 200, snapped: false })
+    const written = await setCameraControl('cam-1', 'brightness', 200, 'Test Camera')
+    expect(written).toEqual({ value: 200, snapped: false })
     expect(mockInvoke).toHaveBeenCalledWith('set_camera_control', {
       deviceId: 'cam-1',
       controlId: 'brightness',
@@ -52,6 +238,17 @@ describe('controls API', () => {
     })
   })
 
+  it('calls set_camera_control_auto with the mode', async () => {
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setCameraControlAuto('cam-1', 'exposure', true, 'Test Camera')
+    expect(mockInvoke).toHaveBeenCalledWith('set_camera_control_auto', {
+      deviceId: 'cam-1',
+      controlId: 'exposure',
+      auto: true,
+      cameraName: 'Test Camera',
+    })
+  })
+
   it('calls reset_camera_control and returns default value', async () => {
     mockInvoke.mockResolvedValueOnce(128)
     const result = await resetCameraControl('cam-1', 'brightness')
@@ -69,8 +266,8 @@ describe('controls API', () => {
 
   it('calls reset_to_defaults and returns array of reset results', async () => {
     const results = [
-      { controlId: 'brightness', value: 128 },
-      { controlId: 'contrast', value: 64 },
+      { controlId: 'brightness', value: 128, auto: false },
+      { controlId: 'contrast', value: 64, auto: false },
     ]
     mockInvoke.mockResolvedValueOnce(results)
     const result = await resetAllToDefaults('cam-1')
@@ -79,16 +276,244 @@ describe('controls API', () => {
   })
 
   it('calls get_saved_settings and returns settings or null', async () => {
-    const settings = { name: 'Test Cam', controls: { brightness: 200 } }
+    const settings = {
+      name: 'Test Cam',
+      controls: { brightness: { value: 200, auto: false }, exposure: { value: -6, auto: true } },
+    }
     mockInvoke.mockResolvedValueOnce(settings)
     const result = await getSavedSettings('cam-1')
     expect(mockInvoke).toHaveBeenCalledWith('get_saved_settings', { deviceId: 'cam-1' })
     expect(result).toEqual(settings)
   })
 
+  it('calls apply_preset with the preset id and camera name', async () => {
+    const applied = { applied: ['brightness', 'contrast'], skipped: ['zoom'] }
+    mockInvoke.mockResolvedValueOnce(applied)
+    const result = await applyPreset('cam-1', 'desk', 'Test Camera')
+    expect(mockInvoke).toHaveBeenCalledWith('apply_preset', {
+      deviceId: 'cam-1',
+      presetId: 'desk',
+      cameraName: 'Test Camera',
+    })
+    expect(result).toEqual(applied)
+  })
+
+  it('lists presets, optionally for one camera', async () => {
+    const presets = [{ id: 'desk', name: 'Desk', controls: { brightness: 150 }, normalised: false }]
+    mockInvoke.mockResolvedValue(presets)
+
+    expect(await listPresets('cam-1')).toEqual(presets)
+    expect(mockInvoke).toHaveBeenCalledWith('list_presets', { deviceId: 'cam-1' })
+
+    await listPresets()
+    expect(mockInvoke).toHaveBeenLastCalledWith('list_presets', { deviceId: null })
+  })
+
+  it('calls delete_preset with the preset id', async () => {
+    mockInvoke.mockResolvedValueOnce(true)
+    expect(await deletePreset('desk')).toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('delete_preset', { presetId: 'desk' })
+  })
+
+  it('fetches and reverts drift from the applied preset', async () => {
+    const drift = [
+      {
+        controlId: 'brightness',
+        presetValue: 150,
+        current: { value: 170, auto: false, changed_at: 1700000000 },
+      },
+    ]
+    mockInvoke.mockResolvedValueOnce(drift)
+    expect(await getSettingsDrift('cam-1')).toEqual(drift)
+    expect(mockInvoke).toHaveBeenCalledWith('get_settings_drift', { deviceId: 'cam-1' })
+
+    mockInvoke.mockResolvedValueOnce(drift)
+    expect(await revertToPreset('cam-1')).toEqual(drift)
+    expect(mockInvoke).toHaveBeenCalledWith('revert_to_preset', { deviceId: 'cam-1' })
+  })
+
   it('returns null when no saved settings exist', async () => {
     mockInvoke.mockResolvedValueOnce(null)
     const result = await getSavedSettings('cam-1')
     expect(result).toBeNull()
   })
+
+  it('forwards controls-refreshed payloads', async () => {
+    const payload = {
+      deviceId: 'cam-1',
+      controls: [brightness],
+      added: [],
+      removed: ['contrast'],
+      changed: true,
+    }
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onControlsRefreshed(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('controls-refreshed', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+
+  it('gets and sets a camera schedule', async () => {
+    const rules = [
+      { presetId: 'day', startTime: '07:30', daysOfWeek: ['monday' as const, 'friday' as const] },
+      { presetId: 'night', startTime: '19:00' },
+    ]
+    mockInvoke.mockResolvedValueOnce(rules)
+    expect(await getSchedule('cam-1')).toEqual(rules)
+    expect(mockInvoke).toHaveBeenCalledWith('get_schedule', { deviceId: 'cam-1' })
+
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setSchedule('cam-1', rules)
+    expect(mockInvoke).toHaveBeenCalledWith('set_schedule', { deviceId: 'cam-1', rules })
+  })
+
+  it('forwards schedule-applied payloads', async () => {
+    const payload = { deviceId: 'cam-1', presetId: 'night', ruleIndex: 1, controlsWritten: 3 }
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onScheduleApplied(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('schedule-applied', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+
+  it('gets, sets and removes control guards', async () => {
+    const guard = { controlId: 'gain', maxValue: 40, cooldownSecs: 30 }
+    mockInvoke.mockResolvedValueOnce([guard])
+    expect(await getControlGuards('cam-1')).toEqual([guard])
+    expect(mockInvoke).toHaveBeenCalledWith('get_control_guards', { deviceId: 'cam-1' })
+
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setControlGuard('cam-1', guard)
+    expect(mockInvoke).toHaveBeenCalledWith('set_control_guard', { deviceId: 'cam-1', guard })
+
+    mockInvoke.mockResolvedValueOnce(true)
+    expect(await removeControlGuard('cam-1', 'gain')).toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('remove_control_guard', {
+      deviceId: 'cam-1',
+      controlId: 'gain',
+    })
+  })
+
+  it('forwards guard-triggered payloads', async () => {
+    const payload = { deviceId: 'cam-1', controlId: 'gain', before: 64, after: 40 }
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onGuardTriggered(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('guard-triggered', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+
+  it('saves, lists, deletes and activates scenes', async () => {
+    const scene = {
+      name: 'Interview',
+      cameras: [{ deviceId: 'cam-1', actions: [{ type: 'startPreview' as const }] }],
+    }
+    mockInvoke.mockResolvedValueOnce(scene)
+    expect(await saveScene(scene)).toEqual(scene)
+    expect(mockInvoke).toHaveBeenCalledWith('save_scene', { scene })
+
+    mockInvoke.mockResolvedValueOnce([scene])
+    expect(await listScenes()).toEqual([scene])
+    expect(mockInvoke).toHaveBeenCalledWith('list_scenes')
+
+    mockInvoke.mockResolvedValueOnce(true)
+    expect(await deleteScene('Interview')).toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('delete_scene', { name: 'Interview' })
+
+    const activation = {
+      scene: 'Interview',
+      status: 'succeeded' as const,
+      cameras: [{ deviceId: 'cam-1', completed: 1, total: 1 }],
+    }
+    mockInvoke.mockResolvedValueOnce(activation)
+    expect(await activateScene('Interview')).toEqual(activation)
+    expect(mockInvoke).toHaveBeenCalledWith('activate_scene', { name: 'Interview' })
+  })
+
+  it('forwards scene-activated payloads', async () => {
+    const payload = { scene: 'Interview', status: 'partial', cameras: [] }
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onSceneActivated(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('scene-activated', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+
+  it('forwards settings-reconciled payloads', async () => {
+    const payload = {
+      deviceId: 'cam-1',
+      cameraName: 'Test Camera',
+      adjustments: [
+        { controlId: 'white_balance', stored: 2700, applied: 2800, reason: 'outOfRange' },
+      ],
+      updated: true,
+    }
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onSettingsReconciled(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('settings-reconciled', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+
+  it('forwards settings-degraded payloads', async () => {
+    const payload = { store: 'settings', panics: 3 }
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onSettingsDegraded(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('settings-degraded', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+
+  it('fetches the suggested power-line frequency', async () => {
+    const suggestion = { value: 1, label: '50 Hz', region: 'GB' }
+    mockInvoke.mockResolvedValueOnce(suggestion)
+    expect(await suggestPowerlineFrequency()).toEqual(suggestion)
+    expect(mockInvoke).toHaveBeenCalledWith('suggest_powerline_frequency')
+  })
+
+  it('sets a tally light', async () => {
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setTally('cam-1', true)
+    expect(mockInvoke).toHaveBeenCalledWith('set_tally', { deviceId: 'cam-1', on: true })
+  })
+
+  it('reads and sets automatic tally lights', async () => {
+    mockInvoke.mockResolvedValueOnce(true)
+    expect(await getTallyAuto()).toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('get_tally_auto')
+
+    mockInvoke.mockResolvedValueOnce(undefined)
+    await setTallyAuto(false)
+    expect(mockInvoke).toHaveBeenCalledWith('set_tally_auto', { enabled: false })
+  })
 })
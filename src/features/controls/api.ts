@@ -1,19 +1,93 @@
 import { invoke } from '@tauri-apps/api/core'
-import type { CameraSettings, ControlDescriptor, ResetResult } from '../../types/camera'
+import { type UnlistenFn, listen } from '@tauri-apps/api/event'
+import type {
+  BackendCapabilities,
+  CameraControls,
+  CameraSettings,
+  ColourSpace,
+  ControlDrift,
+  ControlGuard,
+  ControlNudged,
+  ControlValueResponse,
+  ControlsRefreshedPayload,
+  GuardTriggered,
+  KeyBinding,
+  OutputProcessor,
+  OutputTarget,
+  PostProcessing,
+  PowerLineSuggestion,
+  Preset,
+  PresetApplyResult,
+  ResetResult,
+  ResolutionGroup,
+  SavedPreset,
+  Scene,
+  SceneActivation,
+  SettingsDegradedPayload,
+  SettingsReconciledPayload,
+  ScheduleApplied,
+  ScheduleRule,
+  SnappedValue,
+  VideoSource,
+  ZoomSplit,
+} from '../../types/camera'
 
-/** Fetch all supported controls for a camera. */
-export async function getCameraControls(deviceId: string): Promise<ControlDescriptor[]> {
-  return invoke<ControlDescriptor[]>('get_camera_controls', { deviceId })
+/**
+ * Fetch all supported controls for a camera. May be served from the
+ * last-known cache (`cached: true`), in which case `controls-refreshed` follows.
+ */
+export async function getCameraControls(deviceId: string): Promise<CameraControls> {
+  return invoke<CameraControls>('get_camera_controls', { deviceId })
 }
 
-/** Set a camera control value. */
+/** Read one control's current value, to refresh a single widget. */
+export async function getCameraControl(
+  deviceId: string,
+  controlId: string,
+): Promise<ControlValueResponse> {
+  return invoke<ControlValueResponse>('get_camera_control', { deviceId, controlId })
+}
+
+/** Fetch supported video formats grouped by resolution, largest first. */
+export async function getCameraFormatsGrouped(deviceId: string): Promise<ResolutionGroup[]> {
+  return invoke<ResolutionGroup[]>('get_camera_formats_grouped', { deviceId })
+}
+
+/** Fetch which optional features the camera's backend supports, to hide the rest. */
+export async function getDeviceCapabilities(deviceId: string): Promise<BackendCapabilities> {
+  return invoke<BackendCapabilities>('get_device_capabilities', { deviceId })
+}
+
+/** Subscribe to live control lists replacing cached ones. Returns an unlisten function. */
+export async function onControlsRefreshed(
+  callback: (payload: ControlsRefreshedPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<ControlsRefreshedPayload>('controls-refreshed', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Set a camera control value. The backend clamps it to the control's range and
+ * step; the result carries the value actually written.
+ */
 export async function setCameraControl(
   deviceId: string,
   controlId: string,
   value: number,
   cameraName: string,
+): Promise<SnappedValue> {
+  return invoke<SnappedValue>('set_camera_control', { deviceId, controlId, value, cameraName })
+}
+
+/** Switch a camera control between auto and manual mode. */
+export async function setCameraControlAuto(
+  deviceId: string,
+  controlId: string,
+  auto: boolean,
+  cameraName: string,
 ): Promise<void> {
-  return invoke('set_camera_control', { deviceId, controlId, value, cameraName })
+  return invoke('set_camera_control_auto', { deviceId, controlId, auto, cameraName })
 }
 
 /** Reset a camera control to its hardware default. Returns the default value. */
@@ -26,7 +100,261 @@ export async function resetAllToDefaults(deviceId: string): Promise<ResetResult[
   return invoke<ResetResult[]>('reset_to_defaults', { deviceId })
 }
 
+/** Power-line frequency suggested for this machine's region. */
+export async function suggestPowerlineFrequency(): Promise<PowerLineSuggestion> {
+  return invoke<PowerLineSuggestion>('suggest_powerline_frequency')
+}
+
+/** Turn a camera's tally light on or off. Not saved as a setting. */
+export async function setTally(deviceId: string, on: boolean): Promise<void> {
+  return invoke('set_tally', { deviceId, on })
+}
+
+/** Whether tally lights follow the default camera and scene activations. */
+export async function getTallyAuto(): Promise<boolean> {
+  return invoke<boolean>('get_tally_auto')
+}
+
+/** Make tally lights follow the default camera and scene activations. */
+export async function setTallyAuto(enabled: boolean): Promise<void> {
+  return invoke('set_tally_auto', { enabled })
+}
+
 /** Fetch saved settings for a camera, or null if none exist. */
 export async function getSavedSettings(deviceId: string): Promise<CameraSettings | null> {
   return invoke<CameraSettings | null>('get_saved_settings', { deviceId })
 }
+
+/**
+ * Save a camera's current control values as a preset under `presetId`.
+ * Rejected if another preset already has the name.
+ */
+export async function savePreset(
+  deviceId: string,
+  presetId: string,
+  name: string,
+  normalised: boolean,
+): Promise<Preset> {
+  return invoke<Preset>('save_preset', { deviceId, presetId, name, normalised })
+}
+
+/** Saved presets, sorted by ID. With `deviceId`, only those with a control that camera has. */
+export async function listPresets(deviceId?: string): Promise<SavedPreset[]> {
+  return invoke<SavedPreset[]>('list_presets', { deviceId: deviceId ?? null })
+}
+
+/** Delete a saved preset. Returns whether it existed. */
+export async function deletePreset(presetId: string): Promise<boolean> {
+  return invoke<boolean>('delete_preset', { presetId })
+}
+
+/** Apply a saved preset to a camera. Returns the controls written and skipped. */
+export async function applyPreset(
+  deviceId: string,
+  presetId: string,
+  cameraName: string,
+): Promise<PresetApplyResult> {
+  return invoke<PresetApplyResult>('apply_preset', { deviceId, presetId, cameraName })
+}
+
+/** Controls whose saved value differs from the preset last applied to the camera. */
+export async function getSettingsDrift(deviceId: string): Promise<ControlDrift[]> {
+  return invoke<ControlDrift[]>('get_settings_drift', { deviceId })
+}
+
+/** Re-apply the drifted controls of the preset last applied. Returns the reverted drift. */
+export async function revertToPreset(deviceId: string): Promise<ControlDrift[]> {
+  return invoke<ControlDrift[]>('revert_to_preset', { deviceId })
+}
+
+/** Set sharpening and denoise for a camera's preview and persist them. Takes effect next frame. */
+export async function setPostProcessing(
+  deviceId: string,
+  cameraName: string,
+  settings: PostProcessing,
+): Promise<void> {
+  return invoke<void>('set_post_processing', { deviceId, cameraName, settings })
+}
+
+/**
+ * Give one of a camera's outputs its own processor chain, such as a watermark on recordings
+ * only, and persist it. `null` goes back to the preview's chain.
+ */
+export async function setOutputProcessors(
+  deviceId: string,
+  cameraName: string,
+  target: OutputTarget,
+  config: OutputProcessor[] | null,
+): Promise<void> {
+  return invoke<void>('set_output_processors', { deviceId, cameraName, target, config })
+}
+
+/** List a multi-input device's video sources, such as a capture card's inputs. Often empty. */
+export async function getVideoSources(deviceId: string): Promise<VideoSource[]> {
+  return invoke<VideoSource[]>('get_video_sources', { deviceId })
+}
+
+/**
+ * Capture one of a device's video sources from now on and persist the choice. A running preview
+ * restarts to pick it up.
+ */
+export async function setVideoSource(
+  deviceId: string,
+  cameraName: string,
+  index: number,
+): Promise<void> {
+  return invoke<void>('set_video_source', { deviceId, cameraName, index })
+}
+
+/**
+ * Override how a camera's YUV frames are decoded and persist it, or go back to detecting it with
+ * `null`. Takes effect from the next frame.
+ */
+export async function setColourSpace(
+  deviceId: string,
+  cameraName: string,
+  colourSpace: ColourSpace | null,
+): Promise<void> {
+  return invoke<void>('set_colour_space', { deviceId, cameraName, colourSpace })
+}
+
+/**
+ * Zoom to `factor` around a point of the frame (0–1 each way), using the camera's optical zoom as far
+ * as it goes and a digital crop for the rest. Returns how the zoom was split.
+ */
+export async function setCombinedZoom(
+  deviceId: string,
+  factor: number,
+  centerX: number,
+  centerY: number,
+): Promise<ZoomSplit> {
+  return invoke<ZoomSplit>('set_combined_zoom', { deviceId, factor, centerX, centerY })
+}
+
+/** Put the optical zoom back to its minimum and remove the digital crop. */
+export async function resetCombinedZoom(deviceId: string): Promise<ZoomSplit> {
+  return invoke<ZoomSplit>('reset_combined_zoom', { deviceId })
+}
+
+/** A camera's scheduled presets, in priority order. */
+export async function getSchedule(deviceId: string): Promise<ScheduleRule[]> {
+  return invoke<ScheduleRule[]>('get_schedule', { deviceId })
+}
+
+/** Replace a camera's schedule; an empty list removes it. Every rule must name a saved preset. */
+export async function setSchedule(deviceId: string, rules: ScheduleRule[]): Promise<void> {
+  return invoke<void>('set_schedule', { deviceId, rules })
+}
+
+/**
+ * Subscribe to saved values restore had to clamp, snap or skip because the
+ * camera's advertised controls changed. Returns an unlisten function.
+ */
+export async function onSettingsReconciled(
+  callback: (payload: SettingsReconciledPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<SettingsReconciledPayload>('settings-reconciled', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Subscribe to a settings save loop panicking repeatedly, after which
+ * changes may not be reaching the disk. Returns an unlisten function.
+ */
+export async function onSettingsDegraded(
+  callback: (payload: SettingsDegradedPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<SettingsDegradedPayload>('settings-degraded', (event) => {
+    callback(event.payload)
+  })
+}
+
+/** Subscribe to presets applied by the scheduler. Returns an unlisten function. */
+export async function onScheduleApplied(
+  callback: (payload: ScheduleApplied) => void,
+): Promise<UnlistenFn> {
+  return listen<ScheduleApplied>('schedule-applied', (event) => {
+    callback(event.payload)
+  })
+}
+
+/** Fetch a camera's control guards. */
+export async function getControlGuards(deviceId: string): Promise<ControlGuard[]> {
+  return invoke<ControlGuard[]>('get_control_guards', { deviceId })
+}
+
+/** Guard a control, replacing any guard already on it. */
+export async function setControlGuard(deviceId: string, guard: ControlGuard): Promise<void> {
+  return invoke<void>('set_control_guard', { deviceId, guard })
+}
+
+/** Remove a control's guard. Resolves to whether there was one. */
+export async function removeControlGuard(deviceId: string, controlId: string): Promise<boolean> {
+  return invoke<boolean>('remove_control_guard', { deviceId, controlId })
+}
+
+/** Subscribe to guards holding a control at its ceiling. Returns an unlisten function. */
+export async function onGuardTriggered(
+  callback: (payload: GuardTriggered) => void,
+): Promise<UnlistenFn> {
+  return listen<GuardTriggered>('guard-triggered', (event) => {
+    callback(event.payload)
+  })
+}
+
+/** Validate and save a scene, replacing any with the same name (ignoring case). */
+export async function saveScene(scene: Scene): Promise<Scene> {
+  return invoke<Scene>('save_scene', { scene })
+}
+
+/** All saved scenes. */
+export async function listScenes(): Promise<Scene[]> {
+  return invoke<Scene[]>('list_scenes')
+}
+
+/** Delete a saved scene. Resolves to whether it existed. */
+export async function deleteScene(name: string): Promise<boolean> {
+  return invoke<boolean>('delete_scene', { name })
+}
+
+/** Activate a saved scene on all its cameras at once. Resolves to the per-camera results. */
+export async function activateScene(name: string): Promise<SceneActivation> {
+  return invoke<SceneActivation>('activate_scene', { name })
+}
+
+/** Subscribe to scene activations. Returns an unlisten function. */
+export async function onSceneActivated(
+  callback: (payload: SceneActivation) => void,
+): Promise<UnlistenFn> {
+  return listen<SceneActivation>('scene-activated', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Forward a key press or release from a focused preview. Resolves to whether
+ * the key is bound to a control; held keys repeat in the backend.
+ */
+export async function keyInput(deviceId: string, key: string, pressed: boolean): Promise<boolean> {
+  return invoke<boolean>('key_input', { deviceId, key, pressed })
+}
+
+/** Keys that nudge controls. */
+export async function getKeyBindings(): Promise<KeyBinding[]> {
+  return invoke<KeyBinding[]>('get_key_bindings')
+}
+
+/** Replace the key bindings, or restore the built-in ones with `null`. */
+export async function setKeyBindings(bindings: KeyBinding[] | null): Promise<KeyBinding[]> {
+  return invoke<KeyBinding[]>('set_key_bindings', { bindings })
+}
+
+/** Subscribe to controls nudged from the keyboard. Returns an unlisten function. */
+export async function onControlNudged(
+  callback: (payload: ControlNudged) => void,
+): Promise<UnlistenFn> {
+  return listen<ControlNudged>('control-nudged', (event) => {
+    callback(event.payload)
+  })
+}
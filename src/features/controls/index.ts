@@ -6,3 +6,4 @@ export { ControlSelect } from './ControlSelect'
 export { AccordionSection } from './AccordionSection'
 export { ResetAllButton } from './ResetAllButton'
 export { ConfirmModal } from './ConfirmModal'
+export { useKeyboardControl } from './useKeyboardControl'
@@ -0,0 +1,97 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import { act, renderHook } from '@testing-library/react'
+import { useKeyboardControl } from './useKeyboardControl'
+
+vi.mock('./api', () => ({
+  getKeyBindings: vi.fn(),
+  keyInput: vi.fn(),
+}))
+
+const { getKeyBindings, keyInput } = await import('./api')
+const mockGetBindings = vi.mocked(getKeyBindings)
+const mockKeyInput = vi.mocked(keyInput)
+
+function key(type: 'keydown' | 'keyup', key: string, init: KeyboardEventInit = {}) {
+  const event = new KeyboardEvent(type, { key, cancelable: true, ...init })
+  window.dispatchEvent(event)
+  return event
+}
+
+async function renderBound(deviceId: string | null = 'cam-1') {
+  const hook = renderHook(({ id }) => useKeyboardControl(id), { initialProps: { id: deviceId } })
+  // Let the bindings arrive
+  await act(async () => {})
+  return hook
+}
+
+describe('useKeyboardControl', () => {
+  beforeEach(() => {
+    mockGetBindings.mockReset()
+    mockGetBindings.mockResolvedValue([
+      { key: 'ArrowLeft', controlId: 'pan', up: false },
+      { key: '+', controlId: 'zoom', up: true },
+    ])
+    mockKeyInput.mockReset()
+    mockKeyInput.mockResolvedValue(true)
+  })
+
+  it('forwards a press and release of a bound key once', async () => {
+    const { unmount } = await renderBound()
+
+    expect(key('keydown', 'ArrowLeft').defaultPrevented).toBe(true)
+    // The browser's own repeat isn't forwarded
+    key('keydown', 'ArrowLeft', { repeat: true })
+    key('keyup', 'ArrowLeft')
+
+    expect(mockKeyInput.mock.calls).toEqual([
+      ['cam-1', 'ArrowLeft', true],
+      ['cam-1', 'ArrowLeft', false],
+    ])
+    unmount()
+  })
+
+  it('ignores unbound keys, shortcuts and typing', async () => {
+    const { unmount } = await renderBound()
+
+    expect(key('keydown', 'a').defaultPrevented).toBe(false)
+    key('keydown', '+', { ctrlKey: true })
+    const input = document.createElement('input')
+    document.body.appendChild(input)
+    input.dispatchEvent(new KeyboardEvent('keydown', { key: '+', bubbles: true }))
+    input.remove()
+
+    expect(mockKeyInput).not.toHaveBeenCalled()
+    unmount()
+  })
+
+  it('releases held keys when the window loses focus', async () => {
+    const { unmount } = await renderBound()
+
+    key('keydown', '+')
+    window.dispatchEvent(new Event('blur'))
+    key('keyup', '+')
+
+    expect(mockKeyInput.mock.calls).toEqual([
+      ['cam-1', '+', true],
+      ['cam-1', '+', false],
+    ])
+    unmount()
+  })
+
+  it('releases held keys on the old camera when the camera changes', async () => {
+    const { rerender, unmount } = await renderBound()
+
+    key('keydown', 'ArrowLeft')
+    rerender({ id: 'cam-2' })
+
+    expect(mockKeyInput).toHaveBeenLastCalledWith('cam-1', 'ArrowLeft', false)
+    unmount()
+  })
+
+  it('does nothing without a camera', () => {
+    renderHook(() => useKeyboardControl(null))
+    key('keydown', 'ArrowLeft')
+    expect(mockGetBindings).not.toHaveBeenCalled()
+    expect(mockKeyInput).not.toHaveBeenCalled()
+  })
+})
@@ -0,0 +1,69 @@
+import { useEffect } from 'react'
+import { getKeyBindings, keyInput } from './api'
+
+/** Whether the key is going to a field the user is typing or sliding in. */
+function isEditing(target: EventTarget | null): boolean {
+  if (!(target instanceof HTMLElement)) return false
+  return target.isContentEditable || ['INPUT', 'SELECT', 'TEXTAREA'].includes(target.tagName)
+}
+
+/**
+ * Forwards bound keys pressed while this window has focus to the backend,
+ * which nudges the camera's controls and repeats them while held. Keys still
+ * held are released when the window loses focus or the camera changes.
+ */
+export function useKeyboardControl(deviceId: string | null) {
+  useEffect(() => {
+    if (!deviceId) return undefined
+
+    let bound = new Set<string>()
+    const held = new Set<string>()
+    let cancelled = false
+
+    getKeyBindings().then(
+      (bindings) => {
+        if (!cancelled) bound = new Set(bindings.map((b) => b.key))
+      },
+      () => {
+        // Backend unavailable — no keyboard control
+      },
+    )
+
+    const send = (key: string, pressed: boolean) => {
+      keyInput(deviceId, key, pressed).catch((err: unknown) => {
+        console.error('Failed to forward key:', err)
+      })
+    }
+
+    const onKeyDown = (e: KeyboardEvent) => {
+      if (!bound.has(e.key) || e.ctrlKey || e.metaKey || e.altKey || isEditing(e.target)) return
+      e.preventDefault()
+      // The backend repeats held keys itself
+      if (held.has(e.key)) return
+      held.add(e.key)
+      send(e.key, true)
+    }
+
+    const onKeyUp = (e: KeyboardEvent) => {
+      if (!held.delete(e.key)) return
+      send(e.key, false)
+    }
+
+    const releaseAll = () => {
+      held.forEach((key) => send(key, false))
+      held.clear()
+    }
+
+    window.addEventListener('keydown', onKeyDown)
+    window.addEventListener('keyup', onKeyUp)
+    window.addEventListener('blur', releaseAll)
+
+    return () => {
+      cancelled = true
+      window.removeEventListener('keydown', onKeyDown)
+      window.removeEventListener('keyup', onKeyUp)
+      window.removeEventListener('blur', releaseAll)
+      releaseAll()
+    }
+  }, [deviceId])
+}
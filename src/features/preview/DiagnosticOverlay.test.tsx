@@ -12,6 +12,21 @@ const mockSnapshot: DiagnosticSnapshot = {
   latencyMs: 12.5,
   bandwidthBps: 5_000_000,
   usbBusInfo: null,
+  negotiatedFps: null,
+  panicCount: 0,
+  captureFps: 29.97,
+  timestampRegressions: 0,
+  contentHealth: 'ok',
+  snapshotCount: 0,
+  dropsByReason: { nullBuffer: 0, unsupportedFormat: 0, sizeMismatch: 3, decodeFailed: 0 },
+  conversionAvgUs: 2_450,
+  conversionMaxUs: 6_100,
+  currentFps: 29.5,
+  effectiveJpegQuality: null,
+  recentEncodeMs: [],
+  frameDelivery: [],
+  jpegCacheBytes: 2_400_000,
+  negotiatedFormat: null,
 }
 
 describe('DiagnosticOverlay', () => {
@@ -42,6 +57,26 @@ describe('DiagnosticOverlay', () => {
     expect(screen.getByText('5.0 MB/s')).toBeInTheDocument()
   })
 
+  it('shows conversion time and why frames were dropped', async () => {
+    const user = userEvent.setup()
+    render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+
+    expect(screen.getByText('2.5 ms')).toHaveAttribute('title', 'Slowest recent frame: 6.1 ms')
+    expect(screen.getByText('3')).toHaveAttribute('title', '3 size mismatch')
+  })
+
+  it('shows how much the JPEG caches hold', async () => {
+    const user = userEvent.setup()
+    render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+
+    expect(screen.getByText('JPEG cache')).toBeInTheDocument()
+    expect(screen.getByText('2.4 MB')).toBeInTheDocument()
+  })
+
   it('has semi-transparent background for readability', async () => {
     const user = userEvent.setup()
     render(<DiagnosticOverlay snapshot={mockSnapshot} />)
@@ -65,6 +100,96 @@ describe('DiagnosticOverlay', () => {
     expect(screen.getByText('USB 3.0 Bus 2')).toBeInTheDocument()
   })
 
+  it('shows the negotiated format once the camera has settled on one', async () => {
+    const user = userEvent.setup()
+    const { rerender } = render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+    expect(screen.queryByText('Format')).not.toBeInTheDocument()
+
+    const negotiatedFormat = { width: 1920, height: 1080, pixelFormat: 'MJPG' }
+    rerender(<DiagnosticOverlay snapshot={{ ...mockSnapshot, negotiatedFormat }} />)
+    expect(screen.getByText('Format')).toBeInTheDocument()
+    expect(screen.getByText('1920x1080 MJPG')).toBeInTheDocument()
+  })
+
+  it('shows the frame rate the camera agreed to when it reports one', async () => {
+    const user = userEvent.setup()
+    const { rerender } = render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+    expect(screen.queryByText('Camera FPS')).not.toBeInTheDocument()
+
+    rerender(<DiagnosticOverlay snapshot={{ ...mockSnapshot, negotiatedFps: 25 }} />)
+    expect(screen.getByText('Camera FPS')).toBeInTheDocument()
+    expect(screen.getByText('25.0')).toBeInTheDocument()
+  })
+
+  it('shows clock jumps only when the camera has had any', async () => {
+    const user = userEvent.setup()
+    const { rerender } = render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+    expect(screen.queryByText('Clock jumps')).not.toBeInTheDocument()
+
+    rerender(<DiagnosticOverlay snapshot={{ ...mockSnapshot, timestampRegressions: 7 }} />)
+    expect(screen.getByText('Clock jumps')).toBeInTheDocument()
+    expect(screen.getByText('7')).toBeInTheDocument()
+  })
+
+  it('shows content health only when frames are black or frozen', async () => {
+    const user = userEvent.setup()
+    const { rerender } = render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+    expect(screen.queryByText('Content')).not.toBeInTheDocument()
+
+    rerender(<DiagnosticOverlay snapshot={{ ...mockSnapshot, contentHealth: 'black' }} />)
+    expect(screen.getByText('Content')).toBeInTheDocument()
+    expect(screen.getByText('Black')).toBeInTheDocument()
+
+    rerender(<DiagnosticOverlay snapshot={{ ...mockSnapshot, contentHealth: 'frozen' }} />)
+    expect(screen.getByText('Frozen')).toBeInTheDocument()
+  })
+
+  it('shows the share of frames the preview has seen', async () => {
+    const user = userEvent.setup()
+    const snapshotWithDelivery: DiagnosticSnapshot = {
+      ...mockSnapshot,
+      frameDelivery: [
+        {
+          consumer: 'preview',
+          produced: 900,
+          consumed: 432,
+          overwrittenUnseen: 465,
+          seenPercent: 48,
+        },
+        {
+          consumer: 'thumbnail',
+          produced: 900,
+          consumed: 9,
+          overwrittenUnseen: 888,
+          seenPercent: 1,
+        },
+      ],
+    }
+    render(<DiagnosticOverlay snapshot={snapshotWithDelivery} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+
+    expect(screen.getByText('48%')).toBeInTheDocument()
+    expect(screen.getByText('465')).toBeInTheDocument()
+  })
+
+  it('omits frame delivery rows until the preview has consumed frames', async () => {
+    const user = userEvent.setup()
+    render(<DiagnosticOverlay snapshot={mockSnapshot} />)
+
+    await user.click(screen.getByRole('button', { name: 'Stats' }))
+
+    expect(screen.queryByText('Frames seen')).not.toBeInTheDocument()
+  })
+
   it('omits USB bus info row when null', async () => {
     const user = userEvent.setup()
     render(<DiagnosticOverlay snapshot={mockSnapshot} />)
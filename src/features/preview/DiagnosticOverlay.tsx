@@ -1,5 +1,5 @@
 import { useState } from 'react'
-import type { DiagnosticSnapshot } from './useDiagnostics.ts'
+import type { DiagnosticSnapshot, DropCounts } from './useDiagnostics.ts'
 import './DiagnosticOverlay.css'
 
 interface DiagnosticOverlayProps {
@@ -12,9 +12,31 @@ function formatBandwidth(bps: number): string {
   return `${bps} B/s`
 }
 
+function formatBytes(bytes: number): string {
+  if (bytes >= 1_000_000) return `${(bytes / 1_000_000).toFixed(1)} MB`
+  if (bytes >= 1_000) return `${(bytes / 1_000).toFixed(1)} KB`
+  return `${bytes} B`
+}
+
+const DROP_REASONS: [keyof DropCounts, string][] = [
+  ['nullBuffer', 'empty buffer'],
+  ['unsupportedFormat', 'unsupported format'],
+  ['sizeMismatch', 'size mismatch'],
+  ['decodeFailed', 'failed to decode'],
+]
+
+/** Breakdown of dropped frames, such as "2 size mismatch, 1 empty buffer". */
+function describeDrops(drops: DropCounts): string | undefined {
+  const parts = DROP_REASONS.filter(([key]) => drops[key] > 0).map(
+    ([key, label]) => `${drops[key]} ${label}`,
+  )
+  return parts.length > 0 ? parts.join(', ') : undefined
+}
+
 /** Toggleable diagnostic stats overlay for the preview canvas. */
 export function DiagnosticOverlay({ snapshot }: DiagnosticOverlayProps) {
   const [visible, setVisible] = useState(false)
+  const preview = snapshot?.frameDelivery.find((d) => d.consumer === 'preview')
 
   return (
     <>
@@ -32,14 +54,63 @@ export function DiagnosticOverlay({ snapshot }: DiagnosticOverlayProps) {
           <dl className="diagnostic-overlay__grid">
             <dt>FPS</dt>
             <dd>{snapshot.fps.toFixed(1)}</dd>
+            {snapshot.negotiatedFps !== null && (
+              <>
+                <dt>Camera FPS</dt>
+                <dd title="Frame rate the camera agreed to deliver">
+                  {snapshot.negotiatedFps.toFixed(1)}
+                </dd>
+              </>
+            )}
+            {snapshot.negotiatedFormat && (
+              <>
+                <dt>Format</dt>
+                <dd>
+                  {snapshot.negotiatedFormat.width}x{snapshot.negotiatedFormat.height}{' '}
+                  {snapshot.negotiatedFormat.pixelFormat}
+                </dd>
+              </>
+            )}
             <dt>Drops</dt>
-            <dd>{snapshot.dropCount}</dd>
+            <dd title={describeDrops(snapshot.dropsByReason)}>{snapshot.dropCount}</dd>
             <dt>Drop rate</dt>
             <dd>{snapshot.dropRate.toFixed(1)}%</dd>
             <dt>Latency</dt>
             <dd>{snapshot.latencyMs.toFixed(1)} ms</dd>
             <dt>Bandwidth</dt>
             <dd>{formatBandwidth(snapshot.bandwidthBps)}</dd>
+            <dt>Convert</dt>
+            <dd title={`Slowest recent frame: ${(snapshot.conversionMaxUs / 1000).toFixed(1)} ms`}>
+              {(snapshot.conversionAvgUs / 1000).toFixed(1)} ms
+            </dd>
+            {preview && (
+              <>
+                <dt>Frames seen</dt>
+                <dd title={`${preview.consumed} of ${preview.produced} frames`}>
+                  {preview.seenPercent.toFixed(0)}%
+                </dd>
+                <dt>Missed</dt>
+                <dd>{preview.overwrittenUnseen}</dd>
+              </>
+            )}
+            {snapshot.timestampRegressions > 0 && (
+              <>
+                <dt>Clock jumps</dt>
+                <dd title="Frames the camera stamped earlier than the frame before">
+                  {snapshot.timestampRegressions}
+                </dd>
+              </>
+            )}
+            {snapshot.contentHealth !== 'ok' && (
+              <>
+                <dt>Content</dt>
+                <dd title="Frames arrive but show nothing — check the shutter or source">
+                  {snapshot.contentHealth === 'black' ? 'Black' : 'Frozen'}
+                </dd>
+              </>
+            )}
+            <dt>JPEG cache</dt>
+            <dd>{formatBytes(snapshot.jpegCacheBytes)}</dd>
             {snapshot.usbBusInfo && (
               <>
                 <dt>USB bus</dt>
@@ -0,0 +1,56 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { FrameChunk } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { getFrameChunked } from './chunkedFrame.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+const chunks = (sequence: number, data: string[]): FrameChunk[] =>
+  data.map((slice, chunk) => ({
+    sequence,
+    chunk,
+    chunkCount: data.length,
+    data: slice,
+    last: chunk === data.length - 1,
+  }))
+
+describe('chunked frames', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('requests chunks in order and joins them', async () => {
+    for (const chunk of chunks(7, ['/9j/', 'AAAA', 'AA=='])) mockInvoke.mockResolvedValueOnce(chunk)
+
+    await expect(getFrameChunked('cam-1')).resolves.toEqual({
+      sequence: 7,
+      base64: '/9j/AAAAAA==',
+    })
+    expect(mockInvoke.mock.calls).toEqual([
+      ['get_frame_chunked', { deviceId: 'cam-1', chunk: 0 }],
+      ['get_frame_chunked', { deviceId: 'cam-1', chunk: 1 }],
+      ['get_frame_chunked', { deviceId: 'cam-1', chunk: 2 }],
+    ])
+  })
+
+  it('returns a single-chunk frame straight away', async () => {
+    mockInvoke.mockResolvedValueOnce(chunks(3, ['/9j/AA=='])[0])
+
+    await expect(getFrameChunked('cam-1')).resolves.toEqual({ sequence: 3, base64: '/9j/AA==' })
+    expect(mockInvoke).toHaveBeenCalledTimes(1)
+  })
+
+  it('starts over once when the frame changes part-way', async () => {
+    const [stale] = chunks(1, ['AAAA', 'BBBB'])
+    const [, switched] = chunks(2, ['CCCC', 'DDDD'])
+    mockInvoke.mockResolvedValueOnce(stale).mockResolvedValueOnce(switched)
+    for (const chunk of chunks(2, ['CCCC', 'DDDD'])) mockInvoke.mockResolvedValueOnce(chunk)
+
+    await expect(getFrameChunked('cam-1')).resolves.toEqual({ sequence: 2, base64: 'CCCCDDDD' })
+  })
+})
@@ -0,0 +1,31 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { FrameChunk } from '../../types/camera'
+
+/**
+ * Fetch a camera's latest frame at full quality, chunk by chunk, for consumers that can't take the
+ * size-reduced frame `get_frame` serves. Resolves to the frame's sequence number and base64 JPEG.
+ *
+ * If another consumer starts a chunked fetch of the same camera part-way through, the chunks stop
+ * matching and the fetch starts over once.
+ */
+export async function getFrameChunked(
+  deviceId: string,
+): Promise<{ sequence: number; base64: string }> {
+  for (let attempt = 0; ; attempt++) {
+    const first = await invoke<FrameChunk>('get_frame_chunked', { deviceId, chunk: 0 })
+    const parts = [first.data]
+    let current = first
+    while (!current.last) {
+      current = await invoke<FrameChunk>('get_frame_chunked', {
+        deviceId,
+        chunk: current.chunk + 1,
+      })
+      if (current.sequence !== first.sequence) break
+      parts.push(current.data)
+    }
+    if (current.sequence === first.sequence) {
+      return { sequence: first.sequence, base64: parts.join('') }
+    }
+    if (attempt > 0) throw new Error(`frame for ${deviceId} kept changing while being fetched`)
+  }
+}
@@ -0,0 +1,48 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { ExposureMask } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { decodeMaskRuns, getExposureMask } from './exposureMask.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+const mask = (width: number, height: number, runs: number[]): ExposureMask => ({
+  width,
+  height,
+  threshold: 235,
+  sequence: 12,
+  runs,
+})
+
+describe('exposure mask', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('requests the mask for a device and threshold', async () => {
+    const result = mask(4, 1, [2, 2])
+    mockInvoke.mockResolvedValueOnce(result)
+
+    await expect(getExposureMask('cam-1', 235)).resolves.toEqual(result)
+    expect(mockInvoke).toHaveBeenCalledWith('get_exposure_mask', {
+      deviceId: 'cam-1',
+      threshold: 235,
+    })
+  })
+
+  it('decodes runs starting with unset cells', () => {
+    expect(Array.from(decodeMaskRuns(mask(3, 2, [1, 2, 2, 1])))).toEqual([0, 1, 1, 0, 0, 1])
+  })
+
+  it('decodes a mask whose first cell is set', () => {
+    expect(Array.from(decodeMaskRuns(mask(2, 2, [0, 3, 1])))).toEqual([1, 1, 1, 0])
+  })
+
+  it('decodes an empty mask', () => {
+    expect(Array.from(decodeMaskRuns(mask(2, 1, [2])))).toEqual([0, 0])
+  })
+})
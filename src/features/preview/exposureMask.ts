@@ -0,0 +1,21 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { ExposureMask } from '../../types/camera'
+
+/** Zebra mask of a camera's latest frame, for cells brighter than `threshold` (0–255 luma). */
+export async function getExposureMask(
+  deviceId: string,
+  threshold: number,
+): Promise<ExposureMask> {
+  return invoke<ExposureMask>('get_exposure_mask', { deviceId, threshold })
+}
+
+/** Expand a mask's runs into one flag per cell, row-major. */
+export function decodeMaskRuns(mask: ExposureMask): Uint8Array {
+  const cells = new Uint8Array(mask.width * mask.height)
+  let offset = 0
+  mask.runs.forEach((length, i) => {
+    if (i % 2 === 1) cells.fill(1, offset, offset + length)
+    offset += length
+  })
+  return cells
+}
@@ -0,0 +1,48 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { StreamedFrame } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+  Channel: class {
+    onmessage: (message: unknown) => void = () => {}
+  },
+}))
+
+import { Channel, invoke } from '@tauri-apps/api/core'
+import { streamFrames } from './frameStream.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+describe('streamFrames', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('starts a stream and forwards channel messages', async () => {
+    mockInvoke.mockResolvedValueOnce(7)
+    const onFrame = vi.fn()
+
+    await streamFrames('cam-1', onFrame)
+
+    expect(mockInvoke).toHaveBeenCalledWith('stream_frames', {
+      deviceId: 'cam-1',
+      channel: expect.any(Channel),
+    })
+    const { channel } = mockInvoke.mock.calls[0][1] as { channel: Channel<StreamedFrame> }
+    const frame: StreamedFrame = { jpeg: 'abc', width: 2, height: 1, timestampUs: 5, dropped: 0 }
+    channel.onmessage(frame)
+    expect(onFrame).toHaveBeenCalledWith(frame)
+  })
+
+  it('stops the stream by id', async () => {
+    mockInvoke.mockResolvedValueOnce(7).mockResolvedValueOnce(true)
+
+    const stop = await streamFrames('cam-1', vi.fn())
+    await stop()
+
+    expect(mockInvoke).toHaveBeenLastCalledWith('stop_frame_stream', {
+      deviceId: 'cam-1',
+      streamId: 7,
+    })
+  })
+})
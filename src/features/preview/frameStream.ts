@@ -0,0 +1,18 @@
+import { Channel, invoke } from '@tauri-apps/api/core'
+import type { StreamedFrame } from '../../types/camera'
+
+/**
+ * Receive every frame a camera captures, instead of polling `get_frame`.
+ * Resolves to a function that stops the stream.
+ */
+export async function streamFrames(
+  deviceId: string,
+  onFrame: (frame: StreamedFrame) => void,
+): Promise<() => Promise<void>> {
+  const channel = new Channel<StreamedFrame>()
+  channel.onmessage = onFrame
+  const streamId = await invoke<number>('stream_frames', { deviceId, channel })
+  return async () => {
+    await invoke('stop_frame_stream', { deviceId, streamId })
+  }
+}
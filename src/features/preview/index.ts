@@ -2,5 +2,25 @@ export { PreviewCanvas } from './PreviewCanvas.tsx'
 export { DiagnosticOverlay } from './DiagnosticOverlay.tsx'
 export { usePreview } from './usePreview.ts'
 export { useThumbnail } from './useThumbnail.ts'
+export { useThumbnailSize } from './useThumbnailSize.ts'
+export { streamFrames } from './frameStream.ts'
+export { onPreviewFrame, subscribePreview, unsubscribePreview } from './previewSubscription.ts'
+export { disableShmExport, enableShmExport } from './shmExport.ts'
+export { getResourceUsage, getResourceUsagePerDevice } from './resourceUsage.ts'
+export { getFrameChunked } from './chunkedFrame.ts'
+export { getFrameMeta, getFrameRaw } from './rawFrame.ts'
+export { captureSnapshot } from './snapshot.ts'
+export { decodeMaskRuns, getExposureMask } from './exposureMask.ts'
+export {
+  onTimelapseProgress,
+  onTimelapseStopped,
+  startTimelapse,
+  stopTimelapse,
+} from './timelapse.ts'
 export { useDiagnostics } from './useDiagnostics.ts'
-export type { DiagnosticSnapshot } from './useDiagnostics.ts'
+export type {
+  ContentHealth,
+  DiagnosticSnapshot,
+  NegotiatedFormat,
+  PreviewContentPayload,
+} from './useDiagnostics.ts'
@@ -0,0 +1,54 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { PreviewFramePayload } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { onPreviewFrame, subscribePreview, unsubscribePreview } from './previewSubscription.ts'
+
+const mockInvoke = vi.mocked(invoke)
+const mockListen = vi.mocked(listen)
+
+type FrameHandler = (event: { payload: PreviewFramePayload }) => void
+
+describe('preview subscriptions', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+    mockListen.mockReset()
+  })
+
+  it('subscribes at the requested rate', async () => {
+    mockInvoke.mockResolvedValue(undefined)
+    await subscribePreview('cam-1', 30)
+    expect(mockInvoke).toHaveBeenCalledWith('subscribe_preview', { deviceId: 'cam-1', maxFps: 30 })
+  })
+
+  it('unsubscribes, resolving to whether there was a subscription', async () => {
+    mockInvoke.mockResolvedValue(true)
+    await expect(unsubscribePreview('cam-1')).resolves.toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('unsubscribe_preview', { deviceId: 'cam-1' })
+  })
+
+  it('forwards only frames from the given camera', async () => {
+    const unlisten = vi.fn()
+    mockListen.mockResolvedValue(unlisten)
+    const callback = vi.fn()
+
+    await expect(onPreviewFrame('cam-1', callback)).resolves.toBe(unlisten)
+
+    expect(mockListen).toHaveBeenCalledWith('preview-frame', expect.any(Function))
+    const handler = mockListen.mock.calls[0][1] as FrameHandler
+    const frame: PreviewFramePayload = { deviceId: 'cam-1', sequence: 3, jpeg: 'abc' }
+    handler({ payload: { ...frame, deviceId: 'cam-2' } })
+    handler({ payload: frame })
+    expect(callback).toHaveBeenCalledTimes(1)
+    expect(callback).toHaveBeenCalledWith(frame)
+  })
+})
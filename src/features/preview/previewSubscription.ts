@@ -0,0 +1,29 @@
+import { invoke } from '@tauri-apps/api/core'
+import { type UnlistenFn, listen } from '@tauri-apps/api/event'
+import type { PreviewFramePayload } from '../../types/camera'
+
+/**
+ * Have a camera's new preview frames pushed as `preview-frame` events, at
+ * most `maxFps` a second, instead of polling `get_frame`. Replaces the
+ * camera's existing subscription; stopping the preview ends it.
+ */
+export async function subscribePreview(deviceId: string, maxFps: number): Promise<void> {
+  return invoke('subscribe_preview', { deviceId, maxFps })
+}
+
+/** End a camera's preview subscription. Resolves to whether it had one. */
+export async function unsubscribePreview(deviceId: string): Promise<boolean> {
+  return invoke<boolean>('unsubscribe_preview', { deviceId })
+}
+
+/** Receive `deviceId`'s pushed preview frames. Returns an unlisten function. */
+export async function onPreviewFrame(
+  deviceId: string,
+  callback: (payload: PreviewFramePayload) => void,
+): Promise<UnlistenFn> {
+  return listen<PreviewFramePayload>('preview-frame', (event) => {
+    if (event.payload.deviceId === deviceId) {
+      callback(event.payload)
+    }
+  })
+}
@@ -0,0 +1,33 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { getFrameMeta, getFrameRaw } from './rawFrame.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+describe('raw frames', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('fetches the JPEG bytes as an ArrayBuffer', async () => {
+    const jpeg = new Uint8Array([0xff, 0xd8, 0xff, 0xd9]).buffer
+    mockInvoke.mockResolvedValueOnce(jpeg)
+
+    const bytes = await getFrameRaw('cam-1')
+    expect(new Uint8Array(bytes).slice(0, 2)).toEqual(new Uint8Array([0xff, 0xd8]))
+    expect(mockInvoke).toHaveBeenCalledWith('get_frame_raw', { deviceId: 'cam-1' })
+  })
+
+  it('fetches the frame metadata', async () => {
+    const meta = { sequence: 3, width: 64, height: 32, byteLen: 1024 }
+    mockInvoke.mockResolvedValueOnce(meta)
+
+    await expect(getFrameMeta('cam-1')).resolves.toEqual(meta)
+    expect(mockInvoke).toHaveBeenCalledWith('get_frame_meta', { deviceId: 'cam-1' })
+  })
+})
@@ -0,0 +1,15 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { FrameMeta } from '../../types/camera'
+
+/**
+ * Fetch a camera's latest frame as raw JPEG bytes, skipping the base64 round trip `get_frame`
+ * makes. Shares the backend's frame cache with `get_frame`, so neither compresses twice.
+ */
+export async function getFrameRaw(deviceId: string): Promise<ArrayBuffer> {
+  return invoke<ArrayBuffer>('get_frame_raw', { deviceId })
+}
+
+/** Sequence number, size and dimensions of the frame `getFrameRaw` would return. */
+export async function getFrameMeta(deviceId: string): Promise<FrameMeta> {
+  return invoke<FrameMeta>('get_frame_meta', { deviceId })
+}
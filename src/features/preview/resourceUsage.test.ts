@@ -0,0 +1,56 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { ResourceUsage, ResourceUsagePerDevice } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { getResourceUsage, getResourceUsagePerDevice } from './resourceUsage.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+describe('resource usage', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('returns the usage reported by the backend', async () => {
+    const usage: ResourceUsage = {
+      sessionCount: 4,
+      runningSessions: 3,
+      maxSessions: 16,
+      frameBufferBytes: 11059200,
+      cacheBytes: 524288,
+      processThreads: 42,
+      settings: { lastSaved: 1760000000, unsavedChanges: 0 },
+      framePayloads: {
+        responses: 120,
+        totalBytes: 96000000,
+        largestBytes: 2097000,
+        reduced: 3,
+        overBudget: 0,
+        chunks: 8,
+      },
+    }
+    mockInvoke.mockResolvedValueOnce(usage)
+
+    await expect(getResourceUsage()).resolves.toEqual(usage)
+    expect(mockInvoke).toHaveBeenCalledWith('get_resource_usage')
+  })
+
+  it('returns per-device usage reported by the backend', async () => {
+    const cam = {
+      cpuPercent1m: 12.5,
+      captureThreadTimeMs: 5400,
+      compressTimeMs: 9100,
+      bufferBytes: 2764800,
+      cacheBytes: 131072,
+    }
+    const usage: ResourceUsagePerDevice = { devices: { 'cam-1': cam }, totals: cam }
+    mockInvoke.mockResolvedValueOnce(usage)
+
+    await expect(getResourceUsagePerDevice()).resolves.toEqual(usage)
+    expect(mockInvoke).toHaveBeenCalledWith('get_resource_usage_per_device')
+  })
+})
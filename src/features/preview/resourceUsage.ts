@@ -0,0 +1,12 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { ResourceUsage, ResourceUsagePerDevice } from '../../types/camera'
+
+/** Sessions, buffered bytes, threads, settings save health and frame sizes, for diagnostics. */
+export async function getResourceUsage(): Promise<ResourceUsage> {
+  return invoke<ResourceUsage>('get_resource_usage')
+}
+
+/** CPU time, buffered and cached bytes for each camera with a session, plus totals. */
+export async function getResourceUsagePerDevice(): Promise<ResourceUsagePerDevice> {
+  return invoke<ResourceUsagePerDevice>('get_resource_usage_per_device')
+}
@@ -0,0 +1,43 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { ShmLayout } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { disableShmExport, enableShmExport } from './shmExport.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+describe('shared-memory export', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('enables an export and returns its layout', async () => {
+    const layout: ShmLayout = {
+      name: 'Local\\cameras-frames-cam-1-1',
+      size: 12441728,
+      version: 1,
+      headerSize: 128,
+      bufferCount: 2,
+      bufferSize: 6220800,
+      width: 1920,
+      height: 1080,
+      stride: 5760,
+      pixelFormat: 'rgb24',
+    }
+    mockInvoke.mockResolvedValueOnce(layout)
+
+    expect(await enableShmExport('cam-1')).toEqual(layout)
+    expect(mockInvoke).toHaveBeenCalledWith('enable_shm_export', { deviceId: 'cam-1' })
+  })
+
+  it('disables an export', async () => {
+    mockInvoke.mockResolvedValueOnce(true)
+
+    expect(await disableShmExport('cam-1')).toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('disable_shm_export', { deviceId: 'cam-1' })
+  })
+})
@@ -0,0 +1,15 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { ShmLayout } from '../../types/camera'
+
+/**
+ * Export a camera's raw frames to shared memory for a companion process.
+ * Resolves to the mapping's name and layout; the preview must be running.
+ */
+export async function enableShmExport(deviceId: string): Promise<ShmLayout> {
+  return invoke<ShmLayout>('enable_shm_export', { deviceId })
+}
+
+/** Stop a shared-memory export. Resolves to whether one was running. */
+export async function disableShmExport(deviceId: string): Promise<boolean> {
+  return invoke<boolean>('disable_shm_export', { deviceId })
+}
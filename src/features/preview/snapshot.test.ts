@@ -0,0 +1,40 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { captureSnapshot } from './snapshot.ts'
+
+const mockInvoke = vi.mocked(invoke)
+
+describe('snapshots', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('leaves the path and quality to the backend by default', async () => {
+    mockInvoke.mockResolvedValueOnce('C:\\Users\\me\\Pictures\\cameras\\cam-1.jpg')
+
+    await expect(captureSnapshot('cam-1')).resolves.toBe(
+      'C:\\Users\\me\\Pictures\\cameras\\cam-1.jpg',
+    )
+    expect(mockInvoke).toHaveBeenCalledWith('capture_snapshot', {
+      deviceId: 'cam-1',
+      path: null,
+      quality: null,
+    })
+  })
+
+  it('passes a chosen path and quality through', async () => {
+    mockInvoke.mockResolvedValueOnce('/tmp/still.jpg')
+
+    await expect(captureSnapshot('cam-1', '/tmp/still.jpg', 80)).resolves.toBe('/tmp/still.jpg')
+    expect(mockInvoke).toHaveBeenCalledWith('capture_snapshot', {
+      deviceId: 'cam-1',
+      path: '/tmp/still.jpg',
+      quality: 80,
+    })
+  })
+})
@@ -0,0 +1,18 @@
+import { invoke } from '@tauri-apps/api/core'
+
+/**
+ * Save a camera's latest frame as a full-resolution JPEG. Writes to `path` if given, otherwise to
+ * `Pictures/cameras/<device>-<time>.jpg`, and resolves to where the still was written. `quality`
+ * is 1-100 and defaults to 92.
+ */
+export async function captureSnapshot(
+  deviceId: string,
+  path?: string,
+  quality?: number,
+): Promise<string> {
+  return invoke<string>('capture_snapshot', {
+    deviceId,
+    path: path ?? null,
+    quality: quality ?? null,
+  })
+}
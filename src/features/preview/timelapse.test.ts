@@ -0,0 +1,73 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { TimelapseProgress } from '../../types/camera'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { onTimelapseStopped, startTimelapse, stopTimelapse } from './timelapse.ts'
+
+const mockInvoke = vi.mocked(invoke)
+const mockListen = vi.mocked(listen)
+
+const progress: TimelapseProgress = {
+  deviceId: 'cam-1',
+  framesWritten: 120,
+  skippedTicks: 2,
+  pausedTicks: 0,
+  bytesWritten: 24_000_000,
+  lastFile: 'frame_000120.jpg',
+  stopReason: 'requested',
+}
+
+describe('time-lapse', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+    mockListen.mockReset()
+  })
+
+  it('starts a time-lapse, sending null for no frame limit', async () => {
+    mockInvoke.mockResolvedValue(undefined)
+    await startTimelapse('cam-1', 30, 'D:\\lapse')
+    expect(mockInvoke).toHaveBeenCalledWith('start_timelapse', {
+      deviceId: 'cam-1',
+      intervalSecs: 30,
+      outputDir: 'D:\\lapse',
+      maxFrames: null,
+    })
+
+    await startTimelapse('cam-1', 5, 'D:\\lapse2', 100)
+    expect(mockInvoke).toHaveBeenLastCalledWith('start_timelapse', {
+      deviceId: 'cam-1',
+      intervalSecs: 5,
+      outputDir: 'D:\\lapse2',
+      maxFrames: 100,
+    })
+  })
+
+  it('stops a time-lapse and returns its final progress', async () => {
+    mockInvoke.mockResolvedValueOnce(progress)
+    const result = await stopTimelapse('cam-1')
+    expect(mockInvoke).toHaveBeenCalledWith('stop_timelapse', { deviceId: 'cam-1' })
+    expect(result).toEqual(progress)
+  })
+
+  it('forwards timelapse-stopped payloads', async () => {
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload: progress })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onTimelapseStopped(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('timelapse-stopped', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(progress)
+  })
+})
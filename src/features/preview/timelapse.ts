@@ -0,0 +1,48 @@
+import { invoke } from '@tauri-apps/api/core'
+import { type UnlistenFn, listen } from '@tauri-apps/api/event'
+import type { TimelapseProgress } from '../../types/camera'
+
+/**
+ * Capture a still from a camera's preview every `intervalSecs` seconds into
+ * `outputDir`, with a `timelapse.json` index. Runs until stopped, or until
+ * `maxFrames` stills are written.
+ */
+export async function startTimelapse(
+  deviceId: string,
+  intervalSecs: number,
+  outputDir: string,
+  maxFrames?: number,
+): Promise<void> {
+  return invoke('start_timelapse', {
+    deviceId,
+    intervalSecs,
+    outputDir,
+    maxFrames: maxFrames ?? null,
+  })
+}
+
+/** Stop a camera's time-lapse. Resolves to its final progress, or null if none was running. */
+export async function stopTimelapse(deviceId: string): Promise<TimelapseProgress | null> {
+  return invoke<TimelapseProgress | null>('stop_timelapse', { deviceId })
+}
+
+/** Subscribe to time-lapse progress, sent every few stills. Returns an unlisten function. */
+export async function onTimelapseProgress(
+  callback: (payload: TimelapseProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<TimelapseProgress>('timelapse-progress', (event) => {
+    callback(event.payload)
+  })
+}
+
+/**
+ * Subscribe to time-lapses ending, whether stopped, completed, unplugged or
+ * failed. Returns an unlisten function.
+ */
+export async function onTimelapseStopped(
+  callback: (payload: TimelapseProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<TimelapseProgress>('timelapse-stopped', (event) => {
+    callback(event.payload)
+  })
+}
@@ -1,6 +1,40 @@
 import { useEffect, useRef, useState } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 
+/** Whether a running session's frames are black or frozen. */
+export type ContentHealth = 'ok' | 'black' | 'frozen'
+
+/** Payload of the `preview-content-warning` event. */
+export interface PreviewContentPayload {
+  deviceId: string
+  health: ContentHealth
+}
+
+/** End-to-end delivery counts for one consumer of a session's frames. */
+export interface FrameDelivery {
+  consumer: string
+  produced: number
+  consumed: number
+  /** Frames that left the buffer before this consumer saw them. */
+  overwrittenUnseen: number
+  seenPercent: number
+}
+
+/** Frames the capture callback dropped, by why. */
+export interface DropCounts {
+  nullBuffer: number
+  unsupportedFormat: number
+  sizeMismatch: number
+  decodeFailed: number
+}
+
+/** Frame size and pixel format a capture graph settled on. */
+export interface NegotiatedFormat {
+  width: number
+  height: number
+  pixelFormat: string
+}
+
 export interface DiagnosticSnapshot {
   fps: number
   frameCount: number
@@ -9,6 +43,33 @@ export interface DiagnosticSnapshot {
   latencyMs: number
   bandwidthBps: number
   usbBusInfo: string | null
+  /** Frame rate the camera agreed to; null until the graph connects or if the driver doesn't say. */
+  negotiatedFps: number | null
+  panicCount: number
+  /** Frame rate by the camera's own timestamps; 0 before two frames. */
+  captureFps: number
+  /** Frames the camera stamped earlier than the frame before. */
+  timestampRegressions: number
+  /** Black or frozen frames even though the graph is running. */
+  contentHealth: ContentHealth
+  /** Stills written to disk by `capture_snapshot`. */
+  snapshotCount: number
+  /** Sums to `dropCount`. */
+  dropsByReason: DropCounts
+  /** Mean time spent converting and pushing each of the last few frames; 0 before the first. */
+  conversionAvgUs: number
+  conversionMaxUs: number
+  /** Frame rate by the camera's timestamps over the last couple of seconds. */
+  currentFps: number
+  /** Quality get_frame currently compresses at; null until it has compressed a frame. */
+  effectiveJpegQuality: number | null
+  /** Most recent get_frame encode durations, oldest first. */
+  recentEncodeMs: number[]
+  frameDelivery: FrameDelivery[]
+  /** Bytes held by the frame and thumbnail JPEG caches across all cameras. */
+  jpegCacheBytes: number
+  /** Frame size and pixel format the camera settled on; null until the graph is connected. */
+  negotiatedFormat: NegotiatedFormat | null
 }
 
 /** Polls diagnostic stats at 1fps (1000ms interval). */
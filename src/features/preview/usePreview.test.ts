@@ -1,6 +1,7 @@
 import { afterEach, beforeEach, describe, expect, it, vi } from 'vitest'
 import { act, renderHook } from '@testing-library/react'
 import { usePreview } from './usePreview.ts'
+import type { PixelRect } from '../../types/camera'
 
 vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn(),
@@ -119,6 +120,81 @@ describe('usePreview', () => {
     })
   })
 
+  it('requests PNG frames and labels their blobs as PNG', async () => {
+    mockInvoke.mockImplementation(async (cmd: string) => {
+      if (cmd === 'get_frame') return btoa(String.fromCharCode(0x89, 0x50, 0x4e, 0x47))
+      return undefined
+    })
+
+    const rafCallbacks: FrameRequestCallback[] = []
+    vi.spyOn(globalThis, 'requestAnimationFrame').mockImplementation((cb) => {
+      rafCallbacks.push(cb)
+      return rafCallbacks.length
+    })
+
+    const { result } = renderHook(() => usePreview('device-1', 'png'))
+
+    act(() => {
+      result.current.start()
+    })
+
+    await act(async () => {
+      if (rafCallbacks.length > 0) {
+        await rafCallbacks[rafCallbacks.length - 1](performance.now())
+      }
+    })
+
+    expect(mockInvoke).toHaveBeenCalledWith('get_frame', {
+      deviceId: 'device-1',
+      format: 'png',
+      crop: null,
+    })
+    expect((mockCreateObjectURL.mock.calls[0][0] as Blob).type).toBe('image/png')
+
+    act(() => {
+      result.current.stop()
+    })
+  })
+
+  it('requests the current crop without restarting the loop', async () => {
+    mockInvoke.mockImplementation(async (cmd: string) => {
+      if (cmd === 'get_frame') return btoa(String.fromCharCode(0xff, 0xd8))
+      return undefined
+    })
+
+    const rafCallbacks: FrameRequestCallback[] = []
+    vi.spyOn(globalThis, 'requestAnimationFrame').mockImplementation((cb) => {
+      rafCallbacks.push(cb)
+      return rafCallbacks.length
+    })
+
+    const crop = { x: 10, y: 20, width: 64, height: 48 }
+    const { result, rerender } = renderHook(
+      ({ crop }: { crop: PixelRect | null }) => usePreview('device-1', 'jpeg', crop),
+      { initialProps: { crop: null as PixelRect | null } },
+    )
+
+    act(() => {
+      result.current.start()
+    })
+    rerender({ crop })
+
+    await act(async () => {
+      await rafCallbacks[rafCallbacks.length - 1](performance.now())
+    })
+
+    expect(mockInvoke).toHaveBeenCalledWith('get_frame', {
+      deviceId: 'device-1',
+      format: 'jpeg',
+      crop,
+    })
+    expect(result.current.isActive).toBe(true)
+
+    act(() => {
+      result.current.stop()
+    })
+  })
+
   it('revokes previous blob URL when creating new one', async () => {
     let frameCount = 0
     mockInvoke.mockImplementation(async (cmd: string) => {
@@ -189,7 +265,7 @@ describe('usePreview', () => {
   })
 
   it('sets error when preview-error event matches deviceId', async () => {
-    type PreviewErrorPayload = { deviceId: string; error: string }
+    type PreviewErrorPayload = { deviceId: string; error: { code: string; message: string } }
     type EventHandler = (event: { event: string; id: number; payload: PreviewErrorPayload }) => void
     let errorCallback: EventHandler | null = null
     mockListen.mockImplementation(async (_event, cb) => {
@@ -209,7 +285,10 @@ describe('usePreview', () => {
       errorCallback?.({
         event: 'preview-error',
         id: 1,
-        payload: { deviceId: 'device-1', error: 'Camera disconnected' },
+        payload: {
+          deviceId: 'device-1',
+          error: { code: 'canon_disconnected', message: 'Camera disconnected' },
+        },
       })
     })
 
@@ -218,7 +297,7 @@ describe('usePreview', () => {
   })
 
   it('ignores preview-error events for other devices', async () => {
-    type PreviewErrorPayload = { deviceId: string; error: string }
+    type PreviewErrorPayload = { deviceId: string; error: { code: string; message: string } }
     type EventHandler = (event: { event: string; id: number; payload: PreviewErrorPayload }) => void
     let errorCallback: EventHandler | null = null
     mockListen.mockImplementation(async (_event, cb) => {
@@ -234,7 +313,7 @@ describe('usePreview', () => {
       errorCallback?.({
         event: 'preview-error',
         id: 2,
-        payload: { deviceId: 'device-2', error: 'Some error' },
+        payload: { deviceId: 'device-2', error: { code: 'preview_failed', message: 'Some error' } },
       })
     })
 
@@ -345,6 +424,53 @@ describe('usePreview', () => {
     now.mockRestore()
   })
 
+  it('upgrades a thumbnail-only session once without counting failures', async () => {
+    const now = vi.spyOn(Date, 'now')
+    const startTime = 1000
+    now.mockReturnValue(startTime)
+
+    mockInvoke.mockImplementation(async (cmd: string) => {
+      if (cmd === 'get_frame') {
+        throw {
+          code: 'preview_thumbnail_only',
+          message: 'preview is thumbnail-only; call upgrade_preview for full frames',
+        }
+      }
+      return undefined
+    })
+
+    const rafCallbacks: FrameRequestCallback[] = []
+    vi.spyOn(globalThis, 'requestAnimationFrame').mockImplementation((cb) => {
+      rafCallbacks.push(cb)
+      return rafCallbacks.length
+    })
+
+    const { result } = renderHook(() => usePreview('device-1'))
+
+    act(() => {
+      result.current.start()
+    })
+
+    now.mockReturnValue(startTime + 5001)
+    for (let i = 0; i < 200; i++) {
+      await act(async () => {
+        const cb = rafCallbacks[rafCallbacks.length - 1]
+        if (cb) await cb(performance.now())
+      })
+    }
+
+    const upgrades = mockInvoke.mock.calls.filter(([cmd]) => cmd === 'upgrade_preview')
+    expect(upgrades).toEqual([['upgrade_preview', { deviceId: 'device-1' }]])
+    expect(result.current.error).toBeNull()
+    expect(result.current.isActive).toBe(true)
+
+    act(() => {
+      result.current.stop()
+    })
+
+    now.mockRestore()
+  })
+
   it('resets failure counter on successful frame', async () => {
     const now = vi.spyOn(Date, 'now')
     const startTime = 1000
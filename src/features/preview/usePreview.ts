@@ -1,6 +1,8 @@
 import { useCallback, useEffect, useRef, useState } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
+import type { FrameFormat, PixelRect, PreviewErrorPayload } from '../../types/camera'
+import { hasErrorCode } from '../../types/error'
 import { useToastStore } from '../notifications/useToast'
 
 interface UsePreviewResult {
@@ -25,9 +27,17 @@ const STARTUP_GRACE_MS = 5_000
  *
  * Capture sessions are started by the backend at startup and on hotplug.
  * This hook only drives the frame-fetch rAF loop — it does NOT call
- * `start_preview` or `stop_preview` IPC commands.
+ * `start_preview` or `stop_preview` IPC commands. Sessions auto-started
+ * thumbnail-only are upgraded once via `upgrade_preview`.
+ *
+ * Frames are JPEG unless `format` asks for lossless PNG, as the focus-check view does.
+ * `crop` fetches only that region of the frame; it can change without restarting the loop.
  */
-export function usePreview(deviceId: string | null): UsePreviewResult {
+export function usePreview(
+  deviceId: string | null,
+  format: FrameFormat = 'jpeg',
+  crop: PixelRect | null = null,
+): UsePreviewResult {
   const [frameSrc, setFrameSrc] = useState<string | null>(null)
   const [isActive, setIsActive] = useState(false)
   const [error, setError] = useState<string | null>(null)
@@ -36,8 +46,13 @@ export function usePreview(deviceId: string | null): UsePreviewResult {
   const prevBlobUrlRef = useRef<string | null>(null)
   const failureCountRef = useRef(0)
   const startTimeRef = useRef(0)
+  const upgradeRequestedRef = useRef(false)
   /** Incremented on each start() call so stale fetch loops self-terminate. */
   const generationRef = useRef(0)
+  const cropRef = useRef(crop)
+  useEffect(() => {
+    cropRef.current = crop
+  }, [crop])
 
   const cancelLoop = useCallback(() => {
     runningRef.current = false
@@ -72,12 +87,17 @@ export function usePreview(deviceId: string | null): UsePreviewResult {
     runningRef.current = true
     failureCountRef.current = 0
     startTimeRef.current = Date.now()
+    upgradeRequestedRef.current = false
     const gen = ++generationRef.current
 
     const fetchFrame = async () => {
       if (!runningRef.current || gen !== generationRef.current) return
       try {
-        const base64 = await invoke<string>('get_frame', { deviceId })
+        const base64 = await invoke<string>('get_frame', {
+          deviceId,
+          format,
+          crop: cropRef.current,
+        })
         // After the await, check generation again — a newer start() may have
         // been called while this request was in-flight.
         if (gen !== generationRef.current) return
@@ -85,15 +105,32 @@ export function usePreview(deviceId: string | null): UsePreviewResult {
         const raw = atob(base64)
         const bytes = new Uint8Array(raw.length)
         for (let i = 0; i < raw.length; i++) bytes[i] = raw.charCodeAt(i)
-        const blob = new Blob([bytes], { type: 'image/jpeg' })
+        const blob = new Blob([bytes], { type: `image/${format}` })
         const url = URL.createObjectURL(blob)
         if (prevBlobUrlRef.current) {
           URL.revokeObjectURL(prevBlobUrlRef.current)
         }
         prevBlobUrlRef.current = url
         setFrameSrc(url)
-      } catch {
+      } catch (e) {
         if (gen !== generationRef.current) return
+        // get_frame refuses sessions auto-started for thumbnails
+        if (hasErrorCode(e, 'preview_thumbnail_only')) {
+          // Not a failure — the session is restarting at full resolution,
+          // so give it the startup grace period again.
+          if (!upgradeRequestedRef.current) {
+            upgradeRequestedRef.current = true
+            startTimeRef.current = Date.now()
+            invoke('upgrade_preview', { deviceId }).catch(() => {
+              upgradeRequestedRef.current = false
+            })
+          }
+          failureCountRef.current = 0
+          if (runningRef.current) {
+            rafIdRef.current = requestAnimationFrame(() => void fetchFrame())
+          }
+          return
+        }
         const elapsed = Date.now() - startTimeRef.current
         if (elapsed < STARTUP_GRACE_MS) {
           // During startup grace period, reset the counter so the camera
@@ -117,22 +154,19 @@ export function usePreview(deviceId: string | null): UsePreviewResult {
     }
 
     rafIdRef.current = requestAnimationFrame(() => void fetchFrame())
-  }, [deviceId, cancelLoop])
+  }, [deviceId, format, cancelLoop])
 
   // Listen for preview-error events from the Rust backend
   useEffect(() => {
     if (!deviceId) return
 
-    const unlistenPromise = listen<{ deviceId: string; error: string }>(
-      'preview-error',
-      (event) => {
-        if (event.payload.deviceId === deviceId) {
-          setError(event.payload.error)
-          setFrameSrc(null)
-          useToastStore.getState().addToast(event.payload.error, 'error')
-        }
-      },
-    )
+    const unlistenPromise = listen<PreviewErrorPayload>('preview-error', (event) => {
+      if (event.payload.deviceId === deviceId) {
+        setError(event.payload.error.message)
+        setFrameSrc(null)
+        useToastStore.getState().addToast(event.payload.error.message, 'error')
+      }
+    })
 
     return () => {
       unlistenPromise.then((fn) => fn())
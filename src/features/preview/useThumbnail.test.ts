@@ -35,6 +35,7 @@ describe('useThumbnail', () => {
 
     expect(mockInvoke).toHaveBeenCalledWith('get_thumbnail', {
       deviceId: 'device-1',
+      format: 'jpeg',
     })
     expect(result.current).toBe('data:image/jpeg;base64,THUMB_DATA')
 
@@ -42,6 +43,50 @@ describe('useThumbnail', () => {
     vi.useRealTimers()
   })
 
+  it('asks for PNG thumbnails when told to', async () => {
+    vi.useFakeTimers()
+    mockInvoke.mockResolvedValue('PNG_DATA')
+
+    const { result, unmount } = renderHook(() => useThumbnail('device-1', 'png'))
+
+    await act(async () => {
+      await vi.advanceTimersByTimeAsync(200)
+    })
+
+    expect(mockInvoke).toHaveBeenCalledWith('get_thumbnail', {
+      deviceId: 'device-1',
+      format: 'png',
+    })
+    expect(result.current).toBe('data:image/png;base64,PNG_DATA')
+
+    unmount()
+    vi.useRealTimers()
+  })
+
+  it('asks for its own box and fit when given one', async () => {
+    vi.useFakeTimers()
+    mockInvoke.mockResolvedValue('GRID_DATA')
+
+    const { unmount } = renderHook(() =>
+      useThumbnail('device-1', 'jpeg', { width: 320, height: 180, fit: 'cover' }),
+    )
+
+    await act(async () => {
+      await vi.advanceTimersByTimeAsync(200)
+    })
+
+    expect(mockInvoke).toHaveBeenCalledWith('get_thumbnail', {
+      deviceId: 'device-1',
+      format: 'jpeg',
+      width: 320,
+      height: 180,
+      fit: 'cover',
+    })
+
+    unmount()
+    vi.useRealTimers()
+  })
+
   it('cleans up interval on unmount', async () => {
     vi.useFakeTimers()
     mockInvoke.mockResolvedValue('DATA')
@@ -1,8 +1,16 @@
 import { useEffect, useRef, useState } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import type { FrameFormat, ThumbnailOptions } from '../../types/camera'
 
-/** Polls for sidebar thumbnail frames at 5fps (200ms interval). */
-export function useThumbnail(deviceId: string | null): string | null {
+/**
+ * Polls for sidebar thumbnail frames at 5fps (200ms interval), as JPEG unless `format` says PNG,
+ * at the configured size unless `options` asks for another box or fit.
+ */
+export function useThumbnail(
+  deviceId: string | null,
+  format: FrameFormat = 'jpeg',
+  { width, height, fit }: ThumbnailOptions = {},
+): string | null {
   const [state, setState] = useState<{
     deviceId: string | null
     src: string | null
@@ -19,8 +27,14 @@ export function useThumbnail(deviceId: string | null): string | null {
 
     intervalRef.current = setInterval(async () => {
       try {
-        const base64 = await invoke<string>('get_thumbnail', { deviceId })
-        setState((prev) => ({ ...prev, src: `data:image/jpeg;base64,${base64}` }))
+        const base64 = await invoke<string>('get_thumbnail', {
+          deviceId,
+          format,
+          width,
+          height,
+          fit,
+        })
+        setState((prev) => ({ ...prev, src: `data:image/${format};base64,${base64}` }))
       } catch {
         // Thumbnail not available yet — skip
       }
@@ -32,7 +46,7 @@ export function useThumbnail(deviceId: string | null): string | null {
         intervalRef.current = null
       }
     }
-  }, [deviceId])
+  }, [deviceId, format, width, height, fit])
 
   return state.src
 }
@@ -0,0 +1,87 @@
+import { afterEach, beforeEach, describe, expect, it, vi } from 'vitest'
+import { renderHook } from '@testing-library/react'
+import { useThumbnailSize } from './useThumbnailSize.ts'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from '@tauri-apps/api/core'
+const mockInvoke = vi.mocked(invoke)
+
+function mockMatchMedia() {
+  const listeners: Array<() => void> = []
+  const media = {
+    addEventListener: vi.fn((_: string, listener: () => void) => listeners.push(listener)),
+    removeEventListener: vi.fn(),
+  }
+  vi.stubGlobal('matchMedia', vi.fn(() => media))
+  return { media, fire: () => listeners.forEach((listener) => listener()) }
+}
+
+describe('useThumbnailSize', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+    mockInvoke.mockResolvedValue(undefined)
+    vi.stubGlobal('devicePixelRatio', 2)
+  })
+
+  afterEach(() => {
+    vi.unstubAllGlobals()
+  })
+
+  it('configures thumbnails with the box and pixel ratio', () => {
+    mockMatchMedia()
+    renderHook(() => useThumbnailSize(80, 60))
+
+    expect(mockInvoke).toHaveBeenCalledWith('configure_thumbnails', {
+      width: 80,
+      height: 60,
+      dpr: 2,
+      deviceId: undefined,
+    })
+  })
+
+  it('passes a device id through', () => {
+    mockMatchMedia()
+    renderHook(() => useThumbnailSize(80, 60, 'cam-1'))
+
+    expect(mockInvoke).toHaveBeenCalledWith('configure_thumbnails', {
+      width: 80,
+      height: 60,
+      dpr: 2,
+      deviceId: 'cam-1',
+    })
+  })
+
+  it('reconfigures when the pixel ratio changes', () => {
+    const { fire } = mockMatchMedia()
+    renderHook(() => useThumbnailSize(80, 60))
+    mockInvoke.mockClear()
+
+    vi.stubGlobal('devicePixelRatio', 1.5)
+    fire()
+
+    expect(mockInvoke).toHaveBeenCalledWith('configure_thumbnails', {
+      width: 80,
+      height: 60,
+      dpr: 1.5,
+      deviceId: undefined,
+    })
+  })
+
+  it('stops listening on unmount', () => {
+    const { media } = mockMatchMedia()
+    const { unmount } = renderHook(() => useThumbnailSize(80, 60))
+    unmount()
+
+    expect(media.removeEventListener).toHaveBeenCalledWith('change', expect.any(Function))
+  })
+
+  it('ignores backend errors', () => {
+    mockMatchMedia()
+    mockInvoke.mockRejectedValue(new Error('not ready'))
+
+    expect(() => renderHook(() => useThumbnailSize(80, 60))).not.toThrow()
+  })
+})
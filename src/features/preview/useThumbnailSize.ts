@@ -0,0 +1,30 @@
+import { useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+
+/**
+ * Tells the backend the CSS box thumbnails are shown in and the display's
+ * pixel ratio, so they are drawn sharp without wasting bandwidth. Reports
+ * again when the pixel ratio changes, e.g. moving the window to another screen.
+ */
+export function useThumbnailSize(width: number, height: number, deviceId?: string) {
+  useEffect(() => {
+    let media: MediaQueryList | null = null
+
+    const configure = () => {
+      const dpr = window.devicePixelRatio || 1
+      invoke('configure_thumbnails', { width, height, dpr, deviceId }).catch(() => {
+        // Backend unavailable — thumbnails keep their previous size
+      })
+
+      media?.removeEventListener('change', configure)
+      media = window.matchMedia?.(`(resolution: ${dpr}dppx)`) ?? null
+      media?.addEventListener('change', configure)
+    }
+
+    configure()
+
+    return () => {
+      media?.removeEventListener('change', configure)
+    }
+  }, [width, height, deviceId])
+}
@@ -3,6 +3,7 @@ import type { GpuAdapterInfo } from '../../types/gpu'
 import { useToastStore } from '../notifications/useToast'
 import { getActiveGpu, listGpuAdapters, setGpuAdapter } from './gpu-api'
 import './GpuAdapterSelector.css'
+import { errorMessage } from '../../types/error'
 
 /** Value representing CPU-only mode in the dropdown. */
 const CPU_ONLY_VALUE = 'cpu'
@@ -39,7 +40,7 @@ export function GpuAdapterSelector() {
       })
       .catch((err: unknown) => {
         if (cancelled) return
-        const message = err instanceof Error ? err.message : String(err)
+        const message = errorMessage(err)
         useToastStore.getState().addToast(`Failed to load GPU adapters: ${message}`, 'error')
       })
       .finally(() => {
@@ -69,7 +70,7 @@ export function GpuAdapterSelector() {
         }
       })
       .catch((err: unknown) => {
-        const message = err instanceof Error ? err.message : String(err)
+        const message = errorMessage(err)
         useToastStore.getState().addToast(`Failed to switch GPU: ${message}`, 'error')
       })
   }, [])
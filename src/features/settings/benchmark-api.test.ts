@@ -0,0 +1,53 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { BenchmarkReport } from '../../types/benchmark'
+import { runPipelineBenchmark } from './benchmark-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+const testReport: BenchmarkReport = {
+  width: 1920,
+  height: 1080,
+  iterationsRequested: 50,
+  iterationsCompleted: 50,
+  timedOut: false,
+  stages: [
+    { stage: 'nv12_to_rgb', samples: 50, meanUs: 4200, minUs: 3900, maxUs: 6100, maxFps: 238.1 },
+  ],
+  pipelines: [
+    {
+      format: 'nv12',
+      stages: ['nv12_to_rgb', 'jpeg_q75', 'base64'],
+      meanUs: 16000,
+      maxFps: 62.5,
+    },
+  ],
+}
+
+describe('pipeline benchmark API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('runs the benchmark at the requested size', async () => {
+    mockInvoke.mockResolvedValueOnce(testReport)
+    const result = await runPipelineBenchmark(1920, 1080, 50)
+    expect(mockInvoke).toHaveBeenCalledWith('run_pipeline_benchmark', {
+      width: 1920,
+      height: 1080,
+      iterations: 50,
+    })
+    expect(result).toEqual(testReport)
+  })
+
+  it('propagates validation errors', async () => {
+    mockInvoke.mockRejectedValueOnce(
+      new Error('Benchmark iterations must be between 1 and 1000, got 0'),
+    )
+    await expect(runPipelineBenchmark(640, 480, 0)).rejects.toThrow('between 1 and 1000')
+  })
+})
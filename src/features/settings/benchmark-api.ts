@@ -0,0 +1,14 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { BenchmarkReport } from '../../types/benchmark'
+
+/**
+ * Time each stage of the frame pipeline on synthesised frames of the given
+ * size. Runs without a camera and stops early after a few seconds.
+ */
+export async function runPipelineBenchmark(
+  width: number,
+  height: number,
+  iterations: number,
+): Promise<BenchmarkReport> {
+  return invoke<BenchmarkReport>('run_pipeline_benchmark', { width, height, iterations })
+}
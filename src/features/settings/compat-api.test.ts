@@ -0,0 +1,62 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { CompatReport } from '../../types/compat'
+import { generateCompatReport } from './compat-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+const testReport: CompatReport = {
+  reportVersion: 1,
+  appVersion: '1.2.3',
+  os: 'windows',
+  cameras: [
+    {
+      label: 'camera-1',
+      vendorProduct: '046d:085e',
+      name: 'Logitech BRIO',
+      kind: 'primary',
+      formats: {
+        status: 'collected',
+        data: [{ width: 1920, height: 1080, fps: 30, pixelFormat: 'MJPG' }],
+      },
+      negotiatedSubtype: { status: 'notCollected', reason: 'not recorded' },
+      frames: { status: 'notCollected', reason: 'no preview running for this camera' },
+      controls: { status: 'collected', data: [] },
+      functionalControls: { status: 'notCollected', reason: 'not recorded' },
+      capabilities: {
+        status: 'collected',
+        data: {
+          supportsPreview: true,
+          supportsFormats: true,
+          supportsAutoToggle: true,
+          supportsHotplug: true,
+          supportsRelativePtz: false,
+        },
+      },
+    },
+  ],
+}
+
+describe('compatibility report API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('generates a report at the chosen path', async () => {
+    mockInvoke.mockResolvedValueOnce(testReport)
+    const result = await generateCompatReport('C:\\Users\\me\\compat.json')
+    expect(mockInvoke).toHaveBeenCalledWith('generate_compat_report', {
+      path: 'C:\\Users\\me\\compat.json',
+    })
+    expect(result).toEqual(testReport)
+  })
+
+  it('propagates write errors', async () => {
+    mockInvoke.mockRejectedValueOnce(new Error('Failed to write the report: access denied'))
+    await expect(generateCompatReport('/nope/compat.json')).rejects.toThrow('access denied')
+  })
+})
@@ -0,0 +1,10 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { CompatReport } from '../../types/compat'
+
+/**
+ * Generate an anonymous compatibility report for the connected cameras and
+ * write it as JSON to `path`. Returns the report so it can be reviewed.
+ */
+export async function generateCompatReport(path: string): Promise<CompatReport> {
+  return invoke<CompatReport>('generate_compat_report', { path })
+}
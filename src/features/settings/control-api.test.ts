@@ -0,0 +1,45 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import { getControlApi, regenerateControlApiToken, setControlApiEnabled } from './control-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+const running = { enabled: true, running: true, port: 47820, token: 'abc123' }
+
+describe('control API settings', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('gets the status', async () => {
+    mockInvoke.mockResolvedValueOnce({ enabled: false, running: false, port: 47820, token: null })
+    const result = await getControlApi()
+    expect(mockInvoke).toHaveBeenCalledWith('get_control_api')
+    expect(result.token).toBeNull()
+  })
+
+  it('enables the API and returns the token', async () => {
+    mockInvoke.mockResolvedValueOnce(running)
+    const result = await setControlApiEnabled(true)
+    expect(mockInvoke).toHaveBeenCalledWith('set_control_api_enabled', { enabled: true })
+    expect(result).toEqual(running)
+  })
+
+  it('propagates start failures', async () => {
+    mockInvoke.mockRejectedValueOnce(
+      new Error("Couldn't listen on 127.0.0.1:47820: address in use"),
+    )
+    await expect(setControlApiEnabled(true)).rejects.toThrow('address in use')
+  })
+
+  it('regenerates the token', async () => {
+    mockInvoke.mockResolvedValueOnce({ ...running, token: 'def456' })
+    const result = await regenerateControlApiToken()
+    expect(mockInvoke).toHaveBeenCalledWith('regenerate_control_api_token')
+    expect(result.token).toBe('def456')
+  })
+})
@@ -0,0 +1,21 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { ControlApiStatus } from '../../types/control-api'
+
+/** Whether the localhost control API is enabled and running, its port and token. */
+export async function getControlApi(): Promise<ControlApiStatus> {
+  return invoke<ControlApiStatus>('get_control_api')
+}
+
+/**
+ * Enable or disable the localhost control API. Enabling it the first time
+ * generates its token. Rejects if the server can't start, though the
+ * preference is still saved.
+ */
+export async function setControlApiEnabled(enabled: boolean): Promise<ControlApiStatus> {
+  return invoke<ControlApiStatus>('set_control_api_enabled', { enabled })
+}
+
+/** Replace the token; the old one stops working straight away. */
+export async function regenerateControlApiToken(): Promise<ControlApiStatus> {
+  return invoke<ControlApiStatus>('regenerate_control_api_token')
+}
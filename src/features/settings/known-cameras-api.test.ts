@@ -0,0 +1,51 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { KnownCamera } from '../../types/camera'
+import {
+  forgetCamera,
+  getPruneAfterDays,
+  listKnownCameras,
+  setPruneAfterDays,
+} from './known-cameras-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+describe('known cameras API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('lists known cameras', async () => {
+    const cameras: KnownCamera[] = [
+      { deviceId: 'cam-1', name: 'Brio', connected: true, lastSeen: 1792324800, savedControls: 4 },
+      { deviceId: 'cam-2', name: 'C920', connected: false, lastSeen: null, savedControls: 1 },
+    ]
+    mockInvoke.mockResolvedValueOnce(cameras)
+    expect(await listKnownCameras()).toEqual(cameras)
+    expect(mockInvoke).toHaveBeenCalledWith('list_known_cameras')
+  })
+
+  it('forgets a camera, unforced by default', async () => {
+    mockInvoke.mockResolvedValue(true)
+    expect(await forgetCamera('cam-2')).toBe(true)
+    expect(mockInvoke).toHaveBeenCalledWith('forget_camera', { deviceId: 'cam-2', force: false })
+    await forgetCamera('cam-1', true)
+    expect(mockInvoke).toHaveBeenCalledWith('forget_camera', { deviceId: 'cam-1', force: true })
+  })
+
+  it('gets and sets the prune age', async () => {
+    mockInvoke.mockResolvedValueOnce(90)
+    expect(await getPruneAfterDays()).toBe(90)
+    expect(mockInvoke).toHaveBeenCalledWith('get_prune_after_days')
+
+    mockInvoke.mockResolvedValue(undefined)
+    await setPruneAfterDays(180)
+    expect(mockInvoke).toHaveBeenCalledWith('set_prune_after_days', { days: 180 })
+    await setPruneAfterDays(null)
+    expect(mockInvoke).toHaveBeenCalledWith('set_prune_after_days', { days: null })
+  })
+})
@@ -0,0 +1,25 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { KnownCamera } from '../../types/camera'
+
+/** Every camera with saved settings, connected ones first. */
+export async function listKnownCameras(): Promise<KnownCamera[]> {
+  return invoke<KnownCamera[]>('list_known_cameras')
+}
+
+/**
+ * Forget a camera's saved settings. Refused for a connected camera unless
+ * `force` is set. Resolves to whether there was anything to forget.
+ */
+export async function forgetCamera(deviceId: string, force = false): Promise<boolean> {
+  return invoke<boolean>('forget_camera', { deviceId, force })
+}
+
+/** Days a camera can go unseen before it's pruned at startup, or `null` if pruning is off. */
+export async function getPruneAfterDays(): Promise<number | null> {
+  return invoke<number | null>('get_prune_after_days')
+}
+
+/** Turn pruning of unseen cameras on with a number of days, or off with `null`. */
+export async function setPruneAfterDays(days: number | null): Promise<void> {
+  return invoke('set_prune_after_days', { days })
+}
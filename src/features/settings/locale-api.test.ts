@@ -0,0 +1,29 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import { getLocale, setLocale } from './locale-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+describe('locale API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('gets the active locale', async () => {
+    mockInvoke.mockResolvedValueOnce('nl')
+    const result = await getLocale()
+    expect(mockInvoke).toHaveBeenCalledWith('get_locale')
+    expect(result).toBe('nl')
+  })
+
+  it('sends the locale and returns the one in use', async () => {
+    mockInvoke.mockResolvedValueOnce('en')
+    const result = await setLocale('fr-FR')
+    expect(mockInvoke).toHaveBeenCalledWith('set_locale', { locale: 'fr-FR' })
+    expect(result).toBe('en')
+  })
+})
@@ -0,0 +1,15 @@
+import { invoke } from '@tauri-apps/api/core'
+
+/** Code of the locale backend-produced text is in, such as `en`. */
+export async function getLocale(): Promise<string> {
+  return invoke<string>('get_locale')
+}
+
+/**
+ * Switch the language of backend-produced text (control names, errors, the
+ * tray menu). Languages the backend has no catalogue for fall back to
+ * English; resolves to the code of the locale now in use.
+ */
+export async function setLocale(locale: string): Promise<string> {
+  return invoke<string>('set_locale', { locale })
+}
@@ -0,0 +1,78 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import {
+  cancelMidiLearn,
+  listMidiInputs,
+  removeMidiMapping,
+  setMidiInput,
+  setMidiMapping,
+  startMidiLearn,
+} from './midi'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+const mapping = {
+  midiChannel: 0,
+  ccNumber: 74,
+  deviceId: 'cam-1',
+  controlId: 'zoom',
+  min: 100,
+  max: 500,
+}
+
+describe('MIDI settings', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('lists inputs', async () => {
+    mockInvoke.mockResolvedValueOnce(['nanoKONTROL2'])
+    expect(await listMidiInputs()).toEqual(['nanoKONTROL2'])
+    expect(mockInvoke).toHaveBeenCalledWith('list_midi_inputs')
+  })
+
+  it('propagates builds without MIDI', async () => {
+    mockInvoke.mockRejectedValueOnce(new Error("MIDI support isn't built into this version"))
+    await expect(listMidiInputs()).rejects.toThrow("isn't built")
+  })
+
+  it('clears the input with null', async () => {
+    mockInvoke.mockResolvedValueOnce({
+      available: true,
+      input: null,
+      connected: false,
+      learning: null,
+      mappings: [],
+    })
+    await setMidiInput(null)
+    expect(mockInvoke).toHaveBeenCalledWith('set_midi_input', { name: null })
+  })
+
+  it('saves and removes mappings', async () => {
+    mockInvoke.mockResolvedValueOnce([mapping]).mockResolvedValueOnce([])
+    expect(await setMidiMapping(mapping)).toEqual([mapping])
+    expect(mockInvoke).toHaveBeenCalledWith('set_midi_mapping', { mapping })
+    expect(await removeMidiMapping(0, 74)).toEqual([])
+    expect(mockInvoke).toHaveBeenCalledWith('remove_midi_mapping', {
+      midiChannel: 0,
+      ccNumber: 74,
+    })
+  })
+
+  it('starts and cancels learn mode', async () => {
+    mockInvoke.mockResolvedValueOnce({ deviceId: 'cam-1', controlId: 'zoom', min: 100, max: 500 })
+    const target = await startMidiLearn('cam-1', 'zoom')
+    expect(mockInvoke).toHaveBeenCalledWith('start_midi_learn', {
+      deviceId: 'cam-1',
+      controlId: 'zoom',
+    })
+    expect(target.max).toBe(500)
+
+    mockInvoke.mockResolvedValueOnce(true)
+    expect(await cancelMidiLearn()).toBe(true)
+  })
+})
@@ -0,0 +1,49 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { MidiLearnTarget, MidiMapping, MidiStatus } from '../../types/midi'
+
+/** Names of the MIDI inputs currently available. */
+export async function listMidiInputs(): Promise<string[]> {
+  return invoke<string[]>('list_midi_inputs')
+}
+
+/** The chosen MIDI input, whether it's connected, learn mode and the mapping table. */
+export async function getMidi(): Promise<MidiStatus> {
+  return invoke<MidiStatus>('get_midi')
+}
+
+/**
+ * Choose the MIDI input to listen to, or stop listening with null. Rejects if
+ * the input can't be opened, though the choice is still saved.
+ */
+export async function setMidiInput(name: string | null): Promise<MidiStatus> {
+  return invoke<MidiStatus>('set_midi_input', { name })
+}
+
+/** Map a knob or fader, replacing any mapping on the same channel and CC. */
+export async function setMidiMapping(mapping: MidiMapping): Promise<MidiMapping[]> {
+  return invoke<MidiMapping[]>('set_midi_mapping', { mapping })
+}
+
+/** Remove the mapping on a channel and CC. */
+export async function removeMidiMapping(
+  midiChannel: number,
+  ccNumber: number,
+): Promise<MidiMapping[]> {
+  return invoke<MidiMapping[]>('remove_midi_mapping', { midiChannel, ccNumber })
+}
+
+/**
+ * Map the next knob or fader moved to a control over its full range. The
+ * saved mapping arrives as a `midi-learned` event.
+ */
+export async function startMidiLearn(
+  deviceId: string,
+  controlId: string,
+): Promise<MidiLearnTarget> {
+  return invoke<MidiLearnTarget>('start_midi_learn', { deviceId, controlId })
+}
+
+/** Leave learn mode; resolves to whether it was waiting. */
+export async function cancelMidiLearn(): Promise<boolean> {
+  return invoke<boolean>('cancel_midi_learn')
+}
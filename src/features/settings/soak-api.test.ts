@@ -0,0 +1,40 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { SoakReport } from '../../types/soak'
+import { runSoakTest } from './soak-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+const testReport: SoakReport = {
+  seed: 42,
+  iterations: 500,
+  completed: 211,
+  operations: { startPreview: 60, stopPreview: 45, unplug: 14, replug: 13, setControl: 79 },
+  violation: {
+    iteration: 212,
+    operation: { kind: 'unplug', device: 0 },
+    message: '1 preview sessions, expected 0',
+  },
+}
+
+describe('soak test API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('runs the soak test with the given seed', async () => {
+    mockInvoke.mockResolvedValueOnce(testReport)
+    const result = await runSoakTest(500, 42)
+    expect(mockInvoke).toHaveBeenCalledWith('run_soak_test', { iterations: 500, seed: 42 })
+    expect(result).toEqual(testReport)
+  })
+
+  it('propagates refusals', async () => {
+    mockInvoke.mockRejectedValueOnce(new Error('a soak test is already running'))
+    await expect(runSoakTest(10, 1)).rejects.toThrow('already running')
+  })
+})
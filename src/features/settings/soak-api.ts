@@ -0,0 +1,11 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { SoakReport } from '../../types/soak'
+
+/**
+ * Churn previews, presets, controls, hotplug and thumbnail sizes on the
+ * dummy camera, checking for leaks and stuck locks after each operation.
+ * Development builds only. Runs with the same seed pick the same operations.
+ */
+export async function runSoakTest(iterations: number, seed: number): Promise<SoakReport> {
+  return invoke<SoakReport>('run_soak_test', { iterations, seed })
+}
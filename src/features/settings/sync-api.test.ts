@@ -0,0 +1,60 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import type { DeviceSync } from '../../types/camera'
+import { getSyncDir, onSettingsSynced, setSyncDir } from './sync-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+const { listen } = await import('@tauri-apps/api/event')
+const mockListen = vi.mocked(listen)
+
+describe('sync API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+    mockListen.mockReset()
+  })
+
+  it('gets the sync folder', async () => {
+    mockInvoke.mockResolvedValueOnce('/shared/cameras')
+    const result = await getSyncDir()
+    expect(mockInvoke).toHaveBeenCalledWith('get_sync_dir')
+    expect(result).toBe('/shared/cameras')
+  })
+
+  it('sets and clears the sync folder', async () => {
+    mockInvoke.mockResolvedValue(undefined)
+    await setSyncDir('/shared/cameras')
+    expect(mockInvoke).toHaveBeenCalledWith('set_sync_dir', { dir: '/shared/cameras' })
+    await setSyncDir(null)
+    expect(mockInvoke).toHaveBeenCalledWith('set_sync_dir', { dir: null })
+  })
+
+  it('forwards settings-synced payloads', async () => {
+    const payload: DeviceSync[] = [
+      {
+        deviceId: 'cam-1',
+        cameraName: 'Brio',
+        inbound: ['zoom'],
+        conflicts: [],
+        written: false,
+      },
+    ]
+    mockListen.mockImplementation(((_event: string, handler: (event: unknown) => void) => {
+      handler({ payload })
+      return Promise.resolve(vi.fn())
+    }) as unknown as typeof listen)
+    const callback = vi.fn()
+
+    await onSettingsSynced(callback)
+
+    expect(mockListen).toHaveBeenCalledWith('settings-synced', expect.any(Function))
+    expect(callback).toHaveBeenCalledWith(payload)
+  })
+})
@@ -0,0 +1,28 @@
+import { invoke } from '@tauri-apps/api/core'
+import { type UnlistenFn, listen } from '@tauri-apps/api/event'
+import type { DeviceSync } from '../../types/camera'
+
+/** Folder settings are synced through, or `null` if sync is off. */
+export async function getSyncDir(): Promise<string | null> {
+  return invoke<string | null>('get_sync_dir')
+}
+
+/**
+ * Sync settings with other machines through a shared folder, created if it
+ * doesn't exist, or turn sync off with `null`.
+ */
+export async function setSyncDir(dir: string | null): Promise<void> {
+  return invoke('set_sync_dir', { dir })
+}
+
+/**
+ * Subscribe to settings changed on another machine and applied here, and to
+ * sync conflicts. Returns an unlisten function.
+ */
+export async function onSettingsSynced(
+  callback: (payload: DeviceSync[]) => void,
+): Promise<UnlistenFn> {
+  return listen<DeviceSync[]>('settings-synced', (event) => {
+    callback(event.payload)
+  })
+}
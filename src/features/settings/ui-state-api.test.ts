@@ -0,0 +1,38 @@
+import { beforeEach, describe, expect, it, vi } from 'vitest'
+import { getUiState, setUiState } from './ui-state-api'
+
+vi.mock('@tauri-apps/api/core', () => ({
+  invoke: vi.fn(),
+}))
+
+const { invoke } = await import('@tauri-apps/api/core')
+const mockInvoke = vi.mocked(invoke)
+
+describe('UI state API', () => {
+  beforeEach(() => {
+    mockInvoke.mockReset()
+  })
+
+  it('gets the persisted UI state', async () => {
+    mockInvoke.mockResolvedValueOnce({ lastSelectedCamera: 'dev-1', sidebar: { width: 280 } })
+    const result = await getUiState()
+    expect(mockInvoke).toHaveBeenCalledWith('get_ui_state')
+    expect(result.lastSelectedCamera).toBe('dev-1')
+  })
+
+  it('sends the patch and returns the merged state', async () => {
+    mockInvoke.mockResolvedValueOnce({ sidebar: { width: 300 } })
+    const result = await setUiState({ sidebar: { width: 300 }, lastSelectedCamera: null })
+    expect(mockInvoke).toHaveBeenCalledWith('set_ui_state', {
+      patch: { sidebar: { width: 300 }, lastSelectedCamera: null },
+    })
+    expect(result).toEqual({ sidebar: { width: 300 } })
+  })
+
+  it('propagates size limit errors', async () => {
+    mockInvoke.mockRejectedValueOnce(
+      new Error('UI state would be 70000 bytes, over the 65536 byte limit'),
+    )
+    await expect(setUiState({ notes: 'x' })).rejects.toThrow('byte limit')
+  })
+})
@@ -0,0 +1,15 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { UiState, UiStatePatch } from '../../types/ui-state'
+
+/** The persisted UI state, or an empty object on first run. */
+export async function getUiState(): Promise<UiState> {
+  return invoke<UiState>('get_ui_state')
+}
+
+/**
+ * Merge a patch into the persisted UI state and return the merged state.
+ * Saving is debounced on the backend, so frequent patches are cheap.
+ */
+export async function setUiState(patch: UiStatePatch): Promise<UiState> {
+  return invoke<UiState>('set_ui_state', { patch })
+}
@@ -0,0 +1,32 @@
+/** Timings of one pipeline stage — matches Rust `StageReport`. */
+export interface BenchmarkStage {
+  /** Stage name, e.g. `nv12_to_rgb`, `buffer_push`, `downscale`, `jpeg_q75` or `base64`. */
+  stage: string
+  samples: number
+  meanUs: number
+  minUs: number
+  maxUs: number
+  /** Frames per second the stage alone could sustain, or null if it wasn't timed. */
+  maxFps: number | null
+}
+
+/** A capture format's path to a base64 JPEG — matches Rust `PipelineReport`. */
+export interface BenchmarkPipeline {
+  format: string
+  stages: string[]
+  meanUs: number
+  maxFps: number | null
+}
+
+/** Result of `run_pipeline_benchmark`. */
+export interface BenchmarkReport {
+  width: number
+  height: number
+  iterationsRequested: number
+  /** Iterations every stage completed. */
+  iterationsCompleted: number
+  /** Whether the run stopped early at its time limit. */
+  timedOut: boolean
+  stages: BenchmarkStage[]
+  pipelines: BenchmarkPipeline[]
+}
@@ -1,18 +1,58 @@
+import type { AppError } from './error'
+
+/** Which stream a device filter carries on a multi-filter camera. */
+export type DeviceKind = 'primary' | 'infrared' | 'depth'
+
 /** Camera device as serialised from the Rust backend (camelCase). */
 export interface CameraDevice {
   id: string
   name: string
   devicePath: string
   isConnected: boolean
+  kind: DeviceKind
+  /** For IR/depth filters, the colour device on the same physical camera. */
+  primaryId?: string
+  /**
+   * Set when another backend's device for the same physical camera is
+   * preferred: that device's ID. Only listed when suppressed devices are shown.
+   */
+  suppressedBy?: string
 }
 
-/** Hot-plug event emitted by the `camera-hotplug` Tauri event. */
+/**
+ * Hot-plug event emitted by the `camera-hotplug` Tauri event, and again as
+ * `camera-connected` or `camera-disconnected` once the backend has dealt with
+ * it (restoring a connected camera's saved settings first).
+ */
 export interface HotplugEvent {
   type: 'connected' | 'disconnected'
   id: string
   name?: string
   devicePath?: string
   isConnected?: boolean
+  kind?: DeviceKind
+  primaryId?: string
+}
+
+/**
+ * How far startup discovery has got: waiting for backends, every backend has
+ * answered or been skipped (skipped ones may still add cameras), or done.
+ */
+export type StartupPhase = 'enumerating' | 'ready' | 'complete'
+
+/** Payload of the `startup-progress` Tauri event. */
+export interface StartupProgress {
+  phase: StartupPhase
+  devicesFound: number
+  /** Backends that haven't answered yet. */
+  pendingBackends: number
+}
+
+/** A saved camera now reported under a new name, from the `camera-renamed` event. */
+export interface CameraRename {
+  deviceId: string
+  oldName: string
+  newName: string
 }
 
 /** Type of UI control widget — matches Rust ControlType. */
@@ -38,21 +78,459 @@ export interface ControlDescriptor {
   max: number | null
   step: number | null
   default: number | null
+  /** Whether the control powers on in auto mode, so reset re-enables auto. */
+  defaultAuto: boolean
   current: number
   flags: ControlFlags
   supported: boolean
 }
 
+/** Controls returned by `get_camera_controls`. */
+export interface CameraControls {
+  controls: ControlDescriptor[]
+  /** Served from the last-known cache; `controls-refreshed` follows with the live list. */
+  cached: boolean
+}
+
+/** Optional features a camera's backend supports — matches Rust `BackendCapabilities`. */
+export interface BackendCapabilities {
+  supportsPreview: boolean
+  /** Whether formats are the camera's real ones rather than a placeholder. */
+  supportsFormats: boolean
+  supportsAutoToggle: boolean
+  supportsHotplug: boolean
+  supportsRelativePtz: boolean
+}
+
+/** Payload emitted by the `controls-refreshed` Tauri event. */
+export interface ControlsRefreshedPayload {
+  deviceId: string
+  controls: ControlDescriptor[]
+  /** Control IDs the cached list didn't have. */
+  added: string[]
+  /** Cached control IDs the camera no longer reports. */
+  removed: string[]
+  /** Whether anything differs from the cached list. */
+  changed: boolean
+}
+
+/** Per-signal contributions to a camera's default-camera score. */
+export interface ScoreBreakdown {
+  virtualCamera: number
+  sibling: number
+  controls: number
+  resolution: number
+  /** Zero when the camera wasn't probed. */
+  probe: number
+}
+
+/** A camera ranked by `suggest_default_camera`. */
+export interface CameraSuggestion {
+  deviceId: string
+  name: string
+  score: number
+  breakdown: ScoreBreakdown
+}
+
+/** Operations serialised per device by the backend's device queue. */
+export type DeviceOperationKind =
+  | 'format_change'
+  | 'preview_start'
+  | 'preview_stop'
+  | 'preview_restart'
+  | 'preset_apply'
+  | 'bracket_capture'
+  | 'zoom_change'
+  | 'frame_read'
+  | 'control_read'
+
+/** Payload emitted by the `device-operation` Tauri event. */
+export interface DeviceOperationProgress {
+  opId: number
+  deviceId: string
+  kind: DeviceOperationKind
+  /** 0 when the operation starts, 1 once it has finished. */
+  progress: number
+}
+
+/** Value written by a control change — matches Rust SnappedValue. */
+export interface SnappedValue {
+  value: number
+  /** The value differs from the one requested: it was out of range or between steps. */
+  snapped: boolean
+}
+
+/** A control's current value read back from the camera — matches Rust ControlValueResponse. */
+export interface ControlValueResponse {
+  value: number
+  isAutoEnabled: boolean
+  min: number | null
+  max: number | null
+}
+
 /** Result of resetting a single control to its hardware default. */
 export interface ResetResult {
   controlId: string
   value: number
+  /** Whether the control went back to auto mode rather than having `value` written. */
+  auto: boolean
+}
+
+/** Power-line frequency setting suggested from the OS region. */
+export interface PowerLineSuggestion {
+  /** Value for the `power_line_frequency` control: 0 off, 1 50 Hz, 2 60 Hz, 3 auto. */
+  value: number
+  label: string
+  /** ISO 3166 region the suggestion came from, or null when none was found. */
+  region: string | null
+}
+
+/** Clockwise rotation and mirroring applied to delivered frames. */
+export interface Orientation {
+  rotation: 0 | 90 | 180 | 270
+  mirror: boolean
+}
+
+/** What last set a saved control. */
+export type ControlSource = 'manual' | 'restore' | { preset: string }
+
+/** A saved control: its value, whether it was left in auto mode, and where it came from. */
+export interface SavedControl {
+  value: number
+  auto: boolean
+  /** Omitted for manual changes. */
+  source?: ControlSource
+  /** Unix time (seconds) the value was saved. Omitted for older values. */
+  changed_at?: number
+}
+
+/** A control whose saved value differs from the preset last applied. */
+export interface ControlDrift {
+  controlId: string
+  presetValue: number
+  /** Null if the control was never saved. */
+  current: SavedControl | null
+}
+
+/** A named set of control values that can be applied to a camera. */
+export interface Preset {
+  name: string
+  controls: Record<string, number>
+  normalised: boolean
+  exposureSeconds?: number
+  focus?: number
+}
+
+/** A saved preset with the ID it's stored under. */
+export interface SavedPreset extends Preset {
+  id: string
+}
+
+/** What applying a preset did. Both lists are sorted by control id. */
+export interface PresetApplyResult {
+  applied: string[]
+  /** The preset's controls the camera doesn't have or refused. */
+  skipped: string[]
+}
+
+/** Day of the week a schedule rule runs on. */
+export type Weekday =
+  | 'monday'
+  | 'tuesday'
+  | 'wednesday'
+  | 'thursday'
+  | 'friday'
+  | 'saturday'
+  | 'sunday'
+
+/** Applies a preset at a local time of day, on the listed days or every day. */
+export interface ScheduleRule {
+  presetId: string
+  /** Local time as `HH:MM`. */
+  startTime: string
+  /** Omitted to run every day. */
+  daysOfWeek?: Weekday[]
+}
+
+/** A key that nudges a control while a preview has focus — matches Rust KeyBinding. */
+export interface KeyBinding {
+  /** `KeyboardEvent.key` of the key, e.g. `ArrowLeft` or `+`. */
+  key: string
+  controlId: string
+  /** Nudge towards the control's maximum rather than its minimum. */
+  up: boolean
+}
+
+/** Payload emitted by the `control-nudged` Tauri event. */
+export interface ControlNudged {
+  deviceId: string
+  controlId: string
+  /** Value written, after clamping to the control's range. */
+  value: number
+}
+
+/** Payload emitted by the `schedule-applied` Tauri event. */
+export interface ScheduleApplied {
+  deviceId: string
+  presetId: string
+  /** Position of the rule in the camera's schedule. */
+  ruleIndex: number
+  controlsWritten: number
+}
+
+/** Holds an auto-managed control at or below a ceiling — matches Rust ControlGuard. */
+export interface ControlGuard {
+  controlId: string
+  maxValue: number
+  /** Seconds at the ceiling before auto mode is handed back; omitted to stay manual. */
+  cooldownSecs?: number
+}
+
+/** Payload emitted by the `guard-triggered` Tauri event. */
+export interface GuardTriggered {
+  deviceId: string
+  controlId: string
+  /** Value auto mode had reached. */
+  before: number
+  /** Ceiling the control was set to. */
+  after: number
+}
+
+/** One control's synced state; `saved` is omitted once it was deleted. */
+export interface SyncedControl {
+  revision: number
+  machineId: string
+  saved?: SavedControl
+}
+
+/** Two machines changed a control at the same time; `kept` won. */
+export interface SyncConflict {
+  controlId: string
+  kept: SyncedControl
+  discarded: SyncedControl
+}
+
+/** One camera in the payload of the `settings-synced` Tauri event. */
+export interface DeviceSync {
+  deviceId: string
+  cameraName: string
+  /** Controls changed on another machine and applied here. */
+  inbound: string[]
+  conflicts: SyncConflict[]
+  /** Whether this machine wrote the camera's file in the sync folder. */
+  written: boolean
+}
+
+/** One step of a scene on a camera. */
+export type SceneAction =
+  | { type: 'applyPreset'; presetId: string }
+  /** Native values by control id, written in id order. */
+  | { type: 'setControls'; controls: Record<string, number> }
+  /** Run the preview at full resolution, starting it if needed. */
+  | { type: 'startPreview' }
+  | { type: 'stopPreview' }
+  /** Drop the preview to thumbnails only, keeping the camera open. */
+  | { type: 'pausePreview' }
+
+/** What a scene does to one camera. */
+export interface SceneCamera {
+  deviceId: string
+  /** Run in order; the first failure skips the rest. */
+  actions: SceneAction[]
+}
+
+/** A named set of per-camera actions, activated together. */
+export interface Scene {
+  name: string
+  cameras: SceneCamera[]
+}
+
+/** What happened to one camera when a scene was activated. */
+export interface CameraOutcome {
+  deviceId: string
+  /** Actions that succeeded, counted from the start of the list. */
+  completed: number
+  total: number
+  /** Why the action after the completed ones failed; the rest were skipped. */
+  error?: string
+}
+
+/** Result of `activate_scene`, also emitted as `scene-activated`. */
+export interface SceneActivation {
+  scene: string
+  status: 'succeeded' | 'partial' | 'failed'
+  /** In the scene's camera order. */
+  cameras: CameraOutcome[]
 }
 
 /** Saved camera settings as stored by the Rust backend. */
 export interface CameraSettings {
   name: string
-  controls: Record<string, number>
+  controls: Record<string, SavedControl>
+  /** Omitted when the camera is unrotated and unmirrored. */
+  orientation?: Orientation
+  /** Omitted when left at the defaults. */
+  jpeg_quality?: QualityProfile
+  /** Omitted when sharpening and denoise are both off. */
+  post_processing?: PostProcessing
+  /** Processor chains by output. Omitted when no output has one. */
+  output_processors?: Partial<Record<OutputTarget, OutputProcessor[]>>
+  /** Video source picked on a multi-input device. Omitted when none has been picked. */
+  video_source?: { index: number; name: string }
+  /** How YUV frames are decoded, overriding detection. Omitted when detected. */
+  colour_space?: ColourSpace
+  /** Show a "no signal" card while the camera has failed or stalled. Omitted when off. */
+  placeholder_on_error?: boolean
+  /** Preview size and rate last chosen, reused by auto-started previews. Omitted until chosen. */
+  preview_format?: { width: number; height: number; fps: number }
+  /** Unix time (seconds) the camera was last enumerated. Omitted until recorded. */
+  last_seen?: number
+  /** ID of the preset last applied. */
+  preset?: string
+}
+
+/** A camera with saved settings, as listed by `list_known_cameras`. */
+export interface KnownCamera {
+  deviceId: string
+  name: string
+  connected: boolean
+  /** Unix time (seconds) the camera was last enumerated, if recorded. */
+  lastSeen: number | null
+  savedControls: number
+}
+
+/** Per-device JPEG quality settings for frames compressed on demand. */
+export interface QualityProfile {
+  /** Adjust quality to hold the encode budget; when false, `quality` is pinned. */
+  adaptive: boolean
+  quality: number
+  minQuality: number
+  maxQuality: number
+  budgetMs: number
+}
+
+/** Software sharpening and denoise applied to a camera's preview frames. */
+export interface PostProcessing {
+  /** Unsharp-mask strength, 0 (off) to 100. */
+  sharpen: number
+  /** Box-blur denoise strength, 0 (off) to 100. */
+  denoise: number
+}
+
+/** Where rendered frames go — matches Rust `OutputTarget`. */
+export type OutputTarget = 'preview' | 'snapshot' | 'recording' | 'stream'
+
+/** Corner of the frame a watermark sits in. */
+export type WatermarkCorner = 'topLeft' | 'topRight' | 'bottomLeft' | 'bottomRight'
+
+/** A text watermark drawn over a semi-transparent box. */
+export interface Watermark {
+  /** Text to draw; `{camera}`, `{date}`, `{time}` and `{timestamp}` are expanded. */
+  template: string
+  corner: WatermarkCorner
+  /** Size of a font pixel in frame pixels, 1 to 8. */
+  scale: number
+  /** Opacity of the box behind the text, 0 (none) to 100 (solid). */
+  backgroundOpacity: number
+}
+
+/** One step of an output's processor chain. */
+export type OutputProcessor = { kind: 'watermark' } & Watermark
+
+/** What kind of input a video source is — matches Rust `SourceKind`. */
+export type VideoSourceType =
+  | 'pin'
+  | 'tuner'
+  | 'composite'
+  | 'sVideo'
+  | 'rgb'
+  | 'component'
+  | 'sdi'
+  | 'digital'
+  | 'usb'
+  | 'other'
+
+/** One input of a multi-input device, as listed by `get_video_sources`. */
+export interface VideoSource {
+  index: number
+  /** E.g. "SDI 1", "HDMI/DVI", or the driver's name for a capture pin. */
+  name: string
+  type: VideoSourceType
+}
+
+/** How a camera's YUV frames are decoded — matches Rust ColourSpace. */
+export interface ColourSpace {
+  /** BT.601 for SD video, BT.709 for HD. */
+  matrix: 'bt601' | 'bt709'
+  /** Limited is 16–235 luma, as video cameras send; full is 0–255. */
+  range: 'limited' | 'full'
+}
+
+/** A region of the frame, in normalised coordinates from (0, 0) top left. */
+export interface CropRect {
+  x: number
+  y: number
+  width: number
+  height: number
+}
+
+/** How a combined zoom is split between the optical zoom and a digital crop — matches Rust ZoomSplit. */
+export interface ZoomSplit {
+  /** Magnification requested. */
+  factor: number
+  /** Value written to the Zoom control; null when the camera has no zoom. */
+  hardwareValue: number | null
+  /** Magnification from the optical zoom. */
+  hardwareFactor: number
+  /** Magnification from the digital crop. */
+  digitalFactor: number
+  /** Region of the optically zoomed frame kept by the crop. */
+  crop: CropRect
+}
+
+/**
+ * Payload emitted by the `preview-error` Tauri event. The context fields are
+ * for bug reports and are omitted when unknown.
+ */
+export interface PreviewErrorPayload {
+  deviceId: string
+  error: AppError
+  framesDelivered?: number
+  sessionUptimeMs?: number
+  /** Omitted before the first frame. */
+  lastFrameAgeMs?: number
+  /** Resolution frames were delivered at, e.g. `1920x1080`. */
+  format?: string
+  /** 1 for the session's first error, 2 for the next, and so on. */
+  errorOrdinal?: number
+}
+
+/**
+ * Payload emitted by the `preview-started` Tauri event once a capture graph
+ * is connected: the resolution and pixel format the camera actually
+ * delivers, which may differ from the ones requested.
+ */
+export interface PreviewStartedPayload {
+  deviceId: string
+  width: number
+  height: number
+  /** FourCC such as `MJPG`, or hex digits for subtypes without one. */
+  pixelFormat: string
+}
+
+/**
+ * Payload emitted by the `preview-recovering` Tauri event when a session's
+ * capture graph failed and is about to be rebuilt. `preview-error` follows
+ * only if the last attempt fails too.
+ */
+export interface PreviewRecoveringPayload {
+  deviceId: string
+  /** The attempt about to be made; the first retry is attempt 2. */
+  attempt: number
+  maxAttempts: number
+  /** Why the previous attempt failed. */
+  error: AppError
+  retryInMs: number
 }
 
 /** Payload emitted by the `settings-restored` Tauri event. */
@@ -61,3 +539,224 @@ export interface SettingsRestoredPayload {
   cameraName: string
   controlsApplied: number
 }
+
+/** A saved value restore had to adjust. */
+export interface Reconciliation {
+  controlId: string
+  stored: number
+  /** What was written instead, or null if the camera no longer has the control. */
+  applied: number | null
+  reason: 'outOfRange' | 'stepSnapped' | 'controlMissing'
+}
+
+/** Payload emitted by the `settings-reconciled` Tauri event. */
+export interface SettingsReconciledPayload {
+  deviceId: string
+  cameraName: string
+  adjustments: Reconciliation[]
+  /** Whether the saved values were updated to the applied ones. */
+  updated: boolean
+}
+
+/** Payload emitted by the `settings-degraded` Tauri event: a save loop keeps panicking. */
+export interface SettingsDegradedPayload {
+  /** Which store, `settings` or `ui_state`. */
+  store: string
+  /** Times its save loop has panicked. */
+  panics: number
+}
+
+/** Payload of the `preview-frame` event a `subscribe_preview` subscription emits. */
+export interface PreviewFramePayload {
+  deviceId: string
+  /** Sequence number of the frame; 0 for the "no signal" card. */
+  sequence: number
+  /** Base64-encoded JPEG, exactly as `get_frame` returns it. */
+  jpeg: string
+}
+
+/** A frame pushed over a `stream_frames` channel. */
+export interface StreamedFrame {
+  /** Base64-encoded JPEG, oriented like `get_frame`. */
+  jpeg: string
+  width: number
+  height: number
+  timestampUs: number
+  /** Frames this stream has dropped so far because it fell behind. */
+  dropped: number
+}
+
+/**
+ * Where a shared-memory frame export lives and how it's laid out. The header
+ * layout and buffer-flip protocol are documented in `preview/shm.rs`.
+ */
+export interface ShmLayout {
+  /** File mapping name to open, e.g. with `OpenFileMappingW`. */
+  name: string
+  /** Total size of the region in bytes. */
+  size: number
+  version: number
+  headerSize: number
+  bufferCount: number
+  bufferSize: number
+  width: number
+  height: number
+  stride: number
+  pixelFormat: 'rgb24'
+}
+
+/**
+ * Zebra mask from `get_exposure_mask`: which cells of the displayed frame,
+ * 4x4 source pixels each, are brighter than `threshold`.
+ */
+export interface ExposureMask {
+  /** Cells across the displayed frame. */
+  width: number
+  /** Cells down the displayed frame. */
+  height: number
+  threshold: number
+  /** Sequence of the frame the mask was computed from. */
+  sequence: number
+  /**
+   * Row-major run lengths, alternating between unset and set cells and
+   * starting with unset ones. Expand with `decodeMaskRuns`.
+   */
+  runs: number[]
+}
+
+/** Resources held by preview sessions, from `get_resource_usage`. */
+export interface ResourceUsage {
+  sessionCount: number
+  runningSessions: number
+  /** Most sessions that may exist at once; further starts are refused. */
+  maxSessions: number
+  /** Bytes of frames held in session frame buffers. */
+  frameBufferBytes: number
+  /** Bytes held by the frame and thumbnail JPEG caches. */
+  cacheBytes: number
+  /** Threads in the whole process, or null where the OS doesn't report it. */
+  processThreads: number | null
+  /** How saving settings is going. */
+  settings: SaveHealth
+  /** Sizes of the frames sent to the webview. */
+  framePayloads: PayloadSnapshot
+}
+
+/** Counters of frame responses sent, from `get_resource_usage`. */
+export interface PayloadSnapshot {
+  /** Whole-frame responses sent. */
+  responses: number
+  /** Bytes of base64 those responses carried. */
+  totalBytes: number
+  largestBytes: number
+  /** Responses whose quality was stepped down to fit the size budget. */
+  reduced: number
+  /** Responses still over the budget at the lowest quality. */
+  overBudget: number
+  /** Chunks served by `get_frame_chunked`. */
+  chunks: number
+}
+
+/** One camera's share of the resources, from `get_resource_usage_per_device`. */
+export interface DeviceResources {
+  /** CPU over the last minute as a share of one core; can pass 100. */
+  cpuPercent1m: number
+  /** CPU spent capturing since the session started. */
+  captureThreadTimeMs: number
+  /** CPU spent compressing since the session started. */
+  compressTimeMs: number
+  /** Bytes of frames held in the session's buffers. */
+  bufferBytes: number
+  /** Bytes of the device's cached frame and thumbnail. */
+  cacheBytes: number
+}
+
+/** Resources by device ID, plus their sum. */
+export interface ResourceUsagePerDevice {
+  devices: Record<string, DeviceResources>
+  totals: DeviceResources
+}
+
+/** Image format `get_frame` and `get_thumbnail` deliver; PNG is lossless, for checking focus. */
+export type FrameFormat = 'jpeg' | 'png'
+
+/** How a thumbnail fills its box: all of the frame inside it, the middle cropped to fill it, or scaled to it. */
+export type ThumbnailFit = 'contain' | 'cover' | 'stretch'
+
+/** Box in physical pixels and fit for `get_thumbnail`; a side left out is the configured one. */
+export interface ThumbnailOptions {
+  width?: number
+  height?: number
+  fit?: ThumbnailFit
+}
+
+/** Region of the oriented frame in pixels, as `get_frame` takes for `crop`. */
+export interface PixelRect {
+  x: number
+  y: number
+  width: number
+  height: number
+}
+
+/** One slice of a frame from `get_frame_chunked`. */
+export interface FrameChunk {
+  /** Frame the chunk belongs to; chunks of different frames must not be mixed. */
+  sequence: number
+  chunk: number
+  chunkCount: number
+  /** Base64 slice of the JPEG. */
+  data: string
+  last: boolean
+}
+
+/** Size and sequence number of a preview frame, from `get_frame_meta`. */
+export interface FrameMeta {
+  /** 0 for the "no signal" card. */
+  sequence: number
+  width: number
+  height: number
+  /** Size of the JPEG in bytes. */
+  byteLen: number
+}
+
+/** When a store was last written and what's waiting to be. */
+export interface SaveHealth {
+  /** Unix seconds of the last write since startup, or null if none yet. */
+  lastSaved: number | null
+  /** Changes made since the last successful write. */
+  unsavedChanges: number
+}
+
+/** Why a time-lapse ended. */
+export type TimelapseStopReason = 'requested' | 'completed' | 'disconnected' | 'failed'
+
+/** Payload of the `timelapse-progress` and `timelapse-stopped` Tauri events. */
+export interface TimelapseProgress {
+  deviceId: string
+  framesWritten: number
+  /** Ticks with no fresh frame, including ones the recorder was held up past. */
+  skippedTicks: number
+  /** Ticks skipped while the preview was stalled or restarting. */
+  pausedTicks: number
+  bytesWritten: number
+  lastFile?: string
+  /** Set once the time-lapse has ended. */
+  stopReason?: TimelapseStopReason
+  error?: string
+}
+
+/** The frame rates one pixel format offers at a resolution. */
+export interface PixelFormatRates {
+  pixelFormat: string
+  /** Distinct rates, descending; near-duplicates like 29.97 and 30 are merged. */
+  fps: number[]
+  minFps?: number
+  maxFps?: number
+}
+
+/** One resolution from `get_camera_formats_grouped`, largest first. */
+export interface ResolutionGroup {
+  width: number
+  height: number
+  formats: PixelFormatRates[]
+}
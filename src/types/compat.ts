@@ -0,0 +1,56 @@
+import type { BackendCapabilities } from './camera'
+
+/** A report section, or why it wasn't collected — matches Rust `Section`. */
+export type CompatSection<T> =
+  | { status: 'collected'; data: T }
+  | { status: 'notCollected'; reason: string }
+
+/** An advertised format. */
+export interface CompatFormat {
+  width: number
+  height: number
+  fps: number
+  pixelFormat: string
+}
+
+/** Frame delivery of a running preview. */
+export interface CompatFrames {
+  framesFlowed: boolean
+  frameCount: number
+  dropCount: number
+  dropRate: number
+  fps: number
+  panicCount: number
+}
+
+/** A control the camera exposes. */
+export interface CompatControl {
+  id: string
+  supported: boolean
+  readOnly: boolean
+  supportsAuto: boolean
+}
+
+/** Compatibility details for one camera, with serials and device paths redacted. */
+export interface CompatCamera {
+  /** `camera-1`, `camera-2`, … in enumeration order. */
+  label: string
+  /** USB `vvvv:pppp`, or null for devices without USB IDs. */
+  vendorProduct: string | null
+  name: string
+  kind: 'primary' | 'infrared' | 'depth'
+  formats: CompatSection<CompatFormat[]>
+  negotiatedSubtype: CompatSection<string>
+  frames: CompatSection<CompatFrames>
+  controls: CompatSection<CompatControl[]>
+  functionalControls: CompatSection<Record<string, boolean>>
+  capabilities: CompatSection<BackendCapabilities>
+}
+
+/** Anonymous hardware compatibility report — matches Rust `CompatReport`. */
+export interface CompatReport {
+  reportVersion: number
+  appVersion: string
+  os: string
+  cameras: CompatCamera[]
+}
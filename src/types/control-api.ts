@@ -0,0 +1,8 @@
+/** Localhost control API settings — matches Rust ControlApiStatus. */
+export interface ControlApiStatus {
+  enabled: boolean
+  running: boolean
+  port: number
+  /** Bearer token for requests; null until the API is first enabled. */
+  token: string | null
+}
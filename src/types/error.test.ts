@@ -0,0 +1,30 @@
+import { describe, expect, it } from 'vitest'
+import { errorMessage, hasErrorCode, isAppError } from './error'
+
+describe('AppError helpers', () => {
+  const busy = { code: 'device_busy', message: 'Camera is in use by another application' }
+
+  it('recognises command errors', () => {
+    expect(isAppError(busy)).toBe(true)
+    expect(isAppError({ ...busy, details: { cause: '0x800705AA' } })).toBe(true)
+    expect(isAppError('device busy')).toBe(false)
+    expect(isAppError(null)).toBe(false)
+    expect(isAppError(new Error('boom'))).toBe(false)
+  })
+
+  it('matches on the code', () => {
+    expect(hasErrorCode(busy, 'device_busy')).toBe(true)
+    expect(hasErrorCode(busy, 'access_denied')).toBe(false)
+    expect(hasErrorCode('device_busy', 'device_busy')).toBe(false)
+  })
+
+  it('shows the message of command errors and exceptions', () => {
+    expect(errorMessage(busy)).toBe('Camera is in use by another application')
+    expect(errorMessage(new Error('boom'), 'Reset failed')).toBe('boom')
+  })
+
+  it('falls back for anything else', () => {
+    expect(errorMessage('plain text')).toBe('plain text')
+    expect(errorMessage(undefined, 'Reset failed')).toBe('Reset failed')
+  })
+})
@@ -0,0 +1,61 @@
+/** Codes commands fail with — matches Rust `error::code`. */
+export type ErrorCode =
+  | 'device_not_found'
+  | 'device_busy'
+  | 'access_denied'
+  | 'device_failed'
+  | 'enumeration_failed'
+  | 'hotplug_failed'
+  | 'format_query_failed'
+  | 'format_unsupported'
+  | 'control_unavailable'
+  | 'control_rejected'
+  | 'unsupported'
+  | 'canon_sdk'
+  | 'canon_busy'
+  | 'canon_session_not_open'
+  | 'canon_disconnected'
+  | 'preview_not_running'
+  | 'preview_thumbnail_only'
+  | 'preview_failed'
+  | 'preview_limit'
+  | 'frame_unavailable'
+  | 'settings_io'
+  | 'not_found'
+  | 'invalid_argument'
+  | 'already_running'
+  | 'cancelled'
+  | 'integration_failed'
+  | 'internal'
+
+/**
+ * Error every command rejects with — matches Rust AppError. Branch on
+ * `code`; `message` is for display and may be reworded or localised.
+ */
+export interface AppError {
+  code: ErrorCode
+  message: string
+  /** Structured context, such as the underlying error `message` replaced. */
+  details?: unknown
+}
+
+/** Whether `err` is an error a command rejected with. */
+export function isAppError(err: unknown): err is AppError {
+  return (
+    typeof err === 'object' &&
+    err !== null &&
+    typeof (err as AppError).code === 'string' &&
+    typeof (err as AppError).message === 'string'
+  )
+}
+
+/** Whether `err` is an error a command rejected with `code`. */
+export function hasErrorCode(err: unknown, code: ErrorCode): boolean {
+  return isAppError(err) && err.code === code
+}
+
+/** Text to show for whatever a command or callback threw. */
+export function errorMessage(err: unknown, fallback?: string): string {
+  if (isAppError(err) || err instanceof Error) return err.message
+  return fallback ?? String(err)
+}
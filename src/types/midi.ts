@@ -0,0 +1,32 @@
+/** A knob or fader mapped to a camera control — matches Rust MidiMapping. */
+export interface MidiMapping {
+  /** MIDI channel, 0–15. */
+  midiChannel: number
+  /** Controller number, 0–127. */
+  ccNumber: number
+  deviceId: string
+  controlId: string
+  /** Control value at CC 0. */
+  min: number
+  /** Control value at CC 127; below `min` inverts the knob. */
+  max: number
+}
+
+/** The control learn mode is waiting to map — matches Rust LearnTarget. */
+export interface MidiLearnTarget {
+  deviceId: string
+  controlId: string
+  min: number
+  max: number
+}
+
+/** MIDI settings — matches Rust MidiStatus. */
+export interface MidiStatus {
+  /** Whether this build can open MIDI inputs. */
+  available: boolean
+  /** The chosen input, connected or not. */
+  input: string | null
+  connected: boolean
+  learning: MidiLearnTarget | null
+  mappings: MidiMapping[]
+}
@@ -0,0 +1,29 @@
+/** An operation of a soak run — matches Rust `SoakOp`. */
+export type SoakOperation =
+  | { kind: 'startPreview'; device: number }
+  | { kind: 'stopPreview'; device: number }
+  | { kind: 'applyPreset'; device: number }
+  | { kind: 'setControl'; device: number; percent: number }
+  | { kind: 'unplug'; device: number }
+  | { kind: 'replug'; device: number }
+  | { kind: 'resizeThumbnails'; width: number }
+
+/** The first broken invariant of a soak run — matches Rust `SoakViolation`. */
+export interface SoakViolation {
+  /** Iteration it showed after, from 1; 0 if broken before the first operation. */
+  iteration: number
+  operation: SoakOperation | null
+  message: string
+}
+
+/** Result of `run_soak_test`. */
+export interface SoakReport {
+  /** Seed to pass to replay the run. */
+  seed: number
+  iterations: number
+  /** Iterations whose operation was done and passed the checks. */
+  completed: number
+  /** Operations done, by kind. */
+  operations: Record<string, number>
+  violation: SoakViolation | null
+}
@@ -0,0 +1,23 @@
+/** A JSON value that can be stored in the persisted UI state. */
+export type UiStateValue =
+  | string
+  | number
+  | boolean
+  | null
+  | UiStateValue[]
+  | { [key: string]: UiStateValue }
+
+/**
+ * Persisted frontend UI state. The shape is owned by the frontend; the
+ * backend reads only `lastSelectedCamera`, as a default-camera fallback.
+ */
+export interface UiState {
+  lastSelectedCamera?: string
+  [key: string]: UiStateValue | undefined
+}
+
+/**
+ * A JSON merge patch for the UI state: nested objects merge key by key,
+ * `null` deletes a key, and arrays replace the stored value wholesale.
+ */
+export type UiStatePatch = { [key: string]: UiStateValue }